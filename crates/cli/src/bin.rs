@@ -3,22 +3,19 @@
 //! can't be in dev-dependencies. This doesn't actually add anything to the
 //! final dependency tree though.
 
-use slumber_cli::Args;
+use slumber_cli::{Args, print_error};
 use std::process::ExitCode;
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> ExitCode {
     let args = Args::parse();
+    let error_format = args.global.error_format;
     args.subcommand
         .expect("Subcommand required for CLI tests")
         .execute(args.global)
         .await
         .unwrap_or_else(|error| {
-            eprintln!("{error}");
-            error
-                .chain()
-                .skip(1)
-                .for_each(|cause| eprintln!("  {cause}"));
+            print_error(&error, error_format);
             ExitCode::FAILURE
         })
 }
@@ -1,8 +1,15 @@
+pub mod audit;
+pub mod capture;
 pub mod collection;
 pub mod config;
+pub mod cookies;
 pub mod db;
+pub mod echo;
 pub mod generate;
 #[cfg(feature = "import")]
 pub mod import;
+pub mod keyring;
 pub mod new;
+pub mod open;
 pub mod request;
+pub mod test;
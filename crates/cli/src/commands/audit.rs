@@ -0,0 +1,49 @@
+use crate::{GlobalArgs, Subcommand, util::print_table};
+use chrono::Local;
+use clap::Parser;
+use itertools::Itertools;
+use slumber_core::database::Database;
+use std::process::ExitCode;
+
+/// View the audit log for the current collection
+///
+/// When `audit_log` is enabled in the config, every external effect of a
+/// template render (running a command, reading a file or environment variable,
+/// triggering a request) is recorded. Events are listed newest first. Effects
+/// of template previews in the TUI are marked with `(preview)`.
+#[derive(Clone, Debug, Parser)]
+pub struct AuditCommand {
+    /// Maximum number of events to show
+    #[clap(long, short, default_value_t = 100)]
+    limit: u32,
+}
+
+impl Subcommand for AuditCommand {
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let database =
+            Database::load()?.into_collection(&global.collection_file()?)?;
+        let events = database.get_audit_events(Some(self.limit))?;
+        print_table(
+            ["Time", "Source", "Detail"],
+            &events
+                .into_iter()
+                .map(|event| {
+                    [
+                        event
+                            .time
+                            .with_timezone(&Local)
+                            .format("%FT%TZ%Z")
+                            .to_string(),
+                        if event.preview {
+                            format!("{} (preview)", event.source)
+                        } else {
+                            event.source.to_string()
+                        },
+                        event.detail,
+                    ]
+                })
+                .collect_vec(),
+        );
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -0,0 +1,617 @@
+use crate::{GlobalArgs, Subcommand, http1::RawRequest};
+use anyhow::{Context, anyhow, bail};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use clap::Parser;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use reqwest::{Client, Method, Url, header::HeaderName, redirect};
+use slumber_core::collection::{
+    Authentication, AuthenticationSetting, Collection, Folder, HttpMethod,
+    JsonTemplate, QueryParameterValue, Recipe, RecipeBody, RecipeId,
+    RecipeNode, RecipeTree,
+};
+use slumber_template::Template;
+use slumber_util::{ResultTracedAnyhow, yaml::SourceLocation};
+use std::{
+    collections::HashSet,
+    fmt::Write,
+    fs,
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+    process::ExitCode,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{self, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+/// Run an HTTP proxy that records each request as a recipe
+///
+/// Point any HTTP client at the proxy (e.g. with the `HTTP_PROXY` environment
+/// variable) and every request it makes is forwarded to its destination and
+/// saved as a draft recipe, grouped into one folder per host. The output file
+/// is rewritten after each new request, so you can stop the proxy at any time.
+///
+/// HTTPS requests are tunneled to their destination, but can't be recorded
+/// because they're encrypted.
+///
+/// Credentials are not saved by default. Basic and Bearer `Authorization`
+/// headers become the recipe's `authentication`, with `{{ username }}`,
+/// `{{ password }}`, or `{{ api_token }}` in place of the real values. Other
+/// `Authorization` headers, and API key or password headers, query parameters
+/// and form/JSON body fields are replaced with a template field the same way.
+/// `Cookie` headers are dropped because Slumber's cookie jar manages cookies
+/// itself.
+#[derive(Clone, Debug, Parser)]
+pub struct CaptureCommand {
+    /// Address to listen on
+    #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    host: IpAddr,
+
+    /// Port to listen on. Pass 0 to use any open port
+    #[clap(long, short, default_value_t = 8888)]
+    port: u16,
+
+    /// Collection file to write recipes to. Overwritten if it exists
+    #[clap(long, short, default_value = "slumber-capture.yml")]
+    output: PathBuf,
+
+    /// Save credentials verbatim instead of replacing them with placeholders
+    ///
+    /// This writes passwords, tokens, API keys and cookies to the output file
+    /// in plain text. Don't share or commit the file!
+    #[clap(long)]
+    keep_credentials: bool,
+}
+
+impl Subcommand for CaptureCommand {
+    async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let listener = TcpListener::bind((self.host, self.port))
+            .await
+            .with_context(|| {
+                format!("Error listening on {}:{}", self.host, self.port)
+            })?;
+        eprintln!("Listening on http://{}", listener.local_addr()?);
+        eprintln!("Recording to {}", self.output.display());
+
+        // The proxy forwards requests verbatim, so it shouldn't follow
+        // redirects or go through another proxy. The latter would loop back to
+        // ourselves if HTTP_PROXY is set in this shell.
+        let client = Client::builder()
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .build()?;
+        let recorder = Arc::new(Mutex::new(Recorder::new(
+            self.output,
+            self.keep_credentials,
+        )));
+
+        // Run until the process is killed
+        loop {
+            let (stream, address) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    eprintln!("Error accepting connection: {error}");
+                    continue;
+                }
+            };
+            let client = client.clone();
+            let recorder = Arc::clone(&recorder);
+            tokio::spawn(async move {
+                if let Err(error) = serve(stream, &client, &recorder).await {
+                    eprintln!(
+                        "Error handling request from {address}: {error:#}"
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// Headers that apply to a single connection, and therefore must not be
+/// forwarded by a proxy or saved in a recipe
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Headers that Slumber sets itself when sending a recipe, so they're left
+/// out of generated recipes
+const GENERATED_HEADERS: &[&str] = &["content-length", "expect", "host"];
+
+/// Name fragments that indicate an API key or other secret in a header, query
+/// parameter, or body field. These values aren't saved unless
+/// `--keep-credentials` is given
+const CREDENTIAL_NAME_PATTERNS: &[&str] = &[
+    "api-key", "apikey", "api_key", "token", "secret", "password",
+];
+
+/// Handle a single connection. The connection is closed after one request
+async fn serve(
+    stream: TcpStream,
+    client: &Client,
+    recorder: &Mutex<Recorder>,
+) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let request = RawRequest::read(&mut stream).await?;
+
+    if request.method.eq_ignore_ascii_case("CONNECT") {
+        eprintln!(
+            "CONNECT {} (tunneled, HTTPS requests can't be recorded)",
+            request.target
+        );
+        let mut upstream =
+            TcpStream::connect(&request.target).await.with_context(|| {
+                format!("Error connecting to {}", request.target)
+            })?;
+        stream
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+        io::copy_bidirectional(&mut stream, &mut upstream).await?;
+        return Ok(());
+    }
+
+    // Proxied requests give the full URL in the request line. Anything else
+    // means the client is treating us as the server
+    let Ok(url) = request.target.parse::<Url>() else {
+        let body = "slumber capture is a proxy; send requests through it \
+            with HTTP_PROXY instead of sending them to it directly\n";
+        let head = format!(
+            "HTTP/1.1 400 Bad Request\r\n\
+            content-type: text/plain\r\n\
+            content-length: {}\r\n\
+            connection: close\r\n\r\n",
+            body.len()
+        );
+        let stream = stream.get_mut();
+        stream.write_all(head.as_bytes()).await?;
+        stream.write_all(body.as_bytes()).await?;
+        stream.shutdown().await?;
+        bail!("Request target `{}` is not an absolute URL", request.target);
+    };
+
+    let response = forward(client, &request, url.clone()).await?;
+
+    // Send the response back to the client. We buffer the whole body so we
+    // can give a content-length; this is a dev tool, not a production proxy
+    let status = response.status();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default()
+    );
+    for (name, value) in response.headers() {
+        if !is_hop_by_hop(name.as_str()) && name != "content-length" {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            let _ = write!(head, "{name}: {value}\r\n");
+        }
+    }
+    let body = response.bytes().await?;
+    let _ = write!(
+        head,
+        "content-length: {}\r\nconnection: close\r\n\r\n",
+        body.len()
+    );
+    let stream = stream.get_mut();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+
+    // Record only once the client has its response, so writing the output
+    // file doesn't hold it up. Failing to record isn't the client's problem,
+    // so it doesn't fail the request.
+    let recorded = recorder
+        .lock()
+        .expect("Recorder lock poisoned")
+        .record(&request, &url)
+        .traced();
+    eprintln!(
+        "{} {url} -> {} ({})",
+        request.method,
+        status.as_u16(),
+        match &recorded {
+            Ok(Some(recipe_id)) => format!("recorded as `{recipe_id}`"),
+            Ok(None) => "already recorded".to_owned(),
+            Err(error) => format!("not recorded: {error:#}"),
+        },
+    );
+    Ok(())
+}
+
+/// Send a request on to its destination
+async fn forward(
+    client: &Client,
+    request: &RawRequest,
+    url: Url,
+) -> anyhow::Result<reqwest::Response> {
+    let method = Method::from_bytes(request.method.as_bytes())
+        .with_context(|| format!("Invalid method `{}`", request.method))?;
+    let mut builder = client.request(method, url);
+    for (name, value) in &request.headers {
+        // Host is set by reqwest from the URL
+        if !is_hop_by_hop(name) && !name.eq_ignore_ascii_case("host") {
+            builder = builder.header(name, value);
+        }
+    }
+    let response = builder
+        .body(request.body.clone())
+        .send()
+        .await
+        .with_context(|| format!("Error forwarding to {}", request.target))?;
+    Ok(response)
+}
+
+fn is_hop_by_hop(header: &str) -> bool {
+    HOP_BY_HOP_HEADERS
+        .iter()
+        .any(|hop| header.eq_ignore_ascii_case(hop))
+}
+
+/// Accumulates recorded requests, and writes them to the output file as a
+/// collection
+#[derive(Debug)]
+struct Recorder {
+    output: PathBuf,
+    /// Save credentials verbatim instead of replacing them with placeholders
+    keep_credentials: bool,
+    /// Recorded requests, grouped by host. Each host becomes a folder. We
+    /// store the requests rather than recipes so the collection can be rebuilt
+    /// each time the file is written
+    hosts: IndexMap<String, Vec<Captured>>,
+    /// `(method, URL without query)` of each recorded request, so repeats of
+    /// the same endpoint aren't recorded twice
+    seen: HashSet<(String, String)>,
+}
+
+/// A request that will be written out as a recipe
+#[derive(Debug)]
+struct Captured {
+    id: RecipeId,
+    method: HttpMethod,
+    url: Url,
+    request: RawRequest,
+}
+
+impl Recorder {
+    fn new(output: PathBuf, keep_credentials: bool) -> Self {
+        Self {
+            output,
+            keep_credentials,
+            hosts: IndexMap::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Add a request to the collection, and rewrite the output file. Return
+    /// the ID of the new recipe, or `None` if the endpoint was already recorded
+    fn record(
+        &mut self,
+        request: &RawRequest,
+        url: &Url,
+    ) -> anyhow::Result<Option<RecipeId>> {
+        let method: HttpMethod =
+            request.method.parse().map_err(|error| anyhow!("{error}"))?;
+        let mut base_url = url.clone();
+        base_url.set_query(None);
+        base_url.set_fragment(None);
+        if !self.seen.insert((method.to_string(), base_url.to_string())) {
+            return Ok(None);
+        }
+
+        let id = self.recipe_id(method, url.path());
+        if std::str::from_utf8(&request.body).is_err() {
+            eprintln!("Body of `{id}` is binary and can't be recorded");
+        }
+        let host = url.host_str().unwrap_or_default().to_owned();
+        self.hosts.entry(host).or_default().push(Captured {
+            id: id.clone(),
+            method,
+            url: url.clone(),
+            request: request.clone(),
+        });
+
+        let yaml = serde_yaml::to_string(&self.collection()?)?;
+        fs::write(&self.output, yaml).with_context(|| {
+            format!("Error writing to `{}`", self.output.display())
+        })?;
+        Ok(Some(id))
+    }
+
+    /// Generate a unique recipe ID from the method and path, e.g.
+    /// `get_users_3`
+    fn recipe_id(&self, method: HttpMethod, path: &str) -> RecipeId {
+        let slug = format!("{method} {path}")
+            .to_lowercase()
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .join("_");
+        let is_taken = |id: &str| {
+            self.hosts
+                .values()
+                .flatten()
+                .any(|captured| *captured.id == *id)
+        };
+        let mut id = slug.clone();
+        let mut i = 1;
+        while is_taken(&id) {
+            i += 1;
+            id = format!("{slug}_{i}");
+        }
+        id.into()
+    }
+
+    /// Build a collection of everything recorded so far
+    fn collection(&self) -> anyhow::Result<Collection> {
+        let folders = self
+            .hosts
+            .iter()
+            .map(|(host, captures)| {
+                let id = RecipeId::from(host.as_str());
+                let folder = Folder {
+                    id: id.clone(),
+                    location: SourceLocation::default(),
+                    name: Some(host.clone()),
+                    authentication: None,
+                    children: captures
+                        .iter()
+                        .map(|captured| {
+                            let recipe =
+                                captured.to_recipe(self.keep_credentials);
+                            (recipe.id.clone(), RecipeNode::Recipe(recipe))
+                        })
+                        .collect(),
+                };
+                (id, RecipeNode::Folder(folder))
+            })
+            .collect();
+        Ok(Collection {
+            name: Some("Captured Requests".into()),
+            template_delimiters: None,
+            variables: IndexMap::new(),
+            profiles: IndexMap::new(),
+            toggles: IndexMap::new(),
+            recipes: RecipeTree::new(folders)?,
+        })
+    }
+}
+
+impl Captured {
+    /// Convert the request into a recipe. All values are stored as raw
+    /// templates, so nothing in the request is accidentally interpreted as a
+    /// template. Unless `keep_credentials` is set, credentials are replaced
+    /// with placeholder template fields.
+    fn to_recipe(&self, keep_credentials: bool) -> Recipe {
+        let mut base_url = self.url.clone();
+        base_url.set_query(None);
+        base_url.set_fragment(None);
+
+        let query = self.url.query_pairs().into_owned().fold(
+            IndexMap::<String, Vec<Template>>::new(),
+            |mut acc, (param, value)| {
+                let value = redact(&param, value, keep_credentials);
+                acc.entry(param).or_default().push(value);
+                acc
+            },
+        );
+        let query = query
+            .into_iter()
+            .map(|(param, mut values)| {
+                let value = if values.len() == 1 {
+                    QueryParameterValue::One(values.remove(0))
+                } else {
+                    QueryParameterValue::Many(values)
+                };
+                (param, value)
+            })
+            .collect();
+
+        let body = build_body(&self.request, keep_credentials);
+        // JSON and form bodies set their own content type
+        let skip_content_type = matches!(
+            body,
+            Some(RecipeBody::Json(_) | RecipeBody::FormUrlencoded(_))
+        );
+        let authentication = self
+            .request
+            .header("authorization")
+            .and_then(|value| build_authentication(value, keep_credentials));
+        let headers = self
+            .request
+            .headers
+            .iter()
+            .map(|(name, value)| (name.to_lowercase(), value))
+            .filter(|(name, _)| {
+                let is_excluded = is_hop_by_hop(name)
+                    || GENERATED_HEADERS.contains(&name.as_str())
+                    || (skip_content_type && name == "content-type")
+                    || (authentication.is_some() && name == "authorization")
+                    // The cookie jar sends cookies on its own
+                    || (!keep_credentials && name == "cookie");
+                !is_excluded && HeaderName::try_from(name.as_str()).is_ok()
+            })
+            .map(|(name, value)| {
+                let value = if !keep_credentials && name == "authorization" {
+                    Template::from_field("authorization")
+                } else {
+                    redact(&name, value.clone(), keep_credentials)
+                };
+                (name, value)
+            })
+            .collect();
+
+        Recipe {
+            id: self.id.clone(),
+            location: SourceLocation::default(),
+            persist: true,
+            name: Some(format!("{} {}", self.method, self.url.path())),
+            method: self.method,
+            url: Template::raw(base_url.to_string()),
+            body,
+            authentication: authentication.map(AuthenticationSetting::Defined),
+            query,
+            headers,
+            user_agent: None,
+            transport: None,
+            certificate: None,
+            retry: None,
+            redirect: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            compression: None,
+            reauthenticate: false,
+            capture: IndexMap::new(),
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: None,
+            params: IndexMap::new(),
+        }
+    }
+}
+
+/// Get the template for a header, query parameter or body field value. If the
+/// name looks like it holds a password or other secret, the value is replaced
+/// with `{{ password }}` or `{{ api_key }}`, unless `keep_credentials` is set.
+fn redact(name: &str, value: String, keep_credentials: bool) -> Template {
+    credential_placeholder(name)
+        .filter(|_| !keep_credentials)
+        .unwrap_or_else(|| Template::raw(value))
+}
+
+/// Get the placeholder for a value with the given name, or `None` if the name
+/// doesn't look like it holds a secret
+fn credential_placeholder(name: &str) -> Option<Template> {
+    let name = name.to_ascii_lowercase();
+    if name.contains("password") {
+        Some(Template::from_field("password"))
+    } else if CREDENTIAL_NAME_PATTERNS
+        .iter()
+        .any(|pattern| name.contains(pattern))
+    {
+        Some(Template::from_field("api_key"))
+    } else {
+        None
+    }
+}
+
+/// Convert a Basic or Bearer `Authorization` header into recipe
+/// authentication. Unless `keep_credentials` is set, the credentials are
+/// replaced with placeholder template fields. Return `None` for any other
+/// scheme, so the header is recorded as a header instead.
+fn build_authentication(
+    value: &str,
+    keep_credentials: bool,
+) -> Option<Authentication> {
+    let (scheme, credentials) = value.trim().split_once(' ')?;
+    let credentials = credentials.trim();
+    if scheme.eq_ignore_ascii_case("basic") {
+        if !keep_credentials {
+            return Some(Authentication::Basic {
+                username: Template::from_field("username"),
+                password: Some(Template::from_field("password")),
+            });
+        }
+        let decoded = BASE64_STANDARD.decode(credentials).ok()?;
+        let decoded = String::from_utf8(decoded).ok()?;
+        let (username, password) = match decoded.split_once(':') {
+            Some((username, password)) => (username, Some(password)),
+            None => (decoded.as_str(), None),
+        };
+        Some(Authentication::Basic {
+            username: Template::raw(username.to_owned()),
+            password: password
+                .map(|password| Template::raw(password.to_owned())),
+        })
+    } else if scheme.eq_ignore_ascii_case("bearer") {
+        Some(Authentication::Bearer {
+            token: if keep_credentials {
+                Template::raw(credentials.to_owned())
+            } else {
+                Template::from_field("api_token")
+            },
+        })
+    } else {
+        None
+    }
+}
+
+/// Convert a request body into a recipe body, based on its content type.
+/// Binary bodies can't be represented in a recipe, so they're dropped. Unless
+/// `keep_credentials` is set, secrets in form and JSON fields are replaced
+/// with placeholders.
+fn build_body(
+    request: &RawRequest,
+    keep_credentials: bool,
+) -> Option<RecipeBody> {
+    if request.body.is_empty() {
+        return None;
+    }
+    let content_type = request
+        .header("content-type")
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    if content_type == "application/json" || content_type.ends_with("+json") {
+        if let Ok(json) = serde_json::from_slice(&request.body) {
+            return Some(if keep_credentials {
+                RecipeBody::untemplated_json(json)
+            } else {
+                RecipeBody::Json(redact_json(json))
+            });
+        }
+    } else if content_type == "application/x-www-form-urlencoded" {
+        let form = url::form_urlencoded::parse(&request.body)
+            .into_owned()
+            .map(|(field, value)| {
+                let value = redact(&field, value, keep_credentials);
+                (field, value)
+            })
+            .collect();
+        return Some(RecipeBody::FormUrlencoded(form));
+    }
+
+    if let Ok(text) = std::str::from_utf8(&request.body) {
+        Some(RecipeBody::Raw(Template::raw(text.to_owned())))
+    } else {
+        None
+    }
+}
+
+/// Convert a JSON body into a template, replacing the value of any field that
+/// looks like it holds a secret with a placeholder. Other strings are stored
+/// as raw templates, like [RecipeBody::untemplated_json].
+fn redact_json(json: serde_json::Value) -> JsonTemplate {
+    match json {
+        serde_json::Value::Array(values) => {
+            JsonTemplate::Array(values.into_iter().map(redact_json).collect())
+        }
+        serde_json::Value::Object(map) => JsonTemplate::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    // Only secrets themselves are replaced. Nested objects are
+                    // searched instead, and nulls and bools are left alone.
+                    let placeholder = credential_placeholder(&key)
+                        .filter(|_| value.is_string() || value.is_number());
+                    let value = match placeholder {
+                        Some(placeholder) => JsonTemplate::String(placeholder),
+                        None => redact_json(value),
+                    };
+                    (Template::raw(key), value)
+                })
+                .collect(),
+        ),
+        json => JsonTemplate::raw(json),
+    }
+}
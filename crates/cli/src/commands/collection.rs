@@ -4,6 +4,7 @@ use crate::{
 };
 use clap::Parser;
 use slumber_config::Config;
+use slumber_util::i18n;
 use std::process::ExitCode;
 
 /// View and edit the active Slumber request collection file
@@ -39,6 +40,7 @@ impl Subcommand for CollectionCommand {
             Ok(ExitCode::SUCCESS)
         } else if self.edit {
             let config = Config::load()?;
+            i18n::set_locale(config.locale.as_deref());
             edit_and_validate(&config, collection_file.path(), || {
                 collection_file.load()
             })
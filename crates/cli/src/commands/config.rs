@@ -4,6 +4,7 @@ use crate::{
 };
 use clap::Parser;
 use slumber_config::Config;
+use slumber_util::i18n;
 use std::process::ExitCode;
 
 /// View and edit global Slumber configuration
@@ -32,6 +33,7 @@ impl Subcommand for ConfigCommand {
             // If the config is invalid, the user is probably trying to
             // fix it so we should open anyway
             let config = Config::load().unwrap_or_default();
+            i18n::set_locale(config.locale.as_deref());
             let path = Config::path();
             edit_and_validate(&config, &path, Config::load)
         } else {
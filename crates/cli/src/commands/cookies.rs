@@ -0,0 +1,144 @@
+use crate::{GlobalArgs, Subcommand, util::print_table};
+use anyhow::anyhow;
+use chrono::Local;
+use clap::Parser;
+use itertools::Itertools;
+use slumber_core::{
+    database::Database,
+    http::{Cookie, CookieJar},
+};
+use slumber_util::tr;
+use std::process::ExitCode;
+
+/// View and edit the cookie jar for the current collection
+///
+/// Cookies set by responses are stored per collection and session, and sent
+/// with later requests to matching URLs. Without a subcommand, this lists every
+/// cookie in the jar. Domains starting with `.` also match their subdomains.
+/// Use `--session` to view or edit a named session's jar.
+///
+/// EXAMPLES:
+///
+/// Replace a session cookie:
+///
+///   slumber cookies set api.example.com session abc123
+///
+/// Log out everywhere:
+///
+///   slumber cookies clear
+#[derive(Clone, Debug, Parser)]
+#[clap(verbatim_doc_comment)]
+pub struct CookiesCommand {
+    #[command(subcommand)]
+    subcommand: Option<CookiesSubcommand>,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum CookiesSubcommand {
+    /// Store a cookie, replacing the existing one if any
+    ///
+    /// The cookie never expires; it's kept until it's replaced or deleted.
+    Set {
+        /// Host the cookie is sent to
+        domain: String,
+        name: String,
+        value: String,
+        /// Only send the cookie to URLs under this path
+        #[clap(long, default_value = "/")]
+        path: String,
+        /// Send the cookie to subdomains of the domain as well
+        #[clap(long)]
+        include_subdomains: bool,
+        /// Only send the cookie over HTTPS
+        #[clap(long)]
+        secure: bool,
+    },
+    /// Delete a cookie
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Host the cookie is sent to, without a leading `.`
+        domain: String,
+        name: String,
+        /// Path of the cookie
+        #[clap(long, default_value = "/")]
+        path: String,
+    },
+    /// Delete every cookie
+    Clear,
+}
+
+impl Subcommand for CookiesCommand {
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let database = Database::load()?
+            .into_collection(&global.collection_file()?)?
+            .with_session(global.session);
+        match self.subcommand {
+            None => {
+                let cookies = CookieJar::new(database).cookies();
+                print_table(
+                    ["Domain", "Path", "Name", "Value", "Expires"],
+                    &cookies.into_iter().map(row).collect_vec(),
+                );
+            }
+            Some(CookiesSubcommand::Set {
+                domain,
+                name,
+                value,
+                path,
+                include_subdomains,
+                secure,
+            }) => {
+                let cookie = Cookie {
+                    name,
+                    value,
+                    domain: domain.trim_start_matches('.').to_lowercase(),
+                    host_only: !include_subdomains,
+                    path,
+                    expires: None,
+                    secure,
+                    http_only: false,
+                };
+                database.set_cookie(&cookie)?;
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "cli-cookie-stored",
+                        name = cookie.name,
+                        domain = domain
+                    )
+                );
+            }
+            Some(CookiesSubcommand::Delete { domain, name, path }) => {
+                let domain = domain.trim_start_matches('.').to_lowercase();
+                if !database.delete_cookie(&domain, &path, &name)? {
+                    return Err(anyhow!(
+                        "No cookie `{name}` for `{domain}` with path `{path}`"
+                    ));
+                }
+                eprintln!(
+                    "{}",
+                    tr!("cli-cookie-deleted", name = name, domain = domain)
+                );
+            }
+            Some(CookiesSubcommand::Clear) => {
+                let deleted = database.clear_cookies()?;
+                eprintln!("{}", tr!("cli-cookies-cleared", count = deleted));
+            }
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Format a cookie as a table row
+fn row(cookie: Cookie) -> [String; 5] {
+    let domain = if cookie.host_only {
+        cookie.domain
+    } else {
+        format!(".{}", cookie.domain)
+    };
+    let expires = cookie.expires.map_or_else(
+        || "session".to_owned(),
+        |expires| expires.with_timezone(&Local).format("%FT%TZ%Z").to_string(),
+    );
+    [domain, cookie.path, cookie.name, cookie.value, expires]
+}
@@ -3,7 +3,11 @@ use crate::{
     util::print_table,
 };
 use clap::Parser;
-use slumber_core::database::{CollectionId, Database};
+use slumber_core::{
+    collection::{CollectionFile, RemoteSource},
+    database::{CollectionId, Database},
+};
+use slumber_util::tr;
 use std::{
     fmt::{self, Display},
     path::PathBuf,
@@ -44,6 +48,24 @@ enum DbCollectionSubcommand {
         /// Path or ID of the collection to migrate *into*
         to: CollectionSpecifier,
     },
+    /// Trust a collection, giving it full access to the local system
+    ///
+    /// This only has an effect if the `trusted_directories` config field is
+    /// set. Collections outside those directories are sandboxed unless
+    /// trusted. Remote collections are always sandboxed unless trusted; pass
+    /// the URL or git source to trust its current content. If it changes
+    /// upstream, it will need to be trusted again.
+    Trust {
+        /// Path, ID, or remote source of the collection to trust
+        #[clap(add = complete_collection_specifier())]
+        collection: CollectionSpecifier,
+    },
+    /// Revoke trust from a collection, so it will be sandboxed again
+    Untrust {
+        /// Path or ID of the collection to untrust
+        #[clap(add = complete_collection_specifier())]
+        collection: CollectionSpecifier,
+    },
 }
 
 impl Subcommand for DbCollectionCommand {
@@ -68,14 +90,45 @@ impl Subcommand for DbCollectionCommand {
                 for collection in collection {
                     let id = collection.to_id(&database)?;
                     database.delete_collection(id)?;
-                    println!("Deleted collection {id}");
+                    println!(
+                        "{}",
+                        tr!("cli-collection-deleted", collection = id)
+                    );
                 }
             }
             DbCollectionSubcommand::Migrate { from, to } => {
                 let from_id = from.to_id(&database)?;
                 let to_id = to.to_id(&database)?;
                 database.merge_collections(from_id, to_id)?;
-                println!("Migrated {from} into {to}");
+                println!(
+                    "{}",
+                    tr!("cli-collection-migrated", from = from, to = to)
+                );
+            }
+            DbCollectionSubcommand::Trust { collection } => {
+                // Trust in a remote collection is pinned to its content, so
+                // fetch it to see what's being trusted
+                if let CollectionSpecifier::Path(path) = &collection
+                    && RemoteSource::parse(path).is_some()
+                {
+                    let file = CollectionFile::new(Some(path.clone()))?;
+                    file.trust(&database.clone().into_collection(&file)?)?;
+                } else {
+                    let id = collection.to_id(&database)?;
+                    database.set_collection_trusted(id, true, None)?;
+                }
+                println!(
+                    "{}",
+                    tr!("cli-collection-trusted", collection = collection)
+                );
+            }
+            DbCollectionSubcommand::Untrust { collection } => {
+                let id = collection.to_id(&database)?;
+                database.set_collection_trusted(id, false, None)?;
+                println!(
+                    "{}",
+                    tr!("cli-collection-untrusted", collection = collection)
+                );
             }
         }
         Ok(ExitCode::SUCCESS)
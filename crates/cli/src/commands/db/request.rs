@@ -18,6 +18,7 @@ use slumber_core::{
     database::{Database, ProfileFilter},
     http::RequestId,
 };
+use slumber_util::tr;
 use std::{process::ExitCode, str::FromStr};
 
 /// View and modify request history
@@ -109,7 +110,11 @@ impl Subcommand for DbRequestCommand {
                     // All requests for a single recipe in current collection
                     (Some(recipe_id), profile, false) => database
                         .into_collection(&global.collection_file()?)?
-                        .get_recipe_requests(profile.into(), &recipe_id)?,
+                        .get_recipe_requests(
+                            profile.into(),
+                            &recipe_id,
+                            None,
+                        )?,
 
                     // Reject invalid arg groupings. This is a bit of a code
                     // stink because invalid states should generally be
@@ -175,7 +180,10 @@ impl Subcommand for DbRequestCommand {
                 for id in &request {
                     database.delete_request(*id)?;
                 }
-                println!("Deleted {} request(s)", request.len());
+                println!(
+                    "{}",
+                    tr!("cli-requests-deleted", count = request.len())
+                );
             }
         }
         Ok(ExitCode::SUCCESS)
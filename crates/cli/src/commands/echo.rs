@@ -0,0 +1,227 @@
+use crate::{GlobalArgs, Subcommand, http1::RawRequest};
+use anyhow::Context;
+use base64::{Engine, prelude::BASE64_STANDARD};
+use clap::Parser;
+use serde::Serialize;
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Arc,
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        ServerConfig, ServerConnection,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    },
+};
+
+/// Run a local server that responds to each request with a copy of it
+///
+/// The response is a JSON object describing the request exactly as it was
+/// received: method, target, HTTP version, headers (in their original order
+/// and case), body, and TLS details. Point a recipe at this server to check
+/// what Slumber actually sends, e.g. when developing authentication or request
+/// signing.
+#[derive(Clone, Debug, Parser)]
+pub struct EchoCommand {
+    /// Address to listen on
+    #[clap(long, default_value_t = IpAddr::V4(Ipv4Addr::LOCALHOST))]
+    host: IpAddr,
+
+    /// Port to listen on. Pass 0 to use any open port
+    #[clap(long, short, default_value_t = 8080)]
+    port: u16,
+
+    /// Serve HTTPS, using the certificate chain in this PEM file
+    #[clap(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// Private key for `--tls-cert`, in PEM format
+    #[clap(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+}
+
+impl Subcommand for EchoCommand {
+    async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let acceptor = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Some(tls_acceptor(cert, key)?),
+            _ => None,
+        };
+        let listener = TcpListener::bind((self.host, self.port))
+            .await
+            .with_context(|| {
+                format!("Error listening on {}:{}", self.host, self.port)
+            })?;
+        let scheme = if acceptor.is_some() { "https" } else { "http" };
+        eprintln!("Listening on {scheme}://{}", listener.local_addr()?);
+
+        // Run until the process is killed
+        loop {
+            let (stream, address) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(error) => {
+                    eprintln!("Error accepting connection: {error}");
+                    continue;
+                }
+            };
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Err(error) = serve(stream, acceptor).await {
+                    eprintln!(
+                        "Error handling request from {address}: {error:#}"
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// Build a TLS acceptor from PEM certificate and key files
+fn tls_acceptor(
+    cert_path: &Path,
+    key_path: &Path,
+) -> anyhow::Result<TlsAcceptor> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(Iterator::collect::<Result<Vec<_>, _>>)
+        .with_context(|| {
+            format!("Error loading certificate `{}`", cert_path.display())
+        })?;
+    let key = PrivateKeyDer::from_pem_file(key_path).with_context(|| {
+        format!("Error loading private key `{}`", key_path.display())
+    })?;
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .context("Invalid certificate or private key")?;
+    // We only speak HTTP/1.1
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+/// Handle a single connection. The connection is closed after one request
+async fn serve(
+    stream: TcpStream,
+    acceptor: Option<TlsAcceptor>,
+) -> anyhow::Result<()> {
+    if let Some(acceptor) = acceptor {
+        let stream = acceptor
+            .accept(stream)
+            .await
+            .context("Error in TLS handshake")?;
+        let tls = TlsInfo::new(stream.get_ref().1);
+        echo(stream, Some(tls)).await
+    } else {
+        echo(stream, None).await
+    }
+}
+
+/// Read a request and send it back as the response body
+async fn echo(
+    stream: impl AsyncRead + AsyncWrite + Unpin,
+    tls: Option<TlsInfo>,
+) -> anyhow::Result<()> {
+    let mut stream = BufReader::new(stream);
+    let request = EchoRequest::new(RawRequest::read(&mut stream).await?, tls);
+    eprintln!(
+        "{} {} ({} B)",
+        request.method, request.target, request.body_size
+    );
+
+    let body = serde_json::to_vec_pretty(&request)?;
+    let head = format!(
+        "HTTP/1.1 200 OK\r\n\
+        content-type: application/json\r\n\
+        content-length: {}\r\n\
+        connection: close\r\n\r\n",
+        body.len()
+    );
+    let stream = stream.get_mut();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+/// An HTTP request, as reflected back to the client
+#[derive(Debug, Serialize)]
+struct EchoRequest {
+    method: String,
+    /// Path and query, exactly as given in the request line
+    target: String,
+    version: String,
+    /// `(name, value)` pairs, in the order they were received
+    headers: Vec<(String, String)>,
+    /// Body as text if it's valid UTF-8, otherwise base64
+    body: String,
+    body_encoding: BodyEncoding,
+    body_size: usize,
+    /// `null` for plain HTTP
+    tls: Option<TlsInfo>,
+}
+
+impl EchoRequest {
+    fn new(request: RawRequest, tls: Option<TlsInfo>) -> Self {
+        let body_size = request.body.len();
+        let (body, body_encoding) = match String::from_utf8(request.body) {
+            Ok(body) => (body, BodyEncoding::Utf8),
+            Err(error) => (
+                BASE64_STANDARD.encode(error.into_bytes()),
+                BodyEncoding::Base64,
+            ),
+        };
+        Self {
+            method: request.method,
+            target: request.target,
+            version: request.version,
+            headers: request.headers,
+            body,
+            body_encoding,
+            body_size,
+            tls,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+enum BodyEncoding {
+    #[serde(rename = "utf-8")]
+    Utf8,
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+/// Details of the TLS session a request was received over
+#[derive(Debug, Serialize)]
+struct TlsInfo {
+    /// e.g. `TLSv1_3`
+    version: Option<String>,
+    /// e.g. `TLS13_AES_256_GCM_SHA384`
+    cipher_suite: Option<String>,
+    /// Hostname requested via SNI
+    server_name: Option<String>,
+    /// Protocol negotiated via ALPN
+    alpn_protocol: Option<String>,
+}
+
+impl TlsInfo {
+    fn new(connection: &ServerConnection) -> Self {
+        Self {
+            version: connection
+                .protocol_version()
+                .map(|version| format!("{version:?}")),
+            cipher_suite: connection
+                .negotiated_cipher_suite()
+                .map(|suite| format!("{:?}", suite.suite())),
+            server_name: connection.server_name().map(str::to_owned),
+            alpn_protocol: connection
+                .alpn_protocol()
+                .map(|protocol| String::from_utf8_lossy(protocol).into_owned()),
+        }
+    }
+}
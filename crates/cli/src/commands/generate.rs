@@ -28,21 +28,20 @@ impl Subcommand for GenerateCommand {
                 let (_, http_engine, seed, template_context) = self
                     .build_request
                     .build_seed(global, self.execute_triggers)?;
-                let command = http_engine
-                    .build_curl(seed, &template_context)
-                    .await
-                    .map_err(|error| {
-                        // If the build failed because triggered requests are
-                        // disabled, replace it with a custom error message
-                        if error.has_trigger_disabled_error() {
-                            anyhow::Error::from(error.error).context(
-                                "Triggered requests are disabled by default; \
-                                 pass `--execute-triggers` to enable",
-                            )
-                        } else {
-                            error.error.into()
-                        }
-                    })?;
+                // Rendering builds a large future, so keep it off the stack
+                let future = http_engine.build_curl(seed, &template_context);
+                let command = Box::pin(future).await.map_err(|error| {
+                    // If the build failed because triggered requests are
+                    // disabled, replace it with a custom error message
+                    if error.has_trigger_disabled_error() {
+                        anyhow::Error::from(error.error).context(
+                            "Triggered requests are disabled by default; \
+                             pass `--execute-triggers` to enable",
+                        )
+                    } else {
+                        error.error.into()
+                    }
+                })?;
                 println!("{command}");
             }
         }
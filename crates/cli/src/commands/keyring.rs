@@ -0,0 +1,124 @@
+use crate::{GlobalArgs, Subcommand};
+use anyhow::{Context, anyhow};
+use clap::Parser;
+use dialoguer::Password;
+use slumber_core::keyring::KeyringEntry;
+use slumber_util::tr;
+use std::{
+    io::{self, IsTerminal, Read},
+    process::ExitCode,
+};
+
+/// Manage secrets in the operating system's credential store
+///
+/// Secrets are stored in Keychain on macOS, Credential Manager on Windows, or
+/// the Secret Service (e.g. GNOME Keyring or KWallet) on Linux. Load them in
+/// templates with the `keyring()` function:
+///
+///   token: "{{ keyring('fish-api', 'admin') }}"
+#[derive(Clone, Debug, Parser)]
+#[clap(verbatim_doc_comment)]
+pub struct KeyringCommand {
+    #[command(subcommand)]
+    subcommand: KeyringSubcommand,
+}
+
+#[derive(Clone, Debug, clap::Subcommand)]
+enum KeyringSubcommand {
+    /// Store a secret, replacing the existing one if any
+    ///
+    /// The secret is read from a hidden prompt, or from stdin if stdin isn't a
+    /// terminal.
+    Set {
+        /// Service the secret belongs to, e.g. the name of the API
+        service: String,
+        /// Username or account name within the service
+        username: String,
+    },
+    /// Print a stored secret
+    Get {
+        /// Service the secret belongs to
+        service: String,
+        /// Username or account name within the service
+        username: String,
+    },
+    /// Delete a stored secret
+    #[command(visible_alias = "rm")]
+    Delete {
+        /// Service the secret belongs to
+        service: String,
+        /// Username or account name within the service
+        username: String,
+    },
+}
+
+impl Subcommand for KeyringCommand {
+    async fn execute(self, _global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        match self.subcommand {
+            KeyringSubcommand::Set { service, username } => {
+                let secret = read_secret()?;
+                KeyringEntry::new(&service, &username)
+                    .set(&secret)
+                    .await
+                    .context("Error storing secret")?;
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "cli-secret-stored",
+                        username = username,
+                        service = service
+                    )
+                );
+            }
+            KeyringSubcommand::Get { service, username } => {
+                let secret = KeyringEntry::new(&service, &username)
+                    .get()
+                    .await
+                    .context("Error loading secret")?
+                    .ok_or_else(|| {
+                        anyhow!("No secret for `{username}` in `{service}`")
+                    })?;
+                println!("{secret}");
+            }
+            KeyringSubcommand::Delete { service, username } => {
+                let deleted = KeyringEntry::new(&service, &username)
+                    .delete()
+                    .await
+                    .context("Error deleting secret")?;
+                if !deleted {
+                    return Err(anyhow!(
+                        "No secret for `{username}` in `{service}`"
+                    ));
+                }
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "cli-secret-deleted",
+                        username = username,
+                        service = service
+                    )
+                );
+            }
+        }
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+/// Read the secret to store from a hidden prompt, or from stdin if it's piped
+fn read_secret() -> anyhow::Result<String> {
+    if io::stdin().is_terminal() {
+        Password::new()
+            .with_prompt("Secret")
+            .interact()
+            .context("Error reading secret")
+    } else {
+        let mut secret = String::new();
+        io::stdin()
+            .read_to_string(&mut secret)
+            .context("Error reading secret from stdin")?;
+        // Drop the trailing newline from `echo` and friends
+        let len = secret.trim_end_matches(['\r', '\n']).len();
+        secret.truncate(len);
+        Ok(secret)
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{GlobalArgs, Subcommand};
 use anyhow::Context;
 use clap::Parser;
-use slumber_util::git_link;
+use slumber_util::{git_link, tr};
 use std::{fs::OpenOptions, io::Write, path::PathBuf, process::ExitCode};
 
 const DEFAULT_PATH: &str = "slumber.yml";
@@ -37,7 +37,7 @@ impl Subcommand for NewCommand {
             format!("Error writing to file `{}`", path.display())
         })?;
 
-        eprintln!("New collection created at `{}`", path.display());
+        eprintln!("{}", tr!("cli-collection-created", path = path.display()));
 
         Ok(ExitCode::SUCCESS)
     }
@@ -57,15 +57,15 @@ fn source() -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use indexmap::indexmap;
+    use indexmap::{IndexMap, indexmap};
     use pretty_assertions::assert_eq;
     use rstest::rstest;
     use serde_json::json;
     use slumber_core::{
         collection::{
-            Collection, Folder, Profile, Recipe, RecipeBody, RecipeNode,
+            Collection, Folder, HttpMethod, Profile, Recipe, RecipeBody,
+            RecipeNode,
         },
-        http::HttpMethod,
         test_util::by_id,
     };
     use slumber_util::{Factory, TempDir, temp_dir, yaml::SourceLocation};
@@ -115,6 +115,8 @@ mod tests {
         let collection: Collection = Collection::parse(&source()).unwrap();
         let expected = Collection {
             name: Some("My Collection".into()),
+            template_delimiters: None,
+            variables: IndexMap::new(),
             profiles: by_id([Profile {
                 id: "example".into(),
                 location: SourceLocation::default(),
@@ -123,7 +125,17 @@ mod tests {
                 data: indexmap! {
                     "host".into() => "https://my-host".into()
                 },
+                chaos: None,
+                certificate: None,
+                proxy: None,
+                timeout: None,
+                throttle: None,
+                protocol: None,
+                socket: None,
+                resolve: IndexMap::new(),
+                pinning: None,
             }]),
+            toggles: IndexMap::new(),
             recipes: by_id([
                 RecipeNode::Recipe(Recipe {
                     id: "example_get".into(),
@@ -136,6 +148,7 @@ mod tests {
                     id: "example_folder".into(),
                     location: SourceLocation::default(),
                     name: Some("Example Folder".into()),
+                    authentication: None,
                     children: by_id([RecipeNode::Recipe(Recipe {
                         id: "example_post".into(),
                         name: Some("Example POST".into()),
@@ -0,0 +1,65 @@
+use crate::{
+    GlobalArgs, Subcommand,
+    completions::{complete_profile, complete_recipe},
+};
+use anyhow::{anyhow, bail};
+use clap::Parser;
+use itertools::Itertools;
+use slumber_core::{
+    collection::{ProfileId, RecipeId},
+    database::Database,
+    ipc::OpenRequest,
+};
+use std::process::ExitCode;
+
+/// Select a recipe and/or profile in a running TUI
+///
+/// This is intended for editor integrations, e.g. to jump from a recipe in the
+/// collection file to the same recipe in the TUI. Any TUI running for the
+/// same collection will switch its selection. If no TUI is running, this does
+/// nothing.
+#[derive(Clone, Debug, Parser)]
+pub struct OpenCommand {
+    /// ID of the recipe (or folder) to select
+    #[clap(long, short, add = complete_recipe())]
+    recipe: Option<RecipeId>,
+
+    /// ID of the profile to select
+    #[clap(long, short, add = complete_profile())]
+    profile: Option<ProfileId>,
+}
+
+impl Subcommand for OpenCommand {
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        if self.recipe.is_none() && self.profile.is_none() {
+            bail!("At least one of `--recipe` or `--profile` is required");
+        }
+
+        // Validate IDs, so we can provide a good error if they're invalid
+        let collection_file = global.collection_file()?;
+        let collection = collection_file.load()?;
+        if let Some(recipe_id) = &self.recipe {
+            collection
+                .recipes
+                .get(recipe_id)
+                .ok_or_else(|| anyhow!("No recipe with ID `{recipe_id}`"))?;
+        }
+        if let Some(profile_id) = &self.profile {
+            collection.profiles.get(profile_id).ok_or_else(|| {
+                anyhow!(
+                    "No profile with ID `{profile_id}`; options are: {}",
+                    collection.profiles.keys().format(", ")
+                )
+            })?;
+        }
+
+        let database = Database::load()?.into_collection(&collection_file)?;
+        OpenRequest {
+            collection_id: database.collection_id(),
+            recipe_id: self.recipe,
+            profile_id: self.profile,
+        }
+        .send()?;
+        Ok(ExitCode::SUCCESS)
+    }
+}
@@ -1,44 +1,73 @@
 use crate::{
     GlobalArgs, Subcommand,
-    completions::{complete_profile, complete_recipe},
+    completions::{complete_profile, complete_recipe, complete_toggle},
+    util::{ErrorFormat, ErrorReport},
 };
-use anyhow::{Context, anyhow};
+use anyhow::{Context, anyhow, bail};
 use async_trait::async_trait;
+use bytes::Bytes;
 use clap::{Parser, ValueHint};
 use dialoguer::{Input, Password, Select as DialoguerSelect};
+use futures::{Stream, stream};
 use indexmap::IndexMap;
 use itertools::Itertools;
+use reqwest::{StatusCode, header::HeaderMap};
 use slumber_config::Config;
 use slumber_core::{
     collection::{
         Authentication, ProfileId, QueryParameterValue, Recipe, RecipeId,
+        RenderMode, ToggleId,
     },
     database::{CollectionDatabase, Database},
+    error::ErrorCode,
     http::{
-        BuildFieldOverride, BuildOptions, Exchange, HttpEngine, RequestRecord,
-        RequestSeed, ResponseRecord, StoredRequestError, TriggeredRequestError,
+        BuildFieldOverride, BuildOptions, CookieJar, DownloadProgress,
+        Exchange, HttpEngine, RequestBuildError, RequestRecord, RequestSeed,
+        RequestTicket, ResponseRecord, StoredRequestError, TokenStore,
+        TriggeredRequestError,
+    },
+    render::{
+        Auditor, HttpProvider, Prompt, Prompter, SelectOption, TemplateContext,
     },
-    render::{HttpProvider, Prompt, Prompter, SelectOption, TemplateContext},
     util::MaybeStr,
 };
 use slumber_template::{Expression, Template};
-use slumber_util::{ResultTraced, ResultTracedAnyhow};
+use slumber_util::{
+    ResultTraced, ResultTracedAnyhow, format_byte_size, i18n, tr,
+};
 use std::{
     error::Error,
     fs::OpenOptions,
     io::{self, IsTerminal, Write},
+    mem,
+    ops::ControlFlow,
     path::{Path, PathBuf},
     process::ExitCode,
+    time::{Duration, Instant},
 };
+use tokio::io::{AsyncBufReadExt, BufReader};
 use tracing::warn;
 
 /// Exit code to return when `exit_status` flag is set and the HTTP response has
 /// an error status code
 const HTTP_ERROR_EXIT_CODE: u8 = 2;
 
+/// Exit code to return when the response fails any of the recipe's assertions
+pub const ASSERTION_EXIT_CODE: u8 = 3;
+
+/// Printed instead of the response body when it's binary and we're writing to
+/// a terminal
+const BINARY_WARNING: &str = "Response body is not text. Binary output can \
+    mess up your terminal. Pass `--output -` if you're sure you want to print \
+    the output, or consider `--output <FILE>` to save to a file.";
+
+/// Minimum time between redraws of the download progress line
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Execute a single request and print its response
 #[derive(Clone, Debug, Parser)]
 #[clap(visible_aliases = &["req", "rq"])]
+#[expect(clippy::struct_excessive_bools)] // These are all CLI flags
 pub struct RequestCommand {
     #[clap(flatten)]
     build_request: BuildRequestCommand,
@@ -56,6 +85,19 @@ pub struct RequestCommand {
     #[clap(long)]
     exit_status: bool,
 
+    /// Write the response body to the output as it's received, instead of
+    /// waiting for the entire body to load. Useful for streamed responses such
+    /// as Server-Sent Events or newline-delimited JSON.
+    #[clap(long, short = 'N')]
+    no_buffer: bool,
+
+    /// Open a WebSocket connection, even if the recipe doesn't define a
+    /// `websocket` field. Each line of stdin is sent as a message, and each
+    /// message from the server is written to the output as it's received.
+    /// The connection is closed when stdin ends.
+    #[clap(long)]
+    ws: bool,
+
     /// Persist the completed request to Slumber's history database. By
     /// default, CLI-based requests are not persisted. The CLI ignores the
     /// `persist` field in the global configuration and recipe definition; this
@@ -86,6 +128,16 @@ pub struct BuildRequestCommand {
     )]
     profile: Option<ProfileId>,
 
+    /// ID of a toggle to enable on top of the profile. To enable multiple
+    /// toggles, pass this flag multiple times. If multiple toggles define the
+    /// same field, the last one wins.
+    #[clap(
+        long = "toggle",
+        short = 't',
+        add = complete_toggle(),
+    )]
+    toggles: Vec<ToggleId>,
+
     /// Set credentials for HTTP Basic authentication
     ///
     /// The username and password are split on the first colon. This means the
@@ -127,10 +179,20 @@ pub struct BuildRequestCommand {
     /// - If there is no body, the given override will become a raw body
     /// - Raw and stream bodies are replaced directly
     /// - JSON bodies are parsed as JSON before being rendered as a string
+    /// - GraphQL bodies have their query replaced
     /// - Form bodies CANNOT be overridden by this flag
     #[clap(long, visible_alias = "data", value_hint = ValueHint::Other)]
     body: Option<Template>,
 
+    /// Override the variables of a GraphQL body
+    ///
+    /// The given value is parsed as JSON, with strings parsed as templates.
+    /// Requires the recipe to have a graphql body.
+    ///
+    ///   slumber request my-query --variables '{"id": "{{user_id}}"}'
+    #[clap(long, value_hint = ValueHint::Other)]
+    variables: Option<Template>,
+
     /// Override a request form field (format: `field=value`)
     ///
     /// The given value is parsed and rendered as a template. To override
@@ -188,6 +250,30 @@ pub struct BuildRequestCommand {
     )]
     overrides: Vec<(String, Template)>,
 
+    /// Set a recipe parameter (format: `param=value`)
+    ///
+    /// The given value is parsed as a template, then converted to the
+    /// parameter's type. To set multiple parameters, pass this flag multiple
+    /// times. Parameters that aren't given use their default.
+    ///
+    ///   slumber request get-user --param user_id=42
+    #[clap(
+        long = "param",
+        value_parser = parse_profile_override,
+        value_hint = ValueHint::Other, // Disable completions
+        value_name = "param=value",
+        verbatim_doc_comment,
+    )]
+    params: Vec<(String, Template)>,
+
+    /// What to do when a template references an undefined profile field
+    ///
+    /// Overrides the `render_mode` of every recipe in the request, including
+    /// upstream requests. One of: strict (fail the request), lenient (render
+    /// as empty), prompt (ask for the value).
+    #[clap(long, value_hint = ValueHint::Other, value_name = "mode")]
+    render_mode: Option<RenderMode>,
+
     /// Override a request query parameter (format: `parameter=value`)
     ///
     /// The given value is parsed as a template. To override multiple
@@ -232,30 +318,31 @@ pub struct DisplayExchangeCommand {
     verbose: bool,
 
     /// Write to file instead of stdout
+    ///
+    /// The response body is streamed into the file as it's received, so large
+    /// bodies are never held in memory. When stderr is a terminal, download
+    /// progress is shown there.
     #[clap(long, value_name = "path")]
     output: Option<PathBuf>,
 }
 
 impl Subcommand for RequestCommand {
     async fn execute(mut self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let error_format = global.error_format;
         // Don't execute sub-requests in a dry run
         let trigger_dependencies = !self.dry_run;
-        let (database, http_engine, seed, template_context) = self
+        let (database, http_engine, mut seed, mut template_context) = self
             .build_request
             .build_seed(global, trigger_dependencies)?;
-        let ticket = http_engine.build(seed, &template_context).await.map_err(
-            |error| {
-                // If the build failed because triggered requests are disabled,
-                // replace it with a custom error message
-                if error.has_trigger_disabled_error() {
-                    anyhow::Error::from(error.error).context(
-                        "Triggered requests are disabled with `--dry-run`",
-                    )
-                } else {
-                    error.error.into()
-                }
-            },
-        )?;
+        seed.options.websocket = self.ws;
+        // Messages are useless if they don't show up until the end
+        let streaming = self.no_buffer || self.ws;
+        // Keep a copy in case the request has to be retried
+        let retry_seed = seed.clone();
+        let ticket = http_engine
+            .build(seed, &template_context)
+            .await
+            .map_err(build_error)?;
 
         if self.dry_run {
             // With --dry-run, we don't do anything unless the verbose flag is
@@ -266,18 +353,84 @@ impl Subcommand for RequestCommand {
             Ok(ExitCode::SUCCESS)
         } else {
             self.display.write_request(ticket.record());
+            let downloaded = self.display.download_path(&ticket).is_some();
+            let mut exchange =
+                self.display.send(ticket, streaming, self.ws).await?;
 
-            // Run the request
-            let exchange = ticket.send().await?;
+            // If authentication was rejected, refresh upstream requests and
+            // try once more
+            if exchange.should_reauthenticate(&template_context.collection) {
+                template_context.reauthenticate();
+                let ticket = http_engine
+                    .build(retry_seed, &template_context)
+                    .await
+                    .map_err(build_error)?;
+                self.display.write_request(ticket.record());
+                exchange =
+                    self.display.send(ticket, streaming, self.ws).await?;
+            }
             if self.persist {
                 // Error here shouldn't be propagated, just logged
                 let _ = database.insert_exchange(&exchange).traced();
             }
+            // The response may have come from somewhere it shouldn't have.
+            // This doesn't fail the command, but it's shown at any verbosity
+            for violation in exchange.pin_violations(
+                exchange.request.profile_id.as_ref().and_then(|id| {
+                    template_context.collection.profiles.get(id)
+                }),
+            ) {
+                eprintln!("Warning: {violation}");
+            }
+            let assertions = template_context
+                .collection
+                .recipes
+                .get_recipe(&exchange.request.recipe_id)
+                .and_then(|recipe| recipe.assert.as_ref())
+                .map(|assertions| exchange.check_assertions(assertions))
+                .unwrap_or_default();
             let status = exchange.response.status;
 
-            self.display.write_response(&exchange.response)?;
+            // A downloaded body is already in the output file
+            if !streaming && !downloaded {
+                self.display.write_response(&exchange.response)?;
+            }
 
-            if self.exit_status && status.as_u16() >= 400 {
+            if !assertions.passed() {
+                // Unlike error statuses, failures are always reported. The
+                // exit code alone doesn't say which check failed
+                if error_format == ErrorFormat::Text {
+                    for failure in &assertions.failures {
+                        eprintln!("Assertion failed: {failure}");
+                    }
+                } else {
+                    ErrorReport {
+                        code: ErrorCode::Assertion,
+                        message: tr!(
+                            "cli-error-assertion",
+                            failed = assertions.failures.len(),
+                            total = assertions.total
+                        ),
+                        causes: assertions
+                            .failures
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect(),
+                    }
+                    .print();
+                }
+                Ok(ExitCode::from(ASSERTION_EXIT_CODE))
+            } else if self.exit_status && status.as_u16() >= 400 {
+                // Text output stays quiet; the exit code says enough. Scripts
+                // parsing errors need a report for every failure though
+                if error_format == ErrorFormat::Json {
+                    ErrorReport {
+                        code: ErrorCode::Http,
+                        message: tr!("cli-error-status", status = status),
+                        causes: Vec::new(),
+                    }
+                    .print();
+                }
                 Ok(ExitCode::from(HTTP_ERROR_EXIT_CODE))
             } else {
                 Ok(ExitCode::SUCCESS)
@@ -286,6 +439,26 @@ impl Subcommand for RequestCommand {
     }
 }
 
+/// Stream the lines of stdin, without their line endings. The stream ends
+/// when stdin does, or if it can't be read
+fn stdin_lines() -> impl Stream<Item = Bytes> {
+    let lines = BufReader::new(tokio::io::stdin()).lines();
+    stream::unfold(lines, |mut lines| async move {
+        let line = lines.next_line().await.traced().ok().flatten()?;
+        Some((Bytes::from(line), lines))
+    })
+}
+
+/// Convert a build error for display. If the build failed because triggered
+/// requests are disabled, replace it with a custom error message
+fn build_error(error: RequestBuildError) -> anyhow::Error {
+    if error.has_trigger_disabled_error() {
+        anyhow::Error::from(error.error).context(tr!("cli-dry-run-trigger"))
+    } else {
+        error.error.into()
+    }
+}
+
 impl BuildRequestCommand {
     /// Get all the components needed to build a request for the recipe selected
     /// by this command. The returned values can be used to build the request
@@ -309,28 +482,15 @@ impl BuildRequestCommand {
         RequestSeed,
         TemplateContext,
     )> {
-        let collection_file = global.collection_file()?;
-        let config = Config::load()?;
-        let collection = collection_file.load()?;
-        let database = Database::load()?.into_collection(&collection_file)?;
-        database.set_name(&collection);
-        let http_engine = HttpEngine::new(&config.http);
-
-        // Validate profile ID, so we can provide a good error if it's invalid
-        if let Some(profile_id) = &self.profile {
-            collection.profiles.get(profile_id).ok_or_else(|| {
-                anyhow!(
-                    "No profile with ID `{profile_id}`; options are: {}",
-                    collection.profiles.keys().format(", ")
-                )
-            })?;
-        }
-
-        // Fall back to default profile if defined in the collection
-        let selected_profile = self.profile.or_else(|| {
-            let default_profile = collection.default_profile()?;
-            Some(default_profile.id.clone())
-        });
+        let (database, http_engine, template_context) = build_context(
+            global,
+            self.profile,
+            self.toggles,
+            self.overrides,
+            self.params,
+            self.render_mode,
+            trigger_dependencies,
+        )?;
 
         // Build the request
         let authentication = match (self.basic, self.bearer) {
@@ -342,35 +502,197 @@ impl BuildRequestCommand {
                 unreachable!("--basic and --bearer are mutually exclusive")
             }
         };
-        let recipe = collection.recipes.try_get_recipe(&self.recipe_id)?;
+        let recipe = template_context
+            .collection
+            .recipes
+            .try_get_recipe(&self.recipe_id)?;
+        for param in template_context.params.keys() {
+            if !recipe.params.contains_key(param) {
+                bail!(tr!(
+                    "cli-unknown-param",
+                    param = param,
+                    recipe = recipe.id.to_string(),
+                    options = recipe.params.keys().format(", "),
+                ));
+            }
+        }
         let build_options = BuildOptions {
             url: self.url,
             authentication,
             headers: IndexMap::from_iter(self.header),
             body: self.body,
+            graphql_variables: self.variables,
+            graphql_introspection: false,
             query_parameters: get_query_parameters(recipe, self.query),
             form_fields: IndexMap::from_iter(self.form),
-        };
-        let template_context = TemplateContext {
-            selected_profile,
-            collection: collection.into(),
-            http_provider: Box::new(CliHttpProvider {
-                database: database.clone(),
-                http_engine: http_engine.clone(),
-                trigger_dependencies,
-            }),
-            overrides: IndexMap::from_iter(self.overrides),
-            prompter: Box::new(CliPrompter),
-            show_sensitive: true,
-            root_dir: collection_file.parent().to_owned(),
-            state: Default::default(),
+            link: None,
+            websocket: false,
         };
         let seed = RequestSeed::new(self.recipe_id, build_options);
         Ok((database, http_engine, seed, template_context))
     }
 }
 
+/// Load the collection, config, and database, and build a template context
+/// for rendering requests. Shared by every subcommand that sends requests.
+///
+/// ## Parameters
+///
+/// - `profile`: Profile to render with. Falls back to the collection's default
+///   profile
+/// - `toggles`: Toggles to enable on top of the profile
+/// - `overrides`: Profile field overrides
+/// - `params`: Recipe parameter values
+/// - `render_mode`: Override for the `render_mode` of every recipe
+/// - `trigger_dependencies`: Whether chained requests can be executed if
+///   their triggers apply
+pub fn build_context(
+    global: GlobalArgs,
+    profile: Option<ProfileId>,
+    toggles: Vec<ToggleId>,
+    overrides: Vec<(String, Template)>,
+    params: Vec<(String, Template)>,
+    render_mode: Option<RenderMode>,
+    trigger_dependencies: bool,
+) -> anyhow::Result<(CollectionDatabase, HttpEngine, TemplateContext)> {
+    let collection_file = global.collection_file()?;
+    let config = Config::load()?;
+    i18n::set_locale(config.locale.as_deref());
+    let collection = collection_file.load()?;
+    let database = Database::load()?
+        .into_collection(&collection_file)?
+        .with_session(global.session);
+    database.set_name(&collection);
+    let http_engine = HttpEngine::new(&config.http);
+    let sandboxed = !collection_file
+        .is_trusted(config.trusted_directories.as_deref(), &database);
+
+    // Validate profile ID, so we can provide a good error if it's invalid
+    if let Some(profile_id) = &profile {
+        collection.profiles.get(profile_id).ok_or_else(|| {
+            anyhow!(tr!(
+                "cli-unknown-profile",
+                profile = profile_id,
+                options = collection.profiles.keys().format(", "),
+            ))
+        })?;
+    }
+
+    for toggle_id in &toggles {
+        collection.toggles.get(toggle_id).ok_or_else(|| {
+            anyhow!(tr!(
+                "cli-unknown-toggle",
+                toggle = toggle_id,
+                options = collection.toggles.keys().format(", "),
+            ))
+        })?;
+    }
+
+    // Fall back to default profile if defined in the collection
+    let selected_profile = profile.or_else(|| {
+        let default_profile = collection.default_profile()?;
+        Some(default_profile.id.clone())
+    });
+
+    let template_context = TemplateContext {
+        selected_profile,
+        enabled_toggles: toggles,
+        collection: collection.into(),
+        http_provider: Box::new(CliHttpProvider {
+            database: database.clone(),
+            http_engine: http_engine.clone(),
+            trigger_dependencies,
+        }),
+        overrides: IndexMap::from_iter(overrides),
+        prompter: Box::new(CliPrompter),
+        show_sensitive: true,
+        root_dir: collection_file.parent().to_owned(),
+        sandboxed,
+        auditor: Auditor::new(&config, database.clone()),
+        state: Default::default(),
+        command_cache: Default::default(),
+        captures: Default::default(),
+        render_mode,
+        oauth2_tokens: TokenStore::new(database.clone()),
+        cookies: CookieJar::new(database.clone()),
+        params: IndexMap::from_iter(params),
+        prompt_params: false,
+        prompt_missing: false,
+    };
+    Ok((database, http_engine, template_context))
+}
+
 impl DisplayExchangeCommand {
+    /// Run the request. In streaming mode, the response is written as it's
+    /// received. If `stdin_messages` is enabled, each line of stdin is sent
+    /// as a WebSocket message.
+    async fn send(
+        &self,
+        ticket: RequestTicket,
+        streaming: bool,
+        stdin_messages: bool,
+    ) -> anyhow::Result<Exchange> {
+        if streaming {
+            self.send_streaming(ticket, stdin_messages).await
+        } else if let Some(path) = self.download_path(&ticket) {
+            self.download(ticket, path).await
+        } else {
+            Ok(ticket.send().await?)
+        }
+    }
+
+    /// Get the file that the response body should be streamed into. `None` if
+    /// the body is going to stdout, or it's a gRPC response, whose raw body
+    /// isn't useful.
+    fn download_path(&self, ticket: &RequestTicket) -> Option<&Path> {
+        self.output
+            .as_deref()
+            .filter(|path| *path != Path::new("-") && !ticket.is_grpc())
+    }
+
+    /// Send a request, writing the response body straight to a file as it's
+    /// received. If stderr is a terminal, show progress there.
+    async fn download(
+        &self,
+        ticket: RequestTicket,
+        path: &Path,
+    ) -> anyhow::Result<Exchange> {
+        let show_progress = io::stderr().is_terminal();
+        let mut last_draw: Option<Instant> = None;
+        let mut received = 0;
+        let on_response = |status, headers: &HeaderMap| {
+            self.write_response_metadata(status, headers);
+        };
+        let on_progress = |progress: DownloadProgress| {
+            received = progress.received;
+            // Redrawing for every chunk would flood the terminal
+            if show_progress
+                && last_draw
+                    .is_none_or(|last| last.elapsed() >= PROGRESS_INTERVAL)
+            {
+                eprint!("\r\x1b[2K{progress}");
+                last_draw = Some(Instant::now());
+            }
+        };
+        let result = ticket.download(path, on_response, on_progress).await;
+        if show_progress && last_draw.is_some() {
+            // Clear the progress line, so it doesn't run into what's next
+            eprint!("\r\x1b[2K");
+        }
+        let exchange = result?;
+        if show_progress {
+            eprintln!(
+                "{}",
+                tr!(
+                    "cli-download-complete",
+                    size = format_byte_size(received as usize),
+                    path = path.display().to_string(),
+                )
+            );
+        }
+        Ok(exchange)
+    }
+
     /// Print request details to stderr
     pub fn write_request(&self, request: &RequestRecord) {
         // The request is entirely hidden unless verbose mode is enabled
@@ -395,19 +717,96 @@ impl DisplayExchangeCommand {
         &self,
         response: &ResponseRecord,
     ) -> anyhow::Result<()> {
-        // Print metadata
+        self.write_response_metadata(response.status, &response.headers);
+
+        let (mut output, allow_binary) = self.open_output()?;
+        if response.body.text().is_none() && !allow_binary {
+            eprintln!("{BINARY_WARNING}");
+        } else {
+            output.write_all(response.body.bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Send a request, writing the response body to the user's designated
+    /// output as it's received instead of waiting for the entire body. For
+    /// WebSocket requests, if `stdin_messages` is enabled, each line of stdin
+    /// is sent as a message until stdin ends.
+    pub async fn send_streaming(
+        &self,
+        ticket: RequestTicket,
+        stdin_messages: bool,
+    ) -> anyhow::Result<Exchange> {
+        let (mut output, allow_binary) = self.open_output()?;
+        let mut is_first_chunk = true;
+        let mut write_body = true;
+        let mut write_error = None;
+        let on_response = |status, headers: &HeaderMap| {
+            self.write_response_metadata(status, headers);
+        };
+        let on_chunk = |chunk: &[u8]| {
+            // We can't know if the whole body is text until it's done,
+            // so just check the first chunk. A chunk may end in the
+            // middle of a multi-byte character, so only invalid bytes
+            // count as binary.
+            if mem::take(&mut is_first_chunk)
+                && !allow_binary
+                && std::str::from_utf8(chunk)
+                    .is_err_and(|error| error.error_len().is_some())
+            {
+                eprintln!("{BINARY_WARNING}");
+                write_body = false;
+            }
+            if !write_body {
+                // Keep loading so the full body can be persisted
+                return ControlFlow::Continue(());
+            }
+
+            match output.write_all(chunk).and_then(|()| output.flush()) {
+                Ok(()) => ControlFlow::Continue(()),
+                // The reader went away (e.g. piped to `head`), so
+                // there's nothing more to do
+                Err(error) if error.kind() == io::ErrorKind::BrokenPipe => {
+                    ControlFlow::Break(())
+                }
+                Err(error) => {
+                    write_error = Some(error);
+                    ControlFlow::Break(())
+                }
+            }
+        };
+        let exchange = if stdin_messages {
+            ticket
+                .send_websocket(stdin_lines(), on_response, on_chunk)
+                .await?
+        } else {
+            ticket.send_streaming(on_response, on_chunk).await?
+        };
+        if let Some(error) = write_error {
+            return Err(anyhow::Error::from(error)
+                .context("Error writing response body"));
+        }
+        Ok(exchange)
+    }
+
+    /// Print response status and headers to stderr, if verbose mode is enabled
+    fn write_response_metadata(&self, status: StatusCode, headers: &HeaderMap) {
         if self.verbose {
             eprintln!();
-            eprintln!("< {}", response.status);
-            for (header, value) in &response.headers {
+            eprintln!("< {status}");
+            for (header, value) in headers {
                 eprintln!("< {}: {}", header, MaybeStr(value.as_bytes()));
             }
         }
+    }
 
+    /// Open the output that the response body should be written to. Also
+    /// return a flag indicating if binary content can be written to it.
+    fn open_output(&self) -> anyhow::Result<(Box<dyn Write>, bool)> {
         // By default we won't print binary to the terminal, but the user can
         // override this with `--output -`. We will happily write binary to a
         // file though
-        let (mut output, allow_binary) = if let Some(path) = &self.output {
+        let output = if let Some(path) = &self.output {
             let output: Box<dyn Write> = if path == Path::new("-") {
                 Box::new(io::stdout())
             } else {
@@ -430,17 +829,7 @@ impl DisplayExchangeCommand {
             let allow_binary = !stdout.is_terminal();
             (Box::new(stdout) as Box<dyn Write>, allow_binary)
         };
-
-        if response.body.text().is_none() && !allow_binary {
-            eprintln!(
-                "Response body is not text. Binary output can mess up your \
-                terminal. Pass `--output -` if you're sure you want to print \
-                the output, or consider `--output <FILE>` to save to a file."
-            );
-        } else {
-            output.write_all(response.body.bytes())?;
-        }
-        Ok(())
+        Ok(output)
     }
 }
 
@@ -564,7 +953,7 @@ impl Prompter for CliPrompter {
 
 /// Parse a single key=value pair for a profile override. The `=` must be
 /// present. Profile fields cannot be omitted.
-fn parse_profile_override(
+pub fn parse_profile_override(
     s: &str,
 ) -> Result<(String, Template), anyhow::Error> {
     let (key, value) = s
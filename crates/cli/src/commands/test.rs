@@ -0,0 +1,446 @@
+use crate::{
+    GlobalArgs, Subcommand,
+    commands::request::{
+        ASSERTION_EXIT_CODE, build_context, parse_profile_override,
+    },
+    completions::{complete_profile, complete_recipe, complete_toggle},
+};
+use anyhow::{Context, bail};
+use clap::{Parser, ValueHint};
+use indexmap::IndexSet;
+use itertools::Itertools;
+use serde::Serialize;
+use slumber_core::{
+    collection::{Collection, ProfileId, RecipeId, ToggleId},
+    database::CollectionDatabase,
+    http::{BuildOptions, Exchange, HttpEngine, RequestSeed},
+    render::TemplateContext,
+};
+use slumber_template::Template;
+use slumber_util::{ResultTraced, tr};
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::Instant,
+};
+
+/// Run a set of recipes as a test suite
+///
+/// Each selected recipe is sent in order, and its response is checked against
+/// the recipe's `assert` field. Recipes can be selected by ID, by tag, or both.
+/// If neither is given, every recipe with at least one tag is run. Requests
+/// share a session, so upstream responses and captured values are reused
+/// between recipes. Exits with code 3 if any test fails.
+///
+/// EXAMPLES:
+///
+/// Run every recipe tagged `smoke`:
+///
+///   slumber test --tag smoke
+///
+/// Run two recipes and write a report for CI:
+///
+///   slumber test login get_user --junit report.xml
+#[derive(Clone, Debug, Parser)]
+#[clap(verbatim_doc_comment)]
+pub struct TestCommand {
+    /// IDs of recipes to run, in order
+    #[clap(add = complete_recipe())]
+    recipe_ids: Vec<RecipeId>,
+
+    /// Run every recipe with this tag. Pass this flag multiple times to run
+    /// recipes with any of the tags. Recipes selected by ID run first.
+    #[clap(long = "tag", value_hint = ValueHint::Other, value_name = "tag")]
+    tags: Vec<String>,
+
+    /// ID of the profile to pull template values from. If omitted and the
+    /// collection has default profile defined, use that profile. Otherwise,
+    /// profile data will not be available.
+    #[clap(long = "profile", short, add = complete_profile())]
+    profile: Option<ProfileId>,
+
+    /// ID of a toggle to enable on top of the profile. To enable multiple
+    /// toggles, pass this flag multiple times.
+    #[clap(long = "toggle", short = 't', add = complete_toggle())]
+    toggles: Vec<ToggleId>,
+
+    /// Override the value of a profile field (format: `field=value`)
+    #[clap(
+        long = "override",
+        short = 'o',
+        value_parser = parse_profile_override,
+        value_hint = ValueHint::Other, // Disable completions
+        value_name = "field=value",
+    )]
+    overrides: Vec<(String, Template)>,
+
+    /// Stop after the first test that doesn't pass
+    #[clap(long)]
+    fail_fast: bool,
+
+    /// Persist each request to Slumber's history database
+    #[clap(long)]
+    persist: bool,
+
+    /// Write a JUnit XML report to a file
+    #[clap(long, value_name = "path")]
+    junit: Option<PathBuf>,
+
+    /// Write a JSON report to a file
+    #[clap(long, value_name = "path")]
+    json: Option<PathBuf>,
+}
+
+impl Subcommand for TestCommand {
+    async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
+        let (database, http_engine, mut template_context) = build_context(
+            global,
+            self.profile,
+            self.toggles,
+            self.overrides,
+            Vec::new(),
+            None,
+            true,
+        )?;
+        let recipe_ids = select_recipes(
+            &template_context.collection,
+            self.recipe_ids,
+            &self.tags,
+        )?;
+
+        let start = Instant::now();
+        let mut results = Vec::new();
+        for recipe_id in recipe_ids {
+            let result = run_test(
+                &http_engine,
+                &mut template_context,
+                &database,
+                recipe_id,
+                self.persist,
+            )
+            .await;
+            result.print();
+            let passed = result.passed();
+            results.push(result);
+            if self.fail_fast && !passed {
+                break;
+            }
+        }
+        let report = TestReport::new(results, start);
+        report.print_summary();
+
+        let suite_name = template_context
+            .collection
+            .name
+            .as_deref()
+            .unwrap_or("slumber");
+        if let Some(path) = &self.junit {
+            write_report(path, &report.to_junit(suite_name))?;
+        }
+        if let Some(path) = &self.json {
+            let json = serde_json::to_string_pretty(&report)
+                .expect("Report is serializable");
+            write_report(path, &json)?;
+        }
+
+        if report.passed == report.tests {
+            Ok(ExitCode::SUCCESS)
+        } else {
+            Ok(ExitCode::from(ASSERTION_EXIT_CODE))
+        }
+    }
+}
+
+/// Get the recipes to run: recipes given by ID in the given order, followed by
+/// all recipes with any of the given tags, in collection order. With no IDs or
+/// tags, select every tagged recipe.
+fn select_recipes(
+    collection: &Collection,
+    recipe_ids: Vec<RecipeId>,
+    tags: &[String],
+) -> anyhow::Result<IndexSet<RecipeId>> {
+    for recipe_id in &recipe_ids {
+        // Fail before sending anything
+        collection.recipes.try_get_recipe(recipe_id)?;
+    }
+    let all_tagged = recipe_ids.is_empty() && tags.is_empty();
+    let tagged = collection
+        .recipes
+        .iter()
+        .filter_map(|(_, node)| node.recipe())
+        .filter(|recipe| {
+            if all_tagged {
+                !recipe.tags.is_empty()
+            } else {
+                recipe.tags.iter().any(|tag| tags.contains(tag))
+            }
+        })
+        .map(|recipe| recipe.id.clone());
+    let selected: IndexSet<RecipeId> =
+        recipe_ids.into_iter().chain(tagged).collect();
+
+    if selected.is_empty() {
+        if all_tagged {
+            bail!("No recipes have tags; pass recipe IDs or `--tag` to select");
+        }
+        bail!(tr!(
+            "cli-test-no-tagged",
+            count = tags.len(),
+            tags = tags.iter().format(", ")
+        ));
+    }
+    Ok(selected)
+}
+
+/// Send one recipe's request and check its assertions
+async fn run_test(
+    http_engine: &HttpEngine,
+    template_context: &mut TemplateContext,
+    database: &CollectionDatabase,
+    recipe_id: RecipeId,
+    persist: bool,
+) -> TestResult {
+    let start = Instant::now();
+    let outcome =
+        match send(http_engine, template_context, recipe_id.clone()).await {
+            Ok(exchange) => {
+                if persist {
+                    // Error here shouldn't be propagated, just logged
+                    let _ = database.insert_exchange(&exchange).traced();
+                }
+                let status = exchange.response.status.as_u16();
+                let results = template_context
+                    .collection
+                    .recipes
+                    .get_recipe(&recipe_id)
+                    .and_then(|recipe| recipe.assert.as_ref())
+                    .map(|assertions| exchange.check_assertions(assertions))
+                    .unwrap_or_default();
+                if results.passed() {
+                    TestOutcome::Passed { status }
+                } else {
+                    TestOutcome::Failed {
+                        status,
+                        failures: results
+                            .failures
+                            .iter()
+                            .map(ToString::to_string)
+                            .collect(),
+                    }
+                }
+            }
+            Err(error) => TestOutcome::Error {
+                error: format!("{error:#}"),
+            },
+        };
+    TestResult {
+        recipe: recipe_id,
+        duration_ms: start.elapsed().as_millis() as u64,
+        outcome,
+    }
+}
+
+/// Build and send a recipe's request. If authentication is rejected, refresh
+/// upstream requests and try once more.
+async fn send(
+    http_engine: &HttpEngine,
+    template_context: &mut TemplateContext,
+    recipe_id: RecipeId,
+) -> anyhow::Result<Exchange> {
+    let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+    let ticket = http_engine
+        .build(seed.clone(), template_context)
+        .await
+        .map_err(|error| error.error)?;
+    let exchange = ticket.send().await?;
+    if exchange.should_reauthenticate(&template_context.collection) {
+        template_context.reauthenticate();
+        let ticket = http_engine
+            .build(seed, template_context)
+            .await
+            .map_err(|error| error.error)?;
+        Ok(ticket.send().await?)
+    } else {
+        Ok(exchange)
+    }
+}
+
+fn write_report(path: &Path, content: &str) -> anyhow::Result<()> {
+    fs::write(path, content).with_context(|| {
+        format!("Error writing report to `{}`", path.display())
+    })
+}
+
+/// Results of a whole test run. This is also the format of the JSON report.
+#[derive(Debug, Serialize)]
+struct TestReport {
+    tests: usize,
+    passed: usize,
+    failed: usize,
+    errors: usize,
+    duration_ms: u64,
+    results: Vec<TestResult>,
+}
+
+impl TestReport {
+    fn new(results: Vec<TestResult>, start: Instant) -> Self {
+        let count = |f: fn(&TestOutcome) -> bool| {
+            results.iter().filter(|result| f(&result.outcome)).count()
+        };
+        Self {
+            tests: results.len(),
+            passed: count(|outcome| {
+                matches!(outcome, TestOutcome::Passed { .. })
+            }),
+            failed: count(|outcome| {
+                matches!(outcome, TestOutcome::Failed { .. })
+            }),
+            errors: count(|outcome| {
+                matches!(outcome, TestOutcome::Error { .. })
+            }),
+            duration_ms: start.elapsed().as_millis() as u64,
+            results,
+        }
+    }
+
+    /// Print the counts of each outcome. The summary goes to stderr, like
+    /// other metadata, so stdout only has per-test results.
+    fn print_summary(&self) {
+        eprintln!(
+            "{}",
+            tr!(
+                "cli-test-summary",
+                tests = self.tests,
+                passed = self.passed,
+                failed = self.failed,
+                errors = self.errors,
+                duration = self.duration_ms
+            )
+        );
+    }
+
+    /// Render as JUnit XML, the de facto standard format for CI test reports
+    fn to_junit(&self, suite_name: &str) -> String {
+        let suite_name = escape_xml(suite_name);
+        let time = seconds(self.duration_ms);
+        let mut xml = String::from(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        xml.push('\n');
+        let _ = writeln!(
+            xml,
+            r#"<testsuites name="{suite_name}" tests="{}" failures="{}" errors="{}" time="{time}">"#,
+            self.tests, self.failed, self.errors,
+        );
+        let _ = writeln!(
+            xml,
+            r#"  <testsuite name="{suite_name}" tests="{}" failures="{}" errors="{}" time="{time}">"#,
+            self.tests, self.failed, self.errors,
+        );
+        for result in &self.results {
+            let _ = write!(
+                xml,
+                r#"    <testcase name="{}" classname="{suite_name}" time="{}""#,
+                escape_xml(&result.recipe),
+                seconds(result.duration_ms),
+            );
+            match &result.outcome {
+                TestOutcome::Passed { .. } => xml.push_str(" />\n"),
+                TestOutcome::Failed { status, failures } => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <failure message=\"{}\">{}</failure>\n    \
+                        </testcase>",
+                        escape_xml(&tr!(
+                            "cli-test-failed",
+                            count = failures.len(),
+                            status = status
+                        )),
+                        escape_xml(&failures.join("\n")),
+                    );
+                }
+                TestOutcome::Error { error } => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n      <error message=\"{}\" />\n    </testcase>",
+                        escape_xml(error),
+                    );
+                }
+            }
+        }
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+/// Result of running a single recipe
+#[derive(Debug, Serialize)]
+struct TestResult {
+    recipe: RecipeId,
+    duration_ms: u64,
+    #[serde(flatten)]
+    outcome: TestOutcome,
+}
+
+impl TestResult {
+    fn passed(&self) -> bool {
+        matches!(self.outcome, TestOutcome::Passed { .. })
+    }
+
+    /// Print a line for the result, followed by the reason if it didn't pass
+    fn print(&self) {
+        let Self {
+            recipe,
+            duration_ms,
+            outcome,
+        } = self;
+        match outcome {
+            TestOutcome::Passed { .. } => {
+                println!("PASS  {recipe} ({duration_ms}ms)");
+            }
+            TestOutcome::Failed { failures, .. } => {
+                println!("FAIL  {recipe} ({duration_ms}ms)");
+                for failure in failures {
+                    println!("      {failure}");
+                }
+            }
+            TestOutcome::Error { error } => {
+                println!("ERROR {recipe} ({duration_ms}ms)");
+                println!("      {error}");
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+enum TestOutcome {
+    /// Response passed all assertions (or the recipe has none)
+    Passed { status: u16 },
+    /// Response failed at least one assertion
+    Failed { status: u16, failures: Vec<String> },
+    /// Request couldn't be built or sent
+    Error { error: String },
+}
+
+/// Format milliseconds as seconds, for JUnit `time` attributes
+fn seconds(ms: u64) -> String {
+    format!("{}.{:03}", ms / 1000, ms % 1000)
+}
+
+/// Escape text for use in XML content or a quoted attribute
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
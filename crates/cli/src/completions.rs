@@ -11,7 +11,9 @@ use clap_complete::{
     engine::ValueCompleter,
 };
 use slumber_core::{
-    collection::{Collection, CollectionError, CollectionFile, ProfileId},
+    collection::{
+        Collection, CollectionError, CollectionFile, ProfileId, ToggleId,
+    },
     database::Database,
 };
 use std::{ffi::OsStr, ops::Deref};
@@ -31,6 +33,20 @@ pub fn complete_profile() -> ArgValueCompleter {
     })
 }
 
+/// Build a completer for toggle IDs from the default collection
+pub fn complete_toggle() -> ArgValueCompleter {
+    ArgValueCompleter::new(|current: &OsStr| {
+        load_collection()
+            .map(|collection| {
+                get_candidates(
+                    collection.toggles.keys().map(ToggleId::to_string),
+                    current,
+                )
+            })
+            .unwrap_or_default()
+    })
+}
+
 /// Build a completer for recipe IDs from the default collection
 pub fn complete_recipe() -> ArgValueCompleter {
     ArgValueCompleter::new(|current: &OsStr| {
@@ -130,9 +146,12 @@ pub fn complete_log_level() -> ArgValueCompleter {
 /// should be able to look for a --file arg in the command and use that path,
 /// but clap doesn't support that yet
 /// <https://github.com/clap-rs/clap/issues/5784>
+///
+/// Completions only need IDs, so template parsing is deferred to keep them
+/// fast on large collections.
 fn load_collection() -> Result<Collection, CollectionError> {
     let collection_file = CollectionFile::new(None)?;
-    collection_file.load()
+    collection_file.load_deferred()
 }
 
 /// Get a completer for YAML files
@@ -210,7 +229,9 @@ mod tests {
                 "urlencoded",
                 "multipart",
                 "chained",
+                "reauthenticate",
                 "override",
+                "asserted",
             ]
         );
     }
@@ -249,7 +270,9 @@ mod tests {
                 "urlencoded",
                 "multipart",
                 "chained",
+                "reauthenticate",
                 "override",
+                "asserted",
                 &id2.to_string(),
                 &id1.to_string()
             ]
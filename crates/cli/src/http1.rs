@@ -0,0 +1,179 @@
+//! Minimal HTTP/1.1 request parsing, for the subcommands that run a local
+//! server (`echo` and `capture`). This only handles what those commands need:
+//! one request per connection, with `content-length` or chunked bodies.
+
+use anyhow::{Context, anyhow, bail};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt,
+};
+
+/// Largest request body we'll read. Bodies are buffered in memory, so this
+/// stops a client from making us allocate whatever size it claims.
+const MAX_BODY_SIZE: usize = 256 * 1024 * 1024;
+
+/// An HTTP request, read off the wire with as little interpretation as
+/// possible
+#[derive(Clone, Debug)]
+pub struct RawRequest {
+    pub method: String,
+    /// Request target, exactly as given in the request line. For a server
+    /// this is typically the path and query. For a proxy, it's an absolute URL
+    /// (or `host:port` for `CONNECT`).
+    pub target: String,
+    pub version: String,
+    /// `(name, value)` pairs, in the order they were received
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RawRequest {
+    /// Read a request from a stream. If the client asks for permission to send
+    /// the body (`expect: 100-continue`), it's granted over the same stream.
+    pub async fn read(
+        reader: &mut (impl AsyncBufRead + AsyncWrite + Unpin),
+    ) -> anyhow::Result<Self> {
+        let request_line = read_line(reader).await?;
+        let mut parts = request_line.split(' ');
+        let (Some(method), Some(target), Some(version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            bail!("Invalid request line `{request_line}`");
+        };
+        let mut request = Self {
+            method: method.to_owned(),
+            target: target.to_owned(),
+            version: version.to_owned(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        };
+
+        loop {
+            let line = read_line(reader).await?;
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| anyhow!("Invalid header `{line}`"))?;
+            request
+                .headers
+                .push((name.to_owned(), value.trim().to_owned()));
+        }
+
+        // Clients can wait for permission before sending a large body
+        if request
+            .header("expect")
+            .is_some_and(|value| value.eq_ignore_ascii_case("100-continue"))
+        {
+            reader.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+        }
+
+        if request
+            .header("transfer-encoding")
+            .is_some_and(|value| value.to_ascii_lowercase().contains("chunked"))
+        {
+            request.body = read_chunked(reader).await?;
+        } else if let Some(length) = request.header("content-length") {
+            let length: usize = length.parse().with_context(|| {
+                format!("Invalid content-length `{length}`")
+            })?;
+            check_body_size(length)?;
+            request.body = vec![0; length];
+            reader.read_exact(&mut request.body).await?;
+        }
+
+        Ok(request)
+    }
+
+    /// Get the value of the first header with the given name, ignoring case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read a single line, without its line ending
+async fn read_line(
+    reader: &mut (impl AsyncBufRead + Unpin),
+) -> anyhow::Result<String> {
+    let mut line = Vec::new();
+    reader.read_until(b'\n', &mut line).await?;
+    if !line.ends_with(b"\n") {
+        bail!("Connection closed before end of request");
+    }
+    line.pop();
+    if line.ends_with(b"\r") {
+        line.pop();
+    }
+    Ok(String::from_utf8_lossy(&line).into_owned())
+}
+
+/// Read and decode a body with `transfer-encoding: chunked`
+async fn read_chunked(
+    reader: &mut (impl AsyncBufRead + Unpin),
+) -> anyhow::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line = read_line(reader).await?;
+        // Ignore chunk extensions
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .with_context(|| format!("Invalid chunk size `{size}`"))?;
+        if size == 0 {
+            break;
+        }
+        let start = body.len();
+        check_body_size(start.saturating_add(size))?;
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).await?;
+        read_line(reader).await?; // Trailing CRLF
+    }
+    // Skip trailers
+    while !read_line(reader).await?.is_empty() {}
+    Ok(body)
+}
+
+/// Fail if a body of the given size is over [MAX_BODY_SIZE]
+fn check_body_size(size: usize) -> anyhow::Result<()> {
+    if size > MAX_BODY_SIZE {
+        bail!(
+            "Request body of {size} bytes is over the limit of \
+            {MAX_BODY_SIZE} bytes"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_util::assert_err;
+    use tokio::io::{BufReader, duplex};
+
+    /// Bodies over the size limit are rejected before they're allocated
+    #[rstest]
+    #[case::content_length(format!(
+        "POST / HTTP/1.1\r\ncontent-length: {}\r\n\r\n",
+        MAX_BODY_SIZE + 1
+    ))]
+    #[case::chunked(format!(
+        "POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n\
+        5\r\nhello\r\n{:x}\r\n",
+        MAX_BODY_SIZE
+    ))]
+    #[case::chunk_overflow(
+        "POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n\
+        5\r\nhello\r\nffffffffffffffff\r\n"
+            .to_owned()
+    )]
+    #[tokio::test]
+    async fn test_read_body_too_large(#[case] request: String) {
+        let (mut client, server) = duplex(1024);
+        client.write_all(request.as_bytes()).await.unwrap();
+        let mut reader = BufReader::new(server);
+        assert_err!(RawRequest::read(&mut reader).await, "over the limit");
+    }
+}
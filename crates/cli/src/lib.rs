@@ -6,23 +6,28 @@
 
 mod commands;
 mod completions;
+mod http1;
 mod util;
 
-pub use util::print_error;
+pub use util::{ErrorFormat, print_error};
 
 #[cfg(feature = "import")]
 use crate::commands::import::ImportCommand;
 use crate::{
     commands::{
-        collection::CollectionCommand, config::ConfigCommand, db::DbCommand,
-        generate::GenerateCommand, new::NewCommand, request::RequestCommand,
+        audit::AuditCommand, capture::CaptureCommand,
+        collection::CollectionCommand, config::ConfigCommand,
+        cookies::CookiesCommand, db::DbCommand, echo::EchoCommand,
+        generate::GenerateCommand, keyring::KeyringCommand, new::NewCommand,
+        open::OpenCommand, request::RequestCommand, test::TestCommand,
     },
     completions::{complete_collection_path, complete_log_level},
 };
 use clap::{CommandFactory, Parser};
 use clap_complete::CompleteEnv;
+use slumber_config::Appearance;
 use slumber_core::collection::{CollectionError, CollectionFile};
-use slumber_util::paths;
+use slumber_util::{paths, tr};
 use std::{path::PathBuf, process::ExitCode};
 use tracing::level_filters::LevelFilter;
 
@@ -65,7 +70,11 @@ pub struct GlobalArgs {
     /// following files (in this order): slumber.yml, slumber.yaml,
     /// .slumber.yml, .slumber.yaml. If a directory is passed, apply the
     /// same search logic from the given directory rather than the current.
-    #[clap(long, short, add = complete_collection_path())]
+    ///
+    /// May also be a remote collection: an http(s):// URL, or a file in a git
+    /// repository as git+<repository>[#<ref>][:<path>]. Remote collections
+    /// are cached locally, and the cached copy is used if fetching fails.
+    #[clap(long, short, alias = "collection", add = complete_collection_path())]
     pub file: Option<PathBuf>,
 
     /// Set logging verbosity
@@ -89,6 +98,53 @@ pub struct GlobalArgs {
     #[clap(long)]
     pub print_log_path: bool,
 
+    /// Print the time spent in each phase of startup
+    ///
+    /// For the CLI, timings are printed to stderr. For the TUI, they are
+    /// written to the log file because the terminal is in use.
+    #[clap(long)]
+    pub profile_startup: bool,
+
+    /// Format for errors printed to stderr
+    ///
+    /// With `json`, each error is printed as a single JSON object with a
+    /// stable `code` field (e.g. `network` or `template`), so scripts can
+    /// react to specific classes of failure.
+    #[clap(long, value_enum, default_value_t = ErrorFormat::Text)]
+    pub error_format: ErrorFormat,
+
+    /// Select the light or dark variant of the TUI theme
+    ///
+    /// Available options are `light` and `dark`. If omitted, the appearance is
+    /// detected from the terminal's background color. This only has an effect
+    /// if the config defines `theme.light` or `theme.dark`.
+    #[clap(long)]
+    pub appearance: Option<Appearance>,
+
+    /// Use a named session, with its own cookie jar and OAuth2 token cache
+    ///
+    /// Sessions let you use multiple identities against the same API side by
+    /// side, e.g. by running one TUI per session. Request history is shared
+    /// between sessions. If omitted, the default session is used. The session
+    /// can't be changed while the TUI is running.
+    #[clap(long, value_name = "NAME")]
+    pub session: Option<String>,
+
+    /// Play a script of key presses into the TUI
+    ///
+    /// The script is a YAML file of steps, which are played alongside normal
+    /// terminal input. Use this for reproducible demos and bug reports. Only
+    /// applies to the TUI.
+    #[clap(long, value_name = "PATH")]
+    pub script: Option<PathBuf>,
+
+    /// Record key presses in the TUI to a script file
+    ///
+    /// The file can be played back later with `--script`. If it exists, it's
+    /// overwritten. Only applies to the TUI.
+    #[clap(long, value_name = "PATH")]
+    pub record: Option<PathBuf>,
+
     /// Test only: set the directory for the config, database, and log files
     #[cfg(debug_assertions)]
     #[clap(long, hide = true)]
@@ -99,7 +155,16 @@ impl GlobalArgs {
     /// Get the path to the active collection file. Return an error if there is
     /// no collection file present, or if the user specified an invalid file.
     fn collection_file(&self) -> Result<CollectionFile, CollectionError> {
-        CollectionFile::new(self.file.clone())
+        let file = CollectionFile::new(self.file.clone())?;
+        if let Some(error) =
+            file.remote().and_then(|remote| remote.error.as_ref())
+        {
+            eprintln!(
+                "{}",
+                tr!("cli-cached-collection", file = file, error = error)
+            );
+        }
+        Ok(file)
     }
 }
 
@@ -109,6 +174,12 @@ impl Default for GlobalArgs {
             file: None,
             log_level: LevelFilter::OFF,
             print_log_path: false,
+            profile_startup: false,
+            error_format: ErrorFormat::Text,
+            appearance: None,
+            session: None,
+            script: None,
+            record: None,
             #[cfg(debug_assertions)]
             data_dir: None,
         }
@@ -118,14 +189,21 @@ impl Default for GlobalArgs {
 /// A CLI subcommand
 #[derive(Clone, Debug, clap::Subcommand)]
 pub enum CliCommand {
+    Audit(AuditCommand),
+    Capture(CaptureCommand),
     Collection(CollectionCommand),
     Config(ConfigCommand),
+    Cookies(CookiesCommand),
     Db(DbCommand),
+    Echo(EchoCommand),
     Generate(GenerateCommand),
     #[cfg(feature = "import")]
     Import(ImportCommand),
+    Keyring(KeyringCommand),
     New(NewCommand),
+    Open(OpenCommand),
     Request(RequestCommand),
+    Test(TestCommand),
 }
 
 impl CliCommand {
@@ -133,7 +211,7 @@ impl CliCommand {
     pub async fn execute(self, global: GlobalArgs) -> anyhow::Result<ExitCode> {
         if global.print_log_path {
             let path = paths::log_file();
-            println!("Logging to {}", path.display());
+            println!("{}", tr!("cli-logging-to", path = path.display()));
         }
 
         // The --data-dir flag is used in integration tests to isolate files
@@ -143,14 +221,23 @@ impl CliCommand {
         }
 
         match self {
+            Self::Audit(command) => command.execute(global).await,
+            Self::Capture(command) => command.execute(global).await,
             Self::Collection(command) => command.execute(global).await,
             Self::Config(command) => command.execute(global).await,
+            Self::Cookies(command) => command.execute(global).await,
             Self::Db(command) => command.execute(global).await,
+            Self::Echo(command) => command.execute(global).await,
             Self::Generate(command) => command.execute(global).await,
             #[cfg(feature = "import")]
             Self::Import(command) => command.execute(global).await,
+            Self::Keyring(command) => command.execute(global).await,
             Self::New(command) => command.execute(global).await,
-            Self::Request(command) => command.execute(global).await,
+            Self::Open(command) => command.execute(global).await,
+            // This future is much larger than the rest because it holds the
+            // entire template context, so box it to keep this one small
+            Self::Request(command) => Box::pin(command.execute(global)).await,
+            Self::Test(command) => Box::pin(command.execute(global)).await,
         }
     }
 }
@@ -1,15 +1,59 @@
+use clap::ValueEnum;
 use serde::Serialize;
 use slumber_config::Config;
-use slumber_core::util::confirm;
+use slumber_core::{error::ErrorCode, util::confirm};
+use slumber_util::tr;
 use std::{error::Error, io, iter, path::Path, process::ExitCode};
 
+/// Format for errors printed to stderr
+#[derive(Copy, Clone, Debug, Default, PartialEq, ValueEnum)]
+pub enum ErrorFormat {
+    /// Human-readable error message, followed by each cause on its own line
+    #[default]
+    Text,
+    /// A single-line JSON object with the error code, message, and causes
+    Json,
+}
+
 /// Print an error chain to stderr
-pub fn print_error(error: &anyhow::Error) {
-    eprintln!("{error}");
-    error
-        .chain()
-        .skip(1)
-        .for_each(|cause| eprintln!("  {cause}"));
+pub fn print_error(error: &anyhow::Error, format: ErrorFormat) {
+    match format {
+        ErrorFormat::Text => {
+            eprintln!("{error}");
+            error
+                .chain()
+                .skip(1)
+                .for_each(|cause| eprintln!("  {cause}"));
+        }
+        ErrorFormat::Json => ErrorReport::new(error).print(),
+    }
+}
+
+/// JSON representation of an error, for `--error-format json`. The `code`
+/// field is stable and can be used by scripts to react to specific classes of
+/// failure. Messages are for humans and may change at any time.
+#[derive(Debug, Serialize)]
+pub struct ErrorReport {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Messages for each error in the chain of sources, outermost first
+    pub causes: Vec<String>,
+}
+
+impl ErrorReport {
+    fn new(error: &anyhow::Error) -> Self {
+        Self {
+            code: ErrorCode::from_error(&**error),
+            message: error.to_string(),
+            causes: error.chain().skip(1).map(ToString::to_string).collect(),
+        }
+    }
+
+    /// Print as a single line of JSON to stderr
+    pub fn print(&self) {
+        // Serializing a struct of strings can't fail
+        eprintln!("{}", serde_json::to_string(self).unwrap());
+    }
 }
 
 /// Print rows in a table
@@ -55,18 +99,16 @@ pub fn edit_and_validate<T, E: 'static + Error + Send + Sync>(
     path: &Path,
     validate: impl Fn() -> Result<T, E>,
 ) -> anyhow::Result<ExitCode> {
-    let editor = config.editor()?;
+    let editor = config.editor(path)?;
     loop {
         let status = editor.open(path).spawn()?.wait()?;
 
         // After editing, verify the file is valid. If not, offer to reopen
         if let Err(error) = validate() {
             // Convert to anyhow for display
-            print_error(&error.into());
-            if confirm(format!(
-                "{path} is invalid, would you like to reopen it?",
-                path = path.display(),
-            )) {
+            // Text only, because the user is interacting with us here
+            print_error(&error.into(), ErrorFormat::Text);
+            if confirm(tr!("cli-reopen-invalid", path = path.display())) {
                 continue;
             }
         }
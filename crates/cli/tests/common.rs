@@ -13,11 +13,20 @@ use std::{
 /// will be used for the database. Most tests can just ignore this.
 pub fn slumber() -> (Command, TempDir) {
     let data_dir = temp_dir();
+    let command = slumber_with_data_dir(&data_dir);
+    (command, data_dir)
+}
+
+/// Get a command to run Slumber with an existing data directory. Use this to
+/// run multiple commands against the same database.
+pub fn slumber_with_data_dir(data_dir: &Path) -> Command {
     let mut command = cargo_bin_cmd!("slumber_cli");
     command
         .current_dir(tests_dir())
-        .args(["--data-dir", data_dir.deref().to_str().unwrap()]);
-    (command, data_dir)
+        .args(["--data-dir", data_dir.to_str().unwrap()])
+        // Don't let the developer's locale leak into output
+        .env("LC_ALL", "C");
+    command
 }
 
 pub fn tests_dir() -> PathBuf {
@@ -0,0 +1,54 @@
+//! Test the `slumber audit` subcommand
+
+mod common;
+
+use crate::common::collection_file;
+use chrono::Utc;
+use slumber_config::TemplateSource;
+use slumber_core::{database::Database, render::AuditEvent};
+
+/// `slumber audit` lists events for the current collection, newest first
+#[test]
+fn test_audit() {
+    let (mut command, data_dir) = common::slumber();
+    let database = Database::from_directory(&data_dir)
+        .unwrap()
+        .into_collection(&collection_file())
+        .unwrap();
+    for (source, detail, preview) in [
+        (TemplateSource::Env, "HOME", false),
+        (TemplateSource::Command, "echo hello", true),
+        (TemplateSource::File, "/tmp/token.txt", false),
+    ] {
+        database
+            .insert_audit_event(&AuditEvent {
+                time: Utc::now(),
+                source,
+                detail: detail.into(),
+                preview,
+            })
+            .unwrap();
+    }
+
+    let output = command
+        .args(["audit", "--limit", "2"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    // Trim out the timestamps, which aren't deterministic
+    let lines = output
+        .lines()
+        .map(|line| line.split_once(' ').unwrap().1.trim())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "Source            Detail",
+            "file              /tmp/token.txt",
+            "command (preview) echo hello",
+        ]
+    );
+}
@@ -0,0 +1,422 @@
+//! Test the `slumber capture` subcommand
+
+mod common;
+
+use pretty_assertions::assert_eq;
+use reqwest::{Client, Proxy};
+use rstest::rstest;
+use serde_json::json;
+use serde_yaml::Value;
+use slumber_util::{TempDir, temp_dir};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    path::PathBuf,
+    process::{Child, ChildStderr, Command, Stdio},
+};
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+/// Requests sent through the proxy are forwarded to their destination, and
+/// each distinct endpoint is recorded as a recipe
+#[tokio::test]
+async fn test_capture() {
+    let host = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/users"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+        .mount(&host)
+        .await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/users"))
+        .respond_with(
+            ResponseTemplate::new(201)
+                .insert_header("x-id", "3")
+                .set_body_string("created"),
+        )
+        .mount(&host)
+        .await;
+
+    let mut proxy = CaptureProxy::start(&[]);
+    let client = proxy.client();
+
+    let response = client
+        .get(format!("{}/users?page=1&tag=a&tag=b", host.uri()))
+        .header("x-token", "abc")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "[]");
+
+    let response = client
+        .post(format!("{}/users", host.uri()))
+        .json(&json!({"name": "Frodo"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.headers()["x-id"], "3");
+    assert_eq!(response.text().await.unwrap(), "created");
+
+    // Same endpoint again; not recorded twice
+    client
+        .get(format!("{}/users?page=2", host.uri()))
+        .send()
+        .await
+        .unwrap();
+    proxy.wait_for_requests(3);
+
+    let collection: Value =
+        serde_yaml::from_str(&fs::read_to_string(&proxy.output).unwrap())
+            .unwrap();
+    let url = format!("{}/users", host.uri());
+    let expected: Value = serde_yaml::from_str(&format!(
+        "
+name: Captured Requests
+profiles: {{}}
+requests:
+  127.0.0.1:
+    name: 127.0.0.1
+    requests:
+      get_users:
+        name: GET /users
+        method: GET
+        url: {url}
+        query:
+          page: '1'
+          tag: [a, b]
+        headers:
+          x-token: '{{{{ api_key }}}}'
+          accept: '*/*'
+      post_users:
+        name: POST /users
+        method: POST
+        url: {url}
+        body:
+          type: json
+          data:
+            name: Frodo
+        headers:
+          accept: '*/*'
+"
+    ))
+    .unwrap();
+    assert_eq!(collection, expected);
+}
+
+/// Credentials are replaced with placeholders unless `--keep-credentials` is
+/// given. Basic and Bearer auth are mapped to `authentication`
+#[rstest]
+#[case::redacted(
+    &[],
+    "
+    basic:
+      authentication:
+        type: basic
+        username: '{{ username }}'
+        password: '{{ password }}'
+      headers:
+        x-api-key: '{{ api_key }}'
+    bearer:
+      authentication:
+        type: bearer
+        token: '{{ api_token }}'
+    digest:
+      headers:
+        authorization: '{{ authorization }}'
+    "
+)]
+#[case::kept(
+    &["--keep-credentials"],
+    "
+    basic:
+      authentication:
+        type: basic
+        username: user
+        password: hunter2
+      headers:
+        x-api-key: key
+        cookie: session=abc
+    bearer:
+      authentication:
+        type: bearer
+        token: abc
+    digest:
+      headers:
+        authorization: Digest username=\"user\"
+    "
+)]
+#[tokio::test]
+async fn test_capture_credentials(
+    #[case] args: &[&str],
+    #[case] expected: &str,
+) {
+    let host = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&host)
+        .await;
+    let mut proxy = CaptureProxy::start(args);
+    let client = proxy.client();
+
+    client
+        .get(format!("{}/basic", host.uri()))
+        .basic_auth("user", Some("hunter2"))
+        .header("x-api-key", "key")
+        .header("cookie", "session=abc")
+        .send()
+        .await
+        .unwrap();
+    client
+        .get(format!("{}/bearer", host.uri()))
+        .bearer_auth("abc")
+        .send()
+        .await
+        .unwrap();
+    client
+        .get(format!("{}/digest", host.uri()))
+        .header("authorization", "Digest username=\"user\"")
+        .send()
+        .await
+        .unwrap();
+    proxy.wait_for_requests(3);
+
+    let output = fs::read_to_string(&proxy.output).unwrap();
+    // The encoded header is never saved; it's decoded into `authentication`
+    assert!(!output.contains("dXNlcjpodW50ZXIy"), "{output}");
+    for secret in ["hunter2", "key", "abc"] {
+        assert_eq!(
+            output.contains(&format!(": {secret}")),
+            !args.is_empty(),
+            "`{secret}` in output:\n{output}"
+        );
+    }
+    let collection: Value = serde_yaml::from_str(&output).unwrap();
+    let recipes = &collection["requests"]["127.0.0.1"]["requests"];
+    let expected: Value = serde_yaml::from_str(expected).unwrap();
+    for (id, expected) in expected.as_mapping().unwrap() {
+        let recipe = &recipes[format!("get_{}", id.as_str().unwrap())];
+        assert_eq!(recipe["authentication"], expected["authentication"]);
+        let headers = recipe["headers"].as_mapping().unwrap();
+        if let Some(expected_headers) = expected["headers"].as_mapping() {
+            for (name, value) in expected_headers {
+                assert_eq!(&headers[name], value);
+            }
+        }
+        if args.is_empty() {
+            assert!(!headers.contains_key("cookie"));
+        }
+    }
+}
+
+/// Query parameters and form/JSON body fields that look like credentials are
+/// replaced with placeholders unless `--keep-credentials` is given
+#[rstest]
+#[case::redacted(
+    &[],
+    "
+    get_search:
+      query:
+        api_key: '{{ api_key }}'
+        q: frodo
+    post_login:
+      body:
+        type: form_urlencoded
+        data:
+          username: frodo
+          password: '{{ password }}'
+    post_tokens:
+      body:
+        type: json
+        data:
+          access_token: '{{ api_key }}'
+          nested:
+            client_secret: '{{ api_key }}'
+            expires_in: 3600
+          refresh_token: null
+    "
+)]
+#[case::kept(
+    &["--keep-credentials"],
+    "
+    get_search:
+      query:
+        api_key: key
+        q: frodo
+    post_login:
+      body:
+        type: form_urlencoded
+        data:
+          username: frodo
+          password: hunter2
+    post_tokens:
+      body:
+        type: json
+        data:
+          access_token: abc
+          nested:
+            client_secret: shh
+            expires_in: 3600
+          refresh_token: null
+    "
+)]
+#[tokio::test]
+async fn test_capture_credentials_query_body(
+    #[case] args: &[&str],
+    #[case] expected: &str,
+) {
+    let host = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&host)
+        .await;
+    let mut proxy = CaptureProxy::start(args);
+    let client = proxy.client();
+
+    client
+        .get(format!("{}/search?api_key=key&q=frodo", host.uri()))
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("{}/login", host.uri()))
+        .form(&[("username", "frodo"), ("password", "hunter2")])
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("{}/tokens", host.uri()))
+        .json(&json!({
+            "access_token": "abc",
+            "nested": {"client_secret": "shh", "expires_in": 3600},
+            "refresh_token": null,
+        }))
+        .send()
+        .await
+        .unwrap();
+    proxy.wait_for_requests(3);
+
+    let collection: Value =
+        serde_yaml::from_str(&fs::read_to_string(&proxy.output).unwrap())
+            .unwrap();
+    let recipes = &collection["requests"]["127.0.0.1"]["requests"];
+    let expected: Value = serde_yaml::from_str(expected).unwrap();
+    for (id, expected) in expected.as_mapping().unwrap() {
+        let recipe = &recipes[id.as_str().unwrap()];
+        for (field, value) in expected.as_mapping().unwrap() {
+            assert_eq!(&recipe[field.as_str().unwrap()], value, "{id:?}");
+        }
+    }
+}
+
+/// The client gets its response even if the request can't be recorded
+#[tokio::test]
+async fn test_capture_record_error() {
+    let host = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+        .mount(&host)
+        .await;
+    let mut proxy = CaptureProxy::start(&[]);
+    // Writing the output file will fail
+    fs::create_dir(&proxy.output).unwrap();
+
+    let response = proxy
+        .client()
+        .get(format!("{}/users", host.uri()))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().await.unwrap(), "ok");
+    proxy.wait_for_requests(1);
+}
+
+/// Requests that aren't in proxy form are rejected
+#[test]
+fn test_capture_not_proxied() {
+    let proxy = CaptureProxy::start(&[]);
+    let address = proxy.url.strip_prefix("http://").unwrap();
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream
+        .write_all(b"GET /users HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(
+        response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+        "{response}"
+    );
+    assert!(!proxy.output.exists());
+}
+
+/// A capture proxy running in a subprocess. Killed on drop
+struct CaptureProxy {
+    process: Child,
+    stderr: BufReader<ChildStderr>,
+    url: String,
+    output: PathBuf,
+    _dir: TempDir,
+}
+
+impl CaptureProxy {
+    fn start(args: &[&str]) -> Self {
+        let dir = temp_dir();
+        let output = dir.join("capture.yml");
+        let mut process =
+            Command::new(assert_cmd::cargo::cargo_bin!("slumber_cli"))
+                .args(["capture", "--port", "0", "--output"])
+                .arg(&output)
+                .args(args)
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+        // Wait for the proxy to tell us which port it's on
+        let mut stderr = BufReader::new(process.stderr.take().unwrap());
+        let mut line = String::new();
+        stderr.read_line(&mut line).unwrap();
+        let url = line
+            .trim()
+            .strip_prefix("Listening on ")
+            .unwrap_or_else(|| panic!("Unexpected output: {line}"))
+            .to_owned();
+        Self {
+            process,
+            stderr,
+            url,
+            output,
+            _dir: dir,
+        }
+    }
+
+    /// Wait until the proxy has handled `count` more requests. Requests are
+    /// recorded after the response is sent, so the client can't tell when the
+    /// output file is ready.
+    fn wait_for_requests(&mut self, count: usize) {
+        let mut handled = 0;
+        while handled < count {
+            let mut line = String::new();
+            let read = self.stderr.read_line(&mut line).unwrap();
+            assert!(read > 0, "Proxy exited early");
+            if line.contains(" -> ") {
+                handled += 1;
+            }
+        }
+    }
+
+    /// Build a client that sends requests through the proxy
+    fn client(&self) -> Client {
+        Client::builder()
+            .proxy(Proxy::http(&self.url).unwrap())
+            .build()
+            .unwrap()
+    }
+}
+
+impl Drop for CaptureProxy {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
@@ -0,0 +1,110 @@
+//! Test the `slumber cookies` subcommand
+
+mod common;
+
+use crate::common::collection_file;
+use slumber_core::{database::Database, http::Cookie};
+
+/// `slumber cookies` lists, sets, and deletes cookies for the current
+/// collection
+#[test]
+fn test_cookies() {
+    let (_, data_dir) = common::slumber();
+    let database = Database::from_directory(&data_dir)
+        .unwrap()
+        .into_collection(&collection_file())
+        .unwrap();
+    database
+        .set_cookie(&Cookie {
+            name: "session".into(),
+            value: "abc".into(),
+            domain: "example.com".into(),
+            host_only: false,
+            path: "/".into(),
+            expires: None,
+            secure: true,
+            http_only: true,
+        })
+        .unwrap();
+
+    let list = || {
+        let mut command = common::slumber_with_data_dir(&data_dir);
+        let output = command
+            .arg("cookies")
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| line.trim_end().to_owned())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        list(),
+        [
+            "Domain       Path Name    Value Expires",
+            ".example.com /    session abc   session",
+        ]
+    );
+
+    let mut command = common::slumber_with_data_dir(&data_dir);
+    command
+        .args(["cookies", "set", "API.example.com", "theme", "dark"])
+        .assert()
+        .success();
+    let mut command = common::slumber_with_data_dir(&data_dir);
+    command
+        .args(["cookies", "delete", "example.com", "session"])
+        .assert()
+        .success();
+    assert_eq!(
+        list(),
+        [
+            "Domain          Path Name  Value Expires",
+            "api.example.com /    theme dark  session",
+        ]
+    );
+
+    // Deleting a cookie that doesn't exist is an error
+    let mut command = common::slumber_with_data_dir(&data_dir);
+    command
+        .args(["cookies", "delete", "example.com", "session"])
+        .assert()
+        .failure();
+
+    let mut command = common::slumber_with_data_dir(&data_dir);
+    command.args(["cookies", "clear"]).assert().success();
+    assert_eq!(list(), ["Domain Path Name Value Expires"]);
+}
+
+/// `--session` scopes `slumber cookies` to a named session's jar
+#[test]
+fn test_cookies_session() {
+    let (_, data_dir) = common::slumber();
+    let database = Database::from_directory(&data_dir)
+        .unwrap()
+        .into_collection(&collection_file())
+        .unwrap();
+
+    let mut command = common::slumber_with_data_dir(&data_dir);
+    command
+        .args(["--session", "admin", "cookies", "set"])
+        .args(["example.com", "session", "admin"])
+        .assert()
+        .success();
+
+    let admin = database.clone().with_session(Some("admin".into()));
+    assert_eq!(
+        admin
+            .get_cookies()
+            .unwrap()
+            .into_iter()
+            .map(|cookie| cookie.value)
+            .collect::<Vec<_>>(),
+        ["admin"]
+    );
+    assert_eq!(database.get_cookies().unwrap(), []);
+}
@@ -147,6 +147,30 @@ fn test_collection_delete() {
     assert_eq!(database.get_all_requests().unwrap().len(), 1);
 }
 
+/// `slumber db collection trust/untrust`
+#[test]
+fn test_collection_trust() {
+    let (mut command, data_dir) = common::slumber();
+    let database = init_db(&data_dir)
+        .into_collection(&collection_file())
+        .unwrap();
+    assert!(!database.is_trusted(None).unwrap());
+
+    command
+        .args(["db", "collection", "trust", "slumber.yml"])
+        .assert()
+        .success()
+        .stdout("Trusted collection slumber.yml\n");
+    assert!(database.is_trusted(None).unwrap());
+
+    common::slumber_with_data_dir(&data_dir)
+        .args(["db", "collection", "untrust", "slumber.yml"])
+        .assert()
+        .success()
+        .stdout("Untrusted collection slumber.yml\n");
+    assert!(!database.is_trusted(None).unwrap());
+}
+
 /// Test collection deletion when the file is already gone. Should still work
 #[test]
 fn test_collection_delete_file_missing() {
@@ -0,0 +1,174 @@
+//! Test the `slumber echo` subcommand
+
+mod common;
+
+use reqwest::tls::Certificate;
+use rstest::rstest;
+use serde_json::{Value, json};
+use std::{
+    fs,
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+};
+
+/// Each request is reflected back exactly as it was sent, including header
+/// case, order, and duplicates
+#[rstest]
+#[case::no_body(
+    b"GET /users?id=3 HTTP/1.1\r\nHost: localhost\r\n\r\n",
+    json!({
+        "method": "GET",
+        "target": "/users?id=3",
+        "version": "HTTP/1.1",
+        "headers": [["Host", "localhost"]],
+        "body": "",
+        "body_encoding": "utf-8",
+        "body_size": 0,
+        "tls": null,
+    }),
+)]
+#[case::content_length(
+    b"POST /sign HTTP/1.1\r\nX-Signature: abc\r\nx-signature: def\r\n\
+    Content-Length: 13\r\n\r\n{\"data\": 123}",
+    json!({
+        "method": "POST",
+        "target": "/sign",
+        "version": "HTTP/1.1",
+        "headers": [
+            ["X-Signature", "abc"],
+            ["x-signature", "def"],
+            ["Content-Length", "13"],
+        ],
+        "body": "{\"data\": 123}",
+        "body_encoding": "utf-8",
+        "body_size": 13,
+        "tls": null,
+    }),
+)]
+#[case::chunked(
+    b"PUT / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n\
+    5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\n",
+    json!({
+        "method": "PUT",
+        "target": "/",
+        "version": "HTTP/1.1",
+        "headers": [["Transfer-Encoding", "chunked"]],
+        "body": "hello world",
+        "body_encoding": "utf-8",
+        "body_size": 11,
+        "tls": null,
+    }),
+)]
+#[case::binary(
+    b"POST / HTTP/1.1\r\nContent-Length: 3\r\n\r\n\x01\x02\xff",
+    json!({
+        "method": "POST",
+        "target": "/",
+        "version": "HTTP/1.1",
+        "headers": [["Content-Length", "3"]],
+        "body": "AQL/",
+        "body_encoding": "base64",
+        "body_size": 3,
+        "tls": null,
+    }),
+)]
+fn test_echo(#[case] request: &[u8], #[case] expected: Value) {
+    let server = EchoServer::start(&[]);
+    let address = server.url.strip_prefix("http://").unwrap();
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(request).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{head}");
+    let body: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body, expected);
+}
+
+/// Serve HTTPS, and include TLS details in the response
+#[tokio::test]
+async fn test_echo_tls() {
+    let cert_path = common::tests_dir().join("echo_cert.pem");
+    let key_path = common::tests_dir().join("echo_key.pem");
+    let server = EchoServer::start(&[
+        "--tls-cert",
+        cert_path.to_str().unwrap(),
+        "--tls-key",
+        key_path.to_str().unwrap(),
+    ]);
+    let url = server.url.replace("127.0.0.1", "localhost");
+    assert!(url.starts_with("https://"), "{url}");
+
+    let cert = Certificate::from_pem(&fs::read(cert_path).unwrap()).unwrap();
+    let client = reqwest::Client::builder()
+        .tls_certs_only([cert])
+        .build()
+        .unwrap();
+    let body: Value = client
+        .get(format!("{url}/secure"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["target"], "/secure");
+    assert_eq!(
+        body["tls"],
+        json!({
+            "version": "TLSv1_3",
+            "cipher_suite": body["tls"]["cipher_suite"],
+            "server_name": "localhost",
+            "alpn_protocol": "http/1.1",
+        })
+    );
+}
+
+/// TLS requires both a certificate and a key
+#[test]
+fn test_echo_tls_missing_key() {
+    let (mut command, _) = common::slumber();
+    command
+        .args(["echo", "--tls-cert", "echo_cert.pem"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("--tls-key <TLS_KEY>"));
+}
+
+/// An echo server running in a subprocess. Killed on drop
+struct EchoServer {
+    process: Child,
+    url: String,
+}
+
+impl EchoServer {
+    fn start(args: &[&str]) -> Self {
+        let mut process =
+            Command::new(assert_cmd::cargo::cargo_bin!("slumber_cli"))
+                .args(["echo", "--port", "0"])
+                .args(args)
+                .stderr(Stdio::piped())
+                .spawn()
+                .unwrap();
+        // Wait for the server to tell us which port it's on
+        let mut line = String::new();
+        BufReader::new(process.stderr.as_mut().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let url = line
+            .trim()
+            .strip_prefix("Listening on ")
+            .unwrap_or_else(|| panic!("Unexpected output: {line}"))
+            .to_owned();
+        Self { process, url }
+    }
+}
+
+impl Drop for EchoServer {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
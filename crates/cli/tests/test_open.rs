@@ -0,0 +1,54 @@
+//! Test the `slumber open` subcommand
+
+mod common;
+
+use crate::common::collection_file;
+use predicates::prelude::*;
+use rstest::rstest;
+use slumber_core::{database::Database, ipc::OpenRequest};
+use std::fs;
+
+/// `slumber open` writes a request for running TUIs to pick up
+#[test]
+fn test_open() {
+    let (mut command, data_dir) = common::slumber();
+    command
+        .args(["open", "--recipe", "getUser", "--profile", "profile2"])
+        .assert()
+        .success();
+
+    let collection_id = Database::from_directory(&data_dir)
+        .unwrap()
+        .into_collection(&collection_file())
+        .unwrap()
+        .collection_id();
+    let content = fs::read(data_dir.join("open.json")).unwrap();
+    let request: OpenRequest = serde_json::from_slice(&content).unwrap();
+    assert_eq!(
+        request,
+        OpenRequest {
+            collection_id,
+            recipe_id: Some("getUser".into()),
+            profile_id: Some("profile2".into()),
+        }
+    );
+}
+
+/// Invalid arguments are rejected before anything is sent
+#[rstest]
+#[case::empty(&[], "At least one of `--recipe` or `--profile` is required")]
+#[case::unknown_recipe(&["--recipe", "fake"], "No recipe with ID `fake`")]
+#[case::unknown_profile(
+    &["--profile", "fake"],
+    "No profile with ID `fake`; options are: profile1, profile2",
+)]
+fn test_open_error(#[case] args: &[&str], #[case] expected_error: &str) {
+    let (mut command, data_dir) = common::slumber();
+    command
+        .arg("open")
+        .args(args)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(expected_error));
+    assert!(!data_dir.join("open.json").exists());
+}
@@ -9,6 +9,7 @@ use rstest::rstest;
 use serde_json::json;
 use slumber_core::{database::Database, http::ExchangeSummary};
 use slumber_util::assert_matches;
+use std::fs;
 use wiremock::{Mock, MockServer, Request, ResponseTemplate, matchers};
 
 /// Test the basic request use case, including `--profile`
@@ -358,17 +359,70 @@ async fn test_request_verbose() {
     command.assert().success().stdout(body.to_string());
 }
 
+/// Test the `--no-buffer` flag. wiremock doesn't stream, so we can't check
+/// that the body is written incrementally, but it should produce the same
+/// output
+#[rstest]
+#[case::long("--no-buffer")]
+#[case::short("-N")]
+#[tokio::test]
+async fn test_request_no_buffer(#[case] flag: &str) {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    let body = json!({
+        "username": "username1",
+        "name": "Frederick Smidgen"
+    });
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/json"))
+        .and(matchers::body_json(&body))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&body))
+        .mount(&server)
+        .await;
+
+    let (mut command, _) = common::slumber();
+    command.args(["request", "jsonBody", flag, "-o", &format!("host={host}")]);
+    command.assert().success().stdout(body.to_string());
+}
+
+/// `--output` streams the body into the file instead of stdout
+#[tokio::test]
+async fn test_request_output() {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    let body = json!({
+        "username": "username1",
+        "name": "Frederick Smidgen"
+    });
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/json"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&body))
+        .mount(&server)
+        .await;
+
+    let (mut command, data_dir) = common::slumber();
+    let path = data_dir.join("response.json");
+    command
+        .args(["request", "jsonBody", "-o", &format!("host={host}")])
+        .arg("--output")
+        .arg(&path);
+    command.assert().success().stdout("");
+    assert_eq!(fs::read_to_string(&path).unwrap(), body.to_string());
+}
+
 /// Test the `--dry-run` flag
 #[tokio::test]
 async fn test_request_dry_run() {
     let (mut command, _) = common::slumber();
     command.args(["request", "jsonBody", "--dry-run"]);
-    command.assert().success().stderr(
+    command.assert().success().stderr(format!(
         "> POST http://server/json HTTP/1.1
 > content-type: application/json
-> {\"username\":\"username1\",\"name\":\"Frederick Smidgen\"}
+> user-agent: slumber/{version}
+> {{\"username\":\"username1\",\"name\":\"Frederick Smidgen\"}}
 ",
-    );
+        version = env!("CARGO_PKG_VERSION"),
+    ));
 }
 
 /// Test the `--exit-status` flag
@@ -398,6 +452,115 @@ async fn test_request_exit_status() {
     command.assert().failure().stdout(body.to_string());
 }
 
+/// With `--error-format json`, errors are printed as JSON with a stable code
+#[rstest]
+#[case::network(&["jsonBody"], "network")]
+#[case::chain(&["chained"], "chain")]
+#[case::template(&["getUser", "-o", "username={{ unknown }}"], "template")]
+#[case::collection(&["unknownRecipe"], "collection")]
+#[tokio::test]
+async fn test_request_error_format(
+    #[case] args: &[&str],
+    #[case] expected_code: &str,
+) {
+    let (mut command, _) = common::slumber();
+    command
+        .args(["--error-format", "json", "request"])
+        .args(args)
+        // Nothing is listening on this port
+        .env("HOST", "http://127.0.0.1:1")
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(format!(
+            r#"{{"code":"{expected_code}","message":"#
+        )));
+}
+
+/// With `--error-format json`, `--exit-status` also prints an error
+#[tokio::test]
+async fn test_request_error_format_http() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+
+    let (mut command, _) = common::slumber();
+    command
+        .args(["--error-format", "json", "request", "getUser", "--exit-status"])
+        .env("HOST", server.uri())
+        .assert()
+        .failure()
+        .stderr(
+            r#"{"code":"http","message":"Response has error status 404 Not Found","causes":[]}
+"#,
+        );
+}
+
+/// Failed assertions are reported and set the exit code, with or without
+/// `--exit-status`
+#[rstest]
+#[case::pass(200, json!({"id": 3}), None)]
+#[case::fail(
+    404,
+    json!({"id": 4}),
+    Some("Assertion failed: Status is 404; expected one of [200]
+Assertion failed: `$.id` is `4`; expected `3`
+"),
+)]
+#[tokio::test]
+async fn test_request_assertions(
+    #[case] status: u16,
+    #[case] body: serde_json::Value,
+    #[case] expected_stderr: Option<&str>,
+) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/asserted"))
+        .respond_with(ResponseTemplate::new(status).set_body_json(&body))
+        .mount(&server)
+        .await;
+
+    let (mut command, _) = common::slumber();
+    let assert = command
+        .args(["request", "asserted"])
+        .env("HOST", server.uri())
+        .assert()
+        .stdout(body.to_string());
+    if let Some(expected_stderr) = expected_stderr {
+        assert.code(3).stderr(expected_stderr.to_owned());
+    } else {
+        assert.success();
+    }
+}
+
+/// Messages are translated based on the locale from the environment
+#[tokio::test]
+async fn test_request_locale() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&server)
+        .await;
+
+    let (mut command, _) = common::slumber();
+    command
+        .args([
+            "--error-format",
+            "json",
+            "request",
+            "getUser",
+            "--exit-status",
+        ])
+        .env("HOST", server.uri())
+        .env("LC_ALL", "es_ES.UTF-8")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "La respuesta tiene un estado de error 404 Not Found",
+        ));
+}
+
 /// Test the `--persist` flag. The main request should be persisted, but the
 /// triggered will **will not**. This is partially a technical decision (makes
 /// the code simpler) and partially a user-friendliness one. It's not entirely
@@ -450,6 +613,49 @@ async fn test_request_persist() {
     assert_eq!(*status, StatusCode::OK);
 }
 
+/// With `reauthenticate: true`, a rejected request triggers its upstream
+/// requests again and is retried once
+#[tokio::test]
+async fn test_request_reauthenticate() {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    let body = json!({
+        "username": "username1",
+        "name": "Frederick Smidgen"
+    });
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/users/username1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(&body))
+        .expect(2)
+        .mount(&server)
+        .await;
+    // First attempt is rejected, second succeeds
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/protected"))
+        .respond_with(ResponseTemplate::new(401))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/protected"))
+        .and(matchers::header("Authorization", "Bearer username1"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("welcome"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (mut command, _) = common::slumber();
+    command.args([
+        "request",
+        "reauthenticate",
+        "--exit-status",
+        "-o",
+        &format!("host={host}"),
+    ]);
+    command.assert().success().stdout("welcome");
+}
+
 /// When loading a collection, the DB should be updated to reflect its name
 #[tokio::test]
 async fn test_set_collection_name() {
@@ -0,0 +1,122 @@
+//! Test the `slumber test` subcommand
+
+mod common;
+
+use predicates::prelude::predicate;
+use rstest::rstest;
+use serde_json::json;
+use slumber_util::{TempDir, temp_dir};
+use std::fs;
+use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+/// With no selection, every tagged recipe is run
+#[tokio::test]
+async fn test_test_tagged() {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/asserted"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"id": 3})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let (mut command, _) = common::slumber();
+    command
+        .arg("test")
+        .env("HOST", server.uri())
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("PASS  asserted ("))
+        .stderr(predicate::str::starts_with(
+            "1 test: 1 passed, 0 failed, 0 errors",
+        ));
+}
+
+/// Recipes selected by ID run before tagged recipes. Failures are printed and
+/// written to the JUnit and JSON reports
+#[rstest]
+#[tokio::test]
+async fn test_test_reports(temp_dir: TempDir) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/users/username1"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&server)
+        .await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/asserted"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"id": 4})),
+        )
+        .mount(&server)
+        .await;
+
+    let junit_path = temp_dir.join("report.xml");
+    let json_path = temp_dir.join("report.json");
+    let (mut command, _) = common::slumber();
+    command
+        .args(["test", "getUser", "--tag", "smoke", "--junit"])
+        .arg(&junit_path)
+        .arg("--json")
+        .arg(&json_path)
+        .env("HOST", server.uri())
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("FAIL  asserted ("))
+        .stdout(predicate::str::contains(
+            "      `$.id` is `4`; expected `3`",
+        ))
+        .stderr(predicate::str::starts_with(
+            "2 tests: 1 passed, 1 failed, 0 errors",
+        ));
+
+    let mut report: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&json_path).unwrap()).unwrap();
+    // Durations vary
+    report.as_object_mut().unwrap().remove("duration_ms");
+    for result in report["results"].as_array_mut().unwrap() {
+        result.as_object_mut().unwrap().remove("duration_ms");
+    }
+    assert_eq!(
+        report,
+        json!({
+            "tests": 2,
+            "passed": 1,
+            "failed": 1,
+            "errors": 0,
+            "results": [
+                {"recipe": "getUser", "result": "passed", "status": 200},
+                {
+                    "recipe": "asserted",
+                    "result": "failed",
+                    "status": 200,
+                    "failures": ["`$.id` is `4`; expected `3`"],
+                },
+            ],
+        })
+    );
+
+    let junit = fs::read_to_string(&junit_path).unwrap();
+    assert!(junit.contains(
+        r#"<testsuite name="CLI Tests" tests="2" failures="1" errors="0""#
+    ));
+    assert!(
+        junit.contains(r#"<testcase name="getUser" classname="CLI Tests""#)
+    );
+    assert!(junit.contains(
+        r#"<failure message="1 assertion failed (status 200)">`$.id` is `4`; expected `3`</failure>"#
+    ));
+}
+
+/// Selecting nothing is an error
+#[test]
+fn test_test_no_recipes() {
+    let (mut command, _) = common::slumber();
+    command
+        .args(["test", "--tag", "unknown"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No recipes have the tag unknown"));
+}
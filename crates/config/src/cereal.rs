@@ -5,10 +5,13 @@
 //! [saphyr-serde](https://docs.rs/saphyr-serde/latest/saphyr_serde/) gets
 //! built.
 
-use crate::{Config, HttpEngineConfig};
+use crate::{
+    ClientCertificate, Config, HttpEngineConfig, RetryPolicy, TemplateSource,
+    Timeout, TlsHost, host_pattern_regex,
+};
 use slumber_util::yaml::{
-    self, DeserializeYaml, Expected, Field, SourceMap, SourcedYaml,
-    StructDeserializer,
+    self, DeserializeYaml, Expected, Field, LocatedError, SourceMap,
+    SourcedYaml, StructDeserializer,
 };
 
 impl DeserializeYaml for Config {
@@ -29,6 +32,25 @@ impl DeserializeYaml for Config {
         let config = Self {
             editor: deserializer
                 .get(Field::new("editor").or(default.editor), source_map)?,
+            editor_file_types: deserializer.get(
+                Field::new("editor_file_types").or(default.editor_file_types),
+                source_map,
+            )?,
+            trusted_directories: deserializer.get(
+                Field::new("trusted_directories")
+                    .or(default.trusted_directories),
+                source_map,
+            )?,
+            audit_log: deserializer.get(
+                Field::new("audit_log").or(default.audit_log),
+                source_map,
+            )?,
+            confirm_sources: deserializer.get(
+                Field::new("confirm_sources").or(default.confirm_sources),
+                source_map,
+            )?,
+            locale: deserializer
+                .get(Field::new("locale").or(default.locale), source_map)?,
             // Both these configs get flattened to the top, so they share the
             // same deserializer
             http: deserialize_http_config(&mut deserializer, source_map)?,
@@ -48,6 +70,144 @@ impl DeserializeYaml for Config {
     }
 }
 
+impl DeserializeYaml for TemplateSource {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        _source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = yaml.try_into_string()?;
+        s.parse()
+            .map_err(|error| LocatedError::other(error, location))
+    }
+}
+
+impl DeserializeYaml for ClientCertificate {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let certificate = Self {
+            certificate: deserializer
+                .get(Field::new("certificate"), source_map)?,
+            key: deserializer.get(Field::new("key").opt(), source_map)?,
+            password: deserializer
+                .get(Field::new("password").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(certificate)
+    }
+}
+
+impl DeserializeYaml for TlsHost {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let host = Self {
+            danger_accept_invalid_certs: deserializer.get(
+                Field::new("danger_accept_invalid_certs").or(false),
+                source_map,
+            )?,
+            danger_accept_invalid_hostnames: deserializer.get(
+                Field::new("danger_accept_invalid_hostnames").or(false),
+                source_map,
+            )?,
+        };
+        deserializer.done()?;
+        Ok(host)
+    }
+}
+
+impl DeserializeYaml for RetryPolicy {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let default = Self::default();
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let policy = Self {
+            max_attempts: deserializer.get::<usize>(
+                Field::new("max_attempts").or(default.max_attempts as usize),
+                source_map,
+            )? as u32,
+            statuses: deserializer
+                .get::<Vec<usize>>(
+                    Field::new("statuses").or(default
+                        .statuses
+                        .iter()
+                        .map(|status| *status as usize)
+                        .collect()),
+                    source_map,
+                )?
+                .into_iter()
+                .map(|status| status as u16)
+                .collect(),
+            network_errors: deserializer.get(
+                Field::new("network_errors").or(default.network_errors),
+                source_map,
+            )?,
+            backoff_ms: deserializer.get::<usize>(
+                Field::new("backoff_ms").or(default.backoff_ms as usize),
+                source_map,
+            )? as u64,
+            max_backoff_ms: deserializer.get::<usize>(
+                Field::new("max_backoff_ms")
+                    .or(default.max_backoff_ms as usize),
+                source_map,
+            )? as u64,
+            jitter: deserializer
+                .get(Field::new("jitter").or(default.jitter), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(policy)
+    }
+}
+
+impl DeserializeYaml for Timeout {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let timeout = Self {
+            connect_ms: deserializer
+                .get::<Option<usize>>(
+                    Field::new("connect_ms").opt(),
+                    source_map,
+                )?
+                .map(|ms| ms as u64),
+            total_ms: deserializer
+                .get::<Option<usize>>(Field::new("total_ms").opt(), source_map)?
+                .map(|ms| ms as u64),
+        };
+        deserializer.done()?;
+        Ok(timeout)
+    }
+}
+
 /// Deserialize HTTP-specific config fields from an existing deserializer
 fn deserialize_http_config(
     deserializer: &mut StructDeserializer,
@@ -60,6 +220,16 @@ fn deserialize_http_config(
                 .or(default.ignore_certificate_hosts),
             source_map,
         )?,
+        client_certificates: deserializer.get(
+            Field::new("client_certificates").or(default.client_certificates),
+            source_map,
+        )?,
+        ca_certificates: deserializer.get(
+            Field::new("ca_certificates").or(default.ca_certificates),
+            source_map,
+        )?,
+        tls_hosts: deserializer
+            .get(Field::new("tls_hosts").or(default.tls_hosts), source_map)?,
         large_body_size: deserializer.get(
             Field::new("large_body_size").or(default.large_body_size),
             source_map,
@@ -68,13 +238,68 @@ fn deserialize_http_config(
             Field::new("follow_redirects").or(default.follow_redirects),
             source_map,
         )?,
+        cookie_jar: deserializer
+            .get(Field::new("cookie_jar").or(default.cookie_jar), source_map)?,
+        allowed_hosts: deserializer
+            .get::<Vec<HostPatternString>>(
+                Field::new("allowed_hosts").or(Vec::new()),
+                source_map,
+            )?
+            .into_iter()
+            .map(|pattern| pattern.0)
+            .collect(),
+        denied_hosts: deserializer
+            .get::<Vec<HostPatternString>>(
+                Field::new("denied_hosts").or(Vec::new()),
+                source_map,
+            )?
+            .into_iter()
+            .map(|pattern| pattern.0)
+            .collect(),
+        proxy: deserializer.get(Field::new("proxy").opt(), source_map)?,
+        no_proxy: deserializer
+            .get(Field::new("no_proxy").or(default.no_proxy), source_map)?,
+        user_agent: deserializer
+            .get(Field::new("user_agent").opt(), source_map)?,
+        transport: deserializer
+            .get(Field::new("transport").or(default.transport), source_map)?,
+        retry: deserializer.get(Field::new("retry").opt(), source_map)?,
+        timeout: deserializer
+            .get(Field::new("timeout").or(default.timeout), source_map)?,
     })
 }
 
+/// An `allowed_hosts`/`denied_hosts` pattern. These are stored as strings and
+/// compiled by the HTTP engine, but we compile them here too so an invalid
+/// regex is reported when the config is loaded
+struct HostPatternString(String);
+
+impl DeserializeYaml for HostPatternString {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        _source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let pattern = yaml.try_into_string()?;
+        host_pattern_regex(&pattern)
+            .map_err(|error| LocatedError::other(error, location))?;
+        Ok(Self(pattern))
+    }
+}
+
 /// TUI-specific config deserialization
 #[cfg(feature = "tui")]
 mod tui {
-    use crate::tui::{CommandsConfig, Theme, TuiConfig};
+    use crate::tui::{
+        BorderKind, CommandsConfig, EnvironmentBadge, ExchangeTab,
+        HyperlinkMode, Palette, RecipeTab, Spinner, StartupConfig, StartupPane,
+        StyleKey, StyleModifier, StyleOverride, Theme, ThemeBorders,
+        ThemeMarkers, ThemePreset, TuiConfig,
+    };
     use ratatui_core::style::Color;
     use serde::de::{self, value::StringDeserializer};
     use slumber_util::yaml::{
@@ -93,6 +318,10 @@ mod tui {
                 .get(Field::new("commands").or(default.commands), source_map)?,
             pager: deserializer
                 .get(Field::new("pager").or(default.pager), source_map)?,
+            split_command: deserializer.get(
+                Field::new("split_command").or(default.split_command),
+                source_map,
+            )?,
             preview_templates: deserializer.get(
                 Field::new("preview_templates").or(default.preview_templates),
                 source_map,
@@ -103,6 +332,34 @@ mod tui {
             )?,
             theme: deserializer
                 .get(Field::new("theme").or(default.theme), source_map)?,
+            reader_mode: deserializer.get(
+                Field::new("reader_mode").or(default.reader_mode),
+                source_map,
+            )?,
+            spinner: deserializer
+                .get(Field::new("spinner").or(default.spinner), source_map)?,
+            reduced_motion: deserializer.get(
+                Field::new("reduced_motion").or(default.reduced_motion),
+                source_map,
+            )?,
+            hyperlinks: deserializer.get(
+                Field::new("hyperlinks").or(default.hyperlinks),
+                source_map,
+            )?,
+            environment_badges: deserializer.get(
+                Field::new("environment_badges").or(default.environment_badges),
+                source_map,
+            )?,
+            diff_gutter: deserializer.get(
+                Field::new("diff_gutter").or(default.diff_gutter),
+                source_map,
+            )?,
+            offline_queue: deserializer.get(
+                Field::new("offline_queue").or(default.offline_queue),
+                source_map,
+            )?,
+            startup: deserializer
+                .get(Field::new("startup").or(default.startup), source_map)?,
             debug: deserializer
                 .get(Field::new("debug").or(default.debug), source_map)?,
             persist: deserializer
@@ -128,6 +385,10 @@ mod tui {
                     Field::new("default_query").or(default.default_query),
                     source_map,
                 )?,
+                renderers: deserializer.get(
+                    Field::new("renderers").or(default.renderers),
+                    source_map,
+                )?,
             };
             deserializer.done()?;
             Ok(config)
@@ -143,50 +404,405 @@ mod tui {
             yaml: SourcedYaml,
             source_map: &SourceMap,
         ) -> yaml::Result<Self> {
-            let default = Self::default();
+            deserialize_theme(yaml, source_map, true)
+        }
+    }
+
+    /// A `light` or `dark` theme variant. This is a full theme, except it can't
+    /// define its own variants
+    struct ThemeVariant(Theme);
+
+    impl DeserializeYaml for ThemeVariant {
+        fn expected() -> Expected {
+            Expected::Mapping
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            deserialize_theme(yaml, source_map, false).map(Self)
+        }
+    }
+
+    /// Deserialize a theme. If `variants` is disabled, the `light` and `dark`
+    /// fields will be rejected as unknown
+    fn deserialize_theme(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+        variants: bool,
+    ) -> yaml::Result<Theme> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        // The preset and palette provide defaults for all the colors, so
+        // get them first
+        let preset = deserializer
+            .get(Field::new("preset").or(ThemePreset::default()), source_map)?;
+        let palette = deserializer
+            .get(Field::new("palette").or(Palette::default()), source_map)?;
+        let default = Theme::new(preset, palette);
+        let mut config = Theme {
+            preset,
+            color: deserializer
+                .get(Field::new("color").or(default.color), source_map)?,
+            ascii: deserializer
+                .get(Field::new("ascii").or(default.ascii), source_map)?,
+            palette,
+            markers: deserializer
+                .get(Field::new("markers").or(default.markers), source_map)?,
+            borders: deserializer
+                .get(Field::new("borders").or(default.borders), source_map)?,
+            disabled_modifiers: deserializer.get(
+                Field::new("disabled_modifiers").or(default.disabled_modifiers),
+                source_map,
+            )?,
+            primary_color: deserializer
+                .get::<Adopt<_>>(
+                    Field::new("primary_color")
+                        .or(Adopt(default.primary_color)),
+                    source_map,
+                )?
+                .0,
+            primary_text_color: deserializer
+                .get::<Adopt<_>>(
+                    Field::new("primary_text_color")
+                        .or(Adopt(default.primary_text_color)),
+                    source_map,
+                )?
+                .0,
+            secondary_color: deserializer
+                .get::<Adopt<_>>(
+                    Field::new("secondary_color")
+                        .or(Adopt(default.secondary_color)),
+                    source_map,
+                )?
+                .0,
+            success_color: deserializer
+                .get::<Adopt<_>>(
+                    Field::new("success_color")
+                        .or(Adopt(default.success_color)),
+                    source_map,
+                )?
+                .0,
+            error_color: deserializer
+                .get::<Adopt<_>>(
+                    Field::new("error_color").or(Adopt(default.error_color)),
+                    source_map,
+                )?
+                .0,
+            overrides: deserializer.get(
+                Field::new("overrides").or(default.overrides),
+                source_map,
+            )?,
+            light: None,
+            dark: None,
+        };
+        if variants {
+            config.light = deserializer
+                .get::<Option<ThemeVariant>>(
+                    Field::new("light").opt(),
+                    source_map,
+                )?
+                .map(|variant| Box::new(variant.0));
+            config.dark = deserializer
+                .get::<Option<ThemeVariant>>(
+                    Field::new("dark").opt(),
+                    source_map,
+                )?
+                .map(|variant| Box::new(variant.0));
+        }
+        deserializer.done()?;
+        Ok(config)
+    }
+
+    impl DeserializeYaml for Palette {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for ThemeMarkers {
+        fn expected() -> Expected {
+            Expected::Mapping
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
             let mut deserializer = StructDeserializer::new(yaml)?;
-            let config = Self {
-                primary_color: deserializer
-                    .get::<Adopt<_>>(
-                        Field::new("primary_color")
-                            .or(Adopt(default.primary_color)),
-                        source_map,
-                    )?
-                    .0,
-                primary_text_color: deserializer
-                    .get::<Adopt<_>>(
-                        Field::new("primary_text_color")
-                            .or(Adopt(default.primary_text_color)),
-                        source_map,
-                    )?
-                    .0,
-                secondary_color: deserializer
-                    .get::<Adopt<_>>(
-                        Field::new("secondary_color")
-                            .or(Adopt(default.secondary_color)),
-                        source_map,
-                    )?
-                    .0,
-                success_color: deserializer
-                    .get::<Adopt<_>>(
-                        Field::new("success_color")
-                            .or(Adopt(default.success_color)),
+            let markers = Self {
+                selected: deserializer
+                    .get(Field::new("selected").opt(), source_map)?,
+                success: deserializer
+                    .get(Field::new("success").opt(), source_map)?,
+                error: deserializer
+                    .get(Field::new("error").opt(), source_map)?,
+            };
+            deserializer.done()?;
+            Ok(markers)
+        }
+    }
+
+    impl DeserializeYaml for ThemeBorders {
+        fn expected() -> Expected {
+            Expected::Mapping
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let mut deserializer = StructDeserializer::new(yaml)?;
+            let borders = Self {
+                pane: deserializer.get(Field::new("pane").opt(), source_map)?,
+                pane_selected: deserializer
+                    .get(Field::new("pane_selected").opt(), source_map)?,
+                modal: deserializer
+                    .get(Field::new("modal").opt(), source_map)?,
+                menu: deserializer.get(Field::new("menu").opt(), source_map)?,
+            };
+            deserializer.done()?;
+            Ok(borders)
+        }
+    }
+
+    impl DeserializeYaml for BorderKind {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for StyleKey {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for StyleOverride {
+        fn expected() -> Expected {
+            Expected::Mapping
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let mut deserializer = StructDeserializer::new(yaml)?;
+            let style_override = Self {
+                fg: deserializer
+                    .get::<Option<Adopt<_>>>(
+                        Field::new("fg").opt(),
                         source_map,
                     )?
-                    .0,
-                error_color: deserializer
-                    .get::<Adopt<_>>(
-                        Field::new("error_color")
-                            .or(Adopt(default.error_color)),
+                    .map(|color| color.0),
+                bg: deserializer
+                    .get::<Option<Adopt<_>>>(
+                        Field::new("bg").opt(),
                         source_map,
                     )?
+                    .map(|color| color.0),
+                modifiers: deserializer
+                    .get(Field::new("modifiers").opt(), source_map)?,
+                remove_modifiers: deserializer
+                    .get(Field::new("remove_modifiers").opt(), source_map)?,
+            };
+            deserializer.done()?;
+            Ok(style_override)
+        }
+    }
+
+    impl DeserializeYaml for EnvironmentBadge {
+        fn expected() -> Expected {
+            Expected::Mapping
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let mut deserializer = StructDeserializer::new(yaml)?;
+            let badge = Self {
+                label: deserializer.get(Field::new("label"), source_map)?,
+                color: deserializer
+                    .get::<Adopt<_>>(Field::new("color"), source_map)?
                     .0,
             };
             deserializer.done()?;
+            Ok(badge)
+        }
+    }
+
+    impl DeserializeYaml for StyleModifier {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for HyperlinkMode {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for Spinner {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for ThemePreset {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for StartupConfig {
+        fn expected() -> Expected {
+            Expected::Mapping
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let default = Self::default();
+            let mut deserializer = StructDeserializer::new(yaml)?;
+            let config = Self {
+                focus: deserializer
+                    .get(Field::new("focus").opt(), source_map)?,
+                open_last_response: deserializer.get(
+                    Field::new("open_last_response")
+                        .or(default.open_last_response),
+                    source_map,
+                )?,
+                recipe_tab: deserializer
+                    .get(Field::new("recipe_tab").opt(), source_map)?,
+                exchange_tab: deserializer
+                    .get(Field::new("exchange_tab").opt(), source_map)?,
+            };
+            deserializer.done()?;
             Ok(config)
         }
     }
 
+    impl DeserializeYaml for StartupPane {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for RecipeTab {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
+    impl DeserializeYaml for ExchangeTab {
+        fn expected() -> Expected {
+            Expected::String
+        }
+
+        fn deserialize(
+            yaml: SourcedYaml,
+            _source_map: &SourceMap,
+        ) -> yaml::Result<Self> {
+            let location = yaml.location;
+            let s = yaml.try_into_string()?;
+            s.parse()
+                .map_err(|error| LocatedError::other(error, location))
+        }
+    }
+
     /// Workaround for the orphan rule
     #[derive(Debug, Default)]
     struct Adopt<T>(T);
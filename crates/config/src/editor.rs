@@ -0,0 +1,217 @@
+//! External editor/pager commands
+
+use editor_command::{Editor, EditorBuilder, EditorBuilderError};
+use slumber_util::doc_link;
+use std::{path::Path, process::Command};
+use thiserror::Error;
+
+/// A command to open a file in an external program, such as an editor or
+/// pager. The command is a shell-like string. By default, the file path is
+/// appended as the final argument. If the command contains a `{file}`
+/// placeholder, the path is inserted there instead, along with `{line}` and
+/// `{column}` when opening to a location:
+///
+/// ```yaml
+/// editor: code --wait --goto {file}:{line}:{column}
+/// ```
+#[derive(Clone, Debug)]
+pub struct EditorCommand {
+    kind: EditorCommandKind,
+    /// Does the program open its own window (as opposed to running in the
+    /// terminal)?
+    is_gui: bool,
+}
+
+#[derive(Clone, Debug)]
+enum EditorCommandKind {
+    /// No placeholders; let [Editor] decide where to put the path
+    Editor(Editor),
+    /// Command has placeholders, which will be replaced in each argument
+    Template {
+        program: String,
+        arguments: Vec<String>,
+    },
+}
+
+impl EditorCommand {
+    /// Programs that open in their own window rather than the terminal. This
+    /// doesn't need to be exhaustive: unknown programs are treated as terminal
+    /// programs, which is always safe but leaves the TUI suspended while the
+    /// program is open.
+    const GUI_PROGRAMS: &[&str] = &[
+        "atom",
+        "code",
+        "code-insiders",
+        "codium",
+        "cursor",
+        "gedit",
+        "gvim",
+        "idea",
+        "kate",
+        "mate",
+        "mvim",
+        "notepad",
+        "notepad++",
+        "open",
+        "subl",
+        "xdg-open",
+        "zed",
+    ];
+
+    /// Build a command from the first source that provides one. Like
+    /// [EditorBuilder], a source that's present but empty is an error rather
+    /// than being skipped.
+    pub(crate) fn new<'a>(
+        sources: impl IntoIterator<Item = Option<&'a str>>,
+    ) -> Result<Self, EditorError> {
+        let command = sources
+            .into_iter()
+            .flatten()
+            .next()
+            .ok_or(EditorError(EditorBuilderError::NoCommand))?;
+        let mut words = shell_words::split(command).map_err(|error| {
+            EditorError(EditorBuilderError::ParseError(error))
+        })?;
+        let Some(program) = words.first() else {
+            return Err(EditorError(EditorBuilderError::EmptyCommand));
+        };
+        let is_gui = Self::is_gui_program(program);
+
+        let kind = if words.iter().any(|word| word.contains("{file}")) {
+            let program = words.remove(0);
+            EditorCommandKind::Template {
+                program,
+                arguments: words,
+            }
+        } else {
+            let editor = EditorBuilder::new()
+                .string(Some(command))
+                .build()
+                .map_err(EditorError)?;
+            EditorCommandKind::Editor(editor)
+        };
+        Ok(Self { kind, is_gui })
+    }
+
+    /// Does the program open in its own window? If so, the caller doesn't need
+    /// to hand over the terminal while it runs.
+    pub fn is_gui(&self) -> bool {
+        self.is_gui
+    }
+
+    /// Build a command that will open a file
+    pub fn open(&self, path: impl AsRef<Path>) -> Command {
+        match &self.kind {
+            EditorCommandKind::Editor(editor) => editor.open(path),
+            EditorCommandKind::Template { .. } => self.open_at(path, 1, 1),
+        }
+    }
+
+    /// Build a command that will open a file to a particular line and column
+    pub fn open_at(
+        &self,
+        path: impl AsRef<Path>,
+        line: u32,
+        column: u32,
+    ) -> Command {
+        match &self.kind {
+            EditorCommandKind::Editor(editor) => {
+                editor.open_at(path, line, column)
+            }
+            EditorCommandKind::Template { program, arguments } => {
+                let path = path.as_ref().display().to_string();
+                let mut command = Command::new(program);
+                command.args(arguments.iter().map(|argument| {
+                    argument
+                        .replace("{file}", &path)
+                        .replace("{line}", &line.to_string())
+                        .replace("{column}", &column.to_string())
+                }));
+                command
+            }
+        }
+    }
+
+    /// Is the program known to open in its own window? Match on the file name
+    /// so full paths and Windows extensions are handled
+    fn is_gui_program(program: &str) -> bool {
+        // Split on both separators so Windows paths work on any platform
+        let file_name = program.rsplit(['/', '\\']).next().unwrap_or(program);
+        let name = file_name.to_lowercase();
+        let name = name.strip_suffix(".exe").unwrap_or(&name);
+        Self::GUI_PROGRAMS.contains(&name)
+    }
+}
+
+/// Error opening a configured editor/pager
+#[derive(Debug, Error)]
+#[error("Error opening editor; see {}", doc_link("user_guide/tui/editor"))]
+pub struct EditorError(#[source] pub(crate) EditorBuilderError);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_util::assert_result;
+
+    /// Test building commands with and without placeholders
+    #[rstest]
+    #[case::append("vim", None, &["file.txt"])]
+    #[case::append_at("code --wait", Some((3, 4)), &["--wait", "file.txt:3:4"])]
+    #[case::placeholder("code -g {file}", None, &["-g", "file.txt"])]
+    #[case::placeholder_at(
+        "code --wait -g {file}:{line}:{column}",
+        Some((3, 4)),
+        &["--wait", "-g", "file.txt:3:4"],
+    )]
+    #[case::placeholder_default_location(
+        "hx {file}:{line}",
+        None,
+        &["file.txt:1"],
+    )]
+    #[case::quoted("'my editor' --file={file}", None, &["--file=file.txt"])]
+    fn test_open(
+        #[case] command: &str,
+        #[case] location: Option<(u32, u32)>,
+        #[case] expected: &[&str],
+    ) {
+        let editor = EditorCommand::new([Some(command)]).unwrap();
+        let command = match location {
+            Some((line, column)) => editor.open_at("file.txt", line, column),
+            None => editor.open("file.txt"),
+        };
+        assert_eq!(command.get_args().collect::<Vec<_>>(), expected);
+    }
+
+    /// First present source wins, even if it's invalid
+    #[rstest]
+    #[case::first(&[Some("a"), Some("b")], Ok("a"))]
+    #[case::skip_none(&[None, Some("b")], Ok("b"))]
+    #[case::empty(&[Some(" "), Some("b")], Err("Editor command is empty"))]
+    #[case::none(&[None], Err("Edit command not defined"))]
+    fn test_source_priority(
+        #[case] sources: &[Option<&str>],
+        #[case] expected: Result<&str, &str>,
+    ) {
+        assert_result(
+            EditorCommand::new(sources.iter().copied())
+                .map(|editor| editor.open("file").get_program().to_owned()),
+            expected,
+        );
+    }
+
+    #[rstest]
+    #[case::terminal("vim", false)]
+    #[case::unknown("my-editor", false)]
+    #[case::gui("code --wait", true)]
+    #[case::gui_path("/usr/bin/subl", true)]
+    #[case::gui_windows("Notepad.exe", true)]
+    #[case::gui_windows_path(
+        r"'C:\Program Files\Notepad++\notepad++.exe'",
+        true
+    )]
+    fn test_is_gui(#[case] command: &str, #[case] expected: bool) {
+        let editor = EditorCommand::new([Some(command)]).unwrap();
+        assert_eq!(editor.is_gui(), expected);
+    }
+}
@@ -14,16 +14,25 @@
 //! do so at your own risk of breakage.
 
 mod cereal;
+mod editor;
 #[cfg(feature = "tui")]
 mod tui;
 
 #[cfg(feature = "tui")]
 pub use tui::*;
 
-use editor_command::{Editor, EditorBuilder, EditorBuilderError};
-use serde::Serialize;
+pub use editor::{EditorCommand, EditorError};
+
+use derive_more::Display;
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::{
+    Deserialize, Serialize,
+    de::{self, value::StringDeserializer},
+};
+use slumber_template::Template;
 use slumber_util::{
-    ResultTraced, doc_link, git_link,
+    ResultTraced, STARTUP_TARGET, git_link,
     paths::{self, create_parent, expand_home},
     yaml::{self, YamlError},
 };
@@ -33,9 +42,10 @@ use std::{
     fs::{self, File},
     io::{self, Write},
     path::{Path, PathBuf},
+    str::FromStr,
 };
 use thiserror::Error;
-use tracing::{error, info, warn};
+use tracing::{error, info, info_span, warn};
 
 const PATH_ENV_VAR: &str = "SLUMBER_CONFIG_PATH";
 const FILE: &str = "config.yml";
@@ -43,9 +53,8 @@ const FILE: &str = "config.yml";
 const DEFAULT_OLD: &str = include_str!("default_old.yml");
 
 /// App-level configuration, which is global across all sessions and
-/// collections. This is *not* meant to modifiable during a session. If changes
-/// are made to the config file while a TUI session is running, they won't be
-/// picked up until the app restarts.
+/// collections. The TUI watches the config file and reloads it when it
+/// changes; other consumers load it once on startup.
 #[derive(Debug, Default, Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
@@ -62,10 +71,11 @@ const DEFAULT_OLD: &str = include_str!("default_old.yml");
 )]
 pub struct Config {
     /// Command to use for in-app editing. If provided, overrides
-    /// `VISUAL`/`EDITOR` environment variables.
-    // This only supports a single command, *not* a content type map. This is
-    // because there isn't much value in it, and plumbing the content type
-    // around to support it is annoying.
+    /// `VISUAL`/`EDITOR` environment variables. Use `{file}`, `{line}`, and
+    /// `{column}` placeholders to control where the file location is passed.
+    // This isn't a content type map like `pager`, because plumbing the content
+    // type around to support it is annoying. Use `editor_file_types` instead,
+    // which only needs the file path.
     //
     // We could potentially convert this to `Editor` during deserialization and
     // load env vars/defaults there. That would centralize the error handling
@@ -73,6 +83,30 @@ pub struct Config {
     // match what was deserialized, and could vary based on env vars.
     pub editor: Option<String>,
 
+    /// Editor commands for specific file types, keyed by file extension (e.g.
+    /// `json`). These take priority over `editor`.
+    pub editor_file_types: IndexMap<String, String>,
+
+    /// Directories containing trusted collections. If set, collections outside
+    /// these directories are sandboxed until trusted explicitly: template
+    /// functions that access the local system (`command()`, `file()`) are
+    /// disabled. If omitted, all collections are trusted.
+    pub trusted_directories: Option<Vec<String>>,
+
+    /// Record every external effect of template rendering (commands run, files
+    /// read, environment variables accessed, requests triggered) in the
+    /// database. View the log with `slumber audit` or in the TUI.
+    pub audit_log: bool,
+
+    /// Template sources that require confirmation from the user each time
+    /// they're used
+    pub confirm_sources: Vec<TemplateSource>,
+
+    /// Language for user-facing text, e.g. `es` or `es_MX`. If omitted, the
+    /// locale is detected from the `LC_ALL`, `LC_MESSAGES`, and `LANG`
+    /// environment variables. Unsupported languages fall back to English.
+    pub locale: Option<String>,
+
     /// HTTP engine configuration, which will be flattened for ser/de
     #[serde(flatten)]
     pub http: HttpEngineConfig,
@@ -114,6 +148,8 @@ impl Config {
     /// startup, when all operations are synchronous.
     pub fn load() -> Result<Self, ConfigError> {
         let path = Self::path();
+        let _span =
+            info_span!(target: STARTUP_TARGET, "Load config", ?path).entered();
         info!(?path, "Loading configuration file");
 
         // Replace pre-4.3.0 default file with the current one
@@ -148,17 +184,38 @@ impl Config {
         }
     }
 
-    /// Get an [Editor] to open the given file in the user's configured editor.
-    /// Default editor is `vim`. Return an error if the command couldn't be
-    /// built.
-    pub fn editor(&self) -> Result<Editor, EditorError> {
-        EditorBuilder::new()
-            // Config field takes priority over environment variables
-            .string(self.editor.as_deref())
-            .environment()
-            .string(Some("vim"))
-            .build()
-            .map_err(EditorError)
+    /// Reload configuration from a file that has already been loaded via
+    /// [Self::load]. Unlike the initial load, *any* error is returned,
+    /// including a missing file. The caller should keep using the previous
+    /// config in that case.
+    pub fn reload(path: &Path) -> Result<Self, ConfigError> {
+        info!(?path, "Reloading configuration file");
+        Ok(yaml::deserialize_file::<Config>(path)?)
+    }
+
+    /// Get an [EditorCommand] to open the given file in the user's configured
+    /// editor. Default editor is `vim`. Return an error if the command
+    /// couldn't be built.
+    pub fn editor(&self, path: &Path) -> Result<EditorCommand, EditorError> {
+        let file_type_command = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(|extension| {
+                self.editor_file_types.iter().find_map(|(key, command)| {
+                    key.eq_ignore_ascii_case(extension)
+                        .then_some(command.as_str())
+                })
+            });
+        let visual = env::var("VISUAL").ok();
+        let editor = env::var("EDITOR").ok();
+        EditorCommand::new([
+            // Config fields take priority over environment variables
+            file_type_command,
+            self.editor.as_deref(),
+            visual.as_deref(),
+            editor.as_deref(),
+            Some("vim"),
+        ])
     }
 
     /// When the config file fails to open, we'll attempt to create a new one
@@ -232,6 +289,38 @@ impl Config {
     }
 }
 
+/// A template function that has an effect outside of Slumber. These can be
+/// recorded in the audit log and gated behind a confirmation prompt.
+#[derive(Copy, Clone, Debug, Display, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateSource {
+    /// `command()`
+    #[display("command")]
+    Command,
+    /// `env()`
+    #[display("env")]
+    Env,
+    /// `file()`
+    #[display("file")]
+    File,
+    /// `keyring()`
+    #[display("keyring")]
+    Keyring,
+    /// `response()`/`response_header()` triggering an upstream request
+    #[display("request")]
+    Request,
+}
+
+impl FromStr for TemplateSource {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Use serde's implementation for consistency with serialization
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
 /// Configuration for the engine that handles HTTP requests
 #[derive(Debug, Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
@@ -240,11 +329,59 @@ impl Config {
 pub struct HttpEngineConfig {
     /// TLS cert errors on these hostnames are ignored. Be careful!
     pub ignore_certificate_hosts: Vec<String>,
+    /// TLS client certificates (mutual TLS), keyed by hostname. The
+    /// certificate is presented to any host with a matching entry. Recipes and
+    /// profiles can override this with their own `certificate` field.
+    pub client_certificates: IndexMap<String, ClientCertificate>,
+    /// Paths to additional CA certificates (PEM), trusted alongside the
+    /// system's certificate store. Useful for internal CAs.
+    pub ca_certificates: Vec<String>,
+    /// Relaxed TLS verification for specific hosts, keyed by hostname
+    pub tls_hosts: IndexMap<String, TlsHost>,
     /// Request/response bodies over this size are treated differently, for
     /// performance reasons
     pub large_body_size: usize,
     /// Follow 3xx redirects automatically. Enabled by default
     pub follow_redirects: bool,
+    /// Store cookies set by responses, and send them with later requests to
+    /// matching URLs. Enabled by default
+    pub cookie_jar: bool,
+    /// If non-empty, requests may only be sent to hosts matching one of these
+    /// patterns. A pattern is a glob (e.g. `*.example.com`), or a regex
+    /// wrapped in slashes (e.g. `/api-\d+\.example\.com/`). Redirects are
+    /// checked too.
+    pub allowed_hosts: Vec<String>,
+    /// Requests to hosts matching any of these patterns are blocked. This
+    /// takes precedence over `allowed_hosts`.
+    pub denied_hosts: Vec<String>,
+    /// URL of a proxy to send all requests through. `http`, `https`, and
+    /// `socks5` proxies are supported. This is a template, so it can use
+    /// profile fields and functions. Profiles can override this with their own
+    /// `proxy` field. If not given, the standard proxy environment variables
+    /// (`HTTP_PROXY`, `HTTPS_PROXY`, `ALL_PROXY`) are used.
+    pub proxy: Option<Template>,
+    /// Requests to hosts matching any of these patterns bypass the proxy. This
+    /// uses the same syntax as the `NO_PROXY` environment variable: a domain
+    /// matches itself and all its subdomains, and IP ranges can be given in
+    /// CIDR notation.
+    pub no_proxy: Vec<String>,
+    /// Value of the `User-Agent` header for all requests. This is a template,
+    /// so it can use profile fields and functions. Recipes can override this
+    /// with their own `user_agent` field. An empty value omits the header
+    /// entirely. If not given, Slumber's default user agent is used.
+    pub user_agent: Option<Template>,
+    /// Name of the backend used to send requests. Recipes can override this
+    /// with their own `transport` field. The only built-in transport is
+    /// `reqwest`; others are only available when embedding Slumber as a
+    /// library.
+    pub transport: String,
+    /// Automatically retry requests that fail with a network error or a
+    /// retryable status code. Recipes can override this with their own
+    /// `retry` field. If not given, requests are never retried.
+    pub retry: Option<RetryPolicy>,
+    /// Give up on requests that take too long. Profiles and recipes can
+    /// override each limit with their own `timeout` field.
+    pub timeout: Timeout,
 }
 
 impl HttpEngineConfig {
@@ -256,16 +393,136 @@ impl HttpEngineConfig {
     }
 }
 
+/// Compile a pattern from `allowed_hosts`/`denied_hosts` into a regex that
+/// matches an entire hostname, case-insensitively. A pattern wrapped in slashes
+/// (`/api-\d+\.example\.com/`) is a regex. Anything else is a glob, where `*`
+/// matches any sequence of characters (including `.`), so `*.example.com`
+/// matches `api.example.com` and `a.b.example.com`, but not `example.com`.
+pub fn host_pattern_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    let regex = if let Some(regex) = pattern
+        .strip_prefix('/')
+        .and_then(|pattern| pattern.strip_suffix('/'))
+    {
+        // Group it so alternations can't escape the anchors
+        format!("(?:{regex})")
+    } else {
+        pattern
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(".*")
+    };
+    Regex::new(&format!("(?i)^{regex}$"))
+}
+
 impl Default for HttpEngineConfig {
     fn default() -> Self {
         Self {
             ignore_certificate_hosts: Default::default(),
+            client_certificates: IndexMap::new(),
+            ca_certificates: Vec::new(),
+            tls_hosts: IndexMap::new(),
             large_body_size: 1000 * 1000, // 1MB
             follow_redirects: true,
+            cookie_jar: true,
+            allowed_hosts: Vec::new(),
+            denied_hosts: Vec::new(),
+            proxy: None,
+            no_proxy: Vec::new(),
+            user_agent: None,
+            transport: "reqwest".to_owned(),
+            retry: None,
+            timeout: Timeout::default(),
         }
     }
 }
 
+/// When and how to retry a failed request. Delays between attempts grow
+/// exponentially: `backoff_ms`, then twice that, then four times, and so on, up
+/// to `max_backoff_ms`.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct RetryPolicy {
+    /// Total number of times to send the request, including the first
+    pub max_attempts: u32,
+    /// Response status codes that trigger a retry
+    pub statuses: Vec<u16>,
+    /// Retry when the request fails without a response, e.g. the connection
+    /// is refused or dropped
+    pub network_errors: bool,
+    /// Delay before the first retry, in milliseconds
+    pub backoff_ms: u64,
+    /// Upper bound on the delay between attempts, in milliseconds
+    pub max_backoff_ms: u64,
+    /// Randomize each delay between half and all of its value, so many
+    /// clients retrying at once don't stay in lockstep
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            statuses: vec![429, 502, 503, 504],
+            network_errors: true,
+            backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            jitter: true,
+        }
+    }
+}
+
+/// Limits on how long a request can take. Each limit is disabled if not given.
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct Timeout {
+    /// Max time to establish a connection to the server, including the TLS
+    /// handshake, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// Max time for the entire request, from sending it until the whole
+    /// response body is received, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_ms: Option<u64>,
+}
+
+/// A TLS client certificate to present during the handshake, for servers that
+/// require mutual TLS. The certificate is either PEM-encoded, with the key in
+/// the same file or a separate one, or a PKCS#12 archive (`.p12`/`.pfx`)
+/// containing both.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ClientCertificate {
+    /// Path to the certificate (chain). If `key` is omitted, this file must
+    /// contain the private key as well. A leading `~` is expanded to $HOME.
+    pub certificate: String,
+    /// Path to the private key, if it's not in the certificate file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Password to decrypt a PKCS#12 archive. Ignored for PEM certificates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+/// TLS verification settings for a single host. Everything is verified by
+/// default; each flag disables part of the verification.
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TlsHost {
+    /// Accept any certificate, including expired or self-signed ones. This
+    /// is the same as listing the host in `ignore_certificate_hosts`.
+    pub danger_accept_invalid_certs: bool,
+    /// Accept certificates that were issued for a different hostname. The
+    /// certificate must still be signed by one of the `ca_certificates`.
+    pub danger_accept_invalid_hostnames: bool,
+}
+
 /// Error creating or loading a config file
 #[derive(Debug, Error)]
 pub enum ConfigError {
@@ -278,11 +535,6 @@ pub enum ConfigError {
     Yaml(#[from] YamlError),
 }
 
-/// Error opening a configured editor/pager
-#[derive(Debug, Error)]
-#[error("Error opening editor; see {}", doc_link("user_guide/tui/editor"))]
-pub struct EditorError(#[source] EditorBuilderError);
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,6 +603,11 @@ mod tests {
             config,
             Config {
                 editor: None,
+                editor_file_types: IndexMap::new(),
+                trusted_directories: None,
+                audit_log: false,
+                confirm_sources: Vec::new(),
+                locale: None,
                 http: HttpEngineConfig {
                     large_body_size: 1000,
                     ..Default::default()
@@ -440,4 +697,54 @@ mod tests {
             Config::default_content()
         );
     }
+
+    /// An invalid regex in `allowed_hosts`/`denied_hosts` is an error when the
+    /// config is loaded, rather than when the first request is sent
+    #[rstest]
+    fn test_load_file_invalid_host_pattern(config_path: ConfigPath) {
+        fs::write(&config_path.path, "denied_hosts: [\"/api(/\"]\n").unwrap();
+        slumber_util::assert_err(Config::load(), "unclosed group");
+    }
+
+    #[rstest]
+    #[case::glob("*.example.com", "api.example.com", true)]
+    #[case::glob_literal_dot("a.com", "abcom", false)]
+    #[case::regex(r"/api-\d+\.example\.com/", "api-12.example.com", true)]
+    #[case::regex_no_match(
+        r"/api-\d+\.example\.com/",
+        "api-x.example.com",
+        false
+    )]
+    #[case::regex_anchored(r"/example\.com/", "example.com.evil", false)]
+    #[case::regex_alternation(r"/a\.com|b\.com/", "evil.a.com", false)]
+    #[case::regex_case_insensitive(
+        "/API\\.example\\.com/",
+        "api.example.com",
+        true
+    )]
+    #[case::slash_only("/", "/", true)]
+    fn test_host_pattern_regex(
+        #[case] pattern: &str,
+        #[case] host: &str,
+        #[case] expected: bool,
+    ) {
+        let regex = host_pattern_regex(pattern).unwrap();
+        assert_eq!(regex.is_match(host), expected);
+    }
+
+    /// File type overrides take priority over the `editor` field and env vars
+    #[rstest]
+    #[case::file_type("body.JSON", "jless")]
+    #[case::no_match("body.txt", "code")]
+    #[case::no_extension("body", "code")]
+    fn test_editor_file_types(#[case] path: &str, #[case] expected: &str) {
+        let _guard = env_lock::lock_env([("VISUAL", Some("nano"))]);
+        let config = Config {
+            editor: Some("code --wait".into()),
+            editor_file_types: [("json".into(), "jless".into())].into(),
+            ..Config::default()
+        };
+        let editor = config.editor(Path::new(path)).unwrap();
+        assert_eq!(editor.open(path).get_program(), expected);
+    }
 }
@@ -1,23 +1,34 @@
 //! TUI-specific configuration
 
+mod badge;
 mod input;
 mod mime;
+mod startup;
 mod theme;
 
+pub use badge::{EnvironmentBadge, EnvironmentBadges, HostPattern};
 pub use input::{Action, InputBinding, InputMap, KeyCombination};
-pub use theme::Theme;
+pub use startup::{ExchangeTab, RecipeTab, StartupConfig, StartupPane};
+pub use theme::{
+    Appearance, BorderKind, Palette, StyleKey, StyleModifier, StyleOverride,
+    Theme, ThemeBorders, ThemeMarkers, ThemePreset,
+};
 
-use crate::{EditorError, tui::mime::MimeMap};
+use crate::{EditorCommand, EditorError, tui::mime::MimeMap};
 use ::mime::Mime;
-use editor_command::Editor;
-use serde::Serialize;
-use std::env;
+use editor_command::EditorBuilderError;
+use serde::{
+    Deserialize, Serialize,
+    de::{self, value::StringDeserializer},
+};
+use std::{env, ffi::OsStr, iter, process::Command, str::FromStr};
 
 /// Configuration specific to the TUI
 #[derive(Debug, Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[cfg_attr(feature = "schema", schemars(default))]
+#[expect(clippy::struct_excessive_bools)] // These are all independent toggles
 pub struct TuiConfig {
     /// Configuration for in-app query and export commands
     pub commands: CommandsConfig,
@@ -30,6 +41,11 @@ pub struct TuiConfig {
     #[serde(alias = "viewer", default)]
     pub pager: MimeMap<String>,
 
+    /// Command to open the pager in a new terminal multiplexer pane/window
+    /// (e.g. tmux or zellij) instead of suspending the TUI. `{command}` is
+    /// replaced with the pager command and its arguments.
+    pub split_command: Option<String>,
+
     /// Should templates be rendered inline in the UI, or should we show
     /// the raw text?
     pub preview_templates: bool,
@@ -40,6 +56,37 @@ pub struct TuiConfig {
     /// Visual configuration for the TUI (e.g. colors)
     pub theme: Theme,
 
+    /// Show one pane at a time, filling the screen, instead of laying panes
+    /// out side by side. This linear layout works better with screen readers.
+    pub reader_mode: bool,
+
+    /// Animation shown next to in-flight requests
+    pub spinner: Spinner,
+
+    /// Disable all animation, including spinners and live request timers
+    pub reduced_motion: bool,
+
+    /// Make URLs on screen clickable, using OSC 8 hyperlinks. By default,
+    /// this is only enabled in terminals that are known to support it
+    pub hyperlinks: HyperlinkMode,
+
+    /// Badges to show next to requests based on the host they're sent to,
+    /// keyed by hostname glob pattern (e.g. `*.prod.example.com`). Use this to
+    /// make it obvious when a request is going to a sensitive environment,
+    /// regardless of the profile name.
+    pub environment_badges: EnvironmentBadges,
+
+    /// Mark lines in a response body that changed since the previous response
+    /// for the same recipe+profile
+    pub diff_gutter: bool,
+
+    /// When a request fails because the network is unreachable, queue it and
+    /// send it again automatically once the host can be reached
+    pub offline_queue: bool,
+
+    /// Initial pane focus and tab selection when the TUI launches
+    pub startup: StartupConfig,
+
     /// Enable debug monitor in TUI
     ///
     /// Mainly meant for development so don't expose it
@@ -54,10 +101,13 @@ pub struct TuiConfig {
 }
 
 impl TuiConfig {
-    /// Get an [Editor] to open the given file in the user's configured file
-    /// pager. Default is `less` on Unix, `more` on Windows. Return an error
-    /// if the command couldn't be built.
-    pub fn pager(&self, mime: Option<&Mime>) -> Result<Editor, EditorError> {
+    /// Get an [EditorCommand] to open the given file in the user's configured
+    /// file pager. Default is `less` on Unix, `more` on Windows. Return an
+    /// error if the command couldn't be built.
+    pub fn pager(
+        &self,
+        mime: Option<&Mime>,
+    ) -> Result<EditorCommand, EditorError> {
         // Use a built-in pager
         let default = if cfg!(windows) { "more" } else { "less" };
 
@@ -66,13 +116,63 @@ impl TuiConfig {
             .and_then(|mime| self.pager.get(mime))
             .map(String::as_str);
 
-        editor_command::EditorBuilder::new()
-            // Config field takes priority over environment variables
-            .string(config_command)
-            .string(env::var("PAGER").ok())
-            .string(Some(default))
-            .build()
-            .map_err(EditorError)
+        let env_command = env::var("PAGER").ok();
+        // Config field takes priority over environment variables
+        EditorCommand::new([
+            config_command,
+            env_command.as_deref(),
+            Some(default),
+        ])
+    }
+
+    /// If `split_command` is set, wrap a command so it runs in a new
+    /// multiplexer pane/window. Return `None` if it isn't set, or an error if
+    /// the configured command can't be parsed.
+    pub fn split(
+        &self,
+        command: &Command,
+    ) -> Result<Option<Command>, EditorError> {
+        let Some(split_command) = &self.split_command else {
+            return Ok(None);
+        };
+        let mut words = shell_words::split(split_command)
+            .map_err(|error| {
+                EditorError(EditorBuilderError::ParseError(error))
+            })?
+            .into_iter();
+        let program = words
+            .next()
+            .ok_or(EditorError(EditorBuilderError::EmptyCommand))?;
+        let inner = iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(OsStr::to_owned)
+            .collect::<Vec<_>>();
+
+        let mut split = Command::new(program);
+        let mut has_placeholder = false;
+        for word in words {
+            if word == "{command}" {
+                has_placeholder = true;
+                split.args(&inner);
+            } else {
+                split.arg(word);
+            }
+        }
+        // Without a placeholder, the command goes at the end
+        if !has_placeholder {
+            split.args(&inner);
+        }
+        Ok(Some(split))
+    }
+
+    /// Get the spinner to show for in-flight requests, or `None` if animation
+    /// is disabled
+    pub fn active_spinner(&self) -> Option<Spinner> {
+        if self.reduced_motion || self.spinner == Spinner::None {
+            None
+        } else {
+            Some(self.spinner)
+        }
     }
 }
 
@@ -81,15 +181,113 @@ impl Default for TuiConfig {
         Self {
             commands: CommandsConfig::default(),
             pager: Default::default(),
+            split_command: None,
             preview_templates: true,
             input_bindings: Default::default(),
             theme: Default::default(),
+            reader_mode: false,
+            spinner: Spinner::default(),
+            reduced_motion: false,
+            hyperlinks: HyperlinkMode::default(),
+            environment_badges: EnvironmentBadges::default(),
+            diff_gutter: false,
+            offline_queue: false,
+            startup: StartupConfig::default(),
             debug: false,
             persist: true,
         }
     }
 }
 
+/// Animation style for in-flight requests
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Spinner {
+    /// Braille dots: `⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏`
+    #[default]
+    Dots,
+    /// ASCII line: `|/-\`
+    Line,
+    /// Quarter circles: `◐◓◑◒`
+    Circle,
+    /// No spinner
+    None,
+}
+
+impl FromStr for Spinner {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// When should URLs be rendered as clickable hyperlinks?
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum HyperlinkMode {
+    /// Enable hyperlinks if the terminal is known to support them
+    #[default]
+    Auto,
+    /// Always enable hyperlinks. Terminals that don't support them should
+    /// ignore them, but some may print garbage instead
+    Always,
+    /// Never enable hyperlinks
+    Never,
+}
+
+impl HyperlinkMode {
+    /// Should hyperlinks be enabled? For `auto`, this checks environment
+    /// variables to detect the terminal
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Auto => Self::detect(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+
+    /// Guess whether the terminal supports OSC 8 hyperlinks. This errs on the
+    /// side of disabling them, because terminals that don't support them may
+    /// print the escape sequences verbatim.
+    fn detect() -> bool {
+        let var = |name: &str| env::var(name).ok().filter(|v| !v.is_empty());
+
+        // Multiplexers only pass hyperlinks through with extra configuration
+        if var("TMUX").is_some() || var("STY").is_some() {
+            return false;
+        }
+        // These are set by the terminal itself
+        if ["KITTY_WINDOW_ID", "WEZTERM_EXECUTABLE", "WT_SESSION"]
+            .into_iter()
+            .any(|name| var(name).is_some())
+        {
+            return true;
+        }
+        if var("TERM_PROGRAM").is_some_and(|program| {
+            ["ghostty", "iTerm.app", "vscode", "WezTerm"]
+                .contains(&program.as_str())
+        }) {
+            return true;
+        }
+        // GNOME Terminal and other VTE-based terminals support them since
+        // VTE 0.50
+        var("VTE_VERSION")
+            .and_then(|version| version.parse::<u32>().ok())
+            .is_some_and(|version| version >= 5000)
+    }
+}
+
+impl FromStr for HyperlinkMode {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
 /// Configuration for in-app query and export commands
 #[derive(Debug, Serialize)]
 #[cfg_attr(test, derive(PartialEq))]
@@ -102,6 +300,11 @@ pub struct CommandsConfig {
     /// Default query command for responses
     #[serde(default)]
     pub default_query: MimeMap<String>,
+    /// External commands to render response bodies for display, by content
+    /// type. The body is passed to stdin, and the output is shown in place
+    /// of the body. ANSI colors and styles in the output are preserved.
+    #[serde(default)]
+    pub renderers: MimeMap<String>,
 }
 
 impl Default for CommandsConfig {
@@ -118,6 +321,78 @@ impl Default for CommandsConfig {
         Self {
             shell: default_shell.iter().map(ToString::to_string).collect(),
             default_query: MimeMap::default(),
+            renderers: MimeMap::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// The wrapped command is inserted at the placeholder, or appended
+    #[rstest]
+    #[case::none(None, None)]
+    #[case::placeholder(
+        Some("tmux split-window -h {command}"),
+        Some(&["tmux", "split-window", "-h", "less", "-R", "file.txt"][..]),
+    )]
+    #[case::append(
+        Some("zellij run --"),
+        Some(&["zellij", "run", "--", "less", "-R", "file.txt"][..]),
+    )]
+    fn test_split(
+        #[case] split_command: Option<&str>,
+        #[case] expected: Option<&[&str]>,
+    ) {
+        let config = TuiConfig {
+            split_command: split_command.map(String::from),
+            ..TuiConfig::default()
+        };
+        let mut command = Command::new("less");
+        command.args(["-R", "file.txt"]);
+        let split = config.split(&command).unwrap();
+        let actual = split.as_ref().map(|split| {
+            iter::once(split.get_program())
+                .chain(split.get_args())
+                .map(|arg| arg.to_str().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert_eq!(actual.as_deref(), expected);
+    }
+
+    /// Detect hyperlink support from environment variables
+    #[rstest]
+    #[case::unknown(&[], false)]
+    #[case::kitty(&[("KITTY_WINDOW_ID", "1")], true)]
+    #[case::term_program(&[("TERM_PROGRAM", "WezTerm")], true)]
+    #[case::term_program_unknown(&[("TERM_PROGRAM", "Apple_Terminal")], false)]
+    #[case::vte_new(&[("VTE_VERSION", "7600")], true)]
+    #[case::vte_old(&[("VTE_VERSION", "4800")], false)]
+    #[case::tmux(&[("TMUX", "/tmp/tmux"), ("TERM_PROGRAM", "vscode")], false)]
+    fn test_hyperlinks_detect(
+        #[case] env: &[(&str, &str)],
+        #[case] expected: bool,
+    ) {
+        let vars = [
+            "TMUX",
+            "STY",
+            "KITTY_WINDOW_ID",
+            "WEZTERM_EXECUTABLE",
+            "WT_SESSION",
+            "TERM_PROGRAM",
+            "VTE_VERSION",
+        ];
+        let _guard = env_lock::lock_env(vars.map(|name| {
+            let value = env
+                .iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| *value);
+            (name, value)
+        }));
+        assert_eq!(HyperlinkMode::Auto.enabled(), expected);
+        assert!(HyperlinkMode::Always.enabled());
+        assert!(!HyperlinkMode::Never.enabled());
+    }
+}
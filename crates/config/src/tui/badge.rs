@@ -0,0 +1,211 @@
+use glob::{MatchOptions, Pattern, PatternError};
+use indexmap::IndexMap;
+use ratatui_core::style::Color;
+use serde::{Deserialize, Serialize};
+use slumber_util::yaml::{
+    self, DeserializeYaml, Expected, LocatedError, SourceMap, SourcedYaml,
+};
+use std::str::FromStr;
+
+/// A map of hostname patterns to badges. A request's badge is determined by
+/// the host it's sent to, regardless of which profile was used to build it.
+/// The first matching pattern wins, so more specific patterns should go
+/// first.
+#[derive(Debug, Default, Serialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(transparent)]
+pub struct EnvironmentBadges {
+    patterns: IndexMap<HostPattern, EnvironmentBadge>,
+}
+
+impl EnvironmentBadges {
+    /// Get the badge for the **first** pattern that matches the host.
+    /// Matching is case-insensitive.
+    pub fn get(&self, host: &str) -> Option<&EnvironmentBadge> {
+        self.patterns
+            .iter()
+            .find(|(pattern, _)| pattern.matches(host))
+            .map(|(_, badge)| badge)
+    }
+}
+
+impl FromIterator<(HostPattern, EnvironmentBadge)> for EnvironmentBadges {
+    fn from_iter<T: IntoIterator<Item = (HostPattern, EnvironmentBadge)>>(
+        iter: T,
+    ) -> Self {
+        Self {
+            patterns: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl DeserializeYaml for EnvironmentBadges {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        Ok(Self {
+            patterns: DeserializeYaml::deserialize(yaml, source_map)?,
+        })
+    }
+}
+
+/// A label to show next to requests sent to a particular environment
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct EnvironmentBadge {
+    /// Text of the badge, e.g. `PROD`
+    pub label: String,
+    /// Background color of the badge
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "super::theme::schema::Color")
+    )]
+    pub color: Color,
+}
+
+/// Newtype for [glob::Pattern] so we can define ser/de for it. `*` matches any
+/// sequence of characters, including `.`
+#[derive(
+    Clone,
+    Debug,
+    derive_more::Display,
+    derive_more::Deref,
+    Serialize,
+    Deserialize,
+    Eq,
+    Hash,
+    PartialEq,
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(try_from = "String", into = "String")]
+pub struct HostPattern(Pattern);
+
+impl HostPattern {
+    fn matches(&self, host: &str) -> bool {
+        self.0.matches_with(
+            host,
+            MatchOptions {
+                case_sensitive: false,
+                ..MatchOptions::default()
+            },
+        )
+    }
+}
+
+impl From<HostPattern> for String {
+    fn from(value: HostPattern) -> Self {
+        value.to_string()
+    }
+}
+
+impl FromStr for HostPattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+impl TryFrom<String> for HostPattern {
+    type Error = PatternError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+/// Deserialize via FromStr
+impl DeserializeYaml for HostPattern {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        _source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = yaml.try_into_string()?;
+        s.parse()
+            .map_err(|error| LocatedError::other(error, location))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_util::{
+        assert_err,
+        yaml::{deserialize_yaml, yaml_mapping},
+    };
+
+    #[rstest]
+    #[case::exact("localhost", Some("LOCAL"))]
+    #[case::glob("api.prod.example.com", Some("PROD"))]
+    #[case::glob_nested("a.b.prod.example.com", Some("PROD"))]
+    #[case::case_insensitive("API.PROD.example.com", Some("PROD"))]
+    // Earlier patterns take priority
+    #[case::priority("admin.staging.example.com", Some("ADMIN"))]
+    #[case::fallback("api.staging.example.com", Some("STAGING"))]
+    #[case::no_match("example.com", None)]
+    fn test_get(#[case] host: &str, #[case] expected: Option<&str>) {
+        let badges: EnvironmentBadges = [
+            ("localhost", "LOCAL"),
+            ("*.prod.example.com", "PROD"),
+            ("admin.*", "ADMIN"),
+            ("*.staging.example.com", "STAGING"),
+        ]
+        .into_iter()
+        .map(|(pattern, label)| {
+            (
+                pattern.parse().unwrap(),
+                EnvironmentBadge {
+                    label: label.to_owned(),
+                    color: Color::Red,
+                },
+            )
+        })
+        .collect();
+        assert_eq!(
+            badges.get(host).map(|badge| badge.label.as_str()),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let badges = deserialize_yaml::<EnvironmentBadges>(yaml_mapping([(
+            "*.prod.example.com",
+            yaml_mapping([("label", "PROD"), ("color", "red")]),
+        )]))
+        .unwrap();
+        assert_eq!(
+            badges,
+            [(
+                "*.prod.example.com".parse().unwrap(),
+                EnvironmentBadge {
+                    label: "PROD".into(),
+                    color: Color::Red,
+                }
+            )]
+            .into_iter()
+            .collect()
+        );
+
+        assert_err!(
+            deserialize_yaml::<EnvironmentBadges>(yaml_mapping([(
+                "[prod",
+                yaml_mapping([("label", "PROD"), ("color", "red")]),
+            )]))
+            .map_err(LocatedError::into_error),
+            "invalid range pattern"
+        );
+    }
+}
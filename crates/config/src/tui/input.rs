@@ -164,6 +164,10 @@ pub enum Action {
     View,
     /// Browse request history
     History,
+    /// Go back to the previously selected recipe/request
+    Back,
+    /// Go forward to the next selected recipe/request, after going back
+    Forward,
     /// Start a search/filter operation
     #[display("Search/Filter")]
     Search,
@@ -184,6 +188,14 @@ pub enum Action {
     /// Search command history in query text box
     #[display("Search Command History")]
     SearchHistory,
+    /// Bookmark the current position in a text window. The next key pressed
+    /// names the bookmark
+    #[display("Set Bookmark")]
+    SetBookmark,
+    /// Jump to a bookmark in a text window. The next key pressed names the
+    /// bookmark
+    #[display("Jump to Bookmark")]
+    JumpToBookmark,
     /// Select the lower pane in the current layout
     ///
     /// Aliases provide backward compatibility
@@ -194,6 +206,21 @@ pub enum Action {
     SelectCollection,
     /// Select profile list pane
     SelectProfileList,
+    /// Open the profile quick switcher
+    #[display("Switch Profile")]
+    SwitchProfile,
+    /// Open the menu to enable/disable profile toggles
+    #[display("Toggles")]
+    OpenToggles,
+    /// Send the most recent request again, regardless of what's selected
+    #[display("Resend Last Request")]
+    ResendLast,
+    /// Open the list of recent requests, to send one again
+    #[display("Recent Requests")]
+    RecentRequests,
+    /// Open the list of requests waiting for the network to come back
+    #[display("Offline Queue")]
+    OfflineQueue,
     /// Select recipe list pane
     SelectRecipeList,
     /// Select the upper pane in the current layout
@@ -263,6 +290,12 @@ impl InputBinding {
     pub fn matches(&self, event: &KeyEvent) -> bool {
         self.0.iter().any(|combo| combo.matches(event))
     }
+
+    /// Get the first key combination in the binding, which is the one shown
+    /// to the user
+    pub fn first(&self) -> Option<KeyCombination> {
+        self.0.first().copied()
+    }
 }
 
 impl Display for InputBinding {
@@ -310,8 +343,7 @@ impl From<KeyCode> for InputBinding {
 }
 
 /// Key input sequence, which can trigger an action
-#[derive(Copy, Clone, Debug, Serialize)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(into = "String", try_from = "String")]
 pub struct KeyCombination {
@@ -336,6 +368,19 @@ impl KeyCombination {
         to_lowercase(event.code) == to_lowercase(self.code)
             && event.modifiers == self.modifiers
     }
+
+    /// Stringify in the same format that's parsed from config. Return `None`
+    /// if the key code has no name (e.g. media keys), because it couldn't be
+    /// parsed back
+    pub fn to_config_string(self) -> Option<String> {
+        if KEY_CODES.get_label(self.code).is_some()
+            || matches!(self.code, KeyCode::Char(_))
+        {
+            Some(self.into())
+        } else {
+            None
+        }
+    }
 }
 
 /// User-friendly and compact display for a key combination. This is meant to
@@ -502,6 +547,14 @@ impl Default for InputMap {
             Action::Fullscreen => KeyCode::Char('f').into(),
             Action::ReloadCollection => KeyCode::F(5).into(),
             Action::History => KeyCode::Char('h').into(),
+            Action::Back => KeyCombination {
+                code: KeyCode::Left,
+                modifiers: KeyModifiers::ALT,
+            }.into(),
+            Action::Forward => KeyCombination {
+                code: KeyCode::Right,
+                modifiers: KeyModifiers::ALT,
+            }.into(),
             Action::Search => KeyCode::Char('/').into(),
             Action::Export => KeyCode::Char(':').into(),
             Action::PreviousPane => KeyCombination {
@@ -528,9 +581,19 @@ impl Default for InputMap {
                 code: KeyCode::Char('r'),
                 modifiers: KeyModifiers::CTRL,
             }.into(),
+            Action::SetBookmark => KeyCode::Char('m').into(),
+            Action::JumpToBookmark => KeyCode::Char('\'').into(),
             Action::SelectBottomPane => KeyCode::Char('2').into(),
             Action::SelectCollection => KeyCode::F(3).into(),
             Action::SelectProfileList => KeyCode::Char('p').into(),
+            Action::SwitchProfile => KeyCombination {
+                code: KeyCode::Char('p'),
+                modifiers: KeyModifiers::CTRL,
+            }.into(),
+            Action::OpenToggles => KeyCode::Char('t').into(),
+            Action::ResendLast => KeyCode::Char('.').into(),
+            Action::RecentRequests => KeyCode::Char(';').into(),
+            Action::OfflineQueue => KeyCode::Char('o').into(),
             Action::SelectRecipeList => KeyCode::Char('r').into(),
             Action::SelectTopPane => KeyCode::Char('1').into(),
             // ^^^^^ If making changes, make sure to update the docs ^^^^^
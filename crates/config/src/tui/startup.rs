@@ -0,0 +1,134 @@
+use serde::{
+    Deserialize, Serialize,
+    de::{self, value::StringDeserializer},
+};
+use std::str::FromStr;
+
+/// Initial state of the TUI when it launches. Each field that isn't set falls
+/// back to the state from the previous session.
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default, deny_unknown_fields)]
+pub struct StartupConfig {
+    /// Pane to select at startup
+    pub focus: Option<StartupPane>,
+    /// Open the previously selected request/response at startup. If disabled,
+    /// the Request/Response pane stays empty until a request is selected
+    pub open_last_response: bool,
+    /// Tab to select in the Recipe pane at startup
+    pub recipe_tab: Option<RecipeTab>,
+    /// Tab to select in the Request/Response pane at startup
+    pub exchange_tab: Option<ExchangeTab>,
+}
+
+impl Default for StartupConfig {
+    fn default() -> Self {
+        Self {
+            focus: None,
+            open_last_response: true,
+            recipe_tab: None,
+            exchange_tab: None,
+        }
+    }
+}
+
+/// A pane that can be selected at startup
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum StartupPane {
+    /// Recipe list, in the sidebar
+    RecipeList,
+    /// Profile list, in the sidebar
+    ProfileList,
+    /// Request history list, in the sidebar
+    History,
+    /// Recipe pane
+    Recipe,
+    /// Request/Response pane
+    Exchange,
+}
+
+impl FromStr for StartupPane {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// A tab in the Recipe pane
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeTab {
+    Url,
+    Body,
+    Query,
+    Headers,
+    Authentication,
+}
+
+impl FromStr for RecipeTab {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// A tab in the Request/Response pane
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ExchangeTab {
+    Request,
+    Body,
+    Headers,
+}
+
+impl FromStr for ExchangeTab {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slumber_util::{
+        assert_err,
+        yaml::{LocatedError, deserialize_yaml, yaml_mapping},
+    };
+
+    #[test]
+    fn test_deserialize() {
+        let startup = deserialize_yaml::<StartupConfig>(yaml_mapping([
+            ("focus", serde_yaml::Value::from("history")),
+            ("open_last_response", false.into()),
+            ("exchange_tab", "headers".into()),
+        ]))
+        .unwrap();
+        assert_eq!(
+            startup,
+            StartupConfig {
+                focus: Some(StartupPane::History),
+                open_last_response: false,
+                recipe_tab: None,
+                exchange_tab: Some(ExchangeTab::Headers),
+            }
+        );
+
+        assert_err!(
+            deserialize_yaml::<StartupConfig>(yaml_mapping([(
+                "recipe_tab",
+                "response"
+            )]))
+            .map_err(LocatedError::into_error),
+            "unknown variant `response`"
+        );
+    }
+}
@@ -1,5 +1,10 @@
-use ratatui_core::style::Color;
-use serde::{Deserialize, Serialize};
+use indexmap::IndexMap;
+use ratatui_core::style::{Color, Modifier, Style};
+use serde::{
+    Deserialize, Serialize,
+    de::{self, value::StringDeserializer},
+};
+use std::{env, str::FromStr};
 
 /// User-configurable visual settings. These are used to generate the full style
 /// set.
@@ -8,6 +13,29 @@ use serde::{Deserialize, Serialize};
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default, deny_unknown_fields)]
 pub struct Theme {
+    /// Base set of styles that the colors are applied to
+    pub preset: ThemePreset,
+    /// Use color at all? If disabled, color cues are replaced with text
+    /// markers such as `[SELECTED]` and `[ERROR]`. Color is also disabled if
+    /// the `NO_COLOR` environment variable is set.
+    pub color: bool,
+    /// Draw borders and other decorations with plain ASCII characters instead
+    /// of Unicode box drawing characters and symbols. Use this if your
+    /// terminal or font doesn't render those characters well.
+    pub ascii: bool,
+    /// Base colors for the theme. Any color field that's set explicitly
+    /// overrides the palette.
+    pub palette: Palette,
+    /// Text markers shown next to semantic colors, so the meaning doesn't
+    /// rely on color alone
+    pub markers: ThemeMarkers,
+    /// Border characters for each component. Any border that isn't set uses
+    /// the default for the preset and `ascii` setting.
+    pub borders: ThemeBorders,
+    /// Text modifiers that are never drawn, e.g. `italic` if your font doesn't
+    /// have an italic variant. This applies to all text in the TUI, including
+    /// modifiers added by `overrides`.
+    pub disabled_modifiers: Vec<StyleModifier>,
     /// Color for primary content such as the selected pane
     #[cfg_attr(feature = "schema", schemars(with = "schema::Color"))]
     pub primary_color: Color,
@@ -28,23 +56,506 @@ pub struct Theme {
     /// Color representing error (e.g. for 4xx status codes)
     #[cfg_attr(feature = "schema", schemars(with = "schema::Color"))]
     pub error_color: Color,
+    /// Overrides for individual styles, applied on top of the styles
+    /// generated from the rest of the theme
+    pub overrides: IndexMap<StyleKey, StyleOverride>,
+    /// Theme to use instead of this one when the terminal has a light
+    /// background. The variant replaces this theme entirely; fields are *not*
+    /// inherited.
+    pub light: Option<Box<Theme>>,
+    /// Theme to use instead of this one when the terminal has a dark
+    /// background. The variant replaces this theme entirely; fields are *not*
+    /// inherited.
+    pub dark: Option<Box<Theme>>,
 }
 
 impl Default for Theme {
     fn default() -> Self {
+        Self::new(ThemePreset::default(), Palette::default())
+    }
+}
+
+impl Theme {
+    /// Get a default theme with a particular preset and color palette. If the
+    /// preset defines its own colors, they take precedence over the palette.
+    pub fn new(preset: ThemePreset, palette: Palette) -> Self {
+        let colors = preset.colors().unwrap_or_else(|| palette.colors());
         Self {
-            primary_color: Color::Blue,
-            primary_text_color: Color::White,
-            secondary_color: Color::Yellow,
-            success_color: Color::Green,
-            error_color: Color::Red,
+            preset,
+            color: true,
+            ascii: false,
+            palette,
+            markers: ThemeMarkers::default(),
+            borders: ThemeBorders::default(),
+            disabled_modifiers: Vec::new(),
+            primary_color: colors.primary,
+            primary_text_color: colors.primary_text,
+            secondary_color: colors.secondary,
+            success_color: colors.success,
+            error_color: colors.error,
+            overrides: IndexMap::new(),
+            light: None,
+            dark: None,
         }
     }
+
+    /// Get a default theme with a particular color palette
+    pub fn from_palette(palette: Palette) -> Self {
+        Self::new(ThemePreset::default(), palette)
+    }
+
+    /// Select the variant of this theme for the terminal's appearance. If the
+    /// appearance is unknown or there's no variant defined for it, return the
+    /// theme as-is.
+    #[must_use]
+    pub fn resolve(mut self, appearance: Option<Appearance>) -> Self {
+        let variant = match appearance {
+            Some(Appearance::Light) => self.light.take(),
+            Some(Appearance::Dark) => self.dark.take(),
+            None => None,
+        };
+        variant.map_or(self, |theme| *theme)
+    }
+
+    /// Does this theme define a light or dark variant? If not, the terminal
+    /// appearance has no effect on it
+    pub fn has_variants(&self) -> bool {
+        self.light.is_some() || self.dark.is_some()
+    }
+
+    /// Should color be used? This checks both the `color` field and the
+    /// `NO_COLOR` environment variable. See <https://no-color.org/>
+    pub fn use_color(&self) -> bool {
+        self.color
+            && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+    }
+}
+
+/// Light/dark appearance of the terminal, used to select a theme variant
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Appearance {
+    Light,
+    Dark,
+}
+
+impl FromStr for Appearance {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// Base set of styles for the theme. Theme colors are applied on top of the
+/// preset. Some presets also bundle a well-known color scheme, which replaces
+/// the palette.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ThemePreset {
+    #[default]
+    Default,
+    /// Maximize contrast: no dimmed or dark gray text, and selections are
+    /// bold and reversed instead of relying on a background color
+    HighContrast,
+    /// Colors from the dark variant of [Gruvbox](https://github.com/morhetz/gruvbox)
+    Gruvbox,
+    /// Colors from [Solarized](https://ethanschoonover.com/solarized/)
+    /// (dark)
+    SolarizedDark,
+    /// Colors from [Dracula](https://draculatheme.com/)
+    Dracula,
+    /// Colors from [Nord](https://www.nordtheme.com/)
+    Nord,
+}
+
+impl ThemePreset {
+    /// Get the color scheme bundled with this preset. `None` for presets that
+    /// only define styles, in which case colors come from the palette.
+    fn colors(self) -> Option<ThemeColors> {
+        let colors = match self {
+            Self::Default | Self::HighContrast => return None,
+            Self::Gruvbox => ThemeColors {
+                primary: Color::Rgb(131, 165, 152),
+                primary_text: Color::Rgb(40, 40, 40),
+                secondary: Color::Rgb(250, 189, 47),
+                success: Color::Rgb(184, 187, 38),
+                error: Color::Rgb(251, 73, 52),
+            },
+            Self::SolarizedDark => ThemeColors {
+                primary: Color::Rgb(38, 139, 210),
+                primary_text: Color::Rgb(253, 246, 227),
+                secondary: Color::Rgb(181, 137, 0),
+                success: Color::Rgb(133, 153, 0),
+                error: Color::Rgb(220, 50, 47),
+            },
+            Self::Dracula => ThemeColors {
+                primary: Color::Rgb(189, 147, 249),
+                primary_text: Color::Rgb(40, 42, 54),
+                secondary: Color::Rgb(241, 250, 140),
+                success: Color::Rgb(80, 250, 123),
+                error: Color::Rgb(255, 85, 85),
+            },
+            Self::Nord => ThemeColors {
+                primary: Color::Rgb(136, 192, 208),
+                primary_text: Color::Rgb(46, 52, 64),
+                secondary: Color::Rgb(235, 203, 139),
+                success: Color::Rgb(163, 190, 140),
+                error: Color::Rgb(191, 97, 106),
+            },
+        };
+        Some(colors)
+    }
+}
+
+impl FromStr for ThemePreset {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Use serde's implementation for consistency with serialization
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// Base colors for a theme
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    /// Standard terminal colors, which adapt to the terminal's color scheme
+    #[default]
+    Default,
+    /// Safe for red-green color blindness (green-weak)
+    Deuteranopia,
+    /// Safe for red-green color blindness (red-weak)
+    Protanopia,
+    /// Safe for blue-yellow color blindness
+    Tritanopia,
+}
+
+impl Palette {
+    /// Get the base colors for this palette
+    fn colors(self) -> ThemeColors {
+        // Color-blind palettes are based on the Okabe-Ito palette:
+        // https://jfly.uni-koeln.de/color/
+        const BLUE: Color = Color::Rgb(0, 114, 178);
+        const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+        const BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+        const YELLOW: Color = Color::Rgb(240, 228, 66);
+        const ORANGE: Color = Color::Rgb(230, 159, 0);
+        const VERMILLION: Color = Color::Rgb(213, 94, 0);
+        const REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+
+        let (primary, secondary, success, error) = match self {
+            Self::Default => {
+                (Color::Blue, Color::Yellow, Color::Green, Color::Red)
+            }
+            // Red and green are indistinguishable, so use blue for success
+            Self::Deuteranopia => (BLUE, YELLOW, SKY_BLUE, VERMILLION),
+            // Red is also very dark, so use a brighter orange for errors
+            Self::Protanopia => (BLUE, YELLOW, SKY_BLUE, ORANGE),
+            // Blue/green and yellow/violet are hard to tell apart, but
+            // red/green is fine
+            Self::Tritanopia => {
+                (BLUE, REDDISH_PURPLE, BLUISH_GREEN, VERMILLION)
+            }
+        };
+        ThemeColors {
+            primary,
+            primary_text: Color::White,
+            secondary,
+            success,
+            error,
+        }
+    }
+}
+
+impl FromStr for Palette {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// Default values for the color fields of [Theme], provided by a palette or
+/// preset
+struct ThemeColors {
+    primary: Color,
+    primary_text: Color,
+    secondary: Color,
+    success: Color,
+    error: Color,
+}
+
+/// Text markers that accompany semantic colors. Each marker is shown before
+/// the colored text, separated by a space. If a marker is omitted, it's only
+/// shown when color is disabled. Use an empty string to never show it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeMarkers {
+    /// Marker for the selected item in a list. Default without color:
+    /// `[SELECTED]`
+    pub selected: Option<String>,
+    /// Marker for success values, such as 2xx status codes
+    pub success: Option<String>,
+    /// Marker for error values, such as 4xx/5xx status codes. Default without
+    /// color: `[ERROR]`
+    pub error: Option<String>,
+}
+
+/// Border characters for each bordered component. `None` uses the default
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeBorders {
+    /// Border of a pane when not selected. Default: `rounded`
+    pub pane: Option<BorderKind>,
+    /// Border of the selected pane. Default: `double`
+    pub pane_selected: Option<BorderKind>,
+    /// Border of modals. Default: `double`
+    pub modal: Option<BorderKind>,
+    /// Border of menus. Default: `rounded`
+    pub menu: Option<BorderKind>,
+}
+
+/// A set of border characters
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum BorderKind {
+    /// Single line with square corners: `┌─┐`
+    Plain,
+    /// Single line with rounded corners: `╭─╮`
+    Rounded,
+    /// Double line: `╔═╗`
+    Double,
+    /// Thick single line: `┏━┓`
+    Thick,
+    /// Plain ASCII characters: `+-+`
+    Ascii,
+}
+
+impl FromStr for BorderKind {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// Name of an individual style in the TUI that can be overridden. Each key is
+/// `<group>.<style>`, where the group is generally a single component.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum StyleKey {
+    /// Input field title in a form when not selected
+    #[serde(rename = "form.title")]
+    FormTitle,
+    /// Input field title in a form when selected
+    #[serde(rename = "form.title_highlight")]
+    FormTitleHighlight,
+    /// Highlighted item in a list
+    #[serde(rename = "list.highlight")]
+    ListHighlight,
+    /// Highlighted item in a list that isn't in focus
+    #[serde(rename = "list.highlight_inactive")]
+    ListHighlightInactive,
+    /// Disabled item in a list
+    #[serde(rename = "list.disabled")]
+    ListDisabled,
+    /// Modal border
+    #[serde(rename = "modal.border")]
+    ModalBorder,
+    /// Pane border when not selected
+    #[serde(rename = "pane.border")]
+    PaneBorder,
+    /// Pane border when selected
+    #[serde(rename = "pane.border_selected")]
+    PaneBorderSelected,
+    /// Success HTTP status codes (2xx)
+    #[serde(rename = "status_code.success")]
+    StatusCodeSuccess,
+    /// Error HTTP status codes (4xx/5xx)
+    #[serde(rename = "status_code.error")]
+    StatusCodeError,
+    /// Disabled tab
+    #[serde(rename = "tab.disabled")]
+    TabDisabled,
+    /// Selected tab
+    #[serde(rename = "tab.highlight")]
+    TabHighlight,
+    /// Table column header
+    #[serde(rename = "table.header")]
+    TableHeader,
+    /// Table row text
+    #[serde(rename = "table.text")]
+    TableText,
+    /// Alternating table rows
+    #[serde(rename = "table.alt")]
+    TableAlt,
+    /// Disabled table row
+    #[serde(rename = "table.disabled")]
+    TableDisabled,
+    /// Selected table row
+    #[serde(rename = "table.highlight")]
+    TableHighlight,
+    /// Table title
+    #[serde(rename = "table.title")]
+    TableTitle,
+    /// Rendered template preview
+    #[serde(rename = "template_preview.text")]
+    TemplatePreviewText,
+    /// Template preview that failed to render
+    #[serde(rename = "template_preview.error")]
+    TemplatePreviewError,
+    /// Template preview that failed to render, but was replaced by a
+    /// `fallback()` default
+    #[serde(rename = "template_preview.fallback")]
+    TemplatePreviewFallback,
+    /// Template preview value that was loaded from a cache instead of being
+    /// computed, e.g. `command(cache=...)`
+    #[serde(rename = "template_preview.cached")]
+    TemplatePreviewCached,
+    /// Text that needs visual emphasis
+    #[serde(rename = "text.highlight")]
+    TextHighlight,
+    /// De-emphasized informational text
+    #[serde(rename = "text.hint")]
+    TextHint,
+    /// Text in the primary color
+    #[serde(rename = "text.primary")]
+    TextPrimary,
+    /// Templates that have been edited in the current session
+    #[serde(rename = "text.edited")]
+    TextEdited,
+    /// Error text
+    #[serde(rename = "text.error")]
+    TextError,
+    /// Titles
+    #[serde(rename = "text.title")]
+    TextTitle,
+    /// Text in a text box
+    #[serde(rename = "text_box.text")]
+    TextBoxText,
+    /// Cursor in a text box
+    #[serde(rename = "text_box.cursor")]
+    TextBoxCursor,
+    /// Placeholder text in an empty text box
+    #[serde(rename = "text_box.placeholder")]
+    TextBoxPlaceholder,
+    /// Text box with invalid contents
+    #[serde(rename = "text_box.invalid")]
+    TextBoxInvalid,
+    /// Line numbers next to large text
+    #[serde(rename = "text_window.gutter")]
+    TextWindowGutter,
+    /// Line numbers of bookmarked lines
+    #[serde(rename = "text_window.bookmark")]
+    TextWindowBookmark,
+    /// Diff marker for an added line
+    #[serde(rename = "text_window.diff_added")]
+    TextWindowDiffAdded,
+    /// Diff marker for a changed line
+    #[serde(rename = "text_window.diff_changed")]
+    TextWindowDiffChanged,
+    /// Diff marker for removed lines
+    #[serde(rename = "text_window.diff_removed")]
+    TextWindowDiffRemoved,
+}
+
+impl FromStr for StyleKey {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
+}
+
+/// Modifications to a single style. Colors replace the generated colors, and
+/// modifiers are added to or removed from the generated modifiers.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default, deny_unknown_fields)]
+pub struct StyleOverride {
+    /// Foreground (text) color
+    #[cfg_attr(feature = "schema", schemars(with = "Option<schema::Color>"))]
+    pub fg: Option<Color>,
+    /// Background color
+    #[cfg_attr(feature = "schema", schemars(with = "Option<schema::Color>"))]
+    pub bg: Option<Color>,
+    /// Modifiers to add to the style
+    pub modifiers: Vec<StyleModifier>,
+    /// Modifiers to remove from the style
+    pub remove_modifiers: Vec<StyleModifier>,
+}
+
+impl StyleOverride {
+    /// Apply this override on top of a style
+    pub fn apply(&self, mut style: Style) -> Style {
+        if let Some(fg) = self.fg {
+            style = style.fg(fg);
+        }
+        if let Some(bg) = self.bg {
+            style = style.bg(bg);
+        }
+        let modifiers = |modifiers: &[StyleModifier]| {
+            modifiers.iter().fold(Modifier::empty(), |acc, modifier| {
+                acc | Modifier::from(*modifier)
+            })
+        };
+        style
+            .add_modifier(modifiers(&self.modifiers))
+            .remove_modifier(modifiers(&self.remove_modifiers))
+    }
+}
+
+/// A text modifier, such as bold or italic. Not all terminals support every
+/// modifier.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum StyleModifier {
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    SlowBlink,
+    RapidBlink,
+    Reversed,
+    Hidden,
+    CrossedOut,
+}
+
+impl From<StyleModifier> for Modifier {
+    fn from(modifier: StyleModifier) -> Self {
+        match modifier {
+            StyleModifier::Bold => Self::BOLD,
+            StyleModifier::Dim => Self::DIM,
+            StyleModifier::Italic => Self::ITALIC,
+            StyleModifier::Underlined => Self::UNDERLINED,
+            StyleModifier::SlowBlink => Self::SLOW_BLINK,
+            StyleModifier::RapidBlink => Self::RAPID_BLINK,
+            StyleModifier::Reversed => Self::REVERSED,
+            StyleModifier::Hidden => Self::HIDDEN,
+            StyleModifier::CrossedOut => Self::CROSSED_OUT,
+        }
+    }
+}
+
+impl FromStr for StyleModifier {
+    type Err = de::value::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::deserialize(StringDeserializer::new(s.to_owned()))
+    }
 }
 
 /// Helpers for JSON Schema generation
 #[cfg(feature = "schema")]
-mod schema {
+pub(super) mod schema {
     /// ANSI color code
     ///
     /// This type accepts input beyond the enumerated values, but for simplicity
@@ -86,3 +597,216 @@ mod schema {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slumber_util::{
+        assert_err,
+        yaml::{LocatedError, deserialize_yaml, yaml_mapping},
+    };
+
+    /// The palette provides default colors, but explicit colors override it
+    #[test]
+    fn test_deserialize_palette() {
+        let theme = deserialize_yaml::<Theme>(yaml_mapping([
+            ("palette", "deuteranopia"),
+            ("error_color", "magenta"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            theme,
+            Theme {
+                error_color: Color::Magenta,
+                ..Theme::from_palette(Palette::Deuteranopia)
+            }
+        );
+
+        assert_err!(
+            deserialize_yaml::<Theme>(yaml_mapping([("palette", "purple")]))
+                .map_err(LocatedError::into_error),
+            "unknown variant `purple`"
+        );
+    }
+
+    /// Presets with a color scheme replace the palette, and explicit colors
+    /// override both
+    #[test]
+    fn test_deserialize_preset() {
+        let theme = deserialize_yaml::<Theme>(yaml_mapping([
+            ("preset", "gruvbox"),
+            ("palette", "tritanopia"),
+            ("primary_color", "#ffffff"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            theme,
+            Theme {
+                primary_color: Color::Rgb(255, 255, 255),
+                palette: Palette::Tritanopia,
+                ..Theme::new(ThemePreset::Gruvbox, Palette::default())
+            }
+        );
+        assert_eq!(theme.error_color, Color::Rgb(251, 73, 52));
+
+        // Style-only presets use the palette's colors
+        let theme = deserialize_yaml::<Theme>(yaml_mapping([
+            ("preset", "high_contrast"),
+            ("palette", "tritanopia"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            theme,
+            Theme {
+                preset: ThemePreset::HighContrast,
+                ..Theme::from_palette(Palette::Tritanopia)
+            }
+        );
+    }
+
+    /// Light and dark variants are full themes, selected by appearance
+    #[test]
+    fn test_deserialize_variants() {
+        let theme = deserialize_yaml::<Theme>(yaml_mapping([
+            ("primary_color", serde_yaml::Value::from("red")),
+            ("light", yaml_mapping([("preset", "high_contrast")])),
+            ("dark", yaml_mapping([("preset", "nord")])),
+        ]))
+        .unwrap();
+        let light = Theme::new(ThemePreset::HighContrast, Palette::default());
+        let dark = Theme::new(ThemePreset::Nord, Palette::default());
+        assert_eq!(
+            theme,
+            Theme {
+                primary_color: Color::Red,
+                light: Some(light.into()),
+                dark: Some(dark.into()),
+                ..Theme::default()
+            }
+        );
+
+        let theme = theme.resolve(Some(Appearance::Dark));
+        assert_eq!(theme, Theme::new(ThemePreset::Nord, Palette::default()));
+
+        // Variants can't be nested
+        assert_err!(
+            deserialize_yaml::<Theme>(yaml_mapping([(
+                "light",
+                yaml_mapping([("dark", yaml_mapping([("color", false)]))]),
+            )]))
+            .map_err(LocatedError::into_error),
+            "Unexpected field `dark`"
+        );
+    }
+
+    /// Border kinds can be set per component, and modifiers can be disabled
+    #[test]
+    fn test_deserialize_borders() {
+        let theme = deserialize_yaml::<Theme>(yaml_mapping([
+            (
+                "borders",
+                yaml_mapping([("pane", "plain"), ("modal", "thick")]),
+            ),
+            (
+                "disabled_modifiers",
+                serde_yaml::Value::Sequence(vec!["italic".into()]),
+            ),
+        ]))
+        .unwrap();
+        assert_eq!(
+            theme,
+            Theme {
+                borders: ThemeBorders {
+                    pane: Some(BorderKind::Plain),
+                    modal: Some(BorderKind::Thick),
+                    ..ThemeBorders::default()
+                },
+                disabled_modifiers: vec![StyleModifier::Italic],
+                ..Theme::default()
+            }
+        );
+
+        assert_err!(
+            deserialize_yaml::<Theme>(yaml_mapping([(
+                "borders",
+                yaml_mapping([("pane", "dotted")]),
+            )]))
+            .map_err(LocatedError::into_error),
+            "dotted"
+        );
+    }
+
+    /// Without a variant for the appearance, the base theme is used
+    #[test]
+    fn test_resolve_fallback() {
+        let theme = || Theme {
+            light: Some(Theme::from_palette(Palette::Tritanopia).into()),
+            ..Theme::default()
+        };
+        assert!(theme().has_variants());
+        assert!(!Theme::default().has_variants());
+        assert_eq!(theme().resolve(None).palette, Palette::default());
+        assert_eq!(
+            theme().resolve(Some(Appearance::Dark)).palette,
+            Palette::default()
+        );
+        assert_eq!(
+            theme().resolve(Some(Appearance::Light)).palette,
+            Palette::Tritanopia
+        );
+    }
+
+    #[test]
+    fn test_deserialize_overrides() {
+        let theme = deserialize_yaml::<Theme>(yaml_mapping([(
+            "overrides",
+            yaml_mapping([(
+                "pane.border_selected",
+                yaml_mapping([
+                    ("fg", serde_yaml::Value::from("#ff0000")),
+                    ("modifiers", vec!["italic", "underlined"].into()),
+                    ("remove_modifiers", vec!["bold"].into()),
+                ]),
+            )]),
+        )]))
+        .unwrap();
+        let style_override = &theme.overrides[&StyleKey::PaneBorderSelected];
+        assert_eq!(
+            style_override.apply(
+                Style::default()
+                    .fg(Color::Blue)
+                    .add_modifier(Modifier::BOLD)
+            ),
+            Style::default()
+                .fg(Color::Rgb(255, 0, 0))
+                .add_modifier(Modifier::ITALIC | Modifier::UNDERLINED)
+                .remove_modifier(Modifier::BOLD)
+        );
+
+        assert_err!(
+            deserialize_yaml::<Theme>(yaml_mapping([(
+                "overrides",
+                yaml_mapping([("pane.bored", yaml_mapping([("fg", "red")]))]),
+            )]))
+            .map_err(LocatedError::into_error),
+            "unknown variant `pane.bored`"
+        );
+    }
+
+    #[test]
+    fn test_deserialize_markers() {
+        let theme = deserialize_yaml::<Theme>(yaml_mapping([(
+            "markers",
+            yaml_mapping([("success", "✔"), ("error", "")]),
+        )]))
+        .unwrap();
+        assert_eq!(
+            theme.markers,
+            ThemeMarkers {
+                selected: None,
+                success: Some("✔".into()),
+                error: Some(String::new()),
+            }
+        );
+    }
+}
@@ -0,0 +1,68 @@
+//! Benchmarks for loading large collections. Run with:
+//!
+//! ```sh
+//! cargo bench -p slumber_core --bench load_collection
+//! ```
+
+use slumber_core::collection::CollectionFile;
+use std::{fmt::Write, fs, path::PathBuf};
+
+fn main() {
+    divan::main();
+}
+
+/// Number of recipes in each generated collection, spread across folders of
+/// 50 recipes each
+const SIZES: &[usize] = &[500, 5000];
+
+/// Load every template up front, as the CLI does
+#[divan::bench(args = SIZES)]
+fn load(bencher: divan::Bencher, recipes: usize) {
+    let file = collection_file(recipes);
+    bencher.bench(|| file.load().unwrap());
+}
+
+/// Defer template parsing until each template is used, as the TUI does
+#[divan::bench(args = SIZES)]
+fn load_deferred(bencher: divan::Bencher, recipes: usize) {
+    let file = collection_file(recipes);
+    bencher.bench(|| file.load_deferred().unwrap());
+}
+
+/// Write a collection with the given number of recipes to a temp file. Each
+/// recipe has a handful of templates, representative of a real collection.
+fn collection_file(recipes: usize) -> CollectionFile {
+    let mut yaml = String::from(
+        "profiles:\n  local:\n    data:\n      host: http://localhost\n\
+        requests:\n",
+    );
+    for i in 0..recipes {
+        if i % 50 == 0 {
+            writeln!(yaml, "  folder{i}:\n    requests:").unwrap();
+        }
+        write!(
+            yaml,
+            "      recipe{i}:
+        method: POST
+        url: \"{{{{ host }}}}/items/{i}/{{{{ response('recipe0') | jsonpath('$.id') }}}}\"
+        query:
+          search: \"{{{{ host }}}} {i}\"
+        headers:
+          Accept: application/json
+          X-Request-Id: \"{{{{ {i} }}}}\"
+        body:
+          type: json
+          data: {{\"id\": \"{{{{ host }}}}\", \"items\": [1, 2, \"{{{{ {i} }}}}\"]}}
+"
+        )
+        .unwrap();
+    }
+
+    let dir: PathBuf = [env!("CARGO_TARGET_TMPDIR"), "bench_collections"]
+        .iter()
+        .collect();
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(format!("slumber_{recipes}.yml"));
+    fs::write(&path, yaml).unwrap();
+    CollectionFile::new(Some(path)).unwrap()
+}
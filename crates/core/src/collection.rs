@@ -2,25 +2,39 @@
 //! possible
 
 mod cereal;
+#[cfg(feature = "native")]
+mod changelog;
 mod json;
 mod models;
 mod recipe_tree;
+#[cfg(feature = "native")]
+mod remote;
 #[cfg(feature = "schema")]
 mod schema;
 
 pub use cereal::HasId;
+#[cfg(feature = "native")]
+pub use changelog::{ChangelogError, CollectionCommit};
 pub use json::{JsonTemplate, JsonTemplateError};
 pub use models::*;
 pub use recipe_tree::*;
+#[cfg(feature = "native")]
+pub use remote::{RemoteError, RemoteSource, RemoteStatus};
 
+#[cfg(feature = "native")]
+use crate::database::{CollectionDatabase, DatabaseError};
 use itertools::Itertools;
+use slumber_template::Template;
+use slumber_util::STARTUP_TARGET;
+#[cfg(feature = "native")]
+use slumber_util::paths::expand_home;
 use std::{
     env,
     fmt::{self, Debug, Display},
     fs,
     path::{Path, PathBuf},
 };
-use tracing::{trace, warn};
+use tracing::{info_span, trace, warn};
 
 /// The support file names to be automatically loaded as a config. We only
 /// support loading from one file at a time, so if more than one of these is
@@ -38,8 +52,15 @@ const CONFIG_FILES: &[&str] = &[
 /// Invariants:
 /// - The path exists
 /// - The path points to a file
+///
+/// For a remote collection, the path is the locally cached copy.
 #[derive(Clone, Debug)]
-pub struct CollectionFile(PathBuf);
+pub struct CollectionFile {
+    path: PathBuf,
+    /// Populated if the collection was fetched from a URL or git repository
+    #[cfg(feature = "native")]
+    remote: Option<RemoteStatus>,
+}
 
 impl CollectionFile {
     /// Get a handle to the collection file, returning an error if none is
@@ -63,6 +84,11 @@ impl CollectionFile {
         // If the override is a dir, search that dir instead. If it's a file,
         // just return it
         if let Some(override_path) = override_path {
+            #[cfg(feature = "native")]
+            if let Some(source) = RemoteSource::parse(&override_path) {
+                return Self::fetch_remote(&override_path, source);
+            }
+
             let joined = dir.join(override_path);
             if fs::metadata(&joined)
                 .map_err(|error| CollectionError::Io {
@@ -73,38 +99,150 @@ impl CollectionFile {
             {
                 dir = joined;
             } else {
-                return Ok(Self(joined));
+                return Ok(Self::local(joined));
             }
         }
 
         detect_path(&dir)
             .ok_or_else(|| CollectionError::NoFile { path: dir })
-            .map(Self)
+            .map(Self::local)
+    }
+
+    fn local(path: PathBuf) -> Self {
+        Self {
+            path,
+            #[cfg(feature = "native")]
+            remote: None,
+        }
+    }
+
+    /// Fetch a remote collection into the cache, or revalidate the cached copy
+    #[cfg(feature = "native")]
+    fn fetch_remote(
+        override_path: &Path,
+        source: Result<RemoteSource, RemoteError>,
+    ) -> Result<Self, CollectionError> {
+        let remote_error = |error| CollectionError::Remote {
+            location: override_path.display().to_string(),
+            error: Box::new(error),
+        };
+        let remote = source
+            .and_then(|source| source.fetch())
+            .map_err(remote_error)?;
+        Ok(Self {
+            path: remote.path,
+            remote: Some(remote.status),
+        })
     }
 
     /// Load collection from this file. Use [Self::new] to get a handle to the
     /// file. This pattern enables the TUI to start up and watch the collection
     /// file, even if it's invalid.
     pub fn load(&self) -> Result<Collection, CollectionError> {
-        Collection::load(&self.0)
+        let _span =
+            info_span!(target: STARTUP_TARGET, "Load collection", path = ?self.path)
+                .entered();
+        Collection::load(&self.path)
+    }
+
+    /// Load collection from this file, deferring template parsing until each
+    /// template is first used. See [Template::defer_parsing].
+    ///
+    /// This makes loading large collections much faster, but invalid templates
+    /// aren't reported until they're rendered, so it should only be used where
+    /// render errors are shown to the user, i.e. the TUI.
+    pub fn load_deferred(&self) -> Result<Collection, CollectionError> {
+        Template::defer_parsing(|| self.load())
     }
 
     /// Get the path of the file that this collection was loaded from
     pub fn path(&self) -> &Path {
-        &self.0
+        &self.path
     }
 
     /// Get the directory that contains this file
     pub fn parent(&self) -> &Path {
         // This is safe because of the invariants: the path always points to a
         // file
-        self.0.parent().expect("Collection file does not exist")
+        self.path.parent().expect("Collection file does not exist")
+    }
+
+    /// Should this collection be given full access to the local system? If
+    /// `trusted_directories` is `None`, sandboxing is disabled so everything is
+    /// trusted. Otherwise, the collection is trusted if it's within one of
+    /// those directories or the user has explicitly trusted it.
+    ///
+    /// Remote collections are written by someone else, so they're sandboxed
+    /// unless the user has explicitly trusted them, regardless of
+    /// `trusted_directories`. That trust is pinned to the fetched content, so
+    /// the collection is sandboxed again whenever it changes upstream.
+    #[cfg(feature = "native")]
+    pub fn is_trusted(
+        &self,
+        trusted_directories: Option<&[String]>,
+        database: &CollectionDatabase,
+    ) -> bool {
+        if let Some(remote) = &self.remote {
+            // If we couldn't identify the content, we can't know that it's
+            // what the user trusted
+            return remote.fingerprint.as_deref().is_some_and(|fingerprint| {
+                database.is_trusted(Some(fingerprint)).unwrap_or(false)
+            });
+        }
+        let Some(trusted_directories) = trusted_directories else {
+            return true;
+        };
+        self.is_in_directories(trusted_directories)
+            // Error has already been logged; fall back to the safe option
+            || database.is_trusted(None).unwrap_or(false)
+    }
+
+    /// Was this remote collection trusted, but has changed upstream since?
+    /// Always `false` for local collections.
+    #[cfg(feature = "native")]
+    pub fn is_trust_outdated(&self, database: &CollectionDatabase) -> bool {
+        self.remote.is_some()
+            && database.is_trusted(None).unwrap_or(false)
+            && !self.is_trusted(None, database)
+    }
+
+    /// Explicitly trust this collection. For a remote collection, the trust
+    /// only applies to the content that's currently cached.
+    #[cfg(feature = "native")]
+    pub fn trust(
+        &self,
+        database: &CollectionDatabase,
+    ) -> Result<(), DatabaseError> {
+        let fingerprint = self
+            .remote
+            .as_ref()
+            .and_then(|remote| remote.fingerprint.as_deref());
+        database.set_trusted(true, fingerprint)
+    }
+
+    /// Is this file within any of the given directories? Paths are
+    /// canonicalized first, so `..` and symlinks can't be used to escape a
+    /// directory. A leading `~` in a directory is expanded to `$HOME`.
+    #[cfg(feature = "native")]
+    fn is_in_directories(&self, directories: &[String]) -> bool {
+        let Ok(path) = fs::canonicalize(&self.path) else {
+            return false;
+        };
+        directories.iter().any(|directory| {
+            fs::canonicalize(expand_home(Path::new(directory)))
+                .is_ok_and(|directory| path.starts_with(directory))
+        })
     }
 }
 
 impl Display for CollectionFile {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0.display())
+        // The cache path isn't meaningful to the user; show where it came from
+        #[cfg(feature = "native")]
+        if let Some(remote) = &self.remote {
+            return write!(f, "{}", remote.source);
+        }
+        write!(f, "{}", self.path.display())
     }
 }
 
@@ -155,7 +293,7 @@ fn detect_path(dir: &Path) -> Option<PathBuf> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{http::HttpMethod, test_util::by_id};
+    use crate::{database::Database, test_util::by_id};
     use indexmap::indexmap;
     use pretty_assertions::assert_eq;
     use rstest::rstest;
@@ -234,6 +372,81 @@ mod tests {
         );
     }
 
+    /// A collection is trusted if sandboxing is disabled, it's in a trusted
+    /// directory, or it was trusted explicitly
+    #[rstest]
+    #[case::sandbox_disabled(None, false, true)]
+    #[case::untrusted(Some(&["other"][..]), false, false)]
+    #[case::trusted_directory(Some(&["trusted"][..]), false, true)]
+    #[case::trusted_parent(Some(&["."][..]), false, true)]
+    #[case::trusted_explicitly(Some(&[][..]), true, true)]
+    fn test_is_trusted(
+        temp_dir: TempDir,
+        #[case] trusted_directories: Option<&[&str]>,
+        #[case] trusted_in_db: bool,
+        #[case] expected: bool,
+    ) {
+        let dir = temp_dir.join("trusted");
+        fs::create_dir(&dir).unwrap();
+        fs::create_dir(temp_dir.join("other")).unwrap();
+        let path = dir.join("slumber.yml");
+        File::create(&path).unwrap();
+        let file = CollectionFile::new(Some(path)).unwrap();
+        let database = Database::factory(()).into_collection(&file).unwrap();
+        database.set_trusted(trusted_in_db, None).unwrap();
+
+        let trusted_directories: Option<Vec<String>> =
+            trusted_directories.map(|directories| {
+                directories
+                    .iter()
+                    .map(|directory| {
+                        temp_dir.join(directory).to_string_lossy().into_owned()
+                    })
+                    .collect()
+            });
+        assert_eq!(
+            file.is_trusted(trusted_directories.as_deref(), &database),
+            expected
+        );
+    }
+
+    /// A remote collection is sandboxed unless it was trusted explicitly, even
+    /// if sandboxing is disabled for local collections. Trust only applies to
+    /// the content it was given for
+    #[rstest]
+    #[case::untrusted(false, None, Some("abc"), false)]
+    #[case::trusted(true, Some("abc"), Some("abc"), true)]
+    #[case::changed(true, Some("abc"), Some("def"), false)]
+    #[case::unpinned(true, None, Some("abc"), false)]
+    #[case::unknown_content(true, Some("abc"), None, false)]
+    fn test_is_trusted_remote(
+        temp_dir: TempDir,
+        #[case] trusted_in_db: bool,
+        #[case] trusted_fingerprint: Option<&str>,
+        #[case] fingerprint: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let path = temp_dir.join("slumber.yml");
+        File::create(&path).unwrap();
+        let file = CollectionFile {
+            path,
+            remote: Some(RemoteStatus {
+                source: "https://example.com/slumber.yml".into(),
+                fetched_at: Default::default(),
+                error: None,
+                fingerprint: fingerprint.map(String::from),
+            }),
+        };
+        let database = Database::factory(()).into_collection(&file).unwrap();
+        database
+            .set_trusted(trusted_in_db, trusted_fingerprint)
+            .unwrap();
+
+        let everything = vec![temp_dir.to_string_lossy().into_owned()];
+        assert_eq!(file.is_trusted(None, &database), expected);
+        assert_eq!(file.is_trusted(Some(&everything), &database), expected);
+    }
+
     /// Test various error cases when deserializing a collection. Make sure
     /// we get a useful error message for each one
     #[rstest]
@@ -284,6 +497,48 @@ mod tests {
         assert_err(Collection::parse(yaml), expected_error);
     }
 
+    /// Deferred loading leaves templates unparsed until they're used. Invalid
+    /// templates load fine and fail on use instead.
+    #[rstest]
+    fn test_load_deferred(temp_dir: TempDir) {
+        let path = temp_dir.join("slumber.yml");
+        fs::write(
+            &path,
+            "
+requests:
+  r1:
+    method: GET
+    url: http://localhost/{{ id }}
+  r2:
+    method: GET
+    url: http://localhost/{{ id
+",
+        )
+        .unwrap();
+        let file = CollectionFile::new(Some(path)).unwrap();
+        assert_err!(file.load(), "slumber.yml:8:9");
+
+        let collection = file.load_deferred().unwrap();
+        let url =
+            |id: &str| &collection.recipes.get_recipe(&id.into()).unwrap().url;
+        assert!(!url("r1").is_parsed());
+        assert!(!url("r2").is_parsed());
+
+        assert_eq!(url("r1").display(), "http://localhost/{{ id }}");
+        assert!(url("r1").is_parsed());
+        assert!(url("r1").parse_error().is_none());
+        assert!(url("r2").parse_error().is_some());
+    }
+
+    /// Deferred loading should produce the same collection as eager loading
+    #[rstest]
+    fn test_load_deferred_regression(test_data_dir: PathBuf) {
+        let file =
+            CollectionFile::new(Some(test_data_dir.join("regression.yml")))
+                .unwrap();
+        assert_eq!(file.load_deferred().unwrap(), file.load().unwrap());
+    }
+
     /// Should detect various indicators that the collection is v3 collection
     /// and provide a helpful error message about migration
     #[rstest]
@@ -331,6 +586,10 @@ requests:
             Collection::load(&test_data_dir.join("regression.yml")).unwrap();
         let expected = Collection {
             name: Some("Regression Test".to_owned()),
+            template_delimiters: None,
+            variables: indexmap! {
+                "api_version".into() => "v2".into(),
+            },
             profiles: by_id([
                 Profile {
                     id: "profile1".into(),
@@ -355,6 +614,20 @@ requests:
                     ..Profile::factory(())
                 },
             ]),
+            toggles: by_id([
+                Toggle {
+                    id: "admin".into(),
+                    name: Some("As Admin".into()),
+                    data: indexmap! {"username".into() => "admin".into()},
+                    ..Toggle::factory(())
+                },
+                Toggle {
+                    id: "verbose".into(),
+                    name: None,
+                    data: indexmap! {"verbose".into() => "true".into()},
+                    ..Toggle::factory(())
+                },
+            ]),
             recipes: by_id([
                 RecipeNode::Recipe(Recipe {
                     id: "text_body".into(),
@@ -385,6 +658,7 @@ requests:
                     id: "users".into(),
                     location: SourceLocation::default(),
                     name: Some("Users".into()),
+                    authentication: None,
                     children: by_id([
                         RecipeNode::Recipe(Recipe {
                             id: "simple".into(),
@@ -396,6 +670,18 @@ requests:
                                 "many".into() =>
                                     ["{{ field1 }}", "{{ field2 }}"].into(),
                             },
+                            params: indexmap! {
+                                "page".into() => RecipeParam {
+                                    param_type: RecipeParamType::Integer,
+                                    required: false,
+                                    default: Some("1".into()),
+                                    description: Some("Page of results".into()),
+                                },
+                                "search".into() => RecipeParam {
+                                    required: true,
+                                    ..RecipeParam::default()
+                                },
+                            },
                             ..Recipe::factory(())
                         }),
                         RecipeNode::Recipe(Recipe {
@@ -415,7 +701,7 @@ requests:
                             authentication: Some(Authentication::Bearer {
                                 token: "{{ response('login') | jsonpath('$.token') }}"
                                     .into(),
-                            }),
+                            }.into()),
                             headers: indexmap! {
                                 "accept".into() => "application/json".into(),
                             },
@@ -435,7 +721,7 @@ requests:
                             authentication: Some(Authentication::Basic {
                                 username: "{{ username }}".into(),
                                 password: Some("{{ password }}".into()),
-                            }),
+                            }.into()),
                             headers: indexmap! {
                                 "accept".into() => "application/json".into(),
                             },
@@ -7,27 +7,30 @@
 //! by serde/serde_yaml, because there's no need for error messages and the
 //! derive macros are sufficient to generate the corresponding YAML.
 
-use crate::{
-    collection::{
-        Authentication, Collection, Folder, JsonTemplate, Profile, ProfileId,
-        QueryParameterValue, Recipe, RecipeBody, RecipeId, RecipeTree,
-        recipe_tree::RecipeNode,
-    },
-    http::HttpMethod,
+use crate::collection::{
+    Assertions, Authentication, AuthenticationSetting, CaptureSelector, Chaos,
+    ClientCertificate, Collection, Compression, ContentEncoding, Folder,
+    FormPart, GraphQlBody, Grpc, HttpMethod, HttpProtocol, JsonTemplate,
+    OAuth2, OAuth2Grant, Pinning, Profile, ProfileId, QueryParameterValue,
+    Recipe, RecipeBody, RecipeId, RecipeParam, RecipeParamType, RecipeTree,
+    RedirectPolicy, RenderMode, RetryPolicy, Throttle, Timeout, Toggle,
+    ToggleId, WebSocket, WebSocketFrame, recipe_tree::RecipeNode,
 };
 use indexmap::IndexMap;
 use saphyr::{Scalar, YamlData};
-use slumber_template::Template;
+use slumber_template::{Delimiters, Template};
 use slumber_util::{
     deserialize_enum, impl_deserialize_from,
     yaml::{
         self, DeserializeYaml, Expected, Field, LocatedError, SourceMap,
-        SourcedYaml, StructDeserializer, yaml_parse_panic,
+        SourcedYaml, StructDeserializer, YamlErrorKind, yaml_parse_panic,
     },
 };
+use strum::IntoEnumIterator;
 
 impl_deserialize_from!(ProfileId, String);
 impl_deserialize_from!(RecipeId, String);
+impl_deserialize_from!(ToggleId, String);
 
 impl DeserializeYaml for Collection {
     fn expected() -> Expected {
@@ -45,18 +48,30 @@ impl DeserializeYaml for Collection {
             !key.data.as_str().is_some_and(|s| s.starts_with('.'))
         });
 
-        let collection = Self {
-            name: deserializer.get(Field::new("name").opt(), source_map)?,
-            profiles: deserializer
-                .get::<Adopt<_>>(Field::new("profiles").opt(), source_map)?
-                .0,
-            // Internally we call these recipes, but extensive market research
-            // shows that `requests` is more intuitive to the user
-            recipes: deserializer
-                .get(Field::new("requests").opt(), source_map)?,
-        };
-        deserializer.done()?;
-        Ok(collection)
+        // Delimiters have to be known before any templates are parsed
+        let template_delimiters: Option<Delimiters> = deserializer
+            .get(Field::new("template_delimiters").opt(), source_map)?;
+        let delimiters = template_delimiters.clone().unwrap_or_default();
+        delimiters.scope(|| {
+            let collection = Self {
+                name: deserializer.get(Field::new("name").opt(), source_map)?,
+                template_delimiters,
+                variables: deserializer
+                    .get(Field::new("variables").opt(), source_map)?,
+                profiles: deserializer
+                    .get::<Adopt<_>>(Field::new("profiles").opt(), source_map)?
+                    .0,
+                toggles: deserializer
+                    .get::<Adopt<_>>(Field::new("toggles").opt(), source_map)?
+                    .0,
+                // Internally we call these recipes, but extensive market
+                // research shows that `requests` is more intuitive to the user
+                recipes: deserializer
+                    .get(Field::new("requests").opt(), source_map)?,
+            };
+            deserializer.done()?;
+            Ok(collection)
+        })
     }
 }
 
@@ -125,12 +140,492 @@ impl DeserializeYaml for Profile {
             default: deserializer
                 .get(Field::new("default").opt(), source_map)?,
             data: deserializer.get(Field::new("data").opt(), source_map)?,
+            chaos: deserializer.get(Field::new("chaos").opt(), source_map)?,
+            certificate: deserializer
+                .get(Field::new("certificate").opt(), source_map)?,
+            proxy: deserializer.get(Field::new("proxy").opt(), source_map)?,
+            timeout: deserializer
+                .get(Field::new("timeout").opt(), source_map)?,
+            throttle: deserializer
+                .get(Field::new("throttle").opt(), source_map)?,
+            protocol: deserializer
+                .get(Field::new("protocol").opt(), source_map)?,
+            socket: deserializer.get(Field::new("socket").opt(), source_map)?,
+            resolve: deserializer
+                .get(Field::new("resolve").opt(), source_map)?,
+            pinning: deserializer
+                .get(Field::new("pinning").opt(), source_map)?,
         };
         deserializer.done()?;
         Ok(profile)
     }
 }
 
+/// Deserialize a map of toggles. This needs a custom implementation to call
+/// [HasId::set_id] on each value.
+impl DeserializeYaml for Adopt<IndexMap<ToggleId, Toggle>> {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        mut yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        yaml.drop_dot_fields();
+        yaml.try_into_mapping()?
+            .into_iter()
+            .map(|(k, v)| {
+                let key = ToggleId::deserialize(k, source_map)?;
+                let mut value = Toggle::deserialize(v, source_map)?;
+                value.set_id(key.clone());
+                Ok((key, value))
+            })
+            .collect::<yaml::Result<_>>()
+            .map(Adopt)
+    }
+}
+
+impl DeserializeYaml for Toggle {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location.resolve(source_map);
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let toggle = Self {
+            id: ToggleId::default(), // Will be set by parent based on key
+            location,
+            name: deserializer.get(Field::new("name").opt(), source_map)?,
+            data: deserializer.get(Field::new("data").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(toggle)
+    }
+}
+
+impl DeserializeYaml for Chaos {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let chaos = Self {
+            latency_ms: deserializer
+                .get::<usize>(Field::new("latency_ms").opt(), source_map)?
+                as u64,
+            drop_percent: deserializer
+                .get::<Percent>(Field::new("drop_percent").opt(), source_map)?
+                .0,
+            error_percent: deserializer
+                .get::<Percent>(Field::new("error_percent").opt(), source_map)?
+                .0,
+            seed: deserializer
+                .get::<Option<usize>>(Field::new("seed").opt(), source_map)?
+                .map(|seed| seed as u64),
+        };
+        deserializer.done()?;
+        Ok(chaos)
+    }
+}
+
+impl DeserializeYaml for RetryPolicy {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let default = Self::default();
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let policy = Self {
+            max_attempts: deserializer.get::<usize>(
+                Field::new("max_attempts").or(default.max_attempts as usize),
+                source_map,
+            )? as u32,
+            statuses: deserializer
+                .get::<Vec<usize>>(
+                    Field::new("statuses").or(default
+                        .statuses
+                        .iter()
+                        .map(|status| *status as usize)
+                        .collect()),
+                    source_map,
+                )?
+                .into_iter()
+                .map(|status| status as u16)
+                .collect(),
+            network_errors: deserializer.get(
+                Field::new("network_errors").or(default.network_errors),
+                source_map,
+            )?,
+            backoff_ms: deserializer.get::<usize>(
+                Field::new("backoff_ms").or(default.backoff_ms as usize),
+                source_map,
+            )? as u64,
+            max_backoff_ms: deserializer.get::<usize>(
+                Field::new("max_backoff_ms")
+                    .or(default.max_backoff_ms as usize),
+                source_map,
+            )? as u64,
+            jitter: deserializer
+                .get(Field::new("jitter").or(default.jitter), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(policy)
+    }
+}
+
+impl DeserializeYaml for RedirectPolicy {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let default = Self::default();
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let policy = Self {
+            follow: deserializer
+                .get(Field::new("follow").or(default.follow), source_map)?,
+            max_redirects: deserializer.get(
+                Field::new("max_redirects").or(default.max_redirects),
+                source_map,
+            )?,
+            resend_body: deserializer.get(
+                Field::new("resend_body").or(default.resend_body),
+                source_map,
+            )?,
+            strip_auth: deserializer.get(
+                Field::new("strip_auth").or(default.strip_auth),
+                source_map,
+            )?,
+        };
+        deserializer.done()?;
+        Ok(policy)
+    }
+}
+
+impl DeserializeYaml for Timeout {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let timeout = Self {
+            connect_ms: deserializer
+                .get::<Option<usize>>(
+                    Field::new("connect_ms").opt(),
+                    source_map,
+                )?
+                .map(|ms| ms as u64),
+            total_ms: deserializer
+                .get::<Option<usize>>(Field::new("total_ms").opt(), source_map)?
+                .map(|ms| ms as u64),
+        };
+        deserializer.done()?;
+        Ok(timeout)
+    }
+}
+
+impl DeserializeYaml for Throttle {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let throttle = Self {
+            download_kbps: deserializer
+                .get::<Option<usize>>(
+                    Field::new("download_kbps").opt(),
+                    source_map,
+                )?
+                .map(|kbps| kbps as u64),
+            upload_kbps: deserializer
+                .get::<Option<usize>>(
+                    Field::new("upload_kbps").opt(),
+                    source_map,
+                )?
+                .map(|kbps| kbps as u64),
+        };
+        deserializer.done()?;
+        Ok(throttle)
+    }
+}
+
+impl DeserializeYaml for Assertions {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let assertions = Self {
+            status: deserializer
+                .get::<Vec<usize>>(Field::new("status").opt(), source_map)?
+                .into_iter()
+                .map(|status| status as u16)
+                .collect(),
+            headers: deserializer
+                .get(Field::new("headers").opt(), source_map)?,
+            body: deserializer
+                .get::<IndexMap<String, ScalarText>>(
+                    Field::new("body").opt(),
+                    source_map,
+                )?
+                .into_iter()
+                .map(|(query, value)| (query, value.0))
+                .collect(),
+            max_latency_ms: deserializer
+                .get::<Option<usize>>(
+                    Field::new("max_latency_ms").opt(),
+                    source_map,
+                )?
+                .map(|ms| ms as u64),
+        };
+        deserializer.done()?;
+        Ok(assertions)
+    }
+}
+
+/// Any YAML scalar, converted to text. Used where values are compared as
+/// text, so users don't have to quote numbers and booleans
+struct ScalarText(String);
+
+impl DeserializeYaml for ScalarText {
+    fn expected() -> Expected {
+        Expected::OneOf(&[
+            &Expected::Null,
+            &Expected::Boolean,
+            &Expected::Number,
+            &Expected::String,
+        ])
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        _source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let text = match yaml.data {
+            YamlData::Value(Scalar::Null) => "null".to_owned(),
+            YamlData::Value(Scalar::Boolean(b)) => b.to_string(),
+            YamlData::Value(Scalar::Integer(i)) => i.to_string(),
+            YamlData::Value(Scalar::FloatingPoint(f)) => f.0.to_string(),
+            YamlData::Value(Scalar::String(s)) => s.into_owned(),
+            _ => return Err(LocatedError::unexpected(Self::expected(), yaml)),
+        };
+        Ok(Self(text))
+    }
+}
+
+impl DeserializeYaml for Pinning {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let pinning = Self {
+            certificate: deserializer
+                .get::<Option<Fingerprint>>(
+                    Field::new("certificate").opt(),
+                    source_map,
+                )?
+                .map(|fingerprint| fingerprint.0),
+            headers: deserializer
+                .get(Field::new("headers").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(pinning)
+    }
+}
+
+impl DeserializeYaml for Compression {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let default = Self::default();
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let compression = Self {
+            accept: deserializer.get(Field::new("accept").opt(), source_map)?,
+            decompress: deserializer.get(
+                Field::new("decompress").or(default.decompress),
+                source_map,
+            )?,
+        };
+        deserializer.done()?;
+        Ok(compression)
+    }
+}
+
+impl DeserializeYaml for ContentEncoding {
+    fn expected() -> Expected {
+        Expected::OneOf(&[
+            &Expected::Literal("gzip"),
+            &Expected::Literal("deflate"),
+            &Expected::Literal("identity"),
+        ])
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = String::deserialize(yaml, source_map)?;
+        Self::iter()
+            .find(|encoding| encoding.to_str() == s)
+            .ok_or_else(|| LocatedError {
+                error: YamlErrorKind::Unexpected {
+                    expected: Self::expected(),
+                    actual: format!("{s:?}"),
+                },
+                location,
+            })
+    }
+}
+
+impl DeserializeYaml for HttpProtocol {
+    fn expected() -> Expected {
+        Expected::OneOf(&[
+            &Expected::Literal("auto"),
+            &Expected::Literal("http1"),
+            &Expected::Literal("http2"),
+        ])
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = String::deserialize(yaml, source_map)?;
+        Self::iter()
+            .find(|protocol| protocol.to_str() == s)
+            .ok_or_else(|| LocatedError {
+                error: YamlErrorKind::Unexpected {
+                    expected: Self::expected(),
+                    actual: format!("{s:?}"),
+                },
+                location,
+            })
+    }
+}
+
+impl DeserializeYaml for ClientCertificate {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let certificate = Self {
+            certificate: deserializer
+                .get(Field::new("certificate"), source_map)?,
+            key: deserializer.get(Field::new("key").opt(), source_map)?,
+            password: deserializer
+                .get(Field::new("password").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(certificate)
+    }
+}
+
+/// An integer percentage, in the range `[0, 100]`
+#[derive(Default)]
+struct Percent(u8);
+
+impl DeserializeYaml for Percent {
+    fn expected() -> Expected {
+        Expected::Number
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let value = usize::deserialize(yaml, source_map)?;
+        if value <= 100 {
+            Ok(Self(value as u8))
+        } else {
+            Err(LocatedError::other(
+                CerealError::InvalidPercent(value),
+                location,
+            ))
+        }
+    }
+}
+
+/// A SHA-256 certificate fingerprint. The hex digits can be in either case,
+/// with or without colons between bytes. It's normalized to uppercase bytes
+/// separated by colons, the same format that `openssl x509 -fingerprint`
+/// prints.
+struct Fingerprint(String);
+
+impl DeserializeYaml for Fingerprint {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let value = String::deserialize(yaml, source_map)?;
+        let digits = value.replace(':', "");
+        if digits.len() == 64 && digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            let digits = digits.to_ascii_uppercase();
+            let bytes = digits
+                .as_bytes()
+                .chunks(2)
+                .map(|byte| str::from_utf8(byte).expect("Hex digits are ASCII"))
+                .collect::<Vec<_>>();
+            Ok(Self(bytes.join(":")))
+        } else {
+            Err(LocatedError::other(
+                CerealError::InvalidFingerprint(value),
+                location,
+            ))
+        }
+    }
+}
+
 impl DeserializeYaml for RecipeTree {
     fn expected() -> Expected {
         Expected::Mapping
@@ -243,12 +738,151 @@ impl DeserializeYaml for Recipe {
                 .into_iter()
                 .map(|(k, v)| (k.to_lowercase(), v))
                 .collect(),
+            user_agent: deserializer
+                .get(Field::new("user_agent").opt(), source_map)?,
+            transport: deserializer
+                .get(Field::new("transport").opt(), source_map)?,
+            certificate: deserializer
+                .get(Field::new("certificate").opt(), source_map)?,
+            retry: deserializer.get(Field::new("retry").opt(), source_map)?,
+            redirect: deserializer
+                .get(Field::new("redirect").opt(), source_map)?,
+            timeout: deserializer
+                .get(Field::new("timeout").opt(), source_map)?,
+            throttle: deserializer
+                .get(Field::new("throttle").opt(), source_map)?,
+            compression: deserializer
+                .get(Field::new("compression").opt(), source_map)?,
+            protocol: deserializer
+                .get(Field::new("protocol").opt(), source_map)?,
+            socket: deserializer.get(Field::new("socket").opt(), source_map)?,
+            reauthenticate: deserializer
+                .get(Field::new("reauthenticate").opt(), source_map)?,
+            capture: deserializer
+                .get(Field::new("capture").opt(), source_map)?,
+            assert: deserializer.get(Field::new("assert").opt(), source_map)?,
+            tags: deserializer.get(Field::new("tags").opt(), source_map)?,
+            websocket: deserializer
+                .get(Field::new("websocket").opt(), source_map)?,
+            grpc: deserializer.get(Field::new("grpc").opt(), source_map)?,
+            render_mode: deserializer
+                .get(Field::new("render_mode").opt(), source_map)?,
+            params: deserializer.get(Field::new("params").opt(), source_map)?,
         };
         deserializer.done()?;
         Ok(recipe)
     }
 }
 
+impl DeserializeYaml for RecipeParam {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let param = RecipeParam {
+            param_type: deserializer
+                .get(Field::new("type").opt(), source_map)?,
+            required: deserializer
+                .get(Field::new("required").opt(), source_map)?,
+            default: deserializer
+                .get(Field::new("default").opt(), source_map)?,
+            description: deserializer
+                .get(Field::new("description").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(param)
+    }
+}
+
+impl DeserializeYaml for RecipeParamType {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = String::deserialize(yaml, source_map)?;
+        s.parse()
+            .map_err(|error| LocatedError::other(error, location))
+    }
+}
+
+impl DeserializeYaml for WebSocket {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let websocket = WebSocket {
+            messages: deserializer
+                .get(Field::new("messages").opt(), source_map)?,
+            frame: deserializer.get(Field::new("frame").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(websocket)
+    }
+}
+
+impl DeserializeYaml for WebSocketFrame {
+    fn expected() -> Expected {
+        Expected::OneOf(&[
+            &Expected::Literal("text"),
+            &Expected::Literal("binary"),
+        ])
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = String::deserialize(yaml, source_map)?;
+        Self::iter()
+            .find(|frame| frame.to_str() == s)
+            .ok_or_else(|| LocatedError {
+                error: YamlErrorKind::Unexpected {
+                    expected: Self::expected(),
+                    actual: format!("{s:?}"),
+                },
+                location,
+            })
+    }
+}
+
+impl DeserializeYaml for Grpc {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let grpc = Grpc {
+            method: deserializer.get(Field::new("method"), source_map)?,
+            proto_files: deserializer
+                .get(Field::new("proto_files").opt(), source_map)?,
+            import_paths: deserializer
+                .get(Field::new("import_paths").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(grpc)
+    }
+}
+
 impl DeserializeYaml for Folder {
     fn expected() -> Expected {
         Expected::Mapping
@@ -264,6 +898,8 @@ impl DeserializeYaml for Folder {
             id: RecipeId::default(), // Will be set by parent based on key
             location,
             name: deserializer.get(Field::new("name").opt(), source_map)?,
+            authentication: deserializer
+                .get(Field::new("authentication").opt(), source_map)?,
             // `requests` matches the root field name
             children: deserializer
                 .get::<Adopt<_>>(Field::new("requests").opt(), source_map)?
@@ -274,7 +910,39 @@ impl DeserializeYaml for Folder {
     }
 }
 
-impl DeserializeYaml for HttpMethod {
+impl DeserializeYaml for HttpMethod {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = String::deserialize(yaml, source_map)?;
+        s.parse()
+            .map_err(|error| LocatedError::other(error, location))
+    }
+}
+
+impl DeserializeYaml for RenderMode {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = String::deserialize(yaml, source_map)?;
+        s.parse()
+            .map_err(|error| LocatedError::other(error, location))
+    }
+}
+
+impl DeserializeYaml for CaptureSelector {
     fn expected() -> Expected {
         Expected::String
     }
@@ -310,6 +978,33 @@ impl DeserializeYaml for QueryParameterValue {
     }
 }
 
+impl DeserializeYaml for FormPart {
+    fn expected() -> Expected {
+        Expected::OneOf(&[&Expected::String, &Expected::Mapping])
+    }
+
+    /// Deserialize from a single template or a mapping with part headers
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        if yaml.data.is_mapping() {
+            let mut deserializer = StructDeserializer::new(yaml)?;
+            let part = Self::Detailed {
+                value: deserializer.get(Field::new("value"), source_map)?,
+                content_type: deserializer
+                    .get(Field::new("content_type").opt(), source_map)?,
+                filename: deserializer
+                    .get(Field::new("filename").opt(), source_map)?,
+            };
+            deserializer.done()?;
+            Ok(part)
+        } else {
+            Template::deserialize(yaml, source_map).map(Self::Value)
+        }
+    }
+}
+
 impl DeserializeYaml for Authentication {
     fn expected() -> Expected {
         Expected::Mapping
@@ -334,13 +1029,129 @@ impl DeserializeYaml for Authentication {
                     token: deserializer.get(Field::new("token"), source_map)?,
                 })
             },
+            "digest" => |yaml: SourcedYaml| {
+                let mut deserializer = StructDeserializer::new(yaml)?;
+                Ok(Authentication::Digest {
+                    username: deserializer.get(Field::new("username"), source_map)?,
+                    password: deserializer.get(Field::new("password"), source_map)?,
+                })
+            },
+            "ntlm" => |yaml: SourcedYaml| {
+                let mut deserializer = StructDeserializer::new(yaml)?;
+                Ok(Authentication::Ntlm {
+                    username: deserializer.get(Field::new("username"), source_map)?,
+                    password: deserializer.get(Field::new("password"), source_map)?,
+                    domain: deserializer.get(Field::new("domain").opt(), source_map)?,
+                })
+            },
+            "oauth2" => |yaml: SourcedYaml| {
+                let mut deserializer = StructDeserializer::new(yaml)?;
+                // Grant fields are flattened into the OAuth2 mapping
+                let grant = match deserializer
+                    .get(Field::new("grant_type"), source_map)?
+                {
+                    OAuth2GrantType::ClientCredentials => {
+                        OAuth2Grant::ClientCredentials
+                    }
+                    OAuth2GrantType::AuthorizationCode => {
+                        OAuth2Grant::AuthorizationCode {
+                            authorization_url: deserializer
+                                .get(Field::new("authorization_url"), source_map)?,
+                            redirect_uri: deserializer
+                                .get(Field::new("redirect_uri").opt(), source_map)?,
+                        }
+                    }
+                };
+                let oauth2 = OAuth2 {
+                    grant,
+                    token_url: deserializer.get(Field::new("token_url"), source_map)?,
+                    client_id: deserializer.get(Field::new("client_id"), source_map)?,
+                    client_secret: deserializer
+                        .get(Field::new("client_secret").opt(), source_map)?,
+                    scope: deserializer.get(Field::new("scope").opt(), source_map)?,
+                };
+                deserializer.done()?;
+                Ok(Authentication::OAuth2(oauth2))
+            },
+        }
+    }
+}
+
+/// The `grant_type` field of OAuth2 authentication. Each grant's fields are
+/// siblings of the common OAuth2 fields, so the type is deserialized on its own
+/// to determine which fields to look for.
+enum OAuth2GrantType {
+    ClientCredentials,
+    AuthorizationCode,
+}
+
+impl DeserializeYaml for OAuth2GrantType {
+    fn expected() -> Expected {
+        Expected::OneOf(&[
+            &Expected::Literal("client_credentials"),
+            &Expected::Literal("authorization_code"),
+        ])
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let location = yaml.location;
+        let s = String::deserialize(yaml, source_map)?;
+        match s.as_str() {
+            "client_credentials" => Ok(Self::ClientCredentials),
+            "authorization_code" => Ok(Self::AuthorizationCode),
+            _ => Err(LocatedError {
+                error: YamlErrorKind::Unexpected {
+                    expected: Self::expected(),
+                    actual: format!("{s:?}"),
+                },
+                location,
+            }),
+        }
+    }
+}
+
+/// YAML tag to disable authentication: `authentication: !none`
+pub const TAG_NONE: &str = "none";
+
+impl DeserializeYaml for AuthenticationSetting {
+    fn expected() -> Expected {
+        Expected::OneOf(&[&Expected::Mapping, &Expected::Literal("!none")])
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        // `!none` is the only tag we accept, and it can't have a value
+        if let YamlData::Tagged(tag, value) = &yaml.data {
+            return if tag.handle == "!" && tag.suffix == TAG_NONE {
+                if value.data.is_null() {
+                    Ok(Self::Disabled)
+                } else {
+                    let value = (**value).clone();
+                    Err(LocatedError::unexpected(Expected::Null, value))
+                }
+            } else {
+                Err(LocatedError::unexpected(Self::expected(), yaml))
+            };
         }
+        Authentication::deserialize(yaml, source_map).map(Self::Defined)
     }
 }
 
+/// YAML tag to stream a body from a file: `body: !file data.bin`
+pub const TAG_FILE: &str = "file";
+
 impl DeserializeYaml for RecipeBody {
     fn expected() -> Expected {
-        Expected::OneOf(&[&Expected::String, &Expected::Mapping])
+        Expected::OneOf(&[
+            &Expected::String,
+            &Expected::Mapping,
+            &Expected::Literal("!file"),
+        ])
     }
 
     fn deserialize(
@@ -358,6 +1169,16 @@ impl DeserializeYaml for RecipeBody {
             Ok(data)
         }
 
+        // `!file` is the only tag we accept. Its value is the path template
+        if let YamlData::Tagged(tag, value) = &yaml.data {
+            return if tag.handle == "!" && tag.suffix == TAG_FILE {
+                let value = (**value).clone();
+                Template::deserialize(value, source_map).map(Self::File)
+            } else {
+                Err(LocatedError::unexpected(Self::expected(), yaml))
+            };
+        }
+
         // Mapping deserializes as some sort of structured body. It should have
         // a `type` and `data` field
         if yaml.data.is_mapping() {
@@ -375,6 +1196,9 @@ impl DeserializeYaml for RecipeBody {
                 "stream" => |yaml| {
                     Ok(Self::Stream(deserialize_data(yaml, source_map)?))
                 },
+                "graphql" => |yaml| {
+                    Ok(Self::Graphql(deserialize_data(yaml, source_map)?))
+                },
             }
         } else {
             // Otherwise it's a raw body - deserialize as a template
@@ -383,6 +1207,28 @@ impl DeserializeYaml for RecipeBody {
     }
 }
 
+impl DeserializeYaml for GraphQlBody {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let body = GraphQlBody {
+            query: deserializer.get(Field::new("query"), source_map)?,
+            variables: deserializer
+                .get(Field::new("variables").opt(), source_map)?,
+            operation_name: deserializer
+                .get(Field::new("operation_name").opt(), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(body)
+    }
+}
+
 impl DeserializeYaml for JsonTemplate {
     fn expected() -> Expected {
         Expected::OneOf(&[
@@ -416,9 +1262,10 @@ impl DeserializeYaml for JsonTemplate {
             )),
             // Parse string as a template
             YamlData::Value(Scalar::String(s)) => {
-                let template = s.parse::<Template>().map_err(|error| {
-                    LocatedError::other(error, yaml.location)
-                })?;
+                let template =
+                    Template::parse_or_defer(s.into()).map_err(|error| {
+                        LocatedError::other(error, yaml.location)
+                    })?;
                 Ok(Self::String(template))
             }
             YamlData::Sequence(sequence) => {
@@ -455,6 +1302,15 @@ enum CerealError {
     #[error("Invalid float `{0}`; JSON does not support NaN or Infinity")]
     InvalidJsonFloat(f64),
 
+    #[error("Invalid percentage `{0}`; must be between 0 and 100")]
+    InvalidPercent(usize),
+
+    #[error(
+        "Invalid certificate fingerprint `{0}`; expected a SHA-256 hash as 64 \
+        hex digits"
+    )]
+    InvalidFingerprint(String),
+
     #[error(
         "Cannot set profile `{second}` as default; `{first}` is already default"
     )]
@@ -490,6 +1346,18 @@ impl HasId for Profile {
     }
 }
 
+impl HasId for Toggle {
+    type Id = ToggleId;
+
+    fn id(&self) -> &Self::Id {
+        &self.id
+    }
+
+    fn set_id(&mut self, id: Self::Id) {
+        self.id = id;
+    }
+}
+
 impl HasId for RecipeNode {
     type Id = RecipeId;
 
@@ -536,6 +1404,22 @@ pub fn is_false(b: &bool) -> bool {
     !b
 }
 
+/// Predicate for skip_serializing_if
+pub fn is_zero<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+/// Serialize a file body as the `!file` tag
+pub fn serialize_file_body<S>(
+    path: &Template,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    serializer.serialize_newtype_variant("RecipeBody", 0, TAG_FILE, path)
+}
+
 /// Expose this for RecipeTree's tests
 #[cfg(test)]
 pub use tests::deserialize_recipe_tree;
@@ -567,6 +1451,23 @@ mod tests {
             ])),
         ]),
         "Cannot set profile `profile2` as default; `profile1` is already default",
+        )]
+    #[case::chaos_invalid_percent(
+        yaml_mapping([
+            ("profile1", yaml_mapping([
+                ("chaos", yaml_mapping([("drop_percent", 101)])),
+            ])),
+        ]),
+        "Invalid percentage `101`; must be between 0 and 100",
+    )]
+    #[case::pinning_invalid_fingerprint(
+        yaml_mapping([
+            ("profile1", yaml_mapping([
+                ("pinning", yaml_mapping([("certificate", "AB:CD")])),
+            ])),
+        ]),
+        "Invalid certificate fingerprint `AB:CD`; expected a SHA-256 hash as \
+        64 hex digits",
     )]
     fn test_deserialize_profiles_error(
         #[case] yaml: impl Into<serde_yaml::Value>,
@@ -607,6 +1508,24 @@ mod tests {
             ("password", "{{ prompt('Password', sensitive=true) }}"),
         ]))]),
     )]
+    #[case::graphql(
+        RecipeBody::Graphql(GraphQlBody {
+            query: "query($id: ID!) { user(id: $id) { name } }".into(),
+            variables: Some(json!({"id": "{{ user_id }}"}).try_into().unwrap()),
+            operation_name: None,
+        }),
+        yaml_enum("graphql", [("data", yaml_mapping([
+            ("query", "query($id: ID!) { user(id: $id) { name } }".into()),
+            ("variables", yaml_mapping([("id", "{{ user_id }}")])),
+        ]))]),
+    )]
+    #[case::file(
+        RecipeBody::File("data/{{ user_id }}.bin".into()),
+        serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+            tag: serde_yaml::value::Tag::new("file"),
+            value: "data/{{ user_id }}.bin".into(),
+        })),
+    )]
     fn test_serde_recipe_body(
         #[case] body: RecipeBody,
         #[case] yaml: impl Into<serde_yaml::Value>,
@@ -624,6 +1543,337 @@ mod tests {
         );
     }
 
+    /// Test serializing and deserializing profile chaos settings. Unset fields
+    /// are omitted from serialization.
+    #[rstest]
+    #[case::empty(Chaos::default(), Mapping::default())]
+    #[case::full(
+        Chaos {
+            latency_ms: 250,
+            drop_percent: 10,
+            error_percent: 5,
+            seed: Some(42),
+        },
+        yaml_mapping([
+            ("latency_ms", 250),
+            ("drop_percent", 10),
+            ("error_percent", 5),
+            ("seed", 42),
+        ]),
+    )]
+    fn test_serde_chaos(
+        #[case] chaos: Chaos,
+        #[case] yaml: impl Into<serde_yaml::Value>,
+    ) {
+        let yaml = yaml.into();
+        assert_eq!(
+            serde_yaml::to_value(&chaos).unwrap(),
+            yaml,
+            "Serialization mismatch"
+        );
+        assert_eq!(
+            deserialize_yaml::<Chaos>(yaml).unwrap(),
+            chaos,
+            "Deserialization mismatch"
+        );
+    }
+
+    /// Certificate fingerprints are normalized to colon-separated uppercase
+    /// bytes, with or without colons in the input
+    #[rstest]
+    #[case::colons(&"ab:".repeat(32)[..95])]
+    #[case::no_colons(&"Ab".repeat(32))]
+    fn test_deserialize_pinning(#[case] certificate: &str) {
+        let yaml = yaml_mapping([
+            ("certificate", serde_yaml::Value::from(certificate)),
+            ("headers", yaml_mapping([("server", "nginx")])),
+        ]);
+        assert_eq!(
+            deserialize_yaml::<Pinning>(yaml).unwrap(),
+            Pinning {
+                certificate: Some(["AB"; 32].join(":")),
+                headers: indexmap! { "server".into() => "nginx".into() },
+            }
+        );
+    }
+
+    /// Test serializing and deserializing client certificates. The key and
+    /// password are omitted from serialization if not given
+    #[rstest]
+    #[case::certificate_only(
+        ClientCertificate {
+            certificate: "client.pem".into(),
+            key: None,
+            password: None,
+        },
+        yaml_mapping([("certificate", "client.pem")]),
+    )]
+    #[case::key(
+        ClientCertificate {
+            certificate: "client.pem".into(),
+            key: Some("client.key".into()),
+            password: None,
+        },
+        yaml_mapping([("certificate", "client.pem"), ("key", "client.key")]),
+    )]
+    #[case::pkcs12(
+        ClientCertificate {
+            certificate: "client.p12".into(),
+            key: None,
+            password: Some("hunter2".into()),
+        },
+        yaml_mapping([("certificate", "client.p12"), ("password", "hunter2")]),
+    )]
+    fn test_serde_client_certificate(
+        #[case] certificate: ClientCertificate,
+        #[case] yaml: impl Into<serde_yaml::Value>,
+    ) {
+        let yaml = yaml.into();
+        assert_eq!(
+            serde_yaml::to_value(&certificate).unwrap(),
+            yaml,
+            "Serialization mismatch"
+        );
+        assert_eq!(
+            deserialize_yaml::<ClientCertificate>(yaml).unwrap(),
+            certificate,
+            "Deserialization mismatch"
+        );
+    }
+
+    /// Test serializing and deserializing WebSocket settings. Messages and
+    /// frame type are omitted from serialization if not given
+    #[rstest]
+    #[case::empty(WebSocket::default(), Mapping::default())]
+    #[case::messages(
+        WebSocket {
+            messages: vec!["hello".into(), "{{ token }}".into()],
+            frame: None,
+        },
+        yaml_mapping([(
+            "messages",
+            serde_yaml::Value::Sequence(vec![
+                "hello".into(),
+                "{{ token }}".into(),
+            ]),
+        )]),
+    )]
+    #[case::frame(
+        WebSocket {
+            messages: vec![],
+            frame: Some(WebSocketFrame::Binary),
+        },
+        yaml_mapping([("frame", "binary")]),
+    )]
+    fn test_serde_websocket(
+        #[case] websocket: WebSocket,
+        #[case] yaml: impl Into<serde_yaml::Value>,
+    ) {
+        let yaml = yaml.into();
+        assert_eq!(
+            serde_yaml::to_value(&websocket).unwrap(),
+            yaml,
+            "Serialization mismatch"
+        );
+        assert_eq!(
+            deserialize_yaml::<WebSocket>(yaml).unwrap(),
+            websocket,
+            "Deserialization mismatch"
+        );
+    }
+
+    /// Test serializing and deserializing authentication settings.
+    /// Authentication can be disabled with the `!none` tag
+    #[rstest]
+    #[case::defined(
+        AuthenticationSetting::Defined(Authentication::Bearer {
+            token: "{{ token }}".into(),
+        }),
+        yaml_enum("bearer", [("token", "{{ token }}")]),
+    )]
+    #[case::digest(
+        AuthenticationSetting::Defined(Authentication::Digest {
+            username: "user".into(),
+            password: "{{ password }}".into(),
+        }),
+        yaml_enum("digest", [
+            ("username", "user"),
+            ("password", "{{ password }}"),
+        ]),
+    )]
+    #[case::ntlm(
+        AuthenticationSetting::Defined(Authentication::Ntlm {
+            username: "user".into(),
+            password: "{{ password }}".into(),
+            domain: Some("CORP".into()),
+        }),
+        yaml_enum("ntlm", [
+            ("username", "user"),
+            ("password", "{{ password }}"),
+            ("domain", "CORP"),
+        ]),
+    )]
+    #[case::oauth2_client_credentials(
+        AuthenticationSetting::Defined(Authentication::OAuth2(OAuth2 {
+            grant: OAuth2Grant::ClientCredentials,
+            token_url: "{{ host }}/token".into(),
+            client_id: "{{ client_id }}".into(),
+            client_secret: Some("{{ client_secret }}".into()),
+            scope: None,
+        })),
+        yaml_enum("oauth2", [
+            ("grant_type", serde_yaml::Value::from("client_credentials")),
+            ("token_url", "{{ host }}/token".into()),
+            ("client_id", "{{ client_id }}".into()),
+            ("client_secret", "{{ client_secret }}".into()),
+            ("scope", serde_yaml::Value::Null),
+        ]),
+    )]
+    #[case::oauth2_authorization_code(
+        AuthenticationSetting::Defined(Authentication::OAuth2(OAuth2 {
+            grant: OAuth2Grant::AuthorizationCode {
+                authorization_url: "{{ host }}/authorize".into(),
+                redirect_uri: None,
+            },
+            token_url: "{{ host }}/token".into(),
+            client_id: "{{ client_id }}".into(),
+            client_secret: None,
+            scope: Some("read write".into()),
+        })),
+        yaml_enum("oauth2", [
+            ("grant_type", serde_yaml::Value::from("authorization_code")),
+            ("authorization_url", "{{ host }}/authorize".into()),
+            ("redirect_uri", serde_yaml::Value::Null),
+            ("token_url", "{{ host }}/token".into()),
+            ("client_id", "{{ client_id }}".into()),
+            ("client_secret", serde_yaml::Value::Null),
+            ("scope", "read write".into()),
+        ]),
+    )]
+    #[case::disabled(
+        AuthenticationSetting::Disabled,
+        serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+            tag: serde_yaml::value::Tag::new("none"),
+            value: serde_yaml::Value::Null,
+        })),
+    )]
+    fn test_serde_authentication_setting(
+        #[case] authentication: AuthenticationSetting,
+        #[case] yaml: serde_yaml::Value,
+    ) {
+        assert_eq!(
+            serde_yaml::to_value(&authentication).unwrap(),
+            yaml,
+            "Serialization mismatch"
+        );
+        assert_eq!(
+            deserialize_yaml::<AuthenticationSetting>(yaml).unwrap(),
+            authentication,
+            "Deserialization mismatch"
+        );
+    }
+
+    /// Only `!none` is accepted as a tag, and it can't have a value
+    #[rstest]
+    #[case::unknown_tag(
+        "!basic {username: user}",
+        "Expected one of mapping, \"!none\", received tag `!basic`"
+    )]
+    #[case::none_with_value("!none value", "Expected null, received \"value\"")]
+    #[case::oauth2_unknown_grant(
+        "{type: oauth2, grant_type: password, token_url: t, client_id: c}",
+        "Expected one of \"client_credentials\", \"authorization_code\", \
+        received \"password\""
+    )]
+    #[case::oauth2_missing_authorization_url(
+        "{type: oauth2, grant_type: authorization_code, token_url: t, \
+        client_id: c}",
+        "Expected field `authorization_url`"
+    )]
+    fn test_deserialize_authentication_setting_error(
+        #[case] yaml: &str,
+        #[case] expected_error: &str,
+    ) {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        assert_err!(
+            deserialize_yaml::<AuthenticationSetting>(yaml)
+                .map_err(LocatedError::into_error),
+            expected_error
+        );
+    }
+
+    /// Test serializing and deserializing capture selectors. Anything without
+    /// the `header:` prefix is a JSONPath query
+    #[rstest]
+    #[case::body(CaptureSelector::Body("$.token".into()), "$.token")]
+    #[case::header(
+        CaptureSelector::Header("X-Request-Id".into()),
+        "header:X-Request-Id",
+    )]
+    fn test_serde_capture_selector(
+        #[case] selector: CaptureSelector,
+        #[case] yaml: &str,
+    ) {
+        assert_eq!(
+            serde_yaml::to_value(&selector).unwrap(),
+            serde_yaml::Value::from(yaml),
+            "Serialization mismatch"
+        );
+        assert_eq!(
+            deserialize_yaml::<CaptureSelector>(yaml.into()).unwrap(),
+            selector,
+            "Deserialization mismatch"
+        );
+    }
+
+    #[rstest]
+    #[case::empty("", "Capture selector cannot be empty")]
+    #[case::empty_header("header: ", "Missing header name after `header:`")]
+    fn test_deserialize_capture_selector_error(
+        #[case] yaml: &str,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(
+            deserialize_yaml::<CaptureSelector>(yaml.into())
+                .map_err(LocatedError::into_error),
+            expected_error
+        );
+    }
+
+    /// Form parts can be a plain template, or a mapping with part headers
+    #[rstest]
+    #[case::value(FormPart::Value("{{ user_id }}".into()), "'{{ user_id }}'")]
+    #[case::detailed(
+        FormPart::Detailed {
+            value: "{{ file('image.png') }}".into(),
+            content_type: Some("image/png".into()),
+            filename: Some("avatar.png".into()),
+        },
+        "{value: \"{{ file('image.png') }}\", content_type: image/png, \
+        filename: avatar.png}",
+    )]
+    #[case::detailed_partial(
+        FormPart::Detailed {
+            value: "hello".into(),
+            content_type: Some("text/plain".into()),
+            filename: None,
+        },
+        "{value: hello, content_type: text/plain}",
+    )]
+    fn test_serde_form_part(#[case] part: FormPart, #[case] yaml: &str) {
+        let yaml: serde_yaml::Value = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            serde_yaml::to_value(&part).unwrap(),
+            yaml,
+            "Serialization mismatch"
+        );
+        assert_eq!(
+            deserialize_yaml::<FormPart>(yaml).unwrap(),
+            part,
+            "Deserialization mismatch"
+        );
+    }
+
     /// Test various errors when deserializing a recipe body. We use serde_yaml
     /// instead of serde_test because the handling of enums is a bit different,
     /// and we specifically only care about YAML.
@@ -641,12 +1891,19 @@ mod tests {
     #[case::raw_tag(
         yaml_enum("raw", [("data", "data")]),
         "Expected one of \"json\", \"form_urlencoded\", \"form_multipart\", \
-        \"stream\", received \"raw\"",
+        \"stream\", \"graphql\", received \"raw\"",
     )]
     #[case::form_urlencoded_missing_data(
         yaml_enum("form_urlencoded", [] as [(_, serde_yaml::Value); 0]),
         "Expected field `data` with mapping"
     )]
+    #[case::unknown_tag(
+        serde_yaml::Value::Tagged(Box::new(serde_yaml::value::TaggedValue {
+            tag: serde_yaml::value::Tag::new("json"),
+            value: "data".into(),
+        })),
+        "Expected one of string, mapping, \"!file\", received tag `!json`"
+    )]
     fn test_deserialize_recipe_body_error(
         #[case] yaml: impl Into<serde_yaml::Value>,
         #[case] expected_error: &str,
@@ -664,6 +1921,67 @@ mod tests {
         assert_eq!(Collection::parse("").unwrap(), Collection::default());
     }
 
+    /// Custom delimiters apply to every template in the collection, including
+    /// ones nested inside JSON bodies
+    #[test]
+    fn test_deserialize_template_delimiters() {
+        let collection = Collection::parse(
+            r#"
+template_delimiters:
+  open: "<%"
+  close: "%>"
+profiles:
+  profile1:
+    data:
+      host: "<% base %>"
+requests:
+  recipe1:
+    method: POST
+    url: "<% host %>/users"
+    body:
+      type: json
+      data: {"name": "{{ name }}", "id": "<% id %>"}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            collection.template_delimiters,
+            Some(Delimiters::new("<%", "%>").unwrap())
+        );
+        let profile = &collection.profiles[&ProfileId::from("profile1")];
+        assert_eq!(profile.data["host"], "{{ base }}".parse().unwrap());
+        let recipe = collection.recipes.get_recipe(&"recipe1".into()).unwrap();
+        assert_eq!(recipe.url, "{{ host }}/users".parse().unwrap());
+        assert_eq!(
+            recipe.body,
+            Some(
+                RecipeBody::json(
+                    json!({"name": "{_{ name }}", "id": "{{ id }}"})
+                )
+                .unwrap()
+            )
+        );
+        // Delimiters don't leak past the collection
+        assert_eq!(
+            "<% id %>".parse::<Template>().unwrap(),
+            Template::raw("<% id %>".into())
+        );
+    }
+
+    /// Invalid delimiters are a deserialization error
+    #[test]
+    fn test_deserialize_template_delimiters_error() {
+        let yaml = yaml_mapping([(
+            "template_delimiters",
+            yaml_mapping([("open", "<"), ("close", ">")]),
+        )]);
+        assert_err!(
+            deserialize_yaml::<Collection>(yaml)
+                .map_err(LocatedError::into_error),
+            "Opening delimiter must be at least 2 characters"
+        );
+    }
+
     /// Helper for deserializing in RecipeTree's tests. We export this
     pub fn deserialize_recipe_tree(
         yaml: serde_yaml::Value,
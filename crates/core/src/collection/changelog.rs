@@ -0,0 +1,343 @@
+//! Git history of a collection file. Shared collections drift as teammates
+//! edit them, so this answers "what changed recently?" and "when was this
+//! recipe last changed, and by whom?". History is read from the `git`
+//! executable, so this only works if git is installed and the collection file
+//! is in a repository.
+
+use crate::collection::{Collection, CollectionFile, RecipeId, RecipeNode};
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    io,
+    ops::Range,
+    path::Path,
+    process::{Command, Output},
+};
+use thiserror::Error;
+
+/// Separates fields in the `git log` output. This can't appear in a commit
+/// subject or author name.
+const FIELD_SEPARATOR: char = '\x1f';
+
+/// A commit that modified the collection file
+#[derive(Clone, Debug, PartialEq)]
+pub struct CollectionCommit {
+    /// Abbreviated commit hash
+    pub hash: String,
+    pub author: String,
+    pub time: DateTime<Utc>,
+    /// First line of the commit message
+    pub summary: String,
+    /// Recipes whose most recent change was made in this commit, in the
+    /// order they appear in the collection
+    pub recipes: Vec<RecipeId>,
+}
+
+impl CollectionFile {
+    /// Get the most recent commits that modified this file, newest first.
+    /// Each recipe defined in this file is attributed to the commit that last
+    /// changed any of its lines. Recipes defined in other files (via `$ref`)
+    /// aren't attributed. Uncommitted changes are ignored.
+    pub fn changelog(
+        &self,
+        collection: &Collection,
+        limit: usize,
+    ) -> Result<Vec<CollectionCommit>, ChangelogError> {
+        let path = self.path();
+        let log = git(
+            path,
+            &[
+                "log",
+                "--follow",
+                &format!("--max-count={limit}"),
+                &format!(
+                    "--format=%h{FIELD_SEPARATOR}%H{FIELD_SEPARATOR}%an\
+                    {FIELD_SEPARATOR}%at{FIELD_SEPARATOR}%s"
+                ),
+            ],
+        )?;
+        let blame = git(path, &["blame", "--line-porcelain"])?;
+        let line_commits = parse_blame(&blame);
+
+        // Attribute each recipe to the newest commit among its lines
+        let mut recipe_commits: HashMap<&str, Vec<RecipeId>> = HashMap::new();
+        for (recipe_id, lines) in recipe_spans(collection, path) {
+            let end = lines.end.min(line_commits.len());
+            let newest = line_commits
+                .get(lines.start..end)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .max_by_key(|line| line.time);
+            if let Some(line) = newest {
+                recipe_commits
+                    .entry(line.hash)
+                    .or_default()
+                    .push(recipe_id.clone());
+            }
+        }
+
+        log.lines()
+            .map(|line| {
+                let mut fields = line.split(FIELD_SEPARATOR);
+                let mut next = || {
+                    fields.next().ok_or_else(|| ChangelogError::Parse {
+                        line: line.to_owned(),
+                    })
+                };
+                let (hash, full_hash, author, time, summary) =
+                    (next()?, next()?, next()?, next()?, next()?);
+                let time = time
+                    .parse()
+                    .ok()
+                    .and_then(|time| DateTime::from_timestamp(time, 0))
+                    .ok_or_else(|| ChangelogError::Parse {
+                        line: line.to_owned(),
+                    })?;
+                Ok(CollectionCommit {
+                    hash: hash.to_owned(),
+                    author: author.to_owned(),
+                    time,
+                    summary: summary.to_owned(),
+                    recipes: recipe_commits
+                        .remove(full_hash)
+                        .unwrap_or_default(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Error loading the git history of a collection file
+#[derive(Debug, Error)]
+pub enum ChangelogError {
+    /// `git` couldn't be run at all, probably because it's not installed
+    #[error("Error running `git`")]
+    Io(#[source] io::Error),
+    /// `git` exited with an error, e.g. because the file isn't in a repo
+    #[error("`git {command}` failed: {stderr}")]
+    Git { command: String, stderr: String },
+    /// `git` gave us output we don't understand
+    #[error("Unexpected output from `git log`: `{line}`")]
+    Parse { line: String },
+}
+
+/// Run a git subcommand on a file, from the file's directory so the right
+/// repository is found. Return stdout.
+fn git(path: &Path, args: &[&str]) -> Result<String, ChangelogError> {
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let file_name = path.file_name().unwrap_or(path.as_os_str());
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = Command::new("git")
+        .args(args)
+        .arg("--")
+        .arg(file_name)
+        .current_dir(directory)
+        .output()
+        .map_err(ChangelogError::Io)?;
+    if status.success() {
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    } else {
+        Err(ChangelogError::Git {
+            command: args.join(" "),
+            stderr: String::from_utf8_lossy(&stderr).trim().to_owned(),
+        })
+    }
+}
+
+/// The commit that last changed a line of the file, according to `git blame`
+#[derive(Debug, PartialEq)]
+struct BlameLine<'a> {
+    /// Full commit hash
+    hash: &'a str,
+    /// Author timestamp, in seconds
+    time: i64,
+}
+
+/// Parse `git blame --line-porcelain` output into the commit for each line of
+/// the file. Index 0 is line 1. Lines that haven't been committed yet are
+/// `None`.
+fn parse_blame(blame: &str) -> Vec<Option<BlameLine<'_>>> {
+    let mut lines = Vec::new();
+    // Each entry is a header line, then `key value` lines, then the content
+    // of the line prefixed with a tab
+    let mut entry_hash = None;
+    let mut time = 0;
+    for line in blame.lines() {
+        let Some(hash) = entry_hash else {
+            // Header: <hash> <original line> <final line> [<group size>]
+            entry_hash = line.split(' ').next();
+            continue;
+        };
+        if line.starts_with('\t') {
+            // Uncommitted lines are blamed on the all-zero hash
+            let is_committed = hash.bytes().any(|byte| byte != b'0');
+            lines.push(is_committed.then_some(BlameLine { hash, time }));
+            entry_hash = None;
+        } else if let Some(value) = line.strip_prefix("author-time ") {
+            time = value.parse().unwrap_or_default();
+        }
+    }
+    lines
+}
+
+/// Get the lines occupied by each recipe defined in the collection file. A
+/// recipe runs from its first line until the key of the next recipe, folder,
+/// or profile in the file. Ranges are 0-indexed so they can index into the
+/// blame, and the last recipe's range is unbounded.
+fn recipe_spans<'a>(
+    collection: &'a Collection,
+    path: &Path,
+) -> Vec<(&'a RecipeId, Range<usize>)> {
+    let source = path.display().to_string();
+    // Everything that starts a new block of lines in the file, sorted by line
+    let mut starts: Vec<(usize, Option<&RecipeId>)> = collection
+        .recipes
+        .iter()
+        .map(|(_, node)| match node {
+            RecipeNode::Folder(folder) => (&folder.location, None),
+            RecipeNode::Recipe(recipe) => (&recipe.location, Some(&recipe.id)),
+        })
+        .chain(
+            collection
+                .profiles
+                .values()
+                .map(|profile| (&profile.location, None)),
+        )
+        .filter(|(location, _)| location.source == source)
+        .map(|(location, id)| (location.line as usize, id))
+        .collect();
+    starts.sort_by_key(|(line, _)| *line);
+
+    starts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (line, id))| {
+            let id = (*id)?;
+            // The next node's key is on the line before its body
+            let end = starts
+                .get(i + 1)
+                .map_or(usize::MAX, |(next, _)| next.saturating_sub(1));
+            Some((id, line - 1..end.max(*line)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use slumber_util::{TempDir, temp_dir};
+    use std::fs;
+
+    /// Run git in the repo, committing as the given author at the given time
+    fn git_commit(dir: &Path, author: &str, time: i64, message: &str) {
+        let date = format!("@{time} +0000");
+        for args in [&["add", "-A"][..], &["commit", "-q", "-m", message]] {
+            let status = Command::new("git")
+                .args(["-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(dir)
+                .env("GIT_AUTHOR_NAME", author)
+                .env("GIT_AUTHOR_DATE", &date)
+                .env("GIT_COMMITTER_NAME", author)
+                .env("GIT_COMMITTER_DATE", &date)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+    }
+
+    /// Each commit lists the recipes it was the last to change
+    #[rstest]
+    fn test_changelog(temp_dir: TempDir) {
+        let status = Command::new("git")
+            .args(["init", "-q"])
+            .current_dir(&*temp_dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let path = temp_dir.join("slumber.yml");
+        fs::write(
+            &path,
+            "\
+profiles:
+  local:
+    data:
+      host: http://localhost
+requests:
+  login:
+    method: POST
+    url: \"{{ host }}/login\"
+  users:
+    method: GET
+    url: \"{{ host }}/users\"
+  logout:
+    method: POST
+    url: \"{{ host }}/logout\"
+",
+        )
+        .unwrap();
+        git_commit(&temp_dir, "Alice", 1_700_000_000, "Add recipes");
+
+        // Change one recipe and leave the others alone
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace("GET", "PUT")).unwrap();
+        git_commit(&temp_dir, "Bob", 1_700_086_400, "Update users method");
+
+        // Uncommitted changes are ignored, so this is still Alice's
+        let content = fs::read_to_string(&path).unwrap();
+        fs::write(&path, content.replace("/logout", "/log-out")).unwrap();
+
+        let file = CollectionFile::new(Some(path)).unwrap();
+        let collection = file.load().unwrap();
+        let commits: Vec<_> = file
+            .changelog(&collection, 10)
+            .unwrap()
+            .into_iter()
+            .map(|commit| {
+                (
+                    commit.author,
+                    commit.time.timestamp(),
+                    commit.summary,
+                    commit.recipes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            commits,
+            vec![
+                (
+                    "Bob".to_owned(),
+                    1_700_086_400,
+                    "Update users method".to_owned(),
+                    vec!["users".into()]
+                ),
+                (
+                    "Alice".to_owned(),
+                    1_700_000_000,
+                    "Add recipes".to_owned(),
+                    vec!["login".into(), "logout".into()]
+                ),
+            ]
+        );
+    }
+
+    /// Collections outside a repository can't have a history
+    #[rstest]
+    fn test_changelog_no_repo(temp_dir: TempDir) {
+        let path = temp_dir.join("slumber.yml");
+        fs::write(&path, "requests: {}").unwrap();
+        let file = CollectionFile::new(Some(path)).unwrap();
+        let collection = file.load().unwrap();
+        assert!(matches!(
+            file.changelog(&collection, 10),
+            Err(ChangelogError::Git { .. })
+        ));
+    }
+}
@@ -1,11 +1,13 @@
 //! Utilities for working with templated JSON
 
-use crate::render::TemplateContext;
+#[cfg(feature = "native")]
+use crate::render::SingleRenderContext;
+#[cfg(feature = "native")]
 use futures::future;
 use serde::{Serialize, Serializer, ser::SerializeMap};
-use slumber_template::{
-    RenderError, Template, TemplateParseError, TryFromValue,
-};
+#[cfg(feature = "native")]
+use slumber_template::{RenderError, TryFromValue};
+use slumber_template::{Template, TemplateParseError};
 use std::str::FromStr;
 use thiserror::Error;
 
@@ -50,10 +52,30 @@ impl JsonTemplate {
         }
     }
 
+    /// Visit every template in the value, including object keys, in order
+    pub fn walk_templates<'a>(&'a self, f: &mut impl FnMut(&'a Template)) {
+        match self {
+            Self::Null | Self::Bool(_) | Self::Number(_) => {}
+            Self::String(template) => f(template),
+            Self::Array(array) => {
+                for item in array {
+                    item.walk_templates(f);
+                }
+            }
+            Self::Object(map) => {
+                for (key, value) in map {
+                    f(key);
+                    value.walk_templates(f);
+                }
+            }
+        }
+    }
+
     /// Render all templates to strings and return a static JSON value
+    #[cfg(feature = "native")]
     pub async fn render(
         &self,
-        context: &TemplateContext,
+        context: &SingleRenderContext<'_>,
     ) -> Result<serde_json::Value, RenderError> {
         let rendered = match self {
             Self::Null => serde_json::Value::Null,
@@ -65,11 +87,7 @@ impl JsonTemplate {
                 // (e.g. a number or array), use that value directly. This
                 // enables non-string values
                 serde_json::Value::try_from_value(
-                    template
-                        .render(&context.streaming(false))
-                        .await
-                        .try_collect_value()
-                        .await?,
+                    template.render(context).await.try_collect_value().await?,
                 )
                 .map_err(|error| RenderError::Value(error.error))?
             }
@@ -83,9 +101,7 @@ impl JsonTemplate {
             Self::Object(map) => {
                 let map = future::try_join_all(map.iter().map(
                     |(key, value)| async {
-                        let key = key
-                            .render_string(&context.streaming(false))
-                            .await?;
+                        let key = key.render_string(context).await?;
                         let value = value.render(context).await?;
                         Ok::<_, RenderError>((key, value))
                     },
@@ -246,7 +262,9 @@ mod tests {
             // If we're expecting an error, it should happen during the parse
             // so don't check for errors during render. Saves having to
             // consolidate the error types
-            Ok(json) => Ok(json.render(&context).await.unwrap()),
+            Ok(json) => {
+                Ok(json.render(&context.streaming(false)).await.unwrap())
+            }
             Err(error) => Err(error),
         };
         assert_result(result, expected);
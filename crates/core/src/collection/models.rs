@@ -1,19 +1,16 @@
 //! The plain data types that make up a request collection
 
-use crate::{
-    collection::{
-        cereal,
-        json::JsonTemplate,
-        recipe_tree::{RecipeNode, RecipeTree},
-    },
-    http::HttpMethod,
+use crate::collection::{
+    cereal,
+    json::JsonTemplate,
+    recipe_tree::{RecipeNode, RecipeTree},
 };
 use derive_more::{Deref, From, Into};
 use indexmap::IndexMap;
+use itertools::Itertools;
 use mime::Mime;
-use reqwest::header;
 use serde::{Deserialize, Serialize};
-use slumber_template::{Template, TemplateParseError};
+use slumber_template::{Delimiters, Template, TemplateParseError};
 use slumber_util::{
     ResultTraced, doc_link,
     yaml::{self, SourceLocation, YamlError, YamlErrorKind},
@@ -22,8 +19,11 @@ use std::{
     error::Error as StdError,
     fmt::{self, Display},
     io, iter,
+    net::IpAddr,
     path::{Path, PathBuf},
+    str::FromStr,
 };
+use strum::{EnumIter, IntoEnumIterator};
 use thiserror::Error;
 use tracing::info;
 
@@ -46,8 +46,24 @@ pub struct Collection {
     /// Descriptive name for the collection
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    /// Override the `{{ }}` delimiters for every template in the collection.
+    /// Templates are always serialized with the default delimiters, so this
+    /// isn't serialized either.
+    #[serde(skip_serializing)]
+    pub template_delimiters: Option<Delimiters>,
+    /// Values that are constant across all profiles, such as API versions or
+    /// shared IDs. Referenced in templates as `{{ vars.name }}`.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub variables: IndexMap<String, Template>,
     /// Map of profiles, keyed by their unique IDs
     pub profiles: IndexMap<ProfileId, Profile>,
+    /// Named sets of profile values that can be switched on and off
+    /// independently of the selected profile. Enabled toggles are layered on
+    /// top of the profile's data.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub toggles: IndexMap<ToggleId, Toggle>,
     /// Map of requests and folders, keyed by their unique IDs. Folders allow
     /// for nested maps of more requests and folders. All IDs must be unique
     /// **throughout the entire tree**, not just at their level.
@@ -129,8 +145,11 @@ impl slumber_util::Factory for Collection {
         let profile = Profile::factory(());
         Collection {
             name: None,
+            template_delimiters: None,
+            variables: IndexMap::new(),
             recipes: by_id([recipe]).into(),
             profiles: by_id([profile]),
+            toggles: IndexMap::new(),
         }
     }
 }
@@ -155,6 +174,46 @@ pub struct Profile {
     #[cfg_attr(feature = "schema", schemars(default))]
     pub default: bool,
     pub data: IndexMap<String, Template>,
+    /// Inject faults into requests sent while this profile is selected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chaos: Option<Chaos>,
+    /// TLS client certificate to present for every request sent while this
+    /// profile is selected. Overrides the global `client_certificates` config
+    /// field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<ClientCertificate>,
+    /// URL of a proxy to send requests through while this profile is
+    /// selected. Overrides the global `proxy` config field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Template>,
+    /// Limits on how long requests can take while this profile is selected.
+    /// Each limit overrides the global `timeout` config field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Timeout>,
+    /// Slow down requests sent while this profile is selected, to simulate a
+    /// poor connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<Throttle>,
+    /// HTTP version to use for requests sent while this profile is selected
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<HttpProtocol>,
+    /// Path to a Unix domain socket to send requests over while this profile
+    /// is selected, e.g. `/var/run/docker.sock`. On Windows, this is a named
+    /// pipe instead, e.g. `\\.\pipe\docker_engine`. The URL's path and query
+    /// are still used, but its host isn't resolved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket: Option<Template>,
+    /// Hostnames mapped to the IP address to connect to, instead of
+    /// resolving them via DNS (like curl's `--resolve`). The URL, `Host`
+    /// header, and TLS verification still use the hostname.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub resolve: IndexMap<String, IpAddr>,
+    /// What responses under this profile are expected to look like. A
+    /// response that doesn't match gets a warning, to catch requests that hit
+    /// the wrong environment or went through an intercepting proxy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinning: Option<Pinning>,
 }
 
 impl Profile {
@@ -182,10 +241,264 @@ impl slumber_util::Factory for Profile {
             name: None,
             default: false,
             data: IndexMap::new(),
+            chaos: None,
+            certificate: None,
+            proxy: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            resolve: IndexMap::new(),
+            pinning: None,
+        }
+    }
+}
+
+/// Simulated network faults for a profile, to exercise a client's retry and
+/// timeout handling. Each fault is applied before the request is handed to
+/// the transport, so dropped and failed requests never hit the network.
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Chaos {
+    /// Delay every request by this many milliseconds
+    #[serde(skip_serializing_if = "cereal::is_zero")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub latency_ms: u64,
+    /// Percentage of requests (0-100) that fail with a connection error
+    #[serde(skip_serializing_if = "cereal::is_zero")]
+    #[cfg_attr(feature = "schema", schemars(default, range(max = 100)))]
+    pub drop_percent: u8,
+    /// Percentage of requests (0-100) that get a synthetic `500` response
+    #[serde(skip_serializing_if = "cereal::is_zero")]
+    #[cfg_attr(feature = "schema", schemars(default, range(max = 100)))]
+    pub error_percent: u8,
+    /// Seed for fault selection. With a seed, the same sequence of requests
+    /// will always see the same faults. Without one, faults are random.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// When and how to retry a failed request. Delays between attempts grow
+/// exponentially: `backoff_ms`, then twice that, then four times, and so on, up
+/// to `max_backoff_ms`.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct RetryPolicy {
+    /// Total number of times to send the request, including the first
+    pub max_attempts: u32,
+    /// Response status codes that trigger a retry
+    pub statuses: Vec<u16>,
+    /// Retry when the request fails without a response, e.g. the connection
+    /// is refused or dropped
+    pub network_errors: bool,
+    /// Delay before the first retry, in milliseconds
+    pub backoff_ms: u64,
+    /// Upper bound on the delay between attempts, in milliseconds
+    pub max_backoff_ms: u64,
+    /// Randomize each delay between half and all of its value, so many
+    /// clients retrying at once don't stay in lockstep
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            statuses: vec![429, 502, 503, 504],
+            network_errors: true,
+            backoff_ms: 500,
+            max_backoff_ms: 30_000,
+            jitter: true,
         }
     }
 }
 
+/// How to handle 3xx redirect responses
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct RedirectPolicy {
+    /// Follow redirects automatically. If disabled, the redirect response is
+    /// returned as-is
+    pub follow: bool,
+    /// Most redirects to follow for a single request. Exceeding this is an
+    /// error
+    pub max_redirects: usize,
+    /// On a `307`/`308` redirect, send the request again with the same method
+    /// and body. If disabled, these redirects aren't followed for requests
+    /// with a body. Other redirects always switch to a `GET` without a body.
+    pub resend_body: bool,
+    /// Remove the `Authorization` and `Cookie` headers when redirected to a
+    /// different origin (scheme, host, or port), so credentials aren't leaked
+    /// to another server
+    pub strip_auth: bool,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        Self {
+            follow: true,
+            max_redirects: 10,
+            resend_body: true,
+            strip_auth: true,
+        }
+    }
+}
+
+/// Limits on how long a request can take. A limit that isn't given is
+/// inherited from the next level up: recipe, then profile, then global config.
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct Timeout {
+    /// Max time to establish a connection to the server, including the TLS
+    /// handshake, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_ms: Option<u64>,
+    /// Max time for the entire request, from sending it until the whole
+    /// response body is received, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_ms: Option<u64>,
+}
+
+/// Expectations for every response received under a profile. These don't
+/// block anything; a response that violates them is shown with a warning.
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct Pinning {
+    /// SHA-256 fingerprint of the server's TLS certificate, as hex digits.
+    /// Colons between bytes are allowed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<String>,
+    /// Headers that each response must include, mapped to a value the header
+    /// must contain. Header names are case-insensitive.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub headers: IndexMap<String, String>,
+}
+
+/// Max transfer rates for a request, to simulate a slow network (e.g. 3G). A
+/// rate that isn't given on the recipe is inherited from the profile. Without
+/// either, the rate is unlimited.
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct Throttle {
+    /// Max rate for receiving the response body, in kilobits per second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_kbps: Option<u64>,
+    /// Max rate for sending the request body, in kilobits per second
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upload_kbps: Option<u64>,
+}
+
+/// How a response body can be compressed. By default, no `Accept-Encoding`
+/// header is sent, and a compressed response is decoded if its encoding is
+/// supported.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct Compression {
+    /// Encodings to ask the server for, in order of preference. These are
+    /// sent as the `Accept-Encoding` header. Use `[identity]` to ask for an
+    /// uncompressed body. An `Accept-Encoding` entry in `headers` takes
+    /// precedence over this.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub accept: Vec<ContentEncoding>,
+    /// Decode a compressed response body, according to its `Content-Encoding`
+    /// header. Disable this to see the raw bytes as the server sent them.
+    pub decompress: bool,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Self {
+            accept: Vec::new(),
+            decompress: true,
+        }
+    }
+}
+
+/// A [content coding](https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Headers/Content-Encoding)
+/// for an HTTP body
+#[derive(Copy, Clone, Debug, EnumIter, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEncoding {
+    /// gzip compression
+    Gzip,
+    /// zlib compression
+    Deflate,
+    /// No compression
+    Identity,
+}
+
+impl ContentEncoding {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            Self::Identity => "identity",
+        }
+    }
+}
+
+/// Which HTTP version a request is sent over
+#[derive(
+    Copy, Clone, Debug, Default, EnumIter, Eq, Hash, PartialEq, Serialize,
+)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum HttpProtocol {
+    /// HTTP/2 if the server offers it during the TLS handshake, otherwise
+    /// HTTP/1.1
+    #[default]
+    Auto,
+    /// Always HTTP/1.1
+    Http1,
+    /// Always HTTP/2, without negotiating it first ("prior knowledge"). This
+    /// works over plaintext too, but only if the server supports HTTP/2.
+    Http2,
+}
+
+impl HttpProtocol {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Http1 => "http1",
+            Self::Http2 => "http2",
+        }
+    }
+}
+
+/// A TLS client certificate to present during the handshake, for servers that
+/// require mutual TLS. The certificate is either PEM-encoded, with the key in
+/// the same file or a separate one, or a PKCS#12 archive (`.p12`/`.pfx`)
+/// containing both.
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ClientCertificate {
+    /// Path to the certificate (chain), relative to the collection file. If
+    /// `key` is omitted, this file must contain the private key as well.
+    pub certificate: String,
+    /// Path to the private key, relative to the collection file. Only needed
+    /// if the key isn't in the certificate file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    /// Password to decrypt a PKCS#12 archive. Ignored for PEM certificates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
 #[derive(
     Clone,
     Debug,
@@ -205,7 +518,6 @@ impl slumber_util::Factory for Profile {
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct ProfileId(String);
 
-#[cfg(any(test, feature = "test"))]
 impl From<&str> for ProfileId {
     fn from(value: &str) -> Self {
         value.to_owned().into()
@@ -219,6 +531,75 @@ impl slumber_util::Factory for ProfileId {
     }
 }
 
+/// A named set of profile values that can be enabled on top of any profile.
+/// This allows orthogonal variations, e.g. "as admin" vs "as regular user",
+/// without defining a profile for every combination.
+#[derive(Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Toggle {
+    #[serde(skip)] // This will be auto-populated from the map key
+    pub id: ToggleId,
+    /// Location where this toggle is defined in YAML
+    #[serde(skip)]
+    pub location: SourceLocation,
+    pub name: Option<String>,
+    /// Fields to set while this toggle is enabled. These take precedence
+    /// over the selected profile's fields of the same name.
+    pub data: IndexMap<String, Template>,
+}
+
+impl Toggle {
+    /// Get a presentable name for this toggle
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+}
+
+#[cfg(any(test, feature = "test"))]
+impl slumber_util::Factory for Toggle {
+    fn factory((): ()) -> Self {
+        Self {
+            id: ToggleId::factory(()),
+            location: SourceLocation::default(),
+            name: None,
+            data: IndexMap::new(),
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    Debug,
+    Deref,
+    Default,
+    derive_more::Display,
+    Eq,
+    From,
+    Hash,
+    Into,
+    PartialEq,
+    Serialize,
+    Deserialize,
+)]
+#[deref(forward)]
+#[serde(transparent)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ToggleId(String);
+
+impl From<&str> for ToggleId {
+    fn from(value: &str) -> Self {
+        value.to_owned().into()
+    }
+}
+
+#[cfg(any(test, feature = "test"))]
+impl slumber_util::Factory for ToggleId {
+    fn factory((): ()) -> Self {
+        uuid::Uuid::new_v4().to_string().into()
+    }
+}
+
 /// A gathering of like-minded recipes and/or folders
 #[derive(Debug, Serialize)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
@@ -232,6 +613,11 @@ pub struct Folder {
     pub location: SourceLocation,
     /// Display name
     pub name: Option<String>,
+    /// Authentication for every recipe in this folder, including those in
+    /// subfolders. A recipe or subfolder can replace this with its own
+    /// `authentication`, or disable it with `authentication: !none`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authentication: Option<AuthenticationSetting>,
     /// Child requests of this folder
     // Use `requests` in serde to match the root collection field
     #[serde(rename = "requests")]
@@ -256,6 +642,7 @@ impl slumber_util::Factory for Folder {
                 column: 4,
             },
             name: None,
+            authentication: None,
             children: IndexMap::new(),
         }
     }
@@ -311,10 +698,12 @@ pub struct Recipe {
     ///
     /// - `type: basic`: [Basic authentication](https://swagger.io/docs/specification/v3_0/authentication/basic-authentication/)
     /// - `type: bearer`: [Bearer authentication](https://swagger.io/docs/specification/v3_0/authentication/bearer-authentication/)
+    /// - `!none`: No authentication, even if a parent folder defines it
     ///
-    /// See individual variants for more details on usage.
+    /// If omitted, authentication is inherited from the closest parent folder
+    /// that defines it. See individual variants for more details on usage.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub authentication: Option<Authentication>,
+    pub authentication: Option<AuthenticationSetting>,
     /// A map of [URL query parameters](https://developer.mozilla.org/en-US/docs/Learn_web_development/Howto/Web_mechanics/What_is_a_URL#parameters).
     /// Each value can either be a single value (`?foo=bar`) or multiple
     /// (`?foo=bar&foo=baz`)
@@ -325,6 +714,93 @@ pub struct Recipe {
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     #[cfg_attr(feature = "schema", schemars(default))]
     pub headers: IndexMap<String, Template>,
+    /// Value of the `User-Agent` header. Overrides the global `user_agent`
+    /// config field. An empty value omits the header entirely. A `User-Agent`
+    /// entry in `headers` takes precedence over this.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<Template>,
+    /// Name of the backend used to send this recipe's requests. Overrides the
+    /// global `transport` config field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport: Option<String>,
+    /// TLS client certificate to present when sending this recipe's requests.
+    /// Overrides the selected profile's `certificate` and the global
+    /// `client_certificates` config field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<ClientCertificate>,
+    /// Automatically retry requests that fail with a network error or a
+    /// retryable status code. Overrides the global `retry` config field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// How to handle redirects. If not given, redirects are followed
+    /// according to the global `follow_redirects` config field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirect: Option<RedirectPolicy>,
+    /// Limits on how long this recipe's requests can take. Each limit
+    /// overrides the selected profile's `timeout` and the global `timeout`
+    /// config field.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<Timeout>,
+    /// Slow down this recipe's requests, to simulate a poor connection. Each
+    /// rate overrides the selected profile's `throttle`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub throttle: Option<Throttle>,
+    /// Which compressed encodings to ask the server for, and whether to
+    /// decode a compressed response body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+    /// HTTP version to use for this recipe's requests. Overrides the selected
+    /// profile's `protocol`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<HttpProtocol>,
+    /// Path to a Unix domain socket or Windows named pipe to send this
+    /// recipe's requests over. Overrides the selected profile's `socket`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket: Option<Template>,
+    /// If the server rejects the request with `401 Unauthorized` or `403
+    /// Forbidden`, send every upstream request referenced by
+    /// `response()`/`response_header()` again (e.g. to log in again), then
+    /// retry the request once with the fresh values
+    #[serde(skip_serializing_if = "cereal::is_false")] // Skip if default
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub reauthenticate: bool,
+    /// Values to extract from each successful response. Each captured value is
+    /// stored under its name for the rest of the session, and can be used in
+    /// templates just like a profile field.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub capture: IndexMap<String, CaptureSelector>,
+    /// Checks to run on each response. Failures are shown with the response
+    /// in the TUI, and make `slumber request` exit with an error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assert: Option<Assertions>,
+    /// Labels for grouping recipes. `slumber test --tag <tag>` runs every
+    /// recipe with the tag
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub tags: Vec<String>,
+    /// Open a [WebSocket](https://developer.mozilla.org/en-US/docs/Web/API/WebSockets_API)
+    /// connection instead of sending a one-off request. The method must be
+    /// `GET`. Each message received from the server is added to the response
+    /// body as its own line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub websocket: Option<WebSocket>,
+    /// Call a unary [gRPC](https://grpc.io/) method instead of sending a plain
+    /// HTTP request. The method must be `POST`. The body is the request
+    /// message as JSON, and the response message is decoded to JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grpc: Option<Grpc>,
+    /// What to do when a template references a profile field that isn't
+    /// defined. Defaults to `strict`. Can be overridden for a single run from
+    /// the CLI.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub render_mode: Option<RenderMode>,
+    /// Named inputs to the recipe, referenced in templates just like profile
+    /// fields. Values are given when the request is sent: the TUI asks for
+    /// them in a form, and the CLI accepts them with `--param`.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub params: IndexMap<String, RecipeParam>,
 }
 
 impl Recipe {
@@ -341,7 +817,7 @@ impl Recipe {
     /// will fail.
     pub fn mime(&self) -> Option<Mime> {
         self.headers
-            .get(header::CONTENT_TYPE.as_str())
+            .get(http::header::CONTENT_TYPE.as_str())
             .and_then(|template| template.display().parse::<Mime>().ok())
             .or_else(|| self.body.as_ref()?.mime())
     }
@@ -363,6 +839,418 @@ impl Recipe {
     }
 }
 
+/// [HTTP request method](https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Methods)
+// This is duplicated from [reqwest::Method] so we can enforce
+// the method is valid during deserialization. This is also generally more
+// ergonomic at the cost of some flexibility.
+#[derive(Copy, Clone, Debug, EnumIter, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+// Use FromStr to enable case-insensitivity
+#[serde(into = "&str", try_from = "String")]
+// Show as a string enum
+#[cfg_attr(feature = "schema", schemars(!try_from, rename_all = "UPPERCASE"))]
+pub enum HttpMethod {
+    Connect,
+    Delete,
+    Get,
+    Head,
+    Options,
+    Patch,
+    Post,
+    Put,
+    Trace,
+}
+
+impl HttpMethod {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Connect => "CONNECT",
+            Self::Delete => "DELETE",
+            Self::Get => "GET",
+            Self::Head => "HEAD",
+            Self::Options => "OPTIONS",
+            Self::Patch => "PATCH",
+            Self::Post => "POST",
+            Self::Put => "PUT",
+            Self::Trace => "TRACE",
+        }
+    }
+}
+
+impl Display for HttpMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_str())
+    }
+}
+
+impl FromStr for HttpMethod {
+    type Err = HttpMethodParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_uppercase().as_str() {
+            "CONNECT" => Ok(Self::Connect),
+            "DELETE" => Ok(Self::Delete),
+            "GET" => Ok(Self::Get),
+            "HEAD" => Ok(Self::Head),
+            "OPTIONS" => Ok(Self::Options),
+            "PATCH" => Ok(Self::Patch),
+            "POST" => Ok(Self::Post),
+            "PUT" => Ok(Self::Put),
+            "TRACE" => Ok(Self::Trace),
+            _ => Err(HttpMethodParseError {
+                input: s.to_owned(),
+            }),
+        }
+    }
+}
+
+/// For serialization
+impl From<HttpMethod> for &'static str {
+    fn from(method: HttpMethod) -> Self {
+        method.to_str()
+    }
+}
+
+/// For deserialization
+impl TryFrom<String> for HttpMethod {
+    type Error = <Self as FromStr>::Err;
+
+    fn try_from(method: String) -> Result<Self, Self::Error> {
+        method.parse()
+    }
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "Invalid HTTP method `{input}`. Must be one of: {}",
+    HttpMethod::iter().map(HttpMethod::to_str).format(", "),
+)]
+pub struct HttpMethodParseError {
+    input: String,
+}
+
+/// Where to find a [captured](Recipe::capture) value in a response
+///
+/// - `header:<name>`: Value of a response header
+/// - Anything else is a JSONPath query on the JSON response body
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(into = "String")]
+pub enum CaptureSelector {
+    /// JSONPath query on the response body. The query is parsed when the
+    /// capture is taken, because the JSONPath parser isn't available to the
+    /// collection layer
+    Body(String),
+    /// Value of a response header
+    Header(String),
+}
+
+impl CaptureSelector {
+    /// Prefix to select a header instead of querying the body
+    const HEADER_PREFIX: &str = "header:";
+}
+
+impl Display for CaptureSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Body(query) => write!(f, "{query}"),
+            Self::Header(header) => {
+                write!(f, "{}{header}", Self::HEADER_PREFIX)
+            }
+        }
+    }
+}
+
+impl FromStr for CaptureSelector {
+    type Err = CaptureSelectorParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(header) = s.strip_prefix(Self::HEADER_PREFIX) {
+            let header = header.trim();
+            if header.is_empty() {
+                return Err(CaptureSelectorParseError::EmptyHeader);
+            }
+            Ok(Self::Header(header.to_owned()))
+        } else if s.is_empty() {
+            Err(CaptureSelectorParseError::Empty)
+        } else {
+            Ok(Self::Body(s.to_owned()))
+        }
+    }
+}
+
+/// For serialization
+impl From<CaptureSelector> for String {
+    fn from(selector: CaptureSelector) -> Self {
+        selector.to_string()
+    }
+}
+
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for CaptureSelector {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "CaptureSelector".into()
+    }
+
+    fn json_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "JSONPath query on the response body (e.g. \
+                `$.token`), or `header:<name>` for a response header",
+            "type": "string",
+            "minLength": 1,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum CaptureSelectorParseError {
+    #[error("Capture selector cannot be empty")]
+    Empty,
+    #[error("Missing header name after `header:`")]
+    EmptyHeader,
+}
+
+/// Expectations for each response to a recipe, to use a collection as a
+/// lightweight API test suite. A response that fails any of them is still
+/// stored and captured from as usual.
+#[derive(Debug, Default, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(default))]
+pub struct Assertions {
+    /// Status codes the response is allowed to have. If empty, any status is
+    /// allowed.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub status: Vec<u16>,
+    /// Headers that each response must include, mapped to the header's exact
+    /// value. Header names are case-insensitive.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    pub headers: IndexMap<String, String>,
+    /// JSONPath queries on the response body, mapped to the value the result
+    /// must equal. Values are compared as text, so `3`, `true`, and `"3"`
+    /// are all written the same way. A query with multiple results is
+    /// compared as a JSON array.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "IndexMap<String, serde_json::Value>")
+    )]
+    pub body: IndexMap<String, String>,
+    /// Max time for the whole request, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_latency_ms: Option<u64>,
+}
+
+impl Assertions {
+    /// Total number of individual checks
+    pub fn len(&self) -> usize {
+        usize::from(!self.status.is_empty())
+            + self.headers.len()
+            + self.body.len()
+            + usize::from(self.max_latency_ms.is_some())
+    }
+
+    /// Are there no checks at all?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Settings for a WebSocket recipe
+#[derive(Debug, Default, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct WebSocket {
+    /// Messages to send as soon as the connection opens, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub messages: Vec<Template>,
+    /// Frame type of every message sent over the connection. If omitted,
+    /// messages that are valid UTF-8 are sent as text, anything else as
+    /// binary.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frame: Option<WebSocketFrame>,
+}
+
+/// Frame type of a WebSocket message
+#[derive(Copy, Clone, Debug, EnumIter, Eq, Hash, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum WebSocketFrame {
+    /// Text frame. Messages must be valid UTF-8
+    Text,
+    /// Binary frame
+    Binary,
+}
+
+impl WebSocketFrame {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Binary => "binary",
+        }
+    }
+}
+
+/// Settings for a gRPC recipe
+#[derive(Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Grpc {
+    /// Fully qualified method to call, e.g. `helloworld.Greeter/SayHello`
+    pub method: String,
+    /// `.proto` files that define the service, relative to the collection
+    /// file. If empty, the schema is loaded from the server via
+    /// [reflection](https://grpc.io/docs/guides/reflection/).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub proto_files: Vec<String>,
+    /// Directories to search for files imported by `proto_files`, relative to
+    /// the collection file. Defaults to the collection file's directory.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub import_paths: Vec<String>,
+}
+
+impl Grpc {
+    /// Split the method into its service and method names. Both
+    /// `package.Service/Method` and `package.Service.Method` are accepted.
+    /// Return `None` if there's no separator.
+    pub fn service_method(&self) -> Option<(&str, &str)> {
+        let (service, method) = self
+            .method
+            .rsplit_once('/')
+            .or_else(|| self.method.rsplit_once('.'))?;
+        (!service.is_empty() && !method.is_empty()).then_some((service, method))
+    }
+}
+
+/// A named input to a recipe
+#[derive(Clone, Debug, Default, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RecipeParam {
+    /// Type that the value is converted to before it's used
+    #[serde(rename = "type")]
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub param_type: RecipeParamType,
+    /// Must a value be given? A parameter with a default is always satisfied
+    #[serde(skip_serializing_if = "cereal::is_false")] // Skip if default
+    #[cfg_attr(feature = "schema", schemars(default))]
+    pub required: bool,
+    /// Value to use if none is given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<Template>,
+    /// Explanation of the parameter, shown when asking for a value
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Type of a [RecipeParam]
+#[derive(Copy, Clone, Debug, Default, EnumIter, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeParamType {
+    #[default]
+    String,
+    Integer,
+    Float,
+    Boolean,
+}
+
+impl RecipeParamType {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Float => "float",
+            Self::Boolean => "boolean",
+        }
+    }
+}
+
+impl Display for RecipeParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+impl FromStr for RecipeParamType {
+    type Err = RecipeParamTypeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::iter().find(|ty| ty.to_str() == s).ok_or_else(|| {
+            RecipeParamTypeParseError {
+                input: s.to_owned(),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "Invalid parameter type `{input}`. Must be one of: {}",
+    RecipeParamType::iter().map(RecipeParamType::to_str).format(", "),
+)]
+pub struct RecipeParamTypeParseError {
+    input: String,
+}
+
+/// How to handle a template that references a profile field that isn't
+/// defined in the selected profile
+#[derive(Copy, Clone, Debug, Default, EnumIter, PartialEq, Serialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum RenderMode {
+    /// Fail the request with an error
+    #[default]
+    Strict,
+    /// Render the field as an empty string
+    Lenient,
+    /// Ask the user for the value. The answer is used for the rest of the
+    /// render, but isn't saved.
+    Prompt,
+}
+
+impl RenderMode {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Strict => "strict",
+            Self::Lenient => "lenient",
+            Self::Prompt => "prompt",
+        }
+    }
+}
+
+impl Display for RenderMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
+impl FromStr for RenderMode {
+    type Err = RenderModeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::iter().find(|mode| mode.to_str() == s).ok_or_else(|| {
+            RenderModeParseError {
+                input: s.to_owned(),
+            }
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "Invalid render mode `{input}`. Must be one of: {}",
+    RenderMode::iter().map(RenderMode::to_str).format(", "),
+)]
+pub struct RenderModeParseError {
+    input: String,
+}
+
 #[cfg(any(test, feature = "test"))]
 impl slumber_util::Factory for Recipe {
     fn factory((): ()) -> Self {
@@ -381,6 +1269,24 @@ impl slumber_util::Factory for Recipe {
             authentication: None,
             query: IndexMap::new(),
             headers: IndexMap::new(),
+            user_agent: None,
+            transport: None,
+            certificate: None,
+            retry: None,
+            redirect: None,
+            timeout: None,
+            throttle: None,
+            compression: None,
+            protocol: None,
+            socket: None,
+            reauthenticate: false,
+            capture: IndexMap::new(),
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: None,
+            params: IndexMap::new(),
         }
     }
 }
@@ -421,7 +1327,6 @@ fn persist_default() -> bool {
 #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct RecipeId(String);
 
-#[cfg(any(test, feature = "test"))]
 impl From<&str> for RecipeId {
     fn from(value: &str) -> Self {
         value.to_owned().into()
@@ -460,6 +1365,109 @@ pub enum Authentication<T = Template> {
     Basic { username: T, password: Option<T> },
     /// `Authorization: Bearer {token}`
     Bearer { token: T },
+    /// HTTP Digest authentication (RFC 7616). The `Authorization` header is
+    /// computed from the server's challenge, so every request takes two round
+    /// trips.
+    Digest { username: T, password: T },
+    /// NTLM authentication, sent via either the `NTLM` or `Negotiate` scheme
+    /// depending on what the server offers. Like Digest, the credentials are
+    /// exchanged in a challenge/response handshake.
+    Ntlm {
+        username: T,
+        password: T,
+        /// Windows domain of the user. Can also be given as a prefix of the
+        /// username: `DOMAIN\user`
+        domain: Option<T>,
+    },
+    /// `Authorization: Bearer {token}`, where the token is fetched from an
+    /// OAuth 2.0 provider. Tokens are cached until they expire.
+    #[serde(rename = "oauth2")]
+    OAuth2(OAuth2<T>),
+}
+
+/// Settings for OAuth 2.0 authentication
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct OAuth2<T = Template> {
+    /// How to obtain an access token
+    #[serde(flatten)]
+    pub grant: OAuth2Grant<T>,
+    /// Endpoint that issues access tokens
+    pub token_url: T,
+    pub client_id: T,
+    /// Secret for confidential clients. Public clients (e.g. CLI apps using
+    /// PKCE) don't have one.
+    pub client_secret: Option<T>,
+    /// Space-separated scopes to request
+    pub scope: Option<T>,
+}
+
+/// OAuth 2.0 grant type, which defines how an access token is obtained
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(tag = "grant_type", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum OAuth2Grant<T = Template> {
+    /// Exchange the client's own credentials for a token, with no user
+    /// involved
+    ClientCredentials,
+    /// Have the user log in via the browser, then exchange the resulting code
+    /// for a token. Secured with PKCE.
+    AuthorizationCode {
+        /// Login page that the user is sent to
+        authorization_url: T,
+        /// Where the provider sends the user after logging in. Slumber listens
+        /// on this address to receive the code, so it must be an `http` URL
+        /// on the local machine. Defaults to
+        /// `http://127.0.0.1:8765/callback`.
+        redirect_uri: Option<T>,
+    },
+}
+
+/// Authentication as defined on a recipe or folder. A recipe without this
+/// inherits the setting of its closest ancestor folder that has one.
+#[derive(Clone, Debug, From)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "schema", schemars(untagged))]
+pub enum AuthenticationSetting {
+    /// Use this authentication
+    Defined(Authentication),
+    /// `!none`: Don't send authentication, even if it's defined on a parent
+    /// folder
+    #[from(skip)]
+    Disabled,
+}
+
+impl AuthenticationSetting {
+    /// Get the defined authentication, or `None` if it's disabled
+    pub fn authentication(&self) -> Option<&Authentication> {
+        match self {
+            Self::Defined(authentication) => Some(authentication),
+            Self::Disabled => None,
+        }
+    }
+}
+
+/// Serialize as the contained authentication, or the `!none` tag if disabled
+impl Serialize for AuthenticationSetting {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Defined(authentication) => {
+                authentication.serialize(serializer)
+            }
+            Self::Disabled => serializer.serialize_newtype_variant(
+                "AuthenticationSetting",
+                1,
+                cereal::TAG_NONE,
+                &(),
+            ),
+        }
+    }
 }
 
 /// A value for a particular query parameter key
@@ -511,11 +1519,21 @@ pub enum RecipeBody {
     FormUrlencoded(IndexMap<String, Template>),
     /// `multipart/form-data` body. Value is a mapping of form field
     /// name to value. Values are templates while field names are not. Values
-    /// can render to any bytes.
-    FormMultipart(IndexMap<String, Template>),
+    /// can render to any bytes. Each value can also be given as a mapping to
+    /// set the part's `Content-Type` and filename.
+    FormMultipart(IndexMap<String, FormPart>),
     /// Plain body, but the bytes will be streamed instead of being loaded
     /// into memory
     Stream(Template),
+    /// [GraphQL](https://graphql.org/learn/serving-over-http/) operation. The
+    /// query and variables are combined into an `application/json` body.
+    Graphql(GraphQlBody),
+    /// `!file`: Body is streamed from a file at send time. The value is a
+    /// template that renders to the path, relative to the collection file.
+    /// `Content-Length` is taken from the file's size, and `Content-Type` is
+    /// guessed from its extension unless the recipe sets the header.
+    #[serde(untagged, serialize_with = "cereal::serialize_file_body")]
+    File(Template),
     /// Plain string/bytes body. Must be the last variant to support untagged.
     /// This captures any value that doesn't fit one of the above variants.
     #[serde(untagged)]
@@ -541,8 +1559,12 @@ impl RecipeBody {
     /// an explicit header.
     pub fn mime(&self) -> Option<Mime> {
         match self {
-            RecipeBody::Raw(_) | RecipeBody::Stream(_) => None,
-            RecipeBody::Json(_) => Some(mime::APPLICATION_JSON),
+            RecipeBody::Raw(_)
+            | RecipeBody::Stream(_)
+            | RecipeBody::File(_) => None,
+            RecipeBody::Json(_) | RecipeBody::Graphql(_) => {
+                Some(mime::APPLICATION_JSON)
+            }
             RecipeBody::FormUrlencoded(_) => {
                 Some(mime::APPLICATION_WWW_FORM_URLENCODED)
             }
@@ -551,6 +1573,83 @@ impl RecipeBody {
     }
 }
 
+/// A single field of a `multipart/form-data` body
+#[derive(Clone, Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[serde(untagged)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum FormPart {
+    /// The common case: just the value. If the value is a single `file()`
+    /// call, the file is streamed from disk, and its name and `Content-Type`
+    /// are sent with the part.
+    Value(Template),
+    /// A value with explicit part headers, which take precedence over those
+    /// derived from a file
+    Detailed {
+        value: Template,
+        /// `Content-Type` of the part, e.g. `image/png`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        content_type: Option<Template>,
+        /// Filename sent in the part's `Content-Disposition` header
+        #[serde(skip_serializing_if = "Option::is_none")]
+        filename: Option<Template>,
+    },
+}
+
+impl FormPart {
+    /// Get the template for the part's content
+    pub fn value(&self) -> &Template {
+        match self {
+            Self::Value(value) | Self::Detailed { value, .. } => value,
+        }
+    }
+
+    /// Get the template for the part's `Content-Type`, if given
+    pub fn content_type(&self) -> Option<&Template> {
+        match self {
+            Self::Value(_) => None,
+            Self::Detailed { content_type, .. } => content_type.as_ref(),
+        }
+    }
+
+    /// Get the template for the part's filename, if given
+    pub fn filename(&self) -> Option<&Template> {
+        match self {
+            Self::Value(_) => None,
+            Self::Detailed { filename, .. } => filename.as_ref(),
+        }
+    }
+}
+
+impl From<Template> for FormPart {
+    fn from(value: Template) -> Self {
+        Self::Value(value)
+    }
+}
+
+#[cfg(any(test, feature = "test"))]
+impl From<&'static str> for FormPart {
+    fn from(value: &'static str) -> Self {
+        Self::Value(value.into())
+    }
+}
+
+/// A GraphQL operation, sent as the body of a request
+#[derive(Debug, Serialize)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GraphQlBody {
+    /// GraphQL document, e.g. `query { user(id: 1) { name } }`
+    pub query: Template,
+    /// Values for the variables declared in the query. Strings are parsed as
+    /// templates, just like a `json` body.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variables: Option<JsonTemplate>,
+    /// Operation to execute, if the document defines more than one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<Template>,
+}
+
 #[cfg(any(test, feature = "test"))]
 impl From<&'static str> for RecipeBody {
     fn from(template: &'static str) -> Self {
@@ -577,6 +1676,15 @@ pub enum CollectionError {
     #[error("No collection file found in `{}` or its ancestors", path.display())]
     NoFile { path: PathBuf },
 
+    /// Error fetching a collection from a URL or git repository
+    #[cfg(feature = "native")]
+    #[error("Error fetching collection from `{location}`")]
+    Remote {
+        location: String,
+        #[source]
+        error: Box<super::RemoteError>,
+    },
+
     /// Error parsing/deserializing the YAML
     #[error(transparent)]
     Yaml(YamlCollectionError),
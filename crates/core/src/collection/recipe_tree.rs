@@ -1,6 +1,8 @@
 //! Recipe/folder tree structure
 
-use crate::collection::{Folder, HasId, Recipe, RecipeId};
+use crate::collection::{
+    Authentication, AuthenticationSetting, Folder, HasId, Recipe, RecipeId,
+};
 use derive_more::From;
 use indexmap::{IndexMap, map::Values};
 use itertools::Itertools;
@@ -118,6 +120,41 @@ impl RecipeTree {
         })
     }
 
+    /// Get the authentication that applies to a recipe or folder. A node that
+    /// doesn't define authentication inherits it from its closest ancestor
+    /// folder that does. Return `None` if no authentication applies, either
+    /// because none is defined or because it's disabled with `!none`.
+    pub fn authentication(
+        &self,
+        id: &RecipeId,
+    ) -> Option<ResolvedAuthentication<'_>> {
+        let lookup_key = self.nodes_by_id.get(id)?;
+        // Walk up from the node to the root, and stop at the first setting
+        for (i, node_id) in lookup_key.0.iter().enumerate().rev() {
+            let node = self.get(node_id)?;
+            let setting = match node {
+                RecipeNode::Folder(folder) => folder.authentication.as_ref(),
+                RecipeNode::Recipe(recipe) => recipe.authentication.as_ref(),
+            };
+            match setting {
+                None => {}
+                Some(AuthenticationSetting::Disabled) => return None,
+                Some(AuthenticationSetting::Defined(authentication)) => {
+                    let is_own = i == lookup_key.0.len() - 1;
+                    return Some(ResolvedAuthentication {
+                        authentication,
+                        inherited_from: if is_own {
+                            None
+                        } else {
+                            node.folder()
+                        },
+                    });
+                }
+            }
+        }
+        None
+    }
+
     /// Get all **recipe** IDs in the tree. Useful for printing a list to the
     /// user
     pub fn recipe_ids(&self) -> impl Iterator<Item = &RecipeId> {
@@ -130,25 +167,43 @@ impl RecipeTree {
     /// search. Each yielded item will include the lookup key to retrieve
     /// that item.
     pub fn iter(&self) -> impl Iterator<Item = (RecipeLookupKey, &RecipeNode)> {
+        self.iter_expanded(|_| true)
+    }
+
+    /// Get a flat iterator over nodes in the tree, using depth first search,
+    /// but only descend into folders for which `is_expanded` returns `true`.
+    /// Collapsed folders are yielded, but their descendants are never visited,
+    /// so the cost is proportional to the number of visible nodes rather than
+    /// the size of the tree.
+    pub fn iter_expanded<'a>(
+        &'a self,
+        is_expanded: impl FnMut(&Folder) -> bool + 'a,
+    ) -> impl Iterator<Item = (RecipeLookupKey, &'a RecipeNode)> {
         // We'll lean on the inner IndexMap iterator for the hard work. We just
         // keep a stack of all the branches we're iterating over
 
-        struct Iter<'a> {
+        struct Iter<'a, F> {
             stack: Vec<Values<'a, RecipeId, RecipeNode>>,
             path: Vec<&'a RecipeId>,
+            is_expanded: F,
         }
 
-        impl<'a> Iterator for Iter<'a> {
+        impl<'a, F: FnMut(&Folder) -> bool> Iterator for Iter<'a, F> {
             type Item = (RecipeLookupKey, &'a RecipeNode);
 
             fn next(&mut self) -> Option<Self::Item> {
                 while let Some(iter) = self.stack.last_mut() {
                     match iter.next() {
                         Some(node @ RecipeNode::Folder(folder)) => {
+                            let mut lookup_key: RecipeLookupKey =
+                                (&self.path).into();
+                            lookup_key.0.push(folder.id.clone());
                             // Go down this branch next
-                            self.path.push(&folder.id);
-                            self.stack.push(folder.children.values());
-                            return Some(((&self.path).into(), node));
+                            if (self.is_expanded)(folder) {
+                                self.path.push(&folder.id);
+                                self.stack.push(folder.children.values());
+                            }
+                            return Some((lookup_key, node));
                         }
                         Some(node @ RecipeNode::Recipe(recipe)) => {
                             let mut lookup_key: RecipeLookupKey =
@@ -170,6 +225,7 @@ impl RecipeTree {
         Iter {
             stack: vec![self.tree.values()],
             path: Vec::new(),
+            is_expanded,
         }
     }
 }
@@ -192,6 +248,17 @@ impl From<IndexMap<RecipeId, RecipeNode>> for RecipeTree {
     }
 }
 
+/// Authentication that applies to a recipe or folder, and where it came from.
+/// See [RecipeTree::authentication].
+#[derive(Debug)]
+#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
+pub struct ResolvedAuthentication<'a> {
+    pub authentication: &'a Authentication,
+    /// Folder the authentication is defined on. `None` if it's defined on the
+    /// node itself.
+    pub inherited_from: Option<&'a Folder>,
+}
+
 /// A path into the recipe tree. Every constructed path is assumed to be valid,
 /// which must be enforced by the creator.
 #[derive(Clone, Debug, From, Eq, Hash, PartialEq)]
@@ -397,6 +464,106 @@ mod tests {
         );
     }
 
+    /// Iteration skips the contents of collapsed folders, but still yields the
+    /// folders themselves
+    #[rstest]
+    fn test_iter_expanded(tree: IndexMap<RecipeId, RecipeNode>) {
+        let tree = RecipeTree::new(tree).unwrap();
+        let expected: Vec<(RecipeLookupKey, RecipeId)> = vec![
+            (["r1"].into(), id("r1")),
+            (["f1"].into(), id("f1")),
+            (["f1", "f2"].into(), id("f2")),
+            (["f1", "r3"].into(), id("r3")),
+            (["r4"].into(), id("r4")),
+        ];
+
+        let mut visited = vec![];
+        assert_eq!(
+            tree.iter_expanded(|folder| {
+                visited.push(folder.id.clone());
+                folder.id != id("f2")
+            })
+            .map(|(key, node)| (key, node.id().clone()))
+            .collect_vec(),
+            expected
+        );
+        // Each folder is only checked once
+        assert_eq!(visited, [id("f1"), id("f2")]);
+    }
+
+    /// Authentication is inherited from the closest folder that defines it,
+    /// and `!none` stops inheritance
+    #[rstest]
+    #[case::own("r2", Some(("r2", None)))]
+    #[case::folder_own("f1", Some(("f1", None)))]
+    #[case::inherited("r1", Some(("f1", Some("f1"))))]
+    #[case::inherited_nested("r4", Some(("f1", Some("f1"))))]
+    #[case::disabled("r3", None)]
+    #[case::disabled_folder("r5", None)]
+    #[case::root("r6", None)]
+    #[case::unknown("unknown", None)]
+    fn test_authentication(
+        #[case] recipe_id: &str,
+        #[case] expected: Option<(&str, Option<&str>)>,
+    ) {
+        let bearer = |token: &'static str| {
+            Some(AuthenticationSetting::Defined(Authentication::Bearer {
+                token: token.into(),
+            }))
+        };
+        let recipe = |id: &str, authentication| -> RecipeNode {
+            Recipe {
+                id: id.into(),
+                authentication,
+                ..Recipe::factory(())
+            }
+            .into()
+        };
+        let tree = RecipeTree::new(by_id([
+            Folder {
+                id: id("f1"),
+                authentication: bearer("f1"),
+                children: by_id([
+                    recipe("r1", None),
+                    recipe("r2", bearer("r2")),
+                    recipe("r3", Some(AuthenticationSetting::Disabled)),
+                    Folder {
+                        id: id("f2"),
+                        children: by_id([recipe("r4", None)]),
+                        ..Folder::factory(())
+                    }
+                    .into(),
+                    Folder {
+                        id: id("f3"),
+                        authentication: Some(AuthenticationSetting::Disabled),
+                        children: by_id([recipe("r5", None)]),
+                        ..Folder::factory(())
+                    }
+                    .into(),
+                ]),
+                ..Folder::factory(())
+            }
+            .into(),
+            recipe("r6", None),
+        ]))
+        .unwrap();
+
+        let actual = tree.authentication(&id(recipe_id)).map(|resolved| {
+            let Authentication::Bearer { token } = resolved.authentication
+            else {
+                panic!("Unexpected authentication {resolved:?}")
+            };
+            (
+                token.display().into_owned(),
+                resolved.inherited_from.map(|folder| folder.id.to_string()),
+            )
+        });
+        let expected = expected.map(|(token, folder)| {
+            (token.to_owned(), folder.map(str::to_owned))
+        });
+        assert_eq!(actual, expected);
+    }
+
     /// Deserializing with a duplicate ID anywhere in the tree should fail
     #[rstest]
     #[case::recipe(
@@ -0,0 +1,720 @@
+//! Collections fetched from a URL or git repository, so a team can share one
+//! canonical collection. The remote file is cached in the data directory, and
+//! everything else (loading, watching, request history) works on the cached
+//! copy. The cache is revalidated each time the collection file is resolved:
+//! with `If-None-Match`/`If-Modified-Since` for URLs, and a shallow fetch for
+//! git. If that fails, e.g. because we're offline, the cached copy is used and
+//! marked as stale.
+
+use crate::{collection::CollectionFile, util::encode_hex};
+use aws_lc_rs::digest::{SHA256, digest};
+use chrono::{DateTime, Utc};
+use md5::{Digest, Md5};
+use reqwest::{
+    StatusCode, Url,
+    header::{self, HeaderMap},
+};
+use serde::{Deserialize, Serialize};
+use slumber_util::{ResultTraced, paths};
+use std::{
+    fmt::{self, Display},
+    fs, io,
+    path::{Component, Path, PathBuf},
+    process::{Command, Output},
+    thread,
+};
+use thiserror::Error;
+use tokio::runtime;
+use tracing::{info, warn};
+
+/// Prefix for a git source. Everything after it is passed to `git fetch`
+const GIT_PREFIX: &str = "git+";
+/// File to load from a git repository, if the source doesn't specify one
+const DEFAULT_GIT_PATH: &str = "slumber.yml";
+/// File name for an HTTP source whose URL path doesn't end in a file name
+const DEFAULT_HTTP_FILE: &str = "slumber.yml";
+/// Cache metadata file, stored next to the cached collection
+const METADATA_FILE: &str = "remote.json";
+
+/// A collection that lives somewhere other than the local file system
+#[derive(Clone, Debug, PartialEq)]
+pub enum RemoteSource {
+    /// `http://` or `https://` URL to a collection file
+    Http(Url),
+    /// A file in a git repository:
+    /// `git+<repository>[#<ref>][:<path>]`
+    Git {
+        /// Anything that `git fetch` accepts, e.g. an HTTPS or SSH URL
+        repository: String,
+        /// Branch, tag, or commit. `None` for the default branch
+        reference: Option<String>,
+        /// Path to the collection file within the repository
+        path: PathBuf,
+    },
+}
+
+impl RemoteSource {
+    /// Parse a collection path as a remote source. Return `None` if it's a
+    /// local path.
+    pub fn parse(path: &Path) -> Option<Result<Self, RemoteError>> {
+        let source = path.to_str()?;
+        if let Some(git) = source.strip_prefix(GIT_PREFIX) {
+            Some(Self::parse_git(source, git))
+        } else if source.starts_with("http://")
+            || source.starts_with("https://")
+        {
+            Some(Url::parse(source).map(Self::Http).map_err(|error| {
+                RemoteError::InvalidSource {
+                    input: source.to_owned(),
+                    message: error.to_string(),
+                }
+            }))
+        } else {
+            None
+        }
+    }
+
+    /// Parse the part of a git source after `git+`
+    fn parse_git(source: &str, git: &str) -> Result<Self, RemoteError> {
+        let (repository, fragment) = git.split_once('#').unwrap_or((git, ""));
+        if repository.is_empty() {
+            return Err(RemoteError::InvalidSource {
+                input: source.to_owned(),
+                message: "Repository is empty".into(),
+            });
+        }
+        let (reference, path) =
+            fragment.split_once(':').unwrap_or((fragment, ""));
+        // These are passed to git as arguments, so a leading dash would make
+        // them options
+        if repository.starts_with('-') || reference.starts_with('-') {
+            return Err(RemoteError::InvalidSource {
+                input: source.to_owned(),
+                message: "Repository and ref cannot start with `-`".into(),
+            });
+        }
+        // The path is joined onto the checkout, so it can't leave it
+        if Path::new(path).components().any(|component| {
+            !matches!(component, Component::Normal(_) | Component::CurDir)
+        }) {
+            return Err(RemoteError::InvalidSource {
+                input: source.to_owned(),
+                message: "Path must be relative, within the repository".into(),
+            });
+        }
+        Ok(Self::Git {
+            repository: repository.to_owned(),
+            reference: (!reference.is_empty()).then(|| reference.to_owned()),
+            path: if path.is_empty() {
+                DEFAULT_GIT_PATH.into()
+            } else {
+                path.into()
+            },
+        })
+    }
+
+    /// Fetch the collection, or revalidate the cached copy. If the fetch fails
+    /// but there's a cached copy, return that instead, marked as stale.
+    pub fn fetch(&self) -> Result<RemoteCollection, RemoteError> {
+        // This has to be resolved on the calling thread, because tests
+        // override the data directory per-thread
+        let cache_dir = self.cache_dir();
+        let path = self.cached_path(&cache_dir);
+        let metadata = CacheMetadata::load(&cache_dir);
+
+        let result = match self {
+            Self::Http(url) => fetch_http(url, &path, &metadata),
+            Self::Git {
+                repository,
+                reference,
+                ..
+            } => fetch_git(
+                repository,
+                reference.as_deref(),
+                &cache_dir.join("repo"),
+                &path,
+            ),
+        };
+
+        let status = match (result, metadata.fetched_at) {
+            (Ok(new_metadata), _) => {
+                info!(source = %self, ?path, "Fetched remote collection");
+                let metadata = CacheMetadata {
+                    fetched_at: Some(Utc::now()),
+                    ..new_metadata.unwrap_or(metadata)
+                };
+                // Failing to save means we'll do a full fetch next time. Not
+                // worth failing over
+                let _ = metadata.save(&cache_dir).traced();
+                RemoteStatus {
+                    source: self.to_string(),
+                    fetched_at: metadata.fetched_at.unwrap_or_default(),
+                    error: None,
+                    fingerprint: self.fingerprint(&cache_dir, &path),
+                }
+            }
+            // Offline or server is down; fall back to the last good copy
+            (Err(error), Some(fetched_at)) if path.is_file() => {
+                warn!(
+                    source = %self,
+                    error = &error as &dyn std::error::Error,
+                    "Error fetching remote collection; using cached copy"
+                );
+                RemoteStatus {
+                    source: self.to_string(),
+                    fetched_at,
+                    error: Some(error.to_string()),
+                    fingerprint: self.fingerprint(&cache_dir, &path),
+                }
+            }
+            (Err(error), _) => return Err(error),
+        };
+        Ok(RemoteCollection { path, status })
+    }
+
+    /// Identify the content of the cached copy, so trust can be pinned to it.
+    /// For git this is the checked out commit, which covers every file in the
+    /// repository (e.g. for `$ref`s). For HTTP it's a hash of the file.
+    fn fingerprint(&self, cache_dir: &Path, path: &Path) -> Option<String> {
+        match self {
+            Self::Http(_) => fs::read(path)
+                .map(|content| encode_hex(digest(&SHA256, &content).as_ref()))
+                .traced()
+                .ok(),
+            Self::Git { .. } => {
+                git(&cache_dir.join("repo"), &["rev-parse", "HEAD"])
+                    .traced()
+                    .ok()
+            }
+        }
+    }
+
+    /// Directory to cache this source in. Each source gets its own directory,
+    /// named after a hash of the source
+    fn cache_dir(&self) -> PathBuf {
+        let hash = encode_hex(&Md5::digest(self.to_string().as_bytes()));
+        paths::data_directory().join("remote").join(hash)
+    }
+
+    /// Path to the cached collection file. For HTTP, the file name is kept
+    /// from the URL so the extension is the same
+    fn cached_path(&self, cache_dir: &Path) -> PathBuf {
+        match self {
+            Self::Http(url) => {
+                let file_name = url
+                    .path_segments()
+                    .and_then(|mut segments| segments.next_back())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or(DEFAULT_HTTP_FILE);
+                cache_dir.join(file_name)
+            }
+            // The whole repo is checked out, so `$ref`s to other files in the
+            // repo work
+            Self::Git { path, .. } => cache_dir.join("repo").join(path),
+        }
+    }
+}
+
+impl Display for RemoteSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(url) => write!(f, "{url}"),
+            Self::Git {
+                repository,
+                reference,
+                path,
+            } => write!(
+                f,
+                "{GIT_PREFIX}{repository}#{}:{}",
+                reference.as_deref().unwrap_or_default(),
+                path.display()
+            ),
+        }
+    }
+}
+
+/// A fetched remote collection
+#[derive(Debug)]
+pub struct RemoteCollection {
+    /// Path to the cached collection file
+    pub path: PathBuf,
+    pub status: RemoteStatus,
+}
+
+/// Freshness of a cached remote collection
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteStatus {
+    /// Where the collection was fetched from
+    pub source: String,
+    /// When the cached copy was last fetched or revalidated
+    pub fetched_at: DateTime<Utc>,
+    /// If the latest fetch failed, this is the error. The cached copy may be
+    /// out of date
+    pub error: Option<String>,
+    /// Identifies the content of the cached copy. Trust is pinned to this, so
+    /// a trusted collection is sandboxed again when it changes upstream.
+    /// `None` if it couldn't be determined, in which case the collection
+    /// can't be trusted.
+    pub fingerprint: Option<String>,
+}
+
+impl RemoteStatus {
+    /// Is the cached copy possibly out of date?
+    pub fn is_stale(&self) -> bool {
+        self.error.is_some()
+    }
+}
+
+impl CollectionFile {
+    /// If this collection was fetched from a remote source, get the status of
+    /// the cached copy
+    pub fn remote(&self) -> Option<&RemoteStatus> {
+        self.remote.as_ref()
+    }
+}
+
+/// Error fetching a remote collection
+#[derive(Debug, Error)]
+pub enum RemoteError {
+    #[error("Invalid collection source `{input}`: {message}")]
+    InvalidSource { input: String, message: String },
+
+    #[error("Error fetching `{url}`")]
+    Http {
+        url: Url,
+        #[source]
+        error: reqwest::Error,
+    },
+
+    #[error("Fetching `{url}` failed with status {status}")]
+    Status { url: Url, status: StatusCode },
+
+    #[error("Error writing to `{}`", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+
+    /// `git` couldn't be run at all, probably because it's not installed
+    #[error("Error running `git`")]
+    GitIo(#[source] io::Error),
+
+    /// `git` exited with an error, e.g. because the ref doesn't exist
+    #[error("`git {command}` failed: {stderr}")]
+    Git { command: String, stderr: String },
+
+    #[error("`{}` does not exist in the repository", path.display())]
+    GitNoFile { path: PathBuf },
+}
+
+/// Data about the cached copy, used to revalidate it
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheMetadata {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fetched_at: Option<DateTime<Utc>>,
+}
+
+impl CacheMetadata {
+    /// Load metadata from a cache directory. If it's missing or invalid,
+    /// return the default, which forces a full fetch
+    fn load(cache_dir: &Path) -> Self {
+        fs::read(cache_dir.join(METADATA_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, cache_dir: &Path) -> Result<(), RemoteError> {
+        let path = cache_dir.join(METADATA_FILE);
+        let json = serde_json::to_vec(self).expect("Metadata is serializable");
+        fs::write(&path, json).map_err(|error| RemoteError::Io { path, error })
+    }
+}
+
+/// Download a collection over HTTP, unless the server says the cached copy is
+/// still good. Return updated metadata if the file was downloaded, or `None`
+/// if the cached copy is current.
+fn fetch_http(
+    url: &Url,
+    path: &Path,
+    metadata: &CacheMetadata,
+) -> Result<Option<CacheMetadata>, RemoteError> {
+    // Only revalidate if the file is actually there. Otherwise a 304 would
+    // leave us with nothing
+    let mut headers = HeaderMap::new();
+    if path.is_file() {
+        if let Some(etag) =
+            metadata.etag.as_deref().and_then(|etag| etag.parse().ok())
+        {
+            headers.insert(header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = metadata
+            .last_modified
+            .as_deref()
+            .and_then(|last_modified| last_modified.parse().ok())
+        {
+            headers.insert(header::IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    // This is called while resolving the collection file, which is
+    // synchronous. We may already be in an async runtime, so we can't block
+    // on this thread. Run the request in a runtime on its own thread instead
+    let http_error = |error| RemoteError::Http {
+        url: url.clone(),
+        error,
+    };
+    let request_url = url.clone();
+    let response = thread::spawn(move || {
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Error building HTTP runtime");
+        runtime.block_on(async move {
+            let response = reqwest::Client::new()
+                .get(request_url)
+                .headers(headers)
+                .send()
+                .await?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.bytes().await?;
+            Ok((status, headers, body))
+        })
+    })
+    .join()
+    .expect("HTTP fetch panicked")
+    .map_err(http_error)?;
+
+    let (status, headers, body) = response;
+    if status == StatusCode::NOT_MODIFIED {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(RemoteError::Status {
+            url: url.clone(),
+            status,
+        });
+    }
+
+    let io_error = |error| RemoteError::Io {
+        path: path.to_owned(),
+        error,
+    };
+    paths::create_parent(path).map_err(io_error)?;
+    fs::write(path, &body).map_err(io_error)?;
+    let header = |name| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    Ok(Some(CacheMetadata {
+        etag: header(header::ETAG),
+        last_modified: header(header::LAST_MODIFIED),
+        fetched_at: None,
+    }))
+}
+
+/// Fetch a single commit from a git repository into the cache, and check it
+/// out. This works the same for the first fetch and updates, and for
+/// branches, tags, and commit hashes.
+fn fetch_git(
+    repository: &str,
+    reference: Option<&str>,
+    repo_dir: &Path,
+    path: &Path,
+) -> Result<Option<CacheMetadata>, RemoteError> {
+    fs::create_dir_all(repo_dir).map_err(|error| RemoteError::Io {
+        path: repo_dir.to_owned(),
+        error,
+    })?;
+    git(repo_dir, &["init", "--quiet"])?;
+    git(
+        repo_dir,
+        &[
+            "fetch",
+            "--quiet",
+            "--depth=1",
+            // Everything after this is positional, even with a leading dash
+            "--",
+            repository,
+            reference.unwrap_or("HEAD"),
+        ],
+    )?;
+    git(repo_dir, &["checkout", "--quiet", "--force", "FETCH_HEAD"])?;
+
+    if path.is_file() {
+        Ok(None)
+    } else {
+        Err(RemoteError::GitNoFile {
+            path: path.strip_prefix(repo_dir).unwrap_or(path).to_owned(),
+        })
+    }
+}
+
+/// Run a git subcommand in a directory, returning its trimmed stdout
+fn git(directory: &Path, args: &[&str]) -> Result<String, RemoteError> {
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = Command::new("git")
+        .args(args)
+        .current_dir(directory)
+        .output()
+        .map_err(RemoteError::GitIo)?;
+    if status.success() {
+        Ok(String::from_utf8_lossy(&stdout).trim().to_owned())
+    } else {
+        Err(RemoteError::Git {
+            command: args.join(" "),
+            stderr: String::from_utf8_lossy(&stderr).trim().to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use rstest::rstest;
+    use slumber_util::{
+        DataDir, Factory, TempDir, assert_err, data_dir, temp_dir,
+    };
+    use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
+
+    const COLLECTION: &str = "requests:\n  get:\n    url: http://localhost\n";
+
+    #[rstest]
+    #[case::local("slumber.yml", None)]
+    #[case::local_dir("./collections/", None)]
+    #[case::http(
+        "https://example.com/team/slumber.yml",
+        Some(RemoteSource::Http(
+            "https://example.com/team/slumber.yml".parse().unwrap()
+        )),
+    )]
+    #[case::git_default(
+        "git+https://github.com/team/api.git",
+        Some(RemoteSource::Git {
+            repository: "https://github.com/team/api.git".into(),
+            reference: None,
+            path: "slumber.yml".into(),
+        }),
+    )]
+    #[case::git_ref(
+        "git+ssh://git@github.com/team/api.git#v2",
+        Some(RemoteSource::Git {
+            repository: "ssh://git@github.com/team/api.git".into(),
+            reference: Some("v2".into()),
+            path: "slumber.yml".into(),
+        }),
+    )]
+    #[case::git_path(
+        "git+https://github.com/team/api.git#:api/slumber.yml",
+        Some(RemoteSource::Git {
+            repository: "https://github.com/team/api.git".into(),
+            reference: None,
+            path: "api/slumber.yml".into(),
+        }),
+    )]
+    #[case::git_ref_path(
+        "git+https://github.com/team/api.git#main:api/slumber.yml",
+        Some(RemoteSource::Git {
+            repository: "https://github.com/team/api.git".into(),
+            reference: Some("main".into()),
+            path: "api/slumber.yml".into(),
+        }),
+    )]
+    fn test_parse(#[case] path: &str, #[case] expected: Option<RemoteSource>) {
+        let actual = RemoteSource::parse(Path::new(path)).transpose().unwrap();
+        assert_eq!(actual, expected);
+    }
+
+    #[rstest]
+    #[case::git_empty("git+", "Repository is empty")]
+    #[case::git_option_repository(
+        "git+--upload-pack=touch /tmp/pwned",
+        "cannot start with `-`"
+    )]
+    #[case::git_option_ref(
+        "git+https://github.com/team/api.git#--upload-pack=evil",
+        "cannot start with `-`"
+    )]
+    #[case::git_path_parent(
+        "git+https://github.com/team/api.git#main:../../secret.yml",
+        "within the repository"
+    )]
+    #[case::git_path_absolute(
+        "git+https://github.com/team/api.git#main:/etc/passwd",
+        "within the repository"
+    )]
+    #[case::http("https://", "empty host")]
+    fn test_parse_error(#[case] path: &str, #[case] expected_error: &str) {
+        assert_err(
+            RemoteSource::parse(Path::new(path)).unwrap(),
+            expected_error,
+        );
+    }
+
+    /// The first fetch downloads the file. Later fetches revalidate with the
+    /// ETag, and keep the cached copy if the server says it's current
+    #[rstest]
+    #[tokio::test]
+    async fn test_fetch_http(_data_dir: DataDir) {
+        let server = MockServer::start().await;
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/slumber.yml"))
+            .and(matchers::header("If-None-Match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/slumber.yml"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", "\"v1\"")
+                    .set_body_string(COLLECTION),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let source = RemoteSource::Http(
+            format!("{}/slumber.yml", server.uri()).parse().unwrap(),
+        );
+
+        let first = source.fetch().unwrap();
+        assert_eq!(fs::read_to_string(&first.path).unwrap(), COLLECTION);
+        assert!(!first.status.is_stale());
+
+        let second = source.fetch().unwrap();
+        assert_eq!(second.path, first.path);
+        assert_eq!(fs::read_to_string(&second.path).unwrap(), COLLECTION);
+        assert!(!second.status.is_stale());
+        assert!(first.status.fingerprint.is_some());
+        assert_eq!(second.status.fingerprint, first.status.fingerprint);
+    }
+
+    /// Trusting a remote collection only trusts the content that was fetched.
+    /// If it changes upstream, it's sandboxed again until re-trusted
+    #[rstest]
+    #[tokio::test]
+    async fn test_trust_content_changed(_data_dir: DataDir) {
+        let server = MockServer::start().await;
+        let mount = async |body: &str| {
+            server.reset().await;
+            Mock::given(matchers::method("GET"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                .mount(&server)
+                .await;
+        };
+        let location = PathBuf::from(format!("{}/slumber.yml", server.uri()));
+        let database = Database::factory(());
+
+        mount(COLLECTION).await;
+        let file = CollectionFile::new(Some(location.clone())).unwrap();
+        let collection_database =
+            database.clone().into_collection(&file).unwrap();
+        assert!(!file.is_trusted(None, &collection_database));
+        file.trust(&collection_database).unwrap();
+        assert!(file.is_trusted(None, &collection_database));
+        assert!(!file.is_trust_outdated(&collection_database));
+
+        // Same content is still trusted
+        let file = CollectionFile::new(Some(location.clone())).unwrap();
+        assert!(file.is_trusted(None, &collection_database));
+
+        // Upstream changes; it's sandboxed again
+        mount("requests: {}\n").await;
+        let file = CollectionFile::new(Some(location.clone())).unwrap();
+        let collection_database =
+            database.clone().into_collection(&file).unwrap();
+        assert!(!file.is_trusted(None, &collection_database));
+        assert!(file.is_trust_outdated(&collection_database));
+
+        file.trust(&collection_database).unwrap();
+        assert!(file.is_trusted(None, &collection_database));
+    }
+
+    /// If the server fails, the cached copy is used and marked stale. With no
+    /// cached copy, it's an error
+    #[rstest]
+    #[tokio::test]
+    async fn test_fetch_http_error(_data_dir: DataDir) {
+        let server = MockServer::start().await;
+        Mock::given(matchers::method("GET"))
+            .and(matchers::path("/slumber.yml"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(COLLECTION),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(matchers::method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        let url: Url = format!("{}/slumber.yml", server.uri()).parse().unwrap();
+        let source = RemoteSource::Http(url.clone());
+        let fetched = source.fetch().unwrap();
+
+        let stale = source.fetch().unwrap();
+        assert_eq!(stale.path, fetched.path);
+        assert!(stale.status.is_stale());
+        assert_eq!(stale.status.fetched_at, fetched.status.fetched_at);
+
+        let source = RemoteSource::Http(url.join("other.yml").unwrap());
+        assert_err(source.fetch(), "failed with status 503");
+    }
+
+    /// A file is fetched from a git repository by branch, and updated on the
+    /// next fetch
+    #[rstest]
+    fn test_fetch_git(_data_dir: DataDir, temp_dir: TempDir) {
+        let run = |args: &[&str]| git(&temp_dir, args).unwrap();
+        run(&["init", "--quiet", "--initial-branch=main"]);
+        run(&["config", "user.name", "Test"]);
+        run(&["config", "user.email", "test@example.com"]);
+        fs::create_dir(temp_dir.join("api")).unwrap();
+        fs::write(temp_dir.join("api/slumber.yml"), COLLECTION).unwrap();
+        run(&["add", "."]);
+        run(&["commit", "--quiet", "-m", "first"]);
+
+        let source = RemoteSource::parse(Path::new(&format!(
+            "git+file://{}#main:api/slumber.yml",
+            temp_dir.display()
+        )))
+        .unwrap()
+        .unwrap();
+        let fetched = source.fetch().unwrap();
+        assert_eq!(fs::read_to_string(&fetched.path).unwrap(), COLLECTION);
+        // Fingerprint is the fetched commit
+        assert_eq!(
+            fetched.status.fingerprint,
+            Some(run(&["rev-parse", "HEAD"]))
+        );
+
+        fs::write(temp_dir.join("api/slumber.yml"), "requests: {}\n").unwrap();
+        run(&["commit", "--quiet", "--all", "-m", "second"]);
+        let fetched = source.fetch().unwrap();
+        assert_eq!(
+            fs::read_to_string(&fetched.path).unwrap(),
+            "requests: {}\n"
+        );
+        assert_eq!(
+            fetched.status.fingerprint,
+            Some(run(&["rev-parse", "HEAD"]))
+        );
+
+        let source = RemoteSource::Git {
+            repository: format!("file://{}", temp_dir.display()),
+            reference: Some("main".into()),
+            path: "missing.yml".into(),
+        };
+        assert_err(source.fetch(), "`missing.yml` does not exist");
+    }
+}
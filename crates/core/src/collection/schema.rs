@@ -2,13 +2,12 @@
 
 use crate::{
     collection::{
-        Authentication, Collection, Folder, Profile, Recipe, RecipeBody,
-        RecipeTree,
+        Authentication, Collection, Folder, HttpMethod, Profile, Recipe,
+        RecipeBody, RecipeTree,
     },
-    http::HttpMethod,
     test_util::by_id,
 };
-use indexmap::indexmap;
+use indexmap::{IndexMap, indexmap};
 use serde_json::json;
 use slumber_util::{Factory, yaml::SourceLocation};
 
@@ -17,6 +16,8 @@ impl Collection {
     pub fn example() -> Self {
         Self {
             name: Some("Example".into()),
+            template_delimiters: None,
+            variables: indexmap! {"api_version".into() => "v2".into()},
             profiles: by_id([
                 Profile::example(),
                 Profile {
@@ -27,14 +28,25 @@ impl Collection {
                     data: indexmap! {
                         "host".into() => "https://myfishes.fish".into()
                     },
+                    chaos: None,
+                    certificate: None,
+                    proxy: None,
+                    timeout: None,
+                    throttle: None,
+                    protocol: None,
+                    socket: None,
+                    resolve: IndexMap::new(),
+                    pinning: None,
                 },
             ]),
+            toggles: IndexMap::new(),
             recipes: RecipeTree::new(by_id([
                 Recipe::example().into(),
                 Folder {
                     id: "my_folder".into(),
                     location: SourceLocation::default(),
                     name: Some("My Folder".to_owned()),
+                    authentication: None,
                     children: by_id([
                         Recipe::factory("recipe1").into(),
                         Recipe::factory("recipe2").into(),
@@ -58,6 +70,15 @@ impl Profile {
             data: indexmap! {
                 "host".into() => "http://localhost:8000".into()
             },
+            chaos: None,
+            certificate: None,
+            proxy: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            resolve: IndexMap::new(),
+            pinning: None,
         }
     }
 }
@@ -69,6 +90,12 @@ impl Folder {
             id: "my_folder".into(),
             location: SourceLocation::default(),
             name: Some("My Folder".into()),
+            authentication: Some(
+                Authentication::Bearer {
+                    token: "{{ token }}".into(),
+                }
+                .into(),
+            ),
             children: by_id([Recipe::example().into()]),
         }
     }
@@ -92,12 +119,16 @@ impl Recipe {
                 .try_into()
                 .unwrap(),
             )),
-            authentication: Some(Authentication::Basic {
-                username: "mememe".into(),
-                password: Some(
-                    "{{ prompt(message='Password', sensitive=true) }}".into(),
-                ),
-            }),
+            authentication: Some(
+                Authentication::Basic {
+                    username: "mememe".into(),
+                    password: Some(
+                        "{{ prompt(message='Password', sensitive=true) }}"
+                            .into(),
+                    ),
+                }
+                .into(),
+            ),
             query: indexmap! {
                 "submit".into() => "true".into(),
                 "param_with_multiple_values".into() => ["value1", "value2"].into(),
@@ -105,6 +136,24 @@ impl Recipe {
             headers: indexmap! {
                 "Accept".into() => "application/json".into(),
             },
+            user_agent: None,
+            transport: None,
+            certificate: None,
+            retry: None,
+            redirect: None,
+            timeout: None,
+            throttle: None,
+            compression: None,
+            protocol: None,
+            socket: None,
+            reauthenticate: false,
+            capture: indexmap! {},
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: None,
+            params: IndexMap::new(),
         }
     }
 }
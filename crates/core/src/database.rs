@@ -9,11 +9,12 @@ mod tests;
 use crate::{
     collection::{Collection, CollectionFile, ProfileId, RecipeId},
     database::convert::{CollectionPath, SqlWrap},
-    http::{Exchange, ExchangeSummary, RequestId},
+    http::{Cookie, Exchange, ExchangeSummary, OAuth2Token, RequestId},
+    render::AuditEvent,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use rusqlite::{Connection, OptionalExtension, named_params};
-use slumber_util::{ResultTraced, paths};
+use slumber_util::{ResultTraced, STARTUP_TARGET, paths};
 use std::{
     borrow::Cow,
     fmt::Debug,
@@ -23,7 +24,7 @@ use std::{
     sync::{Arc, Mutex},
 };
 use thiserror::Error;
-use tracing::{debug, info, trace};
+use tracing::{debug, info, info_span, trace};
 use uuid::Uuid;
 
 /// Maximum number of commands to store in history **per collection**. When we
@@ -73,6 +74,8 @@ impl Database {
     fn from_path(path: &Path) -> Result<Self, DatabaseError> {
         paths::create_parent(path).map_err(DatabaseError::Directory)?;
 
+        let _span = info_span!(target: STARTUP_TARGET, "Load database", ?path)
+            .entered();
         info!(?path, "Loading database");
         let mut connection = Connection::open(path)
             .and_then(|conn| {
@@ -193,6 +196,9 @@ impl Database {
             "DELETE FROM requests_v2 WHERE collection_id = :id",
             "DELETE FROM ui_state_v2 WHERE collection_id = :id",
             "DELETE FROM commands WHERE collection_id = :id",
+            "DELETE FROM audit_log WHERE collection_id = :id",
+            "DELETE FROM oauth2_tokens WHERE collection_id = :id",
+            "DELETE FROM cookies WHERE collection_id = :id",
             "DELETE FROM collections WHERE id = :id",
         ];
 
@@ -254,6 +260,31 @@ impl Database {
         )
         .map_err(DatabaseError::add_context("Merging table `commands`"))
         .traced()?;
+        tx.execute(
+            "UPDATE audit_log SET collection_id = :target
+                WHERE collection_id = :source",
+            named_params! {":source": source, ":target": target},
+        )
+        .map_err(DatabaseError::add_context("Merging table `audit_log`"))
+        .traced()?;
+        tx.execute(
+            // Overwrite tokens. They're interchangeable so it doesn't matter
+            // which one we keep
+            "UPDATE OR REPLACE oauth2_tokens SET collection_id = :target
+                WHERE collection_id = :source",
+            named_params! {":source": source, ":target": target},
+        )
+        .map_err(DatabaseError::add_context("Merging table `oauth2_tokens`"))
+        .traced()?;
+        tx.execute(
+            // Overwrite cookies. The source is newer from the user's
+            // perspective, since it's the collection they were just using
+            "UPDATE OR REPLACE cookies SET collection_id = :target
+                WHERE collection_id = :source",
+            named_params! {":source": source, ":target": target},
+        )
+        .map_err(DatabaseError::add_context("Merging table `cookies`"))
+        .traced()?;
 
         // Delete the collection now that nothing is referencing it
         tx.execute(
@@ -267,6 +298,33 @@ impl Database {
         Ok(())
     }
 
+    /// Mark a collection as trusted or untrusted. Trusted collections are
+    /// never sandboxed, regardless of where they're located. For a remote
+    /// collection, `fingerprint` identifies the content being trusted; see
+    /// [CollectionDatabase::is_trusted].
+    pub fn set_collection_trusted(
+        &self,
+        collection: CollectionId,
+        trusted: bool,
+        fingerprint: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        info!(%collection, trusted, ?fingerprint, "Setting collection trust");
+        self.connection()
+            .execute(
+                "UPDATE collections
+                SET trusted = :trusted, trusted_fingerprint = :fingerprint
+                WHERE id = :id",
+                named_params! {
+                    ":id": collection,
+                    ":trusted": trusted,
+                    ":fingerprint": fingerprint,
+                },
+            )
+            .map_err(DatabaseError::add_context("Updating collection trust"))
+            .traced()?;
+        Ok(())
+    }
+
     /// Get all requests for all collections
     pub fn get_all_requests(
         &self,
@@ -339,6 +397,7 @@ impl Database {
 
         Ok(CollectionDatabase {
             collection_id,
+            session: None,
             database: self,
         })
     }
@@ -350,10 +409,34 @@ impl Database {
 #[derive(Clone, Debug)]
 pub struct CollectionDatabase {
     collection_id: CollectionId,
+    /// Named session that cookies and OAuth2 tokens are scoped to. `None` for
+    /// the default session
+    session: Option<String>,
     database: Database,
 }
 
 impl CollectionDatabase {
+    /// Scope cookies and OAuth2 tokens to a named session. Each session has
+    /// its own cookie jar and token cache, so multiple identities can be used
+    /// against the same API side by side. Everything else, including request
+    /// history, is shared between sessions. `None` is the default session.
+    #[must_use]
+    pub fn with_session(self, session: Option<String>) -> Self {
+        Self { session, ..self }
+    }
+
+    /// Get the name of the session that cookies and OAuth2 tokens are scoped
+    /// to. `None` for the default session
+    pub fn session(&self) -> Option<&str> {
+        self.session.as_deref()
+    }
+
+    /// Session key for cookie and OAuth2 token queries. The default session
+    /// is stored as an empty string
+    fn session_key(&self) -> &str {
+        self.session().unwrap_or_default()
+    }
+
     /// Get metadata for the collection associated with this DB handle
     pub fn metadata(&self) -> Result<CollectionMetadata, DatabaseError> {
         self.database
@@ -392,6 +475,46 @@ impl CollectionDatabase {
             .traced();
     }
 
+    /// Has the user explicitly trusted this collection? If a fingerprint is
+    /// given, the collection is only trusted if it was trusted with that same
+    /// fingerprint. This pins trust in a remote collection to the content the
+    /// user actually saw.
+    pub fn is_trusted(
+        &self,
+        fingerprint: Option<&str>,
+    ) -> Result<bool, DatabaseError> {
+        let (trusted, trusted_fingerprint): (bool, Option<String>) = self
+            .database
+            .connection()
+            .query_row(
+                "SELECT trusted, trusted_fingerprint FROM collections
+                WHERE id = :id",
+                named_params! {":id": self.collection_id},
+                |row| {
+                    Ok((row.get("trusted")?, row.get("trusted_fingerprint")?))
+                },
+            )
+            .map_err(DatabaseError::add_context("Querying collection trust"))
+            .traced()?;
+        Ok(trusted
+            && fingerprint
+                .is_none_or(|f| trusted_fingerprint.as_deref() == Some(f)))
+    }
+
+    /// Mark this collection as trusted or untrusted. See
+    /// [Database::set_collection_trusted]
+    pub fn set_trusted(
+        &self,
+        trusted: bool,
+        fingerprint: Option<&str>,
+    ) -> Result<(), DatabaseError> {
+        self.database.set_collection_trusted(
+            self.collection_id,
+            trusted,
+            fingerprint,
+        )
+    }
+
     /// Get a request by ID, or `None` if it does not exist in history.
     pub fn get_request(
         &self,
@@ -461,15 +584,19 @@ impl CollectionDatabase {
             .traced()
     }
 
-    /// Get a list of all requests for a profile+recipe combo
+    /// Get a list of requests for a profile+recipe combo, sorted by start
+    /// time descending. If `limit` is given, return at most that many of the
+    /// most recent requests.
     pub fn get_recipe_requests(
         &self,
         profile_filter: ProfileFilter,
         recipe_id: &RecipeId,
+        limit: Option<usize>,
     ) -> Result<Vec<ExchangeSummary>, DatabaseError> {
         trace!(
             profile_id = ?profile_filter,
             recipe_id = %recipe_id,
+            ?limit,
             "Fetching requests from database"
         );
         self.database
@@ -485,7 +612,8 @@ impl CollectionDatabase {
                 WHERE collection_id = :collection_id
                     AND (:ignore_profile_id OR profile_id IS :profile_id)
                     AND recipe_id = :recipe_id
-                ORDER BY start_time DESC",
+                ORDER BY start_time DESC, id DESC
+                LIMIT :limit",
             )?
             .query_map(
                 named_params! {
@@ -493,6 +621,68 @@ impl CollectionDatabase {
                     ":ignore_profile_id": profile_filter == ProfileFilter::All,
                     ":profile_id": profile_filter,
                     ":recipe_id": recipe_id,
+                    // A negative limit means no limit
+                    ":limit": limit.map_or(-1, |limit| {
+                        i64::try_from(limit).unwrap_or(i64::MAX)
+                    }),
+                },
+                |row| row.try_into(),
+            )
+            .map_err(DatabaseError::add_context(
+                "Querying request history from database",
+            ))
+            .traced()?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(DatabaseError::add_context("Extracting request history"))
+    }
+
+    /// Get the next page of requests for a profile+recipe combo, after a page
+    /// from [get_recipe_requests](Self::get_recipe_requests). `last` is the
+    /// `(start_time, id)` of the final request on the previous page; at most
+    /// `limit` requests that sort after it are returned, in the same order.
+    ///
+    /// This seeks straight to the page with the `(collection_id, recipe_id,
+    /// start_time)` index, so a deep page costs the same as the first.
+    pub fn get_recipe_requests_after(
+        &self,
+        profile_filter: ProfileFilter,
+        recipe_id: &RecipeId,
+        last: (DateTime<Utc>, RequestId),
+        limit: usize,
+    ) -> Result<Vec<ExchangeSummary>, DatabaseError> {
+        let (last_start_time, last_id) = last;
+        trace!(
+            profile_id = ?profile_filter,
+            recipe_id = %recipe_id,
+            %last_id,
+            limit,
+            "Fetching next page of requests from database"
+        );
+        self.database
+            .connection()
+            .prepare(
+                // The first start_time condition is the index range. The
+                // second breaks ties between requests that started at the same
+                // time, the same way ORDER BY does
+                "SELECT id, recipe_id, profile_id, start_time, end_time,
+                    status_code FROM requests_v2
+                WHERE collection_id = :collection_id
+                    AND (:ignore_profile_id OR profile_id IS :profile_id)
+                    AND recipe_id = :recipe_id
+                    AND start_time <= :start_time
+                    AND (start_time < :start_time OR id < :id)
+                ORDER BY start_time DESC, id DESC
+                LIMIT :limit",
+            )?
+            .query_map(
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":ignore_profile_id": profile_filter == ProfileFilter::All,
+                    ":profile_id": profile_filter,
+                    ":recipe_id": recipe_id,
+                    ":start_time": last_start_time,
+                    ":id": last_id,
+                    ":limit": i64::try_from(limit).unwrap_or(i64::MAX),
                 },
                 |row| row.try_into(),
             )
@@ -562,7 +752,10 @@ impl CollectionDatabase {
                     request_body,
                     status_code,
                     response_headers,
-                    response_body
+                    response_body,
+                    redirects,
+                    response_http_version,
+                    response_certificate_fingerprint
                 )
                 VALUES (
                     :id,
@@ -578,7 +771,10 @@ impl CollectionDatabase {
                     :request_body,
                     :status_code,
                     :response_headers,
-                    :response_body
+                    :response_body,
+                    :redirects,
+                    :response_http_version,
+                    :response_certificate_fingerprint
                 )",
                 named_params! {
                     ":id": exchange.id,
@@ -597,6 +793,10 @@ impl CollectionDatabase {
                     ":status_code": exchange.response.status.as_u16(),
                     ":response_headers": SqlWrap(&exchange.response.headers),
                     ":response_body": exchange.response.body.bytes().deref(),
+                    ":redirects": SqlWrap(exchange.response.redirects.as_slice()),
+                    ":response_http_version": exchange.response.http_version,
+                    ":response_certificate_fingerprint":
+                        &exchange.response.certificate_fingerprint,
                 },
             )
             .map_err({
@@ -864,6 +1064,225 @@ impl CollectionDatabase {
             .traced()
     }
 
+    /// Record an external effect of a render in the audit log
+    pub fn insert_audit_event(
+        &self,
+        event: &AuditEvent,
+    ) -> Result<(), DatabaseError> {
+        trace!(?event, "Recording audit event");
+        self.database
+            .connection()
+            .execute(
+                "INSERT INTO audit_log
+                    (collection_id, time, source, detail, preview)
+                VALUES (:collection_id, :time, :source, :detail, :preview)",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":time": event.time,
+                    ":source": SqlWrap(event.source),
+                    ":detail": event.detail,
+                    ":preview": event.preview,
+                },
+            )
+            .map_err(DatabaseError::add_context("Inserting audit event"))
+            .traced()?;
+        Ok(())
+    }
+
+    /// Get events from the audit log, most recent first. If `limit` is given,
+    /// return at most that many events.
+    pub fn get_audit_events(
+        &self,
+        limit: Option<u32>,
+    ) -> Result<Vec<AuditEvent>, DatabaseError> {
+        self.database
+            .connection()
+            .prepare(
+                // LIMIT -1 is unbounded
+                "SELECT time, source, detail, preview FROM audit_log
+                WHERE collection_id = :collection_id
+                ORDER BY id DESC
+                LIMIT :limit",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(
+                    named_params! {
+                        ":collection_id": self.collection_id,
+                        ":limit": limit.map_or(-1, i64::from),
+                    },
+                    |row| row.try_into(),
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .map_err(DatabaseError::add_context("Querying audit log"))
+            .traced()
+    }
+
+    /// Get the OAuth2 token stored under a key
+    pub fn get_oauth2_token(
+        &self,
+        key: &str,
+    ) -> Result<Option<OAuth2Token>, DatabaseError> {
+        trace!(key, "Fetching OAuth2 token");
+        self.database
+            .connection()
+            .query_row(
+                "SELECT access_token, refresh_token, expires_at
+                FROM oauth2_tokens
+                WHERE collection_id = :collection_id AND session = :session
+                    AND key = :key",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":session": self.session_key(),
+                    ":key": key,
+                },
+                |row| row.try_into(),
+            )
+            .optional()
+            .map_err(DatabaseError::add_context("Querying OAuth2 token"))
+            .traced()
+    }
+
+    /// Store an OAuth2 token under a key, replacing any previous token
+    pub fn set_oauth2_token(
+        &self,
+        key: &str,
+        token: &OAuth2Token,
+    ) -> Result<(), DatabaseError> {
+        trace!(key, expires_at = ?token.expires_at, "Setting OAuth2 token");
+        self.database
+            .connection()
+            .execute(
+                "INSERT INTO oauth2_tokens
+                (collection_id, session, key, access_token, refresh_token,
+                    expires_at)
+                VALUES
+                (:collection_id, :session, :key, :access_token, :refresh_token,
+                    :expires_at)
+                ON CONFLICT DO UPDATE SET
+                    access_token = excluded.access_token,
+                    refresh_token = excluded.refresh_token,
+                    expires_at = excluded.expires_at",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":session": self.session_key(),
+                    ":key": key,
+                    ":access_token": token.access_token,
+                    ":refresh_token": token.refresh_token,
+                    ":expires_at": token.expires_at,
+                },
+            )
+            .map_err(DatabaseError::add_context("Setting OAuth2 token"))
+            .traced()?;
+        Ok(())
+    }
+
+    /// Get every cookie in the jar, including expired ones
+    pub fn get_cookies(&self) -> Result<Vec<Cookie>, DatabaseError> {
+        trace!("Fetching cookies");
+        self.database
+            .connection()
+            .prepare(
+                "SELECT * FROM cookies
+                WHERE collection_id = :collection_id AND session = :session",
+            )
+            .and_then(|mut stmt| {
+                stmt.query_map(
+                    named_params! {
+                        ":collection_id": self.collection_id,
+                        ":session": self.session_key(),
+                    },
+                    |row| row.try_into(),
+                )?
+                .collect::<rusqlite::Result<Vec<_>>>()
+            })
+            .map_err(DatabaseError::add_context("Querying cookies"))
+            .traced()
+    }
+
+    /// Store a cookie, replacing any cookie with the same domain, path, and
+    /// name
+    pub fn set_cookie(&self, cookie: &Cookie) -> Result<(), DatabaseError> {
+        trace!(domain = cookie.domain, name = cookie.name, "Setting cookie");
+        self.database
+            .connection()
+            .execute(
+                "INSERT INTO cookies
+                (collection_id, session, domain, path, name, value, host_only,
+                    expires, secure, http_only)
+                VALUES
+                (:collection_id, :session, :domain, :path, :name, :value,
+                    :host_only, :expires, :secure, :http_only)
+                ON CONFLICT DO UPDATE SET
+                    value = excluded.value,
+                    host_only = excluded.host_only,
+                    expires = excluded.expires,
+                    secure = excluded.secure,
+                    http_only = excluded.http_only",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":session": self.session_key(),
+                    ":domain": cookie.domain,
+                    ":path": cookie.path,
+                    ":name": cookie.name,
+                    ":value": cookie.value,
+                    ":host_only": cookie.host_only,
+                    ":expires": cookie.expires,
+                    ":secure": cookie.secure,
+                    ":http_only": cookie.http_only,
+                },
+            )
+            .map_err(DatabaseError::add_context("Setting cookie"))
+            .traced()?;
+        Ok(())
+    }
+
+    /// Delete the cookie with the given domain, path, and name. Return
+    /// whether it existed.
+    pub fn delete_cookie(
+        &self,
+        domain: &str,
+        path: &str,
+        name: &str,
+    ) -> Result<bool, DatabaseError> {
+        trace!(domain, path, name, "Deleting cookie");
+        let deleted = self
+            .database
+            .connection()
+            .execute(
+                "DELETE FROM cookies WHERE collection_id = :collection_id
+                    AND session = :session AND domain = :domain
+                    AND path = :path AND name = :name",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":session": self.session_key(),
+                    ":domain": domain,
+                    ":path": path,
+                    ":name": name,
+                },
+            )
+            .map_err(DatabaseError::add_context("Deleting cookie"))
+            .traced()?;
+        Ok(deleted > 0)
+    }
+
+    /// Delete every cookie in the jar. Return how many were deleted
+    pub fn clear_cookies(&self) -> Result<usize, DatabaseError> {
+        trace!("Clearing cookies");
+        self.database
+            .connection()
+            .execute(
+                "DELETE FROM cookies
+                WHERE collection_id = :collection_id AND session = :session",
+                named_params! {
+                    ":collection_id": self.collection_id,
+                    ":session": self.session_key(),
+                },
+            )
+            .map_err(DatabaseError::add_context("Clearing cookies"))
+            .traced()
+    }
+
     /// Get the unique ID of this collection
     pub fn collection_id(&self) -> CollectionId {
         self.collection_id
@@ -873,9 +1292,17 @@ impl CollectionDatabase {
 /// A unique ID for a collection. This is generated when the collection is
 /// inserted into the DB.
 #[derive(
-    Copy, Clone, Debug, derive_more::Display, derive_more::FromStr, PartialEq,
+    Copy,
+    Clone,
+    Debug,
+    derive_more::Display,
+    derive_more::FromStr,
+    PartialEq,
+    serde::Serialize,
+    serde::Deserialize,
 )]
 #[cfg_attr(any(test, feature = "test"), derive(Eq, Hash))]
+#[serde(transparent)]
 pub struct CollectionId(Uuid);
 
 impl CollectionId {
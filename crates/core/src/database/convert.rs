@@ -1,14 +1,15 @@
 //! Implementations to convert between Rust types and SQL data
 
 use crate::{
-    collection::{ProfileId, RecipeId},
+    collection::{HttpMethod, ProfileId, RecipeId},
     database::{
         CollectionId, CollectionMetadata, DatabaseError, ProfileFilter,
     },
     http::{
-        Exchange, ExchangeSummary, HttpMethod, HttpVersion, RequestId,
-        RequestRecord, ResponseRecord,
+        Cookie, Exchange, ExchangeSummary, HttpVersion, OAuth2Token, Redirect,
+        RequestId, RequestRecord, ResponseRecord,
     },
+    render::AuditEvent,
 };
 use bytes::Bytes;
 use core::str;
@@ -21,10 +22,11 @@ use rusqlite::{
     Row, ToSql,
     types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef},
 };
+use slumber_config::TemplateSource;
 use slumber_util::{ResultTraced, paths};
 use std::{
     env,
-    fmt::Debug,
+    fmt::{Debug, Write},
     ops::Deref,
     path::{Path, PathBuf},
     str::Utf8Error,
@@ -231,6 +233,52 @@ impl<'a, 'b> TryFrom<&'a Row<'b>> for CollectionMetadata {
     }
 }
 
+/// Convert from `SELECT time, source, detail, preview FROM audit_log`
+impl<'a, 'b> TryFrom<&'a Row<'b>> for AuditEvent {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'b>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            time: row.get("time")?,
+            source: row.get::<_, SqlWrap<TemplateSource>>("source")?.0,
+            detail: row.get("detail")?,
+            preview: row.get("preview")?,
+        })
+    }
+}
+
+/// Convert from `SELECT access_token, refresh_token, expires_at FROM
+/// oauth2_tokens`
+impl<'a, 'b> TryFrom<&'a Row<'b>> for OAuth2Token {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'b>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            access_token: row.get("access_token")?,
+            refresh_token: row.get("refresh_token")?,
+            expires_at: row.get("expires_at")?,
+        })
+    }
+}
+
+/// Convert from `SELECT * FROM cookies`
+impl<'a, 'b> TryFrom<&'a Row<'b>> for Cookie {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &'a Row<'b>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            name: row.get("name")?,
+            value: row.get("value")?,
+            domain: row.get("domain")?,
+            host_only: row.get("host_only")?,
+            path: row.get("path")?,
+            expires: row.get("expires")?,
+            secure: row.get("secure")?,
+            http_only: row.get("http_only")?,
+        })
+    }
+}
+
 /// Convert from `SELECT * FROM requests_v2`
 impl<'a, 'b> TryFrom<&'a Row<'b>> for Exchange {
     type Error = rusqlite::Error;
@@ -256,11 +304,15 @@ impl<'a, 'b> TryFrom<&'a Row<'b>> for Exchange {
             }),
             response: Arc::new(ResponseRecord {
                 id,
+                http_version: row.get("response_http_version")?,
+                certificate_fingerprint: row
+                    .get("response_certificate_fingerprint")?,
                 status: row.get::<_, SqlWrap<StatusCode>>("status_code")?.0,
                 headers: row
                     .get::<_, SqlWrap<HeaderMap>>("response_headers")?
                     .0,
                 body: row.get::<_, SqlWrap<Bytes>>("response_body")?.0.into(),
+                redirects: row.get::<_, SqlWrap<Vec<Redirect>>>("redirects")?.0,
             }),
         })
     }
@@ -292,6 +344,18 @@ impl FromSql for SqlWrap<Url> {
     }
 }
 
+impl ToSql for SqlWrap<TemplateSource> {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(self.0.to_string().into())
+    }
+}
+
+impl FromSql for SqlWrap<TemplateSource> {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        value.as_str()?.parse().map(Self).map_err(error_other)
+    }
+}
+
 impl FromSql for SqlWrap<Bytes> {
     fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
         // Clone is necessary because the bytes live in sqlite FFI land
@@ -386,6 +450,50 @@ impl FromSql for SqlWrap<HeaderMap> {
     }
 }
 
+// Serialize a redirect chain as one `<status> <location>` line per redirect.
+// URLs can't contain spaces or newlines, so both are safe delimiters
+
+impl ToSql for SqlWrap<&[Redirect]> {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let mut text = String::new();
+        for redirect in self.0 {
+            let _ = writeln!(
+                text,
+                "{} {}",
+                redirect.status.as_u16(),
+                redirect.location
+            );
+        }
+        Ok(text.into())
+    }
+}
+
+impl FromSql for SqlWrap<Vec<Redirect>> {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        #[derive(Debug, Error)]
+        #[error("Invalid redirect `{0}`")]
+        struct RedirectParseError(String);
+
+        let parse_line = |line: &str| {
+            let (status, location) = line.split_once(' ')?;
+            Some(Redirect {
+                status: StatusCode::from_bytes(status.as_bytes()).ok()?,
+                location: location.parse().ok()?,
+            })
+        };
+        value
+            .as_str()?
+            .lines()
+            .map(|line| {
+                parse_line(line).ok_or_else(|| {
+                    error_other(RedirectParseError(line.to_owned()))
+                })
+            })
+            .collect::<FromSqlResult<_>>()
+            .map(Self)
+    }
+}
+
 impl ToSql for ProfileFilter<'_> {
     fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
         match self {
@@ -114,6 +114,185 @@ pub fn migrations() -> Migrations<'static> {
             )",
         )
         .down("DROP TABLE IF EXISTS commands"),
+        // History lookups always filter by collection+recipe and sort by start
+        // time. Without this, large histories require a full table scan
+        M::up(
+            "CREATE INDEX requests_v2_recipe_start_time
+            ON requests_v2 (collection_id, recipe_id, start_time DESC)",
+        )
+        .down("DROP INDEX IF EXISTS requests_v2_recipe_start_time"),
+        // Collections outside the trusted directories are sandboxed unless
+        // the user has explicitly trusted them
+        M::up(
+            "ALTER TABLE collections ADD COLUMN \
+            trusted BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .down("ALTER TABLE collections DROP COLUMN trusted"),
+        M::up(
+            // Every external effect of a render (command, file read, etc.).
+            // Only written if the audit log is enabled in the config
+            "CREATE TABLE audit_log (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                collection_id   UUID NOT NULL,
+                time            TEXT NOT NULL,
+                source          TEXT NOT NULL,
+                detail          TEXT NOT NULL,
+                FOREIGN KEY(collection_id) REFERENCES collections(id)
+            )",
+        )
+        .down("DROP TABLE IF EXISTS audit_log"),
+        M::up(
+            // OAuth2 access tokens, so they can be reused across sessions. The
+            // key identifies the provider+client+scope that issued the token
+            "CREATE TABLE oauth2_tokens (
+                collection_id   UUID NOT NULL,
+                key             TEXT NOT NULL,
+                access_token    TEXT NOT NULL,
+                refresh_token   TEXT,
+                expires_at      TEXT,
+
+                PRIMARY KEY (collection_id, key),
+                FOREIGN KEY(collection_id) REFERENCES collections(id)
+            )",
+        )
+        .down("DROP TABLE IF EXISTS oauth2_tokens"),
+        // Redirects followed on the way to the response, one per line
+        M::up(
+            "ALTER TABLE requests_v2 ADD COLUMN \
+            redirects TEXT NOT NULL DEFAULT ''",
+        )
+        .down("ALTER TABLE requests_v2 DROP COLUMN redirects"),
+        M::up(
+            // The version negotiated for the response. Before this, requests
+            // were always sent with the client's default negotiation, which
+            // reqwest records as HTTP/1.1
+            "ALTER TABLE requests_v2 ADD COLUMN \
+            response_http_version TEXT NOT NULL DEFAULT 'HTTP/1.1'",
+        )
+        .down("ALTER TABLE requests_v2 DROP COLUMN response_http_version"),
+        // SHA-256 fingerprint of the server's TLS certificate
+        M::up(
+            "ALTER TABLE requests_v2 ADD COLUMN \
+            response_certificate_fingerprint TEXT",
+        )
+        .down(
+            "ALTER TABLE requests_v2 DROP COLUMN \
+            response_certificate_fingerprint",
+        ),
+        M::up(
+            // Cookie jar. A cookie is identified by its domain, path, and
+            // name; setting the same three again replaces it
+            "CREATE TABLE cookies (
+                collection_id   UUID NOT NULL,
+                domain          TEXT NOT NULL,
+                path            TEXT NOT NULL,
+                name            TEXT NOT NULL,
+                value           TEXT NOT NULL,
+                host_only       BOOLEAN NOT NULL,
+                expires         TEXT,
+                secure          BOOLEAN NOT NULL,
+                http_only       BOOLEAN NOT NULL,
+
+                PRIMARY KEY (collection_id, domain, path, name),
+                FOREIGN KEY(collection_id) REFERENCES collections(id)
+            )",
+        )
+        .down("DROP TABLE IF EXISTS cookies"),
+        // Effects of template previews are logged too, but tagged so they can
+        // be told apart from user-initiated requests
+        M::up(
+            "ALTER TABLE audit_log ADD COLUMN \
+            preview BOOLEAN NOT NULL DEFAULT FALSE",
+        )
+        .down("ALTER TABLE audit_log DROP COLUMN preview"),
+        // Trust in a remote collection is pinned to the content that was
+        // trusted, so it's sandboxed again when that content changes upstream
+        M::up("ALTER TABLE collections ADD COLUMN trusted_fingerprint TEXT")
+            .down("ALTER TABLE collections DROP COLUMN trusted_fingerprint"),
+        // Named sessions, each with their own cookies and OAuth2 tokens. The
+        // default session is ''. Sqlite can't change a primary key in place,
+        // so the tables are rebuilt
+        M::up(
+            "ALTER TABLE cookies RENAME TO cookies_old;
+            CREATE TABLE cookies (
+                collection_id   UUID NOT NULL,
+                session         TEXT NOT NULL DEFAULT '',
+                domain          TEXT NOT NULL,
+                path            TEXT NOT NULL,
+                name            TEXT NOT NULL,
+                value           TEXT NOT NULL,
+                host_only       BOOLEAN NOT NULL,
+                expires         TEXT,
+                secure          BOOLEAN NOT NULL,
+                http_only       BOOLEAN NOT NULL,
+
+                PRIMARY KEY (collection_id, session, domain, path, name),
+                FOREIGN KEY(collection_id) REFERENCES collections(id)
+            );
+            INSERT INTO cookies (collection_id, domain, path, name, value,
+                host_only, expires, secure, http_only)
+            SELECT collection_id, domain, path, name, value, host_only,
+                expires, secure, http_only
+            FROM cookies_old;
+            DROP TABLE cookies_old;
+
+            ALTER TABLE oauth2_tokens RENAME TO oauth2_tokens_old;
+            CREATE TABLE oauth2_tokens (
+                collection_id   UUID NOT NULL,
+                session         TEXT NOT NULL DEFAULT '',
+                key             TEXT NOT NULL,
+                access_token    TEXT NOT NULL,
+                refresh_token   TEXT,
+                expires_at      TEXT,
+
+                PRIMARY KEY (collection_id, session, key),
+                FOREIGN KEY(collection_id) REFERENCES collections(id)
+            );
+            INSERT INTO oauth2_tokens (collection_id, key, access_token,
+                refresh_token, expires_at)
+            SELECT collection_id, key, access_token, refresh_token, expires_at
+            FROM oauth2_tokens_old;
+            DROP TABLE oauth2_tokens_old;",
+        )
+        // Only the default session survives a rollback
+        .down(
+            "ALTER TABLE cookies RENAME TO cookies_old;
+            CREATE TABLE cookies (
+                collection_id   UUID NOT NULL,
+                domain          TEXT NOT NULL,
+                path            TEXT NOT NULL,
+                name            TEXT NOT NULL,
+                value           TEXT NOT NULL,
+                host_only       BOOLEAN NOT NULL,
+                expires         TEXT,
+                secure          BOOLEAN NOT NULL,
+                http_only       BOOLEAN NOT NULL,
+
+                PRIMARY KEY (collection_id, domain, path, name),
+                FOREIGN KEY(collection_id) REFERENCES collections(id)
+            );
+            INSERT INTO cookies
+            SELECT collection_id, domain, path, name, value, host_only,
+                expires, secure, http_only
+            FROM cookies_old WHERE session = '';
+            DROP TABLE cookies_old;
+
+            ALTER TABLE oauth2_tokens RENAME TO oauth2_tokens_old;
+            CREATE TABLE oauth2_tokens (
+                collection_id   UUID NOT NULL,
+                key             TEXT NOT NULL,
+                access_token    TEXT NOT NULL,
+                refresh_token   TEXT,
+                expires_at      TEXT,
+
+                PRIMARY KEY (collection_id, key),
+                FOREIGN KEY(collection_id) REFERENCES collections(id)
+            );
+            INSERT INTO oauth2_tokens
+            SELECT collection_id, key, access_token, refresh_token, expires_at
+            FROM oauth2_tokens_old WHERE session = '';
+            DROP TABLE oauth2_tokens_old;",
+        ),
     ])
 }
 
@@ -185,4 +364,59 @@ mod tests {
             .unwrap();
         assert_eq!(request_count, 0);
     }
+
+    /// Existing cookies and tokens move into the default session, and only
+    /// the default session survives a rollback
+    #[test]
+    fn test_migrate_sessions() {
+        let mut connection = Connection::open_in_memory().unwrap();
+        let migrations = migrations();
+        let count = |connection: &Connection, table: &str| {
+            connection
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {table}"),
+                    [],
+                    |row| row.get::<_, u32>(0),
+                )
+                .unwrap()
+        };
+        let latest =
+            migrations.pending_migrations(&connection).unwrap() as usize;
+        migrations.to_version(&mut connection, latest - 1).unwrap();
+        connection
+            .execute_batch(
+                "INSERT INTO collections (id, path)
+                VALUES ('00000000-0000-0000-0000-000000000000', 'slumber.yml');
+                INSERT INTO cookies VALUES (
+                    '00000000-0000-0000-0000-000000000000', 'example.com',
+                    '/', 'session', 'abc', TRUE, NULL, FALSE, FALSE
+                );
+                INSERT INTO oauth2_tokens VALUES (
+                    '00000000-0000-0000-0000-000000000000', 'key', 'token',
+                    NULL, NULL
+                );",
+            )
+            .unwrap();
+
+        migrations.to_latest(&mut connection).unwrap();
+        let session: String = connection
+            .query_row("SELECT session FROM cookies", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(session, "");
+        assert_eq!(count(&connection, "oauth2_tokens"), 1);
+        connection
+            .execute_batch(
+                "INSERT INTO cookies SELECT collection_id, 'alice', domain,
+                    path, name, value, host_only, expires, secure, http_only
+                FROM cookies;
+                INSERT INTO oauth2_tokens SELECT collection_id, 'alice', key,
+                    access_token, refresh_token, expires_at
+                FROM oauth2_tokens;",
+            )
+            .unwrap();
+
+        migrations.to_version(&mut connection, latest - 1).unwrap();
+        assert_eq!(count(&connection, "cookies"), 1);
+        assert_eq!(count(&connection, "oauth2_tokens"), 1);
+    }
 }
@@ -1,6 +1,8 @@
 use super::*;
+use crate::http::{Redirect, RequestRecord, ResponseRecord};
 use indexmap::IndexMap;
 use itertools::Itertools;
+use reqwest::StatusCode;
 use rstest::{fixture, rstest};
 use slumber_util::{Factory, paths::get_repo_root};
 use std::collections::HashMap;
@@ -103,6 +105,9 @@ fn test_collection_delete(collection_file: CollectionFile) {
     collection.insert_exchange(&exchange).unwrap();
     collection.set_ui(key_type, ui_key, "value1").unwrap();
     collection.insert_command("jq .").unwrap();
+    collection
+        .set_oauth2_token("token1", &oauth2_token("a"))
+        .unwrap();
 
     // Sanity checks
     assert_eq!(collection.get_all_requests().unwrap().len(), 1);
@@ -122,6 +127,7 @@ fn test_collection_delete(collection_file: CollectionFile) {
     assert_eq!(database.get_all_requests().unwrap(), []);
     assert_eq!(collection.get_ui(key_type, ui_key).unwrap(), None);
     assert_eq!(collection.get_commands("jq").unwrap(), [""; 0]);
+    assert_eq!(collection.get_oauth2_token("token1").unwrap(), None);
 }
 
 /// Merge one collection into the other. The source collection should no longer
@@ -150,11 +156,17 @@ fn test_collection_merge(
     target.insert_exchange(&exchange1).unwrap();
     target.set_ui(key_type, ui_key, "value1").unwrap();
     target.insert_command("jq .").unwrap();
+    target
+        .set_oauth2_token("token1", &oauth2_token("a"))
+        .unwrap();
     // Source
     source.insert_exchange(&exchange2).unwrap();
     source.set_ui(key_type, ui_key, "value2").unwrap();
     source.insert_command("jq .").unwrap(); // Merged
     source.insert_command("jq .data").unwrap(); // Inserted
+    source
+        .set_oauth2_token("token1", &oauth2_token("b"))
+        .unwrap();
 
     // Sanity checks
     // Target
@@ -205,6 +217,10 @@ fn test_collection_merge(
         Some("value2".into())
     );
     assert_eq!(target.get_commands("jq").unwrap(), ["jq .data", "jq ."]);
+    assert_eq!(
+        target.get_oauth2_token("token1").unwrap(),
+        Some(oauth2_token("b"))
+    );
 
     // Make sure source was deleted
     assert_eq!(
@@ -309,7 +325,11 @@ fn test_get_recipe_requests() {
             // Leave the Option here so a non-match will trigger a handy
             // assertion error
             let ids = database
-                .get_recipe_requests(profile_id.as_ref().into(), &recipe_id)
+                .get_recipe_requests(
+                    profile_id.as_ref().into(),
+                    &recipe_id,
+                    None,
+                )
                 .unwrap()
                 .into_iter()
                 .map(|exchange| exchange.id)
@@ -330,7 +350,7 @@ fn test_get_recipe_requests() {
     // Load all requests for a recipe (across all profiles)
     let recipe_id = "recipe1".into();
     let ids = database
-        .get_recipe_requests(ProfileFilter::All, &recipe_id)
+        .get_recipe_requests(ProfileFilter::All, &recipe_id, None)
         .unwrap()
         .into_iter()
         .map(|exchange| exchange.id)
@@ -346,6 +366,84 @@ fn test_get_recipe_requests() {
     assert_eq!(ids, expected_ids);
 }
 
+/// Test fetching a limited page of requests for a recipe. Should get the
+/// most recent requests
+#[test]
+fn test_get_recipe_requests_limit() {
+    let database = CollectionDatabase::factory(());
+    let recipe_id: RecipeId = "recipe1".into();
+    let mut ids = (0..5)
+        .map(|_| {
+            let exchange = Exchange::factory((None, recipe_id.clone()));
+            database.insert_exchange(&exchange).unwrap();
+            exchange.id
+        })
+        .collect_vec();
+    ids.reverse();
+
+    let get_ids = |limit| {
+        database
+            .get_recipe_requests(ProfileFilter::None, &recipe_id, limit)
+            .unwrap()
+            .into_iter()
+            .map(|exchange| exchange.id)
+            .collect_vec()
+    };
+    assert_eq!(get_ids(Some(2)), ids[..2]);
+    assert_eq!(get_ids(Some(10)), ids);
+    assert_eq!(get_ids(None), ids);
+}
+
+/// Pages of history pick up right after the last request of the previous
+/// page, including when several requests started at the same time
+#[test]
+fn test_get_recipe_requests_after() {
+    let database = CollectionDatabase::factory(());
+    let recipe_id: RecipeId = "recipe1".into();
+    let start_time = Utc::now();
+    // Three distinct start times, with two requests at each
+    for i in 0..6 {
+        let exchange = Exchange {
+            start_time: start_time + chrono::Duration::seconds(i / 2),
+            ..Exchange::factory((None, recipe_id.clone()))
+        };
+        database.insert_exchange(&exchange).unwrap();
+    }
+    // Another recipe's requests never show up
+    database
+        .insert_exchange(&Exchange::factory((None, "recipe2".into())))
+        .unwrap();
+    let expected = database
+        .get_recipe_requests(ProfileFilter::None, &recipe_id, None)
+        .unwrap()
+        .into_iter()
+        .map(|exchange| exchange.id)
+        .collect_vec();
+    assert_eq!(expected.len(), 6);
+
+    // Walk through the history in pages that split the ties
+    let mut ids = database
+        .get_recipe_requests(ProfileFilter::None, &recipe_id, Some(3))
+        .unwrap();
+    loop {
+        let last = ids.last().unwrap();
+        let page = database
+            .get_recipe_requests_after(
+                ProfileFilter::None,
+                &recipe_id,
+                (last.start_time, last.id),
+                3,
+            )
+            .unwrap();
+        if page.is_empty() {
+            break;
+        }
+        ids.extend(page);
+    }
+    let ids = ids.into_iter().map(|exchange| exchange.id).collect_vec();
+    assert_eq!(ids, expected);
+}
+
 /// Test deleting all requests for a recipe/profile combo
 #[rstest]
 #[case(ProfileFilter::All, "recipe1", 3)]
@@ -380,6 +478,36 @@ fn test_delete_request(request_db: RequestDb) {
     assert_eq!(collection2.count_requests(), 6);
 }
 
+/// The redirect chain of a response is persisted with it
+#[rstest]
+fn test_redirects(collection_file: CollectionFile) {
+    let collection = Database::factory(())
+        .into_collection(&collection_file)
+        .unwrap();
+    let id = RequestId::new();
+    let redirects = vec![
+        Redirect {
+            status: StatusCode::MOVED_PERMANENTLY,
+            location: "http://localhost/a".parse().unwrap(),
+        },
+        Redirect {
+            status: StatusCode::TEMPORARY_REDIRECT,
+            location: "https://example.com/b?c=d".parse().unwrap(),
+        },
+    ];
+    let exchange = Exchange::factory((
+        RequestRecord::factory(id),
+        ResponseRecord {
+            redirects,
+            ..ResponseRecord::factory(id)
+        },
+    ));
+    collection.insert_exchange(&exchange).unwrap();
+
+    let loaded = collection.get_request(exchange.id).unwrap().unwrap();
+    assert_eq!(loaded.response.redirects, exchange.response.redirects);
+}
+
 /// Test UI state storage and retrieval
 #[rstest]
 fn test_ui_state(
@@ -409,6 +537,120 @@ fn test_ui_state(
     );
 }
 
+/// Store and replace OAuth2 tokens. Tokens are isolated by collection
+#[rstest]
+fn test_oauth2_tokens(request_db: RequestDb) {
+    let [collection1, collection2] = request_db.collections;
+    assert_eq!(collection1.get_oauth2_token("key").unwrap(), None);
+
+    let token = OAuth2Token {
+        refresh_token: Some("refresh".into()),
+        expires_at: Some(Utc::now()),
+        ..oauth2_token("a")
+    };
+    collection1.set_oauth2_token("key", &token).unwrap();
+    assert_eq!(collection1.get_oauth2_token("key").unwrap(), Some(token));
+    assert_eq!(collection2.get_oauth2_token("key").unwrap(), None);
+
+    // Replace
+    collection1
+        .set_oauth2_token("key", &oauth2_token("b"))
+        .unwrap();
+    assert_eq!(
+        collection1.get_oauth2_token("key").unwrap(),
+        Some(oauth2_token("b"))
+    );
+}
+
+/// Store, replace, and delete cookies. Cookies are isolated by collection
+#[rstest]
+fn test_cookies(request_db: RequestDb) {
+    let [collection1, collection2] = request_db.collections;
+    let cookie = Cookie {
+        name: "session".into(),
+        value: "abc".into(),
+        domain: "example.com".into(),
+        host_only: true,
+        path: "/".into(),
+        expires: Some(Utc::now()),
+        secure: true,
+        http_only: false,
+    };
+    collection1.set_cookie(&cookie).unwrap();
+    assert_eq!(collection1.get_cookies().unwrap(), [cookie.clone()]);
+    assert_eq!(collection2.get_cookies().unwrap(), []);
+
+    // Replace
+    let replacement = Cookie {
+        value: "def".into(),
+        expires: None,
+        ..cookie.clone()
+    };
+    collection1.set_cookie(&replacement).unwrap();
+    assert_eq!(collection1.get_cookies().unwrap(), [replacement]);
+
+    assert!(
+        collection1
+            .delete_cookie("example.com", "/", "session")
+            .unwrap()
+    );
+    assert!(
+        !collection1
+            .delete_cookie("example.com", "/", "session")
+            .unwrap()
+    );
+    collection1.set_cookie(&cookie).unwrap();
+    collection2.set_cookie(&cookie).unwrap();
+    assert_eq!(collection1.clear_cookies().unwrap(), 1);
+    assert_eq!(collection1.get_cookies().unwrap(), []);
+    assert_eq!(collection2.get_cookies().unwrap(), [cookie]);
+}
+
+/// Each named session has its own cookies and OAuth2 tokens, but request
+/// history is shared
+#[rstest]
+fn test_sessions(request_db: RequestDb) {
+    let [collection, _] = request_db.collections;
+    let alice = collection.clone().with_session(Some("alice".into()));
+    let bob = collection.clone().with_session(Some("bob".into()));
+    let cookie = |value: &str| Cookie {
+        name: "session".into(),
+        value: value.into(),
+        domain: "example.com".into(),
+        host_only: true,
+        path: "/".into(),
+        expires: None,
+        secure: false,
+        http_only: false,
+    };
+    alice.set_cookie(&cookie("alice")).unwrap();
+    bob.set_cookie(&cookie("bob")).unwrap();
+    alice.set_oauth2_token("token", &oauth2_token("a")).unwrap();
+    bob.set_oauth2_token("token", &oauth2_token("b")).unwrap();
+
+    assert_eq!(alice.get_cookies().unwrap(), [cookie("alice")]);
+    assert_eq!(bob.get_cookies().unwrap(), [cookie("bob")]);
+    assert_eq!(collection.get_cookies().unwrap(), []);
+    assert_eq!(
+        alice.get_oauth2_token("token").unwrap(),
+        Some(oauth2_token("a"))
+    );
+    assert_eq!(
+        bob.get_oauth2_token("token").unwrap(),
+        Some(oauth2_token("b"))
+    );
+    assert_eq!(collection.get_oauth2_token("token").unwrap(), None);
+    assert_eq!(
+        alice.count_requests(),
+        collection.count_requests(),
+        "History should be shared"
+    );
+
+    // Clearing one session's jar leaves the others alone
+    assert_eq!(alice.clear_cookies().unwrap(), 1);
+    assert_eq!(bob.get_cookies().unwrap(), [cookie("bob")]);
+}
+
 /// Insert and query command history
 #[test]
 fn test_commands() {
@@ -473,3 +715,12 @@ fn test_commands_max_history_size(request_db: RequestDb) {
         Some("command 1")
     );
 }
+
+/// Build a token with no expiry or refresh token
+fn oauth2_token(access_token: &str) -> OAuth2Token {
+    OAuth2Token {
+        access_token: access_token.into(),
+        refresh_token: None,
+        expires_at: None,
+    }
+}
@@ -0,0 +1,205 @@
+//! Classification of errors into a small, stable set of codes. Errors are
+//! defined throughout the codebase alongside the code that produces them, but
+//! consumers (scripts calling the CLI, or a user looking at an error in the
+//! TUI) usually only care about the broad class of failure. [ErrorCode] maps
+//! any error chain onto one of those classes.
+
+use crate::{
+    collection::{CollectionError, UnknownRecipeError},
+    database::DatabaseError,
+    http::{
+        HostBlockedError, RequestBuildError, RequestError, StoredRequestError,
+        TransportError, TriggeredRequestError,
+    },
+    render::FunctionError,
+};
+use derive_more::Display;
+use serde::Serialize;
+use slumber_config::ConfigError;
+use slumber_template::RenderError;
+use std::error::Error;
+use strum::{EnumIter, IntoStaticStr};
+
+/// The class of an error. The string form of each code (e.g. `network`) is
+/// stable and can be relied on by scripts. Variants are ordered by priority:
+/// when an error chain contains errors of multiple classes, the first variant
+/// wins. For example, a template that fails because a chained request couldn't
+/// connect is a [Chain](Self::Chain) error, not a [Network](Self::Network)
+/// or [Template](Self::Template) error, because the chained request is what the
+/// user needs to look at.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Display,
+    EnumIter,
+    Eq,
+    IntoStaticStr,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+)]
+#[display("{}", self.as_str())]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ErrorCode {
+    /// A chained request, loaded via `response()` or `response_header()`,
+    /// couldn't be loaded, built, or sent
+    Chain,
+    /// The request couldn't be sent or the response couldn't be received.
+    /// This includes DNS, connection, TLS, and timeout errors, as well as
+    /// hosts blocked by `allowed_hosts`/`denied_hosts`
+    Network,
+    /// The server responded with an error (4xx/5xx) status. This is only an
+    /// error when the caller asks for error statuses to be treated as
+    /// failures, e.g. `slumber request --exit-status`
+    Http,
+    /// The response failed one or more of its recipe's `assert` checks
+    Assertion,
+    /// A template failed to render, e.g. a function call failed or a profile
+    /// field is missing
+    Template,
+    /// The local database couldn't be read or written
+    Persistence,
+    /// The collection file couldn't be found or loaded, or doesn't contain a
+    /// requested recipe
+    Collection,
+    /// The config file couldn't be loaded
+    Config,
+    /// Anything that doesn't fit in another class
+    Other,
+}
+
+impl ErrorCode {
+    /// Get the stable string form of this code
+    pub fn as_str(self) -> &'static str {
+        self.into()
+    }
+
+    /// Classify an error by walking its chain of sources. The highest-priority
+    /// class found anywhere in the chain is returned.
+    pub fn from_error(error: &(dyn Error + 'static)) -> Self {
+        let mut code = Self::Other;
+        // unstable: Use error.sources()
+        // https://github.com/rust-lang/rust/issues/58520
+        let mut next = Some(error);
+        while let Some(error) = next {
+            if let Some(error_code) = Self::classify(error) {
+                code = code.min(error_code);
+            }
+            next = error.source();
+        }
+        code
+    }
+
+    /// Get the class of a single error, ignoring its sources
+    fn classify(error: &(dyn Error + 'static)) -> Option<Self> {
+        if error.is::<TriggeredRequestError>()
+            || error.is::<StoredRequestError>()
+        {
+            Some(Self::Chain)
+        } else if let Some(error) = error.downcast_ref::<FunctionError>() {
+            match error {
+                FunctionError::RecipeUnknown { .. }
+                | FunctionError::ResponseMissing
+                | FunctionError::ResponseMissingHeader { .. }
+                | FunctionError::StoredRequest(_)
+                | FunctionError::Trigger { .. } => Some(Self::Chain),
+                _ => Some(Self::Template),
+            }
+        } else if error.is::<RequestError>()
+            || error.is::<HostBlockedError>()
+            || error.is::<TransportError>()
+            || error.is::<reqwest::Error>()
+        {
+            Some(Self::Network)
+        } else if error.is::<RenderError>() || error.is::<RequestBuildError>() {
+            Some(Self::Template)
+        } else if error.is::<DatabaseError>() {
+            Some(Self::Persistence)
+        } else if error.is::<CollectionError>()
+            || error.is::<UnknownRecipeError>()
+        {
+            Some(Self::Collection)
+        } else if error.is::<ConfigError>() {
+            Some(Self::Config)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::RequestRecord;
+    use chrono::Utc;
+    use rstest::rstest;
+    use slumber_util::Factory;
+    use std::{collections::HashSet, io, path::PathBuf, sync::Arc};
+    use strum::IntoEnumIterator;
+
+    /// Get an error from the HTTP client by connecting to a port that nothing
+    /// is listening on
+    async fn request_error() -> RequestError {
+        RequestError {
+            error: reqwest::get("http://127.0.0.1:1").await.unwrap_err().into(),
+            request: RequestRecord::factory(()).into(),
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+        }
+    }
+
+    /// Classify errors by their type
+    #[rstest]
+    #[case::other(io::Error::other("bad").into(), ErrorCode::Other)]
+    #[case::collection(
+        CollectionError::NoFile { path: PathBuf::new() }.into(),
+        ErrorCode::Collection,
+    )]
+    #[case::template(FunctionError::CommandEmpty.into(), ErrorCode::Template)]
+    #[case::chain(FunctionError::ResponseMissing.into(), ErrorCode::Chain)]
+    #[case::persistence(
+        DatabaseError::Directory(io::Error::other("bad")).into(),
+        ErrorCode::Persistence,
+    )]
+    fn test_from_error(
+        #[case] error: Box<dyn Error + Send + Sync>,
+        #[case] expected: ErrorCode,
+    ) {
+        assert_eq!(ErrorCode::from_error(&*error), expected);
+    }
+
+    /// Network errors are classified as such, unless they occurred in a
+    /// chained request
+    #[rstest]
+    #[tokio::test]
+    async fn test_network() {
+        let error = request_error().await;
+        assert_eq!(ErrorCode::from_error(&error), ErrorCode::Network);
+
+        // The network error is nested within the chain error
+        let error = FunctionError::Trigger {
+            recipe_id: "login".into(),
+            error: TriggeredRequestError::Send(Arc::new(request_error().await)),
+        };
+        assert_eq!(ErrorCode::from_error(&error), ErrorCode::Chain);
+    }
+
+    /// Codes are serialized in snake case, and the string forms are stable
+    #[test]
+    fn test_as_str() {
+        assert_eq!(ErrorCode::Network.as_str(), "network");
+        assert_eq!(ErrorCode::Persistence.to_string(), "persistence");
+        assert_eq!(
+            serde_json::to_string(&ErrorCode::Chain).unwrap(),
+            "\"chain\""
+        );
+        // Make sure we don't have duplicate codes
+        let codes: HashSet<_> =
+            ErrorCode::iter().map(ErrorCode::as_str).collect();
+        assert_eq!(codes.len(), ErrorCode::iter().count());
+    }
+}
@@ -35,38 +35,104 @@
 //! +----------+
 //! ```
 
+mod assertion;
+mod capture;
+mod certificate;
+mod challenge;
+mod chaos;
+mod compression;
 pub mod content_type;
+mod cookie;
 mod curl;
+mod download;
+pub mod graphql;
+mod grpc;
+mod hint;
+mod host_policy;
 mod models;
+mod oauth2;
+mod pinning;
+mod redirect;
+mod retry;
+pub mod sse;
 #[cfg(test)]
 mod tests;
-
+mod throttle;
+mod timeout;
+mod token;
+mod transport;
+mod websocket;
+
+pub use assertion::{AssertionFailure, AssertionResults};
+pub use capture::{CaptureError, CaptureErrorKind, CaptureStore};
+pub use challenge::ChallengeError;
+pub use chaos::ChaosError;
+pub use cookie::{Cookie, CookieJar};
+pub use download::{DownloadError, DownloadProgress};
+pub use grpc::{GrpcError, GrpcStatus};
+pub use host_policy::HostBlockedError;
 pub use models::*;
+pub use oauth2::{OAuth2Error, OAuth2Token, TokenStore};
+pub use pinning::PinViolation;
+pub use redirect::RedirectError;
+pub use retry::RetryProgress;
+pub use timeout::{TimeoutError, TimeoutPhase};
+pub use token::TOKEN_REFRESH_MARGIN;
+pub use transport::{
+    DEFAULT_TRANSPORT, ReqwestTransport, TlsConfigError, Transport,
+    TransportError,
+};
+pub use websocket::WebSocketError;
 
 use crate::{
-    collection::{Authentication, JsonTemplate, Recipe, RecipeBody},
-    http::curl::CurlBuilder,
-    render::TemplateContext,
+    collection::{
+        Authentication, FormPart, GraphQlBody, HttpMethod, HttpProtocol,
+        JsonTemplate, OAuth2, OAuth2Grant, Profile, Recipe, RecipeBody,
+        RecipeId, RedirectPolicy, RetryPolicy, Timeout, UnknownRecipeError,
+    },
+    http::{
+        certificate::{CertificatePaths, ClientCertificates},
+        challenge::ChallengeAuth,
+        chaos::ChaosState,
+        curl::CurlBuilder,
+        grpc::{GrpcCall, GrpcSchema},
+        host_policy::HostPolicy,
+        redirect::RedirectChain,
+        transport::{ClientOverrides, OverrideError},
+    },
+    render::{Auditor, SingleRenderContext, TemplateContext},
 };
 use bytes::{Bytes, BytesMut};
 use chrono::Utc;
 use futures::{
-    Future, StreamExt, TryFutureExt, TryStreamExt,
-    future::{self, OptionFuture, try_join_all},
-    stream::BoxStream,
+    Future, Stream, StreamExt, TryFutureExt, TryStreamExt,
+    future::{self, Either, OptionFuture, try_join_all},
+    stream::{self, BoxStream},
     try_join,
 };
 use indexmap::IndexMap;
+use mime::Mime;
 use reqwest::{
-    Body, Client, RequestBuilder, Response, Url,
-    header::{HeaderMap, HeaderName, HeaderValue},
+    Body, Client, Request, RequestBuilder, Response, StatusCode, Url,
+    header::{self, HeaderMap, HeaderName, HeaderValue},
     multipart::{Form, Part},
-    redirect,
 };
-use slumber_config::HttpEngineConfig;
+use slumber_config::{HttpEngineConfig, TemplateSource};
 use slumber_template::{RenderError, StreamSource, Template};
-use slumber_util::ResultTraced;
-use std::{collections::HashSet, error::Error, hash::Hash};
+use slumber_util::{ResultTraced, paths::expand_home};
+use std::{
+    collections::HashMap,
+    error::Error,
+    hash::Hash,
+    net::IpAddr,
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    pin::pin,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::fs::{self, File};
+use tokio_util::io::ReaderStream;
 use tracing::{error, info, info_span};
 
 const USER_AGENT: &str = concat!("slumber/", env!("CARGO_PKG_VERSION"));
@@ -75,54 +141,101 @@ const USER_AGENT: &str = concat!("slumber/", env!("CARGO_PKG_VERSION"));
 /// de-asyncify HTTP so it can be called in the main TUI thread. All heavy
 /// lifting will be pushed to background tasks.
 ///
+/// Requests are built with reqwest, then sent by a [Transport]. The built-in
+/// reqwest transport is always available; others can be added with
+/// [Self::with_transport].
+///
 /// This is safe and cheap to clone because reqwest's `Client` type uses `Arc`
 /// internally. [reqwest::Client]
 #[derive(Clone, Debug)]
 pub struct HttpEngine {
+    /// Client used to *build* requests. Requests are sent by the transport
     client: Client,
-    /// A client that ignores TLS errors, and the hostnames we should use it
-    /// for. If the user didn't specify any (99.9% of cases), don't bother
-    /// creating a client because it's expensive.
-    danger_client: Option<(Client, HashSet<String>)>,
+    /// Available transports, keyed by name
+    transports: HashMap<String, Arc<dyn Transport>>,
+    /// Name of the transport to use for recipes that don't specify one
+    default_transport: String,
     large_body_size: usize,
+    /// Global `User-Agent` template. Can be overridden per-recipe
+    user_agent: Option<Template>,
+    /// Hosts that requests may be sent to
+    host_policy: HostPolicy,
+    /// The built-in transport, which requests with client-level settings
+    /// (certificates and proxies) are derived from
+    reqwest_transport: ReqwestTransport,
+    /// TLS client certificates from the config
+    certificates: ClientCertificates,
+    /// Global proxy URL template. Can be overridden per-profile
+    proxy: Option<Template>,
+    /// Hosts that bypass the proxy
+    no_proxy: Vec<String>,
+    /// Error in the TLS config, returned for every request that uses the
+    /// reqwest transport. Stored so the engine can be created infallibly.
+    tls_error: Option<Arc<TlsConfigError>>,
+    /// Fault injection for profiles with `chaos` enabled
+    chaos: ChaosState,
+    /// Global retry policy. Can be overridden per-recipe
+    retry: Option<RetryPolicy>,
+    /// Follow redirects for recipes that don't have their own policy
+    follow_redirects: bool,
+    /// Store response cookies and attach them to requests
+    cookie_jar: bool,
+    /// Global time limits. Each can be overridden per-profile or per-recipe
+    timeout: Timeout,
 }
 
 impl HttpEngine {
     /// Build a new HTTP engine, which can be used for the entire program life
     pub fn new(config: &HttpEngineConfig) -> Self {
-        let make_builder = || {
-            let redirect_policy = if config.follow_redirects {
-                redirect::Policy::default()
-            } else {
-                redirect::Policy::none()
+        let host_policy = HostPolicy::new(config);
+        let (ca_certificates, tls_error) =
+            match transport::load_ca_certificates(config) {
+                Ok(certificates) => (certificates, None),
+                Err(error) => {
+                    error!(error = &error as &dyn Error, "Invalid TLS config");
+                    (Vec::new(), Some(Arc::new(error)))
+                }
             };
-
-            Client::builder()
-                .user_agent(USER_AGENT)
-                .redirect(redirect_policy)
-        };
-
-        let client = make_builder()
-            .build()
-            .expect("Error building reqwest client");
-        let danger_client = if config.ignore_certificate_hosts.is_empty() {
-            None
-        } else {
-            Some((
-                make_builder()
-                    .danger_accept_invalid_certs(true)
-                    .build()
-                    .expect("Error building reqwest client"),
-                config.ignore_certificate_hosts.iter().cloned().collect(),
-            ))
-        };
+        let reqwest_transport =
+            ReqwestTransport::new(config, &host_policy, ca_certificates);
         Self {
-            client,
-            danger_client,
+            client: reqwest_transport
+                .redirecting_client(config.follow_redirects),
+            transports: HashMap::from([(
+                DEFAULT_TRANSPORT.to_owned(),
+                Arc::new(reqwest_transport.clone()) as Arc<dyn Transport>,
+            )]),
+            default_transport: config.transport.clone(),
             large_body_size: config.large_body_size,
+            user_agent: config.user_agent.clone(),
+            host_policy,
+            reqwest_transport,
+            certificates: ClientCertificates::new(config),
+            proxy: config.proxy.clone(),
+            no_proxy: config.no_proxy.clone(),
+            tls_error,
+            chaos: ChaosState::new(),
+            retry: config.retry.as_ref().map(RetryPolicy::from),
+            follow_redirects: config.follow_redirects,
+            cookie_jar: config.cookie_jar,
+            timeout: Timeout::from(&config.timeout),
         }
     }
 
+    /// Register a [Transport] under the given name. Recipes can select it
+    /// with their `transport` field, or it can be made the default with the
+    /// `transport` config field. Registering a name that's already in use
+    /// replaces the existing transport, including the built-in one.
+    #[must_use]
+    pub fn with_transport(
+        mut self,
+        name: impl Into<String>,
+        transport: impl Transport + 'static,
+    ) -> Self {
+        self.transports.insert(name.into(), Arc::new(transport));
+        self
+    }
+
     /// Build a [RequestTicket] from a [RequestSeed]. This will render the
     /// recipe into a request. The returned ticket can then be launched.
     pub async fn build(
@@ -140,15 +253,57 @@ impl HttpEngine {
                 .entered();
 
         let future = async {
+            let mut link_recipe = None;
             let recipe =
-                context.collection.recipes.try_get_recipe(recipe_id)?;
+                get_recipe(context, recipe_id, options, &mut link_recipe)?;
+            let is_websocket = recipe.websocket.is_some() || options.websocket;
+            if is_websocket && !matches!(recipe.method, HttpMethod::Get) {
+                return Err(RequestBuildErrorKind::WebSocketMethod {
+                    method: recipe.method,
+                });
+            }
+            recipe.check_sandbox(context)?;
+            let grpc = recipe.grpc_call(context)?;
+            let transport_name = self.transport_name(recipe);
+            let mut transport = self.get_transport(recipe)?;
+
+            // Find every missing value before rendering anything, so they can
+            // all be asked for or reported at once
+            recipe
+                .render_context(context, false)
+                .preflight(recipe.templates(
+                    options,
+                    context,
+                    self.user_agent.as_ref(),
+                    self.proxy.as_ref(),
+                ))
+                .await
+                .map_err(RequestBuildErrorKind::Preflight)?;
 
             // Render everything up front so we can parallelize it
-            let (url, query, headers, authentication, body) = try_join!(
+            let (
+                url,
+                query,
+                headers,
+                user_agent,
+                proxy,
+                socket,
+                authentication,
+                messages,
+                body,
+            ) = try_join!(
                 recipe.render_url(options, context),
                 recipe.render_query(options, context),
                 recipe.render_headers(options, context),
+                recipe.render_user_agent(self.user_agent.as_ref(), context),
+                recipe.render_proxy(
+                    self.proxy.as_ref(),
+                    &self.host_policy,
+                    context,
+                ),
+                recipe.render_socket(context),
                 recipe.render_authentication(options, context),
+                recipe.render_websocket_messages(context),
                 // Body *has* to go last. Bodies are the only component that
                 // can be streamed. If a profile field is present in both the
                 // body and elsewhere, it should *never* be streamed. By
@@ -163,23 +318,230 @@ impl HttpEngine {
             // hard work of encoding query params/authorization/etc.
             // We'll just copy its homework at the end to get our
             // RequestRecord
-            let client = self.get_client(&url);
+            self.host_policy.check(&url)?;
+            if transport_name == DEFAULT_TRANSPORT
+                && let Some(error) = &self.tls_error
+            {
+                return Err(RequestBuildErrorKind::TlsConfig(Arc::clone(
+                    error,
+                )));
+            }
+            // The certificate can depend on the host, so we need the URL first
+            let certificate = self.certificates.get(recipe, context, &url);
+            if certificate.is_some() && transport_name != DEFAULT_TRANSPORT {
+                return Err(RequestBuildErrorKind::CertificateTransport {
+                    name: transport_name.to_owned(),
+                });
+            }
+            // Proxies are only supported by the built-in transport. Other
+            // transports are responsible for their own networking.
+            let proxy = proxy.filter(|_| transport_name == DEFAULT_TRANSPORT);
+            let resolve = if transport_name == DEFAULT_TRANSPORT {
+                resolve_overrides(context, &self.host_policy)?
+            } else {
+                Vec::new()
+            };
+            if let Some(socket) = &socket {
+                if transport_name != DEFAULT_TRANSPORT {
+                    return Err(RequestBuildErrorKind::SocketTransport {
+                        name: transport_name.to_owned(),
+                    });
+                }
+                if is_named_pipe(socket) {
+                    if !cfg!(windows) {
+                        return Err(
+                            RequestBuildErrorKind::NamedPipeUnsupported,
+                        );
+                    }
+                } else if !cfg!(unix) {
+                    return Err(RequestBuildErrorKind::SocketUnsupported);
+                }
+            }
+            // Recipe limits beat profile limits, which beat global limits
+            let profile_timeout = context
+                .current_profile()
+                .and_then(|profile| profile.timeout.clone())
+                .unwrap_or_default();
+            let recipe_timeout = recipe
+                .timeout
+                .clone()
+                .unwrap_or_default()
+                .or(&profile_timeout);
+            let timeout = recipe_timeout.or(&self.timeout);
+            // The global connect timeout is already on the default client, so
+            // only an override needs its own client
+            let connect_timeout = recipe_timeout
+                .connect()
+                .filter(|_| transport_name == DEFAULT_TRANSPORT);
+            // gRPC always uses HTTP/2, and WebSockets always use HTTP/1.1
+            let protocol = if transport_name == DEFAULT_TRANSPORT
+                && !is_websocket
+                && grpc.is_none()
+            {
+                recipe.protocol(context)
+            } else {
+                HttpProtocol::Auto
+            };
+            if let Some(overridden) = self
+                .override_transport(
+                    certificate.as_ref(),
+                    proxy,
+                    socket,
+                    resolve,
+                    connect_timeout,
+                    protocol,
+                )
+                .await?
+            {
+                transport = overridden;
+            }
+            let compression = recipe.compression.clone().unwrap_or_default();
+            // Throttling wraps the network itself, so every redirect and retry
+            // is slowed down too. WebSockets take over the connection, so they
+            // can't be throttled. Decoding goes just outside, so the throttled
+            // rate applies to the bytes on the wire.
+            if !is_websocket {
+                let profile_throttle = context
+                    .current_profile()
+                    .and_then(|profile| profile.throttle.clone())
+                    .unwrap_or_default();
+                transport = recipe
+                    .throttle
+                    .clone()
+                    .unwrap_or_default()
+                    .or(&profile_throttle)
+                    .wrap(transport);
+                transport = compression.wrap(transport);
+            }
+            // Cookies go just inside redirects, so each hop gets the cookies
+            // for its own URL, including any set by the previous hop. A
+            // recipe that sets its own Cookie header doesn't get the jar's.
+            let attach_cookies = !headers.contains_key(header::COOKIE);
+            let cookie = if self.cookie_jar {
+                transport = context.cookies.wrap(transport, attach_cookies);
+                context.cookies.header(&url).filter(|_| attach_cookies)
+            } else {
+                None
+            };
+            // Redirects go innermost, so authentication and retries see only
+            // the final response. Other transports handle redirects themselves
+            let redirects = RedirectChain::default();
+            if transport_name == DEFAULT_TRANSPORT {
+                let policy =
+                    recipe.redirect.clone().unwrap_or_else(|| RedirectPolicy {
+                        follow: self.follow_redirects,
+                        ..RedirectPolicy::default()
+                    });
+                if policy.follow {
+                    transport = redirect::wrap(
+                        transport,
+                        &policy,
+                        &self.host_policy,
+                        &redirects,
+                    );
+                }
+            }
             let mut builder =
-                client.request(recipe.method.into(), url).query(&query);
+                self.client.request(recipe.method.into(), url).query(&query);
+            // WebSockets stay open indefinitely, so only the connection is
+            // limited
+            if let Some(total) = timeout.total()
+                && !is_websocket
+            {
+                builder = builder.timeout(total);
+            }
             if let Some(body) = body {
-                builder = body.apply(builder).await?;
+                builder = body.apply(builder, &context.auditor).await?;
+            }
+            // An empty User-Agent means the user wants it omitted
+            match user_agent {
+                None => {
+                    builder = builder.header(
+                        header::USER_AGENT,
+                        HeaderValue::from_static(USER_AGENT),
+                    );
+                }
+                Some(value) if value.is_empty() => {}
+                Some(value) => {
+                    builder = builder.header(header::USER_AGENT, value);
+                }
+            }
+            if let Some(accept) = compression.accept_header() {
+                builder = builder.header(header::ACCEPT_ENCODING, accept);
             }
-            // Set headers *after* body so the use can override the Content-Type
-            // header that was set if they want to
+            // Set headers *after* body, User-Agent, and Accept-Encoding so the
+            // user can override the Content-Type header that was set if they
+            // want to
             builder = builder.headers(headers);
-            if let Some(authentication) = authentication {
-                builder = authentication.apply(builder);
+            // The transport attaches cookies too, but setting them here means
+            // they're included in the request record
+            if let Some(cookie) = cookie {
+                builder = builder.header(header::COOKIE, cookie);
+            }
+            if let Some(authentication) =
+                self.authorize(authentication, context).await?
+            {
+                match ChallengeAuth::try_from(authentication) {
+                    // The header depends on the server's challenge, so the
+                    // transport has to compute it
+                    Ok(challenge) => transport = challenge.wrap(transport),
+                    Err(authentication) => {
+                        builder = authentication.apply(builder);
+                    }
+                }
+            }
+            // Chaos goes outside authentication, so a dropped request fails
+            // the whole exchange rather than one leg of a handshake
+            if let Some(profile) = context.current_profile()
+                && let Some(chaos) = &profile.chaos
+            {
+                transport = self.chaos.wrap(transport, &profile.id, chaos);
+            }
+            // Retries go outermost, so each attempt passes through the whole
+            // stack, including chaos
+            let retry = recipe.retry.as_ref().or(self.retry.as_ref());
+            let retry_progress = RetryProgress::new(
+                retry.map_or(1, |policy| policy.max_attempts),
+            );
+            if let Some(policy) = retry
+                && retry_progress.max_attempts() > 1
+            {
+                transport = retry::wrap(transport, policy, &retry_progress);
+            }
+            // Handshake headers go last because they're not negotiable
+            if is_websocket {
+                builder = builder
+                    .header(header::CONNECTION, "Upgrade")
+                    .header(header::UPGRADE, "websocket")
+                    .header(header::SEC_WEBSOCKET_VERSION, "13")
+                    .header(
+                        header::SEC_WEBSOCKET_KEY,
+                        websocket::handshake_key(),
+                    );
             }
 
-            let request = builder.build()?;
-            Ok((client, request))
+            let mut request = builder.build()?;
+            if let Some(grpc) = &grpc {
+                grpc::prepare(&mut request, grpc);
+            }
+            Ok((
+                transport,
+                request,
+                is_websocket.then(|| websocket::Session {
+                    messages,
+                    frame: recipe
+                        .websocket
+                        .as_ref()
+                        .and_then(|websocket| websocket.frame),
+                }),
+                grpc,
+                retry_progress,
+                redirects,
+                timeout,
+            ))
         };
-        let (client, request) = seed.run_future(future, context).await?;
+        let (transport, request, websocket, grpc, retry, redirects, timeout) =
+            seed.run_future(future, context).await?;
 
         Ok(RequestTicket {
             record: RequestRecord::new(
@@ -189,8 +551,13 @@ impl HttpEngine {
                 self.large_body_size,
             )
             .into(),
-            client: client.clone(),
+            transport,
             request,
+            websocket,
+            grpc,
+            retry,
+            redirects,
+            timeout,
         })
     }
 
@@ -210,8 +577,9 @@ impl HttpEngine {
                 .entered();
 
         let future = async {
+            let mut link_recipe = None;
             let recipe =
-                context.collection.recipes.try_get_recipe(recipe_id)?;
+                get_recipe(context, recipe_id, options, &mut link_recipe)?;
 
             // Parallelization!
             let (url, query) = try_join!(
@@ -220,8 +588,8 @@ impl HttpEngine {
             )?;
 
             // Use RequestBuilder so we can offload the handling of query params
-            let client = self.get_client(&url);
-            let request = client
+            let request = self
+                .client
                 .request(recipe.method.into(), url)
                 .query(&query)
                 .build()?;
@@ -248,8 +616,10 @@ impl HttpEngine {
                 .entered();
 
         let future = async {
+            let mut link_recipe = None;
             let recipe =
-                context.collection.recipes.try_get_recipe(recipe_id)?;
+                get_recipe(context, recipe_id, options, &mut link_recipe)?;
+            recipe.check_sandbox(context)?;
 
             let Some(body) = recipe.render_body(options, context).await? else {
                 return Ok(None);
@@ -267,15 +637,25 @@ impl HttpEngine {
                         .map_err(RequestBuildErrorKind::BodyStream)?;
                     Ok(Some(bytes.into()))
                 }
+                RenderedBody::File { path, .. } => {
+                    context.auditor.record(
+                        TemplateSource::File,
+                        path.display().to_string(),
+                    );
+                    let bytes = fs::read(&path).await.map_err(|error| {
+                        RequestBuildErrorKind::BodyFile { path, error }
+                    })?;
+                    Ok(Some(bytes.into()))
+                }
 
                 // The body is complex - offload the hard work to RequestBuilder
                 RenderedBody::Json(_)
                 | RenderedBody::FormUrlencoded(_)
                 | RenderedBody::FormMultipart(_) => {
                     let url = Url::parse("http://localhost").unwrap();
-                    let client = self.get_client(&url);
-                    let mut builder = client.request(reqwest::Method::GET, url);
-                    builder = body.apply(builder).await?;
+                    let mut builder =
+                        self.client.request(reqwest::Method::GET, url);
+                    builder = body.apply(builder, &context.auditor).await?;
                     let request = builder.build()?;
                     // We just added a body so we know it's present, and we
                     // know it's not a stream. This requires a clone which sucks
@@ -316,23 +696,71 @@ impl HttpEngine {
                 .entered();
 
         let future = async {
+            let mut link_recipe = None;
             let recipe =
-                context.collection.recipes.try_get_recipe(recipe_id)?;
+                get_recipe(context, recipe_id, options, &mut link_recipe)?;
+            recipe.check_sandbox(context)?;
 
             // Render everything up front so we can parallelize it
-            let (url, query, headers, authentication, body) = try_join!(
+            let (
+                url,
+                query,
+                headers,
+                user_agent,
+                proxy,
+                socket,
+                authentication,
+                body,
+            ) = try_join!(
                 recipe.render_url(options, context),
                 recipe.render_query(options, context),
                 recipe.render_headers(options, context),
+                recipe.render_user_agent(self.user_agent.as_ref(), context),
+                recipe.render_proxy(
+                    self.proxy.as_ref(),
+                    &self.host_policy,
+                    context,
+                ),
+                recipe.render_socket(context),
                 recipe.render_authentication(options, context),
                 recipe.render_body(options, context),
             )?;
 
-            // Buidl the command
-            let mut builder = CurlBuilder::new(recipe.method)
-                .url(url, &query)
-                .headers(&headers)?;
-            if let Some(authentication) = authentication {
+            // Build the command
+            let certificate = self.certificates.get(recipe, context, &url);
+            // curl needs the port, so only the URL's host can be mapped
+            let resolve = resolve_overrides(context, &self.host_policy)?;
+            let resolved = url.host_str().and_then(|host| {
+                let (_, address) = resolve
+                    .into_iter()
+                    .find(|(resolve_host, _)| resolve_host == host)?;
+                Some((host.to_owned(), url.port_or_known_default()?, address))
+            });
+            let mut builder = CurlBuilder::new(recipe.method).url(url, &query);
+            if let Some(certificate) = &certificate {
+                builder = builder.certificate(certificate);
+            }
+            if let Some(proxy) = &proxy {
+                builder = builder.proxy(proxy, &self.no_proxy);
+            }
+            // curl has no option for named pipes, so they're left out
+            if let Some(socket) = socket.filter(|socket| !is_named_pipe(socket))
+            {
+                builder = builder.socket(&socket);
+            }
+            if let Some((host, port, address)) = resolved {
+                builder = builder.resolve(&host, port, address);
+            }
+            builder = builder.protocol(recipe.protocol(context));
+            // If User-Agent isn't configured, let curl use its own. An empty
+            // value tells curl to omit the header, which is what we want.
+            if let Some(user_agent) = user_agent {
+                builder = builder.header(&header::USER_AGENT, &user_agent)?;
+            }
+            builder = builder.headers(&headers)?;
+            if let Some(authentication) =
+                self.authorize(authentication, context).await?
+            {
                 builder = builder.authentication(&authentication);
             }
             if let Some(body) = body {
@@ -343,16 +771,116 @@ impl HttpEngine {
         seed.run_future(future, context).await
     }
 
-    /// Get the appropriate client to use for this request. If the request URL's
-    /// host is one for which the user wants to ignore TLS certs, use the
-    /// dangerous client.
-    fn get_client(&self, url: &Url) -> &Client {
-        let host = url.host_str().unwrap_or_default();
-        match &self.danger_client {
-            Some((client, hostnames)) if hostnames.contains(host) => client,
-            _ => &self.client,
+    /// Get a transport for a request that needs client-level settings: a TLS
+    /// client certificate, a proxy, a Unix socket, DNS overrides, a connect
+    /// timeout, and/or a fixed HTTP version. Return `None` if the request needs none of them, in
+    /// which case the default transport can be used.
+    async fn override_transport(
+        &self,
+        certificate: Option<&CertificatePaths>,
+        proxy: Option<String>,
+        socket: Option<PathBuf>,
+        resolve: Vec<(String, IpAddr)>,
+        connect_timeout: Option<Duration>,
+        protocol: HttpProtocol,
+    ) -> Result<Option<Arc<dyn Transport>>, RequestBuildErrorKind> {
+        let identity = match certificate {
+            Some(certificate) => Some(certificate.load().await?),
+            None => None,
+        };
+        if identity.is_none()
+            && proxy.is_none()
+            && socket.is_none()
+            && resolve.is_empty()
+            && connect_timeout.is_none()
+            && protocol == HttpProtocol::Auto
+        {
+            return Ok(None);
+        }
+
+        let overrides = ClientOverrides {
+            identity,
+            proxy: proxy.clone(),
+            socket,
+            resolve,
+            connect_timeout,
+            protocol,
+        };
+        let transport = self
+            .reqwest_transport
+            .with_overrides(overrides)
+            .map_err(|error| match error {
+                OverrideError::Identity(error) => {
+                    RequestBuildErrorKind::CertificateInvalid {
+                        // Identity is only set when there's a certificate
+                        path: certificate
+                            .map(|certificate| certificate.certificate.clone())
+                            .unwrap_or_default(),
+                        error,
+                    }
+                }
+                OverrideError::Proxy(error) => {
+                    RequestBuildErrorKind::ProxyInvalid {
+                        url: proxy.unwrap_or_default(),
+                        error,
+                    }
+                }
+            })?;
+        Ok(Some(transport))
+    }
+
+    /// Resolve authentication that has to be fetched from somewhere. OAuth2 is
+    /// replaced by a bearer token from the provider. Everything else is
+    /// returned as-is.
+    async fn authorize(
+        &self,
+        authentication: Option<Authentication<String>>,
+        context: &TemplateContext,
+    ) -> Result<Option<Authentication<String>>, RequestBuildErrorKind> {
+        match authentication {
+            Some(Authentication::OAuth2(oauth2)) => {
+                // If the previous token was rejected, get a new one even if it
+                // hasn't expired
+                let token = context
+                    .oauth2_tokens
+                    .access_token(
+                        &self.client,
+                        &self.host_policy,
+                        &oauth2,
+                        context.is_reauthenticating(),
+                    )
+                    .await
+                    .map_err(RequestBuildErrorKind::AuthOAuth2)?;
+                Ok(Some(Authentication::Bearer { token }))
+            }
+            authentication => Ok(authentication),
         }
     }
+
+    /// Get the name of the transport that should send a recipe's requests:
+    /// the recipe's own choice, or the default
+    fn transport_name<'a>(&'a self, recipe: &'a Recipe) -> &'a str {
+        recipe
+            .transport
+            .as_deref()
+            .unwrap_or(&self.default_transport)
+    }
+
+    /// Get the transport that should send a recipe's requests
+    fn get_transport(
+        &self,
+        recipe: &Recipe,
+    ) -> Result<Arc<dyn Transport>, RequestBuildErrorKind> {
+        let name = self.transport_name(recipe);
+        self.transports.get(name).cloned().ok_or_else(|| {
+            let mut options: Vec<_> = self.transports.keys().cloned().collect();
+            options.sort();
+            RequestBuildErrorKind::TransportUnknown {
+                name: name.to_owned(),
+                options,
+            }
+        })
+    }
 }
 
 impl Default for HttpEngine {
@@ -393,7 +921,216 @@ impl RequestTicket {
     /// launched until the consumer starts awaiting the future. For in-flight
     /// time tracking, track your own start time immediately before/after
     /// sending the request.
+    ///
+    /// WebSocket requests stay connected until the server closes the
+    /// connection. Use [Self::send_websocket] to control when it closes.
     pub async fn send(self) -> Result<Exchange, RequestError> {
+        if self.is_grpc() {
+            return self
+                .send_grpc(|_, _| {}, |_| ControlFlow::Continue(()))
+                .await;
+        }
+        if self.is_websocket() {
+            return self
+                .send_websocket(
+                    stream::pending(),
+                    |_, _| {},
+                    |_| ControlFlow::Continue(()),
+                )
+                .await;
+        }
+        let id = self.record.id;
+        self.send_with(|response| ResponseRecord::from_response(id, response))
+            .await
+    }
+
+    /// Launch an HTTP request, passing the response body to `on_chunk` as it
+    /// arrives instead of waiting for the entire body to load. `on_response`
+    /// is called with the status and headers as soon as they're received,
+    /// before any body chunks. If `on_chunk` returns [ControlFlow::Break], the
+    /// remainder of the body is abandoned.
+    ///
+    /// The returned exchange contains whatever portion of the body was
+    /// received.
+    ///
+    /// For WebSocket requests, each message from the server is passed as its
+    /// own chunk. See [Self::send_websocket].
+    pub async fn send_streaming(
+        self,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        on_chunk: impl FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<Exchange, RequestError> {
+        self.send_streaming_until(future::pending(), on_response, on_chunk)
+            .await
+    }
+
+    /// [Self::send_streaming], but stop receiving the body once `stop`
+    /// resolves. Unlike cancelling the request, this still returns an
+    /// exchange with the portion of the body received so far. Useful for
+    /// long-lived streams (e.g. Server-Sent Events) that never end on their
+    /// own.
+    ///
+    /// For WebSocket requests, resolving `stop` closes the connection.
+    pub async fn send_streaming_until(
+        self,
+        stop: impl Future<Output = ()>,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        on_chunk: impl FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<Exchange, RequestError> {
+        if self.is_grpc() {
+            // Unary calls are over as soon as the response arrives
+            return self.send_grpc(on_response, on_chunk).await;
+        }
+        if self.is_websocket() {
+            // Closing the outgoing stream closes the connection
+            let outgoing = stream::once(stop)
+                .filter_map(|()| future::ready(None::<Bytes>));
+            return self.send_websocket(outgoing, on_response, on_chunk).await;
+        }
+        let id = self.record.id;
+        self.send_with(|response| {
+            ResponseRecord::from_response_streaming(
+                id,
+                response,
+                stop,
+                on_response,
+                on_chunk,
+            )
+        })
+        .await
+    }
+
+    /// Send the request, writing the response body to a file as it's received
+    /// instead of loading it into memory. The file is created, or truncated
+    /// if it exists. `on_response` is called once the response headers are
+    /// received, and `on_progress` after each chunk is written.
+    ///
+    /// The returned exchange has an empty body, so the body never ends up in
+    /// memory or in the history database. The raw body is written as-is, so
+    /// this isn't meant for WebSocket or gRPC requests.
+    pub async fn download(
+        self,
+        path: &Path,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<Exchange, RequestError> {
+        let id = self.record.id;
+        self.send_with(|response| {
+            ResponseRecord::from_response_download(
+                id,
+                response,
+                path,
+                on_response,
+                on_progress,
+            )
+        })
+        .await
+    }
+
+    /// Open a WebSocket connection. The recipe's messages are sent first,
+    /// followed by each item of `outgoing` as it's yielded. When `outgoing`
+    /// ends, the connection is closed. `on_response` is called once the
+    /// server accepts the connection, and `on_message` with each message from
+    /// the server, followed by a newline. If `on_message` returns
+    /// [ControlFlow::Break], the connection is closed immediately.
+    ///
+    /// The returned exchange's body contains every message received, one per
+    /// line. If the server doesn't accept the connection, its response is
+    /// loaded like any other HTTP response.
+    pub async fn send_websocket(
+        mut self,
+        outgoing: impl Stream<Item = Bytes>,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        on_message: impl FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<Exchange, RequestError> {
+        let id = self.record.id;
+        let session = self.websocket.take().unwrap_or_default();
+        // The handshake is plain HTTP, so reqwest needs an HTTP URL. The
+        // record keeps the URL as the user wrote it
+        let url = self.request.url_mut();
+        let scheme = match url.scheme() {
+            "ws" => Some("http"),
+            "wss" => Some("https"),
+            _ => None,
+        };
+        if let Some(scheme) = scheme {
+            // Both schemes are special, so the switch can't fail
+            let _ = url.set_scheme(scheme);
+        }
+        let key = self
+            .request
+            .headers()
+            .get(header::SEC_WEBSOCKET_KEY)
+            .cloned();
+        self.send_with(|response| {
+            ResponseRecord::from_websocket(
+                id,
+                response,
+                key,
+                session,
+                outgoing,
+                on_response,
+                on_message,
+            )
+        })
+        .await
+    }
+
+    /// Make a gRPC call. The request body is encoded and the response
+    /// decoded according to the method's schema. Unary calls have a single
+    /// response message, so it's passed to `on_response` and `on_chunk` all
+    /// at once.
+    async fn send_grpc(
+        mut self,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        mut on_chunk: impl FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<Exchange, RequestError> {
+        let id = self.record.id;
+        let call = self.grpc.take().expect("Request is not gRPC");
+        self.exchange(|transport, request| async move {
+            let response = grpc::call(&*transport, request, call).await?;
+            on_response(response.status, &response.headers);
+            let _ = on_chunk(&response.body);
+            Ok(ResponseRecord {
+                id,
+                // gRPC always uses HTTP/2
+                http_version: HttpVersion::Http2,
+                certificate_fingerprint: None,
+                status: response.status,
+                headers: response.headers,
+                body: response.body.into(),
+                redirects: Vec::new(),
+            })
+        })
+        .await
+    }
+
+    /// Launch an HTTP request, using the given function to load the response
+    async fn send_with<Fut, E>(
+        self,
+        load_response: impl FnOnce(Response) -> Fut,
+    ) -> Result<Exchange, RequestError>
+    where
+        Fut: Future<Output = Result<ResponseRecord, E>>,
+        TransportError: From<E>,
+    {
+        self.exchange(|transport, request| async move {
+            let response = transport.send(request).await?;
+            // Load the response and convert it to our format
+            Ok(load_response(response).await?)
+        })
+        .await
+    }
+
+    /// Send the request and load its response with the given function,
+    /// recording how long it takes. Errors are attached to the request record.
+    async fn exchange<Fut>(
+        self,
+        send: impl FnOnce(Arc<dyn Transport>, Request) -> Fut,
+    ) -> Result<Exchange, RequestError>
+    where
+        Fut: Future<Output = Result<ResponseRecord, TransportError>>,
+    {
         let id = self.record.id;
 
         // Capture the rest of this method in a span
@@ -402,17 +1139,13 @@ impl RequestTicket {
         // This start time will be accurate because the request doesn't launch
         // until this whole future is awaited
         let start_time = Utc::now();
-        let result = async {
-            let response = self.client.execute(self.request).await?;
-            // Load the full response and convert it to our format
-            ResponseRecord::from_response(id, response).await
-        }
-        .await;
+        let result = send(self.transport, self.request).await;
         let end_time = Utc::now();
 
         match result {
-            Ok(response) => {
+            Ok(mut response) => {
                 info!(status = response.status.as_u16(), "Response");
+                response.redirects = self.redirects.take();
                 let exchange = Exchange {
                     id,
                     request: self.record,
@@ -430,7 +1163,7 @@ impl RequestTicket {
                 request: self.record,
                 start_time,
                 end_time,
-                error,
+                error: self.timeout.classify(error),
             })
             .inspect_err(|err| error!(error = err as &dyn Error)),
         }
@@ -447,6 +1180,9 @@ impl ResponseRecord {
     ) -> reqwest::Result<ResponseRecord> {
         // Copy response metadata out first, because we need to move the
         // response to resolve content (not sure why...)
+        let http_version = response.version().into();
+        let certificate_fingerprint =
+            pinning::certificate_fingerprint(&response);
         let status = response.status();
         let headers = response.headers().clone();
 
@@ -455,15 +1191,524 @@ impl ResponseRecord {
 
         Ok(ResponseRecord {
             id,
+            http_version,
+            certificate_fingerprint,
             status,
             headers,
             body,
+            redirects: Vec::new(),
+        })
+    }
+
+    /// Convert [reqwest::Response] type into [ResponseRecord], passing each
+    /// chunk of the body to a callback as it's received. See
+    /// [RequestTicket::send_streaming].
+    async fn from_response_streaming(
+        id: RequestId,
+        mut response: Response,
+        stop: impl Future<Output = ()>,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        mut on_chunk: impl FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> reqwest::Result<ResponseRecord> {
+        let http_version = response.version().into();
+        let certificate_fingerprint =
+            pinning::certificate_fingerprint(&response);
+        let status = response.status();
+        let headers = response.headers().clone();
+        on_response(status, &headers);
+
+        let mut body = BytesMut::new();
+        let mut stop = pin!(stop);
+        loop {
+            let chunk =
+                match future::select(pin!(response.chunk()), stop.as_mut())
+                    .await
+                {
+                    Either::Left((chunk, _)) => chunk?,
+                    // Keep whatever we've received so far
+                    Either::Right(((), _)) => break,
+                };
+            let Some(chunk) = chunk else { break };
+            body.extend_from_slice(&chunk);
+            if on_chunk(&chunk).is_break() {
+                break;
+            }
+        }
+
+        Ok(ResponseRecord {
+            id,
+            http_version,
+            certificate_fingerprint,
+            status,
+            headers,
+            body: body.freeze().into(),
+            redirects: Vec::new(),
+        })
+    }
+}
+
+impl ResponseRecord {
+    /// Load the response to a WebSocket handshake. If the server switched
+    /// protocols, run the session until the connection closes. See
+    /// [RequestTicket::send_websocket].
+    async fn from_websocket(
+        id: RequestId,
+        response: Response,
+        key: Option<HeaderValue>,
+        session: websocket::Session,
+        outgoing: impl Stream<Item = Bytes>,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        on_message: impl FnMut(&[u8]) -> ControlFlow<()>,
+    ) -> Result<ResponseRecord, TransportError> {
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            // The server refused the connection. The response probably
+            // explains why, so show it to the user. There's nowhere to send
+            // outgoing messages, but the stream ending still means stop
+            return Ok(Self::from_response_streaming(
+                id,
+                response,
+                outgoing.for_each(|_| future::ready(())),
+                on_response,
+                on_message,
+            )
+            .await?);
+        }
+
+        let http_version = response.version().into();
+        let certificate_fingerprint =
+            pinning::certificate_fingerprint(&response);
+        let status = response.status();
+        let headers = response.headers().clone();
+        websocket::check_accept(
+            key.as_ref(),
+            headers.get(header::SEC_WEBSOCKET_ACCEPT),
+        )
+        .map_err(TransportError::new)?;
+        on_response(status, &headers);
+
+        let connection = response.upgrade().await?;
+        let body = websocket::run(connection, session, outgoing, on_message)
+            .await
+            .map_err(TransportError::new)?;
+        Ok(ResponseRecord {
+            id,
+            http_version,
+            certificate_fingerprint,
+            status,
+            headers,
+            body: body.into(),
+            redirects: Vec::new(),
         })
     }
 }
 
+/// Get the selected profile's DNS overrides. Hostnames are lowercased to
+/// match how URLs are parsed.
+///
+/// An override sends an allowed hostname's traffic to any address, so each
+/// mapped address has to pass the host policy as well.
+fn resolve_overrides(
+    context: &TemplateContext,
+    host_policy: &HostPolicy,
+) -> Result<Vec<(String, IpAddr)>, RequestBuildErrorKind> {
+    let Some(profile) = context.current_profile() else {
+        return Ok(Vec::new());
+    };
+    profile
+        .resolve
+        .iter()
+        .map(|(host, address)| {
+            host_policy.check_host(&address.to_string())?;
+            Ok((host.to_lowercase(), *address))
+        })
+        .collect()
+}
+
+/// Is this socket path a Windows named pipe (`\\.\pipe\...`) rather than a
+/// Unix socket? The prefix is matched case-insensitively, as Windows does.
+fn is_named_pipe(path: &Path) -> bool {
+    const PREFIX: &str = r"\\.\pipe\";
+    path.to_str().is_some_and(|path| {
+        path.get(..PREFIX.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+    })
+}
+
+/// Get the recipe to build a request from. If the request is following a link,
+/// derive a new recipe for the link instead. The derived recipe is stored in
+/// `link_recipe`, so the caller owns it.
+fn get_recipe<'a>(
+    context: &'a TemplateContext,
+    recipe_id: &RecipeId,
+    options: &BuildOptions,
+    link_recipe: &'a mut Option<Recipe>,
+) -> Result<&'a Recipe, UnknownRecipeError> {
+    let recipe = context.collection.recipes.try_get_recipe(recipe_id)?;
+    Ok(match &options.link {
+        Some(url) => link_recipe.insert(recipe.link(url)),
+        None => recipe,
+    })
+}
+
 /// Render steps for individual pieces of a recipe
 impl Recipe {
+    /// Wrap the context for rendering one of this recipe's templates, so the
+    /// recipe's render mode and parameters are used
+    fn render_context<'a>(
+        &'a self,
+        context: &'a TemplateContext,
+        can_stream: bool,
+    ) -> SingleRenderContext<'a> {
+        context
+            .streaming(can_stream)
+            .with_render_mode(self.render_mode)
+            .with_params(&self.params)
+    }
+
+    /// Get every template that will be rendered to build this recipe, with
+    /// overrides applied. `user_agent` and `proxy` are the global defaults
+    /// from the config.
+    fn templates<'a>(
+        &'a self,
+        options: &'a BuildOptions,
+        context: &'a TemplateContext,
+        user_agent: Option<&'a Template>,
+        proxy: Option<&'a Template>,
+    ) -> Vec<&'a Template> {
+        let mut templates = vec![options.url.as_ref().unwrap_or(&self.url)];
+        templates.extend(
+            apply_overrides(
+                self.query_iter().map(|(k, i, v)| ((k, i), v)),
+                options
+                    .query_parameters
+                    .iter()
+                    .map(|((k, i), v)| ((k.as_str(), *i), v)),
+            )
+            .into_values(),
+        );
+        templates.extend(
+            apply_overrides(&self.headers, &options.headers).into_values(),
+        );
+        if !self.headers.contains_key(header::USER_AGENT.as_str()) {
+            templates.extend(self.user_agent.as_ref().or(user_agent));
+        }
+        templates.extend(
+            context
+                .current_profile()
+                .and_then(|profile| profile.proxy.as_ref())
+                .or(proxy),
+        );
+        templates.extend(self.socket_template(context));
+
+        let authentication = options.authentication.as_ref().or_else(|| {
+            let resolved =
+                context.collection.recipes.authentication(&self.id)?;
+            Some(resolved.authentication)
+        });
+        match authentication {
+            Some(Authentication::Basic { username, password }) => {
+                templates.push(username);
+                templates.extend(password);
+            }
+            Some(Authentication::Bearer { token }) => templates.push(token),
+            Some(Authentication::Digest { username, password }) => {
+                templates.extend([username, password]);
+            }
+            Some(Authentication::Ntlm {
+                username,
+                password,
+                domain,
+            }) => {
+                templates.extend([username, password]);
+                templates.extend(domain);
+            }
+            Some(Authentication::OAuth2(oauth2)) => {
+                if let OAuth2Grant::AuthorizationCode {
+                    authorization_url,
+                    redirect_uri,
+                } = &oauth2.grant
+                {
+                    templates.push(authorization_url);
+                    templates.extend(redirect_uri);
+                }
+                templates.extend([&oauth2.token_url, &oauth2.client_id]);
+                templates.extend(&oauth2.client_secret);
+                templates.extend(&oauth2.scope);
+            }
+            None => {}
+        }
+
+        if let Some(websocket) = &self.websocket {
+            templates.extend(&websocket.messages);
+        }
+
+        match &self.body {
+            Some(RecipeBody::FormUrlencoded(fields)) => {
+                templates.extend(
+                    apply_overrides(fields, &options.form_fields).into_values(),
+                );
+            }
+            Some(RecipeBody::FormMultipart(fields)) => {
+                let merged = apply_overrides(
+                    fields.iter().map(|(field, part)| (field, part.value())),
+                    &options.form_fields,
+                );
+                for (field, template) in merged {
+                    templates.push(template);
+                    if let Some(part) = fields.get(field) {
+                        templates.extend(part.content_type());
+                        templates.extend(part.filename());
+                    }
+                }
+            }
+            Some(RecipeBody::Graphql(graphql)) => {
+                templates.push(options.body.as_ref().unwrap_or(&graphql.query));
+                // Overridden variables are reparsed as JSON, which doesn't
+                // change the expressions they contain
+                match &options.graphql_variables {
+                    Some(variables) => templates.push(variables),
+                    None => {
+                        if let Some(variables) = &graphql.variables {
+                            variables.walk_templates(&mut |template| {
+                                templates.push(template);
+                            });
+                        }
+                    }
+                }
+                templates.extend(&graphql.operation_name);
+            }
+            Some(RecipeBody::Json(json)) => match &options.body {
+                Some(body) => templates.push(body),
+                None => json.walk_templates(&mut |template| {
+                    templates.push(template);
+                }),
+            },
+            Some(
+                RecipeBody::Raw(template)
+                | RecipeBody::Stream(template)
+                | RecipeBody::File(template),
+            ) => {
+                templates.push(options.body.as_ref().unwrap_or(template));
+            }
+            None => templates.extend(&options.body),
+        }
+        templates
+    }
+
+    /// In an untrusted collection, refuse every field that reaches outside the
+    /// request URL: fields that send the request somewhere else, which
+    /// `allowed_hosts` can't see, and fields that read local files on the
+    /// collection's behalf, like `file()`.
+    ///
+    /// This is the only sandbox check for recipe and profile fields, and it
+    /// fails closed: both structs are destructured without `..`, so a new
+    /// field won't compile until it's been sorted into one group or the other.
+    fn check_sandbox(
+        &self,
+        context: &TemplateContext,
+    ) -> Result<(), RequestBuildErrorKind> {
+        if !context.sandboxed {
+            return Ok(());
+        }
+
+        let Self {
+            // Only affect the request sent to the URL
+            id: _,
+            location: _,
+            persist: _,
+            name: _,
+            method: _,
+            url: _,
+            // The OAuth2 token URL is checked by the host policy
+            authentication: _,
+            query: _,
+            headers: _,
+            user_agent: _,
+            transport: _,
+            retry: _,
+            // Redirects are checked by the host policy
+            redirect: _,
+            timeout: _,
+            throttle: _,
+            compression: _,
+            protocol: _,
+            reauthenticate: _,
+            capture: _,
+            assert: _,
+            tags: _,
+            websocket: _,
+            render_mode: _,
+            params: _,
+            // Reach outside the URL
+            body,
+            certificate,
+            socket,
+            grpc,
+        } = self;
+        let (profile_certificate, proxy, profile_socket, resolve) =
+            match context.current_profile() {
+                Some(Profile {
+                    // Only affect the request sent to the URL
+                    id: _,
+                    location: _,
+                    name: _,
+                    default: _,
+                    data: _,
+                    chaos: _,
+                    timeout: _,
+                    throttle: _,
+                    protocol: _,
+                    pinning: _,
+                    // Reach outside the URL
+                    certificate,
+                    proxy,
+                    socket,
+                    resolve,
+                }) => (
+                    certificate.is_some(),
+                    proxy.is_some(),
+                    socket.is_some(),
+                    !resolve.is_empty(),
+                ),
+                None => (false, false, false, false),
+            };
+
+        let fields = [
+            // Read local files
+            ("!file", matches!(body, Some(RecipeBody::File(_)))),
+            ("certificate", certificate.is_some() || profile_certificate),
+            (
+                "grpc.proto_files",
+                grpc.as_ref()
+                    .is_some_and(|grpc| !grpc.proto_files.is_empty()),
+            ),
+            // Send the request somewhere else. A socket can reach local
+            // daemons (e.g. Docker) that were never meant to be exposed over
+            // the network. The proxy from the config is still used.
+            ("socket", socket.is_some() || profile_socket),
+            ("proxy", proxy),
+            ("resolve", resolve),
+        ];
+        match fields.into_iter().find(|(_, is_used)| *is_used) {
+            Some((field, _)) => Err(RequestBuildErrorKind::Sandboxed { field }),
+            None => Ok(()),
+        }
+    }
+
+    /// For gRPC recipes, check that the recipe is valid and get the method to
+    /// call. `None` for other recipes
+    fn grpc_call(
+        &self,
+        context: &TemplateContext,
+    ) -> Result<Option<GrpcCall>, RequestBuildErrorKind> {
+        let Some(grpc) = &self.grpc else {
+            return Ok(None);
+        };
+        if !matches!(self.method, HttpMethod::Post) {
+            return Err(RequestBuildErrorKind::GrpcMethod {
+                method: self.method,
+            });
+        }
+        if matches!(
+            self.body,
+            Some(RecipeBody::Stream(_) | RecipeBody::File(_))
+        ) {
+            return Err(RequestBuildErrorKind::GrpcBodyStream);
+        }
+        let (service, method) = grpc.service_method().ok_or_else(|| {
+            RequestBuildErrorKind::GrpcMethodName {
+                method: grpc.method.clone(),
+            }
+        })?;
+
+        // Paths are relative to the collection file, like file()
+        let resolve = |path: &String| {
+            context.root_dir.join(expand_home(PathBuf::from(path)))
+        };
+        let schema = if grpc.proto_files.is_empty() {
+            GrpcSchema::Reflection
+        } else {
+            let import_paths = if grpc.import_paths.is_empty() {
+                vec![context.root_dir.clone()]
+            } else {
+                grpc.import_paths.iter().map(resolve).collect()
+            };
+            let files: Vec<PathBuf> =
+                grpc.proto_files.iter().map(resolve).collect();
+            GrpcSchema::Files {
+                files,
+                import_paths,
+            }
+        };
+        Ok(Some(GrpcCall {
+            service: service.to_owned(),
+            method: method.to_owned(),
+            schema,
+        }))
+    }
+
+    /// Derive a recipe to follow a link from one of this recipe's responses.
+    /// It's a `GET` to the link URL that keeps this recipe's authentication,
+    /// but nothing else
+    fn link(&self, url: &Url) -> Self {
+        Self {
+            id: self.id.clone(),
+            location: self.location.clone(),
+            persist: self.persist,
+            name: self.name.clone(),
+            method: HttpMethod::Get,
+            url: Template::raw(url.to_string()),
+            body: None,
+            authentication: self.authentication.clone(),
+            query: IndexMap::new(),
+            headers: IndexMap::new(),
+            user_agent: None,
+            transport: self.transport.clone(),
+            certificate: self.certificate.clone(),
+            retry: self.retry.clone(),
+            redirect: self.redirect.clone(),
+            timeout: self.timeout.clone(),
+            throttle: self.throttle.clone(),
+            compression: self.compression.clone(),
+            protocol: self.protocol,
+            socket: self.socket.clone(),
+            reauthenticate: self.reauthenticate,
+            capture: IndexMap::new(),
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: self.render_mode,
+            // Authentication may reference parameters
+            params: self.params.clone(),
+        }
+    }
+
+    /// Render the messages a WebSocket recipe sends once connected. Empty if
+    /// the recipe doesn't define any
+    async fn render_websocket_messages(
+        &self,
+        context: &TemplateContext,
+    ) -> Result<Vec<Bytes>, RequestBuildErrorKind> {
+        let Some(websocket) = &self.websocket else {
+            return Ok(Vec::new());
+        };
+        try_join_all(websocket.messages.iter().enumerate().map(
+            |(index, template)| async move {
+                template
+                    .render_bytes(&self.render_context(context, false))
+                    .await
+                    .map_err(|error| {
+                        RequestBuildErrorKind::WebSocketMessageRender {
+                            index,
+                            error,
+                        }
+                    })
+            },
+        ))
+        .await
+    }
+
     /// Render base URL, *excluding* query params
     async fn render_url(
         &self,
@@ -472,7 +1717,7 @@ impl Recipe {
     ) -> Result<Url, RequestBuildErrorKind> {
         let template = options.url.as_ref().unwrap_or(&self.url);
         let url = template
-            .render_string(&context.streaming(false))
+            .render_string(&self.render_context(context, false))
             .await
             .map_err(RequestBuildErrorKind::UrlRender)?;
         url.parse::<Url>()
@@ -496,7 +1741,7 @@ impl Recipe {
 
         let iter = merged.into_iter().map(async |((param, _), template)| {
             let value = template
-                .render_string(&context.streaming(false))
+                .render_string(&self.render_context(context, false))
                 .await
                 .map_err(|error| RequestBuildErrorKind::QueryRender {
                     parameter: param.to_owned(),
@@ -533,6 +1778,117 @@ impl Recipe {
         Ok(headers)
     }
 
+    /// Render the `User-Agent` header value from the recipe's `user_agent`
+    /// field, falling back to the global default. Return `None` if neither is
+    /// defined, or if the recipe defines the header explicitly in `headers`.
+    /// An empty value means the header should be omitted.
+    async fn render_user_agent(
+        &self,
+        default: Option<&Template>,
+        context: &TemplateContext,
+    ) -> Result<Option<HeaderValue>, RequestBuildErrorKind> {
+        if self.headers.contains_key(header::USER_AGENT.as_str()) {
+            return Ok(None);
+        }
+        let Some(template) = self.user_agent.as_ref().or(default) else {
+            return Ok(None);
+        };
+        let (_, value) = self
+            .render_header(context, header::USER_AGENT.as_str(), template)
+            .await?;
+        Ok(Some(value))
+    }
+
+    /// Render the proxy URL from the selected profile's `proxy` field, falling
+    /// back to the global default. Return `None` if neither is defined, or if
+    /// the URL renders to an empty string.
+    ///
+    /// A proxy from the profile decides where the request is really sent, so
+    /// its host must pass the host policy too. The global default comes from
+    /// the user's own config, so it's exempt.
+    async fn render_proxy(
+        &self,
+        default: Option<&Template>,
+        host_policy: &HostPolicy,
+        context: &TemplateContext,
+    ) -> Result<Option<String>, RequestBuildErrorKind> {
+        let profile_proxy = context
+            .current_profile()
+            .and_then(|profile| profile.proxy.as_ref());
+        let Some(template) = profile_proxy.or(default) else {
+            return Ok(None);
+        };
+        let url = template
+            .render_string(&self.render_context(context, false))
+            .await
+            .map_err(RequestBuildErrorKind::ProxyRender)?;
+        if url.is_empty() {
+            return Ok(None);
+        }
+        if profile_proxy.is_some() {
+            // reqwest assumes http:// for a proxy without a scheme, so do the
+            // same. If it doesn't parse, check an empty host so an allowlist
+            // still rejects it.
+            let parsed = if url.contains("://") {
+                Url::parse(&url)
+            } else {
+                Url::parse(&format!("http://{url}"))
+            };
+            let host = parsed
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_owned))
+                .unwrap_or_default();
+            host_policy.check_host(&host)?;
+        }
+        Ok(Some(url))
+    }
+
+    /// Render the path of the Unix socket or Windows named pipe to send over.
+    /// The recipe's `socket` beats the selected profile's. Relative socket
+    /// paths are relative to the collection file. Return `None` if neither is
+    /// defined, or if the path renders to an empty string.
+    async fn render_socket(
+        &self,
+        context: &TemplateContext,
+    ) -> Result<Option<PathBuf>, RequestBuildErrorKind> {
+        let Some(template) = self.socket_template(context) else {
+            return Ok(None);
+        };
+        let path = template
+            .render_string(&self.render_context(context, false))
+            .await
+            .map_err(RequestBuildErrorKind::SocketRender)?;
+        Ok(Some(path).filter(|path| !path.is_empty()).map(|path| {
+            let path = PathBuf::from(path);
+            if is_named_pipe(&path) {
+                path
+            } else {
+                context.root_dir.join(expand_home(path))
+            }
+        }))
+    }
+
+    /// Get the template for the Unix socket path. The recipe's setting beats
+    /// the selected profile's
+    fn socket_template<'a>(
+        &'a self,
+        context: &'a TemplateContext,
+    ) -> Option<&'a Template> {
+        self.socket.as_ref().or_else(|| {
+            context
+                .current_profile()
+                .and_then(|profile| profile.socket.as_ref())
+        })
+    }
+
+    /// Get the HTTP version to send with. The recipe's setting beats the
+    /// selected profile's
+    fn protocol(&self, context: &TemplateContext) -> HttpProtocol {
+        self.protocol
+            .or_else(|| context.current_profile()?.protocol)
+            .unwrap_or_default()
+    }
+
     /// Render a single key/value header
     async fn render_header(
         &self,
@@ -541,7 +1897,7 @@ impl Recipe {
         value_template: &Template,
     ) -> Result<(HeaderName, HeaderValue), RequestBuildErrorKind> {
         let mut value: Vec<u8> = value_template
-            .render_bytes(&context.streaming(false))
+            .render_bytes(&self.render_context(context, false))
             .await
             .map_err(|error| RequestBuildErrorKind::HeaderRender {
                 header: header.to_owned(),
@@ -579,11 +1935,13 @@ impl Recipe {
         options: &BuildOptions,
         context: &TemplateContext,
     ) -> Result<Option<Authentication<String>>, RequestBuildErrorKind> {
-        let authentication = options
-            .authentication
-            .as_ref()
-            .or(self.authentication.as_ref());
-        let context = context.streaming(false); // Auth templates never support streaming
+        let authentication = options.authentication.as_ref().or_else(|| {
+            let resolved =
+                context.collection.recipes.authentication(&self.id)?;
+            Some(resolved.authentication)
+        });
+        // Auth templates never support streaming
+        let context = self.render_context(context, false);
         match authentication {
             Some(Authentication::Basic { username, password }) => {
                 let (username, password) =
@@ -610,6 +1968,52 @@ impl Recipe {
                     .map_err(RequestBuildErrorKind::AuthTokenRender)?;
                 Ok(Some(Authentication::Bearer { token }))
             }
+
+            Some(Authentication::Digest { username, password }) => {
+                let (username, password) = try_join!(
+                    username
+                        .render_string(&context)
+                        .map_err(RequestBuildErrorKind::AuthUsernameRender),
+                    password
+                        .render_string(&context)
+                        .map_err(RequestBuildErrorKind::AuthPasswordRender),
+                )?;
+                Ok(Some(Authentication::Digest { username, password }))
+            }
+
+            Some(Authentication::Ntlm {
+                username,
+                password,
+                domain,
+            }) => {
+                let (username, password, domain) = try_join!(
+                    username
+                        .render_string(&context)
+                        .map_err(RequestBuildErrorKind::AuthUsernameRender),
+                    password
+                        .render_string(&context)
+                        .map_err(RequestBuildErrorKind::AuthPasswordRender),
+                    async {
+                        OptionFuture::from(
+                            domain
+                                .as_ref()
+                                .map(|domain| domain.render_string(&context)),
+                        )
+                        .await
+                        .transpose()
+                        .map_err(RequestBuildErrorKind::AuthDomainRender)
+                    },
+                )?;
+                Ok(Some(Authentication::Ntlm {
+                    username,
+                    password,
+                    domain,
+                }))
+            }
+
+            Some(Authentication::OAuth2(oauth2)) => {
+                Ok(Some(Authentication::OAuth2(oauth2.render(&context).await?)))
+            }
             None => Ok(None),
         }
     }
@@ -620,6 +2024,12 @@ impl Recipe {
         options: &BuildOptions,
         context: &TemplateContext,
     ) -> Result<Option<RenderedBody>, RequestBuildErrorKind> {
+        if options.graphql_introspection
+            && !matches!(self.body, Some(RecipeBody::Graphql(_)))
+        {
+            return Err(RequestBuildErrorKind::GraphQlIntrospectionBody);
+        }
+
         // Make sure the override+body combo is valid. If there's no body but
         // there is an override, we'll make it a raw body
         let body = match (&self.body, &options.body) {
@@ -648,7 +2058,7 @@ impl Recipe {
                 let template = options.body.as_ref().unwrap_or(template);
                 RenderedBody::Raw(
                     template
-                        .render_bytes(&context.streaming(false))
+                        .render_bytes(&self.render_context(context, false))
                         .await
                         .map_err(RequestBuildErrorKind::BodyRender)?,
                 )
@@ -657,7 +2067,8 @@ impl Recipe {
                 // Use override if it's given
                 let template = options.body.as_ref().unwrap_or(template);
                 // Stream body is rendered as a stream (!!)
-                let output = template.render(&context.streaming(true)).await;
+                let output =
+                    template.render(&self.render_context(context, true)).await;
                 let source = output.stream_source().cloned();
                 let stream = output
                     .try_into_stream()
@@ -665,6 +2076,41 @@ impl Recipe {
                     .boxed();
                 RenderedBody::Stream(BodyStream { stream, source })
             }
+            RecipeBody::File(template) => {
+                // Use override if it's given. The override is the path too
+                let template = options.body.as_ref().unwrap_or(template);
+                let path = template
+                    .render_string(&self.render_context(context, false))
+                    .await
+                    .map_err(RequestBuildErrorKind::BodyRender)?;
+                // Paths are relative to the collection file. The sandbox is
+                // checked before rendering, so this only needs the auditor.
+                let path =
+                    context.root_dir.join(expand_home(PathBuf::from(path)));
+                let access_error =
+                    |error| RequestBuildErrorKind::BodyFileAccess {
+                        path: path.clone(),
+                        error: Box::new(error),
+                    };
+                context
+                    .auditor
+                    .confirm(
+                        &*context.prompter,
+                        TemplateSource::File,
+                        &path.display().to_string(),
+                    )
+                    .await
+                    .map_err(access_error)?;
+                // An explicit header beats a guess
+                let content_type =
+                    if self.headers.contains_key(header::CONTENT_TYPE.as_str())
+                    {
+                        None
+                    } else {
+                        mime_guess::from_path(&path).first()
+                    };
+                RenderedBody::File { path, content_type }
+            }
             RecipeBody::Json(json) => {
                 // Use override if it's given
                 let override_json: Option<JsonTemplate> = options
@@ -675,16 +2121,20 @@ impl Recipe {
                     .transpose()?;
                 let json = override_json.as_ref().unwrap_or(json);
                 let value = json
-                    .render(context)
+                    .render(&self.render_context(context, false))
                     .await
                     .map_err(RequestBuildErrorKind::BodyRender)?;
                 RenderedBody::Json(value)
             }
+            RecipeBody::Graphql(graphql) => {
+                let context = self.render_context(context, false);
+                RenderedBody::Json(graphql.render(options, &context).await?)
+            }
             RecipeBody::FormUrlencoded(fields) => {
                 let merged = apply_overrides(fields, &options.form_fields);
                 let iter = merged.into_iter().map(async |(field, template)| {
                     let value = template
-                        .render_string(&context.streaming(false))
+                        .render_string(&self.render_context(context, false))
                         .await
                         .map_err(|error| {
                             RequestBuildErrorKind::BodyFormFieldRender {
@@ -698,10 +2148,36 @@ impl Recipe {
                 RenderedBody::FormUrlencoded(rendered)
             }
             RecipeBody::FormMultipart(fields) => {
-                let merged = apply_overrides(fields, &options.form_fields);
+                let merged = apply_overrides(
+                    fields.iter().map(|(field, part)| (field, part.value())),
+                    &options.form_fields,
+                );
                 let iter = merged.into_iter().map(async |(field, template)| {
-                    let output =
-                        template.render(&context.streaming(true)).await;
+                    // Part headers are kept even if the value is overridden
+                    let part = fields.get(field);
+                    let render_header = async |template: Option<&Template>| {
+                        let Some(template) = template else {
+                            return Ok(None);
+                        };
+                        template
+                            .render_string(&self.render_context(context, false))
+                            .await
+                            .map(Some)
+                            .map_err(|error| {
+                                RequestBuildErrorKind::BodyFormFieldRender {
+                                    field: field.clone(),
+                                    error,
+                                }
+                            })
+                    };
+                    let (content_type, filename) = try_join!(
+                        render_header(part.and_then(FormPart::content_type)),
+                        render_header(part.and_then(FormPart::filename)),
+                    )?;
+
+                    let output = template
+                        .render(&self.render_context(context, true))
+                        .await;
                     // If this is a single-chunk template, we might be able to
                     // load directly from the source, since we support file
                     // streams natively. In that case, the stream will be thrown
@@ -717,10 +2193,12 @@ impl Recipe {
                         })?
                         .boxed();
 
-                    Ok::<_, RequestBuildErrorKind>((
-                        field.clone(),
-                        BodyStream { stream, source },
-                    ))
+                    Ok::<_, RequestBuildErrorKind>(RenderedFormPart {
+                        field: field.clone(),
+                        stream: BodyStream { stream, source },
+                        content_type,
+                        filename,
+                    })
                 });
                 let rendered = try_join_all(iter).await?;
                 RenderedBody::FormMultipart(rendered)
@@ -730,6 +2208,56 @@ impl Recipe {
     }
 }
 
+impl GraphQlBody {
+    /// Render the query and variables into the standard JSON request body:
+    /// `{"query": ..., "variables": ..., "operationName": ...}`. Absent fields
+    /// are omitted.
+    async fn render(
+        &self,
+        options: &BuildOptions,
+        context: &SingleRenderContext<'_>,
+    ) -> Result<serde_json::Value, RequestBuildErrorKind> {
+        // Loading the schema replaces the whole operation. Variables and the
+        // operation name refer to the recipe's query, so they're dropped too
+        if options.graphql_introspection {
+            return Ok(
+                serde_json::json!({"query": graphql::INTROSPECTION_QUERY}),
+            );
+        }
+
+        // Use overrides if given. Variables are reparsed as JSON
+        let query = options.body.as_ref().unwrap_or(&self.query);
+        let override_variables: Option<JsonTemplate> = options
+            .graphql_variables
+            .as_ref()
+            .map(|template| template.display().parse())
+            .transpose()?;
+        let variables = override_variables.as_ref().or(self.variables.as_ref());
+
+        let mut body = serde_json::Map::new();
+        let query = query
+            .render_string(context)
+            .await
+            .map_err(RequestBuildErrorKind::BodyRender)?;
+        body.insert("query".into(), query.into());
+        if let Some(variables) = variables {
+            let variables = variables
+                .render(context)
+                .await
+                .map_err(RequestBuildErrorKind::BodyRender)?;
+            body.insert("variables".into(), variables);
+        }
+        if let Some(operation_name) = &self.operation_name {
+            let operation_name = operation_name
+                .render_string(context)
+                .await
+                .map_err(RequestBuildErrorKind::BodyRender)?;
+            body.insert("operationName".into(), operation_name.into());
+        }
+        Ok(body.into())
+    }
+}
+
 impl Authentication<String> {
     fn apply(self, builder: RequestBuilder) -> RequestBuilder {
         match self {
@@ -737,7 +2265,95 @@ impl Authentication<String> {
                 builder.basic_auth(username, password)
             }
             Authentication::Bearer { token } => builder.bearer_auth(token),
+            Authentication::OAuth2(_) => {
+                unreachable!(
+                    "OAuth2 is resolved to a bearer token before building"
+                )
+            }
+            Authentication::Digest { .. } | Authentication::Ntlm { .. } => {
+                unreachable!("Challenge authentication is applied by transport")
+            }
+        }
+    }
+}
+
+impl OAuth2 {
+    /// Render all templates in the OAuth2 settings
+    async fn render(
+        &self,
+        context: &SingleRenderContext<'_>,
+    ) -> Result<OAuth2<String>, RequestBuildErrorKind> {
+        let grant = async {
+            match &self.grant {
+                OAuth2Grant::ClientCredentials => {
+                    Ok(OAuth2Grant::ClientCredentials)
+                }
+                OAuth2Grant::AuthorizationCode {
+                    authorization_url,
+                    redirect_uri,
+                } => {
+                    let (authorization_url, redirect_uri) = try_join!(
+                        render_field(
+                            "authorization_url",
+                            authorization_url,
+                            context
+                        ),
+                        render_option_field(
+                            "redirect_uri",
+                            redirect_uri.as_ref(),
+                            context,
+                        ),
+                    )?;
+                    Ok(OAuth2Grant::AuthorizationCode {
+                        authorization_url,
+                        redirect_uri,
+                    })
+                }
+            }
+        };
+        let (grant, token_url, client_id, client_secret, scope) = try_join!(
+            grant,
+            render_field("token_url", &self.token_url, context),
+            render_field("client_id", &self.client_id, context),
+            render_option_field(
+                "client_secret",
+                self.client_secret.as_ref(),
+                context,
+            ),
+            render_option_field("scope", self.scope.as_ref(), context),
+        )?;
+        Ok(OAuth2 {
+            grant,
+            token_url,
+            client_id,
+            client_secret,
+            scope,
+        })
+    }
+}
+
+/// Render a single OAuth2 field to a string
+async fn render_field(
+    field: &'static str,
+    template: &Template,
+    context: &SingleRenderContext<'_>,
+) -> Result<String, RequestBuildErrorKind> {
+    template.render_string(context).await.map_err(|error| {
+        RequestBuildErrorKind::AuthOAuth2Render { field, error }
+    })
+}
+
+/// Render an optional OAuth2 field to a string
+async fn render_option_field(
+    field: &'static str,
+    template: Option<&Template>,
+    context: &SingleRenderContext<'_>,
+) -> Result<Option<String>, RequestBuildErrorKind> {
+    match template {
+        Some(template) => {
+            render_field(field, template, context).await.map(Some)
         }
+        None => Ok(None),
     }
 }
 
@@ -752,20 +2368,30 @@ enum RenderedBody {
     /// streamed over the network as the data becomes available. Only certain
     /// data sources (such as files and commands) can be streamed.
     Stream(BodyStream),
+    /// A file that will be streamed from disk. Unlike a generic stream, the
+    /// size is known up front so it can be sent as `Content-Length`
+    File {
+        path: PathBuf,
+        /// `Content-Type` guessed from the file extension. `None` if the
+        /// recipe sets the header explicitly, or the extension is unknown.
+        content_type: Option<Mime>,
+    },
     /// JSON body
     Json(serde_json::Value),
     /// Field:value mapping. Value is `String` because only string data can be
     /// URL-encoded
     FormUrlencoded(Vec<(String, String)>),
     /// Field:value mapping. Values can be arbitrary bytes or a binary stream
-    FormMultipart(Vec<(String, BodyStream)>),
+    FormMultipart(Vec<RenderedFormPart>),
 }
 
 impl RenderedBody {
-    /// Add this body to the builder
+    /// Add this body to the builder. Files read directly (rather than via a
+    /// template stream) are recorded with the auditor.
     async fn apply(
         self,
         builder: RequestBuilder,
+        auditor: &Auditor,
     ) -> Result<RequestBuilder, RequestBuildErrorKind> {
         // Set body. The variant tells us _how_ to set it
         match self {
@@ -774,6 +2400,25 @@ impl RenderedBody {
                 let body = Body::wrap_stream(stream.stream);
                 Ok(builder.body(body))
             }
+            RenderedBody::File { path, content_type } => {
+                let io_error = |error| RequestBuildErrorKind::BodyFile {
+                    path: path.clone(),
+                    error,
+                };
+                auditor
+                    .record(TemplateSource::File, path.display().to_string());
+                let file = File::open(&path).await.map_err(io_error)?;
+                let metadata = file.metadata().await.map_err(io_error)?;
+                // A streamed body has no known length, which would switch the
+                // request to chunked encoding. We know the size, so use it
+                let mut builder =
+                    builder.header(header::CONTENT_LENGTH, metadata.len());
+                if let Some(content_type) = content_type {
+                    builder = builder
+                        .header(header::CONTENT_TYPE, content_type.as_ref());
+                }
+                Ok(builder.body(Body::wrap_stream(ReaderStream::new(file))))
+            }
             RenderedBody::Json(json) => Ok(builder.json(&json)),
             RenderedBody::FormUrlencoded(fields) => Ok(builder.form(&fields)),
             RenderedBody::FormMultipart(fields) => {
@@ -787,18 +2432,45 @@ impl RenderedBody {
                     tests::MULTIPART_BOUNDARY.set(form.boundary().to_owned());
                 }
 
-                for (field, stream) in fields {
+                for RenderedFormPart {
+                    field,
+                    stream,
+                    content_type,
+                    filename,
+                } in fields
+                {
                     // Convert the stream to a form part
-                    let part = match stream.source {
+                    let mut part = match stream.source {
                         // Files can be handled natively by reqwest, which gets
                         // bonus support for Content-Type and
-                        // Content-Disposition goodies
-                        Some(StreamSource::File { path }) => Part::file(path)
-                            .await
-                            .map_err(RequestBuildErrorKind::BodyFileStream)?,
+                        // Content-Disposition goodies. The template stream is
+                        // dropped, so we record the access instead of it
+                        Some(StreamSource::File { path }) => {
+                            auditor.record(
+                                TemplateSource::File,
+                                path.display().to_string(),
+                            );
+                            Part::file(path).await.map_err(
+                                RequestBuildErrorKind::BodyFileStream,
+                            )?
+                        }
                         // Any other stream can be streamed directly as bytes
                         _ => Part::stream(Body::wrap_stream(stream.stream)),
                     };
+                    // Explicit headers beat whatever was derived from the file
+                    if let Some(content_type) = content_type {
+                        part =
+                            part.mime_str(&content_type).map_err(|error| {
+                                RequestBuildErrorKind::BodyFormContentType {
+                                    field: field.clone(),
+                                    content_type,
+                                    error,
+                                }
+                            })?;
+                    }
+                    if let Some(filename) = filename {
+                        part = part.file_name(filename);
+                    }
                     form = form.part(field, part);
                 }
 
@@ -808,6 +2480,16 @@ impl RenderedBody {
     }
 }
 
+/// A rendered field of a `multipart/form-data` body
+struct RenderedFormPart {
+    field: String,
+    stream: BodyStream,
+    /// Explicit `Content-Type` of the part
+    content_type: Option<String>,
+    /// Explicit filename for the part's `Content-Disposition` header
+    filename: Option<String>,
+}
+
 /// A stream to be used in a request body
 struct BodyStream {
     /// Stream of bytes
@@ -0,0 +1,300 @@
+//! Response assertions. A recipe can declare checks on the status code,
+//! headers, body, and latency of its responses. Failures don't stop the
+//! response from being stored; they're shown alongside it in the TUI, and
+//! make the CLI exit with an error.
+
+use crate::{collection::Assertions, http::Exchange};
+use serde_json::Value;
+use serde_json_path::JsonPath;
+use thiserror::Error;
+
+impl Exchange {
+    /// Run a recipe's assertions against this exchange
+    pub fn check_assertions(
+        &self,
+        assertions: &Assertions,
+    ) -> AssertionResults {
+        let response = &self.response;
+        let mut failures = Vec::new();
+
+        if !assertions.status.is_empty()
+            && !assertions.status.contains(&response.status.as_u16())
+        {
+            failures.push(AssertionFailure::Status {
+                expected: assertions.status.clone(),
+                actual: response.status.as_u16(),
+            });
+        }
+
+        for (name, expected) in &assertions.headers {
+            let actual = response
+                .headers
+                .get(name.as_str())
+                .map(|value| String::from_utf8_lossy(value.as_bytes()));
+            match actual {
+                Some(actual) if actual == expected.as_str() => {}
+                Some(actual) => {
+                    failures.push(AssertionFailure::HeaderMismatch {
+                        name: name.clone(),
+                        expected: expected.clone(),
+                        actual: actual.into_owned(),
+                    });
+                }
+                None => failures.push(AssertionFailure::HeaderMissing {
+                    name: name.clone(),
+                }),
+            }
+        }
+
+        if !assertions.body.is_empty() {
+            // Parse once for all queries
+            match serde_json::from_slice::<Value>(response.body.bytes()) {
+                Ok(json) => {
+                    for (query, expected) in &assertions.body {
+                        if let Err(failure) = check_body(&json, query, expected)
+                        {
+                            failures.push(failure);
+                        }
+                    }
+                }
+                Err(error) => failures
+                    .push(AssertionFailure::InvalidJson(error.to_string())),
+            }
+        }
+
+        if let Some(max) = assertions.max_latency_ms {
+            let actual = self.duration().num_milliseconds().max(0) as u64;
+            if actual > max {
+                failures.push(AssertionFailure::Latency { max, actual });
+            }
+        }
+
+        AssertionResults {
+            total: assertions.len(),
+            failures,
+        }
+    }
+}
+
+/// Run a single JSONPath assertion against a parsed body
+fn check_body(
+    json: &Value,
+    query: &str,
+    expected: &str,
+) -> Result<(), AssertionFailure> {
+    let path =
+        JsonPath::parse(query).map_err(|error| AssertionFailure::Query {
+            query: query.to_owned(),
+            error: error.to_string(),
+        })?;
+    // Follow the same rules as `jsonpath()`: a single result is unpacked and
+    // multiple results are compared as an array
+    let nodes = path.query(json).all();
+    let actual = match nodes.as_slice() {
+        [] => {
+            return Err(AssertionFailure::NoMatch {
+                query: query.to_owned(),
+            });
+        }
+        [node] => to_text(node),
+        _ => to_text(&Value::Array(nodes.into_iter().cloned().collect())),
+    };
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(AssertionFailure::BodyMismatch {
+            query: query.to_owned(),
+            expected: expected.to_owned(),
+            actual,
+        })
+    }
+}
+
+/// Stringify a JSON value for comparison. Strings are compared without quotes,
+/// so they match what's written in the collection.
+fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Outcome of running a recipe's assertions against one exchange
+#[derive(Debug, Default, PartialEq)]
+pub struct AssertionResults {
+    /// Number of assertions that were run
+    pub total: usize,
+    /// Every assertion that didn't pass
+    pub failures: Vec<AssertionFailure>,
+}
+
+impl AssertionResults {
+    /// Did every assertion pass?
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// A response didn't satisfy one of its recipe's assertions
+#[derive(Debug, Error, PartialEq)]
+pub enum AssertionFailure {
+    #[error("Status is {actual}; expected one of {expected:?}")]
+    Status { expected: Vec<u16>, actual: u16 },
+    #[error("Header `{name}` is `{actual}`; expected `{expected}`")]
+    HeaderMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("Header `{name}` is missing")]
+    HeaderMissing { name: String },
+    #[error("`{query}` is `{actual}`; expected `{expected}`")]
+    BodyMismatch {
+        query: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("No results for JSONPath query `{query}`")]
+    NoMatch { query: String },
+    #[error("Invalid JSONPath query `{query}`: {error}")]
+    Query { query: String, error: String },
+    #[error("Body assertions require a JSON body: {0}")]
+    InvalidJson(String),
+    #[error("Request took {actual}ms; max is {max}ms")]
+    Latency { max: u64, actual: u64 },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{http::ResponseRecord, test_util::header_map};
+    use chrono::TimeDelta;
+    use indexmap::indexmap;
+    use reqwest::StatusCode;
+    use rstest::rstest;
+    use serde_json::json;
+    use slumber_util::Factory;
+    use std::sync::Arc;
+
+    fn exchange() -> Exchange {
+        let response = ResponseRecord {
+            headers: header_map([("content-type", "application/json")]),
+            body: json!({"id": 3, "name": "Frodo", "tags": ["a", "b"]}).into(),
+            ..ResponseRecord::factory(StatusCode::OK)
+        };
+        let exchange = Exchange::factory(());
+        Exchange {
+            response: Arc::new(response),
+            end_time: exchange.start_time + TimeDelta::milliseconds(200),
+            ..exchange
+        }
+    }
+
+    /// Each kind of assertion passes when the response matches
+    #[test]
+    fn test_pass() {
+        let assertions = Assertions {
+            status: vec![200, 201],
+            headers: indexmap! {
+                "Content-Type".into() => "application/json".into(),
+            },
+            body: indexmap! {
+                "$.id".into() => "3".into(),
+                "$.name".into() => "Frodo".into(),
+                "$.tags[*]".into() => r#"["a","b"]"#.into(),
+            },
+            max_latency_ms: Some(500),
+        };
+        assert_eq!(
+            exchange().check_assertions(&assertions),
+            AssertionResults {
+                total: 6,
+                failures: vec![],
+            }
+        );
+    }
+
+    /// Each kind of assertion fails when the response doesn't match
+    #[rstest]
+    #[case::status(
+        Assertions { status: vec![404], ..Assertions::default() },
+        AssertionFailure::Status { expected: vec![404], actual: 200 },
+    )]
+    #[case::header_mismatch(
+        Assertions {
+            headers: indexmap! {"content-type".into() => "text/plain".into()},
+            ..Assertions::default()
+        },
+        AssertionFailure::HeaderMismatch {
+            name: "content-type".into(),
+            expected: "text/plain".into(),
+            actual: "application/json".into(),
+        },
+    )]
+    #[case::header_missing(
+        Assertions {
+            headers: indexmap! {"x-missing".into() => String::new()},
+            ..Assertions::default()
+        },
+        AssertionFailure::HeaderMissing { name: "x-missing".into() },
+    )]
+    #[case::body_mismatch(
+        Assertions {
+            body: indexmap! {"$.name".into() => "Sam".into()},
+            ..Assertions::default()
+        },
+        AssertionFailure::BodyMismatch {
+            query: "$.name".into(),
+            expected: "Sam".into(),
+            actual: "Frodo".into(),
+        },
+    )]
+    #[case::body_no_match(
+        Assertions {
+            body: indexmap! {"$.missing".into() => String::new()},
+            ..Assertions::default()
+        },
+        AssertionFailure::NoMatch { query: "$.missing".into() },
+    )]
+    #[case::latency(
+        Assertions { max_latency_ms: Some(100), ..Assertions::default() },
+        AssertionFailure::Latency { max: 100, actual: 200 },
+    )]
+    fn test_fail(
+        #[case] assertions: Assertions,
+        #[case] expected: AssertionFailure,
+    ) {
+        let results = exchange().check_assertions(&assertions);
+        assert_eq!(results.total, 1);
+        assert_eq!(results.failures, [expected]);
+    }
+
+    /// Invalid queries and non-JSON bodies are failures, not errors
+    #[test]
+    fn test_invalid_body() {
+        let assertions = Assertions {
+            body: indexmap! {"$[".into() => String::new()},
+            ..Assertions::default()
+        };
+        let results = exchange().check_assertions(&assertions);
+        assert!(
+            results.failures[0]
+                .to_string()
+                .starts_with("Invalid JSONPath query `$[`"),
+            "{results:?}"
+        );
+
+        let exchange = Exchange {
+            response: Arc::new(ResponseRecord {
+                body: "not json".into(),
+                ..ResponseRecord::factory(StatusCode::OK)
+            }),
+            ..exchange()
+        };
+        let results = exchange.check_assertions(&assertions);
+        assert!(
+            matches!(results.failures[..], [AssertionFailure::InvalidJson(_)]),
+            "{results:?}"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//! Response captures. Recipes can declare values to extract from their
+//! responses with `capture`. Captured values are stored for the rest of the
+//! session and can be referenced in templates like a profile field, which
+//! covers the common "log in, then use the token" workflow without any
+//! chaining.
+
+use crate::{
+    collection::{CaptureSelector, Collection},
+    http::Exchange,
+};
+use indexmap::IndexMap;
+use serde_json_path::{JsonPath, ParseError};
+use slumber_template::Value;
+use std::sync::{Arc, Mutex, PoisonError};
+use thiserror::Error;
+
+/// Values captured from responses, keyed by capture name. The same instance
+/// should be shared between all template contexts for a session, so a value
+/// captured from one response is visible to all subsequent renders. Clones are
+/// cheap and share the same storage.
+#[derive(Clone, Debug, Default)]
+pub struct CaptureStore(Arc<Mutex<IndexMap<String, Value>>>);
+
+impl CaptureStore {
+    /// Get the captured value for a name
+    pub fn get(&self, name: &str) -> Option<Value> {
+        self.lock().get(name).cloned()
+    }
+
+    /// Get all captured values, in the order they were first captured
+    pub fn entries(&self) -> Vec<(String, Value)> {
+        self.lock()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Extract each value declared in the `capture` field of the exchange's
+    /// recipe, and store it under its name. Values are only captured from
+    /// successful (2xx) responses. If any captures fail, the others are still
+    /// stored and the first error is returned.
+    pub fn capture(
+        &self,
+        exchange: &Exchange,
+        collection: &Collection,
+    ) -> Result<(), CaptureError> {
+        let Some(recipe) =
+            collection.recipes.get_recipe(&exchange.request.recipe_id)
+        else {
+            return Ok(());
+        };
+        if !exchange.response.status.is_success() {
+            return Ok(());
+        }
+
+        let mut first_error = None;
+        for (name, selector) in &recipe.capture {
+            match exchange.select(selector) {
+                Ok(value) => {
+                    self.lock().insert(name.clone(), value);
+                }
+                Err(kind) => {
+                    first_error.get_or_insert(CaptureError {
+                        name: name.clone(),
+                        kind,
+                    });
+                }
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, IndexMap<String, Value>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Exchange {
+    /// Get the value of a capture selector from this exchange's response
+    fn select(
+        &self,
+        selector: &CaptureSelector,
+    ) -> Result<Value, CaptureErrorKind> {
+        match selector {
+            CaptureSelector::Body(query) => {
+                let path = JsonPath::parse(query).map_err(|error| {
+                    CaptureErrorKind::Query {
+                        query: query.clone(),
+                        error,
+                    }
+                })?;
+                let json: serde_json::Value =
+                    serde_json::from_slice(self.response.body.bytes())
+                        .map_err(CaptureErrorKind::Json)?;
+                // Follow the same rules as `jsonpath()`: a single result is
+                // unpacked and multiple results are returned as an array
+                let mut nodes = path.query(&json).all();
+                match nodes.len() {
+                    0 => Err(CaptureErrorKind::NoMatch {
+                        query: query.clone(),
+                    }),
+                    1 => Ok(Value::from_json(nodes.remove(0).clone())),
+                    _ => Ok(Value::Array(
+                        nodes
+                            .into_iter()
+                            .map(|node| Value::from_json(node.clone()))
+                            .collect(),
+                    )),
+                }
+            }
+            CaptureSelector::Header(header) => {
+                let value =
+                    self.response.headers.get(header.as_str()).ok_or_else(
+                        || CaptureErrorKind::MissingHeader {
+                            header: header.clone(),
+                        },
+                    )?;
+                Ok(String::from_utf8_lossy(value.as_bytes())
+                    .into_owned()
+                    .into())
+            }
+        }
+    }
+}
+
+/// Error extracting a captured value from a response
+#[derive(Debug, Error)]
+#[error("Error capturing `{name}`")]
+pub struct CaptureError {
+    /// Name of the capture that failed
+    pub name: String,
+    #[source]
+    pub kind: CaptureErrorKind,
+}
+
+#[derive(Debug, Error)]
+pub enum CaptureErrorKind {
+    #[error("Invalid JSONPath query `{query}`")]
+    Query {
+        query: String,
+        #[source]
+        error: ParseError,
+    },
+    #[error("Response body is not valid JSON")]
+    Json(#[source] serde_json::Error),
+    #[error("No results for JSONPath query `{query}`")]
+    NoMatch { query: String },
+    #[error("Response has no header `{header}`")]
+    MissingHeader { header: String },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collection::Recipe,
+        http::{RequestRecord, ResponseBody, ResponseRecord},
+        test_util::{by_id, header_map},
+    };
+    use indexmap::indexmap;
+    use reqwest::StatusCode;
+    use rstest::rstest;
+    use serde_json::json;
+    use slumber_util::{Factory, assert_err};
+
+    /// Build a collection with a single recipe that captures the given values,
+    /// and an exchange for that recipe
+    fn setup(
+        capture: IndexMap<String, CaptureSelector>,
+        status: StatusCode,
+    ) -> (Collection, Exchange) {
+        let recipe = Recipe {
+            capture,
+            ..Recipe::factory(())
+        };
+        let request = RequestRecord::factory((None, recipe.id.clone()));
+        let response = ResponseRecord {
+            status,
+            headers: header_map([("x-request-id", "abc123")]),
+            body: ResponseBody::from(json!({
+                "token": "hunter2",
+                "count": 3,
+                "items": [{"id": 1}, {"id": 2}],
+            })),
+            ..ResponseRecord::factory(request.id)
+        };
+        let exchange = Exchange::factory((request, response));
+        let collection = Collection {
+            recipes: by_id([recipe]).into(),
+            ..Collection::factory(())
+        };
+        (collection, exchange)
+    }
+
+    fn selector(s: &str) -> CaptureSelector {
+        s.parse().unwrap()
+    }
+
+    /// Each selector type extracts the expected value
+    #[rstest]
+    #[case::body_string("$.token", "hunter2".into())]
+    #[case::body_number("$.count", 3.into())]
+    #[case::body_multiple(
+        "$.items[*].id",
+        Value::Array(vec![1.into(), 2.into()]),
+    )]
+    #[case::header("header:X-Request-ID", "abc123".into())]
+    fn test_capture(#[case] selector_str: &str, #[case] expected: Value) {
+        let (collection, exchange) = setup(
+            indexmap! {"value".into() => selector(selector_str)},
+            StatusCode::OK,
+        );
+        let store = CaptureStore::default();
+        store.capture(&exchange, &collection).unwrap();
+        assert_eq!(store.get("value"), Some(expected));
+    }
+
+    /// Failed captures are reported, but don't block the others
+    #[rstest]
+    #[case::no_match("$.missing", "No results for JSONPath query `$.missing`")]
+    #[case::invalid_query("$[", "Invalid JSONPath query `$[`")]
+    #[case::missing_header("header:x-missing", "Response has no header")]
+    fn test_capture_error(#[case] selector_str: &str, #[case] expected: &str) {
+        let (collection, exchange) = setup(
+            indexmap! {
+                "bad".into() => selector(selector_str),
+                "token".into() => selector("$.token"),
+            },
+            StatusCode::OK,
+        );
+        let store = CaptureStore::default();
+        let error = store.capture(&exchange, &collection).unwrap_err();
+        assert_eq!(error.name, "bad");
+        assert_err!(Err::<(), _>(error.kind), expected);
+        assert_eq!(store.get("token"), Some("hunter2".into()));
+    }
+
+    /// Nothing is captured from an error response
+    #[test]
+    fn test_capture_error_response() {
+        let (collection, exchange) = setup(
+            indexmap! {"token".into() => selector("$.token")},
+            StatusCode::UNAUTHORIZED,
+        );
+        let store = CaptureStore::default();
+        store.capture(&exchange, &collection).unwrap();
+        assert_eq!(store.get("token"), None);
+    }
+}
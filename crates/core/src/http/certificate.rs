@@ -0,0 +1,186 @@
+//! TLS client certificates, for servers that require mutual TLS. A certificate
+//! can be attached to a recipe, a profile, or a host (via the config). Because
+//! reqwest binds the certificate to the client rather than the request, each
+//! certificate gets its own [ReqwestTransport](super::ReqwestTransport).
+
+use crate::{
+    collection::{self, Recipe},
+    http::RequestBuildErrorKind,
+    render::TemplateContext,
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use indexmap::IndexMap;
+use p12_keystore::{KeyStore, Pkcs12ImportPolicy};
+use reqwest::Url;
+use slumber_config::HttpEngineConfig;
+use slumber_util::paths::expand_home;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+use tokio::fs;
+
+/// File extensions of PKCS#12 archives. Anything else is assumed to be PEM
+const PKCS12_EXTENSIONS: &[&str] = &["p12", "pfx"];
+
+/// Client certificates from the config
+#[derive(Clone, Debug)]
+pub(super) struct ClientCertificates {
+    /// Certificates from the config, keyed by hostname
+    hosts: IndexMap<String, slumber_config::ClientCertificate>,
+}
+
+impl ClientCertificates {
+    pub fn new(config: &HttpEngineConfig) -> Self {
+        Self {
+            hosts: config.client_certificates.clone(),
+        }
+    }
+
+    /// Get the certificate that should be presented for a request. The
+    /// recipe's certificate takes precedence, then the selected profile's,
+    /// then the config's for the URL's host.
+    ///
+    /// Certificates from the collection are disabled in a sandboxed
+    /// collection, but that's checked before this is called. Certificates
+    /// from the config are always allowed.
+    pub fn get(
+        &self,
+        recipe: &Recipe,
+        context: &TemplateContext,
+        url: &Url,
+    ) -> Option<CertificatePaths> {
+        let collection = recipe
+            .certificate
+            .as_ref()
+            .or_else(|| context.current_profile()?.certificate.as_ref());
+        if let Some(certificate) = collection {
+            return Some(CertificatePaths::collection(
+                certificate,
+                &context.root_dir,
+            ));
+        }
+        url.host_str()
+            .and_then(|host| self.hosts.get(host))
+            .map(CertificatePaths::config)
+    }
+}
+
+/// Absolute paths to the files of a client certificate
+#[derive(Debug, PartialEq)]
+pub(super) struct CertificatePaths {
+    pub certificate: PathBuf,
+    pub key: Option<PathBuf>,
+    /// Password for a PKCS#12 archive
+    pub password: Option<String>,
+}
+
+impl CertificatePaths {
+    /// Resolve paths from the collection, relative to the collection file
+    fn collection(
+        certificate: &collection::ClientCertificate,
+        root_dir: &Path,
+    ) -> Self {
+        let resolve = |path: &str| root_dir.join(expand_home(Path::new(path)));
+        Self {
+            certificate: resolve(&certificate.certificate),
+            key: certificate.key.as_deref().map(resolve),
+            password: certificate.password.clone(),
+        }
+    }
+
+    /// Resolve paths from the config. These should be absolute already
+    fn config(certificate: &slumber_config::ClientCertificate) -> Self {
+        let resolve =
+            |path: &str| expand_home(PathBuf::from(path)).into_owned();
+        Self {
+            certificate: resolve(&certificate.certificate),
+            key: certificate.key.as_deref().map(resolve),
+            password: certificate.password.clone(),
+        }
+    }
+
+    /// Is the certificate a PKCS#12 archive? This is based on the file
+    /// extension, because the contents can't be reliably sniffed.
+    pub fn is_pkcs12(&self) -> bool {
+        self.certificate
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                PKCS12_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+            })
+    }
+
+    /// Read the certificate and key files into a single PEM bundle. The files
+    /// are read on every call, so changes are picked up on the next request.
+    /// A PKCS#12 archive is decrypted and converted to PEM, so it can be used
+    /// with rustls.
+    pub async fn load(&self) -> Result<Vec<u8>, RequestBuildErrorKind> {
+        if self.is_pkcs12() {
+            // The archive contains the key, so there's nothing else to read
+            let der = read(&self.certificate).await?;
+            return pkcs12_to_pem(
+                &der,
+                self.password.as_deref().unwrap_or_default(),
+            )
+            .map_err(|error| match error {
+                Some(error) => RequestBuildErrorKind::CertificatePkcs12 {
+                    path: self.certificate.clone(),
+                    error,
+                },
+                None => RequestBuildErrorKind::CertificatePkcs12NoKey {
+                    path: self.certificate.clone(),
+                },
+            });
+        }
+
+        // Combine the certificate and key into a single PEM bundle
+        let mut pem = read(&self.certificate).await?;
+        if let Some(key) = &self.key {
+            pem.push(b'\n');
+            pem.extend(read(key).await?);
+        }
+        Ok(pem)
+    }
+}
+
+/// Decrypt a DER-encoded PKCS#12 archive and re-encode its private key and
+/// certificate chain as PEM. Return `Err(None)` if the archive has no private
+/// key with a matching certificate.
+fn pkcs12_to_pem(
+    der: &[u8],
+    password: &str,
+) -> Result<Vec<u8>, Option<p12_keystore::error::Error>> {
+    let key_store =
+        KeyStore::from_pkcs12(der, password, Pkcs12ImportPolicy::Strict)?;
+    let (_, chain) = key_store.private_key_chain().ok_or(None)?;
+    let mut pem = String::new();
+    for certificate in chain.certs() {
+        pem_encode(&mut pem, "CERTIFICATE", certificate.as_der());
+    }
+    pem_encode(&mut pem, "PRIVATE KEY", chain.key().as_der());
+    Ok(pem.into_bytes())
+}
+
+/// Append a PEM block to a string
+fn pem_encode(pem: &mut String, label: &str, der: &[u8]) {
+    let encoded = BASE64_STANDARD.encode(der);
+    let _ = writeln!(pem, "-----BEGIN {label}-----");
+    // PEM lines are wrapped at 64 characters. Base64 is ASCII, so splitting
+    // bytes can't break a character
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(str::from_utf8(line).expect("Base64 is ASCII"));
+        pem.push('\n');
+    }
+    let _ = writeln!(pem, "-----END {label}-----");
+}
+
+/// Read a certificate or key file
+async fn read(path: &Path) -> Result<Vec<u8>, RequestBuildErrorKind> {
+    fs::read(path).await.map_err(|error| {
+        RequestBuildErrorKind::CertificateRead {
+            path: path.to_owned(),
+            error,
+        }
+    })
+}
@@ -0,0 +1,415 @@
+//! Challenge/response authentication schemes. For Digest and NTLM, the
+//! `Authorization` header is derived from the server's `WWW-Authenticate`
+//! challenge, so it can't be added when the request is built. Instead, the
+//! selected [Transport] is wrapped so that the request is sent once to get a
+//! challenge, then again with credentials computed from that challenge.
+
+mod digest;
+mod ntlm;
+
+use crate::{
+    collection::Authentication,
+    http::{Transport, TransportError},
+};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use futures::{FutureExt, future::BoxFuture};
+use reqwest::{
+    Request, Response, StatusCode,
+    header::{self, HeaderMap, HeaderValue},
+};
+use std::{collections::HashMap, sync::Arc};
+use thiserror::Error;
+use tracing::info;
+
+/// Credentials for an authentication scheme that requires a challenge from
+/// the server before the request can be authorized
+#[derive(Debug)]
+pub(super) enum ChallengeAuth {
+    Digest {
+        username: String,
+        password: String,
+    },
+    Ntlm {
+        username: String,
+        password: String,
+        domain: Option<String>,
+    },
+}
+
+impl ChallengeAuth {
+    /// Wrap a transport so its requests are authorized with these credentials
+    pub(super) fn wrap(
+        self,
+        transport: Arc<dyn Transport>,
+    ) -> Arc<dyn Transport> {
+        Arc::new(ChallengeTransport {
+            inner: transport,
+            auth: Arc::new(self),
+        })
+    }
+
+    /// Send a request, answering the server's challenge if it issues one. If
+    /// the server doesn't challenge us, or uses a scheme we don't support, its
+    /// response is returned as-is.
+    async fn send(
+        &self,
+        transport: &dyn Transport,
+        request: Request,
+    ) -> Result<Response, TransportError> {
+        // The initial request is unauthenticated, to find out which schemes
+        // the server supports
+        let response = transport.send(try_clone(&request)?).await?;
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        let challenges = parse_challenges(response.headers());
+
+        match self {
+            Self::Digest { username, password } => {
+                let Some(challenge) =
+                    digest::DigestChallenge::select(&challenges)
+                else {
+                    return Ok(response);
+                };
+                info!(algorithm = %challenge.algorithm(), "Answering Digest challenge");
+                discard(response).await;
+                let authorization = challenge.authorize(
+                    username,
+                    password,
+                    &request,
+                    &digest::cnonce(),
+                );
+                transport.send(authorize(request, &authorization)).await
+            }
+            Self::Ntlm {
+                username,
+                password,
+                domain,
+            } => {
+                // Negotiate usually means Kerberos, but Windows servers also
+                // accept raw NTLM messages through it
+                let Some(scheme) =
+                    ["NTLM", "Negotiate"].into_iter().find(|scheme| {
+                        challenges
+                            .iter()
+                            .any(|challenge| challenge.is_scheme(scheme))
+                    })
+                else {
+                    return Ok(response);
+                };
+                info!(scheme, "Answering NTLM challenge");
+                discard(response).await;
+
+                // NTLM is a three-message handshake. The first message
+                // advertises our capabilities, and the server's response
+                // contains the actual challenge
+                let negotiate =
+                    BASE64_STANDARD.encode(ntlm::negotiate_message());
+                let response = transport
+                    .send(authorize(
+                        try_clone(&request)?,
+                        &format!("{scheme} {negotiate}"),
+                    ))
+                    .await?;
+                if response.status() != StatusCode::UNAUTHORIZED {
+                    return Ok(response);
+                }
+                let Some(token) = parse_challenges(response.headers())
+                    .into_iter()
+                    .find(|challenge| challenge.is_scheme(scheme))
+                    .and_then(|challenge| challenge.token)
+                else {
+                    return Ok(response);
+                };
+                // The handshake is bound to the connection, so the response
+                // has to be read in full for the connection to be reused
+                discard(response).await;
+
+                let challenge = BASE64_STANDARD
+                    .decode(token)
+                    .map_err(|_| ChallengeError::NtlmChallenge)?;
+                let authenticate = ntlm::authenticate_message(
+                    &challenge,
+                    username,
+                    password,
+                    domain.as_deref(),
+                )?;
+                let authorization = format!(
+                    "{scheme} {}",
+                    BASE64_STANDARD.encode(authenticate)
+                );
+                transport.send(authorize(request, &authorization)).await
+            }
+        }
+    }
+}
+
+/// Extract the credentials if the authentication uses challenge/response.
+/// Otherwise, give it back so it can be applied to the request directly.
+impl TryFrom<Authentication<String>> for ChallengeAuth {
+    type Error = Authentication<String>;
+
+    fn try_from(
+        authentication: Authentication<String>,
+    ) -> Result<Self, Self::Error> {
+        match authentication {
+            Authentication::Digest { username, password } => {
+                Ok(Self::Digest { username, password })
+            }
+            Authentication::Ntlm {
+                username,
+                password,
+                domain,
+            } => Ok(Self::Ntlm {
+                username,
+                password,
+                domain,
+            }),
+            authentication => Err(authentication),
+        }
+    }
+}
+
+/// A [Transport] that answers authentication challenges before handing the
+/// final response back
+#[derive(Debug)]
+struct ChallengeTransport {
+    inner: Arc<dyn Transport>,
+    auth: Arc<ChallengeAuth>,
+}
+
+impl Transport for ChallengeTransport {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let inner = Arc::clone(&self.inner);
+        let auth = Arc::clone(&self.auth);
+        async move { auth.send(&*inner, request).await }.boxed()
+    }
+}
+
+/// Error answering an authentication challenge
+#[derive(Debug, Error)]
+pub enum ChallengeError {
+    /// Challenge/response requires sending the request more than once, but
+    /// streamed bodies can only be sent once
+    #[error(
+        "Digest and NTLM authentication can't be used with a streamed request \
+        body, because the request has to be sent more than once"
+    )]
+    StreamedBody,
+    /// Server's NTLM challenge message is malformed
+    #[error("Invalid NTLM challenge from server")]
+    NtlmChallenge,
+}
+
+impl From<ChallengeError> for TransportError {
+    fn from(error: ChallengeError) -> Self {
+        Self::new(error)
+    }
+}
+
+/// One challenge from a `WWW-Authenticate` header
+#[derive(Debug, Default, PartialEq)]
+struct Challenge {
+    /// Authentication scheme, e.g. `Digest`. Schemes are case-insensitive
+    scheme: String,
+    /// A single opaque value following the scheme, as used by NTLM
+    token: Option<String>,
+    /// `name=value` parameters, as used by Digest. Names are lowercased
+    /// because they're case-insensitive.
+    params: HashMap<String, String>,
+}
+
+impl Challenge {
+    fn is_scheme(&self, scheme: &str) -> bool {
+        self.scheme.eq_ignore_ascii_case(scheme)
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// Parse every challenge from the `WWW-Authenticate` headers of a response.
+/// A single header can contain multiple challenges, and challenges that can't
+/// be parsed are skipped.
+fn parse_challenges(headers: &HeaderMap) -> Vec<Challenge> {
+    headers
+        .get_all(header::WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(parse_header)
+        .collect()
+}
+
+/// Parse the challenges from a single `WWW-Authenticate` value. Commas
+/// separate both challenges and parameters, so a parameter is distinguished by
+/// the `=` following its name.
+///
+/// ```notrust
+/// challenge = auth-scheme [ 1*SP ( token68 / #auth-param ) ]
+/// ```
+fn parse_header(mut input: &str) -> Vec<Challenge> {
+    const SEPARATORS: [char; 3] = [' ', '\t', ','];
+
+    let mut challenges = Vec::new();
+    loop {
+        let (scheme, rest) = split_token(input.trim_start_matches(SEPARATORS));
+        if scheme.is_empty() {
+            break;
+        }
+        let mut challenge = Challenge {
+            scheme: scheme.to_owned(),
+            ..Challenge::default()
+        };
+        input = rest;
+
+        if let Some((token, rest)) = split_token68(input) {
+            challenge.token = Some(token.to_owned());
+            input = rest;
+        } else {
+            loop {
+                let rest = input.trim_start_matches(SEPARATORS);
+                let (name, rest) = split_token(rest);
+                // Anything other than `name=` is the start of the next
+                // challenge (or the end of the header)
+                let Some(rest) = rest
+                    .trim_start()
+                    .strip_prefix('=')
+                    .filter(|_| !name.is_empty())
+                else {
+                    break;
+                };
+                let rest = rest.trim_start();
+                let (value, rest) = if let Some(quoted) = rest.strip_prefix('"')
+                {
+                    split_quoted(quoted)
+                } else {
+                    let (value, rest) = split_token(rest);
+                    (value.to_owned(), rest)
+                };
+                challenge.params.insert(name.to_ascii_lowercase(), value);
+                input = rest;
+            }
+        }
+        challenges.push(challenge);
+    }
+    challenges
+}
+
+/// Split a leading token (RFC 9110 `tchar`s) off a string
+fn split_token(input: &str) -> (&str, &str) {
+    let is_tchar =
+        |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    let end = input.find(|c| !is_tchar(c)).unwrap_or(input.len());
+    input.split_at(end)
+}
+
+/// Split a leading token68 (e.g. base64) off a string. Return `None` if the
+/// input doesn't start with a token68 that spans the rest of the challenge.
+fn split_token68(input: &str) -> Option<(&str, &str)> {
+    let input = input.trim_start_matches([' ', '\t']);
+    let is_char = |c: char| c.is_ascii_alphanumeric() || "-._~+/".contains(c);
+    let end = input.find(|c| !is_char(c)).unwrap_or(input.len());
+    let end =
+        end + input[end..].find(|c| c != '=').unwrap_or(input.len() - end);
+    let (token, rest) = input.split_at(end);
+    let is_end =
+        rest.trim_start().is_empty() || rest.trim_start().starts_with(',');
+    (!token.is_empty() && is_end).then_some((token, rest))
+}
+
+/// Split the remainder of a quoted string off a string that follows the
+/// opening quote, unescaping backslashes. An unterminated string runs to the
+/// end of the input.
+fn split_quoted(input: &str) -> (String, &str) {
+    let mut value = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (value, &input[i + 1..]),
+            '\\' => value.extend(chars.next().map(|(_, c)| c)),
+            c => value.push(c),
+        }
+    }
+    (value, "")
+}
+
+/// Clone a request so it can be sent again later
+fn try_clone(request: &Request) -> Result<Request, ChallengeError> {
+    request.try_clone().ok_or(ChallengeError::StreamedBody)
+}
+
+/// Add an `Authorization` header to a request
+fn authorize(mut request: Request, authorization: &str) -> Request {
+    // Every component of the value is either a token or a quoted string, so
+    // it's a valid header value
+    let value = HeaderValue::from_str(authorization)
+        .expect("Authorization is a valid header value");
+    request.headers_mut().append(header::AUTHORIZATION, value);
+    request
+}
+
+/// Read the body of a challenge response and throw it away. The connection
+/// can only be reused once the response has been read in full.
+async fn discard(response: Response) {
+    let _ = response.bytes().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// `(scheme, token, params)`
+    type ExpectedChallenge = (
+        &'static str,
+        Option<&'static str>,
+        Vec<(&'static str, &'static str)>,
+    );
+
+    /// Parse challenges from `WWW-Authenticate` values
+    #[rstest]
+    #[case::scheme_only("Negotiate", vec![("Negotiate", None, vec![])])]
+    #[case::token("NTLM TlRMTVNTUAACAAAA==", vec![("NTLM", Some("TlRMTVNTUAACAAAA=="), vec![])])]
+    #[case::params(
+        r#"Digest realm="api@example.com", qop="auth, auth-int", algorithm=SHA-256"#,
+        vec![("Digest", None, vec![
+            ("realm", "api@example.com"),
+            ("qop", "auth, auth-int"),
+            ("algorithm", "SHA-256"),
+        ])],
+    )]
+    #[case::escaped(
+        r#"Digest realm="say \"hi\"""#,
+        vec![("Digest", None, vec![("realm", r#"say "hi""#)])],
+    )]
+    #[case::multiple(
+        r#"Digest Realm = "a", nonce="b", Basic realm="c", NTLM"#,
+        vec![
+            ("Digest", None, vec![("realm", "a"), ("nonce", "b")]),
+            ("Basic", None, vec![("realm", "c")]),
+            ("NTLM", None, vec![]),
+        ],
+    )]
+    #[case::empty("", vec![])]
+    fn test_parse_header(
+        #[case] header: &str,
+        #[case] expected: Vec<ExpectedChallenge>,
+    ) {
+        let expected: Vec<_> = expected
+            .into_iter()
+            .map(|(scheme, token, params)| Challenge {
+                scheme: scheme.to_owned(),
+                token: token.map(str::to_owned),
+                params: params
+                    .into_iter()
+                    .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                    .collect(),
+            })
+            .collect();
+        assert_eq!(parse_header(header), expected);
+    }
+}
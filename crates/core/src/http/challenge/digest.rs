@@ -0,0 +1,324 @@
+//! HTTP Digest authentication (RFC 7616)
+
+use crate::{http::challenge::Challenge, util::encode_hex};
+use aws_lc_rs::digest::{SHA256, SHA512_256, digest};
+use derive_more::Display;
+use md5::{Digest, Md5};
+use reqwest::{Request, Url};
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Digest challenges are only answered once per request, so the nonce count
+/// is always 1
+const NONCE_COUNT: &str = "00000001";
+
+/// A Digest challenge that we know how to answer
+#[derive(Debug, PartialEq)]
+pub(super) struct DigestChallenge<'a> {
+    realm: &'a str,
+    nonce: &'a str,
+    opaque: Option<&'a str>,
+    algorithm: Algorithm,
+    /// `None` for legacy RFC 2069 challenges, which don't specify any
+    qop: Option<Qop>,
+    /// Send a hash of the username instead of the username itself
+    userhash: bool,
+}
+
+impl<'a> DigestChallenge<'a> {
+    /// Pick the Digest challenge with the strongest algorithm that we
+    /// support. Servers can offer multiple, e.g. `SHA-256` for modern clients
+    /// and `MD5` for backward compatibility.
+    pub(super) fn select(challenges: &'a [Challenge]) -> Option<Self> {
+        challenges
+            .iter()
+            .filter_map(Self::parse)
+            // Reverse so ties go to the first challenge
+            .rev()
+            .max_by_key(|challenge| challenge.algorithm.hash)
+    }
+
+    /// Extract a Digest challenge. Return `None` if it's a different scheme
+    /// or requires an algorithm or quality of protection we don't support
+    fn parse(challenge: &'a Challenge) -> Option<Self> {
+        if !challenge.is_scheme("Digest") {
+            return None;
+        }
+        let algorithm = match challenge.param("algorithm") {
+            Some(algorithm) => Algorithm::parse(algorithm)?,
+            None => Algorithm::default(),
+        };
+        let qop = match challenge.param("qop") {
+            Some(qop) => {
+                let options: Vec<_> = qop.split(',').map(str::trim).collect();
+                if options.contains(&"auth") {
+                    Some(Qop::Auth)
+                } else if options.contains(&"auth-int") {
+                    Some(Qop::AuthInt)
+                } else {
+                    return None;
+                }
+            }
+            None => None,
+        };
+        Some(Self {
+            realm: challenge.param("realm").unwrap_or_default(),
+            nonce: challenge.param("nonce")?,
+            opaque: challenge.param("opaque"),
+            algorithm,
+            qop,
+            userhash: challenge
+                .param("userhash")
+                .is_some_and(|userhash| userhash.eq_ignore_ascii_case("true")),
+        })
+    }
+
+    pub(super) fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Compute the `Authorization` header value that answers this challenge
+    pub(super) fn authorize(
+        &self,
+        username: &str,
+        password: &str,
+        request: &Request,
+        cnonce: &str,
+    ) -> String {
+        let body = request
+            .body()
+            .and_then(reqwest::Body::as_bytes)
+            .unwrap_or_default();
+        self.authorize_parts(
+            username,
+            password,
+            request.method().as_str(),
+            &request_uri(request.url()),
+            body,
+            cnonce,
+        )
+    }
+
+    /// [Self::authorize], with the request broken into the parts that go into
+    /// the hash
+    fn authorize_parts(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        body: &[u8],
+        cnonce: &str,
+    ) -> String {
+        let Self {
+            realm,
+            nonce,
+            opaque,
+            algorithm,
+            qop,
+            userhash,
+        } = self;
+        let h = |data: &str| algorithm.hash(data.as_bytes());
+
+        let mut ha1 = h(&format!("{username}:{realm}:{password}"));
+        if algorithm.session {
+            ha1 = h(&format!("{ha1}:{nonce}:{cnonce}"));
+        }
+        let ha2 = match qop {
+            Some(Qop::AuthInt) => {
+                h(&format!("{method}:{uri}:{}", algorithm.hash(body)))
+            }
+            Some(Qop::Auth) | None => h(&format!("{method}:{uri}")),
+        };
+        let response = match qop {
+            Some(qop) => {
+                h(&format!("{ha1}:{nonce}:{NONCE_COUNT}:{cnonce}:{qop}:{ha2}"))
+            }
+            None => h(&format!("{ha1}:{nonce}:{ha2}")),
+        };
+
+        let username = if *userhash {
+            h(&format!("{username}:{realm}"))
+        } else {
+            (*username).to_owned()
+        };
+        let mut header = format!(
+            "Digest username={}, realm={}, uri={}, algorithm={algorithm}, \
+            nonce={}",
+            quote(&username),
+            quote(realm),
+            quote(uri),
+            quote(nonce),
+        );
+        // write! to a String is infallible
+        if let Some(qop) = qop {
+            let _ = write!(
+                header,
+                ", nc={NONCE_COUNT}, cnonce={}, qop={qop}",
+                quote(cnonce)
+            );
+        } else if algorithm.session {
+            let _ = write!(header, ", cnonce={}", quote(cnonce));
+        }
+        let _ = write!(header, ", response={}", quote(&response));
+        if let Some(opaque) = opaque {
+            let _ = write!(header, ", opaque={}", quote(opaque));
+        }
+        if *userhash {
+            header.push_str(", userhash=true");
+        }
+        header
+    }
+}
+
+/// Generate a random client nonce
+pub(super) fn cnonce() -> String {
+    Uuid::new_v4().simple().to_string()
+}
+
+/// Digest hash algorithm, e.g. `SHA-256-sess`
+#[derive(Copy, Clone, Debug, Default, Display, PartialEq)]
+#[display("{hash}{}", if *session { "-sess" } else { "" })]
+pub(super) struct Algorithm {
+    hash: HashFunction,
+    /// Mix the nonces into the credentials hash, so it can be reused for the
+    /// rest of the session
+    session: bool,
+}
+
+impl Algorithm {
+    /// Parse an algorithm name. Return `None` if it isn't supported
+    fn parse(name: &str) -> Option<Self> {
+        let name = name.to_ascii_uppercase();
+        let (hash, session) = match name.strip_suffix("-SESS") {
+            Some(hash) => (hash, true),
+            None => (name.as_str(), false),
+        };
+        let hash = match hash {
+            "MD5" => HashFunction::Md5,
+            "SHA-256" => HashFunction::Sha256,
+            "SHA-512-256" => HashFunction::Sha512_256,
+            _ => return None,
+        };
+        Some(Self { hash, session })
+    }
+
+    /// Hash data and encode it as hex
+    fn hash(self, data: &[u8]) -> String {
+        match self.hash {
+            HashFunction::Md5 => encode_hex(&Md5::digest(data)),
+            HashFunction::Sha256 => encode_hex(digest(&SHA256, data).as_ref()),
+            HashFunction::Sha512_256 => {
+                encode_hex(digest(&SHA512_256, data).as_ref())
+            }
+        }
+    }
+}
+
+/// Hash function underlying an [Algorithm], ordered from weakest to strongest
+#[derive(
+    Copy, Clone, Debug, Default, Display, Eq, Ord, PartialEq, PartialOrd,
+)]
+enum HashFunction {
+    #[default]
+    #[display("MD5")]
+    Md5,
+    #[display("SHA-256")]
+    Sha256,
+    #[display("SHA-512-256")]
+    Sha512_256,
+}
+
+/// Quality of protection: what the response hash covers
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+enum Qop {
+    /// Just the method and URI
+    #[display("auth")]
+    Auth,
+    /// The method, URI, and body
+    #[display("auth-int")]
+    AuthInt,
+}
+
+/// Get the request target as it appears in the request line: path and query
+fn request_uri(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_owned(),
+    }
+}
+
+/// Format a quoted string, escaping quotes and backslashes
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::challenge::parse_header;
+    use rstest::rstest;
+
+    /// Test vectors from RFC 7616 section 3.9.1
+    #[rstest]
+    #[case::md5("MD5", "8ca523f5e9506fed4657c9700eebdbec")]
+    #[case::sha256(
+        "SHA-256",
+        "753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1"
+    )]
+    fn test_authorize(
+        #[case] algorithm: &str,
+        #[case] expected_response: &str,
+    ) {
+        let challenges = parse_header(&format!(
+            "Digest realm=\"http-auth@example.org\", \
+            qop=\"auth, auth-int\", algorithm={algorithm}, \
+            nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+            opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+        ));
+        let challenge = DigestChallenge::select(&challenges).unwrap();
+        let header = challenge.authorize_parts(
+            "Mufasa",
+            "Circle of Life",
+            "GET",
+            "/dir/index.html",
+            b"",
+            "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ",
+        );
+        assert_eq!(
+            header,
+            format!(
+                "Digest username=\"Mufasa\", realm=\"http-auth@example.org\", \
+                uri=\"/dir/index.html\", algorithm={algorithm}, \
+                nonce=\"7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v\", \
+                nc=00000001, \
+                cnonce=\"f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ\", \
+                qop=auth, response=\"{expected_response}\", \
+                opaque=\"FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS\""
+            )
+        );
+    }
+
+    /// The strongest supported algorithm wins. Unsupported challenges are
+    /// skipped
+    #[rstest]
+    #[case::default(r#"Digest nonce="a""#, Some("MD5"))]
+    #[case::strongest(
+        r#"Digest nonce="a", algorithm=MD5, Digest nonce="b", algorithm=SHA-256"#,
+        Some("SHA-256")
+    )]
+    #[case::session(
+        r#"Digest nonce="a", algorithm=sha-256-sess"#,
+        Some("SHA-256-sess")
+    )]
+    #[case::unsupported_algorithm(r#"Digest nonce="a", algorithm=SHA-1"#, None)]
+    #[case::unsupported_qop(r#"Digest nonce="a", qop="auth-conf""#, None)]
+    #[case::no_nonce(r#"Digest realm="a""#, None)]
+    #[case::other_scheme(r#"Basic realm="a""#, None)]
+    fn test_select(#[case] header: &str, #[case] expected: Option<&str>) {
+        let challenges = parse_header(header);
+        let algorithm = DigestChallenge::select(&challenges)
+            .map(|challenge| challenge.algorithm().to_string());
+        assert_eq!(algorithm.as_deref(), expected);
+    }
+}
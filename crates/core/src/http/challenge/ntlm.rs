@@ -0,0 +1,383 @@
+//! NTLMv2 authentication, as specified by MS-NLMP. Only authentication is
+//! supported; the session key isn't needed because HTTP doesn't sign or seal
+//! messages.
+
+use crate::http::challenge::ChallengeError;
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use md5::Md5;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Every NTLM message starts with this
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const NEGOTIATE_OEM: u32 = 0x0000_0002;
+const REQUEST_TARGET: u32 = 0x0000_0004;
+const NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+/// Capabilities we advertise in the negotiate message. The authenticate
+/// message echoes whichever of these the server accepted.
+const FLAGS: u32 = NEGOTIATE_UNICODE
+    | NEGOTIATE_OEM
+    | REQUEST_TARGET
+    | NEGOTIATE_NTLM
+    | NEGOTIATE_ALWAYS_SIGN
+    | NEGOTIATE_EXTENDED_SESSIONSECURITY;
+
+/// Size of the fixed authenticate message header. The variable-length
+/// payload that the header points into follows it.
+const AUTHENTICATE_HEADER_LEN: usize = 64;
+
+/// ID of the `MsvAvTimestamp` entry in the server's target info
+const AV_TIMESTAMP: u16 = 7;
+/// ID of the `MsvAvEOL` entry, which terminates the target info
+const AV_EOL: u16 = 0;
+
+/// Seconds between the Windows epoch (1601) and the Unix epoch (1970)
+const WINDOWS_EPOCH_OFFSET: u64 = 11_644_473_600;
+
+/// Build the first message of the handshake, which advertises our
+/// capabilities. The domain and workstation are omitted.
+pub(super) fn negotiate_message() -> Vec<u8> {
+    let mut message = Vec::with_capacity(32);
+    message.extend_from_slice(SIGNATURE);
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&FLAGS.to_le_bytes());
+    // Empty domain and workstation fields
+    message.extend_from_slice(&[0; 16]);
+    message
+}
+
+/// Build the final message of the handshake, which answers the server's
+/// challenge message. If `domain` isn't given, it can be included in the
+/// username as `DOMAIN\user`.
+pub(super) fn authenticate_message(
+    challenge: &[u8],
+    username: &str,
+    password: &str,
+    domain: Option<&str>,
+) -> Result<Vec<u8>, ChallengeError> {
+    let challenge = ChallengeMessage::parse(challenge)
+        .ok_or(ChallengeError::NtlmChallenge)?;
+    let (domain, username) = match domain {
+        Some(domain) => (domain, username),
+        None => username.split_once('\\').unwrap_or(("", username)),
+    };
+    let client_challenge: [u8; 8] = Uuid::new_v4().as_bytes()[..8]
+        .try_into()
+        .expect("UUID is 16 bytes");
+    Ok(challenge.authenticate(
+        &Credentials {
+            username,
+            password,
+            domain,
+        },
+        client_challenge,
+        now(),
+    ))
+}
+
+/// The server's challenge message, the second message of the handshake
+#[derive(Debug)]
+struct ChallengeMessage {
+    flags: u32,
+    server_challenge: [u8; 8],
+    /// Information about the server, as a list of `AV_PAIR` structures. This
+    /// is included verbatim in the response.
+    target_info: Vec<u8>,
+}
+
+impl ChallengeMessage {
+    /// Parse a challenge message. Return `None` if it's malformed
+    fn parse(message: &[u8]) -> Option<Self> {
+        if message.get(..8)? != SIGNATURE || read_u32(message, 8)? != 2 {
+            return None;
+        }
+        let flags = read_u32(message, 20)?;
+        let server_challenge = message.get(24..32)?.try_into().ok()?;
+        // Target info is optional for old servers, but NTLMv2 uses it if
+        // present
+        let target_info = if message.len() >= 48 {
+            read_field(message, 40)?.to_owned()
+        } else {
+            Vec::new()
+        };
+        Some(Self {
+            flags,
+            server_challenge,
+            target_info,
+        })
+    }
+
+    /// Get the server's timestamp from the target info, if it sent one
+    fn timestamp(&self) -> Option<u64> {
+        let mut info = self.target_info.as_slice();
+        while info.len() >= 4 {
+            let id = u16::from_le_bytes([info[0], info[1]]);
+            let len = u16::from_le_bytes([info[2], info[3]]) as usize;
+            let value = info.get(4..4 + len)?;
+            match id {
+                AV_EOL => break,
+                AV_TIMESTAMP => {
+                    return Some(u64::from_le_bytes(value.try_into().ok()?));
+                }
+                _ => info = &info[4 + len..],
+            }
+        }
+        None
+    }
+
+    /// Build the authenticate message. `timestamp` is the current time as a
+    /// Windows `FILETIME`, and is only used if the server didn't provide its
+    /// own.
+    fn authenticate(
+        &self,
+        credentials: &Credentials,
+        client_challenge: [u8; 8],
+        timestamp: u64,
+    ) -> Vec<u8> {
+        let server_timestamp = self.timestamp();
+        let (nt_response, lm_response) = self.responses(
+            credentials,
+            client_challenge,
+            server_timestamp.unwrap_or(timestamp),
+        );
+        // If the server sent a timestamp, the LM response must be empty.
+        // The NT response is authoritative
+        let lm_response = if server_timestamp.is_some() {
+            vec![0; 24]
+        } else {
+            lm_response
+        };
+
+        let flags = self.flags & FLAGS;
+        let unicode = flags & NEGOTIATE_UNICODE != 0;
+        let encode = |s: &str| {
+            if unicode {
+                utf16(s)
+            } else {
+                s.as_bytes().to_owned()
+            }
+        };
+        let fields = [
+            lm_response,
+            nt_response,
+            encode(credentials.domain),
+            encode(credentials.username),
+            // Workstation and session key are empty
+            Vec::new(),
+            Vec::new(),
+        ];
+
+        let mut message = Vec::with_capacity(
+            AUTHENTICATE_HEADER_LEN
+                + fields.iter().map(Vec::len).sum::<usize>(),
+        );
+        message.extend_from_slice(SIGNATURE);
+        message.extend_from_slice(&3u32.to_le_bytes());
+        let mut offset = AUTHENTICATE_HEADER_LEN;
+        for field in &fields {
+            let len = field.len() as u16;
+            message.extend_from_slice(&len.to_le_bytes());
+            message.extend_from_slice(&len.to_le_bytes());
+            message.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += field.len();
+        }
+        message.extend_from_slice(&flags.to_le_bytes());
+        for field in fields {
+            message.extend(field);
+        }
+        message
+    }
+
+    /// Compute the NTLMv2 and LMv2 responses to the server's challenge
+    fn responses(
+        &self,
+        credentials: &Credentials,
+        client_challenge: [u8; 8],
+        timestamp: u64,
+    ) -> (Vec<u8>, Vec<u8>) {
+        let key = ntowfv2(credentials);
+
+        // The blob is a structure of client information that's hashed along
+        // with the challenge, and sent in the clear
+        let mut blob = vec![1, 1, 0, 0, 0, 0, 0, 0];
+        blob.extend_from_slice(&timestamp.to_le_bytes());
+        blob.extend_from_slice(&client_challenge);
+        blob.extend_from_slice(&[0; 4]);
+        blob.extend_from_slice(&self.target_info);
+        blob.extend_from_slice(&[0; 4]);
+
+        let proof = hmac_md5(&key, &[&self.server_challenge, &blob]);
+        let nt_response = [proof.as_slice(), &blob].concat();
+        let lm_response = [
+            hmac_md5(&key, &[&self.server_challenge, &client_challenge])
+                .as_slice(),
+            &client_challenge,
+        ]
+        .concat();
+        (nt_response, lm_response)
+    }
+}
+
+/// User credentials, with the domain split from the username
+struct Credentials<'a> {
+    username: &'a str,
+    password: &'a str,
+    domain: &'a str,
+}
+
+/// Derive the NTLMv2 key from the user's credentials
+fn ntowfv2(credentials: &Credentials) -> [u8; 16] {
+    let nt_hash = Md4::digest(utf16(credentials.password));
+    let identity = format!(
+        "{}{}",
+        credentials.username.to_uppercase(),
+        credentials.domain
+    );
+    hmac_md5(&nt_hash, &[&utf16(&identity)])
+}
+
+fn hmac_md5(key: &[u8], data: &[&[u8]]) -> [u8; 16] {
+    let mut mac =
+        Hmac::<Md5>::new_from_slice(key).expect("HMAC accepts any key length");
+    for data in data {
+        mac.update(data);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// Encode a string as UTF-16LE, which NTLM uses for all text
+fn utf16(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+/// Get the current time as a Windows `FILETIME`: 100ns intervals since 1601
+fn now() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    (since_epoch.as_secs() + WINDOWS_EPOCH_OFFSET) * 10_000_000
+        + u64::from(since_epoch.subsec_nanos() / 100)
+}
+
+fn read_u32(message: &[u8], offset: usize) -> Option<u32> {
+    let bytes = message.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes(bytes.try_into().ok()?))
+}
+
+/// Read a variable-length field, which is stored in the payload and pointed to
+/// by a length and offset in the header
+fn read_field(message: &[u8], header_offset: usize) -> Option<&[u8]> {
+    let header = message.get(header_offset..header_offset + 8)?;
+    let len = u16::from_le_bytes([header[0], header[1]]) as usize;
+    let offset = read_u32(header, 4)? as usize;
+    message.get(offset..offset + len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::encode_hex;
+
+    /// Test vectors from MS-NLMP section 4.2.4
+    const CREDENTIALS: Credentials = Credentials {
+        username: "User",
+        password: "Password",
+        domain: "Domain",
+    };
+    const SERVER_CHALLENGE: [u8; 8] =
+        [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+    const CLIENT_CHALLENGE: [u8; 8] = [0xaa; 8];
+
+    /// Target info from the test vectors: NetBIOS domain and computer names
+    fn target_info() -> Vec<u8> {
+        let mut info = Vec::new();
+        for (id, value) in [(2u16, "Domain"), (1, "Server")] {
+            let value = utf16(value);
+            info.extend_from_slice(&id.to_le_bytes());
+            info.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            info.extend(value);
+        }
+        info.extend_from_slice(&[0; 4]);
+        info
+    }
+
+    #[test]
+    fn test_ntowfv2() {
+        assert_eq!(
+            encode_hex(&ntowfv2(&CREDENTIALS)),
+            "0c868a403bfd7a93a3001ef22ef02e3f"
+        );
+    }
+
+    #[test]
+    fn test_responses() {
+        let challenge = ChallengeMessage {
+            flags: FLAGS,
+            server_challenge: SERVER_CHALLENGE,
+            target_info: target_info(),
+        };
+        let (nt_response, lm_response) =
+            challenge.responses(&CREDENTIALS, CLIENT_CHALLENGE, 0);
+        assert_eq!(
+            encode_hex(&nt_response[..16]),
+            "68cd0ab851e51c96aabc927bebef6a1c"
+        );
+        assert_eq!(
+            encode_hex(&lm_response),
+            "86c35097ac9cec102554764a57cccc19aaaaaaaaaaaaaaaa"
+        );
+    }
+
+    /// Parse a challenge message and answer it. The header fields of the
+    /// authenticate message should point to the right data
+    #[test]
+    fn test_authenticate_message() {
+        let target_info = target_info();
+        let mut message = SIGNATURE.to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        // Empty target name
+        message.extend_from_slice(&[0, 0, 0, 0, 48, 0, 0, 0]);
+        message.extend_from_slice(&FLAGS.to_le_bytes());
+        message.extend_from_slice(&SERVER_CHALLENGE);
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        message.extend_from_slice(&48u32.to_le_bytes());
+        message.extend_from_slice(&target_info);
+
+        let challenge = ChallengeMessage::parse(&message).unwrap();
+        assert_eq!(challenge.server_challenge, SERVER_CHALLENGE);
+        assert_eq!(challenge.target_info, target_info);
+        assert_eq!(challenge.timestamp(), None);
+
+        let message = challenge.authenticate(&CREDENTIALS, CLIENT_CHALLENGE, 0);
+        assert_eq!(&message[..8], SIGNATURE);
+        assert_eq!(read_u32(&message, 8), Some(3));
+        assert_eq!(read_field(&message, 12).unwrap().len(), 24);
+        assert_eq!(
+            encode_hex(&read_field(&message, 20).unwrap()[..16]),
+            "68cd0ab851e51c96aabc927bebef6a1c"
+        );
+        assert_eq!(read_field(&message, 28), Some(utf16("Domain").as_slice()));
+        assert_eq!(read_field(&message, 36), Some(utf16("User").as_slice()));
+        assert_eq!(read_u32(&message, 60), Some(FLAGS));
+    }
+
+    /// Malformed challenges are rejected
+    #[test]
+    fn test_parse_invalid() {
+        assert!(ChallengeMessage::parse(b"").is_none());
+        assert!(ChallengeMessage::parse(b"NTLMSSP\0\x01\0\0\0").is_none());
+        let mut message = SIGNATURE.to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        message.extend_from_slice(&[0; 28]);
+        // Target info points past the end of the message
+        message.extend_from_slice(&[8, 0, 8, 0, 0xff, 0, 0, 0]);
+        assert!(ChallengeMessage::parse(&message).is_none());
+    }
+}
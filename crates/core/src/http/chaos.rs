@@ -0,0 +1,203 @@
+//! Fault injection for resilience testing. A profile's [Chaos] settings wrap
+//! the selected [Transport] so requests can be delayed, dropped, or answered
+//! with a synthetic error before they ever reach the network.
+
+use crate::{
+    collection::{Chaos, ProfileId},
+    http::{Transport, TransportError},
+};
+use futures::{FutureExt, future::BoxFuture};
+use reqwest::{Request, Response, StatusCode, header};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::info;
+
+/// Tracks how many requests have been rolled against chaos settings, so that
+/// seeded profiles produce the same sequence of faults every session
+#[derive(Clone, Debug)]
+pub(super) struct ChaosState {
+    /// Number of requests rolled so far. Shared between clones of the engine
+    counter: Arc<AtomicU64>,
+    /// Seed for profiles that don't specify one
+    default_seed: u64,
+}
+
+impl ChaosState {
+    pub(super) fn new() -> Self {
+        Self {
+            counter: Default::default(),
+            default_seed: uuid::Uuid::new_v4().as_u64_pair().0,
+        }
+    }
+
+    /// Decide which faults apply to the next request, and wrap the transport
+    /// to inject them
+    pub(super) fn wrap(
+        &self,
+        transport: Arc<dyn Transport>,
+        profile_id: &ProfileId,
+        chaos: &Chaos,
+    ) -> Arc<dyn Transport> {
+        let index = self.counter.fetch_add(1, Ordering::Relaxed);
+        let seed = chaos.seed.unwrap_or(self.default_seed);
+        let fault = Fault::roll(chaos, seed, index);
+        info!(%profile_id, ?chaos, ?fault, "Injecting chaos");
+        Arc::new(ChaosTransport {
+            inner: transport,
+            profile_id: profile_id.clone(),
+            latency: Duration::from_millis(chaos.latency_ms),
+            fault,
+        })
+    }
+}
+
+/// A fault to inject in place of sending a request
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Fault {
+    /// Fail as if the connection was lost
+    Drop,
+    /// Respond with a synthetic `500 Internal Server Error`
+    Error,
+}
+
+impl Fault {
+    /// Pick a fault (or lack thereof) for the `index`th request. The outcome
+    /// is a pure function of the inputs, so a seeded sequence is reproducible.
+    fn roll(chaos: &Chaos, seed: u64, index: u64) -> Option<Self> {
+        let roll = (splitmix64(seed.wrapping_add(index)) % 100) as u8;
+        let drop_percent = chaos.drop_percent;
+        let error_percent = chaos.error_percent.saturating_add(drop_percent);
+        if roll < drop_percent {
+            Some(Self::Drop)
+        } else if roll < error_percent {
+            Some(Self::Error)
+        } else {
+            None
+        }
+    }
+}
+
+/// A [Transport] that delays and/or replaces requests before handing them
+/// off to the real transport
+#[derive(Debug)]
+struct ChaosTransport {
+    inner: Arc<dyn Transport>,
+    profile_id: ProfileId,
+    latency: Duration,
+    fault: Option<Fault>,
+}
+
+impl Transport for ChaosTransport {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let inner = Arc::clone(&self.inner);
+        let profile_id = self.profile_id.clone();
+        let latency = self.latency;
+        let fault = self.fault;
+        async move {
+            if !latency.is_zero() {
+                tokio::time::sleep(latency).await;
+            }
+            match fault {
+                None => inner.send(request).await,
+                Some(Fault::Drop) => {
+                    Err(TransportError::new(ChaosError { profile_id }))
+                }
+                Some(Fault::Error) => {
+                    let response = http::Response::builder()
+                        .status(StatusCode::INTERNAL_SERVER_ERROR)
+                        .header(header::CONTENT_TYPE, "text/plain")
+                        .body(format!(
+                            "Synthetic error injected by profile `{profile_id}`"
+                        ))
+                        .expect("Synthetic response is valid");
+                    Ok(Response::from(response))
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Error for a request that was dropped by chaos injection
+#[derive(Debug, Error)]
+#[error("Request dropped by chaos settings of profile `{profile_id}`")]
+pub struct ChaosError {
+    profile_id: ProfileId,
+}
+
+/// SplitMix64, a tiny non-cryptographic PRNG step. Adjacent inputs produce
+/// well-distributed outputs, which is all we need for picking faults.
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Roll faults for a batch of requests
+    fn roll_all(drop_percent: u8, error_percent: u8) -> Vec<Option<Fault>> {
+        let chaos = Chaos {
+            drop_percent,
+            error_percent,
+            ..Chaos::default()
+        };
+        (0..1000).map(|i| Fault::roll(&chaos, 42, i)).collect()
+    }
+
+    /// Rates of 0 and 100 are absolute
+    #[rstest]
+    #[case::none(0, 0, None)]
+    #[case::all_drop(100, 0, Some(Fault::Drop))]
+    #[case::all_error(0, 100, Some(Fault::Error))]
+    #[case::drop_first(100, 100, Some(Fault::Drop))]
+    fn test_roll_absolute(
+        #[case] drop_percent: u8,
+        #[case] error_percent: u8,
+        #[case] expected: Option<Fault>,
+    ) {
+        let faults = roll_all(drop_percent, error_percent);
+        assert!(faults.iter().all(|fault| *fault == expected));
+    }
+
+    /// Partial rates produce a roughly proportional mix of faults
+    #[test]
+    fn test_roll_mixed() {
+        let faults = roll_all(30, 20);
+        let count =
+            |fault| faults.iter().filter(|f| **f == fault).count() as f64;
+        assert!((count(Some(Fault::Drop)) / 1000.0 - 0.3).abs() < 0.05);
+        assert!((count(Some(Fault::Error)) / 1000.0 - 0.2).abs() < 0.05);
+        assert!((count(None) / 1000.0 - 0.5).abs() < 0.05);
+    }
+
+    /// The same seed produces the same sequence of faults
+    #[test]
+    fn test_roll_deterministic() {
+        let chaos = Chaos {
+            drop_percent: 20,
+            error_percent: 20,
+            ..Chaos::default()
+        };
+        let sequence = |seed| {
+            (0..100)
+                .map(|i| Fault::roll(&chaos, seed, i))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(sequence(1), sequence(1));
+        assert_ne!(sequence(1), sequence(2));
+    }
+}
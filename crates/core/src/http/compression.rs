@@ -0,0 +1,241 @@
+//! Response compression. A recipe can ask for compressed bodies via
+//! `Accept-Encoding`, and a compressed response is decoded as it's received
+//! unless the recipe opts out to see the raw bytes.
+
+use crate::{
+    collection::{Compression, ContentEncoding},
+    http::{Transport, TransportError, transport},
+};
+use bytes::Bytes;
+use flate2::write::{GzDecoder, ZlibDecoder};
+use futures::{FutureExt, StreamExt, future::BoxFuture, stream};
+use http_body_util::BodyDataStream;
+use itertools::Itertools;
+use reqwest::{Body, Request, Response, header};
+use std::{
+    error::Error,
+    io::{self, Write},
+    mem,
+    sync::Arc,
+};
+use strum::IntoEnumIterator;
+use tracing::warn;
+
+impl Compression {
+    /// Value of the `Accept-Encoding` header. `None` if no encodings are
+    /// listed, in which case the header isn't sent.
+    pub(super) fn accept_header(&self) -> Option<String> {
+        if self.accept.is_empty() {
+            None
+        } else {
+            Some(
+                self.accept
+                    .iter()
+                    .map(|encoding| encoding.to_str())
+                    .join(", "),
+            )
+        }
+    }
+
+    /// Wrap a transport to decode compressed response bodies. If decoding is
+    /// disabled, the transport is returned as-is.
+    pub(super) fn wrap(
+        &self,
+        transport: Arc<dyn Transport>,
+    ) -> Arc<dyn Transport> {
+        if self.decompress {
+            Arc::new(DecompressTransport { inner: transport })
+        } else {
+            transport
+        }
+    }
+}
+
+impl ContentEncoding {
+    /// Parse a `Content-Encoding` header value. `None` if the encoding isn't
+    /// supported.
+    fn from_header(value: &str) -> Option<Self> {
+        let value = value.trim();
+        // x-gzip is an old alias that servers may still send
+        if value.eq_ignore_ascii_case("x-gzip") {
+            return Some(Self::Gzip);
+        }
+        Self::iter()
+            .find(|encoding| value.eq_ignore_ascii_case(encoding.to_str()))
+    }
+}
+
+/// A [Transport] that decodes compressed response bodies as they're received
+#[derive(Debug)]
+struct DecompressTransport {
+    inner: Arc<dyn Transport>,
+}
+
+impl Transport for DecompressTransport {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let future = self.inner.send(request);
+        async move { Ok(decompress(future.await?)) }.boxed()
+    }
+}
+
+/// If the response body is compressed with a supported encoding, replace it
+/// with one that's decoded as it's received. `Content-Length` is removed
+/// because it's the compressed size, but `Content-Encoding` is kept so it's
+/// clear how the body was sent.
+fn decompress(response: Response) -> Response {
+    let Some(value) = response.headers().get(header::CONTENT_ENCODING) else {
+        return response;
+    };
+    let decoder =
+        match value.to_str().ok().and_then(ContentEncoding::from_header) {
+            Some(ContentEncoding::Gzip) => {
+                Decoder::Gzip(GzDecoder::new(Vec::new()))
+            }
+            Some(ContentEncoding::Deflate) => {
+                Decoder::Deflate(ZlibDecoder::new(Vec::new()))
+            }
+            Some(ContentEncoding::Identity) => return response,
+            None => {
+                warn!(
+                    encoding = ?value,
+                    "Unsupported content encoding; body will not be decoded"
+                );
+                return response;
+            }
+        };
+    transport::map_body(response, |parts, body| {
+        parts.headers.remove(header::CONTENT_LENGTH);
+        decode(body, decoder)
+    })
+}
+
+/// Decode a body as it's received
+fn decode(body: Body, decoder: Decoder) -> Body {
+    // Track whether anything was received. An empty body (e.g. for a HEAD
+    // request) isn't valid compressed data, but there's nothing to decode
+    let stream = stream::unfold(
+        Some((BodyDataStream::new(body), decoder, false)),
+        |state| async move {
+            let (mut stream, mut decoder, received) = state?;
+            let (result, state) = match stream.next().await {
+                Some(Ok(chunk)) => {
+                    let result = decoder.push(&chunk).map_err(BoxError::from);
+                    let received = received || !chunk.is_empty();
+                    (result, Some((stream, decoder, received)))
+                }
+                Some(Err(error)) => (Err(error.into()), None),
+                None if !received => (Ok(Bytes::new()), None),
+                // Body is done; flush out whatever's left
+                None => (decoder.finish().map_err(BoxError::from), None),
+            };
+            Some((result, state))
+        },
+    );
+    Body::wrap_stream(stream)
+}
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// Incremental decoder for a compressed body. Each chunk of input is written
+/// in, and whatever output is ready is taken back out.
+enum Decoder {
+    Gzip(GzDecoder<Vec<u8>>),
+    Deflate(ZlibDecoder<Vec<u8>>),
+}
+
+impl Decoder {
+    /// Decode a chunk of the body
+    fn push(&mut self, chunk: &[u8]) -> io::Result<Bytes> {
+        let output = match self {
+            Self::Gzip(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+            Self::Deflate(decoder) => {
+                decoder.write_all(chunk)?;
+                decoder.get_mut()
+            }
+        };
+        Ok(mem::take(output).into())
+    }
+
+    /// Get the rest of the output once the whole body has been pushed
+    fn finish(self) -> io::Result<Bytes> {
+        let output = match self {
+            Self::Gzip(decoder) => decoder.finish()?,
+            Self::Deflate(decoder) => decoder.finish()?,
+        };
+        Ok(output.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{
+        Compression as Level,
+        write::{GzEncoder, ZlibEncoder},
+    };
+    use futures::TryStreamExt;
+    use rstest::rstest;
+
+    /// Decode a body that arrives in small chunks
+    #[rstest]
+    #[case::gzip(ContentEncoding::Gzip)]
+    #[case::deflate(ContentEncoding::Deflate)]
+    #[tokio::test]
+    async fn test_decode(#[case] encoding: ContentEncoding) {
+        let text = "hello, ".repeat(100);
+        let (compressed, decoder) = match encoding {
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+                encoder.write_all(text.as_bytes()).unwrap();
+                let decoder = Decoder::Gzip(GzDecoder::new(Vec::new()));
+                (encoder.finish().unwrap(), decoder)
+            }
+            ContentEncoding::Deflate => {
+                let mut encoder =
+                    ZlibEncoder::new(Vec::new(), Level::default());
+                encoder.write_all(text.as_bytes()).unwrap();
+                let decoder = Decoder::Deflate(ZlibDecoder::new(Vec::new()));
+                (encoder.finish().unwrap(), decoder)
+            }
+            ContentEncoding::Identity => unreachable!(),
+        };
+        let chunks = compressed
+            .chunks(5)
+            .map(|chunk| Ok::<_, io::Error>(Bytes::copy_from_slice(chunk)))
+            .collect::<Vec<_>>();
+        let body = decode(Body::wrap_stream(stream::iter(chunks)), decoder);
+        let decoded: Vec<Bytes> =
+            BodyDataStream::new(body).try_collect().await.unwrap();
+        assert_eq!(decoded.concat(), text.as_bytes());
+    }
+
+    /// An empty body decodes to nothing, rather than an error
+    #[rstest]
+    #[tokio::test]
+    async fn test_decode_empty() {
+        let body =
+            decode(Body::from(""), Decoder::Gzip(GzDecoder::new(Vec::new())));
+        let decoded: Vec<Bytes> =
+            BodyDataStream::new(body).try_collect().await.unwrap();
+        assert_eq!(decoded.concat(), b"");
+    }
+
+    #[rstest]
+    #[case::gzip("gzip", Some(ContentEncoding::Gzip))]
+    #[case::x_gzip("x-gzip", Some(ContentEncoding::Gzip))]
+    #[case::upper(" Deflate ", Some(ContentEncoding::Deflate))]
+    #[case::identity("identity", Some(ContentEncoding::Identity))]
+    #[case::unsupported("br", None)]
+    fn test_from_header(
+        #[case] value: &str,
+        #[case] expected: Option<ContentEncoding>,
+    ) {
+        assert_eq!(ContentEncoding::from_header(value), expected);
+    }
+}
@@ -0,0 +1,676 @@
+//! Automatic cookie jar. Cookies set by responses are stored per collection,
+//! and attached to later requests whose URL they match, so session-based APIs
+//! work without capturing and forwarding the session cookie by hand. Parsing
+//! and matching follow [RFC 6265](https://www.rfc-editor.org/rfc/rfc6265),
+//! with the [public suffix list](https://publicsuffix.org/) to reject cookies
+//! for domains like `co.uk`.
+
+use crate::{
+    database::CollectionDatabase,
+    http::{Transport, TransportError},
+};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use futures::{FutureExt, future::BoxFuture};
+use itertools::Itertools;
+use reqwest::{
+    Request, Response, Url,
+    header::{self, HeaderMap, HeaderValue},
+};
+use slumber_util::ResultTraced;
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// A cookie set by a server
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Host the cookie is sent to, lowercased without a leading `.`
+    pub domain: String,
+    /// If `true`, the cookie is only sent to exactly `domain`. Otherwise it's
+    /// sent to subdomains as well. This is `false` iff the server gave a
+    /// `Domain` attribute.
+    pub host_only: bool,
+    /// Path prefix of the URLs the cookie is sent to
+    pub path: String,
+    /// When the cookie expires. `None` for session cookies, which are kept
+    /// until they're replaced or deleted.
+    pub expires: Option<DateTime<Utc>>,
+    /// Only send the cookie over HTTPS
+    pub secure: bool,
+    /// Cookie is hidden from scripts in a browser. We don't have scripts, so
+    /// this is just informational.
+    pub http_only: bool,
+}
+
+impl Cookie {
+    /// Parse a `Set-Cookie` header received from `url`. Return `None` if the
+    /// header is malformed, sets a cookie for a domain the URL doesn't belong
+    /// to or for a public suffix, or sets a `Secure` cookie over plain HTTP.
+    pub fn parse(
+        set_cookie: &str,
+        url: &Url,
+        now: DateTime<Utc>,
+    ) -> Option<Self> {
+        let host = url.host_str()?.to_lowercase();
+        let mut parts = set_cookie.split(';');
+        let (name, value) = parts.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+
+        let mut cookie = Self {
+            name: name.to_owned(),
+            value: value.trim().to_owned(),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(url),
+            expires: None,
+            secure: false,
+            http_only: false,
+        };
+        // Max-Age beats Expires regardless of order
+        let mut max_age = None;
+        for attribute in parts {
+            let (key, value) =
+                attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_lowercase().as_str() {
+                "expires" => {
+                    if let Some(expires) = parse_date(value) {
+                        cookie.expires = Some(expires);
+                    }
+                }
+                "max-age" => {
+                    if let Ok(seconds) = value.parse::<i64>() {
+                        max_age = Some(if seconds <= 0 {
+                            DateTime::<Utc>::MIN_UTC
+                        } else {
+                            now + TimeDelta::seconds(seconds)
+                        });
+                    }
+                }
+                "domain" if !value.is_empty() => {
+                    let domain = value.trim_start_matches('.').to_lowercase();
+                    if !domain_matches(&host, &domain) {
+                        return None;
+                    }
+                    // A public suffix (e.g. `com` or `co.uk`) would be sent
+                    // to every site under it. Only the host itself can set
+                    // one, and then it's host-only (RFC 6265 5.3 step 5)
+                    if is_public_suffix(&domain) {
+                        if domain != host {
+                            return None;
+                        }
+                        continue;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => {
+                    value.clone_into(&mut cookie.path);
+                }
+                // An insecure origin can't set a secure cookie, otherwise it
+                // could overwrite one set over HTTPS (RFC 6265bis 5.6 step 8)
+                "secure" if !is_secure(url) => return None,
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+        cookie.expires = max_age.or(cookie.expires);
+        Some(cookie)
+    }
+
+    /// Has this cookie expired? Expired cookies are deleted instead of stored
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+
+    /// Should this cookie be sent with a request to the URL?
+    pub fn matches(&self, url: &Url, now: DateTime<Utc>) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.to_lowercase();
+        let domain_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_matches(&host, &self.domain)
+        };
+        domain_matches
+            && path_matches(url.path(), &self.path)
+            && (is_secure(url) || !self.secure)
+            && !self.is_expired(now)
+    }
+}
+
+/// Storage for cookies. Cookies are persisted to the database if one is given,
+/// otherwise they're kept in memory. The same instance should be shared
+/// between all template contexts for a session. Clones are cheap and share the
+/// same storage.
+#[derive(Clone, Debug, Default)]
+pub struct CookieJar {
+    /// Cookies live here when there's a database, so every session for the
+    /// collection sees the same cookies
+    database: Option<CollectionDatabase>,
+    cookies: Arc<Mutex<Vec<Cookie>>>,
+}
+
+impl CookieJar {
+    /// Create a jar that persists cookies to the given database
+    pub fn new(database: CollectionDatabase) -> Self {
+        Self {
+            database: Some(database),
+            ..Self::default()
+        }
+    }
+
+    /// Get every cookie that hasn't expired, sorted by domain, path, then name
+    pub fn cookies(&self) -> Vec<Cookie> {
+        let now = Utc::now();
+        let mut cookies = if let Some(database) = &self.database {
+            database.get_cookies().traced().unwrap_or_default()
+        } else {
+            self.lock().clone()
+        };
+        cookies.retain(|cookie| !cookie.is_expired(now));
+        cookies.sort_by(|a, b| {
+            (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name))
+        });
+        cookies
+    }
+
+    /// Add a cookie, replacing any cookie with the same domain, path, and
+    /// name. An expired cookie deletes the one it would replace.
+    pub fn insert(&self, cookie: Cookie) {
+        if cookie.is_expired(Utc::now()) {
+            self.remove(&cookie);
+        } else if let Some(database) = &self.database {
+            let _ = database.set_cookie(&cookie);
+        } else {
+            let mut cookies = self.lock();
+            cookies.retain(|other| !is_same(other, &cookie));
+            cookies.push(cookie);
+        }
+    }
+
+    /// Delete the cookie with the same domain, path, and name as the given one
+    pub fn remove(&self, cookie: &Cookie) {
+        if let Some(database) = &self.database {
+            let _ = database.delete_cookie(
+                &cookie.domain,
+                &cookie.path,
+                &cookie.name,
+            );
+        } else {
+            self.lock().retain(|other| !is_same(other, cookie));
+        }
+    }
+
+    /// Delete every cookie
+    pub fn clear(&self) {
+        if let Some(database) = &self.database {
+            let _ = database.clear_cookies();
+        } else {
+            self.lock().clear();
+        }
+    }
+
+    /// Get the `Cookie` header for a request to the URL. Cookies with longer
+    /// paths go first. `None` if no cookies match.
+    pub fn header(&self, url: &Url) -> Option<HeaderValue> {
+        let now = Utc::now();
+        let mut cookies = self.cookies();
+        cookies.retain(|cookie| cookie.matches(url, now));
+        if cookies.is_empty() {
+            return None;
+        }
+        cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path.len()));
+        let header = cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .join("; ");
+        HeaderValue::from_str(&header).ok()
+    }
+
+    /// Store every cookie set by a response to a request for the URL
+    pub fn store(&self, url: &Url, headers: &HeaderMap) {
+        let now = Utc::now();
+        for value in headers.get_all(header::SET_COOKIE) {
+            if let Some(cookie) = value
+                .to_str()
+                .ok()
+                .and_then(|value| Cookie::parse(value, url, now))
+            {
+                self.insert(cookie);
+            }
+        }
+    }
+
+    /// Wrap a transport so every response's cookies are stored in this jar.
+    /// If `attach` is enabled, each request's `Cookie` header is replaced
+    /// with the jar's cookies for its URL. This is done per request rather
+    /// than once up front so redirects carry cookies set along the way.
+    pub(super) fn wrap(
+        &self,
+        transport: Arc<dyn Transport>,
+        attach: bool,
+    ) -> Arc<dyn Transport> {
+        Arc::new(CookieTransport {
+            inner: transport,
+            jar: self.clone(),
+            attach,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Cookie>> {
+        self.cookies.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A [Transport] that attaches cookies from a jar to each request, and stores
+/// the cookies from each response
+#[derive(Debug)]
+struct CookieTransport {
+    inner: Arc<dyn Transport>,
+    jar: CookieJar,
+    /// Manage the `Cookie` header? Disabled if the recipe sets its own
+    attach: bool,
+}
+
+impl Transport for CookieTransport {
+    fn send(
+        &self,
+        mut request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let url = request.url().clone();
+        if self.attach {
+            match self.jar.header(&url) {
+                Some(cookie) => {
+                    request.headers_mut().insert(header::COOKIE, cookie);
+                }
+                None => {
+                    request.headers_mut().remove(header::COOKIE);
+                }
+            }
+        }
+        let future = self.inner.send(request);
+        let jar = self.jar.clone();
+        async move {
+            let response = future.await?;
+            jar.store(&url, response.headers());
+            Ok(response)
+        }
+        .boxed()
+    }
+}
+
+/// Do two cookies occupy the same slot in the jar?
+fn is_same(a: &Cookie, b: &Cookie) -> bool {
+    a.domain == b.domain && a.path == b.path && a.name == b.name
+}
+
+/// Does the host belong to the cookie domain? IP addresses only match
+/// themselves.
+fn domain_matches(host: &str, domain: &str) -> bool {
+    host == domain
+        || (host.ends_with(domain)
+            && host[..host.len() - domain.len()].ends_with('.')
+            && host.parse::<IpAddr>().is_err())
+}
+
+/// Is the domain a public suffix, such as `com` or `co.uk`? Unlisted
+/// single-label domains (e.g. `localhost`) count as suffixes too.
+fn is_public_suffix(domain: &str) -> bool {
+    psl::suffix_str(domain) == Some(domain)
+}
+
+/// Is the URL's connection encrypted?
+fn is_secure(url: &Url) -> bool {
+    matches!(url.scheme(), "https" | "wss")
+}
+
+/// Parse a cookie date with the lenient algorithm from RFC 6265 5.1.1. This
+/// accepts RFC 1123 dates, as well as the Netscape (`Wed, 21-Oct-2015
+/// 07:28:00 GMT`) and asctime (`Wed Oct 21 07:28:00 2015`) formats still sent
+/// by some servers. The time zone is always assumed to be UTC.
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct",
+        "nov", "dec",
+    ];
+
+    let is_delimiter = |c: char| matches!(c, '\t' | ' '..='/' | ';'..='@' | '['..='`' | '{'..='~');
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    for token in value.split(is_delimiter).filter(|token| !token.is_empty()) {
+        if time.is_none()
+            && let Some(parsed) = parse_time(token)
+        {
+            time = Some(parsed);
+        } else if day.is_none()
+            && let Some((parsed, _)) = parse_digits(token, 1, 2)
+        {
+            day = Some(parsed);
+        } else if month.is_none()
+            && let Some(index) = token.get(..3).and_then(|prefix| {
+                MONTHS
+                    .iter()
+                    .position(|month| prefix.eq_ignore_ascii_case(month))
+            })
+        {
+            month = Some(index as u32 + 1);
+        } else if year.is_none()
+            && let Some((parsed, _)) = parse_digits(token, 2, 4)
+        {
+            year = Some(match parsed {
+                70..=99 => parsed + 1900,
+                0..=69 => parsed + 2000,
+                _ => parsed,
+            });
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let year = year?;
+    if year < 1601 || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(year as i32, month?, day?)?
+        .and_hms_opt(hour, minute, second)
+        .map(|date| date.and_utc())
+}
+
+/// Parse a cookie date time token: `hh:mm:ss`, where each field is 1-2 digits
+fn parse_time(token: &str) -> Option<(u32, u32, u32)> {
+    let (hour, rest) = parse_digits(token, 1, 2)?;
+    let (minute, rest) = parse_digits(rest.strip_prefix(':')?, 1, 2)?;
+    let (second, _) = parse_digits(rest.strip_prefix(':')?, 1, 2)?;
+    Some((hour, minute, second))
+}
+
+/// Parse a number of `min..=max` digits from the start of a token, returning
+/// it and the rest of the token. The digits may be followed by anything but
+/// another digit.
+fn parse_digits(token: &str, min: usize, max: usize) -> Option<(u32, &str)> {
+    let len = token.bytes().take_while(u8::is_ascii_digit).count();
+    if !(min..=max).contains(&len) {
+        return None;
+    }
+    Some((token[..len].parse().ok()?, &token[len..]))
+}
+
+/// Is the request path within the cookie path?
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || (path.starts_with(cookie_path)
+            && (cookie_path.ends_with('/')
+                || path[cookie_path.len()..].starts_with('/')))
+}
+
+/// Path for a cookie that doesn't specify one: the request path up to, but not
+/// including, its last `/`
+fn default_path(url: &Url) -> String {
+    match url.path().rsplit_once('/') {
+        Some((directory, _)) if !directory.is_empty() => directory.to_owned(),
+        _ => "/".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::rstest;
+    use slumber_util::Factory;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+    }
+
+    fn cookie(domain: &str, host_only: bool, path: &str) -> Cookie {
+        Cookie {
+            name: "session".into(),
+            value: "abc".into(),
+            domain: domain.into(),
+            host_only,
+            path: path.into(),
+            expires: None,
+            secure: false,
+            http_only: false,
+        }
+    }
+
+    #[rstest]
+    #[case::minimal(
+        "session=abc",
+        "https://api.example.com/v1/login",
+        Some(cookie("api.example.com", true, "/v1"))
+    )]
+    #[case::attributes(
+        "session=abc; Domain=.Example.com; Path=/; Secure; HttpOnly",
+        "https://api.example.com/login",
+        Some(Cookie {
+            secure: true,
+            http_only: true,
+            ..cookie("example.com", false, "/")
+        })
+    )]
+    #[case::expires(
+        "session=abc; Expires=Wed, 01 Jan 2025 01:00:00 GMT",
+        "https://example.com/",
+        Some(Cookie {
+            expires: Some(Utc.with_ymd_and_hms(2025, 1, 1, 1, 0, 0).unwrap()),
+            ..cookie("example.com", true, "/")
+        })
+    )]
+    #[case::max_age_beats_expires(
+        "session=abc; Max-Age=60; Expires=Wed, 01 Jan 2025 01:00:00 GMT",
+        "https://example.com/",
+        Some(Cookie {
+            expires: Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 1, 0).unwrap()),
+            ..cookie("example.com", true, "/")
+        })
+    )]
+    #[case::relative_path_ignored(
+        "session=abc; Path=v2",
+        "https://example.com/v1/login",
+        Some(cookie("example.com", true, "/v1"))
+    )]
+    #[case::other_domain(
+        "session=abc; Domain=other.com",
+        "https://example.com/",
+        None
+    )]
+    #[case::top_level_domain(
+        "session=abc; Domain=com",
+        "https://example.com/",
+        None
+    )]
+    #[case::top_level_domain_leading_dot(
+        "session=abc; Domain=.com",
+        "https://example.com/",
+        None
+    )]
+    #[case::public_suffix(
+        "session=abc; Domain=co.uk",
+        "https://example.co.uk/",
+        None
+    )]
+    #[case::public_suffix_host(
+        "session=abc; Domain=github.io",
+        "https://github.io/",
+        Some(cookie("github.io", true, "/"))
+    )]
+    #[case::below_public_suffix(
+        "session=abc; Domain=example.co.uk",
+        "https://api.example.co.uk/",
+        Some(cookie("example.co.uk", false, "/"))
+    )]
+    #[case::expires_netscape(
+        "session=abc; Expires=Wed, 21-Oct-2015 07:28:00 GMT",
+        "https://example.com/",
+        Some(Cookie {
+            expires: Some(Utc.with_ymd_and_hms(2015, 10, 21, 7, 28, 0).unwrap()),
+            ..cookie("example.com", true, "/")
+        })
+    )]
+    #[case::expires_invalid(
+        "session=abc; Expires=tomorrow",
+        "https://example.com/",
+        Some(cookie("example.com", true, "/"))
+    )]
+    #[case::secure_http("session=abc; Secure", "http://example.com/", None)]
+    #[case::single_label_host(
+        "session=abc; Domain=localhost",
+        "http://localhost/",
+        Some(cookie("localhost", true, "/"))
+    )]
+    #[case::no_value("session", "https://example.com/", None)]
+    #[case::no_name("=abc", "https://example.com/", None)]
+    fn test_parse(
+        #[case] set_cookie: &str,
+        #[case] url: &str,
+        #[case] expected: Option<Cookie>,
+    ) {
+        let url = url.parse().unwrap();
+        assert_eq!(Cookie::parse(set_cookie, &url, now()), expected);
+    }
+
+    /// Dates are parsed leniently, per RFC 6265 5.1.1
+    #[rstest]
+    #[case::rfc1123("Wed, 21 Oct 2015 07:28:00 GMT", Some((2015, 10, 21)))]
+    #[case::netscape("Wed, 21-Oct-2015 07:28:00 GMT", Some((2015, 10, 21)))]
+    #[case::netscape_short_year(
+        "Wednesday, 21-Oct-15 07:28:00 GMT",
+        Some((2015, 10, 21))
+    )]
+    #[case::asctime("Wed Oct 21 07:28:00 2015", Some((2015, 10, 21)))]
+    #[case::last_century("Thu, 01-Jan-70 07:28:00 GMT", Some((1970, 1, 1)))]
+    #[case::case_insensitive("21 OCTOBER 2015 07:28:00", Some((2015, 10, 21)))]
+    #[case::missing_time("Wed, 21 Oct 2015", None)]
+    #[case::invalid_day("Wed, 32 Oct 2015 07:28:00 GMT", None)]
+    #[case::invalid_time("Wed, 21 Oct 2015 24:00:00 GMT", None)]
+    #[case::too_early("Wed, 21 Oct 1600 07:28:00 GMT", None)]
+    #[case::garbage("tomorrow", None)]
+    fn test_parse_date(
+        #[case] value: &str,
+        #[case] expected: Option<(i32, u32, u32)>,
+    ) {
+        let expected = expected.map(|(year, month, day)| {
+            Utc.with_ymd_and_hms(year, month, day, 7, 28, 0).unwrap()
+        });
+        assert_eq!(parse_date(value), expected);
+    }
+
+    #[rstest]
+    #[case::host_only(
+        cookie("example.com", true, "/"),
+        "http://example.com/",
+        true
+    )]
+    #[case::host_only_subdomain(
+        cookie("example.com", true, "/"),
+        "http://api.example.com/",
+        false
+    )]
+    #[case::subdomain(
+        cookie("example.com", false, "/"),
+        "http://api.example.com/",
+        true
+    )]
+    #[case::suffix_only(
+        cookie("example.com", false, "/"),
+        "http://badexample.com/",
+        false
+    )]
+    #[case::path(
+        cookie("example.com", true, "/v1"),
+        "http://example.com/v1/users",
+        true
+    )]
+    #[case::path_exact(
+        cookie("example.com", true, "/v1"),
+        "http://example.com/v1",
+        true
+    )]
+    #[case::path_prefix_only(
+        cookie("example.com", true, "/v1"),
+        "http://example.com/v10",
+        false
+    )]
+    #[case::secure_https(
+        Cookie { secure: true, ..cookie("example.com", true, "/") },
+        "https://example.com/",
+        true
+    )]
+    #[case::secure_http(
+        Cookie { secure: true, ..cookie("example.com", true, "/") },
+        "http://example.com/",
+        false
+    )]
+    #[case::expired(
+        Cookie { expires: Some(now()), ..cookie("example.com", true, "/") },
+        "http://example.com/",
+        false
+    )]
+    fn test_matches(
+        #[case] cookie: Cookie,
+        #[case] url: &str,
+        #[case] expected: bool,
+    ) {
+        assert_eq!(cookie.matches(&url.parse().unwrap(), now()), expected);
+    }
+
+    /// Cookies from responses are stored, replaced, and deleted, and sent
+    /// back to matching URLs with the most specific paths first
+    #[rstest]
+    #[case::memory(CookieJar::default())]
+    #[case::database(CookieJar::new(CollectionDatabase::factory(())))]
+    fn test_jar(#[case] jar: CookieJar) {
+        let url: Url = "https://example.com/api/login".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        for value in [
+            "session=abc; Path=/",
+            "theme=dark; Path=/api",
+            "csrf=xyz; Path=/",
+        ] {
+            headers.append(header::SET_COOKIE, value.parse().unwrap());
+        }
+        jar.store(&url, &headers);
+        assert_eq!(
+            jar.header(&url).unwrap(),
+            "theme=dark; csrf=xyz; session=abc"
+        );
+        assert_eq!(jar.header(&"https://other.com/".parse().unwrap()), None);
+
+        // Replace one cookie and expire another
+        let mut headers = HeaderMap::new();
+        headers
+            .append(header::SET_COOKIE, "session=def; Path=/".parse().unwrap());
+        headers.append(
+            header::SET_COOKIE,
+            "csrf=; Path=/; Max-Age=0".parse().unwrap(),
+        );
+        jar.store(&url, &headers);
+        assert_eq!(
+            jar.cookies()
+                .into_iter()
+                .map(|cookie| (cookie.name, cookie.value))
+                .collect_vec(),
+            [
+                ("session".into(), "def".into()),
+                ("theme".into(), "dark".into())
+            ] as [(String, String); 2]
+        );
+
+        jar.clear();
+        assert_eq!(jar.cookies(), []);
+    }
+}
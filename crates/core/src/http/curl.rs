@@ -1,12 +1,16 @@
 use crate::{
-    collection::Authentication,
-    http::{BodyStream, HttpMethod, RenderedBody, RequestBuildErrorKind},
+    collection::{Authentication, HttpMethod, HttpProtocol},
+    http::{
+        BodyStream, RenderedBody, RenderedFormPart, RequestBuildErrorKind,
+        certificate::CertificatePaths,
+    },
 };
 use bytes::BytesMut;
 use futures::TryStreamExt;
 use itertools::Itertools;
 use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
 use slumber_template::StreamSource;
+use std::{net::IpAddr, path::Path};
 
 /// Builder pattern for constructing cURL commands from a recipe
 pub struct CurlBuilder {
@@ -86,9 +90,112 @@ impl CurlBuilder {
                 )
                 // Failure isn't possible because we know the value is UTF-8
                 .unwrap(),
+            Authentication::Digest { username, password } => {
+                self.groups.push(vec![
+                    "--digest".into(),
+                    "--user".into(),
+                    format!("'{username}:{password}'"),
+                ]);
+                self
+            }
+            Authentication::Ntlm {
+                username,
+                password,
+                domain,
+            } => {
+                // curl takes the domain as a prefix of the username
+                let username = match domain {
+                    Some(domain) => format!("{domain}\\{username}"),
+                    None => username.clone(),
+                };
+                self.groups.push(vec![
+                    "--ntlm".into(),
+                    "--user".into(),
+                    format!("'{username}:{password}'"),
+                ]);
+                self
+            }
+            Authentication::OAuth2(_) => unreachable!(
+                "OAuth2 is resolved to a bearer token before building"
+            ),
         }
     }
 
+    /// Add a TLS client certificate to the command
+    pub fn certificate(mut self, certificate: &CertificatePaths) -> Self {
+        if certificate.is_pkcs12() {
+            // curl takes the password as a suffix of the path
+            let password = certificate
+                .password
+                .as_deref()
+                .map(|password| format!(":{password}"))
+                .unwrap_or_default();
+            self.groups.push(vec![
+                "--cert".into(),
+                format!("'{}{password}'", certificate.certificate.display()),
+            ]);
+            self.groups.push(vec!["--cert-type".into(), "P12".into()]);
+            return self;
+        }
+
+        self.groups.push(vec![
+            "--cert".into(),
+            format!("'{}'", certificate.certificate.display()),
+        ]);
+        if let Some(key) = &certificate.key {
+            self.groups
+                .push(vec!["--key".into(), format!("'{}'", key.display())]);
+        }
+        self
+    }
+
+    /// Send the request through a proxy, except for hosts in `no_proxy`
+    pub fn proxy(mut self, proxy: &str, no_proxy: &[String]) -> Self {
+        self.groups
+            .push(vec!["--proxy".into(), format!("'{proxy}'")]);
+        if !no_proxy.is_empty() {
+            self.groups.push(vec![
+                "--noproxy".into(),
+                format!("'{}'", no_proxy.join(",")),
+            ]);
+        }
+        self
+    }
+
+    /// Connect over a Unix socket instead of TCP
+    pub fn socket(mut self, socket: &Path) -> Self {
+        self.groups.push(vec![
+            "--unix-socket".into(),
+            format!("'{}'", socket.display()),
+        ]);
+        self
+    }
+
+    /// Connect to a fixed address for a host, instead of resolving it
+    pub fn resolve(mut self, host: &str, port: u16, address: IpAddr) -> Self {
+        let address = match address {
+            IpAddr::V4(address) => address.to_string(),
+            IpAddr::V6(address) => format!("[{address}]"),
+        };
+        self.groups.push(vec![
+            "--resolve".into(),
+            format!("'{host}:{port}:{address}'"),
+        ]);
+        self
+    }
+
+    /// Force an HTTP version. curl negotiates the version on its own, so
+    /// [HttpProtocol::Auto] adds nothing
+    pub fn protocol(mut self, protocol: HttpProtocol) -> Self {
+        let flag = match protocol {
+            HttpProtocol::Auto => return self,
+            HttpProtocol::Http1 => "--http1.1",
+            HttpProtocol::Http2 => "--http2-prior-knowledge",
+        };
+        self.groups.push(vec![flag.into()]);
+        self
+    }
+
     /// Add a body to the command. This is async because the body may be an
     /// stream that needs to be resolved.
     pub async fn body(
@@ -111,6 +218,19 @@ impl CurlBuilder {
                     format!("'@{path}'", path = path.to_string_lossy()),
                 ]);
             }
+            // Send the file as-is. --data would strip newlines
+            RenderedBody::File { path, content_type } => {
+                if let Some(content_type) = content_type {
+                    self.groups.push(vec![
+                        "--header".into(),
+                        format!("'{}: {content_type}'", header::CONTENT_TYPE),
+                    ]);
+                }
+                self.groups.push(vec![
+                    "--data-binary".into(),
+                    format!("'@{path}'", path = path.to_string_lossy()),
+                ]);
+            }
             // Any other type of has to be resolved eagerly since curl
             // doesn't support them natively
             RenderedBody::Stream(stream) => {
@@ -136,13 +256,19 @@ impl CurlBuilder {
                 }
             }
             RenderedBody::FormMultipart(form) => {
-                for (field, stream) in form {
+                for RenderedFormPart {
+                    field,
+                    stream,
+                    content_type,
+                    filename,
+                } in form
+                {
                     let argument = if let Some(StreamSource::File { path }) =
                         stream.source
                     {
                         // Files can be passed directly to curl
                         let path = path.to_string_lossy();
-                        format!("'{field}=@{path}'")
+                        format!("{field}=@{path}")
                     } else {
                         let bytes = stream
                             .stream
@@ -150,9 +276,21 @@ impl CurlBuilder {
                             .await
                             .map_err(RequestBuildErrorKind::BodyStream)?;
                         let text = as_text(&bytes)?;
-                        format!("'{field}={text}'")
+                        format!("{field}={text}")
                     };
-                    self.groups.push(vec!["-F".into(), argument]);
+                    // Part headers are passed as parameters after the value
+                    let headers =
+                        content_type
+                            .map(|content_type| format!(";type={content_type}"))
+                            .into_iter()
+                            .chain(filename.map(|filename| {
+                                format!(";filename={filename}")
+                            }))
+                            .join("");
+                    self.groups.push(vec![
+                        "-F".into(),
+                        format!("'{argument}{headers}'"),
+                    ]);
                 }
             }
         }
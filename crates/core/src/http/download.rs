@@ -0,0 +1,140 @@
+//! Stream response bodies straight to a file, for bodies too large to hold in
+//! memory. See [RequestTicket::download](super::RequestTicket::download).
+
+use crate::http::{RequestId, ResponseRecord, TransportError, pinning};
+use bytes::Bytes;
+use reqwest::{
+    Response, StatusCode,
+    header::{self, HeaderMap},
+};
+use slumber_util::format_byte_size;
+use std::{
+    fmt::{self, Display},
+    io,
+    path::{Path, PathBuf},
+};
+use thiserror::Error;
+use tokio::{fs::File, io::AsyncWriteExt};
+
+/// How much of a response body has been written to its file
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct DownloadProgress {
+    /// Bytes written so far
+    pub received: u64,
+    /// Size of the whole body, from the `Content-Length` header. `None` if the
+    /// server didn't say.
+    pub total: Option<u64>,
+}
+
+impl DownloadProgress {
+    /// Portion of the body received, from 0 to 1. `None` if the total size
+    /// isn't known.
+    pub fn ratio(&self) -> Option<f64> {
+        self.total
+            .filter(|total| *total > 0)
+            .map(|total| (self.received as f64 / total as f64).min(1.0))
+    }
+}
+
+impl Display for DownloadProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_byte_size(self.received as usize))?;
+        if let (Some(total), Some(ratio)) = (self.total, self.ratio()) {
+            write!(
+                f,
+                " / {} ({:.0}%)",
+                format_byte_size(total as usize),
+                ratio * 100.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A response body couldn't be written to its file
+#[derive(Debug, Error)]
+#[error("Error writing response body to `{}`", path.display())]
+pub struct DownloadError {
+    pub path: PathBuf,
+    #[source]
+    pub error: io::Error,
+}
+
+impl ResponseRecord {
+    /// Convert [reqwest::Response] type into [ResponseRecord], writing the body
+    /// to a file as it's received. The record's body is left empty. See
+    /// [RequestTicket::download](super::RequestTicket::download).
+    pub(super) async fn from_response_download(
+        id: RequestId,
+        mut response: Response,
+        path: &Path,
+        on_response: impl FnOnce(StatusCode, &HeaderMap),
+        mut on_progress: impl FnMut(DownloadProgress),
+    ) -> Result<ResponseRecord, TransportError> {
+        let http_version = response.version().into();
+        let certificate_fingerprint =
+            pinning::certificate_fingerprint(&response);
+        let status = response.status();
+        let headers = response.headers().clone();
+        on_response(status, &headers);
+
+        let io_error = |error| {
+            TransportError::new(DownloadError {
+                path: path.to_owned(),
+                error,
+            })
+        };
+        let mut file = File::create(path).await.map_err(io_error)?;
+        let mut progress = DownloadProgress {
+            received: 0,
+            total: content_length(&headers),
+        };
+        on_progress(progress);
+        while let Some(chunk) = response.chunk().await? {
+            file.write_all(&chunk).await.map_err(io_error)?;
+            progress.received += chunk.len() as u64;
+            on_progress(progress);
+        }
+        file.flush().await.map_err(io_error)?;
+
+        Ok(ResponseRecord {
+            id,
+            http_version,
+            certificate_fingerprint,
+            status,
+            headers,
+            body: Bytes::new().into(),
+            redirects: Vec::new(),
+        })
+    }
+}
+
+/// Get the body size from the `Content-Length` header
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::unknown_total(1500, None, "1.5 KB")]
+    #[case::partial(500_000, Some(2_000_000), "500.0 KB / 2.0 MB (25%)")]
+    #[case::done(2_000, Some(2_000), "2.0 KB / 2.0 KB (100%)")]
+    #[case::empty(0, Some(0), "0 B")]
+    fn test_progress_display(
+        #[case] received: u64,
+        #[case] total: Option<u64>,
+        #[case] expected: &str,
+    ) {
+        let progress = DownloadProgress { received, total };
+        assert_eq!(progress.to_string(), expected);
+    }
+}
@@ -0,0 +1,212 @@
+//! GraphQL schema introspection. A GraphQL recipe can be sent with the
+//! standard introspection query in place of its own, to load the server's
+//! schema. The schema isn't used by Slumber itself; it's handed to the user's
+//! editor so a GraphQL language server can complete fields in the query.
+
+use crate::http::ResponseRecord;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// The introspection query sent by graphql-js' `getIntrospectionQuery`. Tools
+/// that load a schema from an introspection result expect this shape.
+pub(super) const INTROSPECTION_QUERY: &str = "
+query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+";
+
+/// Get the schema from the response to an introspection query, i.e. a
+/// request built with
+/// [BuildOptions::graphql_introspection](super::BuildOptions::graphql_introspection).
+/// The result is the `data` field of the response, `{"__schema": ...}`, which
+/// is the format GraphQL tooling expects in a `.json` schema file.
+pub fn introspection_schema(
+    response: &ResponseRecord,
+) -> Result<serde_json::Value, IntrospectionError> {
+    #[derive(Deserialize)]
+    struct Body {
+        data: Option<serde_json::Value>,
+        #[serde(default)]
+        errors: Vec<Message>,
+    }
+
+    #[derive(Deserialize)]
+    struct Message {
+        message: String,
+    }
+
+    let body: Body = match serde_json::from_slice(response.body.bytes()) {
+        Ok(body) => body,
+        // GraphQL errors are usually JSON, even with an error status, so only
+        // fall back to the status if the body isn't
+        Err(_) if !response.status.is_success() => {
+            return Err(IntrospectionError::Status(response.status));
+        }
+        Err(error) => return Err(IntrospectionError::Json(error)),
+    };
+    if let Some(error) = body.errors.into_iter().next() {
+        return Err(IntrospectionError::Query(error.message));
+    }
+    body.data
+        .filter(|data| {
+            data.get("__schema")
+                .is_some_and(serde_json::Value::is_object)
+        })
+        .ok_or(IntrospectionError::NoSchema)
+}
+
+/// Error loading a schema from an introspection response
+#[derive(Debug, Error)]
+pub enum IntrospectionError {
+    /// Server returned a GraphQL error, e.g. because introspection is disabled
+    #[error("Introspection query failed: {0}")]
+    Query(String),
+    /// Response body isn't JSON
+    #[error("Invalid introspection response")]
+    Json(#[source] serde_json::Error),
+    /// Response has no schema and no errors to explain why
+    #[error("Introspection response doesn't contain a schema")]
+    NoSchema,
+    /// Response has an error status and a non-JSON body, e.g. from a proxy
+    #[error("Introspection request failed with status {0}")]
+    Status(StatusCode),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::ResponseBody;
+    use rstest::rstest;
+    use serde_json::json;
+    use slumber_util::{Factory, assert_err};
+
+    /// The schema is the `data` field of the response
+    #[test]
+    fn test_introspection_schema() {
+        let data = json!({"__schema": {"queryType": {"name": "Query"}}});
+        let response = response(
+            StatusCode::OK,
+            json!({"data": data}).to_string().as_bytes(),
+        );
+        assert_eq!(introspection_schema(&response).unwrap(), data);
+    }
+
+    /// Responses without a schema are errors
+    #[rstest]
+    #[case::query_error(
+        StatusCode::OK,
+        r#"{"errors": [{"message": "Introspection is disabled"}]}"#,
+        "Introspection query failed: Introspection is disabled"
+    )]
+    #[case::query_error_status(
+        StatusCode::BAD_REQUEST,
+        r#"{"errors": [{"message": "Introspection is disabled"}]}"#,
+        "Introspection query failed: Introspection is disabled"
+    )]
+    #[case::no_schema(
+        StatusCode::OK,
+        r#"{"data": {"user": null}}"#,
+        "Introspection response doesn't contain a schema"
+    )]
+    #[case::invalid_json(
+        StatusCode::OK,
+        "not json",
+        "Invalid introspection response"
+    )]
+    #[case::status(
+        StatusCode::BAD_GATEWAY,
+        "Bad Gateway",
+        "Introspection request failed with status 502 Bad Gateway"
+    )]
+    fn test_introspection_schema_error(
+        #[case] status: StatusCode,
+        #[case] body: &str,
+        #[case] expected_error: &str,
+    ) {
+        let response = response(status, body.as_bytes());
+        assert_err(introspection_schema(&response), expected_error);
+    }
+
+    fn response(status: StatusCode, body: &[u8]) -> ResponseRecord {
+        ResponseRecord {
+            status,
+            body: ResponseBody::new(body.to_vec().into()),
+            ..ResponseRecord::factory(())
+        }
+    }
+}
@@ -0,0 +1,730 @@
+//! gRPC client. Recipes with a `grpc` field are sent as an HTTP/2 `POST`
+//! carrying a single length-prefixed protobuf message. The recipe body is
+//! written as JSON, and encoded according to the method's schema before the
+//! request is sent. The schema is compiled from the recipe's `.proto` files,
+//! or loaded from the server via [reflection](https://github.com/grpc/grpc/blob/master/doc/server-reflection.md).
+//! The response message is decoded back to JSON.
+//!
+//! Only unary methods are supported. Messages are never compressed.
+
+use crate::http::{Transport, TransportError};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use http_body_util::BodyExt;
+use itertools::Itertools;
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, MethodDescriptor, SerializeOptions,
+};
+use prost_types::FileDescriptorProto;
+use reqwest::{
+    Method, Request, Response, StatusCode, Version,
+    header::{self, HeaderMap, HeaderValue},
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    path::PathBuf,
+};
+use thiserror::Error;
+
+/// `Content-Type` of every gRPC request
+pub(super) const CONTENT_TYPE: &str = "application/grpc";
+/// Reflection services to try, newest first. Older servers only implement the
+/// alpha version.
+const REFLECTION_PATHS: &[&str] = &[
+    "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+    "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+];
+/// Length of the prefix before each message: a compression flag and a
+/// big-endian length
+const PREFIX_LENGTH: usize = 5;
+/// Names of the gRPC status codes, indexed by code
+const STATUS_NAMES: &[&str] = &[
+    "OK",
+    "CANCELLED",
+    "UNKNOWN",
+    "INVALID_ARGUMENT",
+    "DEADLINE_EXCEEDED",
+    "NOT_FOUND",
+    "ALREADY_EXISTS",
+    "PERMISSION_DENIED",
+    "RESOURCE_EXHAUSTED",
+    "FAILED_PRECONDITION",
+    "ABORTED",
+    "OUT_OF_RANGE",
+    "UNIMPLEMENTED",
+    "INTERNAL",
+    "UNAVAILABLE",
+    "DATA_LOSS",
+    "UNAUTHENTICATED",
+];
+/// Status code for a method the server doesn't implement
+const UNIMPLEMENTED: u32 = 12;
+
+/// A gRPC method to call, from a recipe's `grpc` field
+#[derive(Debug)]
+pub(super) struct GrpcCall {
+    /// Fully qualified service name, e.g. `helloworld.Greeter`
+    pub service: String,
+    /// Method name within the service, e.g. `SayHello`
+    pub method: String,
+    /// Where to load the schema from
+    pub schema: GrpcSchema,
+}
+
+/// Source of the schema for a gRPC call
+#[derive(Debug)]
+pub(super) enum GrpcSchema {
+    /// Compile `.proto` files. Paths are absolute
+    Files {
+        files: Vec<PathBuf>,
+        import_paths: Vec<PathBuf>,
+    },
+    /// Ask the server for the schema
+    Reflection,
+}
+
+/// A response to a gRPC call, with the message decoded
+#[derive(Debug)]
+pub(super) struct GrpcResponse {
+    pub status: StatusCode,
+    /// Response headers, followed by trailers
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// Make a unary call. The request body should be the request message as JSON;
+/// it's replaced with the encoded message before sending.
+///
+/// If the call succeeds, the response body is the response message as JSON.
+/// If it fails with a gRPC status, the body is a JSON object with the status
+/// code and message. Either way, the `Content-Type` is changed to JSON. If the
+/// response isn't gRPC at all (e.g. a proxy rejected the request), it's
+/// returned unchanged.
+pub(super) async fn call(
+    transport: &dyn Transport,
+    mut request: Request,
+    call: GrpcCall,
+) -> Result<GrpcResponse, GrpcError> {
+    let pool = match call.schema {
+        GrpcSchema::Files {
+            files,
+            import_paths,
+        } => compile(&files, &import_paths)?,
+        GrpcSchema::Reflection => {
+            reflect(transport, &request, &call.service).await?
+        }
+    };
+    let method = get_method(&pool, &call.service, &call.method)?;
+
+    // Build verified that the body isn't a stream
+    let body = request
+        .body()
+        .and_then(reqwest::Body::as_bytes)
+        .unwrap_or_default();
+    let message = encode_request(&method, body)?;
+    *request.body_mut() = Some(frame(&message).into());
+
+    let response = load(transport.send(request).await?).await?;
+    let (status, messages) = match response.body {
+        GrpcBody::Grpc { status, messages } => (status, messages),
+        GrpcBody::Other(body) => {
+            return Ok(GrpcResponse {
+                status: response.status,
+                headers: response.headers,
+                body,
+            });
+        }
+    };
+
+    let body = if status.code == 0 {
+        let [message] = messages.as_slice() else {
+            return Err(GrpcError::MessageCount {
+                count: messages.len(),
+            });
+        };
+        decode_response(&method, message)?
+    } else {
+        let json = serde_json::json!({
+            "code": status.name(),
+            "message": status.message,
+        });
+        serde_json::to_vec_pretty(&json).unwrap().into()
+    };
+    let mut headers = response.headers;
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    Ok(GrpcResponse {
+        status: response.status,
+        headers,
+        body,
+    })
+}
+
+/// Turn a built request into a gRPC call: point the URL at the method, and
+/// set the headers gRPC requires. The body is encoded later, by [call].
+pub(super) fn prepare(request: &mut Request, call: &GrpcCall) {
+    if let Ok(mut segments) = request.url_mut().path_segments_mut() {
+        segments
+            .pop_if_empty()
+            .push(&call.service)
+            .push(&call.method);
+    }
+    *request.version_mut() = Version::HTTP_2;
+    let headers = request.headers_mut();
+    headers
+        .insert(header::CONTENT_TYPE, HeaderValue::from_static(CONTENT_TYPE));
+    headers.insert(header::TE, HeaderValue::from_static("trailers"));
+}
+
+/// Compile `.proto` files into a descriptor pool
+fn compile(
+    files: &[PathBuf],
+    import_paths: &[PathBuf],
+) -> Result<DescriptorPool, GrpcError> {
+    let mut compiler = protox::Compiler::new(import_paths)?;
+    compiler.include_imports(true).open_files(files)?;
+    Ok(compiler.descriptor_pool())
+}
+
+/// Load the schema for a service from the server via reflection. The
+/// reflection requests go to the same server as `request`, with the same
+/// headers, so they're authenticated the same way.
+async fn reflect(
+    transport: &dyn Transport,
+    request: &Request,
+    service: &str,
+) -> Result<DescriptorPool, GrpcError> {
+    let mut reflection_paths = REFLECTION_PATHS.iter();
+    let mut reflection_path = reflection_paths.next().unwrap();
+    let mut files: HashMap<String, FileDescriptorProto> = HashMap::new();
+    let mut requested: HashSet<String> = HashSet::new();
+    let mut queue =
+        vec![ReflectionRequest::FileContainingSymbol(service.to_owned())];
+
+    while let Some(message_request) = queue.pop() {
+        let message = ServerReflectionRequest {
+            host: String::new(),
+            message_request: Some(message_request),
+        }
+        .encode_to_vec();
+        let reflection_request =
+            reflection_request(request, reflection_path, &message);
+        let response = load(transport.send(reflection_request).await?).await?;
+        let messages = match response.body {
+            GrpcBody::Grpc { status, messages } if status.code == 0 => messages,
+            // Fall back to the older version of the service
+            GrpcBody::Grpc { status, .. } if status.code == UNIMPLEMENTED => {
+                if let Some(path) = reflection_paths.next() {
+                    reflection_path = path;
+                    requested.clear();
+                    queue = vec![ReflectionRequest::FileContainingSymbol(
+                        service.to_owned(),
+                    )];
+                    continue;
+                }
+                return Err(GrpcError::ReflectionStatus { status });
+            }
+            GrpcBody::Grpc { status, .. } => {
+                return Err(GrpcError::ReflectionStatus { status });
+            }
+            GrpcBody::Other(_) => {
+                return Err(GrpcError::ReflectionHttpStatus {
+                    status: response.status,
+                });
+            }
+        };
+
+        for message in messages {
+            let response = ServerReflectionResponse::decode(message)
+                .map_err(GrpcError::ReflectionDecode)?;
+            let descriptors = match response.message_response {
+                Some(ReflectionResponse::FileDescriptorResponse(response)) => {
+                    response.file_descriptor_proto
+                }
+                Some(ReflectionResponse::ErrorResponse(error)) => {
+                    return Err(GrpcError::Reflection {
+                        message: error.error_message,
+                    });
+                }
+                None => continue,
+            };
+            for descriptor in descriptors {
+                let file = FileDescriptorProto::decode(descriptor.as_slice())
+                    .map_err(GrpcError::ReflectionDecode)?;
+                // Servers usually send dependencies along with the file that
+                // needs them, but they don't have to. Ask for any that are
+                // missing
+                for dependency in &file.dependency {
+                    if !files.contains_key(dependency)
+                        && requested.insert(dependency.clone())
+                    {
+                        queue.push(ReflectionRequest::FileByFilename(
+                            dependency.clone(),
+                        ));
+                    }
+                }
+                files.insert(file.name().to_owned(), file);
+            }
+        }
+    }
+
+    let mut pool = DescriptorPool::new();
+    pool.add_file_descriptor_protos(files.into_values())?;
+    Ok(pool)
+}
+
+/// Build a request to the reflection service, based on the request for the
+/// actual call
+fn reflection_request(
+    request: &Request,
+    path: &str,
+    message: &[u8],
+) -> Request {
+    let mut url = request.url().clone();
+    url.set_path(path);
+    let mut reflection_request = Request::new(Method::POST, url);
+    *reflection_request.headers_mut() = request.headers().clone();
+    *reflection_request.version_mut() = Version::HTTP_2;
+    *reflection_request.body_mut() = Some(frame(message).into());
+    reflection_request
+}
+
+/// Find a method in the schema. It must be unary
+fn get_method(
+    pool: &DescriptorPool,
+    service: &str,
+    method: &str,
+) -> Result<MethodDescriptor, GrpcError> {
+    let service_descriptor =
+        pool.get_service_by_name(service).ok_or_else(|| {
+            GrpcError::ServiceUnknown {
+                service: service.to_owned(),
+                options: pool
+                    .services()
+                    .map(|service| service.full_name().to_owned())
+                    .collect(),
+            }
+        })?;
+    let method_descriptor = service_descriptor
+        .methods()
+        .find(|descriptor| descriptor.name() == method)
+        .ok_or_else(|| GrpcError::MethodUnknown {
+            service: service.to_owned(),
+            method: method.to_owned(),
+            options: service_descriptor
+                .methods()
+                .map(|method| method.name().to_owned())
+                .collect(),
+        })?;
+    if method_descriptor.is_client_streaming()
+        || method_descriptor.is_server_streaming()
+    {
+        return Err(GrpcError::Streaming {
+            method: method_descriptor.full_name().to_owned(),
+        });
+    }
+    Ok(method_descriptor)
+}
+
+/// Encode a JSON request body as the method's input message. An empty body is
+/// an empty message.
+fn encode_request(
+    method: &MethodDescriptor,
+    body: &[u8],
+) -> Result<Vec<u8>, GrpcError> {
+    let descriptor = method.input();
+    if body.trim_ascii().is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut deserializer = serde_json::Deserializer::from_slice(body);
+    let message =
+        DynamicMessage::deserialize(descriptor.clone(), &mut deserializer)
+            .and_then(|message| deserializer.end().map(|()| message))
+            .map_err(|error| GrpcError::RequestMessage {
+                message: descriptor.full_name().to_owned(),
+                error,
+            })?;
+    Ok(message.encode_to_vec())
+}
+
+/// Decode a response message as pretty-printed JSON. Fields with default
+/// values are included, so the whole shape of the message is visible.
+fn decode_response(
+    method: &MethodDescriptor,
+    message: &[u8],
+) -> Result<Bytes, GrpcError> {
+    let message = DynamicMessage::decode(method.output(), message)
+        .map_err(GrpcError::ResponseMessage)?;
+    let mut body = Vec::new();
+    let mut serializer = serde_json::Serializer::pretty(&mut body);
+    message
+        .serialize_with_options(
+            &mut serializer,
+            &SerializeOptions::new().skip_default_fields(false),
+        )
+        .expect("Serializing to a Vec can't fail");
+    Ok(body.into())
+}
+
+/// Prefix a message with its length, for a request body
+fn frame(message: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(PREFIX_LENGTH + message.len());
+    frame.put_u8(0); // Not compressed
+    frame.put_u32(message.len().try_into().expect("Message too large"));
+    frame.put_slice(message);
+    frame.freeze()
+}
+
+/// Split a response body into its length-prefixed messages
+fn unframe(mut body: Bytes) -> Result<Vec<Bytes>, GrpcError> {
+    let mut messages = Vec::new();
+    while !body.is_empty() {
+        if body.len() < PREFIX_LENGTH {
+            return Err(GrpcError::Frame);
+        }
+        let compressed = body.get_u8();
+        let length = body.get_u32() as usize;
+        if compressed != 0 {
+            return Err(GrpcError::Compressed);
+        }
+        if body.len() < length {
+            return Err(GrpcError::Frame);
+        }
+        messages.push(body.split_to(length));
+    }
+    Ok(messages)
+}
+
+/// A response, with its body and trailers loaded
+struct LoadedResponse {
+    status: StatusCode,
+    /// Headers, followed by trailers
+    headers: HeaderMap,
+    body: GrpcBody,
+}
+
+enum GrpcBody {
+    /// A gRPC response, split into messages
+    Grpc {
+        status: GrpcStatus,
+        messages: Vec<Bytes>,
+    },
+    /// A response that isn't from a gRPC server, left as-is
+    Other(Bytes),
+}
+
+/// Load an entire response, including trailers. gRPC servers send the status
+/// in the trailers, or in the headers if there's no body.
+async fn load(response: Response) -> Result<LoadedResponse, GrpcError> {
+    let status = response.status();
+    let is_grpc = status == StatusCode::OK
+        && response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with(CONTENT_TYPE));
+    if !is_grpc {
+        let headers = response.headers().clone();
+        let body = response.bytes().await.map_err(GrpcError::Body)?;
+        return Ok(LoadedResponse {
+            status,
+            headers,
+            body: GrpcBody::Other(body),
+        });
+    }
+
+    let (parts, mut body) = http::Response::from(response).into_parts();
+    let mut headers = parts.headers;
+    let mut data = BytesMut::new();
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(GrpcError::Body)?;
+        match frame.into_data() {
+            Ok(chunk) => data.extend_from_slice(&chunk),
+            Err(frame) => {
+                if let Ok(trailers) = frame.into_trailers() {
+                    headers.extend(trailers);
+                }
+            }
+        }
+    }
+
+    let grpc_status = GrpcStatus::from_headers(&headers)?;
+    Ok(LoadedResponse {
+        status,
+        headers,
+        body: GrpcBody::Grpc {
+            status: grpc_status,
+            messages: unframe(data.freeze())?,
+        },
+    })
+}
+
+/// Outcome of a gRPC call, from the `grpc-status` and `grpc-message` headers
+#[derive(Debug)]
+pub struct GrpcStatus {
+    pub code: u32,
+    pub message: String,
+}
+
+impl GrpcStatus {
+    fn from_headers(headers: &HeaderMap) -> Result<Self, GrpcError> {
+        let code = headers
+            .get("grpc-status")
+            .and_then(|value| value.to_str().ok()?.parse().ok())
+            .ok_or(GrpcError::MissingStatus)?;
+        let message = headers
+            .get("grpc-message")
+            .map(|value| percent_decode(value.as_bytes()))
+            .unwrap_or_default();
+        Ok(Self { code, message })
+    }
+
+    /// Name of the status code, e.g. `NOT_FOUND`
+    fn name(&self) -> String {
+        STATUS_NAMES
+            .get(self.code as usize)
+            .map_or_else(|| self.code.to_string(), |name| (*name).to_owned())
+    }
+}
+
+impl Display for GrpcStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())?;
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decode a percent-encoded `grpc-message` value
+fn percent_decode(value: &[u8]) -> String {
+    let mut decoded = Vec::with_capacity(value.len());
+    let mut bytes = value.iter();
+    while let Some(&byte) = bytes.next() {
+        let hex = bytes.clone().take(2).copied().collect_vec();
+        let escaped = (byte == b'%')
+            .then(|| std::str::from_utf8(&hex).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(escaped) = escaped {
+            decoded.push(escaped);
+            bytes.nth(1);
+        } else {
+            decoded.push(byte);
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// An error making a gRPC call. A call that completes with a non-OK status is
+/// *not* an error; the status is returned as the response body.
+#[derive(Debug, Error)]
+pub enum GrpcError {
+    /// Error loading the response body or trailers
+    #[error("Loading response")]
+    Body(#[source] reqwest::Error),
+
+    /// Error compiling the recipe's `.proto` files
+    #[error("Compiling .proto files")]
+    Compile(
+        #[from]
+        #[source]
+        protox::Error,
+    ),
+
+    /// The server compressed a message, even though we never ask it to
+    #[error("Compressed gRPC messages are not supported")]
+    Compressed,
+
+    /// Schema references types that aren't defined
+    #[error("Invalid schema from server reflection")]
+    Descriptor(
+        #[from]
+        #[source]
+        prost_reflect::DescriptorError,
+    ),
+
+    /// A message's length prefix doesn't match the body
+    #[error("Malformed gRPC response body")]
+    Frame,
+
+    /// Unary calls must return exactly one message
+    #[error("Expected one response message, but got {count}")]
+    MessageCount { count: usize },
+
+    /// Method isn't defined in the service
+    #[error(
+        "Method `{method}` not found in service `{service}`; options are: {}",
+        options.join(", ")
+    )]
+    MethodUnknown {
+        service: String,
+        method: String,
+        options: Vec<String>,
+    },
+
+    /// Response didn't include a `grpc-status` header or trailer
+    #[error("Response is missing `grpc-status`")]
+    MissingStatus,
+
+    /// Server returned an error from a reflection request
+    #[error("Server reflection failed: {message}")]
+    Reflection { message: String },
+
+    /// Reflection response isn't a valid protobuf message
+    #[error("Decoding server reflection response")]
+    ReflectionDecode(#[source] prost::DecodeError),
+
+    /// Reflection request didn't get a gRPC response, e.g. because the server
+    /// isn't actually a gRPC server
+    #[error("Server reflection failed with HTTP status {status}")]
+    ReflectionHttpStatus { status: StatusCode },
+
+    /// Reflection call failed
+    #[error(
+        "Server reflection failed with {status}. Enable reflection on the \
+        server, or set `proto_files` on the recipe"
+    )]
+    ReflectionStatus { status: GrpcStatus },
+
+    /// Request body doesn't match the method's input type
+    #[error("Encoding request body as `{message}`")]
+    RequestMessage {
+        message: String,
+        #[source]
+        error: serde_json::Error,
+    },
+
+    /// Response message doesn't match the method's output type
+    #[error("Decoding response message")]
+    ResponseMessage(#[source] prost::DecodeError),
+
+    /// Service isn't defined in the schema
+    #[error(
+        "Service `{service}` not found; options are: {}",
+        options.join(", ")
+    )]
+    ServiceUnknown {
+        service: String,
+        options: Vec<String>,
+    },
+
+    /// Method streams requests or responses
+    #[error(
+        "`{method}` is a streaming method; only unary methods are supported"
+    )]
+    Streaming { method: String },
+
+    /// Error sending a reflection request or the call itself
+    #[error(transparent)]
+    Transport(#[from] TransportError),
+}
+
+impl From<GrpcError> for TransportError {
+    fn from(error: GrpcError) -> Self {
+        match error {
+            // Don't hide transport errors, so they can be inspected
+            GrpcError::Transport(error) => error,
+            error => Self::new(error),
+        }
+    }
+}
+
+/// Request to the reflection service. Only the fields we use are defined.
+/// See [reflection.proto](https://github.com/grpc/grpc/blob/master/src/proto/grpc/reflection/v1/reflection.proto)
+#[derive(Clone, PartialEq, Message)]
+struct ServerReflectionRequest {
+    #[prost(string, tag = "1")]
+    host: String,
+    #[prost(oneof = "ReflectionRequest", tags = "3, 4")]
+    message_request: Option<ReflectionRequest>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum ReflectionRequest {
+    #[prost(string, tag = "3")]
+    FileByFilename(String),
+    #[prost(string, tag = "4")]
+    FileContainingSymbol(String),
+}
+
+/// Response from the reflection service. Only the fields we use are defined.
+#[derive(Clone, PartialEq, Message)]
+struct ServerReflectionResponse {
+    #[prost(oneof = "ReflectionResponse", tags = "4, 7")]
+    message_response: Option<ReflectionResponse>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum ReflectionResponse {
+    #[prost(message, tag = "4")]
+    FileDescriptorResponse(FileDescriptorResponse),
+    #[prost(message, tag = "7")]
+    ErrorResponse(ErrorResponse),
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct FileDescriptorResponse {
+    /// Each file is a serialized `FileDescriptorProto`
+    #[prost(bytes = "vec", repeated, tag = "1")]
+    file_descriptor_proto: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+struct ErrorResponse {
+    #[prost(int32, tag = "1")]
+    error_code: i32,
+    #[prost(string, tag = "2")]
+    error_message: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_util::assert_err;
+
+    /// Split a body into messages
+    #[rstest]
+    #[case::empty(b"", &[])]
+    #[case::one(b"\0\0\0\0\x03abc", &["abc"])]
+    #[case::many(b"\0\0\0\0\x01a\0\0\0\0\0\0\0\0\0\x02bc", &["a", "", "bc"])]
+    fn test_unframe(#[case] body: &'static [u8], #[case] expected: &[&str]) {
+        let messages = unframe(Bytes::from_static(body)).unwrap();
+        assert_eq!(
+            messages,
+            expected.iter().map(|m| m.as_bytes()).collect_vec()
+        );
+    }
+
+    /// Framing and unframing are inverses
+    #[test]
+    fn test_frame() {
+        let framed = frame(b"hello");
+        assert_eq!(&framed[..], b"\0\0\0\0\x05hello");
+        assert_eq!(unframe(framed).unwrap(), [&b"hello"[..]]);
+    }
+
+    #[rstest]
+    #[case::prefix(b"\0\0\0", "Malformed gRPC response body")]
+    #[case::length(b"\0\0\0\0\x05abc", "Malformed gRPC response body")]
+    #[case::compressed(b"\x01\0\0\0\x01a", "Compressed gRPC messages")]
+    fn test_unframe_error(
+        #[case] body: &'static [u8],
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(unframe(Bytes::from_static(body)), expected_error);
+    }
+
+    #[rstest]
+    #[case::plain("not found", "not found")]
+    #[case::escaped("No%20user%3A%20%E2%9C%93", "No user: ✓")]
+    #[case::invalid("100%%zz%", "100%%zz%")]
+    fn test_percent_decode(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(percent_decode(value.as_bytes()), expected);
+    }
+}
@@ -0,0 +1,576 @@
+//! Troubleshooting hints for failed requests. A bare error from the HTTP
+//! client (or a bare 4xx status) often doesn't say what to actually change in
+//! the collection. These hints combine the kind of failure with the rendered
+//! request and the recipe that generated it, to point at the likely culprit.
+
+use crate::{
+    collection::{Authentication, AuthenticationSetting, Recipe},
+    http::{
+        Exchange, RedirectError, RequestError, RequestRecord, TimeoutError,
+        TimeoutPhase, TransportError,
+    },
+    render::TemplateReference,
+};
+use itertools::Itertools;
+use reqwest::{StatusCode, header};
+use slumber_template::{Identifier, Template};
+use slumber_util::doc_link;
+use std::{error::Error, io};
+
+impl RequestError {
+    /// Get a troubleshooting hint for this error, if we have anything useful
+    /// to say. Pass the recipe that the
+    /// request was built from, if it's still available, so the hint can refer
+    /// to the profile fields used by the URL.
+    pub fn hint(&self, recipe: Option<&Recipe>) -> Option<String> {
+        let request = &self.request;
+        let host = request.url.host_str().unwrap_or_default();
+        let hint = match FailureKind::from_error(&self.error) {
+            FailureKind::Dns => format!(
+                "Host `{host}` could not be resolved; {}",
+                url_hint(request, recipe)
+            ),
+            FailureKind::Unreachable => format!(
+                "`{host}` is unreachable from this network. Check your \
+                connection, or whether a VPN is required"
+            ),
+            FailureKind::ConnectionRefused => {
+                let port =
+                    request.url.port_or_known_default().unwrap_or_default();
+                format!(
+                    "Nothing is accepting connections at `{host}:{port}`. Is \
+                    the server running? Otherwise, {}",
+                    url_hint(request, recipe)
+                )
+            }
+            FailureKind::Tls => format!(
+                "TLS handshake with `{host}` failed. If the server's \
+                certificate is issued by an internal CA, add the CA to \
+                `ca_certificates` in the config. If it's self-signed, add \
+                `{host}` to `ignore_certificate_hosts`; see {}",
+                doc_link("troubleshooting/tls")
+            ),
+            FailureKind::Timeout(Some(TimeoutPhase::Total)) => format!(
+                "`{host}` accepted the connection but didn't finish \
+                responding in time. If the endpoint is just slow, raise \
+                `timeout.total_ms` on the recipe"
+            ),
+            FailureKind::Timeout(_) => format!(
+                "`{host}` didn't respond in time. Check that the server is \
+                reachable from this network (e.g. no VPN or proxy required)"
+            ),
+            FailureKind::Redirect => "Too many redirects. Check for a \
+                redirect loop, or set `redirect.follow: false` on the recipe \
+                (or `follow_redirects: false` in the config) to inspect the \
+                first response"
+                .to_owned(),
+            FailureKind::Other => return None,
+        };
+        Some(hint)
+    }
+
+    /// Did the request fail because the network is unavailable, rather than
+    /// because of anything to do with the server? This covers hostnames that
+    /// can't be resolved, unreachable networks, and connections that time
+    /// out. A refused connection means the host was reached, so it doesn't
+    /// count.
+    pub fn is_offline(&self) -> bool {
+        matches!(
+            FailureKind::from_error(&self.error),
+            FailureKind::Dns
+                | FailureKind::Unreachable
+                | FailureKind::Timeout(Some(TimeoutPhase::Connect))
+        )
+    }
+}
+
+impl Exchange {
+    /// Get a troubleshooting hint for a client error (4xx) response. Other
+    /// statuses don't get any hints: success needs no help, and server errors
+    /// are generally out of the user's control. Pass the recipe that the
+    /// request was built from, if it's still available, so the hint can refer
+    /// to the profile fields used by the recipe.
+    pub fn hint(&self, recipe: Option<&Recipe>) -> Option<String> {
+        let request = &self.request;
+        let response = &self.response;
+        let header = |name| {
+            response
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        let hint = match response.status {
+            StatusCode::BAD_REQUEST | StatusCode::UNPROCESSABLE_ENTITY => {
+                "The server rejected the request as invalid. The response body \
+                may say which part; compare it against the Request tab"
+                    .to_owned()
+            }
+            StatusCode::UNAUTHORIZED => {
+                let authentication = recipe
+                    .and_then(|recipe| recipe.authentication.as_ref())
+                    .and_then(AuthenticationSetting::authentication);
+                // Challenge/response schemes add their header after the
+                // request is recorded, so it won't be in the record
+                let is_challenge = matches!(
+                    authentication,
+                    Some(
+                        Authentication::Digest { .. }
+                            | Authentication::Ntlm { .. }
+                    )
+                );
+                if is_challenge
+                    || request.headers.contains_key(header::AUTHORIZATION)
+                {
+                    let fields = authentication
+                        .map(|authentication| match authentication {
+                            Authentication::Basic { username, password } => {
+                                profile_fields(
+                                    [Some(username), password.as_ref()]
+                                        .into_iter()
+                                        .flatten(),
+                                )
+                            }
+                            Authentication::Bearer { token } => {
+                                profile_fields([token])
+                            }
+                            Authentication::Digest { username, password } => {
+                                profile_fields([username, password])
+                            }
+                            Authentication::Ntlm {
+                                username,
+                                password,
+                                domain,
+                            } => profile_fields(
+                                [
+                                    Some(username),
+                                    Some(password),
+                                    domain.as_ref(),
+                                ]
+                                .into_iter()
+                                .flatten(),
+                            ),
+                            Authentication::OAuth2(oauth2) => profile_fields(
+                                [
+                                    Some(&oauth2.client_id),
+                                    oauth2.client_secret.as_ref(),
+                                ]
+                                .into_iter()
+                                .flatten(),
+                            ),
+                        })
+                        .unwrap_or_default();
+                    if fields.is_empty() {
+                        "The credentials were rejected. Check the \
+                        `Authorization` header in the Request tab"
+                            .to_owned()
+                    } else {
+                        format!(
+                            "The credentials were rejected. Check profile {}",
+                            describe_fields(&fields)
+                        )
+                    }
+                } else {
+                    "No credentials were sent. Add `authentication` to the \
+                    recipe"
+                        .to_owned()
+                }
+            }
+            StatusCode::FORBIDDEN => {
+                "The credentials were accepted, but don't \
+                have access to this resource"
+                    .to_owned()
+            }
+            StatusCode::NOT_FOUND => format!(
+                "Nothing was found at `{}`; {}",
+                request.url.path(),
+                url_hint(request, recipe)
+            ),
+            StatusCode::METHOD_NOT_ALLOWED => {
+                let method = request.method;
+                match header(header::ALLOW) {
+                    Some(allow) => format!(
+                        "`{method}` isn't allowed for this URL. The server \
+                        allows: {allow}"
+                    ),
+                    None => format!("`{method}` isn't allowed for this URL"),
+                }
+            }
+            StatusCode::UNSUPPORTED_MEDIA_TYPE => {
+                match request
+                    .headers
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                {
+                    Some(content_type) => format!(
+                        "The server doesn't accept `{content_type}` bodies. \
+                        Check the recipe's body type and `Content-Type` header"
+                    ),
+                    None => "No `Content-Type` header was sent. Add one to \
+                        the recipe, or use a structured body type such as \
+                        `json`"
+                        .to_owned(),
+                }
+            }
+            StatusCode::TOO_MANY_REQUESTS => {
+                match header(header::RETRY_AFTER) {
+                    Some(retry_after) => format!(
+                        "Rate limited by the server. Retry after: {retry_after}"
+                    ),
+                    None => "Rate limited by the server. Wait a bit before \
+                    sending again"
+                        .to_owned(),
+                }
+            }
+            _ => return None,
+        };
+        Some(hint)
+    }
+}
+
+/// Broad categories of request failure, each of which gets its own hint
+#[derive(Debug, PartialEq)]
+enum FailureKind {
+    Dns,
+    /// No route to the network or host
+    Unreachable,
+    ConnectionRefused,
+    Tls,
+    /// Includes the phase that timed out, if known
+    Timeout(Option<TimeoutPhase>),
+    Redirect,
+    Other,
+}
+
+impl FailureKind {
+    fn from_error(error: &TransportError) -> Self {
+        if let Some(error) = error.0.downcast_ref::<TimeoutError>() {
+            return Self::Timeout(Some(error.phase));
+        }
+        if let Some(error) = error.as_reqwest() {
+            if error.is_timeout() {
+                return Self::Timeout(None);
+            }
+            if error.is_redirect() {
+                return Self::Redirect;
+            }
+        }
+        if error.0.downcast_ref::<RedirectError>().is_some() {
+            return Self::Redirect;
+        }
+
+        // The HTTP client doesn't expose the underlying cause in a structured
+        // way, so we have to walk the chain and look for tell-tale signs
+        let mut next: Option<&dyn Error> = Some(&*error.0);
+        while let Some(error) = next {
+            if let Some(error) = error.downcast_ref::<io::Error>() {
+                match error.kind() {
+                    io::ErrorKind::ConnectionRefused => {
+                        return Self::ConnectionRefused;
+                    }
+                    io::ErrorKind::NetworkUnreachable
+                    | io::ErrorKind::HostUnreachable => {
+                        return Self::Unreachable;
+                    }
+                    _ => {}
+                }
+            }
+            let message = error.to_string().to_lowercase();
+            if message.contains("dns error")
+                || message.contains("failed to lookup address")
+            {
+                return Self::Dns;
+            }
+            if message.contains("certificate")
+                || message.contains("handshake")
+                || message.contains("tls")
+            {
+                return Self::Tls;
+            }
+            next = error.source();
+        }
+        Self::Other
+    }
+}
+
+/// Point the user at the source of the request URL. If the URL template uses
+/// profile fields, those are the most likely cause of a bad URL.
+fn url_hint(request: &RequestRecord, recipe: Option<&Recipe>) -> String {
+    let fields = recipe
+        .map(|recipe| profile_fields([&recipe.url]))
+        .unwrap_or_default();
+    if fields.is_empty() {
+        format!("check the recipe URL, currently `{}`", request.url)
+    } else {
+        format!(
+            "check profile {}; the URL currently renders to `{}`",
+            describe_fields(&fields),
+            request.url
+        )
+    }
+}
+
+/// Get all profile fields referenced by some templates, without duplicates
+fn profile_fields<'a>(
+    templates: impl IntoIterator<Item = &'a Template>,
+) -> Vec<Identifier> {
+    let mut fields = Vec::new();
+    for template in templates {
+        for reference in TemplateReference::find_all(template) {
+            if let TemplateReference::ProfileField(field) = reference
+                && !fields.contains(&field)
+            {
+                fields.push(field);
+            }
+        }
+    }
+    fields
+}
+
+/// Describe a list of profile fields in a sentence, e.g. "field `host`" or
+/// "fields `host`, `port`"
+fn describe_fields(fields: &[Identifier]) -> String {
+    let list = fields.iter().map(|field| format!("`{field}`")).join(", ");
+    if fields.len() == 1 {
+        format!("field {list}")
+    } else {
+        format!("fields {list}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http::{RequestId, ResponseRecord},
+        test_util::header_map,
+    };
+    use chrono::Utc;
+    use rstest::rstest;
+    use slumber_util::Factory;
+    use std::net::TcpListener;
+
+    /// Send a real request to get an error from the HTTP client
+    async fn request_error(url: &str) -> RequestError {
+        let error = reqwest::get(url).await.unwrap_err();
+        RequestError {
+            error: error.into(),
+            request: RequestRecord {
+                url: url.parse().unwrap(),
+                ..RequestRecord::factory(())
+            }
+            .into(),
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+        }
+    }
+
+    /// Send a real request to get an error from the HTTP client, then generate
+    /// a hint for it
+    async fn request_error_hint(url: &str, recipe: &Recipe) -> Option<String> {
+        request_error(url).await.hint(Some(recipe))
+    }
+
+    /// Failures to reach the network count as offline, but a refused
+    /// connection means the host is up
+    #[rstest]
+    #[tokio::test]
+    async fn test_is_offline() {
+        assert!(
+            request_error("http://fake.invalid/users")
+                .await
+                .is_offline()
+        );
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{port}/users");
+        assert!(!request_error(&url).await.is_offline());
+    }
+
+    /// Unresolvable hosts point at the profile fields used in the URL
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_error_dns() {
+        let recipe = Recipe {
+            url: "{{ host }}/users".into(),
+            ..Recipe::factory(())
+        };
+        assert_eq!(
+            request_error_hint("http://fake.invalid/users", &recipe)
+                .await
+                .as_deref(),
+            Some(
+                "Host `fake.invalid` could not be resolved; check profile \
+                field `host`; the URL currently renders to \
+                `http://fake.invalid/users`"
+            )
+        );
+    }
+
+    /// Refused connections suggest the server isn't running
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_error_connection_refused() {
+        // Grab a free port, then close it so nothing is listening there
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let url = format!("http://127.0.0.1:{port}/users");
+        let recipe = Recipe {
+            url: Template::raw(url.clone()),
+            ..Recipe::factory(())
+        };
+        assert_eq!(
+            request_error_hint(&url, &recipe).await,
+            Some(format!(
+                "Nothing is accepting connections at `127.0.0.1:{port}`. Is \
+                the server running? Otherwise, check the recipe URL, \
+                currently `{url}`"
+            ))
+        );
+    }
+
+    /// Client errors get a hint specific to the status code
+    #[rstest]
+    #[case::success(StatusCode::OK, &[], &[], None)]
+    #[case::server_error(StatusCode::INTERNAL_SERVER_ERROR, &[], &[], None)]
+    #[case::bad_request(
+        StatusCode::BAD_REQUEST,
+        &[],
+        &[],
+        Some(
+            "The server rejected the request as invalid. The response body \
+            may say which part; compare it against the Request tab"
+        ),
+    )]
+    #[case::unauthorized_no_credentials(
+        StatusCode::UNAUTHORIZED,
+        &[],
+        &[],
+        Some("No credentials were sent. Add `authentication` to the recipe"),
+    )]
+    #[case::unauthorized_rejected(
+        StatusCode::UNAUTHORIZED,
+        &[("Authorization", "Bearer abc")],
+        &[],
+        Some("The credentials were rejected. Check profile field `token`"),
+    )]
+    #[case::not_found(
+        StatusCode::NOT_FOUND,
+        &[],
+        &[],
+        Some(
+            "Nothing was found at `/url`; check profile field `host`; the URL \
+            currently renders to `http://localhost/url`"
+        ),
+    )]
+    #[case::method_not_allowed(
+        StatusCode::METHOD_NOT_ALLOWED,
+        &[],
+        &[("Allow", "POST, PUT")],
+        Some("`GET` isn't allowed for this URL. The server allows: POST, PUT"),
+    )]
+    #[case::unsupported_media_type(
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        &[("Content-Type", "text/plain")],
+        &[],
+        Some(
+            "The server doesn't accept `text/plain` bodies. Check the recipe's \
+            body type and `Content-Type` header"
+        ),
+    )]
+    #[case::rate_limited(
+        StatusCode::TOO_MANY_REQUESTS,
+        &[],
+        &[("Retry-After", "120")],
+        Some("Rate limited by the server. Retry after: 120"),
+    )]
+    fn test_exchange_hint(
+        #[case] status: StatusCode,
+        #[case] request_headers: &[(&str, &str)],
+        #[case] response_headers: &[(&str, &str)],
+        #[case] expected: Option<&str>,
+    ) {
+        let recipe = Recipe {
+            url: "{{ host }}/url".into(),
+            authentication: Some(
+                Authentication::Bearer {
+                    token: "{{ token }}".into(),
+                }
+                .into(),
+            ),
+            ..Recipe::factory(())
+        };
+        let id = RequestId::new();
+        let exchange = Exchange::factory((
+            RequestRecord {
+                headers: header_map(request_headers.iter().copied()),
+                ..RequestRecord::factory(id)
+            },
+            ResponseRecord {
+                status,
+                headers: header_map(response_headers.iter().copied()),
+                ..ResponseRecord::factory(id)
+            },
+        ));
+        assert_eq!(exchange.hint(Some(&recipe)).as_deref(), expected);
+    }
+
+    /// Digest and NTLM headers are added by the transport, so they aren't in
+    /// the record. A 401 still means the credentials were rejected
+    #[test]
+    fn test_exchange_hint_challenge() {
+        let recipe = Recipe {
+            authentication: Some(
+                Authentication::Digest {
+                    username: "user".into(),
+                    password: "{{ password }}".into(),
+                }
+                .into(),
+            ),
+            ..Recipe::factory(())
+        };
+        let id = RequestId::new();
+        let exchange = Exchange::factory((
+            RequestRecord::factory(id),
+            ResponseRecord {
+                status: StatusCode::UNAUTHORIZED,
+                ..ResponseRecord::factory(id)
+            },
+        ));
+        assert_eq!(
+            exchange.hint(Some(&recipe)).as_deref(),
+            Some(
+                "The credentials were rejected. Check profile field `password`"
+            )
+        );
+    }
+
+    /// Without the recipe, hints fall back to the rendered request
+    #[test]
+    fn test_exchange_hint_no_recipe() {
+        let id = RequestId::new();
+        let exchange = Exchange::factory((
+            RequestRecord {
+                headers: header_map([("Authorization", "Bearer abc")]),
+                ..RequestRecord::factory(id)
+            },
+            ResponseRecord {
+                status: StatusCode::UNAUTHORIZED,
+                ..ResponseRecord::factory(id)
+            },
+        ));
+        assert_eq!(
+            exchange.hint(None).as_deref(),
+            Some(
+                "The credentials were rejected. Check the `Authorization` \
+                header in the Request tab"
+            )
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Restrict which hosts the HTTP client may contact
+
+use regex::Regex;
+use reqwest::Url;
+use slumber_config::{HttpEngineConfig, host_pattern_regex};
+use slumber_util::ResultTraced;
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A safety net against collections that send requests to unexpected hosts,
+/// e.g. a collection from an untrusted source that exfiltrates environment
+/// variables. Built from the `allowed_hosts` and `denied_hosts` config fields.
+///
+/// A host is allowed if it matches at least one allow pattern (or there are
+/// no allow patterns) and none of the deny patterns. Deny beats allow.
+///
+/// This is cheap to clone.
+#[derive(Clone, Debug, Default)]
+pub struct HostPolicy(Option<Arc<HostPolicyInner>>);
+
+#[derive(Debug)]
+struct HostPolicyInner {
+    allowed: Vec<HostPattern>,
+    denied: Vec<HostPattern>,
+}
+
+impl HostPolicy {
+    pub fn new(config: &HttpEngineConfig) -> Self {
+        if config.allowed_hosts.is_empty() && config.denied_hosts.is_empty() {
+            // Nothing to check - skip the allocation
+            return Self(None);
+        }
+        let parse = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| HostPattern::new(pattern))
+                .collect()
+        };
+        Self(Some(Arc::new(HostPolicyInner {
+            allowed: parse(&config.allowed_hosts),
+            denied: parse(&config.denied_hosts),
+        })))
+    }
+
+    /// Are there any restrictions at all?
+    pub fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Check if a request to the given URL is allowed
+    pub fn check(&self, url: &Url) -> Result<(), HostBlockedError> {
+        self.check_host(url.host_str().unwrap_or_default())
+    }
+
+    /// Check if a connection to the given hostname or IP address is allowed
+    pub fn check_host(&self, host: &str) -> Result<(), HostBlockedError> {
+        let Some(inner) = &self.0 else {
+            return Ok(());
+        };
+        let is_allowed = (inner.allowed.is_empty()
+            || inner
+                .allowed
+                .iter()
+                .any(|pattern| pattern.matches(host).unwrap_or(false)))
+            && !inner
+                .denied
+                .iter()
+                .any(|pattern| pattern.matches(host).unwrap_or(true));
+        if is_allowed {
+            Ok(())
+        } else {
+            Err(HostBlockedError {
+                host: host.to_owned(),
+            })
+        }
+    }
+}
+
+/// A glob or regex pattern for a hostname. See [host_pattern_regex] for the
+/// syntax.
+#[derive(Debug)]
+struct HostPattern(Option<Regex>);
+
+impl HostPattern {
+    fn new(pattern: &str) -> Self {
+        // Patterns from the config file were validated when it was loaded, so
+        // this can only fail for configs built in code. Fail closed: an invalid
+        // pattern allows nothing and denies everything.
+        Self(host_pattern_regex(pattern).traced().ok())
+    }
+
+    /// Does the pattern match the host? `None` if the pattern is invalid
+    fn matches(&self, host: &str) -> Option<bool> {
+        self.0.as_ref().map(|regex| regex.is_match(host))
+    }
+}
+
+/// A request was sent to a host that isn't permitted by [HostPolicy]
+#[derive(Debug, Error)]
+#[error(
+    "Host `{host}` is blocked by the `allowed_hosts`/`denied_hosts` config"
+)]
+pub struct HostBlockedError {
+    pub host: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::empty(&[], &[], "http://anything.com", true)]
+    #[case::allowed(&["localhost"], &[], "http://localhost:3000", true)]
+    #[case::not_allowed(&["localhost"], &[], "http://evil.com", false)]
+    #[case::glob(&["*.example.com"], &[], "https://api.example.com", true)]
+    #[case::glob_nested(&["*.example.com"], &[], "https://a.b.example.com", true)]
+    #[case::glob_no_subdomain(&["*.example.com"], &[], "https://example.com", false)]
+    #[case::glob_suffix(&["*.example.com"], &[], "https://example.com.evil", false)]
+    #[case::case_insensitive(&["API.example.com"], &[], "https://api.example.com", true)]
+    #[case::denied(&[], &["*.evil.com"], "https://x.evil.com", false)]
+    #[case::not_denied(&[], &["*.evil.com"], "https://good.com", true)]
+    #[case::deny_beats_allow(&["*"], &["evil.com"], "https://evil.com", false)]
+    #[case::ip(&["127.0.0.1"], &[], "http://127.0.0.1:8000", true)]
+    #[case::literal_dot(&["a.com"], &[], "http://abcom", false)]
+    #[case::regex(&[r"/api\d+\.example\.com/"], &[], "https://api2.example.com", true)]
+    #[case::regex_anchored(&[r"/api\d+\.example\.com/"], &[], "https://api2.example.com.evil", false)]
+    #[case::regex_denied(&[], &[r"/.*\.evil\.com/"], "https://x.evil.com", false)]
+    #[case::invalid_allowed(&["/api(/", "good.com"], &[], "https://good.com", true)]
+    #[case::invalid_allowed_only(&["/api(/"], &[], "https://api(", false)]
+    #[case::invalid_denied(&[], &["/api(/"], "https://good.com", false)]
+    fn test_check(
+        #[case] allowed_hosts: &[&str],
+        #[case] denied_hosts: &[&str],
+        #[case] url: &str,
+        #[case] expected: bool,
+    ) {
+        let to_vec = |patterns: &[&str]| {
+            patterns.iter().map(|s| (*s).to_owned()).collect()
+        };
+        let policy = HostPolicy::new(&HttpEngineConfig {
+            allowed_hosts: to_vec(allowed_hosts),
+            denied_hosts: to_vec(denied_hosts),
+            ..Default::default()
+        });
+        let url = url.parse().unwrap();
+        assert_eq!(policy.check(&url).is_ok(), expected);
+    }
+}
@@ -5,10 +5,18 @@
 
 use crate::{
     collection::{
-        Authentication, JsonTemplateError, ProfileId, RecipeId,
-        UnknownRecipeError,
+        Authentication, HttpMethod, JsonTemplateError, ProfileId, RecipeId,
+        Timeout, UnknownRecipeError,
     },
-    http::content_type::ContentType,
+    http::{
+        DEFAULT_TRANSPORT, HostBlockedError, OAuth2Error, RetryProgress,
+        TlsConfigError, Transport, TransportError,
+        content_type::ContentType,
+        grpc::{self, GrpcCall},
+        redirect::RedirectChain,
+        websocket,
+    },
+    render::FunctionError,
 };
 use bytes::Bytes;
 use chrono::{DateTime, Duration, Utc};
@@ -16,9 +24,13 @@ use derive_more::FromStr;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use mime::Mime;
+use regex::Regex;
 use reqwest::{
-    Body, Client, Request, StatusCode, Url,
-    header::{self, HeaderMap, InvalidHeaderName, InvalidHeaderValue},
+    Body, Request, StatusCode, Url,
+    header::{
+        self, HeaderMap, HeaderName, HeaderValue, InvalidHeaderName,
+        InvalidHeaderValue,
+    },
 };
 use serde::{Deserialize, Serialize};
 use slumber_template::{RenderError, Template};
@@ -26,8 +38,9 @@ use std::{
     error::Error,
     fmt::{Debug, Display},
     io,
+    path::PathBuf,
     str::{FromStr, Utf8Error},
-    sync::Arc,
+    sync::{Arc, LazyLock},
 };
 use strum::{EnumIter, IntoEnumIterator};
 use thiserror::Error;
@@ -152,72 +165,6 @@ pub struct HttpVersionParseError {
     input: String,
 }
 
-/// [HTTP request method](https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Methods)
-// This is duplicated from [reqwest::Method] so we can enforce
-// the method is valid during deserialization. This is also generally more
-// ergonomic at the cost of some flexibility.
-#[derive(Copy, Clone, Debug, EnumIter, Serialize, Deserialize)]
-#[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
-#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
-// Use FromStr to enable case-insensitivity
-#[serde(into = "&str", try_from = "String")]
-// Show as a string enum
-#[cfg_attr(feature = "schema", schemars(!try_from, rename_all = "UPPERCASE"))]
-pub enum HttpMethod {
-    Connect,
-    Delete,
-    Get,
-    Head,
-    Options,
-    Patch,
-    Post,
-    Put,
-    Trace,
-}
-
-impl HttpMethod {
-    pub fn to_str(self) -> &'static str {
-        match self {
-            Self::Connect => "CONNECT",
-            Self::Delete => "DELETE",
-            Self::Get => "GET",
-            Self::Head => "HEAD",
-            Self::Options => "OPTIONS",
-            Self::Patch => "PATCH",
-            Self::Post => "POST",
-            Self::Put => "PUT",
-            Self::Trace => "TRACE",
-        }
-    }
-}
-
-impl Display for HttpMethod {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(self.to_str())
-    }
-}
-
-impl FromStr for HttpMethod {
-    type Err = HttpMethodParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_ascii_uppercase().as_str() {
-            "CONNECT" => Ok(Self::Connect),
-            "DELETE" => Ok(Self::Delete),
-            "GET" => Ok(Self::Get),
-            "HEAD" => Ok(Self::Head),
-            "OPTIONS" => Ok(Self::Options),
-            "PATCH" => Ok(Self::Patch),
-            "POST" => Ok(Self::Post),
-            "PUT" => Ok(Self::Put),
-            "TRACE" => Ok(Self::Trace),
-            _ => Err(HttpMethodParseError {
-                input: s.to_owned(),
-            }),
-        }
-    }
-}
-
 impl From<&reqwest::Method> for HttpMethod {
     fn from(method: &reqwest::Method) -> Self {
         // reqwest supports custom methods, but we don't provide any
@@ -226,35 +173,11 @@ impl From<&reqwest::Method> for HttpMethod {
     }
 }
 
-/// For serialization
-impl From<HttpMethod> for &'static str {
-    fn from(method: HttpMethod) -> Self {
-        method.to_str()
-    }
-}
-
-/// For deserialization
-impl TryFrom<String> for HttpMethod {
-    type Error = <Self as FromStr>::Err;
-
-    fn try_from(method: String) -> Result<Self, Self::Error> {
-        method.parse()
-    }
-}
-
-#[derive(Debug, Error)]
-#[error(
-    "Invalid HTTP method `{input}`. Must be one of: {}",
-    HttpMethod::iter().map(HttpMethod::to_str).format(", "),
-)]
-pub struct HttpMethodParseError {
-    input: String,
-}
-
 /// The first stage in building a request. This contains the initialization data
 /// needed to build a request. This holds owned data because we need to be able
 /// to move it between tasks as part of the build process, which requires it
 /// to be `'static`.
+#[derive(Clone)]
 pub struct RequestSeed {
     /// Unique ID for this request
     pub id: RequestId,
@@ -292,7 +215,7 @@ impl RequestSeed {
 /// These store *indexes* rather than keys because keys may not be necessarily
 /// unique (e.g. in the case of query params). Technically some could use keys
 /// and some could use indexes, but I chose consistency.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 pub struct BuildOptions {
     /// URL can be overridden but not disabled
@@ -312,6 +235,25 @@ pub struct BuildOptions {
     /// can be overridden on a field-by-field basis. For JSON bodies, the
     /// template will be reparsed as a JSON template *before* rendering.
     pub body: Option<Template>,
+    /// Override the variables of a GraphQL body. Like a JSON body, the
+    /// template will be reparsed as a JSON template *before* rendering. For
+    /// GraphQL bodies, [Self::body] overrides just the query.
+    pub graphql_variables: Option<Template>,
+    /// Send the standard introspection query instead of a GraphQL body's own
+    /// query, to load the server's schema. See [introspection_schema]. The
+    /// build fails if the recipe doesn't have a GraphQL body.
+    ///
+    /// [introspection_schema]: super::graphql::introspection_schema
+    pub graphql_introspection: bool,
+    /// Follow a link from a previous response instead of building the recipe
+    /// as defined. The request is a `GET` to this URL, and keeps only the
+    /// recipe's authentication. Use [Self::authentication] to override that;
+    /// other overrides should be left empty.
+    pub link: Option<Url>,
+    /// Open a WebSocket connection, even if the recipe doesn't define a
+    /// `websocket` field. The recipe's own messages are still sent if it
+    /// does.
+    pub websocket: bool,
 }
 
 /// Modifications made to a single field (query param, header, etc.) in a
@@ -341,16 +283,43 @@ impl From<&'static str> for BuildFieldOverride {
 pub struct RequestTicket {
     /// A record of the request that we can hang onto and persist
     pub(super) record: Arc<RequestRecord>,
-    /// reqwest client that should be used to launch the request
-    pub(super) client: Client,
+    /// Transport that will launch the request
+    pub(super) transport: Arc<dyn Transport>,
     /// Our brave little astronaut, ready to be launched...
     pub(super) request: Request,
+    /// For WebSocket requests, the rendered messages to send once connected.
+    /// `None` for plain HTTP requests
+    pub(super) websocket: Option<websocket::Session>,
+    /// For gRPC requests, the method to call. The request body is encoded
+    /// just before sending. `None` for plain HTTP requests
+    pub(super) grpc: Option<GrpcCall>,
+    /// Attempts made so far under the recipe's retry policy
+    pub(super) retry: RetryProgress,
+    /// Redirects followed by the request, collected into the response
+    pub(super) redirects: RedirectChain,
+    /// Time limits for the request, used to explain a timeout if it happens
+    pub(super) timeout: Timeout,
 }
 
 impl RequestTicket {
     pub fn record(&self) -> &Arc<RequestRecord> {
         &self.record
     }
+
+    /// Get a handle to watch how many times the request has been attempted
+    pub fn retry_progress(&self) -> &RetryProgress {
+        &self.retry
+    }
+
+    /// Will this request open a WebSocket connection?
+    pub fn is_websocket(&self) -> bool {
+        self.websocket.is_some()
+    }
+
+    /// Will this request make a gRPC call?
+    pub fn is_grpc(&self) -> bool {
+        self.grpc.is_some()
+    }
 }
 
 /// A complete request+response pairing. This is generated by
@@ -479,9 +448,57 @@ impl RequestRecord {
         content_type_header(&self.headers)
     }
 
+    /// Get the content type of the request body. gRPC bodies are recorded
+    /// as JSON, before they're encoded for the wire, so they're treated as
+    /// JSON regardless of the header.
+    pub fn content_type(&self) -> Option<ContentType> {
+        let mime = self.mime()?;
+        if mime.essence_str() == grpc::CONTENT_TYPE {
+            Some(ContentType::Json)
+        } else {
+            ContentType::from_mime(&mime)
+        }
+    }
+
     pub fn body(&self) -> Option<&[u8]> {
         self.body.as_deref()
     }
+
+    /// Get the headers that the HTTP client adds to the request implicitly,
+    /// i.e. those that weren't defined in the recipe but were still sent. A
+    /// header is only included if the request doesn't already define it.
+    ///
+    /// This is computed from the request data, so it can't account for
+    /// headers added by a proxy. `Content-Length` is omitted if the body
+    /// wasn't persisted, because we no longer know its length.
+    pub fn generated_headers(&self) -> HeaderMap {
+        let mut generated = HeaderMap::new();
+        let mut add = |name: HeaderName, value: Option<HeaderValue>| {
+            if let Some(value) = value
+                && !self.headers.contains_key(&name)
+            {
+                generated.insert(name, value);
+            }
+        };
+
+        // The port is only included if it's not the default for the scheme
+        let host = self.url.host_str().map(|host| match self.url.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_owned(),
+        });
+        add(
+            header::HOST,
+            host.and_then(|host| HeaderValue::try_from(host).ok()),
+        );
+        add(header::ACCEPT, Some(HeaderValue::from_static("*/*")));
+        add(
+            header::CONTENT_LENGTH,
+            self.body()
+                .filter(|body| !body.is_empty())
+                .map(|body| body.len().into()),
+        );
+        generated
+    }
 }
 
 #[cfg(any(test, feature = "test"))]
@@ -544,9 +561,12 @@ impl slumber_util::Factory<RequestId> for ResponseRecord {
     fn factory(id: RequestId) -> Self {
         Self {
             id,
+            http_version: HttpVersion::Http11,
+            certificate_fingerprint: None,
             status: StatusCode::OK,
             headers: HeaderMap::new(),
             body: ResponseBody::default(),
+            redirects: Vec::new(),
         }
     }
 }
@@ -556,9 +576,12 @@ impl slumber_util::Factory<StatusCode> for ResponseRecord {
     fn factory(status: StatusCode) -> Self {
         Self {
             id: RequestId::new(),
+            http_version: HttpVersion::Http11,
+            certificate_fingerprint: None,
             status,
             headers: HeaderMap::new(),
             body: ResponseBody::default(),
+            redirects: Vec::new(),
         }
     }
 }
@@ -649,9 +672,28 @@ impl slumber_util::Factory<RequestId> for Exchange {
 #[cfg_attr(any(test, feature = "test"), derive(PartialEq))]
 pub struct ResponseRecord {
     pub id: RequestId,
+    /// HTTP version the response was received over. This is the version that
+    /// was negotiated with the server, which may differ from the request's
+    pub http_version: HttpVersion,
+    /// SHA-256 fingerprint of the server's TLS certificate, formatted the same
+    /// as [Pinning::certificate](crate::collection::Pinning::certificate).
+    /// `None` if the response wasn't received over TLS.
+    pub certificate_fingerprint: Option<String>,
     pub status: StatusCode,
     pub headers: HeaderMap,
     pub body: ResponseBody,
+    /// Redirects that were followed to get this response, in order. Empty if
+    /// the first response was final
+    pub redirects: Vec<Redirect>,
+}
+
+/// A redirect that was followed on the way to a response
+#[derive(Clone, Debug, PartialEq)]
+pub struct Redirect {
+    /// Status of the redirect response
+    pub status: StatusCode,
+    /// Where the request was redirected to
+    pub location: Url,
 }
 
 impl ResponseRecord {
@@ -694,11 +736,105 @@ impl ResponseRecord {
                 Some(format!("data.{}", mime.subtype()))
             })
     }
+
+    /// Find all URLs in the response that could be followed: the `Location`
+    /// header, each entry in the `Link` header, and any absolute `http(s)` URLs
+    /// in a text body. Relative URLs in headers are resolved against the
+    /// request URL. Each URL is included only once, from the first place it
+    /// was found.
+    pub fn links(&self, request_url: &Url) -> Vec<ResponseLink> {
+        static BODY_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+            // Stop at whitespace, quotes, and brackets, since URLs are usually
+            // embedded in JSON/HTML/etc.
+            Regex::new(r#"https?://[^\s"'<>`{}\[\]\\]+"#).unwrap()
+        });
+
+        let location = self
+            .headers
+            .get_all(header::LOCATION)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .map(|value| (value.trim(), LinkSource::Location));
+        let link = self
+            .headers
+            .get_all(header::LINK)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(parse_link_header)
+            .map(|(url, rel)| (url, LinkSource::Link { rel }));
+        let header_links = location.chain(link).filter_map(|(url, source)| {
+            let url = request_url.join(url).ok()?;
+            Some(ResponseLink { url, source })
+        });
+
+        let body_links = self
+            .body
+            .text()
+            .into_iter()
+            .flat_map(|text| BODY_URL_REGEX.find_iter(text))
+            .filter_map(|m| {
+                // Trailing punctuation is more likely to be part of the
+                // surrounding text than the URL
+                let url =
+                    m.as_str().trim_end_matches(['.', ',', ';', ':', ')']);
+                Some(ResponseLink {
+                    url: url.parse().ok()?,
+                    source: LinkSource::Body,
+                })
+            });
+
+        header_links
+            .chain(body_links)
+            .unique_by(|link| link.url.clone())
+            .collect()
+    }
+}
+
+/// Parse a `Link` header into `(url, rel)` pairs
+///
+/// https://developer.mozilla.org/en-US/docs/Web/HTTP/Reference/Headers/Link
+fn parse_link_header(
+    value: &str,
+) -> impl Iterator<Item = (&str, Option<String>)> {
+    // URLs are wrapped in <>, so they can contain commas and semicolons
+    value.split('<').skip(1).filter_map(|entry| {
+        let (url, params) = entry.split_once('>')?;
+        // Drop the separator before the next entry
+        let params = params.trim_end().trim_end_matches(',');
+        let rel = params.split(';').find_map(|param| {
+            let (key, value) = param.trim().split_once('=')?;
+            (key.trim() == "rel")
+                .then(|| value.trim().trim_matches('"').to_owned())
+        });
+        Some((url.trim(), rel))
+    })
+}
+
+/// A URL found in a response that could be followed with another request
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResponseLink {
+    pub url: Url,
+    /// Where in the response the link was found
+    pub source: LinkSource,
+}
+
+/// Where in a response a [ResponseLink] was found
+#[derive(Clone, Debug, PartialEq, derive_more::Display)]
+pub enum LinkSource {
+    /// `Location` header
+    #[display("Location")]
+    Location,
+    /// An entry in the `Link` header, with its `rel` parameter
+    #[display("Link{}", rel.as_ref().map(|rel| format!(" ({rel})")).unwrap_or_default())]
+    Link { rel: Option<String> },
+    /// Somewhere in the body text
+    #[display("Body")]
+    Body,
 }
 
 /// Get the value of the `Content-Type` header, parsed as a MIME. `None` if the
 /// header isn't present or isn't a valid MIME type
-fn content_type_header(headers: &HeaderMap) -> Option<Mime> {
+pub(super) fn content_type_header(headers: &HeaderMap) -> Option<Mime> {
     headers
         .get(header::CONTENT_TYPE)
         .and_then(|value| value.to_str().ok()?.parse().ok())
@@ -843,6 +979,19 @@ impl PartialEq for RequestBuildError {
 /// the error for [RequestBuildError], which then attaches additional context.
 #[derive(Debug, Error)]
 pub enum RequestBuildErrorKind {
+    /// Error rendering domain in NTLM auth
+    #[error("Rendering domain")]
+    AuthDomainRender(#[source] RenderError),
+    /// Error fetching an OAuth2 access token
+    #[error("Fetching OAuth2 access token")]
+    AuthOAuth2(#[source] OAuth2Error),
+    /// Error rendering a field of OAuth2 auth
+    #[error("Rendering OAuth2 `{field}`")]
+    AuthOAuth2Render {
+        field: &'static str,
+        #[source]
+        error: RenderError,
+    },
     /// Error rendering username in Basic auth
     #[error("Rendering password")]
     AuthPasswordRender(#[source] RenderError),
@@ -853,9 +1002,31 @@ pub enum RequestBuildErrorKind {
     #[error("Rendering username")]
     AuthUsernameRender(#[source] RenderError),
 
+    /// Error opening or reading a `!file` body
+    #[error("Reading request body from `{}`", path.display())]
+    BodyFile {
+        path: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+    /// User denied access to a `!file` body
+    #[error("Reading request body from `{}`", path.display())]
+    BodyFileAccess {
+        path: PathBuf,
+        #[source]
+        error: Box<FunctionError>,
+    },
     /// Error streaming directly from a file to a request body (via reqwest)
     #[error("Streaming request body")]
     BodyFileStream(#[source] io::Error),
+    /// Form part has a `Content-Type` that isn't a valid MIME type
+    #[error("Invalid `content_type` `{content_type}` for form field `{field}`")]
+    BodyFormContentType {
+        field: String,
+        content_type: String,
+        #[source]
+        error: reqwest::Error,
+    },
     /// Error rendering a body to bytes/stream
     #[error("Rendering form field `{field}`")]
     BodyFormFieldRender {
@@ -874,11 +1045,71 @@ pub enum RequestBuildErrorKind {
     #[error(transparent)]
     Build(#[from] reqwest::Error),
 
+    /// Client certificate file was read, but isn't a usable PEM certificate
+    /// and key
+    #[error("Invalid client certificate {}", path.display())]
+    CertificateInvalid {
+        path: PathBuf,
+        #[source]
+        error: reqwest::Error,
+    },
+    /// Client certificate is a PKCS#12 archive that couldn't be decrypted or
+    /// parsed (e.g. the password is wrong)
+    #[error("Invalid PKCS#12 client certificate {}", path.display())]
+    CertificatePkcs12 {
+        path: PathBuf,
+        #[source]
+        error: p12_keystore::error::Error,
+    },
+    /// PKCS#12 archive was decrypted, but doesn't contain a private key with
+    /// a matching certificate
+    #[error(
+        "PKCS#12 client certificate {} doesn't contain a private key",
+        path.display()
+    )]
+    CertificatePkcs12NoKey { path: PathBuf },
+    /// Error reading a client certificate or key file
+    #[error("Reading client certificate {}", path.display())]
+    CertificateRead {
+        path: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+    /// Client certificates are presented by the built-in transport, so they
+    /// can't be used with any other
+    #[error(
+        "Client certificates require the `{DEFAULT_TRANSPORT}` transport, \
+        not `{name}`"
+    )]
+    CertificateTransport { name: String },
+
+    /// The TLS settings in the config are invalid, e.g. a CA certificate
+    /// couldn't be loaded
+    #[error(transparent)]
+    TlsConfig(Arc<TlsConfigError>),
+
     /// Attempted to generate a cURL command for a request with non-UTF-8
     /// values, which we don't support representing in the generated command
     #[error("Non-text value in curl output")]
     CurlInvalidUtf8(#[source] Utf8Error),
 
+    /// gRPC messages are encoded after the body is rendered, so it can't be
+    /// streamed
+    #[error("gRPC request bodies can't be streamed")]
+    GrpcBodyStream,
+    /// Schema introspection was requested for a recipe without a GraphQL body
+    #[error("Only recipes with a `graphql` body can load a GraphQL schema")]
+    GraphQlIntrospectionBody,
+
+    /// gRPC calls are always `POST`
+    #[error("gRPC requests must use `POST`, not `{method}`")]
+    GrpcMethod { method: HttpMethod },
+    /// gRPC method isn't qualified with a service name
+    #[error(
+        "Invalid gRPC method `{method}`; expected `package.Service/Method`"
+    )]
+    GrpcMethodName { method: String },
+
     /// Header name does not meet the HTTP spec
     #[error("Invalid header name `{header}`")]
     HeaderInvalidName {
@@ -901,6 +1132,18 @@ pub enum RequestBuildErrorKind {
         error: RenderError,
     },
 
+    /// Request URL is on a host that's blocked by the config
+    #[error(transparent)]
+    HostBlocked(#[from] HostBlockedError),
+
+    /// A recipe or profile field that reaches outside the request URL was
+    /// used in an untrusted collection. See `Recipe::check_sandbox`
+    #[error(
+        "`{field}` is disabled because this collection is not trusted. \
+        Run `slumber db collection trust <path>` to trust it"
+    )]
+    Sandboxed { field: &'static str },
+
     /// Error parsing JSON override template
     #[error("Invalid JSON override")]
     Json(
@@ -916,6 +1159,22 @@ pub enum RequestBuildErrorKind {
     )]
     OverrideFormBody,
 
+    /// Values that the recipe depends on are missing, or couldn't be asked
+    /// for
+    #[error("Checking for missing values")]
+    Preflight(#[source] RenderError),
+
+    /// Rendered proxy URL isn't a valid proxy
+    #[error("Invalid proxy URL `{url}`")]
+    ProxyInvalid {
+        url: String,
+        #[source]
+        error: reqwest::Error,
+    },
+    /// Error rendering the proxy URL
+    #[error("Rendering proxy URL")]
+    ProxyRender(#[source] RenderError),
+
     /// Error rendering query parameter
     #[error("Rendering query parameter `{parameter}`")]
     QueryRender {
@@ -928,6 +1187,31 @@ pub enum RequestBuildErrorKind {
     #[error(transparent)]
     RecipeUnknown(#[from] UnknownRecipeError),
 
+    /// Error rendering the Unix socket path
+    #[error("Rendering socket path")]
+    SocketRender(#[source] RenderError),
+    /// Unix sockets are handled by the built-in transport
+    #[error(
+        "Unix sockets require the `{DEFAULT_TRANSPORT}` transport, not `{name}`"
+    )]
+    SocketTransport { name: String },
+    /// Unix sockets don't exist on this platform
+    #[error(
+        "Unix sockets are not supported on this platform; \
+        use a named pipe (`\\\\.\\pipe\\...`) instead"
+    )]
+    SocketUnsupported,
+    /// Named pipes only exist on Windows
+    #[error("Named pipes are only supported on Windows")]
+    NamedPipeUnsupported,
+
+    /// Recipe or config selected a transport that isn't registered
+    #[error(
+        "Unknown transport `{name}`; options are: {}",
+        options.join(", ")
+    )]
+    TransportUnknown { name: String, options: Vec<String> },
+
     /// URL rendered correctly but the result isn't a valid URL
     #[error("Invalid URL")]
     UrlInvalid {
@@ -938,6 +1222,19 @@ pub enum RequestBuildErrorKind {
     /// Error rendering URL
     #[error("Rendering URL")]
     UrlRender(#[source] RenderError),
+
+    /// Error rendering one of a WebSocket recipe's messages
+    #[error("Rendering WebSocket message {index}")]
+    WebSocketMessageRender {
+        /// Index of the message in the recipe
+        index: usize,
+        #[source]
+        error: RenderError,
+    },
+    /// WebSocket connections are opened with a `GET` handshake, so no other
+    /// method is allowed
+    #[error("WebSocket requests must use `GET`, not `{method}`")]
+    WebSocketMethod { method: HttpMethod },
 }
 
 /// An error that can occur during a request. This does *not* including building
@@ -951,7 +1248,7 @@ pub enum RequestBuildErrorKind {
 pub struct RequestError {
     /// Underlying error
     #[source]
-    pub error: reqwest::Error,
+    pub error: TransportError,
 
     /// The request that caused all this ruckus
     pub request: Arc<RequestRecord>,
@@ -1062,4 +1359,114 @@ mod tests {
     ) {
         assert_eq!(response.file_name().as_deref(), expected);
     }
+
+    /// Find links in headers and body. Relative header URLs are resolved, and
+    /// duplicates are dropped
+    #[rstest]
+    #[case::none(ResponseRecord::factory(()), &[])]
+    #[case::location(
+        ResponseRecord {
+            headers: header_map(indexmap! {"location" => "/users/2"}),
+            ..ResponseRecord::factory(())
+        },
+        &[("http://localhost/users/2", LinkSource::Location)],
+    )]
+    #[case::link(
+        ResponseRecord {
+            headers: header_map(indexmap! {
+                "link" => "<https://api.com/users?page=2>; rel=\"next\", \
+                    </users?page=1>; rel=\"first\", <http://localhost/x>",
+            }),
+            ..ResponseRecord::factory(())
+        },
+        &[
+            (
+                "https://api.com/users?page=2",
+                LinkSource::Link { rel: Some("next".into()) },
+            ),
+            (
+                "http://localhost/users?page=1",
+                LinkSource::Link { rel: Some("first".into()) },
+            ),
+            ("http://localhost/x", LinkSource::Link { rel: None }),
+        ],
+    )]
+    #[case::body(
+        ResponseRecord {
+            headers: header_map(indexmap! {"location" => "/users/2"}),
+            body: r#"{"self": "http://localhost/users/2", "docs": "See https://docs.com/users."}"#
+                .into(),
+            ..ResponseRecord::factory(())
+        },
+        &[
+            ("http://localhost/users/2", LinkSource::Location),
+            ("https://docs.com/users", LinkSource::Body),
+        ],
+    )]
+    #[case::binary_body(
+        ResponseRecord {
+            body: b"\xff http://localhost/users".as_slice().into(),
+            ..ResponseRecord::factory(())
+        },
+        &[],
+    )]
+    fn test_links(
+        #[case] response: ResponseRecord,
+        #[case] expected: &[(&str, LinkSource)],
+    ) {
+        let request_url = "http://localhost/users/1".parse().unwrap();
+        let expected = expected
+            .iter()
+            .map(|(url, source)| ResponseLink {
+                url: url.parse().unwrap(),
+                source: source.clone(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(response.links(&request_url), expected);
+    }
+
+    /// Headers that aren't defined on the request should be generated
+    #[rstest]
+    #[case::default_port(
+        "http://localhost/url",
+        &[],
+        None,
+        &[
+            ("host", "localhost"),
+            ("accept", "*/*"),
+        ],
+    )]
+    #[case::custom_port_and_body(
+        "https://localhost:3000/url",
+        &[],
+        Some("hello"),
+        &[
+            ("host", "localhost:3000"),
+            ("accept", "*/*"),
+            ("content-length", "5"),
+        ],
+    )]
+    #[case::explicit(
+        "http://localhost/url",
+        &[("Accept", "application/json")],
+        Some(""),
+        &[("host", "localhost")],
+    )]
+    fn test_generated_headers(
+        #[case] url: &str,
+        #[case] headers: &[(&str, &str)],
+        #[case] body: Option<&'static str>,
+        #[case] expected: &[(&str, &str)],
+    ) {
+        let request = RequestRecord {
+            url: url.parse().unwrap(),
+            headers: header_map(headers.iter().copied()),
+            body: body.map(Bytes::from),
+            ..RequestRecord::factory(())
+        };
+        assert_eq!(
+            request.generated_headers(),
+            header_map(expected.iter().copied())
+        );
+    }
 }
@@ -0,0 +1,622 @@
+//! OAuth 2.0 authentication. Access tokens are fetched from the provider when
+//! a request needs one, then cached in memory and in the database so they can
+//! be reused until they expire. Expired tokens are refreshed with the refresh
+//! token if the provider gave us one, otherwise the grant flow runs again.
+
+use crate::{
+    collection::{OAuth2, OAuth2Grant},
+    database::CollectionDatabase,
+    http::{
+        HostBlockedError,
+        host_policy::HostPolicy,
+        token::{TOKEN_REFRESH_MARGIN, json_seconds, jwt_expiry},
+    },
+};
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::{Client, StatusCode, Url, header};
+use serde::Deserialize;
+use serde_json::Value;
+use slumber_util::ResultTraced;
+use std::{
+    collections::HashMap,
+    io,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+/// Where the provider sends the user after login, if the recipe doesn't
+/// specify a redirect URI
+const DEFAULT_REDIRECT_URI: &str = "http://127.0.0.1:8765/callback";
+
+/// How long to wait for the user to log in via the browser
+const LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// An access token issued by an OAuth 2.0 provider
+#[derive(Clone, Debug, PartialEq)]
+pub struct OAuth2Token {
+    pub access_token: String,
+    /// Token that can be exchanged for a new access token once this one
+    /// expires. Not all providers issue these.
+    pub refresh_token: Option<String>,
+    /// When the access token expires. `None` if the provider didn't say, in
+    /// which case the token is used until the server rejects it.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl OAuth2Token {
+    /// Can this token still be used? Tokens are considered expired slightly
+    /// before their actual expiry, so they don't expire in flight.
+    fn is_fresh(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| expires_at - TOKEN_REFRESH_MARGIN > now)
+    }
+}
+
+/// Storage for OAuth2 tokens. Tokens are kept in memory, and persisted to the
+/// database if one is given so they survive restarts. The same instance should
+/// be shared between all template contexts for a session. Clones are cheap and
+/// share the same storage.
+#[derive(Clone, Debug)]
+pub struct TokenStore {
+    database: Option<CollectionDatabase>,
+    tokens: Arc<Mutex<HashMap<String, OAuth2Token>>>,
+    /// Held while fetching a token, so concurrent requests that need the same
+    /// token don't each trigger their own login
+    fetch_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Open a URL in the user's browser. Replaceable for tests
+    open_browser: fn(&str) -> io::Result<()>,
+}
+
+impl TokenStore {
+    /// Create a store that persists tokens to the given database
+    pub fn new(database: CollectionDatabase) -> Self {
+        Self {
+            database: Some(database),
+            ..Self::default()
+        }
+    }
+
+    /// Replace the function used to open the login page. The function is
+    /// called with the authorization URL
+    #[cfg(test)]
+    pub(crate) fn with_browser(
+        mut self,
+        open_browser: fn(&str) -> io::Result<()>,
+    ) -> Self {
+        self.open_browser = open_browser;
+        self
+    }
+
+    /// Get a token by key, checking memory then the database
+    pub fn get(&self, key: &str) -> Option<OAuth2Token> {
+        if let Some(token) = self.lock().get(key) {
+            return Some(token.clone());
+        }
+        let token = self
+            .database
+            .as_ref()?
+            .get_oauth2_token(key)
+            .traced()
+            .ok()??;
+        self.lock().insert(key.to_owned(), token.clone());
+        Some(token)
+    }
+
+    /// Store a token under a key, in memory and in the database
+    pub fn insert(&self, key: String, token: OAuth2Token) {
+        if let Some(database) = &self.database {
+            // Failure to persist isn't fatal; the token still works for this
+            // session
+            let _ = database.set_oauth2_token(&key, &token).traced();
+        }
+        self.lock().insert(key, token);
+    }
+
+    /// Get a valid access token for the given OAuth2 settings. A cached token
+    /// is used if it hasn't expired, unless `force` is set. Otherwise the
+    /// cached token is refreshed if possible, and if not we run the grant flow
+    /// to get a new one.
+    pub(super) async fn access_token(
+        &self,
+        client: &Client,
+        host_policy: &HostPolicy,
+        oauth2: &OAuth2<String>,
+        force: bool,
+    ) -> Result<String, OAuth2Error> {
+        let _guard = self.fetch_lock.lock().await;
+        let key = token_key(oauth2);
+        let cached = self.get(&key);
+
+        if let Some(token) = &cached
+            && !force
+            && token.is_fresh(Utc::now())
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        let request = TokenRequest {
+            client,
+            host_policy,
+            oauth2,
+        };
+        if let Some(refresh_token) =
+            cached.and_then(|token| token.refresh_token)
+        {
+            info!(key, "Refreshing OAuth2 token");
+            match request
+                .send(&[
+                    ("grant_type", "refresh_token"),
+                    ("refresh_token", &refresh_token),
+                ])
+                .await
+            {
+                Ok(mut token) => {
+                    // Providers may or may not rotate the refresh token. If
+                    // they don't, the old one is still good
+                    token.refresh_token.get_or_insert(refresh_token);
+                    let access_token = token.access_token.clone();
+                    self.insert(key, token);
+                    return Ok(access_token);
+                }
+                Err(error) => {
+                    // The refresh token may have expired or been revoked. Fall
+                    // back to the full flow
+                    warn!(
+                        error = &error as &dyn std::error::Error,
+                        "Error refreshing OAuth2 token"
+                    );
+                }
+            }
+        }
+
+        info!(key, "Fetching OAuth2 token");
+        let token = match &oauth2.grant {
+            OAuth2Grant::ClientCredentials => {
+                let mut params = vec![("grant_type", "client_credentials")];
+                if let Some(scope) = &oauth2.scope {
+                    params.push(("scope", scope));
+                }
+                request.send(&params).await?
+            }
+            OAuth2Grant::AuthorizationCode {
+                authorization_url,
+                redirect_uri,
+            } => {
+                self.authorization_code(
+                    &request,
+                    authorization_url,
+                    redirect_uri.as_deref().unwrap_or(DEFAULT_REDIRECT_URI),
+                )
+                .await?
+            }
+        };
+        let access_token = token.access_token.clone();
+        self.insert(key, token);
+        Ok(access_token)
+    }
+
+    /// Run the authorization code flow with PKCE: send the user to the login
+    /// page in their browser, wait for the provider to redirect back to us
+    /// with a code, then exchange the code for a token
+    async fn authorization_code(
+        &self,
+        request: &TokenRequest<'_>,
+        authorization_url: &str,
+        redirect_uri: &str,
+    ) -> Result<OAuth2Token, OAuth2Error> {
+        let redirect = parse_url(redirect_uri)?;
+        if redirect.scheme() != "http" {
+            return Err(OAuth2Error::RedirectUri {
+                uri: redirect_uri.to_owned(),
+            });
+        }
+        let addresses = redirect.socket_addrs(|| None).map_err(|error| {
+            OAuth2Error::Listen {
+                address: redirect_uri.to_owned(),
+                error,
+            }
+        })?;
+        // Listen *before* opening the browser, so we're ready for the redirect
+        let listener =
+            TcpListener::bind(&*addresses).await.map_err(|error| {
+                OAuth2Error::Listen {
+                    address: redirect_uri.to_owned(),
+                    error,
+                }
+            })?;
+
+        // The verifier proves to the token endpoint that we're the same client
+        // that initiated the login. The state ties the redirect to this login
+        let verifier = BASE64_URL_SAFE_NO_PAD.encode(
+            [*Uuid::new_v4().as_bytes(), *Uuid::new_v4().as_bytes()].concat(),
+        );
+        let challenge = BASE64_URL_SAFE_NO_PAD.encode(
+            aws_lc_rs::digest::digest(
+                &aws_lc_rs::digest::SHA256,
+                verifier.as_bytes(),
+            )
+            .as_ref(),
+        );
+        let state = Uuid::new_v4().to_string();
+
+        let mut url = parse_url(authorization_url)?;
+        {
+            let mut query = url.query_pairs_mut();
+            query
+                .append_pair("response_type", "code")
+                .append_pair("client_id", &request.oauth2.client_id)
+                .append_pair("redirect_uri", redirect_uri)
+                .append_pair("state", &state)
+                .append_pair("code_challenge", &challenge)
+                .append_pair("code_challenge_method", "S256");
+            if let Some(scope) = &request.oauth2.scope {
+                query.append_pair("scope", scope);
+            }
+        }
+
+        info!(%url, "Opening browser for OAuth2 login");
+        (self.open_browser)(url.as_str()).map_err(|error| {
+            OAuth2Error::Browser {
+                url: url.to_string(),
+                error,
+            }
+        })?;
+        let code = tokio::time::timeout(
+            LOGIN_TIMEOUT,
+            receive_code(&listener, redirect.path(), &state),
+        )
+        .await
+        .map_err(|_| OAuth2Error::LoginTimeout)??;
+
+        request
+            .send(&[
+                ("grant_type", "authorization_code"),
+                ("code", &code),
+                ("redirect_uri", redirect_uri),
+                ("code_verifier", &verifier),
+            ])
+            .await
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, OAuth2Token>> {
+        self.tokens.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for TokenStore {
+    fn default() -> Self {
+        Self {
+            database: None,
+            tokens: Default::default(),
+            fetch_lock: Default::default(),
+            open_browser,
+        }
+    }
+}
+
+/// A request to the provider's token endpoint
+struct TokenRequest<'a> {
+    client: &'a Client,
+    host_policy: &'a HostPolicy,
+    oauth2: &'a OAuth2<String>,
+}
+
+impl TokenRequest<'_> {
+    /// Send a token request with the given grant parameters. Client
+    /// credentials are added automatically
+    async fn send(
+        &self,
+        params: &[(&str, &str)],
+    ) -> Result<OAuth2Token, OAuth2Error> {
+        let url = parse_url(&self.oauth2.token_url)?;
+        self.host_policy.check(&url)?;
+
+        let mut form = params.to_vec();
+        let mut builder = self
+            .client
+            .post(url)
+            .header(header::ACCEPT, "application/json");
+        // Confidential clients authenticate with Basic auth. Public clients
+        // just identify themselves in the body
+        if let Some(client_secret) = &self.oauth2.client_secret {
+            builder =
+                builder.basic_auth(&self.oauth2.client_id, Some(client_secret));
+        } else {
+            form.push(("client_id", &self.oauth2.client_id));
+        }
+
+        let response = builder
+            .form(&form)
+            .send()
+            .await
+            .map_err(OAuth2Error::Send)?;
+        let status = response.status();
+        let body = response.bytes().await.map_err(OAuth2Error::Send)?;
+        if !status.is_success() {
+            return Err(match serde_json::from_slice::<ErrorResponse>(&body) {
+                Ok(ErrorResponse {
+                    error,
+                    error_description,
+                }) => OAuth2Error::Provider {
+                    error,
+                    description: error_description,
+                },
+                Err(_) => OAuth2Error::Status {
+                    status,
+                    body: String::from_utf8_lossy(&body).into_owned(),
+                },
+            });
+        }
+
+        let TokenResponse {
+            access_token,
+            refresh_token,
+            expires_in,
+        } = serde_json::from_slice(&body).map_err(OAuth2Error::Response)?;
+        let expires_at = expires_in
+            .as_ref()
+            .and_then(json_seconds)
+            .and_then(TimeDelta::try_seconds)
+            .map(|expires_in| Utc::now() + expires_in)
+            .or_else(|| jwt_expiry(&access_token));
+        Ok(OAuth2Token {
+            access_token,
+            refresh_token,
+            expires_at,
+        })
+    }
+}
+
+/// Successful response from the token endpoint
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    /// Seconds until expiry. Some providers send this as a string
+    expires_in: Option<Value>,
+}
+
+/// Error response from the token endpoint
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+/// Get the key that a token is stored under. Tokens are shared by all recipes
+/// that use the same provider, client, and scope
+fn token_key(oauth2: &OAuth2<String>) -> String {
+    let grant = match oauth2.grant {
+        OAuth2Grant::ClientCredentials => "client_credentials",
+        OAuth2Grant::AuthorizationCode { .. } => "authorization_code",
+    };
+    format!(
+        "{grant} {} {} {}",
+        oauth2.token_url,
+        oauth2.client_id,
+        oauth2.scope.as_deref().unwrap_or_default()
+    )
+}
+
+fn parse_url(url: &str) -> Result<Url, OAuth2Error> {
+    Url::parse(url).map_err(|error| OAuth2Error::Url {
+        url: url.to_owned(),
+        error,
+    })
+}
+
+/// Accept connections on the redirect listener until the provider redirects
+/// the browser back to us, then get the authorization code from the query.
+/// Requests to other paths (e.g. `/favicon.ico`) are ignored.
+async fn receive_code(
+    listener: &TcpListener,
+    path: &str,
+    state: &str,
+) -> Result<String, OAuth2Error> {
+    let base = Url::parse("http://localhost").expect("Base URL is valid");
+    loop {
+        let (stream, _) =
+            listener.accept().await.map_err(OAuth2Error::Callback)?;
+        let mut stream = BufReader::new(stream);
+
+        // We only care about the request line: GET /callback?code=... HTTP/1.1
+        // Read the headers anyway so the browser doesn't see a reset
+        let mut request_line = String::new();
+        stream
+            .read_line(&mut request_line)
+            .await
+            .map_err(OAuth2Error::Callback)?;
+        let mut line = String::new();
+        while stream
+            .read_line(&mut line)
+            .await
+            .map_err(OAuth2Error::Callback)?
+            > 0
+            && !line.trim().is_empty()
+        {
+            line.clear();
+        }
+
+        let url = request_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|target| base.join(target).ok());
+        let Some(url) = url.filter(|url| url.path() == path) else {
+            respond(stream.into_inner(), "404 Not Found", "Not found").await;
+            continue;
+        };
+
+        let params: HashMap<_, _> = url.query_pairs().collect();
+        let result = if let Some(error) = params.get("error") {
+            Err(OAuth2Error::Provider {
+                error: error.clone().into_owned(),
+                description: params
+                    .get("error_description")
+                    .map(|description| description.clone().into_owned()),
+            })
+        } else if params.get("state").map(AsRef::as_ref) != Some(state) {
+            Err(OAuth2Error::State)
+        } else if let Some(code) = params.get("code") {
+            Ok(code.clone().into_owned())
+        } else {
+            Err(OAuth2Error::MissingCode)
+        };
+
+        let message = match &result {
+            Ok(_) => "Login complete. You can close this window and return \
+                to Slumber."
+                .to_owned(),
+            Err(error) => format!("Login failed: {error}"),
+        };
+        respond(stream.into_inner(), "200 OK", &message).await;
+        return result;
+    }
+}
+
+/// Send a plain text response to the browser. Errors are ignored because
+/// there's nothing we can do about them
+async fn respond(mut stream: TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {status}\r\n\
+        Content-Type: text/plain; charset=utf-8\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\r\n\
+        {body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+/// Open a URL in the default browser
+fn open_browser(url: &str) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    let mut child = command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the process in the background so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Error fetching an OAuth2 access token
+#[derive(Debug, Error)]
+pub enum OAuth2Error {
+    /// Couldn't open the login page
+    #[error("Opening browser to `{url}`; open it manually to log in")]
+    Browser {
+        url: String,
+        #[source]
+        error: io::Error,
+    },
+
+    /// Error receiving the redirect from the provider
+    #[error("Receiving login redirect")]
+    Callback(#[source] io::Error),
+
+    /// Token URL is on a host that's blocked by the config
+    #[error(transparent)]
+    HostBlocked(#[from] HostBlockedError),
+
+    /// Couldn't listen on the redirect URI
+    #[error("Listening on redirect URI `{address}`")]
+    Listen {
+        address: String,
+        #[source]
+        error: io::Error,
+    },
+
+    /// User didn't finish logging in within [LOGIN_TIMEOUT]
+    #[error(
+        "Login timed out after {} seconds", LOGIN_TIMEOUT.as_secs()
+    )]
+    LoginTimeout,
+
+    /// Provider redirected back to us without a code
+    #[error("Login redirect did not include an authorization code")]
+    MissingCode,
+
+    /// Provider returned a standard OAuth2 error
+    #[error(
+        "Provider returned error `{error}`{}",
+        description.as_ref().map(|d| format!(": {d}")).unwrap_or_default()
+    )]
+    Provider {
+        error: String,
+        description: Option<String>,
+    },
+
+    /// Redirect URI isn't something we can listen on
+    #[error("Redirect URI `{uri}` must be an `http` URL on this machine")]
+    RedirectUri { uri: String },
+
+    /// Token response body isn't valid
+    #[error("Invalid token response")]
+    Response(#[source] serde_json::Error),
+
+    /// Error sending the token request
+    #[error("Requesting token")]
+    Send(#[source] reqwest::Error),
+
+    /// Login redirect had the wrong `state`, meaning it's not from the login
+    /// we initiated
+    #[error("Login redirect has incorrect `state`")]
+    State,
+
+    /// Token endpoint returned a non-standard error
+    #[error("Token request failed with status {status}: {body}")]
+    Status { status: StatusCode, body: String },
+
+    /// Token URL or authorization URL is invalid
+    #[error("Invalid URL `{url}`")]
+    Url {
+        url: String,
+        #[source]
+        error: url::ParseError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// A token is stale once it's within the refresh margin of expiry
+    #[rstest]
+    #[case::no_expiry(None, true)]
+    #[case::fresh(Some(TimeDelta::minutes(5)), true)]
+    #[case::margin(Some(TimeDelta::seconds(10)), false)]
+    #[case::expired(Some(TimeDelta::seconds(-10)), false)]
+    fn test_is_fresh(
+        #[case] expires_in: Option<TimeDelta>,
+        #[case] expected: bool,
+    ) {
+        let now = Utc::now();
+        let token = OAuth2Token {
+            access_token: "token".into(),
+            refresh_token: None,
+            expires_at: expires_in.map(|expires_in| now + expires_in),
+        };
+        assert_eq!(token.is_fresh(now), expected);
+    }
+}
@@ -0,0 +1,208 @@
+//! Response pinning checks. A profile can pin the server's TLS certificate and
+//! headers that identify the server (e.g. `Server`, or a CDN's point of
+//! presence). A response that doesn't match probably came from the wrong
+//! environment, or passed through a proxy that intercepts TLS. Violations don't
+//! fail the request; they're shown as warnings alongside the response.
+
+use crate::{collection::Profile, http::Exchange};
+use aws_lc_rs::digest::{SHA256, digest};
+use itertools::Itertools;
+use reqwest::{Response, tls::TlsInfo};
+use thiserror::Error;
+
+impl Exchange {
+    /// Check the response against the `pinning` settings of the profile it
+    /// was sent under. Pass the profile, if it's still available. Return each
+    /// expectation that the response violated.
+    pub fn pin_violations(
+        &self,
+        profile: Option<&Profile>,
+    ) -> Vec<PinViolation> {
+        let Some(pinning) =
+            profile.and_then(|profile| profile.pinning.as_ref())
+        else {
+            return Vec::new();
+        };
+        let response = &self.response;
+        let mut violations = Vec::new();
+
+        if let Some(expected) = &pinning.certificate {
+            match &response.certificate_fingerprint {
+                Some(actual) if actual == expected => {}
+                Some(actual) => {
+                    violations.push(PinViolation::CertificateMismatch {
+                        expected: expected.clone(),
+                        actual: actual.clone(),
+                    });
+                }
+                None => violations.push(PinViolation::CertificateMissing {
+                    expected: expected.clone(),
+                }),
+            }
+        }
+
+        for (name, expected) in &pinning.headers {
+            let actual = response
+                .headers
+                .get(name.as_str())
+                .map(|value| String::from_utf8_lossy(value.as_bytes()));
+            match actual {
+                Some(actual)
+                    if actual
+                        .to_lowercase()
+                        .contains(&expected.to_lowercase()) => {}
+                Some(actual) => {
+                    violations.push(PinViolation::HeaderMismatch {
+                        name: name.clone(),
+                        expected: expected.clone(),
+                        actual: actual.into_owned(),
+                    });
+                }
+                None => violations.push(PinViolation::HeaderMissing {
+                    name: name.clone(),
+                    expected: expected.clone(),
+                }),
+            }
+        }
+
+        violations
+    }
+}
+
+/// A response didn't match one of its profile's pinning expectations
+#[derive(Debug, Error, PartialEq)]
+pub enum PinViolation {
+    #[error(
+        "Server certificate `{actual}` doesn't match pinned fingerprint \
+        `{expected}`. The request may have gone through a proxy that \
+        intercepts TLS"
+    )]
+    CertificateMismatch { expected: String, actual: String },
+    #[error(
+        "Response wasn't received over TLS, so its certificate couldn't be \
+        checked against pinned fingerprint `{expected}`"
+    )]
+    CertificateMissing { expected: String },
+    #[error(
+        "Header `{name}` is `{actual}`, which doesn't contain pinned value \
+        `{expected}`. The request may have reached the wrong environment"
+    )]
+    HeaderMismatch {
+        name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error(
+        "Header `{name}` is missing; expected it to contain pinned value \
+        `{expected}`. The request may have reached the wrong environment"
+    )]
+    HeaderMissing { name: String, expected: String },
+}
+
+/// Get the SHA-256 fingerprint of the server's TLS certificate, as
+/// colon-separated uppercase hex bytes. `None` if the connection didn't use
+/// TLS.
+pub(super) fn certificate_fingerprint(response: &Response) -> Option<String> {
+    let certificate =
+        response.extensions().get::<TlsInfo>()?.peer_certificate()?;
+    Some(format_fingerprint(digest(&SHA256, certificate).as_ref()))
+}
+
+fn format_fingerprint(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02X}")).join(":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collection::Pinning, http::ResponseRecord, test_util::header_map,
+    };
+    use indexmap::IndexMap;
+    use rstest::rstest;
+    use slumber_util::Factory;
+    use std::sync::Arc;
+
+    const FINGERPRINT: &str = "AB:CD";
+
+    #[rstest]
+    #[case::no_pinning(None, Some(FINGERPRINT), &[], &[])]
+    #[case::certificate_match(Some(FINGERPRINT), Some(FINGERPRINT), &[], &[])]
+    #[case::certificate_mismatch(
+        Some(FINGERPRINT),
+        Some("12:34"),
+        &[],
+        &[PinViolation::CertificateMismatch {
+            expected: FINGERPRINT.into(),
+            actual: "12:34".into(),
+        }],
+    )]
+    #[case::certificate_no_tls(
+        Some(FINGERPRINT),
+        None,
+        &[],
+        &[PinViolation::CertificateMissing {
+            expected: FINGERPRINT.into(),
+        }],
+    )]
+    // Names are case-insensitive, and values only have to contain the pin
+    #[case::header_match(
+        None,
+        None,
+        &[("Server", "NGINX"), ("x-served-by", "cache-ams")],
+        &[],
+    )]
+    #[case::header_mismatch(
+        None,
+        None,
+        &[("server", "envoy"), ("x-cdn-pop", "ams")],
+        &[
+            PinViolation::HeaderMismatch {
+                name: "server".into(),
+                expected: "envoy".into(),
+                actual: "nginx/1.27".into(),
+            },
+            PinViolation::HeaderMissing {
+                name: "x-cdn-pop".into(),
+                expected: "ams".into(),
+            },
+        ],
+    )]
+    fn test_pin_violations(
+        #[case] certificate: Option<&str>,
+        #[case] actual_certificate: Option<&str>,
+        #[case] headers: &[(&str, &str)],
+        #[case] expected: &[PinViolation],
+    ) {
+        let pinning =
+            (certificate.is_some() || !headers.is_empty()).then(|| Pinning {
+                certificate: certificate.map(String::from),
+                headers: headers
+                    .iter()
+                    .map(|(name, value)| ((*name).into(), (*value).into()))
+                    .collect::<IndexMap<_, _>>(),
+            });
+        let profile = Profile {
+            pinning,
+            ..Profile::factory(())
+        };
+        let response = ResponseRecord {
+            certificate_fingerprint: actual_certificate.map(String::from),
+            headers: header_map([
+                ("server", "nginx/1.27"),
+                ("x-served-by", "cache-AMS21234-AMS"),
+            ]),
+            ..ResponseRecord::factory(())
+        };
+        let exchange = Exchange {
+            response: Arc::new(response),
+            ..Exchange::factory(())
+        };
+        assert_eq!(exchange.pin_violations(Some(&profile)), expected);
+    }
+
+    #[rstest]
+    fn test_format_fingerprint() {
+        assert_eq!(format_fingerprint(&[0x0a, 0xbc, 0xff]), "0A:BC:FF");
+    }
+}
@@ -0,0 +1,216 @@
+//! Redirect handling for the built-in transport. Reqwest binds its redirect
+//! policy to the client, but ours can change per recipe, so the clients never
+//! follow redirects themselves. Instead, a [RedirectPolicy] wraps the
+//! transport and follows them here, recording each hop along the way.
+
+use crate::{
+    collection::RedirectPolicy,
+    http::{Redirect, Transport, TransportError, host_policy::HostPolicy},
+};
+use futures::{FutureExt, future::BoxFuture};
+use reqwest::{
+    Method, Request, Response, StatusCode, Url,
+    header::{self, HeaderMap},
+};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::info;
+
+/// Headers that carry credentials, which are removed when redirected to
+/// another origin
+const AUTH_HEADERS: &[header::HeaderName] = &[
+    header::AUTHORIZATION,
+    header::COOKIE,
+    header::PROXY_AUTHORIZATION,
+    header::WWW_AUTHENTICATE,
+];
+/// Headers that describe the body, which are removed along with the body
+const CONTENT_HEADERS: &[header::HeaderName] = &[
+    header::CONTENT_ENCODING,
+    header::CONTENT_LENGTH,
+    header::CONTENT_TYPE,
+    header::TRANSFER_ENCODING,
+];
+
+/// Redirects followed by a request, in order. Clones share the same list, so
+/// the chain can be collected after the response is received.
+#[derive(Clone, Debug, Default)]
+pub(super) struct RedirectChain(Arc<Mutex<Vec<Redirect>>>);
+
+impl RedirectChain {
+    /// Take every redirect recorded so far, leaving the chain empty
+    pub(super) fn take(&self) -> Vec<Redirect> {
+        std::mem::take(&mut *self.0.lock().expect("Redirect chain poisoned"))
+    }
+
+    fn push(&self, redirect: Redirect) {
+        self.0
+            .lock()
+            .expect("Redirect chain poisoned")
+            .push(redirect);
+    }
+}
+
+/// Wrap a transport to follow redirects according to the policy. Each
+/// redirect that's followed is recorded in `chain`.
+pub(super) fn wrap(
+    transport: Arc<dyn Transport>,
+    policy: &RedirectPolicy,
+    host_policy: &HostPolicy,
+    chain: &RedirectChain,
+) -> Arc<dyn Transport> {
+    Arc::new(RedirectTransport {
+        inner: transport,
+        policy: policy.clone(),
+        host_policy: host_policy.clone(),
+        chain: chain.clone(),
+    })
+}
+
+/// A [Transport] that sends a new request to the real transport for each
+/// redirect response, until it gets a final response
+#[derive(Debug)]
+struct RedirectTransport {
+    inner: Arc<dyn Transport>,
+    policy: RedirectPolicy,
+    /// Redirects could take us to a blocked host, so check each one
+    host_policy: HostPolicy,
+    chain: RedirectChain,
+}
+
+impl Transport for RedirectTransport {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let inner = Arc::clone(&self.inner);
+        let policy = self.policy.clone();
+        let host_policy = self.host_policy.clone();
+        let chain = self.chain.clone();
+        async move {
+            // If the request is retried, only the final chain is kept
+            chain.take();
+            let mut request = request;
+            let mut redirects = 0;
+            loop {
+                // Keep a copy of everything needed to send the request again.
+                // Streamed bodies can't be copied, so a redirect that needs
+                // the body can't be followed.
+                let url = request.url().clone();
+                let method = request.method().clone();
+                let headers = request.headers().clone();
+                let body = request
+                    .try_clone()
+                    .and_then(|mut copy| copy.body_mut().take());
+                let has_body = request.body().is_some();
+                let version = request.version();
+                let timeout = request.timeout().copied();
+
+                let response = inner.send(request).await?;
+                let status = response.status();
+                let Some(location) = location(&url, response.headers()) else {
+                    return Ok(response);
+                };
+
+                let (method, body) = match status {
+                    StatusCode::MOVED_PERMANENTLY
+                    | StatusCode::FOUND
+                    | StatusCode::SEE_OTHER => {
+                        if method == Method::GET || method == Method::HEAD {
+                            (method, None)
+                        } else {
+                            (Method::GET, None)
+                        }
+                    }
+                    StatusCode::TEMPORARY_REDIRECT
+                    | StatusCode::PERMANENT_REDIRECT => {
+                        if !has_body {
+                            (method, None)
+                        } else if policy.resend_body
+                            && let Some(body) = body
+                        {
+                            (method, Some(body))
+                        } else {
+                            return Ok(response);
+                        }
+                    }
+                    // Other 3xx statuses aren't redirects we can follow
+                    _ => return Ok(response),
+                };
+
+                if redirects >= policy.max_redirects {
+                    return Err(TransportError::new(RedirectError {
+                        max_redirects: policy.max_redirects,
+                    }));
+                }
+                host_policy.check(&location).map_err(TransportError::new)?;
+
+                let mut headers = headers;
+                if body.is_none() {
+                    for name in CONTENT_HEADERS {
+                        headers.remove(name);
+                    }
+                }
+                if policy.strip_auth && location.origin() != url.origin() {
+                    for name in AUTH_HEADERS {
+                        headers.remove(name);
+                    }
+                }
+
+                info!(%status, %location, "Following redirect");
+                let mut next = Request::new(method, location.clone());
+                *next.headers_mut() = headers;
+                *next.body_mut() = body;
+                *next.version_mut() = version;
+                *next.timeout_mut() = timeout;
+                chain.push(Redirect { status, location });
+                request = next;
+                redirects += 1;
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Get the URL a redirect response points to, resolved against the URL of the
+/// request. `None` if the `Location` header is missing or invalid.
+fn location(url: &Url, headers: &HeaderMap) -> Option<Url> {
+    let location = headers.get(header::LOCATION)?.to_str().ok()?;
+    url.join(location).ok()
+}
+
+/// A request was redirected more times than its policy allows
+#[derive(Debug, Error)]
+#[error(
+    "Too many redirects; stopped after {max_redirects}. Increase \
+    `redirect.max_redirects` on the recipe to follow more"
+)]
+pub struct RedirectError {
+    pub max_redirects: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::absolute(Some("https://other.com/a"), Some("https://other.com/a"))]
+    #[case::relative(Some("/b?c=d"), Some("http://localhost/b?c=d"))]
+    #[case::sibling(Some("d"), Some("http://localhost/a/d"))]
+    #[case::missing(None, None)]
+    fn test_location(
+        #[case] header: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let url: Url = "http://localhost/a/b".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        if let Some(header) = header {
+            headers.insert(header::LOCATION, header.parse().unwrap());
+        }
+        assert_eq!(
+            location(&url, &headers),
+            expected.map(|url| url.parse().unwrap())
+        );
+    }
+}
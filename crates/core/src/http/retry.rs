@@ -0,0 +1,225 @@
+//! Automatic retries for requests that fail with a network error or a
+//! retryable status code. A [RetryPolicy] wraps the selected [Transport] so
+//! every attempt passes through the rest of the transport stack.
+
+use crate::{
+    collection::RetryPolicy,
+    http::{Transport, TransportError},
+};
+use futures::{FutureExt, future::BoxFuture};
+use reqwest::{
+    Request, Response,
+    header::{self, HeaderMap},
+};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+    time::Duration,
+};
+use tracing::info;
+
+/// How far a request has progressed through its retry policy. Clones share
+/// the same counter, so the attempt count can be watched while the request is
+/// in flight.
+#[derive(Clone, Debug)]
+pub struct RetryProgress {
+    attempt: Arc<AtomicU32>,
+    max_attempts: u32,
+}
+
+impl RetryProgress {
+    pub(super) fn new(max_attempts: u32) -> Self {
+        Self {
+            attempt: Default::default(),
+            max_attempts: max_attempts.max(1),
+        }
+    }
+
+    /// Number of the current (or final) attempt, starting at 1. `0` if the
+    /// request hasn't been sent yet, or retries are disabled
+    pub fn attempt(&self) -> u32 {
+        self.attempt.load(Ordering::Relaxed)
+    }
+
+    /// Most attempts the request will get. `1` if retries are disabled
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+}
+
+impl Default for RetryProgress {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl From<&slumber_config::RetryPolicy> for RetryPolicy {
+    fn from(policy: &slumber_config::RetryPolicy) -> Self {
+        Self {
+            max_attempts: policy.max_attempts,
+            statuses: policy.statuses.clone(),
+            network_errors: policy.network_errors,
+            backoff_ms: policy.backoff_ms,
+            max_backoff_ms: policy.max_backoff_ms,
+            jitter: policy.jitter,
+        }
+    }
+}
+
+/// Wrap a transport to retry requests according to the policy. Attempts are
+/// reported to `progress`.
+pub(super) fn wrap(
+    transport: Arc<dyn Transport>,
+    policy: &RetryPolicy,
+    progress: &RetryProgress,
+) -> Arc<dyn Transport> {
+    Arc::new(RetryTransport {
+        inner: transport,
+        policy: policy.clone(),
+        progress: progress.clone(),
+    })
+}
+
+/// A [Transport] that re-sends requests to the real transport until one
+/// succeeds or the policy gives up
+#[derive(Debug)]
+struct RetryTransport {
+    inner: Arc<dyn Transport>,
+    policy: RetryPolicy,
+    progress: RetryProgress,
+}
+
+impl Transport for RetryTransport {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let inner = Arc::clone(&self.inner);
+        let policy = self.policy.clone();
+        let progress = self.progress.clone();
+        async move {
+            let mut request = request;
+            let mut attempt = 1;
+            loop {
+                progress.attempt.store(attempt, Ordering::Relaxed);
+                // Streamed bodies can't be replayed, so those requests only
+                // get one attempt
+                let next = if attempt < progress.max_attempts {
+                    request.try_clone()
+                } else {
+                    None
+                };
+                let result = inner.send(request).await;
+                let Some(next) = next else {
+                    return result;
+                };
+
+                let retry_after = match &result {
+                    Ok(response)
+                        if policy
+                            .statuses
+                            .contains(&response.status().as_u16()) =>
+                    {
+                        retry_after(response.headers())
+                    }
+                    Err(_) if policy.network_errors => None,
+                    _ => return result,
+                };
+                // Respect the server's requested delay, within reason
+                let delay = backoff(&policy, attempt, random())
+                    .max(retry_after.unwrap_or_default())
+                    .min(Duration::from_millis(policy.max_backoff_ms));
+                info!(
+                    attempt,
+                    max_attempts = progress.max_attempts,
+                    ?delay,
+                    "Retrying request"
+                );
+                tokio::time::sleep(delay).await;
+                request = next;
+                attempt += 1;
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Get the delay after the given attempt. The delay doubles with each
+/// attempt, up to the policy's max. With jitter enabled, `random` picks a
+/// point between half and all of that delay.
+fn backoff(policy: &RetryPolicy, attempt: u32, random: u64) -> Duration {
+    let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+    let delay = policy
+        .backoff_ms
+        .saturating_mul(factor)
+        .min(policy.max_backoff_ms);
+    let delay = if policy.jitter {
+        let half = delay / 2;
+        delay - half + random % (half + 1)
+    } else {
+        delay
+    };
+    Duration::from_millis(delay)
+}
+
+/// Parse the `Retry-After` header, if present. Only the delay-seconds form is
+/// supported
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers.get(header::RETRY_AFTER)?.to_str().ok()?;
+    seconds.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Get a random number for jitter
+fn random() -> u64 {
+    uuid::Uuid::new_v4().as_u64_pair().0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Delays double with each attempt, up to the max
+    #[rstest]
+    #[case::first(1, false, 0, 100)]
+    #[case::second(2, false, 0, 200)]
+    #[case::third(3, false, 0, 400)]
+    #[case::capped(10, false, 0, 1000)]
+    #[case::jitter_min(2, true, 0, 100)]
+    #[case::jitter_max(2, true, 100, 200)]
+    #[case::jitter_wrap(2, true, 101, 100)]
+    fn test_backoff(
+        #[case] attempt: u32,
+        #[case] jitter: bool,
+        #[case] random: u64,
+        #[case] expected_ms: u64,
+    ) {
+        let policy = RetryPolicy {
+            backoff_ms: 100,
+            max_backoff_ms: 1000,
+            jitter,
+            ..RetryPolicy::default()
+        };
+        assert_eq!(
+            backoff(&policy, attempt, random),
+            Duration::from_millis(expected_ms)
+        );
+    }
+
+    #[rstest]
+    #[case::seconds(Some("3"), Some(Duration::from_secs(3)))]
+    #[case::date(Some("Wed, 21 Oct 2015 07:28:00 GMT"), None)]
+    #[case::missing(None, None)]
+    fn test_retry_after(
+        #[case] header: Option<&str>,
+        #[case] expected: Option<Duration>,
+    ) {
+        let mut headers = HeaderMap::new();
+        if let Some(header) = header {
+            headers.insert(header::RETRY_AFTER, header.parse().unwrap());
+        }
+        assert_eq!(retry_after(&headers), expected);
+    }
+}
@@ -0,0 +1,178 @@
+//! Parsing for Server-Sent Events, i.e. `text/event-stream` response bodies.
+//! See the [spec](https://html.spec.whatwg.org/multipage/server-sent-events.html#event-stream-interpretation)
+
+use crate::http::models::content_type_header;
+use reqwest::header::HeaderMap;
+use std::mem;
+
+/// Do these response headers indicate a Server-Sent Events stream?
+pub fn is_event_stream(headers: &HeaderMap) -> bool {
+    content_type_header(headers).is_some_and(|mime| {
+        mime.essence_str() == mime::TEXT_EVENT_STREAM.essence_str()
+    })
+}
+
+/// A single event from an event stream
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServerEvent {
+    /// Event type, from the `event` field. `None` means the default type,
+    /// `message`
+    pub event: Option<String>,
+    /// Most recent `id` field received in the stream. Per the spec, this
+    /// carries over to subsequent events that don't set their own.
+    pub id: Option<String>,
+    /// All `data` fields of the event, joined by newlines
+    pub data: String,
+}
+
+/// Incremental parser for an event stream. Feed it chunks of the body as
+/// they're received, and it returns each event as soon as it's complete.
+///
+/// Lines may end with `\n` or `\r\n`. A lone `\r` line ending is not
+/// supported, because it's indistinguishable from the first half of `\r\n`
+/// when a chunk ends between the two.
+#[derive(Debug, Default)]
+pub struct EventStreamParser {
+    /// Bytes of the current line that haven't been terminated yet
+    line: Vec<u8>,
+    /// `event` field of the event being built
+    event: Option<String>,
+    /// Last `id` field received
+    id: Option<String>,
+    /// `data` fields of the event being built. `None` if there haven't been
+    /// any yet, so the event shouldn't be dispatched.
+    data: Option<String>,
+}
+
+impl EventStreamParser {
+    /// Parse the next chunk of the stream, returning every event that it
+    /// completes
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<ServerEvent> {
+        let mut events = Vec::new();
+        let mut rest = chunk;
+        while let Some(index) = rest.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&rest[..index]);
+            rest = &rest[index + 1..];
+            let line = mem::take(&mut self.line);
+            events.extend(self.process_line(&line));
+        }
+        self.line.extend_from_slice(rest);
+        events
+    }
+
+    /// Process a complete line, without its line ending. If this line ends an
+    /// event, return it.
+    fn process_line(&mut self, line: &[u8]) -> Option<ServerEvent> {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let line = String::from_utf8_lossy(line);
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        // Lines starting with : are comments
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            // A single leading space in the value is ignored
+            Some((field, value)) => {
+                (field, value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (line.as_ref(), ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_owned()),
+            "data" => {
+                if let Some(data) = &mut self.data {
+                    data.push('\n');
+                    data.push_str(value);
+                } else {
+                    self.data = Some(value.to_owned());
+                }
+            }
+            "id" if !value.contains('\0') => {
+                self.id = Some(value.to_owned());
+            }
+            // `retry` only matters for reconnecting, which we don't do.
+            // Unknown fields are ignored per the spec.
+            _ => {}
+        }
+        None
+    }
+
+    /// Finish the current event. An event with no data is discarded
+    fn dispatch(&mut self) -> Option<ServerEvent> {
+        let event = self.event.take();
+        let data = self.data.take()?;
+        Some(ServerEvent {
+            event,
+            id: self.id.clone(),
+            data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::header_map;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::event_stream(Some("text/event-stream"), true)]
+    #[case::charset(Some("text/event-stream; charset=utf-8"), true)]
+    #[case::plain(Some("text/plain"), false)]
+    #[case::missing(None, false)]
+    fn test_is_event_stream(
+        #[case] content_type: Option<&str>,
+        #[case] expected: bool,
+    ) {
+        let headers = content_type
+            .map(|content_type| header_map([("content-type", content_type)]))
+            .unwrap_or_default();
+        assert_eq!(is_event_stream(&headers), expected);
+    }
+
+    /// Parse an entire stream, split into chunks
+    #[rstest]
+    #[case::empty(&[""], &[])]
+    #[case::single(&["data: hello\n\n"], &[event(None, None, "hello")])]
+    #[case::multiline(
+        &["data: hello\ndata:world\n\n"],
+        &[event(None, None, "hello\nworld")],
+    )]
+    #[case::fields(
+        &["event: update\nid: 1\nretry: 100\ndata: {\"a\": 1}\n\n"],
+        &[event(Some("update"), Some("1"), "{\"a\": 1}")],
+    )]
+    // The last ID carries over; the event type doesn't
+    #[case::multiple(
+        &["event: a\nid: 1\ndata: 1\n\ndata: 2\n\n"],
+        &[event(Some("a"), Some("1"), "1"), event(None, Some("1"), "2")],
+    )]
+    #[case::comment(&[": keepalive\n\ndata: 1\n\n"], &[event(None, None, "1")])]
+    #[case::crlf(&["data: 1\r\n\r\n"], &[event(None, None, "1")])]
+    #[case::empty_data(&["data\n\n"], &[event(None, None, "")])]
+    #[case::no_data(&["event: a\n\n"], &[])]
+    #[case::incomplete(&["data: 1\n"], &[])]
+    #[case::split(
+        &["da", "ta: hel", "lo\r", "\n", "\n"],
+        &[event(None, None, "hello")],
+    )]
+    fn test_parse(#[case] chunks: &[&str], #[case] expected: &[ServerEvent]) {
+        let mut parser = EventStreamParser::default();
+        let events: Vec<ServerEvent> = chunks
+            .iter()
+            .flat_map(|chunk| parser.push(chunk.as_bytes()))
+            .collect();
+        assert_eq!(events, expected);
+    }
+
+    fn event(event: Option<&str>, id: Option<&str>, data: &str) -> ServerEvent {
+        ServerEvent {
+            event: event.map(String::from),
+            id: id.map(String::from),
+            data: data.to_owned(),
+        }
+    }
+}
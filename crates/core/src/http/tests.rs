@@ -2,16 +2,55 @@
 
 use super::*;
 use crate::{
-    collection::{Authentication, Profile},
-    test_util::{TestPrompter, by_id, header_map, http_engine, invalid_utf8},
+    collection::{
+        self, Authentication, AuthenticationSetting, Chaos, Compression,
+        ContentEncoding, Folder, FormPart, Grpc, HttpProtocol, Profile,
+        RecipeNode, RecipeParam, RecipeParamType, RedirectPolicy, RenderMode,
+        RetryPolicy, Throttle, Timeout, WebSocket, WebSocketFrame,
+    },
+    database::CollectionDatabase,
+    http::{content_type::ContentType, transport::Verification},
+    render::Auditor,
+    test_util::{
+        MockTransport, TestPrompter, TestSelectPrompter, by_id, header_map,
+        http_engine, invalid_utf8,
+    },
 };
+use base64::{Engine, prelude::BASE64_STANDARD};
+use futures::{FutureExt, SinkExt, channel::oneshot};
 use indexmap::{IndexMap, indexmap};
+use itertools::Itertools;
 use pretty_assertions::assert_eq;
-use reqwest::{Body, StatusCode, header};
+use reqwest::{Body, Certificate, StatusCode, Version, header};
 use rstest::rstest;
 use serde_json::json;
-use slumber_util::{Factory, assert_err, assert_result, test_data_dir};
-use std::{cell::RefCell, path, ptr};
+use slumber_config::{Config, TemplateSource, TlsHost};
+use slumber_util::{
+    Factory, TempDir, assert_err, assert_matches, assert_result, temp_dir,
+    test_data_dir,
+};
+use std::{
+    cell::RefCell,
+    fs, io,
+    path::{self, Path},
+    time::Instant,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
+use tokio_rustls::{
+    TlsAcceptor,
+    rustls::{
+        RootCertStore, ServerConfig,
+        pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+        server::WebPkiClientVerifier,
+    },
+};
+use tokio_tungstenite::{
+    WebSocketStream,
+    tungstenite::{Message, protocol::Role},
+};
 use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
 
 thread_local! {
@@ -67,28 +106,57 @@ fn seed(context: &TemplateContext, build_options: BuildOptions) -> RequestSeed {
     )
 }
 
-/// Make sure we only use the dangerous client when we really expect to.
-/// There's isn't an easy way to mock TLS errors, so the easiest way to
-/// test this is to just make sure [HttpEngine::get_client] returns the
-/// expected client
+/// Make sure we only use a less secure client when we really expect to. TLS
+/// behavior itself is tested in [test_tls_verification]; this just checks
+/// which client [ReqwestTransport::get_client] picks.
 #[rstest]
-#[case::safe("safe", false)]
-#[case::danger("danger", true)]
+#[case::safe("safe", Version::HTTP_11, None)]
+#[case::danger("danger", Version::HTTP_11, Some((Verification::None, false)))]
+#[case::any_hostname(
+    "any_hostname",
+    Version::HTTP_11,
+    Some((Verification::AnyHostname, false))
+)]
+#[case::verified("verified", Version::HTTP_11, None)]
+#[case::http2("safe", Version::HTTP_2, Some((Verification::Full, true)))]
+#[case::danger_http2(
+    "danger",
+    Version::HTTP_2,
+    Some((Verification::None, true))
+)]
 fn test_get_client(
-    http_engine: HttpEngine,
     #[case] hostname: &str,
-    #[case] expected_danger: bool,
+    #[case] version: Version,
+    #[case] expected_client: Option<(Verification, bool)>,
 ) {
-    let client =
-        http_engine.get_client(&format!("http://{hostname}/").parse().unwrap());
-    if expected_danger {
-        assert!(ptr::eq(
-            client,
-            &raw const http_engine.danger_client.as_ref().unwrap().0
-        ));
-    } else {
-        assert!(ptr::eq(client, &raw const http_engine.client));
-    }
+    let ca_certificates = Certificate::from_pem_bundle(
+        &std::fs::read(test_data_dir().join("tls/server_ca.pem")).unwrap(),
+    )
+    .unwrap();
+    let transport = ReqwestTransport::new(
+        &HttpEngineConfig {
+            ignore_certificate_hosts: vec!["danger".to_owned()],
+            tls_hosts: indexmap! {
+                "any_hostname".into() => TlsHost {
+                    danger_accept_invalid_hostnames: true,
+                    ..TlsHost::default()
+                },
+                "verified".into() => TlsHost::default(),
+            },
+            ..Default::default()
+        },
+        &HostPolicy::default(),
+        ca_certificates,
+    );
+    transport
+        .get_client(&format!("http://{hostname}/").parse().unwrap(), version)
+        .unwrap();
+    // The default client is used unless another one was built
+    let clients = transport.clients.lock().unwrap();
+    assert_eq!(
+        clients.keys().copied().collect::<Vec<_>>(),
+        Vec::from_iter(expected_client)
+    );
 }
 
 #[rstest]
@@ -119,6 +187,7 @@ async fn test_build_request(http_engine: HttpEngine) {
         .parse()
         .unwrap();
     let expected_headers = header_map([
+        ("user-agent", USER_AGENT),
         ("content-type", "application/json"),
         ("accept", "application/json"),
     ]);
@@ -177,6 +246,107 @@ async fn test_build_url(http_engine: HttpEngine) {
     );
 }
 
+/// The recipe's render mode applies to all of its templates
+#[rstest]
+#[tokio::test]
+async fn test_build_render_mode(http_engine: HttpEngine) {
+    let recipe = Recipe {
+        url: "{{ host }}/users/{{ missing }}".into(),
+        headers: indexmap! {"x-missing".into() => "{{ missing }}".into()},
+        body: Some(RecipeBody::json(json!({"id": "{{ missing }}"})).unwrap()),
+        render_mode: Some(RenderMode::Lenient),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+
+    assert_eq!(ticket.record.url.as_str(), "http://localhost/users/");
+    assert_eq!(ticket.record.headers.get("x-missing").unwrap(), "");
+    assert_eq!(ticket.record.body.as_deref(), Some(&br#"{"id":""}"#[..]));
+}
+
+/// Build a context for pre-flight tests. The recipe depends on values that are
+/// undefined, empty, nested in other fields, or only used as a fallback
+fn preflight_context(
+    render_mode: RenderMode,
+    prompt_missing: bool,
+) -> TemplateContext {
+    let recipe = Recipe {
+        url: "{{ host }}/{{ missing }}/{{ id }}".into(),
+        headers: indexmap! {
+            "x-token".into() => "{{ empty }}".into(),
+            "x-nested".into() => "{{ alias }}".into(),
+        },
+        body: Some("{{ fallback('', ignored) }}".into()),
+        render_mode: Some(render_mode),
+        params: indexmap! {
+            "id".into() => RecipeParam {
+                param_type: RecipeParamType::Integer,
+                required: true,
+                default: None,
+                description: None,
+            },
+        },
+        ..Recipe::factory(())
+    };
+    let profile = Profile {
+        data: indexmap! {
+            "host".into() => "http://localhost".into(),
+            "empty".into() => "".into(),
+            "alias".into() => "{{ nested }}".into(),
+        },
+        ..Profile::factory(())
+    };
+    TemplateContext {
+        prompter: Box::new(TestPrompter::new(["a", "b", "c", "4"])),
+        prompt_missing,
+        ..TemplateContext::factory((by_id([profile]), by_id([recipe])))
+    }
+}
+
+/// Every missing value is reported in a single error
+#[rstest]
+#[case::strict(
+    RenderMode::Strict,
+    "Missing values: profile field `missing` is not defined, parameter `id` \
+    is required, profile field `empty` is empty, profile field `nested` is not \
+    defined"
+)]
+#[case::lenient(
+    RenderMode::Lenient,
+    "Missing values: parameter `id` is required"
+)]
+#[tokio::test]
+async fn test_preflight_error(
+    http_engine: HttpEngine,
+    #[case] render_mode: RenderMode,
+    #[case] expected_error: &str,
+) {
+    let context = preflight_context(render_mode, false);
+    let seed = seed(&context, BuildOptions::default());
+    assert_err(http_engine.build(seed, &context).await, expected_error);
+}
+
+/// Every missing value is asked for up front
+#[rstest]
+#[tokio::test]
+async fn test_preflight_prompt(http_engine: HttpEngine) {
+    let context = preflight_context(RenderMode::Strict, true);
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+
+    assert_eq!(ticket.record.url.as_str(), "http://localhost/a/4");
+    assert_eq!(
+        ticket.record.headers,
+        header_map([
+            ("x-token", "b"),
+            ("x-nested", "c"),
+            ("user-agent", USER_AGENT),
+        ])
+    );
+}
+
 /// Test building just a body. URL/query/headers should *not* be built.
 #[rstest]
 #[case::raw(
@@ -188,6 +358,7 @@ async fn test_build_url(http_engine: HttpEngine) {
     br#"{"group_id":"3"}"#,
 )]
 #[case::binary(RecipeBody::Raw(invalid_utf8()), b"\xc3\x28")]
+#[case::file(RecipeBody::File("data.json".into()), br#"{ "a": 1, "b": 2 }"#)]
 #[tokio::test]
 async fn test_build_body(
     http_engine: HttpEngine,
@@ -235,7 +406,7 @@ async fn test_authentication(
         // something a user would ever want to do, but it should be
         // well-defined
         headers: indexmap! {"Authorization".into() => "bogus".into()},
-        authentication: Some(authentication),
+        authentication: Some(authentication.into()),
         ..Recipe::factory(())
     };
     let recipe_id = recipe.id.clone();
@@ -254,6 +425,7 @@ async fn test_authentication(
             http_version: HttpVersion::Http11,
             url: "http://localhost/url".parse().unwrap(),
             headers: header_map([
+                ("user-agent", USER_AGENT),
                 ("authorization", "bogus"),
                 ("authorization", expected_header)
             ]),
@@ -262,6 +434,450 @@ async fn test_authentication(
     );
 }
 
+/// Digest authentication answers the server's challenge with a second request
+#[rstest]
+#[tokio::test]
+async fn test_authentication_digest(http_engine: HttpEngine) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::header_regex(
+        "authorization",
+        "^Digest username=\"user\", realm=\"test\", uri=\"/digest\\?a=1\", \
+        algorithm=SHA-256, nonce=\"abc\", nc=00000001, cnonce=\"[0-9a-f]+\", \
+        qop=auth, response=\"[0-9a-f]{64}\", opaque=\"xyz\"$",
+    ))
+    .respond_with(ResponseTemplate::new(StatusCode::OK))
+    .expect(1)
+    .with_priority(1)
+    .mount(&server)
+    .await;
+    Mock::given(matchers::any())
+        .respond_with(
+            ResponseTemplate::new(StatusCode::UNAUTHORIZED)
+                .append_header("WWW-Authenticate", "Basic realm=\"test\"")
+                .append_header(
+                    "WWW-Authenticate",
+                    "Digest realm=\"test\", qop=\"auth\", nonce=\"abc\", \
+                    opaque=\"xyz\", algorithm=MD5, Digest realm=\"test\", \
+                    qop=\"auth\", nonce=\"abc\", opaque=\"xyz\", \
+                    algorithm=SHA-256",
+                ),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        url: "{{ host }}/digest".into(),
+        query: indexmap! {"a".into() => "1".into()},
+        authentication: Some(
+            Authentication::Digest {
+                username: "user".into(),
+                password: "{{ password }}".into(),
+            }
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    // The header is computed at send time, so it's not in the record
+    assert!(!ticket.record.headers.contains_key(header::AUTHORIZATION));
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+}
+
+/// NTLM authentication takes a three-message handshake: negotiate,
+/// challenge, authenticate
+#[rstest]
+#[tokio::test]
+async fn test_authentication_ntlm(http_engine: HttpEngine) {
+    // Type 3 (authenticate) message
+    let server = MockServer::start().await;
+    Mock::given(matchers::header_regex(
+        "authorization",
+        "^NTLM TlRMTVNTUAADAAAA",
+    ))
+    .respond_with(ResponseTemplate::new(StatusCode::OK))
+    .expect(1)
+    .with_priority(1)
+    .mount(&server)
+    .await;
+    // Type 1 (negotiate) message gets a type 2 (challenge) message in return
+    let mut challenge = b"NTLMSSP\0\x02\0\0\0".to_vec();
+    challenge.extend_from_slice(&[0, 0, 0, 0, 48, 0, 0, 0]);
+    challenge.extend_from_slice(&0x0008_8201u32.to_le_bytes());
+    challenge.extend_from_slice(&[0x11; 8]);
+    challenge.extend_from_slice(&[0; 8]);
+    challenge.extend_from_slice(&[0, 0, 0, 0, 48, 0, 0, 0]);
+    Mock::given(matchers::header_regex(
+        "authorization",
+        "^NTLM TlRMTVNTUAABAAAA",
+    ))
+    .respond_with(
+        ResponseTemplate::new(StatusCode::UNAUTHORIZED).append_header(
+            "WWW-Authenticate",
+            format!("NTLM {}", BASE64_STANDARD.encode(challenge)),
+        ),
+    )
+    .expect(1)
+    .with_priority(2)
+    .mount(&server)
+    .await;
+    // Unauthenticated request gets the list of supported schemes
+    Mock::given(matchers::any())
+        .respond_with(
+            ResponseTemplate::new(StatusCode::UNAUTHORIZED)
+                .append_header("WWW-Authenticate", "Negotiate")
+                .append_header("WWW-Authenticate", "NTLM"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        url: "{{ host }}/ntlm".into(),
+        authentication: Some(
+            Authentication::Ntlm {
+                username: "CORP\\user".into(),
+                password: "{{ password }}".into(),
+                domain: None,
+            }
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+}
+
+/// If the server doesn't challenge the request, it's only sent once
+#[rstest]
+#[tokio::test]
+async fn test_authentication_challenge_unchallenged(http_engine: HttpEngine) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        url: "{{ host }}/open".into(),
+        authentication: Some(
+            Authentication::Digest {
+                username: "user".into(),
+                password: "{{ password }}".into(),
+            }
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+}
+
+/// Recipes inherit authentication from their folder, unless they disable it
+#[rstest]
+#[case::inherited(None, Some("Bearer tokenzzz"))]
+#[case::own(
+    Some(Authentication::Basic {
+        username: "{{ username }}".into(),
+        password: None,
+    }.into()),
+    Some("Basic dXNlcjo="),
+)]
+#[case::disabled(Some(AuthenticationSetting::Disabled), None)]
+#[tokio::test]
+async fn test_authentication_inherited(
+    http_engine: HttpEngine,
+    #[case] recipe_auth: Option<AuthenticationSetting>,
+    #[case] expected_header: Option<&str>,
+) {
+    let recipe = Recipe {
+        authentication: recipe_auth,
+        ..Recipe::factory(())
+    };
+    let recipe_id = recipe.id.clone();
+    let folder = Folder {
+        authentication: Some(
+            Authentication::Bearer {
+                token: "{{ token }}".into(),
+            }
+            .into(),
+        ),
+        children: by_id([recipe.into()]),
+        ..Folder::factory(())
+    };
+    let mut context = template_context(Recipe::factory(()), None);
+    Arc::get_mut(&mut context.collection).unwrap().recipes =
+        by_id([RecipeNode::from(folder)]).into();
+
+    let seed = RequestSeed::new(recipe_id, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+
+    assert_eq!(
+        ticket
+            .record
+            .headers
+            .get(header::AUTHORIZATION)
+            .map(|value| value.to_str().unwrap()),
+        expected_header
+    );
+}
+
+/// OAuth2 client credentials: the token is fetched once and reused until the
+/// server rejects it
+#[rstest]
+#[tokio::test]
+async fn test_oauth2_client_credentials(http_engine: HttpEngine) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/token"))
+        // base64(client:secret)
+        .and(matchers::header(
+            "authorization",
+            "Basic Y2xpZW50OnNlY3JldA==",
+        ))
+        .and(matchers::body_string(
+            "grant_type=client_credentials&scope=read",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({"access_token": "token1", "expires_in": 3600}),
+        ))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        authentication: Some(
+            Authentication::OAuth2(OAuth2 {
+                grant: OAuth2Grant::ClientCredentials,
+                token_url: "{{ host }}/token".into(),
+                client_id: "client".into(),
+                client_secret: Some("secret".into()),
+                scope: Some("read".into()),
+            })
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let mut context = template_context(recipe, Some(&server.uri()));
+
+    // Second build reuses the cached token
+    for _ in 0..2 {
+        let seed = seed(&context, BuildOptions::default());
+        let ticket = http_engine.build(seed, &context).await.unwrap();
+        assert_eq!(
+            ticket.record.headers.get(header::AUTHORIZATION).unwrap(),
+            "Bearer token1"
+        );
+    }
+
+    // Token was rejected; fetch a new one even though it's not expired
+    context.reauthenticate();
+    let seed = seed(&context, BuildOptions::default());
+    http_engine.build(seed, &context).await.unwrap();
+}
+
+/// OAuth2 tokens are refreshed with the refresh token when they expire
+#[rstest]
+#[tokio::test]
+async fn test_oauth2_refresh(http_engine: HttpEngine) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/token"))
+        .and(matchers::body_string(
+            "grant_type=client_credentials&client_id=client",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "token1",
+            "refresh_token": "refresh1",
+            // Already expired
+            "expires_in": 0,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/token"))
+        .and(matchers::body_string(
+            "grant_type=refresh_token&refresh_token=refresh1&client_id=client",
+        ))
+        // No expiry, so this token is used until rejected
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"access_token": "token2"})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        authentication: Some(
+            Authentication::OAuth2(OAuth2 {
+                grant: OAuth2Grant::ClientCredentials,
+                token_url: "{{ host }}/token".into(),
+                client_id: "client".into(),
+                client_secret: None,
+                scope: None,
+            })
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+
+    for expected in ["Bearer token1", "Bearer token2", "Bearer token2"] {
+        let seed = seed(&context, BuildOptions::default());
+        let ticket = http_engine.build(seed, &context).await.unwrap();
+        assert_eq!(
+            ticket.record.headers.get(header::AUTHORIZATION).unwrap(),
+            expected
+        );
+    }
+}
+
+/// OAuth2 authorization code flow. The "browser" follows the provider's
+/// redirect back to Slumber's listener, which gets the code to exchange for a
+/// token
+#[rstest]
+#[tokio::test]
+async fn test_oauth2_authorization_code(http_engine: HttpEngine) {
+    /// Simulate the user logging in: redirect back to the client with a code
+    struct Login;
+
+    impl wiremock::Respond for Login {
+        fn respond(&self, request: &wiremock::Request) -> ResponseTemplate {
+            let params: HashMap<_, _> = request.url.query_pairs().collect();
+            let mut location: Url = params["redirect_uri"].parse().unwrap();
+            location
+                .query_pairs_mut()
+                .append_pair("code", "code1")
+                .append_pair("state", &params["state"]);
+            ResponseTemplate::new(302)
+                .insert_header("Location", location.as_str())
+        }
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/authorize"))
+        .and(matchers::query_param("response_type", "code"))
+        .and(matchers::query_param("client_id", "client"))
+        .and(matchers::query_param("code_challenge_method", "S256"))
+        .respond_with(Login)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/token"))
+        .and(matchers::body_string_contains(
+            "grant_type=authorization_code&code=code1",
+        ))
+        .and(matchers::body_string_contains("code_verifier="))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"access_token": "token1"})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    // Find an open port for the redirect listener
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let recipe = Recipe {
+        authentication: Some(
+            Authentication::OAuth2(OAuth2 {
+                grant: OAuth2Grant::AuthorizationCode {
+                    authorization_url: "{{ host }}/authorize".into(),
+                    redirect_uri: Some(Template::raw(format!(
+                        "http://127.0.0.1:{port}/callback"
+                    ))),
+                },
+                token_url: "{{ host }}/token".into(),
+                client_id: "client".into(),
+                client_secret: None,
+                scope: None,
+            })
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let mut context = template_context(recipe, Some(&server.uri()));
+    context.oauth2_tokens = TokenStore::default().with_browser(|url| {
+        let url = url.to_owned();
+        tokio::spawn(async move { reqwest::get(url).await.unwrap() });
+        Ok(())
+    });
+
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    assert_eq!(
+        ticket.record.headers.get(header::AUTHORIZATION).unwrap(),
+        "Bearer token1"
+    );
+}
+
+/// Errors from the OAuth2 provider are surfaced in the build error
+#[rstest]
+#[case::provider(
+    ResponseTemplate::new(400).set_body_json(json!({
+        "error": "invalid_client",
+        "error_description": "Unknown client",
+    })),
+    "Provider returned error `invalid_client`: Unknown client"
+)]
+#[case::status(
+    ResponseTemplate::new(500).set_body_string("oops"),
+    "Token request failed with status 500 Internal Server Error: oops"
+)]
+#[case::response(
+    ResponseTemplate::new(200).set_body_json(json!({"token": "token1"})),
+    "Invalid token response"
+)]
+#[tokio::test]
+async fn test_oauth2_error(
+    http_engine: HttpEngine,
+    #[case] response: ResponseTemplate,
+    #[case] expected_error: &str,
+) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/token"))
+        .respond_with(response)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        authentication: Some(
+            Authentication::OAuth2(OAuth2 {
+                grant: OAuth2Grant::ClientCredentials,
+                token_url: "{{ host }}/token".into(),
+                client_id: "client".into(),
+                client_secret: None,
+                scope: None,
+            })
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+
+    let seed = seed(&context, BuildOptions::default());
+    assert_err(http_engine.build(seed, &context).await, expected_error);
+}
+
 /// Test each possible type of body. This seems redundant with
 /// [test_build_body], but we need this to test that the `content-type` header
 /// is set correctly. This also allows us to test the actual built request,
@@ -317,14 +933,34 @@ async fn test_authentication(
     Some("application/json"),
     r#"{"a":1,"b":2}"#,
 )]
-#[case::form_urlencoded(
-    RecipeBody::FormUrlencoded(indexmap! {
-        "user_id".into() => "{{ user_id }}".into(),
-        "token".into() => "{{ token }}".into()
+#[case::graphql(
+    RecipeBody::Graphql(GraphQlBody {
+        query: "query { group(id: {{ group_id }}) { name } }".into(),
+        variables: None,
+        operation_name: None,
     }),
     None,
-    Some("application/x-www-form-urlencoded"),
-    "user_id=1&token=tokenzzz",
+    Some("application/json"),
+    r#"{"query":"query { group(id: 3) { name } }"}"#,
+)]
+#[case::graphql_variables(
+    RecipeBody::Graphql(GraphQlBody {
+        query: "query Group($id: ID!) { group(id: $id) { name } }".into(),
+        variables: Some(json!({"id": "{{ group_id }}"}).try_into().unwrap()),
+        operation_name: Some("Group".into()),
+    }),
+    None,
+    Some("application/json"),
+    r#"{"query":"query Group($id: ID!) { group(id: $id) { name } }","variables":{"id":"3"},"operationName":"Group"}"#,
+)]
+#[case::form_urlencoded(
+    RecipeBody::FormUrlencoded(indexmap! {
+        "user_id".into() => "{{ user_id }}".into(),
+        "token".into() => "{{ token }}".into()
+    }),
+    None,
+    Some("application/x-www-form-urlencoded"),
+    "user_id=1&token=tokenzzz",
 )]
 // reqwest sets the content type when initializing the body, so make sure
 // that doesn't override the user's value
@@ -388,6 +1024,11 @@ async fn test_body(
     None, // Content-Type is intentionally *not* inferred from the extension
     r#"{ "a": 1, "b": 2 }"#,
 )]
+#[case::stream_file_encoded(
+    RecipeBody::Stream("{{ file('data.json', encoding='hex') }}".into()),
+    None,
+    "7b202261223a20312c202262223a2032207d",
+)]
 #[case::stream_command(
     RecipeBody::Stream("{{ command(['cat', 'data.json']) }}".into()),
     None,
@@ -431,6 +1072,19 @@ Content-Type: application/json\r
 --{BOUNDARY}--\r
 ",
 )]
+#[case::form_multipart_file_encoded(
+    RecipeBody::FormMultipart(indexmap! {
+        // Encoded files can't use native file support
+        "file".into() => "{{ file('data.json', encoding='base64') }}".into(),
+    }),
+    Some("multipart/form-data; boundary={BOUNDARY}"),
+    "--{BOUNDARY}\r
+Content-Disposition: form-data; name=\"file\"\r
+\r
+eyAiYSI6IDEsICJiIjogMiB9\r
+--{BOUNDARY}--\r
+",
+)]
 #[case::form_multipart_file_multichunk(
     RecipeBody::FormMultipart(indexmap! {
         // This body gets streamed, but it does *not* use native file support
@@ -445,6 +1099,41 @@ data: { \"a\": 1, \"b\": 2 }\r
 --{BOUNDARY}--\r
 ",
 )]
+#[case::form_multipart_file_headers(
+    RecipeBody::FormMultipart(indexmap! {
+        // Explicit headers beat the ones derived from the file
+        "file".into() => FormPart::Detailed {
+            value: "{{ file('data.json') }}".into(),
+            content_type: Some("text/plain".into()),
+            filename: Some("{{ user_id }}.txt".into()),
+        },
+    }),
+    Some("multipart/form-data; boundary={BOUNDARY}"),
+    "--{BOUNDARY}\r
+Content-Disposition: form-data; name=\"file\"; filename=\"1.txt\"\r
+Content-Type: text/plain\r
+\r
+{ \"a\": 1, \"b\": 2 }\r
+--{BOUNDARY}--\r
+",
+)]
+#[case::form_multipart_text_headers(
+    RecipeBody::FormMultipart(indexmap! {
+        "metadata".into() => FormPart::Detailed {
+            value: "{\"id\": {{ user_id }}}".into(),
+            content_type: Some("application/json".into()),
+            filename: None,
+        },
+    }),
+    Some("multipart/form-data; boundary={BOUNDARY}"),
+    "--{BOUNDARY}\r
+Content-Disposition: form-data; name=\"metadata\"\r
+Content-Type: application/json\r
+\r
+{\"id\": 1}\r
+--{BOUNDARY}--\r
+",
+)]
 #[case::form_multipart_command(
     RecipeBody::FormMultipart(indexmap! {
         "command".into() => "{{ command(['cat', 'data.json']) }}".into(),
@@ -529,6 +1218,155 @@ async fn test_body_stream(
     assert_eq!(body, expected_body, "Incorrect body");
 }
 
+/// File bodies are streamed from disk with a known length. `Content-Type` is
+/// guessed from the extension unless the recipe sets it.
+#[rstest]
+#[case::guess(None, "application/json")]
+#[case::explicit(Some("text/plain"), "text/plain")]
+#[tokio::test]
+async fn test_body_file(
+    http_engine: HttpEngine,
+    #[case] content_type: Option<&'static str>,
+    #[case] expected_content_type: &str,
+) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/post"))
+        .and(matchers::header(header::CONTENT_LENGTH, "18"))
+        .and(matchers::header(
+            header::CONTENT_TYPE,
+            expected_content_type,
+        ))
+        .respond_with(|request: &wiremock::Request| {
+            ResponseTemplate::new(StatusCode::OK)
+                .set_body_bytes(request.body.clone())
+        })
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        url: "{{ host }}/post".into(),
+        headers: content_type
+            .map(|content_type| ("content-type".into(), content_type.into()))
+            .into_iter()
+            .collect(),
+        // Path is a template
+        body: Some(RecipeBody::File("{{ test_data_dir }}/data.json".into())),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+
+    assert_eq!(exchange.response.status, StatusCode::OK);
+    assert_eq!(exchange.response.body.text(), Some(r#"{ "a": 1, "b": 2 }"#));
+}
+
+/// A missing file is an error at build time, rather than a half-sent request
+#[rstest]
+#[tokio::test]
+async fn test_body_file_missing(http_engine: HttpEngine) {
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        body: Some(RecipeBody::File("not_real.bin".into())),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+
+    let seed = seed(&context, BuildOptions::default());
+    assert_err(
+        http_engine.build(seed, &context).await,
+        "Reading request body from",
+    );
+}
+
+/// `!file` bodies are disabled for untrusted collections, like `file()`
+#[rstest]
+#[tokio::test]
+async fn test_body_file_sandboxed(http_engine: HttpEngine) {
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        body: Some(RecipeBody::File("data.json".into())),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext {
+        sandboxed: true,
+        ..template_context(recipe, None)
+    };
+
+    assert_err(
+        http_engine
+            .build(seed(&context, BuildOptions::default()), &context)
+            .await,
+        "`!file` is disabled",
+    );
+    // Rendering just the body would read the file too
+    assert_err(
+        http_engine
+            .build_body(seed(&context, BuildOptions::default()), &context)
+            .await,
+        "`!file` is disabled",
+    );
+}
+
+/// Files read directly for `!file` bodies and multipart file parts are
+/// recorded in the audit log, and denied reads never happen
+#[rstest]
+#[case::file(|| RecipeBody::File("data.json".into()))]
+#[case::multipart(|| RecipeBody::FormMultipart(indexmap! {
+    "file".into() => "{{ file('data.json') }}".into(),
+}))]
+#[tokio::test]
+async fn test_body_file_audit(
+    http_engine: HttpEngine,
+    #[case] body: fn() -> RecipeBody,
+) {
+    let recipe = || Recipe {
+        method: HttpMethod::Post,
+        body: Some(body()),
+        ..Recipe::factory(())
+    };
+    let config = Config {
+        audit_log: true,
+        confirm_sources: vec![TemplateSource::File],
+        ..Config::default()
+    };
+    let detail = test_data_dir().join("data.json").display().to_string();
+
+    // Allowed
+    let database = CollectionDatabase::factory(());
+    let context = TemplateContext {
+        auditor: Auditor::new(&config, database.clone()),
+        prompter: Box::new(TestSelectPrompter::new([0])),
+        ..template_context(recipe(), None)
+    };
+    let request_seed = seed(&context, BuildOptions::default());
+    http_engine.build(request_seed, &context).await.unwrap();
+    let events = database.get_audit_events(None).unwrap();
+    assert_matches!(
+        events.as_slice(),
+        [event] if event.source == TemplateSource::File
+            && event.detail == detail
+    );
+
+    // Denied
+    let database = CollectionDatabase::factory(());
+    let context = TemplateContext {
+        auditor: Auditor::new(&config, database.clone()),
+        prompter: Box::new(TestSelectPrompter::new([1])),
+        ..template_context(recipe(), None)
+    };
+    let request_seed = seed(&context, BuildOptions::default());
+    assert_err(
+        http_engine.build(request_seed, &context).await,
+        "was denied",
+    );
+    assert_eq!(database.get_audit_events(None).unwrap(), []);
+}
+
 /// Test overriding URL in BuildOptions
 #[rstest]
 #[tokio::test]
@@ -548,6 +1386,49 @@ async fn test_override_url(http_engine: HttpEngine) {
     assert_eq!(ticket.record.url.as_str(), "http://custom-host/users/user");
 }
 
+/// Following a link sends a GET to the link URL. Only authentication is
+/// inherited from the recipe
+#[rstest]
+#[tokio::test]
+async fn test_link(http_engine: HttpEngine) {
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        query: indexmap! {"mode".into() => "{{ mode }}".into()},
+        headers: indexmap! {"Accept".into() => "application/json".into()},
+        body: Some("{\"group_id\":\"{{ group_id }}\"}".into()),
+        authentication: Some(
+            Authentication::Bearer {
+                token: "{{ user_id }}".into(),
+            }
+            .into(),
+        ),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+
+    let seed = seed(
+        &context,
+        BuildOptions {
+            // Template syntax in the URL should *not* be rendered
+            link: Some("http://localhost/users/2?x={{y}}".parse().unwrap()),
+            ..Default::default()
+        },
+    );
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+
+    let request = &ticket.request;
+    assert_eq!(request.method(), reqwest::Method::GET);
+    assert_eq!(request.url().as_str(), "http://localhost/users/2?x={{y}}");
+    assert_eq!(
+        request.headers(),
+        &header_map([
+            ("user-agent", USER_AGENT),
+            ("authorization", "Bearer 1")
+        ])
+    );
+    assert!(request.body().is_none());
+}
+
 /// Test overriding authentication in BuildOptions
 #[rstest]
 #[case::basic(
@@ -589,7 +1470,7 @@ async fn test_override_authentication(
     #[case] expected_header: &str,
 ) {
     let recipe = Recipe {
-        authentication: recipe_auth,
+        authentication: recipe_auth.map(Into::into),
         ..Recipe::factory(())
     };
     let context = template_context(recipe, None);
@@ -648,6 +1529,7 @@ async fn test_override_headers(http_engine: HttpEngine) {
     assert_eq!(
         ticket.record.headers,
         header_map([
+            ("user-agent", USER_AGENT),
             ("accept", "application/json"),
             ("Big-Guy", "style2"),
             // It picked up the default content-type from the body,
@@ -752,6 +1634,102 @@ async fn test_override_body(
     assert_result(result, expected);
 }
 
+/// Test overriding the query and variables of a GraphQL body. The body
+/// override replaces just the query
+#[rstest]
+#[case::query(
+    Some("query { user(id: {{ user_id }}) { id } }".into()),
+    None,
+    r#"{"query":"query { user(id: 1) { id } }","variables":{"id":"3"}}"#,
+)]
+#[case::variables(
+    None,
+    Some(r#"{"id": "{{ user_id }}", "extra": true}"#.into()),
+    r#"{"query":"query { me { id } }","variables":{"id":"1","extra":true}}"#,
+)]
+#[tokio::test]
+async fn test_override_body_graphql(
+    http_engine: HttpEngine,
+    #[case] override_query: Option<Template>,
+    #[case] override_variables: Option<Template>,
+    #[case] expected: &str,
+) {
+    let recipe = Recipe {
+        body: Some(RecipeBody::Graphql(GraphQlBody {
+            query: "query { me { id } }".into(),
+            variables: Some(
+                json!({"id": "{{ group_id }}"}).try_into().unwrap(),
+            ),
+            operation_name: None,
+        })),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+
+    let seed = seed(
+        &context,
+        BuildOptions {
+            body: override_query,
+            graphql_variables: override_variables,
+            ..Default::default()
+        },
+    );
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let body = ticket
+        .request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .expect("Request body should be defined");
+    assert_eq!(std::str::from_utf8(body).unwrap(), expected);
+}
+
+/// Building for introspection replaces the whole GraphQL operation with the
+/// introspection query, including any overrides
+#[rstest]
+#[tokio::test]
+async fn test_graphql_introspection(http_engine: HttpEngine) {
+    let recipe = Recipe {
+        body: Some(RecipeBody::Graphql(GraphQlBody {
+            query: "query GetUser { me { id } }".into(),
+            variables: Some(json!({"id": "{{ user_id }}"}).try_into().unwrap()),
+            operation_name: Some("GetUser".into()),
+        })),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+
+    let introspection = seed(
+        &context,
+        BuildOptions {
+            body: Some("query { other }".into()),
+            graphql_introspection: true,
+            ..Default::default()
+        },
+    );
+    let ticket = http_engine.build(introspection, &context).await.unwrap();
+    let body = ticket
+        .request
+        .body()
+        .and_then(|body| body.as_bytes())
+        .expect("Request body should be defined");
+    let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+    assert_eq!(body, json!({"query": graphql::INTROSPECTION_QUERY}));
+
+    // Other bodies can't be introspected
+    let context = template_context(Recipe::factory(()), None);
+    let introspection = seed(
+        &context,
+        BuildOptions {
+            graphql_introspection: true,
+            ..Default::default()
+        },
+    );
+    assert_err(
+        http_engine.build(introspection, &context).await,
+        "Only recipes with a `graphql` body can load a GraphQL schema",
+    );
+}
+
 /// Test overriding form body fields. This has to be a separate test
 /// because it's incompatible with testing raw body overrides
 #[rstest]
@@ -796,10 +1774,10 @@ async fn test_override_body_form(http_engine: HttpEngine) {
             method: HttpMethod::Get,
             http_version: HttpVersion::Http11,
             url: "http://localhost/url".parse().unwrap(),
-            headers: header_map([(
-                "content-type",
-                "application/x-www-form-urlencoded"
-            )]),
+            headers: header_map([
+                ("user-agent", USER_AGENT),
+                ("content-type", "application/x-www-form-urlencoded"),
+            ]),
             body: Some(
                 b"user_id=1&preference=small&extra=extra".as_slice().into()
             ),
@@ -961,17 +1939,115 @@ async fn test_send_request(http_engine: HttpEngine) {
         *exchange.response,
         ResponseRecord {
             id: exchange.id,
+            http_version: HttpVersion::Http11,
+            certificate_fingerprint: None,
             status: StatusCode::OK,
             headers: header_map([
                 ("content-type", "text/plain"),
                 ("content-length", "6"),
                 ("date", date_header),
             ]),
-            body: ResponseBody::new(b"hello!".as_slice().into())
+            body: ResponseBody::new(b"hello!".as_slice().into()),
+            redirects: Vec::new(),
         }
     );
 }
 
+/// Test launching a built request and streaming the response body
+#[rstest]
+#[tokio::test]
+async fn test_send_request_streaming(http_engine: HttpEngine) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/get"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::OK).set_body_string("hello!"),
+        )
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        url: "{{ host }}/get".into(),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let mut status = None;
+    let mut streamed = Vec::new();
+    let exchange = ticket
+        .send_streaming(
+            |s, _| status = Some(s),
+            |chunk| {
+                streamed.extend_from_slice(chunk);
+                ControlFlow::Continue(())
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(status, Some(StatusCode::OK));
+    assert_eq!(streamed, b"hello!");
+    // The full body is retained in the exchange as well
+    assert_eq!(exchange.response.body.bytes().as_ref(), b"hello!");
+}
+
+/// Stop a response stream that never ends on its own. The exchange contains
+/// everything received up to that point
+#[rstest]
+#[tokio::test]
+async fn test_send_request_streaming_until(http_engine: HttpEngine) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+    // Send one event, then hold the connection open until the client leaves
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(socket.read_u8().await.unwrap());
+        }
+        socket
+            .write_all(
+                b"HTTP/1.1 200 OK\r\n\
+                content-type: text/event-stream\r\n\
+                transfer-encoding: chunked\r\n\r\n\
+                9\r\ndata: 1\n\n\r\n",
+            )
+            .await
+            .unwrap();
+        let _ = socket.read_to_end(&mut Vec::new()).await;
+    });
+
+    let recipe = Recipe {
+        url: "{{ host }}/events".into(),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let mut stop_tx = Some(stop_tx);
+    let exchange = ticket
+        .send_streaming_until(
+            stop_rx.map(|_| ()),
+            |_, _| {},
+            |_| {
+                // Stop after the first chunk
+                if let Some(stop_tx) = stop_tx.take() {
+                    let _ = stop_tx.send(());
+                }
+                ControlFlow::Continue(())
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(exchange.response.status, StatusCode::OK);
+    assert_eq!(exchange.response.body.bytes().as_ref(), b"data: 1\n\n");
+    server.await.unwrap();
+}
+
 /// Leading/trailing newlines should be stripped from rendered header
 /// values. These characters are invalid and trigger an error, so we assume
 /// they're unintentional and the user won't miss them.
@@ -1108,6 +2184,21 @@ async fn test_build_curl_complex_json(http_engine: HttpEngine) {
     Authentication::Bearer { token: "{{ token }}".into() },
     "--header 'authorization: Bearer tokenzzz'",
 )]
+#[case::digest(
+    Authentication::Digest {
+        username: "{{ username }}".into(),
+        password: "{{ password }}".into(),
+    },
+    "--digest --user 'user:hunter2'",
+)]
+#[case::ntlm(
+    Authentication::Ntlm {
+        username: "{{ username }}".into(),
+        password: "{{ password }}".into(),
+        domain: Some("CORP".into()),
+    },
+    "--ntlm --user 'CORP\\user:hunter2'",
+)]
 #[tokio::test]
 async fn test_build_curl_authentication(
     http_engine: HttpEngine,
@@ -1115,7 +2206,7 @@ async fn test_build_curl_authentication(
     #[case] expected_arguments: &str,
 ) {
     let recipe = Recipe {
-        authentication: Some(authentication),
+        authentication: Some(authentication.into()),
         ..Recipe::factory(())
     };
     let context = template_context(recipe, None);
@@ -1135,6 +2226,15 @@ async fn test_build_curl_authentication(
     RecipeBody::Stream("{{ file('data.json') }}".into()),
     "--data '@{ROOT}/data.json'",
 )]
+#[case::stream_encoded(
+    RecipeBody::Stream("{{ file('data.json', encoding='base64') }}".into()),
+    "--data 'eyAiYSI6IDEsICJiIjogMiB9'",
+)]
+#[case::file(
+    RecipeBody::File("data.json".into()),
+    "--header 'content-type: application/json' \\\n  \
+    --data-binary '@{ROOT}/data.json'",
+)]
 #[case::json(
     RecipeBody::json(json!({"group_id": "{{ group_id }}"})).unwrap(),
     "--json '{\n  \"group_id\": \"3\"\n}'"
@@ -1160,6 +2260,16 @@ async fn test_build_curl_authentication(
     }),
     "-F 'file=@{ROOT}/data.json'"
 )]
+#[case::form_multipart_file_headers(
+    RecipeBody::FormMultipart(indexmap! {
+        "file".into() => FormPart::Detailed {
+            value: "{{ file('data.json') }}".into(),
+            content_type: Some("text/plain".into()),
+            filename: Some("upload.txt".into()),
+        },
+    }),
+    "-F 'file=@{ROOT}/data.json;type=text/plain;filename=upload.txt'"
+)]
 #[case::form_multipart_command(
     RecipeBody::FormMultipart(indexmap! {
         "command".into() => "{{ command(['cat', 'data.json']) }}".into(),
@@ -1235,3 +2345,2332 @@ async fn test_follow_redirects(
 
     assert_eq!(exchange.response.status, expected_status);
 }
+
+/// Requests to hosts outside the allowlist are rejected before being sent
+#[rstest]
+#[case::allowed(&["*.example.com"], &[], None)]
+#[case::not_allowed(
+    &["localhost"],
+    &[],
+    Some("Host `api.example.com` is blocked"),
+)]
+#[case::denied(&[], &["api.*"], Some("Host `api.example.com` is blocked"))]
+#[tokio::test]
+async fn test_host_policy(
+    #[case] allowed_hosts: &[&str],
+    #[case] denied_hosts: &[&str],
+    #[case] expected_error: Option<&str>,
+) {
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        allowed_hosts: allowed_hosts.iter().map(|s| (*s).to_owned()).collect(),
+        denied_hosts: denied_hosts.iter().map(|s| (*s).to_owned()).collect(),
+        ..Default::default()
+    });
+    let recipe = Recipe {
+        url: "https://api.example.com/users".into(),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let result = http_engine.build(seed, &context).await;
+    if let Some(expected_error) = expected_error {
+        assert_err(result, expected_error);
+    } else {
+        result.unwrap();
+    }
+}
+
+/// Redirects to a blocked host are not followed
+#[rstest]
+#[tokio::test]
+async fn test_host_policy_redirect() {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    // Same server, but a different hostname
+    let blocked_url = host.replace("127.0.0.1", "localhost");
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/redirect"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::MOVED_PERMANENTLY)
+                .insert_header("Location", format!("{blocked_url}/get")),
+        )
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        allowed_hosts: vec!["127.0.0.1".to_owned()],
+        ..Default::default()
+    });
+    let recipe = Recipe {
+        url: "{{ host }}/redirect".into(),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    assert_err(ticket.send().await, "Host `localhost` is blocked");
+}
+
+/// Recipes can override how redirects are followed. 301/302/303 switch to a
+/// GET without a body; 307/308 resend the original request.
+#[rstest]
+#[case::default(HttpMethod::Get, "/moved", None, StatusCode::OK, &["/get"])]
+#[case::switch_method(
+    HttpMethod::Post,
+    "/moved",
+    None,
+    StatusCode::OK,
+    &["/get"],
+)]
+#[case::never(
+    HttpMethod::Get,
+    "/moved",
+    Some(RedirectPolicy { follow: false, ..RedirectPolicy::default() }),
+    StatusCode::MOVED_PERMANENTLY,
+    &[],
+)]
+#[case::resend_body(
+    HttpMethod::Post,
+    "/temporary",
+    None,
+    StatusCode::OK,
+    &["/post"],
+)]
+#[case::no_resend_body(
+    HttpMethod::Post,
+    "/temporary",
+    Some(RedirectPolicy { resend_body: false, ..RedirectPolicy::default() }),
+    StatusCode::TEMPORARY_REDIRECT,
+    &[],
+)]
+#[tokio::test]
+async fn test_redirect_policy(
+    #[case] method: HttpMethod,
+    #[case] path: &str,
+    #[case] redirect: Option<RedirectPolicy>,
+    #[case] expected_status: StatusCode,
+    #[case] expected_redirects: &[&str],
+) {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/get"))
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .mount(&server)
+        .await;
+    Mock::given(matchers::method("POST"))
+        .and(matchers::path("/post"))
+        .and(matchers::body_string("hello"))
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .mount(&server)
+        .await;
+    Mock::given(matchers::path("/moved"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::MOVED_PERMANENTLY)
+                .insert_header("Location", "/get"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(matchers::path("/temporary"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::TEMPORARY_REDIRECT)
+                .insert_header("Location", "/post"),
+        )
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::default();
+    let recipe = Recipe {
+        method,
+        url: format!("{{{{ host }}}}{path}").parse().unwrap(),
+        body: (method == HttpMethod::Post).then(|| "hello".into()),
+        redirect,
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+
+    assert_eq!(exchange.response.status, expected_status);
+    let redirects = exchange
+        .response
+        .redirects
+        .iter()
+        .map(|redirect| redirect.location.path())
+        .collect::<Vec<_>>();
+    assert_eq!(redirects, expected_redirects);
+}
+
+/// Following more redirects than the policy allows is an error
+#[rstest]
+#[tokio::test]
+async fn test_redirect_limit() {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::path("/loop"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::FOUND)
+                .insert_header("Location", "/loop"),
+        )
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::default();
+    let recipe = Recipe {
+        url: "{{ host }}/loop".into(),
+        redirect: Some(RedirectPolicy {
+            max_redirects: 2,
+            ..RedirectPolicy::default()
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    assert_err(ticket.send().await, "Too many redirects; stopped after 2");
+}
+
+/// Credentials are removed when redirected to another origin, unless the
+/// policy says otherwise
+#[rstest]
+#[case::strip(true, StatusCode::NOT_FOUND)]
+#[case::keep(false, StatusCode::OK)]
+#[tokio::test]
+async fn test_redirect_strip_auth(
+    #[case] strip_auth: bool,
+    #[case] expected_status: StatusCode,
+) {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    // Same server, but a different origin
+    let other_origin = host.replace("127.0.0.1", "localhost");
+    Mock::given(matchers::path("/redirect"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::FOUND)
+                .insert_header("Location", format!("{other_origin}/get")),
+        )
+        .mount(&server)
+        .await;
+    // Anything without credentials gets a 404
+    Mock::given(matchers::path("/get"))
+        .and(matchers::header("Authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::default();
+    let recipe = Recipe {
+        url: "{{ host }}/redirect".into(),
+        authentication: Some(
+            Authentication::Bearer {
+                token: "token".into(),
+            }
+            .into(),
+        ),
+        redirect: Some(RedirectPolicy {
+            strip_auth,
+            ..RedirectPolicy::default()
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, expected_status);
+}
+
+/// The total timeout can be set globally or per-recipe, with the recipe
+/// winning. A request that runs out of time reports the phase that timed out
+#[rstest]
+#[case::global(Some(100), None, true)]
+#[case::recipe(None, Some(100), true)]
+#[case::recipe_overrides(Some(100), Some(5000), false)]
+#[tokio::test]
+async fn test_timeout_total(
+    #[case] global_ms: Option<u64>,
+    #[case] recipe_ms: Option<u64>,
+    #[case] expect_timeout: bool,
+) {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::path("/slow"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::OK)
+                .set_delay(Duration::from_millis(500)),
+        )
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        timeout: slumber_config::Timeout {
+            connect_ms: None,
+            total_ms: global_ms,
+        },
+        ..Default::default()
+    });
+    let recipe = Recipe {
+        url: "{{ host }}/slow".into(),
+        timeout: recipe_ms.map(|total_ms| Timeout {
+            connect_ms: None,
+            total_ms: Some(total_ms),
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let result = ticket.send().await;
+    if expect_timeout {
+        let error = result.unwrap_err();
+        let timeout = error.error.0.downcast_ref::<TimeoutError>().unwrap();
+        assert_eq!(timeout.phase, TimeoutPhase::Total);
+        assert_eq!(timeout.timeout, Duration::from_millis(100));
+        assert_err(
+            Err::<(), _>(error),
+            "Timed out after 100ms waiting for the response to complete",
+        );
+    } else {
+        assert_eq!(result.unwrap().response.status, StatusCode::OK);
+    }
+}
+
+/// Throttled requests still send and receive the whole body, including the
+/// original `Content-Length`, but take as long as the rates dictate
+#[rstest]
+#[tokio::test]
+async fn test_throttle() {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::path("/upload"))
+        .and(matchers::header("Content-Length", "1000"))
+        .and(matchers::body_bytes(vec![b'x'; 1000]))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::OK)
+                .set_body_bytes(vec![b'y'; 2000]),
+        )
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::default();
+    // 80 kbps is 10,000 bytes per second
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        url: "{{ host }}/upload".into(),
+        body: Some(RecipeBody::Raw(Template::raw("x".repeat(1000)))),
+        throttle: Some(Throttle {
+            download_kbps: Some(80),
+            upload_kbps: Some(80),
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+
+    let start = Instant::now();
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+    assert_eq!(exchange.response.body.bytes().as_ref(), &[b'y'; 2000]);
+    assert!(start.elapsed() >= Duration::from_millis(300));
+}
+
+/// `Accept-Encoding` is sent from the recipe's `compression`, and a gzipped
+/// response is decoded unless decompression is disabled
+#[rstest]
+#[case::decompress(true)]
+#[case::raw(false)]
+#[tokio::test]
+async fn test_compression(#[case] decompress: bool) {
+    use flate2::{Compression as Level, write::GzEncoder};
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Level::default());
+    encoder.write_all(b"hello!").unwrap();
+    let compressed = encoder.finish().unwrap();
+
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::path("/compressed"))
+        .and(matchers::headers(
+            "Accept-Encoding",
+            vec!["gzip", "identity"],
+        ))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::OK)
+                .insert_header("Content-Encoding", "gzip")
+                .set_body_bytes(compressed.clone()),
+        )
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::default();
+    let recipe = Recipe {
+        url: "{{ host }}/compressed".into(),
+        compression: Some(Compression {
+            accept: vec![ContentEncoding::Gzip, ContentEncoding::Identity],
+            decompress,
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    let response = &exchange.response;
+    assert_eq!(response.status, StatusCode::OK);
+    // The encoding is visible either way
+    assert_eq!(response.headers[header::CONTENT_ENCODING], "gzip");
+    if decompress {
+        assert_eq!(response.body.bytes().as_ref(), b"hello!");
+        assert_eq!(response.headers.get(header::CONTENT_LENGTH), None);
+    } else {
+        assert_eq!(response.body.bytes().as_ref(), compressed.as_slice());
+    }
+}
+
+/// The recipe's `protocol` beats the profile's, and the negotiated version is
+/// recorded on the response
+#[rstest]
+#[case::auto(None, None, HttpVersion::Http11)]
+#[case::profile(Some(HttpProtocol::Http2), None, HttpVersion::Http2)]
+#[case::recipe(None, Some(HttpProtocol::Http2), HttpVersion::Http2)]
+#[case::recipe_beats_profile(
+    Some(HttpProtocol::Http2),
+    Some(HttpProtocol::Http1),
+    HttpVersion::Http11
+)]
+#[tokio::test]
+async fn test_protocol(
+    http_engine: HttpEngine,
+    #[case] profile_protocol: Option<HttpProtocol>,
+    #[case] recipe_protocol: Option<HttpProtocol>,
+    #[case] expected: HttpVersion,
+) {
+    // The mock server accepts both HTTP/1.1 and HTTP/2 over plaintext
+    let server = MockServer::start().await;
+    Mock::given(matchers::path("/url"))
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .mount(&server)
+        .await;
+
+    let profile = Profile {
+        data: indexmap! { "host".into() => server.uri().parse().unwrap() },
+        protocol: profile_protocol,
+        ..Profile::factory(())
+    };
+    let recipe = Recipe {
+        url: "{{ host }}/url".into(),
+        protocol: recipe_protocol,
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext::factory((by_id([profile]), by_id([recipe])));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+    assert_eq!(exchange.response.http_version, expected);
+}
+
+/// Requests are sent over the Unix socket from the recipe, falling back to the
+/// profile. The URL's host isn't resolved, but its path and query are sent.
+#[cfg(unix)]
+#[rstest]
+#[case::profile(None, Some("api.sock"))]
+#[case::recipe(Some("api.sock"), None)]
+#[case::recipe_beats_profile(Some("api.sock"), Some("missing.sock"))]
+#[tokio::test]
+async fn test_socket(
+    http_engine: HttpEngine,
+    temp_dir: TempDir,
+    #[case] recipe_socket: Option<&str>,
+    #[case] profile_socket: Option<&str>,
+) {
+    let listener =
+        tokio::net::UnixListener::bind(temp_dir.join("api.sock")).unwrap();
+    // Echo the request line back so we can see what was sent
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(socket.read_u8().await.unwrap());
+        }
+        let request_line = String::from_utf8(request)
+            .unwrap()
+            .lines()
+            .next()
+            .unwrap()
+            .to_owned();
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{request_line}",
+            request_line.len()
+        );
+        socket.write_all(response.as_bytes()).await.unwrap();
+    });
+
+    let socket_template =
+        |path: Option<&str>| path.map(|path| Template::raw(path.to_owned()));
+    let profile = Profile {
+        socket: socket_template(profile_socket),
+        ..Profile::factory(())
+    };
+    let recipe = Recipe {
+        url: "http://docker/v1.43/containers/json?all=true".into(),
+        socket: socket_template(recipe_socket),
+        ..Recipe::factory(())
+    };
+    let mut context =
+        TemplateContext::factory((by_id([profile]), by_id([recipe])));
+    // Paths are relative to the collection directory
+    context.root_dir = temp_dir.to_path_buf();
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    server.await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+    assert_eq!(
+        exchange.response.body.bytes().as_ref(),
+        b"GET /v1.43/containers/json?all=true HTTP/1.1"
+    );
+}
+
+/// Unix sockets can't be used with a non-reqwest transport
+#[rstest]
+#[tokio::test]
+async fn test_socket_transport(http_engine: HttpEngine) {
+    let http_engine = http_engine
+        .with_transport("mock", MockTransport::new(StatusCode::OK, ""));
+    let recipe = Recipe {
+        transport: Some("mock".into()),
+        socket: Some("/var/run/docker.sock".into()),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    assert_err(
+        http_engine.build(seed, &context).await,
+        "Unix sockets require the `reqwest` transport, not `mock`",
+    );
+}
+
+/// Sockets are disabled in untrusted collections, because they reach local
+/// services that `allowed_hosts` can't restrict
+#[rstest]
+#[case::recipe(Some("/var/run/docker.sock"), None)]
+#[case::profile(None, Some("/var/run/docker.sock"))]
+#[tokio::test]
+async fn test_socket_sandboxed(
+    http_engine: HttpEngine,
+    #[case] recipe_socket: Option<&str>,
+    #[case] profile_socket: Option<&str>,
+) {
+    let socket_template =
+        |path: Option<&str>| path.map(|path| Template::raw(path.to_owned()));
+    let profile = Profile {
+        socket: socket_template(profile_socket),
+        ..Profile::factory(())
+    };
+    let recipe = Recipe {
+        socket: socket_template(recipe_socket),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext {
+        sandboxed: true,
+        ..TemplateContext::factory((by_id([profile]), by_id([recipe])))
+    };
+
+    assert_err(
+        http_engine
+            .build(seed(&context, BuildOptions::default()), &context)
+            .await,
+        "`socket` is disabled because this collection is not trusted",
+    );
+    assert_err(
+        http_engine
+            .build_curl(seed(&context, BuildOptions::default()), &context)
+            .await,
+        "`socket` is disabled because this collection is not trusted",
+    );
+}
+
+/// The socket path is passed to curl
+#[rstest]
+#[tokio::test]
+async fn test_build_curl_socket(http_engine: HttpEngine) {
+    let recipe = Recipe {
+        socket: Some("/var/run/docker.sock".into()),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let command = http_engine.build_curl(seed, &context).await.unwrap();
+    assert_eq!(
+        command,
+        r"curl -XGET --url 'http://localhost/url' \
+  --unix-socket '/var/run/docker.sock'"
+    );
+}
+
+/// The profile's DNS overrides send requests to a fixed address. Hostnames
+/// are case-insensitive.
+#[rstest]
+#[tokio::test]
+async fn test_resolve(http_engine: HttpEngine) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::path("/url"))
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .mount(&server)
+        .await;
+    let address = server.address();
+
+    let profile = Profile {
+        resolve: indexmap! {"API.staging.test".into() => address.ip()},
+        ..Profile::factory(())
+    };
+    let recipe = Recipe {
+        url: format!("http://api.staging.test:{}/url", address.port())
+            .parse()
+            .unwrap(),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext::factory((by_id([profile]), by_id([recipe])));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+}
+
+/// The addresses in DNS overrides are checked against the host policy, so an
+/// allowed hostname can't be pointed somewhere else
+#[rstest]
+#[case::allowed(&["api.example.com", "10.0.0.5"], &[], None)]
+#[case::not_allowed(
+    &["api.example.com"],
+    &[],
+    Some("Host `10.0.0.5` is blocked"),
+)]
+#[case::denied(&[], &["10.*"], Some("Host `10.0.0.5` is blocked"))]
+#[tokio::test]
+async fn test_resolve_host_policy(
+    #[case] allowed_hosts: &[&str],
+    #[case] denied_hosts: &[&str],
+    #[case] expected_error: Option<&str>,
+) {
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        allowed_hosts: allowed_hosts.iter().map(|s| (*s).to_owned()).collect(),
+        denied_hosts: denied_hosts.iter().map(|s| (*s).to_owned()).collect(),
+        ..Default::default()
+    });
+    let profile = Profile {
+        resolve: indexmap! {"api.example.com".into() => "10.0.0.5".parse().unwrap()},
+        ..Profile::factory(())
+    };
+    let recipe = Recipe {
+        url: "https://api.example.com/users".into(),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext::factory((by_id([profile]), by_id([recipe])));
+
+    let result = http_engine
+        .build(seed(&context, BuildOptions::default()), &context)
+        .await;
+    let curl_result = http_engine
+        .build_curl(seed(&context, BuildOptions::default()), &context)
+        .await;
+    if let Some(expected_error) = expected_error {
+        assert_err(result, expected_error);
+        assert_err(curl_result, expected_error);
+    } else {
+        result.unwrap();
+        curl_result.unwrap();
+    }
+}
+
+/// DNS overrides are disabled in untrusted collections
+#[rstest]
+#[tokio::test]
+async fn test_resolve_sandboxed(http_engine: HttpEngine) {
+    let profile = Profile {
+        resolve: indexmap! {"localhost".into() => "10.0.0.5".parse().unwrap()},
+        ..Profile::factory(())
+    };
+    let context = TemplateContext {
+        sandboxed: true,
+        ..TemplateContext::factory((
+            by_id([profile]),
+            by_id([Recipe::factory(())]),
+        ))
+    };
+
+    assert_err(
+        http_engine
+            .build(seed(&context, BuildOptions::default()), &context)
+            .await,
+        "`resolve` is disabled because this collection is not trusted",
+    );
+}
+
+/// curl gets a `--resolve` for the URL's host, with the URL's port
+#[rstest]
+#[tokio::test]
+async fn test_build_curl_resolve(http_engine: HttpEngine) {
+    let profile = Profile {
+        resolve: indexmap! {
+            "localhost".into() => "10.0.0.5".parse().unwrap(),
+            "other.test".into() => "10.0.0.6".parse().unwrap(),
+        },
+        ..Profile::factory(())
+    };
+    let recipe = Recipe {
+        url: "http://localhost/url".into(),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext::factory((by_id([profile]), by_id([recipe])));
+    let seed = seed(&context, BuildOptions::default());
+
+    let command = http_engine.build_curl(seed, &context).await.unwrap();
+    assert_eq!(
+        command,
+        r"curl -XGET --url 'http://localhost/url' \
+  --resolve 'localhost:80:10.0.0.5'"
+    );
+}
+
+/// Cookies set by a response are sent with later requests, including the
+/// next hop of a redirect. A recipe's own Cookie header beats the jar.
+#[rstest]
+#[case::jar(None, "session=abc")]
+#[case::explicit(Some("session=mine"), "session=mine")]
+#[tokio::test]
+async fn test_cookie_jar(
+    http_engine: HttpEngine,
+    #[case] cookie_header: Option<&str>,
+    #[case] expected_cookie: &str,
+) {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::path("/login"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::SEE_OTHER)
+                .insert_header("Location", "/home")
+                .insert_header("Set-Cookie", "session=abc; Path=/; HttpOnly"),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(matchers::path("/home"))
+        .and(matchers::header("Cookie", expected_cookie))
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        url: "{{ host }}/login".into(),
+        headers: cookie_header
+            .map(|value| ("Cookie".to_owned(), value.parse().unwrap()))
+            .into_iter()
+            .collect(),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let ticket = http_engine
+        .build(seed(&context, BuildOptions::default()), &context)
+        .await
+        .unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+    assert_eq!(
+        context.cookies.header(&format!("{host}/").parse().unwrap()),
+        Some(header::HeaderValue::from_static("session=abc"))
+    );
+
+    // The next request carries the cookie from the start, and records it
+    let ticket = http_engine
+        .build(
+            seed(
+                &context,
+                BuildOptions {
+                    url: Some(format!("{host}/home").parse().unwrap()),
+                    ..BuildOptions::default()
+                },
+            ),
+            &context,
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        ticket.record().headers.get(header::COOKIE).unwrap(),
+        expected_cookie
+    );
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+}
+
+/// Requests are sent over a Windows named pipe when the socket path is one
+#[cfg(windows)]
+#[rstest]
+#[tokio::test]
+async fn test_socket_named_pipe(http_engine: HttpEngine) {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let pipe_name = format!(r"\\.\pipe\slumber-test-{}", uuid::Uuid::new_v4());
+    let mut pipe = ServerOptions::new().create(&pipe_name).unwrap();
+    let server = tokio::spawn(async move {
+        pipe.connect().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(pipe.read_u8().await.unwrap());
+        }
+        pipe.write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\npipe")
+            .await
+            .unwrap();
+    });
+
+    let recipe = Recipe {
+        url: "http://docker/v1.43/containers/json".into(),
+        socket: Some(Template::raw(pipe_name)),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    server.await.unwrap();
+    assert_eq!(exchange.response.body.bytes().as_ref(), b"pipe");
+}
+
+/// Named pipes are rejected outside Windows, instead of being treated as a
+/// relative Unix socket path
+#[cfg(unix)]
+#[rstest]
+#[case::lowercase(r"\\.\pipe\docker_engine")]
+#[case::uppercase(r"\\.\PIPE\docker_engine")]
+#[tokio::test]
+async fn test_socket_named_pipe_unsupported(
+    http_engine: HttpEngine,
+    #[case] socket: &str,
+) {
+    let recipe = Recipe {
+        socket: Some(Template::raw(socket.to_owned())),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    assert_err(
+        http_engine.build(seed, &context).await,
+        "Named pipes are only supported on Windows",
+    );
+}
+
+/// curl can't connect to named pipes, so they're left out of the command
+#[rstest]
+#[tokio::test]
+async fn test_build_curl_named_pipe(http_engine: HttpEngine) {
+    let recipe = Recipe {
+        socket: Some(Template::raw(r"\\.\pipe\docker_engine".to_owned())),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let command = http_engine.build_curl(seed, &context).await.unwrap();
+    assert_eq!(command, "curl -XGET --url 'http://localhost/url'");
+}
+
+/// Downloading streams the body into a file instead of the exchange, and
+/// reports progress against `Content-Length` along the way
+#[rstest]
+#[tokio::test]
+async fn test_download(temp_dir: TempDir) {
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::path("/artifact"))
+        .respond_with(
+            ResponseTemplate::new(StatusCode::OK)
+                .set_body_bytes(vec![b'z'; 5000]),
+        )
+        .mount(&server)
+        .await;
+
+    let http_engine = HttpEngine::default();
+    let recipe = Recipe {
+        url: "{{ host }}/artifact".into(),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+    let path = temp_dir.join("artifact.bin");
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let mut status = None;
+    let mut progress = Vec::new();
+    let exchange = ticket
+        .download(&path, |s, _| status = Some(s), |p| progress.push(p))
+        .await
+        .unwrap();
+    assert_eq!(status, Some(StatusCode::OK));
+    assert_eq!(exchange.response.status, StatusCode::OK);
+    assert!(exchange.response.body.bytes().is_empty());
+    assert_eq!(fs::read(&path).unwrap(), vec![b'z'; 5000]);
+    assert_eq!(
+        progress.first(),
+        Some(&DownloadProgress {
+            received: 0,
+            total: Some(5000)
+        })
+    );
+    assert_eq!(
+        progress.last(),
+        Some(&DownloadProgress {
+            received: 5000,
+            total: Some(5000)
+        })
+    );
+}
+
+/// User-Agent can be set globally or per-recipe, or omitted entirely. An
+/// explicit header takes precedence over both.
+#[rstest]
+#[case::default(None, None, None, Some(USER_AGENT))]
+#[case::global(Some("global/{{ mode }}"), None, None, Some("global/sudo"))]
+#[case::recipe(Some("global"), Some("recipe"), None, Some("recipe"))]
+#[case::omit(Some("global"), Some(""), None, None)]
+#[case::header(None, Some("recipe"), Some("header"), Some("header"))]
+#[tokio::test]
+async fn test_user_agent(
+    #[case] global: Option<&str>,
+    #[case] recipe_user_agent: Option<&str>,
+    #[case] explicit_header: Option<&str>,
+    #[case] expected: Option<&str>,
+) {
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        user_agent: global.map(|template| template.parse().unwrap()),
+        ..Default::default()
+    });
+    let recipe = Recipe {
+        user_agent: recipe_user_agent.map(|template| template.parse().unwrap()),
+        headers: explicit_header
+            .map(|value| ("user-agent".to_owned(), value.parse().unwrap()))
+            .into_iter()
+            .collect(),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let user_agent = ticket
+        .record
+        .headers
+        .get(header::USER_AGENT)
+        .map(|value| value.to_str().unwrap());
+    assert_eq!(user_agent, expected);
+}
+
+/// cURL commands only include User-Agent if it's customized. An empty value
+/// tells curl to omit the header
+#[rstest]
+#[case::default(None, r"curl -XGET --url 'http://localhost/url'")]
+#[case::custom(
+    Some("custom"),
+    r"curl -XGET --url 'http://localhost/url' \
+  --header 'user-agent: custom'"
+)]
+#[case::omit(
+    Some(""),
+    r"curl -XGET --url 'http://localhost/url' \
+  --header 'user-agent: '"
+)]
+#[tokio::test]
+async fn test_build_curl_user_agent(
+    http_engine: HttpEngine,
+    #[case] user_agent: Option<&str>,
+    #[case] expected: &str,
+) {
+    let recipe = Recipe {
+        user_agent: user_agent.map(|template| template.parse().unwrap()),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let command = http_engine.build_curl(seed, &context).await.unwrap();
+    assert_eq!(command, expected);
+}
+
+/// Requests are sent by the transport selected by the recipe, falling back to
+/// the one selected in the config
+#[rstest]
+#[case::default(None, None, false)]
+#[case::config(Some("mock"), None, true)]
+#[case::recipe(None, Some("mock"), true)]
+#[case::recipe_overrides_config(Some("mock"), Some("reqwest"), false)]
+#[tokio::test]
+async fn test_transport(
+    #[case] config_transport: Option<&str>,
+    #[case] recipe_transport: Option<&str>,
+    #[case] expected_mock: bool,
+) {
+    // Real requests go to a mock server, so we can tell which transport sent
+    // each one
+    let server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(200).set_body_string("server"))
+        .mount(&server)
+        .await;
+    let transport = MockTransport::new(StatusCode::CREATED, "mock");
+    let mut config = HttpEngineConfig::default();
+    if let Some(name) = config_transport {
+        config.transport = name.to_owned();
+    }
+    let http_engine =
+        HttpEngine::new(&config).with_transport("mock", transport.clone());
+    let recipe = Recipe {
+        url: "{{ host }}/users".into(),
+        transport: recipe_transport.map(String::from),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    if expected_mock {
+        assert_eq!(exchange.response.status, StatusCode::CREATED);
+        assert_eq!(exchange.response.body.bytes().as_ref(), b"mock");
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].url().as_str(),
+            format!("{}/users", server.uri())
+        );
+    } else {
+        assert_eq!(exchange.response.status, StatusCode::OK);
+        assert_eq!(exchange.response.body.bytes().as_ref(), b"server");
+        assert!(transport.requests().is_empty());
+    }
+}
+
+/// Selecting a transport that isn't registered is a build error
+#[rstest]
+#[tokio::test]
+async fn test_transport_unknown(http_engine: HttpEngine) {
+    let http_engine = http_engine
+        .with_transport("mock", MockTransport::new(StatusCode::OK, ""));
+    let recipe = Recipe {
+        transport: Some("fake".into()),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    assert_err(
+        http_engine.build(seed, &context).await,
+        "Unknown transport `fake`; options are: mock, reqwest",
+    );
+}
+
+/// The certificate for a request comes from the recipe, then the profile, then
+/// the config. Check which one was picked via the generated curl command.
+#[rstest]
+#[case::none(None, None, None, None)]
+#[case::config(
+    None,
+    None,
+    Some(("localhost", "~/client.pem")),
+    Some("--cert '{home_certificate}'"),
+)]
+#[case::config_other_host(
+    None,
+    None,
+    Some(("other", "/client.pem")),
+    None,
+)]
+#[case::profile(
+    None,
+    Some("profile.pem"),
+    Some(("localhost", "/client.pem")),
+    Some("--cert '{root}/profile.pem'"),
+)]
+#[case::recipe(
+    Some("recipe.pem"),
+    Some("profile.pem"),
+    Some(("localhost", "/client.pem")),
+    Some("--cert '{root}/recipe.pem'"),
+)]
+#[case::pkcs12(
+    Some("recipe.p12"),
+    None,
+    None,
+    Some("--cert '{root}/recipe.p12' --cert-type P12")
+)]
+#[tokio::test]
+async fn test_client_certificate_precedence(
+    #[case] recipe_certificate: Option<&str>,
+    #[case] profile_certificate: Option<&str>,
+    #[case] config_certificate: Option<(&str, &str)>,
+    #[case] expected: Option<&str>,
+) {
+    let certificate = |path: &str| collection::ClientCertificate {
+        certificate: path.into(),
+        key: None,
+        password: None,
+    };
+    let mut config = HttpEngineConfig::default();
+    if let Some((host, path)) = config_certificate {
+        config.client_certificates.insert(
+            host.into(),
+            slumber_config::ClientCertificate {
+                certificate: path.into(),
+                key: None,
+                password: None,
+            },
+        );
+    }
+    let http_engine = HttpEngine::new(&config);
+    let recipe = Recipe {
+        certificate: recipe_certificate.map(certificate),
+        ..Recipe::factory(())
+    };
+    let mut context = template_context(recipe, None);
+    for profile in Arc::get_mut(&mut context.collection)
+        .unwrap()
+        .profiles
+        .values_mut()
+    {
+        profile.certificate = profile_certificate.map(certificate);
+    }
+    let seed = seed(&context, BuildOptions::default());
+
+    let command = http_engine.build_curl(seed, &context).await.unwrap();
+    let expected = expected.map(|expected| {
+        expected
+            .replace(
+                "{home_certificate}",
+                expand_home(Path::new("~/client.pem")).to_str().unwrap(),
+            )
+            .replace("{root}", test_data_dir().to_str().unwrap())
+    });
+    let flags = command
+        .lines()
+        .map(|line| line.trim().trim_end_matches(" \\"))
+        .filter(|line| line.starts_with("--cert"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    assert_eq!(flags, expected.unwrap_or_default());
+}
+
+/// Present a client certificate to a server that requires one (mutual TLS).
+/// Without the certificate, the server rejects the handshake.
+#[rstest]
+#[case::separate_key(Some(("client.pem", Some("client_key.pem"), None)))]
+#[case::combined(Some(("client_combined.pem", None, None)))]
+#[case::pkcs12(Some(("client.p12", None, Some("slumber"))))]
+#[case::none(None)]
+#[tokio::test]
+async fn test_client_certificate_handshake(
+    #[case] certificate: Option<(&str, Option<&str>, Option<&str>)>,
+) {
+    let tls_dir = test_data_dir().join("tls");
+    let (port, server) = tls_server(tls_acceptor(
+        &tls_dir,
+        "server.pem",
+        "server_key.pem",
+        true,
+    ))
+    .await;
+
+    // The server's certificate is self-signed
+    let mut config = HttpEngineConfig {
+        ignore_certificate_hosts: vec!["localhost".into()],
+        ..Default::default()
+    };
+    if let Some((certificate, key, password)) = certificate {
+        config.client_certificates.insert(
+            "localhost".into(),
+            slumber_config::ClientCertificate {
+                certificate: tls_dir.join(certificate).display().to_string(),
+                key: key.map(|key| tls_dir.join(key).display().to_string()),
+                password: password.map(String::from),
+            },
+        );
+    }
+    let http_engine = HttpEngine::new(&config);
+    let recipe = Recipe {
+        url: Template::raw(format!("https://localhost:{port}/")),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let result = ticket.send().await;
+    let server_result = server.await.unwrap();
+    if certificate.is_some() {
+        assert_eq!(result.unwrap().response.status, StatusCode::OK);
+        server_result.unwrap();
+    } else {
+        assert!(result.is_err());
+        assert_err(server_result, "peer sent no certificates");
+    }
+}
+
+/// Verify server certificates against the CAs in `ca_certificates`, and relax
+/// verification per host. `server_signed.pem` is for `localhost`, signed by
+/// `server_ca.pem`. `server.pem` is self-signed.
+#[rstest]
+#[case::ca_trusted("server_signed.pem", "localhost", true, None, Ok(()))]
+#[case::ca_untrusted(
+    "server_signed.pem",
+    "localhost",
+    false,
+    None,
+    Err("UnknownIssuer")
+)]
+#[case::hostname_mismatch(
+    "server_signed.pem",
+    "127.0.0.1",
+    true,
+    None,
+    Err("NotValidForName")
+)]
+#[case::hostname_accepted(
+    "server_signed.pem",
+    "127.0.0.1",
+    true,
+    Some(TlsHost {
+        danger_accept_invalid_hostnames: true,
+        ..TlsHost::default()
+    }),
+    Ok(()),
+)]
+// Ignoring the hostname doesn't mean ignoring the CA
+#[case::hostname_accepted_untrusted(
+    "server.pem",
+    "localhost",
+    true,
+    Some(TlsHost {
+        danger_accept_invalid_hostnames: true,
+        ..TlsHost::default()
+    }),
+    Err("InvalidCertificate"),
+)]
+#[case::invalid_accepted(
+    "server.pem",
+    "localhost",
+    false,
+    Some(TlsHost {
+        danger_accept_invalid_certs: true,
+        ..TlsHost::default()
+    }),
+    Ok(()),
+)]
+#[tokio::test]
+async fn test_tls_verification(
+    #[case] server_certificate: &str,
+    #[case] host: &str,
+    #[case] trust_ca: bool,
+    #[case] tls_host: Option<TlsHost>,
+    #[case] expected: Result<(), &str>,
+) {
+    let tls_dir = test_data_dir().join("tls");
+    let key = server_certificate.replace(".pem", "_key.pem");
+    let (port, server) =
+        tls_server(tls_acceptor(&tls_dir, server_certificate, &key, false))
+            .await;
+
+    let config = HttpEngineConfig {
+        ca_certificates: if trust_ca {
+            vec![tls_dir.join("server_ca.pem").display().to_string()]
+        } else {
+            vec![]
+        },
+        tls_hosts: tls_host
+            .map(|tls_host| (host.to_owned(), tls_host))
+            .into_iter()
+            .collect(),
+        ..Default::default()
+    };
+    let http_engine = HttpEngine::new(&config);
+    let recipe = Recipe {
+        url: Template::raw(format!("https://{host}:{port}/")),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let result = ticket.send().await;
+    match expected {
+        Ok(()) => {
+            assert_eq!(result.unwrap().response.status, StatusCode::OK);
+            server.await.unwrap().unwrap();
+        }
+        Err(expected) => {
+            // The error is buried in the source chain
+            let error = format!("{:?}", result.unwrap_err());
+            assert!(error.contains(expected), "{error}");
+            server.abort();
+        }
+    }
+}
+
+/// The fingerprint of the server's certificate is recorded with the response,
+/// so it can be checked against the profile's pinning
+#[rstest]
+#[tokio::test]
+async fn test_certificate_fingerprint() {
+    let tls_dir = test_data_dir().join("tls");
+    let (port, server) = tls_server(tls_acceptor(
+        &tls_dir,
+        "server_signed.pem",
+        "server_signed_key.pem",
+        false,
+    ))
+    .await;
+    let config = HttpEngineConfig {
+        ca_certificates: vec![
+            tls_dir.join("server_ca.pem").display().to_string(),
+        ],
+        ..Default::default()
+    };
+    let http_engine = HttpEngine::new(&config);
+    let recipe = Recipe {
+        url: Template::raw(format!("https://localhost:{port}/")),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    server.await.unwrap().unwrap();
+
+    let certificate =
+        CertificateDer::from_pem_file(tls_dir.join("server_signed.pem"))
+            .unwrap();
+    let expected =
+        aws_lc_rs::digest::digest(&aws_lc_rs::digest::SHA256, &certificate)
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .join(":");
+    assert_eq!(exchange.response.certificate_fingerprint, Some(expected));
+}
+
+/// Invalid TLS config is reported when building a request
+#[rstest]
+#[case::missing(&["tls/missing.pem"], false, "Reading CA certificate file")]
+#[case::empty(&["data.json"], false, "does not contain any PEM certificates")]
+#[case::no_ca(
+    &[],
+    true,
+    "`danger_accept_invalid_hostnames` for host `localhost` requires \
+    `ca_certificates` to be set",
+)]
+#[tokio::test]
+async fn test_tls_config_error(
+    #[case] ca_certificates: &[&str],
+    #[case] accept_invalid_hostnames: bool,
+    #[case] expected_error: &str,
+) {
+    let config = HttpEngineConfig {
+        ca_certificates: ca_certificates
+            .iter()
+            .map(|path| test_data_dir().join(path).display().to_string())
+            .collect(),
+        tls_hosts: indexmap! {
+            "localhost".into() => TlsHost {
+                danger_accept_invalid_hostnames: accept_invalid_hostnames,
+                ..TlsHost::default()
+            },
+        },
+        ..Default::default()
+    };
+    let http_engine = HttpEngine::new(&config);
+    let context = template_context(Recipe::factory(()), None);
+    let seed = seed(&context, BuildOptions::default());
+    assert_err(http_engine.build(seed, &context).await, expected_error);
+}
+
+/// Errors loading a client certificate are build errors
+#[rstest]
+#[case::missing("missing.pem", "Reading client certificate")]
+#[case::pkcs12_password("client.p12", "Invalid PKCS#12 client certificate")]
+#[case::pkcs12_invalid("client.pfx", "Reading client certificate")]
+#[case::invalid("../data.json", "Invalid client certificate")]
+#[case::no_key("client.pem", "Invalid client certificate")]
+#[tokio::test]
+async fn test_client_certificate_error(
+    http_engine: HttpEngine,
+    #[case] path: &str,
+    #[case] expected_error: &str,
+) {
+    let recipe = Recipe {
+        certificate: Some(collection::ClientCertificate {
+            certificate: format!("tls/{path}"),
+            key: None,
+            password: None,
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    assert_err(http_engine.build(seed, &context).await, expected_error);
+}
+
+/// Client certificates from a sandboxed collection are disabled, but those
+/// from the config are still presented
+#[rstest]
+#[tokio::test]
+async fn test_client_certificate_sandboxed() {
+    let mut config = HttpEngineConfig::default();
+    config.client_certificates.insert(
+        "localhost".into(),
+        slumber_config::ClientCertificate {
+            certificate: "/client.pem".into(),
+            key: None,
+            password: None,
+        },
+    );
+    let http_engine = HttpEngine::new(&config);
+    let recipe = Recipe {
+        certificate: Some(collection::ClientCertificate {
+            certificate: "tls/client_combined.pem".into(),
+            key: None,
+            password: None,
+        }),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext {
+        sandboxed: true,
+        ..template_context(recipe, None)
+    };
+
+    let build_seed = || seed(&context, BuildOptions::default());
+    assert_err(
+        http_engine.build(build_seed(), &context).await,
+        "`certificate` is disabled",
+    );
+    let build_seed = || seed(&context, BuildOptions::default());
+    assert_err(
+        http_engine.build_curl(build_seed(), &context).await,
+        "`certificate` is disabled",
+    );
+
+    // Config certificates are the user's, so they're allowed
+    let context = TemplateContext {
+        sandboxed: true,
+        ..template_context(Recipe::factory(()), None)
+    };
+    let build_seed = || seed(&context, BuildOptions::default());
+    let command = http_engine
+        .build_curl(build_seed(), &context)
+        .await
+        .unwrap();
+    assert!(command.contains("--cert '/client.pem'"), "{command}");
+}
+
+/// Client certificates can't be used with a non-reqwest transport
+#[rstest]
+#[tokio::test]
+async fn test_client_certificate_transport(http_engine: HttpEngine) {
+    let http_engine = http_engine
+        .with_transport("mock", MockTransport::new(StatusCode::OK, ""));
+    let recipe = Recipe {
+        transport: Some("mock".into()),
+        certificate: Some(collection::ClientCertificate {
+            certificate: "tls/client_combined.pem".into(),
+            key: None,
+            password: None,
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+
+    assert_err(
+        http_engine.build(seed, &context).await,
+        "Client certificates require the `reqwest` transport, not `mock`",
+    );
+}
+
+/// Requests are sent through the proxy from the profile, falling back to the
+/// config. A mock server stands in for the proxy; the target host doesn't
+/// exist, so the request can only succeed if it goes through the proxy.
+/// `{proxy}` is replaced with the proxy's URL, and `{closed}` with a URL that
+/// nothing listens on.
+#[rstest]
+#[case::config(Some("{proxy}"), None, &[], false)]
+#[case::profile(Some("{closed}"), Some("{{ proxy }}"), &[], false)]
+// An empty proxy disables the one from the config
+#[case::profile_empty(Some("{closed}"), Some(""), &[], true)]
+#[case::no_proxy(Some("{closed}"), None, &["127.0.0.1"], true)]
+#[tokio::test]
+async fn test_proxy(
+    #[case] config_proxy: Option<&str>,
+    #[case] profile_proxy: Option<&str>,
+    #[case] no_proxy: &[&str],
+    #[case] direct: bool,
+) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/fish"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let replace = |template: &str| -> Template {
+        template
+            .replace("{proxy}", &server.uri())
+            .replace("{closed}", "http://127.0.0.1:1")
+            .parse()
+            .unwrap()
+    };
+
+    let config = HttpEngineConfig {
+        proxy: config_proxy.map(replace),
+        no_proxy: no_proxy.iter().map(ToString::to_string).collect(),
+        ..Default::default()
+    };
+    let http_engine = HttpEngine::new(&config);
+    let url = if direct {
+        format!("{}/fish", server.uri())
+    } else {
+        "http://slumber.invalid/fish".into()
+    };
+    let recipe = Recipe {
+        url: Template::raw(url),
+        ..Recipe::factory(())
+    };
+    let mut context = template_context(recipe, None);
+    for profile in Arc::get_mut(&mut context.collection)
+        .unwrap()
+        .profiles
+        .values_mut()
+    {
+        profile
+            .data
+            .insert("proxy".into(), Template::raw(server.uri()));
+        profile.proxy = profile_proxy.map(replace);
+    }
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, StatusCode::OK);
+}
+
+/// Errors rendering or parsing the proxy URL fail the request
+#[rstest]
+#[case::invalid("not a url", "Invalid proxy URL `not a url`")]
+#[case::render("{{ fake_fn() }}", "Rendering proxy URL")]
+#[tokio::test]
+async fn test_proxy_error(#[case] proxy: &str, #[case] expected_error: &str) {
+    let config = HttpEngineConfig {
+        proxy: Some(proxy.parse().unwrap()),
+        ..Default::default()
+    };
+    let http_engine = HttpEngine::new(&config);
+    let context = template_context(Recipe::factory(()), None);
+    let seed = seed(&context, BuildOptions::default());
+
+    assert_err(http_engine.build(seed, &context).await, expected_error);
+}
+
+/// A proxy from the profile is subject to the host policy, because it's where
+/// the request really goes. The proxy from the config isn't.
+#[rstest]
+#[case::profile_allowed(None, Some("http://localhost:8080"), None)]
+#[case::profile_blocked(
+    None,
+    Some("http://evil.com:8080"),
+    Some("Host `evil.com` is blocked")
+)]
+#[case::profile_no_scheme(
+    None,
+    Some("evil.com:8080"),
+    Some("Host `evil.com` is blocked")
+)]
+#[case::config(Some("http://evil.com:8080"), None, None)]
+#[tokio::test]
+async fn test_proxy_host_policy(
+    #[case] config_proxy: Option<&'static str>,
+    #[case] profile_proxy: Option<&'static str>,
+    #[case] expected_error: Option<&'static str>,
+) {
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        proxy: config_proxy.map(Template::from),
+        allowed_hosts: vec!["localhost".to_owned()],
+        ..Default::default()
+    });
+    let profile = Profile {
+        proxy: profile_proxy.map(Template::from),
+        ..Profile::factory(())
+    };
+    let context = TemplateContext::factory((
+        by_id([profile]),
+        by_id([Recipe::factory(())]),
+    ));
+
+    let result = http_engine
+        .build(seed(&context, BuildOptions::default()), &context)
+        .await;
+    if let Some(expected_error) = expected_error {
+        assert_err(result, expected_error);
+    } else {
+        result.unwrap();
+    }
+}
+
+/// Proxies from the profile are disabled in untrusted collections. The proxy
+/// from the config is still used.
+#[rstest]
+#[case::config(Some("http://localhost:8080"), None, None)]
+#[case::profile(
+    None,
+    Some("http://localhost:8080"),
+    Some("`proxy` is disabled because this collection is not trusted")
+)]
+#[tokio::test]
+async fn test_proxy_sandboxed(
+    #[case] config_proxy: Option<&'static str>,
+    #[case] profile_proxy: Option<&'static str>,
+    #[case] expected_error: Option<&'static str>,
+) {
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        proxy: config_proxy.map(Template::from),
+        ..Default::default()
+    });
+    let profile = Profile {
+        proxy: profile_proxy.map(Template::from),
+        ..Profile::factory(())
+    };
+    let context = TemplateContext {
+        sandboxed: true,
+        ..TemplateContext::factory((
+            by_id([profile]),
+            by_id([Recipe::factory(())]),
+        ))
+    };
+
+    let result = http_engine
+        .build(seed(&context, BuildOptions::default()), &context)
+        .await;
+    let curl_result = http_engine
+        .build_curl(seed(&context, BuildOptions::default()), &context)
+        .await;
+    if let Some(expected_error) = expected_error {
+        assert_err(result, expected_error);
+        assert_err(curl_result, expected_error);
+    } else {
+        result.unwrap();
+        curl_result.unwrap();
+    }
+}
+
+/// cURL commands include the proxy and its exclusions
+#[rstest]
+#[case::proxy(&[], r"curl -XGET --url 'http://localhost/url' \
+  --proxy 'socks5://localhost:1080'")]
+#[case::no_proxy(&["localhost", ".internal"], r"curl -XGET --url 'http://localhost/url' \
+  --proxy 'socks5://localhost:1080' \
+  --noproxy 'localhost,.internal'")]
+#[tokio::test]
+async fn test_build_curl_proxy(
+    #[case] no_proxy: &[&str],
+    #[case] expected: &str,
+) {
+    let config = HttpEngineConfig {
+        proxy: Some("socks5://localhost:1080".into()),
+        no_proxy: no_proxy.iter().map(ToString::to_string).collect(),
+        ..Default::default()
+    };
+    let http_engine = HttpEngine::new(&config);
+    let context = template_context(Recipe::factory(()), None);
+    let seed = seed(&context, BuildOptions::default());
+
+    let command = http_engine.build_curl(seed, &context).await.unwrap();
+    assert_eq!(command, expected);
+}
+
+/// A profile's chaos settings can delay requests, or replace them with a
+/// dropped connection or synthetic error before they hit the transport
+#[rstest]
+#[case::none(Chaos::default(), Some(StatusCode::CREATED))]
+#[case::latency(
+    Chaos { latency_ms: 50, ..Chaos::default() },
+    Some(StatusCode::CREATED),
+)]
+#[case::drop(Chaos { drop_percent: 100, ..Chaos::default() }, None)]
+#[case::error(
+    Chaos { error_percent: 100, ..Chaos::default() },
+    Some(StatusCode::INTERNAL_SERVER_ERROR),
+)]
+#[tokio::test]
+async fn test_chaos(
+    #[case] chaos: Chaos,
+    #[case] expected_status: Option<StatusCode>,
+) {
+    let transport = MockTransport::new(StatusCode::CREATED, "mock");
+    let http_engine =
+        HttpEngine::default().with_transport("mock", transport.clone());
+    let recipe = Recipe {
+        transport: Some("mock".into()),
+        ..Recipe::factory(())
+    };
+    let mut context = template_context(recipe, None);
+    for profile in Arc::get_mut(&mut context.collection)
+        .unwrap()
+        .profiles
+        .values_mut()
+    {
+        profile.chaos = Some(chaos.clone());
+    }
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let result = ticket.send().await;
+    if let Some(status) = expected_status {
+        let exchange = result.unwrap();
+        assert_eq!(exchange.response.status, status);
+        assert!(
+            exchange.end_time - exchange.start_time
+                >= chrono::Duration::milliseconds(chaos.latency_ms as i64)
+        );
+    } else {
+        let error = result.unwrap_err();
+        assert!(error.error.0.downcast_ref::<ChaosError>().is_some());
+    }
+    // Only requests without a fault should reach the transport
+    let expected_requests =
+        usize::from(expected_status == Some(StatusCode::CREATED));
+    assert_eq!(transport.requests().len(), expected_requests);
+}
+
+/// Responses with a retryable status are retried, up to the max attempts
+#[rstest]
+#[case::retried(503, 1, 3, StatusCode::OK, 2)]
+#[case::not_retryable(500, 1, 3, StatusCode::INTERNAL_SERVER_ERROR, 1)]
+#[case::exhausted(503, 2, 2, StatusCode::SERVICE_UNAVAILABLE, 2)]
+#[tokio::test]
+async fn test_retry_status(
+    http_engine: HttpEngine,
+    #[case] failure_status: u16,
+    #[case] failures: u64,
+    #[case] max_attempts: u32,
+    #[case] expected_status: StatusCode,
+    #[case] expected_attempts: u32,
+) {
+    let server = MockServer::start().await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(failure_status))
+        .up_to_n_times(failures)
+        .mount(&server)
+        .await;
+    Mock::given(matchers::any())
+        .respond_with(ResponseTemplate::new(StatusCode::OK))
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        url: "{{ host }}/get".into(),
+        retry: Some(RetryPolicy {
+            max_attempts,
+            backoff_ms: 1,
+            ..RetryPolicy::default()
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&server.uri()));
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let progress = ticket.retry_progress().clone();
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(exchange.response.status, expected_status);
+    assert_eq!(progress.attempt(), expected_attempts);
+}
+
+/// Requests that fail without a response are retried if the policy allows it.
+/// The global policy applies to recipes that don't define their own.
+#[rstest]
+#[case::retried(true, 3)]
+#[case::not_retried(false, 1)]
+#[tokio::test]
+async fn test_retry_network_error(
+    #[case] network_errors: bool,
+    #[case] expected_attempts: u32,
+) {
+    let http_engine = HttpEngine::new(&HttpEngineConfig {
+        retry: Some(slumber_config::RetryPolicy {
+            network_errors,
+            backoff_ms: 1,
+            ..Default::default()
+        }),
+        ..HttpEngineConfig::default()
+    });
+    let mut context = template_context(Recipe::factory(()), None);
+    // Chaos drops every attempt before it hits the network
+    for profile in Arc::get_mut(&mut context.collection)
+        .unwrap()
+        .profiles
+        .values_mut()
+    {
+        profile.chaos = Some(Chaos {
+            drop_percent: 100,
+            ..Chaos::default()
+        });
+    }
+    let seed = seed(&context, BuildOptions::default());
+
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    let progress = ticket.retry_progress().clone();
+    let error = ticket.send().await.unwrap_err();
+    assert!(error.error.0.downcast_ref::<ChaosError>().is_some());
+    assert_eq!(progress.attempt(), expected_attempts);
+}
+
+/// Open a WebSocket connection. The recipe's message is sent once connected,
+/// and the server's messages make up the response body
+#[rstest]
+#[case::auto(None, Message::text("hello user"))]
+#[case::binary(
+    Some(WebSocketFrame::Binary),
+    Message::binary(b"hello user".to_vec()),
+)]
+#[tokio::test]
+async fn test_websocket(
+    http_engine: HttpEngine,
+    #[case] frame: Option<WebSocketFrame>,
+    #[case] expected: Message,
+) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("ws://{}", listener.local_addr().unwrap());
+    // Minimal server: accept the handshake, echo the first message back, then
+    // close the connection
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(socket.read_u8().await.unwrap());
+        }
+        let request = String::from_utf8(request).unwrap();
+        let key = request
+            .lines()
+            .filter_map(|line| line.split_once(": "))
+            .find(|(name, _)| name.eq_ignore_ascii_case("sec-websocket-key"))
+            .unwrap()
+            .1;
+        let accept = websocket::accept_key(key.as_bytes());
+        socket
+            .write_all(
+                format!(
+                    "HTTP/1.1 101 Switching Protocols\r\n\
+                    connection: upgrade\r\n\
+                    upgrade: websocket\r\n\
+                    sec-websocket-accept: {accept}\r\n\r\n"
+                )
+                .as_bytes(),
+            )
+            .await
+            .unwrap();
+
+        let mut socket =
+            WebSocketStream::from_raw_socket(socket, Role::Server, None).await;
+        let message = socket.next().await.unwrap().unwrap();
+        socket.send(message.clone()).await.unwrap();
+        socket.close(None).await.unwrap();
+        message
+    });
+
+    let recipe = Recipe {
+        url: "{{ host }}/chat".into(),
+        websocket: Some(WebSocket {
+            messages: vec!["hello {{ username }}".into()],
+            frame,
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    assert!(ticket.is_websocket());
+    // The record keeps the URL as written
+    assert_eq!(ticket.record().url.as_str(), format!("{host}/chat"));
+    assert_eq!(ticket.record().headers["upgrade"], "websocket");
+
+    let exchange = ticket.send().await.unwrap();
+    assert_eq!(server.await.unwrap(), expected);
+    assert_eq!(exchange.response.status, StatusCode::SWITCHING_PROTOCOLS);
+    assert_eq!(exchange.response.body.bytes().as_ref(), b"hello user\n");
+}
+
+/// WebSocket connections can only be opened with GET, whether the recipe
+/// defines `websocket` or it's forced by the build options
+#[rstest]
+#[case::recipe(Some(WebSocket::default()), false)]
+#[case::forced(None, true)]
+#[tokio::test]
+async fn test_websocket_method(
+    http_engine: HttpEngine,
+    #[case] websocket: Option<WebSocket>,
+    #[case] force: bool,
+) {
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        websocket,
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(
+        &context,
+        BuildOptions {
+            websocket: force,
+            ..BuildOptions::default()
+        },
+    );
+    assert_err(
+        http_engine.build(seed, &context).await,
+        "WebSocket requests must use `GET`, not `POST`",
+    );
+}
+
+/// Make a unary gRPC call. The schema comes from `.proto` files or server
+/// reflection. Servers that only implement the older reflection service
+/// should work too.
+#[rstest]
+#[case::proto_files(&["grpc/greeter.proto"], GrpcReflection::None)]
+#[case::reflection(&[], GrpcReflection::V1)]
+#[case::reflection_v1alpha(&[], GrpcReflection::V1Alpha)]
+#[tokio::test]
+async fn test_grpc(
+    http_engine: HttpEngine,
+    #[case] proto_files: &[&str],
+    #[case] reflection: GrpcReflection,
+) {
+    let host = grpc_server(reflection).await;
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        url: "{{ host }}".into(),
+        body: Some(
+            RecipeBody::json(json!({"name": "{{ username }}", "count": 1}))
+                .unwrap(),
+        ),
+        grpc: Some(Grpc {
+            method: "test.Greeter/SayHello".into(),
+            proto_files: proto_files
+                .iter()
+                .map(|&path| path.to_owned())
+                .collect(),
+            import_paths: vec![],
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    assert!(ticket.is_grpc());
+    // The record has the JSON body, as written
+    let record = ticket.record();
+    assert_eq!(record.url.as_str(), format!("{host}/test.Greeter/SayHello"));
+    assert_eq!(record.headers["content-type"], "application/grpc");
+    assert_eq!(record.content_type(), Some(ContentType::Json));
+    assert_eq!(
+        record.body(),
+        Some(br#"{"name":"user","count":1}"#.as_slice())
+    );
+
+    let exchange = ticket.send().await.unwrap();
+    let response = &exchange.response;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers["content-type"], "application/json");
+    assert_eq!(response.headers["grpc-status"], "0");
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(response.body.bytes())
+            .unwrap(),
+        json!({
+            "message": "Hello, user!",
+            "count": 2,
+            "time": "1970-01-01T00:00:01Z",
+        })
+    );
+}
+
+/// A call that fails with a gRPC status gets a response with the status as
+/// the body, not an error
+#[rstest]
+#[tokio::test]
+async fn test_grpc_status(http_engine: HttpEngine) {
+    let host = grpc_server(GrpcReflection::V1).await;
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        url: "{{ host }}".into(),
+        body: Some(RecipeBody::Raw(r#"{"name": "error"}"#.into())),
+        grpc: Some(Grpc {
+            method: "test.Greeter.SayHello".into(),
+            proto_files: vec![],
+            import_paths: vec![],
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+    let exchange = http_engine
+        .build(seed, &context)
+        .await
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    let response = &exchange.response;
+    assert_eq!(response.headers["grpc-status"], "5");
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(response.body.bytes())
+            .unwrap(),
+        json!({"code": "NOT_FOUND", "message": "No user named error"})
+    );
+}
+
+/// Errors resolving the method or encoding the request fail the request
+#[rstest]
+#[case::service_unknown(
+    "test.Nope/SayHello",
+    "{}",
+    "Service `test.Nope` not found; options are: test.Greeter"
+)]
+#[case::method_unknown(
+    "test.Greeter/Nope",
+    "{}",
+    "Method `Nope` not found in service `test.Greeter`; \
+    options are: SayHello, Chat"
+)]
+#[case::streaming(
+    "test.Greeter/Chat",
+    "{}",
+    "`test.Greeter.Chat` is a streaming method"
+)]
+#[case::unknown_field(
+    "test.Greeter/SayHello",
+    r#"{"nam": "user"}"#,
+    "Encoding request body as `test.HelloRequest`"
+)]
+#[tokio::test]
+async fn test_grpc_error(
+    http_engine: HttpEngine,
+    #[case] method: &str,
+    #[case] body: &'static str,
+    #[case] expected_error: &str,
+) {
+    let host = grpc_server(GrpcReflection::None).await;
+    let recipe = Recipe {
+        method: HttpMethod::Post,
+        url: "{{ host }}".into(),
+        body: Some(RecipeBody::Raw(body.into())),
+        grpc: Some(Grpc {
+            method: method.into(),
+            proto_files: vec!["grpc/greeter.proto".into()],
+            import_paths: vec![],
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, Some(&host));
+    let seed = seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(seed, &context).await.unwrap();
+    assert_err(ticket.send().await, expected_error);
+}
+
+/// Invalid gRPC recipes fail to build
+#[rstest]
+#[case::method(
+    HttpMethod::Get,
+    "test.Greeter/SayHello",
+    None,
+    "gRPC requests must use `POST`, not `GET`"
+)]
+#[case::method_name(
+    HttpMethod::Post,
+    "SayHello",
+    None,
+    "Invalid gRPC method `SayHello`; expected `package.Service/Method`"
+)]
+#[case::body_stream(
+    HttpMethod::Post,
+    "test.Greeter/SayHello",
+    Some(RecipeBody::Stream("{{ file('data.json') }}".into())),
+    "gRPC request bodies can't be streamed"
+)]
+#[tokio::test]
+async fn test_grpc_build_error(
+    http_engine: HttpEngine,
+    #[case] http_method: HttpMethod,
+    #[case] method: &str,
+    #[case] body: Option<RecipeBody>,
+    #[case] expected_error: &str,
+) {
+    let recipe = Recipe {
+        method: http_method,
+        body,
+        grpc: Some(Grpc {
+            method: method.into(),
+            proto_files: vec![],
+            import_paths: vec![],
+        }),
+        ..Recipe::factory(())
+    };
+    let context = template_context(recipe, None);
+    let seed = seed(&context, BuildOptions::default());
+    assert_err(http_engine.build(seed, &context).await, expected_error);
+}
+
+/// `.proto` files can't be read in a sandboxed collection. Reflection doesn't
+/// touch the file system, so it's still allowed.
+#[rstest]
+#[tokio::test]
+async fn test_grpc_sandboxed(http_engine: HttpEngine) {
+    let recipe = |proto_files: Vec<String>| Recipe {
+        method: HttpMethod::Post,
+        grpc: Some(Grpc {
+            method: "test.Greeter/SayHello".into(),
+            proto_files,
+            import_paths: vec!["grpc".into()],
+        }),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext {
+        sandboxed: true,
+        ..template_context(recipe(vec!["greeter.proto".into()]), None)
+    };
+    let build_seed = || seed(&context, BuildOptions::default());
+    assert_err(
+        http_engine.build(build_seed(), &context).await,
+        "`grpc.proto_files` is disabled",
+    );
+
+    let context = TemplateContext {
+        sandboxed: true,
+        ..template_context(recipe(vec![]), None)
+    };
+    let build_seed = || seed(&context, BuildOptions::default());
+    let ticket = http_engine.build(build_seed(), &context).await.unwrap();
+    assert!(ticket.is_grpc());
+}
+
+/// Which reflection service the test gRPC server implements
+#[derive(Copy, Clone, Debug)]
+enum GrpcReflection {
+    None,
+    V1,
+    V1Alpha,
+}
+
+/// Start a gRPC server for the `test.Greeter` service in
+/// `test_data/grpc/greeter.proto`. `SayHello` greets the given name and
+/// increments the count, unless the name is `error`. Return the server's URL
+async fn grpc_server(reflection: GrpcReflection) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let host = format!("http://{}", listener.local_addr().unwrap());
+    let pool = protox::compile(["grpc/greeter.proto"], [test_data_dir()])
+        .map(|files| {
+            prost_reflect::DescriptorPool::from_file_descriptor_set(files)
+                .unwrap()
+        })
+        .unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut connection = h2::server::handshake(socket).await.unwrap();
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                while let Some(result) = connection.accept().await {
+                    let (request, respond) = result.unwrap();
+                    tokio::spawn(grpc_respond(
+                        pool.clone(),
+                        reflection,
+                        request,
+                        respond,
+                    ));
+                }
+            });
+        }
+    });
+    host
+}
+
+/// Handle a single call to the test gRPC server
+async fn grpc_respond(
+    pool: prost_reflect::DescriptorPool,
+    reflection: GrpcReflection,
+    request: http::Request<h2::RecvStream>,
+    mut respond: h2::server::SendResponse<Bytes>,
+) {
+    use prost::Message;
+    use prost_reflect::DynamicMessage;
+
+    let path = request.uri().path().to_owned();
+    let mut body = request.into_body();
+    let mut data = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.unwrap();
+        let _ = body.flow_control().release_capacity(chunk.len());
+        data.extend_from_slice(&chunk);
+    }
+    let message = &data[5..];
+
+    let reply = match (path.as_str(), reflection) {
+        ("/test.Greeter/SayHello", _) => {
+            let request = DynamicMessage::decode(
+                pool.get_message_by_name("test.HelloRequest").unwrap(),
+                message,
+            )
+            .unwrap();
+            let name = request.get_field_by_name("name").unwrap();
+            let name = name.as_str().unwrap();
+            let count = request.get_field_by_name("count").unwrap();
+            let count = count.as_i32().unwrap();
+            if name == "error" {
+                Err((5, "No%20user%20named%20error"))
+            } else {
+                let mut reply = DynamicMessage::new(
+                    pool.get_message_by_name("test.HelloReply").unwrap(),
+                );
+                reply.set_field_by_name(
+                    "message",
+                    prost_reflect::Value::String(format!("Hello, {name}!")),
+                );
+                reply.set_field_by_name(
+                    "count",
+                    prost_reflect::Value::I32(count + 1),
+                );
+                let mut time = DynamicMessage::new(
+                    pool.get_message_by_name("google.protobuf.Timestamp")
+                        .unwrap(),
+                );
+                time.set_field_by_name("seconds", prost_reflect::Value::I64(1));
+                reply.set_field_by_name(
+                    "time",
+                    prost_reflect::Value::Message(time),
+                );
+                Ok(reply.encode_to_vec())
+            }
+        }
+        (
+            "/grpc.reflection.v1.ServerReflection/ServerReflectionInfo",
+            GrpcReflection::V1,
+        )
+        | (
+            "/grpc.reflection.v1alpha.ServerReflection/ServerReflectionInfo",
+            GrpcReflection::V1Alpha,
+        ) => {
+            // Ignore the request and send every file. ServerReflectionResponse
+            // field 4 is a FileDescriptorResponse, whose field 1 is the files
+            let mut files = Vec::new();
+            for file in pool.files() {
+                prost::encoding::bytes::encode(
+                    1,
+                    &file.file_descriptor_proto().encode_to_vec(),
+                    &mut files,
+                );
+            }
+            let mut reply = Vec::new();
+            prost::encoding::bytes::encode(4, &files, &mut reply);
+            Ok(reply)
+        }
+        _ => Err((12, "")),
+    };
+
+    match reply {
+        Ok(reply) => {
+            let response = http::Response::builder()
+                .header("content-type", "application/grpc")
+                .body(())
+                .unwrap();
+            let mut stream = respond.send_response(response, false).unwrap();
+            let mut frame = vec![0];
+            frame.extend_from_slice(&(reply.len() as u32).to_be_bytes());
+            frame.extend_from_slice(&reply);
+            stream.send_data(frame.into(), false).unwrap();
+            let trailers = header_map([("grpc-status", "0")]);
+            stream.send_trailers(trailers).unwrap();
+        }
+        // Errors are trailers-only: the status goes in the headers
+        Err((code, message)) => {
+            let response = http::Response::builder()
+                .header("content-type", "application/grpc")
+                .header("grpc-status", code.to_string())
+                .header("grpc-message", message)
+                .body(())
+                .unwrap();
+            respond.send_response(response, true).unwrap();
+        }
+    }
+}
+
+/// Build a TLS acceptor that presents the given server certificate. With
+/// `client_auth`, clients must present a certificate signed by the test CA.
+fn tls_acceptor(
+    tls_dir: &Path,
+    certificate: &str,
+    key: &str,
+    client_auth: bool,
+) -> TlsAcceptor {
+    let certs = CertificateDer::pem_file_iter(tls_dir.join(certificate))
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let key = PrivateKeyDer::from_pem_file(tls_dir.join(key)).unwrap();
+    let config = ServerConfig::builder();
+    let mut config = if client_auth {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_file(tls_dir.join("ca.pem")).unwrap())
+            .unwrap();
+        let verifier =
+            WebPkiClientVerifier::builder(roots.into()).build().unwrap();
+        config.with_client_cert_verifier(verifier)
+    } else {
+        config.with_no_client_auth()
+    }
+    .with_single_cert(certs, key)
+    .unwrap();
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    TlsAcceptor::from(Arc::new(config))
+}
+
+/// Start a minimal HTTPS server on a random port. It completes the handshake,
+/// then responds to one request with a `200`. Return the port and a handle
+/// that resolves when the server is done.
+async fn tls_server(
+    acceptor: TlsAcceptor,
+) -> (u16, tokio::task::JoinHandle<io::Result<()>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let mut stream = acceptor.accept(socket).await?;
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            request.push(stream.read_u8().await?);
+        }
+        stream
+            .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+            .await?;
+        stream.shutdown().await
+    });
+    (port, server)
+}
@@ -0,0 +1,175 @@
+//! Bandwidth throttling, to simulate a slow network. A [Throttle] wraps the
+//! transport and meters request and response bodies through at a fixed rate,
+//! so timeouts and progressive rendering behave as they would on a real slow
+//! connection.
+
+use crate::{
+    collection::Throttle,
+    http::{Transport, TransportError, transport},
+};
+use bytes::Bytes;
+use futures::{
+    FutureExt, Stream, StreamExt, TryStreamExt, future::BoxFuture, stream,
+};
+use http_body_util::BodyDataStream;
+use reqwest::{Body, Request, Response, header};
+use std::{sync::Arc, time::Duration};
+use tracing::info;
+
+impl Throttle {
+    /// Fill in any rate that isn't set here with the one from `fallback`
+    pub(super) fn or(&self, fallback: &Self) -> Self {
+        Self {
+            download_kbps: self.download_kbps.or(fallback.download_kbps),
+            upload_kbps: self.upload_kbps.or(fallback.upload_kbps),
+        }
+    }
+
+    /// Wrap a transport to limit its transfer rates. If no rate is set, the
+    /// transport is returned as-is.
+    pub(super) fn wrap(
+        &self,
+        transport: Arc<dyn Transport>,
+    ) -> Arc<dyn Transport> {
+        if self.download_kbps.is_none() && self.upload_kbps.is_none() {
+            return transport;
+        }
+        info!(throttle = ?self, "Throttling request");
+        Arc::new(ThrottleTransport {
+            inner: transport,
+            download: self.download_kbps.map(bytes_per_second),
+            upload: self.upload_kbps.map(bytes_per_second),
+        })
+    }
+}
+
+/// A [Transport] that streams the request body out, and the response body in,
+/// no faster than a fixed number of bytes per second
+#[derive(Debug)]
+struct ThrottleTransport {
+    inner: Arc<dyn Transport>,
+    download: Option<u64>,
+    upload: Option<u64>,
+}
+
+impl Transport for ThrottleTransport {
+    fn send(
+        &self,
+        mut request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let inner = Arc::clone(&self.inner);
+        let download = self.download;
+        if let Some(rate) = self.upload
+            && let Some(body) = request.body_mut().take()
+        {
+            // A streamed body has no known length, which would switch the
+            // request to chunked encoding. Keep the length the server would
+            // have seen without throttling.
+            if let Some(bytes) = body.as_bytes() {
+                request
+                    .headers_mut()
+                    .entry(header::CONTENT_LENGTH)
+                    .or_insert_with(|| bytes.len().into());
+            }
+            *request.body_mut() = Some(Body::wrap_stream(throttle(
+                BodyDataStream::new(body),
+                rate,
+            )));
+        }
+        async move {
+            let response = inner.send(request).await?;
+            Ok(match download {
+                Some(rate) => throttle_response(response, rate),
+                None => response,
+            })
+        }
+        .boxed()
+    }
+}
+
+/// Replace the body of a response with one that's received at a limited rate
+fn throttle_response(response: Response, bytes_per_second: u64) -> Response {
+    transport::map_body(response, |_, body| {
+        Body::wrap_stream(throttle(BodyDataStream::new(body), bytes_per_second))
+    })
+}
+
+/// Re-chunk a stream of bytes into small slices, and delay each one long
+/// enough that the stream doesn't exceed the given rate. Small slices keep the
+/// flow steady, rather than stalling and then delivering a large chunk at once.
+fn throttle<E>(
+    stream: impl Stream<Item = Result<Bytes, E>>,
+    bytes_per_second: u64,
+) -> impl Stream<Item = Result<Bytes, E>> {
+    // A tenth of a second's worth of data at a time
+    let slice_size = (bytes_per_second / 10).max(1) as usize;
+    stream
+        .map_ok(move |bytes| stream::iter(slices(bytes, slice_size).map(Ok)))
+        .try_flatten()
+        .then(move |result| async move {
+            if let Ok(slice) = &result {
+                let delay = slice.len() as f64 / bytes_per_second as f64;
+                tokio::time::sleep(Duration::from_secs_f64(delay)).await;
+            }
+            result
+        })
+}
+
+/// Split bytes into slices of at most `size`. This doesn't copy the data
+fn slices(bytes: Bytes, size: usize) -> impl Iterator<Item = Bytes> {
+    (0..bytes.len())
+        .step_by(size)
+        .map(move |start| bytes.slice(start..(start + size).min(bytes.len())))
+}
+
+/// Convert a rate in kilobits per second to bytes per second. A rate of 0
+/// would never finish, so the minimum is 1 byte per second.
+fn bytes_per_second(kbps: u64) -> u64 {
+    (kbps * 1000 / 8).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::{convert::Infallible, time::Instant};
+
+    /// A rate that isn't set on the recipe comes from the profile
+    #[rstest]
+    fn test_precedence() {
+        let profile = Throttle {
+            download_kbps: Some(1600),
+            upload_kbps: Some(750),
+        };
+        let recipe = Throttle {
+            download_kbps: Some(400),
+            upload_kbps: None,
+        };
+        assert_eq!(
+            recipe.or(&profile),
+            Throttle {
+                download_kbps: Some(400),
+                upload_kbps: Some(750),
+            }
+        );
+    }
+
+    /// Data is re-chunked into small slices, and the stream takes as long as
+    /// the rate dictates
+    #[rstest]
+    #[tokio::test]
+    async fn test_throttle() {
+        let input = stream::iter([
+            Ok::<_, Infallible>(Bytes::from(vec![0; 2500])),
+            Ok(Bytes::from(vec![0; 500])),
+        ]);
+        let start = Instant::now();
+        let slices: Vec<usize> = throttle(input, 10_000)
+            .map_ok(|slice| slice.len())
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(slices, [1000, 1000, 500, 500]);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+}
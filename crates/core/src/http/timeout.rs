@@ -0,0 +1,127 @@
+//! Connect and total timeouts for requests. Each limit can be set in the
+//! global config, the profile, or the recipe, with the most specific one
+//! winning. When a request times out, the error says which phase took too long
+//! and which limit to raise.
+
+use crate::{collection::Timeout, http::TransportError};
+use derive_more::Display;
+use std::time::Duration;
+use thiserror::Error;
+
+impl From<&slumber_config::Timeout> for Timeout {
+    fn from(timeout: &slumber_config::Timeout) -> Self {
+        Self {
+            connect_ms: timeout.connect_ms,
+            total_ms: timeout.total_ms,
+        }
+    }
+}
+
+impl Timeout {
+    /// Fill in any limit that isn't set here with the one from `fallback`
+    pub(super) fn or(&self, fallback: &Self) -> Self {
+        Self {
+            connect_ms: self.connect_ms.or(fallback.connect_ms),
+            total_ms: self.total_ms.or(fallback.total_ms),
+        }
+    }
+
+    /// Max time to establish a connection
+    pub(super) fn connect(&self) -> Option<Duration> {
+        self.connect_ms.map(Duration::from_millis)
+    }
+
+    /// Max time for the entire request
+    pub(super) fn total(&self) -> Option<Duration> {
+        self.total_ms.map(Duration::from_millis)
+    }
+
+    /// If the error is from the HTTP client hitting one of these limits,
+    /// replace it with a [TimeoutError] that says which limit it was. Other
+    /// errors are returned unchanged.
+    pub(super) fn classify(&self, error: TransportError) -> TransportError {
+        let Some(reqwest_error) = error.as_reqwest() else {
+            return error;
+        };
+        if !reqwest_error.is_timeout() {
+            return error;
+        }
+        let (phase, timeout) = if reqwest_error.is_connect() {
+            (TimeoutPhase::Connect, self.connect())
+        } else {
+            (TimeoutPhase::Total, self.total())
+        };
+        // A timeout we didn't set came from somewhere else, e.g. the OS
+        match timeout {
+            Some(timeout) => {
+                TransportError::new(TimeoutError { phase, timeout })
+            }
+            None => error,
+        }
+    }
+}
+
+/// A request took longer than one of its time limits
+#[derive(Debug, Error)]
+#[error(
+    "Timed out after {}ms {phase}. Increase `timeout.{}` on the recipe or \
+    profile (or in the config) to wait longer",
+    timeout.as_millis(),
+    phase.field(),
+)]
+pub struct TimeoutError {
+    pub phase: TimeoutPhase,
+    pub timeout: Duration,
+}
+
+/// The part of a request that ran out of time
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+pub enum TimeoutPhase {
+    /// Establishing the connection, including the TLS handshake
+    #[display("connecting to the server")]
+    Connect,
+    /// Sending the request and receiving the whole response
+    #[display("waiting for the response to complete")]
+    Total,
+}
+
+impl TimeoutPhase {
+    /// Name of the field that sets the limit for this phase
+    fn field(self) -> &'static str {
+        match self {
+            Self::Connect => "connect_ms",
+            Self::Total => "total_ms",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    /// Each limit is taken from the most specific level that sets it
+    #[rstest]
+    fn test_precedence() {
+        let global = Timeout {
+            connect_ms: Some(1),
+            total_ms: Some(2),
+        };
+        let profile = Timeout {
+            connect_ms: None,
+            total_ms: Some(20),
+        };
+        let recipe = Timeout {
+            connect_ms: None,
+            total_ms: Some(200),
+        };
+        assert_eq!(
+            recipe.or(&profile.or(&global)),
+            Timeout {
+                connect_ms: Some(1),
+                total_ms: Some(200),
+            }
+        );
+        assert_eq!(Timeout::default().or(&global), global);
+    }
+}
@@ -0,0 +1,222 @@
+//! Auth token lifecycle. Token responses generally advertise their own
+//! lifetime, either with an OAuth-style `expires_in` field or with the `exp`
+//! claim of a JWT. Knowing the expiry lets us refresh a token *before* it's
+//! rejected by the server. If it's rejected anyway, recipes can opt into
+//! refreshing and retrying with `reauthenticate`.
+
+use crate::{collection::Collection, http::Exchange};
+use base64::{Engine, prelude::BASE64_URL_SAFE_NO_PAD};
+use chrono::{DateTime, TimeDelta, Utc};
+use reqwest::StatusCode;
+use serde_json::Value;
+
+/// A token is refreshed if it expires within this much time. This leaves some
+/// headroom for the dependent request to be built and sent before the token
+/// actually expires.
+pub const TOKEN_REFRESH_MARGIN: TimeDelta = TimeDelta::seconds(30);
+
+impl Exchange {
+    /// Get the expiration time of the auth token contained in this response,
+    /// if there is one. The body is checked for (in order):
+    /// - An `expires_in` field, in seconds relative to when the response was
+    ///   received
+    /// - The `exp` claim of a JWT in the `access_token` field, or any other
+    ///   top-level string field
+    /// - The `exp` claim of a JWT that makes up the entire body
+    pub fn token_expiry(&self) -> Option<DateTime<Utc>> {
+        let body = self.response.body.text()?.trim();
+        match serde_json::from_str::<Value>(body) {
+            Ok(Value::Object(object)) => {
+                if let Some(seconds) =
+                    object.get("expires_in").and_then(json_seconds)
+                {
+                    return Some(
+                        self.end_time + TimeDelta::try_seconds(seconds)?,
+                    );
+                }
+                object
+                    .get("access_token")
+                    .into_iter()
+                    .chain(object.values())
+                    .filter_map(Value::as_str)
+                    .find_map(jwt_expiry)
+            }
+            Ok(_) => None,
+            // Some token endpoints return the bare token as text
+            Err(_) => jwt_expiry(body),
+        }
+    }
+
+    /// Should this request be retried with fresh authentication? This is the
+    /// case if the server rejected it with `401`/`403` and its recipe has
+    /// `reauthenticate` enabled. To retry, call
+    /// [TemplateContext::reauthenticate](crate::render::TemplateContext::reauthenticate)
+    /// then build and send the request again.
+    pub fn should_reauthenticate(&self, collection: &Collection) -> bool {
+        matches!(
+            self.response.status,
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN
+        ) && collection
+            .recipes
+            .get_recipe(&self.request.recipe_id)
+            .is_some_and(|recipe| recipe.reauthenticate)
+    }
+
+    /// Should the token in this response be refreshed? `false` if the response
+    /// doesn't have a token with a discoverable expiry.
+    pub fn token_needs_refresh(&self, now: DateTime<Utc>) -> bool {
+        self.token_expiry()
+            .is_some_and(|expiry| expiry - TOKEN_REFRESH_MARGIN <= now)
+    }
+}
+
+/// Get an integer number of seconds from a JSON number or numeric string
+pub(super) fn json_seconds(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            // Truncation is fine; we don't need sub-second precision
+            .or_else(|| number.as_f64().map(|seconds| seconds as i64)),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Decode the `exp` claim from a JWT. The signature is *not* verified; we only
+/// need to know when the server will stop accepting the token.
+pub(super) fn jwt_expiry(token: &str) -> Option<DateTime<Utc>> {
+    let mut segments = token.split('.');
+    let (Some(_), Some(payload), Some(_), None) = (
+        segments.next(),
+        segments.next(),
+        segments.next(),
+        segments.next(),
+    ) else {
+        return None;
+    };
+    let payload = BASE64_URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .ok()?;
+    let claims: Value = serde_json::from_slice(&payload).ok()?;
+    let exp = json_seconds(claims.get("exp")?)?;
+    DateTime::from_timestamp(exp, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        collection::Recipe,
+        http::{RequestRecord, ResponseBody, ResponseRecord},
+        test_util::by_id,
+    };
+    use rstest::rstest;
+    use serde_json::json;
+    use slumber_util::Factory;
+
+    /// Build a JWT with the given claims. The header and signature are junk
+    /// because we never look at them
+    fn jwt(claims: Value) -> String {
+        format!(
+            "e30.{}.c2ln",
+            BASE64_URL_SAFE_NO_PAD.encode(claims.to_string())
+        )
+    }
+
+    /// Build an exchange with the given response body
+    fn exchange(body: String) -> Exchange {
+        let exchange = Exchange::factory(());
+        Exchange {
+            response: ResponseRecord {
+                body: ResponseBody::new(body.into()),
+                ..ResponseRecord::factory(exchange.id)
+            }
+            .into(),
+            ..exchange
+        }
+    }
+
+    fn timestamp(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    #[rstest]
+    #[case::expires_in(
+        json!({"access_token": "abc", "expires_in": 3600})
+            .to_string(),
+        Some(timestamp(1_003_600)),
+    )]
+    #[case::expires_in_string(
+        json!({"expires_in": "60"}).to_string(),
+        Some(timestamp(1_000_060)),
+    )]
+    #[case::access_token_jwt(
+        json!({
+            "id_token": jwt(json!({"exp": 1})),
+            "access_token": jwt(json!({"exp": 2_000_000})),
+        })
+        .to_string(),
+        Some(timestamp(2_000_000)),
+    )]
+    #[case::other_field_jwt(
+        json!({"token": jwt(json!({"exp": 5}))})
+            .to_string(),
+        Some(timestamp(5)),
+    )]
+    #[case::bare_jwt(
+        jwt(json!({"exp": 2_000_000})),
+        Some(timestamp(2_000_000)),
+    )]
+    #[case::jwt_without_exp(jwt(json!({"sub": "me"})), None)]
+    #[case::no_token(json!({"token": "abc"}).to_string(), None)]
+    #[case::not_json("hello".into(), None)]
+    fn test_token_expiry(
+        #[case] body: String,
+        #[case] expected: Option<DateTime<Utc>>,
+    ) {
+        let exchange = Exchange {
+            end_time: timestamp(1_000_000),
+            ..exchange(body)
+        };
+        assert_eq!(exchange.token_expiry(), expected);
+    }
+
+    /// Tokens are refreshed shortly *before* they expire
+    #[rstest]
+    #[case::valid(1_000_000, false)]
+    #[case::within_margin(1_999_980, true)]
+    #[case::expired(2_000_001, true)]
+    fn test_token_needs_refresh(#[case] now: i64, #[case] expected: bool) {
+        let exchange = exchange(jwt(json!({"exp": 2_000_000})));
+        assert_eq!(exchange.token_needs_refresh(timestamp(now)), expected);
+    }
+
+    /// Only rejected requests are retried, and only if the recipe opts in
+    #[rstest]
+    #[case::unauthorized(StatusCode::UNAUTHORIZED, true, true)]
+    #[case::forbidden(StatusCode::FORBIDDEN, true, true)]
+    #[case::success(StatusCode::OK, true, false)]
+    #[case::server_error(StatusCode::INTERNAL_SERVER_ERROR, true, false)]
+    #[case::disabled(StatusCode::UNAUTHORIZED, false, false)]
+    fn test_should_reauthenticate(
+        #[case] status: StatusCode,
+        #[case] reauthenticate: bool,
+        #[case] expected: bool,
+    ) {
+        let recipe = Recipe {
+            reauthenticate,
+            ..Recipe::factory(())
+        };
+        let request = RequestRecord::factory((None, recipe.id.clone()));
+        let response = ResponseRecord {
+            status,
+            ..ResponseRecord::factory(request.id)
+        };
+        let exchange = Exchange::factory((request, response));
+        let collection = Collection {
+            recipes: by_id([recipe]).into(),
+            ..Collection::factory(())
+        };
+        assert_eq!(exchange.should_reauthenticate(&collection), expected);
+    }
+}
@@ -0,0 +1,512 @@
+//! Backends for sending HTTP requests. [HttpEngine](super::HttpEngine) always
+//! builds requests with reqwest, then hands each one to a [Transport] to be
+//! sent. The default transport is reqwest itself, but other implementations can
+//! be registered with the engine and selected via the `transport` config field
+//! or per recipe.
+
+use crate::{collection::HttpProtocol, http::host_policy::HostPolicy};
+use futures::{FutureExt, future::BoxFuture};
+use reqwest::{
+    Body, Certificate, Client, ClientBuilder, Identity, NoProxy, Proxy,
+    Request, Response, ResponseBuilderExt, Url, Version, redirect,
+};
+use slumber_config::{HttpEngineConfig, TlsHost};
+use slumber_util::paths::expand_home;
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::Debug,
+    fs, io,
+    net::{IpAddr, SocketAddr},
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use thiserror::Error;
+
+/// Name of the built-in transport, which sends requests via reqwest
+pub const DEFAULT_TRANSPORT: &str = "reqwest";
+/// Max redirects to follow. This matches reqwest's default policy
+const MAX_REDIRECTS: usize = 10;
+
+/// A backend that sends HTTP requests. The request has already been fully
+/// built, so the transport just has to put it on the wire and hand back the
+/// response.
+///
+/// Responses are returned as [reqwest::Response]. Transports that don't use
+/// reqwest can build one from an [http::Response] via `Response::from`.
+pub trait Transport: Debug + Send + Sync {
+    /// Send a request. The future should resolve as soon as the response
+    /// status and headers are received; the body can be streamed afterward.
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>>;
+}
+
+/// Share a transport between multiple engines
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        (**self).send(request)
+    }
+}
+
+/// An error sending a request or receiving its response. This can originate
+/// from any [Transport], so the underlying error is type-erased. Use
+/// [Self::as_reqwest] to check for errors from the reqwest transport.
+#[derive(Debug, Error)]
+#[error(transparent)]
+pub struct TransportError(pub(super) Box<dyn Error + Send + Sync>);
+
+impl TransportError {
+    pub fn new(error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self(error.into())
+    }
+
+    /// Get the underlying error if it came from reqwest
+    pub fn as_reqwest(&self) -> Option<&reqwest::Error> {
+        self.0.downcast_ref()
+    }
+}
+
+impl From<reqwest::Error> for TransportError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::new(error)
+    }
+}
+
+/// Replace the body of a response, keeping everything else. `f` is given the
+/// response's status, headers, etc. so it can update any that describe the
+/// body.
+pub(super) fn map_body(
+    response: Response,
+    f: impl FnOnce(&mut http::response::Parts, Body) -> Body,
+) -> Response {
+    let url = response.url().clone();
+    let (mut parts, body) = http::Response::from(response).into_parts();
+    // Converting to an http response drops the URL, so put it back
+    let (url_parts, ()) = http::Response::builder()
+        .url(url)
+        .body(())
+        .expect("Empty response is valid")
+        .into_parts();
+    parts.extensions.extend(url_parts.extensions);
+    let body = f(&mut parts, body);
+    http::Response::from_parts(parts, body).into()
+}
+
+/// The default [Transport], which sends requests with reqwest
+///
+/// This is cheap to clone because reqwest's `Client` type uses `Arc`
+/// internally. [reqwest::Client]
+#[derive(Clone, Debug)]
+pub struct ReqwestTransport {
+    /// Client for HTTP/1 requests with full TLS verification, which is almost
+    /// all of them
+    pub(super) client: Client,
+    /// Hosts whose TLS certificates are verified less strictly. Hosts not in
+    /// this map get full verification.
+    verification: Arc<HashMap<String, Verification>>,
+    /// Clients for every other combination of TLS verification and HTTP
+    /// version, keyed by `(verification, http2)`. HTTP/2-only clients are
+    /// for requests that require it (gRPC). HTTP/2 can't be negotiated over
+    /// plaintext, so the server is assumed to support it. These are created
+    /// lazily, because most users never need them.
+    pub(super) clients: Arc<Mutex<HashMap<(Verification, bool), Client>>>,
+    /// Copies of this transport with client-level overrides applied, built on
+    /// demand. See [Self::with_overrides].
+    pub(super) overridden: Arc<Mutex<OverrideCache>>,
+    /// Settings needed to build clients on demand
+    settings: ClientSettings,
+}
+
+/// Overridden transports, keyed by their overrides
+type OverrideCache = HashMap<ClientOverrides, Arc<dyn Transport>>;
+
+impl ReqwestTransport {
+    /// Build the transport. `ca_certificates` should be loaded from the config
+    /// with [load_ca_certificates].
+    pub(super) fn new(
+        config: &HttpEngineConfig,
+        host_policy: &HostPolicy,
+        ca_certificates: Vec<Certificate>,
+    ) -> Self {
+        let verification = config
+            .tls_hosts
+            .iter()
+            .map(|(host, tls)| (host.clone(), Verification::from_config(tls)))
+            .chain(
+                config
+                    .ignore_certificate_hosts
+                    .iter()
+                    .map(|host| (host.clone(), Verification::None)),
+            )
+            .filter(|(_, verification)| *verification != Verification::Full)
+            .collect();
+        // Redirects are followed by the redirect wrapper, so the policy can
+        // vary per recipe
+        let settings = ClientSettings {
+            follow_redirects: false,
+            host_policy: host_policy.clone(),
+            ca_certificates,
+            no_proxy: NoProxy::from_string(&config.no_proxy.join(",")),
+            identity: None,
+            proxy: None,
+            socket: None,
+            resolve: Vec::new(),
+            connect_timeout: config
+                .timeout
+                .connect_ms
+                .map(Duration::from_millis),
+            protocol: HttpProtocol::Auto,
+        };
+        Self::build(Arc::new(verification), settings)
+            .expect("Error building reqwest client")
+    }
+
+    /// Get a copy of this transport with settings that reqwest binds to the
+    /// client rather than the request, such as a TLS client certificate or a
+    /// proxy. Each unique set of overrides is built once, then cached.
+    pub(super) fn with_overrides(
+        &self,
+        overrides: ClientOverrides,
+    ) -> Result<Arc<dyn Transport>, OverrideError> {
+        if let Some(transport) = self.overridden.lock().unwrap().get(&overrides)
+        {
+            return Ok(Arc::clone(transport));
+        }
+
+        let identity = overrides
+            .identity
+            .as_deref()
+            .map(Identity::from_pem)
+            .transpose()
+            .map_err(OverrideError::Identity)?;
+        let proxy = overrides
+            .proxy
+            .as_deref()
+            .map(Proxy::all)
+            .transpose()
+            .map_err(OverrideError::Proxy)?;
+        // If the client can't be built, the certificate is the likely culprit
+        // because the proxy has already been validated
+        let build_error = if identity.is_some() {
+            OverrideError::Identity
+        } else {
+            OverrideError::Proxy
+        };
+        let settings = ClientSettings {
+            identity,
+            proxy,
+            socket: overrides.socket.clone(),
+            resolve: overrides.resolve.clone(),
+            connect_timeout: overrides
+                .connect_timeout
+                .or(self.settings.connect_timeout),
+            protocol: overrides.protocol,
+            ..self.settings.clone()
+        };
+        let transport: Arc<dyn Transport> = Arc::new(
+            Self::build(Arc::clone(&self.verification), settings)
+                .map_err(build_error)?,
+        );
+        self.overridden
+            .lock()
+            .unwrap()
+            .insert(overrides, Arc::clone(&transport));
+        Ok(transport)
+    }
+
+    /// Build the transport and its default client
+    fn build(
+        verification: Arc<HashMap<String, Verification>>,
+        settings: ClientSettings,
+    ) -> reqwest::Result<Self> {
+        Ok(Self {
+            client: settings.builder(Verification::Full, false).build()?,
+            verification,
+            clients: Default::default(),
+            overridden: Default::default(),
+            settings,
+        })
+    }
+
+    /// Build a client that follows redirects according to the config. This
+    /// is for requests the engine sends itself rather than through a
+    /// transport, such as fetching OAuth2 tokens.
+    pub(super) fn redirecting_client(&self, follow_redirects: bool) -> Client {
+        ClientSettings {
+            follow_redirects,
+            ..self.settings.clone()
+        }
+        .builder(Verification::Full, false)
+        .build()
+        .expect("Error building reqwest client")
+    }
+
+    /// Get the appropriate client to use for this request. If the user wants
+    /// to relax TLS verification for the request URL's host, use a client
+    /// that does so. Requests that require HTTP/2 get an HTTP/2-only client.
+    pub(super) fn get_client(
+        &self,
+        url: &Url,
+        version: Version,
+    ) -> reqwest::Result<Client> {
+        let verification = url
+            .host_str()
+            .and_then(|host| self.verification.get(host))
+            .copied()
+            .unwrap_or_default();
+        let http2 = version == Version::HTTP_2;
+        if verification == Verification::Full && !http2 {
+            return Ok(self.client.clone());
+        }
+
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.get(&(verification, http2)) {
+            return Ok(client.clone());
+        }
+        let client = self.settings.builder(verification, http2).build()?;
+        clients.insert((verification, http2), client.clone());
+        Ok(client)
+    }
+}
+
+/// Settings shared by all clients of a [ReqwestTransport]
+#[derive(Clone, Debug)]
+struct ClientSettings {
+    follow_redirects: bool,
+    host_policy: HostPolicy,
+    /// Additional trusted CA certificates
+    ca_certificates: Vec<Certificate>,
+    /// Hosts that bypass the proxy
+    no_proxy: Option<NoProxy>,
+    /// TLS client certificate presented by every client
+    identity: Option<Identity>,
+    /// Proxy that every request is sent through. If not given, reqwest uses
+    /// the proxy environment variables.
+    proxy: Option<Proxy>,
+    /// Unix socket or Windows named pipe that every connection is opened on.
+    /// This bypasses DNS resolution and the proxy.
+    socket: Option<PathBuf>,
+    /// Hostnames mapped to the address to connect to, bypassing DNS
+    resolve: Vec<(String, IpAddr)>,
+    /// Max time to establish a connection
+    connect_timeout: Option<Duration>,
+    /// HTTP version to send with
+    protocol: HttpProtocol,
+}
+
+impl ClientSettings {
+    /// Start building a client with the user's settings
+    fn builder(
+        &self,
+        verification: Verification,
+        http2: bool,
+    ) -> ClientBuilder {
+        let redirect_policy = if !self.follow_redirects {
+            redirect::Policy::none()
+        } else if self.host_policy.is_empty() {
+            redirect::Policy::default()
+        } else {
+            // Redirects could take us to a blocked host, so check each one
+            let host_policy = self.host_policy.clone();
+            redirect::Policy::custom(move |attempt| {
+                if let Err(error) = host_policy.check(attempt.url()) {
+                    attempt.error(error)
+                } else if attempt.previous().len() >= MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else {
+                    attempt.follow()
+                }
+            })
+        };
+
+        // User-Agent is *not* set on the client, because it can be
+        // omitted per-request. Default headers can't be removed.
+        let mut builder = Client::builder().redirect(redirect_policy);
+        let ca_certificates = self.ca_certificates.iter().cloned();
+        builder = match verification {
+            Verification::Full => builder.tls_certs_merge(ca_certificates),
+            // rustls can only skip hostname verification if it doesn't
+            // use the system's certificate store
+            Verification::AnyHostname => builder
+                .tls_certs_only(ca_certificates)
+                .tls_danger_accept_invalid_hostnames(true),
+            Verification::None => builder.tls_danger_accept_invalid_certs(true),
+        };
+        // Requests that require HTTP/2 get it regardless of the setting
+        builder = match (http2, self.protocol) {
+            (true, _) | (false, HttpProtocol::Http2) => {
+                builder.http2_prior_knowledge()
+            }
+            (false, HttpProtocol::Http1) => builder.http1_only(),
+            (false, HttpProtocol::Auto) => builder,
+        };
+        // Record the server's certificate, so it can be checked against the
+        // profile's pinned fingerprint
+        builder = builder.tls_info(true);
+        if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder =
+                builder.proxy(proxy.clone().no_proxy(self.no_proxy.clone()));
+        }
+        for (host, address) in &self.resolve {
+            // The port is ignored; the URL's port is always used
+            builder = builder.resolve(host, SocketAddr::new(*address, 0));
+        }
+        // Unix sockets are rejected on Windows and named pipes everywhere
+        // else before a client is built
+        if let Some(socket) = &self.socket {
+            #[cfg(unix)]
+            {
+                builder = builder.unix_socket(socket.clone());
+            }
+            #[cfg(windows)]
+            {
+                builder = builder.windows_named_pipe(socket.clone());
+            }
+        }
+        builder
+    }
+}
+
+/// Settings that reqwest binds to the client rather than the request. A
+/// request that needs any of these gets its own transport. See
+/// [ReqwestTransport::with_overrides].
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub(super) struct ClientOverrides {
+    /// TLS client certificate and key, PEM-encoded. This is the file content
+    /// rather than the path, so changes to the files are picked up on the next
+    /// request. PKCS#12 archives are converted to PEM before they get here.
+    pub identity: Option<Vec<u8>>,
+    /// URL of the proxy to send requests through
+    pub proxy: Option<String>,
+    /// Path of the Unix socket or named pipe to send requests over
+    pub socket: Option<PathBuf>,
+    /// Hostnames mapped to the address to connect to, bypassing DNS
+    pub resolve: Vec<(String, IpAddr)>,
+    /// Max time to establish a connection, if different from the config
+    pub connect_timeout: Option<Duration>,
+    /// HTTP version to send with, instead of negotiating it
+    pub protocol: HttpProtocol,
+}
+
+/// Error applying [ClientOverrides] to a transport
+#[derive(Debug)]
+pub(super) enum OverrideError {
+    /// Client certificate isn't a usable PEM certificate and key
+    Identity(reqwest::Error),
+    /// Proxy URL is invalid
+    Proxy(reqwest::Error),
+}
+
+/// How strictly a client verifies the server's TLS certificate
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub(super) enum Verification {
+    /// The certificate must be valid, signed by a trusted CA, and issued for
+    /// the request's hostname
+    #[default]
+    Full,
+    /// The certificate must be valid and signed by one of the CAs in the
+    /// `ca_certificates` config, but can be for any hostname
+    AnyHostname,
+    /// Any certificate is accepted
+    None,
+}
+
+impl Verification {
+    fn from_config(tls: &TlsHost) -> Self {
+        if tls.danger_accept_invalid_certs {
+            Self::None
+        } else if tls.danger_accept_invalid_hostnames {
+            Self::AnyHostname
+        } else {
+            Self::Full
+        }
+    }
+}
+
+/// Load the additional CA certificates listed in the config. This also
+/// validates the per-host TLS settings that depend on them.
+pub(super) fn load_ca_certificates(
+    config: &HttpEngineConfig,
+) -> Result<Vec<Certificate>, TlsConfigError> {
+    if config.ca_certificates.is_empty()
+        && let Some((host, _)) = config.tls_hosts.iter().find(|(_, tls)| {
+            Verification::from_config(tls) == Verification::AnyHostname
+        })
+    {
+        return Err(TlsConfigError::NoCaCertificates { host: host.clone() });
+    }
+
+    let mut certificates = Vec::new();
+    for path in &config.ca_certificates {
+        let path = expand_home(PathBuf::from(path)).into_owned();
+        let pem = fs::read(&path).map_err(|error| TlsConfigError::Read {
+            path: path.clone(),
+            error,
+        })?;
+        let bundle = Certificate::from_pem_bundle(&pem).map_err(|error| {
+            TlsConfigError::Invalid {
+                path: path.clone(),
+                error,
+            }
+        })?;
+        if bundle.is_empty() {
+            return Err(TlsConfigError::Empty { path });
+        }
+        certificates.extend(bundle);
+    }
+    Ok(certificates)
+}
+
+/// Error in the TLS settings of the config. The HTTP engine is created
+/// infallibly, so this is stored and returned for each request.
+#[derive(Debug, Error)]
+pub enum TlsConfigError {
+    /// CA certificate file has no certificates in it
+    #[error(
+        "CA certificate file {} does not contain any PEM certificates",
+        path.display()
+    )]
+    Empty { path: PathBuf },
+    /// CA certificate file couldn't be parsed
+    #[error("Invalid CA certificate file {}", path.display())]
+    Invalid {
+        path: PathBuf,
+        #[source]
+        error: reqwest::Error,
+    },
+    /// `danger_accept_invalid_hostnames` requires a CA to verify against
+    #[error(
+        "`danger_accept_invalid_hostnames` for host `{host}` requires \
+        `ca_certificates` to be set. Only those CAs are trusted for the host"
+    )]
+    NoCaCertificates { host: String },
+    /// Error reading a CA certificate file
+    #[error("Reading CA certificate file {}", path.display())]
+    Read {
+        path: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+}
+
+impl Transport for ReqwestTransport {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let client = self.get_client(request.url(), request.version());
+        async move { Ok(client?.execute(request).await?) }.boxed()
+    }
+}
@@ -0,0 +1,461 @@
+//! WebSocket client. Recipes with a `websocket` field are sent as an HTTP/1.1
+//! upgrade handshake. Once the server switches protocols, the connection is
+//! handed off to [run], which sends the recipe's messages and passes every
+//! message from the server back to the caller until the connection closes.
+//!
+//! The handshake goes through reqwest like any other request, so it gets the
+//! same TLS, proxy, and redirect handling. After that, the framing protocol is
+//! handled by [tokio_tungstenite]. Extensions such as compression are never
+//! negotiated.
+
+use crate::collection::WebSocketFrame;
+use aws_lc_rs::digest::{SHA1_FOR_LEGACY_USE_ONLY, digest};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use bytes::{Bytes, BytesMut};
+use futures::{
+    SinkExt, Stream, StreamExt,
+    future::{self, Either},
+    lock::Mutex,
+    pin_mut,
+};
+use reqwest::header::HeaderValue;
+use std::{collections::VecDeque, ops::ControlFlow};
+use thiserror::Error;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::{
+    WebSocketStream,
+    tungstenite::{
+        self, Message, Utf8Bytes,
+        error::ProtocolError,
+        protocol::{
+            CloseFrame, Role, WebSocketConfig, frame::coding::CloseCode,
+        },
+    },
+};
+use tracing::warn;
+use uuid::Uuid;
+
+/// Appended to the handshake key to compute the key the server must respond
+/// with. Defined by the RFC
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Refuse messages larger than this, so a misbehaving server can't make us
+/// allocate unbounded memory
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+/// Max total size of the messages kept for the response body. A long-lived
+/// connection can receive far more than this, so the oldest messages are
+/// dropped to make room for new ones.
+const MAX_TRANSCRIPT_SIZE: usize = 64 * 1024 * 1024;
+
+/// Generate a random value for the `Sec-WebSocket-Key` handshake header
+pub(super) fn handshake_key() -> HeaderValue {
+    let key = BASE64_STANDARD.encode(Uuid::new_v4().as_bytes());
+    HeaderValue::try_from(key).expect("Base64 is a valid header value")
+}
+
+/// Make sure the server's `Sec-WebSocket-Accept` header matches the key we
+/// sent. This proves the server actually speaks WebSocket, rather than some
+/// proxy blindly echoing the upgrade.
+pub(super) fn check_accept(
+    key: Option<&HeaderValue>,
+    accept: Option<&HeaderValue>,
+) -> Result<(), WebSocketError> {
+    match (key, accept) {
+        (Some(key), Some(accept))
+            if accept.as_bytes() == accept_key(key.as_bytes()).as_bytes() =>
+        {
+            Ok(())
+        }
+        _ => Err(WebSocketError::Handshake),
+    }
+}
+
+/// Compute the expected `Sec-WebSocket-Accept` value for a key
+pub(super) fn accept_key(key: &[u8]) -> String {
+    let mut input = key.to_vec();
+    input.extend_from_slice(ACCEPT_GUID.as_bytes());
+    BASE64_STANDARD.encode(digest(&SHA1_FOR_LEGACY_USE_ONLY, &input))
+}
+
+/// Messages and settings for a WebSocket session, taken from the recipe
+#[derive(Debug, Default)]
+pub(super) struct Session {
+    /// Rendered messages to send as soon as the connection opens
+    pub messages: Vec<Bytes>,
+    /// Frame type for every outgoing message. `None` to pick based on content
+    pub frame: Option<WebSocketFrame>,
+}
+
+/// Run a WebSocket session on an upgraded connection. The session's messages
+/// are sent first, followed by each item of `outgoing` as it's yielded. Once
+/// `outgoing` ends, we ask the server to close the connection. Pass a stream
+/// that never ends to leave the connection open until the server closes it.
+///
+/// Each text or binary message from the server is passed to `on_message`,
+/// followed by a newline. If `on_message` returns [ControlFlow::Break], the
+/// connection is closed without waiting for more messages. Return the
+/// messages received, in the same format. If they exceed
+/// [MAX_TRANSCRIPT_SIZE], only the most recent ones are returned.
+pub(super) async fn run(
+    connection: impl AsyncRead + AsyncWrite + Unpin,
+    session: Session,
+    outgoing: impl Stream<Item = Bytes>,
+    mut on_message: impl FnMut(&[u8]) -> ControlFlow<()>,
+) -> Result<Bytes, WebSocketError> {
+    let config = WebSocketConfig::default()
+        .max_message_size(Some(MAX_MESSAGE_SIZE))
+        .max_frame_size(Some(MAX_MESSAGE_SIZE));
+    let stream = WebSocketStream::from_raw_socket(
+        connection,
+        Role::Client,
+        Some(config),
+    )
+    .await;
+    let (sink, mut incoming) = stream.split();
+    // Both halves need to be able to close the connection
+    let sink = Mutex::new(sink);
+    let close = async || {
+        let frame = CloseFrame {
+            code: CloseCode::Normal,
+            reason: Utf8Bytes::default(),
+        };
+        match sink.lock().await.send(Message::Close(Some(frame))).await {
+            // The other side may have started closing first
+            Ok(())
+            | Err(
+                tungstenite::Error::ConnectionClosed
+                | tungstenite::Error::AlreadyClosed
+                | tungstenite::Error::Protocol(ProtocolError::SendAfterClosing),
+            ) => Ok(()),
+            Err(error) => Err(WebSocketError::from(error)),
+        }
+    };
+
+    let write = async {
+        pin_mut!(outgoing);
+        let mut outgoing =
+            futures::stream::iter(session.messages).chain(outgoing);
+        while let Some(message) = outgoing.next().await {
+            let message = encode(session.frame, message)?;
+            sink.lock().await.send(message).await?;
+        }
+        close().await
+    };
+
+    let read = async {
+        let mut transcript = Transcript::new(MAX_TRANSCRIPT_SIZE);
+        while let Some(message) = incoming.next().await {
+            let payload = match message {
+                Ok(Message::Text(text)) => Bytes::from(text),
+                Ok(Message::Binary(data)) => data,
+                // Pings are answered and the closing handshake is completed
+                // automatically. Once it's done, the stream ends.
+                Ok(
+                    Message::Ping(_)
+                    | Message::Pong(_)
+                    | Message::Close(_)
+                    | Message::Frame(_),
+                ) => continue,
+                // Server hung up without a closing handshake. Rude, but
+                // there's nothing more to receive
+                Err(tungstenite::Error::Protocol(
+                    ProtocolError::ResetWithoutClosingHandshake,
+                )) => break,
+                Err(error) => return Err(error.into()),
+            };
+
+            let mut line = BytesMut::with_capacity(payload.len() + 1);
+            line.extend_from_slice(&payload);
+            line.extend_from_slice(b"\n");
+            let line = line.freeze();
+            let flow = on_message(&line);
+            transcript.push(line);
+            if flow.is_break() {
+                close().await?;
+                break;
+            }
+        }
+        Ok(transcript.into_bytes())
+    };
+
+    // Reading finishes when the connection closes. If we run out of messages
+    // to send first, keep reading until the server acknowledges the close
+    pin_mut!(read, write);
+    match future::select(read, write).await {
+        Either::Left((result, _)) => result,
+        Either::Right((result, read)) => {
+            result?;
+            read.await
+        }
+    }
+}
+
+/// Wrap an outgoing message in the appropriate frame type
+fn encode(
+    frame: Option<WebSocketFrame>,
+    message: Bytes,
+) -> Result<Message, WebSocketError> {
+    match frame {
+        Some(WebSocketFrame::Text) => Utf8Bytes::try_from(message)
+            .map(Message::Text)
+            .map_err(|_| WebSocketError::NotText),
+        Some(WebSocketFrame::Binary) => Ok(Message::Binary(message)),
+        // Cloning is cheap; it just bumps a refcount
+        None => Ok(Utf8Bytes::try_from(message.clone())
+            .map_or(Message::Binary(message), Message::Text)),
+    }
+}
+
+/// Messages received over a connection, capped to a max total size. Once the
+/// cap is exceeded, the oldest messages are dropped.
+#[derive(Debug)]
+struct Transcript {
+    max_size: usize,
+    messages: VecDeque<Bytes>,
+    /// Total size of all messages currently held
+    size: usize,
+    /// Number of messages dropped to stay under the cap
+    dropped: usize,
+}
+
+impl Transcript {
+    fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            messages: VecDeque::new(),
+            size: 0,
+            dropped: 0,
+        }
+    }
+
+    fn push(&mut self, message: Bytes) {
+        self.size += message.len();
+        self.messages.push_back(message);
+        // Always keep the latest message, even if it's over the cap alone
+        while self.size > self.max_size && self.messages.len() > 1 {
+            let dropped = self.messages.pop_front().expect("Checked length");
+            self.size -= dropped.len();
+            self.dropped += 1;
+        }
+    }
+
+    /// Concatenate the messages into a single body
+    fn into_bytes(self) -> Bytes {
+        if self.dropped > 0 {
+            warn!(
+                dropped = self.dropped,
+                max_size = self.max_size,
+                "WebSocket transcript exceeded max size; oldest messages \
+                were dropped"
+            );
+        }
+        let mut body = BytesMut::with_capacity(self.size);
+        for message in self.messages {
+            body.extend_from_slice(&message);
+        }
+        body.freeze()
+    }
+}
+
+/// Error in a WebSocket connection, after the HTTP request was sent
+#[derive(Debug, Error)]
+pub enum WebSocketError {
+    #[error("Server did not accept the WebSocket handshake")]
+    Handshake,
+    #[error(
+        "WebSocket message is not valid UTF-8, but the recipe's `frame` is \
+        `text`"
+    )]
+    NotText,
+    #[error(transparent)]
+    Connection(#[from] tungstenite::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_util::{assert_err, assert_matches};
+    use tokio::io::{AsyncWriteExt, DuplexStream, duplex};
+
+    /// Server side of a connection, for tests
+    type Server = WebSocketStream<DuplexStream>;
+
+    async fn connect() -> (DuplexStream, Server) {
+        let (client, server) = duplex(1024 * 1024);
+        let server =
+            WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+        (client, server)
+    }
+
+    /// Receive the next message from the client
+    async fn receive(server: &mut Server) -> Message {
+        server.next().await.unwrap().unwrap()
+    }
+
+    /// Known key/accept pair from the RFC
+    #[test]
+    fn test_accept_key() {
+        let key = HeaderValue::from_static("dGhlIHNhbXBsZSBub25jZQ==");
+        let accept = HeaderValue::from_static("s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+        check_accept(Some(&key), Some(&accept)).unwrap();
+        assert_err!(
+            check_accept(Some(&key), Some(&key)),
+            "did not accept the WebSocket handshake"
+        );
+        assert_err!(
+            check_accept(Some(&key), None),
+            "did not accept the WebSocket handshake"
+        );
+    }
+
+    /// Send the initial messages, receive messages, answer pings, and close
+    /// when the server does
+    #[tokio::test]
+    async fn test_run() {
+        let (client, mut server) = connect().await;
+        let server_task = async move {
+            assert_eq!(receive(&mut server).await, Message::text("hello"));
+            assert_eq!(receive(&mut server).await, Message::binary(vec![0xff]));
+
+            server.send(Message::text("one")).await.unwrap();
+            server.send(Message::Ping("ping".into())).await.unwrap();
+            assert_eq!(
+                receive(&mut server).await,
+                Message::Pong("ping".into())
+            );
+            server.send(Message::binary(b"two".to_vec())).await.unwrap();
+            server.close(None).await.unwrap();
+            // Client echoes the close
+            assert_matches!(receive(&mut server).await, Message::Close(_));
+        };
+
+        let mut received = Vec::new();
+        let session = Session {
+            messages: vec!["hello".into(), Bytes::from_static(&[0xff])],
+            frame: None,
+        };
+        let (body, ()) = tokio::join!(
+            run(client, session, futures::stream::pending(), |message| {
+                received.push(message.to_vec());
+                ControlFlow::Continue(())
+            }),
+            server_task,
+        );
+        assert_eq!(body.unwrap(), "one\ntwo\n");
+        assert_eq!(received, [b"one\n".to_vec(), b"two\n".to_vec()]);
+    }
+
+    /// The recipe can force every message to be text or binary
+    #[rstest]
+    #[case::auto(None, b"text", Ok(Message::text("text")))]
+    #[case::auto_binary(
+        None,
+        b"\xff",
+        Ok(Message::binary(b"\xff".to_vec())),
+    )]
+    #[case::text(
+        Some(WebSocketFrame::Text),
+        b"text",
+        Ok(Message::text("text"))
+    )]
+    #[case::text_invalid(
+        Some(WebSocketFrame::Text),
+        b"\xff",
+        Err("not valid UTF-8")
+    )]
+    #[case::binary(
+        Some(WebSocketFrame::Binary),
+        b"text",
+        Ok(Message::binary(b"text".to_vec())),
+    )]
+    fn test_encode(
+        #[case] frame: Option<WebSocketFrame>,
+        #[case] message: &'static [u8],
+        #[case] expected: Result<Message, &str>,
+    ) {
+        let result = encode(frame, Bytes::from_static(message));
+        match expected {
+            Ok(expected) => assert_eq!(result.unwrap(), expected),
+            Err(expected) => assert_err!(result, expected),
+        }
+    }
+
+    /// When the outgoing stream ends, the client closes the connection
+    #[tokio::test]
+    async fn test_run_outgoing_ends() {
+        let (client, mut server) = connect().await;
+        let server_task = async move {
+            assert_eq!(receive(&mut server).await, Message::text("from stdin"));
+            assert_matches!(receive(&mut server).await, Message::Close(_));
+            // Our reply to the close is sent automatically
+            assert!(server.next().await.is_none());
+        };
+
+        let (body, ()) = tokio::join!(
+            run(
+                client,
+                Session::default(),
+                futures::stream::iter([Bytes::from("from stdin")]),
+                |_| ControlFlow::Continue(()),
+            ),
+            server_task,
+        );
+        assert_eq!(body.unwrap(), "");
+    }
+
+    /// Breaking from the callback closes the connection immediately
+    #[tokio::test]
+    async fn test_run_break() {
+        let (client, mut server) = connect().await;
+        server.send(Message::text("first")).await.unwrap();
+        server.send(Message::text("second")).await.unwrap();
+        let body = run(
+            client,
+            Session::default(),
+            futures::stream::pending(),
+            |_| ControlFlow::Break(()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body, "first\n");
+        // Second message is skipped
+        assert_matches!(receive(&mut server).await, Message::Close(_));
+    }
+
+    /// Protocol violations from the server are errors
+    #[tokio::test]
+    async fn test_run_protocol_error() {
+        let (client, mut server) = duplex(1024);
+        // Continuation frame without a message to continue
+        server
+            .write_all(&[0x80, 3, b'h', b'u', b'h'])
+            .await
+            .unwrap();
+        assert_err!(
+            run(
+                client,
+                Session::default(),
+                futures::stream::pending(),
+                |_| { ControlFlow::Continue(()) }
+            )
+            .await,
+            "Continue frame but nothing to continue"
+        );
+    }
+
+    /// Once the transcript exceeds its max size, the oldest messages are
+    /// dropped. The latest message is always kept
+    #[test]
+    fn test_transcript() {
+        let mut transcript = Transcript::new(8);
+        transcript.push("one\n".into());
+        transcript.push("two\n".into());
+        transcript.push("three\n".into());
+        assert_eq!(transcript.dropped, 2);
+        assert_eq!(transcript.into_bytes(), "three\n");
+
+        let mut transcript = Transcript::new(8);
+        transcript.push("too large!\n".into());
+        assert_eq!(transcript.into_bytes(), "too large!\n");
+    }
+}
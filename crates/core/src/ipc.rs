@@ -0,0 +1,103 @@
+//! Communication with a running TUI from other processes. This allows external
+//! tools, such as editor plugins, to control the TUI via `slumber open`.
+//!
+//! Requests are passed through a single file in the data directory. Each new
+//! request overwrites the file, and every running TUI watches it for changes.
+//! Requests are tagged with a collection, so TUIs for other collections can
+//! ignore them.
+
+use crate::{
+    collection::{ProfileId, RecipeId},
+    database::CollectionId,
+};
+use serde::{Deserialize, Serialize};
+use slumber_util::paths;
+use std::{fs, io, path::PathBuf};
+use thiserror::Error;
+
+/// A request for a running TUI to select a recipe and/or profile
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OpenRequest {
+    /// Collection that the request applies to
+    pub collection_id: CollectionId,
+    /// Recipe (or folder) to select. `None` leaves the selection unchanged
+    pub recipe_id: Option<RecipeId>,
+    /// Profile to select. `None` leaves the selection unchanged
+    pub profile_id: Option<ProfileId>,
+}
+
+impl OpenRequest {
+    const FILE: &str = "open.json";
+
+    /// Path to the file that requests are passed through. The TUI should watch
+    /// this for changes. **File may not exist yet.**
+    pub fn path() -> PathBuf {
+        paths::data_directory().join(Self::FILE)
+    }
+
+    /// Send this request to any TUIs that are running
+    pub fn send(&self) -> Result<(), IpcError> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|error| IpcError::Io {
+                path: parent.to_owned(),
+                error,
+            })?;
+        }
+        let content = serde_json::to_vec(self)?;
+        // Write to a temp file then move it into place, so a watcher never
+        // reads a partially written request
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, content).map_err(|error| IpcError::Io {
+            path: temp_path.clone(),
+            error,
+        })?;
+        fs::rename(&temp_path, &path)
+            .map_err(|error| IpcError::Io { path, error })
+    }
+
+    /// Load the most recently sent request
+    pub fn receive() -> Result<Self, IpcError> {
+        let path = Self::path();
+        let content =
+            fs::read(&path).map_err(|error| IpcError::Io { path, error })?;
+        Ok(serde_json::from_slice(&content)?)
+    }
+}
+
+/// Error sending or receiving an [OpenRequest]
+#[derive(Debug, Error)]
+pub enum IpcError {
+    /// Error reading or writing the request file
+    #[error("Error accessing `{}`", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        error: io::Error,
+    },
+
+    /// Request file contains invalid content
+    #[error("Invalid open request")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::CollectionDatabase;
+    use rstest::rstest;
+    use slumber_util::{DataDir, Factory, data_dir};
+
+    /// Sending a request makes it available to receivers
+    #[rstest]
+    fn test_send_receive(data_dir: DataDir) {
+        let request = OpenRequest {
+            collection_id: CollectionDatabase::factory(()).collection_id(),
+            recipe_id: Some("recipe1".into()),
+            profile_id: None,
+        };
+        request.send().unwrap();
+        assert!(data_dir.join("open.json").exists());
+        assert_eq!(OpenRequest::receive().unwrap(), request);
+    }
+}
@@ -0,0 +1,308 @@
+//! Access to the operating system's credential store, so secrets don't have to
+//! live in collection files or environment variables.
+//!
+//! Rather than linking against each platform's API, we shell out to the tool
+//! that ships with it:
+//! - macOS: `security` (Keychain)
+//! - Windows: PowerShell (Credential Manager)
+//! - Everything else: `secret-tool` (Secret Service, e.g. GNOME Keyring or
+//!   KWallet)
+
+use std::{
+    io,
+    process::{ExitStatus, Output, Stdio},
+    string::FromUtf8Error,
+};
+use thiserror::Error;
+use tokio::{io::AsyncWriteExt, process::Command};
+use tracing::debug;
+
+/// A single secret in the OS credential store, identified by a service name
+/// and a username
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyringEntry {
+    service: String,
+    username: String,
+}
+
+impl KeyringEntry {
+    pub fn new(
+        service: impl Into<String>,
+        username: impl Into<String>,
+    ) -> Self {
+        Self {
+            service: service.into(),
+            username: username.into(),
+        }
+    }
+
+    /// Get the stored secret, or `None` if there is no such entry
+    pub async fn get(&self) -> Result<Option<String>, KeyringError> {
+        let Some(output) = platform::get(self).run().await? else {
+            return Ok(None);
+        };
+        let mut secret = String::from_utf8(output)?;
+        // Some tools terminate the secret with a newline
+        if secret.ends_with('\n') {
+            secret.pop();
+        }
+        Ok(Some(secret))
+    }
+
+    /// Store a secret, replacing the existing one if any
+    pub async fn set(&self, secret: &str) -> Result<(), KeyringError> {
+        platform::set(self, secret).run().await?;
+        Ok(())
+    }
+
+    /// Delete the entry. Return `false` if it's known not to have existed;
+    /// not every platform reports this
+    pub async fn delete(&self) -> Result<bool, KeyringError> {
+        Ok(platform::delete(self).run().await?.is_some())
+    }
+}
+
+/// An invocation of the platform's credential store tool
+struct Invocation {
+    program: &'static str,
+    arguments: Vec<String>,
+    env: Vec<(&'static str, String)>,
+    /// Data to write to stdin. Secrets are passed this way where possible,
+    /// so they aren't visible in the process list
+    stdin: Option<String>,
+    /// Does the output indicate that the entry doesn't exist?
+    is_missing: fn(&Output) -> bool,
+}
+
+impl Invocation {
+    /// Run the tool and return its stdout, or `None` if the entry is missing
+    async fn run(self) -> Result<Option<Vec<u8>>, KeyringError> {
+        let program = self.program;
+        debug!(program, arguments = ?self.arguments, "Accessing keyring");
+        let spawn_error = |error| KeyringError::Spawn { program, error };
+
+        let mut child = Command::new(program)
+            .args(&self.arguments)
+            .envs(self.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(spawn_error)?;
+        // Write stdin then drop the handle, so the tool sees EOF
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        if let Some(input) = self.stdin {
+            stdin
+                .write_all(input.as_bytes())
+                .await
+                .map_err(spawn_error)?;
+        }
+        drop(stdin);
+        let output = child.wait_with_output().await.map_err(spawn_error)?;
+
+        if output.status.success() {
+            Ok(Some(output.stdout))
+        } else if (self.is_missing)(&output) {
+            Ok(None)
+        } else {
+            Err(KeyringError::Status {
+                program,
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr)
+                    .trim()
+                    .to_owned(),
+            })
+        }
+    }
+}
+
+/// Keychain access via `security`
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{Invocation, KeyringEntry};
+
+    /// Exit code when the item isn't in the keychain
+    const NOT_FOUND: i32 = 44;
+
+    pub fn get(entry: &KeyringEntry) -> Invocation {
+        invocation("find-generic-password", entry, &["-w"])
+    }
+
+    pub fn set(entry: &KeyringEntry, secret: &str) -> Invocation {
+        // `security` only accepts the secret as an argument. -U updates the
+        // item if it already exists
+        invocation("add-generic-password", entry, &["-U", "-w", secret])
+    }
+
+    pub fn delete(entry: &KeyringEntry) -> Invocation {
+        invocation("delete-generic-password", entry, &[])
+    }
+
+    fn invocation(
+        command: &str,
+        entry: &KeyringEntry,
+        extra: &[&str],
+    ) -> Invocation {
+        let arguments = [
+            command,
+            "-s",
+            entry.service.as_str(),
+            "-a",
+            entry.username.as_str(),
+        ]
+        .into_iter()
+        .chain(extra.iter().copied())
+        .map(String::from)
+        .collect();
+        Invocation {
+            program: "security",
+            arguments,
+            env: Vec::new(),
+            stdin: None,
+            is_missing: |output| output.status.code() == Some(NOT_FOUND),
+        }
+    }
+}
+
+/// Credential Manager access via the WinRT password vault in PowerShell. The
+/// entry is passed via environment variables to avoid quoting issues.
+#[cfg(windows)]
+mod platform {
+    use super::{Invocation, KeyringEntry};
+
+    /// Exit code our scripts use when the entry doesn't exist
+    const NOT_FOUND: i32 = 44;
+
+    pub fn get(entry: &KeyringEntry) -> Invocation {
+        invocation(
+            entry,
+            "$c = $vault.Retrieve($service, $username); \
+            $c.RetrievePassword(); [Console]::Out.Write($c.Password)",
+            None,
+        )
+    }
+
+    pub fn set(entry: &KeyringEntry, secret: &str) -> Invocation {
+        invocation(
+            entry,
+            "$secret = [Console]::In.ReadToEnd(); \
+            $vault.Add((New-Object Windows.Security.Credentials.PasswordCredential(\
+            $service, $username, $secret)))",
+            Some(secret),
+        )
+    }
+
+    pub fn delete(entry: &KeyringEntry) -> Invocation {
+        invocation(
+            entry,
+            "$vault.Remove($vault.Retrieve($service, $username))",
+            None,
+        )
+    }
+
+    fn invocation(
+        entry: &KeyringEntry,
+        script: &str,
+        stdin: Option<&str>,
+    ) -> Invocation {
+        // Retrieve() throws if the entry doesn't exist
+        let script = format!(
+            "$ErrorActionPreference = 'Stop'; \
+            [void][Windows.Security.Credentials.PasswordVault,\
+            Windows.Security.Credentials,ContentType=WindowsRuntime]; \
+            $vault = New-Object Windows.Security.Credentials.PasswordVault; \
+            $service = $env:SLUMBER_KEYRING_SERVICE; \
+            $username = $env:SLUMBER_KEYRING_USERNAME; \
+            try {{ {script} }} catch {{ \
+                if ($_.Exception.HResult -eq -2147023728) {{ exit {NOT_FOUND} }} \
+                throw \
+            }}"
+        );
+        Invocation {
+            program: "powershell",
+            arguments: vec![
+                "-NoProfile".into(),
+                "-NonInteractive".into(),
+                "-Command".into(),
+                script,
+            ],
+            env: vec![
+                ("SLUMBER_KEYRING_SERVICE", entry.service.clone()),
+                ("SLUMBER_KEYRING_USERNAME", entry.username.clone()),
+            ],
+            stdin: stdin.map(String::from),
+            is_missing: |output| output.status.code() == Some(NOT_FOUND),
+        }
+    }
+}
+
+/// Secret Service access via `secret-tool` (from libsecret)
+#[cfg(not(any(target_os = "macos", windows)))]
+mod platform {
+    use super::{Invocation, KeyringEntry};
+
+    pub fn get(entry: &KeyringEntry) -> Invocation {
+        invocation(&["lookup"], entry, None)
+    }
+
+    pub fn set(entry: &KeyringEntry, secret: &str) -> Invocation {
+        let label =
+            format!("--label=Slumber: {} ({})", entry.service, entry.username);
+        invocation(&["store", &label], entry, Some(secret))
+    }
+
+    pub fn delete(entry: &KeyringEntry) -> Invocation {
+        // `clear` succeeds even if nothing matched, so we can't tell whether
+        // the entry existed
+        invocation(&["clear"], entry, None)
+    }
+
+    fn invocation(
+        command: &[&str],
+        entry: &KeyringEntry,
+        stdin: Option<&str>,
+    ) -> Invocation {
+        let arguments = command
+            .iter()
+            .copied()
+            .chain(["service", &entry.service, "username", &entry.username])
+            .map(String::from)
+            .collect();
+        Invocation {
+            program: "secret-tool",
+            arguments,
+            env: Vec::new(),
+            stdin: stdin.map(String::from),
+            // A missing entry is a silent failure; real errors are reported
+            is_missing: |output| {
+                output.status.code() == Some(1) && output.stderr.is_empty()
+            },
+        }
+    }
+}
+
+/// An error accessing the OS credential store
+#[derive(Debug, Error)]
+pub enum KeyringError {
+    /// Couldn't run the credential store tool, most likely because it isn't
+    /// installed
+    #[error("Executing `{program}`")]
+    Spawn {
+        program: &'static str,
+        #[source]
+        error: io::Error,
+    },
+
+    /// The credential store tool failed
+    #[error("`{program}` exited with {status}: {stderr}")]
+    Status {
+        program: &'static str,
+        status: ExitStatus,
+        stderr: String,
+    },
+
+    /// Stored secret isn't valid text
+    #[error("Secret is not valid UTF-8")]
+    InvalidUtf8(#[from] FromUtf8Error),
+}
@@ -2,12 +2,32 @@
 //!
 //! **This crate is not semver compliant**. The version is locked to the root
 //! `slumber` crate version. If you choose to depend directly on this crate, you
-//! do so at your own risk of breakage.
+//! do so at your own risk of breakage. To embed Slumber's request engine in
+//! another program, use the `slumber_engine` crate instead, which provides a
+//! stable API.
+//!
+//! ## Features
+//!
+//! - `native` (default): The HTTP engine, template render context, request
+//!   history, and everything else that needs the network, file system,
+//!   subprocesses, or a database. Disable default features to get just the
+//!   collection layer (loading, parsing, and validating collections), which has
+//!   no system dependencies and compiles to `wasm32-unknown-unknown`.
 
 pub mod collection;
+#[cfg(feature = "native")]
 pub mod database;
+#[cfg(feature = "native")]
+pub mod error;
+#[cfg(feature = "native")]
 pub mod http;
+#[cfg(feature = "native")]
+pub mod ipc;
+#[cfg(feature = "native")]
+pub mod keyring;
+#[cfg(feature = "native")]
 pub mod render;
 #[cfg(any(test, feature = "test"))]
 pub mod test_util;
+#[cfg(feature = "native")]
 pub mod util;
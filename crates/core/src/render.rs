@@ -2,22 +2,32 @@
 //! [slumber_template], with context and functions specific to rendering HTTP
 //! requests.
 
+mod audit;
 mod functions;
+mod preflight;
 #[cfg(test)]
 mod tests;
 mod util;
 
+pub use audit::{AuditEvent, Auditor};
+pub use preflight::MissingValue;
+pub use util::CommandCache;
+
 #[cfg(any(test, feature = "test"))]
 use crate::collection::Recipe;
 use crate::{
-    collection::{Collection, Profile, ProfileId, RecipeId},
+    collection::{
+        Collection, Profile, ProfileId, RecipeId, RecipeParam, RecipeParamType,
+        RenderMode, ToggleId,
+    },
     http::{
-        Exchange, RequestSeed, ResponseRecord, StoredRequestError,
-        TriggeredRequestError,
+        CaptureStore, CookieJar, Exchange, RequestSeed, ResponseRecord,
+        StoredRequestError, TokenStore, TriggeredRequestError,
     },
+    keyring::KeyringError,
     render::{
-        functions::RequestTrigger,
-        util::{FieldCache, FieldCacheOutcome},
+        functions::{FileFormat, RequestTrigger},
+        util::{FutureCache, FutureCacheGuard, FutureCacheOutcome},
     },
 };
 use async_trait::async_trait;
@@ -26,11 +36,20 @@ use derive_more::{Deref, From, derive::Display};
 use indexmap::IndexMap;
 use itertools::Itertools;
 use serde::Deserialize;
+use slumber_config::TemplateSource;
 use slumber_template::{
-    Arguments, Identifier, LazyValue, RenderError, Template, Value,
+    Arguments, Expression, Identifier, LazyValue, Literal, RenderError,
+    Template, Value,
 };
+use slumber_util::{ResultTraced, TimeSpan};
 use std::{
-    fmt::Debug, io, iter, path::PathBuf, process::ExitStatus, sync::Arc,
+    collections::HashMap,
+    fmt::Debug,
+    io, iter, mem,
+    path::PathBuf,
+    process::ExitStatus,
+    sync::{self, Arc},
+    time::Duration,
 };
 use thiserror::Error;
 use tokio::sync::oneshot;
@@ -46,6 +65,7 @@ use tracing::error;
 /// wrapper for each individual render. Use [Self::streaming] to get the wrapper
 /// for a render.
 #[derive(Debug)]
+#[expect(clippy::struct_excessive_bools)] // These are all independent toggles
 pub struct TemplateContext {
     /// Entire request collection
     pub collection: Arc<Collection>,
@@ -53,6 +73,10 @@ pub struct TemplateContext {
     /// the caller should check the ID is valid before passing it, to
     /// provide a better error to the user if not.
     pub selected_profile: Option<ProfileId>,
+    /// IDs of the toggles whose data should be layered on top of the selected
+    /// profile. If multiple toggles define the same field, the last one wins.
+    /// Unknown IDs are ignored.
+    pub enabled_toggles: Vec<ToggleId>,
     /// An interface to allow accessing and sending HTTP chained requests
     pub http_provider: Box<dyn HttpProvider>,
     /// Additional profile key=value overrides passed directly from the user.
@@ -68,9 +92,46 @@ pub struct TemplateContext {
     /// Directory in which to run file system operations. Should be the
     /// directory containing the collection file.
     pub root_dir: PathBuf,
+    /// Is the collection untrusted? If so, functions that access the local
+    /// system (`command()`, `file()`) are disabled, and so is every recipe
+    /// and profile field that reaches outside the request URL (e.g. `socket`,
+    /// `proxy`). See
+    /// [CollectionFile::is_trusted](crate::collection::CollectionFile::is_trusted)
+    pub sandboxed: bool,
     /// State that should be shared across all renders that use this context.
     /// This is meant to be opaque; just use [Default::default] to initialize.
     pub state: RenderGroupState,
+    /// Records and gates functions with external effects, e.g. `command()`
+    pub auditor: Auditor,
+    /// Cache for the output of `command()` calls with the `cache` argument.
+    /// Unlike `state`, this should be shared across render groups, so that
+    /// cached values are reused for subsequent requests and previews.
+    pub command_cache: CommandCache,
+    /// Values captured from responses via the recipe `capture` field. Like
+    /// `command_cache`, this should be shared across render groups. Captured
+    /// values take precedence over profile fields, but not overrides.
+    pub captures: CaptureStore,
+    /// Render mode for every render in the group, regardless of what each
+    /// recipe declares. `None` uses each recipe's own mode.
+    pub render_mode: Option<RenderMode>,
+    /// OAuth2 access tokens. Like `command_cache`, this should be shared
+    /// across render groups so tokens are reused until they expire.
+    pub oauth2_tokens: TokenStore,
+    /// Cookies set by responses, attached to later requests. Like
+    /// `oauth2_tokens`, this should be shared across render groups.
+    pub cookies: CookieJar,
+    /// Values for recipe parameters, by name. These apply to every recipe in
+    /// the render group that declares a parameter of the same name. A
+    /// parameter without a value here uses its default.
+    pub params: IndexMap<String, Template>,
+    /// Ask the user for each recipe parameter that isn't in `params`, with
+    /// the default pre-filled. Enabled for request builds in the TUI.
+    pub prompt_params: bool,
+    /// Before building a request, ask the user for every profile field and
+    /// required parameter that's missing, all at once. If disabled, the build
+    /// fails with a single error listing them. Enabled for request builds in
+    /// the TUI.
+    pub prompt_missing: bool,
 }
 
 impl TemplateContext {
@@ -80,21 +141,87 @@ impl TemplateContext {
         SingleRenderContext {
             context: self,
             can_stream,
+            render_mode: self.render_mode.unwrap_or_default(),
+            params: None,
         }
     }
 
-    fn current_profile(&self) -> Option<&Profile> {
+    /// Prepare this context to rebuild a request whose authentication was
+    /// rejected. All render state is reset, and every upstream request
+    /// referenced via `response()` or `response_header()` will be sent again
+    /// regardless of its trigger, so any tokens derived from them are fresh.
+    /// OAuth2 tokens are fetched again as well.
+    pub fn reauthenticate(&mut self) {
+        self.state = RenderGroupState {
+            refresh_responses: true,
+            // Don't ask for parameters or missing fields again
+            param_cache: mem::take(&mut self.state.param_cache),
+            prompted_fields: mem::take(&mut self.state.prompted_fields),
+            ..RenderGroupState::default()
+        };
+    }
+
+    /// Is this context rebuilding a request whose authentication was
+    /// rejected? See [Self::reauthenticate]
+    pub(crate) fn is_reauthenticating(&self) -> bool {
+        self.state.refresh_responses
+    }
+
+    pub(crate) fn current_profile(&self) -> Option<&Profile> {
         self.selected_profile
             .as_ref()
             .and_then(|id| self.collection.profiles.get(id))
     }
 
+    /// Get a field's template from the enabled toggles. Later toggles take
+    /// precedence over earlier ones
+    fn toggle_field(&self, field: &str) -> Option<&Template> {
+        self.enabled_toggles
+            .iter()
+            .rev()
+            .filter_map(|id| self.collection.toggles.get(id))
+            .find_map(|toggle| toggle.data.get(field))
+    }
+
     /// Get the most recent response for a profile+recipe pair. This will
-    /// trigger the request if it is expired, and await the response
+    /// trigger the request if it is expired, and await the response.
+    ///
+    /// The response is memoized for the render group, so multiple references
+    /// to the same recipe with the same trigger will only load or send the
+    /// request once.
     async fn get_latest_response(
         &self,
         recipe_id: &RecipeId,
         trigger: RequestTrigger,
+    ) -> Result<Arc<ResponseRecord>, FunctionError> {
+        // When reauthenticating, everything upstream has to be refreshed
+        let trigger = if self.state.refresh_responses {
+            RequestTrigger::Always
+        } else {
+            trigger
+        };
+        let guard = match self
+            .state
+            .response_cache
+            .get_or_init((recipe_id.clone(), trigger))
+            .await
+        {
+            FutureCacheOutcome::Hit(response) => return Ok(response),
+            FutureCacheOutcome::Miss(guard) => guard,
+        };
+        // If this fails, the guard is dropped and the next caller will retry
+        let response =
+            self.get_latest_response_inner(recipe_id, trigger).await?;
+        guard.set(Arc::clone(&response));
+        Ok(response)
+    }
+
+    /// Load or send the request for [Self::get_latest_response], bypassing the
+    /// response cache
+    async fn get_latest_response_inner(
+        &self,
+        recipe_id: &RecipeId,
+        trigger: RequestTrigger,
     ) -> Result<Arc<ResponseRecord>, FunctionError> {
         // First, make sure it's a valid recipe. Technically it's possible to
         // return a cached response for a recipe that's no longer in the
@@ -132,6 +259,16 @@ impl TemplateContext {
                     _ => self.send_request(recipe_id).await?,
                 }
             }
+            RequestTrigger::TokenExpiry => {
+                match self.get_latest_cached(recipe_id).await? {
+                    Some(exchange)
+                        if !exchange.token_needs_refresh(Utc::now()) =>
+                    {
+                        exchange
+                    }
+                    _ => self.send_request(recipe_id).await?,
+                }
+            }
             RequestTrigger::Always => self.send_request(recipe_id).await?,
         };
 
@@ -167,7 +304,11 @@ impl TemplateContext {
         // to implement so I'm going with that for now.
         let build_options = Default::default();
 
-        self.http_provider
+        self.auditor
+            .confirm(&*self.prompter, TemplateSource::Request, recipe_id)
+            .await?;
+        let exchange = self
+            .http_provider
             .send_request(
                 RequestSeed::new(recipe_id.clone(), build_options),
                 self,
@@ -176,7 +317,13 @@ impl TemplateContext {
             .map_err(|error| FunctionError::Trigger {
                 recipe_id: recipe_id.clone(),
                 error,
-            })
+            })?;
+        self.auditor
+            .record(TemplateSource::Request, recipe_id.to_string());
+        // Upstream requests capture values just like any other request. A
+        // failed capture shouldn't fail the render that triggered it though
+        let _ = self.captures.capture(&exchange, &self.collection).traced();
+        Ok(exchange)
     }
 }
 
@@ -192,49 +339,198 @@ pub struct SingleRenderContext<'a> {
     /// Is streaming supported for this component? Enabled for request bodies,
     /// disabled for everything else.
     can_stream: bool,
+    /// What to do with references to undefined profile fields
+    render_mode: RenderMode,
+    /// Parameters declared by the recipe being rendered. Fields with these
+    /// names are resolved as parameters first
+    params: Option<&'a IndexMap<String, RecipeParam>>,
 }
 
-impl slumber_template::Context for SingleRenderContext<'_> {
-    fn can_stream(&self) -> bool {
-        self.can_stream
+impl<'a> SingleRenderContext<'a> {
+    /// Use a recipe's render mode for this render. The mode set on the
+    /// [TemplateContext], if any, takes precedence.
+    #[must_use]
+    pub fn with_render_mode(mut self, render_mode: Option<RenderMode>) -> Self {
+        self.render_mode =
+            self.context.render_mode.or(render_mode).unwrap_or_default();
+        self
     }
 
-    async fn get_field(
+    /// Resolve fields named after a recipe's parameters as those parameters
+    #[must_use]
+    pub fn with_params(
+        mut self,
+        params: &'a IndexMap<String, RecipeParam>,
+    ) -> Self {
+        self.params = Some(params);
+        self
+    }
+
+    /// Get a value for a field that isn't defined in the overrides, captures,
+    /// or current profile. Depending on the render mode, this is an error,
+    /// an empty string, or the user's answer to a prompt.
+    async fn undefined_field(
         &self,
         field: &Identifier,
-    ) -> Result<LazyValue, RenderError> {
-        // Check the field cache to see if this value is already being computed
-        // somewhere else. If it is, we'll block on that and re-use the result.
-        // If not, we get a guard back, meaning we're responsible for the
-        // computation. At the end, we'll write back to the guard so everyone
-        // else can copy our homework.
+    ) -> Result<Value, FunctionError> {
+        match self.render_mode {
+            RenderMode::Strict => Err(FunctionError::UnknownField {
+                field: field.to_string(),
+            }),
+            RenderMode::Lenient => Ok(Value::from("")),
+            RenderMode::Prompt => {
+                let (tx, rx) = oneshot::channel();
+                self.prompter.prompt(Prompt::Text {
+                    message: field.to_string(),
+                    default: None,
+                    sensitive: false,
+                    channel: tx.into(),
+                });
+                let output =
+                    rx.await.map_err(|_| FunctionError::PromptNoReply)?;
+                Ok(output.into())
+            }
+        }
+    }
+
+    /// Get the value of a recipe parameter. The value is cached for the render
+    /// group, so the user is only asked once. `None` means the parameter is
+    /// optional and wasn't given, so the field falls back to the profile.
+    async fn get_param(
+        &self,
+        field: &Identifier,
+        param: &RecipeParam,
+    ) -> Result<Option<Value>, RenderError> {
         let guard = match self
             .context
             .state
-            .field_cache
+            .param_cache
             .get_or_init(field.clone())
             .await
         {
-            FieldCacheOutcome::Hit(value) => return Ok(value.into()),
-            FieldCacheOutcome::Miss(guard) => guard,
+            FutureCacheOutcome::Hit(value) => return Ok(value),
+            FutureCacheOutcome::Miss(guard) => guard,
         };
 
-        // We're responsible for the computation. Grab the field's value
-        let template = self
-            // Check overrides first
-            .context
-            .overrides
-            .get(field.as_str())
-            .or_else(|| {
-                // Check the current profile
-                let profile = self.context.current_profile()?;
-                profile.data.get(field.as_str())
+        let value =
+            if let Some(template) = self.context.params.get(field.as_str()) {
+                Some(self.render_param(field, template).await?)
+            } else if self.context.prompt_params
+                || (self.context.prompt_missing
+                    && param.required
+                    && param.default.is_none())
+            {
+                self.prompt_param(field, param).await?
+            } else if let Some(default) = &param.default {
+                Some(self.render_param(field, default).await?)
+            } else {
+                None
+            };
+
+        let value = match value {
+            Some(value) => {
+                Some(convert_param(param.param_type, value).map_err(
+                    |value| FunctionError::ParamType {
+                        param: field.clone(),
+                        expected: param.param_type,
+                        value: value.to_string(),
+                    },
+                )?)
+            }
+            None if param.required => {
+                return Err(FunctionError::ParamMissing {
+                    param: field.clone(),
+                }
+                .into());
+            }
+            None => None,
+        };
+        guard.set(value.clone());
+        Ok(value)
+    }
+
+    /// Render the template for a parameter's value or default
+    async fn render_param(
+        &self,
+        field: &Identifier,
+        template: &Template,
+    ) -> Result<Value, RenderError> {
+        template
+            .render(self)
+            .await
+            .try_collect_value()
+            .await
+            .map_err(|error| {
+                FunctionError::ParamNested {
+                    param: field.clone(),
+                    error,
+                }
+                .into()
             })
-            .ok_or_else(|| FunctionError::UnknownField {
-                field: field.to_string(),
-            })?;
+    }
+
+    /// Ask the user for a parameter's value. An empty answer means no value
+    async fn prompt_param(
+        &self,
+        field: &Identifier,
+        param: &RecipeParam,
+    ) -> Result<Option<Value>, RenderError> {
+        let default = match &param.default {
+            Some(default) => Some(
+                self.render_param(field, default)
+                    .await?
+                    .try_into_string()
+                    .map_err(|error| RenderError::from(error.error))?,
+            ),
+            None => None,
+        };
+        let message = match &param.description {
+            Some(description) => format!("{field} - {description}"),
+            None => field.to_string(),
+        };
+
+        if param.param_type == RecipeParamType::Boolean {
+            // Put the default first so it's preselected
+            let mut options = vec![true, false];
+            if default.as_deref() == Some("false") {
+                options.reverse();
+            }
+            let (tx, rx) = oneshot::channel();
+            self.prompter.prompt(Prompt::Select {
+                message,
+                options: options
+                    .into_iter()
+                    .map(|option| SelectOption {
+                        label: option.to_string(),
+                        value: option.into(),
+                    })
+                    .collect(),
+                channel: tx.into(),
+            });
+            let value = rx.await.map_err(|_| FunctionError::SelectNoReply)?;
+            Ok(Some(value))
+        } else {
+            let (tx, rx) = oneshot::channel();
+            self.prompter.prompt(Prompt::Text {
+                message,
+                default,
+                sensitive: false,
+                channel: tx.into(),
+            });
+            let value = rx.await.map_err(|_| FunctionError::PromptNoReply)?;
+            Ok((!value.is_empty()).then(|| value.into()))
+        }
+    }
 
-        // Render the nested template
+    /// Render the template for a profile field or variable. If the output is a
+    /// value, write it back to the cache. A render error is wrapped with
+    /// `wrap_error` to identify the field/variable it came from.
+    async fn render_nested(
+        &self,
+        template: &Template,
+        guard: FutureCacheGuard<GroupValue>,
+        wrap_error: impl FnOnce(RenderError) -> FunctionError,
+    ) -> Result<LazyValue, RenderError> {
         let output = template.render(self).await;
 
         // If the output is a value, we can cache it. If it's a stream, it can't
@@ -249,19 +545,122 @@ impl slumber_template::Context for SingleRenderContext<'_> {
             // stream
             Ok(output.unpack())
         } else {
-            let value = output.try_collect_value().await.map_err(
-                // We *could* just return the error, but wrap it to give
-                // additional context
-                |error| {
-                    RenderError::from(FunctionError::ProfileNested {
-                        field: field.clone(),
-                        error,
-                    })
-                },
-            )?;
+            // We *could* just return the error, but wrap it to give additional
+            // context
+            // Keep track of cached values within the template, so references
+            // to this one can show how fresh it is
+            let cached_age = output.cached_age();
+            let value = output
+                .try_collect_value()
+                .await
+                .map_err(|error| RenderError::from(wrap_error(error)))?;
+            let value = GroupValue { value, cached_age };
             guard.set(value.clone());
-            Ok(LazyValue::Value(value))
+            Ok(value.into())
+        }
+    }
+}
+
+impl slumber_template::Context for SingleRenderContext<'_> {
+    fn can_stream(&self) -> bool {
+        self.can_stream
+    }
+
+    async fn get_field(
+        &self,
+        field: &Identifier,
+    ) -> Result<LazyValue, RenderError> {
+        // Parameters of the recipe being rendered shadow everything else. An
+        // optional parameter with no value falls through to the profile
+        if let Some(param) =
+            self.params.and_then(|params| params.get(field.as_str()))
+            && let Some(value) = self.get_param(field, param).await?
+        {
+            return Ok(LazyValue::Value(value));
+        }
+
+        // Check the field cache to see if this value is already being computed
+        // somewhere else. If it is, we'll block on that and re-use the result.
+        // If not, we get a guard back, meaning we're responsible for the
+        // computation. At the end, we'll write back to the guard so everyone
+        // else can copy our homework.
+        let guard = match self
+            .context
+            .state
+            .field_cache
+            .get_or_init(field.clone())
+            .await
+        {
+            FutureCacheOutcome::Hit(value) => return Ok(value.into()),
+            FutureCacheOutcome::Miss(guard) => guard,
+        };
+
+        // We're responsible for the computation. Check overrides first
+        let template_override = self.context.overrides.get(field.as_str());
+        // Then values captured from previous responses. These are already
+        // rendered, so there's nothing more to do
+        if template_override.is_none()
+            && let Some(value) = self.context.captures.get(field.as_str())
+        {
+            guard.set(value.clone().into());
+            return Ok(LazyValue::Value(value));
         }
+
+        // Grab the field's template
+        let template = template_override
+            .or_else(|| self.context.toggle_field(field.as_str()))
+            .or_else(|| {
+                // Check the current profile
+                let profile = self.context.current_profile()?;
+                profile.data.get(field.as_str())
+            });
+        let Some(template) = template else {
+            // Field isn't defined anywhere. The render mode decides what to do
+            let value = self.undefined_field(field).await?;
+            guard.set(value.clone().into());
+            return Ok(LazyValue::Value(value));
+        };
+
+        self.render_nested(template, guard, |error| {
+            FunctionError::ProfileNested {
+                field: field.clone(),
+                error,
+            }
+        })
+        .await
+    }
+
+    async fn get_variable(
+        &self,
+        variable: &Identifier,
+    ) -> Result<LazyValue, RenderError> {
+        // Variables don't vary by profile and can't be overridden, so an
+        // unknown variable is always an error regardless of render mode
+        let Some(template) =
+            self.context.collection.variables.get(variable.as_str())
+        else {
+            return Err(RenderError::VariableUnknown {
+                variable: variable.clone(),
+            });
+        };
+
+        let guard = match self
+            .context
+            .state
+            .variable_cache
+            .get_or_init(variable.clone())
+            .await
+        {
+            FutureCacheOutcome::Hit(value) => return Ok(value.into()),
+            FutureCacheOutcome::Miss(guard) => guard,
+        };
+        self.render_nested(template, guard, |error| {
+            FunctionError::VariableNested {
+                variable: variable.clone(),
+                error,
+            }
+        })
+        .await
     }
 
     async fn call(
@@ -272,11 +671,13 @@ impl slumber_template::Context for SingleRenderContext<'_> {
         match function_name.as_str() {
             "base64" => functions::base64(arguments),
             "boolean" => functions::boolean(arguments),
-            "command" => functions::command(arguments),
+            "command" => functions::command(arguments).await,
             "concat" => functions::concat(arguments),
             "debug" => functions::debug(arguments),
-            "env" => functions::env(arguments),
-            "file" => functions::file(arguments),
+            "env" => functions::env(arguments).await,
+            "fallback" => functions::fallback(arguments),
+            "file" => functions::file(arguments).await,
+            "file_select" => functions::file_select(arguments).await,
             "float" => functions::float(arguments),
             "index" => functions::index(arguments),
             "integer" => functions::integer(arguments),
@@ -284,6 +685,7 @@ impl slumber_template::Context for SingleRenderContext<'_> {
             "jq" => functions::jq(arguments),
             "json_parse" => functions::json_parse(arguments),
             "jsonpath" => functions::jsonpath(arguments),
+            "keyring" => functions::keyring(arguments).await,
             "lower" => functions::lower(arguments),
             "prompt" => functions::prompt(arguments).await,
             "replace" => functions::replace(arguments),
@@ -294,6 +696,7 @@ impl slumber_template::Context for SingleRenderContext<'_> {
             "slice" => functions::slice(arguments),
             "split" => functions::split(arguments),
             "string" => functions::string(arguments),
+            "totp" => functions::totp(arguments),
             "trim" => functions::trim(arguments),
             "upper" => functions::upper(arguments),
             _ => Err(RenderError::FunctionUnknown),
@@ -334,11 +737,15 @@ impl
         Self {
             collection: Collection {
                 name: None,
+                template_delimiters: None,
+                variables: IndexMap::new(),
                 recipes: recipes.into(),
                 profiles,
+                toggles: IndexMap::new(),
             }
             .into(),
             selected_profile,
+            enabled_toggles: Vec::new(),
             http_provider: Box::new(TestHttpProvider::new(
                 CollectionDatabase::factory(()),
                 None,
@@ -346,12 +753,65 @@ impl
             overrides: IndexMap::new(),
             prompter: Box::<TestPrompter>::default(),
             root_dir: test_data_dir(),
+            sandboxed: false,
             show_sensitive: true,
             state: Default::default(),
+            auditor: Default::default(),
+            command_cache: Default::default(),
+            captures: Default::default(),
+            render_mode: None,
+            oauth2_tokens: Default::default(),
+            cookies: Default::default(),
+            params: IndexMap::new(),
+            prompt_params: false,
+            prompt_missing: false,
         }
     }
 }
 
+/// Something defined elsewhere in the collection that a template refers to.
+/// Used to navigate from a template to the definitions it depends on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateReference {
+    /// A field access (`{{ host }}`), which is defined in the current profile
+    ProfileField(Identifier),
+    /// A recipe whose response is loaded via `response()` or
+    /// `response_header()`
+    Recipe(RecipeId),
+}
+
+impl TemplateReference {
+    /// Find everything a template references, in the order they appear.
+    /// Duplicates are omitted. Recipe references can only be found when the
+    /// recipe ID is given as a string literal.
+    pub fn find_all(template: &Template) -> Vec<Self> {
+        let mut references = Vec::new();
+        template.walk_expressions(|expression| {
+            let reference = match expression {
+                Expression::Field(field) => Self::ProfileField(field.clone()),
+                Expression::Call(call) | Expression::Pipe { call, .. }
+                    if matches!(
+                        call.function().as_str(),
+                        "response" | "response_header"
+                    ) =>
+                {
+                    let Some(Expression::Literal(Literal::String(recipe_id))) =
+                        call.position().first()
+                    else {
+                        return;
+                    };
+                    Self::Recipe(recipe_id.clone().into())
+                }
+                _ => return,
+            };
+            if !references.contains(&reference) {
+                references.push(reference);
+            }
+        });
+        references
+    }
+}
+
 /// State for a render group, which consists of one or more related renders
 /// (e.g. all the template renders for a single recipe). This state is stored in
 /// the template context.
@@ -362,7 +822,55 @@ pub struct RenderGroupState {
     /// times. If a field fails to render, the guard holder should drop the
     /// guard without entering a result. This will kill the entire render so
     /// other renderers of that field will be cancelled.
-    field_cache: FieldCache,
+    field_cache: FutureCache<Identifier, GroupValue>,
+    /// Cache the result of each collection variable, with the same semantics
+    /// as `field_cache`
+    variable_cache: FutureCache<Identifier, GroupValue>,
+    /// Cache the value of each recipe parameter. `None` is an optional
+    /// parameter that wasn't given. Unlike the other caches, this survives
+    /// [TemplateContext::reauthenticate], so the user isn't asked again.
+    param_cache: FutureCache<Identifier, Option<Value>>,
+    /// Answers given for missing fields during pre-flight validation. Like
+    /// `param_cache`, this survives [TemplateContext::reauthenticate].
+    prompted_fields: sync::Mutex<HashMap<Identifier, Value>>,
+    /// Cache the response for each recipe that's referenced via `response()`
+    /// or `response_header()`. If a recipe is referenced multiple times in a
+    /// render group with the same trigger, the request will only be
+    /// fetched/triggered once. References with different triggers are cached
+    /// separately, because they may resolve to different responses (e.g. one
+    /// loads from history while another sends a new request).
+    response_cache:
+        FutureCache<(RecipeId, RequestTrigger), Arc<ResponseRecord>>,
+    /// Send every upstream request again instead of loading from history,
+    /// regardless of trigger. Set by [TemplateContext::reauthenticate].
+    refresh_responses: bool,
+}
+
+/// A rendered profile field or collection variable, cached in the render group
+#[derive(Clone, Debug)]
+pub(crate) struct GroupValue {
+    value: Value,
+    /// Age of the oldest cached value (e.g. a cached command's output) that
+    /// this value was rendered from. `None` if nothing in it was cached.
+    cached_age: Option<Duration>,
+}
+
+impl From<Value> for GroupValue {
+    fn from(value: Value) -> Self {
+        Self {
+            value,
+            cached_age: None,
+        }
+    }
+}
+
+impl From<GroupValue> for LazyValue {
+    fn from(GroupValue { value, cached_age }: GroupValue) -> Self {
+        match cached_age {
+            Some(age) => Self::Cached { value, age },
+            None => Self::Value(value),
+        }
+    }
 }
 
 /// An abstraction that provides behavior for chained HTTP requests. This
@@ -459,6 +967,43 @@ impl<T> ReplyChannel<T> {
     }
 }
 
+/// Convert a recipe parameter's value to the parameter's type. Values typed
+/// by the user are strings, so those are parsed. Return the original value if
+/// it can't be converted.
+fn convert_param(
+    param_type: RecipeParamType,
+    value: Value,
+) -> Result<Value, Value> {
+    match (param_type, value) {
+        (RecipeParamType::String, value) => value
+            .try_into_string()
+            .map(Value::from)
+            .map_err(|error| error.value),
+        (RecipeParamType::Integer, value @ Value::Integer(_))
+        | (RecipeParamType::Float, value @ Value::Float(_))
+        | (RecipeParamType::Boolean, value @ Value::Boolean(_)) => Ok(value),
+        (RecipeParamType::Float, Value::Integer(i)) => {
+            Ok(Value::Float(i as f64))
+        }
+        (param_type, Value::String(s)) => {
+            let parsed = match param_type {
+                RecipeParamType::Integer => {
+                    s.trim().parse().ok().map(Value::Integer)
+                }
+                RecipeParamType::Float => {
+                    s.trim().parse().ok().map(Value::Float)
+                }
+                RecipeParamType::Boolean => {
+                    s.trim().parse().ok().map(Value::Boolean)
+                }
+                RecipeParamType::String => unreachable!("handled above"),
+            };
+            parsed.ok_or(Value::String(s))
+        }
+        (_, value) => Err(value),
+    }
+}
+
 /// An error that can occur within a template function
 #[derive(Debug, Error)]
 pub enum FunctionError {
@@ -488,6 +1033,17 @@ pub enum FunctionError {
         status: ExitStatus,
     },
 
+    /// Command was still running after its timeout, so it was killed
+    #[error(
+        "Command `{command}` timed out after {timeout}",
+        command = iter::once(program).chain(arguments).format(" "),
+    )]
+    CommandTimeout {
+        program: String,
+        arguments: Vec<String>,
+        timeout: TimeSpan,
+    },
+
     /// User passed an empty command arrary
     #[error("Command must have at least one element")]
     CommandEmpty,
@@ -500,6 +1056,29 @@ pub enum FunctionError {
         error: io::Error,
     },
 
+    /// `file_select()` couldn't determine the file format from its extension
+    #[error(
+        "Cannot determine format of file `{path}`; pass `format` as one of \
+        `json`, `yaml`, or `csv`"
+    )]
+    FileFormatUnknown { path: PathBuf },
+
+    /// `file_select()` failed to parse a file in its format
+    #[error("Parsing file `{path}` as {format}: {error}")]
+    FileParse {
+        path: PathBuf,
+        format: FileFormat,
+        error: String,
+    },
+
+    /// User declined a confirmation prompt for a function with external
+    /// effects
+    #[error("`{kind}` access to `{detail}` was denied")]
+    Denied {
+        kind: TemplateSource,
+        detail: String,
+    },
+
     /// Error decoding bytes as UTF-8
     #[error(transparent)]
     InvalidUtf8(#[from] std::string::FromUtf8Error),
@@ -517,6 +1096,12 @@ pub enum FunctionError {
         serde_json::Error,
     ),
 
+    /// Invalid JSONPath query given as a string. Most JSONPath queries are
+    /// parsed during argument conversion; this is for queries that are built
+    /// from other arguments
+    #[error(transparent)]
+    JsonPathParse(serde_json_path::ParseError),
+
     /// jq/JSONPath query returned no results when it should have
     #[error("No results from JSON query `{query}`")]
     JsonQueryNoResults { query: String },
@@ -528,6 +1113,42 @@ pub enum FunctionError {
     )]
     JsonQueryTooMany { query: String, actual_count: usize },
 
+    /// Error accessing the OS credential store
+    #[error(transparent)]
+    Keyring(#[from] KeyringError),
+
+    /// `keyring()` entry doesn't exist
+    #[error(
+        "No keyring entry for service `{service}` and user `{username}`. \
+        Add one with `slumber keyring set {service} {username}`"
+    )]
+    KeyringMissing { service: String, username: String },
+
+    /// Pre-flight validation found values that the recipe depends on, but
+    /// aren't defined
+    #[error("Missing values: {}", values.iter().join(", "))]
+    MissingValues { values: Vec<MissingValue> },
+
+    /// Recipe parameter has no value and no default, but is required
+    #[error("Missing value for required parameter `{param}`")]
+    ParamMissing { param: Identifier },
+
+    /// A bubbled-up error from rendering a recipe parameter's value
+    #[error("Rendering parameter `{param}`")]
+    ParamNested {
+        param: Identifier,
+        #[source]
+        error: RenderError,
+    },
+
+    /// Recipe parameter value can't be converted to the parameter's type
+    #[error("Parameter `{param}` expects {expected}, but got {value}")]
+    ParamType {
+        param: Identifier,
+        expected: RecipeParamType,
+        value: String,
+    },
+
     /// An bubbled-up error from rendering a profile field value
     #[error("Rendering profile field `{field}`")]
     ProfileNested {
@@ -555,6 +1176,14 @@ pub enum FunctionError {
     #[error("No response available")]
     ResponseMissing,
 
+    /// A function that accesses the local system was called from an untrusted
+    /// collection
+    #[error(
+        "`{function}()` is disabled because this collection is not trusted. \
+        Run `slumber db collection trust <path>` to trust it"
+    )]
+    Sandboxed { function: &'static str },
+
     /// Specified header did not exist in the response
     #[error("Header `{header}` not in response")]
     ResponseMissingHeader { header: String },
@@ -574,6 +1203,21 @@ pub enum FunctionError {
     #[error(transparent)]
     StoredRequest(StoredRequestError),
 
+    /// `totp()` was given a code length it doesn't support
+    #[error("Invalid TOTP length {digits}; must be between 6 and 10 digits")]
+    TotpDigits { digits: u32 },
+
+    /// `totp()` was given a period of zero
+    #[error("TOTP period must be greater than 0")]
+    TotpPeriod,
+
+    /// `totp()` secret couldn't be decoded
+    #[error(
+        "Invalid TOTP secret; expected a base32 string of the letters A-Z \
+        and digits 2-7"
+    )]
+    TotpSecret,
+
     /// Something bad happened while triggering a request dependency
     #[error("Triggering upstream recipe `{recipe_id}`")]
     Trigger {
@@ -582,9 +1226,22 @@ pub enum FunctionError {
         error: TriggeredRequestError,
     },
 
-    /// User referenced a field that isn't defined in the current profile
-    #[error("Unknown profile field `{field}`")]
+    /// User referenced a field that isn't defined in the current profile,
+    /// with the `strict` render mode
+    #[error(
+        "Unknown profile field `{field}`. Unknown fields are an error in the \
+        `strict` render mode; set `render_mode` to `lenient` or `prompt` to \
+        allow them"
+    )]
     UnknownField { field: String },
+
+    /// An bubbled-up error from rendering a collection variable
+    #[error("Rendering variable `{variable}`")]
+    VariableNested {
+        variable: Identifier,
+        #[source]
+        error: RenderError,
+    },
 }
 
 impl From<FunctionError> for RenderError {
@@ -0,0 +1,189 @@
+//! Tracking and gating of external effects performed by template renders
+
+use crate::{
+    database::CollectionDatabase,
+    render::{FunctionError, Prompt, Prompter, SelectOption},
+};
+use chrono::{DateTime, Utc};
+use slumber_config::{Config, TemplateSource};
+use slumber_template::Value;
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+/// An external effect performed by a render, e.g. running a command
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditEvent {
+    pub time: DateTime<Utc>,
+    pub source: TemplateSource,
+    /// What was accessed: the command, file path, environment variable, or
+    /// recipe ID, depending on the source
+    pub detail: String,
+    /// Was the effect performed by a template preview, rather than a
+    /// user-initiated request?
+    pub preview: bool,
+}
+
+/// Records external effects of renders in the audit log, and asks the user to
+/// confirm them first if configured to. This is cheap to clone. The default
+/// value does nothing.
+#[derive(Clone, Debug, Default)]
+pub struct Auditor {
+    /// Where events are recorded. `None` if the audit log is disabled
+    database: Option<CollectionDatabase>,
+    /// Sources that require confirmation before each use
+    confirm_sources: Arc<[TemplateSource]>,
+    /// Tag recorded events as coming from a preview
+    preview: bool,
+}
+
+impl Auditor {
+    pub fn new(config: &Config, database: CollectionDatabase) -> Self {
+        Self {
+            database: config.audit_log.then_some(database),
+            confirm_sources: config.confirm_sources.as_slice().into(),
+            preview: false,
+        }
+    }
+
+    /// Tag recorded events as previews. Use this for renders that aren't
+    /// user-initiated, so their effects can be told apart in the log.
+    #[must_use]
+    pub fn for_preview(self) -> Self {
+        Self {
+            preview: true,
+            ..self
+        }
+    }
+
+    /// If the source requires confirmation, ask the user for it. Return an
+    /// error if they deny it, or if they can't be asked (e.g. in a preview).
+    pub(crate) async fn confirm(
+        &self,
+        prompter: &dyn Prompter,
+        source: TemplateSource,
+        detail: &str,
+    ) -> Result<(), FunctionError> {
+        if !self.confirm_sources.contains(&source) {
+            return Ok(());
+        }
+
+        let (tx, rx) = oneshot::channel();
+        prompter.prompt(Prompt::Select {
+            message: format!("Allow {source} `{detail}`?"),
+            options: vec![
+                SelectOption {
+                    label: "Allow".into(),
+                    value: true.into(),
+                },
+                SelectOption {
+                    label: "Deny".into(),
+                    value: false.into(),
+                },
+            ],
+            channel: tx.into(),
+        });
+        // Anything other than an explicit yes is a no. Previews reply with a
+        // placeholder value, so they're always denied
+        if let Ok(Value::Boolean(true)) = rx.await {
+            Ok(())
+        } else {
+            Err(FunctionError::Denied {
+                kind: source,
+                detail: detail.to_owned(),
+            })
+        }
+    }
+
+    /// Record an effect in the audit log. Errors are logged but not returned,
+    /// because the effect has already happened.
+    pub(crate) fn record(&self, source: TemplateSource, detail: String) {
+        if let Some(database) = &self.database {
+            let _ = database.insert_audit_event(&AuditEvent {
+                time: Utc::now(),
+                source,
+                detail,
+                preview: self.preview,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TestSelectPrompter;
+    use rstest::rstest;
+    use slumber_util::{Factory, assert_result};
+
+    /// Only configured sources require confirmation
+    #[rstest]
+    #[case::not_required(&[], 1, Ok(()))]
+    #[case::allowed(&[TemplateSource::Command], 0, Ok(()))]
+    #[case::denied(
+        &[TemplateSource::Command],
+        1,
+        Err("`command` access to `ls` was denied"),
+    )]
+    #[tokio::test]
+    async fn test_confirm(
+        #[case] confirm_sources: &[TemplateSource],
+        #[case] selected_index: usize,
+        #[case] expected: Result<(), &str>,
+    ) {
+        let auditor = Auditor::new(
+            &Config {
+                confirm_sources: confirm_sources.to_owned(),
+                ..Config::default()
+            },
+            CollectionDatabase::factory(()),
+        );
+        let prompter = TestSelectPrompter::new([selected_index]);
+        assert_result(
+            auditor
+                .confirm(&prompter, TemplateSource::Command, "ls")
+                .await,
+            expected,
+        );
+    }
+
+    /// Events are only recorded if the audit log is enabled
+    #[rstest]
+    #[case::disabled(false, 0)]
+    #[case::enabled(true, 1)]
+    fn test_record(#[case] audit_log: bool, #[case] expected_count: usize) {
+        let database = CollectionDatabase::factory(());
+        let auditor = Auditor::new(
+            &Config {
+                audit_log,
+                ..Config::default()
+            },
+            database.clone(),
+        );
+        auditor.record(TemplateSource::Env, "HOME".into());
+        let events = database.get_audit_events(None).unwrap();
+        assert_eq!(events.len(), expected_count);
+    }
+
+    /// Preview renders are recorded too, but tagged as such
+    #[test]
+    fn test_record_preview() {
+        let database = CollectionDatabase::factory(());
+        let config = Config {
+            audit_log: true,
+            ..Config::default()
+        };
+        let auditor = Auditor::new(&config, database.clone());
+        auditor.record(TemplateSource::Env, "HOME".into());
+        auditor
+            .for_preview()
+            .record(TemplateSource::Env, "HOME".into());
+        let previews = database
+            .get_audit_events(None)
+            .unwrap()
+            .into_iter()
+            .map(|event| event.preview)
+            .collect::<Vec<_>>();
+        // Newest first
+        assert_eq!(previews, [true, false]);
+    }
+}
@@ -2,15 +2,23 @@
 
 use crate::{
     collection::RecipeId,
-    render::{FunctionError, Prompt, SelectOption, SingleRenderContext},
+    keyring::KeyringEntry,
+    render::{
+        FunctionError, Prompt, SelectOption, SingleRenderContext,
+        util::{CommandCacheKey, resolve_program},
+    },
+    util::encode_hex,
 };
+use aws_lc_rs::hmac;
 use base64::{Engine, prelude::BASE64_STANDARD};
-use bytes::Bytes;
-use derive_more::FromStr;
+use bytes::{Bytes, BytesMut};
+use derive_more::{Display, FromStr};
 use futures::{FutureExt, Stream, StreamExt, TryFutureExt, TryStreamExt};
+use indexmap::IndexMap;
 use itertools::Itertools;
 use regex::Regex;
 use serde::{Deserialize, de::IntoDeserializer};
+use slumber_config::TemplateSource;
 use slumber_macros::template;
 use slumber_template::{
     Expected, LazyValue, RenderError, StreamSource, TryFromValue, Value,
@@ -18,16 +26,29 @@ use slumber_template::{
 };
 use slumber_util::{TimeSpan, paths::expand_home};
 use std::{
-    env, fmt::Debug, io, path::PathBuf, process::Stdio, str::FromStr, sync::Arc,
+    env,
+    fmt::Debug,
+    io, mem,
+    path::{Path, PathBuf},
+    process::Stdio,
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tokio::{
     fs::File,
     io::{AsyncRead, AsyncWriteExt},
     process::Command,
     sync::oneshot,
+    time::{self, Instant},
 };
 use tokio_util::io::ReaderStream;
-use tracing::{Instrument, debug, debug_span};
+use tracing::{Instrument, debug, debug_span, warn};
+
+/// Files larger than this when encoded by `file()` get a warning in the
+/// preview. Embedding them in a string means the entire request body is
+/// built in memory, and many servers will reject it anyway.
+const LARGE_ENCODED_FILE_SIZE: u64 = 10 * 1000 * 1000; // 10 MB
 
 // ===========================================================
 // Documentation for these functions is generated automatically by an mdbook
@@ -112,23 +133,62 @@ pub fn boolean(value: Value) -> bool {
 ///   stdin:
 ///     description: Data to pipe to the subprocess's stdin
 ///     default: "b''"
-/// return: Stdout output as bytes. May be returned as a stream (LazyValue).
+///   env:
+///     description: Environment variables to set for the subprocess, in
+///       addition to those inherited from Slumber
+///     default: "{}"
+///   timeout:
+///     description: Kill the subprocess if it hasn't exited after this duration
+///       (e.g. "30s")
+///     default: "null"
+///   trim:
+///     description: Trim whitespace from the output (start/end/both)
+///     default: "null"
+///   json:
+///     description: Parse the output as JSON
+///     default: false
+///   cache:
+///     description: Cache the command's output for this duration (e.g. "5m").
+///       Subsequent renders with the same command, `cwd`, `stdin`, and `env`
+///       will reuse the output instead of re-running the command, including
+///       renders for other requests and previews. Cached output is not
+///       streamed. In the TUI, previews of cached output are labelled with
+///       their age.
+///     default: "null"
+/// return: Stdout output as bytes. May be returned as a stream (LazyValue),
+///   unless `trim` or `json` is given. If `json=true`, the parsed value.
 /// errors:
 ///   - If the command fails to initialize (e.g. program unknown)
 ///   - If the subprocess exits with a non-zero status code
+///   - If the subprocess is still running after `timeout`
+///   - If `json=true` and the output isn't valid JSON
+///   - If the collection is [untrusted](../user_guide/trust.md)
+///   - If the user [denies access](../user_guide/trust.md#confirmation-prompts)
 /// examples:
 ///   - input: command(["echo", "hello"])
 ///     output: "hello\n"
 ///   - input: command(["grep","1"], stdin="line 1\nline2")
 ///     output: "line 1\n"
+///   - input: command(["get-token"], cache="5m")
+///     output: "abc123"
+///   - input: command(["gcloud", "auth", "print-access-token"], trim="end", timeout="10s")
+///     output: "ya29.a0AfH6SM"
+///   - input: 'command(["aws", "sts", "get-session-token"], env={"AWS_PROFILE": "dev"}, json=true) | jsonpath("$.Credentials.SessionToken")'
+///     output: "FwoGZXIvYXdzEBY"
 /// ```
 #[template]
-pub fn command(
+#[expect(clippy::too_many_arguments)] // Each kwarg is an argument
+pub async fn command(
     #[context] context: &SingleRenderContext<'_>,
     command: Vec<String>,
     #[kwarg] cwd: Option<String>,
     #[kwarg] stdin: Option<Bytes>,
-) -> Result<LazyValue, FunctionError> {
+    #[kwarg] env: IndexMap<String, String>,
+    #[kwarg] timeout: Option<TimeSpan>,
+    #[kwarg] trim: Option<TrimMode>,
+    #[kwarg] json: bool,
+    #[kwarg] cache: Option<TimeSpan>,
+) -> Result<LazyValue, RenderError> {
     /// Wrap an IO error
     fn io_error(
         program: &str,
@@ -142,13 +202,41 @@ pub fn command(
         })
     }
 
+    if context.sandboxed {
+        return Err(FunctionError::Sandboxed {
+            function: "command",
+        }
+        .into());
+    }
     let cwd = context.root_dir.join(cwd.unwrap_or_default());
     let [program, arguments @ ..] = command.as_slice() else {
-        return Err(FunctionError::CommandEmpty);
+        return Err(FunctionError::CommandEmpty.into());
     };
     let program = program.clone();
     let arguments = arguments.to_owned();
 
+    // If caching is enabled and we have a fresh value, skip the command
+    let key = CommandCacheKey {
+        command: command.clone(),
+        cwd: cwd.clone(),
+        stdin: stdin.clone(),
+        env: env.clone().into_iter().collect(),
+    };
+    if let Some(ttl) = cache
+        && let Some((output, age)) = context.command_cache.get(&key, ttl)
+    {
+        let value = command_output(output, trim, json)?;
+        return Ok(LazyValue::Cached { value, age });
+    }
+    let command_cache = context.command_cache.clone();
+
+    // Cached output doesn't run anything, so only confirm a real run
+    let detail = command.join(" ");
+    context
+        .auditor
+        .confirm(&*context.prompter, TemplateSource::Command, &detail)
+        .await?;
+
     // We're going to defer command spawning *and* streaming. Streamed commands
     // shouldn't be spawned until the stream is actually resolved, to prevent
     // running large/slow commands in a preview.
@@ -157,12 +245,15 @@ pub fn command(
     // - Spawn command
     // - Stream from stdout
     // - Check command status
-    let future = async {
+    let auditor = context.auditor.clone();
+    let future = async move {
         let span = debug_span!("Running command", ?program, ?arguments);
+        auditor.record(TemplateSource::Command, detail);
 
         // Spawn the command process
-        let mut child = Command::new(&program)
+        let mut child = Command::new(resolve_program(&program))
             .args(&arguments)
+            .envs(&env)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::null())
@@ -170,16 +261,32 @@ pub fn command(
             .kill_on_drop(true)
             .spawn()
             .map_err(|error| io_error(&program, &arguments, error))?;
+        // The timeout covers everything from here until the process exits
+        let deadline = timeout.map(|timeout| Instant::now() + timeout.inner());
+        let timed_out = move |program: String, arguments: Vec<String>| {
+            RenderError::from(FunctionError::CommandTimeout {
+                program,
+                arguments,
+                timeout: timeout.expect("Deadline requires timeout"),
+            })
+        };
 
         // Write the stdin to the process
         if let Some(stdin) = stdin {
-            child
+            let write = child
                 .stdin
                 .as_mut()
                 .expect("Process missing stdin")
-                .write_all(&stdin)
-                .await
-                .map_err(|error| io_error(&program, &arguments, error))?;
+                .write_all(&stdin);
+            match deadline {
+                Some(deadline) => {
+                    time::timeout_at(deadline, write).await.map_err(|_| {
+                        timed_out(program.clone(), arguments.clone())
+                    })?
+                }
+                None => write.await,
+            }
+            .map_err(|error| io_error(&program, &arguments, error))?;
         }
 
         // We have to poll the process (via wait()) and stream from stdout
@@ -187,11 +294,20 @@ pub fn command(
         // output. If we try to wait() then stream from stdout, the stdout
         // buffer may fill up and the process will hang until it's drained. In
         // practice this means we'll poll in a background task, then stream
-        // stdout until it's done.
+        // stdout until it's done. If the process times out, we kill it, which
+        // closes stdout and ends the stream.
         let stdout = child.stdout.take().expect("stdout not set for child");
         let handle = tokio::spawn(
             async move {
-                let result = child.wait().await;
+                let result = match deadline {
+                    Some(deadline) => {
+                        match time::timeout_at(deadline, child.wait()).await {
+                            Ok(result) => result.map(Some),
+                            Err(_) => child.kill().await.map(|()| None),
+                        }
+                    }
+                    None => child.wait().await.map(Some),
+                };
                 debug!(?result, "Command finished");
                 result
             }
@@ -207,28 +323,83 @@ pub fn command(
                 .map_err(RenderError::other)? // Join error - task panicked
                 // Command error
                 .map_err(|error| io_error(&program, &arguments, error))?;
-            if status.success() {
+            match status {
                 // Since we're chaining onto the end of the output stream, we
                 // need to emit empty bytes
-                Ok(Bytes::new())
-            } else {
-                Err(FunctionError::CommandStatus {
+                Some(status) if status.success() => Ok(Bytes::new()),
+                Some(status) => Err(FunctionError::CommandStatus {
                     program,
                     arguments,
                     status,
                 }
-                .into())
+                .into()),
+                None => Err(timed_out(program, arguments)),
             }
         };
         Ok(reader_stream(stdout).chain(status_future.into_stream()))
     };
 
     let stream = future.try_flatten_stream().boxed();
+    // Only one render can run a given command at a time. The lock is taken
+    // when the stream is resolved, i.e. right before the command would run,
+    // and held until it exits. Caching requires the complete output, so
+    // collect the stream into a single chunk.
+    let stream = if let Some(ttl) = cache {
+        async move {
+            let _guard = command_cache.lock(&key).await;
+            // Someone else may have run it while we were waiting
+            if let Some((output, _)) = command_cache.get(&key, ttl) {
+                return Ok(output);
+            }
+            let output = stream.try_collect::<BytesMut>().await?.freeze();
+            command_cache.insert(key, output.clone());
+            Ok(output)
+        }
+        .into_stream()
+        .boxed()
+    } else {
+        async move {
+            let guard = command_cache.lock(&key).await;
+            // The guard lives as long as the output stream
+            Ok(stream.inspect(move |_| {
+                let _ = &guard;
+            }))
+        }
+        .try_flatten_stream()
+        .boxed()
+    };
 
-    Ok(LazyValue::Stream {
-        source: StreamSource::Command { command },
-        stream,
-    })
+    if trim.is_none() && !json {
+        return Ok(LazyValue::Stream {
+            source: StreamSource::Command { command },
+            stream,
+        });
+    }
+    // Post-processing needs the complete output, so we have to run the command
+    // now instead of deferring to the consumer
+    let output = stream.try_collect::<BytesMut>().await?.freeze();
+    command_output(output, trim, json).map(LazyValue::Value)
+}
+
+/// Apply `command()`'s `trim` and `json` arguments to its complete output
+fn command_output(
+    output: Bytes,
+    trim: Option<TrimMode>,
+    json: bool,
+) -> Result<Value, RenderError> {
+    let output = match trim {
+        Some(TrimMode::Start) => output.slice_ref(output.trim_ascii_start()),
+        Some(TrimMode::End) => output.slice_ref(output.trim_ascii_end()),
+        Some(TrimMode::Both) => output.slice_ref(output.trim_ascii()),
+        None => output,
+    };
+    if json {
+        let value: serde_json::Value = serde_json::from_slice(&output)
+            .map_err(FunctionError::JsonParse)?;
+        Ok(value.into())
+    } else {
+        Ok(Value::Bytes(output))
+    }
 }
 
 /// ```notrust
@@ -279,6 +450,8 @@ pub fn debug(value: Value) -> Value {
 ///     description: Value to return when the environment variable is not present
 ///     default: ""
 /// return: Value of the environment variable or the provided default
+/// errors:
+///   - If the user [denies access](../user_guide/trust.md#confirmation-prompts)
 /// examples:
 ///   - input: env("HOME")
 ///     output: "/home/username"
@@ -288,8 +461,47 @@ pub fn debug(value: Value) -> Value {
 ///     output: "default"
 /// ```
 #[template]
-pub fn env(variable: String, #[kwarg] default: String) -> String {
-    env::var(variable).unwrap_or(default)
+pub async fn env(
+    #[context] context: &SingleRenderContext<'_>,
+    variable: String,
+    #[kwarg] default: String,
+) -> Result<String, FunctionError> {
+    context
+        .auditor
+        .confirm(&*context.prompter, TemplateSource::Env, &variable)
+        .await?;
+    let value = env::var(&variable).unwrap_or(default);
+    context.auditor.record(TemplateSource::Env, variable);
+    Ok(value)
+}
+
+/// ```notrust
+/// description: Use a default value if another expression fails to render,
+///   e.g. because a command failed, a selector didn't match, or a request
+///   couldn't be sent. The error is still shown in the template preview, but
+///   it won't prevent the request from being built. Typically used at the end
+///   of a pipe chain.
+/// parameters:
+///   default:
+///     description: Value to return if `value` fails to render
+///   value:
+///     description: Expression to render. If it renders successfully, its value is returned
+/// return: The rendered value, or the default if it failed
+/// examples:
+///   - input: fallback("anonymous", env("USER"))
+///     output: "ted"
+///   - input: response("login") | jsonpath("$.token") | fallback("")
+///     output: '""'
+///     comment: The login response is missing the `token` field
+///   - input: command(["cat", "missing.txt"]) | fallback("none")
+///     output: "none"
+/// ```
+#[template]
+pub fn fallback(default: Value, value: Value) -> Value {
+    // If the value fails to render, the template engine returns the default
+    // before calling this function. See Expression::render
+    let _ = default;
+    value
 }
 
 /// ```notrust
@@ -299,36 +511,356 @@ pub fn env(variable: String, #[kwarg] default: String) -> String {
 /// parameters:
 ///   path:
 ///     description: Path to the file to read, relative to the collection file (`slumber.yml`). A leading `~` will be expanded to $HOME.
+///   encoding:
+///     description: Encode the contents as text (`base64` or `hex`), to embed
+///       a binary file in a string such as a JSON body. In a `stream` body,
+///       the file is encoded chunk-by-chunk instead of being loaded into
+///       memory at once. Files that are larger than 10 MB when encoded show a
+///       warning in the template preview.
+///     default: "null"
 /// return: File contents as bytes (may be a stream)
 /// errors:
 ///   - If an I/O error occurs while opening the file (e.g. file missing)
+///   - If the collection is [untrusted](../user_guide/trust.md)
+///   - If the user [denies access](../user_guide/trust.md#confirmation-prompts)
 /// examples:
 ///   - input: file("config.json")
 ///     output: Contents of config.json file
+///   - input: file("logo.png", encoding="base64")
+///     output: '"iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg=="'
+///   - input: file("firmware.bin", encoding="hex")
+///     output: '"7f454c4602010100"'
 /// ```
 #[template]
-pub fn file(
+pub async fn file(
     #[context] context: &SingleRenderContext<'_>,
     path: String,
-) -> LazyValue {
+    #[kwarg] encoding: Option<FileEncoding>,
+) -> Result<LazyValue, FunctionError> {
+    if context.sandboxed {
+        return Err(FunctionError::Sandboxed { function: "file" });
+    }
     let path = context.root_dir.join(expand_home(PathBuf::from(path)));
-    let source = StreamSource::File { path: path.clone() };
+    let detail = path.display().to_string();
+    context
+        .auditor
+        .confirm(&*context.prompter, TemplateSource::File, &detail)
+        .await?;
+    let source = match encoding {
+        None => StreamSource::File { path: path.clone() },
+        Some(encoding) => {
+            // Missing files are reported when the stream is read, so don't
+            // fail here
+            let size = tokio::fs::metadata(&path)
+                .await
+                .map(|metadata| encoding.encoded_size(metadata.len()))
+                .unwrap_or_default();
+            let warning = (size > LARGE_ENCODED_FILE_SIZE).then(|| {
+                warn!(?path, size, "Embedding large file in template");
+                format!(
+                    "{:.1} MB when encoded; consider a raw or multipart body",
+                    size as f64 / 1_000_000.0
+                )
+            });
+            StreamSource::EncodedFile {
+                path: path.clone(),
+                encoding: encoding.to_string(),
+                warning,
+            }
+        }
+    };
+    let auditor = context.auditor.clone();
     // Return the file as a stream. If streaming isn't available here, it will
     // be resolved immediately instead. If the file doesn't exist or any other
     // error occurs, the error will be deferred until the data is actually
     // streamed.
     let future = async move {
+        auditor.record(TemplateSource::File, detail);
         let file = File::open(&path)
             .await
             .map_err(|error| FunctionError::File { path, error })?;
         Ok(reader_stream(file))
     };
-    LazyValue::Stream {
+    let stream = future.try_flatten_stream().boxed();
+    Ok(LazyValue::Stream {
         source,
-        stream: future.try_flatten_stream().boxed(),
+        stream: match encoding {
+            None => stream,
+            Some(encoding) => encode_stream(stream, encoding).boxed(),
+        },
+    })
+}
+
+/// ```notrust
+/// description: Load a structured data file (JSON, YAML, or CSV) and extract
+///   a value from it with a JSONPath selector
+/// tags: [input, json]
+/// parameters:
+///   path:
+///     description: Path to the file to read, relative to the collection file (`slumber.yml`). A leading `~` will be expanded to $HOME.
+///   selector:
+///     description: JSONPath query to apply to the parsed file. For CSV files,
+///       a plain column name (without a leading `$`) selects that column from
+///       every row.
+///   format:
+///     description: File format (json/yaml/csv). If omitted, the format is
+///       determined by the file extension (`.json`, `.yaml`/`.yml`, `.csv`).
+///     default: "null"
+///   mode:
+///     description: How to handle multiple results (auto/single/array)
+///     default: "auto"
+/// return: Resulting template `Value`
+/// errors:
+///   - If an I/O error occurs while reading the file (e.g. file missing)
+///   - If `format` is omitted and the file extension isn't recognized
+///   - If the file can't be parsed in its format
+///   - If `selector` is not a valid JSONPath query
+///   - If the query returns no results and `mode='auto'` or `mode='single'`
+///   - If the query returns 2+ results and `mode='single'`
+///   - If the collection is [untrusted](../user_guide/trust.md)
+///   - If the user [denies access](../user_guide/trust.md#confirmation-prompts)
+/// examples:
+///   - input: file_select("fixtures/user.json", "$.id")
+///     output: "3"
+///   - input: file_select("fixtures/users.csv", "email", mode="array")
+///     output: '["alice@example.com", "bob@example.com"]'
+///     comment: CSV rows are objects keyed by the header row
+///   - input: file_select("build/manifest.txt", "$.version", format="yaml")
+///     output: '"1.2.0"'
+/// ```
+#[template]
+pub async fn file_select(
+    #[context] context: &SingleRenderContext<'_>,
+    path: String,
+    selector: String,
+    #[kwarg] format: Option<FileFormat>,
+    #[kwarg] mode: JsonQueryMode,
+) -> Result<Value, FunctionError> {
+    if context.sandboxed {
+        return Err(FunctionError::Sandboxed {
+            function: "file_select",
+        });
+    }
+    let path = context.root_dir.join(expand_home(PathBuf::from(path)));
+    let format = match format {
+        Some(format) => format,
+        None => FileFormat::from_path(&path).ok_or_else(|| {
+            FunctionError::FileFormatUnknown { path: path.clone() }
+        })?,
+    };
+    // A CSV column name is shorthand for selecting it from every row. Parse
+    // the query before touching the file so a typo fails fast
+    let selector = if format == FileFormat::Csv && !selector.starts_with('$') {
+        format!("$[*][{}]", serde_json::Value::from(selector))
+    } else {
+        selector
+    };
+    let query = serde_json_path::JsonPath::parse(&selector)
+        .map_err(FunctionError::JsonPathParse)?;
+
+    let detail = path.display().to_string();
+    context
+        .auditor
+        .confirm(&*context.prompter, TemplateSource::File, &detail)
+        .await?;
+    context.auditor.record(TemplateSource::File, detail);
+    let content = tokio::fs::read_to_string(&path).await.map_err(|error| {
+        FunctionError::File {
+            path: path.clone(),
+            error,
+        }
+    })?;
+    let data =
+        format
+            .parse(&content)
+            .map_err(|error| FunctionError::FileParse {
+                path,
+                format,
+                error,
+            })?;
+
+    let node_list = query.query(&data);
+    mode.get_values(query.to_string(), node_list.into_iter().cloned())
+}
+
+/// Text encoding for `file()`
+#[derive(Copy, Clone, Debug, Display)]
+pub enum FileEncoding {
+    #[display("base64")]
+    Base64,
+    #[display("hex")]
+    Hex,
+}
+
+impl FileEncoding {
+    /// Get the length of a file's contents after encoding
+    fn encoded_size(self, size: u64) -> u64 {
+        match self {
+            Self::Base64 => size.div_ceil(3) * 4,
+            Self::Hex => size * 2,
+        }
+    }
+}
+
+// Manual implementation provides the best error messages
+impl FromStr for FileEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "base64" => Ok(Self::Base64),
+            "hex" => Ok(Self::Hex),
+            _ => Err(format!(
+                "Invalid encoding `{s}`; must be `base64` or `hex`"
+            )),
+        }
+    }
+}
+
+impl_try_from_value_str!(FileEncoding);
+
+/// Format of a structured data file for [file_select]
+#[derive(Copy, Clone, Debug, Display, PartialEq)]
+pub enum FileFormat {
+    #[display("JSON")]
+    Json,
+    #[display("YAML")]
+    Yaml,
+    #[display("CSV")]
+    Csv,
+}
+
+impl FileFormat {
+    /// Guess a file's format from its extension
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    /// Parse file content into JSON. CSV files are converted to an array of
+    /// objects, one per row, keyed by the header row.
+    fn parse(self, content: &str) -> Result<serde_json::Value, String> {
+        match self {
+            Self::Json => {
+                serde_json::from_str(content).map_err(|error| error.to_string())
+            }
+            Self::Yaml => {
+                serde_yaml::from_str(content).map_err(|error| error.to_string())
+            }
+            Self::Csv => {
+                let mut rows = parse_csv(content)?.into_iter();
+                let header = rows.next().unwrap_or_default();
+                Ok(rows
+                    .map(|row| {
+                        header
+                            .iter()
+                            .cloned()
+                            .zip(row.into_iter().map(serde_json::Value::from))
+                            .collect::<serde_json::Map<_, _>>()
+                    })
+                    .collect())
+            }
+        }
+    }
+}
+
+// Manual implementation provides the best error messages
+impl FromStr for FileFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(Self::Json),
+            "yaml" => Ok(Self::Yaml),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!(
+                "Invalid format `{s}`; must be `json`, `yaml`, or `csv`"
+            )),
+        }
     }
 }
 
+impl_try_from_value_str!(FileFormat);
+
+/// Parse CSV content into rows of fields, per RFC 4180. Fields may be quoted,
+/// in which case they can contain commas, newlines, and doubled quotes (`""`).
+/// Empty lines are skipped.
+fn parse_csv(content: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    // Has anything been written to the current row? Distinguishes an empty
+    // line from a line with a single empty field
+    let mut row_started = false;
+    let mut chars = content.chars().peekable();
+    let mut line = 1;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => {
+                row_started = true;
+                let start_line = line;
+                // Quoted field: read until the closing quote
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            field.push(c);
+                        }
+                        None => {
+                            return Err(format!(
+                                "Unterminated quoted field on line {start_line}"
+                            ));
+                        }
+                    }
+                }
+                match chars.peek() {
+                    None | Some(',' | '\n' | '\r') => {}
+                    Some(_) => {
+                        return Err(format!(
+                            "Unexpected character after closing quote on \
+                            line {line}"
+                        ));
+                    }
+                }
+            }
+            ',' => {
+                row_started = true;
+                row.push(mem::take(&mut field));
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if row_started || !field.is_empty() {
+                    row.push(mem::take(&mut field));
+                    rows.push(mem::take(&mut row));
+                }
+                row_started = false;
+                line += 1;
+            }
+            c => {
+                row_started = true;
+                field.push(c);
+            }
+        }
+    }
+    if row_started || !field.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
 /// ```notrust
 /// description: Convert a value to a float
 /// tags: [number]
@@ -828,6 +1360,54 @@ impl FromStr for JsonQueryMode {
 
 impl_try_from_value_str!(JsonQueryMode);
 
+/// ```notrust
+/// description: >-
+///   Load a secret from the operating system's credential store: Keychain on
+///   macOS, Credential Manager on Windows, or the Secret Service (e.g. GNOME
+///   Keyring or KWallet) on Linux. Add entries with
+///   [`slumber keyring set`](../user_guide/cli/subcommands.md#slumber-keyring).
+///   The secret is masked in template previews.
+/// tags: [input]
+/// parameters:
+///   service:
+///     description: Service the secret belongs to, e.g. the name of the API
+///   username:
+///     description: Username or account name of the secret within the service
+/// return: Stored secret
+/// errors:
+///   - If there is no entry for the service and username
+///   - If the credential store can't be accessed (e.g. `secret-tool` is not
+///     installed)
+///   - If the collection is [untrusted](../user_guide/trust.md)
+///   - If the user [denies access](../user_guide/trust.md#confirmation-prompts)
+/// examples:
+///   - input: keyring("fish-api", "admin")
+///     output: "hunter2"
+/// ```
+#[template]
+pub async fn keyring(
+    #[context] context: &SingleRenderContext<'_>,
+    service: String,
+    username: String,
+) -> Result<String, FunctionError> {
+    if context.sandboxed {
+        return Err(FunctionError::Sandboxed {
+            function: "keyring",
+        });
+    }
+    let detail = format!("{service}/{username}");
+    context
+        .auditor
+        .confirm(&*context.prompter, TemplateSource::Keyring, &detail)
+        .await?;
+    let secret = KeyringEntry::new(&service, &username)
+        .get()
+        .await?
+        .ok_or(FunctionError::KeyringMissing { service, username })?;
+    context.auditor.record(TemplateSource::Keyring, detail);
+    Ok(mask_sensitive(context, secret))
+}
+
 /// ```notrust
 /// description: Convert a string to lowercase
 /// tags: [string]
@@ -961,13 +1541,15 @@ pub fn replace(
 
 /// ```notrust
 /// description: Load the most recent response body for the given recipe and
-///   current profile
+///   current profile. If a recipe is referenced multiple times with the same
+///   trigger while building a request, the response is only loaded (or
+///   triggered) once.
 /// tags: [input]
 /// parameters:
 ///   recipe_id:
 ///     description: ID (not name) of the recipe to load the response from
 ///   trigger:
-///     description: When to execute the upstream request (never/no_history/always/Duration)
+///     description: When to execute the upstream request (never/no_history/token_expiry/always/Duration)
 ///     default: "never"
 /// return: Most recent response body as bytes
 /// errors:
@@ -979,6 +1561,8 @@ pub fn replace(
 ///     output: '{"token": "abc123"}'
 ///   - input: response("login", trigger="1h")
 ///     output: '{"token": "abc123"}'
+///   - input: response("login", trigger="token_expiry")
+///     output: '{"access_token": "abc123", "expires_in": 3600}'
 /// ```
 #[template]
 pub async fn response(
@@ -1267,6 +1851,70 @@ pub fn string(value: Value) -> Result<String, ValueError> {
     String::try_from_value(value).map_err(WithValue::into_error)
 }
 
+/// ```notrust
+/// description: >-
+///   Generate a time-based one-time password (TOTP), as defined by
+///   [RFC 6238](https://datatracker.ietf.org/doc/html/rfc6238). This is the
+///   same code an authenticator app displays, so login flows that require
+///   two-factor authentication can be automated.
+///
+///   Avoid putting the secret in your collection directly. Load it from an
+///   environment variable with `env()`, or from your OS keychain with
+///   `command()`.
+/// tags: [string]
+/// parameters:
+///   secret:
+///     description: Base32-encoded shared secret. This is the key behind the QR
+///       code a service gives you when enabling 2FA, typically also shown for
+///       manual entry. Case, spaces, dashes, and padding are ignored.
+///   digits:
+///     description: Number of digits in the code (6-10)
+///     default: 6
+///   period:
+///     description: Number of seconds each code is valid for
+///     default: 30
+///   algorithm:
+///     description: HMAC algorithm used to generate the code. Almost all
+///       services use `sha1`.
+///     default: "sha1"
+/// return: The current code, as a string. Leading zeroes are preserved.
+/// errors:
+///   - If `secret` is not valid base32
+///   - If `digits` is outside the range 6-10, or `period` is `0`
+/// examples:
+///   - input: totp("JBSWY3DPEHPK3PXP")
+///     output: '"282760"'
+///   - input: env("GITHUB_TOTP_SECRET") | totp()
+///     output: '"044216"'
+///   - input: command(["security", "find-generic-password", "-s", "myfishes", "-w"], trim="end") | totp()
+///     output: '"903114"'
+///     comment: Load the secret from the macOS keychain
+///   - input: totp("JBSWY3DPEHPK3PXP", digits=8, algorithm="sha256")
+///     output: '"61852903"'
+/// ```
+#[template]
+pub fn totp(
+    secret: String,
+    #[kwarg] digits: Option<u32>,
+    #[kwarg] period: Option<u32>,
+    #[kwarg] algorithm: TotpAlgorithm,
+) -> Result<String, FunctionError> {
+    let digits = digits.unwrap_or(6);
+    let period = period.unwrap_or(30);
+    if !(6..=10).contains(&digits) {
+        return Err(FunctionError::TotpDigits { digits });
+    }
+    if period == 0 {
+        return Err(FunctionError::TotpPeriod);
+    }
+    let key = decode_base32(&secret).ok_or(FunctionError::TotpSecret)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    Ok(hotp(&key, now / u64::from(period), digits, algorithm))
+}
+
 /// ```notrust
 /// description: Trim whitespace from the beginning and/or end of a string
 /// tags: [string]
@@ -1313,6 +1961,60 @@ pub fn upper(value: String) -> String {
     value.to_uppercase()
 }
 
+/// Generate an HMAC-based one-time password
+/// ([RFC 4226](https://datatracker.ietf.org/doc/html/rfc4226)). A TOTP is an
+/// HOTP whose counter is the number of periods since the Unix epoch.
+pub(super) fn hotp(
+    key: &[u8],
+    counter: u64,
+    digits: u32,
+    algorithm: TotpAlgorithm,
+) -> String {
+    let algorithm = match algorithm {
+        TotpAlgorithm::Sha1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        TotpAlgorithm::Sha256 => hmac::HMAC_SHA256,
+        TotpAlgorithm::Sha512 => hmac::HMAC_SHA512,
+    };
+    let tag =
+        hmac::sign(&hmac::Key::new(algorithm, key), &counter.to_be_bytes());
+    let hash = tag.as_ref();
+    // Dynamic truncation: the low 4 bits of the last byte are the offset of a
+    // 31-bit integer within the hash
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let truncated =
+        u32::from_be_bytes(hash[offset..offset + 4].try_into().unwrap())
+            & 0x7fff_ffff;
+    let code = u64::from(truncated) % 10u64.pow(digits);
+    format!("{code:0width$}", width = digits as usize)
+}
+
+/// Decode an RFC 4648 base32 string, as used for TOTP secrets. Secrets are
+/// often displayed lowercase or in groups, so case, whitespace, dashes, and
+/// padding are ignored. Return `None` if the input has any other characters
+/// or is empty.
+fn decode_base32(input: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes() {
+        if c.is_ascii_whitespace() || c == b'-' || c == b'=' {
+            continue;
+        }
+        let value =
+            ALPHABET.iter().position(|&a| a == c.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    (!bytes.is_empty()).then_some(bytes)
+}
+
 fn mask_sensitive(context: &SingleRenderContext<'_>, value: String) -> String {
     if context.show_sensitive {
         value
@@ -1323,7 +2025,7 @@ fn mask_sensitive(context: &SingleRenderContext<'_>, value: String) -> String {
 
 /// Define when a recipe with a chained request should auto-execute the
 /// dependency request.
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, Eq, Hash, PartialEq)]
 pub enum RequestTrigger {
     /// Never trigger the request. This is the default because upstream
     /// requests could be mutating, so we want the user to explicitly opt into
@@ -1335,6 +2037,12 @@ pub enum RequestTrigger {
     /// Trigger the request if the last response is older than some
     /// duration (or there is none in history)
     Expire { duration: TimeSpan },
+    /// Trigger the request if there is none in history, or if the last
+    /// response contains an auth token that has expired or is about to. The
+    /// expiry is read from an `expires_in` field or a JWT's `exp` claim. If
+    /// the response has no discoverable expiry, this behaves like
+    /// [NoHistory](Self::NoHistory).
+    TokenExpiry,
     /// Trigger the request every time the dependent request is rendered
     Always,
 }
@@ -1349,12 +2057,13 @@ impl FromStr for RequestTrigger {
             // If you add a case here, update the expecting string too
             "never" => Ok(Self::Never),
             "no_history" => Ok(Self::NoHistory),
+            "token_expiry" => Ok(Self::TokenExpiry),
             "always" => Ok(Self::Always),
             // Anything else is parsed as a duration
             _ => {
                 let duration = s.parse::<TimeSpan>().map_err(|_| {
-                    "Expected \"never\", \"no_history\", \"always\", or a \
-                    duration string such as \"1h\" \
+                    "Expected \"never\", \"no_history\", \"token_expiry\", \
+                    \"always\", or a duration string such as \"1h\" \
                     (units are \"s\", \"m\", \"h\", or \"d\")"
                 })?;
                 Ok(Self::Expire { duration })
@@ -1365,6 +2074,32 @@ impl FromStr for RequestTrigger {
 
 impl_try_from_value_str!(RequestTrigger);
 
+/// Hash algorithm for `totp()`
+#[derive(Copy, Clone, Debug, Default)]
+pub enum TotpAlgorithm {
+    #[default]
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+impl FromStr for TotpAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sha1" => Ok(Self::Sha1),
+            "sha256" => Ok(Self::Sha256),
+            "sha512" => Ok(Self::Sha512),
+            _ => Err(format!(
+                "Invalid algorithm `{s}`; must be `sha1`, `sha256`, or `sha512`"
+            )),
+        }
+    }
+}
+
+impl_try_from_value_str!(TotpAlgorithm);
+
 /// Trim whitespace from a string
 #[derive(Copy, Clone, Debug, Default)]
 pub enum TrimMode {
@@ -1465,6 +2200,36 @@ impl From<Sequence> for Value {
     }
 }
 
+/// Encode a stream of bytes to text as it's read. Base64 encodes 3-byte
+/// groups, so any leftover bytes from each chunk are carried over to the next
+/// one, and the final group is padded once the stream is done.
+fn encode_stream(
+    stream: impl Stream<Item = Result<Bytes, RenderError>> + Unpin,
+    encoding: FileEncoding,
+) -> impl Stream<Item = Result<Bytes, RenderError>> {
+    // State is None once the stream is finished
+    let state = Some((stream, BytesMut::new()));
+    futures::stream::unfold(state, move |state| async move {
+        let (mut stream, mut remainder) = state?;
+        match stream.next().await {
+            Some(Ok(chunk)) => {
+                let encoded = match encoding {
+                    FileEncoding::Base64 => {
+                        remainder.extend_from_slice(&chunk);
+                        let whole = remainder.len() - remainder.len() % 3;
+                        BASE64_STANDARD.encode(remainder.split_to(whole))
+                    }
+                    FileEncoding::Hex => encode_hex(&chunk),
+                };
+                Some((Ok(encoded.into()), Some((stream, remainder))))
+            }
+            Some(Err(error)) => Some((Err(error), None)),
+            None if remainder.is_empty() => None,
+            None => Some((Ok(BASE64_STANDARD.encode(remainder).into()), None)),
+        }
+    })
+}
+
 /// Create a stream from an `AsyncRead` value
 fn reader_stream(
     reader: impl AsyncRead,
@@ -0,0 +1,258 @@
+//! Pre-flight validation of the values a recipe depends on. Rendering fails on
+//! the first field that can't be resolved, so this walks every template before
+//! rendering begins to find *all* of them. Missing values are then either
+//! asked for together or reported together.
+
+use crate::{
+    collection::{RecipeParam, RenderMode},
+    render::{
+        FunctionError, Prompt, SingleRenderContext, util::FutureCacheOutcome,
+    },
+};
+use derive_more::Display;
+use futures::{
+    FutureExt,
+    future::{self, LocalBoxFuture},
+};
+use slumber_template::{Expression, Identifier, RenderError, Template, Value};
+use std::collections::HashSet;
+use tokio::sync::oneshot;
+
+/// A value that a recipe depends on, but doesn't have
+#[derive(Clone, Debug, Display, PartialEq)]
+pub enum MissingValue {
+    /// Field isn't defined in the selected profile, or anywhere else
+    #[display("profile field `{_0}` is not defined")]
+    FieldUndefined(Identifier),
+    /// Field is defined, but its value is empty
+    #[display("profile field `{_0}` is empty")]
+    FieldEmpty(Identifier),
+    /// Required recipe parameter has no value and no default
+    #[display("parameter `{_0}` is required")]
+    Param(Identifier),
+}
+
+impl<'a> SingleRenderContext<'a> {
+    /// Check that every field and parameter referenced by the templates,
+    /// directly or through other fields and variables, has a value. This
+    /// should be called before rendering any of the templates.
+    ///
+    /// If the context allows it, the user is asked for every missing value at
+    /// once; otherwise they're all returned in a single error. Values
+    /// referenced only by the value argument of `fallback()` are never
+    /// considered missing.
+    pub(crate) async fn preflight(
+        &self,
+        templates: impl IntoIterator<Item = &'a Template>,
+    ) -> Result<(), RenderError> {
+        let mut walk = Walk::default();
+        for template in templates {
+            walk.visit(self, template);
+        }
+
+        // Report everything that can't be asked for before asking for
+        // anything, so the user doesn't answer prompts for a doomed request
+        let can_prompt_fields = self.context.prompt_missing
+            || self.render_mode == RenderMode::Prompt;
+        let (prompt_fields, missing): (Vec<_>, Vec<_>) =
+            walk.missing.into_iter().partition(|value| match value {
+                MissingValue::FieldUndefined(_)
+                | MissingValue::FieldEmpty(_) => can_prompt_fields,
+                MissingValue::Param(_) => self.context.prompt_missing,
+            });
+        if !missing.is_empty() {
+            return Err(FunctionError::MissingValues { values: missing }.into());
+        }
+
+        // Fire off every prompt before waiting on any of them, so they all
+        // land in the same form
+        let fields =
+            prompt_fields.into_iter().filter_map(|value| match value {
+                MissingValue::FieldUndefined(field)
+                | MissingValue::FieldEmpty(field) => {
+                    Some(self.prompt_field(field).boxed_local())
+                }
+                MissingValue::Param(_) => None,
+            });
+        let params = walk.prompt_params.into_iter().map(|(field, param)| {
+            async move {
+                self.get_param(&field, param).await?;
+                Ok(())
+            }
+            .boxed_local()
+        });
+        let futures: Vec<LocalBoxFuture<'_, Result<(), RenderError>>> =
+            fields.chain(params).collect();
+        future::try_join_all(futures).await?;
+        Ok(())
+    }
+
+    /// Ask the user for a missing field, and cache the answer so the render
+    /// uses it. Answers are remembered for the render group, so the user isn't
+    /// asked again if the request is rebuilt to reauthenticate.
+    async fn prompt_field(&self, field: Identifier) -> Result<(), RenderError> {
+        let guard = match self
+            .context
+            .state
+            .field_cache
+            .get_or_init(field.clone())
+            .await
+        {
+            FutureCacheOutcome::Hit(_) => return Ok(()),
+            FutureCacheOutcome::Miss(guard) => guard,
+        };
+
+        let answered = self
+            .context
+            .state
+            .prompted_fields
+            .lock()
+            .expect("Prompted fields lock poisoned")
+            .get(&field)
+            .cloned();
+        let value = if let Some(value) = answered {
+            value
+        } else {
+            let (tx, rx) = oneshot::channel();
+            self.prompter.prompt(Prompt::Text {
+                message: field.to_string(),
+                default: None,
+                sensitive: false,
+                channel: tx.into(),
+            });
+            let value: Value =
+                rx.await.map_err(|_| FunctionError::PromptNoReply)?.into();
+            self.context
+                .state
+                .prompted_fields
+                .lock()
+                .expect("Prompted fields lock poisoned")
+                .insert(field, value.clone());
+            value
+        };
+        guard.set(value.into());
+        Ok(())
+    }
+}
+
+/// State for walking the templates of a recipe to find missing values
+#[derive(Default)]
+struct Walk<'a> {
+    /// Fields that have already been checked
+    fields: HashSet<Identifier>,
+    /// Variables whose templates have already been walked
+    variables: HashSet<Identifier>,
+    missing: Vec<MissingValue>,
+    /// Parameters that will be asked for while rendering. If any values are
+    /// asked for up front, these are asked for at the same time.
+    prompt_params: Vec<(Identifier, &'a RecipeParam)>,
+}
+
+impl<'a> Walk<'a> {
+    /// Check every field referenced by a template, recursing into the
+    /// templates that provide those fields
+    fn visit(
+        &mut self,
+        context: &SingleRenderContext<'a>,
+        template: &'a Template,
+    ) {
+        let mut stack = vec![template];
+        while let Some(template) = stack.pop() {
+            let mut fields = Vec::new();
+            let mut variables = Vec::new();
+            template.walk_required_expressions(|expression| match expression {
+                Expression::Field(field) => fields.push(field.clone()),
+                Expression::Variable(variable) => {
+                    variables.push(variable.clone());
+                }
+                _ => {}
+            });
+
+            for field in fields {
+                if !self.fields.contains(&field) {
+                    stack.extend(self.check_field(context, &field));
+                    self.fields.insert(field);
+                }
+            }
+            for variable in variables {
+                if self.variables.insert(variable.clone())
+                    && let Some(template) = context
+                        .context
+                        .collection
+                        .variables
+                        .get(variable.as_str())
+                {
+                    stack.push(template);
+                }
+            }
+        }
+    }
+
+    /// Determine where a field's value comes from. If it's missing, record it.
+    /// Otherwise return the template that provides it, if any, so it can be
+    /// checked as well.
+    fn check_field(
+        &mut self,
+        context: &SingleRenderContext<'a>,
+        field: &Identifier,
+    ) -> Option<&'a Template> {
+        let template_context = context.context;
+
+        // Parameters shadow everything else. This mirrors get_param()
+        if let Some(param) =
+            context.params.and_then(|params| params.get(field.as_str()))
+        {
+            if let Some(template) = template_context.params.get(field.as_str())
+            {
+                return Some(template);
+            }
+            if template_context.prompt_params {
+                self.prompt_params.push((field.clone(), param));
+                return param.default.as_ref();
+            }
+            if let Some(default) = &param.default {
+                return Some(default);
+            }
+            if param.required {
+                if template_context.prompt_missing {
+                    self.prompt_params.push((field.clone(), param));
+                }
+                self.missing.push(MissingValue::Param(field.clone()));
+                return None;
+            }
+            // Optional parameter with no value falls through to the profile
+        }
+
+        // Overrides and captures are given explicitly, so even empty values
+        // are intentional
+        if let Some(template) = template_context.overrides.get(field.as_str()) {
+            return Some(template);
+        }
+        if template_context.captures.get(field.as_str()).is_some() {
+            return None;
+        }
+
+        // The lenient render mode accepts undefined and empty fields
+        let strict = context.render_mode != RenderMode::Lenient;
+        let template =
+            template_context.toggle_field(field.as_str()).or_else(|| {
+                template_context.current_profile()?.data.get(field.as_str())
+            });
+        match template {
+            Some(template) if template.is_empty() => {
+                if strict {
+                    self.missing.push(MissingValue::FieldEmpty(field.clone()));
+                }
+                None
+            }
+            Some(template) => Some(template),
+            None => {
+                if strict {
+                    self.missing
+                        .push(MissingValue::FieldUndefined(field.clone()));
+                }
+                None
+            }
+        }
+    }
+}
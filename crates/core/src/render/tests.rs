@@ -2,30 +2,71 @@
 //! syntax/parsing here because that's handled in the template lib.
 
 use crate::{
-    collection::{Profile, Recipe},
+    collection::{
+        Collection, Profile, Recipe, RecipeParam, RecipeParamType, RenderMode,
+        Toggle, ToggleId,
+    },
     database::CollectionDatabase,
     http::{Exchange, HttpEngine, RequestId, RequestRecord, ResponseRecord},
-    render::TemplateContext,
+    render::{
+        Auditor, CommandCache, TemplateContext, TemplateReference,
+        functions::{TotpAlgorithm, hotp},
+    },
     test_util::{
         TestHttpProvider, TestPrompter, TestSelectPrompter, by_id, header_map,
         http_engine,
     },
 };
+use base64::{Engine, prelude::BASE64_STANDARD};
 use bytes::BytesMut;
 use chrono::{DateTime, Utc};
-use futures::{StreamExt, TryStreamExt};
+use futures::{StreamExt, TryStreamExt, join};
 use indexmap::{IndexMap, indexmap};
+use itertools::Itertools;
 use rstest::rstest;
 use serde_json::json;
+use slumber_config::{Config, TemplateSource};
 use slumber_template::{Expression, Literal, StreamSource, Template, Value};
 use slumber_util::{
-    Factory, TempDir, assert_matches, assert_result, paths::get_repo_root,
-    temp_dir,
+    Factory, TempDir, assert_err, assert_matches, assert_result,
+    paths::get_repo_root, temp_dir,
 };
 use std::time::Duration;
 use tokio::fs;
 use wiremock::{Mock, MockServer, ResponseTemplate, matchers};
 
+/// Find profile fields and recipes referenced by a template
+#[rstest]
+#[case::none("no references", vec![])]
+#[case::fields(
+    "{{ host }}/{{ user_id }}/{{ host }}",
+    vec![
+        TemplateReference::ProfileField("host".into()),
+        TemplateReference::ProfileField("user_id".into()),
+    ],
+)]
+#[case::response(
+    "{{ response('login') | jsonpath('$.token') }}",
+    vec![TemplateReference::Recipe("login".into())],
+)]
+#[case::response_header(
+    "{{ response_header('login', 'Token', trigger=ttl) }}",
+    vec![
+        TemplateReference::Recipe("login".into()),
+        TemplateReference::ProfileField("ttl".into()),
+    ],
+)]
+// Dynamic recipe IDs can't be resolved without rendering
+#[case::dynamic_recipe("{{ response(recipe) }}", vec![
+    TemplateReference::ProfileField("recipe".into()),
+])]
+fn test_template_references(
+    #[case] template: Template,
+    #[case] expected: Vec<TemplateReference>,
+) {
+    assert_eq!(TemplateReference::find_all(&template), expected);
+}
+
 /// Profile fields
 #[tokio::test]
 async fn test_profile() {
@@ -79,6 +120,272 @@ async fn test_override() {
     );
 }
 
+/// Values captured from responses are available as fields. They take
+/// precedence over the profile, but not over overrides
+#[rstest]
+#[case::capture(indexmap! {}, "captured")]
+#[case::override_(indexmap! {"token".into() => "override".into()}, "override")]
+#[tokio::test]
+async fn test_capture(
+    #[case] overrides: IndexMap<String, Template>,
+    #[case] expected: &str,
+) {
+    let profile = Profile {
+        data: indexmap! {"token".into() => "profile".into()},
+        ..Profile::factory(())
+    };
+    let recipe = Recipe {
+        capture: indexmap! {"token".into() => "$.token".parse().unwrap()},
+        ..Recipe::factory(())
+    };
+    let request = RequestRecord::factory((None, recipe.id.clone()));
+    let response = ResponseRecord {
+        body: json!({"token": "captured"}).into(),
+        ..ResponseRecord::factory(request.id)
+    };
+    let exchange = Exchange::factory((request, response));
+    let context = TemplateContext {
+        overrides,
+        ..TemplateContext::factory((by_id([profile]), by_id([recipe])))
+    };
+    context
+        .captures
+        .capture(&exchange, &context.collection)
+        .unwrap();
+
+    assert_eq!(
+        Template::from("{{ token }}")
+            .render_bytes(&context.streaming(false))
+            .await
+            .unwrap(),
+        expected
+    );
+}
+
+/// Collection variables, which are shared between all profiles
+#[rstest]
+#[case::value("{{ host }}/{{ vars.version }}", Ok("http://localhost/v2"))]
+#[case::nested("{{ vars.base }}/users", Ok("http://localhost/v2/users"))]
+// Variables are never prompted for, regardless of render mode
+#[case::unknown("{{ vars.missing }}", Err("Unknown variable `missing`"))]
+#[case::nested_error("{{ vars.invalid }}", Err("fake(): Unknown function"))]
+#[tokio::test]
+async fn test_variable(
+    #[case] template: Template,
+    #[case] expected: Result<&str, &str>,
+) {
+    let profile = Profile {
+        data: indexmap! {"host".into() => "http://localhost".into()},
+        ..Profile::factory(())
+    };
+    let profile_id = profile.id.clone();
+    let collection = Collection {
+        variables: indexmap! {
+            "version".into() => "v2".into(),
+            "base".into() => "{{ host }}/{{ vars.version }}".into(),
+            "invalid".into() => "{{ fake() }}".into(),
+        },
+        profiles: by_id([profile]),
+        ..Collection::default()
+    };
+    let context = TemplateContext {
+        collection: collection.into(),
+        selected_profile: Some(profile_id),
+        render_mode: Some(RenderMode::Prompt),
+        ..TemplateContext::factory(())
+    };
+    assert_result(
+        template.render_string(&context.streaming(false)).await,
+        expected,
+    );
+}
+
+/// Enabled toggles are layered on top of the profile. Later toggles take
+/// precedence, and overrides take precedence over all toggles
+#[rstest]
+#[case::none(&[], indexmap! {}, "profile-profile")]
+#[case::one(&["admin"], indexmap! {}, "admin-admin")]
+#[case::later_wins(&["admin", "guest"], indexmap! {}, "guest-admin")]
+#[case::order(&["guest", "admin"], indexmap! {}, "admin-admin")]
+#[case::unknown(&["unknown"], indexmap! {}, "profile-profile")]
+#[case::override_(
+    &["admin"],
+    indexmap! {"user".into() => "override".into()},
+    "override-admin",
+)]
+#[tokio::test]
+async fn test_toggle(
+    #[case] enabled_toggles: &[&str],
+    #[case] overrides: IndexMap<String, Template>,
+    #[case] expected: &str,
+) {
+    let profile = Profile {
+        data: indexmap! {
+            "user".into() => "profile".into(),
+            "role".into() => "profile".into(),
+        },
+        ..Profile::factory(())
+    };
+    let profile_id = profile.id.clone();
+    let collection = Collection {
+        profiles: by_id([profile]),
+        toggles: by_id([
+            Toggle {
+                id: "admin".into(),
+                data: indexmap! {
+                    "user".into() => "admin".into(),
+                    "role".into() => "admin".into(),
+                },
+                ..Toggle::factory(())
+            },
+            Toggle {
+                id: "guest".into(),
+                data: indexmap! {"user".into() => "guest".into()},
+                ..Toggle::factory(())
+            },
+        ]),
+        ..Collection::default()
+    };
+    let context = TemplateContext {
+        collection: collection.into(),
+        selected_profile: Some(profile_id),
+        enabled_toggles: enabled_toggles
+            .iter()
+            .map(|id| ToggleId::from(*id))
+            .collect(),
+        overrides,
+        ..TemplateContext::factory(())
+    };
+    assert_eq!(
+        Template::from("{{ user }}-{{ role }}")
+            .render_string(&context.streaming(false))
+            .await
+            .unwrap(),
+        expected
+    );
+}
+
+/// Undefined fields are handled according to the render mode. The mode on the
+/// context takes precedence over the recipe's
+#[rstest]
+#[case::default(None, None, Err("Unknown profile field `missing`"))]
+#[case::strict(
+    None,
+    Some(RenderMode::Strict),
+    Err("error in the `strict` render mode")
+)]
+#[case::lenient(None, Some(RenderMode::Lenient), Ok("-"))]
+// The user is only asked once per field
+#[case::prompt(None, Some(RenderMode::Prompt), Ok("answer-answer"))]
+#[case::context_override(
+    Some(RenderMode::Strict),
+    Some(RenderMode::Lenient),
+    Err("Unknown profile field `missing`")
+)]
+#[tokio::test]
+async fn test_render_mode(
+    #[case] context_mode: Option<RenderMode>,
+    #[case] recipe_mode: Option<RenderMode>,
+    #[case] expected: Result<&str, &str>,
+) {
+    let context = TemplateContext {
+        prompter: Box::new(TestPrompter::new(["answer"])),
+        render_mode: context_mode,
+        ..TemplateContext::factory(())
+    };
+    let template: Template = "{{ missing }}-{{ missing }}".parse().unwrap();
+    assert_result(
+        template
+            .render_string(
+                &context.streaming(false).with_render_mode(recipe_mode),
+            )
+            .await,
+        expected,
+    );
+}
+
+/// Fields named after a recipe parameter resolve to the given value, the user's
+/// answer to a prompt, or the parameter's default, converted to its type
+#[rstest]
+#[case::value(
+    RecipeParamType::Integer, false, None, Some("3"), false, Ok(3.into())
+)]
+#[case::default(
+    RecipeParamType::Integer, false, Some("4"), None, false, Ok(4.into())
+)]
+#[case::value_over_default(
+    RecipeParamType::String, false, Some("default"), Some("value"), false,
+    Ok("value".into())
+)]
+#[case::prompt(
+    RecipeParamType::Float, false, Some("4"), None, true, Ok(7.5.into())
+)]
+#[case::boolean(
+    RecipeParamType::Boolean, false, None, Some("false"), false,
+    Ok(false.into())
+)]
+#[case::required(
+    RecipeParamType::String,
+    true,
+    None,
+    None,
+    false,
+    Err("Missing value for required parameter `id`")
+)]
+// Optional parameters without a value fall back to the profile
+#[case::optional(
+    RecipeParamType::String,
+    false,
+    None,
+    None,
+    false,
+    Err("Unknown profile field `id`")
+)]
+#[case::wrong_type(
+    RecipeParamType::Integer,
+    false,
+    None,
+    Some("three"),
+    false,
+    Err("Parameter `id` expects integer, but got 'three'")
+)]
+#[tokio::test]
+async fn test_param(
+    #[case] param_type: RecipeParamType,
+    #[case] required: bool,
+    #[case] default: Option<&'static str>,
+    #[case] value: Option<&'static str>,
+    #[case] prompt_params: bool,
+    #[case] expected: Result<Value, &str>,
+) {
+    let params = indexmap! {
+        "id".to_owned() => RecipeParam {
+            param_type,
+            required,
+            default: default.map(Template::from),
+            description: None,
+        },
+    };
+    let context = TemplateContext {
+        params: value
+            .map(|value| ("id".to_owned(), Template::from(value)))
+            .into_iter()
+            .collect(),
+        prompt_params,
+        prompter: Box::new(TestPrompter::new(["7.5"])),
+        ..TemplateContext::factory(())
+    };
+    let template: Template = "{{ id }}".parse().unwrap();
+    assert_result(
+        template
+            .render(&context.streaming(false).with_params(&params))
+            .await
+            .try_collect_value()
+            .await,
+        expected,
+    );
+}
+
 /// `base64()`
 #[rstest]
 #[case::encode_string(b"test", false, Ok("dGVzdA==".as_bytes()))]
@@ -240,6 +547,126 @@ async fn test_command_lazy() {
     );
 }
 
+/// `command(cache=...)` should reuse output across render groups until the
+/// cache is cleared
+#[cfg(unix)]
+#[rstest]
+#[tokio::test]
+async fn test_command_cache(temp_dir: TempDir) {
+    // Each run appends a line to a file and prints the line count, so we can
+    // tell how many times the command was actually executed
+    let template: Template = "{{ command(['sh', '-c', \
+        'echo x >> runs.txt && wc -l < runs.txt | tr -d \" \"'], \
+        cwd=cwd, cache='1h') }}"
+        .parse()
+        .unwrap();
+    let command_cache = CommandCache::default();
+    let context = || TemplateContext {
+        overrides: indexmap! {
+            "cwd".into() => Template::raw(temp_dir.to_str().unwrap().into()),
+        },
+        command_cache: command_cache.clone(),
+        ..TemplateContext::factory(())
+    };
+    let render = async || {
+        template
+            .render_string(&context().streaming(false))
+            .await
+            .unwrap()
+    };
+
+    assert_eq!(render().await, "1\n");
+    // Second render in a new render group hits the cache
+    assert_eq!(render().await, "1\n");
+    // Cache hits are flagged so previews can show how fresh they are
+    let output = template.render(&context().streaming(false)).await;
+    assert!(output.cached_age().is_some());
+    assert_eq!(command_cache.clear(), 1);
+    assert_eq!(render().await, "2\n");
+}
+
+/// Concurrent renders in different render groups only run a command once at a
+/// time. A cached command that was waiting reuses the output of the first run
+#[cfg(unix)]
+#[rstest]
+#[tokio::test]
+async fn test_command_cache_concurrent(temp_dir: TempDir) {
+    let template: Template = "{{ command(['sh', '-c', \
+        'sleep 0.1; echo x >> runs.txt && wc -l < runs.txt | tr -d \" \"'], \
+        cwd=cwd, cache='1h') }}"
+        .parse()
+        .unwrap();
+    let command_cache = CommandCache::default();
+    let context = || TemplateContext {
+        overrides: indexmap! {
+            "cwd".into() => Template::raw(temp_dir.to_str().unwrap().into()),
+        },
+        command_cache: command_cache.clone(),
+        ..TemplateContext::factory(())
+    };
+    let (context1, context2) = (context(), context());
+    let (context1, context2) =
+        (context1.streaming(false), context2.streaming(false));
+
+    let (output1, output2) = join!(
+        template.render_string(&context1),
+        template.render_string(&context2),
+    );
+    assert_eq!(output1.unwrap(), "1\n");
+    assert_eq!(output2.unwrap(), "1\n");
+}
+
+/// `command()` kwargs for the subprocess environment and post-processing
+#[cfg(unix)]
+#[rstest]
+#[case::env(
+    "command(['sh', '-c', 'echo $GREETING'], env={'GREETING': 'hello'})",
+    Ok("hello\n".into()),
+)]
+#[case::trim_both(
+    "command(['printf', ' hello \\n'], trim='both')",
+    Ok("hello".into()),
+)]
+#[case::trim_start(
+    "command(['printf', ' hello \\n'], trim='start')",
+    Ok("hello \n".into()),
+)]
+#[case::trim_end(
+    "command(['printf', ' hello \\n'], trim='end')",
+    Ok(" hello".into()),
+)]
+#[case::json(
+    "command(['echo', '{\"token\": \"abc\"}'], json=true)",
+    Ok(json!({"token": "abc"}).into()),
+)]
+#[case::error_env_type(
+    "command(['env'], env=['GREETING=hello'])",
+    Err("Expected object")
+)]
+#[case::error_json(
+    "command(['echo', 'not json'], json=true)",
+    Err("expected ident at line 1 column 2")
+)]
+#[case::error_timeout(
+    "command(['sleep', '10'], timeout='1s')",
+    Err("Command `sleep 10` timed out after 1s")
+)]
+#[tokio::test]
+async fn test_command_kwargs(
+    #[case] template: &str,
+    #[case] expected: Result<Value, &str>,
+) {
+    let template: Template = format!("{{{{ {template} }}}}").parse().unwrap();
+    assert_result(
+        template
+            .render(&TemplateContext::factory(()).streaming(false))
+            .await
+            .try_collect_value()
+            .await,
+        expected,
+    );
+}
+
 /// `concat()`
 #[rstest]
 #[case::empty(vec![], Ok(""))]
@@ -286,6 +713,28 @@ async fn test_env(
     );
 }
 
+/// `fallback()`
+#[rstest]
+#[case::ok("{{ fallback('default', env('CARGO_PKG_NAME')) }}", "slumber_core")]
+#[case::command_error(
+    "{{ command(['fake-command']) | fallback('default') }}",
+    "default"
+)]
+#[case::selector_miss(
+    "{{ '{\"a\": 1}' | jsonpath('$.b') | fallback('default') }}",
+    "default"
+)]
+#[tokio::test]
+async fn test_fallback(#[case] template: Template, #[case] expected: &str) {
+    assert_eq!(
+        template
+            .render_string(&TemplateContext::factory(()).streaming(false))
+            .await
+            .unwrap(),
+        expected
+    );
+}
+
 /// `file()`
 #[rstest]
 #[case::text("data.txt", Ok("text".as_bytes()))]
@@ -323,6 +772,198 @@ async fn test_file(
     );
 }
 
+/// `file()` with `encoding`. Files are read in 4 KB chunks, so the large
+/// cases check that base64 groups are carried across chunk boundaries
+#[rstest]
+#[case::base64("data.txt", "base64", Ok("dGV4dA==".to_owned()))]
+#[case::base64_large(
+    "large.bin",
+    "base64",
+    Ok(BASE64_STANDARD.encode(large_file())),
+)]
+#[case::hex("data.txt", "hex", Ok("74657874".to_owned()))]
+#[case::hex_large(
+    "large.bin",
+    "hex",
+    Ok(large_file().iter().map(|byte| format!("{byte:02x}")).join("")),
+)]
+#[case::empty("empty.txt", "base64", Ok(String::new()))]
+#[case::error_encoding(
+    "data.txt",
+    "base32",
+    Err("Invalid encoding `base32`; must be `base64` or `hex`")
+)]
+#[case::error_not_exists(
+    "fake.txt",
+    "base64",
+    Err(if cfg!(unix) {
+        "No such file or directory"
+    } else {
+        "The system cannot find the file specified"
+    })
+)]
+#[tokio::test]
+async fn test_file_encoding(
+    temp_dir: TempDir,
+    #[case] path: &str,
+    #[case] encoding: &str,
+    #[case] expected: Result<String, &str>,
+) {
+    fs::write(temp_dir.join("data.txt"), "text").await.unwrap();
+    fs::write(temp_dir.join("large.bin"), large_file())
+        .await
+        .unwrap();
+    fs::write(temp_dir.join("empty.txt"), "").await.unwrap();
+
+    let template = Template::function_call(
+        "file",
+        [path.into()],
+        [("encoding", Some(encoding.into()))],
+    );
+    let context = TemplateContext {
+        root_dir: temp_dir.to_owned(),
+        ..TemplateContext::factory(())
+    };
+    assert_result(
+        template.render_string(&context.streaming(true)).await,
+        expected,
+    );
+}
+
+/// Encoded files get their own stream source, so consumers that read files
+/// natively don't skip the encoding. Large files get a warning.
+#[rstest]
+#[case::small(10, "as base64")]
+#[case::large(
+    8_000_000,
+    "as base64 (10.7 MB when encoded; consider a raw or multipart body)"
+)]
+#[tokio::test]
+async fn test_file_encoding_source(
+    temp_dir: TempDir,
+    #[case] size: u64,
+    #[case] expected: &str,
+) {
+    // Sparse file, so we don't actually have to write 8 MB
+    let path = temp_dir.join("data.bin");
+    std::fs::File::create(&path).unwrap().set_len(size).unwrap();
+
+    let template: Template =
+        "{{ file('data.bin', encoding='base64') }}".parse().unwrap();
+    let context = TemplateContext {
+        root_dir: temp_dir.to_owned(),
+        ..TemplateContext::factory(())
+    };
+    let output = template.render(&context.streaming(true)).await;
+    let source = output.stream_source().unwrap();
+    assert_matches!(source, StreamSource::EncodedFile { .. });
+    assert_eq!(
+        source.to_string(),
+        format!("file {} {expected}", path.display())
+    );
+}
+
+/// `file_select()`
+#[rstest]
+#[case::json("data.json", "$.user.id", None, None, Ok(3.into()))]
+#[case::yaml("data.yaml", "$.tags[1]", None, None, Ok("b".into()))]
+#[case::yml("data.yml", "$.name", None, None, Ok("yml".into()))]
+#[case::csv_column(
+    "data.csv",
+    "email",
+    None,
+    Some("array"),
+    Ok(vec!["a@example.com", "b,c@example.com"].into())
+)]
+#[case::csv_jsonpath(
+    "data.csv",
+    "$[1].note",
+    None,
+    None,
+    Ok("say \"hi\"\nbye".into())
+)]
+#[case::format_override("data.txt", "$.a", Some("json"), None, Ok(1.into()))]
+#[case::error_unknown_format(
+    "data.txt",
+    "$.a",
+    None,
+    None,
+    Err("Cannot determine format of file")
+)]
+#[case::error_parse("bad.json", "$.a", None, None, Err("as JSON"))]
+#[case::error_no_results(
+    "data.json",
+    "$.missing",
+    None,
+    None,
+    Err("No results from JSON query `$.missing`")
+)]
+#[case::error_invalid_query(
+    "data.json",
+    "bad query",
+    None,
+    None,
+    Err("parser error")
+)]
+#[case::error_not_exists(
+    "fake.json",
+    "$.a",
+    None,
+    None,
+    Err(if cfg!(unix) {
+        "No such file or directory"
+    } else {
+        "The system cannot find the file specified"
+    })
+)]
+#[tokio::test]
+async fn test_file_select(
+    temp_dir: TempDir,
+    #[case] path: &str,
+    #[case] selector: &str,
+    #[case] format: Option<&str>,
+    #[case] mode: Option<&str>,
+    #[case] expected: Result<Value, &str>,
+) {
+    let files = [
+        ("data.json", r#"{"user": {"id": 3}}"#),
+        ("data.yaml", "tags: [a, b]"),
+        ("data.yml", "name: yml"),
+        (
+            "data.csv",
+            "email,note\r\na@example.com,hello\r\n\
+            \"b,c@example.com\",\"say \"\"hi\"\"\nbye\"\r\n",
+        ),
+        ("data.txt", r#"{"a": 1}"#),
+        ("bad.json", "{"),
+    ];
+    for (name, content) in files {
+        fs::write(temp_dir.join(name), content).await.unwrap();
+    }
+
+    // Path should be relative to the context's root dir
+    let template = Template::function_call(
+        "file_select",
+        [path.into(), selector.into()],
+        [
+            ("format", format.map(Expression::from)),
+            ("mode", mode.map(Expression::from)),
+        ],
+    );
+    let context = TemplateContext {
+        root_dir: temp_dir.to_owned(),
+        ..TemplateContext::factory(())
+    };
+    assert_result(
+        template
+            .render(&context.streaming(false))
+            .await
+            .try_collect_value()
+            .await,
+        expected,
+    );
+}
+
 /// Bonus test case for ~ expansion in file(). Only test on Linux because
 /// setting the home dir on Windows is annoying. As long as we call expand_home
 /// we can trust it will work
@@ -348,6 +989,82 @@ async fn test_file_tilde(temp_dir: TempDir) {
     drop(guard);
 }
 
+/// Functions that access the local system are disabled for untrusted
+/// collections
+#[rstest]
+#[case::command("{{ command(['echo', 'hi']) }}", "`command()` is disabled")]
+#[case::file("{{ file('data.json') }}", "`file()` is disabled")]
+#[case::file_select(
+    "{{ file_select('data.json', '$.a') }}",
+    "`file_select()` is disabled"
+)]
+#[case::keyring(
+    "{{ keyring('fish-api', 'admin') }}",
+    "`keyring()` is disabled"
+)]
+#[tokio::test]
+async fn test_sandboxed(#[case] template: Template, #[case] expected: &str) {
+    let context = TemplateContext {
+        sandboxed: true,
+        ..TemplateContext::factory(())
+    };
+    assert_result(
+        template.render_bytes(&context.streaming(false)).await,
+        Err::<&[u8], _>(expected),
+    );
+}
+
+/// External effects are recorded in the audit log, and denied effects never
+/// happen
+#[rstest]
+#[case::command(
+    "{{ command(['echo', 'hi']) }}",
+    TemplateSource::Command,
+    "echo hi"
+)]
+#[case::env("{{ env('HOME') }}", TemplateSource::Env, "HOME")]
+#[tokio::test]
+async fn test_audit(
+    #[case] template: Template,
+    #[case] source: TemplateSource,
+    #[case] detail: &str,
+) {
+    // Allowed
+    let database = CollectionDatabase::factory(());
+    let config = Config {
+        audit_log: true,
+        confirm_sources: vec![source],
+        ..Config::default()
+    };
+    let context = TemplateContext {
+        auditor: Auditor::new(&config, database.clone()),
+        prompter: Box::new(TestSelectPrompter::new([0])),
+        ..TemplateContext::factory(())
+    };
+    template
+        .render_bytes(&context.streaming(false))
+        .await
+        .unwrap();
+    let events = database.get_audit_events(None).unwrap();
+    assert_matches!(
+        events.as_slice(),
+        [event] if event.source == source && event.detail == detail
+    );
+
+    // Denied
+    let database = CollectionDatabase::factory(());
+    let context = TemplateContext {
+        auditor: Auditor::new(&config, database.clone()),
+        prompter: Box::new(TestSelectPrompter::new([1])),
+        ..TemplateContext::factory(())
+    };
+    assert_result(
+        template.render_bytes(&context.streaming(false)).await,
+        Err::<&[u8], _>("was denied"),
+    );
+    assert_eq!(database.get_audit_events(None).unwrap(), []);
+}
+
 /// `float()`
 #[rstest]
 #[case::null(Expression::Literal(Literal::Null), Ok(0.0))]
@@ -894,6 +1611,123 @@ async fn test_response(
     );
 }
 
+/// `response(trigger='token_expiry')` reuses the cached response until the
+/// token in it is about to expire
+#[rstest]
+#[case::no_history(None, true)]
+#[case::valid(Some(json!({"access_token": "abc", "expires_in": 3600})), false)]
+#[case::expiring(Some(json!({"access_token": "abc", "expires_in": 10})), true)]
+#[case::no_expiry(Some(json!({"access_token": "abc"})), false)]
+#[tokio::test]
+async fn test_response_token_expiry(
+    #[case] cached_body: Option<serde_json::Value>,
+    #[case] expect_triggered: bool,
+    http_engine: HttpEngine,
+) {
+    let template = Template::function_call(
+        "response",
+        ["upstream".into()],
+        [("trigger", Some("token_expiry".into()))],
+    );
+
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("triggered"))
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        id: "upstream".into(),
+        url: format!("{host}/token").parse().unwrap(),
+        ..Recipe::factory(())
+    };
+    let database = CollectionDatabase::factory(());
+    if let Some(body) = &cached_body {
+        let id = RequestId::new();
+        let exchange = Exchange {
+            id,
+            request: RequestRecord::factory((id, None, recipe.id.clone()))
+                .into(),
+            response: ResponseRecord {
+                body: body.to_string().as_bytes().into(),
+                ..ResponseRecord::factory(id)
+            }
+            .into(),
+            start_time: Utc::now(),
+            end_time: Utc::now(),
+        };
+        database.insert_exchange(&exchange).unwrap();
+    }
+    let context = TemplateContext {
+        http_provider: Box::new(TestHttpProvider::new(
+            database,
+            Some(http_engine),
+        )),
+        ..TemplateContext::factory((IndexMap::new(), by_id([recipe])))
+    };
+
+    let expected = if expect_triggered {
+        "triggered".to_owned()
+    } else {
+        cached_body.unwrap().to_string()
+    };
+    assert_result(
+        template.render_bytes(&context.streaming(false)).await,
+        Ok(expected.as_str()),
+    );
+}
+
+/// After [TemplateContext::reauthenticate], upstream requests are re-sent even
+/// if their trigger would otherwise use the cached response
+#[rstest]
+#[tokio::test]
+async fn test_response_reauthenticate(http_engine: HttpEngine) {
+    let template = Template::function_call("response", ["upstream".into()], []);
+
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("triggered"))
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        id: "upstream".into(),
+        url: format!("{host}/token").parse().unwrap(),
+        ..Recipe::factory(())
+    };
+    let database = CollectionDatabase::factory(());
+    let request = RequestRecord::factory((None, recipe.id.clone()));
+    let response = ResponseRecord {
+        body: b"stale".as_slice().into(),
+        ..ResponseRecord::factory(request.id)
+    };
+    let exchange = Exchange::factory((request, response));
+    database.insert_exchange(&exchange).unwrap();
+    let mut context = TemplateContext {
+        http_provider: Box::new(TestHttpProvider::new(
+            database,
+            Some(http_engine),
+        )),
+        ..TemplateContext::factory((IndexMap::new(), by_id([recipe])))
+    };
+
+    // Cached response is used by default
+    assert_result(
+        template.render_bytes(&context.streaming(false)).await,
+        Ok("stale"),
+    );
+
+    context.reauthenticate();
+    assert_result(
+        template.render_bytes(&context.streaming(false)).await,
+        Ok("triggered"),
+    );
+}
+
 /// `response_header()`. We're leaning on the `response()` tests for most of
 /// the work here, and just testing things specific to headers
 #[rstest]
@@ -963,6 +1797,103 @@ async fn test_response_header(
     );
 }
 
+/// Multiple references to the same recipe within a render group should only
+/// trigger the request once
+#[rstest]
+#[tokio::test]
+async fn test_response_memoized(http_engine: HttpEngine) {
+    let template: Template = "{{ response('upstream', trigger='always') }} \
+        {{ response_header('upstream', 'My-Header', trigger='always') }}"
+        .parse()
+        .unwrap();
+
+    // Mock HTTP response. The mock will panic on drop if it's called more
+    // than once
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/get"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("My-Header", "header")
+                .set_body_string("body"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        id: "upstream".into(),
+        url: format!("{host}/get").parse().unwrap(),
+        ..Recipe::factory(())
+    };
+    let context = TemplateContext {
+        http_provider: Box::new(TestHttpProvider::new(
+            CollectionDatabase::factory(()),
+            Some(http_engine),
+        )),
+        ..TemplateContext::factory((IndexMap::new(), by_id([recipe])))
+    };
+
+    assert_eq!(
+        template
+            .render_string(&context.streaming(false))
+            .await
+            .unwrap(),
+        "body header"
+    );
+}
+
+/// References to the same recipe with different triggers are resolved
+/// independently. One loads from history while the other sends a new request
+#[rstest]
+#[tokio::test]
+async fn test_response_memoized_by_trigger(http_engine: HttpEngine) {
+    let template: Template = "{{ response('upstream') }} \
+        {{ response('upstream', trigger='always') }}"
+        .parse()
+        .unwrap();
+
+    let server = MockServer::start().await;
+    let host = server.uri();
+    Mock::given(matchers::method("GET"))
+        .and(matchers::path("/get"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("triggered"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recipe = Recipe {
+        id: "upstream".into(),
+        url: format!("{host}/get").parse().unwrap(),
+        ..Recipe::factory(())
+    };
+    let database = CollectionDatabase::factory(());
+    let request = RequestRecord::factory((None, recipe.id.clone()));
+    let response = ResponseRecord {
+        body: b"cached".as_slice().into(),
+        ..ResponseRecord::factory(request.id)
+    };
+    database
+        .insert_exchange(&Exchange::factory((request, response)))
+        .unwrap();
+    let context = TemplateContext {
+        http_provider: Box::new(TestHttpProvider::new(
+            database,
+            Some(http_engine),
+        )),
+        ..TemplateContext::factory((IndexMap::new(), by_id([recipe])))
+    };
+
+    assert_eq!(
+        template
+            .render_string(&context.streaming(false))
+            .await
+            .unwrap(),
+        "cached triggered"
+    );
+}
+
 /// `select()`
 #[rstest]
 #[case::reply(
@@ -1120,6 +2051,91 @@ async fn test_string(
     );
 }
 
+/// `totp()`. The code changes with time, so we can only check its shape here.
+/// Code generation is checked against the RFC test vectors in [test_hotp].
+#[rstest]
+#[case::default("{{ totp('JBSWY3DPEHPK3PXP') }}", Ok(6))]
+#[case::formatted("{{ totp('jbsw y3dp-ehpk 3pxp====') }}", Ok(6))]
+#[case::digits("{{ totp('JBSWY3DPEHPK3PXP', digits=8) }}", Ok(8))]
+#[case::algorithm(
+    "{{ totp('JBSWY3DPEHPK3PXP', period=60, algorithm='SHA512') }}",
+    Ok(6)
+)]
+#[case::error_secret("{{ totp('JBSWY3DP1') }}", Err("Invalid TOTP secret"))]
+#[case::error_secret_empty("{{ totp('') }}", Err("Invalid TOTP secret"))]
+#[case::error_digits(
+    "{{ totp('JBSWY3DPEHPK3PXP', digits=5) }}",
+    Err("Invalid TOTP length 5")
+)]
+#[case::error_period(
+    "{{ totp('JBSWY3DPEHPK3PXP', period=0) }}",
+    Err("TOTP period must be greater than 0")
+)]
+#[case::error_algorithm(
+    "{{ totp('JBSWY3DPEHPK3PXP', algorithm='md5') }}",
+    Err("Invalid algorithm `md5`")
+)]
+#[tokio::test]
+async fn test_totp(
+    #[case] template: Template,
+    #[case] expected: Result<usize, &str>,
+) {
+    let result = template
+        .render_string(&TemplateContext::factory(()).streaming(false))
+        .await;
+    match expected {
+        Ok(digits) => {
+            let code = result.unwrap();
+            assert_eq!(code.len(), digits, "Incorrect length for `{code}`");
+            assert!(code.chars().all(|c| c.is_ascii_digit()), "{code}");
+        }
+        Err(expected) => assert_err(result, expected),
+    }
+}
+
+/// Code generation for `totp()`, using the test vectors from RFC 4226
+/// (HOTP) and RFC 6238 (TOTP). TOTP counters are `time / 30`.
+#[rstest]
+#[case::hotp_0(b"12345678901234567890", 0, 6, TotpAlgorithm::Sha1, "755224")]
+#[case::hotp_9(b"12345678901234567890", 9, 6, TotpAlgorithm::Sha1, "520489")]
+#[case::sha1_59(
+    b"12345678901234567890",
+    59 / 30,
+    8,
+    TotpAlgorithm::Sha1,
+    "94287082"
+)]
+#[case::sha1_1111111109(
+    b"12345678901234567890",
+    1_111_111_109 / 30,
+    8,
+    TotpAlgorithm::Sha1,
+    "07081804"
+)]
+#[case::sha256_1234567890(
+    b"12345678901234567890123456789012",
+    1_234_567_890 / 30,
+    8,
+    TotpAlgorithm::Sha256,
+    "91819424"
+)]
+#[case::sha512_20000000000(
+    b"1234567890123456789012345678901234567890123456789012345678901234",
+    20_000_000_000 / 30,
+    8,
+    TotpAlgorithm::Sha512,
+    "47863826"
+)]
+fn test_hotp(
+    #[case] key: &[u8],
+    #[case] counter: u64,
+    #[case] digits: u32,
+    #[case] algorithm: TotpAlgorithm,
+    #[case] expected: &str,
+) {
+    assert_eq!(hotp(key, counter, digits, algorithm), expected);
+}
+
 /// `trim()`
 #[rstest]
 #[case::default("  test  ", None, "test")]
@@ -1267,3 +2283,9 @@ async fn test_stream_chunks(temp_dir: TempDir) {
 fn invalid_utf8() -> &'static [u8] {
     b"\xc3\x28"
 }
+
+/// Binary file that's large enough to be read in multiple chunks. The length
+/// isn't a multiple of 3, so base64 encoding needs padding.
+fn large_file() -> Vec<u8> {
+    (0..10_000u32).map(|i| (i * 7 % 256) as u8).collect()
+}
@@ -1,17 +1,24 @@
-use slumber_template::{Identifier, Value};
+use bytes::Bytes;
+use itertools::Itertools;
+use slumber_util::TimeSpan;
 use std::{
-    collections::{HashMap, hash_map::Entry},
+    collections::{BTreeMap, HashMap, hash_map::Entry},
+    env,
+    ffi::OsStr,
+    hash::Hash,
     ops::DerefMut,
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{self, Arc, PoisonError, Weak},
+    time::{Duration, Instant},
 };
 use tokio::sync::{Mutex, OwnedMutexGuard};
 use tracing::error;
 
-/// A cache of template values that either have been computed, or are
-/// asynchronously being computed. This allows multiple references to the same
-/// template field to share their work.
-#[derive(Debug, Default)]
-pub struct FieldCache {
+/// A cache of values that either have been computed, or are asynchronously
+/// being computed. This allows multiple references to the same value (e.g. the
+/// same template field) to share their work.
+#[derive(Debug)]
+pub struct FutureCache<K, V> {
     /// Cache each value by key. The outer mutex will only be held open for as
     /// long as it takes to check if the value is in the cache or not. The
     /// inner mutex will be blocked on until the value is available.
@@ -21,36 +28,33 @@ pub struct FieldCache {
     /// which case the second entrant has to upgrade their read to a write. The
     /// contention on the mutex should be extremely low once the write is done,
     /// so the difference between mutex and rwlock is minimal.
-    cache: Mutex<HashMap<Identifier, Arc<Mutex<Option<Value>>>>>,
+    cache: Mutex<HashMap<K, Arc<Mutex<Option<V>>>>>,
 }
 
-impl FieldCache {
+impl<K: Eq + Hash, V: Clone> FutureCache<K, V> {
     /// Get a value from the cache, or if not present, insert a placeholder
     /// value and return a guard that can be used to insert the completed value
     /// later. The placeholder will tell subsequent accessors of this key that
     /// the value is being computed, and will be present later. If the
     /// placeholder is present and the final value being computed, **this block
     /// will not return until the value is available**.
-    pub(crate) async fn get_or_init(
-        &self,
-        field: Identifier,
-    ) -> FieldCacheOutcome {
+    pub(crate) async fn get_or_init(&self, key: K) -> FutureCacheOutcome<V> {
         let mut cache = self.cache.lock().await;
-        match cache.entry(field) {
+        match cache.entry(key) {
             Entry::Occupied(entry) => {
                 let lock = Arc::clone(entry.get());
                 drop(cache); // Drop the outer lock before acquiring the inner
                 let guard = lock.clone().lock_owned().await;
 
                 if let Some(value) = &*guard {
-                    FieldCacheOutcome::Hit(value.clone())
+                    FutureCacheOutcome::Hit(value.clone())
                 } else {
                     // If someone else grabbed the lock but didn't write to it,
                     // we're now responsible for computing+caching it. This can
                     // happen in two scenarios:
                     // - Other task failed in an unexpected way
                     // - Field evaluated to a stream, which can't be cached
-                    FieldCacheOutcome::Miss(FieldCacheGuard(guard))
+                    FutureCacheOutcome::Miss(FutureCacheGuard(guard))
                 }
             }
             Entry::Vacant(entry) => {
@@ -65,37 +69,46 @@ impl FieldCache {
                 // own future, to prevent other tasks grabbing it first
                 drop(cache);
 
-                FieldCacheOutcome::Miss(FieldCacheGuard(guard))
+                FutureCacheOutcome::Miss(FutureCacheGuard(guard))
             }
         }
     }
 }
 
+// Derive would add unnecessary `Default` bounds on the params
+impl<K, V> Default for FutureCache<K, V> {
+    fn default() -> Self {
+        Self {
+            cache: Mutex::default(),
+        }
+    }
+}
+
 /// Outcome of check a future cache for a particular key
 #[derive(Debug)]
-pub(crate) enum FieldCacheOutcome {
+pub(crate) enum FutureCacheOutcome<V> {
     /// The value is already in the cache
-    Hit(Value),
+    Hit(V),
     /// The value is not in the cache. Caller is responsible for inserting it
-    /// by calling [FieldCacheGuard::set] once computed.
-    Miss(FieldCacheGuard),
+    /// by calling [FutureCacheGuard::set] once computed.
+    Miss(FutureCacheGuard<V>),
 }
 
 /// A handle for writing a computed future value back into the cache. This is
 /// returned once per key, to the first caller of that key. The caller is then
-/// responsible for calling [FieldCacheGuard::set] to insert the value for
+/// responsible for calling [FutureCacheGuard::set] to insert the value for
 /// everyone else. Subsequent callers to the cache will block until `set` is
 /// called.
 #[derive(Debug)]
-pub(crate) struct FieldCacheGuard(OwnedMutexGuard<Option<Value>>);
+pub(crate) struct FutureCacheGuard<V>(OwnedMutexGuard<Option<V>>);
 
-impl FieldCacheGuard {
-    pub fn set(mut self, value: Value) {
+impl<V> FutureCacheGuard<V> {
+    pub fn set(mut self, value: V) {
         *self.0.deref_mut() = Some(value);
     }
 }
 
-impl Drop for FieldCacheGuard {
+impl<V> Drop for FutureCacheGuard<V> {
     fn drop(&mut self) {
         if self.0.is_none() {
             // Friendly little error logging. We don't have a good way of
@@ -105,23 +118,143 @@ impl Drop for FieldCacheGuard {
     }
 }
 
+/// A cache of `command()` outputs, for commands that opt into caching with the
+/// `cache` argument. Unlike [FutureCache], this is meant to persist across
+/// render groups, so the same instance should be shared between all template
+/// contexts for a session. Clones are cheap and share the same storage.
+///
+/// This also limits each distinct command to one run at a time across all
+/// render groups, so concurrent renders (e.g. a burst of previews) don't
+/// stampede whatever the command talks to. A cached command that was waiting
+/// on another run will reuse its output.
+#[derive(Clone, Debug, Default)]
+pub struct CommandCache {
+    outputs: Arc<sync::Mutex<HashMap<CommandCacheKey, (Instant, Bytes)>>>,
+    /// A lock for each command that's currently running or waiting to run.
+    /// Entries are pruned once nobody holds them.
+    running: Arc<sync::Mutex<HashMap<CommandCacheKey, Weak<Mutex<()>>>>>,
+}
+
+impl CommandCache {
+    /// Get the cached output for a command and its age, if it's present and
+    /// younger than `ttl`
+    pub(crate) fn get(
+        &self,
+        key: &CommandCacheKey,
+        ttl: TimeSpan,
+    ) -> Option<(Bytes, Duration)> {
+        let cache = self.outputs.lock().unwrap_or_else(PoisonError::into_inner);
+        let (cached_at, output) = cache.get(key)?;
+        let age = cached_at.elapsed();
+        (age < ttl.inner()).then(|| (output.clone(), age))
+    }
+
+    /// Insert the output of a command, replacing any previous entry
+    pub(crate) fn insert(&self, key: CommandCacheKey, output: Bytes) {
+        self.outputs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(key, (Instant::now(), output));
+    }
+
+    /// Wait until no other render is running this command, then return a
+    /// guard that blocks others from running it until dropped
+    pub(crate) async fn lock(
+        &self,
+        key: &CommandCacheKey,
+    ) -> OwnedMutexGuard<()> {
+        let lock = {
+            let mut running =
+                self.running.lock().unwrap_or_else(PoisonError::into_inner);
+            running.retain(|_, lock| lock.strong_count() > 0);
+            if let Some(lock) = running.get(key).and_then(Weak::upgrade) {
+                lock
+            } else {
+                let lock = Arc::new(Mutex::new(()));
+                running.insert(key.clone(), Arc::downgrade(&lock));
+                lock
+            }
+        };
+        lock.lock_owned().await
+    }
+
+    /// Remove all entries from the cache, so the next render of each cached
+    /// command will re-run it. Return the number of removed entries.
+    pub fn clear(&self) -> usize {
+        let mut cache =
+            self.outputs.lock().unwrap_or_else(PoisonError::into_inner);
+        let len = cache.len();
+        cache.clear();
+        len
+    }
+}
+
+/// Everything that determines a command's output, as far as we know
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub(crate) struct CommandCacheKey {
+    pub command: Vec<String>,
+    pub cwd: PathBuf,
+    pub stdin: Option<Bytes>,
+    pub env: BTreeMap<String, String>,
+}
+
+/// Get the path to run for a `command()` program. On Windows, only `.exe`
+/// files are found automatically when the program has no extension, so scripts
+/// like `npm.cmd` can't be run as `npm` the way they can in a shell. To match
+/// shell behavior, search `PATH` for the program with each extension in
+/// `PATHEXT`. On other platforms, the OS handles lookup so this does nothing.
+pub(crate) fn resolve_program(program: &str) -> PathBuf {
+    if cfg!(windows) {
+        let path = env::var_os("PATH").unwrap_or_default();
+        let extensions = env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_owned());
+        if let Some(resolved) = find_program(program, &path, &extensions) {
+            return resolved;
+        }
+    }
+    program.into()
+}
+
+/// Search each directory in `path` for `program` with any of the
+/// `;`-separated `extensions`. Return `None` if the program already has an
+/// extension or a directory component, or if it can't be found.
+fn find_program(
+    program: &str,
+    path: &OsStr,
+    extensions: &str,
+) -> Option<PathBuf> {
+    let program_path = Path::new(program);
+    if program_path.extension().is_some()
+        || program_path.parent() != Some(Path::new(""))
+    {
+        return None;
+    }
+    env::split_paths(path)
+        .cartesian_product(extensions.split(';').filter(|ext| !ext.is_empty()))
+        .map(|(dir, extension)| dir.join(format!("{program}{extension}")))
+        .find(|candidate| candidate.is_file())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use futures::join;
-    use slumber_util::assert_matches;
+    use rstest::rstest;
+    use slumber_template::{Identifier, Value};
+    use slumber_util::{TempDir, assert_matches, temp_dir};
+    use std::fs;
 
     /// If the first writer doesn't write anything, the second should get a
     /// chance to
     #[tokio::test]
-    async fn test_field_cache() {
-        let cache = FieldCache::default();
+    async fn test_future_cache() {
+        let cache = FutureCache::<Identifier, Value>::default();
         let field: Identifier = "field".into();
 
         let fut1 = async {
             let guard = assert_matches!(
                 cache.get_or_init(field.clone()).await,
-                FieldCacheOutcome::Miss(guard) => guard,
+                FutureCacheOutcome::Miss(guard) => guard,
             );
             let value: Value = true.into();
             guard.set(value.clone());
@@ -130,7 +263,7 @@ mod tests {
         let fut2 = async {
             assert_matches!(
                 cache.get_or_init(field.clone()).await,
-                FieldCacheOutcome::Hit(value) => value,
+                FutureCacheOutcome::Hit(value) => value,
             )
         };
 
@@ -143,15 +276,15 @@ mod tests {
     /// If the first writer doesn't write anything, the second should get a
     /// chance to
     #[tokio::test]
-    async fn test_field_cache_dropped_guard() {
-        let cache = FieldCache::default();
+    async fn test_future_cache_dropped_guard() {
+        let cache = FutureCache::<Identifier, Value>::default();
         let field: Identifier = "field".into();
 
         let fut1 = async {
             // We get the write guard, but never write to it
             let guard = assert_matches!(
                 cache.get_or_init(field.clone()).await,
-                FieldCacheOutcome::Miss(guard) => guard,
+                FutureCacheOutcome::Miss(guard) => guard,
             );
             drop(guard);
         };
@@ -159,7 +292,7 @@ mod tests {
             // After fut1 drops the write guard, we get it and write to it
             let guard = assert_matches!(
                 cache.get_or_init(field.clone()).await,
-                FieldCacheOutcome::Miss(guard) => guard,
+                FutureCacheOutcome::Miss(guard) => guard,
             );
             let value: Value = true.into();
             guard.set(value.clone());
@@ -169,7 +302,7 @@ mod tests {
             // We get the value written by fut2
             assert_matches!(
                 cache.get_or_init(field.clone()).await,
-                FieldCacheOutcome::Hit(value) => value,
+                FutureCacheOutcome::Hit(value) => value,
             )
         };
 
@@ -178,4 +311,28 @@ mod tests {
         assert_eq!(v2, true.into());
         assert_eq!(v3, true.into());
     }
+
+    /// Programs without an extension are found with any of the extensions
+    #[rstest]
+    #[case::cmd("npm", Some("npm.cmd"))]
+    #[case::first_extension_wins("both", Some("both.exe"))]
+    #[case::has_extension("npm.cmd", None)]
+    #[case::has_directory("./npm", None)]
+    #[case::missing("missing", None)]
+    fn test_find_program(
+        temp_dir: TempDir,
+        #[case] program: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        for file in ["npm.cmd", "both.exe", "both.cmd"] {
+            fs::write(temp_dir.join(file), "").unwrap();
+        }
+        let path =
+            env::join_paths([temp_dir.join("empty"), temp_dir.to_path_buf()])
+                .unwrap();
+        assert_eq!(
+            find_program(program, &path, ".exe;.cmd;"),
+            expected.map(|file| temp_dir.join(file))
+        );
+    }
 }
@@ -4,20 +4,28 @@ use crate::{
     collection::{HasId, ProfileId, RecipeId},
     database::CollectionDatabase,
     http::{
-        Exchange, HttpEngine, RequestSeed, StoredRequestError,
-        TriggeredRequestError,
+        Exchange, HttpEngine, RequestSeed, StoredRequestError, Transport,
+        TransportError, TriggeredRequestError,
     },
     render::{HttpProvider, Prompt, Prompter, TemplateContext},
 };
 use async_trait::async_trait;
+use bytes::Bytes;
+use futures::{FutureExt, future::BoxFuture};
 use indexmap::IndexMap;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{
+    Request, Response, StatusCode,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
 use rstest::fixture;
 use slumber_config::HttpEngineConfig;
 use slumber_template::Template;
 use std::{
     hash::Hash,
-    sync::atomic::{AtomicUsize, Ordering},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
 /// A template that spits out bytes that are *not* valid UTF-8
@@ -37,6 +45,46 @@ pub fn http_engine() -> HttpEngine {
     })
 }
 
+/// [Transport] that never touches the network. Every request gets the same
+/// response, and each request is recorded so tests can check what was sent.
+/// Clones share the same request log.
+#[derive(Clone, Debug)]
+pub struct MockTransport {
+    status: StatusCode,
+    body: Bytes,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockTransport {
+    pub fn new(status: StatusCode, body: impl Into<Bytes>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+            requests: Default::default(),
+        }
+    }
+
+    /// Get every request sent so far, in order
+    pub fn requests(&self) -> MutexGuard<'_, Vec<Request>> {
+        self.requests.lock().unwrap()
+    }
+}
+
+impl Transport for MockTransport {
+    fn send(
+        &self,
+        request: Request,
+    ) -> BoxFuture<'static, Result<Response, TransportError>> {
+        let response = http::Response::builder()
+            .status(self.status)
+            .body(self.body.clone())
+            .map(Response::from)
+            .map_err(TransportError::new);
+        self.requests().push(request);
+        futures::future::ready(response).boxed()
+    }
+}
+
 /// [HttpProvider] implementation for tests. This pulls persisted requests from
 /// the DB, but does not persist new requests. Triggered requests are sent only
 /// if an HTTP engine is provided.
@@ -13,6 +13,17 @@ pub fn confirm(prompt: impl Into<String>) -> bool {
         .unwrap_or(false)
 }
 
+/// Encode bytes as lowercase hexadecimal
+pub fn encode_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        hex.push(DIGITS[(byte >> 4) as usize] as char);
+        hex.push(DIGITS[(byte & 0x0f) as usize] as char);
+    }
+    hex
+}
+
 /// Helper to printing bytes. If the bytes aren't valid UTF-8, they'll be
 /// printed in hex representation instead
 pub struct MaybeStr<'a>(pub &'a [u8]);
@@ -433,6 +433,8 @@ enum TypeDef {
     Value,
     /// A homogenous list
     List(&'static Self),
+    /// An object with arbitrary string keys and homogenous values
+    Map(&'static Self),
     /// An object with a static set of fields
     Struct(&'static [(&'static str, &'static Self)]),
     /// Any of a set of types
@@ -486,6 +488,7 @@ impl Display for TypeDef {
                     write!(f, "{inner}[]")
                 }
             }
+            Self::Map(inner) => write!(f, "{{ [key: string]: {inner} }}"),
             Self::Struct(fields) => {
                 // { "field1": value1, "field2": value2 }
                 write!(
@@ -526,6 +529,12 @@ fn type_map() -> HashMap<Type, TypeDef> {
             parse_quote!(CommandOutputMode),
             union!("stdout" | "stderr" | "both"),
         ),
+        (parse_quote!(FileEncoding), union!("base64" | "hex")),
+        (parse_quote!(FileFormat), union!("json" | "yaml" | "csv")),
+        (
+            parse_quote!(IndexMap<String, String>),
+            TypeDef::Map(&TypeDef::String),
+        ),
         (parse_quote!(JaqQuery), TypeDef::String),
         (parse_quote!(JsonPath), TypeDef::String),
         (parse_quote!(JsonValue), TypeDef::Value),
@@ -557,6 +566,11 @@ fn type_map() -> HashMap<Type, TypeDef> {
         // We're hiding streams from the type system, since they will
         // transparently convert to bytes
         (parse_quote!(LazyValue), TypeDef::Bytes),
+        (parse_quote!(TimeSpan), TypeDef::Custom("Duration")),
+        (
+            parse_quote!(TotpAlgorithm),
+            union!("sha1" | "sha256" | "sha512"),
+        ),
         (parse_quote!(TrimMode), union!("start" | "end" | "both")),
         (parse_quote!(slumber_template::Value), TypeDef::Value),
         (parse_quote!(Value), TypeDef::Value),
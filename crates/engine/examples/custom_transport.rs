@@ -0,0 +1,39 @@
+//! Register a custom [Transport], which responds to every request without
+//! touching the network. This is useful for tests, or for sending requests
+//! through a channel other than a direct connection.
+//!
+//! ```sh
+//! cargo run -p slumber_engine --example custom_transport -- <recipe>
+//! ```
+
+use futures::{FutureExt, future::BoxFuture};
+use slumber_engine::{Engine, Error, Transport, TransportError};
+use std::env;
+
+/// A transport that responds to each request with its own URL
+#[derive(Debug)]
+struct EchoUrl;
+
+impl Transport for EchoUrl {
+    fn send(
+        &self,
+        request: reqwest::Request,
+    ) -> BoxFuture<'static, Result<reqwest::Response, TransportError>> {
+        let response = http::Response::new(request.url().to_string());
+        async move { Ok(response.into()) }.boxed()
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Error> {
+    let recipe_id = env::args()
+        .nth(1)
+        .expect("Usage: custom_transport <recipe>");
+
+    // Registering the transport as `reqwest` replaces the default transport,
+    // so it's used for every recipe
+    let engine = Engine::builder().transport("reqwest", EchoUrl).build()?;
+    let exchange = engine.request(recipe_id).send().await?;
+    println!("{}", exchange.response.body.text().unwrap_or_default());
+    Ok(())
+}
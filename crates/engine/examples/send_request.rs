@@ -0,0 +1,32 @@
+//! Send a request from the collection in the current directory, and print the
+//! response body.
+//!
+//! ```sh
+//! cargo run -p slumber_engine --example send_request -- <recipe> [profile]
+//! ```
+
+use slumber_engine::{Engine, Error};
+use std::env;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> Result<(), Error> {
+    let mut args = env::args().skip(1);
+    let recipe_id =
+        args.next().expect("Usage: send_request <recipe> [profile]");
+    let profile_id = args.next();
+
+    let engine = Engine::builder().build()?;
+    let mut request = engine.request(recipe_id).persist(true);
+    if let Some(profile_id) = profile_id {
+        request = request.profile(profile_id);
+    }
+    let exchange = request.send().await?;
+
+    eprintln!("{} {}", exchange.request.method, exchange.request.url);
+    eprintln!("{}", exchange.response.status);
+    match exchange.response.body.text() {
+        Some(text) => println!("{text}"),
+        None => println!("<{} bytes of binary>", exchange.response.body.size()),
+    }
+    Ok(())
+}
@@ -0,0 +1,554 @@
+//! Embed Slumber's request engine in other Rust programs. This crate loads
+//! request collections, renders templates, builds and sends requests, and
+//! reads and writes request history, all the same way the Slumber CLI does.
+//! There's no terminal interaction though; anything that needs the user has to
+//! be provided by the caller (see [Prompter]).
+//!
+//! ```no_run
+//! use slumber_engine::Engine;
+//!
+//! # async fn run() -> Result<(), slumber_engine::Error> {
+//! // Load the collection from the current directory, just like the CLI
+//! let engine = Engine::builder().build()?;
+//! let exchange = engine
+//!     .request("login")
+//!     .profile("production")
+//!     .send()
+//!     .await?;
+//! println!("{}", exchange.response.status);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! See the `examples/` directory for complete programs.
+//!
+//! ## Async
+//!
+//! Requests are sent with [reqwest](https://docs.rs/reqwest), so all futures
+//! must be run within a [tokio](https://docs.rs/tokio) runtime. Template
+//! rendering isn't thread-safe, so request and render futures are **not**
+//! `Send`. Run them on a current-thread runtime or within a
+//! [LocalSet](https://docs.rs/tokio/latest/tokio/task/struct.LocalSet.html).
+//!
+//! ## Versioning
+//!
+//! Unlike Slumber's other library crates, **this crate is semver compliant**.
+//! Its version is locked to the root `slumber` crate version, and breaking
+//! changes to its API are only made in major releases. This guarantee covers
+//! everything re-exported from this crate, including the types that originate
+//! in Slumber's internal crates. It does _not_ extend to anything reached by
+//! depending on those internal crates directly.
+//!
+//! Some exceptions:
+//!
+//! - [Error] and other enums marked `#[non_exhaustive]` may gain new variants
+//!   in minor releases
+//! - New fields in the collection and config file formats are added in minor
+//!   releases. These are reflected in the Rust types as new struct fields, so
+//!   construct those types by loading files rather than with struct literals
+//! - The minimum supported Rust version may be raised in minor releases
+
+mod request;
+
+pub use request::{NoPrompter, RenderBuilder, RequestBuilder};
+pub use slumber_config::{Config, ConfigError, HttpEngineConfig};
+pub use slumber_core::{
+    collection::{
+        Collection, CollectionError, CollectionFile, HttpMethod, Profile,
+        ProfileId, Recipe, RecipeId,
+    },
+    database::{DatabaseError, ProfileFilter},
+    http::{
+        BuildOptions, Exchange, ExchangeSummary, HttpVersion,
+        RequestBuildError, RequestError, RequestId, RequestRecord,
+        ResponseBody, ResponseRecord, Transport, TransportError,
+    },
+    render::{Prompt, Prompter, ReplyChannel, SelectOption},
+};
+pub use slumber_template::{RenderError, TemplateParseError, Value};
+
+use slumber_core::{
+    database::{CollectionDatabase, Database},
+    http::{CaptureStore, CookieJar, HttpEngine, TokenStore},
+    render::CommandCache,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use thiserror::Error;
+
+/// A loaded request collection, ready to build and send requests. This is the
+/// entrypoint for everything in this crate. Create one with [Engine::builder].
+///
+/// This is cheap to clone; clones share the same collection, database
+/// connection, and HTTP client.
+#[derive(Clone, Debug)]
+pub struct Engine {
+    collection_file: CollectionFile,
+    collection: Arc<Collection>,
+    config: Arc<Config>,
+    database: CollectionDatabase,
+    http: HttpEngine,
+    /// Should templates be denied access to the local system? Determined by
+    /// the `trusted_directories` config field
+    sandboxed: bool,
+    /// Shared between all renders, so cached `command()` output is reused
+    command_cache: CommandCache,
+    /// Values captured from responses, shared between all renders
+    captures: CaptureStore,
+    /// OAuth2 access tokens, shared between all renders
+    oauth2_tokens: TokenStore,
+    /// Cookie jar, shared between all requests
+    cookies: CookieJar,
+}
+
+impl Engine {
+    /// Start configuring a new engine
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder::default()
+    }
+
+    /// The loaded request collection
+    pub fn collection(&self) -> &Collection {
+        &self.collection
+    }
+
+    /// Path of the file that the collection was loaded from
+    pub fn collection_path(&self) -> &Path {
+        self.collection_file.path()
+    }
+
+    /// The configuration used by this engine
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Reload the collection from its file. Use this to pick up changes made
+    /// to the file since the engine was built. If the file is invalid, the
+    /// previous collection is kept and an error is returned.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.collection = Arc::new(self.collection_file.load()?);
+        Ok(())
+    }
+
+    /// Start building a request for a recipe. Call
+    /// [send](RequestBuilder::send) on the returned builder to send it.
+    pub fn request(
+        &self,
+        recipe_id: impl Into<RecipeId>,
+    ) -> RequestBuilder<'_> {
+        RequestBuilder::new(self, recipe_id.into())
+    }
+
+    /// Start rendering a template string against the collection. Call
+    /// [render](RenderBuilder::render) on the returned builder to render it.
+    pub fn render_template(&self, template: &str) -> RenderBuilder<'_> {
+        RenderBuilder::new(self, template)
+    }
+
+    /// Get a request from history by its ID
+    pub fn get_request(
+        &self,
+        request_id: RequestId,
+    ) -> Result<Option<Exchange>, Error> {
+        Ok(self.database.get_request(request_id)?)
+    }
+
+    /// Get the most recent request for a recipe from history
+    pub fn get_latest_request(
+        &self,
+        profile_filter: ProfileFilter,
+        recipe_id: &RecipeId,
+    ) -> Result<Option<Exchange>, Error> {
+        Ok(self
+            .database
+            .get_latest_request(profile_filter, recipe_id)?)
+    }
+
+    /// Get a summary of each request in history for a recipe, newest first.
+    /// Use [Self::get_request] to load the full request and response.
+    pub fn get_recipe_requests(
+        &self,
+        profile_filter: ProfileFilter,
+        recipe_id: &RecipeId,
+        limit: Option<usize>,
+    ) -> Result<Vec<ExchangeSummary>, Error> {
+        Ok(self.database.get_recipe_requests(
+            profile_filter,
+            recipe_id,
+            limit,
+        )?)
+    }
+
+    /// Add a completed request to history. Requests sent with
+    /// [persist](RequestBuilder::persist) enabled are stored automatically.
+    pub fn insert_request(&self, exchange: &Exchange) -> Result<(), Error> {
+        Ok(self.database.insert_exchange(exchange)?)
+    }
+}
+
+/// Configure and load an [Engine]. Every setting is optional; the defaults
+/// match the behavior of the Slumber CLI.
+#[derive(Debug, Default)]
+pub struct EngineBuilder {
+    collection_path: Option<PathBuf>,
+    config: Option<Config>,
+    database_directory: Option<PathBuf>,
+    session: Option<String>,
+    transports: Vec<(String, Arc<dyn Transport>)>,
+}
+
+impl EngineBuilder {
+    /// Load a specific collection file. If a directory is given, the
+    /// collection file is detected within that directory. By default, the
+    /// collection file is detected within the current directory.
+    #[must_use]
+    pub fn collection_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.collection_path = Some(path.into());
+        self
+    }
+
+    /// Use the given configuration instead of loading the user's config file
+    #[must_use]
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Store request history in a database in the given directory, instead of
+    /// the database shared with the Slumber CLI and TUI. The directory must
+    /// already exist.
+    #[must_use]
+    pub fn database_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.database_directory = Some(directory.into());
+        self
+    }
+
+    /// Use a named session, with its own cookie jar and OAuth2 token cache.
+    /// Request history is shared between sessions. If not set, cookies and
+    /// tokens are shared with the Slumber CLI and TUI's default session.
+    #[must_use]
+    pub fn session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    /// Register a [Transport] to send requests with. Recipes can select it by
+    /// name with their `transport` field, or it can be made the default with
+    /// the `transport` config field. Registering an existing name replaces that
+    /// transport, including the built-in `reqwest` transport.
+    #[must_use]
+    pub fn transport(
+        mut self,
+        name: impl Into<String>,
+        transport: impl Transport + 'static,
+    ) -> Self {
+        self.transports.push((name.into(), Arc::new(transport)));
+        self
+    }
+
+    /// Load the config, collection, and database, and build the engine
+    pub fn build(self) -> Result<Engine, Error> {
+        let config = match self.config {
+            Some(config) => config,
+            None => Config::load()?,
+        };
+        let collection_file = CollectionFile::new(self.collection_path)?;
+        let collection = collection_file.load()?;
+        let database = match &self.database_directory {
+            Some(directory) => Database::from_directory(directory)?,
+            None => Database::load()?,
+        }
+        .into_collection(&collection_file)?
+        .with_session(self.session);
+        database.set_name(&collection);
+        let sandboxed = !collection_file
+            .is_trusted(config.trusted_directories.as_deref(), &database);
+
+        let http_engine = self.transports.into_iter().fold(
+            HttpEngine::new(&config.http),
+            |http_engine, (name, transport)| {
+                http_engine.with_transport(name, transport)
+            },
+        );
+
+        Ok(Engine {
+            collection_file,
+            collection: Arc::new(collection),
+            config: Arc::new(config),
+            oauth2_tokens: TokenStore::new(database.clone()),
+            cookies: CookieJar::new(database.clone()),
+            database,
+            http: http_engine,
+            sandboxed,
+            command_cache: CommandCache::default(),
+            captures: CaptureStore::default(),
+        })
+    }
+}
+
+/// Any error that can occur while using an [Engine]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// Error loading the config file
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+
+    /// Error finding or loading the collection file
+    #[error(transparent)]
+    Collection(#[from] CollectionError),
+
+    /// Error reading or writing request history
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+
+    /// Error parsing a template string
+    #[error(transparent)]
+    TemplateParse(#[from] TemplateParseError),
+
+    /// Error rendering a template
+    #[error(transparent)]
+    Render(#[from] RenderError),
+
+    /// Error rendering a recipe into a request
+    #[error(transparent)]
+    RequestBuild(Box<RequestBuildError>),
+
+    /// Error sending a request or receiving its response
+    #[error(transparent)]
+    Request(Box<RequestError>),
+}
+
+impl From<RequestBuildError> for Error {
+    fn from(error: RequestBuildError) -> Self {
+        Self::RequestBuild(error.into())
+    }
+}
+
+impl From<RequestError> for Error {
+    fn from(error: RequestError) -> Self {
+        Self::Request(error.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+    use serde_json::json;
+    use slumber_util::{TempDir, temp_dir};
+    use std::fs;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header, method, path},
+    };
+
+    /// Build an engine for a collection in a temp dir, with an isolated
+    /// database
+    fn engine(temp_dir: &TempDir, collection: &str) -> Engine {
+        fs::write(temp_dir.join("slumber.yml"), collection).unwrap();
+        Engine::builder()
+            .collection_path(temp_dir.to_path_buf())
+            .config(Config::default())
+            .database_directory(temp_dir.to_path_buf())
+            .build()
+            .unwrap()
+    }
+
+    /// Build and send a request, then find it in history
+    #[rstest]
+    #[tokio::test]
+    async fn test_request(temp_dir: TempDir) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/users"))
+            .and(header("x-token", "abc"))
+            .respond_with(ResponseTemplate::new(201).set_body_string("ok"))
+            .mount(&server)
+            .await;
+        let collection = format!(
+            "
+profiles:
+  local:
+    default: true
+    data:
+      host: {host}
+      token: wrong
+requests:
+  create_user:
+    method: POST
+    url: \"{{{{ host }}}}/users\"
+    headers:
+      X-Token: \"{{{{ token }}}}\"
+",
+            host = server.uri()
+        );
+        let engine = engine(&temp_dir, &collection);
+        let recipe_id: RecipeId = "create_user".into();
+
+        let exchange = engine
+            .request(recipe_id.clone())
+            .override_field("token", "abc")
+            .persist(true)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(exchange.response.status.as_u16(), 201);
+        assert_eq!(exchange.response.body.text(), Some("ok"));
+        assert_eq!(exchange.request.profile_id, Some(ProfileId::from("local")));
+
+        let latest = engine
+            .get_latest_request(ProfileFilter::All, &recipe_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.id, exchange.id);
+        let summaries = engine
+            .get_recipe_requests(ProfileFilter::All, &recipe_id, None)
+            .unwrap();
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|summary| summary.id)
+                .collect::<Vec<_>>(),
+            [exchange.id]
+        );
+    }
+
+    /// Requests aren't stored in history unless requested
+    #[rstest]
+    #[tokio::test]
+    async fn test_request_no_persist(temp_dir: TempDir) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let collection = format!(
+            "
+requests:
+  get:
+    method: GET
+    url: {host}
+",
+            host = server.uri()
+        );
+        let engine = engine(&temp_dir, &collection);
+
+        let exchange = engine.request("get").send().await.unwrap();
+        assert_eq!(exchange.response.status.as_u16(), 200);
+        assert!(
+            engine
+                .get_latest_request(ProfileFilter::All, &"get".into())
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    /// Render templates with a selected profile
+    #[rstest]
+    #[case::default_profile(None, &[], "Hello, Alice!")]
+    #[case::profile(Some("other"), &[], "Hello, Bob!")]
+    #[case::override_field(None, &[("name", "Carol")], "Hello, Carol!")]
+    #[tokio::test]
+    async fn test_render_template(
+        temp_dir: TempDir,
+        #[case] profile: Option<&str>,
+        #[case] overrides: &[(&str, &str)],
+        #[case] expected: &str,
+    ) {
+        let engine = engine(
+            &temp_dir,
+            "
+profiles:
+  default:
+    default: true
+    data:
+      name: Alice
+  other:
+    data:
+      name: Bob
+requests: {}
+",
+        );
+        let mut builder = engine.render_template("Hello, {{ name }}!");
+        if let Some(profile) = profile {
+            builder = builder.profile(profile);
+        }
+        for (field, value) in overrides {
+            builder = builder.override_field(*field, *value);
+        }
+        assert_eq!(builder.render().await.unwrap(), expected);
+    }
+
+    /// Prompts fail unless a prompter is given
+    #[rstest]
+    #[tokio::test]
+    async fn test_render_template_prompt(temp_dir: TempDir) {
+        #[derive(Debug)]
+        struct Reply;
+
+        impl Prompter for Reply {
+            fn prompt(&self, prompt: Prompt) {
+                if let Prompt::Text { channel, .. } = prompt {
+                    channel.reply("yes".into());
+                }
+            }
+        }
+
+        let engine = engine(&temp_dir, "requests: {}");
+        let template = "{{ prompt(message='Continue?') }}";
+        assert!(matches!(
+            engine.render_template(template).render().await,
+            Err(Error::Render(_))
+        ));
+        assert_eq!(
+            engine
+                .render_template(template)
+                .prompter(Reply)
+                .render()
+                .await
+                .unwrap(),
+            "yes"
+        );
+    }
+
+    /// Invalid templates and unknown recipes are reported as errors
+    #[rstest]
+    #[tokio::test]
+    async fn test_errors(temp_dir: TempDir) {
+        let engine = engine(&temp_dir, "requests: {}");
+        assert!(matches!(
+            engine.render_template("{{ bad").render().await,
+            Err(Error::TemplateParse(_))
+        ));
+        assert!(matches!(
+            engine.request("unknown").send().await,
+            Err(Error::RequestBuild(_))
+        ));
+    }
+
+    /// Reload picks up changes to the collection file
+    #[rstest]
+    fn test_reload(temp_dir: TempDir) {
+        let mut engine = engine(&temp_dir, "requests: {}");
+        assert_eq!(engine.collection().recipes.recipe_ids().count(), 0);
+
+        fs::write(
+            temp_dir.join("slumber.yml"),
+            json!({"requests": {"r1": {"method": "GET", "url": "/"}}})
+                .to_string(),
+        )
+        .unwrap();
+        engine.reload().unwrap();
+        assert!(
+            engine
+                .collection()
+                .recipes
+                .get_recipe(&"r1".into())
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Builders for sending requests and rendering templates
+
+use crate::{Engine, Error};
+use async_trait::async_trait;
+use indexmap::IndexMap;
+use slumber_core::{
+    collection::{ProfileId, RecipeId, ToggleId},
+    database::CollectionDatabase,
+    http::{
+        BuildOptions, Exchange, HttpEngine, RequestSeed, StoredRequestError,
+        TriggeredRequestError,
+    },
+    render::{Auditor, HttpProvider, Prompt, Prompter, TemplateContext},
+};
+use slumber_template::Template;
+use slumber_util::ResultTraced;
+
+/// Build and send a request for a recipe. Create one with
+/// [Engine::request].
+#[derive(Debug)]
+#[must_use = "Requests are not sent until `send` is called"]
+pub struct RequestBuilder<'a> {
+    engine: &'a Engine,
+    recipe_id: RecipeId,
+    options: BuildOptions,
+    context: ContextOptions,
+    persist: bool,
+}
+
+impl<'a> RequestBuilder<'a> {
+    pub(crate) fn new(engine: &'a Engine, recipe_id: RecipeId) -> Self {
+        Self {
+            engine,
+            recipe_id,
+            options: BuildOptions::default(),
+            context: ContextOptions::default(),
+            persist: false,
+        }
+    }
+
+    /// Render the request with this profile. Defaults to the collection's
+    /// default profile, if any.
+    pub fn profile(mut self, profile_id: impl Into<ProfileId>) -> Self {
+        self.context.profile_id = Some(profile_id.into());
+        self
+    }
+
+    /// Enable a toggle, layering its values on top of the profile. Call
+    /// multiple times to enable multiple toggles; if they define the same
+    /// field, the last one wins.
+    pub fn toggle(mut self, toggle_id: impl Into<ToggleId>) -> Self {
+        self.context.toggles.push(toggle_id.into());
+        self
+    }
+
+    /// Override a single profile field with a static value. The override also
+    /// applies to any upstream requests triggered while rendering.
+    pub fn override_field(
+        mut self,
+        field: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.context.override_field(field.into(), value.into());
+        self
+    }
+
+    /// Set a value for one of the recipe's parameters. Parameters that aren't
+    /// given a value fall back to their default.
+    pub fn param(
+        mut self,
+        param: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        // Like overrides, parameter values are static
+        self.context
+            .params
+            .insert(param.into(), Template::raw(value.into()));
+        self
+    }
+
+    /// Answer prompts from `prompt()` and `select()` with this prompter. By
+    /// default, prompts receive no reply and the render fails.
+    pub fn prompter(mut self, prompter: impl Prompter + 'static) -> Self {
+        self.context.prompter = Box::new(prompter);
+        self
+    }
+
+    /// Allow `response()` and `response_header()` to send upstream requests?
+    /// If disabled, upstream requests must already be in history. Enabled by
+    /// default.
+    pub fn trigger_dependencies(mut self, trigger: bool) -> Self {
+        self.context.trigger_dependencies = trigger;
+        self
+    }
+
+    /// Override parts of the recipe for this request only, e.g. the URL or
+    /// individual headers
+    pub fn options(mut self, options: BuildOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Store the request and response in history, along with any upstream
+    /// requests triggered while rendering. Disabled by default.
+    pub fn persist(mut self, persist: bool) -> Self {
+        self.persist = persist;
+        self
+    }
+
+    /// Render the recipe into a request and send it. A response with an error
+    /// status code (4xx/5xx) is _not_ considered an error. If the recipe has
+    /// `reauthenticate` enabled, a `401`/`403` response is retried once.
+    pub async fn send(self) -> Result<Exchange, Error> {
+        let mut context = self.context.into_context(self.engine, self.persist);
+        let seed = RequestSeed::new(self.recipe_id, self.options);
+        // Rendering builds a large future, so keep it off the stack
+        let ticket =
+            Box::pin(self.engine.http.build(seed.clone(), &context)).await?;
+        let mut exchange = ticket.send().await?;
+        // If the recipe opts in, refresh upstream requests and retry once when
+        // authentication is rejected
+        if exchange.should_reauthenticate(&context.collection) {
+            context.reauthenticate();
+            let ticket =
+                Box::pin(self.engine.http.build(seed, &context)).await?;
+            exchange = ticket.send().await?;
+        }
+        if self.persist {
+            self.engine.database.insert_exchange(&exchange)?;
+        }
+        // A failed capture doesn't invalidate the response, so just log it
+        let _ = self
+            .engine
+            .captures
+            .capture(&exchange, &context.collection)
+            .traced();
+        Ok(exchange)
+    }
+}
+
+/// Render a template string against a collection. Create one with
+/// [Engine::render_template].
+#[derive(Debug)]
+#[must_use = "Templates are not rendered until `render` is called"]
+pub struct RenderBuilder<'a> {
+    engine: &'a Engine,
+    template: String,
+    context: ContextOptions,
+}
+
+impl<'a> RenderBuilder<'a> {
+    pub(crate) fn new(engine: &'a Engine, template: &str) -> Self {
+        Self {
+            engine,
+            template: template.to_owned(),
+            context: ContextOptions::default(),
+        }
+    }
+
+    /// Render with this profile. Defaults to the collection's default profile,
+    /// if any.
+    pub fn profile(mut self, profile_id: impl Into<ProfileId>) -> Self {
+        self.context.profile_id = Some(profile_id.into());
+        self
+    }
+
+    /// Enable a toggle, layering its values on top of the profile. Call
+    /// multiple times to enable multiple toggles; if they define the same
+    /// field, the last one wins.
+    pub fn toggle(mut self, toggle_id: impl Into<ToggleId>) -> Self {
+        self.context.toggles.push(toggle_id.into());
+        self
+    }
+
+    /// Override a single profile field with a static value
+    pub fn override_field(
+        mut self,
+        field: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Self {
+        self.context.override_field(field.into(), value.into());
+        self
+    }
+
+    /// Answer prompts from `prompt()` and `select()` with this prompter. By
+    /// default, prompts receive no reply and the render fails.
+    pub fn prompter(mut self, prompter: impl Prompter + 'static) -> Self {
+        self.context.prompter = Box::new(prompter);
+        self
+    }
+
+    /// Allow `response()` and `response_header()` to send upstream requests?
+    /// If disabled, upstream requests must already be in history. Enabled by
+    /// default. Triggered requests are never stored in history.
+    pub fn trigger_dependencies(mut self, trigger: bool) -> Self {
+        self.context.trigger_dependencies = trigger;
+        self
+    }
+
+    /// Render the template to a string. Returns an error if the template is
+    /// invalid, fails to render, or renders to non-UTF-8 bytes.
+    pub async fn render(self) -> Result<String, Error> {
+        let template: Template = self.template.parse()?;
+        let context = self.context.into_context(self.engine, false);
+        let output = template.render_string(&context.streaming(false)).await?;
+        Ok(output)
+    }
+}
+
+/// Settings shared by all builders that render templates
+#[derive(Debug)]
+struct ContextOptions {
+    profile_id: Option<ProfileId>,
+    toggles: Vec<ToggleId>,
+    overrides: IndexMap<String, Template>,
+    params: IndexMap<String, Template>,
+    prompter: Box<dyn Prompter>,
+    trigger_dependencies: bool,
+}
+
+impl ContextOptions {
+    fn override_field(&mut self, field: String, value: String) {
+        // Overrides are static values, so don't parse them as templates
+        self.overrides.insert(field, Template::raw(value));
+    }
+
+    fn into_context(self, engine: &Engine, persist: bool) -> TemplateContext {
+        // Fall back to the default profile if defined in the collection
+        let selected_profile = self.profile_id.or_else(|| {
+            let default_profile = engine.collection.default_profile()?;
+            Some(default_profile.id.clone())
+        });
+        TemplateContext {
+            collection: engine.collection.clone(),
+            selected_profile,
+            enabled_toggles: self.toggles,
+            http_provider: Box::new(EngineHttpProvider {
+                database: engine.database.clone(),
+                http_engine: engine.http.clone(),
+                trigger_dependencies: self.trigger_dependencies,
+                persist,
+            }),
+            overrides: self.overrides,
+            params: self.params,
+            prompt_params: false,
+            prompt_missing: false,
+            prompter: self.prompter,
+            show_sensitive: true,
+            root_dir: engine.collection_file.parent().to_owned(),
+            sandboxed: engine.sandboxed,
+            auditor: Auditor::new(&engine.config, engine.database.clone()),
+            state: Default::default(),
+            command_cache: engine.command_cache.clone(),
+            captures: engine.captures.clone(),
+            render_mode: None,
+            oauth2_tokens: engine.oauth2_tokens.clone(),
+            cookies: engine.cookies.clone(),
+        }
+    }
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self {
+            profile_id: None,
+            toggles: Vec::new(),
+            overrides: IndexMap::new(),
+            params: IndexMap::new(),
+            prompter: Box::new(NoPrompter),
+            trigger_dependencies: true,
+        }
+    }
+}
+
+/// A [Prompter] that never replies, causing every prompt to fail. This is the
+/// default for all renders, because there's no user to ask.
+#[derive(Debug)]
+pub struct NoPrompter;
+
+impl Prompter for NoPrompter {
+    fn prompt(&self, _: Prompt) {}
+}
+
+/// [HttpProvider] for the engine. Triggered requests are persisted only if the
+/// root request will be.
+#[derive(Debug)]
+struct EngineHttpProvider {
+    database: CollectionDatabase,
+    http_engine: HttpEngine,
+    trigger_dependencies: bool,
+    persist: bool,
+}
+
+#[async_trait(?Send)]
+impl HttpProvider for EngineHttpProvider {
+    async fn get_latest_request(
+        &self,
+        profile_id: Option<&ProfileId>,
+        recipe_id: &RecipeId,
+    ) -> Result<Option<Exchange>, StoredRequestError> {
+        self.database
+            .get_latest_request(profile_id.into(), recipe_id)
+            .map_err(StoredRequestError::new)
+    }
+
+    async fn send_request(
+        &self,
+        seed: RequestSeed,
+        template_context: &TemplateContext,
+    ) -> Result<Exchange, TriggeredRequestError> {
+        if self.trigger_dependencies {
+            let ticket = self.http_engine.build(seed, template_context).await?;
+            let exchange = ticket.send().await?;
+            if self.persist {
+                // A failure to persist shouldn't fail the render
+                let _ = self.database.insert_exchange(&exchange).traced();
+            }
+            Ok(exchange)
+        } else {
+            Err(TriggeredRequestError::NotAllowed)
+        }
+    }
+}
@@ -10,12 +10,9 @@ use itertools::Itertools;
 use mime::Mime;
 use reqwest::header;
 use serde::{Deserialize, Deserializer, de::Error as _};
-use slumber_core::{
-    collection::{
-        self, Collection, Folder, HasId, Profile, ProfileId, Recipe,
-        RecipeBody, RecipeId, RecipeNode, RecipeTree,
-    },
-    http::HttpMethod,
+use slumber_core::collection::{
+    self, AuthenticationSetting, Collection, Folder, HasId, HttpMethod,
+    Profile, ProfileId, Recipe, RecipeBody, RecipeId, RecipeNode, RecipeTree,
 };
 use slumber_template::Template;
 use slumber_util::{NEW_ISSUE_LINK, yaml::SourceLocation};
@@ -63,7 +60,10 @@ pub async fn from_insomnia(input: &ImportInput) -> anyhow::Result<Collection> {
 
     Ok(Collection {
         name: None,
+        template_delimiters: None,
+        variables: IndexMap::new(),
         profiles,
+        toggles: IndexMap::new(),
         recipes,
     })
 }
@@ -161,6 +161,16 @@ enum Authentication {
     Bearer {
         token: String,
     },
+    Digest {
+        username: String,
+        password: String,
+    },
+    /// Insomnia doesn't have a separate domain field. It can be included in
+    /// the username as `DOMAIN\user`
+    Ntlm {
+        username: String,
+        password: String,
+    },
     /// Catch-all for unknown variants
     #[serde(untagged)]
     Other {
@@ -289,6 +299,7 @@ impl From<RequestGroup> for RecipeNode {
             id: folder.id.into(),
             location: SourceLocation::default(),
             name: Some(folder.name),
+            authentication: None,
             // This will be populated later
             children: IndexMap::new(),
         })
@@ -339,7 +350,7 @@ impl From<Request> for RecipeNode {
                         request.id
                     );
                 }
-                result.ok()
+                result.ok().map(AuthenticationSetting::Defined)
             });
 
         RecipeNode::Recipe(Recipe {
@@ -356,6 +367,24 @@ impl From<Request> for RecipeNode {
                 }),
             ),
             headers,
+            user_agent: None,
+            transport: None,
+            certificate: None,
+            retry: None,
+            redirect: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            compression: None,
+            reauthenticate: false,
+            capture: IndexMap::new(),
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: None,
+            params: IndexMap::new(),
             authentication,
         })
     }
@@ -381,7 +410,13 @@ impl TryFrom<Body> for RecipeBody {
             )
         } else if body.mime_type == mime::MULTIPART_FORM_DATA {
             RecipeBody::FormMultipart(
-                body.params.into_iter().map(FormParam::into).collect(),
+                body.params
+                    .into_iter()
+                    .map(|param| {
+                        let (field, value): (String, Template) = param.into();
+                        (field, value.into())
+                    })
+                    .collect(),
             )
         } else {
             RecipeBody::Raw(parse_template(body.try_text()?))
@@ -431,6 +466,19 @@ impl TryFrom<Authentication> for collection::Authentication {
                     token: parse_template(token),
                 })
             }
+            Authentication::Digest { username, password } => {
+                Ok(collection::Authentication::Digest {
+                    username: parse_template(username),
+                    password: parse_template(password),
+                })
+            }
+            Authentication::Ntlm { username, password } => {
+                Ok(collection::Authentication::Ntlm {
+                    username: parse_template(username),
+                    password: parse_template(password),
+                    domain: None,
+                })
+            }
             // Caller should print a warning for this
             Authentication::Other { kind } => Err(kind),
         }
@@ -481,6 +529,15 @@ fn build_profiles(
                     name: Some(environment.name),
                     default: false,
                     data,
+                    chaos: None,
+                    certificate: None,
+                    proxy: None,
+                    timeout: None,
+                    throttle: None,
+                    protocol: None,
+                    socket: None,
+                    resolve: IndexMap::new(),
+                    pinning: None,
                 },
             )
         })
@@ -10,12 +10,9 @@ use openapiv3::{
     Operation, Parameter, PathItem, PathStyle, Paths, ReferenceOr, RequestBody,
     Schema, SchemaKind, SecurityScheme, Server, Type,
 };
-use slumber_core::{
-    collection::{
-        Authentication, Collection, DuplicateRecipeIdError, Folder, Profile,
-        ProfileId, Recipe, RecipeBody, RecipeId, RecipeNode, RecipeTree,
-    },
-    http::HttpMethod,
+use slumber_core::collection::{
+    Authentication, Collection, DuplicateRecipeIdError, Folder, HttpMethod,
+    Profile, ProfileId, Recipe, RecipeBody, RecipeId, RecipeNode, RecipeTree,
 };
 use slumber_template::Template;
 use slumber_util::{ResultTracedAnyhow, yaml::SourceLocation};
@@ -41,7 +38,10 @@ pub fn from_openapi_v3_0(
 
     Ok(Collection {
         name: Some(info.title),
+        template_delimiters: None,
+        variables: IndexMap::new(),
         profiles,
+        toggles: IndexMap::new(),
         recipes,
     })
 }
@@ -73,6 +73,15 @@ fn build_profiles(servers: Vec<Server>) -> IndexMap<ProfileId, Profile> {
                     name: Some(url),
                     default: false,
                     data,
+                    chaos: None,
+                    certificate: None,
+                    proxy: None,
+                    timeout: None,
+                    throttle: None,
+                    protocol: None,
+                    socket: None,
+                    resolve: IndexMap::new(),
+                    pinning: None,
                 },
             )
         })
@@ -120,6 +129,7 @@ fn build_recipe_tree(
                     id: folder_id,
                     location: SourceLocation::default(),
                     name: Some(tag),
+                    authentication: None,
                     children: IndexMap::default(),
                 }
                 .into()
@@ -273,9 +283,27 @@ impl<'a> RecipeBuilder<'a> {
             method: builder.method,
             url,
             body: builder.body,
-            authentication: builder.authentication,
+            authentication: builder.authentication.map(Into::into),
             query: common::build_query_parameters(builder.query),
             headers: builder.headers,
+            user_agent: None,
+            transport: None,
+            certificate: None,
+            retry: None,
+            redirect: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            compression: None,
+            reauthenticate: false,
+            capture: IndexMap::new(),
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: None,
+            params: IndexMap::new(),
         }
     }
 
@@ -394,6 +422,12 @@ impl<'a> RecipeBuilder<'a> {
                         self.authentication =
                             Some(Authentication::Bearer { token: template });
                     }
+                    "Digest" | "digest" => {
+                        self.authentication = Some(Authentication::Digest {
+                            username: Template::from_field("username"),
+                            password: Template::from_field("password"),
+                        });
+                    }
                     unsupported => {
                         error!(
                         "Unsupported HTTP Authentication scheme {unsupported}"
@@ -696,7 +730,11 @@ impl<'a> RecipeBuilder<'a> {
             Ok(RecipeBody::FormUrlencoded(form))
         } else if mime == &mime::MULTIPART_FORM_DATA {
             let form = unwrap_object(body)?;
-            Ok(RecipeBody::FormMultipart(form))
+            Ok(RecipeBody::FormMultipart(
+                form.into_iter()
+                    .map(|(field, value)| (field, value.into()))
+                    .collect(),
+            ))
         } else {
             warn!(
                 "Unknown content type `{mime}` for body of recipe `{}`",
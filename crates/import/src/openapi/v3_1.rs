@@ -9,12 +9,9 @@ use oas3::spec::{
     SchemaType, SchemaTypeSet, SecurityRequirement, SecurityScheme, Server,
     Spec,
 };
-use slumber_core::{
-    collection::{
-        Authentication, Collection, DuplicateRecipeIdError, Folder, Profile,
-        ProfileId, Recipe, RecipeBody, RecipeId, RecipeNode, RecipeTree,
-    },
-    http::HttpMethod,
+use slumber_core::collection::{
+    Authentication, Collection, DuplicateRecipeIdError, Folder, HttpMethod,
+    Profile, ProfileId, Recipe, RecipeBody, RecipeId, RecipeNode, RecipeTree,
 };
 use slumber_template::Template;
 use slumber_util::{ResultTracedAnyhow, yaml::SourceLocation};
@@ -39,7 +36,10 @@ pub fn from_openapi_v3_1(
 
     Ok(Collection {
         name: Some(name),
+        template_delimiters: None,
+        variables: IndexMap::new(),
         profiles,
+        toggles: IndexMap::new(),
         recipes,
     })
 }
@@ -69,6 +69,15 @@ fn build_profiles(servers: Vec<Server>) -> IndexMap<ProfileId, Profile> {
                     name: Some(url),
                     default: false,
                     data,
+                    chaos: None,
+                    certificate: None,
+                    proxy: None,
+                    timeout: None,
+                    throttle: None,
+                    protocol: None,
+                    socket: None,
+                    resolve: IndexMap::new(),
+                    pinning: None,
                 },
             )
         })
@@ -113,6 +122,7 @@ fn build_recipe_tree(spec: Spec) -> Result<RecipeTree, DuplicateRecipeIdError> {
                     id: folder_id,
                     location: SourceLocation::default(),
                     name: Some(tag),
+                    authentication: None,
                     children: IndexMap::default(),
                 }
                 .into()
@@ -256,9 +266,27 @@ impl<'a> RecipeBuilder<'a> {
             method: builder.method,
             url,
             body: builder.body,
-            authentication: builder.authentication,
+            authentication: builder.authentication.map(Into::into),
             query: common::build_query_parameters(builder.query),
             headers: builder.headers,
+            user_agent: None,
+            transport: None,
+            certificate: None,
+            retry: None,
+            redirect: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            compression: None,
+            reauthenticate: false,
+            capture: IndexMap::new(),
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: None,
+            params: IndexMap::new(),
         }
     }
 
@@ -367,6 +395,12 @@ impl<'a> RecipeBuilder<'a> {
                         token: Template::from_field("api_token"),
                     });
                 }
+                "digest" => {
+                    self.authentication = Some(Authentication::Digest {
+                        username: Template::from_field("username"),
+                        password: Template::from_field("password"),
+                    });
+                }
                 _ => {
                     error!("Unsupported HTTP authentication scheme `{scheme}`");
                 }
@@ -615,7 +649,11 @@ impl<'a> RecipeBuilder<'a> {
             Ok(RecipeBody::FormUrlencoded(form))
         } else if mime == &mime::MULTIPART_FORM_DATA {
             let form = unwrap_object(body)?;
-            Ok(RecipeBody::FormMultipart(form))
+            Ok(RecipeBody::FormMultipart(
+                form.into_iter()
+                    .map(|(field, value)| (field, value.into()))
+                    .collect(),
+            ))
         } else {
             warn!(
                 "Unknown content type `{mime}` for body of recipe `{}`",
@@ -6,11 +6,11 @@ use anyhow::{Context, anyhow};
 use indexmap::IndexMap;
 use slumber_core::{
     collection::{
-        Authentication, Collection, JsonTemplate, Profile, ProfileId,
-        QueryParameterValue, Recipe, RecipeBody, RecipeId, RecipeNode,
-        RecipeTree,
+        Authentication, Collection, HttpMethod, JsonTemplate, Profile,
+        ProfileId, QueryParameterValue, Recipe, RecipeBody, RecipeId,
+        RecipeNode, RecipeTree,
     },
-    http::{HttpMethod, content_type::ContentType},
+    http::content_type::ContentType,
 };
 use slumber_template::{Identifier, Template};
 
@@ -174,10 +174,28 @@ fn try_build_recipe(
         name: name.into(),
         method,
         url,
-        authentication,
+        authentication: authentication.map(Into::into),
         body,
         headers,
         query,
+        user_agent: None,
+        transport: None,
+        certificate: None,
+        retry: None,
+        redirect: None,
+        timeout: None,
+        throttle: None,
+        protocol: None,
+        socket: None,
+        compression: None,
+        reauthenticate: false,
+        capture: IndexMap::new(),
+        assert: None,
+        tags: Vec::new(),
+        websocket: None,
+        grpc: None,
+        render_mode: None,
+        params: IndexMap::new(),
     })
 }
 
@@ -204,6 +222,15 @@ fn build_profile_map(
         name: Some(flavor_name),
         default: true,
         data: build_slumber_templates(variables),
+        chaos: None,
+        certificate: None,
+        proxy: None,
+        timeout: None,
+        throttle: None,
+        protocol: None,
+        socket: None,
+        resolve: IndexMap::new(),
+        pinning: None,
     };
 
     IndexMap::from([(profile_id, default_profile)])
@@ -233,7 +260,10 @@ fn build_collection(rest_format: RestFormat) -> Collection {
 
     Collection {
         name: None,
+        template_delimiters: None,
+        variables: IndexMap::new(),
         profiles,
+        toggles: IndexMap::new(),
         recipes: recipe_tree,
     }
 }
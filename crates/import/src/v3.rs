@@ -54,7 +54,10 @@ pub async fn from_v3(input: &ImportInput) -> anyhow::Result<v4::Collection> {
 
     Ok(v4::Collection {
         name: collection.name,
+        template_delimiters: None,
+        variables: IndexMap::new(),
         profiles,
+        toggles: IndexMap::new(),
         recipes,
     })
 }
@@ -122,6 +125,15 @@ impl IntoV4 for v3::Profile {
             name: self.name,
             default: self.default,
             data,
+            chaos: None,
+            certificate: None,
+            proxy: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            resolve: IndexMap::new(),
+            pinning: None,
         })
     }
 }
@@ -155,6 +167,7 @@ impl IntoV4 for v3::Folder {
             id: self.id,
             location: SourceLocation::default(),
             name: self.name,
+            authentication: None,
             children: self.children.into_v4(chains)?,
         })
     }
@@ -176,9 +189,30 @@ impl IntoV4 for v3::Recipe {
             method: self.method,
             url: self.url.into_v4(chains)?,
             body: self.body.into_v4(chains)?,
-            authentication: self.authentication.into_v4(chains)?,
+            authentication: self
+                .authentication
+                .into_v4(chains)?
+                .map(Into::into),
             query: self.query.into_v4(chains)?,
             headers: self.headers.into_v4(chains)?,
+            user_agent: None,
+            transport: None,
+            certificate: None,
+            retry: None,
+            redirect: None,
+            timeout: None,
+            throttle: None,
+            protocol: None,
+            socket: None,
+            compression: None,
+            reauthenticate: false,
+            capture: IndexMap::new(),
+            assert: None,
+            tags: Vec::new(),
+            websocket: None,
+            grpc: None,
+            render_mode: None,
+            params: IndexMap::new(),
         })
     }
 }
@@ -215,7 +249,13 @@ impl IntoV4 for v3::RecipeBody {
                 Ok(v4::RecipeBody::FormUrlencoded(form.into_v4(chains)?))
             }
             Self::FormMultipart(form) => {
-                Ok(v4::RecipeBody::FormMultipart(form.into_v4(chains)?))
+                let form: IndexMap<String, TemplateV4> =
+                    form.into_v4(chains)?;
+                Ok(v4::RecipeBody::FormMultipart(
+                    form.into_iter()
+                        .map(|(field, value)| (field, value.into()))
+                        .collect(),
+                ))
             }
         }
     }
@@ -18,10 +18,7 @@ use serde::{
     },
 };
 use serde_json_path::JsonPath;
-use slumber_core::{
-    collection::{HasId, ProfileId, RecipeId},
-    http::HttpMethod,
-};
+use slumber_core::collection::{HasId, HttpMethod, ProfileId, RecipeId};
 use std::{str::FromStr, time::Duration};
 use winnow::{ModalResult, Parser, ascii::digit1, token::take_while};
 
@@ -9,6 +9,8 @@ class Collection:
         profile: str | None = None,
         overrides: dict[str, str] = {},
         trigger: bool = True,
+        toggles: list[str] = [],
+        params: dict[str, str] = {},
     ) -> "Response": ...
     def reload(self) -> None: ...
 
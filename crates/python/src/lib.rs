@@ -8,11 +8,12 @@ use pyo3::{
 };
 use slumber_config::Config;
 use slumber_core::{
-    collection::{CollectionFile, ProfileId, RecipeId},
+    collection::{CollectionFile, ProfileId, RecipeId, ToggleId},
     database::{CollectionDatabase, Database},
     http::{
-        BuildOptions, Exchange, HttpEngine, RequestRecord, RequestSeed,
-        ResponseRecord, StoredRequestError, TriggeredRequestError,
+        BuildOptions, CookieJar, Exchange, HttpEngine, RequestRecord,
+        RequestSeed, ResponseRecord, StoredRequestError, TokenStore,
+        TriggeredRequestError,
     },
     render::{HttpProvider, Prompt, Prompter, SelectOption, TemplateContext},
 };
@@ -115,12 +116,17 @@ impl Collection {
     ///   `response()`/`response_header()` calls in request templates will never
     ///   trigger request dependencies, meaning those requests must be run
     ///   manually.
+    /// :param toggles: IDs of toggles to layer on top of the profile. If
+    ///   multiple toggles define the same field, the last one wins.
+    /// :param params: Values for the recipe's parameters, as static values
     /// :return: The returned server response
     #[pyo3(signature = (
         recipe,
         profile=None,
         overrides=IndexMap::new(),
         trigger=true,
+        toggles=Vec::new(),
+        params=IndexMap::new(),
     ))]
     async fn request(
         &self,
@@ -128,6 +134,8 @@ impl Collection {
         profile: Option<String>,
         overrides: IndexMap<String, String>,
         trigger: bool,
+        toggles: Vec<String>,
+        params: IndexMap<String, String>,
     ) -> PyResult<Response> {
         // reqwest/hyper need to be run in tokio, so we have to spawn this in
         // a background task instead of executing it in the python event loop.
@@ -140,7 +148,9 @@ impl Collection {
         let request = Request {
             recipe_id: RecipeId::from(recipe),
             profile_id: profile.map(ProfileId::from),
+            toggles: toggles.into_iter().map(ToggleId::from).collect(),
             overrides,
+            params,
             trigger,
             collection: Arc::clone(&self.collection),
             database: self.database.clone(),
@@ -150,7 +160,7 @@ impl Collection {
         std::thread::spawn(move || {
             let local = LocalSet::new();
             local.spawn_local(async move {
-                let result = request.send().await;
+                let result = Box::pin(request.send()).await;
                 tx.send(result).unwrap();
             });
             rt.block_on(local);
@@ -379,7 +389,9 @@ impl From<ErrorDisplay> for PyErr {
 struct Request {
     recipe_id: RecipeId,
     profile_id: Option<ProfileId>,
+    toggles: Vec<ToggleId>,
     overrides: IndexMap<String, String>,
+    params: IndexMap<String, String>,
     trigger: bool,
     collection: Arc<slumber_core::collection::Collection>,
     database: CollectionDatabase,
@@ -400,6 +412,8 @@ impl Request {
         // reqwest/hyper need to be run in tokio, so we have to spawn this in
         // a background task instead of executing it in the python event loop.
         // Most of the context is !Send so we have to move it all in.
+        let oauth2_tokens = TokenStore::new(self.database.clone());
+        let cookies = CookieJar::new(self.database.clone());
         let http_provider = PythonHttpProvider {
             database: self.database,
             http_engine: self.http_engine.clone(),
@@ -411,24 +425,50 @@ impl Request {
             // Don't support templates in overrides (yet)
             .map(|(field, value)| (field, Template::raw(value)))
             .collect();
-        let context = TemplateContext {
+        let params = self
+            .params
+            .into_iter()
+            .map(|(param, value)| (param, Template::raw(value)))
+            .collect();
+        let mut context = TemplateContext {
             collection: self.collection,
             selected_profile,
+            enabled_toggles: self.toggles,
             http_provider: Box::new(http_provider),
             overrides,
+            params,
+            prompt_params: false,
+            prompt_missing: false,
             prompter: Box::new(PythonPrompter),
             show_sensitive: true,
             root_dir: self.root_dir,
+            sandboxed: false,
+            auditor: Default::default(),
             state: Default::default(),
+            command_cache: Default::default(),
+            captures: Default::default(),
+            render_mode: None,
+            oauth2_tokens,
+            cookies,
         };
         let seed = RequestSeed::new(self.recipe_id, BuildOptions::default());
 
         let ticket = self
             .http_engine
-            .build(seed, &context)
+            .build(seed.clone(), &context)
             .await
             .map_err(ErrorDisplay::new)?;
-        let exchange = ticket.send().await.map_err(ErrorDisplay::new)?;
+        let mut exchange = ticket.send().await.map_err(ErrorDisplay::new)?;
+        // If authentication was rejected, refresh upstream requests and retry
+        if exchange.should_reauthenticate(&context.collection) {
+            context.reauthenticate();
+            let ticket = self
+                .http_engine
+                .build(seed, &context)
+                .await
+                .map_err(ErrorDisplay::new)?;
+            exchange = ticket.send().await.map_err(ErrorDisplay::new)?;
+        }
         Ok(exchange)
     }
 }
@@ -3,7 +3,7 @@
 //! yet. Once saphyr supports serde, we can delete all of this and delete any
 //! `DeserializeYaml` implementations.
 
-use crate::{Template, Value, ValueError};
+use crate::{Delimiters, Template, Value, ValueError};
 use indexmap::IndexMap;
 use saphyr::{Scalar, YamlData};
 use serde::{
@@ -14,7 +14,8 @@ use serde::{
     },
 };
 use slumber_util::yaml::{
-    DeserializeYaml, Expected, LocatedError, SourceMap, SourcedYaml,
+    DeserializeYaml, Expected, Field, LocatedError, SourceMap, SourcedYaml,
+    StructDeserializer,
 };
 
 impl Serialize for Template {
@@ -49,7 +50,7 @@ impl DeserializeYaml for Template {
                 Scalar::Boolean(b) => b.to_string().parse(),
                 Scalar::Integer(i) => i.to_string().parse(),
                 Scalar::FloatingPoint(f) => f.to_string().parse(),
-                Scalar::String(s) => s.parse(),
+                Scalar::String(s) => Template::parse_or_defer(s.into()),
             }
             .map_err(|error| LocatedError::other(error, yaml.location))
         } else {
@@ -58,6 +59,26 @@ impl DeserializeYaml for Template {
     }
 }
 
+impl DeserializeYaml for Delimiters {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> Result<Self, LocatedError<slumber_util::yaml::YamlErrorKind>> {
+        let location = yaml.location;
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let open: String = deserializer.get(Field::new("open"), source_map)?;
+        let close: String =
+            deserializer.get(Field::new("close"), source_map)?;
+        deserializer.done()?;
+        Self::new(open, close)
+            .map_err(|error| LocatedError::other(error, location))
+    }
+}
+
 #[cfg(feature = "schema")]
 impl schemars::JsonSchema for Template {
     fn schema_name() -> std::borrow::Cow<'static, str> {
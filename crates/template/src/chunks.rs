@@ -0,0 +1,116 @@
+//! Lazily parsed template chunks
+
+use crate::{
+    Delimiters, TemplateChunk, error::TemplateParseError, parse::parse_chunks,
+};
+use std::{
+    fmt::{self, Debug},
+    sync::OnceLock,
+};
+
+/// Chunks of a template, which may not have been parsed yet. Parsing is
+/// deferred only within [Template::defer_parsing]; everywhere else, templates
+/// are parsed on creation.
+///
+/// Deferred state is boxed so this is no bigger than a `Vec`. Templates are
+/// everywhere, so their size adds up.
+///
+/// [Template::defer_parsing]: crate::Template::defer_parsing
+#[derive(Clone)]
+pub enum Chunks {
+    /// Template was parsed on creation
+    Parsed(Vec<TemplateChunk>),
+    /// Template will be parsed on first use
+    Deferred(Box<Deferred>),
+}
+
+impl Chunks {
+    /// Create chunks for a template that will be parsed on first use
+    pub fn deferred(source: String, delimiters: Option<Delimiters>) -> Self {
+        Self::Deferred(Box::new(Deferred {
+            source,
+            delimiters,
+            parsed: OnceLock::new(),
+        }))
+    }
+
+    /// Get the parsed chunks, parsing now if parsing was deferred
+    pub fn get(&self) -> Result<&[TemplateChunk], &TemplateParseError> {
+        match self {
+            Self::Parsed(chunks) => Ok(chunks),
+            Self::Deferred(deferred) => deferred
+                .parsed
+                .get_or_init(|| {
+                    let delimiters =
+                        deferred.delimiters.clone().unwrap_or_default();
+                    parse_chunks(&deferred.source, &delimiters)
+                })
+                .as_deref(),
+        }
+    }
+
+    /// Has the template been parsed yet?
+    pub fn is_parsed(&self) -> bool {
+        match self {
+            Self::Parsed(_) => true,
+            Self::Deferred(deferred) => deferred.parsed.get().is_some(),
+        }
+    }
+
+    /// Get the source text of a deferred template
+    pub fn source(&self) -> Option<&str> {
+        match self {
+            Self::Parsed(_) => None,
+            Self::Deferred(deferred) => Some(&deferred.source),
+        }
+    }
+}
+
+impl Default for Chunks {
+    fn default() -> Self {
+        Self::Parsed(Vec::new())
+    }
+}
+
+impl From<Vec<TemplateChunk>> for Chunks {
+    fn from(chunks: Vec<TemplateChunk>) -> Self {
+        Self::Parsed(chunks)
+    }
+}
+
+impl Debug for Chunks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parsed(chunks) => chunks.fmt(f),
+            Self::Deferred(deferred) => match deferred.parsed.get() {
+                Some(Ok(chunks)) => chunks.fmt(f),
+                Some(Err(error)) => error.fmt(f),
+                None => {
+                    f.debug_tuple("Deferred").field(&deferred.source).finish()
+                }
+            },
+        }
+    }
+}
+
+/// Two templates are equal if they parse to the same chunks. Templates that
+/// fail to parse are compared by their source.
+impl PartialEq for Chunks {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.get(), other.get()) {
+            (Ok(a), Ok(b)) => a == b,
+            (Err(_), Err(_)) => self.source() == other.source(),
+            _ => false,
+        }
+    }
+}
+
+/// The unparsed source of a deferred template, and its chunks once parsed
+#[derive(Clone)]
+pub struct Deferred {
+    source: String,
+    /// Delimiters that were in scope when the template was created. `None` for
+    /// the defaults, to avoid cloning them into every template
+    delimiters: Option<Delimiters>,
+    parsed: OnceLock<Result<Vec<TemplateChunk>, TemplateParseError>>,
+}
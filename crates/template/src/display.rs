@@ -2,7 +2,7 @@
 
 use crate::{
     Expression, FunctionCall, Literal, Template, TemplateChunk, Value,
-    parse::{ESCAPE, EXPRESSION_CLOSE, EXPRESSION_OPEN, NULL},
+    parse::{ESCAPE, EXPRESSION_CLOSE, EXPRESSION_OPEN, NULL, VARIABLE_PREFIX},
 };
 use itertools::Itertools;
 use regex::Regex;
@@ -18,10 +18,15 @@ impl Template {
     /// that was parsed to create the template, as whitespace within expressions
     /// is variable.
     pub fn display(&self) -> Cow<'_, str> {
+        // A deferred template that failed to parse can only be displayed as
+        // its source
+        let Ok(chunks) = self.chunks.get() else {
+            return self.chunks.source().unwrap_or_default().into();
+        };
         let mut buf = Cow::Borrowed("");
 
         // Re-stringify the template
-        for chunk in &self.chunks {
+        for chunk in chunks {
             match chunk {
                 TemplateChunk::Raw(s) => {
                     // Add underscores between { to escape them. Any sequence
@@ -87,6 +92,9 @@ impl Display for Expression {
         match self {
             Self::Literal(literal) => write!(f, "{literal}"),
             Self::Field(identifier) => write!(f, "{identifier}"),
+            Self::Variable(identifier) => {
+                write!(f, "{VARIABLE_PREFIX}{identifier}")
+            }
             Self::Array(expressions) => {
                 write!(f, "[{}]", expressions.iter().format(", "))
             }
@@ -13,7 +13,7 @@ use tracing::error;
 use winnow::error::{ContextError, ParseError};
 
 /// An error while parsing a template
-#[derive(Debug, Error)]
+#[derive(Clone, Debug, Error)]
 #[error("{error}")]
 pub struct TemplateParseError {
     /// The string that failed to parse
@@ -40,6 +40,22 @@ impl From<ParseError<&str, ContextError>> for TemplateParseError {
     }
 }
 
+/// Invalid custom template delimiters
+#[derive(Debug, Error)]
+pub enum DelimitersError {
+    #[error("Opening delimiter must be at least 2 characters")]
+    OpenTooShort,
+    #[error("Closing delimiter cannot be empty")]
+    CloseEmpty,
+    #[error("Delimiters cannot contain whitespace")]
+    Whitespace,
+    #[error(
+        "Second character of the opening delimiter cannot be `_`, because \
+        it's used for escaping"
+    )]
+    Escape,
+}
+
 /// Any error that can occur during template rendering.
 ///
 /// The error always holds owned data so it can be detached from the lifetime
@@ -54,6 +70,10 @@ pub enum RenderError {
     #[error("Unknown field `{field}`")]
     FieldUnknown { field: Identifier },
 
+    /// A variable key referred to an undefined variable
+    #[error("Unknown variable `{variable}`")]
+    VariableUnknown { variable: Identifier },
+
     /// An bubbled-up error from rendering a profile field value
     #[error("Rendering nested template for field `{field}`")]
     FieldNested {
@@ -62,6 +82,11 @@ pub enum RenderError {
         error: Box<Self>,
     },
 
+    /// A template whose parsing was deferred (see
+    /// [Template::defer_parsing](crate::Template::defer_parsing)) is invalid
+    #[error("Invalid template")]
+    Parse(#[source] TemplateParseError),
+
     /// No function by this name. Name doesn't need to be given because this
     /// will be wrapped in the `Function` variant
     #[error("Unknown function")]
@@ -258,6 +283,9 @@ pub enum Expected {
     /// Array of any type
     #[display("array")]
     Array,
+    /// Object with any values
+    #[display("object")]
+    Object,
     /// Union
     #[display("one of {}", display_union(_0))]
     OneOf(&'static [&'static Self]),
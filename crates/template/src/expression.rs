@@ -17,6 +17,10 @@ use std::borrow::Cow;
 
 type RenderResult = Result<LazyValue, RenderError>;
 
+/// Name of the function whose value argument is allowed to fail. See
+/// [FunctionCall::call_fallback]
+const FALLBACK_FUNCTION: &str = "fallback";
+
 /// A dynamic segment of a template that will be computed at render time.
 /// Expressions are derived from the template context and may include external
 /// data such as loading a file.
@@ -26,6 +30,9 @@ pub enum Expression {
     Literal(Literal),
     /// Field access, as defined by [Context::get_field]
     Field(Identifier),
+    /// Collection variable access: `vars.name`, as defined by
+    /// [Context::get_variable]
+    Variable(Identifier),
     /// Array literal: `[1, "hello", f()]`
     Array(Vec<Self>),
     /// Object literal: `{"a": 1}`. Store a vec here instead of a map because
@@ -79,7 +86,14 @@ impl Expression {
                 Ok(Value::Object(IndexMap::from_iter(pairs)).into())
             }
             Self::Field(field) => context.get_field(field).await,
+            Self::Variable(variable) => context.get_variable(variable).await,
+            Self::Call(call) if call.is_fallback() => {
+                call.call_fallback(context, None).await
+            }
             Self::Call(call) => call.call(context, None).await,
+            Self::Pipe { expression, call } if call.is_fallback() => {
+                call.call_fallback(context, Some(expression)).await
+            }
             Self::Pipe { expression, call } => {
                 // Compute the left hand side first. Box for recursion
                 let value =
@@ -97,6 +111,44 @@ impl Expression {
         self.render(context).await?.resolve().await
     }
 
+    /// Visit this expression and all of its nested expressions, depth-first
+    /// in lexical order. For a pipe, the left-hand side is visited before the
+    /// function call.
+    pub fn walk(&self, f: &mut impl FnMut(&Self)) {
+        self.walk_inner(f, false);
+    }
+
+    /// Like [Self::walk], but skip expressions whose failure doesn't fail the
+    /// render, i.e. the value argument of `fallback()`
+    pub fn walk_required(&self, f: &mut impl FnMut(&Self)) {
+        self.walk_inner(f, true);
+    }
+
+    fn walk_inner(&self, f: &mut impl FnMut(&Self), skip_fallback: bool) {
+        f(self);
+        match self {
+            Self::Literal(_) | Self::Field(_) | Self::Variable(_) => {}
+            Self::Array(expressions) => {
+                for expression in expressions {
+                    expression.walk_inner(f, skip_fallback);
+                }
+            }
+            Self::Object(entries) => {
+                for (key, value) in entries {
+                    key.walk_inner(f, skip_fallback);
+                    value.walk_inner(f, skip_fallback);
+                }
+            }
+            Self::Call(call) => call.walk_arguments(f, false, skip_fallback),
+            Self::Pipe { expression, call } => {
+                if !(skip_fallback && call.is_fallback()) {
+                    expression.walk_inner(f, skip_fallback);
+                }
+                call.walk_arguments(f, true, skip_fallback);
+            }
+        }
+    }
+
     /// Build a function call expression. Any keyword arguments with `None`
     /// values will be omitted
     pub fn call(
@@ -288,19 +340,119 @@ impl FunctionCall {
         }
     }
 
+    /// Name of the function being called
+    pub fn function(&self) -> &Identifier {
+        &self.function
+    }
+
+    /// Positional arguments passed to the function. For a piped call, this
+    /// does *not* include the piped argument.
+    pub fn position(&self) -> &[Expression] {
+        &self.position
+    }
+
+    /// Visit every argument expression, positional then keyword. If
+    /// `skip_fallback` is set and this is a call to `fallback()`, its value
+    /// argument is skipped. For a piped call, that's the piped argument, so
+    /// nothing here is skipped.
+    fn walk_arguments(
+        &self,
+        f: &mut impl FnMut(&Expression),
+        piped: bool,
+        skip_fallback: bool,
+    ) {
+        let position = match self.position.split_last() {
+            Some((_, rest))
+                if skip_fallback && self.is_fallback() && !piped =>
+            {
+                rest
+            }
+            _ => &self.position,
+        };
+        for expression in position.iter().chain(self.keyword.values()) {
+            expression.walk_inner(f, skip_fallback);
+        }
+    }
+
+    /// Is this a call to `fallback()`? Fallback gets special treatment during
+    /// evaluation because its value argument is allowed to fail.
+    fn is_fallback(&self) -> bool {
+        self.function.as_str() == FALLBACK_FUNCTION
+    }
+
     /// Render arguments and call the function
     async fn call<Ctx: Context>(
         &self,
         context: &Ctx,
         piped_argument: Option<Value>,
+    ) -> RenderResult {
+        self.call_with(context, &self.position, piped_argument)
+            .await
+    }
+
+    /// Evaluate a call to `fallback(default, value)`. The value is the last
+    /// positional argument, or the piped argument. If it renders successfully,
+    /// the function is called as normal. If it fails, the default is rendered
+    /// in its place and the error is retained in a [LazyValue::Fallback] so
+    /// the consumer can still show it to the user.
+    async fn call_fallback<Ctx: Context>(
+        &self,
+        context: &Ctx,
+        piped_expression: Option<&Expression>,
+    ) -> RenderResult {
+        let (value_expression, position) = match piped_expression {
+            Some(expression) => (expression, self.position.as_slice()),
+            None => match self.position.split_last() {
+                Some((last, rest)) => (last, rest),
+                // No arguments at all; let the function report it
+                None => return self.call(context, None).await,
+            },
+        };
+
+        // Box for recursion
+        match value_expression.render_value(context).boxed_local().await {
+            Ok(value) => self.call_with(context, position, Some(value)).await,
+            Err(error) => {
+                let Some(default_expression) = position.first() else {
+                    // No default given; let the function report it
+                    return self.call_with(context, position, None).await;
+                };
+                let value = default_expression
+                    .render_value(context)
+                    .boxed_local()
+                    .await
+                    .map_err(|error| {
+                        error
+                            .context(RenderErrorContext::ArgumentRender {
+                                argument: "0".into(),
+                                expression: default_expression.clone(),
+                            })
+                            .context(RenderErrorContext::Function(
+                                self.function.clone(),
+                            ))
+                    })?;
+                Ok(LazyValue::Fallback { value, error })
+            }
+        }
+    }
+
+    /// Render the given positional arguments, plus all keyword arguments, and
+    /// call the function
+    async fn call_with<Ctx: Context>(
+        &self,
+        context: &Ctx,
+        position: &[Expression],
+        piped_argument: Option<Value>,
     ) -> RenderResult {
         // Provide context to the error
         let map_error = |error: RenderError| {
             error.context(RenderErrorContext::Function(self.function.clone()))
         };
 
-        let mut arguments =
-            self.render_arguments(context).await.map_err(map_error)?;
+        let mut arguments = self
+            .render_arguments(context, position)
+            .await
+            .map_err(map_error)?;
         if let Some(piped_argument) = piped_argument {
             // Pipe the filter value in as the last positional argument
             arguments.push_piped(piped_argument);
@@ -311,16 +463,18 @@ impl FunctionCall {
             .map_err(map_error)
     }
 
-    /// Render each argument passed in this function call
+    /// Render the given positional arguments and each keyword argument passed
+    /// in this function call
     async fn render_arguments<'ctx, Ctx: Context>(
         &self,
         context: &'ctx Ctx,
+        position: &[Expression],
     ) -> Result<Arguments<'ctx, Ctx>, RenderError> {
         // Render all position and keyword arguments concurrently. We attach
         // error context to any failures so the user know which arg failed to
         // render
         let position_future =
-            future::try_join_all(self.position.iter().enumerate().map(
+            future::try_join_all(position.iter().enumerate().map(
                 |(index, expression)| async move {
                     expression.render_value(context).await.map_err(|error| {
                         error.context(RenderErrorContext::ArgumentRender {
@@ -4,6 +4,7 @@
 //! relating to HTTP or other Slumber concepts.
 
 mod cereal;
+mod chunks;
 mod display;
 mod error;
 mod expression;
@@ -15,20 +16,23 @@ mod tests;
 mod value;
 
 pub use error::{
-    Expected, RenderError, TemplateParseError, ValueError, WithValue,
+    DelimitersError, Expected, RenderError, TemplateParseError, ValueError,
+    WithValue,
 };
 pub use expression::{Expression, FunctionCall, Identifier, Literal};
+pub use parse::Delimiters;
 pub use value::{
     Arguments, FunctionOutput, LazyValue, StreamSource, TryFromValue, Value,
 };
 
+use crate::chunks::Chunks;
 use bytes::{Bytes, BytesMut};
 use futures::{Stream, StreamExt, TryStreamExt, future, stream};
 use itertools::Itertools;
 #[cfg(test)]
 use proptest::{arbitrary::any, strategy::Strategy};
 use slumber_util::NEW_ISSUE_LINK;
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
 /// `Context` defines how template fields and functions are resolved. Both
 /// field resolution and function calls can be asynchronous.
@@ -50,6 +54,15 @@ pub trait Context: Sized {
         identifier: &Identifier,
     ) -> Result<LazyValue, RenderError>;
 
+    /// Get the value of a variable (`vars.name`) from the context. Variables
+    /// are distinct from fields so the implementor can source them from
+    /// somewhere else, e.g. a set of values that doesn't change between
+    /// environments.
+    async fn get_variable(
+        &self,
+        identifier: &Identifier,
+    ) -> Result<LazyValue, RenderError>;
+
     /// Call a function by name
     async fn call(
         &self,
@@ -62,8 +75,9 @@ pub trait Context: Sized {
 /// string is parsed during creation to identify template keys, hence the
 /// immutability.
 ///
-/// The original string is *not* stored. To recover the source string, use the
-/// `Display` implementation.
+/// The original string is *not* stored, unless parsing was deferred (see
+/// [Template::defer_parsing]). To recover the source string, use the `Display`
+/// implementation.
 ///
 /// Invariants:
 /// - Two templates with the same source string will have the same set of
@@ -80,10 +94,10 @@ pub struct Template {
     #[cfg_attr(
         test,
         proptest(
-            strategy = "any::<Vec<TemplateChunk>>().prop_map(test_util::join_raw)"
+            strategy = "any::<Vec<TemplateChunk>>().prop_map(test_util::join_raw).prop_map(Chunks::from)"
         )
     )]
-    chunks: Vec<TemplateChunk>,
+    chunks: Chunks,
 }
 
 impl Template {
@@ -114,7 +128,9 @@ impl Template {
             "Invalid chunks in generated template {chunks:?} This is a bug! \
             Please report it. {NEW_ISSUE_LINK}"
         );
-        Self { chunks }
+        Self {
+            chunks: chunks.into(),
+        }
     }
 
     /// Create a new template from a raw string, without parsing it at all.
@@ -129,7 +145,9 @@ impl Template {
             // string to parse correctly later
             vec![TemplateChunk::Raw(template.into())]
         };
-        Self { chunks }
+        Self {
+            chunks: chunks.into(),
+        }
     }
 
     /// Create a template that loads a file
@@ -165,20 +183,66 @@ impl Template {
         let chunks = vec![TemplateChunk::Expression(Expression::call(
             name, position, keyword,
         ))];
-        Self { chunks }
+        Self {
+            chunks: chunks.into(),
+        }
     }
 
     /// Is the template an empty string?
     pub fn is_empty(&self) -> bool {
-        self.chunks.is_empty()
+        match self.chunks.get() {
+            Ok(chunks) => chunks.is_empty(),
+            // Only non-empty strings can fail to parse
+            Err(_) => false,
+        }
     }
 
     /// Does the template have at least one dynamic chunk? If this returns
-    /// `false`, the template will always render to its source text
+    /// `false`, the template will always render to its source text. A deferred
+    /// template that fails to parse is considered dynamic, because rendering
+    /// it will produce the parse error.
     pub fn is_dynamic(&self) -> bool {
-        self.chunks
-            .iter()
-            .any(|chunk| matches!(chunk, TemplateChunk::Expression(_)))
+        match self.chunks.get() {
+            Ok(chunks) => chunks
+                .iter()
+                .any(|chunk| matches!(chunk, TemplateChunk::Expression(_))),
+            Err(_) => true,
+        }
+    }
+
+    /// Has this template been parsed yet? This is always `true` unless parsing
+    /// was deferred with [Template::defer_parsing] and the template hasn't
+    /// been used since.
+    pub fn is_parsed(&self) -> bool {
+        self.chunks.is_parsed()
+    }
+
+    /// Get the error from parsing a deferred template, if it failed. Templates
+    /// that were parsed up front can never have a parse error, because parsing
+    /// them would've failed instead. This will parse the template if it hasn't
+    /// been parsed yet.
+    pub fn parse_error(&self) -> Option<&TemplateParseError> {
+        self.chunks.get().err()
+    }
+
+    /// Visit every expression in the template, including nested expressions
+    /// such as function arguments, in lexical order. See [Expression::walk]
+    pub fn walk_expressions(&self, mut f: impl FnMut(&Expression)) {
+        for chunk in self.chunks.get().unwrap_or_default() {
+            if let TemplateChunk::Expression(expression) = chunk {
+                expression.walk(&mut f);
+            }
+        }
+    }
+
+    /// Visit every expression that must render successfully for the template
+    /// to render. See [Expression::walk_required]
+    pub fn walk_required_expressions(&self, mut f: impl FnMut(&Expression)) {
+        for chunk in self.chunks.get().unwrap_or_default() {
+            if let TemplateChunk::Expression(expression) = chunk {
+                expression.walk_required(&mut f);
+            }
+        }
     }
 
     /// Render the template, returning the individual rendered chunks rather
@@ -188,16 +252,33 @@ impl Template {
     /// rendered. The returned output can be transformed into a variety of final
     /// output types.
     pub async fn render<Ctx: Context>(&self, context: &Ctx) -> RenderedOutput {
+        let chunks = match self.chunks.get() {
+            Ok(chunks) => chunks,
+            // A deferred template that can't be parsed renders to its error
+            Err(error) => {
+                return RenderedOutput(vec![RenderedChunk::Error(
+                    RenderError::Parse(error.clone()),
+                )]);
+            }
+        };
+
         // Map over each parsed chunk, and render the expressions into values.
         // because raw text uses Arc and expressions just contain metadata
         // The raw text chunks will be mapped 1:1. This clone is pretty cheap
-        let futures = self.chunks.iter().map(|chunk| async move {
+        let futures = chunks.iter().map(|chunk| async move {
             match chunk {
                 TemplateChunk::Raw(text) => {
                     RenderedChunk::Raw(Arc::clone(text))
                 }
                 TemplateChunk::Expression(expression) => {
                     match expression.render(context).await {
+                        // Fallbacks and cached values are already resolved,
+                        // and need to be kept intact so the consumer can see
+                        // the error/age
+                        Ok(
+                            lazy @ (LazyValue::Fallback { .. }
+                            | LazyValue::Cached { .. }),
+                        ) => RenderedChunk::Rendered(lazy),
                         Ok(lazy) if context.can_stream() => {
                             RenderedChunk::Rendered(lazy)
                         }
@@ -259,7 +340,7 @@ impl From<&'static str> for Template {
 impl<const N: usize> From<[TemplateChunk; N]> for Template {
     fn from(chunks: [TemplateChunk; N]) -> Self {
         Self {
-            chunks: chunks.into(),
+            chunks: Vec::from(chunks).into(),
         }
     }
 }
@@ -318,7 +399,11 @@ impl RenderedOutput {
     pub fn has_stream(&self) -> bool {
         self.0.iter().any(|chunk| match chunk {
             RenderedChunk::Raw(_) => false,
-            RenderedChunk::Rendered(LazyValue::Value(_)) => false,
+            RenderedChunk::Rendered(
+                LazyValue::Value(_)
+                | LazyValue::Fallback { .. }
+                | LazyValue::Cached { .. },
+            ) => false,
             RenderedChunk::Rendered(LazyValue::Stream { .. }) => true,
             // Recursion!!
             RenderedChunk::Rendered(LazyValue::Nested(output)) => {
@@ -328,6 +413,68 @@ impl RenderedOutput {
         })
     }
 
+    /// Get the first error in the output, if any chunk failed to render.
+    /// Nested outputs (e.g. from profile fields) are searched as well.
+    pub fn error(&self) -> Option<&RenderError> {
+        self.0.iter().find_map(|chunk| match chunk {
+            RenderedChunk::Raw(_)
+            | RenderedChunk::Rendered(
+                LazyValue::Value(_)
+                | LazyValue::Stream { .. }
+                | LazyValue::Fallback { .. }
+                | LazyValue::Cached { .. },
+            ) => None,
+            RenderedChunk::Rendered(LazyValue::Nested(output)) => {
+                output.error()
+            }
+            RenderedChunk::Error(error) => Some(error),
+        })
+    }
+
+    /// Get the first error that was replaced by a `fallback()` default value,
+    /// if any. Nested outputs are searched as well. These errors do *not*
+    /// prevent the output from being collected.
+    pub fn fallback_error(&self) -> Option<&RenderError> {
+        self.0.iter().find_map(|chunk| match chunk {
+            RenderedChunk::Raw(_)
+            | RenderedChunk::Rendered(
+                LazyValue::Value(_)
+                | LazyValue::Stream { .. }
+                | LazyValue::Cached { .. },
+            )
+            | RenderedChunk::Error(_) => None,
+            RenderedChunk::Rendered(LazyValue::Nested(output)) => {
+                output.fallback_error()
+            }
+            RenderedChunk::Rendered(LazyValue::Fallback { error, .. }) => {
+                Some(error)
+            }
+        })
+    }
+
+    /// Get the age of the oldest cached value in the output, if any chunk was
+    /// loaded from a cache. Nested outputs are searched as well.
+    pub fn cached_age(&self) -> Option<Duration> {
+        self.0
+            .iter()
+            .filter_map(|chunk| match chunk {
+                RenderedChunk::Raw(_)
+                | RenderedChunk::Rendered(
+                    LazyValue::Value(_)
+                    | LazyValue::Stream { .. }
+                    | LazyValue::Fallback { .. },
+                )
+                | RenderedChunk::Error(_) => None,
+                RenderedChunk::Rendered(LazyValue::Nested(output)) => {
+                    output.cached_age()
+                }
+                RenderedChunk::Rendered(LazyValue::Cached { age, .. }) => {
+                    Some(*age)
+                }
+            })
+            .max()
+    }
+
     /// Unpack this output into a single lazy value. If the output is a single
     /// dynamic chunk, unpack it into a scalar value. Otherwise return a
     /// [LazyValue::Nested].
@@ -366,7 +513,11 @@ impl RenderedOutput {
                     stream_value(Bytes::from(s.to_string()))
                 }
                 RenderedChunk::Rendered(lazy) => match lazy {
-                    LazyValue::Value(value) => stream_value(value.into_bytes()),
+                    LazyValue::Value(value)
+                    | LazyValue::Fallback { value, .. }
+                    | LazyValue::Cached { value, .. } => {
+                        stream_value(value.into_bytes())
+                    }
                     LazyValue::Stream { stream, .. } => Ok(stream.boxed()),
                     LazyValue::Nested(output) => {
                         Ok(output.try_into_stream()?.boxed())
@@ -391,7 +542,9 @@ impl RenderedOutput {
     pub async fn try_collect_value(self) -> Result<Value, RenderError> {
         // If we only have one chunk, unpack it into a value
         let value = match self.unpack() {
-            LazyValue::Value(value) => value,
+            LazyValue::Value(value)
+            | LazyValue::Fallback { value, .. }
+            | LazyValue::Cached { value, .. } => value,
             lazy @ LazyValue::Stream { .. } => lazy.resolve().await?,
             LazyValue::Nested(output) => {
                 // Render to bytes
@@ -2,11 +2,17 @@
 
 use crate::{
     Template, TemplateChunk,
-    error::TemplateParseError,
+    chunks::Chunks,
+    error::{DelimitersError, TemplateParseError},
     expression::{Expression, FunctionCall, Identifier, Literal},
 };
 use indexmap::IndexMap;
-use std::{convert, str::FromStr, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    convert,
+    str::FromStr,
+    sync::Arc,
+};
 use winnow::{
     ModalParser, ModalResult, Parser,
     ascii::{dec_int, escaped, float, multispace0},
@@ -28,6 +34,20 @@ pub(crate) const EXPRESSION_CLOSE: &str = "}}";
 pub(crate) const NULL: &str = "null";
 pub(crate) const FALSE: &str = "false";
 pub(crate) const TRUE: &str = "true";
+/// Prefix for accessing a collection variable: `vars.name`
+pub(crate) const VARIABLE_PREFIX: &str = "vars.";
+
+thread_local! {
+    /// Delimiters used to parse templates on this thread. This is only changed
+    /// within [Delimiters::scope], so templates parsed anywhere else (e.g.
+    /// overrides entered in the TUI) always use the defaults.
+    static DELIMITERS: RefCell<Delimiters> =
+        RefCell::new(Delimiters::default());
+
+    /// Should [Template::parse_or_defer] store templates unparsed? This is only
+    /// enabled within [Template::defer_parsing].
+    static DEFER_PARSING: Cell<bool> = const { Cell::new(false) };
+}
 
 impl Template {
     /// Create a template that renders a single field, equivalent to
@@ -36,18 +56,162 @@ impl Template {
         Self {
             chunks: vec![TemplateChunk::Expression(Expression::Field(
                 field.into(),
-            ))],
+            ))]
+            .into(),
+        }
+    }
+
+    /// Defer parsing of all templates created with [Template::parse_or_defer]
+    /// on the current thread until `f` returns. Each template will be parsed
+    /// the first time it's used instead. Used to speed up loading large
+    /// collections, where most templates will never be used in a session.
+    ///
+    /// A deferred template that fails to parse will *not* return an error
+    /// until it's rendered, so this should only be used when errors can be
+    /// surfaced at that point.
+    pub fn defer_parsing<T>(f: impl FnOnce() -> T) -> T {
+        /// Restore the previous value on drop, so a panic in `f` can't leak
+        /// it to the rest of the thread
+        struct Reset(bool);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                DEFER_PARSING.set(self.0);
+            }
+        }
+
+        let _reset = Reset(DEFER_PARSING.replace(true));
+        f()
+    }
+
+    /// Parse a template, unless parsing is currently deferred by
+    /// [Template::defer_parsing]. In that case, the source is stored and will
+    /// be parsed on first use with the delimiters that are in scope now.
+    pub fn parse_or_defer(
+        template: String,
+    ) -> Result<Self, TemplateParseError> {
+        if DEFER_PARSING.get() {
+            let delimiters = DELIMITERS.with_borrow(|delimiters| {
+                (delimiters.open != EXPRESSION_OPEN
+                    || delimiters.close != EXPRESSION_CLOSE)
+                    .then(|| delimiters.clone())
+            });
+            Ok(Self {
+                chunks: Chunks::deferred(template, delimiters),
+            })
+        } else {
+            template.parse()
         }
     }
 }
 
-/// Parse a template, extracting all template keys
+/// Parse a template, extracting all template keys. Expressions are bounded by
+/// the delimiters of the enclosing [Delimiters::scope], or `{{ }}` outside of
+/// any scope.
 impl FromStr for Template {
     type Err = TemplateParseError;
 
     fn from_str(template: &str) -> Result<Self, Self::Err> {
-        let chunks = all_chunks.parse(template)?;
-        Ok(Self { chunks })
+        let chunks = DELIMITERS
+            .with_borrow(|delimiters| parse_chunks(template, delimiters))?;
+        Ok(Self {
+            chunks: chunks.into(),
+        })
+    }
+}
+
+/// Parse a template string into chunks with the given delimiters
+pub(crate) fn parse_chunks(
+    template: &str,
+    delimiters: &Delimiters,
+) -> Result<Vec<TemplateChunk>, TemplateParseError> {
+    let chunks =
+        (|input: &mut &str| all_chunks(input, delimiters)).parse(template)?;
+    Ok(chunks)
+}
+
+/// Markers that open and close a template expression. Collections can change
+/// these from the default `{{ }}`, so that bodies containing `{{` literally
+/// (e.g. a template for another engine) don't need to be escaped.
+///
+/// Escaping works the same as with the defaults: insert an underscore after
+/// the first character of the opening delimiter, e.g. `<_%`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Delimiters {
+    /// Opening delimiter. Must be at least 2 characters
+    open: String,
+    /// Closing delimiter
+    close: String,
+}
+
+impl Delimiters {
+    pub fn new(
+        open: impl Into<String>,
+        close: impl Into<String>,
+    ) -> Result<Self, DelimitersError> {
+        let open = open.into();
+        let close = close.into();
+        // The escape sequence goes after the first character, so we need a
+        // second one
+        if open.chars().count() < 2 {
+            return Err(DelimitersError::OpenTooShort);
+        }
+        if close.is_empty() {
+            return Err(DelimitersError::CloseEmpty);
+        }
+        if open.chars().chain(close.chars()).any(char::is_whitespace) {
+            return Err(DelimitersError::Whitespace);
+        }
+        if open.chars().nth(1) == Some('_') {
+            return Err(DelimitersError::Escape);
+        }
+        Ok(Self { open, close })
+    }
+
+    pub fn open(&self) -> &str {
+        &self.open
+    }
+
+    pub fn close(&self) -> &str {
+        &self.close
+    }
+
+    /// Parse all templates on the current thread with these delimiters until
+    /// `f` returns. Used while deserializing a collection that defines its own
+    /// delimiters.
+    pub fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        /// Restore the previous delimiters on drop, so a panic in `f` can't
+        /// leak them to the rest of the thread
+        struct Reset(Option<Delimiters>);
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                if let Some(delimiters) = self.0.take() {
+                    DELIMITERS.set(delimiters);
+                }
+            }
+        }
+
+        let _reset = Reset(Some(DELIMITERS.replace(self.clone())));
+        f()
+    }
+
+    /// Split the opening delimiter after its first character, which is where
+    /// escape underscores go
+    fn split_open(&self) -> (&str, &str) {
+        // Length is checked in the constructor
+        let first = self.open.chars().next().unwrap();
+        self.open.split_at(first.len_utf8())
+    }
+}
+
+impl Default for Delimiters {
+    fn default() -> Self {
+        Self {
+            open: EXPRESSION_OPEN.into(),
+            close: EXPRESSION_CLOSE.into(),
+        }
     }
 }
 
@@ -100,12 +264,16 @@ impl FromStr for Identifier {
 /// Potential optimizations if parsing is slow:
 /// - Use take_till or similar in raw string parsing
 /// - <https://docs.rs/winnow/latest/winnow/_topic/performance/index.html>
-fn all_chunks(input: &mut &str) -> ModalResult<Vec<TemplateChunk>> {
+fn all_chunks(
+    input: &mut &str,
+    delimiters: &Delimiters,
+) -> ModalResult<Vec<TemplateChunk>> {
     repeat_till(
         0..,
         alt((
-            expression_chunk.map(TemplateChunk::Expression),
-            raw.map(TemplateChunk::Raw),
+            (|input: &mut &str| expression_chunk(input, delimiters))
+                .map(TemplateChunk::Expression),
+            (|input: &mut &str| raw(input, delimiters)).map(TemplateChunk::Raw),
         ))
         .context(ctx_label("template chunk")),
         eof,
@@ -116,17 +284,20 @@ fn all_chunks(input: &mut &str) -> ModalResult<Vec<TemplateChunk>> {
 }
 
 /// Parse raw text, until we hit a key or end of input
-fn raw(input: &mut &str) -> ModalResult<Arc<str>> {
+fn raw<'a>(
+    input: &mut &'a str,
+    delimiters: &Delimiters,
+) -> ModalResult<Arc<str>> {
     repeat(
         0..,
         alt((
-            escape_sequence,
+            |input: &mut &'a str| escape_sequence(input, delimiters),
             // Match anything other than a key opening. This is inefficient
             // because it means we'll copy into the accumulating string one
             // char at a time. We could theoretically grab up to the next
             // escape seq or key here but I couldn't figure that out. Potential
             // optimization if perf is a problem
-            (not(EXPRESSION_OPEN), any).take(),
+            (not(delimiters.open()), any).take(),
         )),
     )
     .map(String::into)
@@ -134,27 +305,34 @@ fn raw(input: &mut &str) -> ModalResult<Arc<str>> {
     .parse_next(input)
 }
 
-/// Match an escape sequence `{_{`, `{__}`, etc. The trailing curly brace will
-/// **not** be consumed.
-fn escape_sequence<'a>(input: &mut &'a str) -> ModalResult<&'a str> {
+/// Match an escape sequence `{_{`, `{__}`, etc. The rest of the opening
+/// delimiter (the trailing curly brace, by default) will **not** be consumed.
+fn escape_sequence<'a>(
+    input: &mut &'a str,
+    delimiters: &Delimiters,
+) -> ModalResult<&'a str> {
+    let (first, rest) = delimiters.split_open();
     terminated(
         // Parse {_+
-        ("{", repeat::<_, _, (), _, _>(1.., ESCAPE))
+        (first, repeat::<_, _, (), _, _>(1.., ESCAPE))
             .take()
             // Drop the final underscore
             .map(|s: &str| &s[..s.len() - 1]),
-        // Throw away the final _, don't consume the trailing {
-        peek("{"),
+        // Throw away the final _, don't consume the rest of the delimiter
+        peek(rest),
     )
     .parse_next(input)
 }
 
-/// Parse a template expression with its bounding `{{ }}`
-fn expression_chunk(input: &mut &str) -> ModalResult<Expression> {
+/// Parse a template expression with its bounding delimiters
+fn expression_chunk(
+    input: &mut &str,
+    delimiters: &Delimiters,
+) -> ModalResult<Expression> {
     preceded(
-        EXPRESSION_OPEN,
+        delimiters.open(),
         // Any error inside a template key is fatal, including an unclosed key
-        cut_err(terminated(expression, EXPRESSION_CLOSE)),
+        cut_err(terminated(expression, delimiters.close())),
     )
     .context(ctx_label("expression"))
     .parse_next(input)
@@ -195,12 +373,16 @@ fn primary_expression(input: &mut &str) -> ModalResult<Expression> {
             // Look for a fn call before a field to reduce backtracking. A fn
             // call will always parse as a field, but not vice versa
             call.map(Expression::Call),
+            // Check for the variable prefix before a field, because the
+            // prefix alone is a valid field name
+            variable.map(Expression::Variable),
             identifier.map(Expression::Field),
             // If all cases fail, the error from the last case is used. But we
             // want to report an error of "invalid expression" instead
             fail.context(ctx_expected("literal"))
                 .context(ctx_expected("array"))
                 .context(ctx_expected("function call"))
+                .context(ctx_expected("variable"))
                 .context(ctx_expected("field")),
         )),
         boundary,
@@ -295,6 +477,17 @@ fn object(input: &mut &str) -> ModalResult<Vec<(Expression, Expression)>> {
         .parse_next(input)
 }
 
+/// Parse a variable access: `vars.name`
+fn variable(input: &mut &str) -> ModalResult<Identifier> {
+    preceded(
+        VARIABLE_PREFIX,
+        // Once we've seen the prefix, the only option is a variable name
+        cut_err(identifier.context(ctx_expected("variable name"))),
+    )
+    .context(ctx_label("variable"))
+    .parse_next(input)
+}
+
 /// Parse a function call: `f(...)`
 fn call(input: &mut &str) -> ModalResult<FunctionCall> {
     enum Argument {
@@ -571,7 +764,7 @@ mod tests {
         // Display should avoid allocation if the template is empty or a single
         // raw chunk whose text matches the original source (i.e. it hasn't had
         // any escape characters removed)
-        let display_should_allocate = match expected.chunks.as_slice() {
+        let display_should_allocate = match expected.chunks.get().unwrap() {
             [] => false,
             [TemplateChunk::Raw(text)] => &**text != input,
             _ => true,
@@ -708,6 +901,12 @@ mod tests {
     )]
     // ===== Fields =====
     #[case::field("field1", field("field1"), None)]
+    // ===== Variables =====
+    #[case::variable(
+        "vars.version", Expression::Variable("version".into()), None
+    )]
+    // The prefix without a dot is just a field
+    #[case::variable_prefix_field("vars", field("vars"), None)]
     // ===== Function calls =====
     #[case::function(
         "f(1, 'hi', a=field)",
@@ -770,6 +969,7 @@ mod tests {
         "f(a=1, 2)",
         "invalid positional argument after keyword argument"
     )]
+    #[case::variable_incomplete("vars.", "expected variable name")]
     #[case::pipe_incomplete("bogus |", "expected function call")]
     #[case::pipe_to_literal("f() | 3", "expected function call")]
     // This case is common because Jinja allows this when piping to filters
@@ -791,11 +991,61 @@ mod tests {
         assert_err!(expression.parse(input), expected_error);
     }
 
+    /// Parse templates with custom delimiters. Curly braces are plain text,
+    /// and the new opening delimiter is escaped the same way as `{{`
+    #[rstest]
+    #[case::field(("<%", "%>"), "<% field1 %>", [field_chunk("field1")])]
+    #[case::curly_braces(
+        ("<%", "%>"),
+        "{{ field1 }} <%field1%>",
+        [raw("{{ field1 }} "), field_chunk("field1")],
+    )]
+    #[case::escape(
+        ("<%", "%>"),
+        "<_% field1 %> <__%",
+        [raw("<% field1 %> <_%")],
+    )]
+    #[case::object(
+        ("<%", "%>"),
+        "<%{'a': 1}%>",
+        [object([(literal("a"), literal(1))]).into()],
+    )]
+    #[case::multibyte(("💚💚", "💜"), "💚💚field1💜", [field_chunk("field1")])]
+    fn test_parse_delimiters(
+        #[case] (open, close): (&str, &str),
+        #[case] input: &'static str,
+        #[case] expected: impl Into<Template>,
+    ) {
+        let delimiters = Delimiters::new(open, close).unwrap();
+        let parsed: Template =
+            delimiters.scope(|| input.parse()).expect("Parsing failed");
+        assert_eq!(parsed, expected.into());
+        // Outside the scope, the defaults apply again
+        assert_eq!(
+            "{{ field1 }}".parse::<Template>().unwrap(),
+            Template::from_field("field1")
+        );
+    }
+
+    /// Invalid delimiters are rejected
+    #[rstest]
+    #[case::open_too_short("<", "%>", "at least 2 characters")]
+    #[case::close_empty("<%", "", "cannot be empty")]
+    #[case::whitespace("< %", "%>", "cannot contain whitespace")]
+    #[case::escape("<_", "_>", "used for escaping")]
+    fn test_delimiters_error(
+        #[case] open: &str,
+        #[case] close: &str,
+        #[case] expected_error: &str,
+    ) {
+        assert_err!(Delimiters::new(open, close), expected_error);
+    }
+
     /// Test that [Template::from_field] generates the correct template
     #[test]
     fn test_from_field() {
         let template = Template::from_field("field1");
-        assert_eq!(&template.chunks, &[field("field1").into()]);
+        assert_eq!(template.chunks.get().unwrap(), &[field("field1").into()]);
     }
 
     /// Test [Template::raw]. This should parse+stringify back to the same thing
@@ -52,6 +52,7 @@ pub fn expression_arbitrary() -> impl Strategy<Value = Expression> {
     let leaf = prop_oneof![
         any::<Literal>().prop_map(Expression::Literal),
         any::<Identifier>().prop_map(Expression::Field),
+        any::<Identifier>().prop_map(Expression::Variable),
     ];
     leaf.prop_recursive(2, 10, 2, |inner| {
         prop_oneof![
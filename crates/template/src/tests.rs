@@ -1,6 +1,6 @@
 use crate::{
-    Arguments, Context, Identifier, LazyValue, RenderError, Template, Value,
-    value::StreamSource,
+    Arguments, Context, Delimiters, Expression, Identifier, LazyValue,
+    RenderError, Template, Value, value::StreamSource,
 };
 use bytes::{Bytes, BytesMut};
 use futures::{StreamExt, TryFutureExt, TryStreamExt};
@@ -21,6 +21,8 @@ use tokio_util::io::ReaderStream;
         ("['a', 1]", vec![Value::from("b"), 2.into()].into()),
     ].into(),
 )]
+#[case::variable("{{ vars.version }}", "v2".into())]
+#[case::variable_pipe("{{ vars.version | concat('a') }}", "av2".into())]
 #[case::object_dupe_key(
     // Latest entry takes precedence
     "{{ {'Mike': 1, name: 2, 10: 3, '10': 4} }}",
@@ -109,6 +111,19 @@ async fn test_render_stream_chunk_error() {
     assert_err(result, "unknown(): Unknown function");
 }
 
+/// Get the first error from rendered output
+#[rstest]
+#[case::ok("{{ 1 }} and {{ 'two' }}", None)]
+#[case::error("{{ 1 }} {{ unknown() }} {{ other() }}", Some("unknown()"))]
+#[tokio::test]
+async fn test_output_error(
+    #[case] template: Template,
+    #[case] expected: Option<&str>,
+) {
+    let output = template.render(&TestContext::default()).await;
+    assert_eq!(output.error().map(ToString::to_string).as_deref(), expected);
+}
+
 /// Render to a byte stream, but there's an error while collecting one of the
 /// streams
 #[tokio::test]
@@ -196,6 +211,138 @@ async fn test_pipe(#[case] template: Template, #[case] expected: &str) {
     );
 }
 
+/// `fallback()` uses its default when the value fails to render, and retains
+/// the error in the output
+#[rstest]
+#[case::call("{{ fallback('x', name) }}", "Mike", None)]
+#[case::call_error(
+    "{{ fallback('x', unknown) }}",
+    "x",
+    Some("Unknown field `unknown`")
+)]
+#[case::pipe("{{ name | fallback('x') }}", "Mike", None)]
+#[case::pipe_error(
+    "{{ fake() | concat('a') | fallback('x') }}",
+    "x",
+    Some("fake()")
+)]
+#[case::nested(
+    "hello {{ fallback('x', fake()) }}!",
+    "hello x!",
+    Some("fake()")
+)]
+#[tokio::test]
+async fn test_fallback(
+    #[case] template: Template,
+    #[case] expected: &str,
+    #[case] expected_error: Option<&str>,
+) {
+    let output = template.render(&TestContext::default()).await;
+    assert_eq!(output.error().map(ToString::to_string), None);
+    assert_eq!(
+        output.fallback_error().map(ToString::to_string).as_deref(),
+        expected_error
+    );
+    assert_eq!(
+        output.try_collect_value().await.unwrap(),
+        Value::from(expected)
+    );
+}
+
+/// Test visiting all nested expressions in a template
+#[rstest]
+#[case::raw("hello", &[])]
+#[case::field("{{ a }} and {{ b }}", &["a", "b"])]
+#[case::call("{{ f(a, [b], {c: d}, x=e) }}", &["a", "b", "c", "d", "e"])]
+#[case::pipe("{{ a | f(b) }}", &["a", "b"])]
+fn test_walk_expressions(
+    #[case] template: Template,
+    #[case] expected: &[&str],
+) {
+    let mut fields = Vec::new();
+    template.walk_expressions(|expression| {
+        if let Expression::Field(identifier) = expression {
+            fields.push(identifier.to_string());
+        }
+    });
+    assert_eq!(fields, expected);
+}
+
+/// Test visiting the nested expressions that are required to render, which
+/// excludes the value argument of `fallback()`
+#[rstest]
+#[case::field("{{ a }} and {{ b }}", &["a", "b"])]
+#[case::fallback("{{ fallback(a, f(b)) }}", &["a"])]
+#[case::fallback_pipe("{{ f(b) | fallback(a) }}", &["a"])]
+#[case::fallback_nested("{{ f(a, fallback('', b)) }}", &["a"])]
+fn test_walk_required_expressions(
+    #[case] template: Template,
+    #[case] expected: &[&str],
+) {
+    let mut fields = Vec::new();
+    template.walk_required_expressions(|expression| {
+        if let Expression::Field(identifier) = expression {
+            fields.push(identifier.to_string());
+        }
+    });
+    assert_eq!(fields, expected);
+}
+
+/// Deferred templates aren't parsed until they're used, and then behave
+/// the same as eagerly parsed ones
+#[rstest]
+#[case::default("{{ name }}", None, "Mike")]
+#[case::delimiters("<% name %> {{ name }}", Some(("<%", "%>")), "Mike {{ name }}")]
+#[tokio::test]
+async fn test_defer_parsing(
+    #[case] source: &str,
+    #[case] delimiters: Option<(&str, &str)>,
+    #[case] expected: &str,
+) {
+    let delimiters = delimiters
+        .map(|(open, close)| Delimiters::new(open, close).unwrap())
+        .unwrap_or_default();
+    let (deferred, eager) = delimiters.scope(|| {
+        let deferred =
+            Template::defer_parsing(|| Template::parse_or_defer(source.into()))
+                .unwrap();
+        let eager = Template::parse_or_defer(source.into()).unwrap();
+        (deferred, eager)
+    });
+    assert!(!deferred.is_parsed());
+    assert!(eager.is_parsed());
+
+    // Delimiters were captured at creation, not at parse time
+    assert_eq!(
+        deferred
+            .render_string(&TestContext::default())
+            .await
+            .unwrap(),
+        expected
+    );
+    assert!(deferred.is_parsed());
+    assert_eq!(deferred, eager);
+    assert_eq!(deferred.display(), eager.display());
+}
+
+/// An invalid deferred template is only reported when it's used
+#[tokio::test]
+async fn test_defer_parsing_error() {
+    let source = "{{ unclosed";
+    assert!(Template::parse_or_defer(source.into()).is_err());
+
+    let template =
+        Template::defer_parsing(|| Template::parse_or_defer(source.into()))
+            .unwrap();
+    assert!(template.is_dynamic());
+    assert_eq!(template.display(), source);
+    assert!(template.parse_error().is_some());
+    assert_err(
+        template.render_string(&TestContext::default()).await,
+        "Invalid template",
+    );
+}
+
 /// Test error context on a variety of error cases in function calls
 #[rstest]
 #[case::unknown_function("{{ fake() }}", "fake(): Unknown function")]
@@ -209,6 +356,14 @@ async fn test_pipe(#[case] template: Template, #[case] expected: &str) {
     "{{ add(f(), 2) }}",
     "add(): argument 0=f(): f(): Unknown function"
 )]
+#[case::fallback_no_default(
+    "{{ fallback(fake()) }}",
+    "fallback(): Not enough arguments"
+)]
+#[case::fallback_default_error(
+    "{{ fallback(fake(), unknown) }}",
+    "fallback(): argument 0=fake(): fake(): Unknown function"
+)]
 #[case::arg_convert(
     // Argument renders but doesn't convert to what the func wants
     "{{ add(1, 'b') }}",
@@ -249,6 +404,18 @@ impl Context for TestContext {
         }
     }
 
+    async fn get_variable(
+        &self,
+        identifier: &Identifier,
+    ) -> Result<LazyValue, RenderError> {
+        match identifier.as_str() {
+            "version" => Ok("v2".into()),
+            _ => Err(RenderError::VariableUnknown {
+                variable: identifier.clone(),
+            }),
+        }
+    }
+
     async fn call(
         &self,
         function_name: &Identifier,
@@ -260,6 +427,12 @@ impl Context for TestContext {
                 arguments.ensure_consumed()?;
                 Ok(value.into())
             }
+            "fallback" => {
+                let _: Value = arguments.pop_position()?;
+                let value: Value = arguments.pop_position()?;
+                arguments.ensure_consumed()?;
+                Ok(value.into())
+            }
             "add" => {
                 let a: i64 = arguments.pop_position()?;
                 let b: i64 = arguments.pop_position()?;
@@ -10,7 +10,7 @@ use derive_more::{Display, From};
 use futures::{TryStreamExt, stream::BoxStream};
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
-use std::{collections::VecDeque, fmt::Debug, path::PathBuf};
+use std::{collections::VecDeque, fmt::Debug, path::PathBuf, time::Duration};
 
 /// A runtime template value. This very similar to a JSON value, except:
 /// - Numbers do not support arbitrary size
@@ -189,6 +189,23 @@ pub enum LazyValue {
     },
     /// A template chunk that rendered a nested template with multiple chunks
     Nested(RenderedOutput),
+    /// The value of a `fallback()` call whose primary value failed to render.
+    /// The default value is used in its place, but the error is retained so it
+    /// can be shown to the user.
+    Fallback {
+        /// Rendered default value
+        value: Value,
+        /// Error from the primary value
+        error: RenderError,
+    },
+    /// A value that was loaded from a cache instead of being computed, e.g.
+    /// the output of a cached command. The age is retained so the consumer
+    /// can show how fresh the value is.
+    Cached {
+        value: Value,
+        /// How old the cached value was when it was loaded
+        age: Duration,
+    },
 }
 
 impl LazyValue {
@@ -204,6 +221,9 @@ impl LazyValue {
                 .map(|bytes| Value::Bytes(bytes.into())),
             // Box needed for recursion
             Self::Nested(output) => Box::pin(output.try_collect_value()).await,
+            Self::Fallback { value, .. } | Self::Cached { value, .. } => {
+                Ok(value)
+            }
         }
     }
 }
@@ -230,6 +250,23 @@ pub enum StreamSource {
         /// **Absolute** path to the file
         path: PathBuf,
     },
+    /// Data is being streamed from a file and encoded to text along the way,
+    /// e.g. as base64. Unlike [File](Self::File), consumers can't read the
+    /// file natively because they would skip the encoding.
+    #[display(
+        "file {} as {encoding}{}",
+        path.display(),
+        warning.as_ref().map(|warning| format!(" ({warning})")).unwrap_or_default(),
+    )]
+    EncodedFile {
+        /// **Absolute** path to the file
+        path: PathBuf,
+        /// Name of the encoding, e.g. `base64`
+        encoding: String,
+        /// Something the user should know about the file, e.g. that it's too
+        /// large to be embedded comfortably
+        warning: Option<String>,
+    },
 }
 
 /// Convert [Value] to a type fallibly
@@ -346,6 +383,27 @@ where
     }
 }
 
+/// Convert an object to a map
+impl<T> TryFromValue for IndexMap<String, T>
+where
+    T: TryFromValue,
+{
+    fn try_from_value(value: Value) -> Result<Self, WithValue<ValueError>> {
+        if let Value::Object(map) = value {
+            map.into_iter()
+                .map(|(key, value)| Ok((key, T::try_from_value(value)?)))
+                .collect()
+        } else {
+            Err(WithValue::new(
+                value,
+                ValueError::Type {
+                    expected: Expected::Object,
+                },
+            ))
+        }
+    }
+}
+
 /// Convert a template value to JSON. If the value is bytes, this will
 /// deserialize it as JSON, otherwise it will convert directly. This allows us
 /// to parse response bodies as JSON while accepting anything else as a native
@@ -398,6 +456,8 @@ macro_rules! impl_try_from_value_str {
     };
 }
 
+impl_try_from_value_str!(slumber_util::TimeSpan);
+
 /// Arguments passed to a function call
 ///
 /// This container holds all the data a template function may need to construct
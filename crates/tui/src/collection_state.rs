@@ -12,8 +12,11 @@ use slumber_config::Config;
 use slumber_core::{
     collection::{Collection, CollectionError, CollectionFile},
     database::{CollectionDatabase, Database},
+    http::{CookieJar, TokenStore},
 };
+use slumber_util::{STARTUP_TARGET, tr};
 use std::sync::Arc;
+use tracing::info_span;
 
 /// Collection-specific top-level state
 ///
@@ -45,30 +48,48 @@ pub struct CollectionState {
     /// that are in progress, and also serves as a cache for requests from
     /// the DB.
     pub request_store: RequestStore,
+    /// OAuth2 access tokens, backed by the database
+    pub oauth2_tokens: TokenStore,
+    /// Cookie jar, backed by the database
+    pub cookies: CookieJar,
     /// UI presentation and state
     pub view: View,
+    /// Is the collection untrusted? If so, template functions that access the
+    /// local system are disabled
+    pub sandboxed: bool,
 
     // Private state - we hang onto this stuff so we can use it to rebuild the
-    // view. They should never change between reloads
+    // view. The config is replaced when the config file is reloaded
     config: Arc<Config>,
     messages_tx: MessageSender,
 }
 
 impl CollectionState {
     /// Load the collection from the given file. If the load fails, we'll enter
-    /// the error state.
+    /// the error state. Cookies and OAuth2 tokens are scoped to `session`.
     pub fn load(
         config: Arc<Config>,
         collection_file: CollectionFile,
         database: Database,
+        session: Option<String>,
         messages_tx: MessageSender,
     ) -> Self {
         // If we fail to get a DB handle, there's no way to proceed
-        let database = database.into_collection(&collection_file).unwrap();
+        let database = database
+            .into_collection(&collection_file)
+            .unwrap()
+            .with_session(session);
         let request_store = RequestStore::new(database.clone());
+        let oauth2_tokens = TokenStore::new(database.clone());
+        let cookies = CookieJar::new(database.clone());
+        let sandboxed = !collection_file
+            .is_trusted(config.trusted_directories.as_deref(), &database);
 
         // Wrap the collection in Arc so it can be shared cheaply
-        let collection = collection_file.load().map(Arc::new).map_err(Arc::new);
+        let collection = collection_file
+            .load_deferred()
+            .map(Arc::new)
+            .map_err(Arc::new);
         if let Ok(collection) = &collection {
             // Update the DB with the collection's name
             database.set_name(collection);
@@ -79,12 +100,17 @@ impl CollectionState {
                 file: collection_file.clone(),
                 error,
             });
-        let view = View::new(
-            config.clone(),
-            view_collection,
-            database.clone(),
-            messages_tx.clone(),
-        );
+        View::apply_startup_config(&config.tui.startup, database.clone());
+        let mut view = info_span!(target: STARTUP_TARGET, "Build view")
+            .in_scope(|| {
+                View::new(
+                    config.clone(),
+                    view_collection,
+                    database.clone(),
+                    messages_tx.clone(),
+                )
+            });
+        view.set_remote(collection_file.remote().cloned());
 
         Self {
             collection,
@@ -92,7 +118,10 @@ impl CollectionState {
             component_map: ComponentMap::default(),
             database,
             request_store,
+            oauth2_tokens,
+            cookies,
             view,
+            sandboxed,
             config,
             messages_tx,
         }
@@ -111,11 +140,52 @@ impl CollectionState {
             self.database.clone(),
             self.messages_tx.clone(),
         );
-        self.view.notify("Reloaded collection");
+        self.view.set_remote(self.collection_file.remote().cloned());
+        self.view.notify(tr!("notify-reloaded-collection"));
 
         self.collection = Ok(collection);
     }
 
+    /// Switch to a reloaded config. The view is rebuilt so new styles and input
+    /// bindings take effect immediately. This works in the error state too, so
+    /// the error is shown with the new theme.
+    pub fn set_config(&mut self, config: Arc<Config>) {
+        self.config = config;
+        let collection =
+            self.collection.clone().map_err(|error| InvalidCollection {
+                file: self.collection_file.clone(),
+                error,
+            });
+        self.view = View::new(
+            self.config.clone(),
+            collection,
+            self.database.clone(),
+            self.messages_tx.clone(),
+        );
+        self.view.set_remote(self.collection_file.remote().cloned());
+        self.view.notify(tr!("notify-reloaded-config"));
+    }
+
+    /// Trust the current collection, disabling the sandbox. This is persisted
+    /// so the collection will be trusted in future sessions too. For a remote
+    /// collection, only the current content is trusted.
+    pub fn trust(&mut self) -> anyhow::Result<()> {
+        self.collection_file.trust(&self.database)?;
+        self.sandboxed = false;
+        // Rebuild the view so all previews render again with full access
+        if let Ok(collection) = &self.collection {
+            self.view = View::new(
+                self.config.clone(),
+                Ok(Arc::clone(collection)),
+                self.database.clone(),
+                self.messages_tx.clone(),
+            );
+            self.view.set_remote(self.collection_file.remote().cloned());
+        }
+        self.view.notify(tr!("notify-trusted-collection"));
+        Ok(())
+    }
+
     /// Handle all events in the queue. Return `true` if at least one event was
     /// consumed, `false` if the queue was empty
     pub fn drain_events(&mut self) -> bool {
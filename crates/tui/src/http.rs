@@ -14,18 +14,22 @@ use slumber_core::{
     collection::{ProfileId, RecipeId},
     database::{CollectionDatabase, DatabaseError, ProfileFilter},
     http::{
-        BuildOptions, Exchange, ExchangeSummary, HttpEngine, RequestBuildError,
-        RequestError, RequestId, RequestRecord, RequestSeed,
-        StoredRequestError, TriggeredRequestError,
+        BuildOptions, DownloadProgress, Exchange, ExchangeSummary, HttpEngine,
+        HttpVersion, RequestBuildError, RequestError, RequestId, RequestRecord,
+        RequestSeed, RequestTicket, RetryProgress, StoredRequestError,
+        TriggeredRequestError,
+        sse::{self, EventStreamParser, ServerEvent},
     },
     render::{HttpProvider, Prompt, TemplateContext},
+    util::MaybeStr,
 };
 use slumber_template::Value;
 use std::{
     collections::{HashMap, hash_map::Entry},
-    fmt::Debug,
-    path::Path,
-    sync::Arc,
+    fmt::{Debug, Write},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
 };
 use strum::EnumDiscriminants;
 use tokio::sync::oneshot;
@@ -34,7 +38,7 @@ use tracing::warn;
 use uuid::Uuid;
 
 /// Configuration that defines how to render a request
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 #[cfg_attr(test, derive(PartialEq))]
 pub struct RequestConfig {
     pub profile_id: Option<ProfileId>,
@@ -111,6 +115,10 @@ response = asyncio.run(collection.request({kwargs}))
 pub struct RequestStore {
     database: CollectionDatabase,
     requests: HashMap<RequestId, RequestState>,
+    /// Requests that were triggered while building another request, mapped to
+    /// the ID of the request that triggered them. Only includes requests from
+    /// the current session.
+    parents: HashMap<RequestId, RequestId>,
 }
 
 impl RequestStore {
@@ -118,6 +126,7 @@ impl RequestStore {
         Self {
             database,
             requests: Default::default(),
+            parents: Default::default(),
         }
     }
 
@@ -128,6 +137,21 @@ impl RequestStore {
             .any(|state| matches!(state, RequestState::Loading { .. }))
     }
 
+    /// Progress of all in-flight requests. This changes whenever one of them
+    /// receives body data or starts a retry, so the main loop can tell if
+    /// there's anything new to draw without redrawing on every tick.
+    pub fn active_progress(&self) -> u64 {
+        self.requests
+            .values()
+            .map(|state| match state {
+                RequestState::Loading { body, retry, .. } => {
+                    body.received() + u64::from(retry.attempt())
+                }
+                _ => 0,
+            })
+            .sum()
+    }
+
     /// Get request state by ID
     pub fn get(&self, id: RequestId) -> Option<&RequestState> {
         self.requests.get(&id)
@@ -159,6 +183,62 @@ impl RequestStore {
         }
     }
 
+    /// Insert a new request that was triggered while building another request.
+    /// If the triggering request is known, it's recorded as the new request's
+    /// parent so the chain can be shown.
+    pub fn trigger(
+        &mut self,
+        id: RequestId,
+        parent_id: Option<RequestId>,
+        profile_id: Option<ProfileId>,
+        recipe_id: RecipeId,
+    ) -> &RequestState {
+        if let Some(parent_id) = parent_id {
+            self.parents.insert(id, parent_id);
+        }
+        self.start(id, profile_id, recipe_id, None)
+    }
+
+    /// Get the request at the root of a request chain. If the request wasn't
+    /// triggered by another request, this is the request itself
+    pub fn chain_root(&self, mut id: RequestId) -> RequestId {
+        while let Some(parent_id) = self.parents.get(&id) {
+            id = *parent_id;
+        }
+        id
+    }
+
+    /// Get every request in the chain starting at the given request, with the
+    /// depth of each one in the chain. The order is depth-first, with sibling
+    /// requests in the order they started. Requests no longer in the store are
+    /// excluded, along with everything they triggered.
+    pub fn chain(&self, root_id: RequestId) -> Vec<(usize, &RequestState)> {
+        fn visit<'a>(
+            store: &'a RequestStore,
+            id: RequestId,
+            depth: usize,
+            chain: &mut Vec<(usize, &'a RequestState)>,
+        ) {
+            let Some(state) = store.get(id) else {
+                return;
+            };
+            chain.push((depth, state));
+            let children = store
+                .parents
+                .iter()
+                .filter(|(_, parent_id)| **parent_id == id)
+                .filter_map(|(child_id, _)| store.get(*child_id))
+                .sorted_by_key(|state| state.request_metadata().start_time);
+            for child in children {
+                visit(store, child.id(), depth + 1, chain);
+            }
+        }
+
+        let mut chain = Vec::new();
+        visit(self, root_id, 0, &mut chain);
+        chain
+    }
+
     /// Add a prompt to a building request. The request will remain in the
     /// building state.
     pub fn prompt(
@@ -213,16 +293,26 @@ impl RequestStore {
     }
 
     /// Mark a request as loading. Return the updated state.
-    pub fn loading(&mut self, request: Arc<RequestRecord>) -> &RequestState {
+    pub fn loading(
+        &mut self,
+        request: Arc<RequestRecord>,
+        body: StreamingBody,
+        retry: RetryProgress,
+    ) -> &RequestState {
         self.replace(request.id, |state| {
             // Requests should go building->loading, but it's possible it got
-            // cancelled right before this was called
-            if let RequestState::Building { cancel_token, .. } = state {
+            // cancelled right before this was called. A request that's already
+            // loading can be loaded again if it's retried.
+            if let RequestState::Building { cancel_token, .. }
+            | RequestState::Loading { cancel_token, .. } = state
+            {
                 RequestState::Loading {
                     request,
                     // Reset timer
                     start_time: Utc::now(),
                     cancel_token,
+                    body,
+                    retry,
                 }
             } else {
                 // Can't create loading state since we don't have a join handle
@@ -320,6 +410,8 @@ impl RequestStore {
                 request,
                 start_time,
                 cancel_token: Some(cancel_token),
+                body: _,
+                retry: _,
             } => {
                 cancel_token.cancel();
                 RequestState::Cancelled {
@@ -385,6 +477,39 @@ impl RequestStore {
             .max_by_key(|exchange| exchange.start_time))
     }
 
+    /// Load the most recent _completed_ request that started before the given
+    /// request, for the same profile+recipe. This is what the given request's
+    /// response is compared against. Return `None` if the given request isn't
+    /// in the store or the DB, or there is no earlier exchange.
+    pub fn load_previous_exchange(
+        &mut self,
+        id: RequestId,
+    ) -> anyhow::Result<Option<&Exchange>> {
+        let Some(state) = self.load(id)? else {
+            return Ok(None);
+        };
+        let profile_id = state.profile_id().cloned();
+        let recipe_id = state.recipe_id().clone();
+        let start_time = state.request_metadata().start_time;
+
+        // Summaries are sorted descending, so the first match is the latest
+        let previous_id = self
+            .load_summaries(profile_id.as_ref(), &recipe_id, None)?
+            .find(|summary| {
+                matches!(summary, RequestStateSummary::Response(_))
+                    && summary.start_time() < start_time
+            })
+            .map(|summary| summary.id());
+        let Some(previous_id) = previous_id else {
+            return Ok(None);
+        };
+
+        match self.load(previous_id)? {
+            Some(RequestState::Response { exchange }) => Ok(Some(exchange)),
+            _ => Ok(None),
+        }
+    }
+
     /// Load the most recent matching exchange from the DB and cache it here
     fn cache_latest_exchange(
         &mut self,
@@ -408,16 +533,22 @@ impl RequestStore {
     /// are just summaries, not the full request. This is intended for list
     /// views, so we don't need to load the entire request/response for each
     /// one. Results are sorted by request *start* time, descending.
+    ///
+    /// If `limit` is given, only that many of the most recent requests will be
+    /// loaded from the DB. In-memory requests are always included.
     pub fn load_summaries<'a>(
         &'a self,
         profile_id: Option<&'a ProfileId>,
         recipe_id: &'a RecipeId,
+        limit: Option<usize>,
     ) -> anyhow::Result<impl 'a + Iterator<Item = RequestStateSummary>> {
         // Load summaries from the DB. We do *not* want to insert these into the
         // store, because they don't include request/response data
-        let loaded = self
-            .database
-            .get_recipe_requests(profile_id.into(), recipe_id)?;
+        let loaded = self.database.get_recipe_requests(
+            profile_id.into(),
+            recipe_id,
+            limit,
+        )?;
 
         // Find what we have in memory already
         let iter = self
@@ -438,6 +569,29 @@ impl RequestStore {
         Ok(iter)
     }
 
+    /// Load the next page of historical requests for a recipe+profile, after
+    /// a list from [Self::load_summaries]. `last` is the final request in
+    /// that list. Only requests from the DB are returned; in-memory requests
+    /// were all included in the first page already.
+    pub fn load_summaries_after(
+        &self,
+        profile_id: Option<&ProfileId>,
+        recipe_id: &RecipeId,
+        last: &RequestStateSummary,
+        limit: usize,
+    ) -> anyhow::Result<Vec<RequestStateSummary>> {
+        let loaded = self.database.get_recipe_requests_after(
+            profile_id.into(),
+            recipe_id,
+            (last.start_time(), last.id()),
+            limit,
+        )?;
+        Ok(loaded
+            .into_iter()
+            .map(RequestStateSummary::Response)
+            .collect())
+    }
+
     /// Is the given request either building or loading, and does it have an
     /// abort handle? Triggered requests (nested within another request's
     /// render) cannot be cancelled independently.
@@ -475,6 +629,7 @@ impl RequestStore {
     /// Delete a single request from the store _and_ the database
     pub fn delete_request(&mut self, id: RequestId) -> anyhow::Result<()> {
         self.requests.remove(&id);
+        self.parents.remove(&id);
         self.database.delete_request(id)?;
         Ok(())
     }
@@ -566,37 +721,62 @@ impl HttpProvider for TuiHttpProvider {
             let request_id = seed.id;
             let profile_id = template_context.selected_profile.clone();
             let recipe_id = seed.recipe_id.clone();
+            // The request being built when this was triggered is its parent
+            let parent_id = BUILDING_REQUEST.try_with(|id| *id).ok();
 
             self.messages_tx.send(HttpMessage::Triggered {
                 request_id,
+                parent_id,
                 profile_id,
                 recipe_id,
             });
 
-            let ticket = self
-                .http_engine
-                .build(seed, template_context)
-                .await
-                .map_err(Arc::new)
-                .inspect_err(|error| {
-                    // Report error to the TUI
-                    self.messages_tx
-                        .send(HttpMessage::BuildError(Arc::clone(error)));
-                })?;
+            let ticket =
+                build_request(&self.http_engine, seed, template_context)
+                    .await
+                    .map_err(Arc::new)
+                    .inspect_err(|error| {
+                        // Report error to the TUI
+                        self.messages_tx
+                            .send(HttpMessage::BuildError(Arc::clone(error)));
+                    })?;
 
             // Build successful, send it out
-            self.messages_tx
-                .send(HttpMessage::Loading(Arc::clone(ticket.record())));
+            let body = StreamingBody::default();
+            self.messages_tx.send(HttpMessage::Loading {
+                request: Arc::clone(ticket.record()),
+                body: body.clone(),
+                retry: ticket.retry_progress().clone(),
+            });
 
             // Clone the exchange so we can persist it in the DB/store and
             // still return it
-            let result = ticket.send().await.map_err(Arc::new);
+            let result = body.receive(ticket).await.map_err(Arc::new);
             self.messages_tx.send(HttpMessage::Complete(result.clone()));
             result.map_err(TriggeredRequestError::Send)
         }
     }
 }
 
+tokio::task_local! {
+    /// ID of the request being built in the current task. Requests triggered
+    /// by a build run in the same task as their parent, so this tells them
+    /// what their parent is.
+    static BUILDING_REQUEST: RequestId;
+}
+
+/// Build a request. Any requests triggered while rendering its templates will
+/// be recorded as its children in the request chain.
+pub async fn build_request(
+    http_engine: &HttpEngine,
+    seed: RequestSeed,
+    template_context: &TemplateContext,
+) -> Result<RequestTicket, RequestBuildError> {
+    BUILDING_REQUEST
+        .scope(seed.id, http_engine.build(seed, template_context))
+        .await
+}
+
 /// State of an HTTP response, which can be in various states of
 /// completion/failure. Each request *recipe* should have one request state
 /// stored in the view at a time.
@@ -637,6 +817,11 @@ pub enum RequestState {
         /// request. `None` for triggered requests, because they don't run at
         /// the root of a task and therefore can't be aborted independently.
         cancel_token: Option<CancellationToken>,
+        /// Response body received so far
+        body: StreamingBody,
+        /// Attempts made under the recipe's retry policy. Shared with the
+        /// task sending the request, so it updates as retries happen
+        retry: RetryProgress,
     },
 
     /// User cancelled the request mid-flight. We don't store the request here,
@@ -710,11 +895,19 @@ impl RequestState {
         let id = self.id();
         match self {
             // In-progress states
-            Self::Building { start_time, .. }
-            | Self::Loading { start_time, .. } => RequestMetadata {
+            Self::Building { start_time, .. } => RequestMetadata {
                 id,
                 start_time: *start_time,
                 end_time: None,
+                retry: None,
+            },
+            Self::Loading {
+                start_time, retry, ..
+            } => RequestMetadata {
+                id,
+                start_time: *start_time,
+                end_time: None,
+                retry: Some(retry.clone()),
             },
 
             // Error states
@@ -722,6 +915,7 @@ impl RequestState {
                 id,
                 start_time: error.start_time,
                 end_time: Some(error.end_time),
+                retry: None,
             },
             Self::Cancelled {
                 start_time,
@@ -731,11 +925,13 @@ impl RequestState {
                 id,
                 start_time: *start_time,
                 end_time: Some(*end_time),
+                retry: None,
             },
             Self::RequestError { error } => RequestMetadata {
                 id,
                 start_time: error.start_time,
                 end_time: Some(error.end_time),
+                retry: None,
             },
 
             // Completed
@@ -743,6 +939,7 @@ impl RequestState {
                 id,
                 start_time: exchange.start_time,
                 end_time: Some(exchange.end_time),
+                retry: None,
             },
         }
     }
@@ -752,8 +949,10 @@ impl RequestState {
     pub fn response_metadata(&self) -> Option<ResponseMetadata> {
         if let RequestState::Response { exchange } = self {
             Some(ResponseMetadata {
+                http_version: exchange.response.http_version,
                 status: exchange.response.status,
                 size: exchange.response.body.size(),
+                token_expiry: exchange.token_expiry(),
             })
         } else {
             None
@@ -802,11 +1001,15 @@ impl PartialEq for RequestState {
                     request: l_request,
                     start_time: l_start_time,
                     cancel_token: _,
+                    body: _,
+                    retry: _,
                 },
                 Self::Loading {
                     request: r_request,
                     start_time: r_start_time,
                     cancel_token: _,
+                    body: _,
+                    retry: _,
                 },
             ) => l_request == r_request && l_start_time == r_start_time,
             (
@@ -848,6 +1051,60 @@ impl PartialEq for RequestState {
     }
 }
 
+/// Compare a recorded request to a fresh build of the same recipe, line by
+/// line. Each line of the output is prefixed with `-` (only in the recorded
+/// request), `+` (only in the new request), or a space (unchanged). Return
+/// `None` if the two requests are identical.
+pub fn diff_requests(
+    old: &RequestRecord,
+    new: &RequestRecord,
+) -> Option<String> {
+    let old_text = request_text(old);
+    let new_text = request_text(new);
+    if old_text == new_text {
+        return None;
+    }
+
+    let mut output = String::new();
+    // Both texts end in a newline, which diff would report as an extra line
+    for result in diff::lines(
+        old_text.trim_end_matches('\n'),
+        new_text.trim_end_matches('\n'),
+    ) {
+        let (prefix, line) = match result {
+            diff::Result::Left(line) => ('-', line),
+            diff::Result::Right(line) => ('+', line),
+            diff::Result::Both(line, _) => (' ', line),
+        };
+        output.push(prefix);
+        output.push_str(line);
+        output.push('\n');
+    }
+    Some(output)
+}
+
+/// Format a request as HTTP-ish text, for diffing. Headers are sorted so that
+/// reordering them in the recipe doesn't show up as a change.
+fn request_text(request: &RequestRecord) -> String {
+    let mut text = format!("{} {}\n", request.method, request.url);
+    for (name, value) in request
+        .headers
+        .iter()
+        .sorted_by_key(|(name, _)| name.as_str())
+    {
+        // Writing to a String is infallible
+        let _ = writeln!(text, "{name}: {}", MaybeStr(value.as_bytes()));
+    }
+    match request.body() {
+        Some([]) => {}
+        Some(body) => {
+            let _ = write!(text, "\n{:#}\n", MaybeStr(body));
+        }
+        None => text.push_str("\n<body not available>\n"),
+    }
+    text
+}
+
 /// Metadata derived from a request. The request can be in progress, completed,
 /// or failed.
 #[derive(Debug)]
@@ -859,6 +1116,9 @@ pub struct RequestMetadata {
     /// When did the request end? This could be when the response came back, or
     /// the request failed/was cancelled. `None` if still loading.
     pub end_time: Option<DateTime<Utc>>,
+    /// Attempts made under the recipe's retry policy. Only present while the
+    /// request is in flight
+    pub retry: Option<RetryProgress>,
 }
 
 impl RequestMetadata {
@@ -874,9 +1134,243 @@ impl RequestMetadata {
 /// have completed successfully.
 #[derive(Copy, Clone, Debug)]
 pub struct ResponseMetadata {
+    /// HTTP version negotiated with the server
+    pub http_version: HttpVersion,
     pub status: StatusCode,
     /// Size of the response *body*
     pub size: usize,
+    /// When the auth token in the response expires, if the response contains
+    /// a token with a discoverable expiry
+    pub token_expiry: Option<DateTime<Utc>>,
+}
+
+/// Response body for an in-flight request, which is appended to as chunks are
+/// received. The HTTP task writes to it and the view reads from it at draw
+/// time, so we don't need to route a message through the main loop for every
+/// chunk. Clones share the same buffer.
+#[derive(Clone, Debug, Default)]
+pub struct StreamingBody {
+    data: Arc<Mutex<StreamingBodyData>>,
+    /// Triggered to stop receiving the body early
+    stop: CancellationToken,
+}
+
+impl StreamingBody {
+    /// Create a body that's written to a file instead of this buffer. Only
+    /// the download progress is tracked here.
+    pub fn download(path: PathBuf) -> Self {
+        let body = Self::default();
+        body.lock().download = Some(Download {
+            path,
+            progress: DownloadProgress::default(),
+        });
+        body
+    }
+
+    /// Send a request, appending each chunk of the response body to this
+    /// buffer as it's received. Return the completed exchange. If this is a
+    /// download, the body is written to its file instead.
+    pub async fn receive(
+        &self,
+        ticket: RequestTicket,
+    ) -> Result<Exchange, RequestError> {
+        // A WebSocket or gRPC body isn't a file, so show it as usual
+        if ticket.is_websocket() || ticket.is_grpc() {
+            self.lock().download = None;
+        }
+        let path = self
+            .lock()
+            .download
+            .as_ref()
+            .map(|download| download.path.clone());
+        if let Some(path) = path {
+            return ticket
+                .download(
+                    &path,
+                    |_, _| {},
+                    |progress| {
+                        if let Some(download) = &mut self.lock().download {
+                            download.progress = progress;
+                        }
+                    },
+                )
+                .await;
+        }
+
+        ticket
+            .send_streaming_until(
+                self.stop.cancelled(),
+                |_, headers| {
+                    if sse::is_event_stream(headers) {
+                        self.start_events();
+                    }
+                },
+                |chunk| {
+                    self.push(chunk);
+                    ControlFlow::Continue(())
+                },
+            )
+            .await
+    }
+
+    /// Parse the body as a Server-Sent Events stream from here on
+    pub fn start_events(&self) {
+        self.lock().events = Some(EventStream::default());
+    }
+
+    /// Append a chunk to the body. If the buffer is over its limit, the oldest
+    /// lines are dropped. The complete body is still collected by the engine;
+    /// this buffer is only for display.
+    pub fn push(&self, chunk: &[u8]) {
+        let mut data = self.lock();
+        data.bytes.extend_from_slice(chunk);
+        data.received += chunk.len();
+        data.chunks += 1;
+        data.lines += count_newlines(chunk);
+        if let Some(events) = &mut data.events {
+            events.push(chunk);
+        }
+
+        // Drop down to half the limit at once, so the cost of shifting the
+        // buffer is amortized over many chunks
+        if data.bytes.len() > StreamingBodyData::MAX_BYTES {
+            let excess = data.bytes.len() - StreamingBodyData::MAX_BYTES / 2;
+            // Cut at a line boundary so the first line isn't partial
+            let end = data.bytes[excess..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(data.bytes.len(), |i| excess + i + 1);
+            let dropped = count_newlines(&data.bytes[..end]);
+            data.bytes.drain(..end);
+            data.lines -= dropped;
+        }
+    }
+
+    /// Total bytes received so far. For a download, this is the number of
+    /// bytes written to the file.
+    pub fn received(&self) -> u64 {
+        let data = self.lock();
+        data.download
+            .as_ref()
+            .map_or(data.received as u64, |download| download.progress.received)
+    }
+
+    /// Stop receiving the body. Unlike cancelling the request, this completes
+    /// it with whatever has been received so far. This is how the user ends a
+    /// stream that would otherwise stay open indefinitely.
+    pub fn stop(&self) {
+        self.stop.cancel();
+    }
+
+    /// Has [Self::stop] been called?
+    pub fn is_stopped(&self) -> bool {
+        self.stop.is_cancelled()
+    }
+
+    /// Access the body received so far. Don't hold onto this for long, because
+    /// it blocks the HTTP task from receiving more data.
+    pub fn lock(&self) -> MutexGuard<'_, StreamingBodyData> {
+        // The data is append-only so a panic while writing can't leave it in
+        // an invalid state
+        self.data.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Count the newlines in some bytes
+// Chunks are small enough that pulling in bytecount isn't worth it
+#[expect(clippy::naive_bytecount)]
+fn count_newlines(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|&&b| b == b'\n').count()
+}
+
+/// Contents of a [StreamingBody]
+#[derive(Debug, Default)]
+pub struct StreamingBodyData {
+    /// The most recent bytes received. Once this passes [Self::MAX_BYTES],
+    /// the oldest lines are dropped, so a long-lived stream doesn't grow
+    /// without bound.
+    pub bytes: Vec<u8>,
+    /// Total bytes received, including any that have been dropped
+    pub received: usize,
+    /// Number of chunks received
+    pub chunks: usize,
+    /// Number of newlines in [Self::bytes]. The number of lines in the body is
+    /// this plus one.
+    pub lines: usize,
+    /// If the response is a Server-Sent Events stream, the events parsed from
+    /// the body so far
+    pub events: Option<EventStream>,
+    /// If the body is being written to a file, where it's going and how much
+    /// has been written. The body isn't stored in memory in this case.
+    pub download: Option<Download>,
+}
+
+/// A response body being written to a file as it's received
+#[derive(Debug)]
+pub struct Download {
+    pub path: PathBuf,
+    pub progress: DownloadProgress,
+}
+
+impl StreamingBodyData {
+    /// Most bytes of the body to hold for display
+    pub const MAX_BYTES: usize = 1 << 20; // 1 MiB
+
+    /// Number of lines to display. For event streams, this is the number of
+    /// lines across all events rather than in the raw body.
+    pub fn line_count(&self) -> usize {
+        self.events
+            .as_ref()
+            .map_or(self.lines + 1, |events| events.lines)
+    }
+}
+
+/// Events parsed from a `text/event-stream` response body as it's received
+#[derive(Debug, Default)]
+pub struct EventStream {
+    parser: EventStreamParser,
+    /// The most recent complete events. Once this passes
+    /// [Self::MAX_EVENTS], the oldest events are dropped.
+    pub events: Vec<ReceivedEvent>,
+    /// Total number of events received, including any that have been dropped
+    pub received: usize,
+    /// Total number of data lines across all events in [Self::events]
+    pub lines: usize,
+}
+
+impl EventStream {
+    /// Most events to hold for display
+    pub const MAX_EVENTS: usize = 10_000;
+
+    /// Parse a chunk of the body, recording any events it completes
+    fn push(&mut self, chunk: &[u8]) {
+        let time = Utc::now();
+        for event in self.parser.push(chunk) {
+            self.lines += Self::event_lines(&event);
+            self.received += 1;
+            self.events.push(ReceivedEvent { time, event });
+        }
+
+        // Drop down to half the limit at once to amortize the shift
+        if self.events.len() > Self::MAX_EVENTS {
+            let excess = self.events.len() - Self::MAX_EVENTS / 2;
+            for received in self.events.drain(..excess) {
+                self.lines -= Self::event_lines(&received.event);
+            }
+        }
+    }
+
+    /// Each line of data is displayed on its own line
+    fn event_lines(event: &ServerEvent) -> usize {
+        event.data.split('\n').count()
+    }
+}
+
+/// An event from an event stream, with the time it was received
+#[derive(Debug)]
+pub struct ReceivedEvent {
+    pub time: DateTime<Utc>,
+    pub event: ServerEvent,
 }
 
 /// A simplified version of [RequestState], which only stores metadata. This is
@@ -2,9 +2,12 @@ use super::*;
 use crate::util;
 use chrono::Utc;
 use rstest::{fixture, rstest};
-use slumber_core::http::{
-    Exchange, RequestBuildError, RequestBuildErrorKind, RequestError,
-    RequestRecord,
+use slumber_core::{
+    http::{
+        Exchange, RequestBuildError, RequestBuildErrorKind, RequestError,
+        RequestRecord,
+    },
+    test_util::header_map,
 };
 use slumber_template::RenderError;
 use slumber_util::{Factory, assert_matches};
@@ -68,6 +71,50 @@ response = asyncio.run(collection.request({kwargs}))
     assert_eq!(config.to_python(Path::new(file)), expected_code);
 }
 
+/// Test [diff_requests]. Header order doesn't matter, but everything else
+/// does
+#[rstest]
+#[case::unchanged(RequestRecord::factory(()), None)]
+#[case::header_order(
+    RequestRecord {
+        headers: header_map([
+            ("User-Agent", "slumber"),
+            ("Content-Type", "application/json"),
+            ("Accept", "application/json"),
+        ]),
+        ..RequestRecord::factory(())
+    },
+    None,
+)]
+#[case::changed(
+    RequestRecord {
+        url: "http://localhost/url?page=2".parse().unwrap(),
+        headers: header_map([
+            ("Accept", "application/json"),
+            ("Content-Type", "application/json"),
+        ]),
+        body: Some(br#"{"a": 1}"#.as_slice().into()),
+        ..RequestRecord::factory(())
+    },
+    Some("\
+-GET http://localhost/url
++GET http://localhost/url?page=2
+ accept: application/json
+ content-type: application/json
+-user-agent: slumber
+ 
+-<body not available>
++{\"a\": 1}
+"),
+)]
+fn test_diff_requests(
+    #[case] new: RequestRecord,
+    #[case] expected: Option<&str>,
+) {
+    let old = RequestRecord::factory(());
+    assert_eq!(diff_requests(&old, &new).as_deref(), expected);
+}
+
 /// Test getting a request from the request store
 #[rstest]
 fn test_get() {
@@ -84,6 +131,40 @@ fn test_get() {
     assert_eq!(store.get(RequestId::new()), None);
 }
 
+/// Triggered requests are tracked as a tree under the request that triggered
+/// them
+#[rstest]
+fn test_chain() {
+    let mut store = RequestStore::new(CollectionDatabase::factory(()));
+    let [root, a, b, c, other] = [(); 5].map(|()| RequestId::new());
+    store.start(root, None, "root".into(), None);
+    store.trigger(a, Some(root), None, "a".into());
+    store.trigger(c, Some(a), None, "c".into());
+    // Make sure the sibling starts strictly later
+    std::thread::sleep(Duration::from_millis(1));
+    store.trigger(b, Some(root), None, "b".into());
+    // Parent unknown, so it's its own chain
+    store.trigger(other, None, None, "other".into());
+
+    assert_eq!(store.chain_root(c), root);
+    assert_eq!(store.chain_root(root), root);
+    assert_eq!(store.chain_root(other), other);
+
+    let chain = |store: &RequestStore, id| {
+        store
+            .chain(id)
+            .into_iter()
+            .map(|(depth, state)| (depth, state.id()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        chain(&store, root),
+        [(0, root), (1, a), (2, c), (1, b)].as_slice()
+    );
+    assert_eq!(chain(&store, a), [(0, a), (1, c)].as_slice());
+    assert_eq!(chain(&store, other), [(0, other)].as_slice());
+}
+
 /// building->loading->success
 #[rstest]
 #[tokio::test]
@@ -101,7 +182,11 @@ async fn test_life_cycle_success() {
     );
     assert_matches!(store.get(id), Some(RequestState::Building { .. }));
 
-    store.loading(Arc::clone(&exchange.request));
+    store.loading(
+        Arc::clone(&exchange.request),
+        StreamingBody::default(),
+        RetryProgress::default(),
+    );
     assert_matches!(store.get(id), Some(RequestState::Loading { .. }));
 
     store.response(exchange);
@@ -162,14 +247,18 @@ async fn test_life_cycle_request_error() {
     store.start(id, profile_id.clone(), recipe_id.clone(), None);
     assert_matches!(store.get(id), Some(RequestState::Building { .. }));
 
-    store.loading(Arc::clone(&exchange.request));
+    store.loading(
+        Arc::clone(&exchange.request),
+        StreamingBody::default(),
+        RetryProgress::default(),
+    );
     assert_matches!(store.get(id), Some(RequestState::Loading { .. }));
 
     // reqwest doesn't let you build an error directly
     let error = reqwest::get("fake").await.unwrap_err();
     store.request_error(
         RequestError {
-            error,
+            error: error.into(),
             request: exchange.request,
             start_time: Utc::now(),
             end_time: Utc::now(),
@@ -234,13 +323,88 @@ async fn test_life_cycle_loading_cancel() {
         recipe_id.clone(),
         Some(cancel_token),
     );
-    store.loading(exchange.request);
+    store.loading(
+        exchange.request,
+        StreamingBody::default(),
+        RetryProgress::default(),
+    );
     assert_matches!(store.get(id), Some(RequestState::Loading { .. }));
     store.cancel(id);
     assert_matches!(store.get(id), Some(RequestState::Cancelled { .. }));
     assert!(!future_finished.load(Ordering::Relaxed));
 }
 
+/// Progress of in-flight requests changes when any of them receives data
+#[rstest]
+fn test_active_progress(mut store: RequestStore) {
+    let exchange = Exchange::factory(());
+    let body = StreamingBody::default();
+    store.start(
+        exchange.id,
+        exchange.request.profile_id.clone(),
+        exchange.request.recipe_id.clone(),
+        None,
+    );
+    assert_eq!(store.active_progress(), 0);
+
+    store.loading(
+        Arc::clone(&exchange.request),
+        body.clone(),
+        RetryProgress::default(),
+    );
+    assert_eq!(store.active_progress(), 0);
+    body.push(b"hello");
+    assert_eq!(store.active_progress(), 5);
+    body.push(b" world");
+    assert_eq!(store.active_progress(), 11);
+
+    // Completed requests don't count
+    store.response(exchange);
+    assert_eq!(store.active_progress(), 0);
+}
+
+/// Long streams drop their oldest lines to stay under the size limit, while
+/// still counting everything received
+#[rstest]
+fn test_streaming_body_limit() {
+    let body = StreamingBody::default();
+    let line = [b'x'; 99].iter().chain(b"\n").copied().collect::<Vec<_>>();
+    let total_lines = StreamingBodyData::MAX_BYTES / 100 + 1;
+    for _ in 0..total_lines {
+        body.push(&line);
+    }
+
+    let data = body.lock();
+    assert!(data.bytes.len() <= StreamingBodyData::MAX_BYTES);
+    assert_eq!(data.received, total_lines * 100);
+    assert_eq!(data.chunks, total_lines);
+    // The buffer starts on a line boundary, and its line count matches
+    assert!(data.bytes.starts_with(&line));
+    assert_eq!(data.lines, data.bytes.len() / 100);
+}
+
+/// Long event streams drop their oldest events
+#[rstest]
+fn test_streaming_events_limit() {
+    let body = StreamingBody::default();
+    body.start_events();
+    let total_events = EventStream::MAX_EVENTS + 1;
+    for i in 0..total_events {
+        body.push(format!("data: {i}\ndata: second line\n\n").as_bytes());
+    }
+
+    let data = body.lock();
+    let events = data.events.as_ref().unwrap();
+    assert!(events.events.len() <= EventStream::MAX_EVENTS);
+    assert_eq!(events.received, total_events);
+    assert_eq!(events.lines, events.events.len() * 2);
+    // The newest event is kept
+    assert_eq!(
+        events.events.last().unwrap().event.data,
+        format!("{}\nsecond line", total_events - 1)
+    );
+}
+
 #[rstest]
 fn test_load(mut store: RequestStore) {
     // Generally we would expect this to be in the DB, but in this case omit
@@ -301,6 +465,32 @@ fn test_load_latest_exchange(mut store: RequestStore) {
     );
 }
 
+#[rstest]
+fn test_load_previous_exchange(mut store: RequestStore) {
+    let profile_id = ProfileId::factory(());
+    let recipe_id = RecipeId::factory(());
+
+    let first = create_exchange(&store, Some(&profile_id), Some(&recipe_id));
+    // Confounders with a different profile/recipe
+    create_exchange(&store, Some(&profile_id), None);
+    create_exchange(&store, None, Some(&recipe_id));
+    let second = create_exchange(&store, Some(&profile_id), Some(&recipe_id));
+    let third = create_exchange(&store, Some(&profile_id), Some(&recipe_id));
+
+    assert_eq!(
+        store.load_previous_exchange(third.id).unwrap(),
+        Some(&second)
+    );
+    assert_eq!(
+        store.load_previous_exchange(second.id).unwrap(),
+        Some(&first)
+    );
+    // Nothing before the first exchange
+    assert_matches!(store.load_previous_exchange(first.id), Ok(None));
+    // Unknown request
+    assert_matches!(store.load_previous_exchange(RequestId::new()), Ok(None));
+}
+
 #[rstest]
 #[tokio::test]
 async fn test_load_summaries(mut store: RequestStore) {
@@ -360,6 +550,8 @@ async fn test_load_summaries(mut store: RequestStore) {
             request: request.into(),
             start_time: Utc::now(),
             cancel_token: None,
+            body: StreamingBody::default(),
+            retry: RetryProgress::default(),
         },
     );
 
@@ -370,7 +562,7 @@ async fn test_load_summaries(mut store: RequestStore) {
         request_error_id,
         RequestState::RequestError {
             error: RequestError {
-                error,
+                error: error.into(),
                 request: request.into(),
                 start_time: Utc::now(),
                 end_time: Utc::now(),
@@ -397,7 +589,7 @@ async fn test_load_summaries(mut store: RequestStore) {
     // have to re-create each piece of data (they don't impl Clone), so
     // instead do a pattern match, then check the IDs
     let loaded = store
-        .load_summaries(Some(&profile_id), &recipe_id)
+        .load_summaries(Some(&profile_id), &recipe_id, None)
         .unwrap()
         .collect_vec();
     assert_matches!(
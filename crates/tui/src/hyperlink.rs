@@ -0,0 +1,148 @@
+//! Clickable hyperlinks, using the OSC 8 escape sequence. See
+//! <https://gist.github.com/egmontkob/eb114294efbcd5adb1944c9f3cb5feda>.
+//!
+//! Ratatui doesn't support hyperlinks because it can't calculate the width of
+//! a cell containing an escape sequence. Instead, we find URLs in the finished
+//! buffer and draw those cells a second time directly to the backend, wrapped
+//! in the escape sequence. The backend draws each cell at an explicit
+//! position, so the mismeasured width doesn't matter there.
+
+use ratatui::buffer::{Buffer, Cell};
+
+/// Find all URLs in a drawn buffer, and get a copy of their cells with each
+/// symbol wrapped in a hyperlink. These should be drawn over the buffer's
+/// contents. URLs that wrap across lines are only linked up to the line break.
+pub fn hyperlink_cells(buffer: &Buffer) -> Vec<(u16, u16, Cell)> {
+    let area = buffer.area;
+    let mut cells = Vec::new();
+    for y in area.top()..area.bottom() {
+        // One char per cell, so char indexes are column offsets. Anything that
+        // isn't a single char (e.g. wide characters) can't be part of a URL
+        let line: Vec<char> = (area.left()..area.right())
+            .map(|x| {
+                let mut chars = buffer[(x, y)].symbol().chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => ' ',
+                }
+            })
+            .collect();
+
+        for (start, end) in find_urls(&line) {
+            let url: String = line[start..end].iter().collect();
+            for offset in start..end {
+                let x = area.left() + offset as u16;
+                let mut cell = buffer[(x, y)].clone();
+                let symbol =
+                    format!("\x1b]8;;{url}\x07{}\x1b]8;;\x07", cell.symbol());
+                cell.set_symbol(&symbol);
+                cells.push((x, y, cell));
+            }
+        }
+    }
+    cells
+}
+
+/// Get the `[start, end)` index range of each URL in a line
+fn find_urls(line: &[char]) -> Vec<(usize, usize)> {
+    const SCHEMES: [&str; 2] = ["http://", "https://"];
+
+    let mut urls = Vec::new();
+    let mut start = 0;
+    while start < line.len() {
+        // URLs have to start at a word boundary
+        let at_boundary =
+            start == 0 || !line[start - 1].is_ascii_alphanumeric();
+        let scheme = SCHEMES.iter().find(|scheme| {
+            line[start..]
+                .iter()
+                .copied()
+                .take(scheme.len())
+                .eq(scheme.chars())
+        });
+        let Some(scheme) = scheme.filter(|_| at_boundary) else {
+            start += 1;
+            continue;
+        };
+
+        let mut end = start
+            + line[start..]
+                .iter()
+                .position(|c| !is_url_char(*c))
+                .unwrap_or(line.len() - start);
+        // Trailing punctuation is probably part of the surrounding text
+        while end > start + scheme.len()
+            && matches!(line[end - 1], '.' | ',' | ':' | ';' | '!' | '?' | ')')
+        {
+            end -= 1;
+        }
+        // Don't link a bare scheme
+        if end > start + scheme.len() {
+            urls.push((start, end));
+        }
+        start = end.max(start + 1);
+    }
+    urls
+}
+
+/// Can this character appear in a URL? This excludes characters that are
+/// usually delimiters around URLs, such as quotes and brackets
+fn is_url_char(c: char) -> bool {
+    c.is_ascii_graphic() && !matches!(c, '"' | '\'' | '<' | '>' | '`' | '|')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::{
+        layout::Rect,
+        style::{Color, Style},
+    };
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::none("no links here", &[])]
+    #[case::bare("http://example.com", &["http://example.com"])]
+    #[case::quoted(
+        r#"{"url": "https://example.com/a?b=c"}"#,
+        &["https://example.com/a?b=c"],
+    )]
+    #[case::punctuation(
+        "See https://example.com/docs. Or (http://localhost:3000)",
+        &["https://example.com/docs", "http://localhost:3000"],
+    )]
+    #[case::border("│https://example.com│", &["https://example.com"])]
+    #[case::not_boundary("xhttp://example.com", &[])]
+    #[case::scheme_only("http:// nothing", &[])]
+    fn test_find_urls(#[case] line: &str, #[case] expected: &[&str]) {
+        let chars: Vec<char> = line.chars().collect();
+        let actual: Vec<String> = find_urls(&chars)
+            .into_iter()
+            .map(|(start, end)| chars[start..end].iter().collect())
+            .collect();
+        assert_eq!(actual, expected);
+    }
+
+    /// Each cell of a URL is wrapped in a link, and keeps its style
+    #[test]
+    fn test_hyperlink_cells() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 12, 2));
+        let style = Style::default().fg(Color::Blue);
+        buffer.set_string(0, 0, "a http://x", style);
+        buffer.set_string(0, 1, "http://y.z", Style::default());
+
+        let cells = hyperlink_cells(&buffer);
+        let positions: Vec<(u16, u16)> =
+            cells.iter().map(|(x, y, _)| (*x, *y)).collect();
+        assert_eq!(
+            positions,
+            (2..10)
+                .map(|x| (x, 0))
+                .chain((0..10).map(|x| (x, 1)))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(cells[0].2.symbol(), "\x1b]8;;http://x\x07h\x1b]8;;\x07");
+        assert_eq!(cells[0].2.fg, Color::Blue);
+        assert_eq!(cells[8].2.symbol(), "\x1b]8;;http://y.z\x07h\x1b]8;;\x07");
+    }
+}
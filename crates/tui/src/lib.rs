@@ -6,48 +6,70 @@
 
 mod collection_state;
 mod http;
+mod hyperlink;
 mod input;
 mod message;
+mod screenshot;
+mod script;
 #[cfg(test)]
 mod test_util;
 mod util;
 mod view;
 
+pub use script::{InputRecorder, InputScript, ScriptStep};
+
 use crate::{
     collection_state::CollectionState,
-    http::{RequestConfig, RequestState, TuiHttpProvider},
+    http::{
+        RequestConfig, RequestState, StreamingBody, TuiHttpProvider,
+        diff_requests,
+    },
     input::{InputBindings, InputEvent},
     message::{
         Callback, HttpMessage, Message, MessageSender, RecipeCopyTarget,
+        ScreenDestination,
     },
-    util::ResultReported,
+    screenshot::ScreenFormat,
+    util::{ResultReported, TempFile},
     view::{PreviewPrompter, RequestDisposition, TuiPrompter},
 };
 use anyhow::{Context, anyhow, bail};
 use bytes::Bytes;
 use crossterm::event::{self, EventStream};
-use futures::{Stream, StreamExt, future, pin_mut};
+use futures::{Stream, StreamExt, pin_mut, stream};
+use indexmap::IndexMap;
 use ratatui::{
     Terminal,
     buffer::Buffer,
     layout::Position,
     prelude::{Backend, CrosstermBackend},
 };
-use slumber_config::{Action, Config};
+use slumber_config::{Action, Appearance, Config, EditorCommand, Theme};
 use slumber_core::{
-    collection::{Collection, CollectionFile, ProfileId},
+    collection::{Collection, CollectionFile, ProfileId, RecipeId},
     database::{CollectionDatabase, Database},
-    http::{Exchange, HttpEngine, RequestError, RequestId, RequestSeed},
-    render::{Prompter, TemplateContext},
+    http::{
+        BuildOptions, CaptureStore, Exchange, HttpEngine, RequestError,
+        RequestId, RequestRecord, RequestSeed, graphql,
+    },
+    ipc::OpenRequest,
+    render::{Auditor, CommandCache, Prompter, TemplateContext},
 };
 use slumber_template::{RenderedOutput, Template};
-use slumber_util::{ResultTraced, yaml::SourceLocation};
+use slumber_util::{
+    ResultTraced, STARTUP_TARGET, format_byte_size, i18n, paths, tr,
+    yaml::SourceLocation,
+};
 use std::{
+    fs,
     io::{self, Stdout},
+    mem,
     ops::Deref,
-    path::PathBuf,
+    path::{Path, PathBuf},
+    process::Command,
+    rc::{self, Rc},
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::{
     select,
@@ -55,7 +77,7 @@ use tokio::{
     task, time,
 };
 use tokio_util::sync::CancellationToken;
-use tracing::{error, info, trace};
+use tracing::{error, info, info_span, trace};
 
 /// Main controller struct for the TUI. The app uses a React-ish architecture
 /// for the view, with a wrapping controller (this struct)
@@ -68,8 +90,8 @@ pub struct Tui<B: Backend> {
     /// If run() is called multiple times (e.g. in a test), a new token will be
     /// generated for each call because they are single-use.
     cancel_token: CancellationToken,
-    /// App-wide configuration. This never gets reloaded; it'll be the same for
-    /// the entire session. The Arc allows cheap sharing throughout the app.
+    /// App-wide configuration. This is replaced whenever the config file
+    /// changes. The Arc allows cheap sharing throughout the app.
     config: Arc<Config>,
     /// Persistence database, for storing request state, UI state, etc.
     ///
@@ -78,6 +100,13 @@ pub struct Tui<B: Backend> {
     database: Database,
     /// Make request go brrr
     http_engine: HttpEngine,
+    /// Light/dark appearance of the terminal, used to select a theme variant.
+    /// `None` if unknown, or if the theme had no variants at startup and the
+    /// terminal wasn't queried
+    appearance: Option<Appearance>,
+    /// Input:action bindings, used to convert terminal input into messages.
+    /// Rebuilt when the config is reloaded
+    input_bindings: InputBindings,
     /// Receiver for the async message queue, which allows background tasks and
     /// the view to pass data and trigger side effects. Nobody else gets to
     /// touch this
@@ -89,6 +118,22 @@ pub struct Tui<B: Backend> {
     ///
     /// This gets replaced wholesale when switching collection files
     state: CollectionState,
+    /// Cached `command()` outputs. This is shared across all renders for the
+    /// session, including previews
+    command_cache: CommandCache,
+    /// Values captured from responses. This is shared across all renders for
+    /// the session
+    captures: CaptureStore,
+    /// Template context shared by all previews in the current refresh, so
+    /// they share one render group. Each profile field, variable, or upstream
+    /// response is evaluated once for the whole refresh instead of once per
+    /// preview. Once every preview using it has finished or been cancelled,
+    /// the next preview starts a new group. Commands are additionally limited
+    /// to one run at a time by [CommandCache].
+    preview_context: rc::Weak<TemplateContext>,
+    /// Temp files opened in a multiplexer pane via `split_command`. We can't
+    /// tell when the pane closes, so these are held until the TUI exits
+    detached_files: Vec<TempFile>,
     /// Output terminal. Parameterized for testing.
     terminal: Terminal<B>,
 }
@@ -96,16 +141,58 @@ pub struct Tui<B: Backend> {
 impl Tui<CrosstermBackend<Stdout>> {
     /// Start the TUI on a real terminal. Any errors that occur during startup
     /// will be panics, because they prevent TUI execution.
-    pub async fn start(collection_path: Option<PathBuf>) -> anyhow::Result<()> {
-        let app =
-            Self::new(CrosstermBackend::new(io::stdout()), collection_path)?;
+    ///
+    /// `appearance` selects the light or dark variant of the theme. If `None`
+    /// and the theme defines variants, it's detected from the terminal.
+    /// `session` is the named session to scope cookies and OAuth2 tokens to.
+    /// `script` is a path to an
+    /// [InputScript] to play alongside terminal input, and `record` is a path
+    /// to record terminal input to, as a script.
+    pub async fn start(
+        collection_path: Option<PathBuf>,
+        appearance: Option<Appearance>,
+        session: Option<String>,
+        script: Option<PathBuf>,
+        record: Option<PathBuf>,
+    ) -> anyhow::Result<()> {
+        // Detection has to happen before we start reading input. Querying
+        // the terminal costs a round trip, so skip it when there's nothing to
+        // choose between
+        let app = Self::with_appearance(
+            CrosstermBackend::new(io::stdout()),
+            collection_path,
+            session,
+            |theme| {
+                appearance.or_else(|| {
+                    theme.has_variants().then(util::detect_appearance)?
+                })
+            },
+        )?;
+        // Load the script and recorder before taking over the terminal, so
+        // errors are visible
+        let script_stream = match script {
+            Some(path) => {
+                app.play_script(InputScript::load(&path)?)?.left_stream()
+            }
+            None => stream::empty().right_stream(),
+        };
+        let mut recorder = record.map(InputRecorder::create).transpose()?;
         // Stream input from the terminal
-        let input_stream = EventStream::new().map(|event_result| {
-            let event = event_result.expect("Error reading terminal input");
-            // Convert from crossterm to the common terminput format. This
-            // enables support for multiple terminal backends
-            terminput_crossterm::to_terminput(event).unwrap()
-        });
+        let input_stream = EventStream::new()
+            .map(|event_result| {
+                let event = event_result.expect("Error reading terminal input");
+                // Convert from crossterm to the common terminput format. This
+                // enables support for multiple terminal backends
+                terminput_crossterm::to_terminput(event).unwrap()
+            })
+            .inspect(move |event| {
+                if let Some(recorder) = &mut recorder {
+                    recorder.record(event);
+                }
+            });
+        // Scripted input is interleaved with real input, so the user can still
+        // interact (or quit) while a script plays
+        let input_stream = stream::select(script_stream, input_stream);
 
         // The code to revert the terminal takeover is in `Tui::drop`, so we
         // shouldn't take over the terminal until right before creating the
@@ -137,17 +224,37 @@ where
     B: Backend,
     B::Error: 'static + Send + Sync,
 {
-    /// Rough **maximum** time for each iteration of the main loop
+    /// Time between redraws while something on screen is animating (e.g. the
+    /// elapsed time of an in-flight request). When nothing is animating, the
+    /// loop sleeps until there's input or a message/event to handle.
     const TICK_TIME: Duration = Duration::from_millis(250);
+    /// Delay before a template preview starts rendering. If the preview is
+    /// cancelled within this window, no work is done at all.
+    const PREVIEW_DEBOUNCE: Duration = Duration::from_millis(50);
+    /// Max number of commits to load for the collection history
+    const MAX_CHANGELOG_COMMITS: usize = 100;
 
     /// Create a new TUI
     ///
     /// This will *not* start the TUI process. It initializes all needed state,
     /// config, etc. but will not write to the terminal or read input yet. Call
-    /// [Self::run] to run the main loop.
+    /// [Self::run] to run the main loop. `appearance` selects the light or dark
+    /// variant of the theme, if the config defines them.
     pub fn new(
         backend: B,
         collection_path: Option<PathBuf>,
+        appearance: Option<Appearance>,
+    ) -> anyhow::Result<Self> {
+        Self::with_appearance(backend, collection_path, None, |_| appearance)
+    }
+
+    /// [Self::new], but the appearance is determined from the loaded theme.
+    /// This allows detection to be skipped when the theme has no variants.
+    fn with_appearance(
+        backend: B,
+        collection_path: Option<PathBuf>,
+        session: Option<String>,
+        get_appearance: impl FnOnce(&Theme) -> Option<Appearance>,
     ) -> anyhow::Result<Self> {
         // Create a message queue for handling async tasks
         let (messages_tx, messages_rx) = mpsc::unbounded_channel();
@@ -155,11 +262,13 @@ where
 
         // Load config file. Failure shouldn't be fatal since we can fall back
         // to default, just show an error to the user
-        let config: Arc<Config> = Config::load()
-            .reported(&messages_tx)
-            .unwrap_or_default()
-            .into();
+        let config = Config::load().reported(&messages_tx).unwrap_or_default();
+        let appearance = get_appearance(&config.tui.theme);
+        let config: Arc<Config> = resolve_theme(config, appearance).into();
+        i18n::set_locale(config.locale.as_deref());
         let http_engine = HttpEngine::new(&config.http);
+        let input_bindings =
+            InputBindings::new(config.tui.input_bindings.clone());
         let database = Database::load()?;
 
         // Initialize TUI state, which will try to load the collection. If it
@@ -170,6 +279,7 @@ where
             config.clone(),
             collection_file,
             database.clone(),
+            session,
             messages_tx.clone(),
         );
 
@@ -180,9 +290,15 @@ where
             config,
             database,
             http_engine,
+            appearance,
+            input_bindings,
             messages_rx,
             messages_tx,
             state,
+            command_cache: CommandCache::default(),
+            captures: CaptureStore::default(),
+            preview_context: rc::Weak::new(),
+            detached_files: Vec::new(),
             terminal,
         })
     }
@@ -200,6 +316,15 @@ where
         self.state.collection.as_deref().ok()
     }
 
+    /// Convert a script to a stream of input events, which can be passed to
+    /// [Self::run]. Fails if the script uses an action with no key binding
+    pub fn play_script(
+        &self,
+        script: InputScript,
+    ) -> anyhow::Result<impl Stream<Item = terminput::Event> + use<B>> {
+        script.into_stream(&self.input_bindings)
+    }
+
     /// Get a reference to the database handle
     pub fn database(&self) -> &CollectionDatabase {
         &self.state.database
@@ -217,32 +342,38 @@ where
         // Spawn background tasks
         self.listen_for_signals();
         self.watch_collection();
+        self.watch_config();
+        self.watch_open_requests();
+        self.prompt_trust();
 
-        let input_bindings =
-            InputBindings::new(self.config.tui.input_bindings.clone());
-        // Stream of terminal input events. Events that don't map to a message
-        // (cursor move, focus, etc.) should be filtered out entirely so
-        // they don't trigger any updates
-        let input_stream = input_stream.filter_map(move |event| {
-            future::ready(input_bindings.convert_event(event))
-        });
         pin_mut!(input_stream);
 
-        self.draw(false)?; // Initial draw
+        // Initial draw
+        info_span!(target: STARTUP_TARGET, "Initial draw")
+            .in_scope(|| self.draw(false))?;
 
-        // This loop is limited by the rate that messages come in, with a
-        // minimum rate enforced by a timeout.
+        // This loop is limited by the rate that messages and events come in.
+        // There's no minimum rate unless something is animating, so the loop
+        // is fully idle when nothing is happening.
         // The loop terminates when the cancel token is set
+        let mut last_progress = 0;
         loop {
             // ===== Message Phase =====
-            // Wait for one of 3 things to happen:
+            // Wait for one of these things to happen:
             // - Message appears in the queue
             // - Input event from the terminal
-            // - Timeout (to ensure we show state updates while a request is
-            //   ticking)
+            // - View event pushed from a background task (e.g. a template
+            //   preview finished rendering)
+            // - Timeout, **only** if a request is in flight (to ensure we show
+            //   state updates while a request is ticking)
+            // - Redraw deadline, **only** if something visible changes on its
+            //   own (e.g. a token expiry countdown)
             //
             // The goal is to only do work when there's something to do, to
             // minimize the idle CPU usage
+            let has_active_requests =
+                self.state.request_store.has_active_requests();
+            let redraw_at = self.state.view.redraw_at();
 
             let message = select! {
                 // The ordering and usage of `biased` is very important here:
@@ -262,20 +393,42 @@ where
                 },
                 event_option = input_stream.next() => {
                     if let Some(event) = event_option {
-                        Some(Message::Input(event))
+                        // Events that don't map to a message (cursor move,
+                        // focus, etc.) are dropped so they don't trigger any
+                        // updates. Bindings are looked up on each event
+                        // because they can change on config reload
+                        self.input_bindings
+                            .convert_event(event)
+                            .map(Message::Input)
                     } else {
                         // We ran out of input, just end the program
                         break;
                     }
                 },
-                () = time::sleep(Self::TICK_TIME) => None,
+                // Events pushed within the main loop are drained below, so
+                // this may resolve spuriously. That's fine because we won't
+                // draw unless something actually happened.
+                () = self.state.view.event_pushed() => None,
+                () = time::sleep(Self::TICK_TIME), if has_active_requests => {
+                    None
+                }
+                () = time::sleep_until(
+                    redraw_at.unwrap_or_else(Instant::now).into()
+                ), if redraw_at.is_some() => None,
                 () = self.cancel_token.cancelled() => break,
             };
 
             // We'll try to skip draws if nothing on the screen has changed, to
             // limit idle CPU usage. If a request is running we always need to
-            // update though, because the timer will be ticking.
-            let mut needs_draw = self.state.request_store.has_active_requests();
+            // update though, because the timer and spinner are ticking. With
+            // reduced motion, only redraw when a request has received data, so
+            // streamed bodies and download progress still show up.
+            let progress = self.state.request_store.active_progress();
+            let mut needs_draw = has_active_requests
+                && (!self.config.tui.reduced_motion
+                    || progress != last_progress);
+            last_progress = progress;
+            needs_draw |= redraw_at.is_some_and(|at| at <= Instant::now());
 
             if let Some(message) = message {
                 trace!(?message, "Handling message");
@@ -310,6 +463,12 @@ where
     fn handle_message(&mut self, message: Message) -> anyhow::Result<()> {
         match message {
             Message::ClearTerminal => self.terminal.clear()?,
+            Message::ClearCommandCache => {
+                let count = self.command_cache.clear();
+                self.state
+                    .view
+                    .notify(tr!("notify-command-cache-cleared", count = count));
+            }
 
             Message::CollectionEndReload(collection) => {
                 // Because we're just swapping out the collection value and
@@ -318,16 +477,23 @@ where
                 self.state.set_collection(collection);
             }
             Message::CollectionSelect(path) => {
-                // Collection file has changed, so we have to rebuild state
+                // Collection file has changed, so we have to rebuild state.
+                // Stay in the same session
                 let collection_file = CollectionFile::new(Some(path))?;
                 self.state = CollectionState::load(
                     self.config.clone(),
                     collection_file,
                     self.database.clone(),
+                    self.state.database.session().map(String::from),
                     self.messages_tx.clone(),
                 );
+                self.prompt_trust();
             }
             Message::CollectionStartReload => self.reload_collection(),
+            Message::CollectionTrust => self.state.trust()?,
+
+            Message::ConfigEndReload(config) => self.set_config(config),
+            Message::ConfigStartReload => self.reload_config(),
             Message::CollectionEdit { location } => {
                 self.edit_collection(location)?;
             }
@@ -335,25 +501,43 @@ where
             Message::CopyRecipe(target) => self.copy_recipe(target)?,
             Message::CopyText(text) => self.state.view.copy_text(text)?,
 
+            Message::Download => self.download()?,
+
             Message::Error { error } => self.state.view.error(error),
 
+            Message::ExportScreen {
+                format,
+                destination,
+            } => self.export_screen(format, destination)?,
+
             Message::FileEdit { file, on_complete } => {
-                let editor = self.config.editor()?;
-                util::yield_terminal(
+                let editor = self.config.editor(file.path())?;
+                self.run_external(
+                    &editor,
                     editor.open(file.path()),
-                    &self.messages_tx,
+                    move || on_complete(file),
                 )?;
-                on_complete(file);
             }
             Message::FileView { file, mime } => {
                 let pager = self.config.tui.pager(mime.as_ref())?;
-                util::yield_terminal(
-                    pager.open(file.path()),
-                    &self.messages_tx,
-                )?;
-                // Dropping the file deletes it, so we can't do it until after
-                // the command is done
-                drop(file);
+                let command = pager.open(file.path());
+                if let Some(split) = self.config.tui.split(&command)? {
+                    // The multiplexer returns as soon as the pane is open, so
+                    // hang onto the file until we exit
+                    self.detached_files.push(file);
+                    let messages_tx = self.messages_tx.clone();
+                    self.spawn(async move {
+                        util::run_gui_command(split)
+                            .await
+                            .reported(&messages_tx);
+                    });
+                } else {
+                    // Dropping the file deletes it, so we can't do it until
+                    // after the command is done
+                    self.run_external(&pager, command, || {
+                        drop(file);
+                    })?;
+                }
             }
 
             Message::Http(message) => self.handle_http(message)?,
@@ -386,10 +570,15 @@ where
                 self.terminal.clear()?;
                 self.draw(false)?;
             }
+            Message::GraphQlSchema { on_complete } => {
+                self.load_graphql_schema(on_complete)?;
+            }
             Message::Input(event) => self.state.view.handle_input(event),
 
             Message::Notify(message) => self.state.view.notify(message),
+            Message::OpenRequest => self.open_request()?,
             Message::Question(question) => self.state.view.question(question),
+            Message::RequestDiff(request) => self.diff_request(request)?,
             Message::SaveResponseBody { request_id, data } => {
                 self.save_response_body(request_id, data).with_context(
                     || {
@@ -406,6 +595,7 @@ where
             Message::TemplatePreview {
                 template,
                 can_stream,
+                cancel_token,
                 on_complete,
             } => {
                 // Note: there's a potential bug here, if the selected profile
@@ -413,13 +603,27 @@ where
                 // extremely unlikely (potentially impossible), and this
                 // shortcut saves us a lot of plumbing so it's worth it
                 let profile_id = self.state.view.selected_profile_id().cloned();
+                let recipe_id = self.state.view.selected_recipe_id().cloned();
                 self.render_template_preview(
                     template,
                     profile_id,
+                    recipe_id,
                     can_stream,
+                    cancel_token,
                     on_complete,
                 );
             }
+            Message::QueueOffline { config, url } => {
+                self.state.view.queue_offline(config, url);
+            }
+            Message::ViewCaptures => {
+                self.state.view.captures(self.captures.entries());
+            }
+            Message::ViewChangelog => self.load_changelog(),
+            Message::ViewChangelogLoaded(commits) => {
+                self.state.view.changelog(commits);
+            }
+            Message::ViewLog => self.view_log()?,
         }
         Ok(())
     }
@@ -429,21 +633,45 @@ where
         let disposition = match message {
             HttpMessage::Triggered {
                 request_id,
+                parent_id,
                 profile_id,
                 recipe_id,
             } => {
                 self.state
                     .request_store
-                    .start(request_id, profile_id, recipe_id, None);
+                    .trigger(request_id, parent_id, profile_id, recipe_id);
                 // Request is triggered in the background. Switching to it could
                 // be jarring
                 RequestDisposition::Change(request_id)
             }
             HttpMessage::Begin => {
-                let id = self.send_request()?;
+                let id = self.send_request(self.state.request_config()?, None);
                 // New requests should be shown immediately
                 RequestDisposition::Select(id)
             }
+            HttpMessage::Resend(config) => {
+                let id = self.send_request(config, None);
+                RequestDisposition::Select(id)
+            }
+            HttpMessage::Download { config, path } => {
+                let id = self.send_request(config, Some(path));
+                RequestDisposition::Select(id)
+            }
+            HttpMessage::FollowLink(link) => {
+                // A followed link only inherits authentication; the recipe's
+                // other overrides don't apply to it
+                let config = self.state.request_config()?;
+                let config = RequestConfig {
+                    options: BuildOptions {
+                        authentication: config.options.authentication,
+                        link: Some(link),
+                        ..BuildOptions::default()
+                    },
+                    ..config
+                };
+                let id = self.send_request(config, None);
+                RequestDisposition::Select(id)
+            }
             HttpMessage::Prompt { request_id, prompt } => {
                 let id =
                     self.state.request_store.prompt(request_id, prompt).id();
@@ -466,8 +694,13 @@ where
                 let id = self.state.request_store.build_error(error).id();
                 RequestDisposition::Change(id)
             }
-            HttpMessage::Loading(request) => {
-                let id = self.state.request_store.loading(request).id();
+            HttpMessage::Loading {
+                request,
+                body,
+                retry,
+            } => {
+                let id =
+                    self.state.request_store.loading(request, body, retry).id();
                 RequestDisposition::Change(id)
             }
             HttpMessage::Complete(result) => {
@@ -524,6 +757,64 @@ where
         }));
     }
 
+    /// Spawn a task to watch the config file for changes
+    fn watch_config(&self) {
+        let messages_tx = self.messages_tx.clone();
+        self.spawn(util::watch_file(Config::path(), move || {
+            messages_tx.send(Message::ConfigStartReload);
+        }));
+    }
+
+    /// Spawn a task to watch for requests from `slumber open`
+    fn watch_open_requests(&self) {
+        let messages_tx = self.messages_tx.clone();
+        self.spawn(util::watch_file(OpenRequest::path(), move || {
+            messages_tx.send(Message::OpenRequest);
+        }));
+    }
+
+    /// Apply the latest request from `slumber open`, if it's for the current
+    /// collection
+    fn open_request(&mut self) -> anyhow::Result<()> {
+        let request = OpenRequest::receive()?;
+        if request.collection_id == self.state.database.collection_id() {
+            self.state
+                .view
+                .open(request.recipe_id.as_ref(), request.profile_id.as_ref());
+        }
+        Ok(())
+    }
+
+    /// If the collection is sandboxed, ask the user if they want to trust it
+    fn prompt_trust(&self) {
+        if !self.state.sandboxed {
+            return;
+        }
+        let messages_tx = self.messages_tx.clone();
+        let file = &self.state.collection_file;
+        let message = if file.is_trust_outdated(&self.state.database) {
+            format!(
+                "`{file}` has changed since you trusted it, so `command()` \
+                and `file()` are disabled. Trust the new version?"
+            )
+        } else if file.remote().is_some() {
+            format!(
+                "`{file}` is a remote collection, so `command()` and `file()` \
+                are disabled. Trust this version?"
+            )
+        } else {
+            format!(
+                "`{file}` is not in a trusted directory, so `command()` and \
+                `file()` are disabled. Trust this collection?"
+            )
+        };
+        self.spawn(async move {
+            if util::confirm(&messages_tx, message).await {
+                messages_tx.send(Message::CollectionTrust);
+            }
+        });
+    }
+
     /// Spawn a background task to load+parse the collection file
     ///
     /// YAML parsing is CPU-bound so do it in a blocking task. In all likelihood
@@ -540,9 +831,10 @@ where
         // loop, and we can't send the message from the blocking thread because
         // messages are !Send
         task::spawn_local(async move {
-            let result = task::spawn_blocking(move || collection_file.load())
-                .await
-                .context("Collection loading panicked");
+            let result =
+                task::spawn_blocking(move || collection_file.load_deferred())
+                    .await
+                    .context("Collection loading panicked");
             let message = match result {
                 Ok(Ok(collection)) => Message::CollectionEndReload(collection),
                 // Load error
@@ -556,18 +848,118 @@ where
         });
     }
 
+    /// Spawn a background task to load the git history of the collection file.
+    /// This follows the same pattern as [Self::reload_collection], because
+    /// git can be slow on large repositories.
+    fn load_changelog(&self) {
+        let Ok(collection) = &self.state.collection else {
+            return;
+        };
+        let messages_tx = self.messages_tx.clone();
+        let collection_file = self.state.collection_file.clone();
+        let collection = Arc::clone(collection);
+        task::spawn_local(async move {
+            let result = task::spawn_blocking(move || {
+                collection_file
+                    .changelog(&collection, Self::MAX_CHANGELOG_COMMITS)
+            })
+            .await
+            .context("Loading collection history panicked");
+            let message = match result {
+                Ok(Ok(commits)) => Message::ViewChangelogLoaded(commits),
+                Ok(Err(error)) => Message::Error {
+                    error: anyhow::Error::from(error)
+                        .context("Error loading collection history"),
+                },
+                Err(error) => Message::Error { error },
+            };
+            messages_tx.send(message);
+        });
+    }
+
+    /// Spawn a background task to load+parse the config file. This follows the
+    /// same pattern as [Self::reload_collection]. If the file is invalid, the
+    /// error is shown and the previous config stays in use.
+    fn reload_config(&self) {
+        let messages_tx = self.messages_tx.clone();
+        // Resolve the path here, because the blocking task runs on another
+        // thread
+        let path = Config::path();
+        let appearance = self.appearance;
+        task::spawn_local(async move {
+            let result = task::spawn_blocking(move || {
+                Config::reload(&path)
+                    .map(|config| resolve_theme(config, appearance))
+            })
+            .await
+            .context("Config loading panicked");
+            let message = match result {
+                Ok(Ok(config)) => Message::ConfigEndReload(config),
+                // Load error
+                Ok(Err(error)) => Message::Error {
+                    error: anyhow::Error::from(error)
+                        .context("Error reloading configuration"),
+                },
+                // Join error - panic in the thread
+                Err(error) => Message::Error { error },
+            };
+            messages_tx.send(message);
+        });
+    }
+
+    /// Replace the config with a reloaded version. Everything derived from the
+    /// config (styles, input bindings, HTTP engine) is rebuilt. Requests that
+    /// are already in flight continue with the old config.
+    fn set_config(&mut self, config: Config) {
+        let config = Arc::new(config);
+        self.http_engine = HttpEngine::new(&config.http);
+        self.input_bindings =
+            InputBindings::new(config.tui.input_bindings.clone());
+        self.config = Arc::clone(&config);
+        self.state.set_config(config);
+    }
+
     /// Open the collection file in the user's editor
     fn edit_collection(
         &self,
         location: Option<SourceLocation>,
     ) -> anyhow::Result<()> {
-        let editor = self.config.editor()?;
-        let command = if let Some(location) = location {
-            editor.open_at(location.source, location.line, location.column)
+        let path = location
+            .as_ref()
+            .map_or(self.state.collection_file.path(), |location| {
+                Path::new(&location.source)
+            });
+        let editor = self.config.editor(path)?;
+        let command = if let Some(location) = &location {
+            editor.open_at(&location.source, location.line, location.column)
         } else {
-            editor.open(self.state.collection_file.path())
+            editor.open(path)
         };
-        util::yield_terminal(command, &self.messages_tx)
+        // The file watcher will pick up any changes
+        self.run_external(&editor, command, || {})
+    }
+
+    /// Run an external editor/pager command. Terminal programs take over the
+    /// terminal and block until they exit. GUI programs run in the background,
+    /// so the TUI stays usable while they're open. Either way, `on_complete`
+    /// is called after the program exits.
+    fn run_external(
+        &self,
+        editor: &EditorCommand,
+        command: Command,
+        on_complete: impl 'static + FnOnce(),
+    ) -> anyhow::Result<()> {
+        if editor.is_gui() {
+            let messages_tx = self.messages_tx.clone();
+            self.spawn(async move {
+                util::run_gui_command(command).await.reported(&messages_tx);
+                on_complete();
+            });
+        } else {
+            util::yield_terminal(command, &self.messages_tx)?;
+            on_complete();
+        }
+        Ok(())
     }
 
     /// Spawn a task on the main thread
@@ -599,25 +991,48 @@ where
             let mut buffer = Buffer::empty((Position::default(), size).into());
             self.state.draw(&mut buffer);
         } else {
-            self.terminal
+            let frame = self
+                .terminal
                 .draw(|frame| self.state.draw(frame.buffer_mut()))?;
+            if self.config.tui.hyperlinks.enabled() {
+                // Ratatui only writes cells that changed, which drops the links
+                // from those cells. Rewrite them all to be safe
+                let cells = hyperlink::hyperlink_cells(frame.buffer);
+                if !cells.is_empty() {
+                    let backend = self.terminal.backend_mut();
+                    backend.draw(
+                        cells.iter().map(|(x, y, cell)| (*x, *y, cell)),
+                    )?;
+                    backend.flush()?;
+                }
+            }
         }
         Ok(())
     }
 
-    /// Launch an HTTP request in a separate task
-    fn send_request(&mut self) -> anyhow::Result<RequestId> {
+    /// Launch an HTTP request in a separate task. If `download` is given, the
+    /// response body is written to that file instead of being held in memory.
+    fn send_request(
+        &mut self,
+        config: RequestConfig,
+        download: Option<PathBuf>,
+    ) -> RequestId {
+        // If the network is down, the request can be queued to try again.
+        // Downloads aren't queued, because the user is waiting on the file
+        let queue_config = (self.config.tui.offline_queue
+            && download.is_none())
+        .then(|| config.clone());
         let RequestConfig {
             profile_id,
             recipe_id,
             options,
-        } = self.state.request_config()?;
+        } = config;
         // Launch the request in a separate task so it doesn't block.
         // These clones are all cheap.
 
         let seed = RequestSeed::new(recipe_id.clone(), options);
         let request_id = seed.id;
-        let template_context =
+        let mut template_context =
             self.template_context(profile_id.clone(), Some(request_id));
         let http_engine = self.http_engine.clone();
         let messages_tx = self.messages_tx.clone();
@@ -626,23 +1041,48 @@ where
         // requests
         let cancel_token = CancellationToken::new();
         let future = async move {
-            // Build the request
-            let result = http_engine.build(seed, &template_context).await;
-            let ticket = match result {
-                Ok(ticket) => ticket,
-                Err(error) => {
-                    // Report the error, but don't actually return anything
-                    messages_tx.send(HttpMessage::BuildError(error.into()));
-                    return;
-                }
-            };
-
-            // Report liftoff
-            messages_tx.send(HttpMessage::Loading(Arc::clone(ticket.record())));
-
-            // Send the request and report the result to the main thread
-            let result = ticket.send().await.map_err(Arc::new);
-            messages_tx.send(HttpMessage::Complete(result));
+            // Keep a copy of the seed in case we need to retry. The retry
+            // keeps the same ID, so it replaces the rejected request in the UI
+            let retry_seed = seed.clone();
+            let mut result = send(
+                &http_engine,
+                seed,
+                &template_context,
+                download.as_deref(),
+                &messages_tx,
+            )
+            .await;
+            if let Some(Ok(exchange)) = &result
+                && exchange.should_reauthenticate(&template_context.collection)
+            {
+                template_context.reauthenticate();
+                result = send(
+                    &http_engine,
+                    retry_seed,
+                    &template_context,
+                    download.as_deref(),
+                    &messages_tx,
+                )
+                .await;
+            }
+            if let Some(Ok(exchange)) = &result {
+                let _ = template_context
+                    .captures
+                    .capture(exchange, &template_context.collection)
+                    .reported(&messages_tx);
+            }
+            if let Some(Err(error)) = &result
+                && let Some(config) = queue_config
+                && error.is_offline()
+            {
+                messages_tx.send(Message::QueueOffline {
+                    config,
+                    url: error.request.url.clone(),
+                });
+            }
+            if let Some(result) = result {
+                messages_tx.send(HttpMessage::Complete(result));
+            }
         };
         self.messages_tx
             .spawn(util::cancellable(&cancel_token, future));
@@ -656,7 +1096,7 @@ where
             Some(cancel_token),
         );
 
-        Ok(request_id)
+        request_id
     }
 
     /// Process the result of an HTTP request
@@ -774,6 +1214,130 @@ where
         Ok(())
     }
 
+    /// Open a copy of the log file in the pager. The log is still being written
+    /// to, so the pager gets a snapshot rather than the file itself.
+    fn view_log(&self) -> anyhow::Result<()> {
+        let path = paths::log_file();
+        let contents = fs::read(&path).with_context(|| {
+            format!("Error reading log file `{}`", path.display())
+        })?;
+        let mime = Some(mime::TEXT_PLAIN);
+        let file = TempFile::new(&contents, mime.as_ref())?;
+        self.messages_tx.send(Message::FileView { file, mime });
+        Ok(())
+    }
+
+    /// Build a fresh request from a recorded request's recipe, and open a diff
+    /// of the two in the pager. The new request is built but never sent.
+    fn diff_request(&self, old: Arc<RequestRecord>) -> anyhow::Result<()> {
+        let RequestConfig {
+            profile_id,
+            recipe_id,
+            options,
+        } = self.state.request_config()?;
+        // Overrides only apply to the recipe they were made on
+        let options = if recipe_id == old.recipe_id {
+            options
+        } else {
+            BuildOptions::default()
+        };
+        let seed = RequestSeed::new(old.recipe_id.clone(), options);
+        // Use a real request ID so any prompts are grouped under it
+        let context = self.template_context(profile_id, Some(seed.id));
+        let http_engine = self.http_engine.clone();
+        let messages_tx = self.messages_tx.clone();
+        self.messages_tx.spawn_result(async move {
+            let ticket = http_engine.build(seed, &context).await?;
+            let Some(diff) = diff_requests(&old, ticket.record()) else {
+                messages_tx
+                    .send(Message::Notify(tr!("notify-request-unchanged")));
+                return Ok(());
+            };
+            let mime = "text/x-diff".parse().ok();
+            let file = TempFile::new(diff.as_bytes(), mime.as_ref())?;
+            messages_tx.send(Message::FileView { file, mime });
+            Ok(())
+        });
+        Ok(())
+    }
+
+    /// Send the selected GraphQL recipe with the introspection query, and pass
+    /// the server's schema to the callback
+    fn load_graphql_schema(
+        &self,
+        on_complete: Callback<Arc<str>>,
+    ) -> anyhow::Result<()> {
+        let RequestConfig {
+            profile_id,
+            recipe_id,
+            options,
+        } = self.state.request_config()?;
+        // Keep the user's overrides, so the schema is loaded with the same
+        // URL and authentication they would send the query with
+        let options = BuildOptions {
+            graphql_introspection: true,
+            ..options
+        };
+        let seed = RequestSeed::new(recipe_id, options);
+        let context = self.template_context(profile_id, Some(seed.id));
+        let http_engine = self.http_engine.clone();
+        let messages_tx = self.messages_tx.clone();
+        self.messages_tx.spawn_result(async move {
+            let exchange =
+                http_engine.build(seed, &context).await?.send().await?;
+            let schema = graphql::introspection_schema(&exchange.response)?;
+            on_complete(schema.to_string().into());
+            messages_tx
+                .send(Message::Notify(tr!("notify-graphql-schema-loaded")));
+            Ok(())
+        });
+        Ok(())
+    }
+
+    /// Prompt the user for a file path, then send the current recipe with its
+    /// response body streamed into that file
+    fn download(&self) -> anyhow::Result<()> {
+        // Grab the config now, in case the selection changes while the prompt
+        // is open
+        let config = self.state.request_config()?;
+        let messages_tx = self.messages_tx.clone();
+        self.messages_tx.spawn_result(async move {
+            if let Some(path) = util::prompt_save_path(&messages_tx, None).await
+            {
+                messages_tx.send(HttpMessage::Download { config, path });
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    /// Export the screen to the clipboard or a file. The view is drawn to a
+    /// fresh buffer rather than read from the terminal, so the menu that
+    /// triggered the export is already gone.
+    fn export_screen(
+        &mut self,
+        format: ScreenFormat,
+        destination: ScreenDestination,
+    ) -> anyhow::Result<()> {
+        let size = self.terminal.size()?;
+        let mut buffer = Buffer::empty((Position::default(), size).into());
+        self.state.draw(&mut buffer);
+        let exported = format.export(&buffer);
+        match destination {
+            ScreenDestination::Clipboard => {
+                self.state.view.copy_text(exported)?;
+            }
+            ScreenDestination::File => {
+                self.messages_tx.spawn_result(util::save_file(
+                    self.messages_tx.clone(),
+                    Some(format!("slumber.{}", format.extension())),
+                    exported.into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     /// Save the body of a response to a file, prompting the user for a file
     /// path. If the body text is provided, that will be used. Useful when
     /// what's being saved differs from the actual response body (because of
@@ -811,18 +1375,55 @@ where
     /// lock. As this is a preview, the user will *not* be prompted for any
     /// input. A placeholder value will be used for any prompts.
     fn render_template_preview(
-        &self,
+        &mut self,
         template: Template,
         profile_id: Option<ProfileId>,
+        // Selected recipe, so fields named after its parameters resolve
+        recipe_id: Option<RecipeId>,
         can_stream: bool,
+        cancel_token: CancellationToken,
         on_complete: Callback<RenderedOutput>,
     ) {
-        let context = self.template_context(profile_id, None);
-        self.messages_tx.spawn(async move {
+        let context = self.preview_context(profile_id);
+        let future = async move {
+            // Debounce: if the user is flipping through recipes quickly, the
+            // preview will be dropped (and this task cancelled) before we get
+            // past this point
+            time::sleep(Self::PREVIEW_DEBOUNCE).await;
             // Render chunks, then write them to the output destination
-            let chunks = template.render(&context.streaming(can_stream)).await;
+            let recipe = recipe_id
+                .and_then(|id| context.collection.recipes.get_recipe(&id));
+            let mut render_context = context.streaming(can_stream);
+            if let Some(recipe) = recipe {
+                render_context = render_context.with_params(&recipe.params);
+            }
+            let chunks = template.render(&render_context).await;
             on_complete(chunks);
-        });
+        };
+        self.messages_tx
+            .spawn(util::cancellable(&cancel_token, future));
+    }
+
+    /// Get the template context for a preview. If previews from the current
+    /// refresh are still rendering with the same inputs, join their render
+    /// group. Otherwise start a new one.
+    fn preview_context(
+        &mut self,
+        profile_id: Option<ProfileId>,
+    ) -> Rc<TemplateContext> {
+        let context = self.template_context(profile_id, None);
+        if let Some(shared) = self.preview_context.upgrade()
+            && shared.selected_profile == context.selected_profile
+            && shared.enabled_toggles == context.enabled_toggles
+            && shared.overrides == context.overrides
+            && shared.sandboxed == context.sandboxed
+            && Arc::ptr_eq(&shared.collection, &context.collection)
+        {
+            return shared;
+        }
+        let context = Rc::new(context);
+        self.preview_context = Rc::downgrade(&context);
+        context
     }
 
     /// Expose app state to the templater. Most of the data has to be cloned out
@@ -852,18 +1453,94 @@ where
         } else {
             Box::new(PreviewPrompter)
         };
+        let auditor = Auditor::new(&self.config, self.state.database.clone());
 
         TemplateContext {
             selected_profile: profile_id,
+            enabled_toggles: self.state.view.enabled_toggles(),
             collection: Arc::clone(collection),
             http_provider: Box::new(http_provider),
             prompter,
             overrides: self.state.view.profile_overrides(),
+            // Previews fall back to parameter defaults; request builds prompt
+            // for anything not given a default
+            params: IndexMap::new(),
+            prompt_params: !is_preview,
+            prompt_missing: !is_preview,
             show_sensitive: !is_preview,
             root_dir: self.state.collection_file.parent().to_owned(),
+            sandboxed: self.state.sandboxed,
+            // Previews are logged too, but tagged so they can be told apart
+            // from requests the user sent
+            auditor: if is_preview {
+                auditor.for_preview()
+            } else {
+                auditor
+            },
             state: Default::default(),
+            command_cache: self.command_cache.clone(),
+            captures: self.captures.clone(),
+            render_mode: None,
+            oauth2_tokens: self.state.oauth2_tokens.clone(),
+            cookies: self.state.cookies.clone(),
+        }
+    }
+}
+
+/// Build and send a request, reporting progress to the main thread. Return
+/// `None` if the build failed, in which case the error has already been
+/// reported.
+async fn send(
+    http_engine: &HttpEngine,
+    seed: RequestSeed,
+    template_context: &TemplateContext,
+    download: Option<&Path>,
+    messages_tx: &MessageSender,
+) -> Option<Result<Exchange, Arc<RequestError>>> {
+    let ticket = match Box::pin(http::build_request(
+        http_engine,
+        seed,
+        template_context,
+    ))
+    .await
+    {
+        Ok(ticket) => ticket,
+        Err(error) => {
+            messages_tx.send(HttpMessage::BuildError(error.into()));
+            return None;
         }
+    };
+
+    // Report liftoff
+    let body = match download {
+        Some(path) => StreamingBody::download(path.to_owned()),
+        None => StreamingBody::default(),
+    };
+    messages_tx.send(HttpMessage::Loading {
+        request: Arc::clone(ticket.record()),
+        body: body.clone(),
+        retry: ticket.retry_progress().clone(),
+    });
+
+    // The body is streamed into the shared buffer as it arrives so the UI can
+    // show it before the response is complete
+    let result = body.receive(ticket).await;
+    if result.is_ok()
+        && let Some(download) = &body.lock().download
+    {
+        messages_tx.send(Message::Notify(tr!(
+            "notify-download-complete",
+            size = format_byte_size(download.progress.received as usize),
+            path = download.path.display()
+        )));
     }
+    Some(result.map_err(Arc::new))
+}
+
+/// Replace the config's theme with its variant for the terminal appearance
+fn resolve_theme(mut config: Config, appearance: Option<Appearance>) -> Config {
+    config.tui.theme = mem::take(&mut config.tui.theme).resolve(appearance);
+    config
 }
 
 /// Restore terminal state during a panic
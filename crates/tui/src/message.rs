@@ -2,19 +2,23 @@
 //! state updates.
 
 use crate::{
-    http::{PromptId, PromptReply},
+    http::{PromptId, PromptReply, RequestConfig, StreamingBody},
     input::InputEvent,
+    screenshot::ScreenFormat,
     util::{ResultReported, TempFile},
     view::Question,
 };
 use derive_more::From;
 use futures::{FutureExt, future::LocalBoxFuture};
 use mime::Mime;
+use reqwest::Url;
+use slumber_config::Config;
 use slumber_core::{
-    collection::{Collection, ProfileId, RecipeId},
+    collection::{Collection, CollectionCommit, ProfileId, RecipeId},
     database::ProfileFilter,
     http::{
         Exchange, RequestBuildError, RequestError, RequestId, RequestRecord,
+        RetryProgress,
     },
     render::{Prompt, ReplyChannel},
 };
@@ -22,6 +26,7 @@ use slumber_template::{RenderedOutput, Template};
 use slumber_util::{ResultTraced, yaml::SourceLocation};
 use std::{fmt::Debug, path::PathBuf, sync::Arc};
 use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
 use tracing::trace;
 
 /// Wrapper around a sender for async messages. Cheap to clone and pass around
@@ -68,6 +73,9 @@ impl MessageSender {
 pub enum Message {
     /// Clear the terminal. Use this before deferring to a subprocess
     ClearTerminal,
+    /// Clear all cached `command()` outputs, so they'll be re-run on the next
+    /// render
+    ClearCommandCache,
 
     /// Trigger collection reload
     CollectionStartReload,
@@ -84,15 +92,34 @@ pub enum Message {
     /// Switch to a different collection file. This will start an entirely new
     /// TUI session for the new collection
     CollectionSelect(PathBuf),
+    /// Trust the current collection, disabling the sandbox
+    CollectionTrust,
+
+    /// Trigger config reload
+    ConfigStartReload,
+    /// Store a reloaded config value in state
+    ConfigEndReload(Config),
 
     /// Render request URL from a recipe, then copy rendered URL
     CopyRecipe(RecipeCopyTarget),
     /// Copy some text to the clipboard
     CopyText(String),
 
+    /// Send a request for the current recipe/profile, streaming the response
+    /// body to a file instead of memory. This will trigger a process to prompt
+    /// the user for a file name
+    Download,
+
     /// An error occurred in some async process and should be shown to the user
     Error { error: anyhow::Error },
 
+    /// Export the screen as it's currently drawn, for documentation or bug
+    /// reports
+    ExportScreen {
+        format: ScreenFormat,
+        destination: ScreenDestination,
+    },
+
     /// Open a file in the user's external editor
     FileEdit {
         file: TempFile,
@@ -109,6 +136,14 @@ pub enum Message {
         mime: Option<Mime>,
     },
 
+    /// Send the selected GraphQL recipe with an introspection query instead of
+    /// its own, and pass the server's schema (as JSON) to the callback. The
+    /// request is *not* stored in history.
+    GraphQlSchema {
+        #[debug(skip)]
+        on_complete: Callback<Arc<str>>,
+    },
+
     /// A message that modifies the state of an HTTP request
     Http(HttpMessage),
     /// Get the most recent _completed_ request for a recipe+profile combo
@@ -124,6 +159,10 @@ pub enum Message {
     /// Send an informational notification to the user
     Notify(String),
 
+    /// An external process (e.g. `slumber open`) sent a request to select a
+    /// recipe/profile. Load it from the request file
+    OpenRequest,
+
     /// Ask the user for input to some [Question]. Use the included channel to
     /// return the value.
     ///
@@ -131,9 +170,18 @@ pub enum Message {
     /// [HttpMessage::Prompt].
     Question(Question),
 
+    /// A request failed because the network is unreachable. Hold onto it and
+    /// send it again once its host can be reached
+    QueueOffline { config: RequestConfig, url: Url },
+
     /// Exit the program
     Quit,
 
+    /// Re-build a recorded request's recipe with the current profile and
+    /// templates, then show a diff between the recorded request and the one
+    /// that would be sent now. The new request is *not* sent.
+    RequestDiff(Arc<RequestRecord>),
+
     /// Save a response body to a file. This will trigger a process to prompt
     /// the user for a file name
     SaveResponseBody {
@@ -163,9 +211,22 @@ pub enum Message {
         /// Does the consumer support streaming? If so, the output chunks may
         /// contain streams
         can_stream: bool,
+        /// Cancelled by the preview component when the output is no longer
+        /// needed, e.g. because the template changed or the component was
+        /// dropped
+        cancel_token: CancellationToken,
         #[debug(skip)]
         on_complete: Callback<RenderedOutput>,
     },
+
+    /// Show values captured from responses in a modal
+    ViewCaptures,
+    /// Load the git history of the collection file in the background
+    ViewChangelog,
+    /// Git history of the collection file has been loaded; show it in a modal
+    ViewChangelogLoaded(Vec<CollectionCommit>),
+    /// Open a copy of the log file in the pager
+    ViewLog,
 }
 
 impl From<HttpMessage> for Message {
@@ -181,9 +242,23 @@ impl From<HttpMessage> for Message {
 pub enum HttpMessage {
     /// Build and send an HTTP request based on the current recipe/profile state
     Begin,
+    /// Build and send an HTTP request from a previous configuration, e.g. to
+    /// repeat a recent request regardless of what's currently selected
+    Resend(RequestConfig),
+    /// Send a `GET` to a link from a response, using the authentication of the
+    /// current recipe/profile
+    FollowLink(Url),
+    /// Build and send an HTTP request, writing the response body to a file as
+    /// it's received rather than holding it in memory
+    Download {
+        config: RequestConfig,
+        path: PathBuf,
+    },
     /// An HTTP request was triggered by another request, and is now being built
     Triggered {
         request_id: RequestId,
+        /// The request that was being built when this was triggered, if known
+        parent_id: Option<RequestId>,
         profile_id: Option<ProfileId>,
         recipe_id: RecipeId,
     },
@@ -204,7 +279,13 @@ pub enum HttpMessage {
     /// The error is wrapped in `Arc` because it may be shared with other tasks.
     BuildError(Arc<RequestBuildError>),
     /// Request was sent and we're now waiting on a response
-    Loading(Arc<RequestRecord>),
+    Loading {
+        request: Arc<RequestRecord>,
+        /// Buffer that the response body will be streamed into
+        body: StreamingBody,
+        /// Attempts made under the recipe's retry policy
+        retry: RetryProgress,
+    },
     /// The HTTP request either succeeded or failed. We don't need to store the
     /// recipe ID here because it's in the inner container already. Combining
     /// these two cases saves a bit of boilerplate. The error must be wrapped
@@ -240,5 +321,13 @@ pub enum RecipeCopyTarget {
     Python,
 }
 
+/// Where to send an exported screen
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScreenDestination {
+    Clipboard,
+    /// Prompt the user for a file path, then write to that file
+    File,
+}
+
 /// A static callback included in a message
 pub type Callback<T> = Box<dyn 'static + FnOnce(T)>;
@@ -0,0 +1,465 @@
+//! Export the screen as text or an image, for documentation and bug reports.
+//!
+//! Exports are generated from a drawn buffer rather than read back from the
+//! terminal, so they don't depend on what the terminal supports. The terminal's
+//! own color scheme can't be known, so named colors and the default
+//! foreground/background use a fixed palette in SVG output.
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::{Color, Modifier},
+};
+use std::fmt::Write;
+use unicode_width::UnicodeWidthStr;
+
+/// Width of a single cell in SVG output, in pixels. This is roughly the
+/// advance width of a 14px monospace font
+const SVG_CELL_WIDTH: f32 = 8.4;
+/// Height of a single cell in SVG output, in pixels
+const SVG_CELL_HEIGHT: u16 = 17;
+const SVG_FONT_SIZE: u16 = 14;
+/// Foreground color to use for [Color::Reset] in SVG output
+const SVG_DEFAULT_FG: &str = "#d4d4d4";
+/// Background color to use for [Color::Reset] in SVG output
+const SVG_DEFAULT_BG: &str = "#1e1e1e";
+/// Colors for the 16 named colors, in ANSI order. These match the defaults of
+/// most dark terminal themes
+const PALETTE: [&str; 16] = [
+    "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc",
+    "#11a8cd", "#e5e5e5", "#666666", "#f14c4c", "#23d18b", "#f5f543",
+    "#3b8eea", "#d670d6", "#29b8db", "#ffffff",
+];
+
+/// Format to export the screen as
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScreenFormat {
+    /// Text only, with all styling removed
+    Text,
+    /// Text with ANSI escape codes for colors and modifiers. This looks like
+    /// the original screen when printed to a terminal, e.g. with `cat`
+    Ansi,
+    /// An SVG image
+    Svg,
+}
+
+impl ScreenFormat {
+    /// File extension for this format, to suggest a file name
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Text => "txt",
+            Self::Ansi => "ans",
+            Self::Svg => "svg",
+        }
+    }
+
+    /// Convert a drawn buffer to this format
+    pub fn export(self, buffer: &Buffer) -> String {
+        match self {
+            Self::Text => to_text(buffer),
+            Self::Ansi => to_ansi(buffer),
+            Self::Svg => to_svg(buffer),
+        }
+    }
+}
+
+/// Get the visible cells of each row in the buffer. Cells that are covered by
+/// the previous wide character (e.g. an emoji) are skipped, the same way
+/// ratatui skips them when drawing to the terminal.
+fn rows(buffer: &Buffer) -> impl Iterator<Item = Vec<(u16, &Cell)>> {
+    let area = buffer.area;
+    (area.top()..area.bottom()).map(move |y| {
+        let mut cells = Vec::new();
+        let mut to_skip = 0;
+        for x in area.left()..area.right() {
+            if to_skip > 0 {
+                to_skip -= 1;
+                continue;
+            }
+            let cell = &buffer[(x, y)];
+            to_skip = cell.symbol().width().saturating_sub(1);
+            cells.push((x - area.left(), cell));
+        }
+        cells
+    })
+}
+
+/// Export as plain text. Trailing whitespace is trimmed from each line
+fn to_text(buffer: &Buffer) -> String {
+    let mut output = String::new();
+    for row in rows(buffer) {
+        let line: String = row.iter().map(|(_, cell)| cell.symbol()).collect();
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// Export as text with ANSI escape codes. Each line ends with a reset, so
+/// lines can be printed independently
+fn to_ansi(buffer: &Buffer) -> String {
+    let mut output = String::new();
+    for row in rows(buffer) {
+        let mut previous: Option<&Cell> = None;
+        for (_, cell) in row {
+            let changed = previous.is_none_or(|previous| {
+                (previous.fg, previous.bg, previous.modifier)
+                    != (cell.fg, cell.bg, cell.modifier)
+            });
+            if changed {
+                // Start fresh rather than diffing modifiers, since some can't
+                // be turned off individually in all terminals
+                output.push_str("\x1b[0");
+                for code in sgr_codes(cell) {
+                    write!(output, ";{code}").unwrap();
+                }
+                output.push('m');
+            }
+            output.push_str(cell.symbol());
+            previous = Some(cell);
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+/// Get the SGR parameters to apply a cell's style
+fn sgr_codes(cell: &Cell) -> Vec<String> {
+    const MODIFIERS: [(Modifier, u8); 9] = [
+        (Modifier::BOLD, 1),
+        (Modifier::DIM, 2),
+        (Modifier::ITALIC, 3),
+        (Modifier::UNDERLINED, 4),
+        (Modifier::SLOW_BLINK, 5),
+        (Modifier::RAPID_BLINK, 6),
+        (Modifier::REVERSED, 7),
+        (Modifier::HIDDEN, 8),
+        (Modifier::CROSSED_OUT, 9),
+    ];
+
+    let mut codes: Vec<String> = MODIFIERS
+        .into_iter()
+        .filter(|(modifier, _)| cell.modifier.contains(*modifier))
+        .map(|(_, code)| code.to_string())
+        .collect();
+    codes.extend(ansi_color(cell.fg, false));
+    codes.extend(ansi_color(cell.bg, true));
+    codes
+}
+
+/// Get the SGR parameter for a foreground or background color. `None` for
+/// the default color, which is already set by the reset
+fn ansi_color(color: Color, background: bool) -> Option<String> {
+    let offset = if background { 10 } else { 0 };
+    let code = match color {
+        Color::Reset => return None,
+        Color::Indexed(index) => {
+            format!("{};5;{index}", 38 + offset)
+        }
+        Color::Rgb(r, g, b) => format!("{};2;{r};{g};{b}", 38 + offset),
+        _ => {
+            let index = named_index(color)?;
+            if index < 8 {
+                (30 + offset + index).to_string()
+            } else {
+                (90 + offset + index - 8).to_string()
+            }
+        }
+    };
+    Some(code)
+}
+
+/// Get the 0-15 palette index of a named color
+fn named_index(color: Color) -> Option<u8> {
+    let index = match color {
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Reset | Color::Indexed(_) | Color::Rgb(..) => return None,
+    };
+    Some(index)
+}
+
+/// Export as an SVG image. Each row is a `<text>` element, split into a
+/// `<tspan>` for each run of cells with the same style. Each run is positioned
+/// explicitly, so fonts with slightly different widths don't drift.
+fn to_svg(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let width = f32::from(area.width) * SVG_CELL_WIDTH;
+    let height = area.height * SVG_CELL_HEIGHT;
+
+    let mut output = String::new();
+    writeln!(
+        output,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    )
+    .unwrap();
+    writeln!(
+        output,
+        r#"<rect width="100%" height="100%" fill="{SVG_DEFAULT_BG}"/>"#
+    )
+    .unwrap();
+    writeln!(
+        output,
+        r#"<g font-family="monospace" font-size="{SVG_FONT_SIZE}" xml:space="preserve">"#
+    )
+    .unwrap();
+
+    for (y, row) in rows(buffer).enumerate() {
+        let top = y as u16 * SVG_CELL_HEIGHT;
+        let runs = svg_runs(&row);
+
+        // Backgrounds go first so text is drawn on top of them
+        for run in &runs {
+            if let Some(bg) = &run.bg {
+                writeln!(
+                    output,
+                    r#"<rect x="{x}" y="{top}" width="{width}" height="{SVG_CELL_HEIGHT}" fill="{bg}"/>"#,
+                    x = f32::from(run.x) * SVG_CELL_WIDTH,
+                    width = f32::from(run.width) * SVG_CELL_WIDTH,
+                )
+                .unwrap();
+            }
+        }
+
+        // Baseline sits a bit above the bottom of the cell, for descenders
+        let baseline = top + SVG_CELL_HEIGHT - 4;
+        write!(output, r#"<text y="{baseline}">"#).unwrap();
+        for run in runs {
+            if run.text.trim().is_empty() {
+                continue;
+            }
+            write!(
+                output,
+                r#"<tspan x="{x}" fill="{fg}"{attributes}>{text}</tspan>"#,
+                x = f32::from(run.x) * SVG_CELL_WIDTH,
+                fg = run.fg,
+                attributes = run.attributes,
+                text = escape_xml(&run.text),
+            )
+            .unwrap();
+        }
+        output.push_str("</text>\n");
+    }
+
+    output.push_str("</g>\n</svg>\n");
+    output
+}
+
+/// A sequence of adjacent cells with the same style, in SVG output
+struct SvgRun {
+    /// Column of the first cell
+    x: u16,
+    /// Number of columns covered
+    width: u16,
+    text: String,
+    fg: String,
+    /// `None` for the default background, which is already drawn
+    bg: Option<String>,
+    /// Additional `<tspan>` attributes for modifiers, with a leading space
+    attributes: String,
+}
+
+/// Group a row of cells into runs of the same style. A wide character ends
+/// its run, so the next run is positioned by its column instead of relying on
+/// the font's width for the wide character
+fn svg_runs(row: &[(u16, &Cell)]) -> Vec<SvgRun> {
+    let mut runs: Vec<SvgRun> = Vec::new();
+    let mut previous: Option<&Cell> = None;
+    let mut previous_wide = false;
+    for (x, cell) in row {
+        let width = cell.symbol().width().max(1) as u16;
+        let same_style = previous.is_some_and(|previous| {
+            (previous.fg, previous.bg, previous.modifier)
+                == (cell.fg, cell.bg, cell.modifier)
+        });
+        match runs.last_mut() {
+            Some(run) if same_style && !previous_wide => {
+                run.text.push_str(cell.symbol());
+                run.width += width;
+            }
+            _ => runs.push(svg_run(*x, width, cell)),
+        }
+        previous = Some(cell);
+        previous_wide = width > 1;
+    }
+    runs
+}
+
+/// Start a new SVG run from its first cell
+fn svg_run(x: u16, width: u16, cell: &Cell) -> SvgRun {
+    let (mut fg, mut bg) = (cell.fg, cell.bg);
+    if cell.modifier.contains(Modifier::REVERSED) {
+        (fg, bg) = (bg, fg);
+    }
+    let fg = svg_color(fg).unwrap_or_else(|| SVG_DEFAULT_FG.to_owned());
+    // Reversing the default background gives a visible foreground
+    let bg = if cell.modifier.contains(Modifier::REVERSED) && bg == Color::Reset
+    {
+        Some(SVG_DEFAULT_FG.to_owned())
+    } else {
+        svg_color(bg)
+    };
+
+    let mut attributes = String::new();
+    if cell.modifier.contains(Modifier::BOLD) {
+        attributes.push_str(r#" font-weight="bold""#);
+    }
+    if cell.modifier.contains(Modifier::ITALIC) {
+        attributes.push_str(r#" font-style="italic""#);
+    }
+    if cell.modifier.contains(Modifier::DIM) {
+        attributes.push_str(r#" opacity="0.6""#);
+    }
+    match (
+        cell.modifier.contains(Modifier::UNDERLINED),
+        cell.modifier.contains(Modifier::CROSSED_OUT),
+    ) {
+        (true, true) => {
+            attributes.push_str(r#" text-decoration="underline line-through""#);
+        }
+        (true, false) => attributes.push_str(r#" text-decoration="underline""#),
+        (false, true) => {
+            attributes.push_str(r#" text-decoration="line-through""#);
+        }
+        (false, false) => {}
+    }
+
+    let text = if cell.modifier.contains(Modifier::HIDDEN) {
+        " ".repeat(width.into())
+    } else {
+        cell.symbol().to_owned()
+    };
+    SvgRun {
+        x,
+        width,
+        text,
+        fg,
+        bg,
+        attributes,
+    }
+}
+
+/// Get the hex code for a color. `None` for the default color
+fn svg_color(color: Color) -> Option<String> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some(format!("#{r:02x}{g:02x}{b:02x}")),
+        Color::Indexed(index) => Some(indexed_color(index)),
+        _ => named_index(color).map(|index| PALETTE[index as usize].to_owned()),
+    }
+}
+
+/// Get the hex code for a color from the 256-color palette
+fn indexed_color(index: u8) -> String {
+    match index {
+        0..16 => PALETTE[index as usize].to_owned(),
+        // 6x6x6 color cube
+        16..232 => {
+            let level =
+                |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+            let index = index - 16;
+            let (r, g, b) = (index / 36, (index / 6) % 6, index % 6);
+            format!("#{:02x}{:02x}{:02x}", level(r), level(g), level(b))
+        }
+        // Grayscale ramp
+        232.. => {
+            let level = 8 + (index - 232) * 10;
+            format!("#{level:02x}{level:02x}{level:02x}")
+        }
+    }
+}
+
+/// Escape text for use in an XML text node
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use ratatui::{layout::Rect, style::Style};
+    use rstest::rstest;
+
+    /// Build a small buffer with some styled text
+    fn buffer() -> Buffer {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 8, 2));
+        buffer.set_string(0, 0, "a<b", Style::default().fg(Color::Red));
+        buffer.set_string(
+            4,
+            0,
+            "c",
+            Style::default().bg(Color::Indexed(16)).bold(),
+        );
+        buffer.set_string(0, 1, "界x", Style::default());
+        buffer
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(ScreenFormat::Text.export(&buffer()), "a<b c\n界x\n");
+    }
+
+    #[test]
+    fn test_ansi() {
+        assert_eq!(
+            ScreenFormat::Ansi.export(&buffer()),
+            "\x1b[0;31ma<b\x1b[0m \x1b[0;1;48;5;16mc\x1b[0m   \x1b[0m\n\
+            \x1b[0m界x     \x1b[0m\n"
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = ScreenFormat::Svg.export(&buffer());
+        assert!(svg.starts_with(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="67.2" height="34""#
+        ));
+        // Styles are split into runs, with text escaped
+        assert!(
+            svg.contains(r##"<tspan x="0" fill="#cd3131">a&lt;b</tspan>"##)
+        );
+        assert!(svg.contains(
+            r##"<rect x="33.6" y="0" width="8.4" height="17" fill="#000000"/>"##
+        ));
+        assert!(svg.contains(
+            r##"<tspan x="33.6" fill="#d4d4d4" font-weight="bold">c</tspan>"##
+        ));
+        // Text after a wide character is positioned by its column
+        assert!(
+            svg.contains(r##"<tspan x="16.8" fill="#d4d4d4">x     </tspan>"##)
+        );
+    }
+
+    #[rstest]
+    #[case::black(16, "#000000")]
+    #[case::cube(17, "#00005f")]
+    #[case::white(231, "#ffffff")]
+    #[case::gray(232, "#080808")]
+    #[case::named(9, "#f14c4c")]
+    fn test_indexed_color(#[case] index: u8, #[case] expected: &str) {
+        assert_eq!(indexed_color(index), expected);
+    }
+}
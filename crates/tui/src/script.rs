@@ -0,0 +1,398 @@
+//! Scripted input, for reproducible demos, tests, and bug reports. A script is
+//! a YAML file listing key presses and pauses, which is fed into the TUI as if
+//! it were typed at the terminal. [InputRecorder] generates a script from real
+//! input.
+
+use crate::input::InputBindings;
+use anyhow::{Context, anyhow};
+use futures::{Stream, StreamExt, stream};
+use saphyr::YamlData;
+use slumber_config::{Action, InputBinding, KeyCombination};
+use slumber_util::{
+    ResultTracedAnyhow, deserialize_enum,
+    yaml::{
+        self, DeserializeYaml, Expected, Field, SourceMap, SourcedYaml,
+        StructDeserializer,
+    },
+};
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use terminput::{Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState};
+use tokio::time;
+
+/// A sequence of input steps to play into the TUI
+#[derive(Debug, Default, PartialEq)]
+pub struct InputScript {
+    /// Pause before each key press, in milliseconds. Makes demos readable
+    /// without a `wait` between every step
+    pub delay_ms: usize,
+    pub steps: Vec<ScriptStep>,
+}
+
+impl InputScript {
+    /// Load a script from a YAML file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        yaml::deserialize_file(path).with_context(|| {
+            format!("Error loading input script `{}`", path.display())
+        })
+    }
+
+    /// Convert the script to a stream of terminal events, with each event
+    /// yielded after its delay. `action` steps are mapped to the first key
+    /// bound to the action.
+    pub(crate) fn into_stream(
+        self,
+        bindings: &InputBindings,
+    ) -> anyhow::Result<impl Stream<Item = Event> + use<>> {
+        let delay = Duration::from_millis(self.delay_ms as u64);
+        let mut events: Vec<(Duration, Event)> = Vec::new();
+        // Waits accumulate until the next key press
+        let mut wait = Duration::ZERO;
+        let mut push = |combo: KeyCombination, wait: &mut Duration| {
+            events.push((delay + *wait, key_event(combo)));
+            *wait = Duration::ZERO;
+        };
+        for step in self.steps {
+            match step {
+                ScriptStep::Key { key } => push(key, &mut wait),
+                ScriptStep::Action { action } => {
+                    let combo = bindings
+                        .binding(action)
+                        .and_then(InputBinding::first)
+                        .ok_or_else(|| {
+                            anyhow!("Action `{action}` has no key binding")
+                        })?;
+                    push(combo, &mut wait);
+                }
+                ScriptStep::Text { text } => {
+                    for c in text.chars() {
+                        push(KeyCode::Char(c).into(), &mut wait);
+                    }
+                }
+                ScriptStep::Wait { ms } => {
+                    wait += Duration::from_millis(ms as u64);
+                }
+            }
+        }
+
+        Ok(stream::iter(events).then(|(delay, event)| async move {
+            time::sleep(delay).await;
+            event
+        }))
+    }
+}
+
+/// A single step in an [InputScript]
+#[derive(Debug, PartialEq)]
+pub enum ScriptStep {
+    /// Press a key combination, e.g. `ctrl p`
+    Key { key: KeyCombination },
+    /// Press the key bound to an action. Unlike `key`, this works with any
+    /// input bindings
+    Action { action: Action },
+    /// Type each character of a string
+    Text { text: String },
+    /// Pause before the next step
+    Wait { ms: usize },
+}
+
+impl DeserializeYaml for InputScript {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        let mut deserializer = StructDeserializer::new(yaml)?;
+        let script = Self {
+            delay_ms: deserializer
+                .get(Field::new("delay_ms").or(0), source_map)?,
+            steps: deserializer.get(Field::new("steps"), source_map)?,
+        };
+        deserializer.done()?;
+        Ok(script)
+    }
+}
+
+impl DeserializeYaml for ScriptStep {
+    fn expected() -> Expected {
+        Expected::Mapping
+    }
+
+    fn deserialize(
+        yaml: SourcedYaml,
+        source_map: &SourceMap,
+    ) -> yaml::Result<Self> {
+        deserialize_enum! {
+            yaml,
+            "key" => |yaml: SourcedYaml| {
+                let mut deserializer = StructDeserializer::new(yaml)?;
+                let step = Self::Key {
+                    key: deserializer.get(Field::new("key"), source_map)?,
+                };
+                deserializer.done()?;
+                Ok(step)
+            },
+            "action" => |yaml: SourcedYaml| {
+                let mut deserializer = StructDeserializer::new(yaml)?;
+                let step = Self::Action {
+                    action: deserializer.get(Field::new("action"), source_map)?,
+                };
+                deserializer.done()?;
+                Ok(step)
+            },
+            "text" => |yaml: SourcedYaml| {
+                let mut deserializer = StructDeserializer::new(yaml)?;
+                let step = Self::Text {
+                    text: deserializer.get(Field::new("text"), source_map)?,
+                };
+                deserializer.done()?;
+                Ok(step)
+            },
+            "wait" => |yaml: SourcedYaml| {
+                let mut deserializer = StructDeserializer::new(yaml)?;
+                let step = Self::Wait {
+                    ms: deserializer.get(Field::new("ms"), source_map)?,
+                };
+                deserializer.done()?;
+                Ok(step)
+            },
+        }
+    }
+}
+
+/// Write terminal input to a file as an [InputScript], so it can be played
+/// back later. Each step is appended as soon as it happens, so the script is
+/// complete even if the TUI crashes.
+#[derive(Debug)]
+pub struct InputRecorder {
+    path: PathBuf,
+    file: File,
+    /// Time of the previous recorded event, to calculate waits
+    last_event: Instant,
+}
+
+impl InputRecorder {
+    /// Pauses shorter than this aren't recorded. Keeps out the noise from
+    /// fast typing
+    const MIN_WAIT: Duration = Duration::from_millis(50);
+
+    /// Create the script file, overwriting it if it exists
+    pub fn create(path: PathBuf) -> anyhow::Result<Self> {
+        let mut file = File::create(&path).with_context(|| {
+            format!("Error creating input script `{}`", path.display())
+        })?;
+        writeln!(file, "steps:")?;
+        Ok(Self {
+            path,
+            file,
+            last_event: Instant::now(),
+        })
+    }
+
+    /// Append an input event to the script. Only key presses and pastes are
+    /// recorded; mouse input depends on the layout so it wouldn't replay
+    /// reliably
+    pub fn record(&mut self, event: &Event) {
+        let step = match event {
+            Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press | KeyEventKind::Repeat,
+                ..
+            }) => {
+                let combo = KeyCombination {
+                    code: *code,
+                    modifiers: *modifiers,
+                };
+                let Some(key) = combo.to_config_string() else {
+                    return;
+                };
+                format!("type: key\n    key: {}", quote(&key))
+            }
+            Event::Paste(text) => {
+                format!("type: text\n    text: {}", quote(text))
+            }
+            _ => return,
+        };
+
+        let now = Instant::now();
+        let wait = now - self.last_event;
+        self.last_event = now;
+        let wait_step = if wait >= Self::MIN_WAIT {
+            format!("  - type: wait\n    ms: {}\n", wait.as_millis())
+        } else {
+            String::new()
+        };
+        let content = format!("{wait_step}  - {step}\n");
+        let _ = self
+            .file
+            .write_all(content.as_bytes())
+            .with_context(|| {
+                format!(
+                    "Error writing to input script `{}`",
+                    self.path.display()
+                )
+            })
+            .traced();
+    }
+}
+
+/// Quote a string for YAML. JSON strings are valid YAML, and this handles all
+/// the escaping
+fn quote(s: &str) -> String {
+    serde_json::to_string(s).unwrap()
+}
+
+/// Build a key press event
+fn key_event(combo: KeyCombination) -> Event {
+    Event::Key(KeyEvent {
+        code: combo.code,
+        modifiers: combo.modifiers,
+        kind: KeyEventKind::Press,
+        state: KeyEventState::empty(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use slumber_util::{TempDir, temp_dir};
+    use std::fs;
+    use terminput::KeyModifiers;
+
+    /// Parse each kind of step
+    #[test]
+    fn test_parse() {
+        let yaml = "
+delay_ms: 100
+steps:
+  - type: key
+    key: ctrl p
+  - type: action
+    action: submit
+  - type: text
+    text: hi
+  - type: wait
+    ms: 500
+";
+        let script: InputScript = yaml::deserialize_str(yaml).unwrap();
+        assert_eq!(
+            script,
+            InputScript {
+                delay_ms: 100,
+                steps: vec![
+                    ScriptStep::Key {
+                        key: KeyCombination {
+                            code: KeyCode::Char('p'),
+                            modifiers: KeyModifiers::CTRL,
+                        },
+                    },
+                    ScriptStep::Action {
+                        action: Action::Submit
+                    },
+                    ScriptStep::Text { text: "hi".into() },
+                    ScriptStep::Wait { ms: 500 },
+                ],
+            }
+        );
+    }
+
+    /// Steps are converted to key events, with waits and the delay applied
+    /// before the next key press
+    #[tokio::test]
+    async fn test_into_stream() {
+        let script = InputScript {
+            delay_ms: 10,
+            steps: vec![
+                ScriptStep::Wait { ms: 20 },
+                ScriptStep::Action {
+                    action: Action::Submit,
+                },
+                ScriptStep::Text { text: "ab".into() },
+            ],
+        };
+        let bindings =
+            InputBindings::new(yaml::deserialize_str("submit: [tab]").unwrap());
+        let start = time::Instant::now();
+        let events = script
+            .into_stream(&bindings)
+            .unwrap()
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(
+            events,
+            [
+                key_event(KeyCode::Tab.into()),
+                key_event(KeyCode::Char('a').into()),
+                key_event(KeyCode::Char('b').into()),
+            ]
+        );
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    /// An action without a binding can't be played
+    #[test]
+    fn test_into_stream_unbound() {
+        let script = InputScript {
+            delay_ms: 0,
+            steps: vec![ScriptStep::Action {
+                action: Action::Submit,
+            }],
+        };
+        let bindings =
+            InputBindings::new(yaml::deserialize_str("submit: []").unwrap());
+        assert_eq!(
+            script.into_stream(&bindings).err().unwrap().to_string(),
+            "Action `Send Request/Submit` has no key binding"
+        );
+    }
+
+    /// Recorded input can be loaded back as a script
+    #[rstest::rstest]
+    fn test_record(temp_dir: TempDir) {
+        let path = temp_dir.join("script.yml");
+        let mut recorder = InputRecorder::create(path.clone()).unwrap();
+        recorder.record(&key_event(KeyCombination {
+            code: KeyCode::Char('x'),
+            modifiers: KeyModifiers::CTRL,
+        }));
+        recorder.record(&Event::Paste("\"hi\"".into()));
+        recorder.record(&key_event(KeyCode::Enter.into()));
+        // Ignored
+        recorder.record(&Event::FocusGained);
+        recorder.last_event -= Duration::from_millis(300);
+        recorder.record(&key_event(KeyCode::Char(':').into()));
+
+        let script: InputScript =
+            yaml::deserialize_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            script.steps,
+            [
+                ScriptStep::Key {
+                    key: KeyCombination {
+                        code: KeyCode::Char('x'),
+                        modifiers: KeyModifiers::CTRL,
+                    },
+                },
+                ScriptStep::Text {
+                    text: "\"hi\"".into()
+                },
+                ScriptStep::Key {
+                    key: KeyCode::Enter.into()
+                },
+                ScriptStep::Wait { ms: 300 },
+                ScriptStep::Key {
+                    key: KeyCode::Char(':').into()
+                },
+            ]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use crate::message::{Message, MessageSender};
 use ratatui::{
     Frame, Terminal,
     backend::TestBackend,
+    buffer::Buffer,
     layout::{Position, Rect},
     text::Line,
 };
@@ -116,6 +117,11 @@ impl TestTerminal {
         self.0.borrow().backend().assert_buffer_lines(expected);
     }
 
+    /// Get a copy of the current buffer
+    pub fn buffer(&self) -> Buffer {
+        self.0.borrow().backend().buffer().clone()
+    }
+
     /// Draw to the frame
     pub fn draw(&self, f: impl FnOnce(&mut Frame)) {
         self.0.borrow_mut().draw(f).unwrap();
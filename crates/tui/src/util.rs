@@ -9,12 +9,15 @@ use crossterm::{
     terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use futures::{FutureExt, future};
-use slumber_util::{ResultTraced, ResultTracedAnyhow, paths::expand_home};
+use mime::Mime;
+use slumber_config::Appearance;
+use slumber_util::{ResultTraced, ResultTracedAnyhow, paths::expand_home, tr};
 use std::{
     env,
     fs::{self, File},
     future::Future,
     io::{self, Write},
+    iter,
     ops::Deref,
     path::{Path, PathBuf},
     process::{Command, Stdio},
@@ -28,7 +31,7 @@ use tokio::{
     time::{self, MissedTickBehavior},
 };
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, debug_span, error, info, info_span, warn};
+use tracing::{Instrument, debug, debug_span, error, info, info_span, warn};
 use uuid::Uuid;
 
 /// Extension trait for [Result]
@@ -78,21 +81,31 @@ impl Flag {
 #[derive(Debug)]
 pub struct TempFile {
     path: PathBuf,
+    /// Directory created to hold the file, if any. It's deleted along with the
+    /// file
+    dir: Option<PathBuf>,
 }
 
 impl TempFile {
-    /// Create a new temporary file with the given contents
-    pub fn new(contents: &[u8]) -> anyhow::Result<Self> {
-        Self::with_file(|file| file.write_all(contents))
+    /// Create a new temporary file with the given contents. If a MIME type is
+    /// given, the file gets a matching extension so editors/pagers can detect
+    /// the file type.
+    pub fn new(contents: &[u8], mime: Option<&Mime>) -> anyhow::Result<Self> {
+        Self::with_file(mime, |file| file.write_all(contents))
     }
 
     /// Create a new temporary file and call a function to initialize it with
     /// data. This is used for writing a ratatui `Text` object to a file, which
     /// isn't accessible as a single chunk of bytes.
     pub fn with_file(
+        mime: Option<&Mime>,
         mut writer: impl FnMut(&mut File) -> io::Result<()>,
     ) -> anyhow::Result<Self> {
-        let path = env::temp_dir().join(format!("slumber-{}", Uuid::new_v4()));
+        let mut path =
+            env::temp_dir().join(format!("slumber-{}", Uuid::new_v4()));
+        if let Some(extension) = mime.map(mime_extension) {
+            path.set_extension(extension);
+        }
         let mut file = std::fs::OpenOptions::new()
             .create(true)
             .write(true)
@@ -104,7 +117,36 @@ impl TempFile {
         writer(&mut file).with_context(|| {
             format!("Error writing to temporary file `{}`", path.display())
         })?;
-        Ok(Self { path })
+        Ok(Self { path, dir: None })
+    }
+
+    /// Create a new temporary file in its own temporary directory, alongside
+    /// other files. The whole directory is deleted when the struct is dropped.
+    /// This is used to put project config next to a file, so tools run on it
+    /// (e.g. an editor's language server) can pick it up.
+    pub fn in_dir(
+        name: &str,
+        contents: &[u8],
+        siblings: &[(&str, &[u8])],
+    ) -> anyhow::Result<Self> {
+        let dir = env::temp_dir().join(format!("slumber-{}", Uuid::new_v4()));
+        fs::create_dir(&dir).with_context(|| {
+            format!("Error creating temporary directory `{}`", dir.display())
+        })?;
+        // Take ownership of the directory now, so it's cleaned up on error
+        let file = Self {
+            path: dir.join(name),
+            dir: Some(dir),
+        };
+        for (name, contents) in
+            iter::once((name, contents)).chain(siblings.iter().copied())
+        {
+            let path = file.path.with_file_name(name);
+            fs::write(&path, contents).with_context(|| {
+                format!("Error writing to temporary file `{}`", path.display())
+            })?;
+        }
+        Ok(file)
     }
 
     pub fn path(&self) -> &Path {
@@ -114,23 +156,40 @@ impl TempFile {
 
 impl Drop for TempFile {
     fn drop(&mut self) {
-        let _ = fs::remove_file(&self.path)
-            .with_context(|| {
+        let _ = if let Some(dir) = &self.dir {
+            fs::remove_dir_all(dir).with_context(|| {
+                format!(
+                    "Error deleting temporary directory `{}`",
+                    dir.display()
+                )
+            })
+        } else {
+            fs::remove_file(&self.path).with_context(|| {
                 format!(
                     "Error deleting temporary file `{}`",
                     self.path.display()
                 )
             })
-            .traced();
+        }
+        .traced();
+    }
+}
+
+/// Get a file extension for a MIME type. This doesn't need to be exhaustive;
+/// it's just a hint for external programs. Structured suffixes take priority,
+/// so `application/vnd.api+json` gets `json`.
+fn mime_extension(mime: &Mime) -> &str {
+    match mime.suffix().unwrap_or(mime.subtype()).as_str() {
+        "plain" | "x-www-form-urlencoded" => "txt",
+        "javascript" => "js",
+        "x-diff" => "diff",
+        subtype => subtype,
     }
 }
 
 /// Run a **blocking** subprocess that will take over the terminal. Used
-/// for opening an external editor or pager. Useful for terminal editors since
-/// they'll take over the whole screen. Potentially annoying for GUI editors
-/// that block, but we'll just hope the command is fire-and-forget. If this
-/// becomes an issue we can try to detect if the subprocess took over the
-/// terminal and cut it loose if not, or add a config field for it.
+/// for opening an external editor or pager in the terminal. For programs that
+/// open their own window, use [run_gui_command] instead.
 pub fn yield_terminal(
     mut command: Command,
     messages_tx: &MessageSender,
@@ -209,6 +268,160 @@ pub fn clear_event_buffer() {
     }
 }
 
+/// Detect whether the terminal has a light or dark background. The terminal is
+/// queried for its background color with OSC 11. If it doesn't respond, fall
+/// back to the `COLORFGBG` environment variable. Return `None` if neither is
+/// available. This must be called **before** the terminal input stream is
+/// started, otherwise the stream will swallow the response.
+pub fn detect_appearance() -> Option<Appearance> {
+    let appearance = query_background().or_else(|| {
+        env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| parse_colorfgbg(&value))
+    });
+    info!(?appearance, "Detected terminal appearance");
+    appearance
+}
+
+/// Query the terminal for its background color (OSC 11)
+#[cfg(unix)]
+fn query_background() -> Option<Appearance> {
+    use rustix::event::{PollFd, PollFlags, Timespec, poll};
+    use std::{io::IsTerminal, time::Instant};
+
+    /// Maximum time to wait for the terminal to respond. Terminals generally
+    /// respond instantly, but this can be slower over SSH
+    const TIMEOUT: Duration = Duration::from_millis(200);
+
+    let stdin = io::stdin();
+    if !stdin.is_terminal() || !io::stdout().is_terminal() {
+        return None;
+    }
+
+    crossterm::terminal::enable_raw_mode().ok()?;
+    let read_response = || -> Option<Vec<u8>> {
+        // Follow the OSC 11 query with a Primary Device Attributes (DA1) query.
+        // Every terminal responds to DA1, so once we see that response we know
+        // the terminal won't answer the first query.
+        let mut stdout = io::stdout();
+        stdout.write_all(b"\x1b]11;?\x1b\\\x1b[c").ok()?;
+        stdout.flush().ok()?;
+
+        let deadline = Instant::now() + TIMEOUT;
+        let mut response = Vec::new();
+        let mut buffer = [0; 64];
+        while !has_device_attributes(&response) {
+            let timeout = Timespec::try_from(
+                deadline.checked_duration_since(Instant::now())?,
+            )
+            .ok()?;
+            let mut fds = [PollFd::new(&stdin, PollFlags::IN)];
+            if poll(&mut fds, Some(&timeout)).ok()? == 0 {
+                return None; // Timed out
+            }
+            let len = rustix::io::read(&stdin, &mut buffer).ok()?;
+            if len == 0 {
+                return None;
+            }
+            response.extend_from_slice(&buffer[..len]);
+        }
+        Some(response)
+    };
+    let response = read_response();
+    let _ = crossterm::terminal::disable_raw_mode();
+    parse_background_response(&response?)
+}
+
+/// Querying the terminal is only supported on Unix
+#[cfg(not(unix))]
+fn query_background() -> Option<Appearance> {
+    None
+}
+
+/// Does the terminal output contain a complete DA1 response
+/// (`ESC [ ? ... c`)?
+#[cfg_attr(not(unix), expect(dead_code))]
+fn has_device_attributes(response: &[u8]) -> bool {
+    response
+        .windows(3)
+        .position(|window| window == b"\x1b[?")
+        .is_some_and(|start| response[start..].contains(&b'c'))
+}
+
+/// Parse the terminal's response to an OSC 11 query, which looks like
+/// `ESC ] 11 ; rgb:RRRR/GGGG/BBBB` followed by `BEL` or `ESC \`. Each channel
+/// has 1-4 hex digits.
+#[cfg_attr(not(unix), expect(dead_code))]
+fn parse_background_response(response: &[u8]) -> Option<Appearance> {
+    const PREFIX: &str = "]11;rgb:";
+
+    let response = String::from_utf8_lossy(response);
+    let start = response.find(PREFIX)? + PREFIX.len();
+    let color = &response[start..];
+    let color = &color[..color.find(['\x07', '\x1b'])?];
+
+    let channels: Vec<f64> = color
+        .split('/')
+        .map(|channel| {
+            if !(1..=4).contains(&channel.len()) {
+                return None;
+            }
+            let value = u32::from_str_radix(channel, 16).ok()?;
+            let max = (1u32 << (4 * channel.len())) - 1;
+            Some(f64::from(value) / f64::from(max))
+        })
+        .collect::<Option<_>>()?;
+    let [red, green, blue] = channels.as_slice() else {
+        return None;
+    };
+    // Relative luminance, per ITU-R BT.709
+    let luminance = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+    Some(if luminance > 0.5 {
+        Appearance::Light
+    } else {
+        Appearance::Dark
+    })
+}
+
+/// Parse the `COLORFGBG` environment variable, which is set by some terminals
+/// (e.g. rxvt, Konsole) in the format `fg;bg`. The background is an ANSI color
+/// index; 7 (white) and bright colors other than 8 (dark gray) are light.
+fn parse_colorfgbg(value: &str) -> Option<Appearance> {
+    let background: u8 = value.rsplit(';').next()?.parse().ok()?;
+    match background {
+        0..=6 | 8 => Some(Appearance::Dark),
+        7 | 9..=15 => Some(Appearance::Light),
+        _ => None,
+    }
+}
+
+/// Run a subprocess that opens its own window, such as a GUI editor, and wait
+/// for it to exit. Unlike [yield_terminal], we keep control of the terminal so
+/// the TUI stays responsive while the program is open.
+pub async fn run_gui_command(command: Command) -> anyhow::Result<()> {
+    let span = info_span!("Running command", ?command);
+    let error_context = format!("Error spawning command `{command:?}`");
+    let mut command = tokio::process::Command::from(command);
+    // The program must not write to the terminal, or it'll corrupt the TUI
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let status = command
+        .status()
+        .instrument(span)
+        .await
+        .context(error_context.clone())?;
+    if status.success() {
+        info!(status = status.code(), "Command succeeded");
+        Ok(())
+    } else {
+        error!(status = status.code(), "Command failed");
+        Err(anyhow::anyhow!("Command failed with status {status}"))
+            .context(error_context)
+    }
+}
+
 /// Listen for any exit signals, and return `Ok(())` when any signal is
 /// received. This can only fail during initialization.
 #[cfg(unix)]
@@ -319,7 +532,6 @@ pub fn cancellable(
 
 /// Save some data to disk. This will:
 /// - Ask the user for a path
-/// - Attempt to save a *new* file
 /// - If the file already exists, ask for confirmation
 /// - If confirmed, overwrite existing
 pub async fn save_file(
@@ -327,59 +539,19 @@ pub async fn save_file(
     default_path: Option<String>,
     data: Bytes,
 ) -> anyhow::Result<()> {
-    // If the user closed the prompt, just exit
-    let Some(path) =
-        text_question(&messages_tx, "Enter a path for the file", default_path)
-            .await
-    else {
+    // If the user backed out, just exit
+    let Some(path) = prompt_save_path(&messages_tx, default_path).await else {
         return Ok(());
     };
 
-    // If the user input nothing, assume they just want to exit
-    if path.is_empty() {
-        return Ok(());
-    }
-
-    let path = expand_home(PathBuf::from(path)); // Expand ~
-    let result = {
-        // Attempt to open the file *if it doesn't exist already*
-        let result = OpenOptions::new()
-            .create_new(true)
-            .write(true)
-            .open(&path)
-            .await;
-
-        match result {
-            Ok(file) => Ok(file),
-            // If the file already exists, ask for confirmation to overwrite
-            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
-                warn!(?path, "File already exists, asking to overwrite");
-
-                // Hi, sorry, follow up question. Are you sure?
-                if confirm(
-                    &messages_tx,
-                    format!("`{}` already exists, overwrite?", path.display()),
-                )
-                .await
-                {
-                    // REALLY attempt to open the file
-                    OpenOptions::new()
-                        .create(true)
-                        .write(true)
-                        .truncate(true)
-                        .open(&path)
-                        .await
-                } else {
-                    return Ok(());
-                }
-            }
-            Err(error) => Err(error),
-        }
-    };
-
     debug!(?path, bytes = data.len(), "Writing to file");
     async {
-        let mut file = result?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .await?;
         file.write_all(&data).await?;
         file.flush().await
     }
@@ -390,10 +562,44 @@ pub async fn save_file(
     // It might be nice to show the full path here, but it's not trivial to get
     // that. The stdlib has fs::canonicalize, but it does more than we need
     // (specifically it resolves symlinks), which might be confusing
-    messages_tx.send(Message::Notify(format!("Saved to {}", path.display())));
+    messages_tx
+        .send(Message::Notify(tr!("notify-saved", path = path.display())));
     Ok(())
 }
 
+/// Ask the user for a path to write a file to. If the file already exists,
+/// ask for confirmation to overwrite it. Return `None` if the user closes
+/// either prompt, enters nothing, or declines to overwrite.
+pub async fn prompt_save_path(
+    messages_tx: &MessageSender,
+    default_path: Option<String>,
+) -> Option<PathBuf> {
+    let path =
+        text_question(messages_tx, "Enter a path for the file", default_path)
+            .await?;
+
+    // If the user input nothing, assume they just want to exit
+    if path.is_empty() {
+        return None;
+    }
+
+    let path = expand_home(PathBuf::from(path)); // Expand ~
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        warn!(?path, "File already exists, asking to overwrite");
+
+        // Hi, sorry, follow up question. Are you sure?
+        if !confirm(
+            messages_tx,
+            format!("`{}` already exists, overwrite?", path.display()),
+        )
+        .await
+        {
+            return None;
+        }
+    }
+    Some(path.into_owned())
+}
+
 /// Run a command, optionally piping some stdin to it. This will use given shell
 /// (e.g. `["sh", "-c"]`) to execute the command, or parse+run it natively if no
 /// shell is set. The shell should generally come from the config, but is
@@ -496,6 +702,64 @@ mod tests {
     use slumber_util::{TempDir, assert_matches, temp_dir};
     use tokio::fs;
 
+    #[rstest]
+    #[case::json("application/json", "json")]
+    #[case::suffix("application/vnd.api+json", "json")]
+    #[case::text("text/plain", "txt")]
+    #[case::html("text/html; charset=utf-8", "html")]
+    #[case::diff("text/x-diff", "diff")]
+    fn test_mime_extension(#[case] mime: Mime, #[case] expected: &str) {
+        assert_eq!(mime_extension(&mime), expected);
+    }
+
+    #[rstest]
+    #[case::black_bel(
+        b"\x1b]11;rgb:0000/0000/0000\x07",
+        Some(Appearance::Dark)
+    )]
+    #[case::white_st(
+        b"\x1b]11;rgb:ffff/ffff/ffff\x1b\\\x1b[?62;22c",
+        Some(Appearance::Light)
+    )]
+    #[case::short_channels(b"\x1b]11;rgb:e/e/d\x07", Some(Appearance::Light))]
+    #[case::dark_blue(
+        b"\x1b]11;rgb:1e1e/1e1e/2e2e\x07",
+        Some(Appearance::Dark)
+    )]
+    #[case::device_attributes_only(b"\x1b[?62;22c", None)]
+    #[case::invalid(b"\x1b]11;rgb:zz/00/00\x07", None)]
+    #[case::unterminated(b"\x1b]11;rgb:ffff/ffff/ffff", None)]
+    fn test_parse_background_response(
+        #[case] response: &[u8],
+        #[case] expected: Option<Appearance>,
+    ) {
+        assert_eq!(parse_background_response(response), expected);
+    }
+
+    #[rstest]
+    #[case::dark(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;22c", true)]
+    #[case::incomplete(b"\x1b]11;rgb:0/0/0\x07\x1b[?62;2", false)]
+    #[case::empty(b"", false)]
+    fn test_has_device_attributes(
+        #[case] response: &[u8],
+        #[case] expected: bool,
+    ) {
+        assert_eq!(has_device_attributes(response), expected);
+    }
+
+    #[rstest]
+    #[case::dark("15;0", Some(Appearance::Dark))]
+    #[case::light("0;15", Some(Appearance::Light))]
+    #[case::three_part("0;default;7", Some(Appearance::Light))]
+    #[case::dark_gray("15;8", Some(Appearance::Dark))]
+    #[case::invalid("15;default", None)]
+    fn test_parse_colorfgbg(
+        #[case] value: &str,
+        #[case] expected: Option<Appearance>,
+    ) {
+        assert_eq!(parse_colorfgbg(value), expected);
+    }
+
     /// Test various cases of save_file
     #[rstest]
     #[case::new_file(false, false)]
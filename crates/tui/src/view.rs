@@ -25,20 +25,29 @@ use crate::{
         event::Event,
     },
 };
+#[cfg(not(windows))]
 use crossterm::clipboard::CopyToClipboard;
 use indexmap::IndexMap;
-use ratatui::{buffer::Buffer, crossterm::execute, text::Span};
-use slumber_config::Config;
+#[cfg(not(windows))]
+use ratatui::crossterm::execute;
+use ratatui::{buffer::Buffer, style::Color, text::Span};
+use reqwest::Url;
+use slumber_config::{Config, StartupConfig};
 use slumber_core::{
-    collection::{Collection, ProfileId},
+    collection::{
+        Collection, CollectionCommit, ProfileId, RecipeId, RemoteStatus,
+        ToggleId,
+    },
     database::CollectionDatabase,
     http::RequestId,
 };
-use slumber_template::Template;
+use slumber_template::{Template, Value};
+use slumber_util::tr;
 use std::{
     fmt::{Debug, Display},
     io,
     sync::Arc,
+    time::Instant,
 };
 use tracing::{trace, trace_span, warn};
 
@@ -96,6 +105,18 @@ impl View {
         }
     }
 
+    /// Override persisted UI state according to the `startup` config. Call
+    /// this once when the TUI launches, *before* building the view.
+    pub fn apply_startup_config(
+        config: &StartupConfig,
+        database: CollectionDatabase,
+    ) {
+        Root::apply_startup_config(
+            config,
+            &mut persistent::PersistentStore::new(database),
+        );
+    }
+
     /// Draw the view to a screen buffer
     ///
     /// Return the map of all drawn components.
@@ -108,13 +129,34 @@ impl View {
             return ComponentMap::default();
         }
 
+        // Components re-request a redraw each time they're drawn
+        ViewContext::clear_redraw();
+
         // If debug monitor is enabled, use it to capture view duration
-        if let Some(debug_monitor) = &self.debug_monitor {
+        let component_map = if let Some(debug_monitor) = &self.debug_monitor {
             debug_monitor
                 .draw(buffer, |buffer| Canvas::draw_all(buffer, &self.root, ()))
         } else {
             Canvas::draw_all(buffer, &self.root, ())
+        };
+
+        // Strip color from everything at once, rather than making each
+        // component check. This catches colors that don't come from the theme,
+        // such as syntax highlighting
+        let styles = ViewContext::styles();
+        if !styles.color {
+            for cell in &mut buffer.content {
+                cell.set_fg(Color::Reset).set_bg(Color::Reset);
+            }
         }
+        // Same for disabled modifiers
+        if !styles.disabled_modifiers.is_empty() {
+            for cell in &mut buffer.content {
+                cell.modifier.remove(styles.disabled_modifiers);
+            }
+        }
+
+        component_map
     }
 
     /// Persist all UI state to the database. This should be called at the end
@@ -133,6 +175,12 @@ impl View {
         self.root.selected_profile_id()
     }
 
+    /// ID of the selected recipe. `None` iff the list is empty OR a folder is
+    /// selected
+    pub fn selected_recipe_id(&self) -> Option<&RecipeId> {
+        self.root.selected_recipe_id()
+    }
+
     /// Get a definition of the request that should be sent from the current
     /// recipe settings
     pub fn request_config(&self) -> Option<RequestConfig> {
@@ -144,6 +192,11 @@ impl View {
         self.root.profile_overrides()
     }
 
+    /// Get the IDs of the toggles that are switched on
+    pub fn enabled_toggles(&self) -> Vec<ToggleId> {
+        self.root.enabled_toggles()
+    }
+
     /// Update the displayed request based on a change in HTTP request state.
     /// If the update is not relevant to what's on screen (e.g. an unselected
     /// request was modified), this will do nothing.
@@ -155,6 +208,15 @@ impl View {
         self.root.refresh_request(store, disposition);
     }
 
+    /// Select a recipe and/or profile, as requested by an external process
+    pub fn open(
+        &mut self,
+        recipe_id: Option<&RecipeId>,
+        profile_id: Option<&ProfileId>,
+    ) {
+        self.root.open(recipe_id, profile_id);
+    }
+
     /// Ask the user a [Question]
     pub fn question(&mut self, question: Question) {
         self.root.question(question);
@@ -165,6 +227,28 @@ impl View {
         self.root.error(error);
     }
 
+    /// Show values captured from responses in a modal
+    pub fn captures(&mut self, captures: Vec<(String, Value)>) {
+        self.root.captures(captures);
+    }
+
+    /// Show the git history of the collection file in a modal
+    pub fn changelog(&mut self, commits: Vec<CollectionCommit>) {
+        self.root.changelog(commits);
+    }
+
+    /// Hold a request that failed while offline, to send again once the
+    /// network is back
+    pub fn queue_offline(&mut self, config: RequestConfig, url: Url) {
+        self.root.queue_offline(config, url);
+    }
+
+    /// Show where a remote collection was fetched from, and whether the cached
+    /// copy is stale. `None` for a local collection
+    pub fn set_remote(&mut self, remote: Option<RemoteStatus>) {
+        self.root.set_remote(remote);
+    }
+
     /// Display an informational notification to the user
     pub fn notify(&mut self, message: impl ToString) {
         self.root.notify(message.to_string());
@@ -177,6 +261,20 @@ impl View {
         ViewContext::push_event(Event::Input(event));
     }
 
+    /// Get a future that resolves when an event is pushed onto the view's
+    /// event queue. This allows the main loop to wake up when a background
+    /// task modifies the view.
+    pub fn event_pushed(&self) -> impl 'static + Future<Output = ()> {
+        ViewContext::event_pushed()
+    }
+
+    /// Get the time at which the view needs to be redrawn because something
+    /// on screen changes on its own, e.g. a countdown. `None` if nothing
+    /// visible is time-relative.
+    pub fn redraw_at(&self) -> Option<Instant> {
+        ViewContext::redraw_at()
+    }
+
     /// Drain all view events from the queue. The component three will process
     /// events one by one. This should be called on every TUI loop. Return
     /// whether or not an event was handled.
@@ -206,14 +304,73 @@ impl View {
 
     /// Copy text to the user's clipboard, and notify them
     pub fn copy_text(&mut self, text: String) -> anyhow::Result<()> {
-        execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text))
-            .inspect(|()| self.notify("Copied text to clipboard"))
+        copy_to_clipboard(&text)
+            .inspect(|()| self.notify(tr!("notify-copied")))
             .map_err(|error| {
                 anyhow::Error::from(error).context("Error copying text")
             })
     }
 }
 
+/// Copy text to the clipboard via the terminal (OSC 52). This works over SSH
+/// and in most modern terminals.
+#[cfg(not(windows))]
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text))
+}
+
+/// Copy text to the clipboard via the terminal (OSC 52) *and* `clip.exe`.
+/// Windows Terminal supports OSC 52, and it's the only option over SSH, but
+/// the legacy console silently ignores it. We can't tell which one we're in,
+/// so use both. The copy succeeds if either does.
+#[cfg(windows)]
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let osc52 =
+        execute!(io::stdout(), CopyToClipboard::to_clipboard_from(text));
+    match copy_with_clip_exe(text) {
+        Ok(()) => Ok(()),
+        // The terminal may have handled it, so don't bother the user
+        Err(error) if osc52.is_ok() => {
+            warn!(%error, "Error copying with clip.exe");
+            Ok(())
+        }
+        Err(error) => Err(error),
+    }
+}
+
+/// Copy text to the system clipboard with `clip.exe`. crossterm can't copy via
+/// the Windows API, so this is the fallback for consoles without OSC 52.
+#[cfg(windows)]
+fn copy_with_clip_exe(text: &str) -> io::Result<()> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+
+    let mut child = Command::new("clip.exe")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // clip.exe assumes the console code page unless the input is UTF-16 with
+    // a BOM, so encode it that way to preserve non-ASCII text
+    let bytes: Vec<u8> = std::iter::once(0xFEFF)
+        .chain(text.encode_utf16())
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    child
+        .stdin
+        .take()
+        .expect("clip.exe stdin not set")
+        .write_all(&bytes)?;
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("clip.exe exited with {status}")))
+    }
+}
+
 /// A helper for building a UI. It can be converted into some UI element to be
 /// drawn.
 pub trait Generate {
@@ -276,7 +433,9 @@ mod tests {
         test_util::{TestTerminal, assert_events, terminal},
         view::test_util::{TestHarness, harness},
     };
+    use ratatui::style::Modifier;
     use rstest::rstest;
+    use slumber_config::StyleModifier;
     use slumber_core::collection::Collection;
     use slumber_util::Factory;
 
@@ -320,4 +479,58 @@ mod tests {
         });
         assert_events!();
     }
+
+    /// With color disabled, color is stripped from every cell after drawing
+    #[rstest]
+    #[case::color(true, false)]
+    #[case::no_color(false, true)]
+    fn test_no_color(
+        harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] color: bool,
+        #[case] expected_no_color: bool,
+    ) {
+        let mut config = Config::default();
+        config.tui.theme.color = color;
+        let view = View::new(
+            config.into(),
+            Ok(Collection::factory(()).into()),
+            harness.database.clone(),
+            harness.messages_tx(),
+        );
+        terminal.draw(|frame| {
+            let _ = view.draw(frame.buffer_mut());
+        });
+
+        let no_color = terminal
+            .buffer()
+            .content
+            .iter()
+            .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset);
+        assert_eq!(no_color, expected_no_color);
+    }
+
+    /// Disabled modifiers are stripped from every cell after drawing
+    #[rstest]
+    fn test_disabled_modifiers(harness: TestHarness, terminal: TestTerminal) {
+        let mut config = Config::default();
+        config.tui.theme.disabled_modifiers = vec![StyleModifier::Bold];
+        let view = View::new(
+            config.into(),
+            Ok(Collection::factory(()).into()),
+            harness.database.clone(),
+            harness.messages_tx(),
+        );
+        terminal.draw(|frame| {
+            let _ = view.draw(frame.buffer_mut());
+        });
+
+        assert!(
+            terminal
+                .buffer()
+                .content
+                .iter()
+                .all(|cell| !cell.modifier.contains(Modifier::BOLD))
+        );
+    }
 }
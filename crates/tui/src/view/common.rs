@@ -24,11 +24,13 @@ use chrono::{DateTime, Duration, Utc};
 use itertools::{Itertools, Position};
 use ratatui::{
     prelude::{Buffer, Rect},
+    style::{Modifier, Style},
     symbols::merge::MergeStrategy,
     text::{Span, Text},
     widgets::{Block, Borders, Widget},
 };
 use reqwest::{StatusCode, header::HeaderValue};
+use slumber_config::{EnvironmentBadge, Spinner};
 use slumber_core::{collection::Profile, util::MaybeStr};
 use std::{error::Error, ops::Deref, ptr};
 use unicode_width::UnicodeWidthStr;
@@ -49,11 +51,11 @@ impl Generate for Pane<'_> {
     where
         Self: 'this,
     {
-        let (border_type, border_style) =
+        let (border_set, border_style) =
             ViewContext::styles().pane.border(self.has_focus);
         Block::default()
             .borders(Borders::ALL)
-            .border_type(border_type)
+            .border_set(border_set)
             .border_style(border_style)
             .merge_borders(MergeStrategy::Fuzzy)
             .title(self.title)
@@ -75,6 +77,49 @@ impl Widget for Checkbox {
     }
 }
 
+/// Animated indicator for an in-flight request. The frame is derived from the
+/// time since the request started, so there's no state to track. Generates
+/// nothing if animation is disabled.
+pub struct LoadingSpinner {
+    pub start_time: DateTime<Utc>,
+}
+
+impl LoadingSpinner {
+    /// How long each frame is shown. This matches the rate at which the TUI
+    /// redraws while a request is in flight.
+    const FRAME_MILLIS: i64 = 250;
+
+    fn frames(spinner: Spinner) -> &'static [&'static str] {
+        match spinner {
+            Spinner::Dots => {
+                &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]
+            }
+            Spinner::Line => &["|", "/", "-", "\\"],
+            Spinner::Circle => &["◐", "◓", "◑", "◒"],
+            Spinner::None => &[],
+        }
+    }
+}
+
+impl Generate for LoadingSpinner {
+    type Output<'this> = Span<'static>;
+
+    fn generate<'this>(self) -> Self::Output<'this>
+    where
+        Self: 'this,
+    {
+        let Some(spinner) = ViewContext::config().tui.active_spinner() else {
+            return Span::default();
+        };
+        let frames = Self::frames(spinner);
+        let elapsed = (Utc::now() - self.start_time).num_milliseconds();
+        // Clock skew could make elapsed time negative; don't panic on that
+        let index =
+            (elapsed / Self::FRAME_MILLIS).rem_euclid(frames.len() as i64);
+        format!("{} ", frames[index as usize]).into()
+    }
+}
+
 impl Generate for String {
     /// Use `Text` because a string can be multiple lines
     type Output<'this> = Text<'this>;
@@ -116,6 +161,24 @@ impl Generate for &Profile {
     }
 }
 
+/// Label in a colored block, e.g. ` PROD `
+impl Generate for &EnvironmentBadge {
+    type Output<'this>
+        = Span<'this>
+    where
+        Self: 'this;
+
+    fn generate<'this>(self) -> Self::Output<'this>
+    where
+        Self: 'this,
+    {
+        Span::styled(
+            format!(" {} ", self.label),
+            Style::default().bg(self.color).add_modifier(Modifier::BOLD),
+        )
+    }
+}
+
 /// Format a timestamp in the local timezone
 impl Generate for DateTime<Utc> {
     type Output<'this>
@@ -171,16 +234,19 @@ impl Generate for StatusCode {
     where
         Self: 'this,
     {
-        let styles = ViewContext::styles().status_code;
+        let styles = ViewContext::styles();
         let is_error = self.is_client_error() || self.is_server_error();
-        Span::styled(
-            self.to_string(),
-            if is_error {
-                styles.error
-            } else {
-                styles.success
-            },
-        )
+        if is_error {
+            Span::styled(
+                format!("{}{self}", styles.markers.error),
+                styles.status_code.error,
+            )
+        } else {
+            Span::styled(
+                format!("{}{self}", styles.markers.success),
+                styles.status_code.success,
+            )
+        }
     }
 }
 
@@ -212,6 +278,7 @@ impl Generate for &dyn Error {
     where
         Self: 'this,
     {
+        let symbols = ViewContext::styles().symbols;
         let mut text = Text::default();
         // Walk down the error chain and build out a tree thing
         let mut next = Some(self);
@@ -227,9 +294,9 @@ impl Generate for &dyn Error {
                 ""
             } else if next.is_some() {
                 // If there's a following error, leave a little dangler
-                "└┬"
+                symbols.tree_end_fork
             } else {
-                "└─"
+                symbols.tree_end
             };
             // Add additional indentation for account for the icon (-1 for the
             // dangler overlap). All lines for this error should start at the
@@ -242,7 +309,9 @@ impl Generate for &dyn Error {
                 let icon = match position {
                     Position::First | Position::Only => icon,
                     // If there's an error after, extend the dangler
-                    Position::Middle | Position::Last if next.is_some() => "│",
+                    Position::Middle | Position::Last if next.is_some() => {
+                        symbols.tree_line
+                    }
                     Position::Middle | Position::Last => "",
                 };
                 text.push_line(format!("{icon:>line_indent$}{line}"));
@@ -272,27 +341,81 @@ impl Generate for &anyhow::Error {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::view::test_util::TestHarness;
     use anyhow::anyhow;
+    use rstest::rstest;
+    use slumber_config::Config;
+    use slumber_core::collection::Collection;
+    use slumber_util::Factory;
 
     /// Test error chain display
     ///
     /// - First error is displayed without indentation
     /// - Subsequent errors get a little tree guy with indentation
     /// - Continuation lines from a single error are indented as well
-    #[test]
-    fn test_error() {
-        // Build the error inside-out
-        let error = anyhow!("Third\nPoint at ! ^^\nthird line")
-            .context("Second\nanother line!!")
-            .context("First");
-        let expected = "\
+    #[rstest]
+    #[case::unicode(
+        false,
+        "\
 First
 └┬Second
  │another line!!
  └─Third
    Point at ! ^^
-   third line";
+   third line"
+    )]
+    #[case::ascii(
+        true,
+        "\
+First
+`+Second
+ |another line!!
+ `-Third
+   Point at ! ^^
+   third line"
+    )]
+    fn test_error(#[case] ascii: bool, #[case] expected: &str) {
+        let mut config = Config::default();
+        config.tui.theme.ascii = ascii;
+        let _harness =
+            TestHarness::with_config(Collection::factory(()), config);
+        // Build the error inside-out
+        let error = anyhow!("Third\nPoint at ! ^^\nthird line")
+            .context("Second\nanother line!!")
+            .context("First");
         let actual = error.generate().into_iter().join("\n");
         assert_eq!(actual, expected);
     }
+
+    /// Spinner shows a frame for the configured style, or nothing if animation
+    /// is disabled
+    #[rstest]
+    #[case::dots(Spinner::Dots, false, true)]
+    #[case::line(Spinner::Line, false, true)]
+    #[case::none(Spinner::None, false, false)]
+    #[case::reduced_motion(Spinner::Dots, true, false)]
+    fn test_loading_spinner(
+        #[case] spinner: Spinner,
+        #[case] reduced_motion: bool,
+        #[case] expected_visible: bool,
+    ) {
+        let mut config = Config::default();
+        config.tui.spinner = spinner;
+        config.tui.reduced_motion = reduced_motion;
+        let _harness =
+            TestHarness::with_config(Collection::factory(()), config);
+
+        let span = LoadingSpinner {
+            start_time: Utc::now() - Duration::seconds(1),
+        }
+        .generate();
+        if expected_visible {
+            // We can't predict which frame we'll get, but it should be one
+            // of the frames for this style
+            let frame = span.content.strip_suffix(' ').unwrap();
+            assert!(LoadingSpinner::frames(spinner).contains(&frame));
+        } else {
+            assert_eq!(span.content, "");
+        }
+    }
 }
@@ -90,8 +90,9 @@ impl Draw for ActionMenu {
     fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
         if let Some(content) = &self.content {
             // Our given area is the full screen so we can capture cursor
-            // events. Draw the content just to the middle block
-            let area = content.area(metadata.area());
+            // events. Draw the content just to the middle block. Nested layers
+            // can push the content off screen, so shift it back in
+            let area = content.area(metadata.area()).clamp(metadata.area());
             canvas.draw(content, (), area, true);
         }
     }
@@ -384,7 +385,12 @@ impl Component for ActionMenuContent {
 impl Draw for ActionMenuContent {
     fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
         let styles = ViewContext::styles().menu;
-        let areas = self.areas(metadata.area().as_position());
+        // If the screen is too small to fit every layer, they get cut off
+        let areas = self
+            .areas(metadata.area().as_position())
+            .into_iter()
+            .map(|area| area.intersection(metadata.area()))
+            .collect_vec();
 
         // Clear content/styling underneath all layers. This has to happen
         // before rendering any blocks so we don't clear the border from a
@@ -399,7 +405,7 @@ impl Draw for ActionMenuContent {
             // Add border
             let block = Block::new()
                 .borders(Borders::ALL)
-                .border_type(styles.border_type)
+                .border_set(styles.border_set)
                 .merge_borders(MergeStrategy::Fuzzy);
             let inner_area = block.inner(area);
             canvas.render_widget(block, area);
@@ -91,6 +91,14 @@ where
         &self.selected() == item
     }
 
+    /// Select a specific item
+    pub fn select(&mut self, item: &Item)
+    where
+        Item: PartialEq,
+    {
+        self.inner.select(item);
+    }
+
     /// Select the previous item in the list
     pub fn previous(&mut self) {
         // We only support top-to-bottom, so up is previous
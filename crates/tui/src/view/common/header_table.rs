@@ -1,32 +1,90 @@
-use crate::view::{Generate, common::table::Table};
+use crate::view::{
+    Generate, common::table::Table, util::render_cache::RenderCache,
+};
 use itertools::Itertools;
 use ratatui::{
     prelude::{Buffer, Rect},
-    text::Text,
+    style::Style,
+    text::Span,
     widgets::Widget,
 };
 use reqwest::header::HeaderMap;
 
 /// Render HTTP request/response headers in a table
-pub struct HeaderTable<'a> {
-    pub headers: &'a HeaderMap,
+///
+/// The headers can't change after construction, so the table is laid out once
+/// per area and copied into later frames. Store this in the owning component
+/// rather than rebuilding it on each draw.
+#[derive(Debug)]
+pub struct HeaderTable {
+    title: Option<&'static str>,
+    rows: Vec<[String; 2]>,
+    /// Style applied to the header names and values
+    style: Style,
+    /// The table from the last draw. Nothing in the table can change, so the
+    /// key is empty; it's redrawn only when the area or background changes
+    render_cache: RenderCache<()>,
+}
+
+impl HeaderTable {
+    pub fn new(headers: &HeaderMap) -> Self {
+        let rows = headers
+            .iter()
+            .map(|(k, v)| [k.as_str().to_owned(), v.generate().content.into()])
+            .collect_vec();
+        Self {
+            title: None,
+            rows,
+            style: Style::default(),
+            render_cache: RenderCache::default(),
+        }
+    }
+
+    /// Show a title above the table
+    pub fn title(mut self, title: &'static str) -> Self {
+        self.title = Some(title);
+        self
+    }
+
+    /// Set the style of the header names and values
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Number of headers in the table
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
 }
 
-impl Widget for HeaderTable<'_> {
+impl Widget for &HeaderTable {
     fn render(self, area: Rect, buf: &mut Buffer)
     where
         Self: Sized,
     {
-        Table {
-            rows: self
-                .headers
-                .iter()
-                .map(|(k, v)| [Text::from(k.as_str()), v.generate().into()])
-                .collect_vec(),
-            header: Some(["Header", "Value"]),
-            alternate_row_style: true,
-            ..Default::default()
-        }
-        .render(area, buf);
+        self.render_cache.render((), area, buf, |buf| {
+            Table {
+                title: self.title,
+                rows: self
+                    .rows
+                    .iter()
+                    .map(|[k, v]| {
+                        [
+                            Span::styled(k.as_str(), self.style),
+                            Span::styled(v.as_str(), self.style),
+                        ]
+                    })
+                    .collect_vec(),
+                header: Some(["Header", "Value"]),
+                alternate_row_style: true,
+                ..Default::default()
+            }
+            .render(area, buf);
+        });
     }
 }
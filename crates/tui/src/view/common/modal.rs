@@ -1,8 +1,11 @@
-use crate::view::{
-    Component, ComponentExt, UpdateContext,
-    component::{Canvas, Child, ComponentId, Draw, DrawMetadata},
-    context::ViewContext,
-    event::{Event, EventMatch},
+use crate::{
+    input::InputEvent,
+    view::{
+        Component, ComponentExt, UpdateContext,
+        component::{Canvas, Child, ComponentId, Draw, DrawMetadata},
+        context::ViewContext,
+        event::{Emitter, Event, EventMatch},
+    },
 };
 use ratatui::{
     layout::{Constraint, Margin},
@@ -27,6 +30,8 @@ pub struct ModalQueue<T> {
     /// All the queued modals. The front modal will be visible, the rest are
     /// patiently waiting their turn.
     queue: VecDeque<T>,
+    /// If set, an event is emitted whenever the visible modal closes
+    close_emitter: Option<Emitter<ModalClosed>>,
 }
 
 // Remove bound on T
@@ -35,6 +40,19 @@ impl<T> Default for ModalQueue<T> {
         Self {
             id: ComponentId::default(),
             queue: VecDeque::default(),
+            close_emitter: None,
+        }
+    }
+}
+
+impl<T> ModalQueue<T> {
+    /// Create a queue whose modals are part of the navigation history. A
+    /// [ModalClosed] event is emitted each time the visible modal closes, and
+    /// back/forward actions pass through the modal to the parent.
+    pub fn with_close_emitter(emitter: Emitter<ModalClosed>) -> Self {
+        Self {
+            close_emitter: Some(emitter),
+            ..Self::default()
         }
     }
 }
@@ -52,8 +70,10 @@ impl<T: Modal> ModalQueue<T> {
     }
 
     /// Close the visible modal at the front of the queue without submission
-    fn close(&mut self) {
-        self.queue.pop_front();
+    pub fn close(&mut self) {
+        if self.queue.pop_front().is_some() {
+            self.emit_closed();
+        }
     }
 
     /// Close the visible modal at the front of the queue, calling
@@ -61,15 +81,22 @@ impl<T: Modal> ModalQueue<T> {
     fn submit(&mut self, context: &mut UpdateContext) {
         let popped = self.queue.pop_front();
         if let Some(modal) = popped {
+            self.emit_closed();
             modal.on_submit(context);
         }
     }
 
+    fn emit_closed(&self) {
+        if let Some(emitter) = self.close_emitter {
+            emitter.emit(ModalClosed);
+        }
+    }
+
     fn active(&self) -> Option<&T> {
         self.queue.front()
     }
 
-    fn active_mut(&mut self) -> Option<&mut T> {
+    pub fn active_mut(&mut self) -> Option<&mut T> {
         self.queue.front_mut()
     }
 }
@@ -110,7 +137,13 @@ impl<T: Component + Modal> Component for ModalQueue<T> {
                     self.close();
                 }
             })
-            .any(|event| match event {
+            .any(|event| match &event {
+                // Modals in the navigation history can be left by navigating
+                // away, so let the parent see those actions
+                Event::Input(InputEvent::Key {
+                    action: Some(Action::Back | Action::Forward),
+                    ..
+                }) if self.close_emitter.is_some() => Some(event),
                 // Modals are meant to consume all focus, so don't allow any
                 // events to go to background components
                 Event::Input { .. } => None,
@@ -152,7 +185,7 @@ where
         let block = Block::default()
             .borders(Borders::ALL)
             .border_style(styles.border)
-            .border_type(styles.border_type)
+            .border_set(styles.border_set)
             .title(modal.title());
         // Add one cell of X padding so text doesn't butt up against the border;
         // that would interfere with word-based selection
@@ -164,6 +197,11 @@ where
     }
 }
 
+/// Emitted by a [ModalQueue] when its visible modal closes, whether by the user
+/// or by the owner. See [ModalQueue::with_close_emitter]
+#[derive(Debug)]
+pub struct ModalClosed;
+
 /// A modal (AKA popup or dialog) is a high-priority element to be shown to the
 /// user. It may be informational (e.g. an error message) or interactive (e.g.
 /// an input prompt). Any type that implements this trait can be used as a
@@ -1,3 +1,4 @@
+use crate::view::ViewContext;
 use ratatui::{
     buffer::Buffer,
     layout::{Offset, Rect},
@@ -78,11 +79,14 @@ impl Widget for Scrollbar {
     where
         Self: Sized,
     {
-        let (begin_symbol, thumb_symbol, end_symbol) = match &self.orientation {
+        let styles = ViewContext::styles().symbols;
+        let symbols = match &self.orientation {
             ScrollbarOrientation::VerticalRight
-            | ScrollbarOrientation::VerticalLeft => ("▲", "█", "▼"),
+            | ScrollbarOrientation::VerticalLeft => styles.scrollbar_vertical,
             ScrollbarOrientation::HorizontalBottom
-            | ScrollbarOrientation::HorizontalTop => ("◀", "■", "▶"),
+            | ScrollbarOrientation::HorizontalTop => {
+                styles.scrollbar_horizontal
+            }
         };
 
         // Apply an offset to put this outside the content
@@ -107,9 +111,7 @@ impl Widget for Scrollbar {
 
         let scrollbar =
             ratatui::widgets::Scrollbar::new(self.orientation.clone())
-                .begin_symbol(Some(begin_symbol))
-                .thumb_symbol(thumb_symbol)
-                .end_symbol(Some(end_symbol));
+                .symbols(symbols);
 
         let area = area.offset(offset);
         // Avoid panic if there's nowhere to render the scroll bar. This can
@@ -540,7 +540,9 @@ where
         props: SelectListProps,
         metadata: DrawMetadata,
     ) {
-        let styles = ViewContext::styles().list;
+        let styles = ViewContext::styles();
+        let markers = styles.markers;
+        let styles = styles.list;
 
         // Draw list
         let items: Vec<ListItem<'_>> = self
@@ -563,6 +565,7 @@ where
         };
         let list = List::new(items)
             .highlight_style(highlight_style)
+            .highlight_symbol(markers.selected.as_str())
             .direction(self.direction);
         let area = metadata.area();
         let state = &mut self.state.borrow_mut();
@@ -704,8 +707,9 @@ mod tests {
     use ratatui::text::Span;
     use rstest::rstest;
     use serde::Serialize;
-    use slumber_core::collection::ProfileId;
-    use slumber_util::assert_matches;
+    use slumber_config::Config;
+    use slumber_core::collection::{Collection, ProfileId};
+    use slumber_util::{Factory, assert_matches};
     use std::{collections::HashSet, ops::Deref};
     use terminput::KeyCode;
 
@@ -805,6 +809,28 @@ mod tests {
         ]);
     }
 
+    /// With color disabled, the selected item is marked with text
+    #[rstest]
+    fn test_no_color(#[with(14, 2)] terminal: TestTerminal) {
+        let mut config = Config::default();
+        config.tui.theme.color = false;
+        let harness = TestHarness::with_config(Collection::factory(()), config);
+        let styles = ViewContext::styles().list;
+        let select: Select<&str, ListState> =
+            Select::builder(vec!["one", "two"]).build();
+        let mut component =
+            TestComponent::new::<SelectListProps>(&harness, &terminal, select);
+        component
+            .int::<SelectListProps>()
+            .drain_draw()
+            .assert()
+            .empty();
+        terminal.assert_buffer_lines([
+            "[SELECTED] one".set_style(styles.highlight),
+            "           two".into(),
+        ]);
+    }
+
     /// Apply a filter during build
     #[rstest]
     #[case::no_match("mango", None, &[], None)]
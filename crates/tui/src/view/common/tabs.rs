@@ -39,6 +39,11 @@ impl<K: PersistentKey<Value = T>, T: FixedSelectItem> Tabs<K, T> {
     pub fn selected(&self) -> T {
         self.select.selected()
     }
+
+    /// Select a specific tab, overriding the persisted selection
+    pub fn select(&mut self, tab: &T) {
+        self.select.select(tab);
+    }
 }
 
 impl<K: PersistentKey<Value = T>, T: FixedSelectItem> Component for Tabs<K, T> {
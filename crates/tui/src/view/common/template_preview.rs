@@ -1,7 +1,7 @@
 use crate::{
     message::Message,
     view::{
-        UpdateContext, ViewContext,
+        Generate, UpdateContext, ViewContext,
         component::{Component, ComponentId},
         event::{BroadcastEvent, Emitter, Event, EventMatch, ToEmitter},
         persistent::{PersistentStore, SessionKey},
@@ -12,7 +12,9 @@ use ratatui::{
     text::{Line, Span, Text},
 };
 use slumber_template::{LazyValue, RenderedChunk, RenderedOutput, Template};
-use std::ops::Deref;
+use slumber_util::{TimeSpan, tr};
+use std::{error::Error, ops::Deref, time::Duration};
+use tokio_util::sync::CancellationToken;
 
 /// Generate template preview text
 ///
@@ -30,6 +32,11 @@ use std::ops::Deref;
 /// In addition to handling the preview, this also handles template overriding.
 /// Use [Self::set_override] and [Self::reset_override] to modify the override.
 ///
+/// Only one render task can be active per preview at a time. When the template
+/// changes or the preview is refreshed, the previous task is cancelled. The
+/// task is also cancelled when the component is dropped, e.g. when the user
+/// selects a different recipe, so stale previews don't waste any work.
+///
 /// `PK` is the persistent key used to store override state in the session store
 #[derive(Debug)]
 pub struct TemplatePreview<PK> {
@@ -46,6 +53,8 @@ pub struct TemplatePreview<PK> {
     /// template will be rendered to a stream if possible and its metadata will
     /// be displayed rather than the resolved value.
     can_stream: bool,
+    /// Token to cancel the in-flight render task, if any
+    cancel_token: Option<CancellationToken>,
 }
 
 impl<PK> TemplatePreview<PK> {
@@ -68,13 +77,14 @@ impl<PK> TemplatePreview<PK> {
         PK: SessionKey<Value = Template>,
     {
         let override_template = PersistentStore::get_session(&persistent_key);
-        let slf = Self {
+        let mut slf = Self {
             id: ComponentId::new(),
             original_template: template,
             override_template,
             persistent_key,
             emitter: Emitter::default(),
             can_stream,
+            cancel_token: None,
         };
         slf.render_preview(); // Render preview in the background
         slf
@@ -113,7 +123,10 @@ impl<PK> TemplatePreview<PK> {
         // The template has changed, so we should show the raw template while
         // the preview is rendering
         let raw_text = self.render_raw();
-        self.emitter.emit(TemplatePreviewEvent(raw_text));
+        self.emitter.emit(TemplatePreviewEvent {
+            text: raw_text,
+            error: None,
+        });
 
         self.render_preview();
     }
@@ -129,31 +142,55 @@ impl<PK> TemplatePreview<PK> {
         Text::styled(self.template().display().to_string(), self.style())
     }
 
-    /// Send a message to render a preview of the template in the background
+    /// Send a message to render a preview of the template in the background.
+    /// Any previous render task for this preview will be cancelled.
     ///
     /// If preview rendering is disabled or the template is static, this will
     /// do nothing.
-    fn render_preview(&self) {
+    fn render_preview(&mut self) {
+        self.cancel_render();
+
         // If preview is disabled or the template is static, can skip the work
         let config = &ViewContext::config();
 
         if config.tui.preview_templates && self.template().is_dynamic() {
             let emitter = self.emitter;
             let style = self.style();
-            let on_complete = move |output| {
+            let on_complete = move |output: RenderedOutput| {
+                // Grab the error before the output is consumed. If a
+                // fallback default was used, show the error it replaced
+                let error = output
+                    .error()
+                    .or_else(|| output.fallback_error())
+                    .map(|error| {
+                        (error as &dyn Error)
+                            .generate()
+                            .set_style(ViewContext::styles().text.error)
+                    });
                 // Stitch the output together into Text
                 let text = TextStitcher::stitch_chunks(output).set_style(style);
 
                 // We can emit the event directly from the callback because
                 // the task is run on a local set
-                emitter.emit(TemplatePreviewEvent(text));
+                emitter.emit(TemplatePreviewEvent { text, error });
             };
 
+            let cancel_token = CancellationToken::new();
             ViewContext::send_message(Message::TemplatePreview {
                 template: self.template().clone(),
                 can_stream: self.can_stream,
+                cancel_token: cancel_token.clone(),
                 on_complete: Box::new(on_complete),
             });
+            self.cancel_token = Some(cancel_token);
+        }
+    }
+
+    /// Cancel the in-flight render task, if any. The task's output is no
+    /// longer needed, so there's no point in finishing it.
+    fn cancel_render(&mut self) {
+        if let Some(cancel_token) = self.cancel_token.take() {
+            cancel_token.cancel();
         }
     }
 
@@ -197,6 +234,13 @@ where
     }
 }
 
+impl<PK> Drop for TemplatePreview<PK> {
+    fn drop(&mut self) {
+        // Nobody's around to see the preview anymore
+        self.cancel_render();
+    }
+}
+
 impl<PK> ToEmitter<TemplatePreviewEvent> for TemplatePreview<PK> {
     fn to_emitter(&self) -> Emitter<TemplatePreviewEvent> {
         self.emitter
@@ -205,7 +249,13 @@ impl<PK> ToEmitter<TemplatePreviewEvent> for TemplatePreview<PK> {
 
 /// Emitted event from [TemplatePreview] containing rendered text for a template
 #[derive(Debug)]
-pub struct TemplatePreviewEvent(pub Text<'static>);
+pub struct TemplatePreviewEvent {
+    pub text: Text<'static>,
+    /// Full error chain, if any part of the template failed to render. The
+    /// preview text only shows a placeholder for the failed chunk. This
+    /// includes errors that were replaced by a `fallback()` default
+    pub error: Option<Text<'static>>,
+}
 
 /// A helper for stitching rendered template chunks into ratatui `Text`. This
 /// requires some effort because ratatui *loves* line breaks, so we have to
@@ -230,14 +280,52 @@ impl TextStitcher {
         // manually split the lines
         let mut stitcher = Self::default();
         for chunk in chunks {
-            let style = match chunk {
+            let style = match &chunk {
                 RenderedChunk::Raw(_) => Style::default(),
-                RenderedChunk::Rendered { .. } => styles.template_preview.text,
+                // Highlight defaults that were used in place of an error, so
+                // the user knows something went wrong
+                RenderedChunk::Rendered(LazyValue::Fallback { .. }) => {
+                    styles.template_preview.fallback
+                }
+                RenderedChunk::Rendered(LazyValue::Nested(output))
+                    if output.fallback_error().is_some() =>
+                {
+                    styles.template_preview.fallback
+                }
+                // Values from a cache may be stale, so mark them as such
+                RenderedChunk::Rendered(LazyValue::Cached { .. }) => {
+                    styles.template_preview.cached
+                }
+                RenderedChunk::Rendered(LazyValue::Nested(output))
+                    if output.cached_age().is_some() =>
+                {
+                    styles.template_preview.cached
+                }
+                RenderedChunk::Rendered(_) => styles.template_preview.text,
                 RenderedChunk::Error(_) => styles.template_preview.error,
             };
+            let cached_age = match &chunk {
+                RenderedChunk::Rendered(LazyValue::Cached { age, .. }) => {
+                    Some(*age)
+                }
+                RenderedChunk::Rendered(LazyValue::Nested(output)) => {
+                    output.cached_age()
+                }
+                _ => None,
+            };
             let chunk_text = Self::get_chunk_text(chunk);
 
             stitcher.add_chunk(chunk_text, style);
+            // Tell the user how fresh the cached value is, so they know
+            // whether they need to clear the cache
+            if let Some(age) = cached_age {
+                // Sub-second precision is just noise
+                let age = TimeSpan::from(Duration::from_secs(age.as_secs()));
+                stitcher.text.push_span(Span::styled(
+                    format!(" {}", tr!("template-preview-cached", age = age)),
+                    styles.text.hint,
+                ));
+            }
         }
         stitcher.text
     }
@@ -282,7 +370,9 @@ impl TextStitcher {
         match chunk {
             RenderedChunk::Raw(text) => text.deref().into(),
             RenderedChunk::Rendered(lazy) => match lazy {
-                LazyValue::Value(value) => {
+                LazyValue::Value(value)
+                | LazyValue::Fallback { value, .. }
+                | LazyValue::Cached { value, .. } => {
                     // We could potentially use MaybeStr to show binary data as
                     // hex, but that could get weird if there's text data in the
                     // template as well. This is simpler and prevents giant
@@ -317,7 +407,7 @@ mod tests {
     use rstest::rstest;
     use slumber_core::{
         collection::{Collection, Profile},
-        render::TemplateContext,
+        render::{CommandCache, TemplateContext},
         test_util::by_id,
     };
     use slumber_util::{Factory, assert_matches};
@@ -349,6 +439,35 @@ mod tests {
         }
     }
 
+    /// Changing the template or dropping the preview should cancel the
+    /// in-flight render task
+    #[rstest]
+    fn test_cancel(mut harness: TestHarness) {
+        let get_token = |harness: &mut TestHarness| {
+            assert_matches!(
+                harness.messages().pop_now(),
+                Message::TemplatePreview { cancel_token, .. } => cancel_token
+            )
+        };
+
+        let mut preview = TemplatePreview::new(
+            TestKey,
+            "{{ dynamic }}".parse().unwrap(),
+            false,
+        );
+        let first = get_token(&mut harness);
+        assert!(!first.is_cancelled());
+
+        // Override triggers a new render and cancels the old one
+        preview.set_override("{{ other }}".parse().unwrap());
+        let second = get_token(&mut harness);
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+
+        drop(preview);
+        assert!(second.is_cancelled());
+    }
+
     /// Test line breaks, multi-byte characters, and binary data
     #[rstest]
     #[case::line_breaks(
@@ -379,6 +498,14 @@ mod tests {
         r"binary data: {{ b'\xc3\x28' }}",
         vec![Line::from(vec![Span::raw("binary data: "), rendered("<binary>")])]
     )]
+    #[case::fallback(
+        "{{ fallback('ok', simple) }} {{ unknown | fallback('default') }}",
+        vec![Line::from(vec![
+            rendered("ww"),
+            Span::raw(" "),
+            fallback("default"),
+        ])]
+    )]
     #[tokio::test]
     async fn test_template_stitch(
         _harness: TestHarness,
@@ -409,6 +536,43 @@ mod tests {
         assert_eq!(text, Text::from(expected));
     }
 
+    /// Cached command output is styled differently and labelled with its age
+    #[cfg(unix)]
+    #[rstest]
+    #[tokio::test]
+    async fn test_template_stitch_cached(_harness: TestHarness) {
+        let template: Template = "{{ command(['printf', 'hi'], cache='1h') }}"
+            .parse()
+            .unwrap();
+        let command_cache = CommandCache::default();
+        let context = || TemplateContext {
+            command_cache: command_cache.clone(),
+            ..TemplateContext::factory(())
+        };
+
+        // First render runs the command
+        let chunks = template.render(&context().streaming(false)).await;
+        let text = TextStitcher::stitch_chunks(chunks);
+        assert_eq!(text, Text::from(Line::from(rendered("hi"))));
+
+        // Second render loads from the cache
+        let chunks = template.render(&context().streaming(false)).await;
+        let text = TextStitcher::stitch_chunks(chunks);
+        assert_eq!(
+            text,
+            Text::from(Line::from(vec![
+                Span::styled(
+                    "hi",
+                    ViewContext::styles().template_preview.cached
+                ),
+                Span::styled(
+                    " (cached 0s ago)",
+                    ViewContext::styles().text.hint
+                ),
+            ]))
+        );
+    }
+
     /// Style some text as rendered
     fn rendered(text: &str) -> Span<'_> {
         Span::styled(text, ViewContext::styles().template_preview.text)
@@ -418,4 +582,9 @@ mod tests {
     fn error(text: &str) -> Span<'_> {
         Span::styled(text, ViewContext::styles().template_preview.error)
     }
+
+    /// Style some text as a fallback default
+    fn fallback(text: &str) -> Span<'_> {
+        Span::styled(text, ViewContext::styles().template_preview.fallback)
+    }
 }
@@ -106,7 +106,8 @@ impl TextBox {
     /// [Self::text] for strings that will *not* be displayed.
     pub fn display_text(&self) -> Cow<'_, str> {
         if self.sensitive {
-            Masked::new(&self.state.text, '•').into()
+            Masked::new(&self.state.text, ViewContext::styles().symbols.mask)
+                .into()
         } else {
             self.state.text.as_str().into()
         }
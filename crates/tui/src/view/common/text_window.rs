@@ -1,19 +1,27 @@
-use crate::view::{
-    common::scrollbar::Scrollbar,
-    component::{Canvas, Component, ComponentId, Draw, DrawMetadata},
-    context::{UpdateContext, ViewContext},
-    event::{Event, EventMatch},
+use crate::{
+    input::InputEvent,
+    message::Message,
+    view::{
+        common::scrollbar::Scrollbar,
+        component::{Canvas, Component, ComponentId, Draw, DrawMetadata},
+        context::{UpdateContext, ViewContext},
+        event::{Event, EventMatch},
+        persistent::{PersistentStore, SessionKey},
+        util::render_cache::{Generation, RenderCache},
+    },
 };
 use ratatui::{
     buffer::Buffer,
     layout::{Layout, Rect, Size},
-    prelude::{Alignment, Constraint},
-    text::{Line, StyledGrapheme, Text},
+    prelude::Constraint,
+    text::{StyledGrapheme, Text},
     widgets::{ScrollbarOrientation, Widget},
 };
 use slumber_config::Action;
-use std::{cell::Cell, cmp};
-use terminput::ScrollDirection;
+use slumber_core::http::RequestId;
+use slumber_util::tr;
+use std::{cell::Cell, cmp, collections::BTreeMap, fmt::Write};
+use terminput::{KeyCode, ScrollDirection};
 use unicode_width::UnicodeWidthStr;
 
 /// A scrollable (but not editable) block of text
@@ -35,6 +43,24 @@ pub struct TextWindow {
     /// `(horizontal, vertical)` scroll. In a `Cell` because it may be clamped
     /// if the window size changes
     offset: Cell<Offset>,
+    /// Per-line changes relative to a previous version of the text, drawn
+    /// between the gutter and the text. Empty if there's nothing to compare to
+    diff: Vec<Option<DiffMarker>>,
+    /// Named positions that the user can jump between
+    bookmarks: Bookmarks,
+    /// If set, bookmarks are restored from and saved to the session store
+    bookmark_key: Option<BookmarkKey>,
+    /// A bookmark action is waiting for the next key press to name the mark
+    pending_bookmark: Option<PendingBookmark>,
+    /// Soft-wrap lines that are wider than the window, instead of scrolling
+    /// horizontally
+    wrap: bool,
+    /// Bumped whenever something drawn in the gutter changes. The text and
+    /// diff markers never change, so they don't need one
+    generation: Generation,
+    /// Everything from the last draw. Redrawn only when the window is
+    /// scrolled, resized, or the gutter changes
+    render_cache: RenderCache<(Generation, Offset, bool, ScrollbarMargins)>,
 }
 
 impl TextWindow {
@@ -47,9 +73,38 @@ impl TextWindow {
             text_size,
             window_size: Default::default(),
             offset: Default::default(),
+            diff: Vec::new(),
+            bookmarks: Bookmarks::default(),
+            bookmark_key: None,
+            pending_bookmark: None,
+            wrap: false,
+            generation: Generation::default(),
+            render_cache: RenderCache::default(),
         }
     }
 
+    /// Mark lines that changed relative to a previous version of the text.
+    /// There should be one marker per line; see [diff_lines]
+    pub fn with_diff(mut self, diff: Vec<Option<DiffMarker>>) -> Self {
+        self.diff = diff;
+        self
+    }
+
+    /// Restore bookmarks from the session store. Bookmarks will be saved back
+    /// to the store under the same key as they change.
+    pub fn with_bookmarks(mut self, key: BookmarkKey) -> Self {
+        self.bookmarks = PersistentStore::get_session(&key).unwrap_or_default();
+        self.bookmark_key = Some(key);
+        self
+    }
+
+    /// Enable/disable soft-wrapping of long lines. While wrapping, the text
+    /// only scrolls vertically
+    pub fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+        self.offset.get_mut().x = 0;
+    }
+
     /// Get the full text
     pub fn text(&self) -> &Text<'static> {
         &self.text
@@ -59,13 +114,30 @@ impl TextWindow {
     /// line of the last page of text
     fn max_scroll_line(&self) -> usize {
         let text_height = self.text_size.height;
-        let window_height = self.window_size.get().height as usize;
-        text_height.saturating_sub(window_height)
+        let window_size = self.window_size.get();
+        if self.wrap {
+            // Lines can take up multiple rows, so walk back from the end until
+            // the last page is full
+            let width = usize::from(window_size.width).max(1);
+            let mut rows = 0;
+            for (i, line) in self.text.lines.iter().enumerate().rev() {
+                rows += line.width().div_ceil(width).max(1);
+                if rows > usize::from(window_size.height) {
+                    return i + 1;
+                }
+            }
+            0
+        } else {
+            text_height.saturating_sub(window_size.height.into())
+        }
     }
 
     /// Get the final column that we can't scroll (horizontally) past. This will
     /// be the left edge of the rightmost "page" of text
     fn max_scroll_column(&self) -> usize {
+        if self.wrap {
+            return 0;
+        }
         let text_width = self.text_size.width;
         let window_width = self.window_size.get().width as usize;
         text_width.saturating_sub(window_width)
@@ -95,6 +167,47 @@ impl TextWindow {
             cmp::min(self.offset.get().x + columns, self.max_scroll_column());
     }
 
+    /// Handle the key pressed after a bookmark action. Letters name a bookmark.
+    /// Pressing the jump action again goes back to where the last jump started.
+    /// Any other key cancels.
+    fn handle_bookmark_key(
+        &mut self,
+        pending: PendingBookmark,
+        code: KeyCode,
+        action: Option<Action>,
+    ) {
+        let line = self.offset.get().y;
+        match (pending, code) {
+            (PendingBookmark::Jump, _)
+                if action == Some(Action::JumpToBookmark) =>
+            {
+                if let Some(target) = self.bookmarks.jumps.pop() {
+                    self.scroll_to(target);
+                }
+            }
+            (PendingBookmark::Set, KeyCode::Char(c))
+                if c.is_ascii_alphabetic() =>
+            {
+                self.bookmarks.marks.insert(c, line);
+                self.generation.bump();
+            }
+            (PendingBookmark::Jump, KeyCode::Char(c))
+                if c.is_ascii_alphabetic() =>
+            {
+                if let Some(&target) = self.bookmarks.marks.get(&c) {
+                    self.bookmarks.jumps.push(line);
+                    self.scroll_to(target);
+                } else {
+                    ViewContext::send_message(Message::Notify(tr!(
+                        "notify-bookmark-unset",
+                        mark = c
+                    )));
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// Ensure the scroll state is valid. Called on every render, in case the
     /// text size or draw area changed
     fn clamp_scroll(&self) {
@@ -109,36 +222,99 @@ impl TextWindow {
     /// all this functionality out of the box, but it needs an owned Text and
     /// we only have a reference. A clone could potentially be very expensive
     /// for a large body, so we use our own logic.
-    fn render_text(&self, buf: &mut Buffer, area: Rect) {
+    ///
+    /// Return the index of the line drawn on each row. Rows that continue a
+    /// wrapped line are `None`.
+    fn render_text(&self, buf: &mut Buffer, area: Rect) -> Vec<Option<usize>> {
         let offset = self.offset.get();
-        let window_size = self.window_size.get();
-        let lines = self
-            .text
-            .lines
-            .iter()
-            .skip(offset.y)
-            .take(window_size.height.into())
-            .enumerate();
-        for (y, line) in lines {
+        let height = usize::from(area.height);
+        let mut rows = Vec::with_capacity(height);
+        for (i, line) in self.text.lines.iter().enumerate().skip(offset.y) {
+            if rows.len() >= height {
+                break;
+            }
+            rows.push(Some(i));
             // This could be expensive if we're skipping a lot of graphemes,
             // i.e. scrolled far to the right in a wide body. Fortunately that's
             // a niche use case so not optimized for yet. To fix this we would
             // have to map grapheme number -> byte offset and cache that,
             // because skipping bytes is O(1) instead of O(n)
-            let graphemes = line
-                .styled_graphemes(self.text.style)
-                .skip(offset.x)
-                .take(window_size.width.into());
+            let graphemes =
+                line.styled_graphemes(self.text.style).skip(offset.x);
             let mut x = 0;
             for StyledGrapheme { symbol, style } in graphemes {
-                if x >= area.width {
+                let width = symbol.width() as u16;
+                if self.wrap && x > 0 && x + width > area.width {
+                    // Out of room, continue on the next row
+                    if rows.len() >= height {
+                        break;
+                    }
+                    rows.push(None);
+                    x = 0;
+                } else if x >= area.width {
                     break;
                 }
-                buf[(area.left() + x, area.top() + y as u16)]
+                let y = area.top() + (rows.len() - 1) as u16;
+                buf[(area.left() + x, y)]
                     .set_symbol(symbol)
                     .set_style(style);
-                x += symbol.width() as u16;
+                x += width;
+            }
+        }
+        rows
+    }
+
+    /// Render diff markers for the visible lines into a single column
+    fn render_diff(
+        &self,
+        buf: &mut Buffer,
+        area: Rect,
+        rows: &[Option<usize>],
+    ) {
+        let styles = &ViewContext::styles().text_window;
+        for (y, line) in (area.top()..area.bottom()).zip(rows) {
+            let marker = line.and_then(|line| self.diff.get(line)).copied();
+            let (symbol, style) = match marker.flatten() {
+                Some(DiffMarker::Added) => ("+", styles.diff_added),
+                Some(DiffMarker::Changed) => ("~", styles.diff_changed),
+                Some(DiffMarker::Removed) => ("-", styles.diff_removed),
+                None => continue,
+            };
+            buf[(area.left(), y)].set_symbol(symbol).set_style(style);
+        }
+    }
+
+    /// Render vertical/horizontal scrollbars, if the text overflows the window
+    fn render_scrollbars(
+        &self,
+        buf: &mut Buffer,
+        area: Rect,
+        margins: &ScrollbarMargins,
+    ) {
+        let has_horizontal_scroll =
+            !self.wrap && self.text_size.width > area.width.into();
+        let max_scroll_line = self.max_scroll_line();
+        let offset = self.offset.get();
+        if max_scroll_line > 0 {
+            Scrollbar {
+                // Wrapped lines take up multiple rows, so measure the content
+                // in scroll positions rather than lines
+                content_length: max_scroll_line + usize::from(area.height),
+                offset: offset.y,
+                margin: margins.right,
+                ..Default::default()
+            }
+            .render(area, buf);
+        }
+        if has_horizontal_scroll {
+            Scrollbar {
+                content_length: self.text_size.width,
+                offset: offset.x,
+                orientation: ScrollbarOrientation::HorizontalBottom,
+                margin: margins.bottom,
+                invert: false,
             }
+            .render(area, buf);
         }
     }
 }
@@ -157,6 +333,21 @@ impl Component for TextWindow {
     fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
         event
             .m()
+            // After a bookmark action, the next key names the bookmark. Grab
+            // it before it can trigger any other action
+            .any(|event| match (self.pending_bookmark.take(), event) {
+                (
+                    Some(pending),
+                    Event::Input(InputEvent::Key { code, action, .. }),
+                ) => {
+                    self.handle_bookmark_key(pending, code, action);
+                    None
+                }
+                (pending, event) => {
+                    self.pending_bookmark = pending;
+                    Some(event)
+                }
+            })
             // Scroll for scroll wheel OR keyboard inputs
             .scroll(|direction| match direction {
                 ScrollDirection::Up => self.scroll_up(1),
@@ -180,9 +371,21 @@ impl Component for TextWindow {
                 Action::Home => self.scroll_to(0),
                 // Clamping will limit this at the last line
                 Action::End => self.scroll_to(usize::MAX),
+                Action::SetBookmark => {
+                    self.pending_bookmark = Some(PendingBookmark::Set);
+                }
+                Action::JumpToBookmark => {
+                    self.pending_bookmark = Some(PendingBookmark::Jump);
+                }
                 _ => propagate.set(),
             })
     }
+
+    fn persist(&self, store: &mut PersistentStore) {
+        if let Some(key) = &self.bookmark_key {
+            store.set_session(key.clone(), self.bookmarks.clone());
+        }
+    }
 }
 
 /// `T` has to be convertible to text to be drawn
@@ -193,13 +396,8 @@ impl Draw<TextWindowProps> for TextWindow {
         props: TextWindowProps,
         metadata: DrawMetadata,
     ) {
-        let gutter = Gutter {
-            text_size: self.text_size,
-            offset: self.offset.get(),
-        };
-
-        let [gutter_area, _, text_area] = Layout::horizontal([
-            Constraint::Length(gutter.width()),
+        let [gutter_area, diff_area, text_area] = Layout::horizontal([
+            Constraint::Length(Gutter::width(self.text_size)),
             Constraint::Length(1), // Spacer
             Constraint::Min(0),
         ])
@@ -210,39 +408,36 @@ impl Draw<TextWindowProps> for TextWindow {
         self.window_size.set(window_size);
         self.clamp_scroll(); // Revalidate scroll state if window size changes
 
-        // Draw gutter and text
-        canvas.render_widget(gutter, gutter_area);
-        self.render_text(canvas.buffer_mut(), text_area);
-
-        // Scrollbars
-        let has_horizontal_scroll =
-            self.text_size.width > window_size.width.into();
-        let has_vertical_scroll =
-            self.text_size.height > window_size.height.into();
-        let offset = self.offset.get();
-        if has_vertical_scroll {
-            canvas.render_widget(
-                Scrollbar {
-                    content_length: self.text_size.height,
-                    offset: offset.y,
-                    margin: props.margins.right,
-                    ..Default::default()
-                },
-                text_area,
-            );
-        }
-        if has_horizontal_scroll {
-            canvas.render_widget(
-                Scrollbar {
-                    content_length: self.text_size.width,
-                    offset: offset.x,
-                    orientation: ScrollbarOrientation::HorizontalBottom,
-                    margin: props.margins.bottom,
-                    invert: false,
-                },
-                text_area,
-            );
-        }
+        // Scrollbars can be drawn outside the window, so cache that area too
+        let area = metadata.area();
+        let cache_area = Rect {
+            width: area.width + u16::try_from(props.margins.right).unwrap_or(0),
+            height: area.height
+                + u16::try_from(props.margins.bottom).unwrap_or(0),
+            ..area
+        };
+        // Everything drawn depends only on the scroll state, the gutter, and
+        // the area. Most frames are drawn without touching the window, in
+        // which case the cells are copied from the last frame instead
+        let key = (
+            self.generation,
+            self.offset.get(),
+            self.wrap,
+            props.margins.clone(),
+        );
+        self.render_cache
+            .render(key, cache_area, canvas.buffer_mut(), |buf| {
+                // Draw text, then the gutter and diff based on where each line
+                // landed
+                let rows = self.render_text(buf, text_area);
+                Gutter {
+                    rows: &rows,
+                    bookmarks: &self.bookmarks,
+                }
+                .render(gutter_area, buf);
+                self.render_diff(buf, diff_area, &rows);
+                self.render_scrollbars(buf, text_area, &props.margins);
+            });
     }
 }
 
@@ -257,7 +452,7 @@ pub struct TextWindowProps {
 /// 0 uses the outermost row/column of the text area. Positive values
 /// pushes the scrollbar outside the rendered outside, negative moves
 /// it inside.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ScrollbarMargins {
     pub right: i32,
     pub bottom: i32,
@@ -273,33 +468,54 @@ impl Default for ScrollbarMargins {
 }
 
 /// Widget to draw line numbers in the left gutter
-struct Gutter {
-    text_size: TextSize,
-    offset: Offset,
+struct Gutter<'a> {
+    /// Index of the line on each row. Continuation rows of a wrapped line
+    /// don't get a number
+    rows: &'a [Option<usize>],
+    /// Bookmarked lines are highlighted
+    bookmarks: &'a Bookmarks,
 }
 
-impl Gutter {
-    fn width(&self) -> u16 {
+impl Gutter<'_> {
+    fn width(text_size: TextSize) -> u16 {
         // Width is the number of digits in the biggest number
-        (self.text_size.height as f32).log10().floor() as u16 + 1
+        (text_size.height as f32).log10().floor() as u16 + 1
     }
 }
 
-impl Widget for Gutter {
+impl Widget for Gutter<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let styles = ViewContext::styles();
-        // Draw line numbers in the gutter
-        let first_line = self.offset.y + 1;
-        let last_line = cmp::min(
-            self.text_size.height,
-            first_line + usize::from(area.height),
-        );
-        let text = (first_line..=last_line)
-            .map(|n| Line::from(n.to_string()))
-            .collect::<Text>()
-            .alignment(Alignment::Right)
-            .style(styles.text_window.gutter);
-        text.render(area, buf);
+        let styles = &ViewContext::styles().text_window;
+        let style = styles.gutter;
+        // Draw line numbers in the gutter. This is redrawn on every scroll, so
+        // write directly to the buffer with a single reused string instead of
+        // building a Text
+        buf.set_style(area, style);
+        let mut number = String::new();
+        for (y, line) in (area.top()..area.bottom()).zip(self.rows) {
+            let Some(line) = line else {
+                continue;
+            };
+            number.clear();
+            let _ = write!(number, "{}", line + 1);
+            // Right-align the number
+            let x = area.right().saturating_sub(number.len() as u16);
+            let style = if self.bookmarks.contains_line(*line) {
+                // Highlight the entire width, so it stands out
+                buf.set_style(
+                    Rect {
+                        y,
+                        height: 1,
+                        ..area
+                    },
+                    styles.bookmark,
+                );
+                styles.bookmark
+            } else {
+                style
+            };
+            buf.set_string(cmp::max(x, area.left()), y, &number, style);
+        }
     }
 }
 
@@ -329,13 +545,113 @@ impl TextSize {
                 width = width.max(line.width());
             }
         }
-        let width = lines.iter().map(Line::width).max().unwrap_or_default();
         // Assume no line wrapping when calculating line count
         let height = lines.len();
         Self { width, height }
     }
 }
 
+/// Named positions in a [TextWindow], plus a history of jumps between them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bookmarks {
+    /// Line at the top of the window when each bookmark was set
+    marks: BTreeMap<char, usize>,
+    /// Lines that we jumped away from, most recent last
+    jumps: Vec<usize>,
+}
+
+impl Bookmarks {
+    /// Is there a bookmark on the given line (0-indexed)?
+    fn contains_line(&self, line: usize) -> bool {
+        self.marks.values().any(|marked| *marked == line)
+    }
+}
+
+/// Session key for the bookmarks of a response body. Bookmarks are kept per
+/// exchange, so they don't carry over to the next response for a recipe.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BookmarkKey(pub RequestId);
+
+impl SessionKey for BookmarkKey {
+    type Value = Bookmarks;
+}
+
+/// A bookmark action that's waiting for the user to name the bookmark
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum PendingBookmark {
+    Set,
+    Jump,
+}
+
+/// How a line of text differs from the previous version of the text
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DiffMarker {
+    /// Line is new
+    Added,
+    /// Line replaced a line in the previous version
+    Changed,
+    /// One or more lines were removed immediately before this line
+    Removed,
+}
+
+/// Compare two versions of some text line-by-line, and get a marker for each
+/// line of the new text. Return `None` if the texts are too different to
+/// compare cheaply. The diff algorithm is quadratic in the number of changed
+/// lines, so we don't want to run it on two totally different large bodies.
+pub fn diff_lines(old: &str, new: &str) -> Option<Vec<Option<DiffMarker>>> {
+    /// Maximum size of the diff table, in cells. Each cell is 4 bytes
+    const MAX_CELLS: usize = 4_000_000;
+
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // Only the middle section between the common prefix and suffix needs to be
+    // diffed. The diff crate does this internally too, but we need to know the
+    // size ahead of time
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+    if old_middle.len().saturating_mul(new_middle.len()) > MAX_CELLS {
+        return None;
+    }
+
+    let mut markers = vec![None; prefix];
+    // Removed lines that haven't been paired up with an added line yet
+    let mut removed = 0;
+    for result in diff::slice(old_middle, new_middle) {
+        match result {
+            diff::Result::Left(_) => removed += 1,
+            // An added line that follows a removed one is a change
+            diff::Result::Right(_) if removed > 0 => {
+                removed -= 1;
+                markers.push(Some(DiffMarker::Changed));
+            }
+            diff::Result::Right(_) => markers.push(Some(DiffMarker::Added)),
+            diff::Result::Both(_, _) => {
+                markers.push((removed > 0).then_some(DiffMarker::Removed));
+                removed = 0;
+            }
+        }
+    }
+    // Mark leftover removals on the next unchanged line. If there isn't one,
+    // fall back to the last line
+    let mut suffix_markers = vec![None; suffix];
+    if removed > 0 {
+        if let Some(marker) = suffix_markers.first_mut() {
+            *marker = Some(DiffMarker::Removed);
+        } else if let Some(marker @ None) = markers.last_mut() {
+            *marker = Some(DiffMarker::Removed);
+        }
+    }
+    markers.extend(suffix_markers);
+    Some(markers)
+}
+
 /// Horizontal/vertical scroll offset
 #[derive(Copy, Clone, Debug, Default, PartialEq)]
 struct Offset {
@@ -522,6 +838,162 @@ mod tests {
         assert_eq!(component.offset.get(), Offset { x: 8, y: 1 });
     }
 
+    /// Wrapped lines continue on the next row without a line number, and
+    /// horizontal scrolling is disabled
+    #[rstest]
+    fn test_wrap(#[with(11, 4)] terminal: TestTerminal, harness: TestHarness) {
+        let text = Text::from("one\nline two is long\nthree\nfour");
+        let props = TextWindowProps {
+            margins: ScrollbarMargins {
+                right: 0,
+                bottom: 0,
+            },
+        };
+        let mut text_window = TextWindow::new(text);
+        text_window.set_wrap(true);
+        let mut component =
+            TestComponent::builder(&harness, &terminal, text_window)
+                .with_props(props.clone())
+                .build();
+        terminal.assert_buffer_lines([
+            vec![line_num(1), " one     ▲".into()],
+            vec![line_num(2), " line two█".into()],
+            vec![line_num(0), " is long █".into()],
+            vec![line_num(3), " three   ▼".into()],
+        ]);
+
+        component
+            .int_props(|| props.clone())
+            .send_key_modifiers(KeyCode::Right, KeyModifiers::SHIFT)
+            .send_key(KeyCode::End)
+            .assert()
+            .empty();
+        assert_eq!(component.offset.get(), Offset { x: 0, y: 1 });
+        terminal.assert_buffer_lines([
+            vec![line_num(2), " line two▲".into()],
+            vec![line_num(0), " is long █".into()],
+            vec![line_num(3), " three   █".into()],
+            vec![line_num(4), " four    ▼".into()],
+        ]);
+    }
+
+    /// Set bookmarks, jump between them, and persist them in the session
+    #[rstest]
+    fn test_bookmarks(
+        #[with(10, 3)] terminal: TestTerminal,
+        harness: TestHarness,
+    ) {
+        let key = BookmarkKey(RequestId::new());
+        let text: Text = (1..=10).map(|n| n.to_string()).collect();
+        let props = TextWindowProps {
+            margins: ScrollbarMargins {
+                right: 0,
+                bottom: 0,
+            },
+        };
+        let mut component = TestComponent::builder(
+            &harness,
+            &terminal,
+            TextWindow::new(text).with_bookmarks(key.clone()),
+        )
+        .with_props(props.clone())
+        .build();
+
+        // Set a bookmark at line 5. The letter shouldn't trigger any other
+        // action
+        component
+            .int_props(|| props.clone())
+            .send_keys([KeyCode::Down; 4])
+            .send_keys([KeyCode::Char('m'), KeyCode::Char('q')])
+            .send_key(KeyCode::Home)
+            .assert()
+            .empty();
+        assert_eq!(component.offset.get().y, 0);
+
+        // Jump to it, then jump back
+        component
+            .int_props(|| props.clone())
+            .send_keys([KeyCode::Char('\''), KeyCode::Char('q')])
+            .assert()
+            .empty();
+        assert_eq!(component.offset.get().y, 4);
+        let styles = &ViewContext::styles().text_window;
+        terminal.assert_buffer_lines([
+            vec![Span::styled(" 5", styles.bookmark), " 5     ▲".into()],
+            vec![Span::styled(" 6", styles.gutter), " 6     █".into()],
+            vec![Span::styled(" 7", styles.gutter), " 7     ▼".into()],
+        ]);
+        component
+            .int_props(|| props.clone())
+            .send_keys([KeyCode::Char('\''), KeyCode::Char('\'')])
+            .assert()
+            .empty();
+        assert_eq!(component.offset.get().y, 0);
+
+        // Bookmarks are restored from the session store
+        component.persist(&mut PersistentStore::new(harness.database.clone()));
+        let restored = TextWindow::new(Text::default()).with_bookmarks(key);
+        assert_eq!(restored.bookmarks.marks, [('q', 4)].into());
+    }
+
+    /// Changed lines are marked between the gutter and the text
+    #[rstest]
+    fn test_diff(#[with(10, 3)] terminal: TestTerminal, harness: TestHarness) {
+        let text = Text::from("a\nx\nc");
+        let diff = diff_lines("a\nb\nc\nd", "a\nx\nc").unwrap();
+        TestComponent::builder(
+            &harness,
+            &terminal,
+            TextWindow::new(text).with_diff(diff),
+        )
+        .with_props(TextWindowProps {
+            // Don't overflow the frame
+            margins: ScrollbarMargins {
+                right: 0,
+                bottom: 0,
+            },
+        })
+        .build();
+        let styles = &ViewContext::styles().text_window;
+        terminal.assert_buffer_lines([
+            vec![line_num(1), " a       ".into()],
+            vec![
+                line_num(2),
+                Span::styled("~", styles.diff_changed),
+                "x       ".into(),
+            ],
+            vec![
+                line_num(3),
+                Span::styled("-", styles.diff_removed),
+                "c       ".into(),
+            ],
+        ]);
+    }
+
+    #[rstest]
+    #[case::unchanged("a\nb", "a\nb", &[None, None])]
+    #[case::added("a\nc", "a\nb\nc", &[None, Some(DiffMarker::Added), None])]
+    #[case::changed(
+        "a\nb\nc",
+        "a\nx\nc",
+        &[None, Some(DiffMarker::Changed), None],
+    )]
+    #[case::changed_and_added(
+        "a\nb\nc",
+        "a\nx\ny\nc",
+        &[None, Some(DiffMarker::Changed), Some(DiffMarker::Added), None],
+    )]
+    #[case::removed("a\nb\nc", "a\nc", &[None, Some(DiffMarker::Removed)])]
+    #[case::removed_end("a\nb", "a", &[Some(DiffMarker::Removed)])]
+    #[case::all_new("", "a\nb", &[Some(DiffMarker::Added), Some(DiffMarker::Added)])]
+    fn test_diff_lines(
+        #[case] old: &str,
+        #[case] new: &str,
+        #[case] expected: &[Option<DiffMarker>],
+    ) {
+        assert_eq!(diff_lines(old, new).unwrap(), expected);
+    }
+
     /// Style some text as gutter line numbers
     fn line_num(n: u16) -> Span<'static> {
         let s = if n > 0 { n.to_string() } else { " ".into() };
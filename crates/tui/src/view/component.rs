@@ -1,21 +1,33 @@
+mod audit_log;
+mod captures;
+mod changelog;
 mod collection_select;
 mod command_text_box;
+mod cookies;
 mod editable_template;
 mod exchange_pane;
 mod footer;
 mod help;
 mod history;
 mod internal;
+mod json_path;
 mod misc;
+mod node_query;
+mod offline_queue;
 mod primary;
 mod profile;
+mod profile_switcher;
 mod prompt_form;
 mod queryable_body;
+mod recent_requests;
 mod recipe;
+mod request_chain;
 mod request_view;
+mod response_links;
 mod response_view;
 mod root;
 mod sidebar_list;
+mod toggles;
 
 pub use internal::{
     Canvas, Child, Component, ComponentExt, ComponentId, ComponentMap, Draw,
@@ -0,0 +1,100 @@
+//! Modal to browse the audit log for the current collection
+
+use crate::{
+    util::ResultReported,
+    view::{
+        ToStringGenerate, ViewContext,
+        common::{
+            modal::Modal,
+            select::{Select, SelectListProps},
+        },
+        component::{
+            Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+        },
+    },
+};
+use chrono::Local;
+use derive_more::Display;
+use ratatui::{layout::Constraint, text::Line};
+use slumber_core::render::AuditEvent;
+use slumber_util::tr;
+
+/// Modal listing recent external effects of template renders (commands run,
+/// files read, etc.), newest first
+#[derive(Debug)]
+pub struct AuditLog {
+    id: ComponentId,
+    select: Select<AuditLogItem>,
+}
+
+impl AuditLog {
+    /// Max number of events to load
+    const MAX_EVENTS: u32 = 100;
+    /// Max number of events to show at once
+    const MAX_HEIGHT: u16 = 20;
+
+    pub fn new() -> Self {
+        let events = ViewContext::with_database(|database| {
+            database.get_audit_events(Some(Self::MAX_EVENTS))
+        })
+        .reported(&ViewContext::messages_tx())
+        .unwrap_or_default();
+        let items = events.into_iter().map(AuditLogItem).collect();
+        Self {
+            id: ComponentId::default(),
+            select: Select::builder(items).build(),
+        }
+    }
+}
+
+impl Component for AuditLog {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for AuditLog {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        if self.select.is_empty() {
+            canvas.render_widget(
+                "No events recorded. Enable `audit_log` in the config to \
+                record render events",
+                metadata.area(),
+            );
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                metadata.area(),
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for AuditLog {
+    fn title(&self) -> Line<'_> {
+        tr!("modal-audit-log-title").into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (Constraint::Percentage(80), Constraint::Length(height))
+    }
+}
+
+#[derive(Debug, Display)]
+#[display(
+    "{}  {:<7}  {}{}",
+    _0.time.with_timezone(&Local).format("%F %T"),
+    _0.source,
+    _0.detail,
+    if _0.preview { " (preview)" } else { "" }
+)]
+struct AuditLogItem(AuditEvent);
+
+impl ToStringGenerate for AuditLogItem {}
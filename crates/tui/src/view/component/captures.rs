@@ -0,0 +1,89 @@
+//! Modal to browse values captured from responses in this session
+
+use crate::view::{
+    ToStringGenerate,
+    common::{
+        modal::Modal,
+        select::{Select, SelectListProps},
+    },
+    component::{
+        Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+    },
+};
+use derive_more::Display;
+use ratatui::{layout::Constraint, text::Line};
+use slumber_template::Value;
+use slumber_util::tr;
+
+/// Modal listing values captured via the recipe `capture` field, in the order
+/// they were first captured
+#[derive(Debug)]
+pub struct Captures {
+    id: ComponentId,
+    select: Select<CaptureItem>,
+}
+
+impl Captures {
+    /// Max number of captures to show at once
+    const MAX_HEIGHT: u16 = 20;
+
+    pub fn new(captures: Vec<(String, Value)>) -> Self {
+        let items = captures
+            .into_iter()
+            .map(|(name, value)| CaptureItem { name, value })
+            .collect();
+        Self {
+            id: ComponentId::default(),
+            select: Select::builder(items).build(),
+        }
+    }
+}
+
+impl Component for Captures {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for Captures {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        if self.select.is_empty() {
+            canvas.render_widget(
+                "No values captured. Add a `capture` field to a recipe to \
+                capture values from its responses",
+                metadata.area(),
+            );
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                metadata.area(),
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for Captures {
+    fn title(&self) -> Line<'_> {
+        tr!("modal-captures-title").into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (Constraint::Percentage(80), Constraint::Length(height))
+    }
+}
+
+#[derive(Debug, Display)]
+#[display("{name} = {value}")]
+struct CaptureItem {
+    name: String,
+    value: Value,
+}
+
+impl ToStringGenerate for CaptureItem {}
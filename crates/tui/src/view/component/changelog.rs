@@ -0,0 +1,175 @@
+//! Modal to browse the git history of the collection file
+
+use crate::view::{
+    ToStringGenerate,
+    common::{
+        modal::Modal,
+        select::{Select, SelectListProps},
+    },
+    component::{
+        Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+    },
+};
+use chrono::Local;
+use itertools::Itertools;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::Line,
+};
+use slumber_core::collection::{CollectionCommit, RecipeId};
+use slumber_util::tr;
+use std::fmt::{self, Display};
+
+/// Modal listing recent commits to the collection file, newest first. Each
+/// commit lists the recipes it was the last to change.
+#[derive(Debug)]
+pub struct Changelog {
+    id: ComponentId,
+    /// When the selected recipe was last changed, and by whom. `None` if no
+    /// recipe is selected, or it isn't in the loaded history
+    recipe_summary: Option<String>,
+    select: Select<ChangelogItem>,
+}
+
+impl Changelog {
+    /// Max number of commits to show at once
+    const MAX_HEIGHT: u16 = 20;
+
+    pub fn new(
+        commits: Vec<CollectionCommit>,
+        selected_recipe_id: Option<&RecipeId>,
+    ) -> Self {
+        let recipe_summary = selected_recipe_id.and_then(|recipe_id| {
+            let commit = commits
+                .iter()
+                .find(|commit| commit.recipes.contains(recipe_id))?;
+            Some(format!(
+                "`{recipe_id}` last changed {} by {} ({})",
+                commit.time.with_timezone(&Local).format("%F"),
+                commit.author,
+                commit.hash,
+            ))
+        });
+        let items = commits.into_iter().map(ChangelogItem).collect();
+        Self {
+            id: ComponentId::default(),
+            recipe_summary,
+            select: Select::builder(items).build(),
+        }
+    }
+}
+
+impl Component for Changelog {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for Changelog {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        if self.select.is_empty() {
+            canvas.render_widget(
+                "No commits found for the collection file",
+                metadata.area(),
+            );
+            return;
+        }
+
+        let summary_height = u16::from(self.recipe_summary.is_some());
+        let [summary_area, list_area] = Layout::vertical([
+            Constraint::Length(summary_height),
+            Constraint::Min(0),
+        ])
+        .areas(metadata.area());
+        if let Some(summary) = &self.recipe_summary {
+            canvas.render_widget(summary.as_str(), summary_area);
+        }
+        canvas.draw(&self.select, SelectListProps::modal(), list_area, true);
+    }
+}
+
+impl Modal for Changelog {
+    fn title(&self) -> Line<'_> {
+        tr!("modal-changelog-title").into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT)
+            + u16::from(self.recipe_summary.is_some());
+        (Constraint::Percentage(80), Constraint::Length(height))
+    }
+}
+
+#[derive(Debug)]
+struct ChangelogItem(CollectionCommit);
+
+impl Display for ChangelogItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let commit = &self.0;
+        write!(
+            f,
+            "{}  {}  {}  {}",
+            commit.hash,
+            commit.time.with_timezone(&Local).format("%F"),
+            commit.author,
+            commit.summary,
+        )?;
+        if !commit.recipes.is_empty() {
+            write!(f, "  [{}]", commit.recipes.iter().format(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl ToStringGenerate for ChangelogItem {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::view::test_util::{TestHarness, harness};
+    use chrono::{TimeZone, Utc};
+    use rstest::rstest;
+
+    /// The selected recipe's latest commit is summarized above the list
+    #[rstest]
+    #[case::changed(
+        Some("users"),
+        Some("`users` last changed 2023-11-14 by Bob (def456)")
+    )]
+    #[case::not_in_history(Some("login"), None)]
+    #[case::no_selection(None, None)]
+    fn test_recipe_summary(
+        _harness: TestHarness,
+        #[case] recipe_id: Option<&str>,
+        #[case] expected: Option<&str>,
+    ) {
+        let time = Utc.with_ymd_and_hms(2023, 11, 14, 12, 0, 0).unwrap();
+        let commits = vec![
+            CollectionCommit {
+                hash: "def456".into(),
+                author: "Bob".into(),
+                time,
+                summary: "Update users".into(),
+                recipes: vec!["users".into()],
+            },
+            CollectionCommit {
+                hash: "abc123".into(),
+                author: "Alice".into(),
+                time,
+                summary: "Add recipes".into(),
+                recipes: vec![],
+            },
+        ];
+        let recipe_id = recipe_id.map(RecipeId::from);
+        let changelog = Changelog::new(commits, recipe_id.as_ref());
+        assert_eq!(changelog.recipe_summary.as_deref(), expected);
+        assert_eq!(
+            changelog.select.items().next().unwrap().to_string(),
+            "def456  2023-11-14  Bob  Update users  [users]"
+        );
+    }
+}
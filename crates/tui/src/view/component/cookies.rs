@@ -0,0 +1,239 @@
+//! Modal to view and edit the cookie jar for the current collection
+
+use crate::{
+    message::Message,
+    view::{
+        Question, ToStringGenerate, UpdateContext, ViewContext,
+        common::{
+            modal::Modal,
+            select::{Select, SelectListProps},
+        },
+        component::{
+            Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+        },
+        event::{Event, EventMatch},
+    },
+};
+use chrono::Local;
+use ratatui::{layout::Constraint, text::Line};
+use slumber_config::Action;
+use slumber_core::http::{Cookie, CookieJar};
+use slumber_util::tr;
+use std::fmt::{self, Display};
+use tokio::sync::oneshot;
+
+/// Modal listing every cookie in the jar. Submitting edits the selected
+/// cookie's value, and the delete binding removes it from the jar.
+#[derive(Debug)]
+pub struct Cookies {
+    id: ComponentId,
+    /// Changes go straight to the database, so they apply to the next request
+    jar: CookieJar,
+    select: Select<CookieItem>,
+}
+
+impl Cookies {
+    /// Max number of cookies to show at once
+    const MAX_HEIGHT: u16 = 20;
+
+    pub fn new() -> Self {
+        let jar = ViewContext::with_database(|database| {
+            CookieJar::new(database.clone())
+        });
+        let select = Self::build_select(&jar, 0);
+        Self {
+            id: ComponentId::default(),
+            jar,
+            select,
+        }
+    }
+
+    /// Load the jar's cookies into a list, selecting the given index
+    fn build_select(jar: &CookieJar, index: usize) -> Select<CookieItem> {
+        let items = jar.cookies().into_iter().map(CookieItem).collect();
+        Select::builder(items).preselect_index(index).build()
+    }
+}
+
+impl Component for Cookies {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event.m().action(|action, propagate| match action {
+            Action::Delete => {
+                if let Some(index) = self.select.selected_index()
+                    && let Some(CookieItem(cookie)) = self.select.selected()
+                {
+                    self.jar.remove(cookie);
+                    // Stay in the same spot, so cookies can be deleted quickly
+                    let index = index.min(self.select.len().saturating_sub(2));
+                    self.select = Self::build_select(&self.jar, index);
+                }
+            }
+            _ => propagate.set(),
+        })
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for Cookies {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        if self.select.is_empty() {
+            canvas.render_widget(
+                "No cookies. Cookies set by responses will appear here",
+                metadata.area(),
+            );
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                metadata.area(),
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for Cookies {
+    fn title(&self) -> Line<'_> {
+        tr!("modal-cookies-title").into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (Constraint::Percentage(80), Constraint::Length(height))
+    }
+
+    fn on_submit(self, _: &mut UpdateContext) {
+        let Some(CookieItem(cookie)) = self.select.into_selected() else {
+            return;
+        };
+        let (tx, rx) = oneshot::channel();
+        ViewContext::send_message(Message::Question(Question::Text {
+            message: format!("Value for cookie `{}`", cookie.name),
+            default: Some(cookie.value.clone()),
+            channel: tx.into(),
+        }));
+        let jar = self.jar;
+        ViewContext::spawn(async move {
+            // No response means the prompt was cancelled
+            if let Ok(value) = rx.await {
+                jar.insert(Cookie { value, ..cookie });
+            }
+        });
+    }
+}
+
+#[derive(Debug)]
+struct CookieItem(Cookie);
+
+impl Display for CookieItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cookie = &self.0;
+        // Domains that match subdomains get a leading dot, like curl's
+        // cookie files
+        let dot = if cookie.host_only { "" } else { "." };
+        write!(
+            f,
+            "{dot}{}{}  {}={}",
+            cookie.domain, cookie.path, cookie.name, cookie.value
+        )?;
+        match cookie.expires {
+            Some(expires) => write!(
+                f,
+                "  (expires {})",
+                expires.with_timezone(&Local).format("%F %T")
+            ),
+            None => write!(f, "  (session)"),
+        }
+    }
+}
+
+impl ToStringGenerate for CookieItem {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{TestTerminal, terminal},
+        view::{
+            common::modal::ModalQueue,
+            test_util::{TestComponent, TestHarness, harness},
+        },
+    };
+    use rstest::rstest;
+    use slumber_util::assert_matches;
+    use terminput::KeyCode;
+
+    fn cookie(name: &str) -> Cookie {
+        Cookie {
+            name: name.into(),
+            value: "abc".into(),
+            domain: "example.com".into(),
+            host_only: true,
+            path: "/".into(),
+            expires: None,
+            secure: false,
+            http_only: false,
+        }
+    }
+
+    /// Delete a cookie, then edit another one's value
+    #[rstest]
+    #[tokio::test]
+    async fn test_delete_and_edit(
+        mut harness: TestHarness,
+        terminal: TestTerminal,
+    ) {
+        let jar = CookieJar::new(harness.database.clone());
+        jar.insert(cookie("a"));
+        jar.insert(cookie("b"));
+        let mut modal = ModalQueue::default();
+        modal.open(Cookies::new());
+        let mut component = TestComponent::new(&harness, &terminal, modal);
+        let items = |component: &mut TestComponent<'_, ModalQueue<Cookies>>| {
+            component
+                .active_mut()
+                .unwrap()
+                .select
+                .items()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            items(&mut component),
+            [
+                "example.com/  a=abc  (session)",
+                "example.com/  b=abc  (session)"
+            ]
+        );
+
+        component.int().send_key(KeyCode::Delete).assert().empty();
+        assert_eq!(jar.cookies(), [cookie("b")]);
+        assert_eq!(items(&mut component), ["example.com/  b=abc  (session)"]);
+
+        // Submitting asks for a new value
+        component.int().send_key(KeyCode::Enter).assert().empty();
+        assert!(!component.is_open());
+        let (default, channel) = assert_matches!(
+            harness.messages().pop_now(),
+            Message::Question(Question::Text { default, channel, .. }) =>
+                (default, channel)
+        );
+        assert_eq!(default.as_deref(), Some("abc"));
+        channel.reply("def".into());
+        harness.run_task().await;
+        assert_eq!(
+            jar.cookies(),
+            [Cookie {
+                value: "def".into(),
+                ..cookie("b")
+            }]
+        );
+    }
+}
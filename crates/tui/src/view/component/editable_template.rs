@@ -13,7 +13,9 @@ use crate::view::{
 };
 use ratatui::text::Text;
 use slumber_config::Action;
+use slumber_core::render::TemplateReference;
 use slumber_template::Template;
+use slumber_util::tr;
 use std::fmt::Debug;
 
 /// A component for a template that can be edited in the UI
@@ -35,6 +37,8 @@ pub struct EditableTemplate<PK> {
     preview: TemplatePreview<PK>,
     /// Rendered preview text
     text: Text<'static>,
+    /// Error chain from the latest preview render, if it failed
+    error: Option<Text<'static>>,
     /// An inline text box for editing the override template. `Some` only when
     /// editing.
     edit_text_box: Option<TextBox>,
@@ -77,6 +81,7 @@ impl<PK> EditableTemplate<PK> {
             actions_emitter: Emitter::default(),
             preview,
             text: initial_text,
+            error: None,
             edit_text_box: None,
             refresh_on_edit,
         }
@@ -93,6 +98,22 @@ impl<PK> EditableTemplate<PK> {
         &self.text
     }
 
+    /// Get the raw template text, without rendering
+    pub fn render_raw(&self) -> Text<'static> {
+        self.preview.render_raw()
+    }
+
+    /// Get the error from the latest preview render. `None` if the render
+    /// succeeded or hasn't finished yet
+    pub fn error(&self) -> Option<&Text<'static>> {
+        self.error.as_ref()
+    }
+
+    /// Is the inline edit text box open?
+    pub fn is_editing(&self) -> bool {
+        self.edit_text_box.is_some()
+    }
+
     /// Override the recipe with a new template
     pub fn set_override(&mut self, template: Template) {
         self.preview.set_override(template);
@@ -159,11 +180,18 @@ where
             .emitted(self.actions_emitter, |menu_action| match menu_action {
                 EditableTemplateMenuAction::Edit => self.edit(),
                 EditableTemplateMenuAction::Reset => self.reset_override(),
+                EditableTemplateMenuAction::GoToDefinition(reference) => {
+                    ViewContext::push_event(Event::GoToDefinition(reference));
+                }
             })
             // Store text rendered by the template preview
-            .emitted(self.preview.to_emitter(), |TemplatePreviewEvent(text)| {
-                self.text = text;
-            })
+            .emitted(
+                self.preview.to_emitter(),
+                |TemplatePreviewEvent { text, error }| {
+                    self.text = text;
+                    self.error = error;
+                },
+            )
             .emitted_opt(
                 self.edit_text_box.as_ref().map(ToEmitter::to_emitter),
                 |event| match event {
@@ -176,20 +204,51 @@ where
 
     fn menu(&self) -> Vec<MenuItem> {
         let noun = self.noun;
-        vec![
+        let mut items = vec![
             self.actions_emitter
-                .menu(EditableTemplateMenuAction::Edit, format!("Edit {noun}"))
+                .menu(
+                    EditableTemplateMenuAction::Edit,
+                    tr!("menu-edit-template", noun = noun),
+                )
                 .shortcut(Some(Action::Edit))
                 .into(),
             self.actions_emitter
                 .menu(
                     EditableTemplateMenuAction::Reset,
-                    format!("Reset {noun}"),
+                    tr!("menu-reset-template", noun = noun),
                 )
                 .enable(self.is_overridden())
                 .shortcut(Some(Action::Reset))
                 .into(),
-        ]
+        ];
+
+        // Link to everything the template references
+        let definitions = TemplateReference::find_all(self.template())
+            .into_iter()
+            .map(|reference| {
+                let name = match &reference {
+                    TemplateReference::ProfileField(field) => {
+                        tr!("menu-profile-field", field = field)
+                    }
+                    TemplateReference::Recipe(recipe_id) => {
+                        tr!("menu-recipe", recipe = recipe_id)
+                    }
+                };
+                self.actions_emitter
+                    .menu(
+                        EditableTemplateMenuAction::GoToDefinition(reference),
+                        name,
+                    )
+                    .into()
+            })
+            .collect::<Vec<_>>();
+        if !definitions.is_empty() {
+            items.push(MenuItem::Group {
+                name: tr!("menu-go-to-definition"),
+                children: definitions,
+            });
+        }
+        items
     }
 
     fn children(&mut self) -> Vec<Child<'_>> {
@@ -224,12 +283,14 @@ where
 }
 
 /// Menu action for [EditableTemplate]
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 enum EditableTemplateMenuAction {
     /// Edit the override
     Edit,
     /// Wipe ou the current override
     Reset,
+    /// Navigate to something referenced by the template
+    GoToDefinition(TemplateReference),
 }
 
 #[cfg(test)]
@@ -3,31 +3,42 @@ use crate::{
     view::{
         Generate, RequestState, ViewContext,
         common::{
-            Pane, actions::MenuItem, fixed_select::FixedSelect, tabs::Tabs,
+            LoadingSpinner, Pane, actions::MenuItem, fixed_select::FixedSelect,
+            tabs::Tabs,
         },
         component::{
             Canvas, Component, ComponentId, Draw, DrawMetadata,
             internal::{Child, ToChild},
             prompt_form::PromptForm,
             request_view::RequestView,
-            response_view::{ResponseBodyView, ResponseHeadersView},
+            response_view::{
+                ResponseBodyView, ResponseHeadersView, StreamingBodyView,
+            },
         },
         context::UpdateContext,
         event::{DeleteTarget, Emitter, Event, EventMatch},
-        persistent::PersistentKey,
-        util::format_byte_size,
+        persistent::{PersistentKey, PersistentStore, SessionKey},
     },
 };
+use chrono::{DateTime, Utc};
 use derive_more::Display;
 use ratatui::{
-    layout::{Alignment, Constraint, Layout},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::Style,
     text::{Line, Span, Text},
+    widgets::{Paragraph, Wrap},
 };
 use serde::{Deserialize, Serialize};
-use slumber_config::Action;
-use slumber_core::{collection::RecipeNodeType, http::RequestId};
-use std::{error::Error, sync::Arc};
+use slumber_config::{Action, EnvironmentBadge, ExchangeTab};
+use slumber_core::{
+    collection::{RecipeId, RecipeNodeType},
+    http::{
+        AssertionResults, RequestId, ResponseLink, ResponseRecord,
+        TOKEN_REFRESH_MARGIN,
+    },
+};
+use slumber_util::{TimeSpan, format_byte_size, tr};
+use std::{error::Error, sync::Arc, time::Duration};
 use strum::{EnumCount, EnumIter};
 
 /// Display for a request/response exchange. This allows the user to switch
@@ -41,24 +52,68 @@ pub struct ExchangePane {
 }
 
 impl ExchangePane {
+    /// If `previous_response` is given, the response body will mark changes
+    /// relative to it
     pub fn new(
         selected_request: Option<&RequestState>,
+        previous_response: Option<Arc<ResponseRecord>>,
         selected_recipe_kind: Option<RecipeNodeType>,
     ) -> Self {
         Self {
             id: Default::default(),
-            state: State::new(selected_request, selected_recipe_kind),
+            state: State::new(
+                selected_request,
+                previous_response,
+                selected_recipe_kind,
+            ),
+        }
+    }
+
+    /// Build a pane that hides the selected request until the user asks for
+    /// it. Used at startup when `startup.open_last_response` is disabled
+    pub fn closed() -> Self {
+        Self {
+            id: Default::default(),
+            state: State::Closed,
         }
     }
 
+    /// Is the selected request being hidden? See [Self::closed]
+    pub fn is_closed(&self) -> bool {
+        matches!(self.state, State::Closed)
+    }
+
+    /// Select a tab for the request displayed at startup. This overrides the
+    /// tab persisted for the request's recipe, but only until the end of the
+    /// first update phase
+    pub fn select_tab(store: &mut PersistentStore, tab: ExchangeTab) {
+        store.set_session(StartupTabKey, tab.into());
+    }
+
     /// Get the ID of the displayed request
     pub fn request_id(&self) -> Option<RequestId> {
         match &self.state {
-            State::None | State::Folder | State::NoHistory => None,
+            State::None | State::Folder | State::NoHistory | State::Closed => {
+                None
+            }
             State::Building { form } => Some(form.request_id()),
             State::Content { metadata, .. } => Some(metadata.request.id),
         }
     }
+
+    /// Get the environment badge for the displayed request's host, if any
+    pub fn environment_badge(&self) -> Option<&EnvironmentBadge> {
+        match &self.state {
+            State::None
+            | State::Folder
+            | State::NoHistory
+            | State::Closed
+            | State::Building { .. } => None,
+            State::Content { content, .. } => {
+                content.state.request()?.environment_badge()
+            }
+        }
+    }
 }
 
 impl Component for ExchangePane {
@@ -68,7 +123,7 @@ impl Component for ExchangePane {
 
     fn children(&mut self) -> Vec<Child<'_>> {
         match &mut self.state {
-            State::None | State::Folder | State::NoHistory => {
+            State::None | State::Folder | State::NoHistory | State::Closed => {
                 vec![]
             }
             State::Building { form } => vec![form.to_child_mut()],
@@ -77,6 +132,11 @@ impl Component for ExchangePane {
             }
         }
     }
+
+    fn persist(&self, store: &mut PersistentStore) {
+        // Startup is over, so the tab override no longer applies
+        store.remove_session(&StartupTabKey);
+    }
 }
 
 impl Draw for ExchangePane {
@@ -112,6 +172,13 @@ impl Draw for ExchangePane {
                 "No request history for this recipe & profile",
                 area,
             ),
+            State::Closed => canvas.render_widget(
+                format!(
+                    "Select a request from {} to view it",
+                    ViewContext::add_binding_hint("History", Action::History)
+                ),
+                area,
+            ),
             State::Building { form } => canvas.draw(form, (), area, true),
             State::Content { metadata, content } => {
                 let [metadata_area, content_area] = Layout::vertical([
@@ -139,6 +206,9 @@ enum State {
     Folder,
     /// Recipe selected, but it has no request history
     NoHistory,
+    /// A request is selected, but it's hidden until the user selects one
+    /// explicitly
+    Closed,
     /// Request is being built. We'll show a form for any potential prompts that
     /// are sent. For requests without prompts, there isn't anything useful to
     /// show so this will just be an empty state.
@@ -153,6 +223,7 @@ enum State {
 impl State {
     fn new(
         selected_request: Option<&RequestState>,
+        previous_response: Option<Arc<ResponseRecord>>,
         selected_recipe_kind: Option<RecipeNodeType>,
     ) -> Self {
         if let Some(request_state) = selected_request {
@@ -167,7 +238,10 @@ impl State {
                         request: request_state.request_metadata(),
                         response: request_state.response_metadata(),
                     },
-                    content: ExchangePaneContent::new(request_state),
+                    content: ExchangePaneContent::new(
+                        request_state,
+                        previous_response,
+                    ),
                 },
             }
         } else {
@@ -204,6 +278,15 @@ impl Draw for ExchangePaneMetadata {
         // Request metadata
         canvas.render_widget(
             Line::from(vec![
+                // Only in-flight requests are missing an end time
+                if self.request.end_time.is_none() {
+                    LoadingSpinner {
+                        start_time: self.request.start_time,
+                    }
+                    .generate()
+                } else {
+                    Span::default()
+                },
                 self.request.start_time.generate(),
                 " / ".into(),
                 self.request.duration().generate(),
@@ -211,10 +294,33 @@ impl Draw for ExchangePaneMetadata {
             area,
         );
 
+        // While retrying, show which attempt is in flight
+        if let Some(retry) = &self.request.retry
+            && retry.attempt() > 1
+        {
+            canvas.render_widget(
+                Line::from(format!(
+                    "Attempt {}/{}",
+                    retry.attempt(),
+                    retry.max_attempts()
+                ))
+                .alignment(Alignment::Right),
+                area,
+            );
+        }
+
         // Response metadata
         if let Some(metadata) = self.response {
             canvas.render_widget(
                 Line::from(vec![
+                    metadata
+                        .token_expiry
+                        .map(token_expiry_span)
+                        .unwrap_or_default(),
+                    Span::styled(
+                        format!("{} ", metadata.http_version),
+                        styles.text.hint,
+                    ),
                     metadata.status.generate(),
                     " ".into(),
                     Span::styled(
@@ -235,14 +341,50 @@ impl Draw for ExchangePaneMetadata {
     }
 }
 
-/// Persistence key for selected tab
+/// Describe how long the auth token in a response remains valid. Tokens that
+/// are expired or due for a refresh are styled as errors.
+fn token_expiry_span(expiry: DateTime<Utc>) -> Span<'static> {
+    let styles = ViewContext::styles();
+    let remaining = expiry - Utc::now();
+    match remaining.to_std() {
+        Ok(duration) => {
+            // Redraw when the displayed number of seconds ticks down
+            ViewContext::redraw_after(Duration::from_nanos(
+                duration.subsec_nanos().into(),
+            ));
+            Span::styled(
+                format!("token expires in {} ", TimeSpan::from(duration)),
+                if remaining <= TOKEN_REFRESH_MARGIN {
+                    styles.text.error
+                } else {
+                    styles.text.hint
+                },
+            )
+        }
+        Err(_) => Span::styled("token expired ", styles.text.error),
+    }
+}
+
+/// Persistence key for selected tab. Each recipe remembers its own tab, so
+/// each one opens the way it was last viewed
 #[derive(Debug, Serialize)]
-struct ExchangeTabKey;
+struct ExchangeTabKey {
+    recipe_id: RecipeId,
+}
 
 impl PersistentKey for ExchangeTabKey {
     type Value = Tab;
 }
 
+/// Session key for the tab selected by `startup.exchange_tab`. Removed after
+/// the first update phase, so it only applies to the request shown at launch
+#[derive(Debug, PartialEq)]
+struct StartupTabKey;
+
+impl SessionKey for StartupTabKey {
+    type Value = Tab;
+}
+
 #[derive(
     Copy,
     Clone,
@@ -262,6 +404,16 @@ enum Tab {
     Headers,
 }
 
+impl From<ExchangeTab> for Tab {
+    fn from(tab: ExchangeTab) -> Self {
+        match tab {
+            ExchangeTab::Request => Self::Request,
+            ExchangeTab::Body => Self::Body,
+            ExchangeTab::Headers => Self::Headers,
+        }
+    }
+}
+
 /// Content under the tab bar. Only rendered when a request state is present
 #[derive(Debug)]
 struct ExchangePaneContent {
@@ -272,7 +424,12 @@ struct ExchangePaneContent {
 }
 
 impl ExchangePaneContent {
-    fn new(request_state: &RequestState) -> Self {
+    fn new(
+        request_state: &RequestState,
+        previous_response: Option<Arc<ResponseRecord>>,
+    ) -> Self {
+        let collection = ViewContext::collection();
+        let recipe = collection.recipes.get_recipe(request_state.recipe_id());
         let state = match request_state {
             RequestState::Building { .. } => ExchangePaneContentState::Building,
             RequestState::BuildError { error } => {
@@ -280,9 +437,10 @@ impl ExchangePaneContent {
                     error: (error as &dyn Error).generate(),
                 }
             }
-            RequestState::Loading { request, .. } => {
+            RequestState::Loading { request, body, .. } => {
                 ExchangePaneContentState::Loading {
                     request: RequestView::new(Arc::clone(request)),
+                    response_body: StreamingBodyView::new(body.clone()),
                 }
             }
             RequestState::Cancelled { .. } => {
@@ -297,20 +455,49 @@ impl ExchangePaneContent {
                     response_body: ResponseBodyView::new(
                         exchange.request.recipe_id.clone(),
                         Arc::clone(&exchange.response),
+                        previous_response,
                     ),
+                    links: exchange.response.links(&exchange.request.url),
+                    hint: exchange.hint(recipe),
+                    pin_violations: exchange
+                        .pin_violations(
+                            exchange
+                                .request
+                                .profile_id
+                                .as_ref()
+                                .and_then(|id| collection.profiles.get(id)),
+                        )
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    assertions: recipe
+                        .and_then(|recipe| recipe.assert.as_ref())
+                        .map(|assertions| {
+                            exchange.check_assertions(assertions)
+                        }),
                 }
             }
             RequestState::RequestError { error } => {
                 ExchangePaneContentState::RequestError {
                     request: RequestView::new(Arc::clone(&error.request)),
                     error: (error as &dyn Error).generate(),
+                    hint: error.hint(recipe),
                 }
             }
         };
+        let mut tabs = Tabs::new(
+            ExchangeTabKey {
+                recipe_id: request_state.recipe_id().clone(),
+            },
+            FixedSelect::builder(),
+        );
+        if let Some(tab) = PersistentStore::get_session(&StartupTabKey) {
+            tabs.select(&tab);
+        }
         Self {
             id: Default::default(),
             actions_emitter: Default::default(),
-            tabs: Tabs::new(ExchangeTabKey, FixedSelect::builder()),
+            tabs,
             state,
         }
     }
@@ -348,6 +535,11 @@ impl Component for ExchangePaneContent {
                         request.view_body();
                     }
                 }
+                ExchangePaneMenuAction::DiffRequest => {
+                    if let Some(request) = self.state.request() {
+                        request.diff_current();
+                    }
+                }
                 ExchangePaneMenuAction::CopyRequestBody => {
                     if let Some(request) = self.state.request() {
                         request.copy_body();
@@ -368,6 +560,36 @@ impl Component for ExchangePaneContent {
                         response.save_response_body();
                     }
                 }
+                ExchangePaneMenuAction::ToggleRawBody => {
+                    if let Some(response) = self.state.response_mut() {
+                        response.toggle_raw();
+                    }
+                }
+                ExchangePaneMenuAction::ToggleWrap => {
+                    if let Some(response) = self.state.response_mut() {
+                        response.toggle_wrap();
+                    }
+                }
+                ExchangePaneMenuAction::QueryNodes => {
+                    if let Some(response) = self.state.response() {
+                        response.query_nodes();
+                    }
+                }
+                ExchangePaneMenuAction::CopyJsonPath => {
+                    if let Some(response) = self.state.response() {
+                        response.pick_json_path();
+                    }
+                }
+                ExchangePaneMenuAction::FollowLink => {
+                    let links = self.state.links();
+                    if !links.is_empty() {
+                        // Primary view owns the modal, since it needs the
+                        // whole screen
+                        ViewContext::push_event(Event::OpenLinks(
+                            links.to_owned(),
+                        ));
+                    }
+                }
                 ExchangePaneMenuAction::DeleteRequest => {
                     ViewContext::push_event(Event::DeleteRequests(
                         DeleteTarget::Request,
@@ -389,27 +611,43 @@ impl Component for ExchangePaneContent {
             | ExchangePaneContentState::RequestError { .. } => false,
             ExchangePaneContentState::Response { .. } => true,
         };
+        let is_markup = self
+            .state
+            .response()
+            .is_some_and(ResponseBodyView::is_markup);
+        let is_json =
+            self.state.response().is_some_and(ResponseBodyView::is_json);
+        let is_raw =
+            self.state.response().is_some_and(ResponseBodyView::is_raw);
+        let is_wrapped = self
+            .state
+            .response()
+            .is_some_and(ResponseBodyView::is_wrapped);
+        let has_links = !self.state.links().is_empty();
         let selected_tab = self.tabs.selected();
 
         vec![
             MenuItem::Group {
-                name: "Request".into(),
+                name: tr!("menu-request"),
                 children: vec![
                     emitter
-                        .menu(ExchangePaneMenuAction::CopyUrl, "Copy URL")
+                        .menu(
+                            ExchangePaneMenuAction::CopyUrl,
+                            tr!("menu-copy-url"),
+                        )
                         .enable(has_request)
                         .into(),
                     emitter
                         .menu(
                             ExchangePaneMenuAction::CopyRequestBody,
-                            "Copy Body",
+                            tr!("menu-copy-body"),
                         )
                         .enable(has_request_body)
                         .into(),
                     emitter
                         .menu(
                             ExchangePaneMenuAction::ViewRequestBody,
-                            "View Body",
+                            tr!("menu-view-body"),
                         )
                         .enable(has_request_body)
                         .shortcut(
@@ -417,22 +655,29 @@ impl Component for ExchangePaneContent {
                                 .then_some(Action::View),
                         )
                         .into(),
+                    emitter
+                        .menu(
+                            ExchangePaneMenuAction::DiffRequest,
+                            tr!("menu-diff-current"),
+                        )
+                        .enable(has_request)
+                        .into(),
                 ],
             },
             MenuItem::Group {
-                name: "Response".into(),
+                name: tr!("menu-response"),
                 children: vec![
                     emitter
                         .menu(
                             ExchangePaneMenuAction::CopyResponseBody,
-                            "Copy Body",
+                            tr!("menu-copy-body"),
                         )
                         .enable(has_response_body)
                         .into(),
                     emitter
                         .menu(
                             ExchangePaneMenuAction::ViewResponseBody,
-                            "View Body",
+                            tr!("menu-view-body"),
                         )
                         .enable(has_response_body)
                         .shortcut(
@@ -442,14 +687,60 @@ impl Component for ExchangePaneContent {
                     emitter
                         .menu(
                             ExchangePaneMenuAction::SaveResponseBody,
-                            "Save Body as File",
+                            tr!("menu-save-body"),
+                        )
+                        .enable(has_response_body)
+                        .into(),
+                    emitter
+                        .menu(
+                            ExchangePaneMenuAction::ToggleRawBody,
+                            if is_raw {
+                                tr!("menu-show-formatted-body")
+                            } else {
+                                tr!("menu-show-raw-body")
+                            },
+                        )
+                        .enable(has_response_body)
+                        .into(),
+                    emitter
+                        .menu(
+                            ExchangePaneMenuAction::ToggleWrap,
+                            if is_wrapped {
+                                tr!("menu-unwrap-lines")
+                            } else {
+                                tr!("menu-wrap-lines")
+                            },
                         )
                         .enable(has_response_body)
                         .into(),
+                    emitter
+                        .menu(
+                            ExchangePaneMenuAction::QueryNodes,
+                            tr!("menu-query-nodes"),
+                        )
+                        .enable(is_markup)
+                        .into(),
+                    emitter
+                        .menu(
+                            ExchangePaneMenuAction::CopyJsonPath,
+                            tr!("menu-copy-json-path"),
+                        )
+                        .enable(is_json)
+                        .into(),
+                    emitter
+                        .menu(
+                            ExchangePaneMenuAction::FollowLink,
+                            tr!("menu-follow-link"),
+                        )
+                        .enable(has_links)
+                        .into(),
                 ],
             },
             emitter
-                .menu(ExchangePaneMenuAction::DeleteRequest, "Delete Request")
+                .menu(
+                    ExchangePaneMenuAction::DeleteRequest,
+                    tr!("menu-delete-request"),
+                )
                 .enable(has_request)
                 .shortcut(Some(Action::Delete))
                 .into(),
@@ -462,13 +753,15 @@ impl Component for ExchangePaneContent {
             ExchangePaneContentState::Building
             | ExchangePaneContentState::BuildError { .. }
             | ExchangePaneContentState::Cancelled => vec![],
-            ExchangePaneContentState::Loading { request } => {
-                vec![request.to_child_mut()]
-            }
+            ExchangePaneContentState::Loading {
+                request,
+                response_body,
+            } => vec![request.to_child_mut(), response_body.to_child_mut()],
             ExchangePaneContentState::Response {
                 request,
                 response_headers,
                 response_body,
+                ..
             } => vec![
                 request.to_child_mut(),
                 response_headers.to_child_mut(),
@@ -499,16 +792,16 @@ impl Draw for ExchangePaneContent {
             ExchangePaneContentState::BuildError { error } => {
                 canvas.render_widget(error, content_area);
             }
-            ExchangePaneContentState::Loading { request } => {
-                match self.tabs.selected() {
-                    Tab::Request => {
-                        canvas.draw(request, (), content_area, true);
-                    }
-                    Tab::Body | Tab::Headers => {
-                        canvas.render_widget("Loading...", content_area);
-                    }
+            ExchangePaneContentState::Loading {
+                request,
+                response_body,
+            } => match self.tabs.selected() {
+                Tab::Request => canvas.draw(request, (), content_area, true),
+                Tab::Body => canvas.draw(response_body, (), content_area, true),
+                Tab::Headers => {
+                    canvas.render_widget("Loading...", content_area);
                 }
-            }
+            },
             // Can't show cancelled request here because we might've cancelled
             // during the build
             ExchangePaneContentState::Cancelled => {
@@ -518,27 +811,147 @@ impl Draw for ExchangePaneContent {
                 request,
                 response_body,
                 response_headers,
+                hint,
+                pin_violations,
+                assertions,
+                ..
             } => match self.tabs.selected() {
                 Tab::Request => canvas.draw(request, (), content_area, true),
-                Tab::Body => canvas.draw(response_body, (), content_area, true),
+                Tab::Body => {
+                    let body_area = draw_assertions(
+                        canvas,
+                        assertions.as_ref(),
+                        content_area,
+                    );
+                    let body_area =
+                        draw_warnings(canvas, pin_violations, body_area);
+                    let body_area = match hint {
+                        Some(hint) => draw_hint(canvas, hint, body_area),
+                        None => body_area,
+                    };
+                    canvas.draw(response_body, (), body_area, true);
+                }
                 Tab::Headers => {
-                    canvas.draw(response_headers, (), content_area, true);
+                    let headers_area = draw_assertions(
+                        canvas,
+                        assertions.as_ref(),
+                        content_area,
+                    );
+                    let headers_area =
+                        draw_warnings(canvas, pin_violations, headers_area);
+                    canvas.draw(response_headers, (), headers_area, true);
                 }
             },
-            ExchangePaneContentState::RequestError { request, error } => {
-                match self.tabs.selected() {
-                    Tab::Request => {
-                        canvas.draw(request, (), content_area, true);
-                    }
-                    Tab::Body | Tab::Headers => {
-                        canvas.render_widget(error, content_area);
-                    }
+            ExchangePaneContentState::RequestError {
+                request,
+                error,
+                hint,
+            } => match self.tabs.selected() {
+                Tab::Request => {
+                    canvas.draw(request, (), content_area, true);
                 }
-            }
+                Tab::Body | Tab::Headers => {
+                    let error_area = match hint {
+                        Some(hint) => draw_hint(canvas, hint, content_area),
+                        None => content_area,
+                    };
+                    canvas.render_widget(error, error_area);
+                }
+            },
         }
     }
 }
 
+/// Draw a troubleshooting hint at the top of an area, wrapped to fit. Return
+/// the remaining area below it
+fn draw_hint(canvas: &mut Canvas, hint: &str, area: Rect) -> Rect {
+    let line = Line::from(vec![
+        Span::styled("Hint: ", ViewContext::styles().text.title),
+        hint.into(),
+    ]);
+    let paragraph = Paragraph::new(line).wrap(Wrap { trim: true });
+    let height = paragraph.line_count(area.width) as u16;
+    let [hint_area, _, rest] = Layout::vertical([
+        Constraint::Length(height),
+        Constraint::Length(1),
+        Constraint::Min(0),
+    ])
+    .areas(area);
+    canvas.render_widget(paragraph, hint_area);
+    rest
+}
+
+/// Draw the outcome of the recipe's assertions at the top of an area. A pass
+/// is a single line; otherwise each failure gets its own line. Return the
+/// remaining area below
+fn draw_assertions(
+    canvas: &mut Canvas,
+    assertions: Option<&AssertionResults>,
+    area: Rect,
+) -> Rect {
+    let Some(assertions) = assertions else {
+        return area;
+    };
+    let styles = ViewContext::styles();
+    let lines = if assertions.passed() {
+        vec![
+            Line::from(format!(
+                "{total}/{total} assertions passed",
+                total = assertions.total
+            ))
+            .style(styles.text.hint),
+        ]
+    } else {
+        assertions
+            .failures
+            .iter()
+            .map(|failure| {
+                Line::from(vec![
+                    Span::styled("Assertion failed: ", styles.text.title),
+                    failure.to_string().into(),
+                ])
+                .style(styles.text.error)
+            })
+            .collect()
+    };
+    draw_lines(canvas, lines, area)
+}
+
+/// Draw warnings at the top of an area, wrapped to fit. Return the remaining
+/// area below them
+fn draw_warnings(canvas: &mut Canvas, warnings: &[String], area: Rect) -> Rect {
+    if warnings.is_empty() {
+        return area;
+    }
+    let styles = ViewContext::styles();
+    let lines = warnings
+        .iter()
+        .map(|warning| {
+            Line::from(vec![
+                Span::styled("Warning: ", styles.text.title),
+                warning.as_str().into(),
+            ])
+            .style(styles.text.error)
+        })
+        .collect::<Vec<_>>();
+    draw_lines(canvas, lines, area)
+}
+
+/// Draw lines at the top of an area, wrapped to fit, with a gap below. Return
+/// the remaining area below them
+fn draw_lines(canvas: &mut Canvas, lines: Vec<Line<'_>>, area: Rect) -> Rect {
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: true });
+    let height = paragraph.line_count(area.width) as u16;
+    let [warnings_area, _, rest] = Layout::vertical([
+        Constraint::Length(height),
+        Constraint::Length(1),
+        Constraint::Min(0),
+    ])
+    .areas(area);
+    canvas.render_widget(paragraph, warnings_area);
+    rest
+}
+
 /// Various request states that can appear under the tab bar
 #[derive(Debug)]
 
@@ -549,16 +962,30 @@ enum ExchangePaneContentState {
     },
     Loading {
         request: RequestView,
+        response_body: StreamingBodyView,
     },
     Cancelled,
     Response {
         request: RequestView,
         response_headers: ResponseHeadersView,
         response_body: ResponseBodyView,
+        /// URLs in the response that can be followed. These are found up
+        /// front so we don't scan the body on every draw
+        links: Vec<ResponseLink>,
+        /// Troubleshooting hint for client errors
+        hint: Option<String>,
+        /// Expectations from the profile's `pinning` that the response
+        /// violated. These are shown as warnings above the body and headers
+        pin_violations: Vec<String>,
+        /// Outcome of the recipe's `assert` checks. `None` if the recipe has
+        /// none, or has been removed from the collection
+        assertions: Option<AssertionResults>,
     },
     RequestError {
         request: RequestView,
         error: Text<'static>,
+        /// Troubleshooting hint, derived from the error and rendered request
+        hint: Option<String>,
     },
 }
 
@@ -566,7 +993,7 @@ impl ExchangePaneContentState {
     fn request(&self) -> Option<&RequestView> {
         match self {
             Self::Building | Self::BuildError { .. } | Self::Cancelled => None,
-            Self::Loading { request }
+            Self::Loading { request, .. }
             | Self::Response { request, .. }
             | Self::RequestError { request, .. } => Some(request),
         }
@@ -582,6 +1009,24 @@ impl ExchangePaneContentState {
             Self::Response { response_body, .. } => Some(response_body),
         }
     }
+
+    fn response_mut(&mut self) -> Option<&mut ResponseBodyView> {
+        match self {
+            Self::Building
+            | Self::BuildError { .. }
+            | Self::Cancelled
+            | Self::Loading { .. }
+            | Self::RequestError { .. } => None,
+            Self::Response { response_body, .. } => Some(response_body),
+        }
+    }
+
+    fn links(&self) -> &[ResponseLink] {
+        match self {
+            Self::Response { links, .. } => links,
+            _ => &[],
+        }
+    }
 }
 
 /// Items in the actions popup menu for the Body
@@ -590,8 +1035,313 @@ enum ExchangePaneMenuAction {
     CopyUrl,
     CopyRequestBody,
     ViewRequestBody,
+    DiffRequest,
     CopyResponseBody,
     ViewResponseBody,
     SaveResponseBody,
+    ToggleRawBody,
+    ToggleWrap,
+    QueryNodes,
+    CopyJsonPath,
+    FollowLink,
     DeleteRequest,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{TestTerminal, terminal},
+        view::test_util::{TestComponent, TestHarness, harness},
+    };
+    use chrono::TimeDelta;
+    use indexmap::indexmap;
+    use rstest::rstest;
+    use slumber_core::{
+        collection::{Assertions, Collection, Pinning, Profile, Recipe},
+        http::{Exchange, RequestRecord, ResponseRecord},
+        test_util::{by_id, header_map},
+    };
+    use slumber_util::Factory;
+    use std::time::Instant;
+    use terminput::KeyCode;
+
+    /// Client errors show a troubleshooting hint above the response body
+    #[rstest]
+    fn test_hint(harness: TestHarness, #[with(40, 7)] terminal: TestTerminal) {
+        let id = RequestId::new();
+        let exchange = Exchange::factory((
+            RequestRecord::factory(id),
+            ResponseRecord {
+                status: reqwest::StatusCode::NOT_FOUND,
+                body: "Not found".into(),
+                ..ResponseRecord::factory(id)
+            },
+        ));
+        let state = RequestState::Response { exchange };
+        TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangePaneContent::new(&state, None),
+        );
+        let styles = ViewContext::styles();
+        terminal.assert_buffer_lines([
+            Line::from(vec![
+                " Request │ ".into(),
+                Span::styled("Body", styles.tab.highlight),
+                " │ Headers               ".into(),
+            ]),
+            Line::from(vec![
+                Span::styled("Hint: ", styles.text.title),
+                "Nothing was found at `/url`; check".into(),
+            ]),
+            "the recipe URL, currently               ".into(),
+            "`http://localhost/url`                  ".into(),
+            "                                        ".into(),
+            Line::from(vec![
+                Span::styled("1", styles.text_window.gutter),
+                " Not found                             ".into(),
+            ]),
+            // Query box
+            Line::from(vec![
+                Span::styled(
+                    "[/] to query, [:] to export",
+                    styles.text_box.text.patch(styles.text_box.placeholder),
+                ),
+                Span::styled("             ", styles.text_box.text),
+            ]),
+        ]);
+    }
+
+    /// A response that violates the profile's pinning gets a warning above the
+    /// body
+    #[rstest]
+    fn test_pin_violations(#[with(40, 8)] terminal: TestTerminal) {
+        let profile = Profile {
+            pinning: Some(Pinning {
+                certificate: None,
+                headers: indexmap! { "server".into() => "envoy".into() },
+            }),
+            ..Profile::factory(())
+        };
+        let profile_id = profile.id.clone();
+        let collection = Collection {
+            profiles: by_id([profile]),
+            ..Collection::factory(())
+        };
+        let harness = TestHarness::new(collection);
+        let id = RequestId::new();
+        let exchange = Exchange::factory((
+            RequestRecord::factory((
+                id,
+                Some(profile_id),
+                RecipeId::factory(()),
+            )),
+            ResponseRecord {
+                headers: header_map([("server", "nginx")]),
+                body: "ok".into(),
+                ..ResponseRecord::factory(id)
+            },
+        ));
+        let state = RequestState::Response { exchange };
+        TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangePaneContent::new(&state, None),
+        );
+        let styles = ViewContext::styles();
+        terminal.assert_buffer_lines([
+            Line::from(vec![
+                " Request │ ".into(),
+                Span::styled("Body", styles.tab.highlight),
+                " │ Headers               ".into(),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "Warning: ",
+                    styles.text.error.patch(styles.text.title),
+                ),
+                Span::styled("Header `server` is `nginx`,", styles.text.error),
+                "    ".into(),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "which doesn't contain pinned value",
+                    styles.text.error,
+                ),
+                "      ".into(),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "`envoy`. The request may have reached",
+                    styles.text.error,
+                ),
+                "   ".into(),
+            ]),
+            Line::from(vec![
+                Span::styled("the wrong environment", styles.text.error),
+                "                   ".into(),
+            ]),
+            "                                        ".into(),
+            Line::from(vec![
+                Span::styled("1", styles.text_window.gutter),
+                " ok                                    ".into(),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "[/] to query, [:] to export",
+                    styles.text_box.text.patch(styles.text_box.placeholder),
+                ),
+                Span::styled("             ", styles.text_box.text),
+            ]),
+        ]);
+    }
+
+    /// The outcome of the recipe's assertions is shown above the body
+    #[rstest]
+    #[case::pass(3, false)]
+    #[case::fail(4, true)]
+    fn test_assertions(
+        #[with(50, 5)] terminal: TestTerminal,
+        #[case] id: u32,
+        #[case] failed: bool,
+    ) {
+        let recipe = Recipe {
+            assert: Some(Assertions {
+                body: indexmap! { "$.id".into() => "3".into() },
+                ..Assertions::default()
+            }),
+            ..Recipe::factory(())
+        };
+        let recipe_id = recipe.id.clone();
+        let collection = Collection {
+            recipes: by_id([recipe]).into(),
+            ..Collection::factory(())
+        };
+        let harness = TestHarness::new(collection);
+        let body = format!(r#"{{"id":{id}}}"#);
+        let request_id = RequestId::new();
+        let exchange = Exchange::factory((
+            RequestRecord::factory((request_id, None, recipe_id)),
+            ResponseRecord {
+                body: body.as_str().into(),
+                ..ResponseRecord::factory(request_id)
+            },
+        ));
+        let state = RequestState::Response { exchange };
+        TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangePaneContent::new(&state, None),
+        );
+        let styles = ViewContext::styles();
+        let expected_line = if failed {
+            Line::from(vec![
+                Span::styled(
+                    "Assertion failed: ",
+                    styles.text.error.patch(styles.text.title),
+                ),
+                Span::styled("`$.id` is `4`; expected `3`", styles.text.error),
+                "    ".into(),
+            ])
+        } else {
+            Line::from(vec![
+                Span::styled("1/1 assertions passed", styles.text.hint),
+                " ".repeat(29).into(),
+            ])
+        };
+        terminal.assert_buffer_lines([
+            Line::from(vec![
+                " Request │ ".into(),
+                Span::styled("Body", styles.tab.highlight),
+                format!(" │ Headers{}", " ".repeat(25)).into(),
+            ]),
+            expected_line,
+            " ".repeat(50).into(),
+            Line::from(vec![
+                Span::styled("1", styles.text_window.gutter),
+                format!(" {body}{}", " ".repeat(40)).into(),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "[/] to query, [:] to export",
+                    styles.text_box.text.patch(styles.text_box.placeholder),
+                ),
+                Span::styled(" ".repeat(23), styles.text_box.text),
+            ]),
+        ]);
+    }
+
+    /// Remaining token validity is shown in the metadata bar, and highlighted
+    /// once the token is due for a refresh
+    #[rstest]
+    fn test_token_expiry(_harness: TestHarness) {
+        let styles = ViewContext::styles();
+        let span = token_expiry_span(Utc::now() + TimeDelta::hours(2));
+        assert!(
+            span.content.starts_with("token expires in 1h59m"),
+            "{span:?}"
+        );
+        assert_eq!(span.style, styles.text.hint);
+
+        let span = token_expiry_span(Utc::now() + TimeDelta::seconds(10));
+        assert_eq!(span.style, styles.text.error);
+
+        // The countdown schedules its next redraw, so it ticks while idle
+        let redraw_at = ViewContext::redraw_at().expect("Redraw not requested");
+        assert!(redraw_at <= Instant::now() + Duration::from_secs(1));
+
+        // Once expired, there's nothing left to count down
+        ViewContext::clear_redraw();
+        assert_eq!(
+            token_expiry_span(Utc::now() - TimeDelta::minutes(1)),
+            Span::styled("token expired ", styles.text.error)
+        );
+        assert_eq!(ViewContext::redraw_at(), None);
+    }
+
+    /// Selected tab is persisted per recipe. The startup tab overrides it
+    /// until the end of the first update phase
+    #[rstest]
+    fn test_tab_per_recipe(harness: TestHarness, terminal: TestTerminal) {
+        let state = |recipe_id: &RecipeId| RequestState::Response {
+            exchange: Exchange::factory((None, recipe_id.clone())),
+        };
+        let recipe1 = RecipeId::factory(());
+        let recipe2 = RecipeId::factory(());
+
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangePaneContent::new(&state(&recipe1), None),
+        );
+        component.int().send_key(KeyCode::Right).assert().empty();
+        assert_eq!(component.tabs.selected(), Tab::Headers);
+        component.persist(&mut harness.persistent_store());
+
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangePaneContent::new(&state(&recipe1), None),
+        );
+        assert_eq!(component.tabs.selected(), Tab::Headers);
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangePaneContent::new(&state(&recipe2), None),
+        );
+        assert_eq!(component.tabs.selected(), Tab::Body);
+
+        // Startup tab wins over the persisted tab
+        ExchangePane::select_tab(
+            &mut harness.persistent_store(),
+            ExchangeTab::Request,
+        );
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            ExchangePaneContent::new(&state(&recipe1), None),
+        );
+        assert_eq!(component.tabs.selected(), Tab::Request);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::view::{
-    UpdateContext, ViewContext,
+    Generate, UpdateContext, ViewContext,
     component::{
         Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
         collection_select::CollectionSelect, help::Help,
@@ -7,7 +7,15 @@ use crate::view::{
     event::{Emitter, Event, EventMatch},
     state::Notification,
 };
-use ratatui::layout::{Constraint, Layout};
+use chrono::Utc;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::Span,
+};
+use slumber_config::EnvironmentBadge;
+use slumber_core::collection::RemoteStatus;
+use slumber_util::TimeSpan;
+use std::time::Duration;
 use tokio::time;
 use uuid::Uuid;
 
@@ -23,6 +31,8 @@ pub struct Footer {
     collection_select: CollectionSelect,
     notification: Option<Notification>,
     clear_emitter: Emitter<ClearNotification>,
+    /// Populated if the collection was fetched from a remote source
+    remote: Option<RemoteStatus>,
 }
 
 impl Footer {
@@ -43,6 +53,36 @@ impl Footer {
             });
         }
     }
+
+    /// Set the status of the remote collection, to be shown next to the
+    /// collection name
+    pub fn set_remote(&mut self, remote: Option<RemoteStatus>) {
+        self.remote = remote;
+    }
+
+    /// Indicator for a remote collection. If the latest fetch failed, show how
+    /// old the cached copy is
+    fn remote_text(&self) -> Option<Span<'static>> {
+        let remote = self.remote.as_ref()?;
+        let styles = ViewContext::styles();
+        if remote.is_stale() {
+            let age = (Utc::now() - remote.fetched_at)
+                .to_std()
+                .unwrap_or_default();
+            // Redraw when the displayed number of seconds ticks up
+            ViewContext::redraw_after(
+                Duration::from_secs(1)
+                    - Duration::from_nanos(age.subsec_nanos().into()),
+            );
+            let age = TimeSpan::from(Duration::from_secs(age.as_secs()));
+            Some(Span::styled(
+                format!("stale (fetched {age} ago)"),
+                styles.text.error,
+            ))
+        } else {
+            Some(Span::styled("remote", styles.text.hint))
+        }
+    }
 }
 
 impl Component for Footer {
@@ -71,8 +111,20 @@ impl Component for Footer {
     }
 }
 
-impl Draw for Footer {
-    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+/// Draw props for [Footer]
+#[derive(Debug, Default)]
+pub struct FooterProps<'a> {
+    /// Badge for the host of the selected request
+    pub environment_badge: Option<&'a EnvironmentBadge>,
+}
+
+impl<'a> Draw<FooterProps<'a>> for Footer {
+    fn draw(
+        &self,
+        canvas: &mut Canvas,
+        props: FooterProps<'a>,
+        metadata: DrawMetadata,
+    ) {
         // If a notification is present, it gets the entire footer.
         // Notifications are auto-cleared so it's ok to hide other stuff
         // temporarily
@@ -82,14 +134,34 @@ impl Draw for Footer {
             return;
         }
 
-        // No notification - show help dialog and current collection path
-        let [collection_area, help_area] = Layout::horizontal([
-            Constraint::Length(self.collection_select.text().len() as u16),
-            Constraint::Min(0),
-        ])
-        .areas(metadata.area());
+        // No notification - show environment badge, help dialog and current
+        // collection path
+        let badge = props.environment_badge.map(Generate::generate);
+        let badge_width = badge.as_ref().map_or(0, |badge| badge.width() + 1);
+        let remote = self.remote_text();
+        let [badge_area, collection_area, remote_area, help_area] =
+            Layout::horizontal([
+                Constraint::Length(badge_width as u16),
+                Constraint::Length(self.collection_select.text().len() as u16),
+                Constraint::Length(
+                    remote.as_ref().map_or(0, |remote| remote.width() + 1)
+                        as u16,
+                ),
+                Constraint::Min(0),
+            ])
+            .areas(metadata.area());
 
+        if let Some(badge) = badge {
+            canvas.render_widget(badge, badge_area);
+        }
         canvas.draw(&self.collection_select, (), collection_area, true);
+        if let Some(remote) = remote {
+            // Leave a space after the collection name
+            let [_, remote_area] =
+                Layout::horizontal([Constraint::Length(1), Constraint::Min(0)])
+                    .areas(remote_area);
+            canvas.render_widget(remote, remote_area);
+        }
 
         // Draw help last. If it's in fullscreen mode, it draws over everything
         // else
@@ -4,7 +4,7 @@ use crate::{
     view::{
         Generate, UpdateContext, ViewContext,
         common::{
-            Pane,
+            LoadingSpinner, Pane,
             actions::MenuItem,
             select::{Select, SelectEventKind, SelectListProps},
         },
@@ -18,6 +18,7 @@ use crate::{
         persistent::{PersistentKey, PersistentStore},
     },
 };
+use itertools::Itertools;
 use ratatui::text::{Line, Span, Text};
 use serde::Serialize;
 use slumber_config::Action;
@@ -25,6 +26,8 @@ use slumber_core::{
     collection::{ProfileId, RecipeId},
     http::RequestId,
 };
+use slumber_util::tr;
+use std::mem;
 
 /// Browse request/response history for a recipe
 #[derive(Debug)]
@@ -38,9 +41,16 @@ pub struct History {
     // will stay in sync.
     selected_profile_id: Option<ProfileId>,
     selected_recipe_id: Option<RecipeId>,
+    /// Did the last load fill a whole page? If so, there may be more requests
+    /// in the DB to load. Large histories are loaded one page at a time; when
+    /// the user selects the final item in the list, the next page is appended.
+    has_more: bool,
 }
 
 impl History {
+    /// Number of requests to load from the DB at a time
+    const PAGE_SIZE: usize = 100;
+
     /// Construct a new history modal with the given list of requests. Parent
     /// is responsible for loading the list from the request store.
     pub fn new(
@@ -52,12 +62,32 @@ impl History {
             actions_emitter: Emitter::default(),
             // Always start with an empty list. On startup, we'll populate when
             // the initial SelectedRecipe/SelectedProfile events are received
-            select: Self::build_select(vec![]),
+            select: Self::build_select(vec![], None),
             selected_profile_id,
             selected_recipe_id,
+            has_more: false,
         }
     }
 
+    /// ID of the profile whose requests are in the list
+    pub fn profile_id(&self) -> Option<&ProfileId> {
+        self.selected_profile_id.as_ref()
+    }
+
+    /// ID of the recipe whose requests are in the list
+    pub fn recipe_id(&self) -> Option<&RecipeId> {
+        self.selected_recipe_id.as_ref()
+    }
+
+    /// Is the list showing requests for the given profile/recipe?
+    pub fn is_showing(
+        &self,
+        profile_id: Option<&ProfileId>,
+        recipe_id: &RecipeId,
+    ) -> bool {
+        self.profile_id() == profile_id && self.recipe_id() == Some(recipe_id)
+    }
+
     /// Get the ID of the request that's currently selected. Return `None` iff
     /// the request list is empty
     pub fn selected_id(&self) -> Option<RequestId> {
@@ -71,20 +101,80 @@ impl History {
     }
 
     /// Rebuild the request list from the store. This uses the retained
-    /// profile/recipe IDs to query the DB for all matching requests
+    /// profile/recipe IDs to query the DB for the most recent matching
+    /// requests. As many are loaded as are already in the list, so a refresh
+    /// doesn't drop pages the user has scrolled through.
     pub fn refresh(&mut self, store: &mut RequestStore) {
+        let limit = self.select.len().max(Self::PAGE_SIZE);
         // If there's no recipe selected, there's no requests to show
         let requests = if let Some(recipe_id) = &self.selected_recipe_id {
             // Load matching requests from the DB
             store
-                .load_summaries(self.selected_profile_id.as_ref(), recipe_id)
+                .load_summaries(
+                    self.selected_profile_id.as_ref(),
+                    recipe_id,
+                    Some(limit),
+                )
                 .reported(&ViewContext::messages_tx())
                 .map(Vec::from_iter)
                 .unwrap_or_default()
         } else {
             vec![]
         };
-        self.select = Self::build_select(requests);
+        // In-memory requests are included in the list as well, so this may
+        // report a false positive. That just costs us one extra query
+        self.has_more = requests.len() >= limit;
+        self.set_requests(requests, None);
+    }
+
+    /// Clear the list and load the first page. Used when the profile or recipe
+    /// changes, so the new list starts from the top.
+    fn reset(&mut self, store: &mut RequestStore) {
+        self.select = Self::build_select(vec![], None);
+        self.refresh(store);
+    }
+
+    /// Load the page of requests after the last one in the list from the DB,
+    /// and append it. The current selection is retained.
+    fn load_more(&mut self, store: &mut RequestStore) {
+        let (Some(recipe_id), Some(last)) =
+            (&self.selected_recipe_id, self.select.items().last())
+        else {
+            return;
+        };
+        let Some(page) = store
+            .load_summaries_after(
+                self.selected_profile_id.as_ref(),
+                recipe_id,
+                last,
+                Self::PAGE_SIZE,
+            )
+            .reported(&ViewContext::messages_tx())
+        else {
+            return;
+        };
+        self.has_more = page.len() >= Self::PAGE_SIZE;
+
+        let selected_id = self.selected_id();
+        let select =
+            mem::replace(&mut self.select, Self::build_select(vec![], None));
+        let requests = select
+            .into_items()
+            .chain(page)
+            // A request could have been stored since the first page loaded
+            .unique_by(RequestStateSummary::id)
+            .collect();
+        self.set_requests(requests, selected_id);
+    }
+
+    /// Replace the request list. If `preselect` is given and is in the list,
+    /// it will be selected. Otherwise the persisted selection is used.
+    fn set_requests(
+        &mut self,
+        requests: Vec<RequestStateSummary>,
+        preselect: Option<RequestId>,
+    ) {
+        self.select = Self::build_select(requests, preselect.as_ref());
 
         // If the list is empty, it never sends a Select event so we need to
         // manually notify our friends that there's no request selected.
@@ -95,10 +185,12 @@ impl History {
 
     fn build_select(
         requests: Vec<RequestStateSummary>,
+        preselect: Option<&RequestId>,
     ) -> Select<RequestStateSummary> {
         Select::builder(requests)
             .subscribe([SelectEventKind::Select])
             .persisted(&SelectedRequestKey)
+            .preselect_opt(preselect)
             .build()
     }
 }
@@ -141,6 +233,15 @@ impl Component for History {
                     ViewContext::push_event(BroadcastEvent::SelectedRequest(
                         Some(id),
                     ));
+
+                    // If we've reached the bottom of the list, load the next
+                    // page
+                    if self.has_more
+                        && self.select.selected_index()
+                            == Some(self.select.len() - 1)
+                    {
+                        self.load_more(context.request_store);
+                    }
                 }
                 SelectEventKind::Submit | SelectEventKind::Toggle => {}
             })
@@ -148,11 +249,11 @@ impl Component for History {
                 // When the profile or recipe select changes, rebuild our list
                 BroadcastEvent::SelectedProfile(profile_id) => {
                     self.selected_profile_id = profile_id;
-                    self.refresh(context.request_store);
+                    self.reset(context.request_store);
                 }
                 BroadcastEvent::SelectedRecipe(recipe_id) => {
                     self.selected_recipe_id = recipe_id;
-                    self.refresh(context.request_store);
+                    self.reset(context.request_store);
                 }
                 _ => {}
             })
@@ -163,22 +264,25 @@ impl Component for History {
         let has_requests = !self.select.is_empty();
         vec![
             emitter
-                .menu(HistoryAction::DeleteRequest, "Delete Request")
+                .menu(HistoryAction::DeleteRequest, tr!("menu-delete-request"))
                 .shortcut(Some(Action::Delete))
                 .enable(has_requests)
                 .into(),
             MenuItem::Group {
-                name: "Delete All Requests".into(),
+                name: tr!("menu-delete-all-requests"),
                 children: vec![
                     emitter
                         .menu(
                             HistoryAction::DeleteRecipeProfile,
-                            "This Profile",
+                            tr!("menu-this-profile"),
                         )
                         .enable(has_requests)
                         .into(),
                     emitter
-                        .menu(HistoryAction::DeleteRecipeAll, "All Profiles")
+                        .menu(
+                            HistoryAction::DeleteRecipeAll,
+                            tr!("menu-all-profiles"),
+                        )
                         .enable(has_requests)
                         .into(),
                 ],
@@ -225,33 +329,42 @@ impl Generate for &RequestStateSummary {
     where
         Self: 'this,
     {
-        let styles = ViewContext::styles();
-        let description: Span = match self {
-            RequestStateSummary::Building { .. } => "Initializing...".into(),
-            RequestStateSummary::BuildError { .. } => {
-                Span::styled("Build error", styles.text.error)
-            }
-            RequestStateSummary::Loading { .. } => "Loading...".into(),
-            RequestStateSummary::Cancelled { .. } => "Cancelled".into(),
-            RequestStateSummary::Response(exchange) => {
-                exchange.status.generate()
-            }
-            RequestStateSummary::RequestError { .. } => {
-                Span::styled("Request error", styles.text.error)
-            }
-        };
         vec![
             Line::from_iter([
                 self.start_time().generate(),
                 " / ".into(),
                 self.duration().generate(),
             ]),
-            description.into(),
+            state_description(self).into(),
         ]
         .into()
     }
 }
 
+/// Short description of the state of a request, e.g. its status code once
+/// complete
+pub fn state_description(summary: &RequestStateSummary) -> Span<'static> {
+    let styles = ViewContext::styles();
+    match summary {
+        RequestStateSummary::Building { .. } => "Initializing...".into(),
+        RequestStateSummary::BuildError { .. } => {
+            Span::styled("Build error", styles.text.error)
+        }
+        RequestStateSummary::Loading { start_time, .. } => {
+            let spinner = LoadingSpinner {
+                start_time: *start_time,
+            }
+            .generate();
+            format!("{}Loading...", spinner.content).into()
+        }
+        RequestStateSummary::Cancelled { .. } => "Cancelled".into(),
+        RequestStateSummary::Response(exchange) => exchange.status.generate(),
+        RequestStateSummary::RequestError { .. } => {
+            Span::styled("Request error", styles.text.error)
+        }
+    }
+}
+
 /// Allow selection by ID
 impl PartialEq<RequestId> for RequestStateSummary {
     fn eq(&self, id: &RequestId) -> bool {
@@ -330,4 +443,51 @@ mod tests {
             BroadcastEvent::SelectedRequest(Some(exchanges[1].id)),
         ]);
     }
+
+    /// Test that large histories are loaded one page at a time, with the next
+    /// page loaded when the last item is selected
+    #[rstest]
+    fn test_pagination(harness: TestHarness, terminal: TestTerminal) {
+        let profile_id = harness.collection.first_profile_id();
+        let recipe_id = harness.collection.first_recipe_id();
+        let exchanges = (0..=History::PAGE_SIZE)
+            .map(|_| {
+                Exchange::factory((Some(profile_id.clone()), recipe_id.clone()))
+            })
+            .sorted_by_key(|exchange| exchange.start_time)
+            .rev()
+            .collect_vec();
+        for exchange in &exchanges {
+            harness.database.insert_exchange(exchange).unwrap();
+        }
+
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            History::new(Some(profile_id.clone()), Some(recipe_id.clone())),
+        );
+        component.refresh(&mut harness.request_store_mut());
+        component.int().drain_draw().assert().broadcast([
+            BroadcastEvent::SelectedRequest(Some(exchanges[0].id)),
+        ]);
+        assert_eq!(component.select.len(), History::PAGE_SIZE);
+
+        // Selecting the last item loads the next page, without changing the
+        // selection
+        let last_id = exchanges[History::PAGE_SIZE - 1].id;
+        component.select.select_index(History::PAGE_SIZE - 1);
+        // The rebuilt list re-emits the same selection
+        component.int().drain_draw().assert().broadcast([
+            BroadcastEvent::SelectedRequest(Some(last_id)),
+            BroadcastEvent::SelectedRequest(Some(last_id)),
+        ]);
+        assert_eq!(component.select.len(), History::PAGE_SIZE + 1);
+        assert_eq!(component.selected_id(), Some(last_id));
+        assert!(!component.has_more);
+
+        // Refreshing keeps every page that's been loaded
+        component.refresh(&mut harness.request_store_mut());
+        assert_eq!(component.select.len(), History::PAGE_SIZE + 1);
+        assert_eq!(component.selected_id(), Some(last_id));
+    }
 }
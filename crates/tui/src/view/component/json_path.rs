@@ -0,0 +1,382 @@
+//! Modal to generate JSONPath selectors from a JSON response body
+
+use crate::{
+    message::Message,
+    view::{
+        ToStringGenerate, UpdateContext, ViewContext,
+        common::{
+            modal::Modal,
+            select::{Select, SelectListProps},
+            text_box::{TextBox, TextBoxEvent, TextBoxProps},
+        },
+        component::{
+            Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+        },
+        event::{Event, EventMatch, ToEmitter},
+    },
+};
+use anyhow::{Context, anyhow};
+use derive_more::Display;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::Line,
+};
+use serde_json::Value;
+use serde_json_path::{JsonPath, NormalizedPath, PathElement};
+use slumber_config::Action;
+use slumber_core::{
+    collection::RecipeId,
+    http::{ResponseRecord, content_type::ContentType},
+};
+use slumber_template::{Expression, Template};
+
+/// Modal to browse the nodes of a JSON body and copy the exact JSONPath
+/// selector for one of them. Nodes are filtered by a JSONPath query as it's
+/// typed. Submitting copies either the bare selector, or a full template that
+/// pulls the selected value out of the latest response for the current recipe.
+#[derive(Debug)]
+pub struct JsonPathPicker {
+    id: ComponentId,
+    /// Recipe that the response belongs to, for generated templates
+    recipe_id: RecipeId,
+    body: Value,
+    /// Copy a full template instead of just the selector?
+    copy_template: bool,
+    query: TextBox,
+    select: Select<JsonPathItem>,
+    /// Error from the latest query, if it was invalid
+    error: Option<String>,
+}
+
+impl JsonPathPicker {
+    /// Max number of nodes to show at once
+    const MAX_HEIGHT: u16 = 20;
+    /// Query used when the text box is empty: every node in the body
+    const DEFAULT_QUERY: &str = "$..*";
+
+    /// Parse the response body. Return an error if the body isn't valid JSON
+    pub fn new(
+        recipe_id: RecipeId,
+        response: &ResponseRecord,
+    ) -> anyhow::Result<Self> {
+        let body = match response.content_type() {
+            Some(ContentType::Json) => {
+                serde_json::from_slice(response.body.bytes())
+                    .context("Error parsing response body")?
+            }
+            None => return Err(anyhow!("Response body is not JSON")),
+        };
+
+        let query = TextBox::default()
+            .placeholder("JSONPath filter (ex: `$.users[*].name`)")
+            .subscribe([TextBoxEvent::Change]);
+        let mut picker = Self {
+            id: ComponentId::default(),
+            recipe_id,
+            body,
+            copy_template: false,
+            query,
+            select: Select::default(),
+            error: None,
+        };
+        picker.update_query();
+        Ok(picker)
+    }
+
+    /// Re-run the query after the text changes
+    fn update_query(&mut self) {
+        let query = self.query.text().trim();
+        let query = if query.is_empty() {
+            Self::DEFAULT_QUERY
+        } else {
+            query
+        };
+        let items = match JsonPath::parse(query) {
+            Ok(path) => {
+                self.error = None;
+                path.query_located(&self.body)
+                    .into_iter()
+                    .map(|node| JsonPathItem {
+                        path: selector(node.location()),
+                        preview: preview(node.node()),
+                    })
+                    .collect()
+            }
+            Err(error) => {
+                self.error = Some(error.to_string());
+                Vec::new()
+            }
+        };
+        self.select = Select::builder(items).build();
+    }
+
+    /// Get the text to copy for a selected path
+    fn output(&self, path: String) -> String {
+        if self.copy_template {
+            let expression = Expression::call(
+                "response",
+                [self.recipe_id.to_string().into()],
+                [],
+            )
+            .pipe("jsonpath", [path.into()], []);
+            Template::from(expression).display().into_owned()
+        } else {
+            path
+        }
+    }
+}
+
+impl Component for JsonPathPicker {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event
+            .m()
+            .action(|action, propagate| match action {
+                Action::NextPane => self.copy_template ^= true,
+                _ => propagate.set(),
+            })
+            .emitted(self.query.to_emitter(), |event| {
+                if let TextBoxEvent::Change = event {
+                    self.update_query();
+                }
+            })
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        // Select gets priority so it can handle up/down before the text box
+        vec![self.select.to_child_mut(), self.query.to_child_mut()]
+    }
+}
+
+impl Draw for JsonPathPicker {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        let [query_area, select_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                .areas(metadata.area());
+        canvas.draw(
+            &self.query,
+            TextBoxProps {
+                has_error: self.error.is_some(),
+                ..TextBoxProps::default()
+            },
+            query_area,
+            true,
+        );
+        if let Some(error) = &self.error {
+            canvas.render_widget(
+                Line::styled(error, ViewContext::styles().text.error),
+                select_area,
+            );
+        } else if self.select.is_empty() {
+            canvas.render_widget("No matches", select_area);
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                select_area,
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for JsonPathPicker {
+    fn title(&self) -> Line<'_> {
+        let next_pane = ViewContext::binding_display(Action::NextPane);
+        if self.copy_template {
+            format!("Copy Template ({next_pane} to copy JSONPath)").into()
+        } else {
+            format!("Copy JSONPath ({next_pane} to copy template)").into()
+        }
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        // Leave a line for the query, and another for the error/empty state
+        let list_height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (
+            Constraint::Percentage(80),
+            Constraint::Length(list_height + 1),
+        )
+    }
+
+    fn on_submit(self, _: &mut UpdateContext) {
+        let Some(path) = self.select.selected().map(|item| item.path.clone())
+        else {
+            return;
+        };
+        ViewContext::send_message(Message::CopyText(self.output(path)));
+    }
+}
+
+/// Build a selector that matches exactly one node. This is equivalent to the
+/// normalized path, but uses dot notation wherever possible because it's much
+/// easier to read and edit, e.g. `$.users[0].name` instead of
+/// `$['users'][0]['name']`
+fn selector(path: &NormalizedPath) -> String {
+    let mut selector = "$".to_owned();
+    for element in path.iter() {
+        match element {
+            PathElement::Name(name) if is_identifier(name) => {
+                selector.push('.');
+                selector.push_str(name);
+            }
+            // JSON string escaping is valid for JSONPath string literals
+            PathElement::Name(name) => {
+                selector.push('[');
+                selector.push_str(&Value::from(*name).to_string());
+                selector.push(']');
+            }
+            PathElement::Index(index) => {
+                selector.push('[');
+                selector.push_str(&index.to_string());
+                selector.push(']');
+            }
+        }
+    }
+    selector
+}
+
+/// Can a key be used with dot notation?
+fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Short one-line preview of a JSON value. Objects and arrays are summarized
+/// by size so rows stay readable for large bodies
+fn preview(value: &Value) -> String {
+    match value {
+        Value::Array(array) => format!("[{} items]", array.len()),
+        Value::Object(object) => format!("{{{} fields}}", object.len()),
+        _ => value.to_string(),
+    }
+}
+
+#[derive(Debug, Display)]
+#[display("{path} = {preview}")]
+struct JsonPathItem {
+    /// Path to the node, e.g. `$.users[0].name`. This is the exact path of a
+    /// single node, regardless of the query that found it
+    path: String,
+    preview: String,
+}
+
+impl ToStringGenerate for JsonPathItem {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{TestTerminal, terminal},
+        view::test_util::{TestComponent, TestHarness, harness},
+    };
+    use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
+    use rstest::rstest;
+    use slumber_util::Factory;
+    use terminput::KeyCode;
+
+    fn json_response(body: &'static str) -> ResponseRecord {
+        ResponseRecord {
+            headers: HeaderMap::from_iter([(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )]),
+            body: body.into(),
+            ..ResponseRecord::factory(())
+        }
+    }
+
+    /// All nodes are listed by default, and typing a query filters them
+    #[rstest]
+    fn test_query(harness: TestHarness, terminal: TestTerminal) {
+        let response =
+            json_response(r#"{"users": [{"name": "A"}, {"name": "B"}]}"#);
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            JsonPathPicker::new("r1".into(), &response).unwrap(),
+        );
+        let paths = |component: &TestComponent<JsonPathPicker>| {
+            component
+                .select
+                .items()
+                .map(|item| item.path.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            paths(&component),
+            [
+                "$.users",
+                "$.users[0]",
+                "$.users[1]",
+                "$.users[0].name",
+                "$.users[1].name",
+            ]
+        );
+
+        component
+            .int()
+            .send_text("$.users[*].name")
+            .assert()
+            .empty();
+        assert_eq!(paths(&component), ["$.users[0].name", "$.users[1].name"]);
+
+        component.int().send_text("[").assert().empty();
+        assert!(component.error.is_some());
+        assert!(component.select.is_empty());
+    }
+
+    /// Next pane toggles between copying the selector and a full template
+    #[rstest]
+    fn test_output(harness: TestHarness, terminal: TestTerminal) {
+        let response = json_response(r#"{"token": "abc", "a b": [1]}"#);
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            JsonPathPicker::new("login".into(), &response).unwrap(),
+        );
+        let paths = component
+            .select
+            .items()
+            .map(|item| item.path.clone())
+            .collect::<Vec<_>>();
+        // Keys that aren't identifiers need bracket notation
+        assert_eq!(paths, ["$.token", r#"$["a b"]"#, r#"$["a b"][0]"#]);
+        assert_eq!(component.output("$.token".into()), "$.token");
+
+        component.int().send_key(KeyCode::Tab).assert().empty();
+        assert_eq!(
+            component.output("$.token".into()),
+            "{{ response('login') | jsonpath('$.token') }}"
+        );
+    }
+
+    /// Only valid JSON bodies are supported
+    #[rstest]
+    #[case::html("text/html", "<p></p>", "Response body is not JSON")]
+    #[case::invalid("application/json", "{", "Error parsing response body")]
+    fn test_invalid(
+        _harness: TestHarness,
+        #[case] content_type: &'static str,
+        #[case] body: &'static str,
+        #[case] expected_error: &str,
+    ) {
+        let response = ResponseRecord {
+            headers: HeaderMap::from_iter([(
+                CONTENT_TYPE,
+                HeaderValue::from_static(content_type),
+            )]),
+            body: body.into(),
+            ..ResponseRecord::factory(())
+        };
+        let error = JsonPathPicker::new("r1".into(), &response).unwrap_err();
+        assert!(
+            error.to_string().contains(expected_error),
+            "Unexpected error: {error}"
+        );
+    }
+}
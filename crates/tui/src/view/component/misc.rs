@@ -14,9 +14,10 @@ use crate::view::{
     },
     context::UpdateContext,
 };
-use derive_more::Display;
 use ratatui::{prelude::Constraint, text::Line};
-use std::fmt::Debug;
+use slumber_core::error::ErrorCode;
+use slumber_util::tr;
+use std::fmt::{self, Debug, Display};
 use strum::{EnumCount, EnumIter};
 use unicode_width::UnicodeWidthStr;
 
@@ -39,7 +40,8 @@ impl ErrorModal {
 
 impl Modal for ErrorModal {
     fn title(&self) -> Line<'_> {
-        "Error".into()
+        let code = ErrorCode::from_error(&*self.error);
+        tr!("modal-error-title", code = code).into()
     }
 
     fn dimensions(&self) -> (Constraint, Constraint) {
@@ -60,15 +62,22 @@ impl Draw for ErrorModal {
 }
 
 /// Buttons in a yes/no confirmation modal
-#[derive(
-    Copy, Clone, Debug, Default, Display, EnumCount, EnumIter, PartialEq,
-)]
+#[derive(Copy, Clone, Debug, Default, EnumCount, EnumIter, PartialEq)]
 pub enum ConfirmButton {
     No,
     #[default]
     Yes,
 }
 
+impl Display for ConfirmButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::No => write!(f, "{}", tr!("confirm-no")),
+            Self::Yes => write!(f, "{}", tr!("confirm-yes")),
+        }
+    }
+}
+
 impl ConfirmButton {
     pub fn to_bool(self) -> bool {
         match self {
@@ -0,0 +1,299 @@
+//! Modal to query an XML/HTML response body with XPath or CSS selectors
+
+use crate::{
+    message::Message,
+    view::{
+        ToStringGenerate, UpdateContext, ViewContext,
+        common::{
+            modal::Modal,
+            select::{Select, SelectListProps},
+            text_box::{TextBox, TextBoxEvent, TextBoxProps},
+        },
+        component::{
+            Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+        },
+        event::{Event, EventMatch, ToEmitter},
+        util::markup::{Document, MarkupKind, Match, Query, QuerySyntax},
+    },
+};
+use anyhow::anyhow;
+use derive_more::Display;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::Line,
+};
+use slumber_config::Action;
+use slumber_core::http::ResponseRecord;
+
+/// Modal to query the nodes of an XML/HTML body. Matching nodes are listed as
+/// the query is typed. Submitting copies the selected node, and the next pane
+/// action replaces the query with a selector for the selected node, so it can
+/// be refined further.
+#[derive(Debug)]
+pub struct NodeQuery {
+    id: ComponentId,
+    document: Document,
+    /// Syntax for generated selectors when the query is empty or invalid
+    default_syntax: QuerySyntax,
+    query: TextBox,
+    select: Select<NodeQueryItem>,
+    /// Error from the latest query, if it was invalid
+    error: Option<String>,
+}
+
+impl NodeQuery {
+    /// Max number of nodes to show at once
+    const MAX_HEIGHT: u16 = 20;
+
+    /// Parse the response body. Return an error if the body isn't valid
+    /// XML/HTML
+    pub fn new(response: &ResponseRecord) -> anyhow::Result<Self> {
+        let kind = response
+            .mime()
+            .as_ref()
+            .and_then(MarkupKind::from_mime)
+            .ok_or_else(|| anyhow!("Response body is not XML or HTML"))?;
+        let text = response
+            .body
+            .text()
+            .ok_or_else(|| anyhow!("Response body is not valid UTF-8"))?;
+        let document = Document::parse(kind, text)?;
+
+        let query = TextBox::default()
+            .placeholder(match kind.default_syntax() {
+                QuerySyntax::Css => "CSS selector (ex: `ul > li a`)",
+                QuerySyntax::XPath => "XPath (ex: `//item/title/text()`)",
+            })
+            .subscribe([TextBoxEvent::Change]);
+        Ok(Self {
+            id: ComponentId::default(),
+            document,
+            default_syntax: kind.default_syntax(),
+            query,
+            select: Select::default(),
+            error: None,
+        })
+    }
+
+    /// Re-run the query after the text changes
+    fn update_query(&mut self) {
+        let query = self.query.text().trim();
+        let items = if query.is_empty() {
+            self.error = None;
+            Vec::new()
+        } else {
+            match query.parse::<Query>() {
+                Ok(query) => {
+                    self.error = None;
+                    self.document
+                        .query(&query)
+                        .into_iter()
+                        .map(|node| NodeQueryItem {
+                            summary: self.document.summary(&node),
+                            node,
+                        })
+                        .collect()
+                }
+                Err(error) => {
+                    self.error = Some(error.to_string());
+                    Vec::new()
+                }
+            }
+        };
+        self.select = Select::builder(items).build();
+    }
+
+    /// Replace the query with one that selects only the selected node
+    fn generate_selector(&mut self) {
+        let Some(item) = self.select.selected() else {
+            return;
+        };
+        // Stick with whatever language the user is already using
+        let syntax = self
+            .query
+            .text()
+            .parse::<Query>()
+            .map(|query| query.syntax())
+            .unwrap_or(self.default_syntax);
+        let selector = self.document.selector(&item.node, syntax);
+        self.query.set_text(selector);
+        self.update_query();
+    }
+}
+
+impl Component for NodeQuery {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event
+            .m()
+            .action(|action, propagate| match action {
+                Action::NextPane => self.generate_selector(),
+                _ => propagate.set(),
+            })
+            .emitted(self.query.to_emitter(), |event| {
+                if let TextBoxEvent::Change = event {
+                    self.update_query();
+                }
+            })
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        // Select gets priority so it can handle up/down before the text box
+        vec![self.select.to_child_mut(), self.query.to_child_mut()]
+    }
+}
+
+impl Draw for NodeQuery {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        let [query_area, select_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                .areas(metadata.area());
+        canvas.draw(
+            &self.query,
+            TextBoxProps {
+                has_error: self.error.is_some(),
+                ..TextBoxProps::default()
+            },
+            query_area,
+            true,
+        );
+        if let Some(error) = &self.error {
+            canvas.render_widget(
+                Line::styled(error, ViewContext::styles().text.error),
+                select_area,
+            );
+        } else if self.select.is_empty() {
+            if !self.query.text().trim().is_empty() {
+                canvas.render_widget("No matches", select_area);
+            }
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                select_area,
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for NodeQuery {
+    fn title(&self) -> Line<'_> {
+        let next_pane = ViewContext::binding_display(Action::NextPane);
+        format!("Query Nodes ({next_pane} to generate selector)").into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        // Leave a line for the query, and another for the error/empty state
+        let list_height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (
+            Constraint::Percentage(80),
+            Constraint::Length(list_height + 1),
+        )
+    }
+
+    fn on_submit(self, _: &mut UpdateContext) {
+        if let Some(item) = self.select.into_selected() {
+            ViewContext::send_message(Message::CopyText(
+                self.document.to_markup(&item.node),
+            ));
+        }
+    }
+}
+
+#[derive(Debug, Display)]
+#[display("{summary}")]
+struct NodeQueryItem {
+    node: Match,
+    /// Precomputed so we don't have to serialize on every draw
+    summary: String,
+}
+
+impl ToStringGenerate for NodeQueryItem {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{TestTerminal, terminal},
+        view::test_util::{TestComponent, TestHarness, harness},
+    };
+    use reqwest::header::{CONTENT_TYPE, HeaderMap, HeaderValue};
+    use rstest::rstest;
+    use slumber_util::Factory;
+    use terminput::KeyCode;
+
+    /// Type a query, then generate a selector from a match
+    #[rstest]
+    fn test_query(harness: TestHarness, terminal: TestTerminal) {
+        let response = ResponseRecord {
+            headers: HeaderMap::from_iter([(
+                CONTENT_TYPE,
+                HeaderValue::from_static("text/html"),
+            )]),
+            body: "<ul><li>One</li><li>Two</li></ul>".into(),
+            ..ResponseRecord::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            NodeQuery::new(&response).unwrap(),
+        );
+
+        component.int().send_text("li").assert().empty();
+        assert_eq!(component.select.len(), 2);
+
+        // Select the second item and generate a selector that matches only it
+        component
+            .int()
+            .send_keys([KeyCode::Down, KeyCode::Tab])
+            .assert()
+            .empty();
+        assert_eq!(
+            component.query.text(),
+            "html > body > ul > li:nth-of-type(2)"
+        );
+        assert_eq!(component.select.len(), 1);
+
+        // XPath works too, and errors are shown instead of the list
+        component.query.set_text("//li[".into());
+        component.update_query();
+        assert!(component.error.is_some());
+
+        component.query.set_text("//li[1]/text()".into());
+        component.update_query();
+        assert_eq!(component.error, None);
+    }
+
+    /// Only XML and HTML bodies can be queried
+    #[rstest]
+    #[case::json("application/json", "{}", "Response body is not XML or HTML")]
+    #[case::invalid_xml(
+        "application/xml",
+        "<a>",
+        "the root node was opened but never closed"
+    )]
+    fn test_invalid(
+        _harness: TestHarness,
+        #[case] content_type: &'static str,
+        #[case] body: &'static str,
+        #[case] expected_error: &str,
+    ) {
+        let response = ResponseRecord {
+            headers: HeaderMap::from_iter([(
+                CONTENT_TYPE,
+                HeaderValue::from_static(content_type),
+            )]),
+            body: body.into(),
+            ..ResponseRecord::factory(())
+        };
+        let error = NodeQuery::new(&response).unwrap_err();
+        assert!(
+            error.to_string().contains(expected_error),
+            "Unexpected error: {error}"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Requests that failed because the network was unreachable, held until their
+//! host can be reached again
+
+use crate::{
+    http::RequestConfig,
+    util,
+    view::{
+        ToStringGenerate, UpdateContext, ViewContext,
+        common::{
+            modal::Modal,
+            select::{Select, SelectListProps},
+        },
+        component::{
+            Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+        },
+        event::{Emitter, Event, EventMatch},
+    },
+};
+use derive_more::Display;
+use ratatui::{layout::Constraint, text::Line};
+use reqwest::Url;
+use slumber_config::Action;
+use slumber_util::tr;
+use std::time::Duration;
+use tokio::{net::TcpStream, time};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+/// Requests waiting for the network to come back, oldest first. Each one has a
+/// background task that checks its host periodically. Once the host accepts a
+/// connection, the task emits [OfflineQueueEvent::Send] so the request can be
+/// sent again.
+#[derive(Debug, Default)]
+pub struct OfflineQueue(Vec<QueuedRequest>);
+
+impl OfflineQueue {
+    /// How long to wait between attempts to reach a host
+    const POLL_INTERVAL: Duration = Duration::from_secs(5);
+    /// Max time for a single attempt to reach a host
+    const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+    /// Add a request to the queue and start watching its host. If the same
+    /// recipe+profile is already queued, the older request is replaced.
+    pub fn push(
+        &mut self,
+        config: RequestConfig,
+        url: Url,
+        emitter: Emitter<OfflineQueueEvent>,
+    ) {
+        self.0.retain(|other| {
+            other.config.recipe_id != config.recipe_id
+                || other.config.profile_id != config.profile_id
+        });
+        let request = QueuedRequest {
+            id: Uuid::new_v4(),
+            config,
+            url,
+            cancel_token: CancellationToken::new(),
+        };
+
+        // Hack alert! We skip this in tests, same as notifications in the
+        // footer. The task never ends if the host is unreachable.
+        if !cfg!(test)
+            && let Some(host) = request.url.host_str()
+            && let Some(port) = request.url.port_or_known_default()
+        {
+            let id = request.id;
+            let host = host.to_owned();
+            let future = async move {
+                Self::wait_for_host(&host, port).await;
+                emitter.emit(OfflineQueueEvent::Send(id));
+            };
+            ViewContext::spawn(util::cancellable(
+                &request.cancel_token,
+                future,
+            ));
+        }
+        self.0.push(request);
+    }
+
+    /// Remove a request from the queue and stop watching its host. Return its
+    /// config so it can be sent. `None` if the request was already removed.
+    pub fn remove(&mut self, id: Uuid) -> Option<RequestConfig> {
+        let index = self.0.iter().position(|request| request.id == id)?;
+        // Dropping the request stops its watcher
+        Some(self.0.remove(index).config.clone())
+    }
+
+    /// Number of requests waiting
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Try to open a TCP connection to the host until one succeeds
+    async fn wait_for_host(host: &str, port: u16) {
+        loop {
+            let connect = TcpStream::connect((host, port));
+            if let Ok(Ok(_)) =
+                time::timeout(Self::CONNECT_TIMEOUT, connect).await
+            {
+                return;
+            }
+            time::sleep(Self::POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct QueuedRequest {
+    id: Uuid,
+    config: RequestConfig,
+    /// URL the request failed to reach. Its host is polled for connectivity
+    url: Url,
+    /// Cancels the task watching the host
+    cancel_token: CancellationToken,
+}
+
+impl Drop for QueuedRequest {
+    fn drop(&mut self) {
+        // Don't leave the watcher running once the request leaves the queue,
+        // including when the whole view is rebuilt
+        self.cancel_token.cancel();
+    }
+}
+
+/// Emitted when a queued request should be sent or dropped. These come from
+/// both the host watchers and [OfflineQueueMenu].
+#[derive(Debug)]
+pub enum OfflineQueueEvent {
+    /// Send the request now
+    Send(Uuid),
+    /// Drop the request without sending it
+    Cancel(Uuid),
+}
+
+/// Modal listing the requests waiting for the network. The selected request
+/// can be sent immediately, or removed from the queue with the delete
+/// binding.
+#[derive(Debug)]
+pub struct OfflineQueueMenu {
+    id: ComponentId,
+    /// This belongs to the parent, because the modal is rebuilt each time
+    /// it's opened
+    emitter: Emitter<OfflineQueueEvent>,
+    select: Select<QueuedRequestItem>,
+}
+
+impl OfflineQueueMenu {
+    pub fn new(
+        emitter: Emitter<OfflineQueueEvent>,
+        queue: &OfflineQueue,
+    ) -> Self {
+        let collection = ViewContext::collection();
+        let items = queue
+            .0
+            .iter()
+            .map(|request| {
+                // The recipe may have been removed since the request was
+                // queued. Fall back to the ID
+                let name = collection
+                    .recipes
+                    .get_recipe(&request.config.recipe_id)
+                    .map_or(&*request.config.recipe_id, |recipe| recipe.name())
+                    .to_owned();
+                QueuedRequestItem {
+                    id: request.id,
+                    name,
+                    host: request.url.host_str().unwrap_or_default().to_owned(),
+                }
+            })
+            .collect();
+        Self {
+            id: ComponentId::default(),
+            emitter,
+            select: Select::builder(items).build(),
+        }
+    }
+}
+
+impl Component for OfflineQueueMenu {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event.m().action(|action, propagate| match action {
+            Action::Delete => {
+                if let Some(item) = self.select.selected() {
+                    self.emitter.emit(OfflineQueueEvent::Cancel(item.id));
+                }
+            }
+            _ => propagate.set(),
+        })
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for OfflineQueueMenu {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        if self.select.is_empty() {
+            canvas.render_widget(
+                "No requests are waiting for the network",
+                metadata.area(),
+            );
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                metadata.area(),
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for OfflineQueueMenu {
+    fn title(&self) -> Line<'_> {
+        ViewContext::add_binding_hint(
+            tr!("modal-offline-queue-title"),
+            Action::OfflineQueue,
+        )
+        .into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).max(1);
+        (Constraint::Length(40), Constraint::Length(height))
+    }
+
+    fn on_submit(self, _: &mut UpdateContext) {
+        if let Some(item) = self.select.into_selected() {
+            self.emitter.emit(OfflineQueueEvent::Send(item.id));
+        }
+    }
+}
+
+#[derive(Clone, Debug, Display)]
+#[display("{name} ({host})")]
+struct QueuedRequestItem {
+    id: Uuid,
+    /// Recipe name
+    name: String,
+    /// Host that couldn't be reached
+    host: String,
+}
+
+impl ToStringGenerate for QueuedRequestItem {}
@@ -1,29 +1,55 @@
 //! Components for the "primary" view, which is the paned request/response view
 
+mod navigation;
 mod view_state;
 
 use crate::{
     http::{RequestConfig, RequestState, RequestStore},
-    message::{HttpMessage, Message},
+    message::{HttpMessage, Message, ScreenDestination},
+    screenshot::ScreenFormat,
     util::ResultReported,
     view::{
         Component, RequestDisposition, ViewContext,
-        common::actions::MenuItem,
+        common::{
+            actions::MenuItem,
+            modal::{ModalClosed, ModalQueue},
+        },
         component::{
             Canvas, Child, ComponentExt, ComponentId, Draw, DrawMetadata,
             ToChild,
+            audit_log::AuditLog,
+            captures::Captures,
+            changelog::Changelog,
+            cookies::Cookies,
             exchange_pane::ExchangePane,
             history::History,
-            primary::view_state::{
-                DefaultPane, PrimaryLayout, Sidebar, SidebarPane, ViewState,
+            json_path::JsonPathPicker,
+            node_query::NodeQuery,
+            offline_queue::{
+                OfflineQueue, OfflineQueueEvent, OfflineQueueMenu,
+            },
+            primary::{
+                navigation::{Location, Navigation, NavigationModal},
+                view_state::{
+                    DefaultPane, PrimaryLayout, Sidebar, SidebarPane, ViewState,
+                },
             },
             profile::{ProfileDetail, ProfileListState},
+            profile_switcher::{
+                ProfileSwitcher, RecentProfiles, SwitchProfile,
+            },
+            recent_requests::{
+                RecentRequests, RecentRequestsMenu, ResendRequest,
+            },
             recipe::{RecipeDetail, RecipeList},
+            request_chain::RequestChain,
+            response_links::ResponseLinks,
             sidebar_list::{SidebarList, SidebarListEvent, SidebarListProps},
+            toggles::{EnabledToggles, SetToggles, ToggleMenu},
         },
         context::UpdateContext,
         event::{BroadcastEvent, Emitter, Event, EventMatch, ToEmitter},
-        persistent::{PersistentKey, PersistentStore},
+        persistent::{PersistentKey, PersistentStore, SessionKey},
     },
 };
 use indexmap::IndexMap;
@@ -31,15 +57,20 @@ use ratatui::{
     layout::{Layout, Rect, Spacing},
     prelude::Constraint,
 };
+use reqwest::Url;
 use serde::Serialize;
-use slumber_config::Action;
+use slumber_config::{Action, EnvironmentBadge, StartupConfig};
 use slumber_core::{
-    collection::{ProfileId, RecipeId, RecipeNode, RecipeNodeType},
+    collection::{
+        CollectionCommit, ProfileId, RecipeId, RecipeNode, RecipeNodeType,
+        ToggleId,
+    },
     http::RequestId,
+    render::TemplateReference,
 };
-use slumber_template::Template;
-use slumber_util::yaml::SourceLocation;
-use std::iter;
+use slumber_template::{Template, Value};
+use slumber_util::{tr, yaml::SourceLocation};
+use std::{iter, sync::Arc};
 
 /// Primary TUI view, which shows request/response panes
 #[derive(Debug)]
@@ -48,8 +79,60 @@ pub struct PrimaryView {
     // Own state
     /// Current layout and selection state of the view
     view: ViewState,
+    /// Back/forward history of selected recipes/requests
+    navigation: Navigation,
+    /// A location we're navigating to via back/forward. Selecting a new
+    /// profile/recipe rebuilds the history list asynchronously (via events),
+    /// so we have to wait for that before selecting the request.
+    pending_location: Option<Location>,
+    /// Profiles in the order they were last selected, for the switcher
+    recent_profiles: RecentProfiles,
+    /// Emitter for the profile switcher modal. The modal is rebuilt each time
+    /// it opens, so we own the emitter
+    profile_switcher_emitter: Emitter<SwitchProfile>,
+    /// Toggles whose values are layered onto the selected profile
+    enabled_toggles: EnabledToggles,
+    /// Emitter for the toggle menu. The modal is rebuilt each time it opens,
+    /// so we own the emitter
+    toggle_menu_emitter: Emitter<SetToggles>,
+    /// Requests sent in this session, so they can be repeated regardless of
+    /// what's selected
+    recent_requests: RecentRequests,
+    /// Emitter for the recent requests menu. The modal is rebuilt each time it
+    /// opens, so we own the emitter
+    recent_requests_emitter: Emitter<ResendRequest>,
+    /// Requests that failed while offline, waiting for the network
+    offline_queue: OfflineQueue,
+    /// Emitter for the offline queue's host watchers and its menu. We own
+    /// this because the menu is rebuilt each time it opens
+    offline_queue_emitter: Emitter<OfflineQueueEvent>,
+    /// Shared by every modal queue that's recorded in the navigation history,
+    /// so closing a modal can be recorded as a visit
+    modal_closed_emitter: Emitter<ModalClosed>,
 
     // Children
+    /// Quick switcher for profiles
+    profile_switcher: ModalQueue<ProfileSwitcher>,
+    /// Switch profile toggles on and off
+    toggle_menu: ModalQueue<ToggleMenu>,
+    /// Pick a recent request to send again
+    recent_requests_menu: ModalQueue<RecentRequestsMenu>,
+    /// See and cancel requests waiting for the network
+    offline_queue_menu: ModalQueue<OfflineQueueMenu>,
+    /// Recent external effects of template renders
+    audit_log: ModalQueue<AuditLog>,
+    /// Values captured from responses in this session
+    captures: ModalQueue<Captures>,
+    /// View and edit stored cookies
+    cookies: ModalQueue<Cookies>,
+    changelog: ModalQueue<Changelog>,
+    links: ModalQueue<ResponseLinks>,
+    /// Live tree of the upstream requests triggered by a request's chains
+    request_chain: ModalQueue<RequestChain>,
+    /// Query the nodes of an XML/HTML response body
+    node_query: ModalQueue<NodeQuery>,
+    /// Copy JSONPath selectors from a JSON response body
+    json_path: ModalQueue<JsonPathPicker>,
     /// Header/sidebar to select a recipe
     recipe_list: RecipeList,
     /// Recipe preview/detail pane
@@ -70,6 +153,29 @@ pub struct PrimaryView {
 }
 
 impl PrimaryView {
+    /// Override persisted UI state according to the `startup` config. This
+    /// should be called once when the TUI launches, *before* the view is
+    /// built, so the overridden state is loaded as if it was persisted.
+    pub fn apply_startup_config(
+        config: &StartupConfig,
+        store: &mut PersistentStore,
+    ) {
+        if let Some(pane) = config.focus {
+            store.set(&ViewStateKey, &ViewState::startup(pane));
+        }
+        if let Some(tab) = config.recipe_tab {
+            RecipeDetail::select_tab(store, tab);
+        }
+        if let Some(tab) = config.exchange_tab {
+            ExchangePane::select_tab(store, tab);
+        }
+        if !config.open_last_response {
+            // Removed at the end of the first update phase, so only the
+            // requests selected during startup are hidden
+            store.set_session(CloseStartupRequestKey, ());
+        }
+    }
+
     pub fn new() -> Self {
         let view = PersistentStore::get(&ViewStateKey).unwrap_or_default();
 
@@ -87,17 +193,47 @@ impl PrimaryView {
         // We don't have the request store here and there aren't any requests
         // loaded into it yet anyway, so we can't fill out the request yet.
         // There will be a message to load it immediately after though
-        let exchange_pane = ExchangePane::new(None, recipe_node_type);
+        let exchange_pane = ExchangePane::new(None, None, recipe_node_type);
 
         let history = History::new(
             profile_list.selected_id().cloned(),
             recipe_list.selected_recipe_id().cloned(),
         );
+        let modal_closed_emitter = Emitter::default();
 
         Self {
             id: ComponentId::default(),
             view,
-
+            navigation: Navigation::default(),
+            pending_location: None,
+            recent_profiles: RecentProfiles::load(),
+            profile_switcher_emitter: Emitter::default(),
+            enabled_toggles: EnabledToggles::load(),
+            toggle_menu_emitter: Emitter::default(),
+            recent_requests: RecentRequests::default(),
+            recent_requests_emitter: Emitter::default(),
+            offline_queue: OfflineQueue::default(),
+            offline_queue_emitter: Emitter::default(),
+            modal_closed_emitter,
+
+            profile_switcher: ModalQueue::with_close_emitter(
+                modal_closed_emitter,
+            ),
+            toggle_menu: ModalQueue::with_close_emitter(modal_closed_emitter),
+            recent_requests_menu: ModalQueue::with_close_emitter(
+                modal_closed_emitter,
+            ),
+            offline_queue_menu: ModalQueue::with_close_emitter(
+                modal_closed_emitter,
+            ),
+            audit_log: ModalQueue::with_close_emitter(modal_closed_emitter),
+            captures: ModalQueue::default(),
+            cookies: ModalQueue::with_close_emitter(modal_closed_emitter),
+            changelog: ModalQueue::default(),
+            links: ModalQueue::default(),
+            request_chain: ModalQueue::default(),
+            node_query: ModalQueue::default(),
+            json_path: ModalQueue::default(),
             recipe_list,
             recipe_detail,
             profile_list,
@@ -125,6 +261,12 @@ impl PrimaryView {
         self.history.selected_id()
     }
 
+    /// Environment badge for the host of the selected request. `None` if no
+    /// request is selected, or its host doesn't match any badge pattern
+    pub fn environment_badge(&self) -> Option<&EnvironmentBadge> {
+        self.exchange_pane.environment_badge()
+    }
+
     fn selected_recipe_node(&self) -> Option<(&RecipeId, RecipeNodeType)> {
         self.recipe_list.selected()
     }
@@ -147,11 +289,108 @@ impl PrimaryView {
         self.profile_detail.overrides()
     }
 
+    /// IDs of the toggles that are switched on
+    pub fn enabled_toggles(&self) -> Vec<ToggleId> {
+        self.enabled_toggles.to_ids()
+    }
+
     /// Send a request for the currently selected recipe
-    fn send_request(&self) {
+    fn send_request(&mut self) {
+        if let Some(config) = self.request_config() {
+            self.recent_requests.push(config);
+        }
         ViewContext::send_message(HttpMessage::Begin);
     }
 
+    /// Send a previous request again, regardless of what's selected
+    fn resend_request(&mut self, config: RequestConfig) {
+        self.recent_requests.push(config.clone());
+        ViewContext::send_message(HttpMessage::Resend(config));
+    }
+
+    /// Open the menu of requests waiting for the network
+    fn open_offline_queue(&mut self) {
+        self.offline_queue_menu.open(OfflineQueueMenu::new(
+            self.offline_queue_emitter,
+            &self.offline_queue,
+        ));
+    }
+
+    /// Open a modal that's part of the navigation history. This doesn't record
+    /// a visit; see [Self::record_modal]
+    fn open_modal(&mut self, modal: NavigationModal) {
+        match modal {
+            NavigationModal::ProfileSwitcher => {
+                self.profile_switcher.open(ProfileSwitcher::new(
+                    self.profile_switcher_emitter,
+                    &self.recent_profiles,
+                ));
+            }
+            NavigationModal::Toggles => {
+                self.toggle_menu.open(ToggleMenu::new(
+                    self.toggle_menu_emitter,
+                    &self.enabled_toggles,
+                ));
+            }
+            NavigationModal::RecentRequests => {
+                self.recent_requests_menu.open(RecentRequestsMenu::new(
+                    self.recent_requests_emitter,
+                    &self.recent_requests,
+                ));
+            }
+            NavigationModal::OfflineQueue => self.open_offline_queue(),
+            NavigationModal::AuditLog => self.audit_log.open(AuditLog::new()),
+            NavigationModal::Cookies => self.cookies.open(Cookies::new()),
+        }
+    }
+
+    /// Which modal from the navigation history is visible, if any?
+    fn visible_modal(&self) -> Option<NavigationModal> {
+        [
+            (
+                self.profile_switcher.is_open(),
+                NavigationModal::ProfileSwitcher,
+            ),
+            (self.toggle_menu.is_open(), NavigationModal::Toggles),
+            (
+                self.recent_requests_menu.is_open(),
+                NavigationModal::RecentRequests,
+            ),
+            (
+                self.offline_queue_menu.is_open(),
+                NavigationModal::OfflineQueue,
+            ),
+            (self.audit_log.is_open(), NavigationModal::AuditLog),
+            (self.cookies.is_open(), NavigationModal::Cookies),
+        ]
+        .into_iter()
+        .find_map(|(is_open, modal)| is_open.then_some(modal))
+    }
+
+    /// Close every modal that's part of the navigation history
+    fn close_modals(&mut self) {
+        self.profile_switcher.close();
+        self.toggle_menu.close();
+        self.recent_requests_menu.close();
+        self.offline_queue_menu.close();
+        self.audit_log.close();
+        self.cookies.close();
+    }
+
+    /// Record a visit to the current location with whichever modal is now
+    /// visible. Call this after the user opens or closes a modal. If it's the
+    /// same as the current location (e.g. the modal was closed because we
+    /// navigated away from it), nothing is recorded.
+    fn record_modal(&mut self) {
+        if let Some(current) = self.navigation.current() {
+            let location = Location {
+                modal: self.visible_modal(),
+                ..current.clone()
+            };
+            self.navigation.visit(location);
+        }
+    }
+
     /// Refresh the recipe preview. Call this whenever the selected recipe *or*
     /// profile changes
     fn refresh_recipe(&mut self) {
@@ -175,6 +414,7 @@ impl PrimaryView {
         // Refresh history list. This has to happen first so the
         // select_request() call below has access to the latest request
         self.history.refresh(store);
+        self.refresh_request_chain(store, &disposition);
 
         match disposition {
             RequestDisposition::Change(request_id) => {
@@ -183,8 +423,7 @@ impl PrimaryView {
                 if Some(request_id) == self.selected_request_id() {
                     // If the request isn't in the store, that means it was just
                     // deleted
-                    let state = store.get(request_id);
-                    self.set_request(state);
+                    self.set_request(store, Some(request_id));
                 }
             }
             RequestDisposition::ChangeAll(request_ids) => {
@@ -194,8 +433,7 @@ impl PrimaryView {
                 {
                     // If the request isn't in the store, that means it was just
                     // deleted
-                    let state = store.get(request_id);
-                    self.set_request(state);
+                    self.set_request(store, Some(request_id));
                 }
             }
             RequestDisposition::Select(request_id) => {
@@ -216,10 +454,9 @@ impl PrimaryView {
                 // If a new prompt appears for a request that isn't selected, we
                 // *don't* want to switch to it
                 if Some(request_id) == self.selected_request_id() {
-                    // State *should* be Some here because the form just updated
-                    let state = store.get(request_id);
-                    // Update the view with the new prompt
-                    self.set_request(state);
+                    // State *should* be Some here because the form just
+                    // updated. Update the view with the new prompt
+                    self.set_request(store, Some(request_id));
                     // Select the form pane
                     self.view.select_exchange_pane();
                 }
@@ -227,11 +464,62 @@ impl PrimaryView {
         }
     }
 
+    /// Open, update, or close the request chain modal. The modal opens as soon
+    /// as a request triggers an upstream request, and closes on its own once
+    /// every request in the chain succeeds. If anything in the chain fails,
+    /// it stays open so the user can see where.
+    fn refresh_request_chain(
+        &mut self,
+        store: &RequestStore,
+        disposition: &RequestDisposition,
+    ) {
+        if let Some(chain) = self.request_chain.active_mut() {
+            chain.refresh(store);
+            if chain.is_complete() && !chain.has_error() {
+                self.request_chain.close();
+            }
+        } else if let RequestDisposition::Change(request_id) = disposition
+            && let Some(RequestState::Building { .. }) = store.get(*request_id)
+        {
+            let root_id = store.chain_root(*request_id);
+            if root_id != *request_id {
+                self.request_chain.open(RequestChain::new(root_id, store));
+            }
+        }
+    }
+
     /// Update the Exchange pane with the selected request. Call this whenever
-    /// a new request is selected or the selected request changes.
-    fn set_request(&mut self, selected_request: Option<&RequestState>) {
+    /// a new request is selected or the selected request changes. The request
+    /// will be loaded from the DB if it isn't in the store yet.
+    fn set_request(
+        &mut self,
+        store: &mut RequestStore,
+        request_id: Option<RequestId>,
+    ) {
+        // The request selected at startup is hidden until the user selects one
+        if request_id.is_some()
+            && PersistentStore::get_session(&CloseStartupRequestKey).is_some()
+        {
+            self.exchange_pane = ExchangePane::closed();
+            return;
+        }
+
+        let messages_tx = ViewContext::messages_tx();
+        // If enabled, find the response to diff this one against. Do this
+        // first, because the selected request borrows the store
+        let previous_response = request_id
+            .filter(|_| ViewContext::config().tui.diff_gutter)
+            .and_then(|id| {
+                store.load_previous_exchange(id).reported(&messages_tx)
+            })
+            .flatten()
+            .map(|exchange| Arc::clone(&exchange.response));
+        let selected_request = request_id
+            .and_then(|id| store.load(id).reported(&messages_tx))
+            .flatten();
         self.exchange_pane = ExchangePane::new(
             selected_request,
+            previous_response,
             self.selected_recipe_node().map(|(_, node_type)| node_type),
         );
     }
@@ -247,6 +535,174 @@ impl PrimaryView {
         RecipeDetail::new(node)
     }
 
+    /// Go back/forward to a previously selected location. Locations that no
+    /// longer exist in the collection are skipped.
+    fn navigate(&mut self, forward: bool) {
+        let collection = ViewContext::collection();
+        let location = loop {
+            let location = if forward {
+                self.navigation.forward()
+            } else {
+                self.navigation.back()
+            };
+            let Some(location) = location else {
+                return;
+            };
+            let exists = location
+                .profile
+                .as_ref()
+                .is_none_or(|id| collection.profiles.contains_key(id))
+                && collection.recipes.get_recipe(&location.recipe).is_some();
+            if exists {
+                break location.clone();
+            }
+            self.navigation.discard_current();
+        };
+
+        if self.visible_modal() != location.modal {
+            self.close_modals();
+            if let Some(modal) = location.modal {
+                self.open_modal(modal);
+            }
+        }
+        if let Some(profile_id) = &location.profile {
+            self.profile_list.select_id(profile_id);
+        }
+        self.recipe_list.select_recipe(&location.recipe);
+        if self.selected_profile_id() != location.profile.as_ref()
+            || self.selected_recipe_id() != Some(&location.recipe)
+        {
+            // Selection failed (e.g. the item is filtered out). Whatever is
+            // selected now will be recorded as a new visit
+            self.navigation.discard_current();
+            self.pending_location = None;
+        } else if self
+            .history
+            .is_showing(location.profile.as_ref(), &location.recipe)
+        {
+            // Profile and recipe didn't change, so the request list is
+            // already correct
+            self.restore_request(location);
+        } else {
+            // Wait for the history list to rebuild before selecting the
+            // request
+            self.pending_location = Some(location);
+        }
+    }
+
+    /// Select the request from a location being navigated to. The history
+    /// list must already be showing the location's profile/recipe. If the
+    /// request is gone, we'll land on whatever the list selects instead.
+    fn restore_request(&mut self, location: Location) {
+        if let Some(request_id) = location.request {
+            self.history.select_request(request_id);
+        }
+        self.navigation.replace(Location {
+            request: self.history.selected_id(),
+            ..location
+        });
+    }
+
+    /// Select a recipe and/or profile, as requested by an external process
+    /// Show values captured from responses in a modal
+    pub fn captures(&mut self, captures: Vec<(String, Value)>) {
+        self.captures.open(Captures::new(captures));
+    }
+
+    /// Show the git history of the collection file in a modal
+    pub fn changelog(&mut self, commits: Vec<CollectionCommit>) {
+        self.changelog
+            .open(Changelog::new(commits, self.selected_recipe_id()));
+    }
+
+    /// Hold a request that failed while offline, to send again once its host
+    /// can be reached
+    pub fn queue_offline(&mut self, config: RequestConfig, url: Url) {
+        let host = url.host_str().unwrap_or_default().to_owned();
+        self.offline_queue
+            .push(config, url, self.offline_queue_emitter);
+        ViewContext::send_message(Message::Notify(tr!(
+            "notify-request-queued",
+            host = host,
+            count = self.offline_queue.len(),
+        )));
+    }
+
+    pub fn open(
+        &mut self,
+        recipe_id: Option<&RecipeId>,
+        profile_id: Option<&ProfileId>,
+    ) {
+        let collection = ViewContext::collection();
+        if let Some(profile_id) = profile_id {
+            if collection.profiles.contains_key(profile_id) {
+                self.switch_profile(profile_id);
+            } else {
+                ViewContext::send_message(Message::Notify(tr!(
+                    "notify-unknown-profile",
+                    profile = profile_id
+                )));
+            }
+        }
+        if let Some(recipe_id) = recipe_id {
+            if collection.recipes.get(recipe_id).is_some() {
+                self.recipe_list.select_recipe(recipe_id);
+                self.view.select_recipe_pane();
+            } else {
+                ViewContext::send_message(Message::Notify(tr!(
+                    "notify-unknown-recipe",
+                    recipe = recipe_id
+                )));
+            }
+        }
+    }
+
+    /// Navigate to the definition of something referenced by a template
+    fn go_to_definition(&mut self, reference: TemplateReference) {
+        let collection = ViewContext::collection();
+        match reference {
+            TemplateReference::ProfileField(field) => {
+                let profile = self
+                    .selected_profile_id()
+                    .and_then(|id| collection.profiles.get(id));
+                if let Some(profile) = profile
+                    && profile.data.contains_key(field.as_str())
+                {
+                    self.profile_detail.select_field(&field);
+                    self.view.open_sidebar(Sidebar::Profile);
+                    self.view.select_profile_pane();
+                } else {
+                    ViewContext::send_message(Message::Notify(tr!(
+                        "notify-unknown-field",
+                        field = field
+                    )));
+                }
+            }
+            TemplateReference::Recipe(recipe_id) => {
+                if collection.recipes.get_recipe(&recipe_id).is_some() {
+                    self.recipe_list.select_recipe(&recipe_id);
+                    self.view.select_recipe_pane();
+                } else {
+                    ViewContext::send_message(Message::Notify(tr!(
+                        "notify-unknown-recipe",
+                        recipe = recipe_id
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Select a profile chosen from the profile switcher
+    fn switch_profile(&mut self, profile_id: &ProfileId) {
+        self.profile_list.select_id(profile_id);
+        if self.selected_profile_id() != Some(profile_id) {
+            ViewContext::send_message(Message::Notify(tr!(
+                "notify-profile-hidden",
+                profile = profile_id
+            )));
+        }
+    }
+
     /// Should a cancel action close the sidebar?
     fn can_close_sidebar(&self, request_store: &RequestStore) -> bool {
         // If the sidebar is open and the request is *not* cancellable. We want
@@ -467,15 +923,51 @@ impl Component for PrimaryView {
                 Action::Submit => self.send_request(),
 
                 // Pane hotkeys
-                Action::History => self.view.open_sidebar(Sidebar::History),
+                Action::History => {
+                    self.view.open_sidebar(Sidebar::History);
+                    // If the startup request was hidden, the user's looking
+                    // for it now
+                    if self.exchange_pane.is_closed() {
+                        context
+                            .persistent_store
+                            .remove_session(&CloseStartupRequestKey);
+                        self.set_request(
+                            context.request_store,
+                            self.selected_request_id(),
+                        );
+                    }
+                }
                 Action::SelectProfileList => {
                     self.view.open_sidebar(Sidebar::Profile);
                 }
+                Action::SwitchProfile => {
+                    self.open_modal(NavigationModal::ProfileSwitcher);
+                    self.record_modal();
+                }
+                Action::OpenToggles => {
+                    self.open_modal(NavigationModal::Toggles);
+                    self.record_modal();
+                }
+                Action::ResendLast => {
+                    if let Some(config) = self.recent_requests.last() {
+                        self.resend_request(config.clone());
+                    }
+                }
+                Action::RecentRequests => {
+                    self.open_modal(NavigationModal::RecentRequests);
+                    self.record_modal();
+                }
+                Action::OfflineQueue => {
+                    self.open_modal(NavigationModal::OfflineQueue);
+                    self.record_modal();
+                }
                 Action::SelectRecipeList => {
                     self.view.open_sidebar(Sidebar::Recipe);
                 }
                 Action::SelectTopPane => self.view.select_top_pane(),
                 Action::SelectBottomPane => self.view.select_bottom_pane(),
+                Action::Back => self.navigate(false),
+                Action::Forward => self.navigate(true),
 
                 // Toggle fullscreen
                 Action::Fullscreen => self.view.toggle_fullscreen(),
@@ -493,7 +985,10 @@ impl Component for PrimaryView {
             })
             .broadcast(|event| match event {
                 // Refresh previews when selected profile/recipe changes
-                BroadcastEvent::SelectedProfile(_) => {
+                BroadcastEvent::SelectedProfile(profile_id) => {
+                    if let Some(profile_id) = &profile_id {
+                        self.recent_profiles.visit(profile_id);
+                    }
                     // Both panes can change when the profile changes
                     self.profile_detail =
                         ProfileDetail::new(self.profile_list.selected_id());
@@ -503,17 +998,70 @@ impl Component for PrimaryView {
                 BroadcastEvent::SelectedRequest(request_id) => {
                     // When a new request is selected, make sure it's loaded
                     // from the DB, then put it in the Exchange pane
-                    let state = request_id.and_then(|id| {
-                        context
-                            .request_store
-                            .load(id)
-                            .reported(&ViewContext::messages_tx())
-                            .flatten()
-                    });
-                    self.set_request(state);
+                    self.set_request(context.request_store, request_id);
+
+                    if self.pending_location.is_some() {
+                        // Wait until the history list has caught up to the
+                        // profile/recipe we're navigating to
+                        let history = &self.history;
+                        if let Some(location) =
+                            self.pending_location.take_if(|location| {
+                                history.is_showing(
+                                    location.profile.as_ref(),
+                                    &location.recipe,
+                                )
+                            })
+                        {
+                            self.restore_request(location);
+                        }
+                    } else if let Some(recipe_id) = self.history.recipe_id() {
+                        self.navigation.visit(Location {
+                            profile: self.history.profile_id().cloned(),
+                            recipe: recipe_id.clone(),
+                            request: request_id,
+                            modal: self.visible_modal(),
+                        });
+                    }
                 }
                 BroadcastEvent::RefreshPreviews => {}
             })
+            .any(|event| match event {
+                Event::GoToDefinition(reference) => {
+                    self.go_to_definition(reference);
+                    None
+                }
+                Event::GoToFolder(folder_id) => {
+                    self.recipe_list.select_recipe(&folder_id);
+                    self.view.select_recipe_pane();
+                    None
+                }
+                Event::OpenLinks(links) => {
+                    self.links.open(ResponseLinks::new(links));
+                    None
+                }
+                Event::QueryNodes(response) => {
+                    match NodeQuery::new(&response) {
+                        Ok(modal) => self.node_query.open(modal),
+                        Err(error) => {
+                            ViewContext::send_message(Message::Error { error });
+                        }
+                    }
+                    None
+                }
+                Event::PickJsonPath {
+                    recipe_id,
+                    response,
+                } => {
+                    match JsonPathPicker::new(recipe_id, &response) {
+                        Ok(modal) => self.json_path.open(modal),
+                        Err(error) => {
+                            ViewContext::send_message(Message::Error { error });
+                        }
+                    }
+                    None
+                }
+                _ => Some(event),
+            })
             .emitted(self.recipe_list.to_emitter(), |event| match event {
                 SidebarListEvent::Open => {
                     self.view.open_sidebar(Sidebar::Recipe);
@@ -536,6 +1084,35 @@ impl Component for PrimaryView {
                 }
                 SidebarListEvent::Close => self.view.close_sidebar(),
             })
+            .emitted(self.profile_switcher_emitter, |SwitchProfile(id)| {
+                self.switch_profile(&id);
+            })
+            .emitted(self.recent_requests_emitter, |ResendRequest(config)| {
+                // Number keys pick a request without submitting the modal
+                self.recent_requests_menu.close();
+                self.resend_request(config);
+            })
+            .emitted(self.offline_queue_emitter, |event| match event {
+                OfflineQueueEvent::Send(id) => {
+                    if let Some(config) = self.offline_queue.remove(id) {
+                        self.resend_request(config);
+                    }
+                }
+                OfflineQueueEvent::Cancel(id) => {
+                    self.offline_queue.remove(id);
+                    // Rebuild the menu without the cancelled request
+                    self.offline_queue_menu.close();
+                    self.open_offline_queue();
+                }
+            })
+            .emitted(self.modal_closed_emitter, |ModalClosed| {
+                self.record_modal();
+            })
+            .emitted(self.toggle_menu_emitter, |SetToggles(enabled)| {
+                self.enabled_toggles = enabled;
+                // Any template could reference a toggled field
+                ViewContext::push_event(BroadcastEvent::RefreshPreviews);
+            })
             // Handle our own menu action type
             .emitted(self.global_actions_emitter, |menu_action| {
                 match menu_action {
@@ -545,6 +1122,43 @@ impl Component for PrimaryView {
                             location,
                         });
                     }
+                    PrimaryMenuAction::ClearCommandCache => {
+                        // The message will be handled before the preview
+                        // renders are, so they'll see the empty cache
+                        ViewContext::send_message(Message::ClearCommandCache);
+                        ViewContext::push_event(
+                            BroadcastEvent::RefreshPreviews,
+                        );
+                    }
+                    PrimaryMenuAction::ViewAuditLog => {
+                        self.open_modal(NavigationModal::AuditLog);
+                        self.record_modal();
+                    }
+                    PrimaryMenuAction::ViewCaptures => {
+                        // Captures are owned by the main loop, which will call
+                        // back into the view to open the modal
+                        ViewContext::send_message(Message::ViewCaptures);
+                    }
+                    PrimaryMenuAction::ViewCookies => {
+                        self.open_modal(NavigationModal::Cookies);
+                        self.record_modal();
+                    }
+                    PrimaryMenuAction::ViewChangelog => {
+                        // The main loop knows where the collection file is, and
+                        // loads the history in the background
+                        ViewContext::send_message(Message::ViewChangelog);
+                    }
+                    PrimaryMenuAction::ViewLog => {
+                        // Opened in the pager, like any other file view
+                        ViewContext::send_message(Message::ViewLog);
+                    }
+                    PrimaryMenuAction::ExportScreen(format, destination) => {
+                        // Only the main loop can draw the whole screen
+                        ViewContext::send_message(Message::ExportScreen {
+                            format,
+                            destination,
+                        });
+                    }
                 }
             })
     }
@@ -558,19 +1172,19 @@ impl Component for PrimaryView {
         let edit_recipe = match selected_recipe_node {
             None => emitter.menu(
                 PrimaryMenuAction::EditCollection(None),
-                "Edit Collection",
+                tr!("menu-edit-collection"),
             ),
             Some(RecipeNode::Folder(folder)) => emitter.menu(
                 PrimaryMenuAction::EditCollection(Some(
                     folder.location.clone(),
                 )),
-                "Edit Folder",
+                tr!("menu-edit-folder"),
             ),
             Some(RecipeNode::Recipe(recipe)) => emitter.menu(
                 PrimaryMenuAction::EditCollection(Some(
                     recipe.location.clone(),
                 )),
-                "Edit Recipe",
+                tr!("menu-edit-recipe"),
             ),
         };
         let profile_location = self.selected_profile_id().and_then(|id| {
@@ -580,19 +1194,98 @@ impl Component for PrimaryView {
         let edit_profile = emitter
             .menu(
                 PrimaryMenuAction::EditCollection(profile_location.cloned()),
-                "Edit Profile",
+                tr!("menu-edit-profile"),
             )
             .enable(profile_location.is_some());
 
-        vec![edit_recipe.into(), edit_profile.into()]
+        let clear_command_cache = emitter.menu(
+            PrimaryMenuAction::ClearCommandCache,
+            tr!("menu-clear-command-cache"),
+        );
+
+        let view_audit_log = emitter
+            .menu(PrimaryMenuAction::ViewAuditLog, tr!("menu-view-audit-log"));
+
+        let view_captures = emitter
+            .menu(PrimaryMenuAction::ViewCaptures, tr!("menu-view-captures"));
+
+        let view_cookies = emitter
+            .menu(PrimaryMenuAction::ViewCookies, tr!("menu-view-cookies"));
+
+        let view_changelog = emitter
+            .menu(PrimaryMenuAction::ViewChangelog, tr!("menu-view-changelog"));
+
+        let view_log =
+            emitter.menu(PrimaryMenuAction::ViewLog, tr!("menu-view-log"));
+
+        let formats = [
+            (ScreenFormat::Text, tr!("menu-text")),
+            (ScreenFormat::Ansi, "ANSI".to_owned()),
+            (ScreenFormat::Svg, "SVG".to_owned()),
+        ];
+        let copy_screen = formats.iter().map(|(format, name)| {
+            emitter
+                .menu(
+                    PrimaryMenuAction::ExportScreen(
+                        *format,
+                        ScreenDestination::Clipboard,
+                    ),
+                    tr!("menu-copy-as", format = name),
+                )
+                .into()
+        });
+        let save_screen = formats.iter().map(|(format, name)| {
+            emitter
+                .menu(
+                    PrimaryMenuAction::ExportScreen(
+                        *format,
+                        ScreenDestination::File,
+                    ),
+                    tr!("menu-save-as", format = name),
+                )
+                .into()
+        });
+        let export_screen = MenuItem::Group {
+            name: tr!("menu-export-screen"),
+            children: copy_screen.chain(save_screen).collect(),
+        };
+
+        vec![
+            edit_recipe.into(),
+            edit_profile.into(),
+            clear_command_cache.into(),
+            view_audit_log.into(),
+            view_captures.into(),
+            view_cookies.into(),
+            view_changelog.into(),
+            view_log.into(),
+            export_screen,
+        ]
     }
 
     fn persist(&self, store: &mut PersistentStore) {
         store.set(&ViewStateKey, &self.view);
+        self.recent_profiles.persist(store);
+        self.enabled_toggles.persist(store);
+        // Startup is over, so any subsequent request selection is shown
+        store.remove_session(&CloseStartupRequestKey);
     }
 
     fn children(&mut self) -> Vec<Child<'_>> {
         vec![
+            // Modal gets priority. It won't eat events when closed
+            self.profile_switcher.to_child_mut(),
+            self.toggle_menu.to_child_mut(),
+            self.recent_requests_menu.to_child_mut(),
+            self.offline_queue_menu.to_child_mut(),
+            self.audit_log.to_child_mut(),
+            self.captures.to_child_mut(),
+            self.cookies.to_child_mut(),
+            self.changelog.to_child_mut(),
+            self.links.to_child_mut(),
+            self.request_chain.to_child_mut(),
+            self.node_query.to_child_mut(),
+            self.json_path.to_child_mut(),
             self.recipe_list.to_child_mut(),
             self.recipe_detail.to_child_mut(),
             self.profile_list.to_child_mut(),
@@ -607,7 +1300,9 @@ impl Draw for PrimaryView {
     fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
         let area = metadata.area();
 
-        if self.view.is_fullscreen() {
+        // Reader mode always shows one pane at a time, so screen readers
+        // don't interleave lines from side-by-side panes
+        if self.view.is_fullscreen() || ViewContext::config().tui.reader_mode {
             // Fullscreen - just a single pane
             self.draw_fullscreen(canvas, area);
         } else {
@@ -624,6 +1319,20 @@ impl Draw for PrimaryView {
                 }
             }
         }
+
+        // Switcher is drawn on top of everything else
+        canvas.draw(&self.profile_switcher, (), area, true);
+        canvas.draw(&self.toggle_menu, (), area, true);
+        canvas.draw(&self.recent_requests_menu, (), area, true);
+        canvas.draw(&self.offline_queue_menu, (), area, true);
+        canvas.draw(&self.audit_log, (), area, true);
+        canvas.draw(&self.captures, (), area, true);
+        canvas.draw(&self.cookies, (), area, true);
+        canvas.draw(&self.changelog, (), area, true);
+        canvas.draw(&self.links, (), area, true);
+        canvas.draw(&self.request_chain, (), area, true);
+        canvas.draw(&self.node_query, (), area, true);
+        canvas.draw(&self.json_path, (), area, true);
     }
 }
 
@@ -635,12 +1344,35 @@ impl PersistentKey for ViewStateKey {
     type Value = ViewState;
 }
 
+/// Session key set when `startup.open_last_response` is disabled, to keep the
+/// Exchange pane closed until the startup phase is over
+#[derive(Debug, PartialEq)]
+struct CloseStartupRequestKey;
+
+impl SessionKey for CloseStartupRequestKey {
+    type Value = ();
+}
+
 /// Menu actions available in all contexts
 #[derive(Clone, Debug)]
 enum PrimaryMenuAction {
     /// Open the collection file in an external editor, jumping to the
     /// specified location (if any)
     EditCollection(Option<SourceLocation>),
+    /// Clear cached `command()` outputs and refresh previews
+    ClearCommandCache,
+    /// Open the audit log modal
+    ViewAuditLog,
+    /// Open the captured values modal
+    ViewCaptures,
+    /// Open the cookie jar modal
+    ViewCookies,
+    /// Open the collection file's git history modal
+    ViewChangelog,
+    /// Open Slumber's log file in the pager
+    ViewLog,
+    /// Export the whole screen in some format
+    ExportScreen(ScreenFormat, ScreenDestination),
 }
 
 #[cfg(test)]
@@ -648,14 +1380,24 @@ mod tests {
     use super::*;
     use crate::{
         http::RequestConfig,
-        message::{Message, RecipeCopyTarget},
+        message::{HttpMessage, Message, RecipeCopyTarget},
         test_util::{TestTerminal, terminal},
         view::test_util::{TestComponent, TestHarness, harness},
     };
+    use indexmap::indexmap;
+    use itertools::Itertools;
     use rstest::rstest;
-    use slumber_core::http::BuildOptions;
-    use slumber_util::assert_matches;
-    use terminput::KeyCode;
+    use slumber_config::{Config, StartupPane};
+    use slumber_core::{
+        collection::{
+            Authentication, Collection, Folder, Profile, Recipe, Toggle,
+        },
+        http::{BuildOptions, Exchange, ResponseRecord},
+        test_util::{by_id, header_map},
+    };
+    use slumber_util::{Factory, assert_matches};
+    use terminput::{KeyCode, KeyModifiers};
+    use tokio_util::sync::CancellationToken;
 
     /// Create component to be tested
     fn create_component<'term>(
@@ -695,6 +1437,92 @@ mod tests {
         assert_eq!(component.view, view);
     }
 
+    /// Startup config overrides the persisted pane selection
+    #[rstest]
+    fn test_startup_focus(mut harness: TestHarness, terminal: TestTerminal) {
+        let mut view = ViewState::default();
+        view.toggle_fullscreen();
+        harness.persistent_store().set(&ViewStateKey, &view);
+
+        let config = StartupConfig {
+            focus: Some(StartupPane::History),
+            ..StartupConfig::default()
+        };
+        PrimaryView::apply_startup_config(
+            &config,
+            &mut harness.persistent_store(),
+        );
+
+        let component = create_component(&mut harness, &terminal);
+        assert_eq!(component.view, ViewState::startup(StartupPane::History));
+    }
+
+    /// With `open_last_response` disabled, the request selected at startup is
+    /// hidden until the History list is opened
+    #[rstest]
+    fn test_startup_close_request(
+        harness: TestHarness,
+        terminal: TestTerminal,
+    ) {
+        let recipe_id = harness.collection.first_recipe_id();
+        let profile_id = harness.collection.first_profile_id();
+        let exchange =
+            Exchange::factory((Some(profile_id.clone()), recipe_id.clone()));
+        harness.database.insert_exchange(&exchange).unwrap();
+
+        let config = StartupConfig {
+            open_last_response: false,
+            ..StartupConfig::default()
+        };
+        PrimaryView::apply_startup_config(
+            &config,
+            &mut harness.persistent_store(),
+        );
+        let mut component =
+            TestComponent::builder(&harness, &terminal, PrimaryView::new())
+                .with_default_props()
+                .with_assert_events(|_| {})
+                .build();
+        assert!(component.exchange_pane.is_closed());
+
+        // End of startup; the session key is cleared
+        component.persist(&mut harness.persistent_store());
+        component
+            .int()
+            .send_key(KeyCode::Char('h'))
+            .assert()
+            .empty();
+        assert_eq!(component.exchange_pane.request_id(), Some(exchange.id));
+    }
+
+    /// Reader mode shows only the selected pane, filling the screen. ASCII
+    /// mode draws the same borders with plain characters
+    #[rstest]
+    #[case::default(false, false, "╰")]
+    #[case::reader_mode(true, false, "╚")]
+    #[case::ascii(false, true, "+")]
+    #[case::ascii_reader_mode(true, true, "#")]
+    fn test_reader_mode(
+        #[with(40, 20)] terminal: TestTerminal,
+        #[case] reader_mode: bool,
+        #[case] ascii: bool,
+        #[case] expected_corner: &str,
+    ) {
+        let mut config = Config::default();
+        config.tui.reader_mode = reader_mode;
+        config.tui.theme.ascii = ascii;
+        let mut harness =
+            TestHarness::with_config(Collection::factory(()), config);
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+
+        // The bottom of the screen is either the unselected exchange pane, or
+        // the selected recipe pane
+        let buffer = terminal.buffer();
+        let bottom_left = &buffer[(0, buffer.area.height - 1)];
+        assert_eq!(bottom_left.symbol(), expected_corner);
+    }
+
     /// Test the request_config() getter
     #[rstest]
     fn test_request_config(mut harness: TestHarness, terminal: TestTerminal) {
@@ -743,6 +1571,171 @@ mod tests {
         assert_eq!(location, expected_location);
     }
 
+    /// Test "Clear Command Cache" action
+    #[rstest]
+    fn test_clear_command_cache(
+        mut harness: TestHarness,
+        terminal: TestTerminal,
+    ) {
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+        harness.messages().clear(); // Clear init junk
+
+        component
+            .int()
+            .action(&["Clear Command Cache"])
+            .assert()
+            .broadcast([BroadcastEvent::RefreshPreviews]);
+        // Previews may be re-rendered after, but the cache is cleared first
+        assert_matches!(
+            harness.messages().pop_now(),
+            Message::ClearCommandCache
+        );
+    }
+
+    /// Test "View Captured Values" action. The main loop owns the captures, so
+    /// the view asks it to open the modal
+    #[rstest]
+    fn test_view_captures(mut harness: TestHarness, terminal: TestTerminal) {
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+        harness.messages().clear(); // Clear init junk
+
+        component
+            .int()
+            .action(&["View Captured Values"])
+            .assert()
+            .empty();
+        assert_matches!(harness.messages().pop_now(), Message::ViewCaptures);
+
+        component.captures(vec![("token".into(), "hunter2".into())]);
+        assert!(component.captures.is_open());
+    }
+
+    /// Test "View Collection History" action. The main loop knows where the
+    /// collection file is, so the view asks it to load the history
+    #[rstest]
+    fn test_view_changelog(mut harness: TestHarness, terminal: TestTerminal) {
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+        harness.messages().clear(); // Clear init junk
+
+        component
+            .int()
+            .action(&["View Collection History"])
+            .assert()
+            .empty();
+        assert_matches!(harness.messages().pop_now(), Message::ViewChangelog);
+
+        component.changelog(vec![]);
+        assert!(component.changelog.is_open());
+    }
+
+    /// Test "View Log" action. The main loop opens the log in the pager
+    #[rstest]
+    fn test_view_log(mut harness: TestHarness, terminal: TestTerminal) {
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+        harness.messages().clear(); // Clear init junk
+
+        component.int().action(&["View Log"]).assert().empty();
+        assert_matches!(harness.messages().pop_now(), Message::ViewLog);
+    }
+
+    /// Test "View Cookies" action
+    #[rstest]
+    fn test_view_cookies(mut harness: TestHarness, terminal: TestTerminal) {
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+
+        component.int().action(&["View Cookies"]).assert().empty();
+        assert!(component.cookies.is_open());
+    }
+
+    /// Test "Export Screen" actions. Only the main loop can draw the whole
+    /// screen, so the view asks it to do the export
+    #[rstest]
+    #[case::copy_text(
+        "Copy as Text",
+        ScreenFormat::Text,
+        ScreenDestination::Clipboard
+    )]
+    #[case::save_svg("Save as SVG", ScreenFormat::Svg, ScreenDestination::File)]
+    fn test_export_screen(
+        mut harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] action: &str,
+        #[case] expected_format: ScreenFormat,
+        #[case] expected_destination: ScreenDestination,
+    ) {
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+        harness.messages().clear(); // Clear init junk
+
+        component
+            .int()
+            .action(&["Export Screen", action])
+            .assert()
+            .empty();
+        let (format, destination) = assert_matches!(
+            harness.messages().pop_now(),
+            Message::ExportScreen { format, destination } => (format, destination),
+        );
+        assert_eq!(format, expected_format);
+        assert_eq!(destination, expected_destination);
+    }
+
+    /// The request chain modal opens when a request triggers an upstream
+    /// request, and closes itself once the whole chain succeeds. If any
+    /// request fails, it stays open
+    #[rstest]
+    #[case::success(false, false)]
+    #[case::error(true, true)]
+    fn test_request_chain(
+        mut harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] cancel: bool,
+        #[case] expected_open: bool,
+    ) {
+        let mut component = create_component(&mut harness, &terminal);
+        let recipe_id = harness.collection.first_recipe_id().clone();
+        let root = Exchange::factory(());
+        let child = Exchange::factory(());
+        let (root_id, child_id) = (root.id, child.id);
+        let mut store = harness.request_store_mut();
+
+        store.start(
+            root_id,
+            None,
+            recipe_id.clone(),
+            Some(CancellationToken::new()),
+        );
+        component
+            .refresh_request(&mut store, RequestDisposition::Change(root_id));
+        // Nothing triggered yet
+        assert!(!component.request_chain.is_open());
+
+        store.trigger(child_id, Some(root_id), None, recipe_id);
+        component
+            .refresh_request(&mut store, RequestDisposition::Change(child_id));
+        assert!(component.request_chain.is_open());
+
+        store.response(child);
+        component
+            .refresh_request(&mut store, RequestDisposition::Change(child_id));
+        // Root is still building
+        assert!(component.request_chain.is_open());
+
+        if cancel {
+            store.cancel(root_id);
+        } else {
+            store.response(root);
+        }
+        component
+            .refresh_request(&mut store, RequestDisposition::Change(root_id));
+        assert_eq!(component.request_chain.is_open(), expected_open);
+    }
+
     /// Test actions under the "Copy" submenu. This should be available in
     /// both the recipe list and recipe detail pane
     #[rstest]
@@ -784,4 +1777,633 @@ mod tests {
         );
         assert_eq!(actual_target, expected_target);
     }
+
+    /// Test back/forward navigation between recipes and requests
+    #[rstest]
+    fn test_back_forward(terminal: TestTerminal) {
+        let collection = Collection {
+            name: None,
+            template_delimiters: None,
+            variables: IndexMap::new(),
+            recipes: by_id([Recipe::factory("r1"), Recipe::factory("r2")])
+                .into(),
+            profiles: by_id([Profile::factory(())]),
+            toggles: IndexMap::new(),
+        };
+        let mut harness = TestHarness::new(collection);
+        let profile_id = harness.collection.first_profile_id().clone();
+        let r1: RecipeId = "r1".into();
+        let r2: RecipeId = "r2".into();
+        // Populate history for the second recipe, newest first
+        let exchanges = (0..2)
+            .map(|_| Exchange::factory((Some(profile_id.clone()), r2.clone())))
+            .sorted_by_key(|exchange| exchange.start_time)
+            .rev()
+            .collect_vec();
+        for exchange in &exchanges {
+            harness.database.insert_exchange(exchange).unwrap();
+        }
+        let mut component = create_component(&mut harness, &terminal);
+
+        // Select r2, then the older request within it
+        component
+            .int()
+            .send_keys([KeyCode::Char('r'), KeyCode::Down])
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some(r2.clone())),
+                BroadcastEvent::SelectedRequest(Some(exchanges[0].id)),
+            ]);
+        component
+            .int()
+            .send_keys([KeyCode::Char('h'), KeyCode::Down])
+            .assert()
+            .broadcast([BroadcastEvent::SelectedRequest(Some(
+                exchanges[1].id,
+            ))]);
+
+        // Switch back to the first recipe
+        component
+            .int()
+            .send_keys([KeyCode::Char('r'), KeyCode::Up])
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some(r1.clone())),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+
+        // Back to r2 and its older request
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Left, KeyModifiers::ALT)
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some(r2.clone())),
+                BroadcastEvent::SelectedRequest(Some(exchanges[1].id)),
+            ]);
+        assert_eq!(component.selected_recipe_id(), Some(&r2));
+        assert_eq!(component.selected_request_id(), Some(exchanges[1].id));
+
+        // Back to the newer request within the same recipe
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Left, KeyModifiers::ALT)
+            .assert()
+            .broadcast([BroadcastEvent::SelectedRequest(Some(
+                exchanges[0].id,
+            ))]);
+
+        // Forward all the way to the end
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Right, KeyModifiers::ALT)
+            .send_key_modifiers(KeyCode::Right, KeyModifiers::ALT)
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRequest(Some(exchanges[1].id)),
+                BroadcastEvent::SelectedRecipe(Some(r1.clone())),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+        assert_eq!(component.selected_recipe_id(), Some(&r1));
+        // Nothing left to go forward to
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Right, KeyModifiers::ALT)
+            .assert()
+            .empty();
+    }
+
+    /// Opening and closing a modal are each recorded in the navigation
+    /// history, so going back reopens the modal. Back/forward also work while
+    /// the modal is open.
+    #[rstest]
+    fn test_back_forward_modal(terminal: TestTerminal) {
+        let collection = Collection {
+            name: None,
+            template_delimiters: None,
+            variables: IndexMap::new(),
+            recipes: by_id([Recipe::factory("r1"), Recipe::factory("r2")])
+                .into(),
+            profiles: by_id([Profile::factory(())]),
+            toggles: IndexMap::new(),
+        };
+        let mut harness = TestHarness::new(collection);
+        let r1: RecipeId = "r1".into();
+        let r2: RecipeId = "r2".into();
+        let mut component = create_component(&mut harness, &terminal);
+
+        // Select r2, then open and close the toggle menu
+        component
+            .int()
+            .send_keys([KeyCode::Char('r'), KeyCode::Down])
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some(r2.clone())),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+        component
+            .int()
+            .send_key(KeyCode::Char('t'))
+            .assert()
+            .empty();
+        assert_eq!(component.visible_modal(), Some(NavigationModal::Toggles));
+        component.int().send_key(KeyCode::Esc).assert().empty();
+        assert_eq!(component.visible_modal(), None);
+
+        // Back reopens the modal, then closes it again
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Left, KeyModifiers::ALT)
+            .assert()
+            .empty();
+        assert_eq!(component.visible_modal(), Some(NavigationModal::Toggles));
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Left, KeyModifiers::ALT)
+            .assert()
+            .empty();
+        assert_eq!(component.visible_modal(), None);
+        assert_eq!(component.selected_recipe_id(), Some(&r2));
+
+        // Past the modal to the previous recipe
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Left, KeyModifiers::ALT)
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some(r1.clone())),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+        assert_eq!(component.visible_modal(), None);
+
+        // Forward to the recipe, then into the modal
+        component
+            .int()
+            .send_key_modifiers(KeyCode::Right, KeyModifiers::ALT)
+            .send_key_modifiers(KeyCode::Right, KeyModifiers::ALT)
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some(r2.clone())),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+        assert_eq!(component.selected_recipe_id(), Some(&r2));
+        assert_eq!(component.visible_modal(), Some(NavigationModal::Toggles));
+    }
+
+    /// Test navigating from a template to the profile field and recipe that
+    /// it references
+    #[rstest]
+    fn test_go_to_definition(terminal: TestTerminal) {
+        let recipe = Recipe {
+            url: "{{ host }}/{{ response('r2') }}".into(),
+            ..Recipe::factory("r1")
+        };
+        let profile = Profile {
+            data: indexmap! {
+                "token".into() => "abc".into(),
+                "host".into() => "http://localhost".into(),
+            },
+            ..Profile::factory(())
+        };
+        let collection = Collection {
+            name: None,
+            template_delimiters: None,
+            variables: IndexMap::new(),
+            recipes: by_id([recipe, Recipe::factory("r2")]).into(),
+            profiles: by_id([profile]),
+            toggles: IndexMap::new(),
+        };
+        let mut harness = TestHarness::new(collection);
+        let mut component = create_component(&mut harness, &terminal);
+
+        // Profile field opens the profile pane
+        component
+            .int()
+            .send_key(KeyCode::Char('1')) // Select recipe detail
+            .action(&["Go to Definition", "Profile Field: host"])
+            .assert()
+            .empty();
+        assert_eq!(component.view.sidebar(), Some(Sidebar::Profile));
+
+        // Recipe is selected in the recipe list
+        component
+            .int()
+            .send_key(KeyCode::Char('1'))
+            .action(&["Go to Definition", "Recipe: r2"])
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some("r2".into())),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+        assert_eq!(component.selected_recipe_id(), Some(&"r2".into()));
+    }
+
+    /// A recipe that inherits authentication can navigate to the folder that
+    /// defines it
+    #[rstest]
+    fn test_go_to_folder(terminal: TestTerminal) {
+        let folder = Folder {
+            id: "f1".into(),
+            name: Some("Users".into()),
+            authentication: Some(
+                Authentication::Bearer {
+                    token: "abc".into(),
+                }
+                .into(),
+            ),
+            children: by_id([Recipe::factory("r1").into()]),
+            ..Folder::factory(())
+        };
+        let collection = Collection {
+            name: None,
+            template_delimiters: None,
+            variables: IndexMap::new(),
+            recipes: by_id([
+                RecipeNode::from(Recipe::factory("r0")),
+                folder.into(),
+            ])
+            .into(),
+            profiles: by_id([Profile::factory(())]),
+            toggles: IndexMap::new(),
+        };
+        let mut harness = TestHarness::new(collection);
+        let mut component = create_component(&mut harness, &terminal);
+        component.recipe_list.select_recipe(&"r1".into());
+        component.int().drain_draw().assert().broadcast([
+            BroadcastEvent::SelectedRecipe(Some("r1".into())),
+            BroadcastEvent::SelectedRequest(None),
+        ]);
+
+        component
+            .int()
+            .send_key(KeyCode::Char('1')) // Select recipe detail
+            .send_key(KeyCode::Left) // Select Authentication tab
+            .action(&["Go to Definition", "Folder: Users"])
+            .assert()
+            .broadcast([
+                // Folders don't count as a selected recipe
+                BroadcastEvent::SelectedRecipe(None),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+        assert_eq!(
+            component.recipe_list.selected(),
+            Some((&"f1".into(), RecipeNodeType::Folder))
+        );
+    }
+
+    /// "Follow Link" action opens a list of links from the response, and
+    /// sends a request for the selected one
+    #[rstest]
+    fn test_follow_link(
+        mut harness: TestHarness,
+        #[with(60, 20)] terminal: TestTerminal,
+    ) {
+        let recipe_id = harness.collection.first_recipe_id();
+        let profile_id = harness.collection.first_profile_id();
+        let exchange = Exchange {
+            response: ResponseRecord {
+                headers: header_map(indexmap! {"location" => "/users/2"}),
+                body: "see http://localhost/docs".into(),
+                ..ResponseRecord::factory(())
+            }
+            .into(),
+            ..Exchange::factory((Some(profile_id.clone()), recipe_id.clone()))
+        };
+        harness.database.insert_exchange(&exchange).unwrap();
+        let mut component =
+            TestComponent::builder(&harness, &terminal, PrimaryView::new())
+                .with_default_props()
+                // Initial selection events aren't relevant here
+                .with_assert_events(|_| {})
+                .build();
+        harness.messages().clear();
+
+        component
+            .int()
+            .drain_draw()
+            .send_key(KeyCode::Char('2')) // Select exchange pane
+            .action(&["Response", "Follow Link"])
+            .send_keys([KeyCode::Down, KeyCode::Enter])
+            .assert()
+            .empty();
+
+        let url = assert_matches!(
+            harness.messages().pop_now(),
+            Message::Http(HttpMessage::FollowLink(url)) => url,
+        );
+        assert_eq!(url.as_str(), "http://localhost/docs");
+    }
+
+    /// "Query Nodes" action opens a modal to query an HTML body. The selected
+    /// node is copied on submit
+    #[rstest]
+    fn test_query_nodes(
+        mut harness: TestHarness,
+        #[with(60, 20)] terminal: TestTerminal,
+    ) {
+        let recipe_id = harness.collection.first_recipe_id();
+        let profile_id = harness.collection.first_profile_id();
+        let exchange = Exchange {
+            response: ResponseRecord {
+                headers: header_map(indexmap! {"content-type" => "text/html"}),
+                body: "<ul><li>One</li><li>Two</li></ul>".into(),
+                ..ResponseRecord::factory(())
+            }
+            .into(),
+            ..Exchange::factory((Some(profile_id.clone()), recipe_id.clone()))
+        };
+        harness.database.insert_exchange(&exchange).unwrap();
+        let mut component =
+            TestComponent::builder(&harness, &terminal, PrimaryView::new())
+                .with_default_props()
+                // Initial selection events aren't relevant here
+                .with_assert_events(|_| {})
+                .build();
+        harness.messages().clear();
+
+        component
+            .int()
+            .drain_draw()
+            .send_key(KeyCode::Char('2')) // Select exchange pane
+            .action(&["Response", "Query Nodes"])
+            .send_text("li")
+            .send_keys([KeyCode::Down, KeyCode::Enter])
+            .assert()
+            .empty();
+
+        let text = assert_matches!(
+            harness.messages().pop_now(),
+            Message::CopyText(text) => text,
+        );
+        assert_eq!(text, "<li>Two</li>");
+    }
+
+    /// "Copy JSONPath" action opens a modal to pick a node from a JSON body.
+    /// Submitting copies a template referencing the current recipe
+    #[rstest]
+    fn test_copy_json_path(
+        mut harness: TestHarness,
+        #[with(60, 20)] terminal: TestTerminal,
+    ) {
+        let recipe_id = harness.collection.first_recipe_id().clone();
+        let profile_id = harness.collection.first_profile_id();
+        let exchange = Exchange {
+            response: ResponseRecord {
+                headers: header_map(
+                    indexmap! {"content-type" => "application/json"},
+                ),
+                body: r#"{"data": {"id": 3}}"#.into(),
+                ..ResponseRecord::factory(())
+            }
+            .into(),
+            ..Exchange::factory((Some(profile_id.clone()), recipe_id.clone()))
+        };
+        harness.database.insert_exchange(&exchange).unwrap();
+        let mut component =
+            TestComponent::builder(&harness, &terminal, PrimaryView::new())
+                .with_default_props()
+                // Initial selection events aren't relevant here
+                .with_assert_events(|_| {})
+                .build();
+        harness.messages().clear();
+
+        component
+            .int()
+            .drain_draw()
+            .send_key(KeyCode::Char('2')) // Select exchange pane
+            .action(&["Response", "Copy JSONPath"])
+            .send_text("$.data.id")
+            .send_keys([KeyCode::Tab, KeyCode::Enter])
+            .assert()
+            .empty();
+
+        let text = assert_matches!(
+            harness.messages().pop_now(),
+            Message::CopyText(text) => text,
+        );
+        assert_eq!(
+            text,
+            format!(
+                "{{{{ response('{recipe_id}') | jsonpath('$.data.id') }}}}"
+            )
+        );
+    }
+
+    /// Test the profile switcher, including MRU ordering and fuzzy search
+    #[rstest]
+    fn test_switch_profile(terminal: TestTerminal) {
+        let profiles = ["dev", "staging", "prod-east"].map(|id| Profile {
+            id: id.into(),
+            ..Profile::factory(())
+        });
+        let collection = Collection {
+            profiles: by_id(profiles),
+            ..Collection::factory(())
+        };
+        let mut harness = TestHarness::new(collection);
+        let mut component = create_component(&mut harness, &terminal);
+        let switch = |component: &mut TestComponent<PrimaryView>,
+                      query: &str,
+                      expected: &str| {
+            component
+                .int()
+                .send_key_modifiers(KeyCode::Char('p'), KeyModifiers::CTRL)
+                .send_text(query)
+                .send_key(KeyCode::Enter)
+                .assert()
+                .broadcast([
+                    BroadcastEvent::SelectedProfile(Some(expected.into())),
+                    BroadcastEvent::SelectedRequest(None),
+                ]);
+        };
+
+        // With no query, the previously used profile is preselected. There
+        // isn't one yet, so we get the next in the list
+        switch(&mut component, "", "staging");
+        // Fuzzy search
+        switch(&mut component, "pe", "prod-east");
+        // Previous profile is first in line
+        switch(&mut component, "", "staging");
+        switch(&mut component, "", "prod-east");
+    }
+
+    /// Toggles are switched on and off from the menu, and changes apply
+    /// immediately in collection order
+    #[rstest]
+    fn test_toggles(terminal: TestTerminal) {
+        let toggles = ["admin", "verbose"].map(|id| Toggle {
+            id: id.into(),
+            ..Toggle::factory(())
+        });
+        let collection = Collection {
+            toggles: by_id(toggles),
+            ..Collection::factory(())
+        };
+        let mut harness = TestHarness::new(collection);
+        let mut component = create_component(&mut harness, &terminal);
+
+        // Enable both, bottom first
+        component
+            .int()
+            .send_key(KeyCode::Char('t'))
+            .send_keys([KeyCode::Down, KeyCode::Char(' ')])
+            .send_keys([KeyCode::Up, KeyCode::Char(' ')])
+            .assert()
+            .broadcast([
+                BroadcastEvent::RefreshPreviews,
+                BroadcastEvent::RefreshPreviews,
+            ]);
+        assert_eq!(
+            component.enabled_toggles(),
+            [ToggleId::from("admin"), "verbose".into()]
+        );
+
+        // Disable one and close the menu. Reopening shows the current state
+        component
+            .int()
+            .send_keys([KeyCode::Char(' '), KeyCode::Enter])
+            .assert()
+            .broadcast([BroadcastEvent::RefreshPreviews]);
+        assert_eq!(component.enabled_toggles(), [ToggleId::from("verbose")]);
+        component
+            .int()
+            .send_keys([KeyCode::Char('t'), KeyCode::Char(' ')])
+            .assert()
+            .broadcast([BroadcastEvent::RefreshPreviews]);
+        assert_eq!(
+            component.enabled_toggles(),
+            [ToggleId::from("admin"), "verbose".into()]
+        );
+    }
+
+    /// Recent requests can be sent again regardless of what's selected, via
+    /// the last-request binding or the quick-repeat menu
+    #[rstest]
+    fn test_resend(terminal: TestTerminal) {
+        let collection = Collection {
+            recipes: by_id([Recipe::factory("r1"), Recipe::factory("r2")])
+                .into(),
+            ..Collection::factory(())
+        };
+        let mut harness = TestHarness::new(collection);
+        let profile_id = harness.collection.first_profile_id().clone();
+        let config = |recipe_id: &str| RequestConfig {
+            profile_id: Some(profile_id.clone()),
+            recipe_id: recipe_id.into(),
+            options: BuildOptions::default(),
+        };
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+        harness.messages().clear();
+
+        // Nothing to resend yet
+        component
+            .int()
+            .send_key(KeyCode::Char('.'))
+            .assert()
+            .empty();
+        harness.messages().assert_empty();
+
+        // Send r1, then r2
+        component.int().send_key(KeyCode::Enter).assert().empty();
+        component
+            .int()
+            .send_keys([KeyCode::Char('r'), KeyCode::Down, KeyCode::Enter])
+            .assert()
+            .broadcast([
+                BroadcastEvent::SelectedRecipe(Some("r2".into())),
+                BroadcastEvent::SelectedRequest(None),
+            ]);
+        for _ in 0..2 {
+            assert_matches!(
+                harness.messages().pop_now(),
+                Message::Http(HttpMessage::Begin)
+            );
+        }
+
+        // Resend the last one
+        component
+            .int()
+            .send_key(KeyCode::Char('.'))
+            .assert()
+            .empty();
+        let actual = assert_matches!(
+            harness.messages().pop_now(),
+            Message::Http(HttpMessage::Resend(config)) => config,
+        );
+        assert_eq!(actual, config("r2"));
+
+        // Pick the older one from the menu by number
+        component
+            .int()
+            .send_keys([KeyCode::Char(';'), KeyCode::Char('2')])
+            .assert()
+            .empty();
+        let actual = assert_matches!(
+            harness.messages().pop_now(),
+            Message::Http(HttpMessage::Resend(config)) => config,
+        );
+        assert_eq!(actual, config("r1"));
+        assert!(!component.recent_requests_menu.is_open());
+
+        // That moved it to the front, so the menu's preselected item is r1
+        component
+            .int()
+            .send_keys([KeyCode::Char(';'), KeyCode::Enter])
+            .assert()
+            .empty();
+        let actual = assert_matches!(
+            harness.messages().pop_now(),
+            Message::Http(HttpMessage::Resend(config)) => config,
+        );
+        assert_eq!(actual, config("r1"));
+    }
+
+    /// Requests that fail while offline are held in a queue, where they can be
+    /// sent early or cancelled
+    #[rstest]
+    fn test_offline_queue(terminal: TestTerminal) {
+        let collection = Collection {
+            recipes: by_id([Recipe::factory("r1"), Recipe::factory("r2")])
+                .into(),
+            ..Collection::factory(())
+        };
+        let mut harness = TestHarness::new(collection);
+        let profile_id = harness.collection.first_profile_id().clone();
+        let config = |recipe_id: &str| RequestConfig {
+            profile_id: Some(profile_id.clone()),
+            recipe_id: recipe_id.into(),
+            options: BuildOptions::default(),
+        };
+        let url: Url = "http://localhost/url".parse().unwrap();
+        let mut component = create_component(&mut harness, &terminal);
+        component.int().drain_draw().assert().empty();
+
+        component.queue_offline(config("r1"), url.clone());
+        component.queue_offline(config("r2"), url.clone());
+        // Queueing the same request again replaces it
+        component.queue_offline(config("r1"), url.clone());
+        assert_eq!(component.offline_queue.len(), 2);
+        harness.messages().clear();
+
+        // Cancel the first one (r2). The menu stays open
+        component
+            .int()
+            .send_keys([KeyCode::Char('o'), KeyCode::Delete])
+            .assert()
+            .empty();
+        assert_eq!(component.offline_queue.len(), 1);
+        assert!(component.offline_queue_menu.is_open());
+        harness.messages().assert_empty();
+
+        // Send the other one without waiting for the network
+        component.int().send_key(KeyCode::Enter).assert().empty();
+        let actual = assert_matches!(
+            harness.messages().pop_now(),
+            Message::Http(HttpMessage::Resend(config)) => config,
+        );
+        assert_eq!(actual, config("r1"));
+        assert_eq!(component.offline_queue.len(), 0);
+        assert!(!component.offline_queue_menu.is_open());
+    }
 }
@@ -0,0 +1,136 @@
+use slumber_core::{
+    collection::{ProfileId, RecipeId},
+    http::RequestId,
+};
+use std::collections::VecDeque;
+
+/// A location in the primary view that can be navigated back/forward to
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub profile: Option<ProfileId>,
+    pub recipe: RecipeId,
+    pub request: Option<RequestId>,
+    /// Modal open on top of the view. Opening and closing a modal each move
+    /// to a new location, so the modal can be reopened by going back
+    pub modal: Option<NavigationModal>,
+}
+
+/// A modal that can be reopened by navigating back/forward to it. Only modals
+/// that are built from the view's own state can be recorded. The others show
+/// data that's gone by the time we navigate back (e.g. a response's links or
+/// a finished request chain), so they aren't part of the history.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum NavigationModal {
+    ProfileSwitcher,
+    Toggles,
+    RecentRequests,
+    OfflineQueue,
+    AuditLog,
+    Cookies,
+}
+
+/// Browser-style back/forward history of visited [Location]s
+#[derive(Debug, Default)]
+pub struct Navigation {
+    /// Locations visited before the current one, oldest first
+    back: VecDeque<Location>,
+    /// Locations that we've gone back from, most recent last
+    forward: Vec<Location>,
+    current: Option<Location>,
+}
+
+impl Navigation {
+    /// Maximum number of locations to remember in the back stack. The oldest
+    /// are dropped first.
+    const MAX_SIZE: usize = 100;
+
+    /// Record a visit to a location. If it's the same as the current location,
+    /// do nothing. Otherwise, the current location is pushed onto the back
+    /// stack and the forward stack is cleared.
+    pub fn visit(&mut self, location: Location) {
+        if self.current.as_ref() == Some(&location) {
+            return;
+        }
+        if let Some(current) = self.current.replace(location) {
+            self.back.push_back(current);
+            if self.back.len() > Self::MAX_SIZE {
+                self.back.pop_front();
+            }
+        }
+        self.forward.clear();
+    }
+
+    /// Get the current location. `None` if nothing has been visited yet
+    pub fn current(&self) -> Option<&Location> {
+        self.current.as_ref()
+    }
+
+    /// Replace the current location without modifying the back/forward stacks.
+    /// Use this when a navigation lands somewhere other than expected.
+    pub fn replace(&mut self, location: Location) {
+        self.current = Some(location);
+    }
+
+    /// Move back one location and return the new current location
+    pub fn back(&mut self) -> Option<&Location> {
+        let location = self.back.pop_back()?;
+        if let Some(current) = self.current.replace(location) {
+            self.forward.push(current);
+        }
+        self.current.as_ref()
+    }
+
+    /// Move forward one location and return the new current location
+    pub fn forward(&mut self) -> Option<&Location> {
+        let location = self.forward.pop()?;
+        if let Some(current) = self.current.replace(location) {
+            self.back.push_back(current);
+        }
+        self.current.as_ref()
+    }
+
+    /// Drop the current location. Use this when navigating to a location that
+    /// no longer exists. The previous location in the navigation direction
+    /// becomes current, but nothing is pushed in its place.
+    pub fn discard_current(&mut self) {
+        self.current = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn location(recipe_id: &str) -> Location {
+        Location {
+            profile: None,
+            recipe: recipe_id.into(),
+            request: None,
+            modal: None,
+        }
+    }
+
+    /// Test back/forward navigation, including clearing the forward stack on
+    /// a new visit
+    #[rstest]
+    fn test_navigation() {
+        let mut navigation = Navigation::default();
+        navigation.visit(location("r1"));
+        navigation.visit(location("r2"));
+        // Duplicates are ignored
+        navigation.visit(location("r2"));
+        navigation.visit(location("r3"));
+
+        assert_eq!(navigation.back(), Some(&location("r2")));
+        assert_eq!(navigation.back(), Some(&location("r1")));
+        assert_eq!(navigation.back(), None);
+        assert_eq!(navigation.forward(), Some(&location("r2")));
+
+        // New visit drops everything forward
+        navigation.visit(location("r4"));
+        assert_eq!(navigation.forward(), None);
+        assert_eq!(navigation.back(), Some(&location("r2")));
+        assert_eq!(navigation.back(), Some(&location("r1")));
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use slumber_config::StartupPane;
 use strum::{EnumIter, IntoEnumIterator};
 
 /// Which panes are visible in the primary view?
@@ -16,6 +17,26 @@ pub struct ViewState {
 }
 
 impl ViewState {
+    /// Initial state with a particular pane selected, as defined by the
+    /// `startup.focus` config field
+    pub fn startup(pane: StartupPane) -> Self {
+        let layout = match pane {
+            StartupPane::RecipeList => PrimaryLayout::sidebar(Sidebar::Recipe),
+            StartupPane::ProfileList => {
+                PrimaryLayout::sidebar(Sidebar::Profile)
+            }
+            StartupPane::History => PrimaryLayout::sidebar(Sidebar::History),
+            StartupPane::Recipe => PrimaryLayout::Default(DefaultPane::Top),
+            StartupPane::Exchange => {
+                PrimaryLayout::Default(DefaultPane::Bottom)
+            }
+        };
+        Self {
+            layout,
+            fullscreen: false,
+        }
+    }
+
     /// Get the current sidebar/pane layout
     pub fn layout(&self) -> PrimaryLayout {
         self.layout
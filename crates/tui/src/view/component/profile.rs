@@ -3,19 +3,22 @@
 use crate::{
     util::ResultReported,
     view::{
-        Generate, ViewContext,
+        Generate, UpdateContext, ViewContext,
         common::{
             Pane,
+            actions::MenuItem,
             component_select::{
                 ComponentSelect, ComponentSelectProps, SelectStyles,
             },
             select::Select,
+            text_box::{TextBox, TextBoxEvent, TextBoxProps},
         },
         component::{
             Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
             editable_template::EditableTemplate,
             sidebar_list::{SidebarListItem, SidebarListState},
         },
+        event::{BroadcastEvent, Emitter, Event, EventMatch, ToEmitter},
         persistent::{PersistentKey, PersistentStore, SessionKey},
     },
 };
@@ -23,14 +26,15 @@ use anyhow::anyhow;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use ratatui::{
-    layout::{Constraint, Layout, Spacing},
-    style::Styled,
+    layout::{Constraint, Layout, Rect, Spacing},
+    style::{Style, Styled},
 };
 use serde::Serialize;
 use slumber_config::Action;
 use slumber_core::collection::{Profile, ProfileId};
 use slumber_template::Template;
-use std::{borrow::Cow, iter};
+use slumber_util::tr;
+use std::{borrow::Cow, iter, mem};
 use unicode_width::UnicodeWidthStr;
 
 /// State for a list of profiles. Use with
@@ -97,12 +101,22 @@ impl PersistentKey for SelectedProfileKey {
     type Value = ProfileId;
 }
 
-/// Preview the fields of a profile
+/// Preview the fields of a profile. Each field shows its raw template next to
+/// its rendered value.
 #[derive(Debug)]
 pub struct ProfileDetail {
     id: ComponentId,
-    /// Navigable list of profile fields
+    actions_emitter: Emitter<ProfileDetailMenuAction>,
+    /// Navigable list of profile fields that match the filter
     select: ComponentSelect<ProfileField>,
+    /// Fields that have been excluded by the filter. We hang onto these so
+    /// their overrides and previews are retained
+    hidden: Vec<ProfileField>,
+    /// Text box for filtering fields by name
+    filter: TextBox,
+    /// Is the user typing in the filter box? User has to explicitly grab focus
+    /// on the box to start typing
+    filter_focused: bool,
 }
 
 impl ProfileDetail {
@@ -110,11 +124,25 @@ impl ProfileDetail {
     /// selected profile changes, because the entire contents of the pane
     /// changes too.
     pub fn new(profile_id: Option<&ProfileId>) -> Self {
+        let filter = TextBox::default()
+            .placeholder(format!(
+                "{binding} to filter",
+                binding = ViewContext::binding_display(Action::Search)
+            ))
+            .subscribe([
+                TextBoxEvent::Cancel,
+                TextBoxEvent::Change,
+                TextBoxEvent::Submit,
+            ]);
         let Some(profile_id) = profile_id else {
             // No profile selected - empty state
             return Self {
                 id: ComponentId::new(),
+                actions_emitter: Emitter::default(),
                 select: ComponentSelect::default(),
+                hidden: Vec::new(),
+                filter,
+                filter_focused: false,
             };
         };
 
@@ -132,9 +160,11 @@ impl ProfileDetail {
         // Create an editable template for each field
         let items = profile_data
             .iter()
-            .map(|(field, template)| {
+            .enumerate()
+            .map(|(index, (field, template))| {
                 ProfileField::new(
                     profile_id.clone(),
+                    index,
                     field.clone(),
                     template.clone(),
                 )
@@ -147,14 +177,30 @@ impl ProfileDetail {
 
         Self {
             id: ComponentId::new(),
+            actions_emitter: Emitter::default(),
             select,
+            hidden: Vec::new(),
+            filter,
+            filter_focused: false,
         }
     }
 
+    /// Select a field by name. If the field is hidden by the filter, the
+    /// filter is cleared. Does nothing if the field isn't in the profile.
+    pub fn select_field(&mut self, field: &str) {
+        let field = field.to_owned();
+        if self.hidden.iter().any(|item| item == &field) {
+            self.filter.clear();
+            self.apply_filter();
+        }
+        self.select.select(&field);
+    }
+
     /// Get a map of overridden profile fields
     pub fn overrides(&self) -> IndexMap<String, Template> {
         self.select
             .items()
+            .chain(&self.hidden)
             .filter_map(|field| {
                 // Only include modified templates
                 if field.template.is_overridden() {
@@ -168,6 +214,26 @@ impl ProfileDetail {
             })
             .collect()
     }
+
+    /// Rebuild the visible list of fields from the current filter text.
+    /// Fields are moved between the select and the hidden list, so their
+    /// state is retained.
+    fn apply_filter(&mut self) {
+        let filter = self.filter.text().trim().to_lowercase();
+        let selected = self.select.selected().map(|item| item.field.clone());
+        let (visible, hidden): (Vec<_>, Vec<_>) = mem::take(&mut self.select)
+            .into_select()
+            .into_items()
+            .chain(self.hidden.drain(..))
+            // Restore the original field order
+            .sorted_by_key(|item| item.index)
+            .partition(|item| item.field.to_lowercase().contains(&filter));
+        self.hidden = hidden;
+        self.select = Select::builder(visible)
+            .preselect_opt(selected.as_ref())
+            .build()
+            .into();
+    }
 }
 
 impl Component for ProfileDetail {
@@ -175,6 +241,37 @@ impl Component for ProfileDetail {
         self.id
     }
 
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event
+            .m()
+            .action(|action, propagate| match action {
+                Action::Search => self.filter_focused = true,
+                _ => propagate.set(),
+            })
+            .emitted(self.actions_emitter, |menu_action| match menu_action {
+                ProfileDetailMenuAction::Refresh => {
+                    ViewContext::push_event(BroadcastEvent::RefreshPreviews);
+                }
+            })
+            .emitted(self.filter.to_emitter(), |event| match event {
+                TextBoxEvent::Change => self.apply_filter(),
+                TextBoxEvent::Cancel | TextBoxEvent::Submit => {
+                    self.filter_focused = false;
+                }
+            })
+    }
+
+    fn menu(&self) -> Vec<MenuItem> {
+        vec![
+            self.actions_emitter
+                .menu(
+                    ProfileDetailMenuAction::Refresh,
+                    tr!("menu-refresh-values"),
+                )
+                .into(),
+        ]
+    }
+
     fn persist(&self, store: &mut PersistentStore) {
         // Persist selected row
         store.set_opt(
@@ -184,7 +281,12 @@ impl Component for ProfileDetail {
     }
 
     fn children(&mut self) -> Vec<Child<'_>> {
-        vec![self.select.to_child_mut()]
+        vec![
+            // Filter gets highest priority because when it's focused, it
+            // should eat all events
+            self.filter.to_child_mut(),
+            self.select.to_child_mut(),
+        ]
     }
 }
 
@@ -208,18 +310,35 @@ impl Draw for ProfileDetail {
             .unwrap_or(0) as u16
             + 1; // Padding!
 
-        let [header_area, rows_area] =
-            Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
-                .areas(area);
-        let [key_header_area, value_header_area] = Layout::horizontal([
-            Constraint::Length(field_column_width),
-            Constraint::Min(1),
-        ])
-        .areas(header_area);
+        // Show the full error for the selected field below the table
+        let error = self.select.selected().and_then(|row| row.template.error());
+        // Cap the height so a long error chain doesn't crowd out the table.
+        // +1 for a blank line above
+        let error_height =
+            error.map_or(0, |error| error.height().min(5) as u16 + 1);
+
+        let [filter_area, header_area, rows_area, error_area] =
+            Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Min(0),
+                Constraint::Length(error_height),
+            ])
+            .areas(area);
+
+        canvas.draw(
+            &self.filter,
+            TextBoxProps::default(),
+            filter_area,
+            self.filter_focused,
+        );
 
         // Draw header
+        let [field_header_area, template_header_area, value_header_area] =
+            ProfileField::columns(field_column_width, header_area);
         let style = ViewContext::styles().table.header;
-        canvas.render_widget("Field".set_style(style), key_header_area);
+        canvas.render_widget("Field".set_style(style), field_header_area);
+        canvas.render_widget("Template".set_style(style), template_header_area);
         canvas.render_widget("Value".set_style(style), value_header_area);
 
         // Draw rows
@@ -235,9 +354,23 @@ impl Draw for ProfileDetail {
             rows_area,
             true,
         );
+
+        if let Some(error) = error {
+            let [_, error_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                    .areas(error_area);
+            canvas.render_widget(error, error_area);
+        }
     }
 }
 
+/// Menu action for [ProfileDetail]
+#[derive(Copy, Clone, Debug)]
+enum ProfileDetailMenuAction {
+    /// Re-render all template previews, including profile values
+    Refresh,
+}
+
 /// Persistence key for selected row in the [ProfileDetail] table
 #[derive(Debug, Serialize)]
 struct SelectedProfileFieldKey;
@@ -262,12 +395,20 @@ impl SessionKey for ProfileFieldOverrideKey {
 #[derive(Debug)]
 struct ProfileField {
     id: ComponentId,
+    /// Position of the field in the profile. Used to restore the original
+    /// order after filtering
+    index: usize,
     field: String,
     template: EditableTemplate<ProfileFieldOverrideKey>,
 }
 
 impl ProfileField {
-    fn new(profile_id: ProfileId, field: String, template: Template) -> Self {
+    fn new(
+        profile_id: ProfileId,
+        index: usize,
+        field: String,
+        template: Template,
+    ) -> Self {
         let template = EditableTemplate::new(
             "Field",
             ProfileFieldOverrideKey {
@@ -283,10 +424,22 @@ impl ProfileField {
         );
         Self {
             id: ComponentId::new(),
+            index,
             field,
             template,
         }
     }
+
+    /// Split a row into field, template, and value columns
+    fn columns(field_column_width: u16, area: Rect) -> [Rect; 3] {
+        Layout::horizontal([
+            Constraint::Length(field_column_width),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ])
+        .spacing(1)
+        .areas(area)
+    }
 }
 
 impl Component for ProfileField {
@@ -306,14 +459,29 @@ impl Draw<ProfileFieldProps> for ProfileField {
         props: ProfileFieldProps,
         metadata: DrawMetadata,
     ) {
-        let [field_area, template_area] = Layout::horizontal([
-            Constraint::Length(props.field_column_width),
-            Constraint::Min(1),
-        ])
-        .areas(metadata.area());
+        let [field_area, template_area, value_area] =
+            Self::columns(props.field_column_width, metadata.area());
+
+        // Flag fields that failed to render
+        let field_style = if self.template.error().is_some() {
+            ViewContext::styles().text.error
+        } else {
+            Style::default()
+        };
+        canvas.render_widget(
+            self.field.as_str().set_style(field_style),
+            field_area,
+        );
 
-        canvas.render_widget(self.field.as_str(), field_area);
-        canvas.draw(&self.template, (), template_area, true);
+        // The edit box replaces the raw template while editing, so the old
+        // value is still visible for reference
+        if self.template.is_editing() {
+            canvas.draw(&self.template, (), template_area, true);
+            canvas.render_widget(self.template.text(), value_area);
+        } else {
+            canvas.render_widget(self.template.render_raw(), template_area);
+            canvas.draw(&self.template, (), value_area, true);
+        }
     }
 }
 
@@ -335,6 +503,7 @@ struct ProfileFieldProps {
 mod tests {
     use super::*;
     use crate::{
+        message::Message,
         test_util::{TestTerminal, terminal},
         view::{
             event::BroadcastEvent,
@@ -343,8 +512,11 @@ mod tests {
     };
     use indexmap::indexmap;
     use rstest::rstest;
-    use slumber_core::{collection::Collection, test_util::by_id};
-    use slumber_util::Factory;
+    use slumber_core::{
+        collection::Collection, render::TemplateContext, test_util::by_id,
+    };
+    use slumber_util::{Factory, assert_matches};
+    use std::sync::Arc;
     use terminput::KeyCode;
 
     #[rstest]
@@ -379,4 +551,111 @@ mod tests {
         let field = &component.select[1];
         assert_eq!(field.template.template(), &"def123".into());
     }
+
+    /// Filtering the field list hides non-matching fields, but their overrides
+    /// are retained
+    #[rstest]
+    fn test_filter(terminal: TestTerminal) {
+        let profile_id = ProfileId::from("profile1");
+        let collection = Collection {
+            profiles: by_id([Profile {
+                id: profile_id.clone(),
+                data: indexmap! {
+                    "field1".into() => "abc".into(),
+                    "other".into() => "def".into(),
+                    "field2".into() => "ghi".into(),
+                },
+                ..Profile::factory(())
+            }]),
+            ..Collection::factory(())
+        };
+        let harness = TestHarness::new(collection);
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            ProfileDetail::new(Some(&profile_id)),
+        );
+        let field_names = |component: &ProfileDetail| {
+            component
+                .select
+                .items()
+                .map(|field| field.field.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Override a field that's about to be hidden
+        component
+            .int()
+            .send_keys([KeyCode::Down, KeyCode::Char('e')])
+            .send_text("123")
+            .send_key(KeyCode::Enter)
+            .assert()
+            .broadcast([BroadcastEvent::RefreshPreviews]);
+
+        // Filter is case-insensitive
+        component
+            .int()
+            .send_key(KeyCode::Char('/'))
+            .send_text("FIELD")
+            .send_key(KeyCode::Enter)
+            .assert()
+            .empty();
+        assert_eq!(field_names(&component), ["field1", "field2"]);
+        assert_eq!(
+            component.overrides(),
+            indexmap! {"other".into() => "def123".into()}
+        );
+
+        // Clearing the filter restores the original order
+        component
+            .int()
+            .send_key(KeyCode::Char('/'))
+            .send_keys([KeyCode::Backspace; 5])
+            .send_key(KeyCode::Enter)
+            .assert()
+            .empty();
+        assert_eq!(field_names(&component), ["field1", "other", "field2"]);
+    }
+
+    /// Render errors are stored on the field so they can be shown in detail
+    #[rstest]
+    #[tokio::test]
+    async fn test_render_error(terminal: TestTerminal) {
+        let profile_id = ProfileId::from("profile1");
+        let collection = Collection {
+            profiles: by_id([Profile {
+                id: profile_id.clone(),
+                data: indexmap! {
+                    "good".into() => "abc".into(),
+                    "bad".into() => "{{ unknown }}".into(),
+                },
+                ..Profile::factory(())
+            }]),
+            ..Collection::factory(())
+        };
+        let mut harness = TestHarness::new(collection);
+        let context = TemplateContext {
+            collection: Arc::clone(&harness.collection),
+            selected_profile: Some(profile_id.clone()),
+            ..TemplateContext::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            ProfileDetail::new(Some(&profile_id)),
+        );
+
+        // Only the dynamic template sends a preview request. Render it here
+        // because the test harness doesn't handle preview messages
+        let (template, on_complete) = assert_matches!(
+            harness.messages().pop_now(),
+            Message::TemplatePreview { template, on_complete, .. } =>
+                (template, on_complete)
+        );
+        on_complete(template.render(&context.streaming(false)).await);
+        component.int().drain_draw().assert().empty();
+
+        assert!(component.select[0].template.error().is_none());
+        assert!(component.select[1].template.error().is_some());
+    }
 }
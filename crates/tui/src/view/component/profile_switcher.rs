@@ -0,0 +1,306 @@
+//! Quick-switch modal for jumping between profiles
+
+use crate::view::{
+    ToStringGenerate, UpdateContext, ViewContext,
+    common::{
+        modal::Modal,
+        select::{Select, SelectListProps},
+        text_box::{TextBox, TextBoxEvent, TextBoxProps},
+    },
+    component::{
+        Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+    },
+    event::{Emitter, Event, EventMatch, ToEmitter},
+    persistent::{PersistentKey, PersistentStore},
+};
+use derive_more::Display;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::Line,
+};
+use serde::Serialize;
+use slumber_config::Action;
+use slumber_core::collection::ProfileId;
+use slumber_util::tr;
+use std::cmp::Reverse;
+
+/// Modal to select a profile with fuzzy search. Profiles are ordered by most
+/// recently used, so the previous profile is always one keystroke away.
+#[derive(Debug)]
+pub struct ProfileSwitcher {
+    id: ComponentId,
+    /// Emits the chosen profile on submission. This belongs to the parent,
+    /// because the modal is rebuilt each time it's opened
+    emitter: Emitter<SwitchProfile>,
+    /// All profiles in the collection, sorted by most recently used
+    profiles: Vec<ProfileSwitcherItem>,
+    select: Select<ProfileSwitcherItem>,
+    /// Fuzzy search box. Always in focus
+    filter: TextBox,
+}
+
+impl ProfileSwitcher {
+    /// Max number of profiles to show at once
+    const MAX_HEIGHT: u16 = 10;
+
+    pub fn new(
+        emitter: Emitter<SwitchProfile>,
+        recent_profiles: &RecentProfiles,
+    ) -> Self {
+        let collection = ViewContext::collection();
+        let mut profiles: Vec<ProfileSwitcherItem> = collection
+            .profiles
+            .values()
+            .map(|profile| ProfileSwitcherItem {
+                id: profile.id.clone(),
+                name: profile.name().to_owned(),
+            })
+            .collect();
+        // Stable sort retains collection order for profiles that have never
+        // been used
+        profiles.sort_by_key(|item| recent_profiles.position(&item.id));
+
+        let filter = TextBox::default()
+            .placeholder("Search profiles")
+            .subscribe([TextBoxEvent::Change]);
+        let mut switcher = Self {
+            id: ComponentId::default(),
+            emitter,
+            profiles,
+            select: Select::default(),
+            filter,
+        };
+        switcher.rebuild_select();
+        switcher
+    }
+
+    /// Rebuild the list of profiles based on the current search text
+    fn rebuild_select(&mut self) {
+        let query = self.filter.text().trim();
+        let items = if query.is_empty() {
+            self.profiles.clone()
+        } else {
+            let mut matches: Vec<_> = self
+                .profiles
+                .iter()
+                .filter_map(|item| Some((item.score(query)?, item)))
+                .collect();
+            // Stable sort, so ties retain MRU order
+            matches.sort_by_key(|(score, _)| Reverse(*score));
+            matches.into_iter().map(|(_, item)| item.clone()).collect()
+        };
+
+        // With no search, the current profile is first in the list. Preselect
+        // the one before it so the user can flip between two profiles quickly
+        let preselect_index = usize::from(query.is_empty() && items.len() > 1);
+        self.select = Select::builder(items)
+            .preselect_index(preselect_index)
+            .build();
+    }
+}
+
+impl Component for ProfileSwitcher {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event.m().emitted(self.filter.to_emitter(), |event| {
+            if let TextBoxEvent::Change = event {
+                self.rebuild_select();
+            }
+        })
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        // Select gets priority so it can handle up/down before the text box
+        vec![self.select.to_child_mut(), self.filter.to_child_mut()]
+    }
+}
+
+impl Draw for ProfileSwitcher {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        let [filter_area, select_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)])
+                .areas(metadata.area());
+        canvas.draw(&self.filter, TextBoxProps::default(), filter_area, true);
+        canvas.draw(&self.select, SelectListProps::modal(), select_area, true);
+    }
+}
+
+impl Modal for ProfileSwitcher {
+    fn title(&self) -> Line<'_> {
+        ViewContext::add_binding_hint(
+            tr!("modal-switch-profile-title"),
+            Action::SwitchProfile,
+        )
+        .into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        // Size is based on the full list so it doesn't jump around while
+        // searching
+        let list_height = (self.profiles.len() as u16).min(Self::MAX_HEIGHT);
+        (Constraint::Length(40), Constraint::Length(list_height + 1))
+    }
+
+    fn on_submit(self, _: &mut UpdateContext) {
+        if let Some(item) = self.select.into_selected() {
+            self.emitter.emit(SwitchProfile(item.id));
+        }
+    }
+}
+
+/// Emitted when the user picks a profile from [ProfileSwitcher]
+#[derive(Debug)]
+pub struct SwitchProfile(pub ProfileId);
+
+/// Profiles that have been selected, most recent first. This is persisted so
+/// the ordering carries across sessions.
+#[derive(Debug, Default)]
+pub struct RecentProfiles(Vec<ProfileId>);
+
+impl RecentProfiles {
+    /// Max number of profiles to remember. Anything older is treated as
+    /// never used
+    const MAX_SIZE: usize = 50;
+
+    /// Load the list from the persistent store
+    pub fn load() -> Self {
+        Self(PersistentStore::get(&RecentProfilesKey).unwrap_or_default())
+    }
+
+    /// Mark a profile as the most recently used
+    pub fn visit(&mut self, profile_id: &ProfileId) {
+        self.0.retain(|id| id != profile_id);
+        self.0.insert(0, profile_id.clone());
+        self.0.truncate(Self::MAX_SIZE);
+    }
+
+    /// Save the list to the persistent store
+    pub fn persist(&self, store: &mut PersistentStore) {
+        store.set(&RecentProfilesKey, &self.0);
+    }
+
+    /// Sort key for a profile. Recently used profiles come first, followed by
+    /// those that have never been used
+    fn position(&self, profile_id: &ProfileId) -> usize {
+        self.0
+            .iter()
+            .position(|id| id == profile_id)
+            .unwrap_or(usize::MAX)
+    }
+}
+
+/// Persistent key for [RecentProfiles]
+#[derive(Debug, Serialize)]
+struct RecentProfilesKey;
+
+impl PersistentKey for RecentProfilesKey {
+    type Value = Vec<ProfileId>;
+}
+
+#[derive(Clone, Debug, Display)]
+#[display("{name}")]
+struct ProfileSwitcherItem {
+    id: ProfileId,
+    name: String,
+}
+
+impl ProfileSwitcherItem {
+    /// Match the search query against this profile's name and ID. Return
+    /// `None` if neither matches
+    fn score(&self, query: &str) -> Option<u32> {
+        let name_score = fuzzy_score(query, &self.name);
+        let id_score = fuzzy_score(query, &self.id);
+        name_score.max(id_score)
+    }
+}
+
+impl PartialEq<ProfileId> for ProfileSwitcherItem {
+    fn eq(&self, id: &ProfileId) -> bool {
+        &self.id == id
+    }
+}
+
+impl ToStringGenerate for ProfileSwitcherItem {}
+
+/// Fuzzy-match a query against some text. Every character in the query must
+/// appear in the text, in order, ignoring case. Return `None` if the text
+/// doesn't match. Otherwise, higher scores are better matches. Consecutive
+/// characters and matches at the start of words are weighted higher, so
+/// `dev` ranks `dev-east` above `old-environment`.
+fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    const MATCH: u32 = 1;
+    const CONSECUTIVE_BONUS: u32 = 4;
+    const WORD_START_BONUS: u32 = 3;
+
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for c in text.chars() {
+        let Some(&expected) = query.peek() else {
+            break;
+        };
+        let matched = c.to_lowercase().eq(expected.to_lowercase());
+        if matched {
+            query.next();
+            score += MATCH;
+            if previous_matched {
+                score += CONSECUTIVE_BONUS;
+            }
+            let is_word_start = previous.is_none_or(|previous| {
+                !previous.is_alphanumeric()
+                    || (previous.is_lowercase() && c.is_uppercase())
+            });
+            if is_word_start {
+                score += WORD_START_BONUS;
+            }
+        }
+        previous_matched = matched;
+        previous = Some(c);
+    }
+
+    // If any query characters are left, it's not a match
+    if query.peek().is_some() {
+        None
+    } else {
+        Some(score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::empty("", "dev", Some(0))]
+    #[case::exact("dev", "dev", Some(3 + 3 + 2 * 4))]
+    #[case::case_insensitive("DEV", "dev", Some(3 + 3 + 2 * 4))]
+    #[case::subsequence("pd", "production", Some(2 + 3))]
+    #[case::word_start("pe", "prod-east", Some(2 + 3 + 3))]
+    #[case::camel_case("pe", "prodEast", Some(2 + 3 + 3))]
+    #[case::out_of_order("ed", "dev", None)]
+    #[case::no_match("x", "dev", None)]
+    fn test_fuzzy_score(
+        #[case] query: &str,
+        #[case] text: &str,
+        #[case] expected: Option<u32>,
+    ) {
+        assert_eq!(fuzzy_score(query, text), expected);
+    }
+
+    /// Recently used profiles move to the front
+    #[rstest]
+    fn test_recent_profiles() {
+        let mut recent = RecentProfiles::default();
+        recent.visit(&"p1".into());
+        recent.visit(&"p2".into());
+        recent.visit(&"p1".into());
+        assert_eq!(recent.0, [ProfileId::from("p1"), "p2".into()]);
+        assert_eq!(recent.position(&"p2".into()), 1);
+        assert_eq!(recent.position(&"p3".into()), usize::MAX);
+    }
+}
@@ -5,7 +5,10 @@ use crate::{
         Component, Generate, ViewContext,
         common::{
             text_box::{TextBox, TextBoxProps},
-            text_window::{ScrollbarMargins, TextWindow, TextWindowProps},
+            text_window::{
+                BookmarkKey, ScrollbarMargins, TextWindow, TextWindowProps,
+                diff_lines,
+            },
         },
         component::{
             Canvas, Child, ComponentExt, ComponentId, Draw, DrawMetadata,
@@ -15,7 +18,7 @@ use crate::{
         context::UpdateContext,
         event::{Emitter, Event, EventMatch, ToEmitter},
         persistent::{PersistentKey, PersistentStore},
-        util::{highlight, str_to_text},
+        util::{ansi::ansi_to_text, highlight, str_to_text},
     },
 };
 use anyhow::Context;
@@ -24,11 +27,13 @@ use ratatui::{
     layout::{Constraint, Layout},
     text::Text,
 };
+use serde::{Deserialize, Serialize};
 use slumber_config::Action;
 use slumber_core::{
     http::{ResponseBody, ResponseRecord, content_type::ContentType},
     util::MaybeStr,
 };
+use slumber_util::tr;
 use std::{borrow::Cow, mem, sync::Arc};
 use tokio_util::sync::CancellationToken;
 
@@ -39,7 +44,18 @@ pub struct QueryableBody<K> {
     id: ComponentId,
     emitter: Emitter<CommandComplete>,
     response: Arc<ResponseRecord>,
+    /// Previous response for the same recipe+profile. If present, changes
+    /// relative to this response are marked in the gutter
+    previous_response: Option<Arc<ResponseRecord>>,
     persistent_key: K,
+    /// User-selected display options
+    display: BodyDisplay,
+    /// External command that renders the body for display, if one is
+    /// configured for its content type. Raw mode skips it.
+    renderer: Option<String>,
+    /// Track status of the render command
+    render_state: CommandState,
+    render_emitter: Emitter<RenderComplete>,
 
     /// Which command box, if any, are we typing in?
     command_focus: CommandFocus,
@@ -59,11 +75,14 @@ pub struct QueryableBody<K> {
 }
 
 impl<K> QueryableBody<K> {
-    /// Create a new body with an optional default query
+    /// Create a new body with an optional default query. If a previous
+    /// response is given, lines that changed since then will be marked
     pub fn new(
         persistent_key: K,
         response: Arc<ResponseRecord>,
+        previous_response: Option<Arc<ResponseRecord>>,
         default_query: Option<String>,
+        display: BodyDisplay,
     ) -> Self
     where
         K: PersistentKey<Value = String>,
@@ -95,14 +114,25 @@ impl<K> QueryableBody<K> {
                 "Enter export command (ex: `tee > response.json`)",
             ));
 
-        let text_state =
-            TextState::new(response.content_type(), &response.body, true);
+        let text_state = TextState::new(
+            response.content_type(),
+            &response.body,
+            !display.raw,
+            previous_response.as_deref(),
+        )
+        .with_bookmarks(BookmarkKey(response.id))
+        .with_wrap(display.wrap);
 
         let mut slf = Self {
             id: ComponentId::default(),
             emitter: Default::default(),
             response,
+            previous_response,
             persistent_key,
+            display,
+            renderer: None,
+            render_state: CommandState::None,
+            render_emitter: Default::default(),
             command_focus: CommandFocus::None,
             query_state: CommandState::None,
             query_text_box,
@@ -115,6 +145,16 @@ impl<K> QueryableBody<K> {
         slf
     }
 
+    /// Render the body with an external command. The output replaces the body
+    /// text whenever the original body would be shown.
+    pub fn with_renderer(mut self, renderer: Option<String>) -> Self {
+        self.renderer = renderer;
+        if self.last_executed_query.is_none() {
+            self.reset_body();
+        }
+        self
+    }
+
     /// If the original body text is _not_ what the user is looking at (because
     /// of a query command or prettification), get the visible text. Otherwise,
     /// return `None` to indicate the response's original body can be used.
@@ -135,6 +175,56 @@ impl<K> QueryableBody<K> {
         self.text_state.text_window.text()
     }
 
+    /// Get the current display options
+    pub fn display(&self) -> BodyDisplay {
+        self.display
+    }
+
+    /// Change display options. Raw mode only applies to the original body;
+    /// query output is never prettified.
+    pub fn set_display(&mut self, display: BodyDisplay) {
+        let old = mem::replace(&mut self.display, display);
+        if display.raw != old.raw && self.last_executed_query.is_none() {
+            self.reset_body();
+        } else {
+            self.text_state.text_window.set_wrap(display.wrap);
+        }
+    }
+
+    /// Show the original response body. If there's a renderer, start it in
+    /// the background; the plain body is shown until it's done
+    fn reset_body(&mut self) {
+        if let Some(token) = self.render_state.take_cancel_token() {
+            token.cancel();
+        }
+        self.render_state = CommandState::None;
+        self.text_state = self.body_text_state();
+
+        if let Some(renderer) = &self.renderer
+            && !self.display.raw
+        {
+            let body = self.response.body.bytes().clone();
+            let emitter = self.render_emitter;
+            let cancel_token =
+                self.spawn_command(renderer.clone(), body, move |_, result| {
+                    emitter.emit(RenderComplete(result));
+                });
+            self.render_state = CommandState::Running(cancel_token);
+        }
+    }
+
+    /// Build display text for the original response body
+    fn body_text_state(&self) -> TextState {
+        TextState::new(
+            self.response.content_type(),
+            &self.response.body,
+            !self.display.raw,
+            self.previous_response.as_deref(),
+        )
+        .with_bookmarks(BookmarkKey(self.response.id))
+        .with_wrap(self.display.wrap)
+    }
+
     fn focus(&mut self, focus: CommandFocus) {
         self.command_focus = focus;
     }
@@ -158,14 +248,18 @@ impl<K> QueryableBody<K> {
             // Reset to initial body
             self.last_executed_query = None;
             self.query_state = CommandState::None;
-            self.text_state = TextState::new(
-                self.response.content_type(),
-                &self.response.body,
-                true, // Prettify
-            );
+            self.reset_body();
         } else {
             // Send it
             self.last_executed_query = Some(command.to_owned());
+            // Query output replaces the rendered body, so don't let a slow
+            // renderer clobber it
+            if let Some(token) = self.render_state.take_cancel_token() {
+                token.cancel();
+            }
+            // Store the command in history. Query and export commands are
+            // stored together. We can toss the error; it gets traced by the DB
+            let _ = ViewContext::with_database(|db| db.insert_command(command));
 
             // Spawn the command in the background because it could be slow.
             // Clone is cheap because Bytes uses refcounting
@@ -198,12 +292,14 @@ impl<K> QueryableBody<K> {
             .modified_text()
             .map(Bytes::from)
             .unwrap_or_else(|| self.response.body.bytes().clone());
+        let _ = ViewContext::with_database(|db| db.insert_command(&command));
 
         self.spawn_command(command, body, |command, result| match result {
             // We provide feedback via a global mechanism in both cases, so
             // we don't need an emitter here
-            Ok(_) => ViewContext::send_message(Message::Notify(format!(
-                "`{command}` succeeded"
+            Ok(_) => ViewContext::send_message(Message::Notify(tr!(
+                "notify-command-succeeded",
+                command = command
             ))),
             Err(error) => ViewContext::send_message(Message::Error { error }),
         });
@@ -220,10 +316,6 @@ impl<K> QueryableBody<K> {
     ) -> CancellationToken {
         let cancel_token = CancellationToken::new();
         let future = async move {
-            // Store the command in history. Query and export commands are
-            // stored together. We can toss the error; it gets traced by the DB
-            let _ =
-                ViewContext::with_database(|db| db.insert_command(&command));
             let shell = &ViewContext::config().tui.commands.shell;
             let result = util::run_command(shell, &command, Some(&body))
                 .await
@@ -269,11 +361,35 @@ impl<K: PersistentKey<Value = String>> Component for QueryableBody<K> {
                         // Don't prettify - user controls this output. If
                         // it's not pretty already, that's on them
                         false,
-                    );
+                        // The previous response hasn't been through the same
+                        // command, so there's nothing to compare to
+                        None,
+                    )
+                    .with_bookmarks(BookmarkKey(self.response.id))
+                    .with_wrap(self.display.wrap);
                 }
                 // Trigger error state. Error will be shown in the pane
                 Err(error) => self.query_state = CommandState::Error(error),
             })
+            .emitted(self.render_emitter, |RenderComplete(result)| {
+                match result {
+                    Ok(stdout) => {
+                        self.render_state = CommandState::Ok;
+                        let text = String::from_utf8_lossy(&stdout);
+                        self.text_state = TextState {
+                            text_window: TextWindow::new(ansi_to_text(&text)),
+                            // The text differs from the body, so copy and
+                            // export should use what's visible
+                            pretty: true,
+                        }
+                        .with_bookmarks(BookmarkKey(self.response.id))
+                        .with_wrap(self.display.wrap);
+                    }
+                    Err(error) => {
+                        self.render_state = CommandState::Error(error);
+                    }
+                }
+            })
             .emitted(self.query_text_box.to_emitter(), |event| match event {
                 CommandTextBoxEvent::Cancel => {
                     // Reset text to whatever was submitted last
@@ -318,7 +434,13 @@ impl<K: PersistentKey<Value = String>> Draw for QueryableBody<K> {
             Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
                 .areas(metadata.area());
 
-        if let CommandState::Error(error) = &self.query_state {
+        // Render errors only apply while the rendered body would be shown
+        let error = match (&self.query_state, &self.render_state) {
+            (CommandState::Error(error), _) => Some(error),
+            (CommandState::None, CommandState::Error(error)) => Some(error),
+            _ => None,
+        };
+        if let Some(error) = error {
             canvas.render_widget(error.generate(), body_area);
         } else {
             canvas.draw(
@@ -377,11 +499,13 @@ struct TextState {
 }
 
 impl TextState {
-    /// Calculate display text based on current body/query
+    /// Calculate display text based on current body/query. If a previous
+    /// response is given, mark lines that changed relative to its body
     fn new<T: AsRef<[u8]>>(
         content_type: Option<ContentType>,
         body: &ResponseBody<T>,
         prettify: bool,
+        previous: Option<&ResponseRecord>,
     ) -> Self {
         if ViewContext::config().http.is_large(body.size()) {
             // For bodies over the "large" size, skip prettification and
@@ -412,22 +536,30 @@ impl TextState {
             // separate task because it's generally very fast. If this is slow
             // enough that it affects the user, the "large" body size is
             // probably too low
-            let (text, pretty): (Cow<str>, bool) = if let Some(content_type) =
-                content_type
-                && prettify
-            {
-                content_type
-                    .prettify(text)
-                    .map(|body| (Cow::Owned(body), true))
-                    .unwrap_or((Cow::Borrowed(text), false))
-            } else {
-                (Cow::Borrowed(text), false)
-            };
+            let (text, pretty) = Self::prettify(content_type, text, prettify);
 
-            let text =
-                highlight::highlight_if(content_type, str_to_text(&text));
+            // Compare against the previous body, formatted the same way. Skip
+            // large or binary bodies for the same reasons as above
+            let diff = previous
+                .filter(|previous| {
+                    !ViewContext::config().http.is_large(previous.body.size())
+                })
+                .and_then(|previous| previous.body.text())
+                .and_then(|previous_text| {
+                    let (previous_text, _) =
+                        Self::prettify(content_type, previous_text, prettify);
+                    diff_lines(&previous_text, &text)
+                });
+
+            let mut text_window = TextWindow::new(highlight::highlight_if(
+                content_type,
+                str_to_text(&text),
+            ));
+            if let Some(diff) = diff {
+                text_window = text_window.with_diff(diff);
+            }
             TextState {
-                text_window: TextWindow::new(text),
+                text_window,
                 pretty,
             }
         } else {
@@ -440,6 +572,47 @@ impl TextState {
             }
         }
     }
+
+    /// Restore bookmarks for an exchange from the session store
+    fn with_bookmarks(mut self, key: BookmarkKey) -> Self {
+        self.text_window = self.text_window.with_bookmarks(key);
+        self
+    }
+
+    /// Enable/disable soft-wrapping of long lines
+    fn with_wrap(mut self, wrap: bool) -> Self {
+        self.text_window.set_wrap(wrap);
+        self
+    }
+
+    /// Prettify text for known content types. Return the text and whether it
+    /// was modified
+    fn prettify(
+        content_type: Option<ContentType>,
+        text: &str,
+        prettify: bool,
+    ) -> (Cow<'_, str>, bool) {
+        if let Some(content_type) = content_type
+            && prettify
+        {
+            content_type
+                .prettify(text)
+                .map(|body| (Cow::Owned(body), true))
+                .unwrap_or((Cow::Borrowed(text), false))
+        } else {
+            (Cow::Borrowed(text), false)
+        }
+    }
+}
+
+/// Display options for a body that the user can toggle
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BodyDisplay {
+    /// Show the body as received, without prettifying it
+    pub raw: bool,
+    /// Soft-wrap lines that don't fit in the window
+    pub wrap: bool,
 }
 
 /// Which command box, if any, is focused?
@@ -455,6 +628,11 @@ enum CommandFocus {
 #[derive(Debug)]
 struct CommandComplete(Result<Vec<u8>, anyhow::Error>);
 
+/// Emitted event to notify when the body renderer has completed. Contains the
+/// stdout of the process if successful.
+#[derive(Debug)]
+struct RenderComplete(Result<Vec<u8>, anyhow::Error>);
+
 #[derive(Debug, Default)]
 enum CommandState {
     /// Command has not been run yet
@@ -540,7 +718,13 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            QueryableBody::new(Key, response, None),
+            QueryableBody::new(
+                Key,
+                response,
+                None,
+                None,
+                BodyDisplay::default(),
+            ),
         );
 
         // Assert initial state/view
@@ -597,6 +781,114 @@ mod tests {
         ]);
     }
 
+    /// A configured renderer replaces the body with its output, until raw
+    /// mode is enabled
+    #[rstest]
+    #[tokio::test]
+    async fn test_renderer(
+        mut harness: TestHarness,
+        #[with(27, 2)] terminal: TestTerminal,
+        response: Arc<ResponseRecord>,
+    ) {
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            QueryableBody::new(
+                Key,
+                response,
+                None,
+                None,
+                BodyDisplay::default(),
+            )
+            .with_renderer(Some("sed s/hello/bye/".into())),
+        );
+        harness.run_task().await; // Run the renderer
+        component.int().drain_draw().assert().empty();
+        assert_eq!(
+            component.modified_text().as_deref(),
+            Some("{\"greeting\":\"bye\"}")
+        );
+        let styles = ViewContext::styles();
+        terminal.assert_buffer_lines([
+            vec![gutter("1"), " {\"greeting\":\"bye\"}  ".into()],
+            vec![Span::styled(
+                "[/] to query, [:] to export",
+                styles.text_box.text.patch(styles.text_box.placeholder),
+            )],
+        ]);
+
+        // Raw mode shows the original body
+        component.set_display(BodyDisplay {
+            raw: true,
+            ..BodyDisplay::default()
+        });
+        component.int().drain_draw().assert().empty();
+        assert_eq!(component.modified_text().as_deref(), None);
+        terminal.assert_buffer_lines([
+            vec![gutter("1"), " {\"greeting\":\"hello\"}".into()],
+            vec![Span::styled(
+                "[/] to query, [:] to export",
+                styles.text_box.text.patch(styles.text_box.placeholder),
+            )],
+        ]);
+    }
+
+    /// When given a previous response, changed lines are marked. The markers
+    /// are dropped while a query is applied
+    #[rstest]
+    #[tokio::test]
+    async fn test_diff(
+        mut harness: TestHarness,
+        #[with(27, 2)] terminal: TestTerminal,
+        response: Arc<ResponseRecord>,
+    ) {
+        let previous = ResponseRecord {
+            headers: Default::default(),
+            body: ResponseBody::new("{\"greeting\":\"hi\"}".into()),
+            ..ResponseRecord::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            QueryableBody::new(
+                Key,
+                response,
+                Some(previous.into()),
+                None,
+                BodyDisplay::default(),
+            ),
+        );
+        let styles = ViewContext::styles();
+        terminal.assert_buffer_lines([
+            vec![
+                gutter("1"),
+                Span::styled("~", styles.text_window.diff_changed),
+                "{\"greeting\":\"hello\"}".into(),
+            ],
+            vec![Span::styled(
+                "[/] to query, [:] to export",
+                styles.text_box.text.patch(styles.text_box.placeholder),
+            )],
+        ]);
+
+        component
+            .int()
+            .send_key(KeyCode::Char('/'))
+            .send_text("head -c 1")
+            .send_key(KeyCode::Enter)
+            .assert()
+            .empty();
+        harness.run_task().await;
+        component.int().drain_draw().assert().empty();
+        terminal.assert_buffer_lines([
+            vec![gutter("1"), " {                   ".into()],
+            vec![Span::styled(
+                "head -c 1                  ",
+                styles.text_box.text,
+            )],
+        ]);
+    }
+
     /// Render a parsed body with query text box, and load initial query from
     /// the DB. This tests the persistence implementation
     #[rstest]
@@ -615,7 +907,13 @@ mod tests {
             &harness,
             &terminal,
             // Default value should get tossed out
-            QueryableBody::new(Key, response, Some("initial".into())),
+            QueryableBody::new(
+                Key,
+                response,
+                None,
+                Some("initial".into()),
+                BodyDisplay::default(),
+            ),
         );
         harness.run_task().await; // Run the initial task
 
@@ -637,7 +935,13 @@ mod tests {
         let component = TestComponent::new(
             &harness,
             &terminal,
-            QueryableBody::new(Key, response, Some("head -n 1".into())),
+            QueryableBody::new(
+                Key,
+                response,
+                None,
+                Some("head -n 1".into()),
+                BodyDisplay::default(),
+            ),
         );
         harness.run_task().await; // Run the initial task
 
@@ -659,7 +963,13 @@ mod tests {
             &harness,
             &terminal,
             // Default should override the persisted value
-            QueryableBody::new(Key, response, Some("head -n 1".into())),
+            QueryableBody::new(
+                Key,
+                response,
+                None,
+                Some("head -n 1".into()),
+                BodyDisplay::default(),
+            ),
         );
         harness.run_task().await; // Run the initial task
 
@@ -678,7 +988,13 @@ mod tests {
         let mut component = TestComponent::builder(
             &harness,
             &terminal,
-            QueryableBody::new(Key, response, None),
+            QueryableBody::new(
+                Key,
+                response,
+                None,
+                None,
+                BodyDisplay::default(),
+            ),
         )
         .with_default_props()
         .with_area(terminal.area().inner(Margin {
@@ -0,0 +1,223 @@
+//! Quick-repeat modal for sending recent requests again
+
+use crate::{
+    http::RequestConfig,
+    input::InputEvent,
+    view::{
+        ToStringGenerate, UpdateContext, ViewContext,
+        common::{
+            modal::Modal,
+            select::{Select, SelectListProps},
+        },
+        component::{
+            Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+        },
+        event::{Emitter, Event, EventMatch},
+    },
+};
+use derive_more::Display;
+use ratatui::{layout::Constraint, text::Line};
+use slumber_config::Action;
+use slumber_util::tr;
+use terminput::KeyCode;
+
+/// Modal listing the most recent distinct requests, most recent first. Each
+/// one can be sent again by selecting it, or by pressing its number.
+#[derive(Debug)]
+pub struct RecentRequestsMenu {
+    id: ComponentId,
+    /// Emits the chosen request. This belongs to the parent, because the modal
+    /// is rebuilt each time it's opened
+    emitter: Emitter<ResendRequest>,
+    select: Select<RecentRequestItem>,
+}
+
+impl RecentRequestsMenu {
+    pub fn new(
+        emitter: Emitter<ResendRequest>,
+        recent_requests: &RecentRequests,
+    ) -> Self {
+        let collection = ViewContext::collection();
+        let items = recent_requests
+            .0
+            .iter()
+            .enumerate()
+            .map(|(i, config)| {
+                // The recipe or profile may have been removed since the
+                // request was sent. Fall back to the IDs
+                let recipe = collection
+                    .recipes
+                    .get_recipe(&config.recipe_id)
+                    .map_or(&*config.recipe_id, |recipe| recipe.name());
+                let profile = config.profile_id.as_ref().map(|profile_id| {
+                    collection
+                        .profiles
+                        .get(profile_id)
+                        .map_or(&**profile_id, |profile| profile.name())
+                        .to_owned()
+                });
+                RecentRequestItem {
+                    number: i + 1,
+                    name: recipe.to_owned(),
+                    profile,
+                    config: config.clone(),
+                }
+            })
+            .collect();
+        Self {
+            id: ComponentId::default(),
+            emitter,
+            select: Select::builder(items).build(),
+        }
+    }
+}
+
+impl Component for RecentRequestsMenu {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event.m().any(|event| match event {
+            // Number keys send the corresponding request immediately. Grab
+            // them before they can trigger any other action
+            Event::Input(InputEvent::Key {
+                code: KeyCode::Char(c @ '1'..='9'),
+                ..
+            }) => {
+                let index = c as usize - '1' as usize;
+                if let Some(item) = self.select.items().nth(index) {
+                    self.emitter.emit(ResendRequest(item.config.clone()));
+                }
+                None
+            }
+            _ => Some(event),
+        })
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for RecentRequestsMenu {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        if self.select.is_empty() {
+            canvas.render_widget(
+                "No requests sent yet. Requests you send will be listed here",
+                metadata.area(),
+            );
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                metadata.area(),
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for RecentRequestsMenu {
+    fn title(&self) -> Line<'_> {
+        ViewContext::add_binding_hint(
+            tr!("modal-recent-requests-title"),
+            Action::RecentRequests,
+        )
+        .into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).max(1);
+        (Constraint::Length(40), Constraint::Length(height))
+    }
+
+    fn on_submit(self, _: &mut UpdateContext) {
+        if let Some(item) = self.select.into_selected() {
+            self.emitter.emit(ResendRequest(item.config));
+        }
+    }
+}
+
+/// Emitted when the user picks a request from [RecentRequestsMenu]
+#[derive(Debug)]
+pub struct ResendRequest(pub RequestConfig);
+
+/// The most recently sent requests, most recent first. Requests are distinct
+/// by recipe and profile; sending the same recipe again with different
+/// overrides replaces the older entry. This only lives for the session,
+/// because overrides are session-only as well.
+#[derive(Debug, Default)]
+pub struct RecentRequests(Vec<RequestConfig>);
+
+impl RecentRequests {
+    /// Max number of requests to remember. Each gets a number key in the menu
+    const MAX_SIZE: usize = 9;
+
+    /// Mark a request as the most recently sent
+    pub fn push(&mut self, config: RequestConfig) {
+        self.0.retain(|other| {
+            other.recipe_id != config.recipe_id
+                || other.profile_id != config.profile_id
+        });
+        self.0.insert(0, config);
+        self.0.truncate(Self::MAX_SIZE);
+    }
+
+    /// Get the most recently sent request
+    pub fn last(&self) -> Option<&RequestConfig> {
+        self.0.first()
+    }
+}
+
+#[derive(Clone, Debug, Display)]
+#[display(
+    "{number} {name}{}",
+    profile.as_ref().map(|profile| format!(" ({profile})")).unwrap_or_default()
+)]
+struct RecentRequestItem {
+    /// Number key that sends this request
+    number: usize,
+    /// Recipe name
+    name: String,
+    /// Profile name
+    profile: Option<String>,
+    config: RequestConfig,
+}
+
+impl ToStringGenerate for RecentRequestItem {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use slumber_core::http::BuildOptions;
+
+    fn config(recipe: &str, profile: &str) -> RequestConfig {
+        RequestConfig {
+            profile_id: Some(profile.into()),
+            recipe_id: recipe.into(),
+            options: BuildOptions::default(),
+        }
+    }
+
+    /// Requests are deduplicated by recipe+profile, most recent first
+    #[rstest]
+    fn test_recent_requests() {
+        let mut recent = RecentRequests::default();
+        recent.push(config("r1", "p1"));
+        recent.push(config("r2", "p1"));
+        recent.push(config("r1", "p2"));
+        recent.push(config("r1", "p1"));
+        assert_eq!(
+            recent.0,
+            [config("r1", "p1"), config("r1", "p2"), config("r2", "p1")]
+        );
+        assert_eq!(recent.last(), Some(&config("r1", "p1")));
+
+        for i in 0..20 {
+            recent.push(config(&format!("r{i}"), "p1"));
+        }
+        assert_eq!(recent.0.len(), RecentRequests::MAX_SIZE);
+    }
+}
@@ -2,6 +2,7 @@
 
 mod authentication;
 mod body;
+mod folder;
 mod recipe;
 mod table;
 mod url;
@@ -14,7 +15,7 @@ use crate::{
         component::{
             Canvas, ComponentId, Draw, DrawMetadata,
             internal::{Child, ToChild},
-            recipe::recipe::RecipeDisplay,
+            recipe::{folder::FolderDisplay, recipe::RecipeDisplay},
             sidebar_list::{
                 SidebarList, SidebarListEvent, SidebarListItem,
                 SidebarListProps, SidebarListState,
@@ -25,22 +26,17 @@ use crate::{
         persistent::{PersistentKey, PersistentStore},
     },
 };
-use itertools::{Itertools, Position};
 use ratatui::{
     layout::Alignment,
-    prelude::{Buffer, Rect},
     text::{Line, Text},
-    widgets::Widget,
 };
 use serde::Serialize;
-use slumber_config::Action;
+use slumber_config::{Action, RecipeTab};
 use slumber_core::{
-    collection::{
-        Folder, HasId, RecipeId, RecipeLookupKey, RecipeNode, RecipeNodeType,
-    },
+    collection::{HasId, RecipeId, RecipeNode, RecipeNodeType},
     http::BuildOptions,
 };
-use slumber_util::doc_link;
+use slumber_util::{doc_link, tr};
 use std::{borrow::Cow, collections::HashSet};
 
 /// Wrapper for [SidebarList] that provides recipe-specific behavior. The recipe
@@ -72,6 +68,29 @@ impl RecipeList {
         })
     }
 
+    /// Select a recipe or folder by ID. If it's hidden within a collapsed
+    /// folder, expand its ancestors to make it visible. Does nothing if the
+    /// node isn't in the collection.
+    pub fn select_recipe(&mut self, recipe_id: &RecipeId) {
+        let collection = ViewContext::collection();
+        let Some(lookup_key) = collection.recipes.get_lookup_key(recipe_id)
+        else {
+            return;
+        };
+
+        let mut changed = false;
+        for folder_id in lookup_key.ancestors() {
+            changed |= self
+                .list
+                .state_mut()
+                .collapse(folder_id.clone(), Collapse::Expand);
+        }
+        if changed {
+            self.list.rebuild_select();
+        }
+        self.list.select_id(recipe_id);
+    }
+
     /// Modify expand/collapse state on the selected node
     fn collapse_selected(&mut self, collapse: Collapse) {
         if let Some(selected) = self.list.selected()
@@ -178,16 +197,6 @@ impl RecipeListState {
         self.collapsed.contains(folder_id)
     }
 
-    /// Is the given node visible? This takes lookup key so it can check all
-    /// ancestors for visibility too.
-    fn is_visible(&self, lookup_key: &RecipeLookupKey) -> bool {
-        // If any ancestors are collapsed, this is *not* visible
-        !lookup_key
-            .ancestors()
-            .iter()
-            .any(|id| self.is_collapsed(id))
-    }
-
     /// Set the currently selected folder as expanded/collapsed (or toggle it).
     /// Returns whether a change was made.
     fn collapse(&mut self, folder_id: RecipeId, collapse: Collapse) -> bool {
@@ -221,10 +230,11 @@ impl SidebarListState for RecipeListState {
     fn items(&self) -> Vec<Self::Item> {
         let recipes = &ViewContext::collection().recipes;
 
-        // No filter - calculate visible nodes based on collapsed state
+        // Only visit visible nodes. The contents of collapsed folders aren't
+        // built until they're expanded, which keeps this cheap for large
+        // collections
         recipes
-            .iter()
-            .filter(|(lookup_key, _)| self.is_visible(lookup_key))
+            .iter_expanded(|folder| !self.is_collapsed(&folder.id))
             .map(|(lookup_key, node)| {
                 RecipeListItem::new(
                     node,
@@ -242,12 +252,6 @@ impl SidebarListState for RecipeListState {
 struct RecipeListItem {
     id: RecipeId,
     name: String,
-    /// The name of this item and *all* of its children, grandchildren, etc.For
-    /// This is used during filtering, so that a folder always shows when any
-    /// of its children match. This duplicates a lot of strings in the recipe
-    /// tree, but the overall size should be very low so it has no meaningful
-    /// impact.
-    search_terms: Vec<String>,
     kind: RecipeNodeType,
     depth: usize,
     collapsed: bool,
@@ -255,23 +259,9 @@ struct RecipeListItem {
 
 impl RecipeListItem {
     fn new(node: &RecipeNode, collapsed: bool, depth: usize) -> Self {
-        fn add_search_terms(terms: &mut Vec<String>, node: &RecipeNode) {
-            terms.push(node.name().to_owned());
-            if let RecipeNode::Folder(folder) = node {
-                for child in folder.children.values() {
-                    // Recursion!
-                    add_search_terms(terms, child);
-                }
-            }
-        }
-
-        let mut search_terms = vec![];
-        add_search_terms(&mut search_terms, node);
-
         Self {
             id: node.id().clone(),
             name: node.name().to_owned(),
-            search_terms,
             kind: node.into(),
             collapsed,
             depth,
@@ -300,9 +290,10 @@ impl SidebarListItem for RecipeListItem {
 
     fn display_list(&self) -> Cow<'_, str> {
         // Add indentation and icons for list display
+        let symbols = ViewContext::styles().symbols;
         let icon = match self.kind {
-            RecipeNodeType::Folder if self.collapsed => "▶",
-            RecipeNodeType::Folder => "▼",
+            RecipeNodeType::Folder if self.collapsed => symbols.collapsed,
+            RecipeNodeType::Folder => symbols.expanded,
             RecipeNodeType::Recipe => "",
         };
 
@@ -316,11 +307,26 @@ impl SidebarListItem for RecipeListItem {
         .into()
     }
 
+    /// The name of this item and *all* of its children, grandchildren, etc.
+    /// This is used during filtering, so that a folder always shows when any
+    /// of its children match. These are collected on demand rather than stored
+    /// on the item, because most list builds don't have a filter.
     fn filter_terms(&self) -> Vec<Cow<'_, str>> {
-        self.search_terms
-            .iter()
-            .map(|term| term.as_str().into())
-            .collect()
+        fn add_search_terms(terms: &mut Vec<Cow<'_, str>>, node: &RecipeNode) {
+            terms.push(node.name().to_owned().into());
+            if let RecipeNode::Folder(folder) = node {
+                for child in folder.children.values() {
+                    // Recursion!
+                    add_search_terms(terms, child);
+                }
+            }
+        }
+
+        let mut terms = vec![];
+        if let Some(node) = ViewContext::collection().recipes.get(&self.id) {
+            add_search_terms(&mut terms, node);
+        }
+        terms
     }
 }
 
@@ -377,6 +383,7 @@ impl RecipeDetail {
             None => RecipeNodeState::None,
             Some(RecipeNode::Folder(folder)) => RecipeNodeState::Folder {
                 id: folder.id.clone(),
+                display: FolderDisplay::new(folder),
             },
             Some(RecipeNode::Recipe(recipe)) => RecipeNodeState::Recipe {
                 id: recipe.id.clone(),
@@ -390,6 +397,12 @@ impl RecipeDetail {
         }
     }
 
+    /// Persist a tab selection for the recipe pane, so it's selected the
+    /// next time the pane is built
+    pub fn select_tab(store: &mut PersistentStore, tab: RecipeTab) {
+        RecipeDisplay::select_tab(store, tab);
+    }
+
     /// Generate a [BuildOptions] instance based on current UI state. Return
     /// `None` only when there is no recipe selected.
     pub fn build_options(&self) -> Option<BuildOptions> {
@@ -423,8 +436,9 @@ impl Component for RecipeDetail {
 
     fn children(&mut self) -> Vec<Child<'_>> {
         match &mut self.state {
-            RecipeNodeState::None | RecipeNodeState::Folder { .. } => {
-                vec![]
+            RecipeNodeState::None => vec![],
+            RecipeNodeState::Folder { display, .. } => {
+                vec![display.to_child_mut()]
             }
             RecipeNodeState::Recipe { display, .. } => {
                 vec![display.to_child_mut()]
@@ -474,15 +488,8 @@ impl Draw for RecipeDetail {
                 ]),
                 inner_area,
             ),
-            RecipeNodeState::Folder { id } => {
-                // Folder *should* always be defined
-                if let Some(folder) =
-                    ViewContext::collection().recipes.get_folder(id)
-                {
-                    // Recompute the text on every render. This is a bit simpler
-                    // than storing it, and shouldn't be too expensive
-                    canvas.render_widget(FolderTree { folder }, inner_area);
-                }
+            RecipeNodeState::Folder { display, .. } => {
+                canvas.draw(display, (), inner_area, true);
             }
             RecipeNodeState::Recipe { display, .. } => {
                 canvas.draw(display, (), inner_area, true);
@@ -498,7 +505,11 @@ enum RecipeNodeState {
     #[default]
     None,
     /// Folder is selected
-    Folder { id: RecipeId },
+    Folder {
+        id: RecipeId,
+        /// Tree of descendants with URL previews
+        display: FolderDisplay,
+    },
     /// Recipe is selected
     Recipe {
         id: RecipeId,
@@ -510,35 +521,44 @@ enum RecipeNodeState {
 /// Items in the actions popup menu. This is used by both the list and detail
 /// components. Handling is stateless so it's shared between them.
 #[derive(Debug)]
-#[expect(clippy::enum_variant_names)]
 enum RecipeMenuAction {
     CopyUrl,
     CopyAsCli,
     CopyAsCurl,
     CopyAsPython,
+    Download,
 }
 
 impl RecipeMenuAction {
     /// Build a list of these actions
     fn menu(emitter: Emitter<Self>, has_recipe: bool) -> Vec<MenuItem> {
-        vec![MenuItem::Group {
-            name: "Copy".into(),
-            children: vec![
-                emitter.menu(Self::CopyUrl, "URL").enable(has_recipe).into(),
-                emitter
-                    .menu(Self::CopyAsCli, "as CLI")
-                    .enable(has_recipe)
-                    .into(),
-                emitter
-                    .menu(Self::CopyAsCurl, "as cURL")
-                    .enable(has_recipe)
-                    .into(),
-                emitter
-                    .menu(Self::CopyAsPython, "as Python")
-                    .enable(has_recipe)
-                    .into(),
-            ],
-        }]
+        vec![
+            MenuItem::Group {
+                name: tr!("menu-copy"),
+                children: vec![
+                    emitter
+                        .menu(Self::CopyUrl, tr!("menu-url"))
+                        .enable(has_recipe)
+                        .into(),
+                    emitter
+                        .menu(Self::CopyAsCli, tr!("menu-as-cli"))
+                        .enable(has_recipe)
+                        .into(),
+                    emitter
+                        .menu(Self::CopyAsCurl, tr!("menu-as-curl"))
+                        .enable(has_recipe)
+                        .into(),
+                    emitter
+                        .menu(Self::CopyAsPython, tr!("menu-as-python"))
+                        .enable(has_recipe)
+                        .into(),
+                ],
+            },
+            emitter
+                .menu(Self::Download, tr!("menu-download"))
+                .enable(has_recipe)
+                .into(),
+        ]
     }
 
     /// Send a global message/event to handle this event
@@ -552,138 +572,7 @@ impl RecipeMenuAction {
             Self::CopyAsCli => copy(RecipeCopyTarget::Cli),
             Self::CopyAsCurl => copy(RecipeCopyTarget::Curl),
             Self::CopyAsPython => copy(RecipeCopyTarget::Python),
+            Self::Download => ViewContext::send_message(Message::Download),
         }
     }
 }
-
-/// Widget to display a folder and its children as a tree
-struct FolderTree<'a> {
-    folder: &'a Folder,
-}
-
-impl Widget for FolderTree<'_> {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        // Generate something like:
-        // Users
-        // ├─Get Users
-        // ├─Get User
-        // ├─inner
-        // │ ├─Get User
-        // │ ├─inner2
-        // │ │ └─Get User
-        // │ └─Get User
-        // └─Modify User
-
-        fn add_lines<'a>(
-            lines: &mut Vec<Line<'a>>,
-            folder: &'a Folder,
-            parent_positions: &mut Vec<Position>,
-        ) {
-            for (position, node) in folder.children.values().with_position() {
-                let mut line = Line::default();
-
-                // Add decoration
-                for parent_position in parent_positions.iter() {
-                    let padding = match parent_position {
-                        // Extend the parent's line down if it has more children
-                        Position::First | Position::Middle => "│ ",
-                        Position::Last | Position::Only => "  ",
-                    };
-                    line.push_span(padding);
-                }
-                line.push_span(match position {
-                    Position::First | Position::Middle => "├─",
-                    Position::Last | Position::Only => "└─",
-                });
-
-                line.push_span(node.name());
-                lines.push(line);
-                if let RecipeNode::Folder(folder) = node {
-                    parent_positions.push(position);
-                    add_lines(lines, folder, parent_positions);
-                    parent_positions.pop();
-                }
-            }
-        }
-
-        // We could probably be more efficient and write as we go instead of
-        // accumulating into Text, but whatever
-        let mut lines = vec![self.folder.name().into()];
-        add_lines(&mut lines, self.folder, &mut Vec::new());
-        let text: Text = lines.into();
-        text.render(area, buf);
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::test_util::{TestTerminal, terminal};
-    use rstest::rstest;
-    use slumber_core::{collection::Recipe, test_util::by_id};
-    use slumber_util::{Factory, yaml::SourceLocation};
-
-    #[rstest]
-    fn test_folder_tree(#[with(14, 10)] terminal: TestTerminal) {
-        let folder = Folder {
-            id: "1f".into(),
-            location: SourceLocation::default(),
-            name: None,
-            children: by_id([
-                RecipeNode::Recipe(Recipe::factory("1.1r")),
-                RecipeNode::Recipe(Recipe::factory("1.2r")),
-                // Nested folder
-                RecipeNode::Folder(Folder {
-                    id: "1.3f".into(),
-                    location: SourceLocation::default(),
-                    name: None,
-                    children: by_id([RecipeNode::Recipe(Recipe::factory(
-                        "1.3.1r",
-                    ))]),
-                }),
-                // Empty folder
-                RecipeNode::Folder(Folder {
-                    id: "1.4f".into(),
-                    location: SourceLocation::default(),
-                    name: None,
-                    children: Default::default(),
-                }),
-                // End with a nested folder to make sure the leftmost
-                // decorations don't appear
-                RecipeNode::Folder(Folder {
-                    id: "1.5f".into(),
-                    location: SourceLocation::default(),
-                    name: None,
-                    children: by_id([
-                        RecipeNode::Recipe(Recipe::factory("1.5.1r")),
-                        RecipeNode::Folder(Folder {
-                            id: "1.5.2f".into(),
-                            location: SourceLocation::default(),
-                            name: None,
-                            children: by_id([RecipeNode::Recipe(
-                                Recipe::factory("1.5.2.1r"),
-                            )]),
-                        }),
-                    ]),
-                }),
-            ]),
-        };
-
-        terminal.draw(|f| {
-            f.render_widget(FolderTree { folder: &folder }, f.area());
-        });
-
-        terminal.assert_buffer_lines([
-            "1f",
-            "├─1.1r",
-            "├─1.2r",
-            "├─1.3f",
-            "│ └─1.3.1r",
-            "├─1.4f",
-            "└─1.5f",
-            "  ├─1.5.1r",
-            "  └─1.5.2f",
-            "    └─1.5.2.1r",
-        ]);
-    }
-}
@@ -1,5 +1,6 @@
 use crate::view::{
     common::{
+        actions::MenuItem,
         component_select::{
             ComponentSelect, ComponentSelectProps, SelectStyles,
         },
@@ -10,28 +11,51 @@ use crate::view::{
         editable_template::EditableTemplate, internal::Child,
     },
     context::{UpdateContext, ViewContext},
-    event::{Event, EventMatch, ToEmitter},
+    event::{Emitter, Event, EventMatch, ToEmitter},
     persistent::SessionKey,
 };
-use ratatui::{layout::Layout, prelude::Constraint, text::Span};
-use slumber_core::collection::{Authentication, RecipeId};
+use ratatui::{
+    layout::Layout,
+    prelude::Constraint,
+    text::{Line, Span},
+};
+use slumber_core::collection::{
+    Authentication, Folder, OAuth2, OAuth2Grant, RecipeId,
+};
 use slumber_template::Template;
+use slumber_util::tr;
 
 /// Display authentication settings for a recipe
 #[derive(Debug)]
 pub struct AuthenticationDisplay {
     id: ComponentId,
     state: State,
+    /// ID and name of the folder the authentication is inherited from.
+    /// `None` if it's defined on the recipe itself
+    inherited_from: Option<(RecipeId, String)>,
+    /// Emitter for menu actions
+    actions_emitter: Emitter<AuthenticationMenuAction>,
 }
 
 impl AuthenticationDisplay {
-    pub fn new(recipe_id: RecipeId, authentication: Authentication) -> Self {
+    /// Build the display. If the authentication is inherited from a parent
+    /// folder, pass that folder so it can be shown and navigated to.
+    pub fn new(
+        recipe_id: RecipeId,
+        authentication: Authentication,
+        inherited_from: Option<&Folder>,
+    ) -> Self {
         let state = match authentication {
             Authentication::Basic { username, password } => {
-                State::Basic(BasicAuthentication::new(
-                    recipe_id,
-                    username,
-                    password.unwrap_or_default(),
+                State::Basic(AuthenticationForm::new(
+                    &recipe_id,
+                    [
+                        (AuthenticationField::Username, username),
+                        (
+                            AuthenticationField::Password,
+                            password.unwrap_or_default(),
+                        ),
+                    ],
                 ))
             }
             Authentication::Bearer { token } => State::Bearer {
@@ -43,10 +67,70 @@ impl AuthenticationDisplay {
                     false,
                 ),
             },
+            Authentication::Digest { username, password } => {
+                State::Digest(AuthenticationForm::new(
+                    &recipe_id,
+                    [
+                        (AuthenticationField::Username, username),
+                        (AuthenticationField::Password, password),
+                    ],
+                ))
+            }
+            Authentication::Ntlm {
+                username,
+                password,
+                domain,
+            } => State::Ntlm(AuthenticationForm::new(
+                &recipe_id,
+                [
+                    (AuthenticationField::Username, username),
+                    (AuthenticationField::Password, password),
+                    (AuthenticationField::Domain, domain.unwrap_or_default()),
+                ],
+            )),
+            Authentication::OAuth2(OAuth2 {
+                grant,
+                token_url,
+                client_id,
+                client_secret,
+                scope,
+            }) => {
+                let mut fields = Vec::new();
+                let redirect_uri = match grant {
+                    OAuth2Grant::ClientCredentials => None,
+                    OAuth2Grant::AuthorizationCode {
+                        authorization_url,
+                        redirect_uri,
+                    } => {
+                        fields.push((
+                            AuthenticationField::AuthorizationUrl,
+                            authorization_url,
+                        ));
+                        Some(redirect_uri.unwrap_or_default())
+                    }
+                };
+                fields.extend([
+                    (AuthenticationField::TokenUrl, token_url),
+                    (AuthenticationField::ClientId, client_id),
+                    (
+                        AuthenticationField::ClientSecret,
+                        client_secret.unwrap_or_default(),
+                    ),
+                    (AuthenticationField::Scope, scope.unwrap_or_default()),
+                ]);
+                if let Some(redirect_uri) = redirect_uri {
+                    fields
+                        .push((AuthenticationField::RedirectUri, redirect_uri));
+                }
+                State::OAuth2(AuthenticationForm::new(&recipe_id, fields))
+            }
         };
         Self {
             id: ComponentId::default(),
             state,
+            inherited_from: inherited_from
+                .map(|folder| (folder.id.clone(), folder.name().to_owned())),
+            actions_emitter: Emitter::default(),
         }
     }
 
@@ -55,15 +139,65 @@ impl AuthenticationDisplay {
     pub fn override_value(&self) -> Option<Authentication> {
         if self.state.is_overridden() {
             Some(match &self.state {
-                State::Basic(basic) => Authentication::Basic {
-                    username: basic.username().clone(),
+                State::Basic(form) => Authentication::Basic {
+                    username: form.get(AuthenticationField::Username).clone(),
                     // We don't use an option internally because an empty
                     // password is equivalent to no password
-                    password: Some(basic.password().clone()),
+                    password: Some(
+                        form.get(AuthenticationField::Password).clone(),
+                    ),
                 },
                 State::Bearer { token, .. } => Authentication::Bearer {
                     token: token.template().clone(),
                 },
+                State::Digest(form) => Authentication::Digest {
+                    username: form.get(AuthenticationField::Username).clone(),
+                    password: form.get(AuthenticationField::Password).clone(),
+                },
+                State::Ntlm(form) => Authentication::Ntlm {
+                    username: form.get(AuthenticationField::Username).clone(),
+                    password: form.get(AuthenticationField::Password).clone(),
+                    // Domain is shown as empty when absent
+                    domain: Some(form.get(AuthenticationField::Domain))
+                        .filter(|template| !template.is_empty())
+                        .cloned(),
+                },
+                State::OAuth2(form) => {
+                    // Optional fields are shown as empty when absent, so map
+                    // empty back to absent
+                    let optional = |field| {
+                        Some(form.get(field))
+                            .filter(|template| !template.is_empty())
+                            .cloned()
+                    };
+                    let grant = if form
+                        .contains(AuthenticationField::AuthorizationUrl)
+                    {
+                        OAuth2Grant::AuthorizationCode {
+                            authorization_url: form
+                                .get(AuthenticationField::AuthorizationUrl)
+                                .clone(),
+                            redirect_uri: optional(
+                                AuthenticationField::RedirectUri,
+                            ),
+                        }
+                    } else {
+                        OAuth2Grant::ClientCredentials
+                    };
+                    Authentication::OAuth2(OAuth2 {
+                        grant,
+                        token_url: form
+                            .get(AuthenticationField::TokenUrl)
+                            .clone(),
+                        client_id: form
+                            .get(AuthenticationField::ClientId)
+                            .clone(),
+                        client_secret: optional(
+                            AuthenticationField::ClientSecret,
+                        ),
+                        scope: optional(AuthenticationField::Scope),
+                    })
+                }
             })
         } else {
             None
@@ -76,9 +210,39 @@ impl Component for AuthenticationDisplay {
         self.id
     }
 
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event
+            .m()
+            .emitted(self.actions_emitter, |menu_action| match menu_action {
+                AuthenticationMenuAction::GoToFolder(folder_id) => {
+                    ViewContext::push_event(Event::GoToFolder(folder_id));
+                }
+            })
+    }
+
+    fn menu(&self) -> Vec<MenuItem> {
+        let Some((folder_id, folder_name)) = &self.inherited_from else {
+            return vec![];
+        };
+        vec![MenuItem::Group {
+            name: tr!("menu-go-to-definition"),
+            children: vec![
+                self.actions_emitter
+                    .menu(
+                        AuthenticationMenuAction::GoToFolder(folder_id.clone()),
+                        tr!("menu-folder", folder = folder_name),
+                    )
+                    .into(),
+            ],
+        }]
+    }
+
     fn children(&mut self) -> Vec<Child<'_>> {
         match &mut self.state {
-            State::Basic(basic) => vec![basic.to_child_mut()],
+            State::Basic(form)
+            | State::Digest(form)
+            | State::Ntlm(form)
+            | State::OAuth2(form) => vec![form.to_child_mut()],
             State::Bearer { token } => vec![token.to_child_mut()],
         }
     }
@@ -94,18 +258,33 @@ impl Draw for AuthenticationDisplay {
         let label = match &self.state {
             State::Basic { .. } => "Basic",
             State::Bearer { .. } => "Bearer",
+            State::Digest(_) => "Digest",
+            State::Ntlm(_) => "NTLM",
+            State::OAuth2(form)
+                if form.contains(AuthenticationField::AuthorizationUrl) =>
+            {
+                "OAuth 2.0 (Authorization Code)"
+            }
+            State::OAuth2(_) => "OAuth 2.0 (Client Credentials)",
         };
-        canvas.render_widget(
-            Span::styled(
-                format!("Authentication Type: {label}"),
-                styles.text.title,
-            ),
-            label_area,
-        );
+        let mut title = Line::from(Span::styled(
+            format!("Authentication Type: {label}"),
+            styles.text.title,
+        ));
+        if let Some((_, folder_name)) = &self.inherited_from {
+            title.push_span(Span::styled(
+                format!(" (inherited from {folder_name})"),
+                styles.text.hint,
+            ));
+        }
+        canvas.render_widget(title, label_area);
 
         match &self.state {
-            State::Basic(basic) => {
-                canvas.draw(basic, (), content_area, true);
+            State::Basic(form)
+            | State::Digest(form)
+            | State::Ntlm(form)
+            | State::OAuth2(form) => {
+                canvas.draw(form, (), content_area, true);
             }
             State::Bearer { token } => {
                 canvas.draw(token, (), content_area, true);
@@ -114,80 +293,100 @@ impl Draw for AuthenticationDisplay {
     }
 }
 
+/// Menu action for [AuthenticationDisplay]
+#[derive(Clone, Debug)]
+enum AuthenticationMenuAction {
+    /// Navigate to the folder that defines the inherited authentication
+    GoToFolder(RecipeId),
+}
+
 /// Private to hide enum variants
 #[derive(Debug)]
 enum State {
-    Basic(BasicAuthentication),
+    Basic(AuthenticationForm),
     Bearer {
         token: EditableTemplate<AuthenticationKey>,
     },
+    Digest(AuthenticationForm),
+    Ntlm(AuthenticationForm),
+    OAuth2(AuthenticationForm),
 }
 
 impl State {
     /// Have *any* fields been overridden?
     fn is_overridden(&self) -> bool {
         match self {
-            Self::Basic(basic) => {
-                basic.select.items().any(|item| item.value.is_overridden())
-            }
+            Self::Basic(form)
+            | Self::Digest(form)
+            | Self::Ntlm(form)
+            | Self::OAuth2(form) => form.is_overridden(),
             Self::Bearer { token } => token.is_overridden(),
         }
     }
 }
 
-/// Wrapper for basic authentication state. This needs to be a separate
-/// component because it has its own event handling for the contained Select
+/// A list of labelled fields, e.g. username and password for basic auth. This
+/// needs to be a separate component because it has its own event handling for
+/// the contained Select
 #[derive(Debug)]
-struct BasicAuthentication {
+struct AuthenticationForm {
     id: ComponentId,
-    /// A list of exactly two fields: [username, password]. This can't use
-    /// `FixedSelect` because there's associated data attached to each
-    /// field
-    select: ComponentSelect<BasicField>,
+    /// One row per field. This can't use `FixedSelect` because there's
+    /// associated data attached to each field
+    select: ComponentSelect<FormField>,
+    /// Width of the label column, to fit the longest label
+    label_width: u16,
 }
 
-impl BasicAuthentication {
+impl AuthenticationForm {
     fn new(
-        recipe_id: RecipeId,
-        username: Template,
-        password: Template,
+        recipe_id: &RecipeId,
+        fields: impl IntoIterator<Item = (AuthenticationField, Template)>,
     ) -> Self {
-        let username = EditableTemplate::new(
-            "Username",
-            AuthenticationKey::Username(recipe_id.clone()),
-            username,
-            false,
-            false,
-        );
-        let password = EditableTemplate::new(
-            "Password",
-            AuthenticationKey::Password(recipe_id.clone()),
-            password,
-            false,
-            false,
-        );
-        let select = Select::builder(vec![
-            BasicField::new("Username", username),
-            BasicField::new("Password", password),
-        ])
-        .subscribe([SelectEventKind::Select])
-        .build();
+        let fields: Vec<_> = fields
+            .into_iter()
+            .map(|(field, template)| FormField::new(recipe_id, field, template))
+            .collect();
+        let label_width = fields
+            .iter()
+            .map(|row| row.field.label().len() as u16 + 1)
+            .max()
+            .unwrap_or_default();
+        let select = Select::builder(fields)
+            .subscribe([SelectEventKind::Select])
+            .build();
         Self {
             id: ComponentId::default(),
             select: ComponentSelect::new(select),
+            label_width,
         }
     }
 
-    fn username(&self) -> &Template {
-        self.select[0].value.template()
+    /// Does the form include a field?
+    fn contains(&self, field: AuthenticationField) -> bool {
+        self.select.items().any(|row| row.field == field)
+    }
+
+    /// Get the active template for a field
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the field isn't in the form
+    fn get(&self, field: AuthenticationField) -> &Template {
+        self.select
+            .items()
+            .find(|row| row.field == field)
+            .map(|row| row.value.template())
+            .unwrap_or_else(|| panic!("Form missing field {field:?}"))
     }
 
-    fn password(&self) -> &Template {
-        self.select[1].value.template()
+    /// Have *any* fields been overridden?
+    fn is_overridden(&self) -> bool {
+        self.select.items().any(|row| row.value.is_overridden())
     }
 }
 
-impl Component for BasicAuthentication {
+impl Component for AuthenticationForm {
     fn id(&self) -> ComponentId {
         self.id
     }
@@ -211,12 +410,14 @@ impl Component for BasicAuthentication {
     }
 }
 
-impl Draw for BasicAuthentication {
+impl Draw for AuthenticationForm {
     fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        let label_width = self.label_width;
         canvas.draw(
             &self.select,
             ComponentSelectProps {
                 styles: SelectStyles::table(),
+                item_props: Box::new(move |_, _| (label_width, 1)),
                 ..Default::default()
             },
             metadata.area(),
@@ -225,29 +426,35 @@ impl Draw for BasicAuthentication {
     }
 }
 
-/// One row in a basic auth form. Each form has exactly two rows: Username and
-/// Password
+/// One row in an authentication form
 #[derive(Debug)]
-struct BasicField {
+struct FormField {
     id: ComponentId,
-    label: &'static str,
+    field: AuthenticationField,
     value: EditableTemplate<AuthenticationKey>,
 }
 
-impl BasicField {
+impl FormField {
     fn new(
-        label: &'static str,
-        template: EditableTemplate<AuthenticationKey>,
+        recipe_id: &RecipeId,
+        field: AuthenticationField,
+        template: Template,
     ) -> Self {
         Self {
             id: ComponentId::default(),
-            label,
-            value: template,
+            field,
+            value: EditableTemplate::new(
+                field.label(),
+                AuthenticationKey::Field(recipe_id.clone(), field),
+                template,
+                false,
+                false,
+            ),
         }
     }
 }
 
-impl Component for BasicField {
+impl Component for FormField {
     fn id(&self) -> ComponentId {
         self.id
     }
@@ -257,23 +464,61 @@ impl Component for BasicField {
     }
 }
 
-impl Draw for BasicField {
-    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
-        let [label_area, value_area] =
-            Layout::horizontal([Constraint::Length(10), Constraint::Min(1)])
-                .areas(metadata.area());
+/// Props are the width of the label column
+impl Draw<u16> for FormField {
+    fn draw(
+        &self,
+        canvas: &mut Canvas,
+        label_width: u16,
+        metadata: DrawMetadata,
+    ) {
+        let [label_area, value_area] = Layout::horizontal([
+            Constraint::Length(label_width),
+            Constraint::Min(1),
+        ])
+        .areas(metadata.area());
 
-        canvas.render_widget(self.label, label_area);
+        canvas.render_widget(self.field.label(), label_area);
         canvas.draw(&self.value, (), value_area, true);
     }
 }
 
+/// A field in an [AuthenticationForm]
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum AuthenticationField {
+    Username,
+    Password,
+    Domain,
+    AuthorizationUrl,
+    TokenUrl,
+    ClientId,
+    ClientSecret,
+    Scope,
+    RedirectUri,
+}
+
+impl AuthenticationField {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Username => "Username",
+            Self::Password => "Password",
+            Self::Domain => "Domain",
+            Self::AuthorizationUrl => "Authorization URL",
+            Self::TokenUrl => "Token URL",
+            Self::ClientId => "Client ID",
+            Self::ClientSecret => "Client Secret",
+            Self::Scope => "Scope",
+            Self::RedirectUri => "Redirect URI",
+        }
+    }
+}
+
 /// Session persistent key for override templates
 #[derive(Clone, Debug, PartialEq)]
 enum AuthenticationKey {
     Token(RecipeId),
-    Username(RecipeId),
-    Password(RecipeId),
+    /// A field in a form
+    Field(RecipeId, AuthenticationField),
 }
 
 impl SessionKey for AuthenticationKey {
@@ -301,7 +546,11 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            AuthenticationDisplay::new(RecipeId::factory(()), authentication),
+            AuthenticationDisplay::new(
+                RecipeId::factory(()),
+                authentication,
+                None,
+            ),
         );
 
         // Check initial state
@@ -369,7 +618,11 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            AuthenticationDisplay::new(RecipeId::factory(()), authentication),
+            AuthenticationDisplay::new(
+                RecipeId::factory(()),
+                authentication,
+                None,
+            ),
         );
 
         // Edit password
@@ -391,7 +644,11 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            AuthenticationDisplay::new(RecipeId::factory(()), authentication),
+            AuthenticationDisplay::new(
+                RecipeId::factory(()),
+                authentication,
+                None,
+            ),
         );
 
         // Check initial state
@@ -421,6 +678,117 @@ mod tests {
         assert_eq!(component.override_value(), None);
     }
 
+    /// Test edit OAuth2 fields via keybinds. Optional fields that are absent
+    /// are shown as empty, and stay absent unless given a value
+    #[rstest]
+    fn test_edit_oauth2(harness: TestHarness, terminal: TestTerminal) {
+        let oauth2 = OAuth2 {
+            grant: OAuth2Grant::AuthorizationCode {
+                authorization_url: "{{ host }}/authorize".into(),
+                redirect_uri: None,
+            },
+            token_url: "{{ host }}/token".into(),
+            client_id: "client".into(),
+            client_secret: None,
+            scope: None,
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            AuthenticationDisplay::new(
+                RecipeId::factory(()),
+                Authentication::OAuth2(oauth2.clone()),
+                None,
+            ),
+        );
+
+        // Check initial state
+        assert_eq!(component.override_value(), None);
+
+        // Edit client secret
+        component
+            .int()
+            .send_keys([
+                KeyCode::Down,
+                KeyCode::Down,
+                KeyCode::Down,
+                KeyCode::Char('e'),
+            ])
+            .send_text("secret")
+            .send_key(KeyCode::Enter)
+            .assert()
+            .empty();
+        assert_eq!(
+            component.override_value(),
+            Some(Authentication::OAuth2(OAuth2 {
+                client_secret: Some("secret".into()),
+                ..oauth2
+            }))
+        );
+
+        // Reset client secret
+        component
+            .int()
+            .send_key(KeyCode::Char('z'))
+            .assert()
+            .empty();
+        assert_eq!(component.override_value(), None);
+    }
+
+    /// Test edit NTLM fields via keybinds. The domain is shown as empty when
+    /// absent, and stays absent unless given a value
+    #[rstest]
+    fn test_edit_ntlm(harness: TestHarness, terminal: TestTerminal) {
+        let authentication = Authentication::Ntlm {
+            username: "user1".into(),
+            password: "hunter2".into(),
+            domain: None,
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            AuthenticationDisplay::new(
+                RecipeId::factory(()),
+                authentication,
+                None,
+            ),
+        );
+
+        // Edit password
+        component
+            .int()
+            .send_keys([KeyCode::Down, KeyCode::Char('e')])
+            .send_text("!")
+            .send_key(KeyCode::Enter)
+            .assert()
+            .empty();
+        assert_eq!(
+            component.override_value(),
+            Some(Authentication::Ntlm {
+                username: "user1".into(),
+                password: "hunter2!".into(),
+                domain: None,
+            })
+        );
+
+        // Edit domain
+        component
+            .int()
+            .send_keys([KeyCode::Down, KeyCode::Char('e')])
+            .send_text("CORP")
+            .send_key(KeyCode::Enter)
+            .assert()
+            .empty();
+        assert_eq!(
+            component.override_value(),
+            Some(Authentication::Ntlm {
+                username: "user1".into(),
+                password: "hunter2!".into(),
+                domain: Some("CORP".into()),
+            })
+        );
+    }
+
     /// Test edit/reset via menu action
     #[rstest]
     fn test_edit_action(harness: TestHarness, terminal: TestTerminal) {
@@ -430,7 +798,11 @@ mod tests {
         let mut component = TestComponent::new(
             &harness,
             &terminal,
-            AuthenticationDisplay::new(RecipeId::factory(()), authentication),
+            AuthenticationDisplay::new(
+                RecipeId::factory(()),
+                authentication,
+                None,
+            ),
         );
 
         component
@@ -455,11 +827,17 @@ mod tests {
     fn test_persisted_load_basic(harness: TestHarness, terminal: TestTerminal) {
         let recipe_id = RecipeId::factory(());
         harness.persistent_store().set_session(
-            AuthenticationKey::Username(recipe_id.clone()),
+            AuthenticationKey::Field(
+                recipe_id.clone(),
+                AuthenticationField::Username,
+            ),
             "user".into(),
         );
         harness.persistent_store().set_session(
-            AuthenticationKey::Password(recipe_id.clone()),
+            AuthenticationKey::Field(
+                recipe_id.clone(),
+                AuthenticationField::Password,
+            ),
             "hunter2".into(),
         );
         let authentication = Authentication::Basic {
@@ -469,7 +847,7 @@ mod tests {
         let component = TestComponent::new(
             &harness,
             &terminal,
-            AuthenticationDisplay::new(recipe_id, authentication),
+            AuthenticationDisplay::new(recipe_id, authentication, None),
         );
 
         assert_eq!(
@@ -496,7 +874,7 @@ mod tests {
         let component = TestComponent::new(
             &harness,
             &terminal,
-            AuthenticationDisplay::new(recipe_id, authentication),
+            AuthenticationDisplay::new(recipe_id, authentication, None),
         );
 
         assert_eq!(
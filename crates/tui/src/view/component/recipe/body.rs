@@ -15,25 +15,25 @@ use crate::{
         },
         context::{UpdateContext, ViewContext},
         event::{Emitter, Event, EventMatch, ToEmitter},
-        persistent::{PersistentKey, SessionKey},
+        persistent::{PersistentKey, PersistentStore, SessionKey},
         util::{highlight, view_text},
     },
 };
 use anyhow::Context;
-use indexmap::IndexMap;
 use mime::Mime;
 use ratatui::{
     layout::{Constraint, Layout},
-    text::Text,
+    text::{Line, Text},
 };
 use serde::Serialize;
 use slumber_config::Action;
 use slumber_core::{
-    collection::{JsonTemplate, Recipe, RecipeBody, RecipeId},
+    collection::{GraphQlBody, JsonTemplate, Recipe, RecipeBody, RecipeId},
     http::content_type::ContentType,
 };
 use slumber_template::{Template, TemplateParseError};
-use std::{error::Error as StdError, fs};
+use slumber_util::tr;
+use std::{error::Error as StdError, fs, sync::Arc};
 use tracing::{debug, error};
 
 /// Render recipe body. The variant is based on the incoming body type, and
@@ -46,6 +46,9 @@ pub enum RecipeBodyDisplay {
     /// uses the same internal type as `Raw`, but the distinction allows us to
     /// parse and generate an override body correctly
     Json(TextBody),
+    /// A body declared with the `graphql` type. The query and variables are
+    /// shown and overridden separately
+    Graphql(GraphQlBodyDisplay),
     Form(RecipeTable<FormTableKind>),
 }
 
@@ -55,32 +58,47 @@ impl RecipeBodyDisplay {
     /// body is not `None`.
     pub fn new(body: &RecipeBody, recipe: &Recipe) -> Self {
         match body {
-            RecipeBody::Raw(body) | RecipeBody::Stream(body) => {
-                Self::Raw(TextBody::new(body.clone(), recipe))
-            }
+            // For file bodies, the path is what's shown and overridden
+            RecipeBody::Raw(body)
+            | RecipeBody::Stream(body)
+            | RecipeBody::File(body) => Self::Raw(TextBody::new(
+                BodyKey::Body(recipe.id.clone()),
+                body.clone(),
+                recipe.mime(),
+            )),
             RecipeBody::Json(json) => {
                 let template = preview_json_template(json);
-                Self::Json(TextBody::new(template, recipe))
+                Self::Json(TextBody::new(
+                    BodyKey::Body(recipe.id.clone()),
+                    template,
+                    recipe.mime(),
+                ))
+            }
+            RecipeBody::Graphql(graphql) => {
+                Self::Graphql(GraphQlBodyDisplay::new(&recipe.id, graphql))
             }
             RecipeBody::FormUrlencoded(fields) => {
                 Self::Form(Self::form_table(&recipe.id, fields, false))
             }
-            RecipeBody::FormMultipart(fields) => {
-                Self::Form(Self::form_table(&recipe.id, fields, true))
-            }
+            // Part headers can't be edited, so only the values are shown
+            RecipeBody::FormMultipart(fields) => Self::Form(Self::form_table(
+                &recipe.id,
+                fields.iter().map(|(field, part)| (field, part.value())),
+                true,
+            )),
         }
     }
 
-    fn form_table(
+    fn form_table<'a>(
         recipe_id: &RecipeId,
-        fields: &IndexMap<String, Template>,
+        fields: impl IntoIterator<Item = (&'a String, &'a Template)>,
         can_stream: bool,
     ) -> RecipeTable<FormTableKind> {
         RecipeTable::new(
             "Field",
             recipe_id.clone(),
             fields
-                .iter()
+                .into_iter()
                 .map(|(field, value)| (field.clone(), value.clone())),
             can_stream,
         )
@@ -90,14 +108,27 @@ impl RecipeBodyDisplay {
     /// value. Return `None` to use the recipe's stock body.
     pub fn override_value(&self) -> Option<Template> {
         match self {
-            RecipeBodyDisplay::Raw(inner) | RecipeBodyDisplay::Json(inner)
-                if inner.preview.is_overridden() =>
-            {
-                // For JSON bodies, the template will be parsed as JSON by the
-                // HTTP engine
-                Some(inner.preview.template().clone())
+            // For JSON bodies, the template will be parsed as JSON by the
+            // HTTP engine
+            RecipeBodyDisplay::Raw(inner) | RecipeBodyDisplay::Json(inner) => {
+                inner.override_value()
+            }
+            // For GraphQL bodies, the body override is just the query
+            RecipeBodyDisplay::Graphql(graphql) => {
+                graphql.query.override_value()
             }
             // Form bodies override per-field so return None for them
+            RecipeBodyDisplay::Form(_) => None,
+        }
+    }
+
+    /// If the user has applied a temporary edit to the variables of a GraphQL
+    /// body, get the override value
+    pub fn graphql_variables_override_value(&self) -> Option<Template> {
+        match self {
+            RecipeBodyDisplay::Graphql(graphql) => {
+                graphql.variables.override_value()
+            }
             _ => None,
         }
     }
@@ -108,6 +139,7 @@ impl Component for RecipeBodyDisplay {
         match self {
             RecipeBodyDisplay::Raw(text_body)
             | RecipeBodyDisplay::Json(text_body) => text_body.id(),
+            RecipeBodyDisplay::Graphql(graphql) => graphql.id(),
             RecipeBodyDisplay::Form(table) => table.id(),
         }
     }
@@ -117,6 +149,7 @@ impl Component for RecipeBodyDisplay {
             Self::Raw(text_body) | Self::Json(text_body) => {
                 vec![text_body.to_child_mut()]
             }
+            Self::Graphql(graphql) => vec![graphql.to_child_mut()],
             Self::Form(form) => vec![form.to_child_mut()],
         }
     }
@@ -131,6 +164,9 @@ impl Draw for RecipeBodyDisplay {
             RecipeBodyDisplay::Json(inner) => {
                 canvas.draw(inner, (), metadata.area(), true);
             }
+            RecipeBodyDisplay::Graphql(graphql) => {
+                canvas.draw(graphql, (), metadata.area(), true);
+            }
             RecipeBodyDisplay::Form(form) => canvas.draw(
                 form,
                 RecipeTableProps {
@@ -162,15 +198,18 @@ pub struct TextBody {
     /// `TextWindow` will hold the invalid template, and the error is stored to
     /// display the error message.
     text_window: Result<TextWindow, (TextWindow, TemplateParseError)>,
+    /// Schema for a GraphQL query, as an introspection result. If loaded, the
+    /// query is edited alongside the schema and a config file pointing to it,
+    /// so the editor's GraphQL language server can complete fields.
+    graphql_schema: Option<Arc<str>>,
 }
 
 impl TextBody {
-    fn new(template: Template, recipe: &Recipe) -> Self {
-        let mime = recipe.mime();
-
+    /// Build a new text body. The MIME type determines syntax highlighting
+    /// and pager selection, but has no impact on the content of the body.
+    fn new(key: BodyKey, template: Template, mime: Option<Mime>) -> Self {
         // Start rendering the preview in the background
-        let preview =
-            TemplatePreview::new(BodyKey(recipe.id.clone()), template, true);
+        let preview = TemplatePreview::new(key, template, true);
 
         // Display the raw template while the preview renders
         let text = highlight(mime.as_ref(), preview.render_raw());
@@ -183,9 +222,17 @@ impl TextBody {
             preview,
             mime,
             text_window: Ok(text_window),
+            graphql_schema: None,
         }
     }
 
+    /// Get the override template, if the user has edited the body
+    fn override_value(&self) -> Option<Template> {
+        self.preview
+            .is_overridden()
+            .then(|| self.preview.template().clone())
+    }
+
     /// Open rendered body in the pager
     fn view_body(&self) {
         let text_window = match &self.text_window {
@@ -198,10 +245,19 @@ impl TextBody {
     /// the body to a temp file so the editor subprocess can access it. We'll
     /// read it back later.
     fn open_editor(&mut self) {
-        let Some(file) =
-            TempFile::new(self.preview.template().display().as_bytes())
-                .reported(&ViewContext::messages_tx())
-        else {
+        let body = self.preview.template().display();
+        let file = match &self.graphql_schema {
+            Some(schema) => TempFile::in_dir(
+                "query.graphql",
+                body.as_bytes(),
+                &[
+                    ("schema.json", schema.as_bytes()),
+                    (".graphqlrc.yml", GRAPHQL_CONFIG.as_bytes()),
+                ],
+            ),
+            None => TempFile::new(body.as_bytes(), self.mime.as_ref()),
+        };
+        let Some(file) = file.reported(&ViewContext::messages_tx()) else {
             // Write failed
             return;
         };
@@ -236,6 +292,15 @@ impl TextBody {
             return;
         };
 
+        // Some editors (e.g. Notepad) convert line endings to CRLF on save.
+        // If the original body didn't have any, assume the editor added them
+        // and undo it, so the body we send matches what the user sees
+        let body = if self.preview.template().display().contains('\r') {
+            body
+        } else {
+            body.replace("\r\n", "\n")
+        };
+
         // Parse the template. If parsing fails, set the error
         match body.parse::<Template>() {
             Ok(template) => {
@@ -276,16 +341,20 @@ impl Component for TextBody {
             .emitted(self.override_emitter, |SaveBodyOverride(file)| {
                 self.load_override(file);
             })
-            .emitted(self.preview.to_emitter(), |TemplatePreviewEvent(text)| {
-                // If the template is valid, accept its preview renders. If not,
-                // the previews will not correspond to the invalid template
-                // we're holding, so ignore these events
-                if let Ok(text_window) = &mut self.text_window {
-                    // Apply syntax highlighting
-                    let text = highlight(self.mime.as_ref(), text);
-                    *text_window = TextWindow::new(text);
-                }
-            })
+            .emitted(
+                self.preview.to_emitter(),
+                |TemplatePreviewEvent { text, .. }| {
+                    // If the template is valid, accept its preview renders. If
+                    // not, the previews will not correspond
+                    // to the invalid template
+                    // we're holding, so ignore these events
+                    if let Ok(text_window) = &mut self.text_window {
+                        // Apply syntax highlighting
+                        let text = highlight(self.mime.as_ref(), text);
+                        *text_window = TextWindow::new(text);
+                    }
+                },
+            )
             .emitted(self.actions_emitter, |menu_action| match menu_action {
                 RawBodyMenuAction::View => self.view_body(),
                 RawBodyMenuAction::Copy => ViewContext::send_message(
@@ -300,16 +369,18 @@ impl Component for TextBody {
         let emitter = self.actions_emitter;
         vec![
             emitter
-                .menu(RawBodyMenuAction::View, "View Body")
+                .menu(RawBodyMenuAction::View, tr!("menu-view-body"))
                 .shortcut(Some(Action::View))
                 .into(),
-            emitter.menu(RawBodyMenuAction::Copy, "Copy Body").into(),
             emitter
-                .menu(RawBodyMenuAction::Edit, "Edit Body")
+                .menu(RawBodyMenuAction::Copy, tr!("menu-copy-body"))
+                .into(),
+            emitter
+                .menu(RawBodyMenuAction::Edit, tr!("menu-edit-body"))
                 .shortcut(Some(Action::Edit))
                 .into(),
             emitter
-                .menu(RawBodyMenuAction::Reset, "Reset Body")
+                .menu(RawBodyMenuAction::Reset, tr!("menu-reset-body"))
                 .enable(self.preview.is_overridden())
                 .shortcut(Some(Action::Reset))
                 .into(),
@@ -364,14 +435,167 @@ impl Draw for TextBody {
     }
 }
 
+/// A GraphQL body, shown as the query with the variables below it. The query
+/// gets the standard body actions, while the variables have their own actions
+/// in the menu.
+#[derive(Debug)]
+pub struct GraphQlBodyDisplay {
+    id: ComponentId,
+    recipe_id: RecipeId,
+    query: TextBody,
+    /// Variables as a JSON template. If the recipe has no variables, this is
+    /// empty but can still be edited to add some.
+    variables: TextBody,
+    /// Emitter for menu actions
+    actions_emitter: Emitter<GraphQlMenuAction>,
+    /// Emitter for the callback from loading the schema
+    schema_emitter: Emitter<SchemaLoaded>,
+}
+
+impl GraphQlBodyDisplay {
+    fn new(recipe_id: &RecipeId, graphql: &GraphQlBody) -> Self {
+        let mut query = TextBody::new(
+            BodyKey::Body(recipe_id.clone()),
+            graphql.query.clone(),
+            Some(graphql_mime()),
+        );
+        query.graphql_schema =
+            PersistentStore::get_session(&GraphQlSchemaKey(recipe_id.clone()));
+        let variables = TextBody::new(
+            BodyKey::GraphQlVariables(recipe_id.clone()),
+            graphql
+                .variables
+                .as_ref()
+                .map(preview_json_template)
+                .unwrap_or_default(),
+            Some(mime::APPLICATION_JSON),
+        );
+        Self {
+            id: ComponentId::default(),
+            recipe_id: recipe_id.clone(),
+            query,
+            variables,
+            actions_emitter: Default::default(),
+            schema_emitter: Default::default(),
+        }
+    }
+
+    /// Ask the main loop to load the schema from the server. The request needs
+    /// the whole recipe, so it can't be built here.
+    fn load_schema(&self) {
+        let emitter = self.schema_emitter;
+        ViewContext::send_message(Message::GraphQlSchema {
+            on_complete: Box::new(move |schema| {
+                emitter.emit(SchemaLoaded(schema));
+            }),
+        });
+    }
+}
+
+impl Component for GraphQlBodyDisplay {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event
+            .m()
+            .emitted(self.actions_emitter, |menu_action| match menu_action {
+                GraphQlMenuAction::EditVariables => {
+                    self.variables.open_editor();
+                }
+                GraphQlMenuAction::ResetVariables => {
+                    self.variables.preview.reset_override();
+                }
+                GraphQlMenuAction::LoadSchema => self.load_schema(),
+            })
+            .emitted(self.schema_emitter, |SchemaLoaded(schema)| {
+                self.query.graphql_schema = Some(schema);
+            })
+    }
+
+    fn menu(&self) -> Vec<MenuItem> {
+        let emitter = self.actions_emitter;
+        vec![
+            emitter
+                .menu(
+                    GraphQlMenuAction::EditVariables,
+                    tr!("menu-edit-variables"),
+                )
+                .into(),
+            emitter
+                .menu(
+                    GraphQlMenuAction::ResetVariables,
+                    tr!("menu-reset-variables"),
+                )
+                .enable(self.variables.preview.is_overridden())
+                .into(),
+            emitter
+                .menu(
+                    GraphQlMenuAction::LoadSchema,
+                    tr!("menu-load-graphql-schema"),
+                )
+                .into(),
+        ]
+    }
+
+    fn persist(&self, store: &mut PersistentStore) {
+        if let Some(schema) = &self.query.graphql_schema {
+            store.set_session(
+                GraphQlSchemaKey(self.recipe_id.clone()),
+                Arc::clone(schema),
+            );
+        }
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.query.to_child_mut(), self.variables.to_child_mut()]
+    }
+}
+
+impl Draw for GraphQlBodyDisplay {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        let styles = ViewContext::styles();
+        let [query_area, label_area, variables_area] = Layout::vertical([
+            Constraint::Fill(2),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(metadata.area());
+
+        canvas.draw(&self.query, (), query_area, true);
+        canvas.render_widget(
+            Line::styled("Variables", styles.text.title),
+            label_area,
+        );
+        // Keyboard input goes to the query; variables are edited via the menu
+        canvas.draw(&self.variables, (), variables_area, false);
+    }
+}
+
 /// Persistent key for text body override template
 #[derive(Clone, Debug, PartialEq)]
-struct BodyKey(RecipeId);
+enum BodyKey {
+    /// The entire body, or just the query of a GraphQL body
+    Body(RecipeId),
+    /// Variables of a GraphQL body
+    GraphQlVariables(RecipeId),
+}
 
 impl SessionKey for BodyKey {
     type Value = Template;
 }
 
+/// Session key for the schema loaded for a GraphQL recipe. The schema is kept
+/// for the session so it doesn't have to be reloaded every time the recipe is
+/// selected.
+#[derive(Debug, PartialEq)]
+struct GraphQlSchemaKey(RecipeId);
+
+impl SessionKey for GraphQlSchemaKey {
+    type Value = Arc<str>;
+}
+
 /// [RecipeTableKind] for the form field table
 #[derive(Debug)]
 pub struct FormTableKind;
@@ -418,13 +642,40 @@ enum RawBodyMenuAction {
     Reset,
 }
 
+/// Action menu items for a GraphQL body
+#[derive(Copy, Clone, Debug)]
+enum GraphQlMenuAction {
+    EditVariables,
+    ResetVariables,
+    LoadSchema,
+}
+
 /// Local event to save a user's override body. Triggered from the on_complete
 /// callback when the user closes the editor.
 #[derive(Debug)]
 struct SaveBodyOverride(TempFile);
 
+/// Local event to store a GraphQL schema. Triggered from the on_complete
+/// callback when the main loop has loaded it.
+#[derive(Debug)]
+struct SchemaLoaded(Arc<str>);
+
+/// [graphql-config](https://the-guild.dev/graphql/config) file written next to
+/// a GraphQL query being edited, so language servers can find the schema
+const GRAPHQL_CONFIG: &str = "schema: schema.json\ndocuments: '*.graphql'\n";
+
+/// MIME type for a GraphQL document. This isn't a registered type, but it's
+/// the de facto standard. It's used for syntax highlighting and the extension
+/// of the file passed to the editor.
+fn graphql_mime() -> Mime {
+    "application/graphql".parse().unwrap()
+}
+
 /// Apply syntax highlighting according to the body MIME type
 fn highlight(mime: Option<&Mime>, text: Text<'static>) -> Text<'static> {
+    if mime.is_some_and(|mime| *mime == graphql_mime()) {
+        return highlight::highlight_graphql(text);
+    }
     let content_type = mime.and_then(ContentType::from_mime);
     highlight::highlight_if(content_type, text)
 }
@@ -502,7 +753,7 @@ mod tests {
 
         // Persistence store should be updated
         let persisted =
-            PersistentStore::get_session(&BodyKey(recipe.id.clone()));
+            PersistentStore::get_session(&BodyKey::Body(recipe.id.clone()));
         assert_eq!(persisted, Some("goodbye!".into()));
 
         // Reset edited state
@@ -514,6 +765,35 @@ mod tests {
         assert_eq!(component.override_value(), None);
     }
 
+    /// If the editor converts line endings to CRLF, they're converted back.
+    /// Line endings that were already in the body are left alone
+    #[rstest]
+    #[case::added("line1\nline2", "line1\r\nline2\r\n", "line1\nline2\n")]
+    #[case::existing(
+        "line1\r\nline2",
+        "line1\r\nline2\r\n",
+        "line1\r\nline2\r\n"
+    )]
+    fn test_edit_crlf(
+        mut harness: TestHarness,
+        terminal: TestTerminal,
+        #[case] initial: &str,
+        #[case] edited: &str,
+        #[case] expected: &str,
+    ) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Raw(initial.parse().unwrap())),
+            ..Recipe::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            RecipeBodyDisplay::new(recipe.body.as_ref().unwrap(), &recipe),
+        );
+        edit(&mut component, &mut harness, initial, edited);
+        assert_eq!(component.override_value(), Some(expected.parse().unwrap()));
+    }
+
     /// Test edit and provide an invalid template. It should show the template
     /// with the error
     #[rstest]
@@ -550,7 +830,7 @@ mod tests {
         // invalid templates from here into OverrideTemplate, or duplicate a
         // bunch of persistence logic
         let persisted =
-            PersistentStore::get_session(&BodyKey(recipe.id.clone()));
+            PersistentStore::get_session(&BodyKey::Body(recipe.id.clone()));
         assert_eq!(persisted, None);
     }
 
@@ -597,7 +877,7 @@ mod tests {
 
         // Persistence store should be updated
         let persisted =
-            PersistentStore::get_session(&BodyKey(recipe.id.clone()));
+            PersistentStore::get_session(&BodyKey::Body(recipe.id.clone()));
         assert_eq!(persisted, Some(override_text.into()));
 
         // Reset edited state
@@ -621,7 +901,7 @@ mod tests {
         };
         harness
             .persistent_store()
-            .set_session(BodyKey(recipe.id.clone()), "hello!".into());
+            .set_session(BodyKey::Body(recipe.id.clone()), "hello!".into());
 
         let component = TestComponent::new(
             &harness,
@@ -669,6 +949,68 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    /// Load the schema for a GraphQL body. Once loaded, the query is edited
+    /// alongside the schema and a graphql-config file pointing to it
+    #[rstest]
+    fn test_graphql_schema(
+        mut harness: TestHarness,
+        #[with(20, 5)] terminal: TestTerminal,
+    ) {
+        let recipe = Recipe {
+            body: Some(RecipeBody::Graphql(GraphQlBody {
+                query: "{ me { id } }".into(),
+                variables: None,
+                operation_name: None,
+            })),
+            ..Recipe::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            RecipeBodyDisplay::new(recipe.body.as_ref().unwrap(), &recipe),
+        );
+        harness.messages().clear();
+
+        component.int().action(&["Load Schema"]).assert().empty();
+        let on_complete = assert_matches!(
+            harness.messages().pop_now(),
+            Message::GraphQlSchema { on_complete } => on_complete,
+        );
+        let schema = r#"{"__schema":{"queryType":{"name":"Query"}}}"#;
+        on_complete(schema.into());
+        component.int().drain_draw().assert().empty();
+
+        // Schema is kept for the session
+        let persisted =
+            PersistentStore::get_session(&GraphQlSchemaKey(recipe.id.clone()));
+        assert_eq!(persisted.as_deref(), Some(schema));
+
+        component
+            .int()
+            .send_key(KeyCode::Char('e'))
+            .assert()
+            .empty();
+        let file = assert_matches!(
+            harness.messages().pop_now(),
+            Message::FileEdit { file, .. } => file,
+        );
+        assert_eq!(file.path().file_name().unwrap(), "query.graphql");
+        assert_eq!(fs::read_to_string(file.path()).unwrap(), "{ me { id } }");
+        let dir = file.path().parent().unwrap().to_owned();
+        assert_eq!(
+            fs::read_to_string(dir.join("schema.json")).unwrap(),
+            schema
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join(".graphqlrc.yml")).unwrap(),
+            GRAPHQL_CONFIG
+        );
+
+        // The whole directory is cleaned up with the file
+        drop(file);
+        assert!(!dir.exists());
+    }
+
     /// Style text to match the text window gutter
     fn gutter(text: &str) -> Span<'_> {
         let styles = ViewContext::styles();
@@ -0,0 +1,336 @@
+use crate::view::{
+    Component, UpdateContext, ViewContext,
+    common::{
+        table::Table,
+        template_preview::{TemplatePreview, TemplatePreviewEvent},
+    },
+    component::{
+        Canvas, ComponentId, Draw, DrawMetadata,
+        internal::{Child, ToChild},
+        recipe::url::UrlKey,
+    },
+    event::{Event, EventMatch, ToEmitter},
+    styles::SymbolStyles,
+};
+use itertools::{Itertools, Position};
+use ratatui::{
+    layout::Constraint,
+    text::{Line, Text},
+};
+use slumber_core::collection::{Folder, Recipe, RecipeNode};
+
+/// Display a folder as a tree of its descendants. Each recipe in the tree is
+/// shown with its URL, rendered under the current profile. This makes it easy
+/// to sanity check a profile against a whole group of recipes at once. Like
+/// [RecipeDisplay](super::recipe::RecipeDisplay), this should be recreated
+/// every time the folder/profile changes.
+#[derive(Debug)]
+pub struct FolderDisplay {
+    id: ComponentId,
+    /// One row per node, in tree order. The folder itself is the first row
+    rows: Vec<FolderRow>,
+}
+
+impl FolderDisplay {
+    pub fn new(folder: &Folder) -> Self {
+        // Generate something like:
+        // Users
+        // ├─Get Users      GET   http://localhost/users
+        // ├─inner
+        // │ ├─Get User     GET   http://localhost/users/1
+        // │ └─Get User     GET   http://localhost/users/2
+        // └─Modify User    PATCH http://localhost/users/1
+
+        fn add_rows(
+            rows: &mut Vec<FolderRow>,
+            folder: &Folder,
+            parent_positions: &mut Vec<Position>,
+            symbols: &SymbolStyles,
+        ) {
+            for (position, node) in folder.children.values().with_position() {
+                let mut name = Line::default();
+
+                // Add decoration
+                for parent_position in parent_positions.iter() {
+                    let padding = match parent_position {
+                        // Extend the parent's line down if it has more children
+                        Position::First | Position::Middle => symbols.tree_line,
+                        Position::Last | Position::Only => " ",
+                    };
+                    name.push_span(format!("{padding} "));
+                }
+                name.push_span(match position {
+                    Position::First | Position::Middle => symbols.tree_branch,
+                    Position::Last | Position::Only => symbols.tree_end,
+                });
+                name.push_span(node.name().to_owned());
+
+                match node {
+                    RecipeNode::Folder(folder) => {
+                        rows.push(FolderRow { name, url: None });
+                        parent_positions.push(position);
+                        add_rows(rows, folder, parent_positions, symbols);
+                        parent_positions.pop();
+                    }
+                    RecipeNode::Recipe(recipe) => rows.push(FolderRow {
+                        name,
+                        url: Some(RecipeUrl::new(recipe)),
+                    }),
+                }
+            }
+        }
+
+        let mut rows = vec![FolderRow {
+            name: folder.name().to_owned().into(),
+            url: None,
+        }];
+        add_rows(
+            &mut rows,
+            folder,
+            &mut Vec::new(),
+            &ViewContext::styles().symbols,
+        );
+        Self {
+            id: ComponentId::default(),
+            rows,
+        }
+    }
+}
+
+impl Component for FolderDisplay {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        self.rows
+            .iter_mut()
+            .filter_map(|row| row.url.as_mut())
+            .map(ToChild::to_child_mut)
+            .collect()
+    }
+}
+
+impl Draw for FolderDisplay {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        // Names and methods get just as much as they need, URLs get the rest
+        let name_width = self
+            .rows
+            .iter()
+            .map(|row| row.name.width())
+            .max()
+            .unwrap_or_default();
+        let method_width = self
+            .rows
+            .iter()
+            .filter_map(|row| Some(row.url.as_ref()?.method.len()))
+            .max()
+            .unwrap_or_default();
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let (method, url) = match &row.url {
+                    Some(url) => (url.method.as_str().into(), url.text.clone()),
+                    None => (Text::default(), Text::default()),
+                };
+                [row.name.clone().into(), method, url]
+            })
+            .collect();
+        canvas.render_widget(
+            Table {
+                rows,
+                column_widths: &[
+                    Constraint::Length(name_width as u16),
+                    Constraint::Length(method_width as u16),
+                    Constraint::Min(0),
+                ],
+                ..Table::default()
+            },
+            metadata.area(),
+        );
+    }
+}
+
+/// A single node in the folder tree
+#[derive(Debug)]
+struct FolderRow {
+    /// Node name, with tree decoration
+    name: Line<'static>,
+    /// Method and URL preview. `None` for folders
+    url: Option<RecipeUrl>,
+}
+
+/// Method and rendered URL for a single recipe in the tree
+#[derive(Debug)]
+struct RecipeUrl {
+    id: ComponentId,
+    method: String,
+    /// Shares the persistent key with the recipe's own URL display, so any
+    /// override on the recipe is reflected here too
+    preview: TemplatePreview<UrlKey>,
+    /// Rendered preview text
+    text: Text<'static>,
+}
+
+impl RecipeUrl {
+    fn new(recipe: &Recipe) -> Self {
+        let preview = TemplatePreview::new(
+            UrlKey(recipe.id.clone()),
+            recipe.url.clone(),
+            false,
+        );
+        let text = preview.render_raw(); // Show raw while rendering
+        Self {
+            id: ComponentId::default(),
+            method: recipe.method.to_string(),
+            preview,
+            text,
+        }
+    }
+}
+
+impl Component for RecipeUrl {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event.m().emitted(
+            self.preview.to_emitter(),
+            |TemplatePreviewEvent { text, .. }| self.text = text,
+        )
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.preview.to_child_mut()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        message::Message,
+        test_util::{TestTerminal, terminal},
+        view::{
+            ViewContext,
+            test_util::{TestComponent, TestHarness},
+        },
+    };
+    use indexmap::indexmap;
+    use ratatui::text::Span;
+    use rstest::rstest;
+    use slumber_core::{
+        collection::{Collection, Profile, ProfileId},
+        render::TemplateContext,
+        test_util::by_id,
+    };
+    use slumber_util::{Factory, assert_matches, yaml::SourceLocation};
+    use std::sync::Arc;
+
+    /// Folders are shown as a tree, with a rendered URL for each recipe
+    #[rstest]
+    #[tokio::test]
+    async fn test_folder_tree(#[with(39, 10)] terminal: TestTerminal) {
+        let folder = Folder {
+            id: "1f".into(),
+            location: SourceLocation::default(),
+            name: None,
+            authentication: None,
+            children: by_id([
+                RecipeNode::Recipe(Recipe {
+                    url: "{{ host }}/1".into(),
+                    ..Recipe::factory("1.1r")
+                }),
+                RecipeNode::Recipe(Recipe::factory("1.2r")),
+                // Nested folder
+                RecipeNode::Folder(Folder {
+                    id: "1.3f".into(),
+                    location: SourceLocation::default(),
+                    name: None,
+                    authentication: None,
+                    children: by_id([RecipeNode::Recipe(Recipe::factory(
+                        "1.3.1r",
+                    ))]),
+                }),
+                // Empty folder
+                RecipeNode::Folder(Folder {
+                    id: "1.4f".into(),
+                    location: SourceLocation::default(),
+                    name: None,
+                    authentication: None,
+                    children: Default::default(),
+                }),
+                // End with a nested folder to make sure the leftmost
+                // decorations don't appear
+                RecipeNode::Folder(Folder {
+                    id: "1.5f".into(),
+                    location: SourceLocation::default(),
+                    name: None,
+                    authentication: None,
+                    children: by_id([
+                        RecipeNode::Recipe(Recipe::factory("1.5.1r")),
+                        RecipeNode::Folder(Folder {
+                            id: "1.5.2f".into(),
+                            location: SourceLocation::default(),
+                            name: None,
+                            authentication: None,
+                            children: by_id([RecipeNode::Recipe(
+                                Recipe::factory("1.5.2.1r"),
+                            )]),
+                        }),
+                    ]),
+                }),
+            ]),
+        };
+        let profile_id = ProfileId::from("profile1");
+        let collection = Collection {
+            profiles: by_id([Profile {
+                id: profile_id.clone(),
+                data: indexmap! { "host".into() => "http://h".into() },
+                ..Profile::factory(())
+            }]),
+            ..Collection::factory(())
+        };
+        let mut harness = TestHarness::new(collection);
+        let context = TemplateContext {
+            collection: Arc::clone(&harness.collection),
+            selected_profile: Some(profile_id),
+            ..TemplateContext::factory(())
+        };
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            FolderDisplay::new(&folder),
+        );
+
+        // Only the dynamic URL sends a preview request. Render it here because
+        // the test harness doesn't handle preview messages
+        let (template, on_complete) = assert_matches!(
+            harness.messages().pop_now(),
+            Message::TemplatePreview { template, on_complete, .. } =>
+                (template, on_complete)
+        );
+        on_complete(template.render(&context.streaming(false)).await);
+        component.int().drain_draw().assert().empty();
+
+        let rendered = ViewContext::styles().template_preview.text;
+        terminal.assert_buffer_lines([
+            "1f                                     ".into(),
+            Line::from(vec![
+                "├─1.1r         GET ".into(),
+                Span::styled("http://h", rendered),
+                "/1          ".into(),
+            ]),
+            "├─1.2r         GET http://localhost/url".into(),
+            "├─1.3f                                 ".into(),
+            "│ └─1.3.1r     GET http://localhost/url".into(),
+            "├─1.4f                                 ".into(),
+            "└─1.5f                                 ".into(),
+            "  ├─1.5.1r     GET http://localhost/url".into(),
+            "  └─1.5.2f                             ".into(),
+            "    └─1.5.2.1r GET http://localhost/url".into(),
+        ]);
+    }
+}
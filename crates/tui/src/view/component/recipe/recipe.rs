@@ -1,5 +1,5 @@
 use crate::view::{
-    Component,
+    Component, ViewContext,
     common::{fixed_select::FixedSelect, tabs::Tabs},
     component::{
         Canvas, ComponentId, Draw, DrawMetadata,
@@ -11,14 +11,15 @@ use crate::view::{
             url::UrlDisplay,
         },
     },
-    persistent::PersistentKey,
+    persistent::{PersistentKey, PersistentStore},
 };
 use derive_more::Display;
 use ratatui::{layout::Layout, prelude::Constraint, widgets::Paragraph};
 use serde::{Deserialize, Serialize};
+use slumber_config::RecipeTab;
 use slumber_core::{
-    collection::Recipe,
-    http::{BuildOptions, HttpMethod},
+    collection::{HttpMethod, Recipe},
+    http::BuildOptions,
 };
 use std::iter;
 use strum::{EnumCount, EnumIter};
@@ -42,15 +43,14 @@ impl RecipeDisplay {
     /// Initialize new recipe state. Should be called whenever the recipe or
     /// profile changes
     pub fn new(recipe: &Recipe) -> Self {
+        // Authentication may be inherited from a parent folder
+        let collection = ViewContext::collection();
+        let authentication = collection.recipes.authentication(&recipe.id);
+
         // Disable tabs that have no content
         let disabled_tabs = iter::empty()
             .chain(recipe.body.is_none().then_some(Tab::Body))
-            .chain(
-                recipe
-                    .authentication
-                    .is_none()
-                    .then_some(Tab::Authentication),
-            );
+            .chain(authentication.is_none().then_some(Tab::Authentication));
         let tabs = Tabs::new(
             RecipeTabKey,
             FixedSelect::builder().disabled(disabled_tabs),
@@ -83,14 +83,13 @@ impl RecipeDisplay {
                 .as_ref()
                 .map(|body| RecipeBodyDisplay::new(body, recipe)),
             // Map authentication type
-            authentication: recipe.authentication.as_ref().map(
-                |authentication| {
-                    AuthenticationDisplay::new(
-                        recipe.id.clone(),
-                        authentication.clone(),
-                    )
-                },
-            ),
+            authentication: authentication.map(|resolved| {
+                AuthenticationDisplay::new(
+                    recipe.id.clone(),
+                    resolved.authentication.clone(),
+                    resolved.inherited_from,
+                )
+            }),
         }
     }
 
@@ -104,7 +103,9 @@ impl RecipeDisplay {
             .body
             .as_ref()
             .and_then(|body| match body {
-                RecipeBodyDisplay::Raw(_) | RecipeBodyDisplay::Json(_) => None,
+                RecipeBodyDisplay::Raw(_)
+                | RecipeBodyDisplay::Json(_)
+                | RecipeBodyDisplay::Graphql(_) => None,
                 RecipeBodyDisplay::Form(form) => {
                     Some(form.to_build_overrides())
                 }
@@ -114,6 +115,10 @@ impl RecipeDisplay {
             .body
             .as_ref()
             .and_then(RecipeBodyDisplay::override_value);
+        let graphql_variables = self
+            .body
+            .as_ref()
+            .and_then(RecipeBodyDisplay::graphql_variables_override_value);
 
         BuildOptions {
             url,
@@ -122,8 +127,18 @@ impl RecipeDisplay {
             query_parameters: self.query.to_build_overrides(),
             form_fields,
             body,
+            graphql_variables,
+            graphql_introspection: false,
+            link: None,
+            websocket: false,
         }
     }
+
+    /// Persist a tab selection, so it's selected the next time a recipe is
+    /// built
+    pub fn select_tab(store: &mut PersistentStore, tab: RecipeTab) {
+        store.set(&RecipeTabKey, &tab.into());
+    }
 }
 
 impl Component for RecipeDisplay {
@@ -234,6 +249,18 @@ enum Tab {
     Authentication,
 }
 
+impl From<RecipeTab> for Tab {
+    fn from(tab: RecipeTab) -> Self {
+        match tab {
+            RecipeTab::Url => Self::Url,
+            RecipeTab::Body => Self::Body,
+            RecipeTab::Query => Self::Query,
+            RecipeTab::Headers => Self::Headers,
+            RecipeTab::Authentication => Self::Authentication,
+        }
+    }
+}
+
 /// [RecipeTableKind] for the query parameter table
 #[derive(Debug)]
 struct QueryTableKind;
@@ -59,7 +59,7 @@ impl Draw for UrlDisplay {
 
 /// Persistent key for URL override template
 #[derive(Clone, Debug, PartialEq)]
-struct UrlKey(RecipeId);
+pub(super) struct UrlKey(pub(super) RecipeId);
 
 impl SessionKey for UrlKey {
     type Value = Template;
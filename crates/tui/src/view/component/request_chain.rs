@@ -0,0 +1,142 @@
+//! Modal showing the live state of a request and every upstream request its
+//! chains triggered
+
+use crate::{
+    http::{RequestStateSummary, RequestStore},
+    view::{
+        Generate, ViewContext,
+        common::modal::Modal,
+        component::{
+            Canvas, Component, ComponentId, Draw, DrawMetadata,
+            history::state_description,
+        },
+    },
+};
+use ratatui::{
+    layout::Constraint,
+    text::{Line, Text},
+};
+use slumber_core::http::RequestId;
+use slumber_util::tr;
+
+/// Tree of the requests in a request chain. The root is the request the user
+/// sent; each request below it was triggered while building its parent. This
+/// is rebuilt from the request store whenever any request changes, so it
+/// reflects the state of the chain as it executes.
+#[derive(Debug)]
+pub struct RequestChain {
+    id: ComponentId,
+    root_id: RequestId,
+    items: Vec<ChainItem>,
+}
+
+impl RequestChain {
+    /// Max number of requests to show at once
+    const MAX_HEIGHT: u16 = 20;
+
+    pub fn new(root_id: RequestId, store: &RequestStore) -> Self {
+        let mut chain = Self {
+            id: ComponentId::default(),
+            root_id,
+            items: Vec::new(),
+        };
+        chain.refresh(store);
+        chain
+    }
+
+    /// Rebuild the tree from the latest state of each request
+    pub fn refresh(&mut self, store: &RequestStore) {
+        let collection = ViewContext::collection();
+        self.items = store
+            .chain(self.root_id)
+            .into_iter()
+            .map(|(depth, state)| {
+                let recipe_id = state.recipe_id();
+                let recipe_name =
+                    collection.recipes.get_recipe(recipe_id).map_or_else(
+                        || recipe_id.to_string(),
+                        |recipe| recipe.name().to_owned(),
+                    );
+                ChainItem {
+                    depth,
+                    recipe_name,
+                    summary: state.into(),
+                }
+            })
+            .collect();
+    }
+
+    /// Has every request in the chain finished, successfully or not?
+    pub fn is_complete(&self) -> bool {
+        self.items.iter().all(|item| {
+            !matches!(
+                item.summary,
+                RequestStateSummary::Building { .. }
+                    | RequestStateSummary::Loading { .. }
+            )
+        })
+    }
+
+    /// Did any request in the chain fail?
+    pub fn has_error(&self) -> bool {
+        self.items.iter().any(|item| {
+            matches!(
+                item.summary,
+                RequestStateSummary::BuildError { .. }
+                    | RequestStateSummary::Cancelled { .. }
+                    | RequestStateSummary::RequestError { .. }
+            )
+        })
+    }
+}
+
+impl Component for RequestChain {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+}
+
+impl Draw for RequestChain {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        let lines: Vec<Line> = self
+            .items
+            .iter()
+            .map(|item| {
+                let indent = if item.depth == 0 {
+                    String::new()
+                } else {
+                    format!("{}└ ", "  ".repeat(item.depth - 1))
+                };
+                Line::from_iter([
+                    indent.into(),
+                    item.recipe_name.as_str().into(),
+                    "  ".into(),
+                    state_description(&item.summary),
+                    " / ".into(),
+                    item.summary.duration().generate(),
+                ])
+            })
+            .collect();
+        canvas.render_widget(Text::from(lines), metadata.area());
+    }
+}
+
+impl Modal for RequestChain {
+    fn title(&self) -> Line<'_> {
+        tr!("modal-request-chain-title").into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.items.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (Constraint::Percentage(60), Constraint::Length(height))
+    }
+}
+
+/// One request in the chain
+#[derive(Debug)]
+struct ChainItem {
+    /// Number of requests between this one and the root
+    depth: usize,
+    recipe_name: String,
+    summary: RequestStateSummary,
+}
@@ -1,7 +1,7 @@
 use crate::{
     message::Message,
     view::{
-        Component, ViewContext,
+        Component, Generate, ViewContext,
         common::{
             header_table::HeaderTable,
             text_window::{TextWindow, TextWindowProps},
@@ -12,15 +12,17 @@ use crate::{
         },
         context::UpdateContext,
         event::{Event, EventMatch},
-        util::{format_byte_size, highlight, view_text},
+        util::{highlight, view_text},
     },
 };
-use ratatui::{layout::Layout, prelude::Constraint, text::Text};
-use slumber_config::Action;
-use slumber_core::{
-    http::{RequestRecord, content_type::ContentType},
-    util::MaybeStr,
+use ratatui::{
+    layout::Layout,
+    prelude::Constraint,
+    text::{Line, Text},
 };
+use slumber_config::{Action, EnvironmentBadge};
+use slumber_core::{http::RequestRecord, util::MaybeStr};
+use slumber_util::format_byte_size;
 use std::sync::Arc;
 
 /// Display rendered HTTP request state. The request could still be in flight,
@@ -30,6 +32,19 @@ pub struct RequestView {
     id: ComponentId,
     /// Store pointer to the request, so we can access it in the update step
     request: Arc<RequestRecord>,
+    /// `<method> <HTTP version>`, computed once so we don't have to allocate
+    /// on every draw
+    version: String,
+    /// Stringified URL, computed once so we don't have to allocate on every
+    /// draw
+    url: String,
+    /// Badge for the environment the request's host belongs to, as defined
+    /// by the `environment_badges` config field
+    environment_badge: Option<EnvironmentBadge>,
+    headers: HeaderTable,
+    /// Headers added implicitly by the HTTP client. These are shown
+    /// separately so it's clear they weren't defined in the recipe
+    generated_headers: HeaderTable,
     /// Body display. `None` if the request has no body
     body_text_window: Option<TextWindow>,
 }
@@ -39,19 +54,34 @@ impl RequestView {
         let text = init_body(&request);
         Self {
             id: ComponentId::default(),
+            version: format!("{} {}", request.method, request.http_version),
+            url: request.url.to_string(),
+            environment_badge: request.url.host_str().and_then(|host| {
+                ViewContext::config()
+                    .tui
+                    .environment_badges
+                    .get(host)
+                    .cloned()
+            }),
+            headers: HeaderTable::new(&request.headers),
+            generated_headers: HeaderTable::new(&request.generated_headers())
+                .title("Generated Headers")
+                .style(ViewContext::styles().text.hint),
             request,
             body_text_window: text.map(TextWindow::new),
         }
     }
 
+    pub fn environment_badge(&self) -> Option<&EnvironmentBadge> {
+        self.environment_badge.as_ref()
+    }
+
     pub fn has_body(&self) -> bool {
         self.body_text_window.is_some()
     }
 
     pub fn copy_url(&self) {
-        ViewContext::send_message(Message::CopyText(
-            self.request.url.to_string(),
-        ));
+        ViewContext::send_message(Message::CopyText(self.url.clone()));
     }
 
     pub fn view_body(&self) {
@@ -60,6 +90,13 @@ impl RequestView {
         }
     }
 
+    /// Compare this request to what the recipe would build now
+    pub fn diff_current(&self) {
+        ViewContext::send_message(Message::RequestDiff(Arc::clone(
+            &self.request,
+        )));
+    }
+
     pub fn copy_body(&self) {
         // Copy exactly what the user sees. Currently requests don't support
         // formatting/querying but that could change
@@ -89,29 +126,43 @@ impl Component for RequestView {
 
 impl Draw for RequestView {
     fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
-        let request = &self.request;
-        let [version_area, url_area, headers_area, body_area] =
-            Layout::vertical([
-                Constraint::Length(1),
-                Constraint::Length(2),
-                Constraint::Length(request.headers.len() as u16 + 2),
-                Constraint::Min(0),
-            ])
-            .areas(metadata.area());
+        // Generated headers get a title row, in addition to the header row and
+        // trailing spacer that both tables get
+        let generated_headers_height = if self.generated_headers.is_empty() {
+            0
+        } else {
+            self.generated_headers.len() as u16 + 3
+        };
+        let [
+            version_area,
+            url_area,
+            headers_area,
+            generated_headers_area,
+            body_area,
+        ] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(2),
+            Constraint::Length(self.headers.len() as u16 + 2),
+            Constraint::Length(generated_headers_height),
+            Constraint::Min(0),
+        ])
+        .areas(metadata.area());
 
         // This can get cut off which is jank but there isn't a good fix. User
         // can copy the URL to see the full thing
-        canvas.render_widget(
-            format!("{} {}", request.method, request.http_version),
-            version_area,
-        );
-        canvas.render_widget(request.url.to_string(), url_area);
-        canvas.render_widget(
-            HeaderTable {
-                headers: &request.headers,
-            },
-            headers_area,
-        );
+        canvas.render_widget(self.version.as_str(), version_area);
+        if let Some(badge) = &self.environment_badge {
+            let line = Line::from_iter([
+                badge.generate(),
+                " ".into(),
+                self.url.as_str().into(),
+            ]);
+            canvas.render_widget(line, url_area);
+        } else {
+            canvas.render_widget(self.url.as_str(), url_area);
+        }
+        canvas.render_widget(&self.headers, headers_area);
+        canvas.render_widget(&self.generated_headers, generated_headers_area);
         if let Some(text_window) = &self.body_text_window {
             canvas.draw(
                 text_window,
@@ -126,7 +177,7 @@ impl Draw for RequestView {
 /// Calculate body text, including syntax highlighting. We have to clone the
 /// body to prevent a self-reference. Return `None` if the request has no body
 fn init_body(request: &RequestRecord) -> Option<Text<'static>> {
-    let content_type = ContentType::from_headers(&request.headers).ok();
+    let content_type = request.content_type();
     request
         .body()
         .map(|body| {
@@ -155,3 +206,66 @@ fn init_body(request: &RequestRecord) -> Option<Text<'static>> {
             }
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_util::{TestTerminal, terminal},
+        view::test_util::{TestComponent, TestHarness},
+    };
+    use ratatui::{
+        style::{Color, Modifier, Style},
+        text::Span,
+    };
+    use rstest::rstest;
+    use slumber_config::{Config, EnvironmentBadges};
+    use slumber_core::{collection::Collection, http::RequestId};
+    use slumber_util::Factory;
+
+    /// If the request's host matches a configured pattern, its badge is shown
+    /// next to the URL
+    #[rstest]
+    fn test_environment_badge(#[with(30, 3)] terminal: TestTerminal) {
+        let badge = EnvironmentBadge {
+            label: "LOCAL".into(),
+            color: Color::Green,
+        };
+        let mut config = Config::default();
+        config.tui.environment_badges =
+            EnvironmentBadges::from_iter([("local*".parse().unwrap(), badge)]);
+        let harness = TestHarness::with_config(Collection::factory(()), config);
+        let request = RequestRecord::factory(RequestId::new());
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            RequestView::new(request.into()),
+        );
+
+        assert_eq!(
+            component
+                .environment_badge()
+                .map(|badge| badge.label.as_str()),
+            Some("LOCAL")
+        );
+        terminal.assert_buffer_lines([
+            "GET HTTP/1.1                  ".into(),
+            Line::from(vec![
+                Span::styled(
+                    " LOCAL ",
+                    Style::default()
+                        .bg(Color::Green)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                " http://localhost/url  ".into(),
+            ]),
+            Line::from(vec![
+                Span::styled(
+                    "Generated Headers",
+                    ViewContext::styles().text.title,
+                ),
+                "             ".into(),
+            ]),
+        ]);
+    }
+}
@@ -0,0 +1,85 @@
+//! Modal to browse and follow links found in a response
+
+use crate::{
+    message::HttpMessage,
+    view::{
+        ToStringGenerate, UpdateContext, ViewContext,
+        common::{
+            modal::Modal,
+            select::{Select, SelectListProps},
+        },
+        component::{
+            Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+        },
+    },
+};
+use derive_more::Display;
+use ratatui::{layout::Constraint, text::Line};
+use slumber_core::http::ResponseLink;
+use slumber_util::tr;
+
+/// Modal listing URLs from a response's `Location`/`Link` headers and body.
+/// Submitting sends a `GET` to the selected URL, using the authentication of
+/// the current recipe.
+#[derive(Debug)]
+pub struct ResponseLinks {
+    id: ComponentId,
+    select: Select<ResponseLinkItem>,
+}
+
+impl ResponseLinks {
+    /// Max number of links to show at once
+    const MAX_HEIGHT: u16 = 20;
+
+    pub fn new(links: Vec<ResponseLink>) -> Self {
+        let items = links.into_iter().map(ResponseLinkItem).collect();
+        Self {
+            id: ComponentId::default(),
+            select: Select::builder(items).build(),
+        }
+    }
+}
+
+impl Component for ResponseLinks {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for ResponseLinks {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        canvas.draw(
+            &self.select,
+            SelectListProps::modal(),
+            metadata.area(),
+            true,
+        );
+    }
+}
+
+impl Modal for ResponseLinks {
+    fn title(&self) -> Line<'_> {
+        tr!("modal-follow-link-title").into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (Constraint::Percentage(80), Constraint::Length(height))
+    }
+
+    fn on_submit(self, _: &mut UpdateContext) {
+        if let Some(ResponseLinkItem(link)) = self.select.into_selected() {
+            ViewContext::send_message(HttpMessage::FollowLink(link.url));
+        }
+    }
+}
+
+#[derive(Debug, Display)]
+#[display("{:<14}  {}", _0.source, _0.url)]
+struct ResponseLinkItem(ResponseLink);
+
+impl ToStringGenerate for ResponseLinkItem {}
@@ -1,30 +1,43 @@
 //! Display for HTTP responses
 
 use crate::{
+    http::{ReceivedEvent, StreamingBody},
     message::Message,
     view::{
         Component, ViewContext,
-        common::header_table::HeaderTable,
+        common::{actions::MenuItem, header_table::HeaderTable, table::Table},
         component::{
-            Canvas, ComponentId, Draw, DrawMetadata, ToChild, internal::Child,
-            queryable_body::QueryableBody,
+            Canvas, ComponentId, Draw, DrawMetadata, ToChild,
+            internal::Child,
+            queryable_body::{BodyDisplay, QueryableBody},
         },
         context::UpdateContext,
-        event::{Event, EventMatch},
-        persistent::PersistentKey,
-        util::view_text,
+        event::{Emitter, Event, EventMatch},
+        persistent::{PersistentKey, PersistentStore},
+        util::{markup::MarkupKind, view_text},
     },
 };
+use chrono::Local;
 use mime::Mime;
+use ratatui::{
+    layout::{Constraint, Layout},
+    text::{Line, Span, Text},
+    widgets::LineGauge,
+};
 use serde::{Serialize, Serializer};
 use slumber_config::Action;
-use slumber_core::{collection::RecipeId, http::ResponseRecord};
-use std::sync::Arc;
+use slumber_core::{
+    collection::RecipeId,
+    http::{ResponseRecord, content_type::ContentType},
+};
+use slumber_util::{format_byte_size, tr};
+use std::{cell::Cell, sync::Arc};
 
 /// Display response body
 #[derive(Debug)]
 pub struct ResponseBodyView {
     id: ComponentId,
+    recipe_id: RecipeId,
     response: Arc<ResponseRecord>,
     /// The presentable version of the response body, which may or may not
     /// match the response body. We apply transformations such as filter,
@@ -33,25 +46,74 @@ pub struct ResponseBodyView {
 }
 
 impl ResponseBodyView {
-    pub fn new(recipe_id: RecipeId, response: Arc<ResponseRecord>) -> Self {
+    /// If `previous_response` is given, lines that changed since that response
+    /// are marked in the gutter
+    pub fn new(
+        recipe_id: RecipeId,
+        response: Arc<ResponseRecord>,
+        previous_response: Option<Arc<ResponseRecord>>,
+    ) -> Self {
         // Select default query based on content type
         let config = &ViewContext::config().tui.commands;
         let mime = response.mime();
         let default_query = mime
             .as_ref()
             .and_then(|mime| config.default_query.get(mime).cloned());
+        let renderer = mime
+            .as_ref()
+            .and_then(|mime| config.renderers.get(mime).cloned());
+        // Each recipe remembers how its body was last displayed
+        let display = PersistentStore::get(&ResponseDisplayKey {
+            recipe_id: recipe_id.clone(),
+        })
+        .unwrap_or_default();
         let body = QueryableBody::new(
-            ResponseQueryKey { recipe_id, mime },
+            ResponseQueryKey {
+                recipe_id: recipe_id.clone(),
+                mime,
+            },
             Arc::clone(&response),
+            previous_response,
             default_query,
-        );
+            display,
+        )
+        .with_renderer(renderer);
         Self {
             id: ComponentId::default(),
+            recipe_id,
             response,
             body,
         }
     }
 
+    /// Is the body shown as received, without prettification?
+    pub fn is_raw(&self) -> bool {
+        self.body.display().raw
+    }
+
+    /// Switch between the raw and prettified body
+    pub fn toggle_raw(&mut self) {
+        let display = self.body.display();
+        self.body.set_display(BodyDisplay {
+            raw: !display.raw,
+            ..display
+        });
+    }
+
+    /// Are long lines soft-wrapped?
+    pub fn is_wrapped(&self) -> bool {
+        self.body.display().wrap
+    }
+
+    /// Enable/disable soft-wrapping of long lines
+    pub fn toggle_wrap(&mut self) {
+        let display = self.body.display();
+        self.body.set_display(BodyDisplay {
+            wrap: !display.wrap,
+            ..display
+        });
+    }
+
     /// Open the visible body in the pager
     pub fn view_body(&self) {
         view_text(self.body.visible_text(), self.response.mime());
@@ -66,6 +128,32 @@ impl ResponseBodyView {
         ));
     }
 
+    /// Can the body be queried with XPath/CSS selectors?
+    pub fn is_markup(&self) -> bool {
+        self.response
+            .mime()
+            .is_some_and(|mime| MarkupKind::from_mime(&mime).is_some())
+    }
+
+    /// Open a modal to query the body's XML/HTML nodes
+    pub fn query_nodes(&self) {
+        // Primary view owns the modal, since it needs the whole screen
+        ViewContext::push_event(Event::QueryNodes(Arc::clone(&self.response)));
+    }
+
+    /// Is the body JSON, so it can be browsed by JSONPath?
+    pub fn is_json(&self) -> bool {
+        matches!(self.response.content_type(), Some(ContentType::Json))
+    }
+
+    /// Open a modal to copy a JSONPath selector for a node in the body
+    pub fn pick_json_path(&self) {
+        ViewContext::push_event(Event::PickJsonPath {
+            recipe_id: self.recipe_id.clone(),
+            response: Arc::clone(&self.response),
+        });
+    }
+
     pub fn save_response_body(&self) {
         // This will trigger a modal to ask the user for a path
         ViewContext::send_message(Message::SaveResponseBody {
@@ -87,6 +175,15 @@ impl Component for ResponseBodyView {
         })
     }
 
+    fn persist(&self, store: &mut PersistentStore) {
+        store.set(
+            &ResponseDisplayKey {
+                recipe_id: self.recipe_id.clone(),
+            },
+            &self.body.display(),
+        );
+    }
+
     fn children(&mut self) -> Vec<Child<'_>> {
         vec![self.body.to_child_mut()]
     }
@@ -98,6 +195,266 @@ impl Draw for ResponseBodyView {
     }
 }
 
+/// Display a response body while it's still being received. By default this
+/// follows the end of the body as it grows, like `tail -f`. Scrolling up pauses
+/// follow mode, and scrolling back to the bottom resumes it.
+///
+/// Server-Sent Events streams are shown as a list of events, each with the
+/// time it was received. The stream can be stopped from the actions menu,
+/// which completes the request with the events received so far.
+#[derive(Debug)]
+pub struct StreamingBodyView {
+    id: ComponentId,
+    actions_emitter: Emitter<StreamingBodyMenuAction>,
+    body: StreamingBody,
+    /// Pin the view to the end of the body?
+    follow: bool,
+    /// Index of the first visible line. Only used while paused; in follow
+    /// mode, the view is pinned to the bottom.
+    offset: usize,
+    /// Height of the text area from the last draw. Needed to know where the
+    /// bottom is when scrolling.
+    height: Cell<usize>,
+}
+
+impl StreamingBodyView {
+    pub fn new(body: StreamingBody) -> Self {
+        Self {
+            id: ComponentId::default(),
+            actions_emitter: Emitter::default(),
+            body,
+            follow: true,
+            offset: 0,
+            height: Cell::default(),
+        }
+    }
+
+    /// Toggle follow mode on/off
+    fn toggle_follow(&mut self) {
+        if self.follow {
+            // Pause where we are so the view doesn't jump
+            self.offset = self.max_offset();
+            self.follow = false;
+        } else {
+            self.follow = true;
+        }
+    }
+
+    /// Scroll up. This always pauses follow mode
+    fn scroll_up(&mut self, lines: usize) {
+        if self.follow {
+            self.offset = self.max_offset();
+            self.follow = false;
+        }
+        self.offset = self.offset.saturating_sub(lines);
+    }
+
+    /// Scroll down. Hitting the bottom resumes follow mode
+    fn scroll_down(&mut self, lines: usize) {
+        if self.follow {
+            return;
+        }
+        let max_offset = self.max_offset();
+        self.offset = (self.offset + lines).min(max_offset);
+        if self.offset >= max_offset {
+            self.follow = true;
+        }
+    }
+
+    /// Highest line offset that still fills the view
+    fn max_offset(&self) -> usize {
+        let lines = self.body.lock().line_count();
+        lines.saturating_sub(self.height.get())
+    }
+}
+
+impl Component for StreamingBodyView {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event
+            .m()
+            .action(|action, propagate| match action {
+                Action::Up | Action::ScrollUp => self.scroll_up(1),
+                Action::Down | Action::ScrollDown => self.scroll_down(1),
+                Action::PageUp => self.scroll_up(self.height.get()),
+                Action::PageDown => self.scroll_down(self.height.get()),
+                Action::Home => {
+                    self.follow = false;
+                    self.offset = 0;
+                }
+                Action::End => self.follow = true,
+                Action::Toggle => self.toggle_follow(),
+                _ => propagate.set(),
+            })
+            .emitted(self.actions_emitter, |menu_action| match menu_action {
+                StreamingBodyMenuAction::ToggleFollow => self.toggle_follow(),
+                StreamingBodyMenuAction::Stop => self.body.stop(),
+            })
+    }
+
+    fn menu(&self) -> Vec<MenuItem> {
+        let label = if self.follow {
+            tr!("menu-pause-follow")
+        } else {
+            tr!("menu-follow")
+        };
+        vec![
+            self.actions_emitter
+                .menu(StreamingBodyMenuAction::ToggleFollow, label)
+                .shortcut(Some(Action::Toggle))
+                .into(),
+            self.actions_emitter
+                .menu(StreamingBodyMenuAction::Stop, tr!("menu-stop-stream"))
+                // Downloads are cancelled instead, since a partial file isn't
+                // useful
+                .enable(
+                    !self.body.is_stopped()
+                        && self.body.lock().download.is_none(),
+                )
+                .into(),
+        ]
+    }
+}
+
+impl Draw for StreamingBodyView {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        let styles = ViewContext::styles();
+        // A downloaded body is going to a file, so there's nothing to show but
+        // how far along it is
+        if let Some(download) = &self.body.lock().download {
+            let [path_area, progress_area] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .areas(metadata.area());
+            canvas.render_widget(
+                format!("Downloading to {}", download.path.display()),
+                path_area,
+            );
+            let label =
+                Span::styled(download.progress.to_string(), styles.text.hint);
+            match download.progress.ratio() {
+                Some(ratio) => canvas.render_widget(
+                    LineGauge::default().ratio(ratio).label(label),
+                    progress_area,
+                ),
+                None => canvas.render_widget(label, progress_area),
+            }
+            return;
+        }
+
+        let [body_area, status_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)])
+                .areas(metadata.area());
+        let height = body_area.height as usize;
+        self.height.set(height);
+
+        // Only hold the lock long enough to copy out the visible lines
+        let (text, size, count) = {
+            let data = self.body.lock();
+            let lines = if let Some(events) = &data.events {
+                let lines = events.events.iter().flat_map(event_lines);
+                self.visible_lines(lines, height)
+            } else {
+                let lines = data.bytes.split(|&b| b == b'\n').map(|line| {
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    String::from_utf8_lossy(line).into_owned().into()
+                });
+                self.visible_lines(lines, height)
+            };
+            let count = if let Some(events) = &data.events {
+                let count = events.received;
+                format!("{count} event{}", if count == 1 { "" } else { "s" })
+            } else {
+                let chunks = data.chunks;
+                format!("{chunks} chunk{}", if chunks == 1 { "" } else { "s" })
+            };
+            (Text::from(lines), data.received, count)
+        };
+        canvas.render_widget(text, body_area);
+
+        let mode = if self.body.is_stopped() {
+            "Stopping".to_owned()
+        } else if self.follow {
+            ViewContext::add_binding_hint("Following", Action::Toggle)
+        } else {
+            ViewContext::add_binding_hint("Paused", Action::Toggle)
+        };
+        canvas.render_widget(
+            Line::styled(
+                format!(
+                    "{} received / {count} / {mode}",
+                    format_byte_size(size)
+                ),
+                styles.text.hint,
+            ),
+            status_area,
+        );
+    }
+}
+
+impl StreamingBodyView {
+    /// Get the lines that fit in the view, either from the end of the body in
+    /// follow mode or from the scroll offset
+    fn visible_lines(
+        &self,
+        lines: impl DoubleEndedIterator<Item = Line<'static>>,
+        height: usize,
+    ) -> Vec<Line<'static>> {
+        if self.follow {
+            // Walk backward from the end so we don't have to scan the entire
+            // body every frame
+            let mut lines = lines.rev().take(height).collect::<Vec<_>>();
+            lines.reverse();
+            lines
+        } else {
+            lines.skip(self.offset).take(height).collect()
+        }
+    }
+}
+
+/// Display an event as one line per line of data. The first line is prefixed
+/// with the time and event type, and the rest are indented to match.
+fn event_lines(received: &ReceivedEvent) -> Vec<Line<'static>> {
+    let styles = ViewContext::styles();
+    let time = received
+        .time
+        .with_timezone(&Local)
+        .format("%H:%M:%S%.3f")
+        .to_string();
+    let event_type = received.event.event.as_deref().unwrap_or("message");
+    let indent = " ".repeat(time.len() + event_type.chars().count() + 2);
+    received
+        .event
+        .data
+        .split('\n')
+        .enumerate()
+        .map(|(i, data)| {
+            if i == 0 {
+                Line::from_iter([
+                    Span::styled(time.clone(), styles.text.hint),
+                    " ".into(),
+                    Span::styled(event_type.to_owned(), styles.text.primary),
+                    " ".into(),
+                    data.to_owned().into(),
+                ])
+            } else {
+                Line::from(format!("{indent}{data}"))
+            }
+        })
+        .collect()
+}
+
+/// Items in the actions popup menu for a streaming body
+#[derive(Copy, Clone, Debug)]
+enum StreamingBodyMenuAction {
+    ToggleFollow,
+    Stop,
+}
+
 /// Persisted key for response body JSONPath query text box
 #[derive(Debug, Serialize)]
 struct ResponseQueryKey {
@@ -114,6 +471,16 @@ impl PersistentKey for ResponseQueryKey {
     type Value = String;
 }
 
+/// Persisted key for the response body display options of a recipe
+#[derive(Debug, Serialize)]
+struct ResponseDisplayKey {
+    recipe_id: RecipeId,
+}
+
+impl PersistentKey for ResponseDisplayKey {
+    type Value = BodyDisplay;
+}
+
 /// Serialize a MIME type as its string representation
 #[expect(clippy::ref_option)]
 fn serialize_mime<S>(
@@ -129,14 +496,24 @@ where
 #[derive(Debug)]
 pub struct ResponseHeadersView {
     id: ComponentId,
-    response: Arc<ResponseRecord>,
+    /// Redirects followed on the way to the response, as `[status, location]`
+    redirects: Vec<[String; 2]>,
+    headers: HeaderTable,
 }
 
 impl ResponseHeadersView {
     pub fn new(response: Arc<ResponseRecord>) -> Self {
+        let redirects = response
+            .redirects
+            .iter()
+            .map(|redirect| {
+                [redirect.status.to_string(), redirect.location.to_string()]
+            })
+            .collect();
         Self {
             id: ComponentId::default(),
-            response,
+            redirects,
+            headers: HeaderTable::new(&response.headers),
         }
     }
 }
@@ -149,12 +526,36 @@ impl Component for ResponseHeadersView {
 
 impl Draw for ResponseHeadersView {
     fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        // The redirect chain only shows up if there is one, above the headers
+        // of the final response. Title row + header row + spacer
+        let redirects_height = if self.redirects.is_empty() {
+            0
+        } else {
+            self.redirects.len() as u16 + 3
+        };
+        let [redirects_area, headers_area] = Layout::vertical([
+            Constraint::Length(redirects_height),
+            Constraint::Min(0),
+        ])
+        .areas(metadata.area());
+
         canvas.render_widget(
-            HeaderTable {
-                headers: &self.response.headers,
+            Table {
+                title: Some("Redirects"),
+                rows: self
+                    .redirects
+                    .iter()
+                    .map(|[status, location]| {
+                        [status.as_str(), location.as_str()]
+                    })
+                    .collect(),
+                header: Some(["Status", "Location"]),
+                alternate_row_style: true,
+                ..Default::default()
             },
-            metadata.area(),
+            redirects_area,
         );
+        canvas.render_widget(&self.headers, headers_area);
     }
 }
 
@@ -212,6 +613,7 @@ mod tests {
             ResponseBodyView::new(
                 exchange.request.recipe_id.clone(),
                 exchange.response,
+                None,
             ),
         );
 
@@ -278,6 +680,7 @@ mod tests {
             ResponseBodyView::new(
                 exchange.request.recipe_id.clone(),
                 exchange.response,
+                None,
             ),
         );
 
@@ -302,4 +705,197 @@ mod tests {
         assert_eq!(request_id, exchange.id);
         assert_eq!(data.as_deref(), expected_body);
     }
+
+    /// Raw/wrap display options are persisted per recipe, so the next response
+    /// for the same recipe is displayed the same way
+    #[rstest]
+    #[tokio::test]
+    async fn test_persist_display(
+        harness: TestHarness,
+        terminal: TestTerminal,
+    ) {
+        let exchange = Exchange {
+            response: ResponseRecord {
+                headers: header_map(
+                    indexmap! {"content-type" => "application/json"},
+                ),
+                body: br#"{"hello":"world"}"#.as_slice().into(),
+                ..ResponseRecord::factory(())
+            }
+            .into(),
+            ..Exchange::factory(())
+        };
+        let recipe_id = exchange.request.recipe_id.clone();
+        let new_view = || {
+            ResponseBodyView::new(
+                recipe_id.clone(),
+                Arc::clone(&exchange.response),
+                None,
+            )
+        };
+
+        let mut component = TestComponent::new(&harness, &terminal, new_view());
+        assert!(!component.is_raw());
+        component.toggle_raw();
+        component.toggle_wrap();
+        // Raw body is the original, so there's nothing modified to save
+        assert_eq!(component.body.modified_text(), None);
+        assert_eq!(
+            component.body.visible_text().to_string(),
+            r#"{"hello":"world"}"#
+        );
+        component.persist(&mut harness.persistent_store());
+
+        let component = TestComponent::new(&harness, &terminal, new_view());
+        assert!(component.is_raw());
+        assert!(component.is_wrapped());
+
+        // Other recipes are unaffected
+        let component = TestComponent::new(
+            &harness,
+            &terminal,
+            ResponseBodyView::new(
+                RecipeId::factory(()),
+                Arc::clone(&exchange.response),
+                None,
+            ),
+        );
+        assert!(!component.is_raw());
+        assert!(!component.is_wrapped());
+    }
+
+    /// Test follow mode for a streaming body. The view should stick to the
+    /// bottom as data arrives until the user scrolls up
+    #[rstest]
+    fn test_streaming_follow(
+        harness: TestHarness,
+        #[with(46, 3)] terminal: TestTerminal,
+    ) {
+        let body = StreamingBody::default();
+        body.push(b"line 1\nline 2\n");
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            StreamingBodyView::new(body.clone()),
+        );
+        let status = |text: &'static str| {
+            Line::styled(text, ViewContext::styles().text.hint)
+        };
+        terminal.assert_buffer_lines([
+            "line 2".into(),
+            "".into(),
+            status("14 B received / 1 chunk / Following [<space>] "),
+        ]);
+
+        // New data scrolls into view
+        body.push(b"line 3\r\nline 4");
+        component.int().drain_draw().assert().empty();
+        terminal.assert_buffer_lines([
+            "line 3".into(),
+            "line 4".into(),
+            status("28 B received / 2 chunks / Following [<space>]"),
+        ]);
+
+        // Scrolling up pauses
+        component.int().send_key(KeyCode::Up).assert().empty();
+        assert!(!component.follow);
+        body.push(b"\nline 5");
+        component.int().drain_draw().assert().empty();
+        terminal.assert_buffer_lines([
+            "line 2".into(),
+            "line 3".into(),
+            status("35 B received / 3 chunks / Paused [<space>]   "),
+        ]);
+
+        // Scrolling back to the bottom resumes
+        component
+            .int()
+            .send_keys([KeyCode::Down, KeyCode::Down])
+            .assert()
+            .empty();
+        assert!(component.follow);
+        terminal.assert_buffer_lines([
+            "line 4".into(),
+            "line 5".into(),
+            status("35 B received / 3 chunks / Following [<space>]"),
+        ]);
+
+        // Toggle pauses in place
+        component
+            .int()
+            .send_key(KeyCode::Char(' '))
+            .assert()
+            .empty();
+        assert!(!component.follow);
+        assert_eq!(component.offset, 3);
+    }
+
+    /// Event streams are shown one event at a time with the time received.
+    /// Stopping the stream ends the request
+    #[rstest]
+    fn test_streaming_events(
+        harness: TestHarness,
+        #[with(46, 4)] terminal: TestTerminal,
+    ) {
+        let body = StreamingBody::default();
+        body.start_events();
+        body.push(b": comment\n\ndata: hello\n\nevent: update\ndata: a\n");
+        body.push(b"data: b\n\ndata: incomplete");
+        let mut component = TestComponent::new(
+            &harness,
+            &terminal,
+            StreamingBodyView::new(body.clone()),
+        );
+
+        let styles = ViewContext::styles();
+        let times = body
+            .lock()
+            .events
+            .as_ref()
+            .unwrap()
+            .events
+            .iter()
+            .map(|event| {
+                event.time.with_timezone(&Local).format("%H:%M:%S%.3f")
+            })
+            .map(|time| Span::styled(time.to_string(), styles.text.hint))
+            .collect::<Vec<_>>();
+        let line = |time: &Span<'static>, event_type, data| {
+            Line::from_iter([
+                time.clone(),
+                " ".into(),
+                Span::styled(event_type, styles.text.primary),
+                " ".into(),
+                Span::raw(data),
+            ])
+        };
+        terminal.assert_buffer_lines([
+            line(&times[0], "message", "hello"),
+            line(&times[1], "update", "a"),
+            "                    b".into(),
+            Line::styled(
+                "71 B received / 2 events / Following [<space>]",
+                styles.text.hint,
+            ),
+        ]);
+
+        component.int().action(&["Stop Stream"]).assert().empty();
+        assert!(body.is_stopped());
+    }
+
+    /// A download shows where the body is going and how much has been
+    /// written, instead of the body itself
+    #[rstest]
+    fn test_streaming_download(
+        harness: TestHarness,
+        #[with(22, 2)] terminal: TestTerminal,
+    ) {
+        let body = StreamingBody::download("out.bin".into());
+        body.lock().download.as_mut().unwrap().progress.received = 1500;
+        TestComponent::new(&harness, &terminal, StreamingBodyView::new(body));
+        terminal.assert_buffer_lines([
+            "Downloading to out.bin".into(),
+            Line::styled("1.5 KB", ViewContext::styles().text.hint),
+        ]);
+    }
 }
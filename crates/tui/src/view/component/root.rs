@@ -7,25 +7,28 @@ use crate::{
         common::{actions::ActionMenu, modal::ModalQueue},
         component::{
             Canvas, Child, ComponentId, Draw, DrawMetadata, ToChild,
-            footer::Footer,
+            footer::{Footer, FooterProps},
             internal::ComponentExt,
             misc::{ErrorModal, QuestionModal},
             primary::PrimaryView,
         },
         context::UpdateContext,
         event::{DeleteTarget, Event, EventMatch},
+        persistent::PersistentStore,
     },
 };
 use indexmap::IndexMap;
 use ratatui::{layout::Layout, prelude::Constraint, text::Text};
-use slumber_config::Action;
+use reqwest::Url;
+use slumber_config::{Action, StartupConfig};
 use slumber_core::{
     collection::{
-        Collection, CollectionError, CollectionFile, HasId, Profile, ProfileId,
+        Collection, CollectionCommit, CollectionError, CollectionFile, HasId,
+        Profile, ProfileId, RecipeId, RemoteStatus, ToggleId,
     },
     database::ProfileFilter,
 };
-use slumber_template::Template;
+use slumber_template::{Template, Value};
 use std::{error::Error as StdError, sync::Arc};
 use tracing::warn;
 
@@ -47,6 +50,14 @@ pub struct Root {
 }
 
 impl Root {
+    /// See [PrimaryView::apply_startup_config]
+    pub fn apply_startup_config(
+        config: &StartupConfig,
+        store: &mut PersistentStore,
+    ) {
+        PrimaryView::apply_startup_config(config, store);
+    }
+
     pub fn new(
         collection_result: Result<Arc<Collection>, InvalidCollection>,
     ) -> Self {
@@ -66,6 +77,43 @@ impl Root {
         }
     }
 
+    /// Show values captured from responses in a modal
+    pub fn captures(&mut self, captures: Vec<(String, Value)>) {
+        match &mut self.primary {
+            Ok(primary) => primary.captures(captures),
+            Err(_) => {}
+        }
+    }
+
+    /// Show the git history of the collection file in a modal
+    pub fn changelog(&mut self, commits: Vec<CollectionCommit>) {
+        match &mut self.primary {
+            Ok(primary) => primary.changelog(commits),
+            Err(_) => {}
+        }
+    }
+
+    /// Hold a request that failed while offline, to send again once the
+    /// network is back
+    pub fn queue_offline(&mut self, config: RequestConfig, url: Url) {
+        match &mut self.primary {
+            Ok(primary) => primary.queue_offline(config, url),
+            Err(_) => {}
+        }
+    }
+
+    /// Select a recipe and/or profile, as requested by an external process
+    pub fn open(
+        &mut self,
+        recipe_id: Option<&RecipeId>,
+        profile_id: Option<&ProfileId>,
+    ) {
+        match &mut self.primary {
+            Ok(primary) => primary.open(recipe_id, profile_id),
+            Err(_) => {}
+        }
+    }
+
     /// Ask the user a yes/no question
     pub fn question(&mut self, question: Question) {
         self.questions.open(QuestionModal::from_question(question));
@@ -81,6 +129,11 @@ impl Root {
         self.footer.notify(message);
     }
 
+    /// See [Footer::set_remote]
+    pub fn set_remote(&mut self, remote: Option<RemoteStatus>) {
+        self.footer.set_remote(remote);
+    }
+
     /// ID of the selected profile. `None` iff the list is empty
     pub fn selected_profile_id(&self) -> Option<&ProfileId> {
         match &self.primary {
@@ -89,6 +142,15 @@ impl Root {
         }
     }
 
+    /// ID of the selected recipe. `None` iff the list is empty OR a folder is
+    /// selected
+    pub fn selected_recipe_id(&self) -> Option<&RecipeId> {
+        match &self.primary {
+            Ok(primary) => primary.selected_recipe_id(),
+            Err(_) => None,
+        }
+    }
+
     /// Get a definition of the request that should be sent from the current
     /// recipe settings
     pub fn request_config(&self) -> Option<RequestConfig> {
@@ -106,6 +168,14 @@ impl Root {
         }
     }
 
+    /// IDs of the toggles that are switched on
+    pub fn enabled_toggles(&self) -> Vec<ToggleId> {
+        match &self.primary {
+            Ok(primary) => primary.enabled_toggles(),
+            Err(_) => Vec::new(),
+        }
+    }
+
     /// Update the UI to reflect the current state of an HTTP request
     pub fn refresh_request(
         &mut self,
@@ -263,6 +333,15 @@ impl Component for Root {
                     None
                 }
 
+                // The primary view handles these. If it's not present (e.g.
+                // the collection failed to load), there's nothing to navigate
+                // to
+                Event::GoToDefinition(_)
+                | Event::GoToFolder(_)
+                | Event::OpenLinks(_)
+                | Event::QueryNodes(_)
+                | Event::PickJsonPath { .. } => None,
+
                 // Ignore any emitted events that made it this far. It's
                 // possible this event is indicative of a bug, but it's also
                 // possible that it's been emitted by a component that has
@@ -312,7 +391,17 @@ impl Draw for Root {
         }
 
         // Footer
-        canvas.draw(&self.footer, (), footer_area, true);
+        let environment_badge = self
+            .primary
+            .as_ref()
+            .ok()
+            .and_then(PrimaryView::environment_badge);
+        canvas.draw(
+            &self.footer,
+            FooterProps { environment_badge },
+            footer_area,
+            true,
+        );
 
         // Draw modals/popups. These are all given the full screen area because
         // they want to capture all cursor events
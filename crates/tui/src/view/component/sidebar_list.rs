@@ -79,6 +79,12 @@ impl<State: SidebarListState> SidebarList<State> {
         self.selected().map(State::Item::id)
     }
 
+    /// Select an item by ID. Does nothing if the item isn't in the list (e.g.
+    /// it's been filtered out)
+    pub fn select_id(&mut self, id: &<State::Item as SidebarListItem>::Id) {
+        self.select.select(id);
+    }
+
     /// Get the inner state value
     pub fn state(&self) -> &State {
         &self.state
@@ -0,0 +1,166 @@
+//! Modal for enabling/disabling profile toggles
+
+use crate::view::{
+    ToStringGenerate, UpdateContext, ViewContext,
+    common::{
+        modal::Modal,
+        select::{Select, SelectEventKind, SelectListProps},
+    },
+    component::{
+        Canvas, Child, Component, ComponentId, Draw, DrawMetadata, ToChild,
+    },
+    event::{Emitter, Event, EventMatch, ToEmitter},
+    persistent::{PersistentKey, PersistentStore},
+};
+use derive_more::Display;
+use ratatui::{layout::Constraint, text::Line};
+use serde::Serialize;
+use slumber_config::Action;
+use slumber_core::collection::ToggleId;
+use slumber_util::tr;
+
+/// Modal listing every toggle in the collection, each of which can be switched
+/// on and off. Changes apply immediately; the modal doesn't need to be
+/// submitted.
+#[derive(Debug)]
+pub struct ToggleMenu {
+    id: ComponentId,
+    /// Emits the new set of enabled toggles after each change. This belongs to
+    /// the parent, because the modal is rebuilt each time it's opened
+    emitter: Emitter<SetToggles>,
+    select: Select<ToggleMenuItem>,
+}
+
+impl ToggleMenu {
+    /// Max number of toggles to show at once
+    const MAX_HEIGHT: u16 = 10;
+
+    pub fn new(
+        emitter: Emitter<SetToggles>,
+        enabled_toggles: &EnabledToggles,
+    ) -> Self {
+        let collection = ViewContext::collection();
+        let items = collection
+            .toggles
+            .values()
+            .map(|toggle| ToggleMenuItem {
+                id: toggle.id.clone(),
+                name: toggle.name().to_owned(),
+                enabled: enabled_toggles.0.contains(&toggle.id),
+            })
+            .collect();
+        Self {
+            id: ComponentId::default(),
+            emitter,
+            select: Select::builder(items)
+                .subscribe([SelectEventKind::Toggle])
+                .build(),
+        }
+    }
+}
+
+impl Component for ToggleMenu {
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn update(&mut self, _: &mut UpdateContext, event: Event) -> EventMatch {
+        event.m().emitted(self.select.to_emitter(), |event| {
+            if let SelectEventKind::Toggle = event.kind {
+                let item = &mut self.select[event];
+                item.enabled = !item.enabled;
+                // Emit in collection order, so precedence between toggles
+                // doesn't depend on the order they were switched on
+                let enabled = self
+                    .select
+                    .items()
+                    .filter(|item| item.enabled)
+                    .map(|item| item.id.clone())
+                    .collect();
+                self.emitter.emit(SetToggles(EnabledToggles(enabled)));
+            }
+        })
+    }
+
+    fn children(&mut self) -> Vec<Child<'_>> {
+        vec![self.select.to_child_mut()]
+    }
+}
+
+impl Draw for ToggleMenu {
+    fn draw(&self, canvas: &mut Canvas, (): (), metadata: DrawMetadata) {
+        if self.select.is_empty() {
+            canvas.render_widget(
+                "No toggles defined. Add a `toggles` field to the collection \
+                to define sets of values to layer onto the selected profile",
+                metadata.area(),
+            );
+        } else {
+            canvas.draw(
+                &self.select,
+                SelectListProps::modal(),
+                metadata.area(),
+                true,
+            );
+        }
+    }
+}
+
+impl Modal for ToggleMenu {
+    fn title(&self) -> Line<'_> {
+        ViewContext::add_binding_hint(
+            tr!("modal-toggles-title"),
+            Action::OpenToggles,
+        )
+        .into()
+    }
+
+    fn dimensions(&self) -> (Constraint, Constraint) {
+        let height = (self.select.len() as u16).clamp(1, Self::MAX_HEIGHT);
+        (Constraint::Length(40), Constraint::Length(height))
+    }
+}
+
+/// Emitted by [ToggleMenu] whenever a toggle is switched on or off
+#[derive(Debug)]
+pub struct SetToggles(pub EnabledToggles);
+
+/// Toggles that are currently switched on, in collection order. This is
+/// persisted so toggles stay on across sessions.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EnabledToggles(Vec<ToggleId>);
+
+impl EnabledToggles {
+    /// Load the list from the persistent store
+    pub fn load() -> Self {
+        Self(PersistentStore::get(&EnabledTogglesKey).unwrap_or_default())
+    }
+
+    /// Save the list to the persistent store
+    pub fn persist(&self, store: &mut PersistentStore) {
+        store.set(&EnabledTogglesKey, &self.0);
+    }
+
+    /// Get the enabled toggle IDs, to be passed to the template context
+    pub fn to_ids(&self) -> Vec<ToggleId> {
+        self.0.clone()
+    }
+}
+
+/// Persistent key for [EnabledToggles]
+#[derive(Debug, Serialize)]
+struct EnabledTogglesKey;
+
+impl PersistentKey for EnabledTogglesKey {
+    type Value = Vec<ToggleId>;
+}
+
+#[derive(Clone, Debug, Display)]
+#[display("{} {name}", if *enabled { "[x]" } else { "[ ]" })]
+struct ToggleMenuItem {
+    id: ToggleId,
+    name: String,
+    enabled: bool,
+}
+
+impl ToStringGenerate for ToggleMenuItem {}
@@ -12,7 +12,12 @@ use crate::{
 use futures::FutureExt;
 use slumber_config::{Action, Config};
 use slumber_core::{collection::Collection, database::CollectionDatabase};
-use std::{cell::RefCell, fmt::Display, sync::Arc};
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use tracing::debug;
 
 /// Thread-local context container, which stores mutable state needed in the
@@ -40,6 +45,10 @@ pub struct ViewContext {
     messages_tx: MessageSender,
     /// Visual styles, derived from the theme
     styles: Styles,
+    /// Earliest time at which something on screen will change on its own,
+    /// e.g. a countdown ticking down. Reset at the start of each draw, so only
+    /// visible components can set it.
+    redraw_at: Option<Instant>,
 }
 
 impl ViewContext {
@@ -79,6 +88,7 @@ impl ViewContext {
                 input_bindings,
                 messages_tx,
                 styles,
+                redraw_at: None,
             });
         });
     }
@@ -131,6 +141,15 @@ impl ViewContext {
         Self::with_mut(|context| context.event_queue.pop())
     }
 
+    /// Wait until an event is pushed onto the event queue. This will resolve
+    /// immediately if an event has been pushed since the last call. If the
+    /// view context is replaced, this will *not* resolve for events on the new
+    /// context.
+    pub fn event_pushed() -> impl Future<Output = ()> {
+        let notify = Self::with(|context| context.event_queue.notifier());
+        async move { notify.notified().await }
+    }
+
     /// Get a clone of the async message sender. Generally you should use
     /// [Self::send_message] instead, but in some contexts you need the whole
     /// sender.
@@ -155,6 +174,31 @@ impl ViewContext {
         Self::with(|context| context.styles.clone())
     }
 
+    /// Request a redraw after the given delay, even if nothing else happens in
+    /// the meantime. Components that show time-relative text (e.g. "expires in
+    /// 5m") call this while drawing, so the text doesn't freeze while the TUI
+    /// is idle. If called multiple times, the earliest deadline wins.
+    pub fn redraw_after(delay: Duration) {
+        let deadline = Instant::now() + delay;
+        Self::with_mut(|context| {
+            context.redraw_at = Some(
+                context
+                    .redraw_at
+                    .map_or(deadline, |redraw_at| redraw_at.min(deadline)),
+            );
+        });
+    }
+
+    /// Get the earliest redraw requested during the last draw
+    pub fn redraw_at() -> Option<Instant> {
+        Self::with(|context| context.redraw_at)
+    }
+
+    /// Clear the requested redraw. Called at the start of each draw
+    pub fn clear_redraw() {
+        Self::with_mut(|context| context.redraw_at = None);
+    }
+
     /// Execute a function with access to the database
     pub fn with_database<T>(f: impl FnOnce(&CollectionDatabase) -> T) -> T {
         Self::with(|context| f(&context.database))
@@ -206,6 +250,8 @@ mod tests {
     };
     use rstest::rstest;
     use slumber_util::assert_matches;
+    use std::time::Duration;
+    use tokio::time;
 
     #[rstest]
     fn test_event_queue(_harness: TestHarness) {
@@ -221,6 +267,17 @@ mod tests {
         assert_events!(); // Empty again
     }
 
+    /// Pushing an event should wake up anyone waiting on the queue
+    #[rstest]
+    #[tokio::test]
+    async fn test_event_pushed(_harness: TestHarness) {
+        let future = ViewContext::event_pushed();
+        ViewContext::push_event(Event::DeleteRequests(DeleteTarget::Request));
+        time::timeout(Duration::from_millis(100), future)
+            .await
+            .expect("Event notification not received");
+    }
+
     #[rstest]
     fn test_send_message(mut harness: TestHarness) {
         ViewContext::send_message(Message::CollectionStartReload);
@@ -10,7 +10,8 @@ use ratatui::layout::Position;
 use slumber_config::Action;
 use slumber_core::{
     collection::{ProfileId, RecipeId},
-    http::RequestId,
+    http::{RequestId, ResponseLink, ResponseRecord},
+    render::TemplateReference,
 };
 use std::{
     any::{self, Any},
@@ -18,8 +19,11 @@ use std::{
     fmt::Debug,
     marker::PhantomData,
     ops::Deref,
+    rc::Rc,
+    sync::Arc,
 };
 use terminput::ScrollDirection;
+use tokio::sync::Notify;
 use tracing::trace;
 use uuid::Uuid;
 
@@ -29,24 +33,36 @@ use uuid::Uuid;
 /// This is drained by the view, which is responsible for passing those events
 /// down the component tree.
 #[derive(Default)]
-pub struct EventQueue(VecDeque<Event>);
+pub struct EventQueue {
+    events: VecDeque<Event>,
+    /// Notified whenever an event is pushed. Events can be pushed from
+    /// background tasks (e.g. a completed template preview), so this is used
+    /// to wake up the main loop to handle them.
+    notify: Rc<Notify>,
+}
 
 impl EventQueue {
     /// Queue a view event to be handled by the component tree
     pub fn push(&mut self, event: Event) {
         trace!(?event, "Queueing view event");
-        self.0.push_back(event);
+        self.events.push_back(event);
+        self.notify.notify_one();
     }
 
     /// Pop an event off the queue
     pub fn pop(&mut self) -> Option<Event> {
-        self.0.pop_front()
+        self.events.pop_front()
+    }
+
+    /// Get a handle that will be notified whenever an event is pushed
+    pub fn notifier(&self) -> Rc<Notify> {
+        Rc::clone(&self.notify)
     }
 
     /// Collect references to each event into a vector, for asserting on it
     #[cfg(test)]
     pub fn to_vec(&self) -> Vec<&Event> {
-        self.0.iter().collect()
+        self.events.iter().collect()
     }
 }
 
@@ -68,6 +84,31 @@ pub enum Event {
     /// recipe. This will trigger a confirmation modal before the deletion
     DeleteRequests(DeleteTarget),
 
+    /// User has requested to navigate to the definition of something
+    /// referenced by a template, e.g. a profile field. Handled by the primary
+    /// view
+    GoToDefinition(TemplateReference),
+
+    /// User has requested to navigate to a folder in the recipe tree, e.g. the
+    /// folder that a recipe inherits authentication from. Handled by the
+    /// primary view
+    GoToFolder(RecipeId),
+
+    /// User wants to browse the links in a response, so they can follow one.
+    /// Handled by the primary view
+    OpenLinks(Vec<ResponseLink>),
+
+    /// User wants to query the nodes of an XML/HTML response body. Handled by
+    /// the primary view
+    QueryNodes(Arc<ResponseRecord>),
+
+    /// User wants to copy a JSONPath selector for a node in a JSON response
+    /// body. Handled by the primary view
+    PickJsonPath {
+        recipe_id: RecipeId,
+        response: Arc<ResponseRecord>,
+    },
+
     /// A localized event emitted by a particular [Emitter] implementation.
     /// The event type here does not need to be unique because the emitter ID
     /// makes sure this will only be consumed by the intended recipient. Use
@@ -1,21 +1,27 @@
 use ratatui::{
     style::{Color, Modifier, Style},
-    widgets::BorderType,
+    symbols::{border, scrollbar},
 };
-use slumber_config::Theme;
+use slumber_config::{BorderKind, StyleKey, Theme, ThemeMarkers, ThemePreset};
 
-/// Concrete styles for the TUI, generated from the theme. We *could* make this
-/// entire thing user-configurable, but that would be way too complex. The theme
-/// provides users some basic settings, then we figure out the minutae from
-/// there. Styles are grouped into sub-structs generally by component.
+/// Concrete styles for the TUI, generated from the theme. The theme provides
+/// users some basic settings, then we figure out the minutae from there.
+/// Individual styles can then be tweaked with the theme's `overrides`. Styles
+/// are grouped into sub-structs generally by component.
 #[derive(Clone, Debug)]
 pub struct Styles {
+    /// If `false`, all color is stripped from the screen after drawing
+    pub color: bool,
+    /// Modifiers that are stripped from the screen after drawing
+    pub disabled_modifiers: Modifier,
     pub form: FormStyles,
     pub list: ListStyles,
+    pub markers: MarkerStyles,
     pub menu: MenuStyles,
     pub modal: ModalStyles,
     pub pane: PaneStyles,
     pub status_code: StatusCodeStyles,
+    pub symbols: SymbolStyles,
     pub tab: TabStyles,
     pub table: TableStyles,
     pub template_preview: TemplatePreviewStyles,
@@ -44,17 +50,53 @@ pub struct ListStyles {
     pub disabled: Style,
 }
 
+/// Text markers that accompany color cues. Unless configured in the theme,
+/// these are only shown when color is disabled. Each non-empty marker includes
+/// a trailing space.
+#[derive(Clone, Debug)]
+pub struct MarkerStyles {
+    /// Prefix for the selected item in a list
+    pub selected: String,
+    /// Prefix for success values, such as 2xx status codes
+    pub success: String,
+    /// Prefix for error values, such as 4xx/5xx status codes
+    pub error: String,
+}
+
+impl MarkerStyles {
+    fn new(markers: &ThemeMarkers, color: bool) -> Self {
+        // Fall back to the no-color default only when color is disabled
+        let marker = |configured: &Option<String>, no_color: &str| {
+            let marker = match configured {
+                Some(marker) => marker.as_str(),
+                None if color => "",
+                None => no_color,
+            };
+            if marker.is_empty() {
+                String::new()
+            } else {
+                format!("{marker} ")
+            }
+        };
+        Self {
+            selected: marker(&markers.selected, "[SELECTED]"),
+            success: marker(&markers.success, ""),
+            error: marker(&markers.error, "[ERROR]"),
+        }
+    }
+}
+
 /// Styles for the action menu
 #[derive(Clone, Debug)]
 pub struct MenuStyles {
-    pub border_type: BorderType,
+    pub border_set: border::Set<'static>,
 }
 
 /// Styles for the Modal component
 #[derive(Clone, Debug)]
 pub struct ModalStyles {
     pub border: Style,
-    pub border_type: BorderType,
+    pub border_set: border::Set<'static>,
 }
 
 /// Styles for Pane component
@@ -65,22 +107,125 @@ pub struct PaneStyles {
     /// Pane border when selected/focused
     pub border_selected: Style,
     /// Pane border characters used when not selected/focused
-    pub border_type: BorderType,
+    pub border_set: border::Set<'static>,
     /// Pane border characters used when selected/focused
-    pub border_type_selected: BorderType,
+    pub border_set_selected: border::Set<'static>,
 }
 
 impl PaneStyles {
-    /// Get the type and style of the border for a pane
-    pub fn border(&self, has_focus: bool) -> (BorderType, Style) {
+    /// Get the characters and style of the border for a pane
+    pub fn border(&self, has_focus: bool) -> (border::Set<'static>, Style) {
         if has_focus {
-            (self.border_type_selected, self.border_selected)
+            (self.border_set_selected, self.border_selected)
         } else {
-            (self.border_type, self.border)
+            (self.border_set, self.border)
         }
     }
 }
 
+/// Characters used for decorations such as scrollbars and trees. In ASCII mode,
+/// these are all plain ASCII.
+#[derive(Clone, Debug)]
+pub struct SymbolStyles {
+    /// Icon for a collapsed folder
+    pub collapsed: &'static str,
+    /// Icon for an expanded folder
+    pub expanded: &'static str,
+    /// Replacement character for sensitive text
+    pub mask: char,
+    pub scrollbar_horizontal: scrollbar::Set<'static>,
+    pub scrollbar_vertical: scrollbar::Set<'static>,
+    /// Tree branch to a child with more siblings after it
+    pub tree_branch: &'static str,
+    /// Tree branch to the last child of a node
+    pub tree_end: &'static str,
+    /// Tree branch to the last child of a node, which has its own child
+    pub tree_end_fork: &'static str,
+    /// Tree line passing by a node, to reach the node's later siblings
+    pub tree_line: &'static str,
+}
+
+impl SymbolStyles {
+    const UNICODE: Self = Self {
+        collapsed: "▶",
+        expanded: "▼",
+        mask: '•',
+        scrollbar_horizontal: scrollbar::Set {
+            track: "═",
+            thumb: "■",
+            begin: "◀",
+            end: "▶",
+        },
+        scrollbar_vertical: scrollbar::Set {
+            track: "║",
+            thumb: "█",
+            begin: "▲",
+            end: "▼",
+        },
+        tree_branch: "├─",
+        tree_end: "└─",
+        tree_end_fork: "└┬",
+        tree_line: "│",
+    };
+
+    const ASCII: Self = Self {
+        collapsed: ">",
+        expanded: "v",
+        mask: '*',
+        scrollbar_horizontal: scrollbar::Set {
+            track: "-",
+            thumb: "#",
+            begin: "<",
+            end: ">",
+        },
+        scrollbar_vertical: scrollbar::Set {
+            track: "|",
+            thumb: "#",
+            begin: "^",
+            end: "v",
+        },
+        tree_branch: "|-",
+        tree_end: "`-",
+        tree_end_fork: "`+",
+        tree_line: "|",
+    };
+}
+
+/// Plain ASCII replacement for [border::ROUNDED]
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// Plain ASCII replacement for [border::DOUBLE]
+const ASCII_BORDER_DOUBLE: border::Set = border::Set {
+    top_left: "#",
+    top_right: "#",
+    bottom_left: "#",
+    bottom_right: "#",
+    vertical_left: "#",
+    vertical_right: "#",
+    horizontal_top: "=",
+    horizontal_bottom: "=",
+};
+
+/// Get the border characters for a configured border kind
+fn border_set(kind: BorderKind) -> border::Set<'static> {
+    match kind {
+        BorderKind::Plain => border::PLAIN,
+        BorderKind::Rounded => border::ROUNDED,
+        BorderKind::Double => border::DOUBLE,
+        BorderKind::Thick => border::THICK,
+        BorderKind::Ascii => ASCII_BORDER,
+    }
+}
+
 /// Styles for HTTP status code display
 #[derive(Clone, Debug)]
 pub struct StatusCodeStyles {
@@ -114,6 +259,10 @@ pub struct TableStyles {
 pub struct TemplatePreviewStyles {
     pub text: Style,
     pub error: Style,
+    /// Default value of a `fallback()` whose value failed to render
+    pub fallback: Style,
+    /// Value loaded from a cache, e.g. `command(cache=...)`
+    pub cached: Style,
 }
 
 /// General text styles
@@ -147,11 +296,57 @@ pub struct TextBoxStyle {
 pub struct TextWindowStyle {
     /// Line numbers on large text areas
     pub gutter: Style,
+    /// Line numbers of bookmarked lines
+    pub bookmark: Style,
+    /// Diff marker for a line added since the previous response
+    pub diff_added: Style,
+    /// Diff marker for a line changed since the previous response
+    pub diff_changed: Style,
+    /// Diff marker for lines removed since the previous response
+    pub diff_removed: Style,
 }
 
 impl Styles {
     pub fn new(theme: &Theme) -> Self {
-        Self {
+        let color = theme.use_color();
+        // Without color, we need the same structural cues as high contrast
+        // mode, because selections can't rely on background colors
+        let high_contrast = theme.preset == ThemePreset::HighContrast || !color;
+        let (border, border_double, symbols) = if theme.ascii {
+            (ASCII_BORDER, ASCII_BORDER_DOUBLE, SymbolStyles::ASCII)
+        } else {
+            (border::ROUNDED, border::DOUBLE, SymbolStyles::UNICODE)
+        };
+        // Explicitly configured borders win over the defaults
+        let border_or = |kind: Option<BorderKind>, default| {
+            kind.map_or(default, border_set)
+        };
+        // Dimmed and dark gray text is hard to read in high contrast mode
+        let (dim, hint) = if high_contrast {
+            (
+                Modifier::ITALIC,
+                Style::default().add_modifier(Modifier::ITALIC),
+            )
+        } else {
+            (Modifier::DIM, Style::default().fg(Color::DarkGray))
+        };
+        let highlight = if high_contrast {
+            Style::default().add_modifier(Modifier::BOLD | Modifier::REVERSED)
+        } else {
+            Style::default()
+                .bg(theme.primary_color)
+                .fg(theme.primary_text_color)
+                .add_modifier(Modifier::BOLD)
+        };
+
+        let mut styles = Self {
+            color,
+            disabled_modifiers: theme
+                .disabled_modifiers
+                .iter()
+                .fold(Modifier::empty(), |acc, modifier| {
+                    acc | Modifier::from(*modifier)
+                }),
             form: FormStyles {
                 title: Style::default().add_modifier(Modifier::UNDERLINED),
                 title_highlight: Style::default()
@@ -159,29 +354,35 @@ impl Styles {
                     .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
             },
             list: ListStyles {
-                highlight: Style::default()
-                    .bg(theme.primary_color)
-                    .fg(theme.primary_text_color)
-                    .add_modifier(Modifier::BOLD),
-                highlight_inactive: Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD),
-                disabled: Style::default().add_modifier(Modifier::DIM),
+                highlight,
+                highlight_inactive: if high_contrast {
+                    Style::default()
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                } else {
+                    Style::default()
+                        .bg(Color::DarkGray)
+                        .add_modifier(Modifier::BOLD)
+                },
+                disabled: Style::default().add_modifier(dim),
             },
+            markers: MarkerStyles::new(&theme.markers, color),
             menu: MenuStyles {
-                border_type: BorderType::Rounded,
+                border_set: border_or(theme.borders.menu, border),
             },
             modal: ModalStyles {
                 border: Style::default(),
-                border_type: BorderType::Double,
+                border_set: border_or(theme.borders.modal, border_double),
             },
             pane: PaneStyles {
                 border: Style::default(),
                 border_selected: Style::default()
                     .fg(theme.primary_color)
                     .add_modifier(Modifier::BOLD),
-                border_type: BorderType::Rounded,
-                border_type_selected: BorderType::Double,
+                border_set: border_or(theme.borders.pane, border),
+                border_set_selected: border_or(
+                    theme.borders.pane_selected,
+                    border_double,
+                ),
             },
             status_code: StatusCodeStyles {
                 success: Style::default()
@@ -189,8 +390,9 @@ impl Styles {
                     .bg(theme.success_color),
                 error: Style::default().bg(theme.error_color),
             },
+            symbols,
             tab: TabStyles {
-                disabled: Style::default().add_modifier(Modifier::DIM),
+                disabled: Style::default().add_modifier(dim),
                 highlight: Style::default()
                     .fg(theme.primary_color)
                     .add_modifier(Modifier::BOLD)
@@ -201,13 +403,13 @@ impl Styles {
                     .add_modifier(Modifier::BOLD)
                     .add_modifier(Modifier::UNDERLINED),
                 text: Style::default(),
-                alt: Style::default().bg(Color::DarkGray),
-                disabled: Style::default().add_modifier(Modifier::DIM),
-                highlight: Style::default()
-                    .bg(theme.primary_color)
-                    .fg(theme.primary_text_color)
-                    .add_modifier(Modifier::BOLD)
-                    .add_modifier(Modifier::UNDERLINED),
+                alt: if high_contrast {
+                    Style::default()
+                } else {
+                    Style::default().bg(Color::DarkGray)
+                },
+                disabled: Style::default().add_modifier(dim),
+                highlight: highlight.add_modifier(Modifier::UNDERLINED),
                 title: Style::default().add_modifier(Modifier::BOLD),
             },
             template_preview: TemplatePreviewStyles {
@@ -217,26 +419,193 @@ impl Styles {
                 error: Style::default()
                     .fg(Color::default()) // Override syntax highlighting
                     .bg(theme.error_color),
+                fallback: Style::default()
+                    .fg(theme.error_color)
+                    .add_modifier(Modifier::UNDERLINED),
+                cached: Style::default()
+                    .fg(theme.secondary_color)
+                    .add_modifier(Modifier::UNDERLINED | Modifier::ITALIC),
             },
             text: TextStyle {
-                highlight: Style::default()
-                    .fg(theme.primary_text_color)
-                    .bg(theme.primary_color),
-                hint: Style::default().fg(Color::DarkGray),
+                highlight: if high_contrast {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                        .fg(theme.primary_text_color)
+                        .bg(theme.primary_color)
+                },
+                hint,
                 primary: Style::default().fg(theme.primary_color),
                 edited: Style::default().add_modifier(Modifier::ITALIC),
                 error: Style::default().fg(theme.error_color),
                 title: Style::default().add_modifier(Modifier::BOLD),
             },
-            text_box: TextBoxStyle {
-                text: Style::default().bg(Color::DarkGray),
-                cursor: Style::default().bg(Color::White).fg(Color::Black),
-                placeholder: Style::default().fg(Color::Black),
-                invalid: Style::default().bg(Color::LightRed),
+            text_box: if high_contrast {
+                TextBoxStyle {
+                    text: Style::default().add_modifier(Modifier::UNDERLINED),
+                    cursor: Style::default().add_modifier(Modifier::REVERSED),
+                    placeholder: Style::default()
+                        .add_modifier(Modifier::ITALIC),
+                    invalid: Style::default()
+                        .fg(theme.error_color)
+                        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                }
+            } else {
+                TextBoxStyle {
+                    text: Style::default().bg(Color::DarkGray),
+                    cursor: Style::default().bg(Color::White).fg(Color::Black),
+                    placeholder: Style::default().fg(Color::Black),
+                    invalid: Style::default().bg(Color::LightRed),
+                }
             },
             text_window: TextWindowStyle {
-                gutter: Style::default().fg(Color::DarkGray),
+                gutter: hint,
+                bookmark: Style::default()
+                    .fg(theme.primary_color)
+                    .add_modifier(Modifier::REVERSED),
+                diff_added: Style::default().fg(theme.success_color),
+                diff_changed: Style::default().fg(theme.secondary_color),
+                diff_removed: Style::default().fg(theme.error_color),
             },
+        };
+
+        for (key, style_override) in &theme.overrides {
+            let style = styles.style_mut(*key);
+            *style = style_override.apply(*style);
         }
+        styles
+    }
+
+    /// Get a mutable reference to the style targeted by a theme override
+    fn style_mut(&mut self, key: StyleKey) -> &mut Style {
+        match key {
+            StyleKey::FormTitle => &mut self.form.title,
+            StyleKey::FormTitleHighlight => &mut self.form.title_highlight,
+            StyleKey::ListHighlight => &mut self.list.highlight,
+            StyleKey::ListHighlightInactive => {
+                &mut self.list.highlight_inactive
+            }
+            StyleKey::ListDisabled => &mut self.list.disabled,
+            StyleKey::ModalBorder => &mut self.modal.border,
+            StyleKey::PaneBorder => &mut self.pane.border,
+            StyleKey::PaneBorderSelected => &mut self.pane.border_selected,
+            StyleKey::StatusCodeSuccess => &mut self.status_code.success,
+            StyleKey::StatusCodeError => &mut self.status_code.error,
+            StyleKey::TabDisabled => &mut self.tab.disabled,
+            StyleKey::TabHighlight => &mut self.tab.highlight,
+            StyleKey::TableHeader => &mut self.table.header,
+            StyleKey::TableText => &mut self.table.text,
+            StyleKey::TableAlt => &mut self.table.alt,
+            StyleKey::TableDisabled => &mut self.table.disabled,
+            StyleKey::TableHighlight => &mut self.table.highlight,
+            StyleKey::TableTitle => &mut self.table.title,
+            StyleKey::TemplatePreviewText => &mut self.template_preview.text,
+            StyleKey::TemplatePreviewError => &mut self.template_preview.error,
+            StyleKey::TemplatePreviewFallback => {
+                &mut self.template_preview.fallback
+            }
+            StyleKey::TemplatePreviewCached => {
+                &mut self.template_preview.cached
+            }
+            StyleKey::TextHighlight => &mut self.text.highlight,
+            StyleKey::TextHint => &mut self.text.hint,
+            StyleKey::TextPrimary => &mut self.text.primary,
+            StyleKey::TextEdited => &mut self.text.edited,
+            StyleKey::TextError => &mut self.text.error,
+            StyleKey::TextTitle => &mut self.text.title,
+            StyleKey::TextBoxText => &mut self.text_box.text,
+            StyleKey::TextBoxCursor => &mut self.text_box.cursor,
+            StyleKey::TextBoxPlaceholder => &mut self.text_box.placeholder,
+            StyleKey::TextBoxInvalid => &mut self.text_box.invalid,
+            StyleKey::TextWindowGutter => &mut self.text_window.gutter,
+            StyleKey::TextWindowBookmark => &mut self.text_window.bookmark,
+            StyleKey::TextWindowDiffAdded => &mut self.text_window.diff_added,
+            StyleKey::TextWindowDiffChanged => {
+                &mut self.text_window.diff_changed
+            }
+            StyleKey::TextWindowDiffRemoved => {
+                &mut self.text_window.diff_removed
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::indexmap;
+    use rstest::rstest;
+    use slumber_config::{StyleModifier, StyleOverride, ThemeBorders};
+
+    /// Overrides are applied on top of the generated style, and only affect
+    /// the targeted style
+    #[test]
+    fn test_overrides() {
+        let theme = Theme {
+            overrides: indexmap! {
+                StyleKey::PaneBorderSelected => StyleOverride {
+                    fg: Some(Color::Magenta),
+                    modifiers: vec![StyleModifier::Italic],
+                    remove_modifiers: vec![StyleModifier::Bold],
+                    ..StyleOverride::default()
+                },
+            },
+            ..Theme::default()
+        };
+        let styles = Styles::new(&theme);
+        assert_eq!(
+            styles.pane.border_selected,
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::ITALIC)
+                .remove_modifier(Modifier::BOLD)
+        );
+        // Other styles derived from the same colors are untouched
+        assert_eq!(
+            styles.tab.highlight,
+            Styles::new(&Theme::default()).tab.highlight
+        );
+    }
+
+    /// Configured markers are shown regardless of color. Unconfigured markers
+    /// are only shown without color, and empty markers are never shown
+    #[rstest]
+    #[case::color_default(true, None, "")]
+    #[case::no_color_default(false, None, "[ERROR] ")]
+    #[case::color_configured(true, Some("✘"), "✘ ")]
+    #[case::no_color_configured(false, Some("✘"), "✘ ")]
+    #[case::no_color_empty(false, Some(""), "")]
+    fn test_markers(
+        #[case] color: bool,
+        #[case] error: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let markers = ThemeMarkers {
+            error: error.map(String::from),
+            ..ThemeMarkers::default()
+        };
+        let styles = MarkerStyles::new(&markers, color);
+        assert_eq!(styles.error, expected);
+        // Success has no default marker
+        assert_eq!(styles.success, "");
+    }
+
+    /// Configured border kinds replace the defaults, and unconfigured ones are
+    /// left alone
+    #[test]
+    fn test_borders() {
+        let theme = Theme {
+            borders: ThemeBorders {
+                pane: Some(BorderKind::Plain),
+                modal: Some(BorderKind::Thick),
+                ..ThemeBorders::default()
+            },
+            ..Theme::default()
+        };
+        let styles = Styles::new(&theme);
+        assert_eq!(styles.pane.border_set, border::PLAIN);
+        assert_eq!(styles.pane.border_set_selected, border::DOUBLE);
+        assert_eq!(styles.modal.border_set, border::THICK);
+        assert_eq!(styles.menu.border_set, border::ROUNDED);
     }
 }
@@ -58,13 +58,18 @@ pub struct TestHarness {
 impl TestHarness {
     /// Create a new test harness and initialize state
     pub fn new(collection: Collection) -> Self {
+        Self::with_config(collection, Config::default())
+    }
+
+    /// Create a new test harness with a custom config
+    pub fn with_config(collection: Collection, config: Config) -> Self {
         let messages = MessageQueue::new();
         let database = CollectionDatabase::factory(());
         let request_store =
             Rc::new(RefCell::new(RequestStore::new(database.clone())));
         let collection = Arc::new(collection);
         ViewContext::init(
-            Config::default().into(),
+            config.into(),
             Arc::clone(&collection),
             database.clone(),
             messages.tx(),
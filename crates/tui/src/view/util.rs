@@ -1,6 +1,9 @@
 //! Helper structs and functions for building components
 
+pub mod ansi;
 pub mod highlight;
+pub mod markup;
+pub mod render_cache;
 
 use crate::{
     message::{HttpMessage, Message, MessageSender},
@@ -117,7 +120,7 @@ pub fn str_to_text(s: &str) -> Text<'static> {
 pub fn view_text(text: &Text, mime: Option<Mime>) {
     // Write text to the file line-by-line. This avoids having to copy the bytes
     // to a single chunk of bytes just to write them out
-    let Some(file) = TempFile::with_file(|file| {
+    let Some(file) = TempFile::with_file(mime.as_ref(), |file| {
         for line in &text.lines {
             for span in &line.spans {
                 file.write_all(span.content.as_bytes())?;
@@ -149,23 +152,6 @@ pub fn format_duration(duration: &Duration) -> String {
     }
 }
 
-/// Format a byte total, e.g. 1_000_000 -> 1 MB
-pub fn format_byte_size(size: usize) -> String {
-    const K: usize = 10usize.pow(3);
-    const M: usize = 10usize.pow(6);
-    const G: usize = 10usize.pow(9);
-    const T: usize = 10usize.pow(12);
-    let (denom, suffix) = match size {
-        ..K => return format!("{size} B"),
-        K..M => (K, "K"),
-        M..G => (M, "M"),
-        G..T => (G, "G"),
-        T.. => (T, "T"),
-    };
-    let size = size as f64 / denom as f64;
-    format!("{size:.1} {suffix}B")
-}
-
 /// Get a minified name for a type. Common prefixes are stripped from the type
 /// to reduce clutter
 pub fn format_type_name(type_name: &str) -> String {
@@ -175,26 +161,3 @@ pub fn format_type_name(type_name: &str) -> String {
         .replace("slumber_tui::view::test_util::", "")
         .replace("slumber_tui::view::util::", "")
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rstest::rstest;
-
-    #[rstest]
-    #[case::zero(0, "0 B")]
-    #[case::one(1, "1 B")]
-    #[case::almost_kb(999, "999 B")]
-    #[case::kb(1000, "1.0 KB")]
-    #[case::kb_round_down(1049, "1.0 KB")]
-    #[case::kb_round_up(1050, "1.1 KB")]
-    #[case::almost_mb(999_999, "1000.0 KB")]
-    #[case::mb(1_000_000, "1.0 MB")]
-    #[case::almost_gb(999_999_999, "1000.0 MB")]
-    #[case::gb(1_000_000_000, "1.0 GB")]
-    #[case::almost_tb(999_999_999_999, "1000.0 GB")]
-    #[case::tb(1_000_000_000_000, "1.0 TB")]
-    fn test_format_byte_size(#[case] size: usize, #[case] expected: &str) {
-        assert_eq!(&format_byte_size(size), expected);
-    }
-}
@@ -0,0 +1,184 @@
+//! Convert terminal output with ANSI escape sequences into styled text. Only
+//! SGR sequences (colors and text attributes) are applied; every other escape
+//! sequence is dropped, because there's no cursor for it to move.
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+const ESCAPE: char = '\x1b';
+const BELL: char = '\x07';
+
+/// Parse text containing ANSI escape sequences into styled text. Styles carry
+/// over line breaks, the same as they would in a terminal.
+pub fn ansi_to_text(input: &str) -> Text<'static> {
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+    let mut style = Style::default();
+    let mut current = String::new();
+
+    // Flush accumulated text into a span with the current style
+    let flush =
+        |current: &mut String, spans: &mut Vec<Span<'static>>, style| {
+            if !current.is_empty() {
+                spans.push(Span::styled(std::mem::take(current), style));
+            }
+        };
+
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ESCAPE => match chars.next() {
+                // Control Sequence Introducer: parameters followed by a
+                // single final byte
+                Some('[') => {
+                    let mut params = String::new();
+                    let mut terminator = None;
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            terminator = Some(c);
+                            break;
+                        }
+                        params.push(c);
+                    }
+                    if terminator == Some('m') {
+                        flush(&mut current, &mut spans, style);
+                        style = apply_sgr(style, &params);
+                    }
+                }
+                // Operating System Command (e.g. hyperlinks or the window
+                // title), terminated by BEL or ESC \
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == BELL {
+                            break;
+                        }
+                        if c == ESCAPE && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                // Any other escape is a single character
+                _ => {}
+            },
+            '\n' => {
+                flush(&mut current, &mut spans, style);
+                lines.push(Line::from(std::mem::take(&mut spans)));
+            }
+            // Carriage returns only matter for overwriting lines, which we
+            // can't do
+            '\r' => {}
+            c => current.push(c),
+        }
+    }
+    flush(&mut current, &mut spans, style);
+    if !spans.is_empty() {
+        lines.push(Line::from(spans));
+    }
+    Text::from(lines)
+}
+
+/// Apply a Select Graphic Rendition sequence, e.g. `1;31`, to a style.
+/// Unknown codes are ignored.
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    // An empty sequence is a reset
+    if params.is_empty() {
+        return Style::default();
+    }
+    let mut codes = params
+        .split([';', ':'])
+        // An empty parameter means 0
+        .map(|code| code.parse::<u8>().unwrap_or(0));
+
+    while let Some(code) = codes.next() {
+        style = match code {
+            0 => Style::default(),
+            1 => style.add_modifier(Modifier::BOLD),
+            2 => style.add_modifier(Modifier::DIM),
+            3 => style.add_modifier(Modifier::ITALIC),
+            4 => style.add_modifier(Modifier::UNDERLINED),
+            5 => style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style.add_modifier(Modifier::REVERSED),
+            8 => style.add_modifier(Modifier::HIDDEN),
+            9 => style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style.remove_modifier(Modifier::ITALIC),
+            24 => style.remove_modifier(Modifier::UNDERLINED),
+            25 => style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style.remove_modifier(Modifier::REVERSED),
+            28 => style.remove_modifier(Modifier::HIDDEN),
+            29 => style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg(Color::Indexed(code - 30)),
+            38 => match extended_color(&mut codes) {
+                Some(color) => style.fg(color),
+                None => style,
+            },
+            39 => style.fg(Color::Reset),
+            40..=47 => style.bg(Color::Indexed(code - 40)),
+            48 => match extended_color(&mut codes) {
+                Some(color) => style.bg(color),
+                None => style,
+            },
+            49 => style.bg(Color::Reset),
+            90..=97 => style.fg(Color::Indexed(code - 90 + 8)),
+            100..=107 => style.bg(Color::Indexed(code - 100 + 8)),
+            _ => style,
+        };
+    }
+    style
+}
+
+/// Parse the remainder of a 256-color (`5;n`) or true color (`2;r;g;b`)
+/// sequence
+fn extended_color(codes: &mut impl Iterator<Item = u8>) -> Option<Color> {
+    match codes.next()? {
+        5 => Some(Color::Indexed(codes.next()?)),
+        2 => Some(Color::Rgb(codes.next()?, codes.next()?, codes.next()?)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::plain("hello\nworld", vec![
+        Line::from("hello"),
+        Line::from("world"),
+    ])]
+    #[case::colors(
+        "\x1b[1;31mred\x1b[0m plain \x1b[38;5;208morange\x1b[m",
+        vec![Line::from(vec![
+            Span::styled("red", Style::new().bold().fg(Color::Indexed(1))),
+            Span::raw(" plain "),
+            Span::styled("orange", Style::new().fg(Color::Indexed(208))),
+        ])],
+    )]
+    #[case::true_color(
+        "\x1b[48;2;1;2;3mbg\x1b[49m",
+        vec![Line::from(vec![
+            Span::styled("bg", Style::new().bg(Color::Rgb(1, 2, 3))),
+        ])],
+    )]
+    #[case::carry_over(
+        "\x1b[32ma\nb\x1b[0m",
+        vec![
+            Line::from(vec![Span::styled("a", Style::new().fg(Color::Indexed(2)))]),
+            Line::from(vec![Span::styled("b", Style::new().fg(Color::Indexed(2)))]),
+        ],
+    )]
+    #[case::other_escapes(
+        "\x1b[2J\x1b]8;;http://x\x1b\\link\x1b]8;;\x07\r\n",
+        vec![Line::from("link")],
+    )]
+    fn test_ansi_to_text(
+        #[case] input: &str,
+        #[case] expected: Vec<Line<'static>>,
+    ) {
+        assert_eq!(ansi_to_text(input), Text::from(expected));
+    }
+}
@@ -14,6 +14,7 @@ use std::{
     borrow::Cow,
     cell::RefCell,
     collections::{HashMap, VecDeque},
+    ops::Range,
 };
 use strum::{EnumIter, IntoEnumIterator};
 use tree_sitter_highlight::{
@@ -91,6 +92,144 @@ pub fn highlight_if(
     }
 }
 
+/// Apply syntax highlighting to a GraphQL document. There's no tree-sitter
+/// grammar for GraphQL available to us, but the lexical syntax is simple
+/// enough to tokenize by hand. Like [highlight], existing styles are retained.
+pub fn highlight_graphql(mut text: Text<'_>) -> Text<'_> {
+    // Block strings can span multiple lines, so we have to carry that state
+    let mut in_block_string = false;
+    for line in &mut text.lines {
+        let joined = join_line(line);
+        let mut builder = LineBuilder::new(line);
+        for (range, name) in graphql_tokens(&joined, &mut in_block_string) {
+            match name {
+                Some(name) => builder.set_style(name.style()),
+                None => builder.reset_style(),
+            }
+            builder.push_span(&joined, range.start, range.end);
+        }
+        *line = builder.build();
+    }
+    text
+}
+
+/// Split a line of GraphQL into highlighted tokens. The returned ranges cover
+/// the entire line; unhighlighted stretches have no name.
+fn graphql_tokens(
+    line: &str,
+    in_block_string: &mut bool,
+) -> Vec<(Range<usize>, Option<HighlightName>)> {
+    /// Find the end of a block string whose contents start at `start`
+    fn block_string_end(
+        line: &str,
+        start: usize,
+        in_block_string: &mut bool,
+    ) -> usize {
+        if let Some(index) = line[start..].find(r#"""""#) {
+            *in_block_string = false;
+            start + index + 3
+        } else {
+            *in_block_string = true;
+            line.len()
+        }
+    }
+
+    /// Find the end of a regular string whose contents start at `start`.
+    /// Strings can't span lines, so an unclosed string ends with the line.
+    fn string_end(line: &str, start: usize) -> usize {
+        let mut escaped = false;
+        for (index, c) in line[start..].char_indices() {
+            match c {
+                '"' if !escaped => return start + index + 1,
+                '\\' => escaped = !escaped,
+                _ => escaped = false,
+            }
+        }
+        line.len()
+    }
+
+    /// Find the end of the run of characters starting at `start` that match
+    /// a predicate
+    fn end_of(line: &str, start: usize, f: impl Fn(char) -> bool) -> usize {
+        line[start..]
+            .find(|c| !f(c))
+            .map_or(line.len(), |index| start + index)
+    }
+
+    fn is_name(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
+
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    // Start of the current unhighlighted stretch
+    let mut plain_start = 0;
+    if *in_block_string {
+        index = block_string_end(line, 0, in_block_string);
+        tokens.push((0..index, Some(HighlightName::String)));
+        plain_start = index;
+    }
+
+    while let Some(c) = line[index..].chars().next() {
+        let start = index;
+        let name = match c {
+            '#' => {
+                index = line.len();
+                HighlightName::Comment
+            }
+            '"' if line[index..].starts_with(r#"""""#) => {
+                index = block_string_end(line, index + 3, in_block_string);
+                HighlightName::String
+            }
+            '"' => {
+                index = string_end(line, index + 1);
+                HighlightName::String
+            }
+            '$' => {
+                index = end_of(line, index + 1, is_name);
+                HighlightName::Variable
+            }
+            '@' => {
+                index = end_of(line, index + 1, is_name);
+                HighlightName::Keyword
+            }
+            '-' | '0'..='9' => {
+                index = end_of(line, index + 1, |c| {
+                    c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E')
+                });
+                if &line[start..index] == "-" {
+                    continue; // Lone minus isn't a number
+                }
+                HighlightName::Number
+            }
+            c if is_name(c) => {
+                index = end_of(line, index, is_name);
+                match &line[start..index] {
+                    "query" | "mutation" | "subscription" | "fragment"
+                    | "on" | "schema" | "type" | "interface" | "union"
+                    | "enum" | "input" | "scalar" | "directive" | "extend"
+                    | "implements" | "repeatable" => HighlightName::Keyword,
+                    "true" | "false" | "null" => HighlightName::ConstantBuiltin,
+                    _ => continue, // Plain name
+                }
+            }
+            _ => {
+                index += c.len_utf8();
+                continue;
+            }
+        };
+        if plain_start < start {
+            tokens.push((plain_start..start, None));
+        }
+        tokens.push((start..index, Some(name)));
+        plain_start = index;
+    }
+    if plain_start < line.len() {
+        tokens.push((plain_start..line.len(), None));
+    }
+    tokens
+}
+
 /// Map [ContentType] to a syntax highlighting language
 fn get_config(content_type: ContentType) -> HighlightConfiguration {
     let mut config = match content_type {
@@ -118,14 +257,18 @@ fn get_config(content_type: ContentType) -> HighlightConfiguration {
 ///
 /// This enum should be the union of all highlight names in all supported langs:
 /// - <https://github.com/tree-sitter/tree-sitter-json/blob/94f5c527b2965465956c2000ed6134dd24daf2a7/queries/highlights.scm>
+/// - GraphQL, via [highlight_graphql]
 #[derive(Copy, Clone, Debug, EnumIter)]
+#[cfg_attr(test, derive(PartialEq))]
 enum HighlightName {
     Comment,
     ConstantBuiltin,
     Escape,
+    Keyword,
     Number,
     String,
     StringSpecial,
+    Variable,
 }
 
 impl HighlightName {
@@ -135,10 +278,12 @@ impl HighlightName {
             Self::Comment => "comment",
             Self::ConstantBuiltin => "constant.builtin",
             Self::Escape => "escape",
+            Self::Keyword => "keyword",
             Self::Number => "number",
             Self::String => "string",
             // This doesn't seem to work??
             Self::StringSpecial => "string.special",
+            Self::Variable => "variable",
         }
     }
 
@@ -157,9 +302,11 @@ impl HighlightName {
             Self::Comment => Color::Gray,
             Self::ConstantBuiltin => Color::Blue,
             Self::Escape => Color::Green,
+            Self::Keyword => Color::Magenta,
             Self::Number => Color::Cyan,
             Self::String => Color::LightGreen,
             Self::StringSpecial => Color::Green,
+            Self::Variable => Color::Yellow,
         };
         Style::default().fg(fg)
     }
@@ -443,6 +590,67 @@ mod tests {
         assert_eq!(highlighted, expected);
     }
 
+    /// Test GraphQL highlighting. Block strings carry across lines, and
+    /// existing styling is retained
+    #[test]
+    fn test_highlight_graphql() {
+        fn fg(color: Color) -> Style {
+            Style::default().fg(color)
+        }
+
+        let text = vec![
+            Line::from("query User($id: ID!) { # comment"),
+            vec![
+                "  user(id: $id, name: \"".into(),
+                Span::styled("ted", fg(Color::Red)),
+                "\", n: -1.5) @skip(if: false)".into(),
+            ]
+            .into(),
+            r#"  """block"#.into(),
+            r#"string""" }"#.into(),
+        ]
+        .into();
+        let highlighted = highlight_graphql(text);
+        let expected = vec![
+            vec![
+                Span::styled("query", fg(Color::Magenta)),
+                " User(".into(),
+                Span::styled("$id", fg(Color::Yellow)),
+                ": ID!) { ".into(),
+                Span::styled("# comment", fg(Color::Gray)),
+            ]
+            .into(),
+            vec![
+                "  user(id: ".into(),
+                Span::styled("$id", fg(Color::Yellow)),
+                ", name: ".into(),
+                Span::styled("\"", fg(Color::LightGreen)),
+                Span::styled("ted", fg(Color::Red)),
+                Span::styled("\"", fg(Color::LightGreen)),
+                ", n: ".into(),
+                Span::styled("-1.5", fg(Color::Cyan)),
+                ") ".into(),
+                Span::styled("@skip", fg(Color::Magenta)),
+                "(if: ".into(),
+                Span::styled("false", fg(Color::Blue)),
+                ")".into(),
+            ]
+            .into(),
+            vec![
+                "  ".into(),
+                Span::styled(r#""""block"#, fg(Color::LightGreen)),
+            ]
+            .into(),
+            vec![
+                Span::styled(r#"string""""#, fg(Color::LightGreen)),
+                " }".into(),
+            ]
+            .into(),
+        ]
+        .into();
+        assert_eq!(highlighted, expected);
+    }
+
     /// Test [StylePatch::split]
     #[test]
     fn test_patch_split() {
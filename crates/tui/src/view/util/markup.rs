@@ -0,0 +1,1097 @@
+//! Querying XML/HTML bodies with XPath or CSS selectors
+//!
+//! Both formats are parsed into the same simplified tree of elements and text,
+//! so both query languages work on both formats. Only the commonly used subset
+//! of each language is supported:
+//!
+//! - XPath: absolute paths of `/` and `//` steps. Steps can be an element name,
+//!   `*`, `text()`, `node()`, `@attr`, `@*`, `.`, or `..`. Predicates can be a
+//!   position (`[2]`), `[last()]`, `[@attr]`, `[@attr='value']`, or a child
+//!   element name (`[title]`).
+//! - CSS: element names, `*`, `#id`, `.class`, attribute selectors (`[attr]`,
+//!   `=`, `~=`, `^=`, `$=`, `*=`), `:first-child`, `:last-child`,
+//!   `:nth-child(n)`, and `:nth-of-type(n)`, joined by descendant (` `) and
+//!   child (`>`) combinators. Multiple selectors can be separated by `,`.
+
+use anyhow::anyhow;
+use itertools::Itertools;
+use mime::Mime;
+use std::{fmt::Write, str::FromStr};
+use winnow::{
+    ModalResult, Parser,
+    ascii::{dec_uint, multispace0, multispace1},
+    combinator::{alt, delimited, opt, preceded, repeat, separated},
+    token::{take_till, take_while},
+};
+
+/// A markup language that can be queried
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MarkupKind {
+    Html,
+    Xml,
+}
+
+impl MarkupKind {
+    /// Get the markup language for a MIME type. Return `None` if the type isn't
+    /// XML or HTML
+    pub fn from_mime(mime: &Mime) -> Option<Self> {
+        match (mime.subtype(), mime.suffix()) {
+            (mime::HTML, _) => Some(Self::Html),
+            (mime::XML, _) | (_, Some(mime::XML)) => Some(Self::Xml),
+            _ => None,
+        }
+    }
+
+    /// Query language to use when the user hasn't picked one
+    pub fn default_syntax(self) -> QuerySyntax {
+        match self {
+            Self::Html => QuerySyntax::Css,
+            Self::Xml => QuerySyntax::XPath,
+        }
+    }
+}
+
+/// A parsed XML/HTML document. Comments, processing instructions, and
+/// whitespace-only text are dropped.
+#[derive(Debug)]
+pub struct Document {
+    kind: MarkupKind,
+    /// All nodes in document order. The first node is the document root,
+    /// which has no name and no parent. Nodes refer to each other by index.
+    nodes: Vec<Node>,
+}
+
+impl Document {
+    /// ID of the root node. This is *not* the root element, but its parent
+    const ROOT: NodeId = NodeId(0);
+
+    /// Parse a document. HTML parsing is lenient so it never fails, but XML
+    /// must be well-formed
+    pub fn parse(kind: MarkupKind, text: &str) -> anyhow::Result<Self> {
+        let mut document = Self {
+            kind,
+            nodes: vec![Node {
+                parent: None,
+                children: Vec::new(),
+                data: NodeData::Root,
+            }],
+        };
+        match kind {
+            MarkupKind::Html => {
+                let html = scraper::Html::parse_document(text);
+                document.add_html_children(Self::ROOT, html.tree.root());
+            }
+            MarkupKind::Xml => {
+                let xml = roxmltree::Document::parse_with_options(
+                    text,
+                    roxmltree::ParsingOptions {
+                        allow_dtd: true,
+                        ..Default::default()
+                    },
+                )?;
+                document.add_xml_children(Self::ROOT, xml.root());
+            }
+        }
+        Ok(document)
+    }
+
+    /// Get all nodes matching a query, in document order
+    pub fn query(&self, query: &Query) -> Vec<Match> {
+        match query {
+            Query::XPath(steps) => self.query_xpath(steps),
+            Query::Css(selectors) => self
+                .elements()
+                .filter(|&id| {
+                    selectors
+                        .iter()
+                        .any(|selector| self.matches_selector(id, &selector.0))
+                })
+                .map(Match::Node)
+                .collect(),
+        }
+    }
+
+    /// Get a single-line summary of a match, for display in a list
+    pub fn summary(&self, node: &Match) -> String {
+        const MAX_LENGTH: usize = 200;
+        let text = self.to_markup(node);
+        // Collapse all whitespace so it fits on one line
+        let summary = text.split_whitespace().join(" ");
+        if summary.chars().count() > MAX_LENGTH {
+            let truncated: String = summary.chars().take(MAX_LENGTH).collect();
+            format!("{truncated}…")
+        } else {
+            summary
+        }
+    }
+
+    /// Serialize a match back to markup. For elements this is the element and
+    /// all its contents. For text and attributes, it's just the text/value.
+    pub fn to_markup(&self, node: &Match) -> String {
+        match node {
+            Match::Node(id) => {
+                let mut output = String::new();
+                self.write_markup(&mut output, *id);
+                output
+            }
+            Match::Attribute { element, index } => {
+                self.attributes(*element)[*index].1.clone()
+            }
+        }
+    }
+
+    /// Generate a query that selects a single node. The query is absolute, so
+    /// it chains together every ancestor of the node. For CSS, text and
+    /// attributes can't be selected so the query selects the containing
+    /// element instead.
+    pub fn selector(&self, node: &Match, syntax: QuerySyntax) -> String {
+        let (id, attribute) = match node {
+            Match::Node(id) => (*id, None),
+            Match::Attribute { element, index } => {
+                (*element, Some(&self.attributes(*element)[*index].0))
+            }
+        };
+        let mut path: Vec<NodeId> =
+            self.ancestors(id).filter(|id| *id != Self::ROOT).collect();
+        path.reverse();
+        path.push(id);
+
+        match syntax {
+            QuerySyntax::XPath => {
+                let mut selector = String::new();
+                for id in path {
+                    let (name, (position, count)) = match &self.nodes[id.0].data
+                    {
+                        NodeData::Element { name, .. } => (
+                            name.as_str(),
+                            self.sibling_position(id, |data| {
+                                data.name() == Some(name)
+                            }),
+                        ),
+                        NodeData::Text(_) => (
+                            "text()",
+                            self.sibling_position(id, |data| {
+                                matches!(data, NodeData::Text(_))
+                            }),
+                        ),
+                        NodeData::Root => continue,
+                    };
+                    write!(selector, "/{name}").unwrap();
+                    if count > 1 {
+                        write!(selector, "[{position}]").unwrap();
+                    }
+                }
+                if let Some(attribute) = attribute {
+                    write!(selector, "/@{attribute}").unwrap();
+                }
+                selector
+            }
+            QuerySyntax::Css => path
+                .into_iter()
+                .filter_map(|id| {
+                    let name = self.name(id)?;
+                    let (position, count) = self
+                        .sibling_position(id, |data| data.name() == Some(name));
+                    Some(if count > 1 {
+                        format!("{name}:nth-of-type({position})")
+                    } else {
+                        name.to_owned()
+                    })
+                })
+                .join(" > "),
+        }
+    }
+
+    /// Recursively copy HTML nodes into this document
+    fn add_html_children(
+        &mut self,
+        parent: NodeId,
+        html_node: ego_tree::NodeRef<scraper::Node>,
+    ) {
+        for child in html_node.children() {
+            let data = match child.value() {
+                scraper::Node::Element(element) => NodeData::Element {
+                    name: element.name().to_owned(),
+                    attributes: element
+                        .attrs()
+                        .map(|(name, value)| {
+                            (name.to_owned(), value.to_owned())
+                        })
+                        .collect(),
+                },
+                scraper::Node::Text(text) => NodeData::Text(text.to_string()),
+                _ => continue,
+            };
+            if let Some(id) = self.add_node(parent, data) {
+                self.add_html_children(id, child);
+            }
+        }
+    }
+
+    /// Recursively copy XML nodes into this document
+    fn add_xml_children(&mut self, parent: NodeId, xml_node: roxmltree::Node) {
+        /// Include the namespace prefix in the name, if any
+        fn qualified_name(
+            node: roxmltree::Node,
+            namespace: Option<&str>,
+            name: &str,
+        ) -> String {
+            match namespace.and_then(|namespace| node.lookup_prefix(namespace))
+            {
+                Some(prefix) => format!("{prefix}:{name}"),
+                None => name.to_owned(),
+            }
+        }
+
+        for child in xml_node.children() {
+            let data = if child.is_element() {
+                let tag_name = child.tag_name();
+                NodeData::Element {
+                    name: qualified_name(
+                        child,
+                        tag_name.namespace(),
+                        tag_name.name(),
+                    ),
+                    attributes: child
+                        .attributes()
+                        .map(|attribute| {
+                            (
+                                qualified_name(
+                                    child,
+                                    attribute.namespace(),
+                                    attribute.name(),
+                                ),
+                                attribute.value().to_owned(),
+                            )
+                        })
+                        .collect(),
+                }
+            } else if let Some(text) = child.text().filter(|_| child.is_text())
+            {
+                NodeData::Text(text.to_owned())
+            } else {
+                continue;
+            };
+            if let Some(id) = self.add_node(parent, data) {
+                self.add_xml_children(id, child);
+            }
+        }
+    }
+
+    /// Add a node to the end of the tree. Return `None` if the node is
+    /// whitespace-only text, which isn't added
+    fn add_node(&mut self, parent: NodeId, data: NodeData) -> Option<NodeId> {
+        if matches!(&data, NodeData::Text(text) if text.trim().is_empty()) {
+            return None;
+        }
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(Node {
+            parent: Some(parent),
+            children: Vec::new(),
+            data,
+        });
+        self.nodes[parent.0].children.push(id);
+        Some(id)
+    }
+
+    fn query_xpath(&self, steps: &[Step]) -> Vec<Match> {
+        let mut context = vec![Match::Node(Self::ROOT)];
+        for step in steps {
+            let mut next = Vec::new();
+            for node in context {
+                // Attributes have no children, so they're a dead end
+                let Match::Node(id) = node else {
+                    continue;
+                };
+                let bases = if step.descendant {
+                    self.descendants_or_self(id)
+                } else {
+                    vec![id]
+                };
+                for base in bases {
+                    let mut candidates = self.step_candidates(base, &step.test);
+                    for predicate in &step.predicates {
+                        let count = candidates.len();
+                        candidates = candidates
+                            .into_iter()
+                            .enumerate()
+                            .filter(|(i, node)| {
+                                self.matches_predicate(
+                                    node,
+                                    predicate,
+                                    i + 1,
+                                    count,
+                                )
+                            })
+                            .map(|(_, node)| node)
+                            .collect();
+                    }
+                    next.extend(candidates);
+                }
+            }
+            // Results from different bases can overlap, e.g. with `..`
+            next.sort_by_key(Match::sort_key);
+            next.dedup();
+            context = next;
+        }
+        context
+    }
+
+    /// Get all nodes selected by a single XPath step from a base node
+    fn step_candidates(&self, base: NodeId, test: &NodeTest) -> Vec<Match> {
+        let children = || self.nodes[base.0].children.iter().copied();
+        match test {
+            NodeTest::Name(name) => children()
+                .filter(|id| self.name_matches(*id, name))
+                .map(Match::Node)
+                .collect(),
+            NodeTest::AnyElement => children()
+                .filter(|id| self.nodes[id.0].data.is_element())
+                .map(Match::Node)
+                .collect(),
+            NodeTest::Text => children()
+                .filter(|id| matches!(self.nodes[id.0].data, NodeData::Text(_)))
+                .map(Match::Node)
+                .collect(),
+            NodeTest::Node => children().map(Match::Node).collect(),
+            NodeTest::Attribute(name) => self
+                .attributes(base)
+                .iter()
+                .enumerate()
+                .filter(|(_, (attribute, _))| {
+                    name.as_ref()
+                        .is_none_or(|name| self.names_equal(attribute, name))
+                })
+                .map(|(index, _)| Match::Attribute {
+                    element: base,
+                    index,
+                })
+                .collect(),
+            NodeTest::SelfNode => vec![Match::Node(base)],
+            NodeTest::Parent => self.nodes[base.0]
+                .parent
+                .map(Match::Node)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    fn matches_predicate(
+        &self,
+        node: &Match,
+        predicate: &Predicate,
+        position: usize,
+        count: usize,
+    ) -> bool {
+        let Match::Node(id) = node else {
+            // Only positional predicates apply to attributes
+            return match predicate {
+                Predicate::Position(n) => *n == position,
+                Predicate::Last => position == count,
+                _ => false,
+            };
+        };
+        match predicate {
+            Predicate::Position(n) => *n == position,
+            Predicate::Last => position == count,
+            Predicate::Attribute { name, value } => {
+                self.attribute(*id, name).is_some_and(|actual| {
+                    value.as_ref().is_none_or(|value| actual == value)
+                })
+            }
+            Predicate::Child(name) => self.nodes[id.0]
+                .children
+                .iter()
+                .any(|child| self.name_matches(*child, name)),
+        }
+    }
+
+    /// Does an element match a chain of CSS compound selectors? The last
+    /// compound is matched against the element, then the rest are matched
+    /// against its ancestors
+    fn matches_selector(&self, id: NodeId, parts: &[SelectorPart]) -> bool {
+        let Some((last, rest)) = parts.split_last() else {
+            return true;
+        };
+        if !self.matches_compound(id, &last.compound) {
+            return false;
+        }
+        if rest.is_empty() {
+            return true;
+        }
+        let mut ancestors = self
+            .ancestors(id)
+            .filter(|id| self.nodes[id.0].data.is_element());
+        match last.combinator {
+            Combinator::Child => ancestors
+                .next()
+                .is_some_and(|parent| self.matches_selector(parent, rest)),
+            Combinator::Descendant => {
+                ancestors.any(|ancestor| self.matches_selector(ancestor, rest))
+            }
+        }
+    }
+
+    fn matches_compound(&self, id: NodeId, compound: &Compound) -> bool {
+        if let Some(name) = &compound.name
+            && !self.name_matches(id, name)
+        {
+            return false;
+        }
+        compound.filters.iter().all(|filter| match filter {
+            Filter::Id(expected) => self.attribute(id, "id") == Some(expected),
+            Filter::Class(class) => {
+                self.attribute(id, "class").is_some_and(|classes| {
+                    classes.split_whitespace().any(|c| c == class)
+                })
+            }
+            Filter::Attribute { name, operator } => {
+                let Some(actual) = self.attribute(id, name) else {
+                    return false;
+                };
+                match operator {
+                    None => true,
+                    Some((AttributeOperator::Equals, value)) => actual == value,
+                    Some((AttributeOperator::Includes, value)) => {
+                        actual.split_whitespace().any(|word| word == value)
+                    }
+                    Some((AttributeOperator::Prefix, value)) => {
+                        actual.starts_with(value.as_str())
+                    }
+                    Some((AttributeOperator::Suffix, value)) => {
+                        actual.ends_with(value.as_str())
+                    }
+                    Some((AttributeOperator::Substring, value)) => {
+                        actual.contains(value.as_str())
+                    }
+                }
+            }
+            Filter::FirstChild => {
+                self.sibling_position(id, NodeData::is_element).0 == 1
+            }
+            Filter::LastChild => {
+                let (position, count) =
+                    self.sibling_position(id, NodeData::is_element);
+                position == count
+            }
+            Filter::NthChild(n) => {
+                self.sibling_position(id, NodeData::is_element).0 == *n
+            }
+            Filter::NthOfType(n) => {
+                let name = self.name(id);
+                self.sibling_position(id, |data| data.name() == name).0 == *n
+            }
+        })
+    }
+
+    /// Write the markup for a node and all its descendants
+    fn write_markup(&self, output: &mut String, id: NodeId) {
+        /// HTML elements that never have content or a closing tag
+        const VOID_ELEMENTS: &[&str] = &[
+            "area", "base", "br", "col", "embed", "hr", "img", "input", "link",
+            "meta", "source", "track", "wbr",
+        ];
+
+        let node = &self.nodes[id.0];
+        match &node.data {
+            NodeData::Root => {
+                for child in &node.children {
+                    self.write_markup(output, *child);
+                }
+            }
+            NodeData::Element { name, attributes } => {
+                output.push('<');
+                output.push_str(name);
+                for (name, value) in attributes {
+                    write!(output, " {name}=\"{}\"", escape(value, true))
+                        .unwrap();
+                }
+                let is_void = match self.kind {
+                    MarkupKind::Html => VOID_ELEMENTS.contains(&name.as_str()),
+                    MarkupKind::Xml => false,
+                };
+                if is_void {
+                    output.push('>');
+                } else if node.children.is_empty()
+                    && self.kind == MarkupKind::Xml
+                {
+                    output.push_str("/>");
+                } else {
+                    output.push('>');
+                    for child in &node.children {
+                        self.write_markup(output, *child);
+                    }
+                    write!(output, "</{name}>").unwrap();
+                }
+            }
+            NodeData::Text(text) => output.push_str(&escape(text, false)),
+        }
+    }
+
+    /// Iterate over all elements, in document order
+    fn elements(&self) -> impl Iterator<Item = NodeId> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.data.is_element())
+            .map(|(index, _)| NodeId(index))
+    }
+
+    /// Get a node and all its descendants, in document order
+    fn descendants_or_self(&self, id: NodeId) -> Vec<NodeId> {
+        let mut nodes = vec![id];
+        for child in &self.nodes[id.0].children {
+            nodes.extend(self.descendants_or_self(*child));
+        }
+        nodes
+    }
+
+    /// Iterate over a node's ancestors, starting with its parent
+    fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> {
+        std::iter::successors(self.nodes[id.0].parent, |id| {
+            self.nodes[id.0].parent
+        })
+    }
+
+    /// Get a node's 1-indexed position among the siblings that pass a filter,
+    /// and the number of those siblings. The node itself should pass the
+    /// filter.
+    fn sibling_position(
+        &self,
+        id: NodeId,
+        filter: impl Fn(&NodeData) -> bool,
+    ) -> (usize, usize) {
+        let Some(parent) = self.nodes[id.0].parent else {
+            return (1, 1);
+        };
+        let siblings: Vec<NodeId> = self.nodes[parent.0]
+            .children
+            .iter()
+            .copied()
+            .filter(|sibling| filter(&self.nodes[sibling.0].data))
+            .collect();
+        let position = siblings
+            .iter()
+            .position(|sibling| *sibling == id)
+            .map_or(1, |index| index + 1);
+        (position, siblings.len())
+    }
+
+    /// Get an element's name. `None` for non-elements
+    fn name(&self, id: NodeId) -> Option<&str> {
+        self.nodes[id.0].data.name()
+    }
+
+    /// Does a node's element name match a name from a query?
+    fn name_matches(&self, id: NodeId, name: &str) -> bool {
+        self.name(id)
+            .is_some_and(|actual| self.names_equal(actual, name))
+    }
+
+    /// Compare a node/attribute name to a name from a query. HTML names are
+    /// case-insensitive. If the query name has no namespace prefix, it matches
+    /// regardless of the prefix of the node.
+    fn names_equal(&self, actual: &str, expected: &str) -> bool {
+        let actual = if expected.contains(':') {
+            actual
+        } else {
+            actual.rsplit(':').next().unwrap_or(actual)
+        };
+        match self.kind {
+            MarkupKind::Html => actual.eq_ignore_ascii_case(expected),
+            MarkupKind::Xml => actual == expected,
+        }
+    }
+
+    /// Get all attributes of an element. Empty for non-elements
+    fn attributes(&self, id: NodeId) -> &[(String, String)] {
+        match &self.nodes[id.0].data {
+            NodeData::Element { attributes, .. } => attributes,
+            NodeData::Root | NodeData::Text(_) => &[],
+        }
+    }
+
+    /// Get the value of an element's attribute
+    fn attribute(&self, id: NodeId, name: &str) -> Option<&String> {
+        self.attributes(id)
+            .iter()
+            .find(|(attribute, _)| self.names_equal(attribute, name))
+            .map(|(_, value)| value)
+    }
+}
+
+/// Index of a node in a [Document]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+#[derive(Debug)]
+struct Node {
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    data: NodeData,
+}
+
+#[derive(Debug)]
+enum NodeData {
+    Root,
+    Element {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    Text(String),
+}
+
+impl NodeData {
+    fn is_element(&self) -> bool {
+        matches!(self, Self::Element { .. })
+    }
+
+    fn name(&self) -> Option<&str> {
+        match self {
+            Self::Element { name, .. } => Some(name),
+            Self::Root | Self::Text(_) => None,
+        }
+    }
+}
+
+/// A node selected by a query
+#[derive(Clone, Debug, PartialEq)]
+pub enum Match {
+    /// An element or text node
+    Node(NodeId),
+    /// An attribute of an element. Only XPath can select attributes
+    Attribute { element: NodeId, index: usize },
+}
+
+impl Match {
+    /// Key to sort matches in document order. Attributes come after their
+    /// element but before its children
+    fn sort_key(&self) -> (NodeId, usize) {
+        match self {
+            Self::Node(id) => (*id, 0),
+            Self::Attribute { element, index } => (*element, index + 1),
+        }
+    }
+}
+
+/// Language of a [Query]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum QuerySyntax {
+    XPath,
+    Css,
+}
+
+/// A parsed XPath or CSS query. Queries starting with `/` are XPath; anything
+/// else is CSS.
+#[derive(Debug, PartialEq)]
+pub enum Query {
+    XPath(Vec<Step>),
+    Css(Vec<Selector>),
+}
+
+impl Query {
+    pub fn syntax(&self) -> QuerySyntax {
+        match self {
+            Self::XPath(_) => QuerySyntax::XPath,
+            Self::Css(_) => QuerySyntax::Css,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let query = query.trim();
+        let result = if query.starts_with('/') {
+            repeat(1.., step).map(Self::XPath).parse(query)
+        } else {
+            separated(1.., delimited(multispace0, selector, multispace0), ',')
+                .map(Self::Css)
+                .parse(query)
+        };
+        result.map_err(|error| {
+            anyhow!("Invalid query at position {}", error.offset() + 1)
+        })
+    }
+}
+
+/// One step in an XPath location path
+#[derive(Debug, PartialEq)]
+pub struct Step {
+    /// Was the step preceded by `//` instead of `/`?
+    descendant: bool,
+    test: NodeTest,
+    predicates: Vec<Predicate>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum NodeTest {
+    Name(String),
+    /// `*`
+    AnyElement,
+    /// `text()`
+    Text,
+    /// `node()`
+    Node,
+    /// `@name`; `None` for `@*`
+    Attribute(Option<String>),
+    /// `.`
+    SelfNode,
+    /// `..`
+    Parent,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Predicate {
+    /// 1-indexed position
+    Position(usize),
+    /// `last()`
+    Last,
+    /// `@name` or `@name='value'`
+    Attribute { name: String, value: Option<String> },
+    /// Has a child element with the given name
+    Child(String),
+}
+
+/// A CSS complex selector (compound selectors joined by combinators)
+#[derive(Debug, PartialEq)]
+pub struct Selector(Vec<SelectorPart>);
+
+#[derive(Debug, PartialEq)]
+struct SelectorPart {
+    /// How this part relates to the *previous* part. Ignored for the first
+    combinator: Combinator,
+    compound: Compound,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, PartialEq)]
+struct Compound {
+    /// Element name; `None` for `*` or when omitted
+    name: Option<String>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Filter {
+    Id(String),
+    Class(String),
+    Attribute {
+        name: String,
+        operator: Option<(AttributeOperator, String)>,
+    },
+    FirstChild,
+    LastChild,
+    NthChild(usize),
+    NthOfType(usize),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum AttributeOperator {
+    /// `=`
+    Equals,
+    /// `~=`
+    Includes,
+    /// `^=`
+    Prefix,
+    /// `$=`
+    Suffix,
+    /// `*=`
+    Substring,
+}
+
+/// Parse an XPath step, including its leading separator
+fn step(input: &mut &str) -> ModalResult<Step> {
+    let descendant =
+        alt(("//".value(true), "/".value(false))).parse_next(input)?;
+    let test = alt((
+        "text()".value(NodeTest::Text),
+        "node()".value(NodeTest::Node),
+        "..".value(NodeTest::Parent),
+        ".".value(NodeTest::SelfNode),
+        preceded('@', alt(("*".value(None), xml_name.map(Some))))
+            .map(NodeTest::Attribute),
+        "*".value(NodeTest::AnyElement),
+        xml_name.map(NodeTest::Name),
+    ))
+    .parse_next(input)?;
+    let predicates = repeat(
+        0..,
+        delimited(
+            ('[', multispace0),
+            alt((
+                "last()".value(Predicate::Last),
+                dec_uint.map(Predicate::Position),
+                (
+                    preceded('@', xml_name),
+                    opt(preceded((multispace0, '=', multispace0), literal)),
+                )
+                    .map(|(name, value)| Predicate::Attribute { name, value }),
+                xml_name.map(Predicate::Child),
+            )),
+            (multispace0, ']'),
+        ),
+    )
+    .parse_next(input)?;
+    Ok(Step {
+        descendant,
+        test,
+        predicates,
+    })
+}
+
+/// Parse a CSS complex selector
+fn selector(input: &mut &str) -> ModalResult<Selector> {
+    let first = compound.parse_next(input)?;
+    let rest: Vec<SelectorPart> = repeat(
+        0..,
+        (
+            alt((
+                delimited(multispace0, '>', multispace0)
+                    .value(Combinator::Child),
+                multispace1.value(Combinator::Descendant),
+            )),
+            compound,
+        )
+            .map(|(combinator, compound)| SelectorPart {
+                combinator,
+                compound,
+            }),
+    )
+    .parse_next(input)?;
+    let mut parts = vec![SelectorPart {
+        combinator: Combinator::Descendant,
+        compound: first,
+    }];
+    parts.extend(rest);
+    Ok(Selector(parts))
+}
+
+/// Parse a CSS compound selector, e.g. `div.item[data-id]`
+fn compound(input: &mut &str) -> ModalResult<Compound> {
+    let attribute_operator = alt((
+        "~=".value(AttributeOperator::Includes),
+        "^=".value(AttributeOperator::Prefix),
+        "$=".value(AttributeOperator::Suffix),
+        "*=".value(AttributeOperator::Substring),
+        "=".value(AttributeOperator::Equals),
+    ));
+    let filter = alt((
+        preceded('#', css_name).map(Filter::Id),
+        preceded('.', css_name).map(Filter::Class),
+        delimited(
+            ('[', multispace0),
+            (
+                css_name,
+                opt((
+                    delimited(multispace0, attribute_operator, multispace0),
+                    alt((literal, css_name)),
+                )),
+            ),
+            (multispace0, ']'),
+        )
+        .map(|(name, operator)| Filter::Attribute { name, operator }),
+        ":first-child".value(Filter::FirstChild),
+        ":last-child".value(Filter::LastChild),
+        delimited(":nth-child(", dec_uint, ')').map(Filter::NthChild),
+        delimited(":nth-of-type(", dec_uint, ')').map(Filter::NthOfType),
+    ));
+    (
+        opt(alt(("*".value(None), css_name.map(Some)))),
+        repeat(0.., filter),
+    )
+        .verify(|(name, filters): &(_, Vec<_>)| {
+            name.is_some() || !filters.is_empty()
+        })
+        .map(|(name, filters)| Compound {
+            name: name.flatten(),
+            filters,
+        })
+        .parse_next(input)
+}
+
+/// Parse an XML name, which may include a namespace prefix
+fn xml_name(input: &mut &str) -> ModalResult<String> {
+    take_while(1.., |c: char| {
+        c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')
+    })
+    .map(String::from)
+    .parse_next(input)
+}
+
+/// Parse a CSS identifier
+fn css_name(input: &mut &str) -> ModalResult<String> {
+    take_while(1.., |c: char| c.is_alphanumeric() || matches!(c, '_' | '-'))
+        .map(String::from)
+        .parse_next(input)
+}
+
+/// Parse a single- or double-quoted string
+fn literal(input: &mut &str) -> ModalResult<String> {
+    alt((
+        delimited('"', take_till(0.., '"'), '"'),
+        delimited('\'', take_till(0.., '\''), '\''),
+    ))
+    .map(String::from)
+    .parse_next(input)
+}
+
+/// Escape special characters in text or an attribute value
+fn escape(text: &str, is_attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if is_attribute => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <body>
+    <ul id="items">
+      <li class="item first"><a href="/1">One</a></li>
+      <li class="item"><a href="/2">Two</a></li>
+      <li class="item last">Three<br></li>
+    </ul>
+  </body>
+</html>"#;
+
+    const XML: &str = r#"<?xml version="1.0"?>
+<feed xmlns:media="http://search.yahoo.com/mrss/">
+  <entry id="1"><title>First</title><media:thumbnail url="a.png"/></entry>
+  <entry id="2"><title>Second &amp; last</title></entry>
+</feed>"#;
+
+    /// Run queries of both syntaxes on both formats
+    #[rstest]
+    #[case::css_name(MarkupKind::Html, "a", &["<a href=\"/1\">One</a>", "<a href=\"/2\">Two</a>"])]
+    #[case::css_class(MarkupKind::Html, "li.last", &["<li class=\"item last\">Three<br></li>"])]
+    #[case::css_id_child(MarkupKind::Html, "#items > li:nth-child(2) a", &["<a href=\"/2\">Two</a>"])]
+    #[case::css_attribute(MarkupKind::Html, "a[href$='1'], br", &["<a href=\"/1\">One</a>", "<br>"])]
+    #[case::css_not_child(MarkupKind::Html, "ul > a", &[])]
+    #[case::css_case_insensitive(MarkupKind::Html, "LI.first > A", &["<a href=\"/1\">One</a>"])]
+    #[case::css_xml(MarkupKind::Xml, "entry:last-child title", &["<title>Second &amp; last</title>"])]
+    #[case::css_xml_prefix(MarkupKind::Xml, "thumbnail", &["<media:thumbnail url=\"a.png\"/>"])]
+    #[case::xpath_attribute(MarkupKind::Html, "//li/a/@href", &["/1", "/2"])]
+    #[case::xpath_position(MarkupKind::Html, "//li[last()]/text()", &["Three"])]
+    #[case::xpath_predicate(MarkupKind::Xml, "/feed/entry[@id='2']/title/text()", &["Second &amp; last"])]
+    #[case::xpath_child_predicate(MarkupKind::Xml, "//entry[media:thumbnail]/@id", &["1"])]
+    #[case::xpath_parent(MarkupKind::Xml, "//title/../@id", &["1", "2"])]
+    #[case::xpath_wildcard(MarkupKind::Xml, "/*/*[2]/*", &["<title>Second &amp; last</title>"])]
+    fn test_query(
+        #[case] kind: MarkupKind,
+        #[case] query: &str,
+        #[case] expected: &[&str],
+    ) {
+        let text = match kind {
+            MarkupKind::Html => HTML,
+            MarkupKind::Xml => XML,
+        };
+        let document = Document::parse(kind, text).unwrap();
+        let query: Query = query.parse().unwrap();
+        let actual = document
+            .query(&query)
+            .iter()
+            .map(|node| document.to_markup(node))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    /// Generated selectors should select exactly the original node
+    #[rstest]
+    #[case::xpath_element(
+        MarkupKind::Html,
+        "li.last",
+        QuerySyntax::XPath,
+        "/html/body/ul/li[3]"
+    )]
+    #[case::css_element(
+        MarkupKind::Html,
+        "//li[2]/a",
+        QuerySyntax::Css,
+        "html > body > ul > li:nth-of-type(2) > a"
+    )]
+    #[case::xpath_attribute(
+        MarkupKind::Xml,
+        "//thumbnail/@url",
+        QuerySyntax::XPath,
+        "/feed/entry[1]/media:thumbnail/@url"
+    )]
+    #[case::xpath_text(
+        MarkupKind::Xml,
+        "//entry[2]/title/text()",
+        QuerySyntax::XPath,
+        "/feed/entry[2]/title/text()"
+    )]
+    // CSS can't select text, so we get the parent
+    #[case::css_text(
+        MarkupKind::Xml,
+        "//entry[2]/title/text()",
+        QuerySyntax::Css,
+        "feed > entry:nth-of-type(2) > title"
+    )]
+    fn test_selector(
+        #[case] kind: MarkupKind,
+        #[case] query: &str,
+        #[case] syntax: QuerySyntax,
+        #[case] expected: &str,
+    ) {
+        let text = match kind {
+            MarkupKind::Html => HTML,
+            MarkupKind::Xml => XML,
+        };
+        let document = Document::parse(kind, text).unwrap();
+        let nodes = document.query(&query.parse().unwrap());
+        let [node] = nodes.as_slice() else {
+            panic!("Expected exactly one match, got {nodes:?}");
+        };
+        let selector = document.selector(node, syntax);
+        assert_eq!(selector, expected);
+
+        // Round trip: the selector should find the same node. CSS can only
+        // find the parent of text nodes
+        if syntax == QuerySyntax::XPath {
+            assert_eq!(document.query(&selector.parse().unwrap()), nodes);
+        }
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::xpath_trailing_slash("//a/")]
+    #[case::xpath_unclosed_predicate("//a[1")]
+    #[case::css_trailing_combinator("ul >")]
+    #[case::css_unclosed_attribute("a[href")]
+    fn test_parse_error(#[case] query: &str) {
+        assert!(query.parse::<Query>().is_err());
+    }
+
+    #[rstest]
+    #[case::html("text/html", Some(MarkupKind::Html))]
+    #[case::xml("application/xml", Some(MarkupKind::Xml))]
+    #[case::xml_text("text/xml", Some(MarkupKind::Xml))]
+    #[case::xml_suffix("application/atom+xml", Some(MarkupKind::Xml))]
+    #[case::json("application/json", None)]
+    fn test_from_mime(
+        #[case] mime: Mime,
+        #[case] expected: Option<MarkupKind>,
+    ) {
+        assert_eq!(MarkupKind::from_mime(&mime), expected);
+    }
+}
@@ -0,0 +1,177 @@
+//! Retained rendering for widgets whose output rarely changes between frames
+
+use ratatui::{buffer::Buffer, layout::Rect};
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug},
+    ops::Range,
+};
+
+/// A version number for some data that a widget renders. Bump it whenever the
+/// data changes, so cached renders of the old data are thrown away.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Generation(u64);
+
+impl Generation {
+    /// Mark the data as changed
+    pub fn bump(&mut self) {
+        self.0 += 1;
+    }
+}
+
+/// The cells a widget drew on its last render, so unchanged frames can be
+/// copied into the buffer instead of being laid out and drawn again.
+///
+/// A cached render is reused if it has the same key and area, *and* the cells
+/// underneath it are the same as before it was drawn. Widgets can patch styles
+/// onto what's already there, so the output depends on whatever the parent
+/// drew first. The key should include everything else the output depends on,
+/// typically a [Generation] of the data plus any view state such as scroll.
+///
+/// This is only correct for widgets that draw within their area. A hit costs
+/// a comparison and a copy of the area's cells, with no allocation.
+pub struct RenderCache<K> {
+    entry: RefCell<Option<Entry<K>>>,
+}
+
+impl<K: PartialEq> RenderCache<K> {
+    /// Draw to the area, either by copying the cached cells or by calling
+    /// `render`. If `render` is called, its output is cached for next time.
+    pub fn render(
+        &self,
+        key: K,
+        area: Rect,
+        buf: &mut Buffer,
+        render: impl FnOnce(&mut Buffer),
+    ) {
+        let area = area.intersection(buf.area);
+        let mut entry = self.entry.borrow_mut();
+        if let Some(entry) = &*entry
+            && entry.key == key
+            && entry.rendered.area == area
+            && region_eq(buf, &entry.underlay)
+        {
+            copy_region(&entry.rendered, buf, area);
+            return;
+        }
+
+        let underlay = snapshot(buf, area);
+        render(buf);
+        let rendered = snapshot(buf, area);
+        *entry = Some(Entry {
+            key,
+            underlay,
+            rendered,
+        });
+    }
+}
+
+impl<K> Default for RenderCache<K> {
+    fn default() -> Self {
+        Self {
+            entry: RefCell::new(None),
+        }
+    }
+}
+
+impl<K: Debug> Debug for RenderCache<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Cells are too noisy to include
+        let key = self
+            .entry
+            .borrow()
+            .as_ref()
+            .map(|entry| format!("{:?}", entry.key));
+        f.debug_struct("RenderCache").field("key", &key).finish()
+    }
+}
+
+/// A cached render
+struct Entry<K> {
+    key: K,
+    /// Cells in the area before the widget was drawn
+    underlay: Buffer,
+    /// Cells in the area after the widget was drawn
+    rendered: Buffer,
+}
+
+/// Copy the cells of an area into a new buffer
+fn snapshot(buf: &Buffer, area: Rect) -> Buffer {
+    let mut snapshot = Buffer::empty(area);
+    copy_region(buf, &mut snapshot, area);
+    snapshot
+}
+
+/// Copy the cells of an area from `src` into the same position in `dest`
+fn copy_region(src: &Buffer, dest: &mut Buffer, area: Rect) {
+    for (src_row, dest_row) in rows(src.area, area).zip(rows(dest.area, area)) {
+        dest.content[dest_row].clone_from_slice(&src.content[src_row]);
+    }
+}
+
+/// Do the cells in `buf` match every cell in `region`?
+fn region_eq(buf: &Buffer, region: &Buffer) -> bool {
+    rows(region.area, region.area)
+        .zip(rows(buf.area, region.area))
+        .all(|(a, b)| region.content[a] == buf.content[b])
+}
+
+/// Get the range of indexes in the content of a buffer covering `buffer_area`
+/// for each row of `area`. Rows are contiguous, so comparing and copying them
+/// as slices is much faster than going cell by cell.
+fn rows(buffer_area: Rect, area: Rect) -> impl Iterator<Item = Range<usize>> {
+    let width = usize::from(area.width);
+    area.rows().map(move |row| {
+        let start = usize::from(row.y - buffer_area.y)
+            * usize::from(buffer_area.width)
+            + usize::from(row.x - buffer_area.x);
+        start..start + width
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::{Color, Style};
+    use std::cell::Cell;
+
+    /// A render is reused until the key, area, or underlying cells change
+    #[test]
+    fn test_render_cache() {
+        let cache = RenderCache::default();
+        let renders = Cell::new(0);
+        let mut buf = Buffer::empty(Rect::new(0, 0, 10, 2));
+        let area = Rect::new(1, 0, 5, 2);
+        let render = |cache: &RenderCache<u32>, key, area, buf: &mut Buffer| {
+            cache.render(key, area, buf, |buf| {
+                renders.set(renders.get() + 1);
+                buf.set_string(area.x, area.y, "hello", Style::default());
+            });
+        };
+
+        render(&cache, 0, area, &mut buf);
+        assert_eq!(renders.get(), 1);
+
+        // Next frame starts from a blank buffer, which matches the underlay
+        buf.reset();
+        render(&cache, 0, area, &mut buf);
+        assert_eq!(renders.get(), 1);
+        assert_eq!(buf, Buffer::with_lines([" hello    ", "          "]));
+
+        // Key changed
+        buf.reset();
+        render(&cache, 1, area, &mut buf);
+        assert_eq!(renders.get(), 2);
+
+        // Area changed
+        buf.reset();
+        render(&cache, 1, Rect::new(2, 0, 5, 2), &mut buf);
+        assert_eq!(renders.get(), 3);
+
+        // Parent drew something different underneath
+        buf.reset();
+        buf.set_style(buf.area, Style::default().bg(Color::Red));
+        render(&cache, 1, Rect::new(2, 0, 5, 2), &mut buf);
+        assert_eq!(renders.get(), 4);
+    }
+}
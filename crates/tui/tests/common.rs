@@ -1,13 +1,15 @@
 //! TUI testing utilities
 
-use futures::Stream;
+#![allow(unused)]
+
+use futures::{Stream, stream};
 use ratatui::{
     buffer::{Buffer, Cell},
     layout::{Position, Rect},
     prelude::Backend,
 };
 use rstest::fixture;
-use slumber_tui::Tui;
+use slumber_tui::{InputScript, Tui};
 use std::{
     cell::{Ref, RefCell},
     convert::Infallible,
@@ -59,10 +61,18 @@ impl Runner {
     /// Because the TUI is run on a local set, **this method alone** will not
     /// cause it to run. Call [Self::done] to run the loop to completion.
     pub fn new(tui: TestTui) -> Self {
+        Self::with_script(tui, InputScript::default())
+    }
+
+    /// Create a new runner that plays an input script, interleaved with the
+    /// input sent by the test
+    pub fn with_script(tui: TestTui, script: InputScript) -> Self {
         let local = LocalSet::new();
         let backend = tui.backend().clone();
         let (tx, rx) = mpsc::unbounded_channel();
-        let join_handle = local.spawn_local(tui.run(InputStream(rx)));
+        let script = tui.play_script(script).expect("Invalid script");
+        let input = stream::select(script, InputStream(rx));
+        let join_handle = local.spawn_local(tui.run(input));
         Self {
             input_tx: tx,
             backend,
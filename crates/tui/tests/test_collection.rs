@@ -5,8 +5,7 @@ mod common;
 use crate::common::{Runner, TestBackend, backend};
 use rstest::rstest;
 use slumber_core::{
-    collection::{Collection, CollectionFile, Recipe},
-    http::HttpMethod,
+    collection::{Collection, CollectionFile, HttpMethod, Recipe},
     test_util::by_id,
 };
 use slumber_tui::Tui;
@@ -21,7 +20,8 @@ use tokio::fs;
 async fn test_collection_reload(backend: TestBackend, data_dir: DataDir) {
     // Start with an empty collection
     let collection_path = collection_file(&data_dir, "name: Test").await;
-    let tui = Tui::new(backend.clone(), Some(collection_path.clone())).unwrap();
+    let tui =
+        Tui::new(backend.clone(), Some(collection_path.clone()), None).unwrap();
 
     // Make sure the initial load is correct
     let collection = tui.collection().expect("Collection should be loaded");
@@ -89,7 +89,7 @@ async fn test_initial_load_error(backend: TestBackend, data_dir: DataDir) {
     // Start with an invalid collection
     let collection_path = collection_file(&data_dir, "requests: 3").await;
 
-    let tui = Tui::new(backend, Some(collection_path.clone())).unwrap();
+    let tui = Tui::new(backend, Some(collection_path.clone()), None).unwrap();
 
     // Should load into an error state - no collection present
     let tui = Runner::new(tui).done().await; // Draw so we can check output
@@ -120,7 +120,7 @@ async fn test_initial_load_error(backend: TestBackend, data_dir: DataDir) {
 async fn test_reload_error(backend: TestBackend, data_dir: DataDir) {
     // Start with an empty collection
     let collection_path = collection_file(&data_dir, "").await;
-    let tui = Tui::new(backend, Some(collection_path.clone())).unwrap();
+    let tui = Tui::new(backend, Some(collection_path.clone()), None).unwrap();
 
     // Make sure it loaded correctly
     let tui = Runner::new(tui).done().await; // Draw so we can check output
@@ -151,7 +151,7 @@ async fn test_reload_error(backend: TestBackend, data_dir: DataDir) {
 async fn test_collection_switch(backend: TestBackend, data_dir: DataDir) {
     // Start with an empty collection
     let collection_path = collection_file(&data_dir, "name: Coll 1").await;
-    let tui = Tui::new(backend, Some(collection_path.clone())).unwrap();
+    let tui = Tui::new(backend, Some(collection_path.clone()), None).unwrap();
 
     // Create a second collection
     let other_collection_path = data_dir.join("other_slumber.yml");
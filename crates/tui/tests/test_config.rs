@@ -0,0 +1,49 @@
+//! Test config reloading
+
+mod common;
+
+use crate::common::{Runner, TestBackend, backend};
+use rstest::rstest;
+use slumber_config::Config;
+use slumber_tui::Tui;
+use slumber_util::{DataDir, data_dir};
+use tokio::fs;
+
+/// Change the config file while the TUI is running. The new config should be
+/// applied immediately
+#[rstest]
+#[tokio::test]
+async fn test_config_reload(backend: TestBackend, data_dir: DataDir) {
+    let collection_path = data_dir.join("slumber.yml");
+    fs::write(&collection_path, "").await.unwrap();
+    let tui = Tui::new(backend, Some(collection_path), None).unwrap();
+
+    let tui = Runner::new(tui)
+        .run_until(fs::write(Config::path(), "theme:\n  preset: nord\n"))
+        .await
+        .wait_for_content("Reloaded configuration", (0, 19).into())
+        .await
+        .done()
+        .await;
+    tui.backend()
+        .assert_buffer_contains("No recipes defined", (1, 3).into());
+}
+
+/// An invalid config file shows an error, and the old config remains in use
+#[rstest]
+#[tokio::test]
+async fn test_config_reload_error(backend: TestBackend, data_dir: DataDir) {
+    let collection_path = data_dir.join("slumber.yml");
+    fs::write(&collection_path, "").await.unwrap();
+    let tui = Tui::new(backend, Some(collection_path), None).unwrap();
+
+    let tui = Runner::new(tui)
+        .run_until(fs::write(Config::path(), "theme: 3"))
+        .await
+        .wait_for_content("Error reloading configuration", (10, 8).into())
+        .await
+        .done()
+        .await;
+    tui.backend()
+        .assert_buffer_contains("No recipes defined", (1, 3).into());
+}
@@ -0,0 +1,50 @@
+//! Test requests from `slumber open`
+
+mod common;
+
+use crate::common::{Runner, TestBackend, backend};
+use rstest::rstest;
+use slumber_core::ipc::OpenRequest;
+use slumber_tui::Tui;
+use slumber_util::{DataDir, data_dir};
+use tokio::fs;
+
+/// An open request selects the recipe and profile in the TUI
+#[rstest]
+#[tokio::test]
+async fn test_open(backend: TestBackend, data_dir: DataDir) {
+    let collection_path = data_dir.join("slumber.yml");
+    fs::write(
+        &collection_path,
+        r#"
+profiles:
+    p1: {name: "Profile 1"}
+    p2: {name: "Profile 2"}
+requests:
+    r1: {method: "GET", url: "http://localhost/r1"}
+    r2: {method: "POST", url: "http://localhost/r2"}
+"#,
+    )
+    .await
+    .unwrap();
+    let tui = Tui::new(backend, Some(collection_path), None).unwrap();
+    let collection_id = tui.database().collection_id();
+
+    let tui = Runner::new(tui)
+        .run_until(async {
+            OpenRequest {
+                collection_id,
+                recipe_id: Some("r2".into()),
+                profile_id: Some("p2".into()),
+            }
+            .send()
+        })
+        .await
+        // Wait for the watcher to pick up the request
+        .wait_for_content("Profile 2", (1, 1).into())
+        .await
+        .done()
+        .await;
+    tui.backend()
+        .assert_buffer_contains("POST http://localhost/r2", (1, 3).into());
+}
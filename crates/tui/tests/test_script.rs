@@ -0,0 +1,51 @@
+//! Test playing input scripts into the TUI
+
+mod common;
+
+use crate::common::{Runner, TestBackend, backend};
+use rstest::rstest;
+use slumber_tui::{InputScript, Tui};
+use slumber_util::{DataDir, data_dir};
+use tokio::fs;
+
+/// Scripted input drives the TUI like real input
+#[rstest]
+#[tokio::test]
+async fn test_script(backend: TestBackend, data_dir: DataDir) {
+    let collection_path = data_dir.join("slumber.yml");
+    fs::write(
+        &collection_path,
+        r#"
+profiles:
+    p1: {name: "Profile 1"}
+    p2: {name: "Profile 2"}
+requests:
+    r1: {method: "GET", url: "http://localhost/r1"}
+"#,
+    )
+    .await
+    .unwrap();
+    let script_path = data_dir.join("script.yml");
+    fs::write(
+        &script_path,
+        "
+steps:
+  - type: action
+    action: switch_profile
+  - type: text
+    text: '2'
+  - type: key
+    key: enter
+",
+    )
+    .await
+    .unwrap();
+    let script = InputScript::load(&script_path).unwrap();
+    let tui = Tui::new(backend, Some(collection_path), None).unwrap();
+
+    Runner::with_script(tui, script)
+        .wait_for_content("Profile 2", (1, 1).into())
+        .await
+        .done()
+        .await;
+}
@@ -0,0 +1,152 @@
+# English messages. This is the source language for all translations: every
+# message used in the code must be defined here. See the "Translations" page
+# in the docs for how to add a new language.
+
+## Common
+
+confirm-yes = Yes
+confirm-no = No
+
+## Modal titles
+
+modal-error-title = Error ({ $code })
+modal-audit-log-title = Audit Log
+modal-captures-title = Captured Values
+modal-cookies-title = Cookies
+modal-changelog-title = Collection History
+modal-request-chain-title = Request Chain
+modal-follow-link-title = Follow Link
+modal-switch-profile-title = Switch Profile
+modal-toggles-title = Toggles
+modal-recent-requests-title = Recent Requests
+modal-offline-queue-title = Offline Queue
+
+## Context menus
+
+menu-edit-collection = Edit Collection
+menu-edit-folder = Edit Folder
+menu-edit-recipe = Edit Recipe
+menu-edit-profile = Edit Profile
+menu-clear-command-cache = Clear Command Cache
+menu-view-audit-log = View Audit Log
+menu-view-captures = View Captured Values
+menu-view-cookies = View Cookies
+menu-view-changelog = View Collection History
+menu-view-log = View Log
+menu-export-screen = Export Screen
+menu-copy-as = Copy as { $format }
+menu-save-as = Save as { $format }
+menu-text = Text
+menu-refresh-values = Refresh Values
+menu-delete-request = Delete Request
+menu-delete-all-requests = Delete All Requests
+menu-this-profile = This Profile
+menu-all-profiles = All Profiles
+menu-request = Request
+menu-response = Response
+menu-copy = Copy
+menu-copy-url = Copy URL
+menu-copy-body = Copy Body
+menu-view-body = View Body
+menu-diff-current = Diff With Current
+menu-edit-body = Edit Body
+menu-reset-body = Reset Body
+menu-edit-variables = Edit Variables
+menu-reset-variables = Reset Variables
+menu-load-graphql-schema = Load Schema
+menu-save-body = Save Body as File
+menu-download = Send and Save Body to File
+menu-show-raw-body = Show Raw Body
+menu-show-formatted-body = Show Formatted Body
+menu-wrap-lines = Wrap Lines
+menu-unwrap-lines = Don't Wrap Lines
+menu-query-nodes = Query Nodes
+menu-copy-json-path = Copy JSONPath
+menu-follow-link = Follow Link
+menu-follow = Follow
+menu-pause-follow = Pause Follow
+menu-stop-stream = Stop Stream
+menu-url = URL
+menu-as-cli = as CLI
+menu-as-curl = as cURL
+menu-as-python = as Python
+menu-edit-template = Edit { $noun }
+menu-reset-template = Reset { $noun }
+menu-go-to-definition = Go to Definition
+menu-profile-field = Profile Field: { $field }
+menu-recipe = Recipe: { $recipe }
+menu-folder = Folder: { $folder }
+
+## Template previews
+
+template-preview-cached = (cached { $age } ago)
+
+## CLI
+
+cli-reopen-invalid = { $path } is invalid, would you like to reopen it?
+cli-dry-run-trigger = Triggered requests are disabled with `--dry-run`
+cli-unknown-profile = No profile with ID `{ $profile }`; options are: { $options }
+cli-unknown-toggle = No toggle with ID `{ $toggle }`; options are: { $options }
+cli-unknown-param = Recipe `{ $recipe }` has no parameter `{ $param }`; options are: { $options }
+cli-error-status = Response has error status { $status }
+cli-error-assertion = { $total ->
+    [one] { $failed } of { $total } assertion failed
+   *[other] { $failed } of { $total } assertions failed
+}
+cli-download-complete = Saved { $size } to `{ $path }`
+cli-cached-collection = Warning: using cached copy of `{ $file }`: { $error }
+cli-logging-to = Logging to { $path }
+cli-collection-created = New collection created at `{ $path }`
+cli-collection-deleted = Deleted collection { $collection }
+cli-collection-migrated = Migrated { $from } into { $to }
+cli-collection-trusted = Trusted collection { $collection }
+cli-collection-untrusted = Untrusted collection { $collection }
+cli-requests-deleted = { $count ->
+    [one] Deleted { $count } request
+   *[other] Deleted { $count } requests
+}
+cli-cookie-stored = Stored cookie `{ $name }` for `{ $domain }`
+cli-cookie-deleted = Deleted cookie `{ $name }` for `{ $domain }`
+cli-cookies-cleared = { $count ->
+    [one] Deleted { $count } cookie
+   *[other] Deleted { $count } cookies
+}
+cli-secret-stored = Stored secret for `{ $username }` in `{ $service }`
+cli-secret-deleted = Deleted secret for `{ $username }` in `{ $service }`
+cli-test-no-tagged = { $count ->
+    [one] No recipes have the tag { $tags }
+   *[other] No recipes have the tags { $tags }
+}
+cli-test-summary = { $tests ->
+    [one] { $tests } test
+   *[other] { $tests } tests
+}: { $passed } passed, { $failed } failed, { $errors ->
+    [one] { $errors } error
+   *[other] { $errors } errors
+} ({ $duration }ms)
+cli-test-failed = { $count ->
+    [one] { $count } assertion failed (status { $status })
+   *[other] { $count } assertions failed (status { $status })
+}
+
+## Notifications
+
+notify-copied = Copied text to clipboard
+notify-saved = Saved to { $path }
+notify-download-complete = Saved { $size } to { $path }
+notify-reloaded-collection = Reloaded collection
+notify-reloaded-config = Reloaded configuration
+notify-trusted-collection = Trusted collection
+notify-command-cache-cleared = { $count ->
+    [one] Cleared { $count } cached command
+   *[other] Cleared { $count } cached commands
+}
+notify-command-succeeded = `{ $command }` succeeded
+notify-request-unchanged = Request is unchanged from the recorded one
+notify-graphql-schema-loaded = Loaded GraphQL schema; it will be available when editing the query
+notify-bookmark-unset = Bookmark `{ $mark }` is not set
+notify-request-queued = `{ $host }` is unreachable; the request will be sent when the network is back ({ $count } waiting)
+notify-unknown-profile = Profile `{ $profile }` is not in the collection
+notify-unknown-recipe = Recipe `{ $recipe }` is not in the collection
+notify-unknown-field = Field `{ $field }` is not defined in the current profile
+notify-profile-hidden = Profile `{ $profile }` is hidden by the profile list filter
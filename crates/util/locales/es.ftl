@@ -0,0 +1,150 @@
+# Spanish messages. Any message missing here falls back to English.
+
+## Common
+
+confirm-yes = Sí
+confirm-no = No
+
+## Modal titles
+
+modal-error-title = Error ({ $code })
+modal-audit-log-title = Registro de auditoría
+modal-captures-title = Valores capturados
+modal-cookies-title = Cookies
+modal-changelog-title = Historial de la colección
+modal-request-chain-title = Cadena de solicitudes
+modal-follow-link-title = Seguir enlace
+modal-switch-profile-title = Cambiar perfil
+modal-toggles-title = Interruptores
+modal-recent-requests-title = Peticiones recientes
+modal-offline-queue-title = Cola sin conexión
+
+## Context menus
+
+menu-edit-collection = Editar colección
+menu-edit-folder = Editar carpeta
+menu-edit-recipe = Editar receta
+menu-edit-profile = Editar perfil
+menu-clear-command-cache = Vaciar caché de comandos
+menu-view-audit-log = Ver registro de auditoría
+menu-view-captures = Ver valores capturados
+menu-view-cookies = Ver cookies
+menu-view-changelog = Ver historial de la colección
+menu-view-log = Ver registro
+menu-export-screen = Exportar pantalla
+menu-copy-as = Copiar como { $format }
+menu-save-as = Guardar como { $format }
+menu-text = Texto
+menu-refresh-values = Actualizar valores
+menu-delete-request = Eliminar petición
+menu-delete-all-requests = Eliminar todas las peticiones
+menu-this-profile = Este perfil
+menu-all-profiles = Todos los perfiles
+menu-request = Petición
+menu-response = Respuesta
+menu-copy = Copiar
+menu-copy-url = Copiar URL
+menu-copy-body = Copiar cuerpo
+menu-view-body = Ver cuerpo
+menu-diff-current = Comparar con actual
+menu-edit-body = Editar cuerpo
+menu-reset-body = Restablecer cuerpo
+menu-edit-variables = Editar variables
+menu-reset-variables = Restablecer variables
+menu-load-graphql-schema = Cargar esquema
+menu-save-body = Guardar cuerpo como archivo
+menu-download = Enviar y guardar cuerpo en archivo
+menu-show-raw-body = Mostrar cuerpo sin formato
+menu-show-formatted-body = Mostrar cuerpo formateado
+menu-wrap-lines = Ajustar líneas
+menu-unwrap-lines = No ajustar líneas
+menu-query-nodes = Consultar nodos
+menu-copy-json-path = Copiar JSONPath
+menu-follow-link = Seguir enlace
+menu-follow = Seguir
+menu-pause-follow = Pausar seguimiento
+menu-stop-stream = Detener transmisión
+menu-url = URL
+menu-as-cli = como CLI
+menu-as-curl = como cURL
+menu-as-python = como Python
+menu-edit-template = Editar { $noun }
+menu-reset-template = Restablecer { $noun }
+menu-go-to-definition = Ir a la definición
+menu-profile-field = Campo del perfil: { $field }
+menu-recipe = Receta: { $recipe }
+menu-folder = Carpeta: { $folder }
+
+## Template previews
+
+template-preview-cached = (en caché hace { $age })
+
+## CLI
+
+cli-reopen-invalid = { $path } no es válido, ¿quieres volver a abrirlo?
+cli-dry-run-trigger = Las peticiones encadenadas están deshabilitadas con `--dry-run`
+cli-unknown-profile = No existe ningún perfil con ID `{ $profile }`; las opciones son: { $options }
+cli-unknown-toggle = No existe ningún interruptor con ID `{ $toggle }`; las opciones son: { $options }
+cli-unknown-param = La receta `{ $recipe }` no tiene ningún parámetro `{ $param }`; las opciones son: { $options }
+cli-error-status = La respuesta tiene un estado de error { $status }
+cli-error-assertion = { $total ->
+    [one] Falló { $failed } de { $total } aserción
+   *[other] Fallaron { $failed } de { $total } aserciones
+}
+cli-download-complete = Se guardaron { $size } en `{ $path }`
+cli-cached-collection = Advertencia: usando la copia en caché de `{ $file }`: { $error }
+cli-logging-to = Registrando en { $path }
+cli-collection-created = Nueva colección creada en `{ $path }`
+cli-collection-deleted = Se eliminó la colección { $collection }
+cli-collection-migrated = Se migró { $from } a { $to }
+cli-collection-trusted = Colección { $collection } marcada como confiable
+cli-collection-untrusted = Colección { $collection } marcada como no confiable
+cli-requests-deleted = { $count ->
+    [one] Se eliminó { $count } petición
+   *[other] Se eliminaron { $count } peticiones
+}
+cli-cookie-stored = Se guardó la cookie `{ $name }` para `{ $domain }`
+cli-cookie-deleted = Se eliminó la cookie `{ $name }` para `{ $domain }`
+cli-cookies-cleared = { $count ->
+    [one] Se eliminó { $count } cookie
+   *[other] Se eliminaron { $count } cookies
+}
+cli-secret-stored = Se guardó el secreto de `{ $username }` en `{ $service }`
+cli-secret-deleted = Se eliminó el secreto de `{ $username }` en `{ $service }`
+cli-test-no-tagged = { $count ->
+    [one] Ninguna receta tiene la etiqueta { $tags }
+   *[other] Ninguna receta tiene las etiquetas { $tags }
+}
+cli-test-summary = { $tests ->
+    [one] { $tests } prueba
+   *[other] { $tests } pruebas
+}: { $passed } aprobadas, { $failed } fallidas, { $errors ->
+    [one] { $errors } error
+   *[other] { $errors } errores
+} ({ $duration }ms)
+cli-test-failed = { $count ->
+    [one] Falló { $count } aserción (estado { $status })
+   *[other] Fallaron { $count } aserciones (estado { $status })
+}
+
+## Notifications
+
+notify-copied = Texto copiado al portapapeles
+notify-saved = Guardado en { $path }
+notify-download-complete = Se guardaron { $size } en { $path }
+notify-reloaded-collection = Colección recargada
+notify-reloaded-config = Configuración recargada
+notify-trusted-collection = Colección marcada como confiable
+notify-command-cache-cleared = { $count ->
+    [one] Se borró { $count } comando en caché
+   *[other] Se borraron { $count } comandos en caché
+}
+notify-command-succeeded = `{ $command }` se ejecutó correctamente
+notify-request-unchanged = La petición no ha cambiado respecto a la grabada
+notify-graphql-schema-loaded = Esquema GraphQL cargado; estará disponible al editar la consulta
+notify-bookmark-unset = El marcador `{ $mark }` no está definido
+notify-request-queued = `{ $host }` no está disponible; la petición se enviará cuando vuelva la red ({ $count } en espera)
+notify-unknown-profile = El perfil `{ $profile }` no está en la colección
+notify-unknown-recipe = La receta `{ $recipe }` no está en la colección
+notify-unknown-field = El campo `{ $field }` no está definido en el perfil actual
+notify-profile-hidden = El perfil `{ $profile }` está oculto por el filtro de la lista de perfiles
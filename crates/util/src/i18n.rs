@@ -0,0 +1,440 @@
+//! Localization of user-facing strings. Translations are written in
+//! [Fluent](https://projectfluent.org/) syntax and bundled into the binary,
+//! one file per language under `locales/`. English is the source language:
+//! every message must be defined in `en.ftl`, and any message missing from
+//! another language falls back to English.
+//!
+//! Messages are formatted with [fluent_bundle], so the full Fluent syntax is
+//! available: terms, selectors, and plural categories. Arguments that format
+//! as integers are passed to Fluent as numbers so they can be used as plural
+//! selectors:
+//!
+//! ```ftl
+//! cli-cookies-cleared = { $count ->
+//!     [one] Deleted { $count } cookie
+//!    *[other] Deleted { $count } cookies
+//! }
+//! ```
+
+use fluent_bundle::{
+    FluentArgs, FluentError, FluentResource, FluentValue,
+    concurrent::FluentBundle,
+};
+use std::{
+    env,
+    fmt::Display,
+    sync::{LazyLock, OnceLock},
+};
+use thiserror::Error;
+use tracing::{info, warn};
+use unic_langid::{LanguageIdentifier, LanguageIdentifierError};
+
+/// Bundled translations, keyed by language code. English must be first
+const LOCALES: &[(&str, &str)] = &[
+    (ENGLISH, include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+const ENGLISH: &str = "en";
+/// Environment variables used to detect the locale when it's not set in the
+/// config, in order of precedence. This matches the POSIX precedence for
+/// message catalogs.
+const LOCALE_ENV_VARS: &[&str] = &["LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// Messages for the active locale. Initialized by [set_locale]
+static ACTIVE: OnceLock<Bundle> = OnceLock::new();
+/// Fallback for messages missing from the active locale
+static FALLBACK: LazyLock<Bundle> = LazyLock::new(Bundle::english);
+
+/// Translate a message into the active locale. The first argument is the
+/// message ID, followed by any number of `name = value` arguments. Values can
+/// be anything that implements [Display].
+///
+/// ```
+/// use slumber_util::tr;
+///
+/// assert_eq!(tr!("confirm-yes"), "Yes");
+/// assert_eq!(tr!("modal-error-title", code = "network"), "Error (network)");
+/// ```
+#[macro_export]
+macro_rules! tr {
+    ($id:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::i18n::translate(
+            $id,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),*],
+        )
+    };
+}
+
+/// Set the locale for all translated strings. If `None`, the locale is detected
+/// from the environment (`LC_ALL`, `LC_MESSAGES`, then `LANG`). Unsupported
+/// locales fall back to English.
+///
+/// This should be called once at startup, before any strings are translated.
+/// Strings translated before this is called are in English. Once the locale
+/// has been set (or a string has been translated), subsequent calls have no
+/// effect.
+pub fn set_locale(locale: Option<&str>) {
+    let bundle = match locale {
+        Some(locale) => Bundle::load(locale),
+        None => Bundle::load(&detect_locale()),
+    };
+    info!(language = bundle.language, "Setting locale");
+    let _ = ACTIVE.set(bundle);
+}
+
+/// Translate a message into the active locale. Generally you should use the
+/// [tr] macro instead. If the message isn't defined in any locale, the ID is
+/// returned.
+pub fn translate(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let bundle = ACTIVE.get_or_init(Bundle::english);
+    bundle
+        .format(id, args)
+        .or_else(|| FALLBACK.format(id, args))
+        .unwrap_or_else(|| {
+            warn!(id, "Unknown message");
+            id.to_owned()
+        })
+}
+
+/// Get the locale from the environment, e.g. `en_US.UTF-8`
+fn detect_locale() -> String {
+    LOCALE_ENV_VARS
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_else(|| ENGLISH.to_owned())
+}
+
+/// Get the language portion of a locale string. Accepts BCP 47 tags (`es-MX`)
+/// as well as POSIX locales (`es_MX.UTF-8`)
+fn language(locale: &str) -> String {
+    locale
+        .split(['-', '_', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// All messages for a single language
+struct Bundle {
+    language: &'static str,
+    bundle: FluentBundle<FluentResource>,
+}
+
+impl Bundle {
+    /// Load the bundled translations for a locale. Fall back to English if the
+    /// language isn't supported or its translations are invalid
+    fn load(locale: &str) -> Self {
+        let language = language(locale);
+        let Some((language, source)) =
+            LOCALES.iter().find(|(code, _)| *code == language)
+        else {
+            // C and POSIX are the default locales on many systems; they just
+            // mean "no preference"
+            if !["c", "posix"].contains(&language.as_str()) {
+                warn!(locale, "Unsupported locale; falling back to English");
+            }
+            return Self::english();
+        };
+        Self::parse(language, source).unwrap_or_else(|error| {
+            warn!(
+                language,
+                %error,
+                "Invalid translations; falling back to English"
+            );
+            Self::english()
+        })
+    }
+
+    /// Parse the bundled English messages
+    fn english() -> Self {
+        Self::parse(ENGLISH, LOCALES[0].1).expect("Invalid English messages")
+    }
+
+    /// Parse a Fluent source file
+    fn parse(language: &'static str, source: &str) -> Result<Self, FtlError> {
+        let resource = FluentResource::try_new(source.to_owned()).map_err(
+            |(_, errors)| {
+                // Report the first error; it's usually the cause of the
+                // rest
+                let error = &errors[0];
+                FtlError::new(source, error.pos.start, error.to_string())
+            },
+        )?;
+        let language_id: LanguageIdentifier =
+            language.parse().map_err(|error: LanguageIdentifierError| {
+                FtlError::new(source, 0, error.to_string())
+            })?;
+        let mut bundle = FluentBundle::new_concurrent(vec![language_id]);
+        // Unicode isolation marks are for bidirectional text in the browser.
+        // In a terminal they're just noise
+        bundle.set_use_isolating(false);
+        bundle
+            .add_resource(resource)
+            .map_err(|errors| match &errors[0] {
+                FluentError::Overriding { id, .. } => {
+                    // The bundle doesn't give a position, so point at the last
+                    // definition
+                    let position = source
+                        .rfind(&format!("\n{id} ="))
+                        .map_or(0, |position| position + 1);
+                    FtlError::new(
+                        source,
+                        position,
+                        format!("Duplicate message `{id}`"),
+                    )
+                }
+                error => FtlError::new(source, 0, error.to_string()),
+            })?;
+        Ok(Self { language, bundle })
+    }
+
+    /// Format a message, or return `None` if it isn't defined
+    fn format(
+        &self,
+        id: &str,
+        args: &[(&str, &dyn Display)],
+    ) -> Option<String> {
+        let pattern = self.bundle.get_message(id)?.value()?;
+        let args: FluentArgs = args
+            .iter()
+            .map(|(name, value)| (*name, fluent_value(*value)))
+            .collect();
+        let mut errors = Vec::new();
+        let output =
+            self.bundle
+                .format_pattern(pattern, Some(&args), &mut errors);
+        if !errors.is_empty() {
+            // Fluent renders anything it can't resolve as its source, e.g.
+            // `{$name}`, so the message is still usable
+            warn!(id, ?errors, "Error formatting message");
+        }
+        Some(output.into_owned())
+    }
+
+    /// Get the names of all variables referenced by a message
+    #[cfg(test)]
+    fn variables(&self, id: &str) -> Vec<&str> {
+        use fluent_syntax::ast::{
+            Expression, InlineExpression, Pattern, PatternElement,
+        };
+
+        fn visit_pattern<'a>(
+            pattern: &Pattern<&'a str>,
+            names: &mut Vec<&'a str>,
+        ) {
+            for element in &pattern.elements {
+                if let PatternElement::Placeable { expression } = element {
+                    visit_expression(expression, names);
+                }
+            }
+        }
+
+        fn visit_expression<'a>(
+            expression: &Expression<&'a str>,
+            names: &mut Vec<&'a str>,
+        ) {
+            match expression {
+                Expression::Select { selector, variants } => {
+                    visit_inline(selector, names);
+                    for variant in variants {
+                        visit_pattern(&variant.value, names);
+                    }
+                }
+                Expression::Inline(expression) => {
+                    visit_inline(expression, names);
+                }
+            }
+        }
+
+        fn visit_inline<'a>(
+            expression: &InlineExpression<&'a str>,
+            names: &mut Vec<&'a str>,
+        ) {
+            match expression {
+                InlineExpression::VariableReference { id } => {
+                    names.push(id.name);
+                }
+                InlineExpression::FunctionReference { arguments, .. }
+                | InlineExpression::TermReference {
+                    arguments: Some(arguments),
+                    ..
+                } => {
+                    for argument in &arguments.positional {
+                        visit_inline(argument, names);
+                    }
+                    for argument in &arguments.named {
+                        visit_inline(&argument.value, names);
+                    }
+                }
+                InlineExpression::Placeable { expression } => {
+                    visit_expression(expression, names);
+                }
+                _ => {}
+            }
+        }
+
+        let mut names = Vec::new();
+        if let Some(value) = self
+            .bundle
+            .get_message(id)
+            .and_then(|message| message.value())
+        {
+            visit_pattern(value, &mut names);
+        }
+        names.sort_unstable();
+        names.dedup();
+        names
+    }
+}
+
+/// Convert a message argument to a Fluent value. Integers are passed as
+/// numbers so they can select plural variants; everything else is a string.
+/// Only values that format back to the exact same text are treated as numbers,
+/// so strings like `007` are passed through unchanged. Fluent numbers are
+/// floats, so anything too large to be represented exactly stays a string.
+fn fluent_value(value: &dyn Display) -> FluentValue<'static> {
+    const MAX_EXACT: u64 = 1 << f64::MANTISSA_DIGITS;
+    let value = value.to_string();
+    match value.parse::<i64>() {
+        Ok(number)
+            if number.unsigned_abs() <= MAX_EXACT
+                && number.to_string() == value =>
+        {
+            number.into()
+        }
+        _ => value.into(),
+    }
+}
+
+/// Error parsing a Fluent file
+#[derive(Debug, Error)]
+#[error("Line {line}: {message}")]
+struct FtlError {
+    line: usize,
+    message: String,
+}
+
+impl FtlError {
+    /// Create an error at a byte position in the source
+    fn new(source: &str, position: usize, message: String) -> Self {
+        let line =
+            source[..position.min(source.len())].matches('\n').count() + 1;
+        Self { line, message }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fluent_syntax::ast::Entry;
+    use pretty_assertions::assert_eq;
+    use rstest::rstest;
+
+    const PLURAL: &str = "hello = { $count ->
+    [0] No cookies
+    [one] { $count } cookie
+   *[other] { $count } cookies
+}";
+
+    /// Parse and format messages
+    #[rstest]
+    #[case::text("hello = Hello", &[], "Hello")]
+    #[case::variable(
+        "hello = Hello, { $name }!",
+        &[("name", "World")],
+        "Hello, World!",
+    )]
+    #[case::variable_missing("hello = Hello, {$name}!", &[], "Hello, {$name}!")]
+    #[case::literal(r#"hello = { "{" }Hello{ "}" }"#, &[], "{Hello}")]
+    #[case::multiline(
+        "# Comment\nhello =\n    Hello\n    World\n\nother = Other",
+        &[],
+        "Hello\nWorld",
+    )]
+    #[case::term("-app = Slumber\nhello = Hello, { -app }", &[], "Hello, Slumber")]
+    #[case::plural_one(PLURAL, &[("count", "1")], "1 cookie")]
+    #[case::plural_other(PLURAL, &[("count", "3")], "3 cookies")]
+    #[case::plural_exact(PLURAL, &[("count", "0")], "No cookies")]
+    // Not a number, so it can only match the default variant
+    #[case::plural_string(PLURAL, &[("count", "01")], "01 cookies")]
+    #[case::large_number(
+        "hello = { $id }",
+        &[("id", "9007199254740993")],
+        "9007199254740993",
+    )]
+    fn test_format(
+        #[case] source: &str,
+        #[case] args: &[(&str, &str)],
+        #[case] expected: &str,
+    ) {
+        let bundle = Bundle::parse("en", source).unwrap();
+        let args: Vec<(&str, &dyn Display)> = args
+            .iter()
+            .map(|(name, value)| (*name, value as &dyn Display))
+            .collect();
+        assert_eq!(bundle.format("hello", &args).as_deref(), Some(expected));
+        assert_eq!(bundle.format("unknown", &args), None);
+    }
+
+    /// Invalid syntax is rejected with the line number
+    #[rstest]
+    #[case::no_equals("hello", "Line 1: Expected a token starting with \"=\"")]
+    #[case::unclosed(
+        "\nhello = { $name",
+        "Line 2: Expected a token starting with \"}\""
+    )]
+    #[case::no_default(
+        "hello = { $count ->\n  [one] One\n}",
+        "Line 3: The select expression must have a default variant"
+    )]
+    #[case::duplicate("a = 1\na = 2", "Line 2: Duplicate message `a`")]
+    fn test_parse_error(#[case] source: &str, #[case] expected: &str) {
+        let Err(error) = Bundle::parse("en", source) else {
+            panic!("Expected parse error")
+        };
+        assert!(
+            error.to_string().starts_with(expected),
+            "Unexpected error: {error}"
+        );
+    }
+
+    /// Map locale strings to bundled languages
+    #[rstest]
+    #[case::bcp47("es-MX", "es")]
+    #[case::posix("es_ES.UTF-8", "es")]
+    #[case::uppercase("ES", "es")]
+    #[case::posix_default("C", "en")]
+    #[case::unsupported("xx_XX", "en")]
+    fn test_load(#[case] locale: &str, #[case] expected: &str) {
+        assert_eq!(Bundle::load(locale).language, expected);
+    }
+
+    /// Every bundled translation must parse, only define messages that exist
+    /// in English, and use the same variables as the English message
+    #[test]
+    fn test_bundled_locales() {
+        let english = &*FALLBACK;
+        for (language, source) in LOCALES {
+            let bundle = Bundle::parse(language, source)
+                .unwrap_or_else(|error| panic!("{language}: {error}"));
+            let resource = FluentResource::try_new((*source).to_owned())
+                .unwrap_or_else(|_| panic!("{language}: invalid messages"));
+            for entry in resource.entries() {
+                let Entry::Message(message) = entry else {
+                    continue;
+                };
+                let id = message.id.name;
+                assert!(
+                    english.bundle.has_message(id),
+                    "{language}: message `{id}` is not defined in English"
+                );
+                assert_eq!(
+                    bundle.variables(id),
+                    english.variables(id),
+                    "{language}: variables for `{id}` don't match English"
+                );
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@
 //! `slumber` crate version. If you choose to depend directly on this crate, you
 //! do so at your own risk of breakage.
 
+pub mod i18n;
 pub mod paths;
 #[cfg(any(test, feature = "test"))]
 mod test_util;
@@ -35,6 +36,10 @@ use winnow::{
 pub const NEW_ISSUE_LINK: &str =
     "https://github.com/LucasPickering/slumber/issues/new";
 
+/// Tracing target for spans that measure startup phases. These spans are
+/// printed with their timing when the `--profile-startup` flag is passed.
+pub const STARTUP_TARGET: &str = "slumber::startup";
+
 /// A static mapping between values (of type `T`) and labels (strings). Used to
 /// both stringify from and parse to `T`.
 pub struct Mapping<'a, T: Copy>(&'a [(T, &'a [&'a str])]);
@@ -138,10 +143,27 @@ pub fn git_link(path: &str) -> String {
     )
 }
 
+/// Format a byte total, e.g. 1_000_000 -> 1 MB
+pub fn format_byte_size(size: usize) -> String {
+    const K: usize = 10usize.pow(3);
+    const M: usize = 10usize.pow(6);
+    const G: usize = 10usize.pow(9);
+    const T: usize = 10usize.pow(12);
+    let (denom, suffix) = match size {
+        ..K => return format!("{size} B"),
+        K..M => (K, "K"),
+        M..G => (M, "M"),
+        G..T => (G, "G"),
+        T.. => (T, "T"),
+    };
+    let size = size as f64 / denom as f64;
+    format!("{size:.1} {suffix}B")
+}
+
 /// A newtype for [Duration] that provides formatting, parsing, and
 /// deserialization. The name is meant to make it harder to confuse with
 /// [Duration].
-#[derive(Copy, Clone, Debug, Eq, derive_more::From, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, derive_more::From, Hash, PartialEq)]
 pub struct TimeSpan(Duration);
 
 impl TimeSpan {
@@ -337,4 +359,21 @@ mod tests {
     ) {
         assert_err(s.parse::<TimeSpan>(), expected_error);
     }
+
+    #[rstest]
+    #[case::zero(0, "0 B")]
+    #[case::one(1, "1 B")]
+    #[case::almost_kb(999, "999 B")]
+    #[case::kb(1000, "1.0 KB")]
+    #[case::kb_round_down(1049, "1.0 KB")]
+    #[case::kb_round_up(1050, "1.1 KB")]
+    #[case::almost_mb(999_999, "1000.0 KB")]
+    #[case::mb(1_000_000, "1.0 MB")]
+    #[case::almost_gb(999_999_999, "1000.0 MB")]
+    #[case::gb(1_000_000_000, "1.0 GB")]
+    #[case::almost_tb(999_999_999_999, "1000.0 GB")]
+    #[case::tb(1_000_000_000_000, "1.0 TB")]
+    fn test_format_byte_size(#[case] size: usize, #[case] expected: &str) {
+        assert_eq!(&format_byte_size(size), expected);
+    }
 }
@@ -6,7 +6,7 @@ use std::{
     sync::OnceLock,
 };
 
-#[cfg(debug_assertions)]
+#[cfg(any(debug_assertions, feature = "test"))]
 thread_local! {
     /// This is dev-only so it can be used in integration tests. In the past
     /// this used an env var, but it's now a thread local so integration tests
@@ -116,7 +116,7 @@ pub fn create_parent(path: &Path) -> io::Result<()> {
 /// will be cached so subsequent calls are fast. If the path can't be found,
 /// panic. This is only used in debug builds so it should always be in a git
 /// repo.
-#[cfg(any(debug_assertions, test))]
+#[cfg(any(debug_assertions, test, feature = "test"))]
 pub fn get_repo_root() -> &'static Path {
     use std::{process::Command, sync::OnceLock};
 
@@ -22,6 +22,7 @@ use saphyr::{
 use std::{
     fs,
     hash::{Hash, Hasher},
+    net::IpAddr,
     path::{Path, PathBuf},
 };
 
@@ -197,6 +198,19 @@ impl DeserializeYaml for String {
     }
 }
 
+impl DeserializeYaml for IpAddr {
+    fn expected() -> Expected {
+        Expected::String
+    }
+
+    fn deserialize(yaml: SourcedYaml, _source_map: &SourceMap) -> Result<Self> {
+        let location = yaml.location;
+        yaml.try_into_string()?
+            .parse()
+            .map_err(|error| LocatedError::other(error, location))
+    }
+}
+
 impl<T: DeserializeYaml> DeserializeYaml for Option<T> {
     fn expected() -> Expected {
         // Techinically we should include `null` here too, but generally
@@ -610,10 +624,11 @@ impl<'a> StructDeserializer<'a> {
         field: Field<T>,
         source_map: &SourceMap,
     ) -> Result<T> {
-        if let Some(value) = self
-            .mapping
-            .remove(&SourcedYaml::value_from_str(field.name))
-        {
+        // Field names are always strings, so build the key directly instead of
+        // inferring its scalar type. This is hot for large collections.
+        if let Some(value) = self.mapping.remove(&SourcedYaml::from(
+            YamlData::Value(Scalar::String(field.name.into())),
+        )) {
             T::deserialize(value, source_map)
         } else if let Some(default) = field.default {
             Ok(default)
@@ -47,6 +47,13 @@ impl SourcedYaml<'_> {
         // Everything after that operates just on the list of references. So
         // steps 2+ scale with the number of references, not the size of the
         // YAML.
+        //
+        // Most documents don't use references at all. Scanning allocates a
+        // path for every node, so check for any $ref key first, which doesn't
+        // allocate. For large collections this is a significant speedup.
+        if !self.contains_reference() {
+            return Ok(self);
+        }
         ReferenceLocations::scan(self, source_map)?
             // Convert the reference list into a dependency graph
             .build_graph()
@@ -57,6 +64,26 @@ impl SourcedYaml<'_> {
     }
 }
 
+impl SourcedYaml<'_> {
+    /// Does this value contain a `$ref` key anywhere within it?
+    fn contains_reference(&self) -> bool {
+        match &self.data {
+            YamlData::Value(_)
+            | YamlData::Representation(_, _, _)
+            | YamlData::BadValue
+            | YamlData::Alias(_) => false,
+            YamlData::Sequence(sequence) => {
+                sequence.iter().any(Self::contains_reference)
+            }
+            YamlData::Mapping(mapping) => mapping.iter().any(|(key, value)| {
+                key.data.as_str() == Some(REFERENCE_KEY)
+                    || value.contains_reference()
+            }),
+            YamlData::Tagged(_, value) => value.contains_reference(),
+        }
+    }
+}
+
 /// A collection all references in the YAML document, each one key by the
 /// location of its *usage* in the document. Locations are unique but references
 /// are not, as the same reference can be used multiple times.
@@ -1,11 +1,15 @@
 use anyhow::Context;
-use slumber_util::{ResultTracedAnyhow, paths};
+use slumber_util::{ResultTraced, ResultTracedAnyhow, STARTUP_TARGET, paths};
 use std::{
     fs::{File, OpenOptions},
     io,
 };
-use tracing::level_filters::LevelFilter;
-use tracing_subscriber::{fmt::format::FmtSpan, prelude::*};
+use tracing::{Level, level_filters::LevelFilter};
+use tracing_subscriber::{
+    filter::Targets,
+    fmt::{format::FmtSpan, writer::BoxMakeWriter},
+    prelude::*,
+};
 
 /// This covers two cases: CLI enabled/TUI disabled, or both enabled. We need
 /// the CLI for some TUI features such as the -f flag
@@ -19,7 +23,11 @@ async fn main() -> anyhow::Result<std::process::ExitCode> {
     Args::complete(); // If COMPLETE var is enabled, process will stop here
     let args = Args::parse();
 
-    initialize_tracing(args.global.log_level, args.subcommand.is_some());
+    initialize_tracing(
+        args.global.log_level,
+        args.subcommand.is_some(),
+        args.global.profile_startup,
+    );
 
     // Select mode based on whether request ID(s) were given
     match args.subcommand {
@@ -27,23 +35,44 @@ async fn main() -> anyhow::Result<std::process::ExitCode> {
         // testing the CLI
         #[cfg(feature = "tui")]
         None => {
+            // The TUI owns the terminal, so this goes to the log file along
+            // with the timing itself
+            if args.global.profile_startup {
+                let path = paths::log_file();
+                tracing::info!(
+                    target: STARTUP_TARGET,
+                    "Writing startup timing to {}",
+                    path.display()
+                );
+            }
             // This should return the error so we get a full stack trace
-            slumber_tui::Tui::start(args.global.file).await?;
+            slumber_tui::Tui::start(
+                args.global.file,
+                args.global.appearance,
+                args.global.session,
+                args.global.script,
+                args.global.record,
+            )
+            .await?;
             Ok(ExitCode::SUCCESS)
         }
         #[cfg(not(feature = "tui"))]
         None => Err(anyhow::anyhow!("TUI feature is disabled")),
 
         // Execute one request without a TUI
-        Some(subcommand) => Ok(subcommand
-            .execute(args.global)
-            .await
-            // Do *not* return the error, because that prints a stack trace
-            // which is way too verbose. Just print the error messages instead
-            .unwrap_or_else(|error| {
-                slumber_cli::print_error(&error);
-                ExitCode::FAILURE
-            })),
+        Some(subcommand) => {
+            let error_format = args.global.error_format;
+            Ok(subcommand
+                .execute(args.global)
+                .await
+                // Do *not* return the error, because that prints a stack trace
+                // which is way too verbose. Just print the error messages
+                // instead
+                .unwrap_or_else(|error| {
+                    slumber_cli::print_error(&error, error_format);
+                    ExitCode::FAILURE
+                }))
+        }
     }
 }
 
@@ -58,9 +87,9 @@ async fn main() -> anyhow::Result<()> {
     let level = env::var("LOG")
         .map(|value| value.parse().unwrap())
         .unwrap_or(LevelFilter::OFF);
-    initialize_tracing(level, false);
+    initialize_tracing(level, false, false);
     let collection_file = env::args().nth(1).map(String::into);
-    slumber_tui::Tui::start(collection_file).await
+    slumber_tui::Tui::start(collection_file, None, None, None, None).await
 }
 
 /// Both disabled - problem!!
@@ -76,13 +105,44 @@ async fn main() -> anyhow::Result<()> {
 /// an error creating the log file, we'll skip that part. This means in the TUI
 /// the error (and all other tracing) will never be visible, but that's a
 /// problem for another day.
-fn initialize_tracing(level_filter: LevelFilter, has_stderr: bool) {
+///
+/// If `profile_startup` is enabled, the timing of each startup span will be
+/// printed to stderr (CLI) or the log file (TUI).
+fn initialize_tracing(
+    level_filter: LevelFilter,
+    has_stderr: bool,
+    profile_startup: bool,
+) {
     // Failing to log shouldn't be a fatal crash, so just move on
     let log_file = initialize_log_file()
         .context("Error creating log file")
         .traced()
         .ok();
 
+    // Print the duration of each startup phase as its span closes. Only spans
+    // with the startup target are included, so this is independent of the
+    // log level. The TUI owns the terminal so we can't use stderr there.
+    let startup_writer = if !profile_startup {
+        None
+    } else if has_stderr {
+        Some(BoxMakeWriter::new(io::stderr))
+    } else {
+        log_file
+            .as_ref()
+            .and_then(|file| file.try_clone().traced().ok())
+            .map(BoxMakeWriter::new)
+    };
+    let startup_subscriber = startup_writer.map(|writer| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_target(false)
+            .with_ansi(false)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_filter(
+                Targets::new().with_target(STARTUP_TARGET, Level::INFO),
+            )
+    });
+
     let file_subscriber = log_file.map(|log_file| {
         // Include PID
         // https://github.com/tokio-rs/tracing/pull/2655
@@ -119,6 +179,7 @@ fn initialize_tracing(level_filter: LevelFilter, has_stderr: bool) {
         tracing_subscriber::registry()
             .with(file_subscriber)
             .with(stderr_subscriber)
+            .with(startup_subscriber)
             .with(console_subscriber::spawn())
             .init()
     }
@@ -127,6 +188,7 @@ fn initialize_tracing(level_filter: LevelFilter, has_stderr: bool) {
         tracing_subscriber::registry()
             .with(file_subscriber)
             .with(stderr_subscriber)
+            .with(startup_subscriber)
             .init()
     }
 }